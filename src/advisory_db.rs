@@ -0,0 +1,145 @@
+//! Local copy of OSV advisories for offline `pybun audit`.
+//!
+//! Every online scan records what OSV returned for each `package==version`
+//! it asked about, including "no known vulnerabilities". `pybun --offline
+//! audit` answers from these records; a package that was never scanned
+//! online is reported as unscanned, not as clean.
+//!
+//! Layout:
+//! ```text
+//! ~/.cache/pybun/advisories/
+//!   {normalized package}/
+//!     {version}.json    # OSV vulnerability records and when they were fetched
+//! ```
+
+use crate::pypi::normalize_project_name;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+const ADVISORIES_DIR: &str = "advisories";
+
+#[derive(Debug, Error)]
+pub enum AdvisoryDbError {
+    #[error("failed to determine home directory")]
+    NoHomeDir,
+    #[error("failed to update advisory database at {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, AdvisoryDbError>;
+
+/// OSV's answer for one `package==version`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdvisoryRecord {
+    pub package: String,
+    pub version: String,
+    /// Unix seconds.
+    pub fetched_at: u64,
+    /// OSV vulnerability objects; empty when none are known.
+    pub vulns: Vec<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdvisoryDb {
+    root: PathBuf,
+}
+
+impl AdvisoryDb {
+    /// Database under the cache root (`PYBUN_HOME`).
+    pub fn new() -> Result<Self> {
+        let cache = crate::cache::Cache::new().map_err(|_| AdvisoryDbError::NoHomeDir)?;
+        Ok(Self::with_root(cache.root().join(ADVISORIES_DIR)))
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn record_path(&self, package: &str, version: &str) -> PathBuf {
+        let version: String = version
+            .trim()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '!' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.root
+            .join(normalize_project_name(package))
+            .join(format!("{version}.json"))
+    }
+
+    /// The recorded answer for `package==version`, if it was ever scanned.
+    pub fn get(&self, package: &str, version: &str) -> Option<AdvisoryRecord> {
+        let content = fs::read_to_string(self.record_path(package, version)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Record OSV's answer for `package==version`, replacing any earlier one.
+    pub fn store(&self, package: &str, version: &str, vulns: &[Value]) -> Result<AdvisoryRecord> {
+        let record = AdvisoryRecord {
+            package: package.to_string(),
+            version: version.to_string(),
+            fetched_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            vulns: vulns.to_vec(),
+        };
+        let path = self.record_path(package, version);
+        let io_error = |source| AdvisoryDbError::Io {
+            path: path.clone(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        // Write then rename, so a concurrent offline scan never reads half a
+        // record.
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp, serde_json::to_vec(&record)?).map_err(io_error)?;
+        fs::rename(&tmp, &path).map_err(io_error)?;
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn records_round_trip_by_normalized_name_and_version() {
+        let temp = tempfile::tempdir().unwrap();
+        let db = AdvisoryDb::with_root(temp.path());
+        assert!(db.get("requests", "2.27.0").is_none());
+
+        db.store("Requests", "2.27.0", &[json!({ "id": "GHSA-1" })])
+            .unwrap();
+        db.store("urllib3", "2.2.0", &[]).unwrap();
+
+        let record = db.get("requests", "2.27.0").unwrap();
+        assert_eq!(record.vulns[0]["id"], "GHSA-1");
+        assert!(record.fetched_at > 0);
+        // A clean answer is recorded too.
+        assert!(db.get("urllib3", "2.2.0").unwrap().vulns.is_empty());
+        assert!(db.get("requests", "2.28.0").is_none());
+        assert!(temp.path().join("requests/2.27.0.json").is_file());
+    }
+}
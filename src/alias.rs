@@ -0,0 +1,260 @@
+//! Project-scoped command aliases (`[tool.pybun.alias]`).
+//!
+//! ```toml
+//! [tool.pybun.alias]
+//! ci = "test --shard 1/1 --report junit=out.xml"
+//! fix = ["lint", "--fix"]
+//! ```
+//!
+//! Aliases are expanded on the raw argument list before clap parses it:
+//! `pybun --format=json ci -v` becomes `pybun --format=json test --shard 1/1
+//! --report junit=out.xml -v`. String values are split with POSIX shell
+//! quoting rules; array values are used verbatim. An alias may expand to
+//! another alias; cycles and chains deeper than [`MAX_DEPTH`] are rejected.
+//! Built-in commands always win over an alias with the same name.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+/// Maximum number of nested alias expansions.
+pub const MAX_DEPTH: usize = 8;
+
+/// Global flags that take a separate value (`--format json`).
+const GLOBAL_VALUE_FLAGS: &[&str] = &["--format", "--progress", "--columns"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AliasError {
+    #[error("alias '{name}' has an invalid command line: {value}")]
+    InvalidValue { name: String, value: String },
+    #[error("alias '{0}' expands to nothing")]
+    Empty(String),
+    #[error("alias recursion detected: {}", .0.join(" -> "))]
+    Recursion(Vec<String>),
+}
+
+impl AliasError {
+    /// Diagnostic code reported for the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AliasError::InvalidValue { .. } => "E_ALIAS_INVALID",
+            AliasError::Empty(_) => "E_ALIAS_EMPTY",
+            AliasError::Recursion(_) => "E_ALIAS_RECURSION",
+        }
+    }
+}
+
+/// Alias definition as written in `pyproject.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AliasValue {
+    Command(String),
+    Args(Vec<String>),
+}
+
+impl AliasValue {
+    /// Split the alias into arguments.
+    pub fn args(&self, name: &str) -> Result<Vec<String>, AliasError> {
+        let args = match self {
+            AliasValue::Command(command) => {
+                shlex::split(command).ok_or_else(|| AliasError::InvalidValue {
+                    name: name.to_string(),
+                    value: command.clone(),
+                })?
+            }
+            AliasValue::Args(args) => args.clone(),
+        };
+        if args.is_empty() {
+            return Err(AliasError::Empty(name.to_string()));
+        }
+        Ok(args)
+    }
+
+    /// Human-readable expansion.
+    pub fn display(&self) -> String {
+        match self {
+            AliasValue::Command(command) => command.clone(),
+            AliasValue::Args(args) => {
+                shlex::try_join(args.iter().map(String::as_str)).unwrap_or_else(|_| args.join(" "))
+            }
+        }
+    }
+}
+
+/// Aliases defined by the project containing `cwd` (empty when there is no
+/// project or it defines none).
+pub fn load_aliases(cwd: &Path) -> BTreeMap<String, AliasValue> {
+    crate::project::Project::discover(cwd)
+        .map(|project| project.pybun_config().alias)
+        .unwrap_or_default()
+}
+
+/// Index of the subcommand token in `args` (skipping leading global flags).
+fn command_index(args: &[String]) -> Option<usize> {
+    let mut index = 0;
+    while index < args.len() {
+        let arg = args[index].as_str();
+        if arg == "--" {
+            return None;
+        }
+        if GLOBAL_VALUE_FLAGS.contains(&arg) {
+            index += 2;
+            continue;
+        }
+        if arg.starts_with('-') {
+            index += 1;
+            continue;
+        }
+        return Some(index);
+    }
+    None
+}
+
+/// Expand an alias in command position. `builtins` are the names of the
+/// CLI's own subcommands, which are never treated as aliases.
+pub fn expand_args(
+    args: &[String],
+    aliases: &BTreeMap<String, AliasValue>,
+    builtins: &[String],
+) -> Result<Vec<String>, AliasError> {
+    let mut args = args.to_vec();
+    let mut chain: Vec<String> = Vec::new();
+    loop {
+        let Some(index) = command_index(&args) else {
+            return Ok(args);
+        };
+        let name = args[index].clone();
+        if builtins.contains(&name) {
+            return Ok(args);
+        }
+        let Some(value) = aliases.get(&name) else {
+            return Ok(args);
+        };
+        if chain.contains(&name) || chain.len() >= MAX_DEPTH {
+            chain.push(name);
+            return Err(AliasError::Recursion(chain));
+        }
+        chain.push(name.clone());
+        let expansion = value.args(&name)?;
+        args.splice(index..=index, expansion);
+    }
+}
+
+/// Names of the built-in subcommands (including `help`).
+pub fn builtin_commands() -> Vec<String> {
+    use clap::CommandFactory;
+    let mut names: Vec<String> = crate::cli::Cli::command()
+        .get_subcommands()
+        .flat_map(|sub| {
+            std::iter::once(sub.get_name().to_string())
+                .chain(sub.get_all_aliases().map(str::to_string))
+        })
+        .collect();
+    names.push("help".to_string());
+    names
+}
+
+/// Expand project aliases in the process arguments (without `argv[0]`).
+pub fn expand_cli_args(args: Vec<String>) -> Result<Vec<String>, AliasError> {
+    let Ok(cwd) = std::env::current_dir() else {
+        return Ok(args);
+    };
+    let aliases = load_aliases(&cwd);
+    if aliases.is_empty() {
+        return Ok(args);
+    }
+    expand_args(&args, &aliases, &builtin_commands())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(entries: &[(&str, AliasValue)]) -> BTreeMap<String, AliasValue> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn builtins() -> Vec<String> {
+        args(&["test", "run", "lint"])
+    }
+
+    #[test]
+    fn expands_alias_after_global_flags() {
+        let table = aliases(&[(
+            "ci",
+            AliasValue::Command("test --shard 1/1 --report 'junit=out dir.xml'".into()),
+        )]);
+        let expanded = expand_args(
+            &args(&["--format", "json", "ci", "-v"]),
+            &table,
+            &builtins(),
+        )
+        .unwrap();
+        assert_eq!(
+            expanded,
+            args(&[
+                "--format",
+                "json",
+                "test",
+                "--shard",
+                "1/1",
+                "--report",
+                "junit=out dir.xml",
+                "-v"
+            ])
+        );
+    }
+
+    #[test]
+    fn nested_aliases_and_builtin_precedence() {
+        let table = aliases(&[
+            ("fix", AliasValue::Args(args(&["lint", "--fix"]))),
+            ("f", AliasValue::Command("fix --quiet".into())),
+            ("test", AliasValue::Command("run nope".into())),
+        ]);
+        assert_eq!(
+            expand_args(&args(&["f"]), &table, &builtins()).unwrap(),
+            args(&["lint", "--fix", "--quiet"])
+        );
+        assert_eq!(
+            expand_args(&args(&["test"]), &table, &builtins()).unwrap(),
+            args(&["test"])
+        );
+    }
+
+    #[test]
+    fn detects_recursion() {
+        let table = aliases(&[
+            ("a", AliasValue::Command("b".into())),
+            ("b", AliasValue::Command("a --x".into())),
+        ]);
+        assert_eq!(
+            expand_args(&args(&["a"]), &table, &builtins()).unwrap_err(),
+            AliasError::Recursion(args(&["a", "b", "a"]))
+        );
+    }
+
+    #[test]
+    fn rejects_empty_and_malformed_values() {
+        let table = aliases(&[
+            ("empty", AliasValue::Args(vec![])),
+            ("bad", AliasValue::Command("test 'unterminated".into())),
+        ]);
+        assert_eq!(
+            expand_args(&args(&["empty"]), &table, &builtins()).unwrap_err(),
+            AliasError::Empty("empty".into())
+        );
+        assert!(matches!(
+            expand_args(&args(&["bad"]), &table, &builtins()),
+            Err(AliasError::InvalidValue { .. })
+        ));
+    }
+}
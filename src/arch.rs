@@ -0,0 +1,445 @@
+//! CPU architecture of the target interpreter.
+//!
+//! Wheels are selected for the architecture the install target's Python
+//! actually runs as, which is not always the architecture PyBun was built
+//! for: an x86_64 Python under Rosetta on Apple Silicon, a 32-bit Python on
+//! 64-bit Windows, or an emulated interpreter in mixed-arch CI. The
+//! interpreter reports its architecture itself ([`detect_interpreter_arch`]);
+//! `PYBUN_PYTHON_ARCH` pins it instead.
+//!
+//! Packages that need a particular variant are forced through
+//! `[tool.pybun.arch]`:
+//!
+//! ```toml
+//! [tool.pybun.arch]
+//! pyobjc-core = "universal2"   # fat macOS wheel, loads on both arches
+//! legacy-ext = "x86_64"        # only ships x86_64 builds
+//! ```
+//!
+//! A forced variant the interpreter cannot load (an x86_64 wheel for an
+//! arm64 Python, or vice versa) is still installed, with a
+//! `W_ARCH_MISMATCH` warning.
+
+use crate::pypi::normalize_project_name;
+use crate::runtime::Platform;
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+/// Environment variable that pins the interpreter architecture.
+pub const PYTHON_ARCH_ENV: &str = "PYBUN_PYTHON_ARCH";
+
+/// Prints `platform.machine()`, `sysconfig.get_platform()` and the pointer
+/// width, one per line.
+const PROBE_SCRIPT: &str = "import platform, struct, sysconfig; print(platform.machine()); print(sysconfig.get_platform()); print(struct.calcsize('P') * 8)";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArchError {
+    #[error(
+        "unknown architecture '{value}' for {package} in [tool.pybun.arch]; expected x86_64, arm64, x86 or universal2"
+    )]
+    Unknown { package: String, value: String },
+    #[error(
+        "{package} is forced to {arch} in [tool.pybun.arch], but no {os} wheels exist for {arch}"
+    )]
+    Unsupported {
+        package: String,
+        arch: Arch,
+        os: &'static str,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, ArchError>;
+
+/// A wheel architecture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arch {
+    X86_64,
+    Arm64,
+    /// 32-bit x86 (`i686`, `win32`).
+    X86,
+    /// macOS fat binaries containing both x86_64 and arm64 code.
+    Universal2,
+}
+
+impl Arch {
+    /// Parse an architecture name as written by users, `uname -m` or
+    /// `platform.machine()` (case-insensitive).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "x86_64" | "amd64" | "x64" => Some(Arch::X86_64),
+            "arm64" | "aarch64" => Some(Arch::Arm64),
+            "x86" | "i386" | "i686" | "win32" => Some(Arch::X86),
+            "universal2" => Some(Arch::Universal2),
+            _ => None,
+        }
+    }
+
+    /// Architecture PyBun itself was built for.
+    pub fn host() -> Option<Self> {
+        match std::env::consts::ARCH {
+            "x86_64" => Some(Arch::X86_64),
+            "aarch64" => Some(Arch::Arm64),
+            "x86" => Some(Arch::X86),
+            _ => None,
+        }
+    }
+
+    /// Architecture of a wheel platform tag (`manylinux_2_17_aarch64`,
+    /// `macosx_11_0_universal2`, `win32`, …). `None` for `any` and tags
+    /// without one.
+    pub fn of_platform_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "win_amd64" => return Some(Arch::X86_64),
+            "win_arm64" => return Some(Arch::Arm64),
+            "win32" => return Some(Arch::X86),
+            _ => {}
+        }
+        let (_, suffix) = tag.rsplit_once('_')?;
+        match suffix {
+            "64" if tag.ends_with("x86_64") => Some(Arch::X86_64),
+            "x64" => Some(Arch::X86_64),
+            "arm64" | "aarch64" => Some(Arch::Arm64),
+            "i686" => Some(Arch::X86),
+            "universal2" => Some(Arch::Universal2),
+            _ => None,
+        }
+    }
+
+    /// Whether an interpreter running as `interpreter` can load wheels
+    /// built for `self`.
+    pub fn loads_on(self, interpreter: Arch) -> bool {
+        self == interpreter
+            || (self == Arch::Universal2 && matches!(interpreter, Arch::X86_64 | Arch::Arm64))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Arm64 => "arm64",
+            Arch::X86 => "x86",
+            Arch::Universal2 => "universal2",
+        }
+    }
+}
+
+impl std::fmt::Display for Arch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Architecture `python` runs as, or `None` when it cannot be determined.
+/// `PYBUN_PYTHON_ARCH` takes precedence over asking the interpreter.
+pub fn detect_interpreter_arch(python: &Path) -> Option<Arch> {
+    if let Ok(value) = std::env::var(PYTHON_ARCH_ENV)
+        && !value.trim().is_empty()
+    {
+        return Arch::parse(&value).filter(|arch| *arch != Arch::Universal2);
+    }
+    let output = std::process::Command::new(python)
+        .args(["-c", PROBE_SCRIPT])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_interpreter_probe(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the output of [`PROBE_SCRIPT`].
+///
+/// `platform.machine()` reports the architecture the process runs as on
+/// macOS (`x86_64` under Rosetta) and Linux, but the OS architecture on
+/// Windows, where `sysconfig.get_platform()` names the interpreter build
+/// instead. A 32-bit interpreter on a 64-bit Linux kernel is recognised by
+/// its pointer width.
+pub fn parse_interpreter_probe(output: &str) -> Option<Arch> {
+    let mut lines = output.lines().map(str::trim);
+    let machine = lines.next()?;
+    let platform = lines.next().unwrap_or("");
+    let bits = lines.next().and_then(|b| b.parse::<u32>().ok());
+    match platform {
+        "win32" => return Some(Arch::X86),
+        "win-amd64" => return Some(Arch::X86_64),
+        "win-arm64" => return Some(Arch::Arm64),
+        _ => {}
+    }
+    match (Arch::parse(machine)?, bits) {
+        (Arch::X86_64, Some(32)) => Some(Arch::X86),
+        (Arch::Arm64, Some(32)) | (Arch::Universal2, _) => None,
+        (arch, _) => Some(arch),
+    }
+}
+
+/// Platform tags wheels are selected with, for the interpreter and for each
+/// package forced to another architecture.
+#[derive(Debug, Clone)]
+pub struct WheelTargets {
+    interpreter: Option<Arch>,
+    tags: Vec<String>,
+    overrides: BTreeMap<String, (Arch, Vec<String>)>,
+}
+
+impl WheelTargets {
+    /// Targets for an interpreter running as `interpreter` (`None`: the
+    /// host's architecture) with the `[tool.pybun.arch]` table `overrides`.
+    pub fn new(interpreter: Option<Arch>, overrides: &BTreeMap<String, String>) -> Result<Self> {
+        let tags = interpreter.map_or_else(crate::resolver::current_platform_tags, platform_tags);
+        let mut forced = BTreeMap::new();
+        for (package, value) in overrides {
+            let arch = Arch::parse(value).ok_or_else(|| ArchError::Unknown {
+                package: package.clone(),
+                value: value.clone(),
+            })?;
+            let tags = platform_tags(arch);
+            if tags.iter().all(|t| t == "any") {
+                return Err(ArchError::Unsupported {
+                    package: package.clone(),
+                    arch,
+                    os: host_os_name(),
+                });
+            }
+            forced.insert(normalize_project_name(package), (arch, tags));
+        }
+        Ok(Self {
+            interpreter,
+            tags,
+            overrides: forced,
+        })
+    }
+
+    /// Architecture the interpreter runs as, when known.
+    pub fn interpreter(&self) -> Option<Arch> {
+        self.interpreter
+    }
+
+    /// Platform tags of the interpreter, most specific first.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Platform tags `package` is selected with.
+    pub fn tags_for(&self, package: &str) -> &[String] {
+        self.overrides
+            .get(&normalize_project_name(package))
+            .map_or(&self.tags, |(_, tags)| tags)
+    }
+
+    /// Architecture `package` is forced to, if any.
+    pub fn override_for(&self, package: &str) -> Option<Arch> {
+        self.overrides
+            .get(&normalize_project_name(package))
+            .map(|(arch, _)| *arch)
+    }
+
+    /// Architecture of a wheel matched on `platform_tag` when the
+    /// interpreter cannot load it.
+    pub fn mismatch(&self, platform_tag: &str) -> Option<Arch> {
+        let interpreter = self.interpreter.or_else(Arch::host)?;
+        Arch::of_platform_tag(platform_tag).filter(|wheel| !wheel.loads_on(interpreter))
+    }
+}
+
+/// Platform tags for `arch` on the host OS, most specific first; only `any`
+/// when the OS has no wheels for `arch`.
+pub fn platform_tags(arch: Arch) -> Vec<String> {
+    if Some(arch) == Arch::host() {
+        return crate::resolver::current_platform_tags();
+    }
+    crate::tags::with_legacy_tags(os_platform_tags(arch), legacy_platform(arch))
+}
+
+#[allow(unused_variables)]
+fn os_platform_tags(arch: Arch) -> Vec<String> {
+    #[cfg(target_os = "macos")]
+    {
+        let (major, minor) = crate::runtime::macos_version();
+        return match arch {
+            Arch::Arm64 => crate::runtime::pep425_macos_arm64_tags(major, minor),
+            Arch::X86_64 => crate::runtime::pep425_macos_x86_64_tags(major, minor),
+            // x86_64 tags reach back to 10.9, the oldest universal2 target.
+            Arch::Universal2 => crate::runtime::pep425_macos_x86_64_tags(major, minor)
+                .into_iter()
+                .filter(|t| t.ends_with("_universal2"))
+                .collect(),
+            Arch::X86 => Vec::new(),
+        };
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let name = match arch {
+            Arch::X86_64 => "x86_64",
+            Arch::Arm64 => "aarch64",
+            Arch::X86 => "i686",
+            Arch::Universal2 => return Vec::new(),
+        };
+        return crate::tags::linux_platform_tags(name, crate::tags::host_libc());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        return match arch {
+            Arch::X86_64 => vec!["win_amd64".to_string()],
+            Arch::Arm64 => vec!["win_arm64".to_string()],
+            Arch::X86 => vec!["win32".to_string()],
+            Arch::Universal2 => Vec::new(),
+        };
+    }
+
+    #[allow(unreachable_code)]
+    Vec::new()
+}
+
+/// Legacy JSON-index platform of `arch` on the host OS.
+fn legacy_platform(arch: Arch) -> Option<Platform> {
+    match (host_os_name(), arch) {
+        ("macOS", Arch::Arm64) => Some(Platform::MacOSArm64),
+        ("macOS", Arch::X86_64) => Some(Platform::MacOSX64),
+        ("Linux", Arch::X86_64) => Some(
+            if matches!(
+                crate::tags::host_libc(),
+                Some(crate::tags::Libc::Musl { .. })
+            ) {
+                Platform::LinuxX64Musl
+            } else {
+                Platform::LinuxX64Gnu
+            },
+        ),
+        ("Linux", Arch::Arm64) => Some(Platform::LinuxArm64Gnu),
+        ("Windows", Arch::X86_64) => Some(Platform::WindowsX64),
+        _ => None,
+    }
+}
+
+fn host_os_name() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "macOS",
+        "linux" => "Linux",
+        "windows" => "Windows",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_architecture_aliases() {
+        assert_eq!(Arch::parse("AMD64"), Some(Arch::X86_64));
+        assert_eq!(Arch::parse("aarch64"), Some(Arch::Arm64));
+        assert_eq!(Arch::parse("arm64"), Some(Arch::Arm64));
+        assert_eq!(Arch::parse("i686"), Some(Arch::X86));
+        assert_eq!(Arch::parse("universal2"), Some(Arch::Universal2));
+        assert_eq!(Arch::parse("ppc64le"), None);
+    }
+
+    #[test]
+    fn probe_follows_the_interpreter_not_the_os() {
+        // x86_64 Python under Rosetta on Apple Silicon.
+        assert_eq!(
+            parse_interpreter_probe("x86_64\nmacosx-10.9-universal2\n64\n"),
+            Some(Arch::X86_64)
+        );
+        assert_eq!(
+            parse_interpreter_probe("arm64\nmacosx-11.0-arm64\n64\n"),
+            Some(Arch::Arm64)
+        );
+        // 32-bit Python on 64-bit Windows: platform.machine() is the OS arch.
+        assert_eq!(
+            parse_interpreter_probe("AMD64\nwin32\n32\n"),
+            Some(Arch::X86)
+        );
+        assert_eq!(
+            parse_interpreter_probe("x86_64\nlinux-x86_64\n32\n"),
+            Some(Arch::X86)
+        );
+        assert_eq!(parse_interpreter_probe("Python 3.12.5\n"), None);
+        assert_eq!(parse_interpreter_probe(""), None);
+    }
+
+    #[test]
+    fn platform_tag_architectures() {
+        assert_eq!(
+            Arch::of_platform_tag("manylinux_2_17_x86_64"),
+            Some(Arch::X86_64)
+        );
+        assert_eq!(
+            Arch::of_platform_tag("musllinux_1_2_aarch64"),
+            Some(Arch::Arm64)
+        );
+        assert_eq!(
+            Arch::of_platform_tag("macosx_11_0_arm64"),
+            Some(Arch::Arm64)
+        );
+        assert_eq!(
+            Arch::of_platform_tag("macosx_10_9_universal2"),
+            Some(Arch::Universal2)
+        );
+        assert_eq!(Arch::of_platform_tag("win32"), Some(Arch::X86));
+        assert_eq!(Arch::of_platform_tag("macos_x64"), Some(Arch::X86_64));
+        assert_eq!(Arch::of_platform_tag("any"), None);
+        assert_eq!(Arch::of_platform_tag("linux"), None);
+    }
+
+    #[test]
+    fn universal2_loads_on_both_mac_arches() {
+        assert!(Arch::Universal2.loads_on(Arch::Arm64));
+        assert!(Arch::Universal2.loads_on(Arch::X86_64));
+        assert!(!Arch::X86_64.loads_on(Arch::Arm64));
+        assert!(!Arch::Arm64.loads_on(Arch::X86_64));
+    }
+
+    #[test]
+    fn host_interpreter_keeps_host_tags() {
+        let targets = WheelTargets::new(Arch::host(), &BTreeMap::new()).unwrap();
+        assert_eq!(targets.tags(), crate::resolver::current_platform_tags());
+        let unknown = WheelTargets::new(None, &BTreeMap::new()).unwrap();
+        assert_eq!(unknown.tags(), targets.tags());
+    }
+
+    #[test]
+    fn overrides_are_keyed_by_normalized_name() {
+        let overrides = BTreeMap::from([("Legacy_Ext".to_string(), "x86_64".to_string())]);
+        let targets = WheelTargets::new(Some(Arch::Arm64), &overrides).unwrap();
+        assert_eq!(targets.override_for("legacy-ext"), Some(Arch::X86_64));
+        assert_eq!(targets.tags_for("legacy-ext"), platform_tags(Arch::X86_64));
+        assert_eq!(targets.tags_for("other"), targets.tags());
+        assert_eq!(
+            targets.mismatch("manylinux_2_17_x86_64"),
+            Some(Arch::X86_64)
+        );
+        assert_eq!(targets.mismatch("macosx_11_0_universal2"), None);
+        assert_eq!(targets.mismatch("any"), None);
+    }
+
+    #[test]
+    fn rejects_unknown_override_values() {
+        let overrides = BTreeMap::from([("numpy".to_string(), "sparc".to_string())]);
+        assert_eq!(
+            WheelTargets::new(None, &overrides).unwrap_err(),
+            ArchError::Unknown {
+                package: "numpy".into(),
+                value: "sparc".into()
+            }
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_tags_follow_the_requested_arch() {
+        let tags = platform_tags(Arch::Arm64);
+        assert!(tags.iter().any(|t| t == "manylinux2014_aarch64"));
+        assert!(!tags.iter().any(|t| t.ends_with("x86_64")));
+        let overrides = BTreeMap::from([("pyobjc".to_string(), "universal2".to_string())]);
+        assert!(matches!(
+            WheelTargets::new(None, &overrides),
+            Err(ArchError::Unsupported {
+                arch: Arch::Universal2,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,816 @@
+//! Native archive extraction.
+//!
+//! Extracts `.tar.gz`, `.tar.zst`, `.tar.bz2`, `.tar`, and `.zip` archives
+//! without shelling out to `tar`/`unzip`. Shared by the runtime manager
+//! (managed Python and PyPy downloads), the wheel installer, and `pybun self update`.
+//!
+//! - Tarballs are decompressed and unpacked in a single streaming pass. The
+//!   SHA-256 of the compressed bytes is computed as they are read, so the
+//!   checksum is verified without a second pass over the file. Entries are
+//!   unpacked into a staging directory next to the destination and only moved
+//!   into place once the checksum matches.
+//! - Zip archives need random access to the central directory, so their
+//!   checksum is verified in a streaming pre-pass before extraction.
+//! - Entry paths are validated: absolute paths and `..` components are
+//!   rejected, symlinks may not point outside the destination, and no entry
+//!   may be written through a symlink created by an earlier entry.
+//! - On Windows, destination paths are written with the `\\?\` prefix so deep
+//!   trees (site-packages, CPython's `lib/`) are not limited to `MAX_PATH`, and
+//!   symlinks fall back to copies when the process lacks symlink privileges.
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+use zip::ZipArchive;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("unsupported archive format: {0}")]
+    UnsupportedFormat(PathBuf),
+    #[error("invalid zstd stream: {0}")]
+    Zstd(String),
+    #[error("checksum mismatch for {path} (expected {expected}, got {actual})")]
+    ChecksumMismatch {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    #[error("refusing to extract unsafe entry '{0}'")]
+    UnsafePath(String),
+}
+
+pub type Result<T> = std::result::Result<T, ArchiveError>;
+
+/// Supported archive formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarZst,
+    TarBz2,
+    Tar,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the file name (wheels are zip archives).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") || name.ends_with(".whl") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(ArchiveFormat::TarZst)
+        } else if name.ends_with(".tar.bz2") || name.ends_with(".tbz2") {
+            Some(ArchiveFormat::TarBz2)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Progress snapshot reported while extracting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractProgress {
+    /// Compressed bytes consumed so far.
+    pub bytes_read: u64,
+    /// Size of the archive file.
+    pub total_bytes: u64,
+    /// Entries written so far.
+    pub entries: u64,
+}
+
+impl ExtractProgress {
+    /// Completion percentage (0-100).
+    pub fn percent(&self) -> u8 {
+        if self.total_bytes == 0 {
+            return 100;
+        }
+        ((self.bytes_read.min(self.total_bytes) * 100) / self.total_bytes) as u8
+    }
+}
+
+/// Summary of a completed extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractSummary {
+    pub format: ArchiveFormat,
+    /// Files, directories, and links written.
+    pub entries: u64,
+    /// Uncompressed bytes written to disk.
+    pub bytes_written: u64,
+    /// SHA-256 of the archive file.
+    pub sha256: String,
+}
+
+/// Options for [`extract`].
+#[derive(Default)]
+pub struct ExtractOptions<'a> {
+    /// Expected SHA-256 (hex) of the archive; extraction fails and leaves the
+    /// destination untouched on mismatch.
+    pub expected_sha256: Option<&'a str>,
+    /// Called after each entry is written.
+    pub on_progress: Option<&'a mut dyn FnMut(ExtractProgress)>,
+}
+
+/// Extract `archive` into `dest`, detecting the format from the file name.
+pub fn extract(archive: &Path, dest: &Path, options: ExtractOptions<'_>) -> Result<ExtractSummary> {
+    let format = ArchiveFormat::from_path(archive)
+        .ok_or_else(|| ArchiveError::UnsupportedFormat(archive.to_path_buf()))?;
+    extract_as(format, archive, dest, options)
+}
+
+/// Extract `archive` into `dest` as `format`.
+pub fn extract_as(
+    format: ArchiveFormat,
+    archive: &Path,
+    dest: &Path,
+    mut options: ExtractOptions<'_>,
+) -> Result<ExtractSummary> {
+    fs::create_dir_all(dest)?;
+    match format {
+        ArchiveFormat::Zip => extract_zip(archive, dest, &mut options),
+        _ => extract_tar(format, archive, dest, &mut options),
+    }
+}
+
+/// Streaming SHA-256 of a file.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut reader = HashingReader::new(fs::File::open(path)?);
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(reader.finish())
+}
+
+fn verify(path: &Path, expected: Option<&str>, actual: &str) -> Result<()> {
+    match expected {
+        Some(expected) if !expected.eq_ignore_ascii_case(actual) => {
+            Err(ArchiveError::ChecksumMismatch {
+                path: path.to_path_buf(),
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reader adapter that hashes and counts the bytes passing through it.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    bytes: std::rc::Rc<std::cell::Cell<u64>>,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            bytes: Default::default(),
+        }
+    }
+
+    fn finish(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.bytes.set(self.bytes.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// Decompressing reader for the supported tar flavours. Owns the hashing
+/// reader so the hash can be recovered after the tar stream is exhausted.
+enum Decoder<R: Read> {
+    Plain(R),
+    Gz(Box<flate2::read::GzDecoder<R>>),
+    Zst(Box<ruzstd::decoding::StreamingDecoder<R, ruzstd::decoding::FrameDecoder>>),
+    Bz2(bzip2::read::MultiBzDecoder<R>),
+}
+
+impl<R: Read> Decoder<R> {
+    fn into_inner(self) -> R {
+        match self {
+            Decoder::Plain(r) => r,
+            Decoder::Gz(d) => d.into_inner(),
+            Decoder::Zst(d) => d.into_inner(),
+            Decoder::Bz2(d) => d.into_inner(),
+        }
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Decoder::Plain(r) => r.read(buf),
+            Decoder::Gz(d) => d.read(buf),
+            Decoder::Zst(d) => d.read(buf),
+            Decoder::Bz2(d) => d.read(buf),
+        }
+    }
+}
+
+fn extract_tar(
+    format: ArchiveFormat,
+    archive: &Path,
+    dest: &Path,
+    options: &mut ExtractOptions<'_>,
+) -> Result<ExtractSummary> {
+    let file = fs::File::open(archive)?;
+    let total_bytes = file.metadata()?.len();
+    let reader = HashingReader::new(io::BufReader::new(file));
+    let bytes_read = reader.bytes.clone();
+    let decoder = match format {
+        ArchiveFormat::TarGz => Decoder::Gz(Box::new(flate2::read::GzDecoder::new(reader))),
+        ArchiveFormat::TarZst => Decoder::Zst(Box::new(
+            ruzstd::decoding::StreamingDecoder::new(reader)
+                .map_err(|e| ArchiveError::Zstd(e.to_string()))?,
+        )),
+        ArchiveFormat::TarBz2 => Decoder::Bz2(bzip2::read::MultiBzDecoder::new(reader)),
+        _ => Decoder::Plain(reader),
+    };
+
+    // Unpack into a staging directory so a checksum mismatch (only known
+    // once the whole stream has been read) never leaves partial output.
+    let staging = tempfile::Builder::new()
+        .prefix(".pybun-extract-")
+        .tempdir_in(dest)?;
+    let mut tar = tar::Archive::new(decoder);
+    let mut entries = 0u64;
+    let mut bytes_written = 0u64;
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let rel = safe_relative_path(&entry.path()?)?;
+        let Some(rel) = rel else { continue };
+        let out_path = staging.path().join(&rel);
+        let entry_type = entry.header().entry_type();
+        prepare_entry_path(staging.path(), &rel)?;
+
+        if entry_type.is_dir() {
+            fs::create_dir_all(long_path(&out_path))?;
+        } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let target = entry
+                .link_name()?
+                .ok_or_else(|| ArchiveError::UnsafePath(rel.display().to_string()))?
+                .into_owned();
+            create_parent(&out_path)?;
+            if entry_type.is_symlink() {
+                create_symlink(staging.path(), &rel, &target)?;
+            } else {
+                let source_rel = safe_relative_path(&target)?
+                    .ok_or_else(|| ArchiveError::UnsafePath(target.display().to_string()))?;
+                reject_symlinked_parent(staging.path(), &source_rel)?;
+                let source = staging.path().join(&source_rel);
+                if is_symlink(&source) {
+                    return Err(ArchiveError::UnsafePath(target.display().to_string()));
+                }
+                if fs::hard_link(long_path(&source), long_path(&out_path)).is_err() {
+                    fs::copy(long_path(&source), long_path(&out_path))?;
+                }
+            }
+        } else if entry_type.is_file() || entry_type == tar::EntryType::Continuous {
+            create_parent(&out_path)?;
+            let mut out = fs::File::create(long_path(&out_path))?;
+            bytes_written += io::copy(&mut entry, &mut out)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                // Setuid, setgid and sticky bits are dropped, as pip does.
+                if let Ok(mode) = entry.header().mode() {
+                    fs::set_permissions(&out_path, fs::Permissions::from_mode(mode & 0o777))?;
+                }
+            }
+        } else {
+            // Devices, FIFOs, and PAX/GNU metadata records are not materialized.
+            continue;
+        }
+
+        entries += 1;
+        if let Some(callback) = options.on_progress.as_mut() {
+            callback(ExtractProgress {
+                bytes_read: bytes_read.get(),
+                total_bytes,
+                entries,
+            });
+        }
+    }
+
+    // Drain trailing padding so the hash covers the whole file.
+    let mut reader = tar.into_inner().into_inner();
+    io::copy(&mut reader, &mut io::sink())?;
+    let sha256 = reader.finish();
+    verify(archive, options.expected_sha256, &sha256)?;
+
+    move_contents(staging.path(), dest)?;
+    Ok(ExtractSummary {
+        format,
+        entries,
+        bytes_written,
+        sha256,
+    })
+}
+
+fn extract_zip(
+    archive: &Path,
+    dest: &Path,
+    options: &mut ExtractOptions<'_>,
+) -> Result<ExtractSummary> {
+    let sha256 = sha256_file(archive)?;
+    verify(archive, options.expected_sha256, &sha256)?;
+
+    let file = fs::File::open(archive)?;
+    let total_bytes = file.metadata()?.len();
+    let mut zip = ZipArchive::new(io::BufReader::new(file))?;
+    let mut entries = 0u64;
+    let mut bytes_written = 0u64;
+    let mut compressed = 0u64;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        compressed += entry.compressed_size();
+        let Some(rel) = entry.enclosed_name() else {
+            return Err(ArchiveError::UnsafePath(entry.name().to_string()));
+        };
+        let out_path = dest.join(rel);
+        if entry.is_dir() {
+            fs::create_dir_all(long_path(&out_path))?;
+        } else {
+            create_parent(&out_path)?;
+            let mut out = fs::File::create(long_path(&out_path))?;
+            bytes_written += io::copy(&mut entry, &mut out)?;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = entry.unix_mode() {
+                fs::set_permissions(&out_path, fs::Permissions::from_mode(mode & 0o777))?;
+            }
+        }
+
+        entries += 1;
+        if let Some(callback) = options.on_progress.as_mut() {
+            callback(ExtractProgress {
+                bytes_read: compressed,
+                total_bytes,
+                entries,
+            });
+        }
+    }
+
+    Ok(ExtractSummary {
+        format: ArchiveFormat::Zip,
+        entries,
+        bytes_written,
+        sha256,
+    })
+}
+
+/// Validate an entry path. Returns `None` for entries that resolve to the
+/// archive root (e.g. `./`).
+fn safe_relative_path(path: &Path) -> Result<Option<PathBuf>> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            _ => return Err(ArchiveError::UnsafePath(path.display().to_string())),
+        }
+    }
+    Ok((!out.as_os_str().is_empty()).then_some(out))
+}
+
+/// Create a symlink at `root/rel` pointing to `target`, refusing targets that
+/// escape `root`.
+fn create_symlink(root: &Path, rel: &Path, target: &Path) -> Result<()> {
+    if target.is_absolute() {
+        return Err(ArchiveError::UnsafePath(format!(
+            "{} -> {}",
+            rel.display(),
+            target.display()
+        )));
+    }
+    // Resolve the target lexically relative to the link's directory.
+    let mut depth: Vec<Component<'_>> = rel
+        .parent()
+        .map(|p| p.components().collect())
+        .unwrap_or_default();
+    for component in target.components() {
+        match component {
+            Component::ParentDir => {
+                // `..` after a link climbs from wherever the link points,
+                // not from its lexical parent.
+                let prefix: PathBuf = depth.iter().collect();
+                if !prefix.as_os_str().is_empty() && is_symlink(&root.join(&prefix)) {
+                    return Err(ArchiveError::UnsafePath(format!(
+                        "{} -> {}",
+                        rel.display(),
+                        target.display()
+                    )));
+                }
+                if depth.pop().is_none() {
+                    return Err(ArchiveError::UnsafePath(format!(
+                        "{} -> {}",
+                        rel.display(),
+                        target.display()
+                    )));
+                }
+            }
+            Component::Normal(_) => depth.push(component),
+            Component::CurDir => {}
+            _ => {
+                return Err(ArchiveError::UnsafePath(target.display().to_string()));
+            }
+        }
+    }
+
+    let link = root.join(rel);
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, &link)?;
+    }
+    #[cfg(windows)]
+    {
+        let resolved: PathBuf = root.join(depth.iter().collect::<PathBuf>());
+        let created = if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(target, long_path(&link))
+        } else {
+            std::os::windows::fs::symlink_file(target, long_path(&link))
+        };
+        // Creating symlinks needs Developer Mode or elevation; fall back to
+        // copying regular files so the tree remains usable.
+        if created.is_err() && resolved.is_file() {
+            fs::copy(long_path(&resolved), long_path(&link))?;
+        }
+    }
+    Ok(())
+}
+
+/// Make `root/rel` safe to write: none of its parents may be a symlink left by
+/// an earlier entry, and an existing symlink at the path itself is replaced
+/// rather than followed.
+fn prepare_entry_path(root: &Path, rel: &Path) -> Result<()> {
+    reject_symlinked_parent(root, rel)?;
+    let path = root.join(rel);
+    if is_symlink(&path) {
+        fs::remove_file(long_path(&path))?;
+    }
+    Ok(())
+}
+
+fn reject_symlinked_parent(root: &Path, rel: &Path) -> Result<()> {
+    let mut current = root.to_path_buf();
+    for component in rel.parent().into_iter().flat_map(Path::components) {
+        current.push(component);
+        if is_symlink(&current) {
+            return Err(ArchiveError::UnsafePath(rel.display().to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(long_path(path)).is_ok_and(|meta| meta.file_type().is_symlink())
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(long_path(parent))?;
+    }
+    Ok(())
+}
+
+/// Move every top-level entry of `from` into `to`, replacing existing entries.
+fn move_contents(from: &Path, to: &Path) -> Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if let Ok(meta) = fs::symlink_metadata(&target) {
+            if meta.is_dir() {
+                fs::remove_dir_all(long_path(&target))?;
+            } else {
+                fs::remove_file(long_path(&target))?;
+            }
+        }
+        fs::rename(entry.path(), &target)?;
+    }
+    Ok(())
+}
+
+/// Extend `path` past `MAX_PATH` on Windows; identity elsewhere.
+#[cfg(windows)]
+fn long_path(path: &Path) -> PathBuf {
+    let text = path.as_os_str().to_string_lossy();
+    if path.is_absolute() && !text.starts_with(r"\\?\") {
+        PathBuf::from(format!(r"\\?\{text}"))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn tar_bytes(build: impl FnOnce(&mut tar::Builder<Vec<u8>>)) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        build(&mut builder);
+        builder.into_inner().unwrap()
+    }
+
+    fn add_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, data: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, path, data).unwrap();
+    }
+
+    fn sample_tar() -> Vec<u8> {
+        tar_bytes(|b| {
+            add_file(b, "python/bin/python3", b"#!python");
+            add_file(b, "python/lib/os.py", b"import sys\n");
+        })
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn detects_formats_from_names() {
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("cpython.tar.zst")),
+            Some(ArchiveFormat::TarZst)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("x.TGZ")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("pkg-1.0-py3-none-any.whl")),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(ArchiveFormat::from_path(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn extracts_tar_gz_with_checksum_and_progress() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("python.tar.gz");
+        let bytes = gzip(&sample_tar());
+        fs::write(&archive, &bytes).unwrap();
+        let expected = hex::encode(Sha256::digest(&bytes));
+
+        let dest = temp.path().join("out");
+        let mut updates = Vec::new();
+        let mut on_progress = |p: ExtractProgress| updates.push(p);
+        let summary = extract(
+            &archive,
+            &dest,
+            ExtractOptions {
+                expected_sha256: Some(&expected),
+                on_progress: Some(&mut on_progress),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(summary.entries, 2);
+        assert_eq!(summary.sha256, expected);
+        assert_eq!(
+            fs::read_to_string(dest.join("python/lib/os.py")).unwrap(),
+            "import sys\n"
+        );
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates.last().unwrap().entries, 2);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dest.join("python/bin/python3"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tar_entries_lose_setuid_setgid_and_sticky_bits() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("pkg.tar");
+        fs::write(
+            &archive,
+            tar_bytes(|b| {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(2);
+                header.set_mode(0o7755);
+                header.set_cksum();
+                b.append_data(&mut header, "pkg/tool", &b"#!"[..]).unwrap();
+            }),
+        )
+        .unwrap();
+
+        let dest = temp.path().join("out");
+        extract(&archive, &dest, ExtractOptions::default()).unwrap();
+        let mode = fs::metadata(dest.join("pkg/tool"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o755);
+    }
+
+    #[test]
+    fn extracts_tar_zst() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("python.tar.zst");
+        let compressed = ruzstd::encoding::compress_to_vec(
+            &sample_tar()[..],
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+        fs::write(&archive, compressed).unwrap();
+
+        let dest = temp.path().join("out");
+        extract(&archive, &dest, ExtractOptions::default()).unwrap();
+        assert!(dest.join("python/bin/python3").is_file());
+    }
+
+    #[test]
+    fn extracts_tar_bz2() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("pypy.tar.bz2");
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+        encoder.write_all(&sample_tar()).unwrap();
+        fs::write(&archive, encoder.finish().unwrap()).unwrap();
+
+        let dest = temp.path().join("out");
+        extract(&archive, &dest, ExtractOptions::default()).unwrap();
+        assert!(dest.join("python/bin/python3").is_file());
+    }
+
+    #[test]
+    fn checksum_mismatch_leaves_destination_untouched() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("python.tar.gz");
+        fs::write(&archive, gzip(&sample_tar())).unwrap();
+        let dest = temp.path().join("out");
+
+        let err = extract(
+            &archive,
+            &dest,
+            ExtractOptions {
+                expected_sha256: Some("00"),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ArchiveError::ChecksumMismatch { .. }));
+        assert_eq!(fs::read_dir(&dest).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn rejects_path_traversal() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("evil.tar");
+        // tar::Builder refuses `..`, so write the name into the header directly.
+        let bytes = tar_bytes(|b| {
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..9].copy_from_slice(b"../evil.t");
+            header.set_size(1);
+            header.set_mode(0o644);
+            header.set_cksum();
+            b.append(&header, &b"x"[..]).unwrap();
+        });
+        fs::write(&archive, bytes).unwrap();
+
+        let err = extract(
+            &archive,
+            &temp.path().join("out"),
+            ExtractOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ArchiveError::UnsafePath(_)));
+        assert!(!temp.path().join("evil.t").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_must_stay_inside_destination() {
+        let temp = tempdir().unwrap();
+        let symlink_tar = |target: &str| {
+            tar_bytes(|b| {
+                add_file(b, "python/bin/python3.12", b"bin");
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                b.append_link(&mut header, "python/bin/python3", target)
+                    .unwrap();
+            })
+        };
+
+        let ok = temp.path().join("ok.tar");
+        fs::write(&ok, symlink_tar("python3.12")).unwrap();
+        let dest = temp.path().join("ok");
+        extract(&ok, &dest, ExtractOptions::default()).unwrap();
+        assert_eq!(
+            fs::read_link(dest.join("python/bin/python3")).unwrap(),
+            Path::new("python3.12")
+        );
+
+        let evil = temp.path().join("evil.tar");
+        fs::write(&evil, symlink_tar("../../../etc/passwd")).unwrap();
+        let err = extract(&evil, &temp.path().join("evil"), ExtractOptions::default()).unwrap_err();
+        assert!(matches!(err, ArchiveError::UnsafePath(_)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_chains_cannot_redirect_later_entries() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("chain.tar");
+        let link = |b: &mut tar::Builder<Vec<u8>>, path: &str, target: &str| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            b.append_link(&mut header, path, target).unwrap();
+        };
+        fs::write(
+            &archive,
+            tar_bytes(|b| {
+                add_file(b, "d/keep", b"x");
+                link(b, "d/b", "..");
+                link(b, "d/b/c", "..");
+                link(b, "d/b/c/x", "../..");
+                add_file(b, "d/b/c/x/PWNED", b"pwned");
+            }),
+        )
+        .unwrap();
+
+        let dest = temp.path().join("out/dest");
+        fs::create_dir_all(&dest).unwrap();
+        let err = extract(&archive, &dest, ExtractOptions::default()).unwrap_err();
+        assert!(matches!(err, ArchiveError::UnsafePath(_)));
+        assert!(!temp.path().join("PWNED").exists());
+        assert!(!temp.path().join("out/PWNED").exists());
+        assert!(!dest.join("PWNED").exists());
+    }
+
+    #[test]
+    fn extracts_zip_and_verifies_checksum() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("bundle.zip");
+        {
+            let mut writer = zip::ZipWriter::new(fs::File::create(&archive).unwrap());
+            writer
+                .start_file("pkg/__init__.py", zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"VERSION = 1\n").unwrap();
+            writer.finish().unwrap();
+        }
+        let expected = sha256_file(&archive).unwrap();
+
+        let dest = temp.path().join("out");
+        let summary = extract(
+            &archive,
+            &dest,
+            ExtractOptions {
+                expected_sha256: Some(&expected),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(summary.entries, 1);
+        assert!(dest.join("pkg/__init__.py").is_file());
+
+        let err = extract(
+            &archive,
+            &temp.path().join("other"),
+            ExtractOptions {
+                expected_sha256: Some("deadbeef"),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ArchiveError::ChecksumMismatch { .. }));
+    }
+}
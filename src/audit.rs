@@ -4,8 +4,13 @@
 //! `pybun audit` command (`src/commands/maintenance.rs`) so the two surfaces
 //! query the same OSV endpoint, apply the same severity normalization, and
 //! report the same counts (Issue #316, in the spirit of the PR-A3 MCP/CLI
-//! unification goal).
+//! unification goal). The CLI scans the project's locked packages when it has
+//! a lockfile, and keeps OSV's answers in [`AdvisoryDb`] so `pybun --offline
+//! audit` can rescan without the network.
 
+use crate::advisory_db::AdvisoryDb;
+use crate::lockfile::{Lockfile, PackageSource};
+use crate::network_policy::{self, Operation};
 use serde_json::{Value, json};
 use std::path::Path;
 use std::process::Command as ProcessCommand;
@@ -34,13 +39,22 @@ pub struct Vulnerability {
 pub struct AuditReport {
     /// Number of packages submitted to OSV.
     pub scanned: usize,
-    /// Packages OSV did not return a result for (partial/mismatched response).
+    /// Packages OSV did not return a result for (partial/mismatched response),
+    /// or, offline, that the advisory database has no record of.
     pub unscanned: usize,
+    /// `name==version` of each unscanned package.
+    pub unscanned_packages: Vec<String>,
     /// Vulnerabilities at or above the requested severity threshold.
     pub vulnerabilities: Vec<Vulnerability>,
 }
 
 impl AuditReport {
+    fn record_unscanned(&mut self, pkg: &InstalledPackage) {
+        self.unscanned += 1;
+        self.unscanned_packages
+            .push(format!("{}=={}", pkg.name, pkg.version));
+    }
+
     pub fn count_at_severity(&self, severity: &str) -> usize {
         self.vulnerabilities
             .iter()
@@ -58,6 +72,19 @@ impl AuditReport {
     }
 }
 
+/// Registry packages pinned in `lock`. Git and direct-URL packages are
+/// skipped: OSV's PyPI ecosystem only knows index releases.
+pub fn locked_packages(lock: &Lockfile) -> Vec<InstalledPackage> {
+    lock.packages
+        .values()
+        .filter(|p| matches!(p.source, PackageSource::Registry { .. }))
+        .map(|p| InstalledPackage {
+            name: p.name.clone(),
+            version: p.version.clone(),
+        })
+        .collect()
+}
+
 /// Default OSV endpoint, overridable via `PYBUN_OSV_URL` (tests point this at
 /// a mock server).
 pub fn default_osv_url() -> String {
@@ -120,14 +147,55 @@ pub async fn scan_for_vulnerabilities(
     osv_url: &str,
     severity_threshold: &str,
 ) -> Result<AuditReport, String> {
-    let threshold_level = severity_level(severity_threshold);
+    let results = query_osv(packages, osv_url).await?;
+    Ok(build_report(
+        packages,
+        &results,
+        severity_level(severity_threshold),
+    ))
+}
 
+/// Like [`scan_for_vulnerabilities`], but records every OSV answer in `db`.
+/// With `offline`, OSV is not contacted at all: packages are answered from
+/// `db`, and those it has no record for are reported as unscanned.
+pub async fn scan_with_advisory_db(
+    packages: &[InstalledPackage],
+    osv_url: &str,
+    severity_threshold: &str,
+    db: &AdvisoryDb,
+    offline: bool,
+) -> Result<AuditReport, String> {
+    let results = if offline {
+        packages
+            .iter()
+            .map(|p| db.get(&p.name, &p.version).map(|record| record.vulns))
+            .collect()
+    } else {
+        let results = query_osv(packages, osv_url).await?;
+        for (pkg, vulns) in packages.iter().zip(&results) {
+            // The cache only serves later offline scans; failing to update it
+            // must not fail this one.
+            if let Some(vulns) = vulns {
+                let _ = db.store(&pkg.name, &pkg.version, vulns);
+            }
+        }
+        results
+    };
+    Ok(build_report(
+        packages,
+        &results,
+        severity_level(severity_threshold),
+    ))
+}
+
+/// OSV's vulnerability list for each of `packages`, in order; `None` where
+/// OSV returned no result for a package.
+async fn query_osv(
+    packages: &[InstalledPackage],
+    osv_url: &str,
+) -> Result<Vec<Option<Vec<Value>>>, String> {
     if packages.is_empty() {
-        return Ok(AuditReport {
-            scanned: 0,
-            unscanned: 0,
-            vulnerabilities: Vec::new(),
-        });
+        return Ok(Vec::new());
     }
 
     let queries: Vec<Value> = packages
@@ -143,8 +211,10 @@ pub async fn scan_for_vulnerabilities(
         })
         .collect();
 
-    let client = reqwest::Client::builder()
+    network_policy::check_url(Operation::Audit, osv_url).map_err(|e| e.to_string())?;
+    let client = crate::http_config::client_builder()
         .timeout(std::time::Duration::from_secs(30))
+        .redirect(network_policy::redirect_policy(Operation::Audit))
         .build()
         .map_err(|e| e.to_string())?;
 
@@ -168,59 +238,78 @@ pub async fn scan_for_vulnerabilities(
         .await
         .map_err(|e| format!("Failed to parse OSV response: {}", e))?;
 
-    // Process OSV results — one result entry per queried package (same order).
-    // Per OSV spec, results.len() == queries.len(); we use zip so mismatches
-    // (e.g., partial error responses or mock servers) are handled gracefully.
-    let empty_vec = vec![];
+    // One result entry per queried package (same order). Per OSV spec,
+    // results.len() == queries.len(); packages past the end of a short
+    // (partial/mismatched) response are left unanswered.
     let results = osv_data
         .get("results")
         .and_then(|r| r.as_array())
-        .unwrap_or(&empty_vec);
-
-    let unscanned = packages.len().saturating_sub(results.len());
-
-    let mut vulnerabilities: Vec<Vulnerability> = Vec::new();
-
-    for (pkg, result) in packages.iter().zip(results.iter()) {
-        let empty_vulns = vec![];
-        let vulns = result
-            .get("vulns")
-            .and_then(|v| v.as_array())
-            .unwrap_or(&empty_vulns);
-
-        for vuln in vulns {
-            let severity = extract_severity(vuln);
-            if severity_level(&severity) < threshold_level {
-                continue;
-            }
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    Ok((0..packages.len())
+        .map(|i| {
+            results.get(i).map(|result| {
+                result
+                    .get("vulns")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default()
+            })
+        })
+        .collect())
+}
 
-            let vulnerability_id = vuln
-                .get("id")
-                .and_then(|i| i.as_str())
-                .unwrap_or("")
-                .to_string();
-            let description = vuln
-                .get("summary")
-                .and_then(|s| s.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            vulnerabilities.push(Vulnerability {
-                package: pkg.name.clone(),
-                installed_version: pkg.version.clone(),
-                vulnerability_id,
-                severity,
-                description,
-                fix_version: extract_fix_version(vuln),
-            });
+fn build_report(
+    packages: &[InstalledPackage],
+    results: &[Option<Vec<Value>>],
+    threshold_level: u8,
+) -> AuditReport {
+    let mut report = AuditReport {
+        scanned: packages.len(),
+        ..AuditReport::default()
+    };
+    for (pkg, vulns) in packages.iter().zip(results) {
+        match vulns {
+            Some(vulns) => collect_vulnerabilities(pkg, vulns, threshold_level, &mut report),
+            None => report.record_unscanned(pkg),
         }
     }
+    report
+}
 
-    Ok(AuditReport {
-        scanned: packages.len(),
-        unscanned,
-        vulnerabilities,
-    })
+fn collect_vulnerabilities(
+    pkg: &InstalledPackage,
+    vulns: &[Value],
+    threshold_level: u8,
+    report: &mut AuditReport,
+) {
+    for vuln in vulns {
+        let severity = extract_severity(vuln);
+        if severity_level(&severity) < threshold_level {
+            continue;
+        }
+
+        let vulnerability_id = vuln
+            .get("id")
+            .and_then(|i| i.as_str())
+            .unwrap_or("")
+            .to_string();
+        let description = vuln
+            .get("summary")
+            .and_then(|s| s.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        report.vulnerabilities.push(Vulnerability {
+            package: pkg.name.clone(),
+            installed_version: pkg.version.clone(),
+            vulnerability_id,
+            severity,
+            description,
+            fix_version: extract_fix_version(vuln),
+        });
+    }
 }
 
 /// Rank a severity string for threshold comparisons. Unknown strings are
@@ -467,6 +556,51 @@ mod tests {
         assert!(report.vulnerabilities.is_empty());
     }
 
+    #[tokio::test]
+    async fn offline_scan_answers_from_advisory_db_and_reports_unknown_as_unscanned() {
+        let temp = tempfile::tempdir().unwrap();
+        let db = AdvisoryDb::with_root(temp.path());
+        db.store(
+            "requests",
+            "2.27.0",
+            &[json!({
+                "id": "GHSA-1",
+                "database_specific": {"severity": "HIGH"},
+                "affected": [{"ranges": [{"events": [{"introduced": "0"}, {"fixed": "2.31.0"}]}]}]
+            })],
+        )
+        .unwrap();
+        db.store("idna", "3.7", &[]).unwrap();
+        let packages = [
+            InstalledPackage {
+                name: "requests".into(),
+                version: "2.27.0".into(),
+            },
+            InstalledPackage {
+                name: "idna".into(),
+                version: "3.7".into(),
+            },
+            InstalledPackage {
+                name: "urllib3".into(),
+                version: "2.2.0".into(),
+            },
+        ];
+
+        // The URL is never contacted offline.
+        let report =
+            scan_with_advisory_db(&packages, "http://127.0.0.1:1/unused", "low", &db, true)
+                .await
+                .unwrap();
+        assert_eq!(report.scanned, 3);
+        assert_eq!(report.unscanned_packages, vec!["urllib3==2.2.0"]);
+        assert_eq!(report.vulnerabilities.len(), 1);
+        assert_eq!(report.vulnerabilities[0].severity, "high");
+        assert_eq!(
+            report.vulnerabilities[0].fix_version.as_deref(),
+            Some("2.31.0")
+        );
+    }
+
     #[test]
     fn list_installed_packages_returns_err_when_python_binary_missing() {
         // A path that doesn't exist should surface as an error, not silently
@@ -489,6 +623,7 @@ mod tests {
         let report = AuditReport {
             scanned: 2,
             unscanned: 0,
+            unscanned_packages: Vec::new(),
             vulnerabilities: vec![
                 Vulnerability {
                     package: "a".into(),
@@ -0,0 +1,948 @@
+//! Stored credentials for package indexes and publishing (`pybun auth`).
+//!
+//! Credentials are scoped to an index or upload URL and, like pip, sent to
+//! every request to that URL's scheme, host, and port (indexes often serve
+//! files outside the index path) and nowhere else. When several logins share
+//! a host, the one whose path is the longest prefix of the request path
+//! wins. Requests whose URL already carries `user:password@` keep those
+//! credentials.
+//!
+//! Secrets live in the OS keychain: the login keychain on macOS (via
+//! `security`), the Secret Service on Linux (via `secret-tool`), and the
+//! Windows Credential Locker (via PowerShell). When no keychain is usable,
+//! e.g. on a headless CI runner, they go to `credentials.enc` under
+//! `$PYBUN_HOME`, encrypted with ChaCha20-Poly1305. Its key is derived from
+//! `PYBUN_AUTH_PASSPHRASE` when set, otherwise it is a random key in
+//! `credentials.key` (mode 0600), which keeps tokens out of scripts and shell
+//! history but does not protect them from other processes of the same user.
+//! `PYBUN_AUTH_BACKEND=keychain|file` forces a backend.
+//!
+//! `auth.json` records which scopes have credentials, the username, and the
+//! backend holding the secret; it never contains secrets.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Credential index under the PyBun home directory.
+pub const AUTH_FILE: &str = "auth.json";
+/// Encrypted secrets for the file backend.
+pub const CREDENTIALS_FILE: &str = "credentials.enc";
+/// Random key for `credentials.enc` when no passphrase is set.
+pub const KEY_FILE: &str = "credentials.key";
+
+/// Forces the storage backend (`keychain` or `file`).
+pub const BACKEND_ENV: &str = "PYBUN_AUTH_BACKEND";
+/// Passphrase the file backend derives its key from.
+pub const PASSPHRASE_ENV: &str = "PYBUN_AUTH_PASSPHRASE";
+
+/// Username for API tokens, as PyPI and most registries expect.
+pub const TOKEN_USERNAME: &str = "__token__";
+/// Default scope of `pybun auth token set`.
+pub const DEFAULT_UPLOAD_URL: &str = "https://upload.pypi.org/legacy/";
+
+const KEYCHAIN_SERVICE: &str = "pybun";
+const AUTH_VERSION: u32 = 1;
+const PBKDF2_ITERATIONS: u32 = 200_000;
+const AAD: &[u8] = b"pybun-credentials-v1";
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("invalid URL `{0}`: expected an http(s) index or upload URL")]
+    InvalidUrl(String),
+    #[error("failed to determine the PyBun home directory: {0}")]
+    Home(#[from] crate::paths::PathError),
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{path} is corrupt: {message}")]
+    Corrupt { path: PathBuf, message: String },
+    #[error("cannot decrypt {0} (wrong {PASSPHRASE_ENV}, or credentials.key changed)")]
+    Decrypt(PathBuf),
+    #[error("keychain error: {0}")]
+    Keychain(String),
+    #[error("no OS keychain is available (set {BACKEND_ENV}=file to use the encrypted file)")]
+    KeychainUnavailable,
+    #[error("no credentials stored for {0}")]
+    NotFound(String),
+    #[error("the secret is empty")]
+    EmptySecret,
+}
+
+pub type Result<T> = std::result::Result<T, AuthError>;
+
+/// Where a secret is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// The OS keychain.
+    Keychain,
+    /// `credentials.enc` under `$PYBUN_HOME`.
+    File,
+}
+
+impl Backend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Backend::Keychain => "keychain",
+            Backend::File => "file",
+        }
+    }
+
+    /// Backend forced by [`BACKEND_ENV`], if any.
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(BACKEND_ENV)
+            .ok()?
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "keychain" => Some(Backend::Keychain),
+            "file" => Some(Backend::File),
+            _ => None,
+        }
+    }
+}
+
+/// A stored login, without its secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialRecord {
+    /// Normalized URL prefix the credentials apply to.
+    pub scope: String,
+    pub username: String,
+    pub backend: Backend,
+    /// Unix timestamp (seconds) of the login.
+    pub created_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AuthFile {
+    version: u32,
+    credentials: Vec<CredentialRecord>,
+}
+
+/// Normalize `url` to a credential scope: scheme, host, port, and path
+/// without a trailing slash. User info, query, and fragment are dropped.
+pub fn scope_for(url: &str) -> Result<String> {
+    let invalid = || AuthError::InvalidUrl(url.to_string());
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|_| invalid())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid());
+    }
+    let host = parsed.host_str().ok_or_else(invalid)?;
+    let port = parsed
+        .port()
+        .map(|port| format!(":{port}"))
+        .unwrap_or_default();
+    Ok(format!(
+        "{}://{}{}{}",
+        parsed.scheme(),
+        host,
+        port,
+        parsed.path().trim_end_matches('/')
+    ))
+}
+
+/// Whether `url` is under `scope`'s path, as opposed to elsewhere on the
+/// same host. `None` when the credentials don't apply to `url` at all.
+pub fn scope_matches(scope: &str, url: &reqwest::Url) -> Option<bool> {
+    let target = scope_for(url.as_str()).ok()?;
+    let origin = |s: &str| {
+        let after_scheme = s.find("://").map_or(0, |i| i + 3);
+        let end = s[after_scheme..]
+            .find('/')
+            .map_or(s.len(), |i| after_scheme + i);
+        s[..end].to_string()
+    };
+    if origin(&target) != origin(scope) {
+        return None;
+    }
+    Some(
+        target == scope
+            || target
+                .strip_prefix(scope)
+                .is_some_and(|rest| rest.starts_with('/')),
+    )
+}
+
+/// Credential store rooted at a PyBun home directory.
+#[derive(Debug, Clone)]
+pub struct AuthStore {
+    dir: PathBuf,
+}
+
+impl AuthStore {
+    /// Store under the PyBun home directory (see [`crate::paths::PyBunPaths`]).
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_dir(crate::paths::PyBunPaths::new()?.root()))
+    }
+
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn index_path(&self) -> PathBuf {
+        self.dir.join(AUTH_FILE)
+    }
+
+    pub fn credentials_path(&self) -> PathBuf {
+        self.dir.join(CREDENTIALS_FILE)
+    }
+
+    /// Stored logins, sorted by scope.
+    pub fn list(&self) -> Result<Vec<CredentialRecord>> {
+        let mut credentials = self.read_index()?.credentials;
+        credentials.sort_by(|a, b| a.scope.cmp(&b.scope));
+        Ok(credentials)
+    }
+
+    /// Store `username`/`secret` for `url`, replacing any previous login for
+    /// the same scope. Without a `backend`, the keychain is tried first and
+    /// the encrypted file is the fallback; the returned note says why the
+    /// fallback was used.
+    pub fn login(
+        &self,
+        url: &str,
+        username: &str,
+        secret: &str,
+        backend: Option<Backend>,
+    ) -> Result<(CredentialRecord, Option<String>)> {
+        let scope = scope_for(url)?;
+        let secret = secret.trim_end_matches(['\r', '\n']);
+        if secret.is_empty() {
+            return Err(AuthError::EmptySecret);
+        }
+        let (backend, note) = match backend.or_else(Backend::from_env) {
+            Some(Backend::Keychain) => {
+                if !keychain::available() {
+                    return Err(AuthError::KeychainUnavailable);
+                }
+                keychain::store(&scope, secret).map_err(AuthError::Keychain)?;
+                (Backend::Keychain, None)
+            }
+            Some(Backend::File) => {
+                self.store_file_secret(&scope, Some(secret))?;
+                (Backend::File, None)
+            }
+            None => {
+                let keychain_result = if keychain::available() {
+                    keychain::store(&scope, secret)
+                } else {
+                    Err("no OS keychain is available".to_string())
+                };
+                match keychain_result {
+                    Ok(()) => (Backend::Keychain, None),
+                    Err(reason) => {
+                        self.store_file_secret(&scope, Some(secret))?;
+                        (
+                            Backend::File,
+                            Some(format!(
+                                "{reason}; stored the secret in {} instead",
+                                self.credentials_path().display()
+                            )),
+                        )
+                    }
+                }
+            }
+        };
+
+        let record = CredentialRecord {
+            scope: scope.clone(),
+            username: username.to_string(),
+            backend,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        };
+        let previous = self.update_index(|credentials| {
+            let previous = credentials
+                .iter()
+                .position(|c| c.scope == scope)
+                .map(|i| credentials.remove(i));
+            credentials.push(record.clone());
+            previous
+        })?;
+        // Don't leave the old secret behind in the other backend.
+        if let Some(previous) = previous
+            && previous.backend != backend
+        {
+            let _ = self.delete_secret(&previous);
+        }
+        Ok((record, note))
+    }
+
+    /// Remove the login for `url`'s scope, returning it.
+    pub fn logout(&self, url: &str) -> Result<CredentialRecord> {
+        let scope = scope_for(url)?;
+        let removed = self.update_index(|credentials| {
+            credentials
+                .iter()
+                .position(|c| c.scope == scope)
+                .map(|i| credentials.remove(i))
+        })?;
+        let record = removed.ok_or(AuthError::NotFound(scope))?;
+        self.delete_secret(&record)?;
+        Ok(record)
+    }
+
+    /// The login whose scope is the longest prefix of `url`.
+    pub fn find(&self, url: &reqwest::Url) -> Result<Option<CredentialRecord>> {
+        Ok(best_match(&self.list()?, url).cloned())
+    }
+
+    /// The secret for `record`.
+    pub fn secret(&self, record: &CredentialRecord) -> Result<String> {
+        let secret = match record.backend {
+            Backend::Keychain => keychain::load(&record.scope).map_err(AuthError::Keychain)?,
+            Backend::File => self.read_file_secrets()?.remove(&record.scope),
+        };
+        secret.ok_or_else(|| AuthError::NotFound(record.scope.clone()))
+    }
+
+    fn delete_secret(&self, record: &CredentialRecord) -> Result<()> {
+        match record.backend {
+            Backend::Keychain => keychain::delete(&record.scope).map_err(AuthError::Keychain),
+            Backend::File => self.store_file_secret(&record.scope, None),
+        }
+    }
+
+    fn read_index(&self) -> Result<AuthFile> {
+        let path = self.index_path();
+        match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| AuthError::Corrupt {
+                path,
+                message: e.to_string(),
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AuthFile::default()),
+            Err(source) => Err(AuthError::Io { path, source }),
+        }
+    }
+
+    fn update_index<T>(&self, f: impl FnOnce(&mut Vec<CredentialRecord>) -> T) -> Result<T> {
+        let mut file = self.read_index()?;
+        let result = f(&mut file.credentials);
+        file.version = AUTH_VERSION;
+        let data = serde_json::to_vec_pretty(&file).map_err(|e| AuthError::Corrupt {
+            path: self.index_path(),
+            message: e.to_string(),
+        })?;
+        write_private(&self.index_path(), &data)?;
+        Ok(result)
+    }
+
+    /// Set (or with `None`, remove) the file-backend secret for `scope`.
+    fn store_file_secret(&self, scope: &str, secret: Option<&str>) -> Result<()> {
+        let mut secrets = self.read_file_secrets()?;
+        let changed = match secret {
+            Some(secret) => {
+                secrets
+                    .insert(scope.to_string(), secret.to_string())
+                    .as_deref()
+                    != Some(secret)
+            }
+            None => secrets.remove(scope).is_some(),
+        };
+        if !changed {
+            return Ok(());
+        }
+        let plaintext = serde_json::to_vec(&secrets).map_err(|e| AuthError::Corrupt {
+            path: self.credentials_path(),
+            message: e.to_string(),
+        })?;
+        let sealed = self.seal(&plaintext)?;
+        let data = serde_json::to_vec_pretty(&sealed).map_err(|e| AuthError::Corrupt {
+            path: self.credentials_path(),
+            message: e.to_string(),
+        })?;
+        write_private(&self.credentials_path(), &data)
+    }
+
+    fn read_file_secrets(&self) -> Result<BTreeMap<String, String>> {
+        let path = self.credentials_path();
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(source) => return Err(AuthError::Io { path, source }),
+        };
+        let corrupt = |message: String| AuthError::Corrupt {
+            path: path.clone(),
+            message,
+        };
+        let sealed: SealedFile =
+            serde_json::from_slice(&data).map_err(|e| corrupt(e.to_string()))?;
+        let plaintext = self.open(&sealed)?;
+        serde_json::from_slice(&plaintext).map_err(|e| corrupt(e.to_string()))
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> Result<SealedFile> {
+        use base64::Engine;
+        use ring::aead::{Aad, LessSafeKey, NONCE_LEN, Nonce};
+        use ring::rand::{SecureRandom, SystemRandom};
+
+        let rng = SystemRandom::new();
+        let passphrase = passphrase();
+        let mut salt = [0u8; 16];
+        if passphrase.is_some() {
+            rng.fill(&mut salt).map_err(|_| self.crypto_error())?;
+        }
+        let key = self.key(passphrase.as_deref(), &salt, true)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut nonce).map_err(|_| self.crypto_error())?;
+
+        let mut in_out = plaintext.to_vec();
+        LessSafeKey::new(key)
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(AAD),
+                &mut in_out,
+            )
+            .map_err(|_| self.crypto_error())?;
+        let b64 = base64::engine::general_purpose::STANDARD;
+        Ok(SealedFile {
+            version: AUTH_VERSION,
+            kdf: if passphrase.is_some() {
+                Kdf::Passphrase
+            } else {
+                Kdf::Keyfile
+            },
+            salt: passphrase.is_some().then(|| b64.encode(salt)),
+            nonce: b64.encode(nonce),
+            ciphertext: b64.encode(in_out),
+        })
+    }
+
+    fn open(&self, sealed: &SealedFile) -> Result<Vec<u8>> {
+        use base64::Engine;
+        use ring::aead::{Aad, LessSafeKey, Nonce};
+
+        let path = self.credentials_path();
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let decode = |value: &str| {
+            b64.decode(value).map_err(|e| AuthError::Corrupt {
+                path: path.clone(),
+                message: e.to_string(),
+            })
+        };
+        let passphrase = match sealed.kdf {
+            Kdf::Passphrase => Some(passphrase().ok_or(AuthError::Decrypt(path.clone()))?),
+            Kdf::Keyfile => None,
+        };
+        let salt = sealed
+            .salt
+            .as_deref()
+            .map(decode)
+            .transpose()?
+            .unwrap_or_default();
+        let key = self.key(passphrase.as_deref(), &salt, false)?;
+        let nonce = Nonce::try_assume_unique_for_key(&decode(&sealed.nonce)?)
+            .map_err(|_| AuthError::Decrypt(path.clone()))?;
+        let mut in_out = decode(&sealed.ciphertext)?;
+        let plaintext = LessSafeKey::new(key)
+            .open_in_place(nonce, Aad::from(AAD), &mut in_out)
+            .map_err(|_| AuthError::Decrypt(path.clone()))?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// The file-backend key: derived from the passphrase, else read from
+    /// (or, when `create`, generated into) `credentials.key`.
+    fn key(
+        &self,
+        passphrase: Option<&str>,
+        salt: &[u8],
+        create: bool,
+    ) -> Result<ring::aead::UnboundKey> {
+        use ring::aead::{CHACHA20_POLY1305, UnboundKey};
+        use ring::rand::{SecureRandom, SystemRandom};
+
+        let mut key = [0u8; 32];
+        if let Some(passphrase) = passphrase {
+            ring::pbkdf2::derive(
+                ring::pbkdf2::PBKDF2_HMAC_SHA256,
+                NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero"),
+                salt,
+                passphrase.as_bytes(),
+                &mut key,
+            );
+        } else {
+            let path = self.dir.join(KEY_FILE);
+            match fs::read(&path) {
+                Ok(data) if data.len() == key.len() => key.copy_from_slice(&data),
+                Ok(_) => return Err(AuthError::Decrypt(path)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound && create => {
+                    SystemRandom::new()
+                        .fill(&mut key)
+                        .map_err(|_| self.crypto_error())?;
+                    write_private(&path, &key)?;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(AuthError::Decrypt(self.credentials_path()));
+                }
+                Err(source) => return Err(AuthError::Io { path, source }),
+            }
+        }
+        UnboundKey::new(&CHACHA20_POLY1305, &key).map_err(|_| self.crypto_error())
+    }
+
+    fn crypto_error(&self) -> AuthError {
+        AuthError::Decrypt(self.credentials_path())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Kdf {
+    Keyfile,
+    Passphrase,
+}
+
+/// On-disk form of `credentials.enc`.
+#[derive(Debug, Serialize, Deserialize)]
+struct SealedFile {
+    version: u32,
+    kdf: Kdf,
+    #[serde(default)]
+    salt: Option<String>,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty())
+}
+
+fn best_match<'a>(
+    credentials: &'a [CredentialRecord],
+    url: &reqwest::Url,
+) -> Option<&'a CredentialRecord> {
+    credentials
+        .iter()
+        .filter_map(|c| scope_matches(&c.scope, url).map(|under_path| (under_path, c)))
+        .max_by_key(|(under_path, c)| (*under_path, c.scope.len()))
+        .map(|(_, c)| c)
+}
+
+/// Write `data` atomically, readable by the owner only.
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    let io = |source| AuthError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let parent = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent).map_err(io)?;
+    let mut tmp = tempfile::NamedTempFile::new_in(parent).map_err(io)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(tmp.path(), fs::Permissions::from_mode(0o600)).map_err(io)?;
+    }
+    tmp.write_all(data).map_err(io)?;
+    tmp.persist(path).map_err(|e| io(e.error))?;
+    Ok(())
+}
+
+/// Whether an OS keychain can be used on this machine.
+pub fn keychain_available() -> bool {
+    keychain::available()
+}
+
+/// Logins loaded once per process for request authorization.
+struct Authenticator {
+    store: AuthStore,
+    credentials: Vec<CredentialRecord>,
+    secrets: Mutex<HashMap<String, Option<String>>>,
+}
+
+fn authenticator() -> Option<&'static Authenticator> {
+    static AUTH: OnceLock<Option<Authenticator>> = OnceLock::new();
+    AUTH.get_or_init(|| {
+        let store = AuthStore::new().ok()?;
+        let credentials = store.list().ok()?;
+        (!credentials.is_empty()).then(|| Authenticator {
+            store,
+            credentials,
+            secrets: Mutex::new(HashMap::new()),
+        })
+    })
+    .as_ref()
+}
+
+/// Username and secret stored for `url`, if any.
+pub fn credentials_for(url: &str) -> Option<(String, String)> {
+    let auth = authenticator()?;
+    let url = reqwest::Url::parse(url).ok()?;
+    if !url.username().is_empty() {
+        return None;
+    }
+    let record = best_match(&auth.credentials, &url)?;
+    let mut secrets = auth
+        .secrets
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let secret = secrets
+        .entry(record.scope.clone())
+        .or_insert_with(|| match auth.store.secret(record) {
+            Ok(secret) => Some(secret),
+            Err(e) => {
+                eprintln!(
+                    "warning: ignoring stored credentials for {}: {}",
+                    record.scope, e
+                );
+                None
+            }
+        })
+        .clone()?;
+    Some((record.username.clone(), secret))
+}
+
+/// Add stored credentials for `url` to `request` (HTTP basic auth).
+pub fn authorize(request: reqwest::RequestBuilder, url: &str) -> reqwest::RequestBuilder {
+    match credentials_for(url) {
+        Some((username, secret)) => request.basic_auth(username, Some(secret)),
+        None => request,
+    }
+}
+
+mod keychain {
+    //! Thin wrappers around each OS's keychain CLI. Secrets are passed on
+    //! stdin, never on the command line.
+
+    use super::KEYCHAIN_SERVICE;
+    use std::io::Write;
+    use std::process::{Command, Output, Stdio};
+
+    fn run(mut command: Command, input: Option<&str>) -> Result<Output, String> {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let mut child = command
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("failed to run {program}: {e}"))?;
+        if let Some(input) = input
+            && let Some(mut stdin) = child.stdin.take()
+        {
+            stdin
+                .write_all(input.as_bytes())
+                .map_err(|e| format!("failed to write to {program}: {e}"))?;
+        }
+        child
+            .wait_with_output()
+            .map_err(|e| format!("failed to run {program}: {e}"))
+    }
+
+    fn failure(output: &Output) -> String {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if stderr.is_empty() {
+            format!("keychain tool exited with {}", output.status)
+        } else {
+            stderr
+        }
+    }
+
+    fn secret_from(output: Output) -> Option<String> {
+        let secret = String::from_utf8_lossy(&output.stdout)
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+        (!secret.is_empty()).then_some(secret)
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn available() -> bool {
+        super::find_in_path("security").is_some()
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn store(scope: &str, secret: &str) -> Result<(), String> {
+        if secret.contains(['\n', '\r']) {
+            return Err("secrets containing newlines cannot be stored in the keychain".into());
+        }
+        // `security -i` reads the command from stdin, keeping the secret out
+        // of the process list.
+        let quote =
+            |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "add-generic-password -U -s {} -a {} -w {}\n",
+            quote(KEYCHAIN_SERVICE),
+            quote(scope),
+            quote(secret)
+        );
+        let mut command = Command::new("security");
+        command.arg("-i");
+        let output = run(command, Some(&script))?;
+        if output.status.success() && output.stderr.is_empty() {
+            Ok(())
+        } else {
+            Err(failure(&output))
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn load(scope: &str) -> Result<Option<String>, String> {
+        let mut command = Command::new("security");
+        command.args([
+            "find-generic-password",
+            "-s",
+            KEYCHAIN_SERVICE,
+            "-a",
+            scope,
+            "-w",
+        ]);
+        let output = run(command, None)?;
+        Ok(output
+            .status
+            .success()
+            .then(|| secret_from(output))
+            .flatten())
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn delete(scope: &str) -> Result<(), String> {
+        let mut command = Command::new("security");
+        command.args([
+            "delete-generic-password",
+            "-s",
+            KEYCHAIN_SERVICE,
+            "-a",
+            scope,
+        ]);
+        run(command, None).map(|_| ())
+    }
+
+    #[cfg(windows)]
+    const VAULT: &str = "[void][Windows.Security.Credentials.PasswordVault,Windows.Security.Credentials,ContentType=WindowsRuntime]; \
+        $vault = New-Object Windows.Security.Credentials.PasswordVault; ";
+
+    #[cfg(windows)]
+    fn powershell(script: &str, scope: &str) -> Command {
+        let mut command = Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command"])
+            .arg(format!("{VAULT}{script}"))
+            .env("PYBUN_KEYCHAIN_SERVICE", KEYCHAIN_SERVICE)
+            .env("PYBUN_KEYCHAIN_SCOPE", scope);
+        command
+    }
+
+    #[cfg(windows)]
+    pub fn available() -> bool {
+        super::find_in_path("powershell").is_some()
+    }
+
+    #[cfg(windows)]
+    pub fn store(scope: &str, secret: &str) -> Result<(), String> {
+        let command = powershell(
+            "$secret = [Console]::In.ReadToEnd(); \
+             $vault.Add((New-Object Windows.Security.Credentials.PasswordCredential($env:PYBUN_KEYCHAIN_SERVICE, $env:PYBUN_KEYCHAIN_SCOPE, $secret)))",
+            scope,
+        );
+        let output = run(command, Some(secret))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(failure(&output))
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn load(scope: &str) -> Result<Option<String>, String> {
+        let command = powershell(
+            "$c = $vault.Retrieve($env:PYBUN_KEYCHAIN_SERVICE, $env:PYBUN_KEYCHAIN_SCOPE); \
+             $c.RetrievePassword(); [Console]::Out.Write($c.Password)",
+            scope,
+        );
+        let output = run(command, None)?;
+        Ok(output
+            .status
+            .success()
+            .then(|| secret_from(output))
+            .flatten())
+    }
+
+    #[cfg(windows)]
+    pub fn delete(scope: &str) -> Result<(), String> {
+        let command = powershell(
+            "$vault.Remove($vault.Retrieve($env:PYBUN_KEYCHAIN_SERVICE, $env:PYBUN_KEYCHAIN_SCOPE))",
+            scope,
+        );
+        run(command, None).map(|_| ())
+    }
+
+    /// Secret Service needs a D-Bus session, which headless CI runners
+    /// usually lack.
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn available() -> bool {
+        std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+            && super::find_in_path("secret-tool").is_some()
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    fn secret_tool(action: &str, scope: &str) -> Command {
+        let mut command = Command::new("secret-tool");
+        command.arg(action);
+        if action == "store" {
+            command.arg(format!("--label=PyBun credentials for {scope}"));
+        }
+        command.args(["service", KEYCHAIN_SERVICE, "scope", scope]);
+        command
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn store(scope: &str, secret: &str) -> Result<(), String> {
+        let output = run(secret_tool("store", scope), Some(secret))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(failure(&output))
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn load(scope: &str) -> Result<Option<String>, String> {
+        let output = run(secret_tool("lookup", scope), None)?;
+        Ok(output
+            .status
+            .success()
+            .then(|| secret_from(output))
+            .flatten())
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    pub fn delete(scope: &str) -> Result<(), String> {
+        run(secret_tool("clear", scope), None).map(|_| ())
+    }
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let exe = dir.join(format!("{name}.exe"));
+        exe.is_file().then_some(exe)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_normalize_and_match_by_origin() {
+        assert_eq!(
+            scope_for("https://user:pw@Pypi.Corp.example:8443/simple/?q=1#x").unwrap(),
+            "https://pypi.corp.example:8443/simple"
+        );
+        assert!(scope_for("file:///tmp/index").is_err());
+
+        let scope = scope_for("https://pypi.corp.example/simple/").unwrap();
+        let url = |s: &str| reqwest::Url::parse(s).unwrap();
+        assert_eq!(
+            scope_matches(&scope, &url("https://pypi.corp.example/simple/six/")),
+            Some(true)
+        );
+        assert_eq!(
+            scope_matches(&scope, &url("https://pypi.corp.example/simple")),
+            Some(true)
+        );
+        assert_eq!(
+            scope_matches(
+                &scope,
+                &url("https://pypi.corp.example/simple-files/six.whl")
+            ),
+            Some(false)
+        );
+        assert_eq!(
+            scope_matches(&scope, &url("http://pypi.corp.example/simple/six/")),
+            None
+        );
+        assert_eq!(
+            scope_matches(&scope, &url("https://pypi.corp.example:444/simple/")),
+            None
+        );
+        assert_eq!(
+            scope_matches(&scope, &url("https://evil.example/simple/six/")),
+            None
+        );
+    }
+
+    #[test]
+    fn longest_scope_wins() {
+        let record = |scope: &str| CredentialRecord {
+            scope: scope.to_string(),
+            username: scope.to_string(),
+            backend: Backend::File,
+            created_at: 0,
+        };
+        let credentials = vec![
+            record("https://corp.example"),
+            record("https://corp.example/team/simple"),
+        ];
+        let url = reqwest::Url::parse("https://corp.example/team/simple/six/").unwrap();
+        assert_eq!(
+            best_match(&credentials, &url).unwrap().scope,
+            "https://corp.example/team/simple"
+        );
+        let url = reqwest::Url::parse("https://corp.example/files/six.whl").unwrap();
+        assert_eq!(
+            best_match(&credentials, &url).unwrap().scope,
+            "https://corp.example"
+        );
+        let url = reqwest::Url::parse("https://other.example/team/simple/").unwrap();
+        assert!(best_match(&credentials, &url).is_none());
+    }
+
+    #[test]
+    fn file_backend_round_trips_without_plaintext() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = AuthStore::with_dir(temp.path());
+        let (record, note) = store
+            .login(
+                "https://pypi.corp.example/simple",
+                TOKEN_USERNAME,
+                "pypi-s3cret\n",
+                Some(Backend::File),
+            )
+            .unwrap();
+        assert_eq!(note, None);
+        assert_eq!(record.backend, Backend::File);
+        assert_eq!(store.secret(&record).unwrap(), "pypi-s3cret");
+
+        let on_disk = fs::read_to_string(store.credentials_path()).unwrap();
+        assert!(!on_disk.contains("pypi-s3cret"));
+        let index = fs::read_to_string(store.index_path()).unwrap();
+        assert!(!index.contains("pypi-s3cret"));
+
+        store.logout("https://pypi.corp.example/simple/").unwrap();
+        assert!(store.list().unwrap().is_empty());
+        assert!(matches!(
+            store.logout("https://pypi.corp.example/simple"),
+            Err(AuthError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn tampered_credentials_fail_to_decrypt() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = AuthStore::with_dir(temp.path());
+        let (record, _) = store
+            .login("https://a.example", "me", "secret", Some(Backend::File))
+            .unwrap();
+        fs::write(temp.path().join(KEY_FILE), [7u8; 32]).unwrap();
+        assert!(matches!(store.secret(&record), Err(AuthError::Decrypt(_))));
+    }
+}
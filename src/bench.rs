@@ -0,0 +1,519 @@
+//! Benchmarks for `pybun bench`.
+//!
+//! Benchmarks are found with the test discovery parser: `bench_*` functions
+//! without parameters in `bench_*.py`/`*_bench.py` files, and functions
+//! taking pytest-benchmark's `benchmark` fixture in those files and in test
+//! files. Each file runs in one interpreter through PyBun's harness
+//! (`bench_harness.py`), which calibrates iterations per round and returns
+//! the raw round times. The statistics are computed here: rounds outside
+//! Tukey's fences (1.5 × IQR beyond the quartiles) are rejected as
+//! outliers, and median, mean and standard deviation describe the rest.
+//!
+//! Every run is recorded in `<cache root>/bench/<project key>.json`, along
+//! with named baselines, and can be compared against the previous run or a
+//! baseline: a benchmark whose median is slower by more than the threshold
+//! has regressed.
+
+use crate::test_discovery::{DiscoveryConfig, TestDiscovery, TestItemType};
+use crate::test_history::{project_key, relative_id};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory under the cache root holding one history file per project.
+pub const BENCH_DIR: &str = "bench";
+
+/// Runs kept per project; named baselines are kept until replaced.
+pub const MAX_RUNS: usize = 50;
+
+/// Default regression threshold, in percent of the baseline median.
+pub const DEFAULT_THRESHOLD_PERCENT: f64 = 10.0;
+
+const HARNESS_SOURCE: &str = include_str!("bench_harness.py");
+
+/// How a benchmark is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchStyle {
+    /// A `bench_*` function, timed as a whole.
+    Function,
+    /// A function using pytest-benchmark's `benchmark` fixture.
+    Fixture,
+}
+
+/// A discovered benchmark.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Benchmark {
+    /// `path::name`, with the path relative to the project root.
+    pub id: String,
+    pub name: String,
+    pub path: PathBuf,
+    pub line: usize,
+    pub style: BenchStyle,
+}
+
+/// A function that looks like a benchmark but cannot run in the harness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Unsupported {
+    pub id: String,
+    /// Fixtures the harness does not provide.
+    pub fixtures: Vec<String>,
+}
+
+/// Benchmarks under `paths`, ordered by file and line, and the
+/// benchmark-like functions that need fixtures the harness lacks. Skipped
+/// functions are left out.
+pub fn discover(root: &Path, paths: &[PathBuf]) -> (Vec<Benchmark>, Vec<Unsupported>) {
+    let discovery = TestDiscovery::with_config(DiscoveryConfig {
+        function_patterns: vec!["bench_*".to_string(), "test_*".to_string()],
+        class_patterns: Vec::new(),
+        file_patterns: vec![
+            "bench_*.py".to_string(),
+            "*_bench.py".to_string(),
+            "test_*.py".to_string(),
+            "*_test.py".to_string(),
+        ],
+        discover_fixtures: false,
+        compat_warnings: false,
+        ..DiscoveryConfig::default()
+    });
+    let mut benchmarks = Vec::new();
+    let mut unsupported = Vec::new();
+    for item in discovery.discover(paths).tests {
+        if item.item_type != TestItemType::Function || item.skipped {
+            continue;
+        }
+        let uses_fixture = item.fixtures.iter().any(|f| f == "benchmark");
+        let style = if uses_fixture {
+            BenchStyle::Fixture
+        } else if item.name.starts_with("bench_") {
+            BenchStyle::Function
+        } else {
+            continue;
+        };
+        let id = format!(
+            "{}::{}",
+            relative_id(root, &root.join(&item.path)),
+            item.name
+        );
+        let others: Vec<String> = item
+            .fixtures
+            .iter()
+            .filter(|f| *f != "benchmark")
+            .cloned()
+            .collect();
+        if !others.is_empty() {
+            unsupported.push(Unsupported {
+                id,
+                fixtures: others,
+            });
+            continue;
+        }
+        benchmarks.push(Benchmark {
+            id,
+            name: item.name,
+            path: item.path,
+            line: item.line,
+            style,
+        });
+    }
+    benchmarks.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    (benchmarks, unsupported)
+}
+
+/// Statistics of one benchmark's rounds, per iteration, in nanoseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchStats {
+    /// Rounds kept after outlier rejection.
+    pub rounds: usize,
+    /// Calls of the target per round.
+    pub iterations: u64,
+    /// Rounds rejected as outliers.
+    pub outliers: usize,
+    pub median_ns: f64,
+    pub mean_ns: f64,
+    pub stddev_ns: f64,
+    pub min_ns: f64,
+    pub max_ns: f64,
+    pub iqr_ns: f64,
+    pub ops_per_sec: f64,
+}
+
+impl BenchStats {
+    /// Statistics of the per-iteration round times `samples`; `None`
+    /// without samples.
+    pub fn from_samples(samples: &[f64], iterations: u64) -> Option<Self> {
+        let mut sorted: Vec<f64> = samples.iter().copied().filter(|s| s.is_finite()).collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+        let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+        let iqr = q3 - q1;
+        let (low, high) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+        let kept: Vec<f64> = sorted
+            .iter()
+            .copied()
+            .filter(|s| (low..=high).contains(s))
+            .collect();
+
+        let n = kept.len() as f64;
+        let mean = kept.iter().sum::<f64>() / n;
+        let variance = if kept.len() > 1 {
+            kept.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+        Some(Self {
+            rounds: kept.len(),
+            iterations,
+            outliers: sorted.len() - kept.len(),
+            median_ns: quantile(&kept, 0.5),
+            mean_ns: mean,
+            stddev_ns: variance.sqrt(),
+            min_ns: kept[0],
+            max_ns: kept[kept.len() - 1],
+            iqr_ns: iqr,
+            ops_per_sec: if mean > 0.0 { 1e9 / mean } else { 0.0 },
+        })
+    }
+}
+
+/// Linearly interpolated quantile of sorted, non-empty `values`.
+fn quantile(values: &[f64], q: f64) -> f64 {
+    let position = q * (values.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    values[lower] + (values[upper] - values[lower]) * (position - lower as f64)
+}
+
+/// Measurement settings passed to the harness.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BenchSettings {
+    /// Untimed rounds before measuring.
+    pub warmup: u32,
+    /// Rounds measured at least.
+    pub min_rounds: u32,
+    /// Seconds to spend measuring each benchmark.
+    pub max_time: f64,
+    /// Seconds a round lasts at least; iterations are calibrated to it.
+    pub min_round_time: f64,
+}
+
+impl Default for BenchSettings {
+    fn default() -> Self {
+        Self {
+            warmup: 2,
+            min_rounds: 5,
+            max_time: 1.0,
+            min_round_time: 0.0001,
+        }
+    }
+}
+
+/// What the harness measured for one benchmark.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Measurement {
+    pub name: String,
+    #[serde(default)]
+    pub samples_ns: Vec<f64>,
+    #[serde(default)]
+    pub iterations: u64,
+    /// Traceback when the benchmark raised.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Run the benchmarks `names` of `file` in one `python` process.
+pub fn run_file(
+    python: &str,
+    file: &Path,
+    names: &[&str],
+    settings: &BenchSettings,
+) -> Result<Vec<Measurement>, String> {
+    let report = tempfile::NamedTempFile::new()
+        .map_err(|e| format!("failed to create benchmark result file: {e}"))?;
+    let settings = serde_json::to_string(settings).map_err(|e| e.to_string())?;
+    let output = Command::new(python)
+        .args(["-c", HARNESS_SOURCE])
+        .arg(report.path())
+        .arg(file)
+        .arg(settings)
+        .args(names)
+        .output()
+        .map_err(|e| format!("failed to run {python}: {e}"))?;
+    let json = fs::read_to_string(report.path()).unwrap_or_default();
+    serde_json::from_str(&json).map_err(|_| {
+        format!(
+            "benchmark harness failed for {} (exit code {:?}): {}",
+            file.display(),
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    })
+}
+
+/// One recorded `pybun bench` run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchRun {
+    /// Unix timestamp (seconds).
+    pub timestamp: u64,
+    /// Interpreter that ran the benchmarks.
+    pub python: String,
+    /// Keyed by benchmark id.
+    pub results: BTreeMap<String, BenchStats>,
+}
+
+impl BenchRun {
+    /// Record for a run that finished just now.
+    pub fn now(python: &str, results: BTreeMap<String, BenchStats>) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            python: python.to_string(),
+            results,
+        }
+    }
+}
+
+/// Recorded runs and baselines of a project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BenchHistory {
+    /// Oldest first.
+    #[serde(default)]
+    pub runs: Vec<BenchRun>,
+    #[serde(default)]
+    pub baselines: BTreeMap<String, BenchRun>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchStore {
+    path: PathBuf,
+}
+
+impl BenchStore {
+    /// History of the project rooted at `project_root`, stored under the
+    /// cache rooted at `cache_root`.
+    pub fn for_project(cache_root: &Path, project_root: &Path) -> Self {
+        Self::with_path(
+            cache_root
+                .join(BENCH_DIR)
+                .join(format!("{}.json", project_key(project_root))),
+        )
+    }
+
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Recorded history; empty when nothing was recorded or the file is
+    /// unreadable.
+    pub fn load(&self) -> BenchHistory {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Append `run`, dropping the oldest runs beyond [`MAX_RUNS`], and save
+    /// it as the baseline `baseline` when given.
+    pub fn record(&self, run: &BenchRun, baseline: Option<&str>) -> std::io::Result<()> {
+        let mut history = self.load();
+        history.runs.push(run.clone());
+        let skip = history.runs.len().saturating_sub(MAX_RUNS);
+        history.runs.drain(..skip);
+        if let Some(name) = baseline {
+            history.baselines.insert(name.to_string(), run.clone());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&history).map_err(std::io::Error::other)?;
+        fs::write(&self.path, json)
+    }
+}
+
+/// How a benchmark compares with its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Regressed,
+    Improved,
+    Unchanged,
+    /// Not in the baseline.
+    New,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    pub id: String,
+    pub baseline_ns: Option<f64>,
+    pub median_ns: f64,
+    /// Change of the median relative to the baseline.
+    pub change_percent: Option<f64>,
+    pub verdict: Verdict,
+}
+
+/// Compare the medians of `results` with `baseline`: slower by more than
+/// `threshold_percent` is a regression, faster by more an improvement.
+pub fn compare(
+    results: &BTreeMap<String, BenchStats>,
+    baseline: &BenchRun,
+    threshold_percent: f64,
+) -> Vec<Comparison> {
+    results
+        .iter()
+        .map(|(id, stats)| {
+            let base = baseline
+                .results
+                .get(id)
+                .map(|b| b.median_ns)
+                .filter(|b| *b > 0.0);
+            let change = base.map(|b| (stats.median_ns - b) / b * 100.0);
+            let verdict = match change {
+                None => Verdict::New,
+                Some(c) if c > threshold_percent => Verdict::Regressed,
+                Some(c) if c < -threshold_percent => Verdict::Improved,
+                Some(_) => Verdict::Unchanged,
+            };
+            Comparison {
+                id: id.clone(),
+                baseline_ns: base,
+                median_ns: stats.median_ns,
+                change_percent: change,
+                verdict,
+            }
+        })
+        .collect()
+}
+
+/// A duration in nanoseconds with a readable unit, e.g. `1.25 µs`.
+pub fn format_ns(ns: f64) -> String {
+    let (value, unit) = if ns >= 1e9 {
+        (ns / 1e9, "s")
+    } else if ns >= 1e6 {
+        (ns / 1e6, "ms")
+    } else if ns >= 1e3 {
+        (ns / 1e3, "µs")
+    } else {
+        (ns, "ns")
+    };
+    format!("{value:.2} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn rejects_outliers_beyond_tukey_fences() {
+        let samples = [10.0, 12.0, 11.0, 13.0, 12.0, 11.0, 500.0];
+        let stats = BenchStats::from_samples(&samples, 100).unwrap();
+        assert_eq!(stats.outliers, 1);
+        assert_eq!(stats.rounds, 6);
+        assert_eq!(stats.median_ns, 11.5);
+        assert_eq!(stats.mean_ns, 11.5);
+        assert_eq!(stats.min_ns, 10.0);
+        assert_eq!(stats.max_ns, 13.0);
+        assert!((stats.stddev_ns - 1.0488).abs() < 0.001);
+        assert!((stats.ops_per_sec - 1e9 / 11.5).abs() < 1.0);
+
+        let single = BenchStats::from_samples(&[42.0], 1).unwrap();
+        assert_eq!((single.median_ns, single.stddev_ns), (42.0, 0.0));
+        assert_eq!(BenchStats::from_samples(&[], 1), None);
+    }
+
+    #[test]
+    fn compares_medians_against_the_threshold() {
+        let stats = |median_ns| BenchStats {
+            median_ns,
+            ..BenchStats::from_samples(&[1.0], 1).unwrap()
+        };
+        let baseline = BenchRun::now(
+            "python3",
+            BTreeMap::from([
+                ("a".to_string(), stats(100.0)),
+                ("b".to_string(), stats(100.0)),
+                ("c".to_string(), stats(100.0)),
+            ]),
+        );
+        let current = BTreeMap::from([
+            ("a".to_string(), stats(125.0)),
+            ("b".to_string(), stats(80.0)),
+            ("c".to_string(), stats(105.0)),
+            ("d".to_string(), stats(1.0)),
+        ]);
+        let verdicts: Vec<(String, Verdict)> = compare(&current, &baseline, 10.0)
+            .into_iter()
+            .map(|c| (c.id, c.verdict))
+            .collect();
+        assert_eq!(
+            verdicts,
+            [
+                ("a".to_string(), Verdict::Regressed),
+                ("b".to_string(), Verdict::Improved),
+                ("c".to_string(), Verdict::Unchanged),
+                ("d".to_string(), Verdict::New),
+            ]
+        );
+    }
+
+    #[test]
+    fn discovers_bench_functions_and_benchmark_fixture_users() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("benchmarks")).unwrap();
+        fs::write(
+            root.join("benchmarks/bench_sort.py"),
+            "def bench_sort():\n    sorted(range(100))\n\ndef helper():\n    pass\n\n\
+             def bench_needs_tmp(tmp_path):\n    pass\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("test_speed.py"),
+            "def test_plain():\n    pass\n\ndef test_fast(benchmark):\n    benchmark(sum, [1, 2])\n",
+        )
+        .unwrap();
+
+        let (benchmarks, unsupported) = discover(root, &[root.to_path_buf()]);
+        let ids: Vec<(&str, BenchStyle)> = benchmarks
+            .iter()
+            .map(|b| (b.id.as_str(), b.style))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("benchmarks/bench_sort.py::bench_sort", BenchStyle::Function),
+                ("test_speed.py::test_fast", BenchStyle::Fixture),
+            ]
+        );
+        assert_eq!(
+            unsupported[0].id,
+            "benchmarks/bench_sort.py::bench_needs_tmp"
+        );
+        assert_eq!(unsupported[0].fixtures, ["tmp_path"]);
+    }
+
+    #[test]
+    fn keeps_recent_runs_and_named_baselines() {
+        let temp = TempDir::new().unwrap();
+        let store = BenchStore::for_project(temp.path(), Path::new("/project"));
+        let run = BenchRun::now("python3", BTreeMap::new());
+        store.record(&run, Some("main")).unwrap();
+        for _ in 0..MAX_RUNS {
+            store.record(&run, None).unwrap();
+        }
+        let history = store.load();
+        assert_eq!(history.runs.len(), MAX_RUNS);
+        assert_eq!(history.baselines["main"], run);
+    }
+}
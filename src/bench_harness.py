@@ -0,0 +1,150 @@
+"""PyBun benchmark harness.
+
+Times the benchmarks of one file for ``pybun bench``:
+
+    python -c <this file> RESULT_FILE BENCH_FILE SETTINGS_JSON NAME...
+
+A ``bench_*`` function is the benchmark itself and is called without
+arguments. A function taking a ``benchmark`` parameter is written for
+pytest-benchmark: it gets a stand-in fixture and times what it passes to
+``benchmark(func, *args, **kwargs)`` or ``benchmark.pedantic(...)``.
+
+Each round calls the target a calibrated number of iterations, enough for
+the round to outlast ``min_round_time`` seconds. After ``warmup`` untimed
+rounds, rounds are timed until ``max_time`` seconds have passed and at
+least ``min_rounds`` ran. Only the raw per-iteration round times (in ns)
+are written as JSON to RESULT_FILE; the statistics are computed by PyBun.
+"""
+
+import importlib.util
+import inspect
+import json
+import os
+import sys
+import time
+import traceback
+
+MAX_ITERATIONS = 1_000_000
+MAX_ROUNDS = 100_000
+
+
+class Settings:
+    def __init__(self, raw):
+        self.warmup = raw.get("warmup", 2)
+        self.min_rounds = raw.get("min_rounds", 5)
+        self.max_time = raw.get("max_time", 1.0)
+        self.min_round_time = raw.get("min_round_time", 0.0001)
+
+
+def time_round(target, iterations):
+    clock = time.perf_counter_ns
+    start = clock()
+    for _ in range(iterations):
+        target()
+    return clock() - start
+
+
+def calibrate(target, settings):
+    iterations = 1
+    while True:
+        elapsed = time_round(target, iterations)
+        if elapsed >= settings.min_round_time * 1e9 or iterations >= MAX_ITERATIONS:
+            return iterations, elapsed
+        iterations = min(iterations * 10, MAX_ITERATIONS)
+
+
+def measure(target, settings, rounds=None, iterations=None, warmup=None):
+    if iterations is None:
+        iterations, elapsed = calibrate(target, settings)
+    else:
+        elapsed = time_round(target, iterations)
+    for _ in range(settings.warmup if warmup is None else warmup):
+        time_round(target, iterations)
+    if rounds is None:
+        budget = settings.max_time * 1e9
+        rounds = int(budget // max(elapsed, 1))
+        rounds = min(max(rounds, settings.min_rounds), MAX_ROUNDS)
+    samples = [time_round(target, iterations) / iterations for _ in range(rounds)]
+    return {"samples_ns": samples, "iterations": iterations}
+
+
+class Benchmark:
+    """Stand-in for pytest-benchmark's ``benchmark`` fixture."""
+
+    def __init__(self, settings):
+        self._settings = settings
+        self.measured = None
+
+    def __call__(self, func, *args, **kwargs):
+        result = []
+
+        def target():
+            result[:] = [func(*args, **kwargs)]
+
+        self.measured = measure(target, self._settings)
+        return result[0] if result else None
+
+    def pedantic(self, target, args=(), kwargs=None, setup=None, rounds=1, warmup_rounds=0, iterations=1):
+        kwargs = kwargs or {}
+        if setup is not None:
+            prepared = setup()
+            if prepared is not None:
+                args, kwargs = prepared
+        result = []
+
+        def call():
+            result[:] = [target(*args, **kwargs)]
+
+        self.measured = measure(call, self._settings, rounds=rounds, iterations=iterations, warmup=warmup_rounds)
+        return result[0] if result else None
+
+
+def load_module(path):
+    name = os.path.splitext(os.path.basename(path))[0]
+    spec = importlib.util.spec_from_file_location(name, path)
+    module = importlib.util.module_from_spec(spec)
+    sys.modules[name] = module
+    spec.loader.exec_module(module)
+    return module
+
+
+def run_one(module, name, settings):
+    func = getattr(module, name)
+    if "benchmark" in inspect.signature(func).parameters:
+        fixture = Benchmark(settings)
+        func(benchmark=fixture)
+        if fixture.measured is None:
+            return {"name": name, "error": "the benchmark fixture was never called"}
+        return dict(fixture.measured, name=name)
+    return dict(measure(func, settings), name=name)
+
+
+def main():
+    result_file, bench_file, raw_settings = sys.argv[1:4]
+    names = sys.argv[4:]
+    settings = Settings(json.loads(raw_settings))
+    bench_file = os.path.abspath(bench_file)
+    sys.path.insert(0, os.path.dirname(bench_file))
+    if os.getcwd() not in sys.path:
+        sys.path.insert(1, os.getcwd())
+
+    results = []
+    try:
+        module = load_module(bench_file)
+    except Exception:
+        error = "error importing benchmark module\n" + traceback.format_exc()
+        results = [{"name": name, "error": error} for name in names]
+    else:
+        for name in names:
+            try:
+                results.append(run_one(module, name, settings))
+            except Exception:
+                results.append({"name": name, "error": traceback.format_exc()})
+    sys.stdout.flush()
+    sys.stderr.flush()
+    with open(result_file, "w", encoding="utf-8") as handle:
+        json.dump(results, handle)
+
+
+if __name__ == "__main__":
+    main()
@@ -110,6 +110,8 @@ pub type Result<T> = std::result::Result<T, BuildCacheError>;
 #[derive(Debug, Clone)]
 pub struct BuildCache {
     root: PathBuf,
+    /// Build directory of the read-only shared cache, if any.
+    shared: Option<PathBuf>,
 }
 
 impl BuildCache {
@@ -118,11 +120,17 @@ impl BuildCache {
         cache.ensure_dirs()?;
         Ok(Self {
             root: cache.build_dir(),
+            shared: cache
+                .shared_root()
+                .map(|shared| shared.join(cache.build_dir().strip_prefix(cache.root()).unwrap())),
         })
     }
 
     pub fn with_root(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self {
+            root: root.into(),
+            shared: None,
+        }
     }
 
     pub fn root(&self) -> &Path {
@@ -133,11 +141,14 @@ impl BuildCache {
         self.root.join(key)
     }
 
+    /// Key over the backend, interpreter, backend environment `env` and
+    /// every build input of `project_root`.
     pub fn compute_cache_key(
         &self,
         project_root: &Path,
         python_path: &Path,
         backend: &BuildBackend,
+        env: &[(String, String)],
     ) -> Result<String> {
         let inputs = collect_build_inputs(project_root)?;
         let mut hasher = Sha256::new();
@@ -146,6 +157,13 @@ impl BuildCache {
         hasher.update(backend.kind.as_str().as_bytes());
         hasher.update(b"|");
         hasher.update(python_path.display().to_string().as_bytes());
+        hasher.update(b"|");
+        for (key, value) in env {
+            hasher.update(key.as_bytes());
+            hasher.update(b"=");
+            hasher.update(value.as_bytes());
+            hasher.update(b"\0");
+        }
 
         for path in inputs {
             hasher.update(b"|");
@@ -167,15 +185,15 @@ impl BuildCache {
     }
 
     pub fn restore_dist(&self, cache_key: &str, dist_dir: &Path) -> Result<bool> {
-        let cache_dist = self.cache_dir_for_key(cache_key).join("dist");
-        if !cache_dist.exists() {
-            return Ok(false);
-        }
-        if !has_files(&cache_dist)? {
-            return Ok(false);
+        let layers = std::iter::once(&self.root).chain(self.shared.as_ref());
+        for root in layers {
+            let cache_dist = root.join(cache_key).join("dist");
+            if cache_dist.exists() && has_files(&cache_dist)? {
+                copy_dir_recursive(&cache_dist, dist_dir)?;
+                return Ok(true);
+            }
         }
-        copy_dir_recursive(&cache_dist, dist_dir)?;
-        Ok(true)
+        Ok(false)
     }
 
     pub fn store_dist(&self, cache_key: &str, dist_dir: &Path) -> Result<()> {
@@ -293,12 +311,12 @@ mod tests {
         };
 
         let first = cache
-            .compute_cache_key(root, Path::new("python"), &backend)
+            .compute_cache_key(root, Path::new("python"), &backend, &[])
             .unwrap();
 
         fs::write(root.join("module.c"), "int demo() { return 2; }").unwrap();
         let second = cache
-            .compute_cache_key(root, Path::new("python"), &backend)
+            .compute_cache_key(root, Path::new("python"), &backend, &[])
             .unwrap();
 
         assert_ne!(first, second);
@@ -0,0 +1,444 @@
+//! Build isolation for source distributions.
+//!
+//! Source builds execute arbitrary code from the package (`setup.py`, build
+//! backends, native toolchains). For packages that are not trusted, PyBun can
+//! run the build inside a throwaway Podman/Docker container instead of on the
+//! host:
+//!
+//! - the container uses a minimal `python:<major.minor>-slim` image matching
+//!   the project's interpreter (override with `container_image`),
+//! - only a private build directory holding the sdist is mounted (at
+//!   `/build`), never the project or home directory,
+//! - built wheels are copied out of `/build/dist` into the wheel cache.
+//!
+//! Configuration lives in `[tool.pybun.build]`:
+//!
+//! ```toml
+//! [tool.pybun.build]
+//! isolation = "container"            # default for every sdist build
+//! container_runtime = "podman"       # podman or docker (auto-detected)
+//! container_image = "python:3.12-slim"
+//! container_packages = ["legacy-pkg"] # always built in a container
+//! ```
+//!
+//! Packages flagged elsewhere are forced into a container as well: those
+//! matching `[packages] container-build` in `pybun-policy.toml` (see
+//! [`crate::execution_policy`]) and those let through the suspicious-package
+//! checks with `--allow-suspicious` (see [`crate::suspicious`]).
+
+use crate::pypi::normalize_project_name;
+use crate::tool_exec::{ToolError, ToolRun};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+/// Mount point of the build directory inside the container.
+const CONTAINER_BUILD_DIR: &str = "/build";
+
+#[derive(Debug, Error)]
+pub enum BuildIsolationError {
+    #[error("container build isolation requested but neither podman nor docker was found in PATH")]
+    NoRuntime,
+    #[error("container build of {sdist} failed: {source}")]
+    BuildFailed { sdist: String, source: ToolError },
+    #[error("container build of {0} produced no wheel")]
+    NoWheel(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, BuildIsolationError>;
+
+/// How source distributions are built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildIsolation {
+    /// Build in an isolated virtual environment on the host.
+    #[default]
+    Venv,
+    /// Build inside a Podman/Docker container.
+    Container,
+}
+
+impl BuildIsolation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BuildIsolation::Venv => "venv",
+            BuildIsolation::Container => "container",
+        }
+    }
+}
+
+/// `[tool.pybun.build]` settings: build isolation and the `pybun build`
+/// environment.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildConfig {
+    /// Default isolation for every sdist build.
+    #[serde(default)]
+    pub isolation: Option<BuildIsolation>,
+    /// Preferred container runtime (`podman` or `docker`).
+    #[serde(default)]
+    pub container_runtime: Option<String>,
+    /// Image used for container builds.
+    #[serde(default)]
+    pub container_image: Option<String>,
+    /// Packages that must always be built in a container.
+    #[serde(default)]
+    pub container_packages: Vec<String>,
+    /// Environment for the backend of `pybun build` (see
+    /// [`crate::reproducible::build_env`]).
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl BuildConfig {
+    /// Isolation to use for `package`. Packages listed in
+    /// `container_packages` or `flagged` by the execution policy or the
+    /// suspicious-package checks always build in a container regardless of
+    /// the CLI choice; otherwise the CLI flag wins over the configured
+    /// default.
+    pub fn isolation_for(
+        &self,
+        package: &str,
+        cli: Option<BuildIsolation>,
+        flagged: bool,
+    ) -> BuildIsolation {
+        let package = normalize_project_name(package.trim());
+        if flagged
+            || self
+                .container_packages
+                .iter()
+                .any(|p| normalize_project_name(p.trim()) == package)
+        {
+            return BuildIsolation::Container;
+        }
+        cli.or(self.isolation).unwrap_or_default()
+    }
+}
+
+/// Whether the execution policy or the suspicious-package checks flagged
+/// `package` for a container build.
+pub fn flagged_for_container(package: &str) -> bool {
+    crate::suspicious::flagged(package)
+        || crate::execution_policy::current()
+            .is_some_and(|policy| policy.requires_container_build(package))
+}
+
+/// Supported container runtimes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerRuntimeKind {
+    Podman,
+    Docker,
+}
+
+impl ContainerRuntimeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContainerRuntimeKind::Podman => "podman",
+            ContainerRuntimeKind::Docker => "docker",
+        }
+    }
+}
+
+/// A container runtime binary found on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerRuntime {
+    pub kind: ContainerRuntimeKind,
+    pub path: PathBuf,
+}
+
+impl ContainerRuntime {
+    /// Locate a runtime. `PYBUN_CONTAINER_RUNTIME` (or the configured
+    /// preference) may be a runtime name or an explicit binary path; without
+    /// a preference Podman is preferred because it runs rootless by default.
+    pub fn detect(preference: Option<&str>) -> Result<Self> {
+        let env_preference = std::env::var("PYBUN_CONTAINER_RUNTIME")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let preference = env_preference.as_deref().or(preference);
+
+        if let Some(pref) = preference {
+            let path = PathBuf::from(pref);
+            let found = if path.components().count() > 1 {
+                path.is_file().then_some(path)
+            } else {
+                find_in_path(pref)
+            };
+            let name = Path::new(pref)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(pref);
+            let kind = if name.contains("docker") {
+                ContainerRuntimeKind::Docker
+            } else {
+                ContainerRuntimeKind::Podman
+            };
+            return found
+                .map(|path| ContainerRuntime { kind, path })
+                .ok_or(BuildIsolationError::NoRuntime);
+        }
+
+        [ContainerRuntimeKind::Podman, ContainerRuntimeKind::Docker]
+            .into_iter()
+            .find_map(|kind| {
+                find_in_path(kind.as_str()).map(|path| ContainerRuntime { kind, path })
+            })
+            .ok_or(BuildIsolationError::NoRuntime)
+    }
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let exe = dir.join(format!("{name}.exe"));
+        exe.is_file().then_some(exe)
+    })
+}
+
+/// Default build image for an interpreter version (`3.12.4` → `python:3.12-slim`).
+pub fn default_image(python_version: Option<&str>) -> String {
+    let minor = python_version
+        .map(|v| v.split('.').take(2).collect::<Vec<_>>().join("."))
+        .filter(|v| v.contains('.'))
+        .unwrap_or_else(|| "3".to_string());
+    format!("python:{minor}-slim")
+}
+
+/// A single containerized sdist build.
+#[derive(Debug, Clone)]
+pub struct ContainerBuild {
+    pub image: String,
+    /// Host directory mounted at `/build`; must contain the sdist.
+    pub build_dir: PathBuf,
+    /// Filename of the sdist inside `build_dir`.
+    pub sdist: String,
+}
+
+impl ContainerBuild {
+    /// Arguments passed to the runtime binary. The container is removed on
+    /// exit, runs without extra capabilities, and sees only `build_dir`.
+    pub fn runtime_args(&self, kind: ContainerRuntimeKind) -> Vec<String> {
+        let mut volume = format!("{}:{}", self.build_dir.display(), CONTAINER_BUILD_DIR);
+        if kind == ContainerRuntimeKind::Podman {
+            // Relabel for SELinux hosts; harmless elsewhere.
+            volume.push_str(":Z");
+        }
+        vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--cap-drop=ALL".to_string(),
+            "--security-opt=no-new-privileges".to_string(),
+            "--volume".to_string(),
+            volume,
+            "--workdir".to_string(),
+            CONTAINER_BUILD_DIR.to_string(),
+            self.image.clone(),
+            "python".to_string(),
+            "-m".to_string(),
+            "pip".to_string(),
+            "wheel".to_string(),
+            "--no-deps".to_string(),
+            "--disable-pip-version-check".to_string(),
+            "--wheel-dir".to_string(),
+            format!("{CONTAINER_BUILD_DIR}/dist"),
+            format!("{CONTAINER_BUILD_DIR}/{}", self.sdist),
+        ]
+    }
+}
+
+/// Build `sdist` inside a container and copy the resulting wheels into
+/// `wheel_cache_dir`, returning their cached paths.
+pub fn build_sdist_in_container(
+    runtime: &ContainerRuntime,
+    image: &str,
+    sdist: &Path,
+    wheel_cache_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let sdist_name = sdist
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("sdist.tar.gz")
+        .to_string();
+
+    // Private scratch directory: the only host path the container can see.
+    let build_dir = tempfile::tempdir()?;
+    std::fs::copy(sdist, build_dir.path().join(&sdist_name))?;
+
+    let build = ContainerBuild {
+        image: image.to_string(),
+        build_dir: build_dir.path().to_path_buf(),
+        sdist: sdist_name.clone(),
+    };
+    // Rebuilding into the private build directory is harmless, so image
+    // pull and registry hiccups are retried.
+    let mut cmd = Command::new(&runtime.path);
+    cmd.args(build.runtime_args(runtime.kind));
+    ToolRun::new(runtime.kind.as_str(), &mut cmd)
+        .idempotent()
+        .run()
+        .map_err(|source| BuildIsolationError::BuildFailed {
+            sdist: sdist_name.clone(),
+            source,
+        })?;
+
+    collect_wheels(&build_dir.path().join("dist"), wheel_cache_dir, &sdist_name)
+}
+
+/// Move built wheels out of the container's output directory into the cache.
+/// Only regular files are taken: the build is untrusted, and a symlink it
+/// left in `dist/` would make the copy read an arbitrary host file.
+fn collect_wheels(dist_dir: &Path, wheel_cache_dir: &Path, sdist: &str) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(wheel_cache_dir)?;
+    let mut wheels = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(dist_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let regular = std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_file());
+            if regular && path.extension().is_some_and(|e| e == "whl") {
+                let dest = wheel_cache_dir.join(entry.file_name());
+                std::fs::copy(&path, &dest)?;
+                wheels.push(dest);
+            }
+        }
+    }
+    if wheels.is_empty() {
+        return Err(BuildIsolationError::NoWheel(sdist.to_string()));
+    }
+    wheels.sort();
+    Ok(wheels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flagged_packages_always_use_container() {
+        let config = BuildConfig {
+            container_packages: vec!["Legacy_Pkg".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.isolation_for("legacy-pkg", Some(BuildIsolation::Venv), false),
+            BuildIsolation::Container
+        );
+        assert_eq!(
+            config.isolation_for("requests", None, false),
+            BuildIsolation::Venv
+        );
+        assert_eq!(
+            config.isolation_for("requests", Some(BuildIsolation::Venv), true),
+            BuildIsolation::Container
+        );
+    }
+
+    #[test]
+    fn cli_choice_overrides_configured_default() {
+        let config = BuildConfig {
+            isolation: Some(BuildIsolation::Container),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.isolation_for("requests", None, false),
+            BuildIsolation::Container
+        );
+        assert_eq!(
+            config.isolation_for("requests", Some(BuildIsolation::Venv), false),
+            BuildIsolation::Venv
+        );
+    }
+
+    #[test]
+    fn default_image_tracks_python_minor_version() {
+        assert_eq!(default_image(Some("3.12.4")), "python:3.12-slim");
+        assert_eq!(default_image(Some("3.11")), "python:3.11-slim");
+        assert_eq!(default_image(None), "python:3-slim");
+    }
+
+    #[test]
+    fn runtime_args_mount_only_build_dir() {
+        let build = ContainerBuild {
+            image: "python:3.12-slim".to_string(),
+            build_dir: PathBuf::from("/tmp/pybun-build"),
+            sdist: "pkg-1.0.tar.gz".to_string(),
+        };
+        let args = build.runtime_args(ContainerRuntimeKind::Podman);
+        let volumes: Vec<_> = args
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| *a == "--volume")
+            .map(|(i, _)| args[i + 1].as_str())
+            .collect();
+        assert_eq!(volumes, ["/tmp/pybun-build:/build:Z"]);
+        assert!(args.contains(&"--rm".to_string()));
+        assert_eq!(args.last().unwrap(), "/build/pkg-1.0.tar.gz");
+
+        let docker = build.runtime_args(ContainerRuntimeKind::Docker);
+        assert!(docker.contains(&"/tmp/pybun-build:/build".to_string()));
+    }
+
+    #[test]
+    fn detect_honours_explicit_runtime_path() {
+        let temp = tempfile::tempdir().unwrap();
+        let fake = temp.path().join("docker");
+        std::fs::write(&fake, "").unwrap();
+        let runtime = ContainerRuntime::detect(Some(fake.to_str().unwrap())).unwrap();
+        assert_eq!(runtime.kind, ContainerRuntimeKind::Docker);
+        assert_eq!(runtime.path, fake);
+
+        let missing = temp.path().join("nope").join("podman");
+        assert!(ContainerRuntime::detect(Some(missing.to_str().unwrap())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn builds_with_fake_runtime_and_collects_wheels() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        // Fake runtime: writes a wheel into the mounted build dir's dist/.
+        let fake = temp.path().join("podman");
+        std::fs::write(
+            &fake,
+            "#!/bin/sh\nfor a in \"$@\"; do case \"$a\" in *:/build*) dir=\"${a%%:/build*}\";; esac; done\n\
+             mkdir -p \"$dir/dist\" && touch \"$dir/dist/pkg-1.0-py3-none-any.whl\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let sdist = temp.path().join("pkg-1.0.tar.gz");
+        std::fs::write(&sdist, b"sdist").unwrap();
+        let cache = temp.path().join("cache");
+
+        let runtime = ContainerRuntime {
+            kind: ContainerRuntimeKind::Podman,
+            path: fake,
+        };
+        let wheels =
+            build_sdist_in_container(&runtime, "python:3.12-slim", &sdist, &cache).unwrap();
+        assert_eq!(wheels, vec![cache.join("pkg-1.0-py3-none-any.whl")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn collect_wheels_skips_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let dist = temp.path().join("dist");
+        std::fs::create_dir_all(&dist).unwrap();
+        let secret = temp.path().join("secret");
+        std::fs::write(&secret, b"host file").unwrap();
+        std::os::unix::fs::symlink(&secret, dist.join("evil-1.0-py3-none-any.whl")).unwrap();
+        std::fs::write(dist.join("pkg-1.0-py3-none-any.whl"), b"wheel").unwrap();
+
+        let cache = temp.path().join("cache");
+        let wheels = collect_wheels(&dist, &cache, "pkg-1.0.tar.gz").unwrap();
+        assert_eq!(wheels, vec![cache.join("pkg-1.0-py3-none-any.whl")]);
+        assert!(!cache.join("evil-1.0-py3-none-any.whl").exists());
+    }
+}
@@ -1,7 +1,8 @@
 //! Global cache management for PyBun.
 //!
 //! Layout:
-//! - ~/.cache/pybun/packages/    (wheels)
+//! - ~/.cache/pybun/wheels/      (content-addressed wheel store)
+//! - ~/.cache/pybun/packages/    (wheels, legacy per-package layout)
 //! - ~/.cache/pybun/envs/        (virtual environments)
 //! - ~/.cache/pybun/build/       (build object cache)
 //! - ~/.cache/pybun/logs/        (structured event logs)
@@ -10,7 +11,21 @@
 //! ## GC (Garbage Collection)
 //! The cache supports LRU-based garbage collection with configurable size limits.
 //! Use `pybun gc --max-size` to enforce cache size limits.
-
+//!
+//! ## Read-only shared cache
+//! With `PYBUN_READONLY_CACHE=1` (for shared CI runners) the cache root is
+//! treated as a read-only lower layer. Every write goes to the job's overlay
+//! directory, which `PYBUN_CACHE_OVERLAY` must name: a shared default would
+//! let concurrent jobs discard each other's files, and a predictable one in
+//! the temp dir could be planted by another user. The overlay is created
+//! private (`0700`), and an existing one owned by someone else is refused.
+//! Lookups check the overlay first and then the shared layer. `pybun gc`
+//! only ever evicts from the overlay, and `pybun gc --export-overlay DIR` /
+//! `--discard-overlay` hand off or drop the job's delta at the end.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -19,10 +34,19 @@ use thiserror::Error;
 
 const DEFAULT_CACHE_DIR: &str = ".cache/pybun";
 const PACKAGES_DIR: &str = "packages";
+const WHEELS_DIR: &str = "wheels";
 const ENVS_DIR: &str = "envs";
 const BUILD_DIR: &str = "build";
 const LOGS_DIR: &str = "logs";
 const PEP723_ENVS_DIR: &str = "pep723-envs";
+const CHUNKS_DIR: &str = "chunks";
+
+/// Enables the read-only shared cache mode.
+pub const READONLY_ENV: &str = "PYBUN_READONLY_CACHE";
+/// Overrides the overlay directory used in read-only mode.
+pub const OVERLAY_ENV: &str = "PYBUN_CACHE_OVERLAY";
+/// Manifest written next to an exported overlay.
+pub const OVERLAY_MANIFEST: &str = "pybun-overlay.json";
 
 #[derive(Debug, Error)]
 pub enum CacheError {
@@ -35,14 +59,126 @@ pub enum CacheError {
     },
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("the shared cache is not read-only; set {READONLY_ENV}=1 to use an overlay")]
+    NotReadonly,
+    #[error("{path} is in the read-only shared cache")]
+    ReadonlyShared { path: PathBuf },
+    #[error("{READONLY_ENV} is set but {OVERLAY_ENV} is not; point it at a directory for this job")]
+    OverlayRequired,
+    #[error("cache overlay {path} is not a directory owned by the current user")]
+    OverlayNotOwned { path: PathBuf },
 }
 
 pub type Result<T> = std::result::Result<T, CacheError>;
 
+/// Default cache root: `PYBUN_HOME`, else `~/.cache/pybun`.
+pub fn default_root() -> Result<PathBuf> {
+    if let Ok(home) = env::var("PYBUN_HOME") {
+        return Ok(PathBuf::from(home));
+    }
+    let home_dir = dirs::home_dir().ok_or(CacheError::NoHomeDir)?;
+    Ok(home_dir.join(DEFAULT_CACHE_DIR))
+}
+
+/// A read-only shared cache with a writable overlay on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheLayers {
+    pub shared: PathBuf,
+    pub overlay: PathBuf,
+}
+
+impl CacheLayers {
+    /// Layers over `shared` when `PYBUN_READONLY_CACHE` is enabled. The
+    /// overlay named by `PYBUN_CACHE_OVERLAY` is created if needed (see
+    /// [`prepare_overlay`]).
+    pub fn from_env(shared: &Path) -> Result<Option<Self>> {
+        let enabled =
+            env::var(READONLY_ENV).is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes" | "on"));
+        if !enabled {
+            return Ok(None);
+        }
+        let overlay = env::var_os(OVERLAY_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .ok_or(CacheError::OverlayRequired)?;
+        prepare_overlay(&overlay)?;
+        Ok(Some(Self {
+            shared: shared.to_path_buf(),
+            overlay,
+        }))
+    }
+
+    /// `relative` in the overlay if it exists there, else in the shared
+    /// layer if it exists there.
+    pub fn find(&self, relative: impl AsRef<Path>) -> Option<PathBuf> {
+        let relative = relative.as_ref();
+        [self.overlay.join(relative), self.shared.join(relative)]
+            .into_iter()
+            .find(|path| path.exists())
+    }
+}
+
+/// Create `overlay` readable by the current user only, or check that an
+/// existing one is a directory the current user owns.
+pub fn prepare_overlay(overlay: &Path) -> Result<()> {
+    match fs::symlink_metadata(overlay) {
+        Ok(meta) => {
+            #[cfg(unix)]
+            let owned = {
+                use std::os::unix::fs::MetadataExt;
+                // SAFETY: geteuid has no preconditions and cannot fail.
+                meta.uid() == unsafe { libc::geteuid() }
+            };
+            #[cfg(not(unix))]
+            let owned = true;
+            if meta.is_dir() && owned {
+                Ok(())
+            } else {
+                Err(CacheError::OverlayNotOwned {
+                    path: overlay.to_path_buf(),
+                })
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if let Some(parent) = overlay.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent).map_err(|source| CacheError::CreateDir {
+                    path: parent.to_path_buf(),
+                    source,
+                })?;
+            }
+            let mut builder = fs::DirBuilder::new();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::DirBuilderExt;
+                builder.mode(0o700);
+            }
+            builder
+                .create(overlay)
+                .map_err(|source| CacheError::CreateDir {
+                    path: overlay.to_path_buf(),
+                    source,
+                })
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A file written to the overlay (part of the job's delta).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OverlayFile {
+    /// Path relative to the overlay root.
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
 /// Represents the global cache configuration and paths.
 #[derive(Debug, Clone)]
 pub struct Cache {
+    /// Writable root (the overlay in read-only mode).
     root: PathBuf,
+    /// Read-only lower layer, when `PYBUN_READONLY_CACHE` is set.
+    shared: Option<PathBuf>,
 }
 
 impl Cache {
@@ -51,31 +187,88 @@ impl Cache {
     /// Priority:
     /// 1. `PYBUN_HOME` environment variable
     /// 2. `~/.cache/pybun`
+    ///
+    /// In read-only mode the root is the overlay and the location above
+    /// becomes the shared layer.
     pub fn new() -> Result<Self> {
-        let root = if let Ok(home) = env::var("PYBUN_HOME") {
-            PathBuf::from(home)
-        } else {
-            let home_dir = dirs::home_dir().ok_or(CacheError::NoHomeDir)?;
-            home_dir.join(DEFAULT_CACHE_DIR)
-        };
-        Ok(Self { root })
+        let root = default_root()?;
+        Ok(match CacheLayers::from_env(&root)? {
+            Some(layers) => Self::with_layers(layers),
+            None => Self { root, shared: None },
+        })
     }
 
     /// Create a cache instance with a custom root directory (useful for testing).
     pub fn with_root(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self {
+            root: root.into(),
+            shared: None,
+        }
     }
 
-    /// Root directory of the cache.
+    /// Create a cache that writes to `layers.overlay` and also reads from
+    /// `layers.shared`.
+    pub fn with_layers(layers: CacheLayers) -> Self {
+        Self {
+            root: layers.overlay,
+            shared: Some(layers.shared),
+        }
+    }
+
+    /// Root directory of the cache (where writes go).
     pub fn root(&self) -> &Path {
         &self.root
     }
 
+    /// The read-only shared layer, if any.
+    pub fn shared_root(&self) -> Option<&Path> {
+        self.shared.as_deref()
+    }
+
+    /// The layers, when running in read-only mode.
+    pub fn layers(&self) -> Option<CacheLayers> {
+        self.shared.as_ref().map(|shared| CacheLayers {
+            shared: shared.clone(),
+            overlay: self.root.clone(),
+        })
+    }
+
+    /// Locate `relative` (to the cache root) in the writable root, then
+    /// the shared layer.
+    pub fn find(&self, relative: impl AsRef<Path>) -> Option<PathBuf> {
+        match self.layers() {
+            Some(layers) => layers.find(relative),
+            None => Some(self.root.join(relative)).filter(|path| path.exists()),
+        }
+    }
+
+    /// Whether `path` lives in the read-only shared layer.
+    pub fn is_shared(&self, path: &Path) -> bool {
+        self.shared
+            .as_deref()
+            .is_some_and(|shared| path.starts_with(shared) && !path.starts_with(&self.root))
+    }
+
     /// Directory for cached wheel packages.
     pub fn packages_dir(&self) -> PathBuf {
         self.root.join(PACKAGES_DIR)
     }
 
+    /// Content-addressed wheel store (see [`crate::wheel_cache`]).
+    pub fn wheel_store_dir(&self) -> PathBuf {
+        self.root.join(WHEELS_DIR)
+    }
+
+    /// Path of a stored wheel relative to the cache root:
+    /// `wheels/{sha256[..2]}/{sha256}/{filename}`.
+    pub fn stored_wheel_relative(sha256: &str, filename: &str) -> PathBuf {
+        let prefix = sha256.get(..2).unwrap_or(sha256);
+        Path::new(WHEELS_DIR)
+            .join(prefix)
+            .join(sha256)
+            .join(filename)
+    }
+
     /// Directory for virtual environments.
     pub fn envs_dir(&self) -> PathBuf {
         self.root.join(ENVS_DIR)
@@ -96,9 +289,16 @@ impl Cache {
         self.root.join(PEP723_ENVS_DIR)
     }
 
+    /// Content-defined chunks of pushed/pulled environment snapshots (see
+    /// [`crate::env_snapshot`]).
+    pub fn chunks_dir(&self) -> PathBuf {
+        self.root.join(CHUNKS_DIR)
+    }
+
     /// Ensure all cache directories exist.
     pub fn ensure_dirs(&self) -> Result<()> {
         for dir in [
+            self.wheel_store_dir(),
             self.packages_dir(),
             self.envs_dir(),
             self.build_dir(),
@@ -127,9 +327,14 @@ impl Cache {
         self.packages_dir().join(name)
     }
 
-    /// Check if a wheel exists in the cache.
-    pub fn has_wheel(&self, name: &str, version: &str, wheel_file: &str) -> bool {
-        self.wheel_path(name, version, wheel_file).exists()
+    /// Check if a wheel exists in the cache (either layer).
+    pub fn has_wheel(&self, name: &str, _version: &str, wheel_file: &str) -> bool {
+        self.existing_wheel(name, wheel_file).is_some()
+    }
+
+    /// Path of a cached wheel in the writable root or the shared layer.
+    pub fn existing_wheel(&self, name: &str, wheel_file: &str) -> Option<PathBuf> {
+        self.find(Path::new(PACKAGES_DIR).join(name).join(wheel_file))
     }
 
     /// Ensure package directory exists.
@@ -145,6 +350,75 @@ impl Cache {
     }
 }
 
+impl Cache {
+    /// Files written to the overlay, sorted by path.
+    pub fn overlay_files(&self) -> Result<Vec<OverlayFile>> {
+        if self.shared.is_none() {
+            return Err(CacheError::NotReadonly);
+        }
+        let mut files = Vec::new();
+        collect_overlay_files(&self.root, &self.root, &mut files)?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// Copy the overlay's files to `dest` (preserving layout) and write an
+    /// [`OVERLAY_MANIFEST`] listing them, ready to upload as a delta.
+    pub fn export_overlay(&self, dest: &Path) -> Result<Vec<OverlayFile>> {
+        let files = self.overlay_files()?;
+        fs::create_dir_all(dest).map_err(|source| CacheError::CreateDir {
+            path: dest.to_path_buf(),
+            source,
+        })?;
+        for file in &files {
+            let target = dest.join(&file.path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(self.root.join(&file.path), &target)?;
+        }
+        let manifest = serde_json::json!({
+            "shared": self.shared,
+            "files": files,
+        });
+        fs::write(
+            dest.join(OVERLAY_MANIFEST),
+            serde_json::to_vec_pretty(&manifest).map_err(std::io::Error::other)?,
+        )?;
+        Ok(files)
+    }
+
+    /// Delete the overlay. Returns the bytes freed.
+    pub fn discard_overlay(&self) -> Result<u64> {
+        let freed = self.overlay_files()?.iter().map(|f| f.size_bytes).sum();
+        if self.root.exists() {
+            fs::remove_dir_all(&self.root)?;
+        }
+        Ok(freed)
+    }
+}
+
+fn collect_overlay_files(root: &Path, dir: &Path, files: &mut Vec<OverlayFile>) -> Result<()> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_overlay_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            let data = fs::read(&path)?;
+            files.push(OverlayFile {
+                path: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+                size_bytes: data.len() as u64,
+                sha256: hex::encode(Sha256::digest(&data)),
+            });
+        }
+    }
+    Ok(())
+}
+
 impl Default for Cache {
     fn default() -> Self {
         Self::new().expect("failed to initialize default cache")
@@ -164,14 +438,16 @@ pub struct GcResult {
     pub size_before: u64,
     /// Current cache size after GC (bytes)
     pub size_after: u64,
+    /// Files kept only because a pinned project locks them
+    pub pinned_kept: usize,
 }
 
 /// A cached entry with metadata for LRU eviction
 #[derive(Debug, Clone)]
-struct CacheEntry {
-    path: PathBuf,
-    size: u64,
-    accessed: SystemTime,
+pub(crate) struct CacheEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) size: u64,
+    pub(crate) accessed: SystemTime,
 }
 
 impl Cache {
@@ -182,14 +458,45 @@ impl Cache {
     ///
     /// If `dry_run` is true, reports what would be deleted without actually deleting.
     pub fn gc(&self, max_bytes: Option<u64>, dry_run: bool) -> Result<GcResult> {
+        self.gc_with_pins(max_bytes, dry_run, &BTreeSet::new())
+    }
+
+    /// [`Cache::gc`] that never evicts files named in `pinned` (wheel
+    /// filenames locked by pinned projects, see
+    /// [`crate::project_registry::pinned_wheels`]).
+    pub fn gc_with_pins(
+        &self,
+        max_bytes: Option<u64>,
+        dry_run: bool,
+        pinned: &BTreeSet<String>,
+    ) -> Result<GcResult> {
+        self.gc_excluding(max_bytes, dry_run, pinned, &BTreeSet::new())
+    }
+
+    /// [`Cache::gc_with_pins`] treating the files in `planned` as already
+    /// evicted (by a `pybun gc --max-age/--budget` policy in the same run),
+    /// so a dry run does not count them twice.
+    pub fn gc_excluding(
+        &self,
+        max_bytes: Option<u64>,
+        dry_run: bool,
+        pinned: &BTreeSet<String>,
+        planned: &BTreeSet<PathBuf>,
+    ) -> Result<GcResult> {
         let mut result = GcResult::default();
 
         // Collect all cache entries
         let mut entries = self.collect_cache_entries()?;
+        entries.retain(|e| !planned.contains(&e.path));
         result.size_before = entries.iter().map(|e| e.size).sum();
 
-        // Sort by access time (oldest first for LRU eviction)
-        entries.sort_by_key(|e| e.accessed);
+        // Sort by access time (oldest first for LRU eviction); ties break on
+        // path so dry runs are deterministic.
+        entries.sort_by(|a, b| {
+            a.accessed
+                .cmp(&b.accessed)
+                .then_with(|| a.path.cmp(&b.path))
+        });
 
         let max_bytes = max_bytes.unwrap_or(u64::MAX);
         let mut current_size = result.size_before;
@@ -199,6 +506,15 @@ impl Cache {
             if current_size <= max_bytes {
                 break;
             }
+            if entry
+                .path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| pinned.contains(name))
+            {
+                result.pinned_kept += 1;
+                continue;
+            }
 
             if dry_run {
                 result.would_remove.push(entry.path.clone());
@@ -235,15 +551,28 @@ impl Cache {
         Ok(entries.iter().map(|e| e.size).sum())
     }
 
+    /// Files `gc` considers for eviction (cached wheels and build artifacts).
+    pub fn entry_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths: Vec<_> = self
+            .collect_cache_entries()?
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
     /// Collect all cache entries with metadata
-    fn collect_cache_entries(&self) -> Result<Vec<CacheEntry>> {
+    pub(crate) fn collect_cache_entries(&self) -> Result<Vec<CacheEntry>> {
         let mut entries = Vec::new();
 
-        // Collect from packages directory
-        if let Ok(dirs) = fs::read_dir(self.packages_dir()) {
-            for dir_entry in dirs.flatten() {
-                if dir_entry.path().is_dir() {
-                    self.collect_entries_from_dir(&dir_entry.path(), &mut entries)?;
+        // Collect from the wheel store and the legacy packages directory
+        for root in [self.wheel_store_dir(), self.packages_dir()] {
+            if let Ok(dirs) = fs::read_dir(root) {
+                for dir_entry in dirs.flatten() {
+                    if dir_entry.path().is_dir() {
+                        self.collect_entries_from_dir(&dir_entry.path(), &mut entries)?;
+                    }
                 }
             }
         }
@@ -297,6 +626,7 @@ impl Cache {
     }
 
     fn remove_empty_dirs(&self) -> Result<()> {
+        self.remove_empty_dirs_recursive(&self.wheel_store_dir())?;
         self.remove_empty_dirs_recursive(&self.packages_dir())?;
         self.remove_empty_dirs_recursive(&self.build_dir())?;
         Ok(())
@@ -321,7 +651,11 @@ impl Cache {
             }
         }
 
-        if is_empty && dir != self.packages_dir() && dir != self.build_dir() {
+        if is_empty
+            && dir != self.wheel_store_dir()
+            && dir != self.packages_dir()
+            && dir != self.build_dir()
+        {
             let _ = fs::remove_dir(dir);
         }
 
@@ -361,24 +695,9 @@ pub fn parse_size(s: &str) -> std::result::Result<u64, String> {
     Ok((num * multiplier as f64) as u64)
 }
 
-/// Format bytes as human-readable size
+/// Format bytes as human-readable size (see [`crate::units::format_bytes`]).
 pub fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-    const TB: u64 = GB * 1024;
-
-    if bytes >= TB {
-        format!("{:.2} TB", bytes as f64 / TB as f64)
-    } else if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
-    }
+    crate::units::format_bytes(bytes)
 }
 
 #[cfg(test)]
@@ -396,6 +715,44 @@ mod tests {
         assert_eq!(cache.envs_dir(), temp.path().join("envs"));
     }
 
+    #[test]
+    fn prepare_overlay_creates_a_private_directory() {
+        let temp = tempdir().unwrap();
+        let overlay = temp.path().join("job/overlay");
+        prepare_overlay(&overlay).unwrap();
+        assert!(overlay.is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&overlay).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        prepare_overlay(&overlay).unwrap();
+
+        let file = temp.path().join("file");
+        fs::write(&file, b"").unwrap();
+        assert!(matches!(
+            prepare_overlay(&file),
+            Err(CacheError::OverlayNotOwned { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prepare_overlay_refuses_directories_owned_by_others() {
+        let temp = tempdir().unwrap();
+        let planted = temp.path().join("planted");
+        fs::create_dir(&planted).unwrap();
+        // Only root can hand a directory to another user.
+        if std::os::unix::fs::chown(&planted, Some(65534), None).is_err() {
+            return;
+        }
+        assert!(matches!(
+            prepare_overlay(&planted),
+            Err(CacheError::OverlayNotOwned { .. })
+        ));
+    }
+
     #[test]
     fn ensure_dirs_creates_structure() {
         let temp = tempdir().unwrap();
@@ -403,6 +760,7 @@ mod tests {
 
         cache.ensure_dirs().unwrap();
 
+        assert!(cache.wheel_store_dir().exists());
         assert!(cache.packages_dir().exists());
         assert!(cache.envs_dir().exists());
         assert!(cache.build_dir().exists());
@@ -482,6 +840,97 @@ mod tests {
         assert!(file.exists());
     }
 
+    #[test]
+    fn gc_keeps_pinned_wheels() {
+        let temp = tempdir().unwrap();
+        let cache = Cache::with_root(temp.path());
+        cache.ensure_dirs().unwrap();
+
+        let pkg_dir = cache.packages_dir().join("test-pkg");
+        fs::create_dir_all(&pkg_dir).unwrap();
+        let pinned = pkg_dir.join("pinned.whl");
+        let loose = pkg_dir.join("loose.whl");
+        fs::write(&pinned, vec![0u8; 1024]).unwrap();
+        fs::write(&loose, vec![0u8; 1024]).unwrap();
+
+        let result = cache
+            .gc_with_pins(Some(0), false, &BTreeSet::from(["pinned.whl".to_string()]))
+            .unwrap();
+        assert_eq!(result.files_removed, 1);
+        assert_eq!(result.pinned_kept, 1);
+        assert!(pinned.exists());
+        assert!(!loose.exists());
+    }
+
+    #[test]
+    fn layered_cache_reads_shared_and_writes_overlay() {
+        let temp = tempdir().unwrap();
+        let layers = CacheLayers {
+            shared: temp.path().join("shared"),
+            overlay: temp.path().join("overlay"),
+        };
+        let shared_wheel = layers.shared.join("packages/foo/foo-1.0-py3-none-any.whl");
+        fs::create_dir_all(shared_wheel.parent().unwrap()).unwrap();
+        fs::write(&shared_wheel, b"shared").unwrap();
+
+        let cache = Cache::with_layers(layers.clone());
+        assert_eq!(cache.root(), layers.overlay);
+        let found = cache
+            .existing_wheel("foo", "foo-1.0-py3-none-any.whl")
+            .unwrap();
+        assert_eq!(found, shared_wheel);
+        assert!(cache.is_shared(&found));
+
+        // An overlay copy shadows the shared one.
+        let overlay_wheel = cache.wheel_path("foo", "1.0", "foo-1.0-py3-none-any.whl");
+        fs::create_dir_all(overlay_wheel.parent().unwrap()).unwrap();
+        fs::write(&overlay_wheel, b"overlay").unwrap();
+        let found = cache
+            .existing_wheel("foo", "foo-1.0-py3-none-any.whl")
+            .unwrap();
+        assert_eq!(found, overlay_wheel);
+        assert!(!cache.is_shared(&found));
+
+        // GC only ever sees the overlay.
+        let result = cache.gc(Some(0), false).unwrap();
+        assert_eq!(result.files_removed, 1);
+        assert!(shared_wheel.exists());
+    }
+
+    #[test]
+    fn overlay_export_and_discard() {
+        let temp = tempdir().unwrap();
+        let cache = Cache::with_layers(CacheLayers {
+            shared: temp.path().join("shared"),
+            overlay: temp.path().join("overlay"),
+        });
+        let wheel = cache.wheel_path("bar", "2.0", "bar-2.0-py3-none-any.whl");
+        fs::create_dir_all(wheel.parent().unwrap()).unwrap();
+        fs::write(&wheel, b"delta").unwrap();
+
+        let dest = temp.path().join("delta");
+        let files = cache.export_overlay(&dest).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].path,
+            Path::new("packages/bar/bar-2.0-py3-none-any.whl")
+        );
+        assert_eq!(files[0].size_bytes, 5);
+        assert_eq!(fs::read(dest.join(&files[0].path)).unwrap(), b"delta");
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&fs::read(dest.join(OVERLAY_MANIFEST)).unwrap()).unwrap();
+        assert_eq!(manifest["files"][0]["sha256"], files[0].sha256);
+
+        assert_eq!(cache.discard_overlay().unwrap(), 5);
+        assert!(!cache.root().exists());
+
+        let plain = Cache::with_root(temp.path());
+        assert!(matches!(
+            plain.overlay_files(),
+            Err(CacheError::NotReadonly)
+        ));
+    }
+
     #[test]
     fn parse_size_various_formats() {
         assert_eq!(parse_size("100").unwrap(), 100);
@@ -0,0 +1,396 @@
+//! What the cache holds, for `pybun cache list`, `info` and `verify`.
+//!
+//! `pybun gc` frees space entry by entry; these views show the same
+//! entries before anything is deleted:
+//!
+//! - wheels in the content-addressed store (`wheels/<ab>/<sha256>/<file>`),
+//!   including the read-only shared layer in read-only cache mode,
+//! - PEP 723 script environments (`pep723-envs/<dir>/deps.json`),
+//! - installed Python runtimes (`python/<version>`).
+//!
+//! Hit and miss counts come from `cache-stats.json` (see
+//! [`crate::cache_stats`]). [`verify`] re-hashes every stored wheel against
+//! the SHA-256 in its path, since the store otherwise trusts a wheel once it
+//! has been checked on download.
+
+use crate::cache::Cache;
+use crate::cache_stats::{self, CacheStats, EntryStats};
+use crate::pep723_cache::{CachedEnvInfo, Pep723Cache};
+use crate::project_registry::ProjectRecord;
+use crate::runtime::RuntimeManager;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use thiserror::Error;
+
+/// Shortest hash prefix `pybun cache info` accepts.
+pub const MIN_PREFIX: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum CacheInspectError {
+    #[error("'{0}' is too short; give at least {MIN_PREFIX} hex digits of the hash")]
+    PrefixTooShort(String),
+    #[error("no cache entry matches '{0}'")]
+    NotFound(String),
+    #[error("'{prefix}' matches {} entries: {}", ids.len(), ids.join(", "))]
+    Ambiguous { prefix: String, ids: Vec<String> },
+}
+
+/// Kind of cache entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Wheel,
+    Env,
+    Runtime,
+}
+
+impl EntryKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            EntryKind::Wheel => "wheel",
+            EntryKind::Env => "env",
+            EntryKind::Runtime => "runtime",
+        }
+    }
+}
+
+/// One cache entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CacheItem {
+    pub kind: EntryKind,
+    /// SHA-256 of a wheel, hash of an environment's inputs, or a runtime's
+    /// version.
+    pub id: String,
+    /// Wheel filename, environment dependencies, or `cpython-<version>`.
+    pub name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// Unix timestamp (seconds): last use of an environment, modification
+    /// time otherwise.
+    pub last_used: Option<u64>,
+    pub hits: u64,
+    pub misses: u64,
+    /// Lives in the read-only shared layer.
+    pub shared: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub python_version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+/// Which kinds `list` collects; all of them when none is selected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Selection {
+    pub wheels: bool,
+    pub envs: bool,
+    pub runtimes: bool,
+}
+
+impl Selection {
+    fn includes(&self, kind: EntryKind) -> bool {
+        let all = !(self.wheels || self.envs || self.runtimes);
+        all || match kind {
+            EntryKind::Wheel => self.wheels,
+            EntryKind::Env => self.envs,
+            EntryKind::Runtime => self.runtimes,
+        }
+    }
+}
+
+/// Entries of the selected kinds, grouped by kind and sorted by name.
+pub fn list(cache: &Cache, selection: Selection) -> Vec<CacheItem> {
+    let stats = CacheStats::for_root(cache.root()).load();
+    let mut items = Vec::new();
+    if selection.includes(EntryKind::Wheel) {
+        items.extend(wheels(cache, &stats));
+    }
+    if selection.includes(EntryKind::Env) {
+        items.extend(envs(cache, &stats));
+    }
+    if selection.includes(EntryKind::Runtime) {
+        items.extend(runtimes(cache));
+    }
+    items.sort_by(|a, b| (a.kind, &a.name, &a.id).cmp(&(b.kind, &b.name, &b.id)));
+    items
+}
+
+/// The wheel or environment whose hash starts with `prefix`.
+pub fn find(cache: &Cache, prefix: &str) -> Result<CacheItem, CacheInspectError> {
+    let prefix = prefix
+        .trim()
+        .trim_start_matches("sha256:")
+        .to_ascii_lowercase();
+    if prefix.len() < MIN_PREFIX {
+        return Err(CacheInspectError::PrefixTooShort(prefix));
+    }
+    let selection = Selection {
+        wheels: true,
+        envs: true,
+        runtimes: false,
+    };
+    let mut matches: Vec<CacheItem> = list(cache, selection)
+        .into_iter()
+        .filter(|item| item.id.starts_with(&prefix))
+        .collect();
+    // The same wheel can sit in both layers; the writable copy wins.
+    matches.dedup_by(|later, first| later.kind == first.kind && later.id == first.id);
+    match matches.len() {
+        0 => Err(CacheInspectError::NotFound(prefix)),
+        1 => Ok(matches.remove(0)),
+        _ => Err(CacheInspectError::Ambiguous {
+            prefix,
+            ids: matches.into_iter().map(|item| item.id).collect(),
+        }),
+    }
+}
+
+/// Registered projects whose lockfile names `filename`.
+pub fn referencing_projects(records: &[ProjectRecord], filename: &str) -> Vec<PathBuf> {
+    records
+        .iter()
+        .filter(|record| crate::project_registry::locked_wheels(record).contains(filename))
+        .map(|record| record.root.clone())
+        .collect()
+}
+
+/// Problem found by [`verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Problem {
+    /// The contents do not hash to the SHA-256 in the path.
+    HashMismatch,
+    /// The file could not be read.
+    Unreadable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyFailure {
+    pub sha256: String,
+    pub filename: String,
+    pub path: PathBuf,
+    pub problem: Problem,
+    /// SHA-256 of the contents actually stored.
+    pub actual: Option<String>,
+    pub shared: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub bytes_checked: u64,
+    pub failures: Vec<VerifyFailure>,
+}
+
+/// Re-hash every stored wheel.
+pub fn verify(cache: &Cache) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    for item in wheels(cache, &BTreeMap::new()) {
+        report.checked += 1;
+        report.bytes_checked += item.size_bytes;
+        let (problem, actual) = match crate::security::sha256_file(&item.path) {
+            Ok(actual) if actual == item.id => continue,
+            Ok(actual) => (Problem::HashMismatch, Some(actual)),
+            Err(_) => (Problem::Unreadable, None),
+        };
+        report.failures.push(VerifyFailure {
+            sha256: item.id,
+            filename: item.name,
+            path: item.path,
+            problem,
+            actual,
+            shared: item.shared,
+        });
+    }
+    report
+}
+
+/// Wheels in the writable store, then the shared layer's.
+fn wheels(cache: &Cache, stats: &BTreeMap<String, EntryStats>) -> Vec<CacheItem> {
+    let mut stores = vec![(cache.root().to_path_buf(), false)];
+    if let Some(shared) = cache.shared_root() {
+        stores.push((shared.to_path_buf(), true));
+    }
+    let mut items = Vec::new();
+    for (root, shared) in stores {
+        let store = Cache::with_root(&root).wheel_store_dir();
+        for prefix in read_dirs(&store) {
+            for hash_dir in read_dirs(&prefix) {
+                let sha256 = file_name(&hash_dir);
+                for file in read_files(&hash_dir) {
+                    let metadata = fs::metadata(&file).ok();
+                    let counters = cache_stats::path_key(&root, &file)
+                        .and_then(|key| stats.get(&key))
+                        .cloned()
+                        .unwrap_or_default();
+                    items.push(CacheItem {
+                        kind: EntryKind::Wheel,
+                        id: sha256.clone(),
+                        name: file_name(&file),
+                        size_bytes: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                        last_used: metadata.as_ref().and_then(modified),
+                        hits: counters.hits,
+                        misses: counters.misses,
+                        shared,
+                        python_version: None,
+                        dependencies: Vec::new(),
+                        path: file,
+                    });
+                }
+            }
+        }
+    }
+    items
+}
+
+fn envs(cache: &Cache, stats: &BTreeMap<String, EntryStats>) -> Vec<CacheItem> {
+    let pep723 = Pep723Cache::with_root(cache.root());
+    read_dirs(&pep723.envs_dir())
+        .into_iter()
+        .filter_map(|path| {
+            let env: CachedEnvInfo =
+                serde_json::from_slice(&fs::read(path.join("deps.json")).ok()?).ok()?;
+            let counters = stats
+                .get(&cache_stats::pep723_key(&env.hash))
+                .cloned()
+                .unwrap_or_default();
+            Some(CacheItem {
+                kind: EntryKind::Env,
+                name: if env.dependencies.is_empty() {
+                    "(no dependencies)".to_string()
+                } else {
+                    env.dependencies.join(", ")
+                },
+                size_bytes: crate::project_registry::dir_size(&path),
+                last_used: Some(env.last_used),
+                hits: counters.hits,
+                misses: counters.misses,
+                shared: false,
+                python_version: Some(env.python_version),
+                dependencies: env.dependencies,
+                id: env.hash,
+                path,
+            })
+        })
+        .collect()
+}
+
+fn runtimes(cache: &Cache) -> Vec<CacheItem> {
+    let manager = RuntimeManager::new(cache.clone());
+    manager
+        .list_installed()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|version| {
+            let path = manager.version_dir(&version);
+            CacheItem {
+                kind: EntryKind::Runtime,
+                name: format!("cpython-{version}"),
+                size_bytes: manager.installed_size(&version),
+                last_used: fs::metadata(&path).ok().as_ref().and_then(modified),
+                hits: 0,
+                misses: 0,
+                shared: false,
+                python_version: Some(version.clone()),
+                dependencies: Vec::new(),
+                id: version,
+                path,
+            }
+        })
+        .collect()
+}
+
+fn read_dirs(dir: &Path) -> Vec<PathBuf> {
+    read_sorted(dir, |path| path.is_dir())
+}
+
+fn read_files(dir: &Path) -> Vec<PathBuf> {
+    read_sorted(dir, |path| path.is_file())
+}
+
+fn read_sorted(dir: &Path, keep: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| keep(path))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn modified(metadata: &fs::Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use tempfile::TempDir;
+
+    fn store_wheel(cache: &Cache, contents: &[u8], filename: &str) -> (String, PathBuf) {
+        let sha256 = hex::encode(Sha256::digest(contents));
+        let path = cache
+            .root()
+            .join(Cache::stored_wheel_relative(&sha256, filename));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        (sha256, path)
+    }
+
+    #[test]
+    fn find_resolves_unique_prefixes() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::with_root(temp.path());
+        let (sha, _) = store_wheel(&cache, b"one", "one-1.0-py3-none-any.whl");
+        store_wheel(&cache, b"two", "two-1.0-py3-none-any.whl");
+
+        let item = find(&cache, &format!("sha256:{}", &sha[..8])).unwrap();
+        assert_eq!(item.name, "one-1.0-py3-none-any.whl");
+        assert_eq!(item.size_bytes, 3);
+        assert!(matches!(
+            find(&cache, "ab"),
+            Err(CacheInspectError::PrefixTooShort(_))
+        ));
+        assert!(matches!(
+            find(&cache, "0000000"),
+            Err(CacheInspectError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn verify_reports_tampered_wheels() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::with_root(temp.path());
+        store_wheel(&cache, b"good", "good-1.0-py3-none-any.whl");
+        let (sha, path) = store_wheel(&cache, b"bad", "bad-1.0-py3-none-any.whl");
+        fs::write(&path, b"tampered").unwrap();
+
+        let report = verify(&cache);
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.failures.len(), 1);
+        let failure = &report.failures[0];
+        assert_eq!(failure.sha256, sha);
+        assert_eq!(failure.problem, Problem::HashMismatch);
+        assert_eq!(
+            failure.actual.as_deref(),
+            Some(hex::encode(Sha256::digest(b"tampered")).as_str())
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Local per-entry cache effectiveness statistics.
+//!
+//! Wheel cache and PEP 723 environment lookups are recorded in
+//! `<cache root>/cache-stats.json` as hits or misses, keyed by the entry's
+//! path relative to the cache root (`wheels/<ab>/<sha256>/<file>`) or by
+//! `pep723/<hash>`. A rolling per-day history is kept for the last
+//! [`HISTORY_DAYS`] days. `pybun gc --dry-run --sweep` reads the file to
+//! show what an eviction would cost. Nothing leaves the machine; set
+//! `PYBUN_CACHE_STATS=0` to stop recording.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Stats file name under the cache root.
+pub const STATS_FILE: &str = "cache-stats.json";
+
+/// Set to `0`/`false`/`off`/`no` to disable recording.
+pub const STATS_ENV: &str = "PYBUN_CACHE_STATS";
+
+/// Days of per-day history kept per entry.
+pub const HISTORY_DAYS: u64 = 30;
+
+const STATS_VERSION: u32 = 1;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Lookup counters for one cache entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Unix timestamp (seconds) of the last hit.
+    #[serde(default)]
+    pub last_hit: Option<u64>,
+    /// Per-day counters, oldest first.
+    #[serde(default)]
+    pub history: Vec<DayStats>,
+}
+
+impl EntryStats {
+    /// Fraction of lookups served from the cache, if there were any.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+/// Counters for one day (days since the Unix epoch, UTC).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayStats {
+    pub day: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StatsFile {
+    version: u32,
+    entries: BTreeMap<String, EntryStats>,
+}
+
+/// Key for a PEP 723 environment, matching the hashes `Pep723Cache::gc` reports.
+pub fn pep723_key(hash: &str) -> String {
+    format!("pep723/{hash}")
+}
+
+/// Key for a file inside the cache rooted at `root`.
+pub fn path_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn enabled() -> bool {
+    !matches!(
+        std::env::var(STATS_ENV)
+            .map(|v| v.trim().to_ascii_lowercase())
+            .as_deref(),
+        Ok("0" | "false" | "off" | "no")
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheStats {
+    path: PathBuf,
+}
+
+impl CacheStats {
+    /// Stats for the cache rooted at `root`.
+    pub fn for_root(root: &Path) -> Self {
+        Self::with_path(root.join(STATS_FILE))
+    }
+
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Recorded stats by key; empty when nothing was recorded or the file
+    /// is unreadable.
+    pub fn load(&self) -> BTreeMap<String, EntryStats> {
+        fs::read(&self.path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<StatsFile>(&data).ok())
+            .map(|file| file.entries)
+            .unwrap_or_default()
+    }
+
+    /// Record a lookup of `key`. Best effort: stats must never fail a command.
+    pub fn record(&self, key: &str, hit: bool) {
+        if enabled() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let _ = self.record_at(key, hit, now);
+        }
+    }
+
+    fn record_at(&self, key: &str, hit: bool, now: u64) -> std::io::Result<()> {
+        let parent = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(parent)?;
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("json.lock"))?;
+        fs2::FileExt::lock_exclusive(&lock)?;
+
+        let mut file = fs::read(&self.path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<StatsFile>(&data).ok())
+            .unwrap_or_default();
+        let today = now / SECONDS_PER_DAY;
+        let entry = file.entries.entry(key.to_string()).or_default();
+        if entry.history.last().is_none_or(|d| d.day != today) {
+            entry.history.push(DayStats {
+                day: today,
+                ..DayStats::default()
+            });
+        }
+        let day = entry.history.last_mut().expect("pushed above");
+        if hit {
+            entry.hits += 1;
+            entry.last_hit = Some(now);
+            day.hits += 1;
+        } else {
+            entry.misses += 1;
+            day.misses += 1;
+        }
+        let oldest = today.saturating_sub(HISTORY_DAYS - 1);
+        entry.history.retain(|d| d.day >= oldest);
+        file.version = STATS_VERSION;
+
+        let data = serde_json::to_vec_pretty(&file).map_err(std::io::Error::other)?;
+        let mut tmp = tempfile::NamedTempFile::new_in(parent)?;
+        tmp.write_all(&data)?;
+        tmp.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn records_hits_misses_and_trims_history() {
+        let temp = tempdir().unwrap();
+        let stats = CacheStats::for_root(temp.path());
+        let day = SECONDS_PER_DAY;
+
+        stats.record_at("packages/six/six.whl", false, day).unwrap();
+        stats
+            .record_at("packages/six/six.whl", true, day + 5)
+            .unwrap();
+        stats
+            .record_at("packages/six/six.whl", true, (HISTORY_DAYS + 1) * day)
+            .unwrap();
+
+        let loaded = stats.load();
+        let entry = &loaded["packages/six/six.whl"];
+        assert_eq!((entry.hits, entry.misses), (2, 1));
+        assert_eq!(entry.last_hit, Some((HISTORY_DAYS + 1) * day));
+        assert_eq!(entry.hit_rate(), Some(2.0 / 3.0));
+        // Day 1 fell out of the window; totals are kept.
+        assert_eq!(
+            entry.history,
+            vec![DayStats {
+                day: HISTORY_DAYS + 1,
+                hits: 1,
+                misses: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn keys_are_relative_and_slash_separated() {
+        let root = Path::new("/cache");
+        assert_eq!(
+            path_key(root, &root.join("packages").join("six").join("six.whl")).as_deref(),
+            Some("packages/six/six.whl")
+        );
+        assert_eq!(path_key(root, Path::new("/elsewhere/x")), None);
+        assert_eq!(pep723_key("abc"), "pep723/abc");
+    }
+}
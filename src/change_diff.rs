@@ -0,0 +1,485 @@
+//! What `pybun add`, `pybun remove` and `pybun upgrade` changed on disk.
+//!
+//! Each modified file is reported twice: as a minimal unified diff for text
+//! output, and as a list of per-dependency changes for JSON output. The
+//! lockfile is binary, so it is diffed through a line-per-field text view:
+//!
+//! ```text
+//! [[package]]
+//! name = "requests"
+//! version = "2.32.3"
+//! wheel = "requests-2.32.3-py3-none-any.whl"
+//! hash = "sha256:..."
+//! dependencies = ["idna>=2.5"]
+//! groups = ["main"]
+//! ```
+//!
+//! `pyproject.toml` changes are listed per section: `project.dependencies`,
+//! `project.optional-dependencies.<extra>` and `dependency-groups.<group>`.
+
+use crate::lockfile::{Lockfile, Package};
+use crate::project::extract_package_name;
+use crate::pypi::normalize_project_name;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Lines of unchanged context around each hunk.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One dependency (`pyproject.toml`) or locked package (lockfile) that
+/// differs. `before`/`after` are the requirement string or locked version,
+/// `None` on the side where it is absent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub file: String,
+    pub section: String,
+    pub name: String,
+    pub change: ChangeKind,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// The changes to one file. `diff` is empty when nothing changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileDiff {
+    pub changes: Vec<Change>,
+    pub diff: String,
+}
+
+impl FileDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.diff.is_empty()
+    }
+}
+
+/// Changes between two versions of a `pyproject.toml`, labelled `file`.
+/// An empty `before` means the file was created.
+pub fn pyproject_changes(file: &str, before: &str, after: &str) -> FileDiff {
+    let (old, new) = (dependency_sections(before), dependency_sections(after));
+    let mut sections: Vec<&String> = old.keys().chain(new.keys()).collect();
+    sections.sort_by_key(|section| (section.as_str() != PROJECT_DEPENDENCIES, *section));
+    sections.dedup();
+    let empty = BTreeMap::new();
+    let changes = sections
+        .into_iter()
+        .flat_map(|section| {
+            keyed_changes(
+                file,
+                section,
+                old.get(section).unwrap_or(&empty),
+                new.get(section).unwrap_or(&empty),
+            )
+        })
+        .collect();
+    FileDiff {
+        changes,
+        diff: unified_diff(file, before, after),
+    }
+}
+
+/// Changes between two lockfiles, labelled `file`. `before` is `None` when
+/// there was no lockfile.
+pub fn lockfile_changes(file: &str, before: Option<&Lockfile>, after: &Lockfile) -> FileDiff {
+    let versions = |lock: Option<&Lockfile>| -> BTreeMap<String, (String, String)> {
+        lock.map(|lock| {
+            lock.packages
+                .values()
+                .map(|pkg| {
+                    (
+                        normalize_project_name(&pkg.name),
+                        (pkg.version.clone(), package_text(pkg)),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    };
+    let (old, new) = (versions(before), versions(Some(after)));
+
+    // A package whose artifacts changed at the same version still counts as
+    // changed; the versions alone then look identical.
+    let mut changes = Vec::new();
+    for name in old
+        .keys()
+        .chain(new.keys().filter(|k| !old.contains_key(*k)))
+    {
+        let (left, right) = (old.get(name), new.get(name));
+        let change = match (left, right) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            (Some(l), Some(r)) if l != r => ChangeKind::Changed,
+            _ => continue,
+        };
+        changes.push(Change {
+            file: file.to_string(),
+            section: "packages".to_string(),
+            name: name.clone(),
+            change,
+            before: left.map(|(version, _)| version.clone()),
+            after: right.map(|(version, _)| version.clone()),
+        });
+    }
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    FileDiff {
+        changes,
+        diff: unified_diff(
+            file,
+            &before.map(lockfile_text).unwrap_or_default(),
+            &lockfile_text(after),
+        ),
+    }
+}
+
+/// Text view of a lockfile, one field per line, for diffing.
+pub fn lockfile_text(lock: &Lockfile) -> String {
+    let mut out = format!(
+        "python_versions = {:?}\nplatforms = {:?}\n",
+        lock.python_versions, lock.platforms
+    );
+    for pkg in lock.packages.values() {
+        out.push('\n');
+        out.push_str(&package_text(pkg));
+    }
+    out
+}
+
+fn package_text(pkg: &Package) -> String {
+    let mut out = format!(
+        "[[package]]\nname = {:?}\nversion = {:?}\nwheel = {:?}\nhash = {:?}\ndependencies = {:?}\n",
+        pkg.name, pkg.version, pkg.wheel, pkg.hash, pkg.dependencies
+    );
+    if !pkg.groups.is_empty() {
+        out.push_str(&format!("groups = {:?}\n", pkg.groups));
+    }
+    for artifact in &pkg.artifacts {
+        if artifact.filename != pkg.wheel {
+            out.push_str(&format!(
+                "artifact = {:?} # {}\n",
+                artifact.filename, artifact.hash
+            ));
+        }
+    }
+    out
+}
+
+const PROJECT_DEPENDENCIES: &str = "project.dependencies";
+
+/// Requirements of `[project.dependencies]`, each optional-dependencies extra
+/// (`project.optional-dependencies.<extra>`) and each PEP 735 group
+/// (`dependency-groups.<group>`), keyed by normalized package name.
+fn dependency_sections(text: &str) -> BTreeMap<String, BTreeMap<String, String>> {
+    let Ok(doc) = text.parse::<toml::Table>() else {
+        return BTreeMap::new();
+    };
+    let keyed = |deps: &toml::Value| -> BTreeMap<String, String> {
+        deps.as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .map(|dep| {
+                (
+                    normalize_project_name(extract_package_name(dep)),
+                    dep.to_string(),
+                )
+            })
+            .collect()
+    };
+    let mut sections = BTreeMap::new();
+    if let Some(deps) = doc.get("project").and_then(|p| p.get("dependencies")) {
+        sections.insert(PROJECT_DEPENDENCIES.to_string(), keyed(deps));
+    }
+    let groups = [
+        (
+            "project.optional-dependencies",
+            doc.get("project")
+                .and_then(|p| p.get("optional-dependencies")),
+        ),
+        ("dependency-groups", doc.get("dependency-groups")),
+    ];
+    for (prefix, table) in groups {
+        for (name, deps) in table.and_then(|t| t.as_table()).into_iter().flatten() {
+            sections.insert(format!("{prefix}.{name}"), keyed(deps));
+        }
+    }
+    sections
+}
+
+fn keyed_changes(
+    file: &str,
+    section: &str,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> Vec<Change> {
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (before, after) = (old.get(name), new.get(name));
+            let change = match (before, after) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(b), Some(a)) if b != a => ChangeKind::Changed,
+                _ => return None,
+            };
+            Some(Change {
+                file: file.to_string(),
+                section: section.to_string(),
+                name: name.clone(),
+                change,
+                before: before.cloned(),
+                after: after.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Unified diff (`--- a/file`, `+++ b/file`, `@@` hunks) of two texts, or
+/// an empty string when they are identical.
+pub fn unified_diff(file: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    let ops = diff_ops(&old, &new);
+    if ops.iter().all(|(op, _)| *op == Op::Equal) {
+        return String::new();
+    }
+
+    // Line numbers (0-based) in `old` and `new` before each op.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut o, mut n) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((o, n));
+        match op {
+            Op::Equal => {
+                o += 1;
+                n += 1;
+            }
+            Op::Delete => o += 1,
+            Op::Insert => n += 1,
+        }
+    }
+    positions.push((o, n));
+
+    let mut out = format!("--- a/{file}\n+++ b/{file}\n");
+    let changed: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != Op::Equal).collect();
+    let mut i = 0;
+    while i < changed.len() {
+        // Extend the hunk while the next change is within both contexts.
+        let mut j = i;
+        while j + 1 < changed.len() && changed[j + 1] - changed[j] <= 2 * CONTEXT + 1 {
+            j += 1;
+        }
+        let start = changed[i].saturating_sub(CONTEXT);
+        let end = (changed[j] + CONTEXT + 1).min(ops.len());
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for (op, line) in &ops[start..end] {
+            let prefix = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+        i = j + 1;
+    }
+    out
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Line-level edit script: the common prefix and suffix are kept as is and
+/// the middle is aligned on its longest common subsequence.
+fn diff_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j]: length of the LCS of a[i..] and b[j..].
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(Op, &str)> = old[..prefix].iter().map(|l| (Op::Equal, *l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((Op::Equal, a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((Op::Delete, a[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|l| (Op::Equal, *l)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::PackageSource;
+
+    #[test]
+    fn unified_diff_emits_minimal_hunks() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let after = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        assert_eq!(
+            unified_diff("f.txt", before, after),
+            "--- a/f.txt\n+++ b/f.txt\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        assert_eq!(unified_diff("f.txt", before, before), "");
+        assert_eq!(
+            unified_diff("new.txt", "", "x\n"),
+            "--- a/new.txt\n+++ b/new.txt\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
+
+    #[test]
+    fn pyproject_changes_are_keyed_by_normalized_name() {
+        let before = "[project]\ndependencies = [\"Click>=8\", \"requests>=2.28\"]\n";
+        let after = "[project]\ndependencies = [\"click>=8.1\", \"rich\"]\n";
+        let diff = pyproject_changes("pyproject.toml", before, after);
+        let summary: Vec<(&str, ChangeKind)> = diff
+            .changes
+            .iter()
+            .map(|c| (c.name.as_str(), c.change))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("click", ChangeKind::Changed),
+                ("requests", ChangeKind::Removed),
+                ("rich", ChangeKind::Added),
+            ]
+        );
+        assert_eq!(diff.changes[0].before.as_deref(), Some("Click>=8"));
+        assert!(diff.diff.contains("-dependencies = [\"Click>=8\""));
+    }
+
+    #[test]
+    fn pyproject_changes_cover_dependency_groups() {
+        let before = "[project]\ndependencies = [\"rich\"]\n\n\
+                      [project.optional-dependencies]\ndev = [\"ruff\"]\n";
+        let after = "[project]\ndependencies = [\"rich\"]\n\n\
+                     [project.optional-dependencies]\ndev = [\"ruff\", \"mypy\"]\n\n\
+                     [dependency-groups]\ntest = [\"pytest\"]\n";
+        let diff = pyproject_changes("pyproject.toml", before, after);
+        let summary: Vec<(&str, &str, ChangeKind)> = diff
+            .changes
+            .iter()
+            .map(|c| (c.section.as_str(), c.name.as_str(), c.change))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("dependency-groups.test", "pytest", ChangeKind::Added),
+                (
+                    "project.optional-dependencies.dev",
+                    "mypy",
+                    ChangeKind::Added
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lockfile_changes_report_versions() {
+        let package = |name: &str, version: &str| Package {
+            name: name.into(),
+            version: version.into(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: format!("sha256:{name}{version}"),
+            dependencies: Vec::new(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        };
+        let mut before = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        before.add_package(package("a", "1.0"));
+        before.add_package(package("b", "1.0"));
+        let mut after = before.clone();
+        after.add_package(package("a", "2.0"));
+        after.packages.remove("b");
+        after.add_package(package("c", "1.0"));
+
+        let diff = lockfile_changes("pybun.lockb", Some(&before), &after);
+        let summary: Vec<(&str, ChangeKind, Option<&str>, Option<&str>)> = diff
+            .changes
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.change,
+                    c.before.as_deref(),
+                    c.after.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a", ChangeKind::Changed, Some("1.0"), Some("2.0")),
+                ("b", ChangeKind::Removed, Some("1.0"), None),
+                ("c", ChangeKind::Added, None, Some("1.0")),
+            ]
+        );
+        assert!(diff.diff.contains("\n-version = \"1.0\"\n"));
+        assert!(diff.diff.contains("\n+version = \"2.0\"\n"));
+        assert!(lockfile_changes("pybun.lockb", Some(&after), &after).is_empty());
+    }
+}
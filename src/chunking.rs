@@ -0,0 +1,158 @@
+//! Content-defined chunking (FastCDC).
+//!
+//! Files are split where a rolling Gear hash of the last 64 bytes matches a
+//! mask, so chunk boundaries follow the content rather than fixed offsets: an
+//! edit only changes the chunks around it, and identical runs of bytes in
+//! different files (or different versions of a file) produce identical
+//! chunks. [`Chunker`] uses FastCDC's normalized chunking: a stricter mask
+//! before the average size and a looser one after it keep chunk sizes close
+//! to [`AVG_SIZE`], bounded by [`MIN_SIZE`] and [`MAX_SIZE`].
+
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+
+pub const MIN_SIZE: usize = 16 * 1024;
+pub const AVG_SIZE: usize = 64 * 1024;
+pub const MAX_SIZE: usize = 256 * 1024;
+
+/// 18 high bits: cuts are rarer than 1 in `AVG_SIZE` before the average.
+const MASK_S: u64 = !(u64::MAX >> 18);
+/// 14 high bits: cuts are likelier than 1 in `AVG_SIZE` after it.
+const MASK_L: u64 = !(u64::MAX >> 14);
+
+/// Random 64-bit value per byte, generated with SplitMix64 from a fixed seed
+/// so chunk boundaries are stable across builds.
+const GEAR: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x5079_4275_6e43_4443;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// Length of the first chunk of `data`.
+pub fn cut_point(data: &[u8]) -> usize {
+    if data.len() <= MIN_SIZE {
+        return data.len();
+    }
+    let normal = data.len().min(AVG_SIZE);
+    let max = data.len().min(MAX_SIZE);
+    let mut hash = 0u64;
+    for (i, &byte) in data.iter().enumerate().take(max).skip(MIN_SIZE) {
+        hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+        let mask = if i < normal { MASK_S } else { MASK_L };
+        if hash & mask == 0 {
+            return i + 1;
+        }
+    }
+    max
+}
+
+/// SHA-256 (hex) of a chunk: its id in a chunk store.
+pub fn chunk_id(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Splits a reader into content-defined chunks.
+pub struct Chunker<R> {
+    reader: R,
+    buf: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: Read> Chunker<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::with_capacity(MAX_SIZE),
+            eof: false,
+        }
+    }
+
+    /// The next chunk, or `None` at end of input.
+    pub fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        while !self.eof && self.buf.len() < MAX_SIZE {
+            let start = self.buf.len();
+            self.buf.resize(MAX_SIZE, 0);
+            let read = self.reader.read(&mut self.buf[start..])?;
+            self.buf.truncate(start + read);
+            self.eof = read == 0;
+        }
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        let len = cut_point(&self.buf);
+        let rest = self.buf.split_off(len);
+        Ok(Some(std::mem::replace(&mut self.buf, rest)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random bytes.
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn chunks(data: &[u8]) -> Vec<Vec<u8>> {
+        let mut chunker = Chunker::new(data);
+        let mut out = Vec::new();
+        while let Some(chunk) = chunker.next_chunk().unwrap() {
+            out.push(chunk);
+        }
+        out
+    }
+
+    #[test]
+    fn chunks_reassemble_and_respect_bounds() {
+        let data = noise(3 * 1024 * 1024, 7);
+        let chunks = chunks(&data);
+        assert_eq!(chunks.concat(), data);
+        let (last, rest) = chunks.split_last().unwrap();
+        assert!(
+            rest.iter()
+                .all(|c| (MIN_SIZE..=MAX_SIZE).contains(&c.len()))
+        );
+        assert!(last.len() <= MAX_SIZE);
+        let average = data.len() / chunks.len();
+        assert!(
+            (AVG_SIZE / 2..AVG_SIZE * 2).contains(&average),
+            "average chunk size {average}"
+        );
+    }
+
+    #[test]
+    fn an_insertion_only_changes_nearby_chunks() {
+        let data = noise(2 * 1024 * 1024, 11);
+        let mut edited = data.clone();
+        edited.splice(700_000..700_000, b"inserted bytes".iter().copied());
+
+        let before: Vec<String> = chunks(&data).iter().map(|c| chunk_id(c)).collect();
+        let after: Vec<String> = chunks(&edited).iter().map(|c| chunk_id(c)).collect();
+        let changed = after.iter().filter(|id| !before.contains(id)).count();
+        assert!(changed <= 2, "{changed} of {} chunks changed", after.len());
+    }
+
+    #[test]
+    fn small_input_is_one_chunk() {
+        assert_eq!(chunks(b"tiny"), vec![b"tiny".to_vec()]);
+        assert!(chunks(b"").is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::sandbox::DEFAULT_SANDBOX_TIMEOUT_SECS;
+use crate::sbom::SbomFormat;
 use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use serde_json::{Value, json};
 
@@ -14,6 +15,11 @@ pub struct Cli {
     #[arg(long, global = true, default_value_t = OutputFormat::Text, value_enum)]
     pub format: OutputFormat,
 
+    /// Comma-separated columns for list output (`--format tsv|json`). Column
+    /// names match the JSON field names, e.g. `--columns name,version`.
+    #[arg(long, global = true, value_delimiter = ',', value_name = "COLUMNS")]
+    pub columns: Vec<String>,
+
     /// Progress UI mode (auto hides on non-TTY).
     #[arg(
         long,
@@ -28,6 +34,51 @@ pub struct Cli {
     #[arg(long = "no-progress", global = true)]
     pub no_progress: bool,
 
+    /// Largest string or list kept inline in JSON output (e.g. `512KB`);
+    /// bigger sections are written to files under $PYBUN_HOME/payloads and
+    /// replaced by a reference with path, sha256, and size. 0 = no limit.
+    #[arg(
+        long,
+        global = true,
+        value_name = "SIZE",
+        default_value = "1MB",
+        value_parser = crate::cache::parse_size,
+        env = "PYBUN_MAX_INLINE_BYTES"
+    )]
+    pub max_inline_bytes: u64,
+
+    /// Longest time to wait for the command (e.g. `90s`, `5m`). On expiry,
+    /// print a checkpoint with the phase, percent complete, ETA, and an
+    /// operation id for `pybun status --operation`.
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        value_parser = crate::operation::parse_duration
+    )]
+    pub max_duration: Option<std::time::Duration>,
+
+    /// What to do with the command when `--max-duration` expires.
+    #[arg(
+        long,
+        global = true,
+        default_value_t = TimeoutAction::Cancel,
+        value_enum,
+        requires = "max_duration"
+    )]
+    pub on_timeout: TimeoutAction,
+
+    /// Never use the network: only the wheel cache, cached PEP 723
+    /// environments and installed runtimes. Commands that need a download
+    /// fail with `E_NETWORK_REQUIRED`, listing the missing artifacts.
+    #[arg(
+        long,
+        global = true,
+        env = "PYBUN_OFFLINE",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub offline: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -36,6 +87,19 @@ pub struct Cli {
 pub enum OutputFormat {
     Text,
     Json,
+    /// Tab-separated rows with a header line (list commands only).
+    Tsv,
+    /// One JSON record per line: events and diagnostics as they happen,
+    /// then the result.
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// Whether stdout carries JSON (the envelope or NDJSON records), so
+    /// commands must keep it free of human-readable output.
+    pub fn is_json(self) -> bool {
+        matches!(self, OutputFormat::Json | OutputFormat::Ndjson)
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
@@ -45,6 +109,14 @@ pub enum ProgressMode {
     Never,
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum TimeoutAction {
+    /// Stop the command.
+    Cancel,
+    /// Leave the command running in the background.
+    Detach,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Initialize a new Python project.
@@ -55,6 +127,13 @@ pub enum Commands {
     Add(PackageArgs),
     /// Remove a package and update lockfile.
     Remove(PackageArgs),
+    /// Rename a dependency across pyproject.toml, the lockfile and
+    /// (optionally) import statements. Previews by default.
+    #[command(name = "rename-dep")]
+    RenameDep(RenameDepArgs),
+    /// Import dependencies from a requirements file, Pipfile, or Poetry
+    /// pyproject.toml, then lock them.
+    Import(ImportArgs),
     /// Lock dependencies for scripts.
     Lock(LockArgs),
     /// Run a script with import/runtime optimizations.
@@ -63,8 +142,12 @@ pub enum Commands {
     X(ToolArgs),
     /// Execute test suite with PyBun's fast runner.
     Test(TestArgs),
+    /// Run benchmarks and compare them with earlier runs.
+    Bench(BenchArgs),
     /// Build distributable artifacts.
     Build(BuildArgs),
+    /// Upload built distributions to a package index.
+    Publish(PublishArgs),
     /// Diagnose environment and produce support bundle.
     Doctor(DoctorArgs),
     /// Run PyBun as an MCP server.
@@ -75,6 +158,9 @@ pub enum Commands {
     SelfCmd(SelfCommands),
     /// Manage caches.
     Gc(GcArgs),
+    /// Inspect the cache, verify it, and share wheels through a remote cache.
+    #[command(subcommand)]
+    Cache(CacheCommands),
     /// Manage Python versions (install, list, remove).
     #[command(subcommand)]
     Python(PythonCommands),
@@ -86,7 +172,7 @@ pub enum Commands {
     LazyImport(LazyImportArgs),
     /// Watch files and reload on changes (dev mode).
     Watch(WatchArgs),
-    /// Show or configure launch profiles.
+    /// Show or configure launch profiles, or profile a script's imports.
     Profile(ProfileArgs),
     /// Print or validate the CLI JSON schema.
     Schema(SchemaArgs),
@@ -99,8 +185,72 @@ pub enum Commands {
     Upgrade(UpgradeArgs),
     /// Detect dependency drift: undeclared imports and unused declarations.
     Drift(DriftArgs),
-    /// Scan installed packages for known vulnerabilities using the OSV database.
+    /// Export the locked dependency graph as DOT, GraphML, or SPDX.
+    Graph(GraphArgs),
+    /// Show the locked dependency tree.
+    Tree(TreeArgs),
+    /// Explain which requirement chains pull a package into the lock.
+    Why(WhyArgs),
+    /// Generate a CycloneDX or SPDX SBOM from the lockfile.
+    Sbom(SbomArgs),
+    /// Export the locked packages as a hash-pinned requirements.txt.
+    Export(ExportArgs),
+    /// Scan locked or installed packages for known vulnerabilities using the OSV database.
     Audit(AuditArgs),
+    /// Manage the shell hook that activates project environments on `cd`.
+    #[command(subcommand)]
+    Hook(HookCommands),
+    /// Inspect and maintain the project virtual environment.
+    #[command(subcommand)]
+    Env(EnvCommands),
+    /// List and check every project PyBun has managed on this machine.
+    #[command(subcommand)]
+    Projects(ProjectsCommands),
+    /// Store index and publish credentials in the OS keychain.
+    #[command(subcommand)]
+    Auth(AuthCommands),
+    /// Show project command aliases from `[tool.pybun.alias]`.
+    #[command(subcommand)]
+    Alias(AliasCommands),
+    /// Check `[tool.pybun]` and `pybun.toml` settings.
+    #[command(subcommand)]
+    Config(ConfigCommands),
+    /// Print a shell completion script (includes project aliases).
+    Completions(CompletionsArgs),
+    /// Manage PEP 723 scripts and their lockfiles.
+    #[command(subcommand)]
+    Script(ScriptCommands),
+    /// Show the progress or result of an operation started with `--max-duration`.
+    Status(StatusArgs),
+    /// Describe an error code and its common fixes.
+    Explain(ExplainArgs),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ScriptCommands {
+    /// Create, refresh, or verify a script's `<script>.lock`.
+    Lock(ScriptLockArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ScriptLockArgs {
+    /// PEP 723 script whose lock to manage.
+    #[arg(value_name = "SCRIPT")]
+    pub script: std::path::PathBuf,
+    /// Re-resolve every dependency to the newest allowed version and rewrite
+    /// the lock, even if it still satisfies the declared requirements.
+    #[arg(long, conflicts_with = "check")]
+    pub upgrade: bool,
+    /// Verify the lock satisfies the declared requirements without writing;
+    /// exits non-zero on drift.
+    #[arg(long)]
+    pub check: bool,
+    /// Use offline mode when cache is sufficient.
+    #[arg(long)]
+    pub offline: bool,
+    /// Path to index JSON (temporary M1 flag).
+    #[arg(long)]
+    pub index: Option<std::path::PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -113,6 +263,8 @@ pub enum PythonCommands {
     Remove(PythonRemoveArgs),
     /// Show path to Python for a version.
     Which(PythonWhichArgs),
+    /// Pin the project's Python version in `.python-version`.
+    Pin(PythonPinArgs),
 }
 
 #[derive(Args, Debug)]
@@ -120,13 +272,39 @@ pub struct PythonListArgs {
     /// Show all available versions (not just installed).
     #[arg(long)]
     pub all: bool,
+    /// Fetch the upstream release manifests not cached yet, for archive
+    /// sizes, build dates and checksums (requires network).
+    #[arg(long)]
+    pub fetch_sizes: bool,
 }
 
 #[derive(Args, Debug)]
 pub struct PythonInstallArgs {
-    /// Version to install (e.g., 3.11, 3.12.7).
+    /// Version to install (e.g., 3.11, 3.12.7, free-threaded 3.13t, or
+    /// PyPy pypy3.10).
     #[arg(value_name = "VERSION")]
     pub version: String,
+    /// Build CPython from a git ref instead of downloading a prebuilt
+    /// runtime. The build is installed as `VERSION+REF[.debug][.lto]`.
+    #[arg(long)]
+    pub from_source: bool,
+    /// Tag, branch, or commit to build (defaults to `vVERSION`).
+    #[arg(long = "ref", value_name = "REF", requires = "from_source")]
+    pub git_ref: Option<String>,
+    /// Configure a debug build (`--with-pydebug`).
+    #[arg(long, requires = "from_source")]
+    pub pydebug: bool,
+    /// Configure with link-time optimization (`--with-lto`).
+    #[arg(long, requires = "from_source")]
+    pub lto: bool,
+    /// Extra argument for CPython's `configure` (can be specified multiple times).
+    #[arg(
+        long = "configure-arg",
+        value_name = "ARG",
+        allow_hyphen_values = true,
+        requires = "from_source"
+    )]
+    pub configure_args: Vec<String>,
 }
 
 #[derive(Args, Debug)]
@@ -134,6 +312,9 @@ pub struct PythonRemoveArgs {
     /// Version to remove.
     #[arg(value_name = "VERSION")]
     pub version: String,
+    /// Remove the runtime even if environments or pins still use it.
+    #[arg(long)]
+    pub force: bool,
 }
 
 #[derive(Args, Debug)]
@@ -143,6 +324,17 @@ pub struct PythonWhichArgs {
     pub version: Option<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct PythonPinArgs {
+    /// Version to pin (e.g., 3.12, 3.12.7, 3.13t or pypy3.10). Without it,
+    /// the current pin is shown.
+    #[arg(value_name = "VERSION")]
+    pub version: Option<String>,
+    /// Pin the version even if the project's `requires-python` excludes it.
+    #[arg(long)]
+    pub force: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct InstallArgs {
     /// Use offline mode when cache is sufficient.
@@ -153,6 +345,15 @@ pub struct InstallArgs {
     /// back to system Python and creates an isolated environment instead.
     #[arg(long)]
     pub system: bool,
+    /// Create the project-local environment without seeding pip/setuptools/wheel,
+    /// overriding `[tool.pybun.seed]`.
+    #[arg(long)]
+    pub no_seed: bool,
+    /// How to build packages that only ship source distributions. `container`
+    /// builds each sdist inside a Podman/Docker container that only sees its
+    /// build directory. Defaults to `[tool.pybun.build].isolation` or `venv`.
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub build_isolation: Option<crate::build_isolation::BuildIsolation>,
     /// Requirements to install (temporary M1 flag).
     #[arg(long = "require", value_name = "NAME==VERSION")]
     pub requirements: Vec<crate::resolver::Requirement>,
@@ -162,6 +363,24 @@ pub struct InstallArgs {
     /// Path to write lockfile.
     #[arg(long, default_value = "pybun.lockb")]
     pub lock: std::path::PathBuf,
+    /// Install exactly what the lockfile pins for this machine, without
+    /// resolving or rewriting the lockfile.
+    #[arg(long, conflicts_with_all = ["requirements", "index", "workspace", "member", "pre"])]
+    pub frozen: bool,
+    /// Install only this workspace member and what it depends on, from its
+    /// lock shard (`pybun.lock.d/<NAME>.lockb`), without resolving. Fails if
+    /// the shard has drifted from the workspace lockfile.
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["requirements", "index", "workspace", "member", "frozen", "group", "pre"]
+    )]
+    pub package: Option<String>,
+    /// Fail unless every artifact has a SHA-256 published by the index (or
+    /// recorded in the lockfile), instead of hashing downloads of artifacts
+    /// without one; also re-verify wheels reused from the wheel store.
+    #[arg(long)]
+    pub require_hashes: bool,
     /// Operate on the whole workspace, merging dependencies from the root and
     /// all members. Useful when run from inside a workspace member directory.
     #[arg(long)]
@@ -169,14 +388,31 @@ pub struct InstallArgs {
     /// Operate on a single workspace member by its `[project.name]`.
     #[arg(long, value_name = "NAME")]
     pub member: Option<String>,
-    /// Operate on a named dependency group (checks
-    /// `[project.optional-dependencies]` then `[dependency-groups]`).
+    /// Also install a named dependency group (checks
+    /// `[project.optional-dependencies]` then `[dependency-groups]`). With
+    /// --frozen, install the locked packages of that group.
     #[arg(long, value_name = "NAME")]
     pub group: Option<String>,
     /// Allow pre-release and dev versions when resolving (PEP 440 excludes
     /// them by default unless a specifier mentions one).
     #[arg(long)]
     pub pre: bool,
+    /// Resolver strategy: `default`, `pubgrub` (backtracking), or
+    /// `external:uv` (delegates to `uv pip compile`).
+    #[arg(long, value_enum, default_value_t = crate::resolver_strategy::ResolverKind::Default)]
+    pub resolver: crate::resolver_strategy::ResolverKind,
+    /// Maximum number of artifacts downloaded at once (default: 10).
+    #[arg(
+        long,
+        value_name = "N",
+        env = "PYBUN_CONCURRENT_DOWNLOADS",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub concurrency: Option<usize>,
+    /// Install new dependencies that look like typosquats, are brand-new, or
+    /// changed maintainers, reporting them as warnings instead of refusing.
+    #[arg(long)]
+    pub allow_suspicious: bool,
 }
 
 #[derive(Args, Debug)]
@@ -190,6 +426,58 @@ pub struct LockArgs {
     /// Path to index JSON (temporary M1 flag).
     #[arg(long)]
     pub index: Option<std::path::PathBuf>,
+    /// Resolver strategy: `default`, `pubgrub` (backtracking), or
+    /// `external:uv` (delegates to `uv pip compile`).
+    #[arg(long, value_enum, default_value_t = crate::resolver_strategy::ResolverKind::Default)]
+    pub resolver: crate::resolver_strategy::ResolverKind,
+    /// Also resolve with a baseline strategy (`default`, or `pubgrub` when
+    /// `--resolver default`) and report the differences and timings.
+    #[arg(long)]
+    pub compare: bool,
+    /// Pick wheels for another machine: the most specific wheel platform tag
+    /// it supports (e.g. `manylinux_2_28_x86_64`, `musllinux_1_2_aarch64`,
+    /// `macosx_14_0_arm64`, `win_amd64`). Repeat to lock for several
+    /// platforms at once. Defaults to this machine.
+    #[arg(long, value_name = "TAG")]
+    pub platform: Vec<String>,
+    /// Python version to lock for (e.g. `3.12`). Repeat to lock for several
+    /// versions at once. Defaults to the project's interpreter.
+    #[arg(long, value_name = "VERSION")]
+    pub python: Vec<String>,
+    /// Fail when the index publishes no SHA-256 for a selected artifact,
+    /// instead of downloading it to record the hash.
+    #[arg(long)]
+    pub require_hashes: bool,
+    /// Rewrite the per-member lock shards of the workspace lockfile without
+    /// resolving.
+    #[arg(long, conflicts_with_all = ["script", "compare", "check_shards"])]
+    pub shards: bool,
+    /// Fail if any per-member lock shard differs from what the workspace
+    /// lockfile implies, without resolving or writing anything.
+    #[arg(long, conflicts_with_all = ["script", "compare"])]
+    pub check_shards: bool,
+    /// Fail if `pybun.lockb` no longer matches pyproject.toml (missing,
+    /// extra, or out-of-range packages), without resolving, writing, or
+    /// using the network.
+    #[arg(long, conflicts_with_all = ["script", "compare", "shards", "check_shards"])]
+    pub check: bool,
+    /// Convert this `uv.lock` or `poetry.lock` into `pybun.lockb` instead of
+    /// resolving: packages keep their locked versions, sources and hashes.
+    #[arg(
+        long,
+        value_name = "LOCKFILE",
+        conflicts_with_all = ["script", "compare", "shards", "check_shards", "check"]
+    )]
+    pub from: Option<std::path::PathBuf>,
+    /// Move one locked package to exactly this version (`NAME==VERSION`),
+    /// bumping only the dependencies it needs and keeping every other
+    /// package, and print the changeset.
+    #[arg(
+        long,
+        value_name = "NAME==VERSION",
+        conflicts_with_all = ["script", "compare", "shards", "check_shards", "check", "from"]
+    )]
+    pub update_only: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -205,24 +493,135 @@ pub struct PackageArgs {
     /// them by default unless a specifier mentions one).
     #[arg(long)]
     pub pre: bool,
+    /// Add to (or remove from) a dependency group instead of
+    /// `[project.dependencies]`: an existing `[project.optional-dependencies]`
+    /// extra, else a PEP 735 `[dependency-groups]` entry.
+    #[arg(long, value_name = "NAME")]
+    pub group: Option<String>,
+    /// Add packages that look like typosquats, are brand-new, or changed
+    /// maintainers, reporting them as warnings instead of refusing.
+    #[arg(long)]
+    pub allow_suspicious: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RenameDepArgs {
+    /// Current dependency name (e.g. `pil`).
+    #[arg(value_name = "OLD")]
+    pub old: String,
+    /// New dependency name (e.g. `pillow`).
+    #[arg(value_name = "NEW")]
+    pub new: String,
+    /// Also rewrite imports of module OLD_MODULE to NEW_MODULE in the
+    /// project's Python files. Without a value, the module names are derived
+    /// from the two dependency names.
+    #[arg(
+        long,
+        value_name = "OLD_MODULE=NEW_MODULE",
+        num_args = 0..=1,
+        default_missing_value = ""
+    )]
+    pub imports: Option<String>,
+    /// Write the changes and re-lock. Without it only the diff is shown.
+    #[arg(long)]
+    pub apply: bool,
+    /// Use offline mode when re-locking.
+    #[arg(long)]
+    pub offline: bool,
+    /// Path to index JSON used when re-locking (temporary M1 flag).
+    #[arg(long)]
+    pub index: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /// File to import: a pip requirements file (`*.txt`, `*.in`), a
+    /// `Pipfile`, or a pyproject.toml with `[tool.poetry]` dependencies.
+    #[arg(value_name = "FILE")]
+    pub file: std::path::PathBuf,
+    /// Put the file's main dependencies in this group instead of
+    /// `[project.dependencies]` (e.g. `--group dev` for requirements-dev.txt).
+    #[arg(long, value_name = "NAME")]
+    pub group: Option<String>,
+    /// Only update pyproject.toml; leave `pybun.lockb` alone.
+    #[arg(long)]
+    pub no_lock: bool,
+    /// Use offline mode when locking.
+    #[arg(long)]
+    pub offline: bool,
+    /// Path to index JSON used when locking (temporary M1 flag).
+    #[arg(long)]
+    pub index: Option<std::path::PathBuf>,
 }
 
 #[derive(Args, Debug)]
 pub struct RunArgs {
-    /// Script or module to execute. Use -c/--code for inline code.
+    /// Script to execute, a task from `[tool.pybun.tasks]`, or the name of a
+    /// console script (`[console_scripts]` entry point) installed in the
+    /// selected environment. Use -c/--code for inline code and -m/--module
+    /// for a module.
     #[arg(value_name = "TARGET", allow_hyphen_values = true)]
     pub target: Option<String>,
+    /// List the tasks defined in `[tool.pybun.tasks]`.
+    #[arg(long, conflicts_with_all = ["code", "module", "target"])]
+    pub list: bool,
     /// Execute the given Python code inline, like `python -c "..."`.
     #[arg(short = 'c', long = "code", value_name = "CODE")]
     pub code: Option<String>,
-    /// Run in sandboxed mode for untrusted code.
+    /// Run a module of the selected environment as a script, like `python -m MODULE`.
+    #[arg(
+        short = 'm',
+        long = "module",
+        value_name = "MODULE",
+        conflicts_with_all = ["code", "target"]
+    )]
+    pub module: Option<String>,
+    /// Run from the root of a single workspace member by its `[project.name]`;
+    /// a relative TARGET is resolved against it.
+    #[arg(long, visible_alias = "package", value_name = "NAME")]
+    pub member: Option<String>,
+    #[command(flatten)]
+    pub sandbox: SandboxArgs,
+    /// Optional profile (dev/prod/benchmark).
+    #[arg(long, default_value = "dev")]
+    pub profile: String,
+    /// Defer imports with the lazy-import hook whatever the profile, and
+    /// report the modules it deferred.
     #[arg(long)]
-    pub sandbox: bool,
+    pub lazy_imports: bool,
+    /// After the run, estimate the start-up time the hook saved by importing
+    /// the never-loaded modules in a second interpreter (this runs their
+    /// import-time code).
+    #[arg(long, requires = "lazy_imports")]
+    pub measure_lazy_savings: bool,
+    /// Python version to run a script target with, e.g. `3.12`. Uses a managed
+    /// runtime (installed if missing) as the base of the script's environment.
+    #[arg(long, value_name = "VERSION")]
+    pub python: Option<String>,
+    /// Pass additional args to the target.
+    #[arg(last = true)]
+    pub passthrough: Vec<String>,
+}
+
+/// `--sandbox` and its policy flags, shared by `pybun run` and `pybun x`.
+#[derive(Args, Debug, Clone)]
+pub struct SandboxArgs {
+    /// Run in sandboxed mode for untrusted code. Network access is refused
+    /// and writes are confined to the project directory, the environment
+    /// and the temp dir, by the kernel where supported (seccomp and Landlock
+    /// on Linux, sandbox-exec on macOS).
+    #[arg(long = "sandbox")]
+    pub enabled: bool,
     /// Allow network access inside the sandbox (escape hatch).
     #[arg(long)]
     pub allow_network: bool,
+    /// Allow network access inside the sandbox to HOST only (can be specified
+    /// multiple times; `*.example.com` matches subdomains).
+    #[arg(long, value_name = "HOST")]
+    pub allow_host: Vec<String>,
     /// Allow reading from a path inside the sandbox (can be specified multiple times).
     /// When set, reads outside these paths are blocked. Python stdlib is always allowed.
+    /// Only Python code is checked: the kernel does not confine reads.
     #[arg(long, value_name = "PATH")]
     pub allow_read: Vec<String>,
     /// Allow writing to a path inside the sandbox (can be specified multiple times).
@@ -235,27 +634,70 @@ pub struct RunArgs {
     #[arg(long, value_name = "VAR")]
     pub allow_env: Vec<String>,
     /// Maximum wall-clock execution time in seconds for sandboxed runs (0 = unlimited).
-    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_SANDBOX_TIMEOUT_SECS)]
-    pub sandbox_timeout: u64,
+    #[arg(long = "sandbox-timeout", value_name = "SECONDS", default_value_t = DEFAULT_SANDBOX_TIMEOUT_SECS)]
+    pub timeout: u64,
     /// Maximum memory (virtual address space) in megabytes for sandboxed runs (Unix only; 0 = unlimited).
-    #[arg(long, value_name = "MB", default_value_t = 0)]
-    pub sandbox_memory: u64,
+    #[arg(long = "sandbox-memory", value_name = "MB", default_value_t = 0)]
+    pub memory: u64,
     /// Maximum CPU time in seconds for sandboxed runs (Unix only; 0 = unlimited).
-    #[arg(long, value_name = "SECONDS", default_value_t = 0)]
-    pub sandbox_cpu: u64,
-    /// Optional profile (dev/prod/benchmark).
-    #[arg(long, default_value = "dev")]
-    pub profile: String,
-    /// Pass additional args to the target.
-    #[arg(last = true)]
-    pub passthrough: Vec<String>,
+    #[arg(long = "sandbox-cpu", value_name = "SECONDS", default_value_t = 0)]
+    pub cpu: u64,
+}
+
+impl Default for SandboxArgs {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow_network: false,
+            allow_host: Vec::new(),
+            allow_read: Vec::new(),
+            allow_write: Vec::new(),
+            allow_env: Vec::new(),
+            timeout: DEFAULT_SANDBOX_TIMEOUT_SECS,
+            memory: 0,
+            cpu: 0,
+        }
+    }
 }
 
 #[derive(Args, Debug)]
 pub struct ToolArgs {
-    /// Package to execute temporarily.
+    /// Package to execute, or with `--from`, the command to run. Its
+    /// environment is cached and reused.
     #[arg(value_name = "PACKAGE")]
     pub package: Option<String>,
+    /// Install PACKAGE and run the command named by the positional argument
+    /// (`pybun x --from httpie http`), for packages whose command is not
+    /// named after them.
+    #[arg(
+        long,
+        value_name = "PACKAGE",
+        requires = "package",
+        conflicts_with_all = ["upgrade", "remove"]
+    )]
+    pub from: Option<String>,
+    /// Python version for the tool environment, e.g. `3.12`. Uses a
+    /// managed runtime, installing it if missing.
+    #[arg(long, value_name = "VERSION")]
+    pub python: Option<String>,
+    /// Pass an environment variable to the tool (repeatable). Tools only
+    /// inherit a standard set (PATH, HOME, locale, proxies, ...) by default;
+    /// accepts `NAME`, `PREFIX*`, or `*` for the whole environment.
+    /// Under `--sandbox`, use `--allow-env` instead.
+    #[arg(long, value_name = "NAME", conflicts_with = "enabled")]
+    pub pass_env: Vec<String>,
+    /// List the cached tool environments instead of running a tool.
+    #[arg(long, conflicts_with_all = ["package", "python", "pass_env", "upgrade", "remove"])]
+    pub list: bool,
+    /// Rebuild PACKAGE's cached environment with the newest matching
+    /// version instead of running it.
+    #[arg(long, requires = "package", conflicts_with_all = ["remove", "pass_env"])]
+    pub upgrade: bool,
+    /// Remove every cached environment of PACKAGE.
+    #[arg(long, requires = "package", conflicts_with_all = ["python", "pass_env"])]
+    pub remove: bool,
+    #[command(flatten)]
+    pub sandbox: SandboxArgs,
     /// Arguments to forward to the tool.
     #[arg(last = true)]
     pub passthrough: Vec<String>,
@@ -264,11 +706,13 @@ pub struct ToolArgs {
 #[derive(Args, Debug)]
 pub struct TestArgs {
     /// Test file(s) or directory to run. Defaults to current directory.
+    /// Also accepts node ids (`tests/test_api.py::TestUser::test_create`)
+    /// and `file:line` targets (`tests/test_api.py:87`, nearest test).
     #[arg(value_name = "PATH")]
     pub paths: Vec<std::path::PathBuf>,
     /// Run tests scoped to a single workspace member by its `[project.name]`.
     /// Used as the search root when no PATH is given.
-    #[arg(long, value_name = "NAME")]
+    #[arg(long, visible_alias = "package", value_name = "NAME")]
     pub member: Option<String>,
     /// Shard identifier (N/M) for distributed testing.
     #[arg(long)]
@@ -288,7 +732,8 @@ pub struct TestArgs {
     /// Run tests in parallel (number of workers).
     #[arg(long, short = 'j')]
     pub parallel: Option<usize>,
-    /// Filter tests by name pattern.
+    /// Filter tests by keyword expression over names and markers
+    /// (e.g. `-k "api and not slow"`).
     #[arg(long, short = 'k')]
     pub filter: Option<String>,
     /// Show verbose output including fixture information.
@@ -309,6 +754,47 @@ pub struct TestArgs {
     /// Number of times to retry a failing test before reporting it as failed (pybun backend only).
     #[arg(long, value_name = "N")]
     pub retries: Option<usize>,
+    /// Set an environment variable for the test processes only (repeatable).
+    /// Overrides `[tool.pybun.test.env]`.
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = crate::test_params::parse_key_value)]
+    pub env: Vec<(String, String)>,
+    /// Provide a value to the `pybun_params` fixture (repeatable).
+    /// Overrides `[tool.pybun.test.fixtures]`.
+    #[arg(long = "fixture", value_name = "KEY=VALUE", value_parser = crate::test_params::parse_key_value)]
+    pub fixtures: Vec<(String, String)>,
+    /// Report recorded test history (slowest-test trends, newly vs
+    /// chronically failing tests, suite duration over time) instead of
+    /// running tests.
+    #[arg(long)]
+    pub history: bool,
+    /// Number of most recent runs the history report covers.
+    #[arg(long, value_name = "N", default_value_t = 20, requires = "history")]
+    pub history_runs: usize,
+    /// Write the history report to a `.json` or `.csv` file.
+    #[arg(long, value_name = "PATH", requires = "history")]
+    pub history_export: Option<std::path::PathBuf>,
+    /// Write a JUnit XML report of the run to PATH (pytest and native
+    /// backends).
+    #[arg(long, value_name = "PATH", conflicts_with = "history")]
+    pub report: Option<std::path::PathBuf>,
+    /// Only run tests affected by files changed since the last run, plus
+    /// tests that did not pass last time.
+    #[arg(long, conflicts_with = "history")]
+    pub changed: bool,
+    /// Run every selected test, overriding `[tool.pybun.test] changed`.
+    #[arg(long, conflicts_with = "changed")]
+    pub all: bool,
+    /// Measure coverage with coverage.py (installed into the project's
+    /// virtual environment when missing) and report it in `detail.coverage`.
+    #[arg(long, conflicts_with = "history")]
+    pub coverage: bool,
+    /// Fail the run when total coverage is below PERCENT.
+    #[arg(long, value_name = "PERCENT", requires = "coverage")]
+    pub coverage_fail_under: Option<f64>,
+    /// Also write a coverage report: `lcov` (coverage.lcov), `xml`
+    /// (coverage.xml) or `json` (coverage.json). Repeatable.
+    #[arg(long, value_enum, value_name = "FORMAT", requires = "coverage")]
+    pub coverage_report: Vec<CoverageFormat>,
     /// Additional arguments to pass to the test runner.
     #[arg(last = true)]
     pub passthrough: Vec<String>,
@@ -320,13 +806,102 @@ pub enum TestBackend {
     Unittest,
     /// Native Rust-based parallel executor (pybun-native).
     Pybun,
+    /// The native executor running each test through PyBun's built-in
+    /// harness instead of pytest.
+    Native,
+}
+
+impl TestBackend {
+    /// Whether tests run per item through the Rust executor.
+    pub fn is_native(self) -> bool {
+        matches!(self, TestBackend::Pybun | TestBackend::Native)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum CoverageFormat {
+    Lcov,
+    Xml,
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Benchmark file(s) or directories. Defaults to `benchmarks/` when it
+    /// exists, else the current directory.
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<std::path::PathBuf>,
+    /// Only run benchmarks whose id contains this text.
+    #[arg(short = 'k', long, value_name = "TEXT")]
+    pub filter: Option<String>,
+    /// Untimed rounds before measuring.
+    #[arg(long, default_value_t = 2)]
+    pub warmup: u32,
+    /// Rounds measured at least.
+    #[arg(long, default_value_t = 5)]
+    pub min_rounds: u32,
+    /// Seconds to spend measuring each benchmark.
+    #[arg(long, default_value_t = 1.0)]
+    pub max_time: f64,
+    /// Compare with the baseline NAME, or the previous run when no name is
+    /// given (`--compare=NAME`).
+    #[arg(long, value_name = "NAME", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    pub compare: Option<String>,
+    /// Save this run as the baseline NAME.
+    #[arg(long, value_name = "NAME")]
+    pub save_baseline: Option<String>,
+    /// Percent a median may be slower than the baseline before it counts
+    /// as a regression.
+    #[arg(long, value_name = "PERCENT", default_value_t = crate::bench::DEFAULT_THRESHOLD_PERCENT)]
+    pub threshold: f64,
 }
 
 #[derive(Args, Debug)]
 pub struct BuildArgs {
-    /// Emit SBOM along with artifacts.
+    /// Write an SBOM of the artifacts and the locked dependencies to
+    /// `dist/` (CycloneDX unless `spdx` is given).
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "cyclonedx"
+    )]
+    pub sbom: Option<SbomFormat>,
+    /// Build twice from a clean `dist/` and fail if the artifacts differ.
     #[arg(long)]
-    pub sbom: bool,
+    pub check_reproducible: bool,
+    /// Set an environment variable for the build backend (repeatable).
+    /// Overrides `[tool.pybun.build.env]`.
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = crate::test_params::parse_key_value)]
+    pub env: Vec<(String, String)>,
+}
+
+#[derive(Args, Debug)]
+pub struct PublishArgs {
+    /// Wheels, sdists, or directories of them to upload (default: `dist/`).
+    #[arg(value_name = "PATH")]
+    pub files: Vec<std::path::PathBuf>,
+    /// Repository: `pypi`, `testpypi`, or an upload URL.
+    #[arg(
+        long,
+        short = 'r',
+        value_name = "NAME|URL",
+        default_value = "pypi",
+        env = "PYBUN_PUBLISH_REPOSITORY"
+    )]
+    pub repository: String,
+    /// Check the files and credentials without uploading anything.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Count files the repository already has as published instead of
+    /// failing.
+    #[arg(long)]
+    pub skip_existing: bool,
+    /// Use trusted publishing (an OIDC ID token from CI exchanged for a
+    /// short-lived API token): `auto` when no other credentials are found.
+    #[arg(long, value_enum, value_name = "WHEN", default_value = "auto")]
+    pub trusted_publishing: crate::publish::TrustedPublishing,
 }
 
 #[derive(Args, Debug)]
@@ -352,6 +927,11 @@ pub struct DoctorArgs {
     /// applied automatically and must be run manually.
     #[arg(long, requires = "fix")]
     pub apply: bool,
+    /// Combine doctor checks, outdated packages, audit findings, lock drift,
+    /// cache health and test history into a 0-100 health score with a
+    /// prioritized list of fix commands.
+    #[arg(long)]
+    pub score: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -403,11 +983,317 @@ pub struct TelemetryEnableArgs {}
 #[derive(Args, Debug)]
 pub struct TelemetryDisableArgs {}
 
+#[derive(Subcommand, Debug)]
+pub enum AliasCommands {
+    /// List aliases defined by the current project.
+    List(AliasListArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AliasListArgs {}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Report unknown keys and wrongly typed values with their file and
+    /// line; exits non-zero if there are any.
+    Validate(ConfigValidateArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigValidateArgs {}
+
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for.
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(Args, Debug)]
+pub struct StatusArgs {
+    /// Operation id printed when `--max-duration` expired.
+    #[arg(long, value_name = "ID")]
+    pub operation: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// Error code (`PYBUN-RESOLVE-001`) or diagnostic code
+    /// (`E_RESOLVE_CONFLICT`). Lists every code when omitted.
+    #[arg(value_name = "CODE")]
+    pub code: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum EnvCommands {
+    /// Remove orphaned bytecode, broken .dist-info directories, and dangling
+    /// entry-point scripts.
+    Clean(EnvCleanArgs),
+    /// List installed distributions with version, license, and size.
+    List(EnvListArgs),
+    /// Upload the environment to a remote cache as content-defined chunks,
+    /// sending only chunks the remote does not have.
+    Push(EnvPushArgs),
+    /// Restore an environment snapshot from a remote cache, fetching only
+    /// chunks missing from the local cache.
+    Pull(EnvPullArgs),
+    /// Write the environment to a deterministic, content-addressed archive
+    /// (files, installed distributions, interpreter version).
+    Snapshot(EnvSnapshotArgs),
+    /// Restore an environment from a `pybun env snapshot` archive, without
+    /// resolving or installing anything.
+    Restore(EnvRestoreArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct EnvCleanArgs {
+    /// Report what would be removed without deleting anything.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Virtual environment to clean (defaults to PYBUN_ENV, then the
+    /// project's `.pybun/venv`, `.venv`, or `venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvListArgs {
+    /// Virtual environment to list (defaults to PYBUN_ENV, then the
+    /// project's `.pybun/venv`, `.venv`, or `venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvPushArgs {
+    /// Remote cache: `https://HOST/PATH`, `s3://BUCKET/PREFIX`, or a
+    /// directory or `file://` URL (defaults to `[tool.pybun.cache.remote] url`).
+    #[arg(long, value_name = "URL", env = "PYBUN_CACHE_URL")]
+    pub remote: Option<String>,
+    /// Snapshot name (defaults to the project directory name).
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Virtual environment to push (defaults to PYBUN_ENV, then the
+    /// project's `.pybun/venv`, `.venv`, or `venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvPullArgs {
+    /// Remote cache: `https://HOST/PATH`, `s3://BUCKET/PREFIX`, or a
+    /// directory or `file://` URL (defaults to `[tool.pybun.cache.remote] url`).
+    #[arg(long, value_name = "URL", env = "PYBUN_CACHE_URL")]
+    pub remote: Option<String>,
+    /// Snapshot name (defaults to the project directory name).
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Directory to restore into, replacing it (defaults to PYBUN_ENV, then
+    /// the project's existing environment, then `.pybun/venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvSnapshotArgs {
+    /// Archive to write; `.tar.zst`, `.tar.gz` or `.tar`.
+    #[arg(long, short = 'o', value_name = "PATH", default_value = "env.tar.zst")]
+    pub output: std::path::PathBuf,
+    /// Virtual environment to snapshot (defaults to PYBUN_ENV, then the
+    /// project's `.pybun/venv`, `.venv`, or `venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvRestoreArgs {
+    /// Archive written by `pybun env snapshot`.
+    #[arg(long, short = 'i', value_name = "PATH", default_value = "env.tar.zst")]
+    pub input: std::path::PathBuf,
+    /// Directory to restore into, replacing it (defaults to PYBUN_ENV, then
+    /// the project's existing environment, then `.pybun/venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProjectsCommands {
+    /// List registered projects with their environments and disk usage.
+    List(ProjectsListArgs),
+    /// Check registered projects for stale lockfiles, missing environments,
+    /// and import drift.
+    Status(ProjectsStatusArgs),
+    /// Keep a project's locked wheels through `pybun gc`.
+    Pin(ProjectPathArgs),
+    /// Undo `pybun projects pin`.
+    Unpin(ProjectPathArgs),
+    /// Remove a project (or, with --missing, every deleted project) from the
+    /// registry.
+    Forget(ProjectsForgetArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectsListArgs {}
+
+#[derive(Args, Debug)]
+pub struct ProjectsStatusArgs {
+    /// Skip the per-project import drift scan.
+    #[arg(long)]
+    pub no_drift: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectPathArgs {
+    /// Project directory (defaults to the current project).
+    #[arg(value_name = "PATH")]
+    pub path: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectsForgetArgs {
+    /// Project directory (defaults to the current project).
+    #[arg(value_name = "PATH", conflicts_with = "missing")]
+    pub path: Option<std::path::PathBuf>,
+    /// Forget every registered project whose directory no longer exists.
+    #[arg(long)]
+    pub missing: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommands {
+    /// Store a username and password for an index or upload URL.
+    Login(AuthLoginArgs),
+    /// Remove the credentials stored for a URL.
+    Logout(AuthUrlArgs),
+    /// List stored credentials (never the secrets) and check they are readable.
+    Status(AuthStatusArgs),
+    /// Manage API tokens.
+    #[command(subcommand)]
+    Token(AuthTokenCommands),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthTokenCommands {
+    /// Store an API token (sent as user `__token__`) for a URL.
+    Set(AuthTokenSetArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AuthLoginArgs {
+    /// Index or upload URL; the credentials are sent to requests to its host.
+    #[arg(value_name = "URL")]
+    pub url: String,
+    /// Username (prompted for when omitted on a terminal).
+    #[arg(long, short)]
+    pub username: Option<String>,
+    /// Read the password from stdin instead of prompting.
+    #[arg(long)]
+    pub password_stdin: bool,
+    /// Where to store the secret (default: the OS keychain, falling back to
+    /// an encrypted file).
+    #[arg(long, value_enum, env = "PYBUN_AUTH_BACKEND")]
+    pub backend: Option<crate::auth::Backend>,
+}
+
+#[derive(Args, Debug)]
+pub struct AuthTokenSetArgs {
+    /// Index or upload URL the token is for.
+    #[arg(value_name = "URL", default_value = crate::auth::DEFAULT_UPLOAD_URL)]
+    pub url: String,
+    /// Read the token from stdin instead of prompting.
+    #[arg(long)]
+    pub token_stdin: bool,
+    /// Where to store the token (default: the OS keychain, falling back to
+    /// an encrypted file).
+    #[arg(long, value_enum, env = "PYBUN_AUTH_BACKEND")]
+    pub backend: Option<crate::auth::Backend>,
+}
+
+#[derive(Args, Debug)]
+pub struct AuthUrlArgs {
+    /// Index or upload URL.
+    #[arg(value_name = "URL")]
+    pub url: String,
+}
+
+#[derive(Args, Debug)]
+pub struct AuthStatusArgs {
+    /// Only show the credentials a request to URL would use.
+    #[arg(value_name = "URL")]
+    pub url: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HookCommands {
+    /// Install the activation hook into the shell's rc file.
+    Install(HookInstallArgs),
+    /// Show whether the hook is installed, enabled, and active here.
+    Status(HookStatusArgs),
+    /// Re-enable the hook after `pybun hook disable`.
+    Enable(HookToggleArgs),
+    /// Disable the hook without removing it from rc files (kill switch).
+    Disable(HookToggleArgs),
+    /// Let the hook activate a project's environment.
+    Allow(HookTrustArgs),
+    /// Stop the hook from activating a project's environment.
+    Deny(HookTrustArgs),
+    /// Print shell code that updates the environment for the current
+    /// directory. Invoked by the installed hook.
+    #[command(hide = true)]
+    Export(HookExportArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct HookInstallArgs {
+    /// Shell to install the hook for.
+    #[arg(value_enum)]
+    pub shell: crate::shell_hook::Shell,
+    /// Rc file to modify (defaults to ~/.bashrc, ~/.zshrc, or
+    /// ~/.config/fish/config.fish).
+    #[arg(long, value_name = "PATH")]
+    pub rc_file: Option<std::path::PathBuf>,
+    /// Print the hook script instead of writing it to the rc file.
+    #[arg(long)]
+    pub print: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HookStatusArgs {}
+
+#[derive(Args, Debug)]
+pub struct HookToggleArgs {}
+
+#[derive(Args, Debug)]
+pub struct HookTrustArgs {
+    /// Project directory (defaults to the project containing the current
+    /// directory).
+    #[arg(value_name = "PATH")]
+    pub path: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct HookExportArgs {
+    /// Shell syntax to emit.
+    #[arg(value_enum)]
+    pub shell: crate::shell_hook::Shell,
+}
+
 #[derive(Args, Debug)]
 pub struct McpServeArgs {
-    /// Port to bind (for HTTP mode).
+    /// Port to bind in HTTP mode (0 picks a free port).
     #[arg(long, default_value_t = 9999)]
     pub port: u16,
+    /// Address to bind in HTTP mode. Non-loopback addresses require --token.
+    #[arg(long, default_value = "127.0.0.1", value_name = "ADDR")]
+    pub host: std::net::IpAddr,
+    /// Require `Authorization: Bearer <TOKEN>` on every HTTP request.
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "PYBUN_MCP_TOKEN",
+        hide_env_values = true
+    )]
+    pub token: Option<String>,
     /// Use stdio mode for MCP communication.
     #[arg(long)]
     pub stdio: bool,
@@ -417,6 +1303,10 @@ pub struct McpServeArgs {
 pub enum SelfCommands {
     /// Update PyBun binary with signature verification.
     Update(SelfUpdateArgs),
+    /// Restore the binary replaced by the last update (works offline).
+    Rollback(SelfRollbackArgs),
+    /// Show the local update history.
+    History(SelfHistoryArgs),
 }
 
 #[derive(Args, Debug)]
@@ -427,6 +1317,85 @@ pub struct SelfUpdateArgs {
     /// Check for updates without installing.
     #[arg(long)]
     pub dry_run: bool,
+    /// Update even if this installation's rollout cohort is not yet
+    /// included, or the release was rolled back locally. A halted release
+    /// is never installed.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SelfRollbackArgs {
+    /// Show what would be restored without changing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SelfHistoryArgs {}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Print the cache directory.
+    Dir,
+    /// List cached wheels, script environments and Python runtimes.
+    List(CacheListArgs),
+    /// Show one cached wheel or script environment.
+    Info(CacheInfoArgs),
+    /// Re-hash every stored wheel and report corrupted ones.
+    Verify,
+    /// Upload the locked environment's cached wheels that the remote cache
+    /// does not have yet.
+    Push(CachePushArgs),
+    /// Download the locked environment's wheels for this platform from the
+    /// remote cache into the local wheel cache.
+    Pull(CachePullArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct CacheListArgs {
+    /// Only wheels.
+    #[arg(long)]
+    pub wheels: bool,
+    /// Only PEP 723 script environments.
+    #[arg(long)]
+    pub envs: bool,
+    /// Only installed Python runtimes.
+    #[arg(long)]
+    pub runtimes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CacheInfoArgs {
+    /// SHA-256 of a wheel or hash of a script environment (a unique prefix
+    /// is enough).
+    #[arg(value_name = "HASH")]
+    pub hash: String,
+}
+
+#[derive(Args, Debug)]
+pub struct CachePushArgs {
+    /// Remote cache: `https://HOST/PATH`, `s3://BUCKET/PREFIX`, or a
+    /// directory or `file://` URL (defaults to `[tool.pybun.cache.remote] url`).
+    #[arg(long, value_name = "URL", env = "PYBUN_CACHE_URL")]
+    pub remote: Option<String>,
+    /// Lockfile naming the environment's wheels.
+    #[arg(long, value_name = "PATH", default_value = "pybun.lockb")]
+    pub lock: std::path::PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct CachePullArgs {
+    /// Remote cache: `https://HOST/PATH`, `s3://BUCKET/PREFIX`, or a
+    /// directory or `file://` URL (defaults to `[tool.pybun.cache.remote] url`).
+    #[arg(long, value_name = "URL", env = "PYBUN_CACHE_URL")]
+    pub remote: Option<String>,
+    /// Lockfile naming the environment's wheels.
+    #[arg(long, value_name = "PATH", default_value = "pybun.lockb")]
+    pub lock: std::path::PathBuf,
+    /// Fetch the wheels of every locked platform, not just this one.
+    #[arg(long)]
+    pub all_platforms: bool,
 }
 
 #[derive(Args, Debug)]
@@ -434,9 +1403,41 @@ pub struct GcArgs {
     /// Maximum cache size (e.g., 10G); LRU eviction if exceeded.
     #[arg(long)]
     pub max_size: Option<String>,
+    /// Evict wheels, build artifacts and PEP 723 environments not used for
+    /// this long (e.g. 30d, 2w, 12h).
+    #[arg(long, value_name = "DURATION", value_parser = crate::operation::parse_duration)]
+    pub max_age: Option<std::time::Duration>,
+    /// Per-category size limits, e.g. `wheels=5G,envs=2G,runtimes=3G`; the
+    /// least recently used entries of a category over budget are evicted.
+    #[arg(long, value_name = "CATEGORY=SIZE", value_delimiter = ',')]
+    pub budget: Vec<String>,
+    /// Never evict wheels locked by any registered project (see
+    /// `pybun projects`), not only by pinned ones.
+    #[arg(long)]
+    pub keep_used_by_lockfiles: bool,
     /// Preview what would be deleted without actually deleting.
     #[arg(long)]
     pub dry_run: bool,
+    /// With --dry-run: simulate these size limits and report what each would
+    /// evict, with per-entry hit stats and a recommended limit
+    /// (default: 1G,5G,10G when --plan is given).
+    #[arg(
+        long,
+        value_name = "SIZES",
+        value_delimiter = ',',
+        requires = "dry_run"
+    )]
+    pub sweep: Vec<String>,
+    /// With --dry-run: write the eviction plan as JSON to FILE.
+    #[arg(long, value_name = "FILE", requires = "dry_run")]
+    pub plan: Option<std::path::PathBuf>,
+    /// Read-only cache mode: copy the job's overlay (its cache delta) to DIR
+    /// with a manifest, for upload.
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["max_size", "max_age", "budget", "dry_run"])]
+    pub export_overlay: Option<std::path::PathBuf>,
+    /// Read-only cache mode: delete the job's overlay.
+    #[arg(long, conflicts_with_all = ["max_size", "max_age", "budget", "dry_run"])]
+    pub discard_overlay: bool,
 }
 
 #[derive(Args, Debug)]
@@ -518,6 +1519,7 @@ pub struct WatchArgs {
 }
 
 #[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct ProfileArgs {
     /// Profile to show or set (dev, prod, benchmark).
     #[arg(value_name = "PROFILE")]
@@ -534,6 +1536,82 @@ pub struct ProfileArgs {
     /// Export profile to a file.
     #[arg(long, short = 'o', value_name = "FILE")]
     pub output: Option<std::path::PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<ProfileCommands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommands {
+    /// Run a script under `python -X importtime`, rank what its imports cost,
+    /// and suggest packages to defer with `pybun lazy-import`.
+    Imports(ProfileImportsArgs),
+    /// Run a script under a profiler, keep its profile file, and report the
+    /// hottest functions.
+    Run(ProfileRunArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ProfileImportsArgs {
+    /// Script to run.
+    #[arg(value_name = "SCRIPT")]
+    pub script: std::path::PathBuf,
+    /// Number of modules to list by the time spent in their own body.
+    #[arg(long, value_name = "N", default_value_t = 15)]
+    pub top: usize,
+    /// Smallest cumulative import time (milliseconds) for a package the
+    /// script imports to be suggested for lazy import.
+    #[arg(long, value_name = "MS", default_value_t = 2.0)]
+    pub min_ms: f64,
+    /// Pass additional args to the script.
+    #[arg(last = true)]
+    pub passthrough: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ProfileRunArgs {
+    /// Script to run.
+    #[arg(value_name = "SCRIPT")]
+    pub script: std::path::PathBuf,
+    /// Profiler to run the script under.
+    #[arg(long, value_enum, default_value_t = ProfileSampler::CProfile)]
+    pub sampler: ProfileSampler,
+    /// Where to write the profile (defaults to
+    /// `.pybun/profiles/<script>.speedscope.json` for py-spy and
+    /// `.pybun/profiles/<script>.prof` for cProfile).
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<std::path::PathBuf>,
+    /// Number of functions to list by the time spent in their own code.
+    #[arg(long, value_name = "N", default_value_t = 15)]
+    pub top: usize,
+    /// Samples per second (py-spy only).
+    #[arg(long, value_name = "HZ", default_value_t = 100)]
+    pub rate: u32,
+    /// Also trace allocations with `tracemalloc` (cProfile only).
+    #[arg(long)]
+    pub memory: bool,
+    /// Pass additional args to the script.
+    #[arg(last = true)]
+    pub passthrough: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ProfileSampler {
+    /// Sample the running interpreter from outside; writes a speedscope
+    /// file. Installed into a cached tool environment when not on PATH.
+    #[value(name = "py-spy")]
+    PySpy,
+    /// The standard library's deterministic profiler; writes a pstats file.
+    #[value(name = "cProfile")]
+    CProfile,
+}
+
+impl ProfileSampler {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProfileSampler::PySpy => "py-spy",
+            ProfileSampler::CProfile => "cProfile",
+        }
+    }
 }
 
 #[derive(Args, Debug)]
@@ -590,6 +1668,131 @@ pub struct DriftArgs {
     pub path: Option<std::path::PathBuf>,
 }
 
+#[derive(Args, Debug)]
+pub struct GraphArgs {
+    /// Export format.
+    #[arg(long, value_enum, default_value_t = GraphExportFormat::Dot)]
+    pub export: GraphExportFormat,
+    /// Write the export to this file instead of stdout.
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<std::path::PathBuf>,
+    /// Virtual environment to read licenses and sizes from (defaults to
+    /// PYBUN_ENV, then the project's `.pybun/venv`, `.venv`, or `venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct TreeArgs {
+    /// Show what requires PACKAGE, up to the project, instead of what the
+    /// project requires.
+    #[arg(long, value_name = "PACKAGE")]
+    pub invert: Option<String>,
+    /// Maximum depth to show (0 = the root only).
+    #[arg(long, value_name = "N")]
+    pub depth: Option<usize>,
+    /// Render as a text tree or as Graphviz DOT of the shown edges.
+    #[arg(long, value_enum, default_value_t = TreeExportFormat::Text)]
+    pub export: TreeExportFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct WhyArgs {
+    /// Locked package to explain.
+    #[arg(value_name = "PACKAGE")]
+    pub package: String,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum TreeExportFormat {
+    /// Indented text tree.
+    Text,
+    /// Graphviz DOT.
+    Dot,
+}
+
+impl TreeExportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TreeExportFormat::Text => "text",
+            TreeExportFormat::Dot => "dot",
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SbomArgs {
+    /// SBOM standard (`--format` still selects PyBun's own output).
+    #[arg(long, value_enum, default_value_t = SbomFormat::Cyclonedx)]
+    pub export: SbomFormat,
+    /// Write the SBOM to this file instead of stdout.
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<std::path::PathBuf>,
+    /// Virtual environment to read licenses from (defaults to PYBUN_ENV,
+    /// then the project's `.pybun/venv`, `.venv`, or `venv`).
+    #[arg(long, value_name = "PATH")]
+    pub venv: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// File format (`--format` still selects PyBun's own output).
+    #[arg(long, value_enum, default_value_t = ExportFormat::RequirementsTxt)]
+    pub export: ExportFormat,
+    /// Write the export to this file instead of stdout.
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<std::path::PathBuf>,
+    /// Also export the packages only this dependency group needs
+    /// (repeatable). Groups must have been locked with `install --group`.
+    /// A uv.lock always holds every locked group.
+    #[arg(long, value_name = "NAME")]
+    pub group: Vec<String>,
+    /// Leave out the `--hash` options.
+    #[arg(long)]
+    pub no_hashes: bool,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    /// pip requirements file with `==` pins, markers and `--hash` options.
+    #[value(name = "requirements.txt")]
+    RequirementsTxt,
+    /// uv's lockfile, with every locked group and the project's requirements.
+    #[value(name = "uv.lock")]
+    UvLock,
+}
+
+impl ExportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExportFormat::RequirementsTxt => "requirements.txt",
+            ExportFormat::UvLock => "uv.lock",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum GraphExportFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// GraphML with version, license, size, direct, and marker attributes.
+    Graphml,
+    /// SPDX 2.3 JSON with DEPENDS_ON relationships.
+    Spdx,
+}
+
+impl GraphExportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GraphExportFormat::Dot => "dot",
+            GraphExportFormat::Graphml => "graphml",
+            GraphExportFormat::Spdx => "spdx",
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct AuditArgs {
     /// Only report vulnerabilities at or above this severity level.
@@ -606,6 +1809,11 @@ pub struct AuditArgs {
     /// `pybun install --system`; see Issue #338).
     #[arg(long)]
     pub system: bool,
+    /// Scan the packages installed in the Python environment even when the
+    /// project has a lockfile. By default the locked packages in
+    /// `pybun.lockb` are scanned.
+    #[arg(long)]
+    pub installed: bool,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
@@ -630,9 +1838,18 @@ impl SeverityLevel {
 
 #[derive(Args, Debug)]
 pub struct UpgradeArgs {
-    /// Package(s) to upgrade (upgrades all if not specified).
+    /// Package(s) to upgrade (upgrades all if not specified). A specifier
+    /// (`requests<3`) constrains how far the package moves.
     #[arg(value_name = "PACKAGE")]
     pub packages: Vec<String>,
+    /// Ignore the upgraded packages' version constraints in pyproject.toml
+    /// and raise them to the newly locked versions.
+    #[arg(long, conflicts_with_all = ["member", "group"])]
+    pub latest: bool,
+    /// Keep a package at its locked version (or at `NAME==VERSION`) while
+    /// the rest are upgraded. Can be repeated.
+    #[arg(long, value_name = "NAME[==VERSION]")]
+    pub pin: Vec<String>,
     /// Path to index JSON (uses PyPI if not specified).
     #[arg(long)]
     pub index: Option<std::path::PathBuf>,
@@ -678,6 +1895,27 @@ pub fn json_help_envelope(args: &[String]) -> Option<String> {
     Some(render_help_envelope(&command, &command_name.join(" ")))
 }
 
+/// JSON envelope reporting an alias expansion error, when the raw
+/// arguments ask for `--format=json`. Aliases are expanded before clap
+/// parses the arguments, so the error never reaches command dispatch.
+pub fn alias_error_envelope(args: &[String], error: &crate::alias::AliasError) -> Option<String> {
+    let (_, wants_json, _, _) = scan_help_request(args);
+    if !wants_json {
+        return None;
+    }
+    let envelope = crate::schema::JsonEnvelope::error(
+        "pybun",
+        std::time::Duration::default(),
+        json!({ "error": error.to_string() }),
+    )
+    .with_diagnostics(vec![
+        crate::schema::Diagnostic::error(error.to_string())
+            .with_code(error.code())
+            .with_suggestion("Fix the alias in the [tool.pybun.alias] table of pyproject.toml."),
+    ]);
+    Some(envelope.to_json())
+}
+
 /// Scan raw CLI arguments for `--help`/`-h`, `--format=json`, and the
 /// subcommand chain (e.g. `pybun mcp serve --help` resolves to the `serve`
 /// `clap::Command` and path `["mcp", "serve"]`).
@@ -710,7 +1948,7 @@ fn scan_help_request(args: &[String]) -> (bool, bool, clap::Command, Vec<String>
                 iter.next();
             }
             "--format=json" => wants_json = true,
-            "--progress" => {
+            "--progress" | "--columns" | "--max-duration" | "--on-timeout" => {
                 iter.next();
             }
             s if s.starts_with('-') => {}
@@ -732,6 +1970,11 @@ fn scan_help_request(args: &[String]) -> (bool, bool, clap::Command, Vec<String>
     (wants_help, wants_json, command, path)
 }
 
+/// Subcommand chain named by `args`, e.g. `["mcp", "serve"]`.
+pub fn subcommand_path(args: &[String]) -> Vec<String> {
+    scan_help_request(args).3
+}
+
 fn render_help_envelope(command: &clap::Command, command_name: &str) -> String {
     let detail = command_help_json(command);
     let envelope = crate::schema::JsonEnvelope::new(
@@ -0,0 +1,127 @@
+//! Project-local audit log of commands that rewrite project files.
+//!
+//! Commands such as `pybun rename-dep --apply` append one JSON object per
+//! line to `<project>/.pybun/history.jsonl`: what ran, when, and which files
+//! it changed. The log is append-only; PyBun never rewrites past entries.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// History file path relative to the project root.
+pub const HISTORY_FILE: &str = ".pybun/history.jsonl";
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{path}:{line}: invalid history entry: {source}")]
+    Corrupt {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, HistoryError>;
+
+/// One recorded command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp (seconds).
+    pub timestamp: u64,
+    /// Subcommand name, e.g. `rename-dep`.
+    pub command: String,
+    /// Project-relative paths of the files the command changed.
+    pub files: Vec<String>,
+    /// Command-specific details (arguments, sections touched, ...).
+    #[serde(default)]
+    pub detail: Value,
+}
+
+impl HistoryEntry {
+    pub fn new(command: impl Into<String>, files: Vec<String>, detail: Value) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            command: command.into(),
+            files,
+            detail,
+        }
+    }
+}
+
+/// Append `entry` to the project's history, creating the file if needed.
+/// Returns the history file path.
+pub fn append(project_root: &Path, entry: &HistoryEntry) -> Result<PathBuf> {
+    let path = project_root.join(HISTORY_FILE);
+    let io_err = |source| HistoryError::Io {
+        path: path.clone(),
+        source,
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    let mut line = serde_json::to_string(entry).expect("history entries serialize");
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(io_err)?;
+    Ok(path)
+}
+
+/// All entries in the project's history, oldest first. A missing file is an
+/// empty history.
+pub fn load(project_root: &Path) -> Result<Vec<HistoryEntry>> {
+    let path = project_root.join(HISTORY_FILE);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => return Err(HistoryError::Io { path, source }),
+    };
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line).map_err(|source| HistoryError::Corrupt {
+                path: path.clone(),
+                line: idx + 1,
+                source,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn append_then_load_round_trips_in_order() {
+        let temp = tempdir().unwrap();
+        assert!(load(temp.path()).unwrap().is_empty());
+
+        let first = HistoryEntry::new("rename-dep", vec!["a.py".into()], json!({"old": "pil"}));
+        let second = HistoryEntry::new("rename-dep", vec![], Value::Null);
+        let path = append(temp.path(), &first).unwrap();
+        append(temp.path(), &second).unwrap();
+
+        assert_eq!(path, temp.path().join(HISTORY_FILE));
+        assert_eq!(load(temp.path()).unwrap(), vec![first, second]);
+    }
+}
@@ -0,0 +1,295 @@
+use super::{RenderDetail, find_python_interpreter};
+use crate::bench::{self, BenchRun, BenchSettings, BenchStats, BenchStore, Benchmark, Verdict};
+use crate::cache::Cache;
+use crate::cli::BenchArgs;
+use crate::schema::{Diagnostic, EventCollector};
+use color_eyre::eyre::{Result, eyre};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// ---------------------------------------------------------------------------
+// pybun bench
+// ---------------------------------------------------------------------------
+
+pub(crate) fn run_bench(args: &BenchArgs, collector: &mut EventCollector) -> Result<RenderDetail> {
+    if args.max_time <= 0.0 {
+        return Err(eyre!("--max-time must be positive"));
+    }
+    let cwd = std::env::current_dir()?;
+    let root = crate::project::Project::discover(&cwd)
+        .map(|project| project.root().to_path_buf())
+        .unwrap_or_else(|_| cwd.clone());
+    let paths = if !args.paths.is_empty() {
+        args.paths.clone()
+    } else if cwd.join("benchmarks").is_dir() {
+        vec![PathBuf::from("benchmarks")]
+    } else {
+        vec![PathBuf::from(".")]
+    };
+
+    let (mut benchmarks, unsupported) = bench::discover(&root, &paths);
+    for skipped in &unsupported {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "{} needs fixtures the benchmark harness does not provide: {}",
+                skipped.id,
+                skipped.fixtures.join(", ")
+            ))
+            .with_code("W_BENCH_UNSUPPORTED_FIXTURES")
+            .with_suggestion("Run it with pytest-benchmark (`pybun test`), or move the setup into the benchmark."),
+        );
+    }
+    if let Some(filter) = &args.filter {
+        benchmarks.retain(|b| b.id.contains(filter.as_str()));
+    }
+    if benchmarks.is_empty() {
+        return Ok(RenderDetail::with_json(
+            "No benchmarks found",
+            json!({ "benchmarks": [], "unsupported": unsupported }),
+        ));
+    }
+
+    let store = BenchStore::for_project(
+        Cache::new()
+            .map_err(|e| eyre!("failed to initialize cache: {}", e))?
+            .root(),
+        &root,
+    );
+    let history = store.load();
+    let baseline = match args.compare.as_deref() {
+        None => None,
+        Some("") => history
+            .runs
+            .last()
+            .map(|run| ("previous run".to_string(), run.clone())),
+        Some(name) => history
+            .baselines
+            .get(name)
+            .map(|run| (format!("baseline '{name}'"), run.clone())),
+    };
+    if let (Some(name), None) = (&args.compare, &baseline) {
+        let (message, suggestion) = if name.is_empty() {
+            (
+                "No earlier run to compare with".to_string(),
+                "Run `pybun bench` once, then compare later runs with `--compare`.".to_string(),
+            )
+        } else {
+            (
+                format!("No baseline named '{name}'"),
+                format!("Save one with `pybun bench --save-baseline {name}`."),
+            )
+        };
+        collector.diagnostic(
+            Diagnostic::warning(message)
+                .with_code("W_BENCH_NO_BASELINE")
+                .with_suggestion(suggestion),
+        );
+    }
+
+    let (python, _) = find_python_interpreter()?;
+    let settings = BenchSettings {
+        warmup: args.warmup,
+        min_rounds: args.min_rounds.max(1),
+        max_time: args.max_time,
+        ..BenchSettings::default()
+    };
+    let mut results: BTreeMap<String, BenchStats> = BTreeMap::new();
+    let mut failed = 0;
+    for (file, group) in by_file(&benchmarks) {
+        let names: Vec<&str> = group.iter().map(|b| b.name.as_str()).collect();
+        let measurements =
+            bench::run_file(&python, file, &names, &settings).map_err(|e| eyre!(e))?;
+        for benchmark in group {
+            let measurement = measurements.iter().find(|m| m.name == benchmark.name);
+            let stats = measurement
+                .filter(|m| m.error.is_none())
+                .and_then(|m| BenchStats::from_samples(&m.samples_ns, m.iterations));
+            match stats {
+                Some(stats) => {
+                    results.insert(benchmark.id.clone(), stats);
+                }
+                None => {
+                    failed += 1;
+                    let traceback = measurement
+                        .and_then(|m| m.error.clone())
+                        .unwrap_or_else(|| "no measurement reported".to_string());
+                    collector.diagnostic(
+                        Diagnostic::error(format!("FAILED {}", benchmark.id))
+                            .with_code("E_BENCH_FAILED")
+                            .with_file(benchmark.path.display().to_string())
+                            .with_line(benchmark.line as u32)
+                            .with_suggestion(format!(
+                                "Fix the benchmark and re-run `pybun bench -k {}`.",
+                                benchmark.name
+                            ))
+                            .with_context(json!({ "traceback": traceback })),
+                    );
+                }
+            }
+        }
+    }
+
+    let comparisons = baseline
+        .as_ref()
+        .map(|(_, run)| bench::compare(&results, run, args.threshold))
+        .unwrap_or_default();
+    let regressions: Vec<&bench::Comparison> = comparisons
+        .iter()
+        .filter(|c| c.verdict == Verdict::Regressed)
+        .collect();
+    for regression in &regressions {
+        collector.error_with_code(
+            "E_BENCH_REGRESSION",
+            format!(
+                "{} regressed: median {} vs {} ({:+.1}%)",
+                regression.id,
+                bench::format_ns(regression.median_ns),
+                bench::format_ns(regression.baseline_ns.unwrap_or_default()),
+                regression.change_percent.unwrap_or_default()
+            ),
+            "Check the change for a slowdown; if it is expected, save a new baseline with `pybun bench --save-baseline NAME`.",
+        );
+    }
+
+    let run = BenchRun::now(&python, results);
+    if !run.results.is_empty()
+        && let Err(e) = store.record(&run, args.save_baseline.as_deref())
+    {
+        collector.warning(format!(
+            "failed to record benchmark results in {}: {e}",
+            store.path().display()
+        ));
+    }
+
+    let rows: Vec<Value> = benchmarks
+        .iter()
+        .filter_map(|b| {
+            let stats = run.results.get(&b.id)?;
+            let comparison = comparisons.iter().find(|c| c.id == b.id);
+            let mut row = json!({
+                "id": b.id,
+                "path": b.path.display().to_string(),
+                "line": b.line,
+                "style": b.style,
+            });
+            merge(&mut row, json!(stats));
+            if let Some(comparison) = comparison {
+                merge(
+                    &mut row,
+                    json!({
+                        "baseline_ns": comparison.baseline_ns,
+                        "change_percent": comparison.change_percent,
+                        "verdict": comparison.verdict,
+                    }),
+                );
+            }
+            Some(row)
+        })
+        .collect();
+
+    let text = render_text(
+        &benchmarks,
+        &run,
+        &comparisons,
+        baseline.as_ref().map(|b| b.0.as_str()),
+    );
+    let summary = format!(
+        "{} benchmark(s): {} measured, {} failed, {} regressed",
+        benchmarks.len(),
+        run.results.len(),
+        failed,
+        regressions.len()
+    );
+    let detail = json!({
+        "summary": summary,
+        "benchmarks": rows,
+        "failed": failed,
+        "regressions": regressions.len(),
+        "unsupported": unsupported,
+        "baseline": baseline.as_ref().map(|(name, run)| json!({
+            "name": name,
+            "timestamp": run.timestamp,
+        })),
+        "saved_baseline": args.save_baseline,
+        "threshold_percent": args.threshold,
+        "settings": settings,
+        "python": python,
+    });
+    let text = format!("{summary}\n{text}");
+    let detail = if failed > 0 || !regressions.is_empty() {
+        RenderDetail::error(text, detail)
+    } else {
+        RenderDetail::with_json(text, detail)
+    };
+    Ok(detail.with_table(crate::table::TableSpec::new(
+        "benchmarks",
+        &[
+            "id",
+            "median_ns",
+            "stddev_ns",
+            "ops_per_sec",
+            "rounds",
+            "outliers",
+        ],
+    )))
+}
+
+/// Benchmarks grouped by file, in discovery order.
+fn by_file(benchmarks: &[Benchmark]) -> Vec<(&Path, Vec<&Benchmark>)> {
+    let mut groups: Vec<(&Path, Vec<&Benchmark>)> = Vec::new();
+    for benchmark in benchmarks {
+        match groups.iter_mut().find(|(path, _)| *path == benchmark.path) {
+            Some((_, group)) => group.push(benchmark),
+            None => groups.push((&benchmark.path, vec![benchmark])),
+        }
+    }
+    groups
+}
+
+fn merge(target: &mut Value, extra: Value) {
+    if let (Value::Object(target), Value::Object(extra)) = (target, extra) {
+        target.extend(extra);
+    }
+}
+
+fn render_text(
+    benchmarks: &[Benchmark],
+    run: &BenchRun,
+    comparisons: &[bench::Comparison],
+    baseline: Option<&str>,
+) -> String {
+    let width = benchmarks.iter().map(|b| b.id.len()).max().unwrap_or(0);
+    let mut lines = Vec::new();
+    if let Some(baseline) = baseline {
+        lines.push(format!("Compared with the {baseline}"));
+    }
+    for benchmark in benchmarks {
+        let Some(stats) = run.results.get(&benchmark.id) else {
+            lines.push(format!("{:<width$}  FAILED", benchmark.id));
+            continue;
+        };
+        let mut line = format!(
+            "{:<width$}  {:>10} ± {:<10} {:>12.0} ops/s  {} rounds",
+            benchmark.id,
+            bench::format_ns(stats.median_ns),
+            bench::format_ns(stats.stddev_ns),
+            stats.ops_per_sec,
+            stats.rounds
+        );
+        if stats.outliers > 0 {
+            line.push_str(&format!(" ({} outliers)", stats.outliers));
+        }
+        if let Some(comparison) = comparisons.iter().find(|c| c.id == benchmark.id) {
+            match (comparison.verdict, comparison.change_percent) {
+                (Verdict::New, _) | (_, None) => line.push_str("  new"),
+                (Verdict::Regressed, Some(change)) => {
+                    line.push_str(&format!("  {change:+.1}% REGRESSED"))
+                }
+                (_, Some(change)) => line.push_str(&format!("  {change:+.1}%")),
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
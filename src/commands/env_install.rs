@@ -0,0 +1,391 @@
+//! Dependency installs for the environments `pybun run` (PEP 723) and
+//! `pybun x` create.
+//!
+//! The native installer resolves the requirements, downloads wheels through
+//! the shared [`WheelCache`] and installs them with
+//! [`installer::install_wheel_into`], so the base interpreter does not need
+//! pip. Packages that only ship an sdist are handed to `uv pip install` (or
+//! pip, bootstrapped with `ensurepip`). `PYBUN_INSTALLER=pip` restores the
+//! subprocess installer for everything.
+
+use super::{python_version_env_override, warn_on_prerelease_fallback};
+use crate::installer::{self, InstallTarget};
+use crate::pypi_index::RemoteIndex;
+use crate::resolver::{
+    Requirement, ResolveOptions, current_platform_tags, python_version_to_cp_tag,
+    resolve_with_options, select_artifact_for_platform_with_cp,
+};
+use crate::schema::{Diagnostic, EventCollector};
+use crate::tool_exec::{ToolError, ToolRun};
+use crate::wheel_cache::WheelCache;
+use color_eyre::eyre::{Result, eyre};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+use std::time::Instant;
+
+/// Selects the installer: `native` (default) or `pip`.
+pub(crate) const INSTALLER_ENV: &str = "PYBUN_INSTALLER";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum InstallerKind {
+    Native,
+    /// `uv pip install` when uv is on PATH, else the environment's pip.
+    Pip,
+}
+
+impl InstallerKind {
+    pub(crate) fn from_env() -> Self {
+        match std::env::var(INSTALLER_ENV) {
+            Ok(value) if matches!(value.to_ascii_lowercase().as_str(), "pip" | "uv") => Self::Pip,
+            _ => Self::Native,
+        }
+    }
+}
+
+/// What an install did, for the JSON detail of `run` and `x`.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct EnvInstall {
+    pub installer: InstallerKind,
+    pub resolve_ms: u64,
+    pub total_ms: u64,
+    pub packages: Vec<PackageInstall>,
+    /// `name==version` pins without a usable wheel, installed by uv/pip.
+    pub fallback: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct PackageInstall {
+    pub name: String,
+    pub version: String,
+    pub wheel: String,
+    /// The wheel came from the wheel cache.
+    pub cached: bool,
+    pub download_ms: u64,
+    pub install_ms: u64,
+}
+
+/// An install into one virtual environment.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct InstallRequest<'a> {
+    pub venv: &'a Path,
+    /// Version of the venv's interpreter (`3.12.4`).
+    pub python_version: &'a str,
+    pub requirements: &'a [String],
+    /// Install exactly the requirements (pins from a script lock).
+    pub no_deps: bool,
+    /// `UV_CACHE_DIR`/`--cache-dir` for the subprocess installer.
+    pub pip_cache_dir: Option<&'a Path>,
+}
+
+/// Create a virtual environment at `venv`. Only the pip installer without uv
+/// needs pip seeded into it.
+pub(crate) fn create_venv(python: &Path, venv: &Path, kind: InstallerKind) -> Result<()> {
+    let mut cmd = ProcessCommand::new(python);
+    cmd.args(["-m", "venv"]);
+    if kind == InstallerKind::Native || crate::env::find_uv_executable().is_some() {
+        cmd.arg("--without-pip");
+    }
+    let status = cmd
+        .arg(venv)
+        .status()
+        .map_err(|e| eyre!("failed to create virtual environment: {}", e))?;
+    if !status.success() {
+        return Err(eyre!("failed to create virtual environment"));
+    }
+    Ok(())
+}
+
+/// Install `request.requirements` into `request.venv` with `kind`.
+pub(crate) async fn install(
+    request: InstallRequest<'_>,
+    kind: InstallerKind,
+    collector: &mut EventCollector,
+) -> Result<EnvInstall> {
+    let started = Instant::now();
+    let mut report = match kind {
+        InstallerKind::Native => install_native(request, collector).await?,
+        InstallerKind::Pip => {
+            eprintln!(
+                "info: installing {} dependencies...",
+                request.requirements.len()
+            );
+            install_with_pip(request, request.requirements, request.no_deps, collector)?;
+            EnvInstall {
+                installer: kind,
+                resolve_ms: 0,
+                total_ms: 0,
+                packages: Vec::new(),
+                fallback: Vec::new(),
+            }
+        }
+    };
+    report.total_ms = elapsed_ms(started);
+    Ok(report)
+}
+
+async fn install_native(
+    request: InstallRequest<'_>,
+    collector: &mut EventCollector,
+) -> Result<EnvInstall> {
+    eprintln!("info: resolving dependencies (native)...");
+    // Cache identities carry a `+<build>` suffix for source-built runtimes,
+    // and `x` may not know the version before the venv exists.
+    let python_version = request
+        .python_version
+        .split('+')
+        .next()
+        .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+        .or_else(|| created_version(request.venv));
+    let resolve_started = Instant::now();
+    let requirements: Vec<Requirement> = request
+        .requirements
+        .iter()
+        .map(|d| d.parse().unwrap_or_else(|_| Requirement::any(d)))
+        .collect();
+    let index = RemoteIndex::from_env(crate::offline::is_enabled()).map_err(|e| eyre!(e))?;
+    let resolution = resolve_with_options(
+        requirements.clone(),
+        &index,
+        ResolveOptions {
+            python_version: python_version_env_override().or_else(|| python_version.clone()),
+            ..Default::default()
+        },
+    )
+    .await;
+    for notice in index.take_stale_cache_notices() {
+        collector.warning(notice);
+    }
+    let resolution = resolution.map_err(|e: crate::resolver::ResolveError| eyre!(e))?;
+    warn_on_prerelease_fallback(&resolution, collector);
+    let resolve_ms = elapsed_ms(resolve_started);
+
+    let requested: Vec<String> = requirements
+        .iter()
+        .map(|r| crate::pypi::normalize_project_name(&r.name))
+        .collect();
+    let packages: Vec<_> = resolution
+        .packages
+        .values()
+        .filter(|pkg| {
+            !request.no_deps || requested.contains(&crate::pypi::normalize_project_name(&pkg.name))
+        })
+        .collect();
+
+    let wheel_cache = WheelCache::new().map_err(|e| eyre!("failed to init wheel cache: {}", e))?;
+    let platform_tags = current_platform_tags();
+    let cp_tag = python_version
+        .as_deref()
+        .and_then(python_version_to_cp_tag)
+        .unwrap_or_else(|| "cp311".to_string());
+    let mut fallback = Vec::new();
+    let mut downloads = Vec::new();
+    for pkg in packages {
+        let selection = select_artifact_for_platform_with_cp(pkg, &platform_tags, &cp_tag);
+        let Some(url) = selection.url.clone().filter(|_| !selection.from_source) else {
+            fallback.push(format!("{}=={}", pkg.name, pkg.version));
+            continue;
+        };
+        let wheel_cache = &wheel_cache;
+        downloads.push(async move {
+            let started = Instant::now();
+            let cached = selection
+                .hash
+                .as_deref()
+                .is_some_and(|hash| wheel_cache.find(hash, &selection.filename).is_some());
+            let path = wheel_cache
+                .get_wheel(
+                    &pkg.name,
+                    &selection.filename,
+                    &url,
+                    selection.hash.as_deref(),
+                )
+                .await;
+            let record = PackageInstall {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                wheel: selection.filename.clone(),
+                cached,
+                download_ms: elapsed_ms(started),
+                install_ms: 0,
+            };
+            (record, path, url)
+        });
+    }
+
+    eprintln!("info: downloading {} packages...", downloads.len());
+    let mut wheels: Vec<(PackageInstall, PathBuf)> = Vec::new();
+    let mut missing = Vec::new();
+    for (record, path, url) in futures::future::join_all(downloads).await {
+        match path {
+            Ok(path) => wheels.push((record, path)),
+            Err(_) if crate::offline::is_enabled() => {
+                missing.push(crate::offline::MissingArtifact::new(
+                    crate::offline::ArtifactKind::Wheel,
+                    record.wheel,
+                    Some(url),
+                ));
+            }
+            Err(e) => return Err(eyre!("download of {} failed: {}", record.wheel, e)),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(crate::offline::NetworkRequired::new(missing).into());
+    }
+
+    let target = venv_target(request.venv, python_version.as_deref().unwrap_or_default());
+    eprintln!("info: installing {} packages...", wheels.len());
+    let mut installed = Vec::new();
+    for (mut record, wheel) in wheels {
+        let started = Instant::now();
+        installer::install_wheel_into(&wheel, &target)
+            .map_err(|e| eyre!("failed to install wheel {}: {}", record.wheel, e))?;
+        record.install_ms = elapsed_ms(started);
+        installed.push(record);
+    }
+
+    if !fallback.is_empty() {
+        let message = format!(
+            "no compatible wheel for {}; installing from source with pip",
+            fallback.join(", ")
+        );
+        eprintln!("info: {}", message);
+        collector.info(message);
+        install_with_pip(request, &fallback, true, collector)?;
+    }
+
+    Ok(EnvInstall {
+        installer: InstallerKind::Native,
+        resolve_ms,
+        total_ms: 0,
+        packages: installed,
+        fallback,
+    })
+}
+
+/// Install layout of `venv`, preferring the `lib/pythonX.Y` directory `venv`
+/// created over `python_version`.
+fn venv_target(venv: &Path, python_version: &str) -> InstallTarget {
+    let created = created_version(venv);
+    InstallTarget::for_venv(venv, created.as_deref().unwrap_or(python_version))
+}
+
+/// `X.Y` of the `lib/pythonX.Y` directory of a POSIX venv.
+fn created_version(venv: &Path) -> Option<String> {
+    std::fs::read_dir(venv.join("lib"))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix("python").map(str::to_string)
+        })
+        .find(|version| version.contains('.'))
+}
+
+/// Install `specs` with `uv pip install`, or with the environment's pip
+/// (seeded with `ensurepip` when the venv was created without it).
+fn install_with_pip(
+    request: InstallRequest<'_>,
+    specs: &[String],
+    no_deps: bool,
+    collector: &mut EventCollector,
+) -> Result<()> {
+    let uv = crate::env::find_uv_executable();
+    let mut cmd = if let Some(uv_path) = &uv {
+        eprintln!("info: using uv for fast installation");
+        let mut cmd = ProcessCommand::new(uv_path);
+        cmd.args(["pip", "install", "--quiet", "--python"])
+            .arg(request.venv);
+        if let Some(dir) = request.pip_cache_dir {
+            if std::env::var_os("UV_CACHE_DIR").is_none() {
+                cmd.env("UV_CACHE_DIR", dir);
+            }
+            if std::env::var_os("PIP_CACHE_DIR").is_none() {
+                cmd.env("PIP_CACHE_DIR", dir);
+            }
+        }
+        cmd
+    } else {
+        let python = venv_target(request.venv, request.python_version).python;
+        let pip = python.with_file_name(if cfg!(windows) { "pip.exe" } else { "pip" });
+        if !pip.exists() {
+            let mut ensurepip = ProcessCommand::new(&python);
+            ensurepip.args(["-m", "ensurepip", "--default-pip"]);
+            ToolRun::new("ensurepip", &mut ensurepip)
+                .run()
+                .map_err(|e| eyre!("failed to bootstrap pip: {}", e))?;
+        }
+        let mut cmd = ProcessCommand::new(&python);
+        cmd.args(["-m", "pip", "install", "--quiet"]);
+        if let Some(dir) = request.pip_cache_dir {
+            cmd.arg("--cache-dir").arg(dir);
+        }
+        cmd
+    };
+    super::offline_install_args(&mut cmd, uv.is_some());
+    if no_deps {
+        cmd.arg("--no-deps");
+    }
+    let installer = if uv.is_some() { "uv" } else { "pip" };
+    cmd.args(specs);
+    // Installing the same pins again is harmless, so index hiccups are
+    // retried.
+    let error = match ToolRun::new(installer, &mut cmd).idempotent().run() {
+        Ok(_) => return Ok(()),
+        Err(error) => error,
+    };
+    if matches!(error, ToolError::NotFound { .. } | ToolError::Spawn { .. }) {
+        return Err(eyre!("failed to run the package installer: {}", error));
+    }
+    if crate::offline::is_enabled() {
+        // Neither uv nor pip reports which packages the cache lacked; list
+        // every requested one.
+        let missing = specs
+            .iter()
+            .map(|spec| {
+                crate::offline::MissingArtifact::new(
+                    crate::offline::ArtifactKind::Wheel,
+                    spec.clone(),
+                    None,
+                )
+            })
+            .collect();
+        return Err(crate::offline::NetworkRequired::new(missing).into());
+    }
+    collector.diagnostic(
+        Diagnostic::warning(format!("failed to install dependencies with {installer}"))
+            .with_tool_failure(&error),
+    );
+    Err(eyre!("failed to install {}: {}", specs.join(" "), error))
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn venv_target_follows_the_created_lib_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let venv = temp.path().join("venv");
+        std::fs::create_dir_all(venv.join("lib/python3.11/site-packages")).unwrap();
+        let target = venv_target(&venv, "unknown");
+        if cfg!(windows) {
+            assert_eq!(target.site_packages, venv.join("Lib").join("site-packages"));
+        } else {
+            assert_eq!(
+                target.site_packages,
+                venv.join("lib/python3.11/site-packages")
+            );
+        }
+        assert_eq!(
+            venv_target(&temp.path().join("none"), "3.12.1").site_packages,
+            InstallTarget::for_venv(&temp.path().join("none"), "3.12").site_packages
+        );
+    }
+}
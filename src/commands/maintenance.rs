@@ -1,20 +1,308 @@
 use super::RenderDetail;
-use crate::audit::{default_osv_url, list_installed_packages, scan_for_vulnerabilities};
+use crate::advisory_db::AdvisoryDb;
+use crate::audit::{
+    InstalledPackage, default_osv_url, list_installed_packages, locked_packages,
+    scan_with_advisory_db,
+};
 use crate::cache::{Cache, format_size, parse_size};
-use crate::cli::AuditArgs;
+use crate::cli::{AuditArgs, GraphExportFormat, TreeExportFormat};
+use crate::dep_graph::DependencyGraph;
+use crate::dep_tree::DependencyTree;
 use crate::env::find_python_env;
+use crate::fix_plan::{self, FixStatus, PlannedFix, Precondition, SimulatedFix, SystemProbe};
+use crate::health::{
+    AuditFinding, AuditSignal, CacheSignal, DoctorSignal, LockSignal, OutdatedPackage,
+    OutdatedSignal, Signals, TestSignal,
+};
+use crate::lockfile::Lockfile;
 use crate::pep723_cache::Pep723Cache;
 use crate::project::Project;
-use crate::schema::{Diagnostic, EventCollector};
-use crate::self_heal::{fix_candidates_for_missing_python, fix_candidates_for_stale_pypi_cache};
+use crate::sbom::Sbom;
+use crate::schema::{Diagnostic, EventCollector, FixCandidate};
+use crate::self_heal::{
+    fix_candidates_for_http_config, fix_candidates_for_missing_python,
+    fix_candidates_for_stale_pypi_cache, fix_candidates_for_tls_interception,
+};
 use crate::support_bundle::{BundleContext, BundleReport, build_support_bundle, upload_bundle};
-use color_eyre::eyre::{Result, eyre};
+use color_eyre::eyre::{Report, Result, eyre};
 use serde_json::{Value, json};
+use std::path::Path;
 
 // ---------------------------------------------------------------------------
 // pybun doctor
 // ---------------------------------------------------------------------------
 
+/// Outcome of one HTTPS request made by [`probe_index`].
+enum ProbeOutcome {
+    Ok,
+    Certificate(String),
+    Failed(String),
+}
+
+fn probe_index(settings: &crate::http_config::HttpSettings, url: &str) -> ProbeOutcome {
+    let client = settings
+        .apply_blocking(reqwest::blocking::Client::builder())
+        .timeout(std::time::Duration::from_secs(5))
+        .build();
+    let result = match client {
+        Ok(client) => client.get(url).send(),
+        Err(e) => return ProbeOutcome::Failed(e.to_string()),
+    };
+    match result {
+        Ok(_) => ProbeOutcome::Ok,
+        Err(e) if crate::http_config::is_certificate_error(&e) => {
+            ProbeOutcome::Certificate(crate::http_config::error_chain(&e))
+        }
+        Err(e) => ProbeOutcome::Failed(crate::http_config::error_chain(&e)),
+    }
+}
+
+/// Add `tls`, `proxy`, and `tls_probe` checks. Returns false on issues.
+fn check_http(
+    checks: &mut Vec<Value>,
+    fix_diagnostics: &mut Vec<Diagnostic>,
+    collector: &mut EventCollector,
+) -> bool {
+    let settings = crate::http_config::current();
+    let mut ok = true;
+
+    let trust_origins: Vec<String> = settings
+        .trust
+        .iter()
+        .map(|t| format!("{} ({})", t.path.display(), t.origin))
+        .collect();
+    let tls_status = if settings.problems.is_empty() {
+        "ok"
+    } else {
+        "error"
+    };
+    checks.push(json!({
+        "name": "tls",
+        "status": tls_status,
+        "message": if trust_origins.is_empty() {
+            "Trusting built-in root certificates only".to_string()
+        } else {
+            format!("Trusting built-in roots plus {}", trust_origins.join(", "))
+        },
+        "trust": settings.trust,
+        "system_certs": settings.system_certs,
+        "problems": settings.problems,
+    }));
+    for problem in &settings.problems {
+        ok = false;
+        collector.warning(problem.clone());
+        fix_diagnostics.push(
+            Diagnostic::error(problem.clone())
+                .with_code("E_DOCTOR_HTTP_CONFIG")
+                .with_fix_candidates(fix_candidates_for_http_config()),
+        );
+    }
+
+    checks.push(match &settings.proxy {
+        Some(proxy) => json!({
+            "name": "proxy",
+            "status": "ok",
+            "message": format!("Using proxy {} (from {})", proxy.url, proxy.origin),
+            "url": proxy.url,
+            "origin": proxy.origin,
+            "explicit": proxy.explicit,
+            "no_proxy": proxy.no_proxy,
+        }),
+        None => json!({
+            "name": "proxy",
+            "status": "ok",
+            "message": "No proxy configured",
+        }),
+    });
+
+    let index_url = std::env::var(crate::pypi_index::INDEX_URL_ENV)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .or_else(|| std::env::var("PYBUN_PYPI_BASE_URL").ok())
+        .unwrap_or_else(|| "https://pypi.org".to_string());
+    let allowed = reqwest::Url::parse(&index_url).ok().is_some_and(|url| {
+        url.scheme() == "https"
+            && url.host_str().is_some_and(|host| {
+                crate::network_policy::current().is_none_or(|policy| {
+                    policy.allows_host(crate::network_policy::Operation::Index, host)
+                })
+            })
+    });
+    if !allowed {
+        checks.push(json!({
+            "name": "tls_probe",
+            "status": "info",
+            "message": format!("Skipped HTTPS probe of {}", index_url),
+            "url": index_url,
+        }));
+        return ok;
+    }
+
+    match probe_index(settings, &index_url) {
+        ProbeOutcome::Ok => {
+            // Succeeding only because of an extra CA means the connection is
+            // intercepted (and trusted).
+            let intercepted = settings.has_extra_trust()
+                && matches!(
+                    probe_index(&settings.without_extra_trust(), &index_url),
+                    ProbeOutcome::Certificate(_)
+                );
+            checks.push(json!({
+                "name": "tls_probe",
+                "status": "ok",
+                "message": if intercepted {
+                    format!(
+                        "{} is reachable; its certificate is issued by a CA from {}, so TLS is being intercepted",
+                        index_url,
+                        trust_origins.join(", ")
+                    )
+                } else {
+                    format!("{} is reachable over HTTPS", index_url)
+                },
+                "url": index_url,
+                "intercepted": intercepted,
+            }));
+            if intercepted {
+                collector.info(format!(
+                    "TLS interception detected for {}; trusted via the configured CA bundle",
+                    index_url
+                ));
+            }
+        }
+        ProbeOutcome::Certificate(error) => {
+            ok = false;
+            checks.push(json!({
+                "name": "tls_probe",
+                "status": "error",
+                "message": format!(
+                    "Certificate validation failed for {}: TLS is likely intercepted by a proxy with an untrusted CA",
+                    index_url
+                ),
+                "url": index_url,
+                "intercepted": true,
+                "error": error,
+            }));
+            fix_diagnostics.push(
+                Diagnostic::error(format!(
+                    "HTTPS certificate for {} is not trusted (likely TLS interception): {}",
+                    index_url, error
+                ))
+                .with_code("E_DOCTOR_TLS_INTERCEPTED")
+                .with_context(json!({ "url": index_url, "trust": settings.trust }))
+                .with_fix_candidates(fix_candidates_for_tls_interception(settings.system_certs)),
+            );
+        }
+        ProbeOutcome::Failed(error) => {
+            checks.push(json!({
+                "name": "tls_probe",
+                "status": "info",
+                "message": format!("Could not reach {}: {}", index_url, error),
+                "url": index_url,
+                "error": error,
+            }));
+        }
+    }
+    ok
+}
+
+/// Free space a managed CPython install needs: the archive plus the
+/// unpacked runtime.
+const PYTHON_INSTALL_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Diagnostics whose fixes restore HTTPS access; fixes that need the
+/// network are ordered after them.
+const NETWORK_FIX_CODES: &[&str] = &["E_DOCTOR_HTTP_CONFIG", "E_DOCTOR_TLS_INTERCEPTED"];
+
+/// The `doctor --fix` plan: one step per fix candidate, with its
+/// preconditions and the doctor check it is expected to turn `ok`.
+fn plan_doctor_fixes(fix_diagnostics: &[Diagnostic]) -> Vec<PlannedFix> {
+    let mut steps = Vec::new();
+    let mut network_fixes = Vec::new();
+    for diag in fix_diagnostics {
+        let code = diag.code.clone().unwrap_or_else(|| "FIX".to_string());
+        for (i, candidate) in diag.fix_candidates.iter().flatten().enumerate() {
+            let id = format!("{}#{}", code, i + 1);
+            if NETWORK_FIX_CODES.contains(&code.as_str()) {
+                network_fixes.push(id.clone());
+            }
+            steps.push(plan_doctor_fix(id, diag, candidate));
+        }
+    }
+    fix_plan::order_after_network_fixes(&mut steps, &network_fixes);
+    steps
+}
+
+fn plan_doctor_fix(id: String, diag: &Diagnostic, candidate: &FixCandidate) -> PlannedFix {
+    let step = PlannedFix::new(id, diag.code.clone(), candidate.clone());
+    if candidate.command == "pybun gc" {
+        let Some(dir) = crate::pypi::pypi_cache_dir() else {
+            return step;
+        };
+        let preview = crate::pypi::gc_stale_pypi_cache(&dir, true);
+        return step
+            .with_precondition(Precondition::Writable { path: dir })
+            .with_prediction("check", "pypi_cache")
+            .with_prediction("stale_count", 0)
+            .with_prediction("files_removed", preview.would_remove.len())
+            .with_prediction("freed_bytes", preview.freed_bytes);
+    }
+    if candidate.command.starts_with("sudo update-ca-certificates") {
+        return step
+            .with_precondition(Precondition::Program {
+                name: "update-ca-certificates".to_string(),
+            })
+            .with_prediction("check", "tls_probe")
+            .with_prediction("status", "ok");
+    }
+    match diag.code.as_deref() {
+        // Listing versions is offline; the install it leads to downloads
+        // and unpacks a runtime.
+        Some("E_DOCTOR_MISSING_PYTHON") => {
+            let runtimes = crate::cache::Cache::new()
+                .map(|cache| crate::runtime::RuntimeManager::new(cache).runtimes_dir());
+            let step = step
+                .with_precondition(Precondition::Reachable {
+                    url: crate::runtime::PBS_RELEASE_BASE.to_string(),
+                })
+                .with_prediction("check", "python")
+                .with_prediction("status", "ok");
+            match runtimes {
+                Ok(path) => step.with_precondition(Precondition::FreeSpace {
+                    path,
+                    bytes: PYTHON_INSTALL_BYTES,
+                }),
+                Err(_) => step,
+            }
+        }
+        Some("E_DOCTOR_TLS_INTERCEPTED") => step
+            .with_prediction("check", "tls_probe")
+            .with_prediction("status", "ok"),
+        Some("E_DOCTOR_HTTP_CONFIG") => step
+            .with_prediction("check", "tls")
+            .with_prediction("status", "ok"),
+        _ => step,
+    }
+}
+
+/// Whether `url` answers over HTTPS with the configured proxy and trust
+/// store, and is allowed by the network policy.
+fn probe_reachable(url: &str) -> std::result::Result<(), String> {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .ok_or_else(|| format!("invalid URL {}", url))?;
+    if crate::network_policy::current()
+        .is_some_and(|policy| !policy.allows_host(crate::network_policy::Operation::Python, &host))
+    {
+        return Err(format!("{} is not allowed by the network policy", host));
+    }
+    match probe_index(crate::http_config::current(), url) {
+        ProbeOutcome::Ok => Ok(()),
+        ProbeOutcome::Certificate(e) => Err(format!("certificate not trusted: {}", e)),
+        ProbeOutcome::Failed(e) => Err(e),
+    }
+}
+
 pub(super) fn run_doctor(
     args: &crate::cli::DoctorArgs,
     collector: &mut EventCollector,
@@ -143,54 +431,113 @@ pub(super) fn run_doctor(
         }
     }
 
+    // Deep checks of the project environment, one doctor check each.
+    let project_root = Project::discover(&working_dir)
+        .map(|project| project.root().to_path_buf())
+        .unwrap_or_else(|_| working_dir.clone());
+    let venv = std::env::var_os("PYBUN_ENV")
+        .map(std::path::PathBuf::from)
+        .or_else(|| crate::env::find_project_venv(&project_root));
+    if let Some(venv) = venv {
+        let lock_path = project_root.join("pybun.lockb");
+        let lock = lock_path
+            .is_file()
+            .then(|| Lockfile::load_from_path(&lock_path).ok())
+            .flatten();
+        for check in crate::env_health::run(&venv, lock.as_ref()) {
+            if check.status == crate::env_health::Status::Error {
+                all_ok = false;
+            }
+            fix_diagnostics.extend(check.diagnostic());
+            checks.push(serde_json::to_value(&check).unwrap_or_default());
+        }
+    }
+
+    // Proxy/TLS trust configuration and an HTTPS probe of the index.
+    if !check_http(&mut checks, &mut fix_diagnostics, collector) {
+        all_ok = false;
+    }
+
     for diag in &fix_diagnostics {
         collector.diagnostic(diag.clone());
     }
 
+    let fix_steps: Vec<SimulatedFix> = if args.fix {
+        fix_plan::simulate(
+            &plan_doctor_fixes(&fix_diagnostics),
+            &SystemProbe {
+                reachable: probe_reachable,
+            },
+        )
+    } else {
+        Vec::new()
+    };
+
     let mut applied_fixes: Vec<Value> = Vec::new();
     if args.apply {
-        for diag in &fix_diagnostics {
-            let Some(candidates) = &diag.fix_candidates else {
+        for step in &fix_steps {
+            // Fixes that would have run unattended are reported as skipped.
+            if step.status == FixStatus::Blocked
+                && step.auto_applicable
+                && step.risk == crate::schema::RiskLevel::Low
+            {
+                applied_fixes.push(json!({
+                    "command": step.command,
+                    "applied": false,
+                    "blocked": true,
+                    "reason": step.blocked_reasons.join("; "),
+                }));
+                collector.warning(format!(
+                    "Skipped blocked fix '{}': {}",
+                    step.command,
+                    step.blocked_reasons.join("; ")
+                ));
                 continue;
-            };
-            for candidate in candidates {
-                if !candidate.auto_applicable || candidate.risk != crate::schema::RiskLevel::Low {
-                    continue;
-                }
-                if candidate.command == "pybun gc" {
-                    match crate::pypi::pypi_cache_dir() {
-                        Some(dir) => {
-                            let gc_outcome = crate::pypi::gc_stale_pypi_cache(&dir, false);
-                            let applied = gc_outcome.files_removed > 0;
-                            applied_fixes.push(json!({
-                                "command": candidate.command,
-                                "applied": applied,
-                                "files_removed": gc_outcome.files_removed,
-                                "freed_bytes": gc_outcome.freed_bytes,
-                            }));
-                            if applied {
-                                collector.info(format!("Applied fix: {}", candidate.command));
-                            } else {
-                                collector.info(format!(
-                                    "No-op: {} found nothing to remove",
-                                    candidate.command
-                                ));
-                            }
+            }
+            if step.status != FixStatus::Ready {
+                continue;
+            }
+            if step.command == "pybun gc" {
+                match crate::pypi::pypi_cache_dir() {
+                    Some(dir) => {
+                        let gc_outcome = crate::pypi::gc_stale_pypi_cache(&dir, false);
+                        let applied = gc_outcome.files_removed > 0;
+                        let stale_count = crate::pypi::pypi_cache_stats(&dir).stale_count;
+                        applied_fixes.push(json!({
+                            "command": step.command,
+                            "applied": applied,
+                            "files_removed": gc_outcome.files_removed,
+                            "freed_bytes": gc_outcome.freed_bytes,
+                            "postconditions_met": stale_count == 0,
+                        }));
+                        if applied {
+                            collector.info(format!("Applied fix: {}", step.command));
+                        } else {
+                            collector
+                                .info(format!("No-op: {} found nothing to remove", step.command));
                         }
-                        None => {
-                            applied_fixes.push(json!({
-                                "command": candidate.command,
-                                "applied": false,
-                                "files_removed": null,
-                                "freed_bytes": null,
-                                "reason": "could not resolve PyPI cache directory",
-                            }));
+                        if stale_count > 0 {
                             collector.warning(format!(
-                                "Could not apply fix '{}': PyPI cache directory could not be resolved",
-                                candidate.command
+                                "{} left {} stale PyPI cache entries in {}",
+                                step.command,
+                                stale_count,
+                                dir.display()
                             ));
                         }
                     }
+                    None => {
+                        applied_fixes.push(json!({
+                            "command": step.command,
+                            "applied": false,
+                            "files_removed": null,
+                            "freed_bytes": null,
+                            "reason": "could not resolve PyPI cache directory",
+                        }));
+                        collector.warning(format!(
+                            "Could not apply fix '{}': PyPI cache directory could not be resolved",
+                            step.command
+                        ));
+                    }
                 }
             }
         }
@@ -315,6 +662,7 @@ pub(super) fn run_doctor(
             })
             .collect();
         detail["fix_plan"] = json!(fix_plan);
+        detail["fix_steps"] = json!(fix_steps);
         if args.apply {
             detail["applied_fixes"] = json!(applied_fixes);
         }
@@ -323,11 +671,24 @@ pub(super) fn run_doctor(
     let summary = if bundle_report.is_some() {
         format!("{}. Support bundle captured", summary)
     } else if args.fix && !fix_diagnostics.is_empty() {
-        format!(
-            "{}. Remediation plan available ({} item(s))",
-            summary,
-            fix_diagnostics.len()
-        )
+        let blocked = fix_steps
+            .iter()
+            .filter(|s| s.status == FixStatus::Blocked)
+            .count();
+        if blocked > 0 {
+            format!(
+                "{}. Remediation plan available ({} item(s), {} blocked)",
+                summary,
+                fix_diagnostics.len(),
+                blocked
+            )
+        } else {
+            format!(
+                "{}. Remediation plan available ({} item(s))",
+                summary,
+                fix_diagnostics.len()
+            )
+        }
     } else {
         summary
     };
@@ -335,6 +696,186 @@ pub(super) fn run_doctor(
     RenderDetail::with_json(summary, detail)
 }
 
+// ---------------------------------------------------------------------------
+// pybun doctor --score
+// ---------------------------------------------------------------------------
+
+/// Test runs the health score looks back over (the `--history` default).
+const HEALTH_TEST_WINDOW: usize = 20;
+
+/// Add `detail.score` (see [`crate::health`]) to a doctor report. Outdated
+/// and audit results come from running those commands with a scratch
+/// collector, so their own diagnostics do not end up in the doctor output.
+pub(super) async fn add_health_score(detail: &mut RenderDetail) {
+    let checks: Vec<Value> = detail.json["checks"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let with_status = |status: &str| -> Vec<String> {
+        checks
+            .iter()
+            .filter(|check| check["status"] == status)
+            .filter_map(|check| check["name"].as_str().map(str::to_string))
+            .collect()
+    };
+    let doctor = Ok(DoctorSignal {
+        errors: with_status("error"),
+        warnings: with_status("warning"),
+    });
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let project = Project::discover(&cwd).ok();
+    let lock = project
+        .as_ref()
+        .and_then(|project| Lockfile::load_from_path(project.root().join("pybun.lockb")).ok());
+
+    let lock_signal = match &project {
+        None => Err("no pyproject.toml found".to_string()),
+        Some(project) => {
+            let mut declared = project.dependencies();
+            declared.extend(project.dependency_groups().into_values().flatten());
+            Ok(LockSignal {
+                drift: lock
+                    .as_ref()
+                    .map(|lock| crate::script_lock::check(lock, &declared)),
+            })
+        }
+    };
+
+    let outdated = match &lock {
+        None => Err("no lockfile".to_string()),
+        Some(lock) => {
+            let args = crate::cli::OutdatedArgs {
+                index: None,
+                offline: crate::offline::is_enabled(),
+                member: None,
+                group: None,
+            };
+            match super::run_outdated(&args, &mut EventCollector::new()).await {
+                Err(e) => Err(e.to_string()),
+                Ok(outdated)
+                    if !lock.packages.is_empty()
+                        && outdated.json["errors"]
+                            .as_array()
+                            .is_some_and(|errors| errors.len() == lock.packages.len()) =>
+                {
+                    Err("could not reach the package index".to_string())
+                }
+                Ok(outdated) => Ok(OutdatedSignal {
+                    locked: lock.packages.len(),
+                    outdated: outdated.json["outdated"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(|item| OutdatedPackage {
+                            package: item["package"].as_str().unwrap_or_default().to_string(),
+                            kind: item["type"].as_str().unwrap_or_default().to_string(),
+                        })
+                        .collect(),
+                }),
+            }
+        }
+    };
+
+    let audit_args = AuditArgs {
+        severity_threshold: crate::cli::SeverityLevel::Low,
+        fail_on: None,
+        system: false,
+        installed: false,
+    };
+    let audit_detail = run_audit(&audit_args, &mut EventCollector::new()).await;
+    let audit = match audit_detail.json["error"].as_str() {
+        Some(error) => Err(error.to_string()),
+        None => Ok(AuditSignal {
+            findings: audit_detail.json["vulnerabilities"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|v| AuditFinding {
+                    package: v["package"].as_str().unwrap_or_default().to_string(),
+                    severity: v["severity"].as_str().unwrap_or_default().to_string(),
+                })
+                .collect(),
+        }),
+    };
+
+    let cache = match Cache::new() {
+        Err(e) => Err(e.to_string()),
+        Ok(cache) => {
+            let stale_entries = checks
+                .iter()
+                .find(|check| check["name"] == "pypi_cache")
+                .and_then(|check| check["stale_count"].as_u64())
+                .unwrap_or(0) as usize;
+            let (hits, misses) = crate::cache_stats::CacheStats::for_root(cache.root())
+                .load()
+                .values()
+                .fold((0, 0), |(hits, misses), entry| {
+                    (hits + entry.hits, misses + entry.misses)
+                });
+            Ok(CacheSignal {
+                stale_entries,
+                hit_rate: (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64),
+            })
+        }
+    };
+
+    let tests = match super::test::history_store() {
+        Err(e) => Err(e.to_string()),
+        Ok(store) => {
+            let runs = store.load();
+            if runs.is_empty() {
+                Err("no recorded test runs".to_string())
+            } else {
+                let report = crate::test_history::report(&runs, HEALTH_TEST_WINDOW, 0);
+                Ok(TestSignal {
+                    runs: report.runs.len(),
+                    failing: report
+                        .tests
+                        .iter()
+                        .filter(|t| t.status != "passing")
+                        .map(|t| t.id.clone())
+                        .collect(),
+                    flaky: report
+                        .tests
+                        .iter()
+                        .filter(|t| t.status == "passing" && t.failures > 0)
+                        .map(|t| t.id.clone())
+                        .collect(),
+                })
+            }
+        }
+    };
+
+    let report = crate::health::score(&Signals {
+        doctor,
+        outdated,
+        audit,
+        lock: lock_signal,
+        cache,
+        tests,
+    });
+
+    match report.score {
+        Some(score) => detail.text.push_str(&format!(
+            "\nHealth score: {}/100 ({})",
+            score,
+            report.grade.unwrap_or("?")
+        )),
+        None => detail.text.push_str("\nHealth score: not enough data"),
+    }
+    for recommendation in &report.recommendations {
+        detail.text.push_str(&format!(
+            "\n  {}. [{}] {} -> {}",
+            recommendation.priority,
+            recommendation.component.as_str(),
+            recommendation.message,
+            recommendation.command
+        ));
+    }
+    detail.json["score"] = serde_json::to_value(&report).unwrap_or(Value::Null);
+}
+
 // ---------------------------------------------------------------------------
 // pybun gc (garbage collection)
 // ---------------------------------------------------------------------------
@@ -345,6 +886,10 @@ pub(super) fn run_gc(
 ) -> Result<RenderDetail> {
     let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
 
+    if args.export_overlay.is_some() || args.discard_overlay {
+        return Ok(run_gc_overlay(args, &cache, collector));
+    }
+
     // Parse max size if provided
     let max_bytes = if let Some(size_str) = &args.max_size {
         Some(parse_size(size_str).map_err(|e| eyre!("invalid size format: {}", e))?)
@@ -353,22 +898,91 @@ pub(super) fn run_gc(
     };
 
     collector.info(format!("Running GC on cache at {}", cache.root().display()));
+    if let Some(shared) = cache.shared_root() {
+        collector.info(format!(
+            "Read-only cache: only the overlay is collected; {} is left untouched",
+            shared.display()
+        ));
+    }
 
     // Ensure cache directories exist
     cache
         .ensure_dirs()
         .map_err(|e| eyre!("failed to ensure cache dirs: {}", e))?;
 
+    // Wheels locked by pinned projects (`pybun projects pin`) are kept, and
+    // with --keep-used-by-lockfiles those of every registered project.
+    let registered = crate::project_registry::ProjectRegistry::new()
+        .and_then(|registry| registry.load())
+        .unwrap_or_else(|e| {
+            collector.warning(format!("ignoring project registry: {}", e));
+            Vec::new()
+        });
+    let pinned_projects: Vec<_> = registered
+        .iter()
+        .filter(|record| record.pinned)
+        .cloned()
+        .collect();
+    let mut pinned_wheels = crate::project_registry::pinned_wheels(&pinned_projects);
+    if args.keep_used_by_lockfiles {
+        pinned_wheels.extend(crate::project_registry::lockfile_wheels(&registered));
+    }
+
+    // Age and budget policies are planned first; the size sweep below then
+    // treats what they evict as gone.
+    let policy = crate::gc_policy::GcPolicy {
+        max_age: args.max_age,
+        budgets: crate::gc_policy::parse_budgets(&args.budget).map_err(|e| eyre!(e))?,
+        keep: pinned_wheels.clone(),
+    };
+    let policy_plan = policy.is_active().then(|| {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        crate::gc_policy::plan(&cache, &policy, now)
+    });
+    let mut policy_removed = 0;
+    if let Some(plan) = policy_plan.as_ref().filter(|_| !args.dry_run) {
+        let (removed, failures) = crate::gc_policy::apply(plan);
+        for (path, e) in failures {
+            collector.warning(format!("failed to remove {}: {}", path.display(), e));
+        }
+        policy_removed = removed;
+    }
+    for category in policy_plan.iter().flat_map(|plan| &plan.categories) {
+        if category.over_budget {
+            collector.diagnostic(
+                Diagnostic::warning(format!(
+                    "{} stay over their {} budget ({}) because the rest is locked by projects",
+                    category.category.label(),
+                    format_size(category.budget.unwrap_or_default()),
+                    format_size(category.size_after)
+                ))
+                .with_code("W_GC_OVER_BUDGET")
+                .with_suggestion("Raise the budget, or unpin projects you no longer need (`pybun projects unpin`)."),
+            );
+        }
+    }
+    let planned_files = policy_plan
+        .as_ref()
+        .map(|plan| plan.planned_files())
+        .unwrap_or_default();
+    let planned_envs = policy_plan
+        .as_ref()
+        .map(|plan| plan.planned_envs())
+        .unwrap_or_default();
+
     // Run garbage collection on packages/build cache
     let gc_result = cache
-        .gc(max_bytes, args.dry_run)
+        .gc_excluding(max_bytes, args.dry_run, &pinned_wheels, &planned_files)
         .map_err(|e| eyre!("GC failed: {}", e))?;
 
     // Also run GC on PEP 723 venv cache
     let pep723_cache =
         Pep723Cache::new().map_err(|e| eyre!("failed to initialize pep723 cache: {}", e))?;
     let pep723_gc_result = pep723_cache
-        .gc(max_bytes, args.dry_run)
+        .gc_excluding(max_bytes, args.dry_run, &planned_envs)
         .map_err(|e| eyre!("PEP 723 GC failed: {}", e))?;
 
     // Remove stale/corrupt PyPI metadata cache entries (see issue #202).
@@ -379,15 +993,40 @@ pub(super) fn run_gc(
         .unwrap_or_default();
 
     // Combine results
-    let total_freed =
-        gc_result.freed_bytes + pep723_gc_result.freed_bytes + pypi_cache_gc.freed_bytes;
-    let total_removed =
-        gc_result.files_removed + pep723_gc_result.envs_removed + pypi_cache_gc.files_removed;
-    let total_size_before = gc_result.size_before + pep723_gc_result.size_before;
+    let policy_freed = policy_plan
+        .as_ref()
+        .map(|plan| plan.evicted_bytes())
+        .unwrap_or(0);
+    let total_freed = policy_freed
+        + gc_result.freed_bytes
+        + pep723_gc_result.freed_bytes
+        + pypi_cache_gc.freed_bytes;
+    let total_removed = policy_removed
+        + gc_result.files_removed
+        + pep723_gc_result.envs_removed
+        + pypi_cache_gc.files_removed;
+    let total_size_before = policy_freed + gc_result.size_before + pep723_gc_result.size_before;
     let total_size_after = gc_result.size_after + pep723_gc_result.size_after;
 
-    let summary = if args.dry_run {
-        let would_remove_count = gc_result.would_remove.len()
+    let plan = if args.dry_run && (!args.sweep.is_empty() || args.plan.is_some()) {
+        Some(gc_eviction_plan(
+            args,
+            &cache,
+            &pep723_cache,
+            &pinned_wheels,
+            policy_plan.as_ref(),
+            collector,
+        )?)
+    } else {
+        None
+    };
+
+    let mut summary = if args.dry_run {
+        let would_remove_count = policy_plan
+            .as_ref()
+            .map(|plan| plan.evictions.len())
+            .unwrap_or(0)
+            + gc_result.would_remove.len()
             + pep723_gc_result.would_remove.len()
             + pypi_cache_gc.would_remove.len();
         if would_remove_count == 0 {
@@ -415,20 +1054,24 @@ pub(super) fn run_gc(
         )
     };
 
-    let json_detail = json!({
+    let mut json_detail = json!({
         "freed_bytes": total_freed,
-        "freed_human": format_size(total_freed),
         "files_removed": gc_result.files_removed,
         "envs_removed": pep723_gc_result.envs_removed,
         "size_before": total_size_before,
-        "size_before_human": format_size(total_size_before),
         "size_after": total_size_after,
-        "size_after_human": format_size(total_size_after),
         "dry_run": args.dry_run,
         "max_size": args.max_size,
         "would_remove": gc_result.would_remove.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
         "would_remove_pep723_envs": pep723_gc_result.would_remove,
         "cache_root": cache.root().display().to_string(),
+        "shared_cache": cache.shared_root().map(|p| p.display().to_string()),
+        "pinned": {
+            "projects": pinned_projects.iter().map(|p| p.root.display().to_string()).collect::<Vec<_>>(),
+            "keep_used_by_lockfiles": args.keep_used_by_lockfiles,
+            "files_kept": gc_result.pinned_kept,
+        },
+        "policy": policy_plan,
         "pep723_cache": {
             "freed_bytes": pep723_gc_result.freed_bytes,
             "envs_removed": pep723_gc_result.envs_removed,
@@ -443,19 +1086,179 @@ pub(super) fn run_gc(
         },
     });
 
+    for category in policy_plan.iter().flat_map(|plan| &plan.categories) {
+        if category.evicted_entries > 0 || category.budget.is_some() {
+            summary.push_str(&format!(
+                "\n  {}: {} {} ({}), {} -> {}{}",
+                category.category.label(),
+                if args.dry_run {
+                    "would evict"
+                } else {
+                    "evicted"
+                },
+                category.evicted_entries,
+                format_size(category.evicted_bytes),
+                format_size(category.size_before),
+                format_size(category.size_after),
+                category
+                    .budget
+                    .map(|budget| format!(" (budget {})", format_size(budget)))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+
+    if let Some(plan) = &plan {
+        if let Some(limit) = &plan.recommendation.limit {
+            summary.push_str(&format!("; recommended --max-size {}", limit));
+        }
+        json_detail["plan"] = json!(plan);
+        json_detail["plan_file"] = json!(args.plan.as_ref().map(|p| p.display().to_string()));
+    }
+
     Ok(RenderDetail::with_json(summary, json_detail))
 }
 
+/// `pybun gc --dry-run --sweep/--plan`: simulate candidate limits and write
+/// the plan to `--plan` if given.
+fn gc_eviction_plan(
+    args: &crate::cli::GcArgs,
+    cache: &Cache,
+    pep723_cache: &Pep723Cache,
+    pinned_wheels: &std::collections::BTreeSet<String>,
+    policy: Option<&crate::gc_policy::PolicyPlan>,
+    collector: &mut EventCollector,
+) -> Result<crate::gc_plan::EvictionPlan> {
+    let sweep: Vec<String> = if args.sweep.is_empty() {
+        crate::gc_plan::DEFAULT_SWEEP
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    } else {
+        args.sweep.clone()
+    };
+    let limits = crate::gc_plan::parse_limits(&sweep).map_err(|e| eyre!("{}", e))?;
+    let stats = crate::cache_stats::CacheStats::for_root(cache.root()).load();
+    if stats.is_empty() {
+        collector.info(format!(
+            "No cache hit stats recorded yet ({}); recommendation is based on size only",
+            crate::cache_stats::STATS_FILE
+        ));
+    }
+    let mut plan = crate::gc_plan::build_plan(cache, pep723_cache, pinned_wheels, &limits, &stats)
+        .map_err(|e| eyre!("failed to build eviction plan: {}", e))?;
+    plan.policy = policy.cloned();
+
+    if let Some(path) = &args.plan {
+        let data = serde_json::to_string_pretty(&plan)?;
+        std::fs::write(path, format!("{}\n", data))
+            .map_err(|e| eyre!("failed to write plan {}: {}", path.display(), e))?;
+    }
+    Ok(plan)
+}
+
+/// `pybun gc --export-overlay DIR` / `--discard-overlay`: end-of-job handling
+/// of the read-only cache overlay.
+fn run_gc_overlay(
+    args: &crate::cli::GcArgs,
+    cache: &Cache,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    let result = match &args.export_overlay {
+        Some(dest) => cache.export_overlay(dest).map(|files| {
+            let bytes: u64 = files.iter().map(|f| f.size_bytes).sum();
+            (
+                format!(
+                    "Exported overlay ({} files, {}) to {}",
+                    files.len(),
+                    format_size(bytes),
+                    dest.display()
+                ),
+                json!({
+                    "action": "export",
+                    "destination": dest.display().to_string(),
+                    "manifest": dest.join(crate::cache::OVERLAY_MANIFEST).display().to_string(),
+                    "files": files,
+                    "total_bytes": bytes,
+                }),
+            )
+        }),
+        None => cache.discard_overlay().map(|freed| {
+            (
+                format!("Discarded overlay ({})", format_size(freed)),
+                json!({
+                    "action": "discard",
+                    "freed_bytes": freed,
+                }),
+            )
+        }),
+    };
+
+    match result {
+        Ok((summary, mut detail)) => {
+            detail["overlay"] = json!(cache.root().display().to_string());
+            detail["shared_cache"] = json!(cache.shared_root().map(|p| p.display().to_string()));
+            RenderDetail::with_json(summary, detail)
+        }
+        Err(e @ crate::cache::CacheError::NotReadonly) => {
+            collector.error_with_code(
+                "E_CACHE_NOT_READONLY",
+                e.to_string(),
+                "Set PYBUN_READONLY_CACHE=1 and PYBUN_CACHE_OVERLAY for the job, then re-run `pybun gc`.",
+            );
+            RenderDetail::error(e.to_string(), json!({ "error": e.to_string() }))
+        }
+        Err(e) => {
+            collector.error_with_code(
+                "E_GC_FAILED",
+                e.to_string(),
+                "Check the overlay and destination directory permissions, then re-run `pybun gc`.",
+            );
+            RenderDetail::error(e.to_string(), json!({ "error": e.to_string() }))
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // pybun audit (OSV vulnerability scan) — Issue #316
 // ---------------------------------------------------------------------------
 
-pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector) -> RenderDetail {
-    let severity_threshold = args.severity_threshold.as_str();
-    let fail_on = args.fail_on.map(|s| s.as_str());
-
+/// Packages `pybun audit` scans: the project's locked packages when it has a
+/// `pybun.lockb` (unless `--installed`), else those installed in its Python
+/// environment. Also returns the JSON describing where they came from.
+fn audit_packages(
+    args: &AuditArgs,
+    collector: &mut EventCollector,
+) -> std::result::Result<(Vec<InstalledPackage>, Value), RenderDetail> {
     let working_dir = std::env::current_dir().unwrap_or_default();
 
+    let lock_path = Project::discover(&working_dir)
+        .ok()
+        .map(|project| project.root().join("pybun.lockb"))
+        .filter(|path| path.is_file());
+    if let Some(lock_path) = lock_path.filter(|_| !args.installed) {
+        let lock = match Lockfile::load_from_path(&lock_path) {
+            Ok(lock) => lock,
+            Err(e) => {
+                let message = format!("failed to read {}: {}", lock_path.display(), e);
+                collector.error_with_code(
+                    "E_AUDIT_LOCKFILE_INVALID",
+                    message.clone(),
+                    "Run `pybun lock` to regenerate the lockfile, or pass --installed to scan the Python environment instead.",
+                );
+                return Err(RenderDetail::error(
+                    format!("Vulnerability scan failed: {}", message),
+                    json!({ "error": message }),
+                ));
+            }
+        };
+        let source = json!({
+            "kind": "lockfile",
+            "path": lock_path.display().to_string(),
+        });
+        return Ok((locked_packages(&lock), source));
+    }
+
     let python_env = match find_python_env(&working_dir) {
         Ok(env) => env,
         Err(e) => {
@@ -464,13 +1267,13 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
                 format!("Python environment not found: {}", e),
                 "Run `pybun install` to create a project environment (or set PYBUN_ENV), then re-run `pybun audit`.",
             );
-            return RenderDetail::error(
+            return Err(RenderDetail::error(
                 format!(
                     "Vulnerability scan failed: Python environment not found: {}",
                     e
                 ),
                 json!({ "error": e.to_string() }),
-            );
+            ));
         }
     };
 
@@ -507,30 +1310,44 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
                 message.clone(),
                 "Run `pybun install` to create a project environment (or set PYBUN_ENV), then re-run `pybun audit`. Pass --system to explicitly audit the system Python interpreter instead.",
             );
-            return RenderDetail::error(
+            return Err(RenderDetail::error(
                 format!("Vulnerability scan failed: {}", message),
                 json!({ "error": message, "python_env": python_env_json }),
-            );
+            ));
         }
     }
 
     // A pip-list failure means the environment could not actually be
     // inspected; treating it as "zero packages" would let `--fail-on` pass
     // silently in CI even though nothing was scanned.
-    let packages = match list_installed_packages(&python_env.python_path) {
-        Ok(packages) => packages,
+    match list_installed_packages(&python_env.python_path) {
+        Ok(packages) => Ok((
+            packages,
+            json!({ "kind": "environment", "python_env": python_env_json }),
+        )),
         Err(e) => {
             collector.error_with_code(
                 "E_AUDIT_PIP_LIST_FAILED",
                 format!("Failed to list installed packages: {}", e),
                 "Ensure pip is available in the active Python environment, then re-run `pybun audit`.",
             );
-            return RenderDetail::error(
+            Err(RenderDetail::error(
                 format!("Vulnerability scan failed: could not list installed packages ({e})"),
                 json!({ "error": e, "python_env": python_env_json }),
-            );
+            ))
         }
+    }
+}
+
+pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector) -> RenderDetail {
+    let severity_threshold = args.severity_threshold.as_str();
+    let fail_on = args.fail_on.map(|s| s.as_str());
+
+    let (packages, source) = match audit_packages(args, collector) {
+        Ok(found) => found,
+        Err(detail) => return detail,
     };
+    let python_env_json = source["python_env"].clone();
 
     // Scan at the more permissive (numerically lower) of --severity-threshold
     // and --fail-on. --severity-threshold alone controls what is *displayed*
@@ -546,14 +1363,21 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
     };
     let scan_threshold = crate::audit::severity_str_for_level(scan_threshold_level);
 
+    // OSV answers are kept in the local advisory database so a later
+    // `pybun --offline audit` can answer from it.
     let osv_url = default_osv_url();
-    let report = match scan_for_vulnerabilities(&packages, &osv_url, scan_threshold).await {
+    let offline = crate::offline::is_enabled();
+    let scan = match AdvisoryDb::new() {
+        Ok(db) => scan_with_advisory_db(&packages, &osv_url, scan_threshold, &db, offline).await,
+        Err(_) => crate::audit::scan_for_vulnerabilities(&packages, &osv_url, scan_threshold).await,
+    };
+    let report = match scan {
         Ok(report) => report,
         Err(e) => {
             collector.error_with_code(
                 "E_AUDIT_OSV_QUERY_FAILED",
                 format!("Failed to query OSV vulnerability database: {}", e),
-                "Check network connectivity, or set PYBUN_OSV_URL to point at a reachable mirror.",
+                "Check network connectivity, set PYBUN_OSV_URL to point at a reachable mirror, or pass --offline to use advisories cached by earlier scans.",
             );
             return RenderDetail::error(
                 format!("Vulnerability scan failed: {}", e),
@@ -573,7 +1397,20 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
             .collect(),
         None => Vec::new(),
     };
-    let should_fail = !failing_vulns.is_empty();
+
+    // Offline, a package never scanned online has no cached answer. It is
+    // reported as missing rather than clean, and fails a --fail-on gate.
+    if offline {
+        for package in &report.unscanned_packages {
+            crate::offline::require(crate::offline::MissingArtifact::new(
+                crate::offline::ArtifactKind::Advisory,
+                package.clone(),
+                None,
+            ));
+        }
+    }
+    let incomplete = offline && fail_on_level.is_some() && !report.unscanned_packages.is_empty();
+    let should_fail = !failing_vulns.is_empty() || incomplete;
 
     // What gets displayed/reported respects --severity-threshold specifically.
     let displayed: Vec<&crate::audit::Vulnerability> = report
@@ -607,6 +1444,7 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
             "installed_version": v.installed_version,
             "vulnerability_id": v.vulnerability_id,
             "severity": v.severity,
+            "fix_version": v.fix_version,
         }));
         if let Some(fix_version) = &v.fix_version {
             diag = diag.with_suggestion(format!(
@@ -637,7 +1475,19 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
         )
     };
 
-    if should_fail {
+    if incomplete {
+        collector.error_with_code(
+            "E_AUDIT_UNSCANNED_OFFLINE",
+            format!(
+                "{} package(s) have no cached advisories and were not scanned offline: {}",
+                report.unscanned_packages.len(),
+                report.unscanned_packages.join(", ")
+            ),
+            "Run `pybun audit` once without --offline to cache their advisories, then retry offline.",
+        );
+    }
+
+    if !failing_vulns.is_empty() {
         let fail_on_str = fail_on.unwrap_or("");
         collector.error_with_code(
             "E_AUDIT_FAIL_ON_THRESHOLD",
@@ -662,10 +1512,14 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
             "unscanned": report.unscanned,
         },
         "vulnerabilities": vulnerabilities,
+        "unscanned_packages": report.unscanned_packages,
         "severity_threshold": severity_threshold,
         "fail_on": fail_on,
         "scanner": "osv",
         "scanner_version": "1.0",
+        "offline": offline,
+        "source": source["kind"],
+        "lockfile": source["path"],
         "python_env": python_env_json,
     });
 
@@ -675,3 +1529,1495 @@ pub(super) async fn run_audit(args: &AuditArgs, collector: &mut EventCollector)
         RenderDetail::with_json(summary, json_detail)
     }
 }
+
+// ---------------------------------------------------------------------------
+// pybun env clean
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_env_clean(
+    args: &crate::cli::EnvCleanArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let venv = selected_venv(args.venv.as_deref())?;
+
+    collector.info(format!("Scanning {}", venv.display()));
+    let report = crate::env_clean::clean(&venv, args.dry_run)?;
+    for anomaly in &report.anomalies {
+        collector.warning(format!("{}: {}", anomaly.path.display(), anomaly.message));
+    }
+
+    let verb = if report.dry_run {
+        "would reclaim"
+    } else {
+        "reclaimed"
+    };
+    let mut summary = format!(
+        "{} item(s) {}, {} {}",
+        report.findings.len(),
+        if report.dry_run { "found" } else { "removed" },
+        verb,
+        format_size(report.reclaimed_bytes)
+    );
+    for finding in &report.findings {
+        let kind = serde_json::to_value(finding.kind).unwrap_or_default();
+        summary.push_str(&format!(
+            "\n  {} {}",
+            kind.as_str().unwrap_or("?"),
+            finding.path.display()
+        ));
+    }
+    if !report.anomalies.is_empty() {
+        summary.push_str(&format!(
+            "\n{} anomaly(ies) need manual attention:",
+            report.anomalies.len()
+        ));
+        for anomaly in &report.anomalies {
+            summary.push_str(&format!(
+                "\n  {}: {}",
+                anomaly.path.display(),
+                anomaly.message
+            ));
+        }
+    }
+
+    Ok(
+        RenderDetail::with_json(summary, serde_json::to_value(&report)?).with_table(
+            crate::table::TableSpec::new("findings", &["kind", "path", "bytes"]),
+        ),
+    )
+}
+
+/// `--venv`, else `PYBUN_ENV`, else the project's environment.
+fn selected_venv(venv: Option<&std::path::Path>) -> Result<std::path::PathBuf> {
+    let cwd = std::env::current_dir()?;
+    venv.map(std::path::Path::to_path_buf)
+        .or_else(|| std::env::var_os("PYBUN_ENV").map(std::path::PathBuf::from))
+        .or_else(|| crate::env::find_project_venv(&cwd))
+        .ok_or_else(|| {
+            eyre!("no virtual environment found; pass --venv or run `pybun install` first")
+        })
+}
+
+pub(super) fn run_env_list(args: &crate::cli::EnvListArgs) -> Result<RenderDetail> {
+    let venv = selected_venv(args.venv.as_deref())?;
+    let site_packages = crate::env_clean::site_packages_dir(&venv)
+        .ok_or_else(|| eyre!("site-packages directory not found in {}", venv.display()))?;
+
+    let packages: Vec<Value> = crate::dist_info::installed(&site_packages)
+        .into_iter()
+        .map(|dist| {
+            let metadata = dist.metadata().unwrap_or_default();
+            json!({
+                "name": metadata.name.as_deref().unwrap_or(&dist.name),
+                "version": dist.version,
+                "license": metadata.license_summary(),
+                "size_bytes": dist.size_bytes().ok(),
+                "requires": metadata.requires_dist,
+                "dist_info": dist.path.display().to_string(),
+            })
+        })
+        .collect();
+    let total: u64 = packages
+        .iter()
+        .filter_map(|p| p["size_bytes"].as_u64())
+        .sum();
+
+    let mut summary = format!(
+        "{} package(s), {} in {}",
+        packages.len(),
+        format_size(total),
+        site_packages.display()
+    );
+    for package in &packages {
+        summary.push_str(&format!(
+            "\n  {} {} ({})",
+            package["name"].as_str().unwrap_or_default(),
+            package["version"].as_str().unwrap_or_default(),
+            package["license"].as_str().unwrap_or("unknown license"),
+        ));
+    }
+
+    Ok(RenderDetail::with_json(
+        summary,
+        json!({
+            "venv": venv.display().to_string(),
+            "site_packages": site_packages.display().to_string(),
+            "packages": packages,
+            "total_size_bytes": total,
+        }),
+    )
+    .with_table(crate::table::TableSpec::new(
+        "packages",
+        &["name", "version", "license", "size_bytes"],
+    )))
+}
+
+// ---------------------------------------------------------------------------
+// pybun env push / pull (chunked snapshots in a remote cache)
+// ---------------------------------------------------------------------------
+
+/// Project root: the nearest directory with `pyproject.toml`, else `cwd`.
+fn project_root(cwd: &std::path::Path) -> std::path::PathBuf {
+    crate::project::Project::discover(cwd)
+        .ok()
+        .and_then(|project| project.path().parent().map(std::path::Path::to_path_buf))
+        .unwrap_or_else(|| cwd.to_path_buf())
+}
+
+fn snapshot_name(name: Option<&str>, root: &std::path::Path) -> Result<String> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| eyre!("cannot derive a snapshot name; pass --name"))?,
+    };
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(eyre!("invalid snapshot name '{name}'"));
+    }
+    Ok(name)
+}
+
+fn transfer_summary(verb: &str, report: &crate::env_snapshot::TransferReport) -> String {
+    format!(
+        "{verb} '{}' ({} file(s), {}): transferred {} in {} chunk(s), reused {} chunk(s), {:.1}% saved",
+        report.name,
+        report.files,
+        format_size(report.total_bytes),
+        format_size(report.transferred_bytes),
+        report.transferred_chunks,
+        report.reused_chunks,
+        report.savings_percent
+    )
+}
+
+pub(super) async fn run_env_push(
+    args: &crate::cli::EnvPushArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let cwd = std::env::current_dir()?;
+    let venv = selected_venv(args.venv.as_deref())?;
+    let name = snapshot_name(args.name.as_deref(), &project_root(&cwd))?;
+    let remote = configured_remote(args.remote.as_deref())?;
+
+    collector.info(format!("Pushing {} to {}", venv.display(), remote.url));
+    let report = crate::env_snapshot::push(&venv, &remote, &name).await?;
+    Ok(RenderDetail::with_json(
+        transfer_summary("pushed", &report),
+        json!({ "venv": venv.display().to_string(), "transfer": report }),
+    ))
+}
+
+pub(super) async fn run_env_pull(
+    args: &crate::cli::EnvPullArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let cwd = std::env::current_dir()?;
+    let root = project_root(&cwd);
+    let venv = match selected_venv(args.venv.as_deref()) {
+        Ok(venv) => venv,
+        Err(_) => root.join(".pybun").join("venv"),
+    };
+    let name = snapshot_name(args.name.as_deref(), &root)?;
+    let remote = configured_remote(args.remote.as_deref())?;
+    let local = crate::env_snapshot::ChunkStore::new(crate::cache::Cache::new()?.chunks_dir());
+
+    collector.info(format!(
+        "Pulling '{name}' from {} into {}",
+        remote.url,
+        venv.display()
+    ));
+    let report = crate::env_snapshot::pull(&remote, &name, &venv, &local).await?;
+    Ok(RenderDetail::with_json(
+        transfer_summary("pulled", &report),
+        json!({ "venv": venv.display().to_string(), "transfer": report }),
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// pybun env snapshot / restore (portable environment archives)
+// ---------------------------------------------------------------------------
+
+/// SHA-256 of the project's lockfile, if it has one.
+fn lock_sha256(root: &std::path::Path) -> Option<String> {
+    crate::archive::sha256_file(&root.join("pybun.lockb")).ok()
+}
+
+pub(super) fn run_env_snapshot(
+    args: &crate::cli::EnvSnapshotArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let cwd = std::env::current_dir()?;
+    let venv = selected_venv(args.venv.as_deref())?;
+    let lock = lock_sha256(&project_root(&cwd));
+
+    collector.info(format!(
+        "Snapshotting {} to {}",
+        venv.display(),
+        args.output.display()
+    ));
+    let report = crate::env_archive::snapshot(&venv, &args.output, lock)?;
+    Ok(RenderDetail::with_json(
+        format!(
+            "Wrote snapshot {} to {} ({} file(s), {} package(s), {} -> {})",
+            &report.id[..12],
+            report.output.display(),
+            report.files,
+            report.distributions,
+            format_size(report.total_bytes),
+            format_size(report.archive_bytes)
+        ),
+        json!({ "venv": venv.display().to_string(), "snapshot": report }),
+    ))
+}
+
+pub(super) fn run_env_restore(
+    args: &crate::cli::EnvRestoreArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let cwd = std::env::current_dir()?;
+    let root = project_root(&cwd);
+    let venv = match selected_venv(args.venv.as_deref()) {
+        Ok(venv) => venv,
+        Err(_) => root.join(".pybun").join("venv"),
+    };
+
+    collector.info(format!(
+        "Restoring {} into {}",
+        args.input.display(),
+        venv.display()
+    ));
+    let report = crate::env_archive::restore(&args.input, &venv)?;
+
+    let platform = crate::env_archive::current_platform();
+    if report.platform != platform {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "snapshot was taken on {}, this machine is {platform}; compiled extensions may not load",
+                report.platform
+            ))
+            .with_code("W_ENV_SNAPSHOT_PLATFORM")
+            .with_suggestion("Take snapshots on the platform that restores them, or run `pybun install`."),
+        );
+    }
+    if let Some(home) = &report.python.home
+        && !std::path::Path::new(home).is_dir()
+    {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "base interpreter directory {home} (Python {}) not found; the restored environment cannot start",
+                report.python.version.as_deref().unwrap_or("unknown")
+            ))
+            .with_code("W_ENV_SNAPSHOT_INTERPRETER")
+            .with_suggestion("Install the same Python at the same location (e.g. a managed `pybun python install`), or run `pybun install`."),
+        );
+    }
+    if let (Some(taken), Some(current)) = (&report.lock_sha256, lock_sha256(&root))
+        && *taken != current
+    {
+        collector.diagnostic(
+            Diagnostic::warning("pybun.lockb changed since the snapshot was taken")
+                .with_code("W_ENV_SNAPSHOT_STALE")
+                .with_suggestion(
+                    "Run `pybun install` to sync the environment, then take a new snapshot.",
+                ),
+        );
+    }
+
+    Ok(RenderDetail::with_json(
+        format!(
+            "Restored snapshot {} into {} ({} file(s), {} package(s), {} relocated)",
+            &report.id[..12],
+            venv.display(),
+            report.files,
+            report.distributions,
+            report.relocated
+        ),
+        json!({
+            "venv": venv.display().to_string(),
+            "input": args.input.display().to_string(),
+            "restore": report,
+        }),
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// pybun cache dir / list / info / verify
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_cache_dir() -> Result<RenderDetail> {
+    let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+    let runtimes = crate::runtime::RuntimeManager::new(cache.clone()).runtimes_dir();
+    let root = cache.root().display().to_string();
+    Ok(RenderDetail::with_json(
+        root.clone(),
+        json!({
+            "root": root,
+            "wheels": cache.wheel_store_dir(),
+            "envs": cache.pep723_envs_dir(),
+            "runtimes": runtimes,
+            "shared": cache.shared_root(),
+        }),
+    ))
+}
+
+pub(super) fn run_cache_list(args: &crate::cli::CacheListArgs) -> Result<RenderDetail> {
+    use crate::cache_inspect::{EntryKind, Selection};
+
+    let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+    let items = crate::cache_inspect::list(
+        &cache,
+        Selection {
+            wheels: args.wheels,
+            envs: args.envs,
+            runtimes: args.runtimes,
+        },
+    );
+    let total: u64 = items.iter().map(|item| item.size_bytes).sum();
+    let count = |kind| items.iter().filter(|item| item.kind == kind).count();
+    let summary = format!(
+        "{} wheel(s), {} script environment(s), {} runtime(s): {} in {}",
+        count(EntryKind::Wheel),
+        count(EntryKind::Env),
+        count(EntryKind::Runtime),
+        format_size(total),
+        cache.root().display()
+    );
+    let mut lines = vec![summary];
+    for item in &items {
+        lines.push(format!(
+            "  {:<8} {:<16} {:>10}  {}{}",
+            item.kind.label(),
+            item.id.get(..16).unwrap_or(&item.id),
+            format_size(item.size_bytes),
+            item.name,
+            if item.shared { " (shared)" } else { "" }
+        ));
+    }
+    Ok(RenderDetail::with_json(
+        lines.join("\n"),
+        json!({
+            "root": cache.root().display().to_string(),
+            "entries": items,
+            "total_bytes": total,
+        }),
+    )
+    .with_table(crate::table::TableSpec::new(
+        "entries",
+        &["kind", "id", "name", "size_bytes"],
+    )))
+}
+
+pub(super) fn run_cache_info(args: &crate::cli::CacheInfoArgs) -> Result<RenderDetail> {
+    let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+    let item = crate::cache_inspect::find(&cache, &args.hash)?;
+    let referenced_by = match item.kind {
+        crate::cache_inspect::EntryKind::Wheel => crate::project_registry::ProjectRegistry::new()
+            .and_then(|registry| registry.load())
+            .map(|records| crate::cache_inspect::referencing_projects(&records, &item.name))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let hit_rate = crate::cache_stats::EntryStats {
+        hits: item.hits,
+        misses: item.misses,
+        ..Default::default()
+    }
+    .hit_rate();
+
+    let mut lines = vec![
+        format!("{} {}", item.kind.label(), item.name),
+        format!("  id:        {}", item.id),
+        format!("  path:      {}", item.path.display()),
+        format!("  size:      {}", format_size(item.size_bytes)),
+        format!(
+            "  lookups:   {} hit(s), {} miss(es){}",
+            item.hits,
+            item.misses,
+            hit_rate
+                .map(|rate| format!(" ({:.0}% hits)", rate * 100.0))
+                .unwrap_or_default()
+        ),
+    ];
+    if let Some(python) = &item.python_version {
+        lines.push(format!("  python:    {python}"));
+    }
+    if item.shared {
+        lines.push("  layer:     shared (read-only)".to_string());
+    }
+    for root in &referenced_by {
+        lines.push(format!("  locked by: {}", root.display()));
+    }
+    let mut detail = json!(item);
+    detail["hit_rate"] = json!(hit_rate);
+    detail["referenced_by"] = json!(referenced_by);
+    Ok(RenderDetail::with_json(lines.join("\n"), detail))
+}
+
+pub(super) fn run_cache_verify(collector: &mut EventCollector) -> Result<RenderDetail> {
+    let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+    collector.info(format!(
+        "Verifying stored wheels in {}",
+        cache.wheel_store_dir().display()
+    ));
+    let report = crate::cache_inspect::verify(&cache);
+    for failure in &report.failures {
+        let suggestion = if failure.shared {
+            "The wheel is in the read-only shared cache; ask its owner to rebuild it.".to_string()
+        } else {
+            format!(
+                "Delete {}; the next install downloads it again.",
+                failure.path.display()
+            )
+        };
+        collector.diagnostic(
+            Diagnostic::error(format!(
+                "{} is corrupted ({})",
+                failure.filename,
+                match failure.problem {
+                    crate::cache_inspect::Problem::HashMismatch =>
+                        "contents do not match its SHA-256",
+                    crate::cache_inspect::Problem::Unreadable => "unreadable",
+                }
+            ))
+            .with_code("E_CACHE_CORRUPT")
+            .with_file(failure.path.display().to_string())
+            .with_suggestion(suggestion)
+            .with_context(json!({
+                "expected": failure.sha256,
+                "actual": failure.actual,
+            })),
+        );
+    }
+    let text = format!(
+        "Verified {} wheel(s) ({}): {} corrupted",
+        report.checked,
+        format_size(report.bytes_checked),
+        report.failures.len()
+    );
+    let corrupted = !report.failures.is_empty();
+    let detail = json!(report);
+    let detail = if corrupted {
+        RenderDetail::error(text, detail)
+    } else {
+        RenderDetail::with_json(text, detail)
+    };
+    Ok(detail.with_table(crate::table::TableSpec::new(
+        "failures",
+        &["filename", "problem", "path"],
+    )))
+}
+
+// ---------------------------------------------------------------------------
+// pybun cache push / pull (remote wheel cache)
+// ---------------------------------------------------------------------------
+
+/// The remote cache named by `--remote`, `PYBUN_CACHE_URL`, or the project's
+/// `[tool.pybun.cache.remote]`.
+fn configured_remote(remote: Option<&str>) -> Result<crate::remote_cache::Remote> {
+    let cwd = std::env::current_dir()?;
+    let config = crate::project::Project::discover(&cwd)
+        .ok()
+        .and_then(|project| project.pybun_config().cache)
+        .and_then(|cache| cache.remote);
+    Ok(crate::remote_cache::Remote::resolve(
+        remote,
+        config.as_ref(),
+    )?)
+}
+
+/// The remote cache and the locked wheels of the project around `cwd`.
+fn remote_cache_inputs(
+    remote: Option<&str>,
+    lock: &std::path::Path,
+) -> Result<(
+    crate::remote_cache::Remote,
+    Vec<crate::remote_cache::ArtifactRef>,
+    std::path::PathBuf,
+)> {
+    let remote = configured_remote(remote)?;
+    let lock_path = project_root(&std::env::current_dir()?).join(lock);
+    let lockfile = crate::lockfile::Lockfile::load_from_path(&lock_path).map_err(|e| {
+        eyre!(
+            "failed to read {}: {e}; run `pybun lock` first",
+            lock_path.display()
+        )
+    })?;
+    Ok((
+        remote,
+        crate::remote_cache::lock_artifacts(&lockfile),
+        lock_path,
+    ))
+}
+
+fn remote_cache_detail(
+    text: String,
+    lock: &std::path::Path,
+    report: &crate::remote_cache::TransferReport,
+) -> RenderDetail {
+    let mut detail = json!(report);
+    detail["lockfile"] = json!(lock.display().to_string());
+    RenderDetail::with_json(text, detail).with_table(crate::table::TableSpec::new(
+        "artifacts",
+        &["filename", "status", "bytes"],
+    ))
+}
+
+pub(super) async fn run_cache_push(
+    args: &crate::cli::CachePushArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let (remote, artifacts, lock) = remote_cache_inputs(args.remote.as_deref(), &args.lock)?;
+    collector.info(format!(
+        "Pushing {} locked wheel(s) to {}",
+        artifacts.len(),
+        remote.url
+    ));
+    let report = crate::remote_cache::push(&remote, Cache::new()?, &artifacts).await?;
+    let summary = &report.summary;
+    if summary.uploaded + summary.present == 0 {
+        collector.diagnostic(
+            Diagnostic::warning("none of the locked wheels are in the local cache")
+                .with_code("W_REMOTE_CACHE_NOTHING_TO_PUSH")
+                .with_suggestion("Run `pybun install` first, then `pybun cache push`."),
+        );
+    }
+    let text = format!(
+        "Pushed to {}: {} uploaded ({}), {} already present, {} not cached locally",
+        remote.url,
+        summary.uploaded,
+        format_size(summary.bytes_transferred),
+        summary.present,
+        summary.absent
+    );
+    Ok(remote_cache_detail(text, &lock, &report))
+}
+
+pub(super) async fn run_cache_pull(
+    args: &crate::cli::CachePullArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let (remote, artifacts, lock) = remote_cache_inputs(args.remote.as_deref(), &args.lock)?;
+    let host_tags = if args.all_platforms {
+        vec!["*".to_string()]
+    } else {
+        crate::tags::host_platform_tags()
+    };
+    collector.info(format!("Pulling locked wheels from {}", remote.url));
+    let report = crate::remote_cache::pull(&remote, Cache::new()?, &artifacts, &host_tags).await?;
+    for artifact in &report.artifacts {
+        if artifact.status == crate::remote_cache::TransferStatus::Corrupt {
+            collector.diagnostic(
+                Diagnostic::warning(format!(
+                    "{} from the remote cache does not match the lockfile hash; discarded",
+                    artifact.filename
+                ))
+                .with_code("W_REMOTE_CACHE_CORRUPT")
+                .with_suggestion("Re-push it from a trusted job; `pybun install` downloads it from the index meanwhile.")
+                .with_context(json!({ "sha256": artifact.sha256 })),
+            );
+        }
+    }
+    let summary = &report.summary;
+    let text = format!(
+        "Pulled from {}: {} hit(s) ({}), {} miss(es), {} already local{}",
+        remote.url,
+        summary.hits,
+        format_size(summary.bytes_transferred),
+        summary.misses,
+        summary.local,
+        summary
+            .hit_rate_percent
+            .map(|rate| format!(", {rate:.1}% hit rate"))
+            .unwrap_or_default()
+    );
+    Ok(remote_cache_detail(text, &lock, &report))
+}
+
+// ---------------------------------------------------------------------------
+// pybun publish
+// ---------------------------------------------------------------------------
+
+pub(super) async fn run_publish(
+    args: &crate::cli::PublishArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    use crate::publish::{self, CredentialSource, FileReport, Repository, UploadStatus};
+
+    let repository = Repository::resolve(&args.repository)?;
+    let paths = if args.files.is_empty() {
+        vec![project_root(&std::env::current_dir()?).join("dist")]
+    } else {
+        args.files.clone()
+    };
+    let dists = publish::collect(&paths)?;
+    collector.info(format!(
+        "Publishing {} file(s) to {}",
+        dists.len(),
+        repository.upload_url
+    ));
+    let message = format!("no credentials for {}", repository.upload_url);
+    let no_credentials = |diagnostic: Diagnostic, code: &str| {
+        diagnostic.with_code(code).with_suggestion(format!(
+                "Set {}, store a token with `pybun auth token set {}`, or publish from a CI job with trusted publishing configured.",
+            publish::TOKEN_ENV,
+            repository.upload_url
+        ))
+    };
+
+    let (reports, credentials) = if args.dry_run {
+        let source = publish::credential_source(&repository, args.trusted_publishing);
+        if source.is_none() {
+            collector.diagnostic(no_credentials(
+                Diagnostic::warning(message),
+                "W_PUBLISH_NO_CREDENTIALS",
+            ));
+        }
+        let reports: Vec<FileReport> = dists
+            .iter()
+            .map(|dist| FileReport::new(dist, UploadStatus::DryRun))
+            .collect();
+        (reports, source.map(|source| json!({ "source": source })))
+    } else {
+        let Some(credentials) = publish::credentials(&repository, args.trusted_publishing).await?
+        else {
+            collector.diagnostic(no_credentials(
+                Diagnostic::error(message.clone()),
+                "E_PUBLISH_NO_CREDENTIALS",
+            ));
+            return Err(eyre!(message));
+        };
+        collector.info(format!(
+            "Authenticating as {} ({})",
+            credentials.username,
+            match credentials.source {
+                CredentialSource::Env => "environment",
+                CredentialSource::Keyring => "stored credentials",
+                CredentialSource::TrustedPublishing => "trusted publishing",
+            }
+        ));
+        let reports =
+            publish::upload(&repository, &dists, &credentials, args.skip_existing).await?;
+        let json = json!({ "source": credentials.source, "username": credentials.username });
+        (reports, Some(json))
+    };
+
+    let count = |status: UploadStatus| reports.iter().filter(|r| r.status == status).count();
+    let uploaded_bytes: u64 = reports
+        .iter()
+        .filter(|r| r.status == UploadStatus::Uploaded)
+        .map(|r| r.size)
+        .sum();
+    for report in reports.iter().filter(|r| r.status == UploadStatus::Failed) {
+        let message = report.message.as_deref().unwrap_or("upload failed");
+        collector.diagnostic(
+            Diagnostic::error(format!("failed to upload {}: {message}", report.filename))
+                .with_code("E_PUBLISH_UPLOAD_FAILED")
+                .with_suggestion(match report.http_status {
+                    Some(401 | 403) => "Check the token and that it may upload to this project; for trusted publishing, check the publisher configured on the index.",
+                    Some(400 | 409) => "The index refused the file; a version that was uploaded (or deleted) once cannot be re-uploaded. Bump the version, or pass --skip-existing when re-running a partial publish.",
+                    _ => "Re-run `pybun publish --skip-existing` to retry the remaining files.",
+                })
+                .with_context(json!(report)),
+        );
+    }
+    let failed = count(UploadStatus::Failed);
+    let detail = json!({
+        "repository": repository.name,
+        "upload_url": repository.upload_url,
+        "dry_run": args.dry_run,
+        "credentials": credentials,
+        "files": reports,
+        "summary": {
+            "files": reports.len(),
+            "uploaded": count(UploadStatus::Uploaded),
+            "exists": count(UploadStatus::Exists),
+            "failed": failed,
+            "not_attempted": count(UploadStatus::NotAttempted),
+            "bytes_uploaded": uploaded_bytes,
+        },
+    });
+    let text = if args.dry_run {
+        format!(
+            "Dry run: {} file(s) ready to publish to {}",
+            reports.len(),
+            repository.upload_url
+        )
+    } else {
+        format!(
+            "Published to {}: {} uploaded ({}), {} already present, {} failed, {} not attempted",
+            repository.upload_url,
+            count(UploadStatus::Uploaded),
+            format_size(uploaded_bytes),
+            count(UploadStatus::Exists),
+            failed,
+            count(UploadStatus::NotAttempted)
+        )
+    };
+    let detail = if failed > 0 {
+        RenderDetail::error(text, detail)
+    } else {
+        RenderDetail::with_json(text, detail)
+    };
+    Ok(detail.with_table(crate::table::TableSpec::new(
+        "files",
+        &["filename", "status", "size"],
+    )))
+}
+
+// ---------------------------------------------------------------------------
+// pybun projects (machine-wide project registry)
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_projects(
+    cmd: &crate::cli::ProjectsCommands,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    use crate::cli::ProjectsCommands;
+    use crate::project_registry::ProjectRegistry;
+
+    let registry = ProjectRegistry::new()?;
+    match cmd {
+        ProjectsCommands::List(_) => run_projects_list(&registry),
+        ProjectsCommands::Status(args) => run_projects_status(&registry, args, collector),
+        ProjectsCommands::Pin(args) | ProjectsCommands::Unpin(args) => {
+            let pinned = matches!(cmd, ProjectsCommands::Pin(_));
+            let root = project_root_arg(args.path.as_deref())?;
+            let record = registry.set_pinned(&root, pinned)?;
+            Ok(RenderDetail::with_json(
+                format!(
+                    "{} {}",
+                    if pinned { "Pinned" } else { "Unpinned" },
+                    record.root.display()
+                ),
+                json!({ "project": record }),
+            ))
+        }
+        ProjectsCommands::Forget(args) => {
+            let forgotten: Vec<std::path::PathBuf> = if args.missing {
+                registry
+                    .forget_missing()?
+                    .into_iter()
+                    .map(|record| record.root)
+                    .collect()
+            } else {
+                let root = project_root_arg(args.path.as_deref())?;
+                if !registry.forget(&root)? {
+                    return Err(crate::project_registry::RegistryError::NotRegistered(root).into());
+                }
+                vec![root]
+            };
+            let mut summary = format!("Forgot {} project(s)", forgotten.len());
+            for root in &forgotten {
+                summary.push_str(&format!("\n  {}", root.display()));
+            }
+            Ok(RenderDetail::with_json(
+                summary,
+                json!({ "forgotten": forgotten }),
+            ))
+        }
+    }
+}
+
+/// `PATH` argument of `pybun projects pin/unpin/forget`: the given directory,
+/// else the project containing the current directory.
+fn project_root_arg(path: Option<&std::path::Path>) -> Result<std::path::PathBuf> {
+    let cwd = std::env::current_dir()?;
+    match path {
+        Some(path) => Ok(cwd.join(path)),
+        None => Project::discover(&cwd)
+            .map(|project| project.root().to_path_buf())
+            .map_err(|_| eyre!("not inside a project; pass the project directory")),
+    }
+}
+
+fn run_projects_list(registry: &crate::project_registry::ProjectRegistry) -> Result<RenderDetail> {
+    let records = registry.load()?;
+    let cache = Cache::new()?;
+    let wheel_dirs: Vec<std::path::PathBuf> = std::iter::once(cache.packages_dir())
+        .chain(crate::pypi::artifacts_cache_dir())
+        .collect();
+    let usage = crate::project_registry::disk_usage(&records, &wheel_dirs);
+
+    let rows: Vec<Value> = records
+        .iter()
+        .zip(&usage)
+        .map(|(record, usage)| {
+            let mut row = serde_json::to_value(record).unwrap_or_default();
+            row["env_bytes"] = json!(usage.env_bytes);
+            row["cache_bytes"] = json!(usage.cache_bytes);
+            row["exclusive_cache_bytes"] = json!(usage.exclusive_cache_bytes);
+            row
+        })
+        .collect();
+    let total_env: u64 = usage.iter().map(|u| u.env_bytes).sum();
+
+    let summary = if records.is_empty() {
+        "no projects registered yet (run `pybun install` in a project)".to_string()
+    } else {
+        let mut text = format!(
+            "{} project(s), {} in environments:",
+            records.len(),
+            format_size(total_env)
+        );
+        for (record, usage) in records.iter().zip(&usage) {
+            text.push_str(&format!(
+                "\n  {}{}  env {}, cache {} ({} exclusive)",
+                record.root.display(),
+                if record.pinned { " [pinned]" } else { "" },
+                format_size(usage.env_bytes),
+                format_size(usage.cache_bytes),
+                format_size(usage.exclusive_cache_bytes)
+            ));
+        }
+        text
+    };
+
+    Ok(RenderDetail::with_json(
+        summary,
+        json!({
+            "registry": registry.path().display().to_string(),
+            "projects": rows,
+            "total_env_bytes": total_env,
+        }),
+    )
+    .with_table(crate::table::TableSpec::new(
+        "projects",
+        &["root", "name", "pinned", "env_bytes", "cache_bytes"],
+    )))
+}
+
+fn run_projects_status(
+    registry: &crate::project_registry::ProjectRegistry,
+    args: &crate::cli::ProjectsStatusArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    use crate::project_registry::{ProjectIssue, check};
+
+    let records = registry.load()?;
+    let mut rows = Vec::new();
+    let mut unhealthy = 0;
+    let mut text = String::new();
+    for record in &records {
+        let issues = check(record);
+        let drift = (!args.no_drift && !issues.contains(&ProjectIssue::Missing)).then(|| {
+            let result = crate::drift::analyze(&record.root);
+            json!({
+                "undeclared_imports": result.undeclared_imports.len(),
+                "unused_declarations": result.unused_declarations.len(),
+            })
+        });
+        let drifted = drift.as_ref().is_some_and(|d| {
+            d["undeclared_imports"].as_u64().unwrap_or(0) > 0
+                || d["unused_declarations"].as_u64().unwrap_or(0) > 0
+        });
+        let status = if !issues.is_empty() || drifted {
+            unhealthy += 1;
+            "attention"
+        } else {
+            "ok"
+        };
+        let mut labels: Vec<String> = issues.iter().map(|i| i.as_str().to_string()).collect();
+        if drifted {
+            labels.push(format!(
+                "drift ({} undeclared, {} unused)",
+                drift
+                    .as_ref()
+                    .map_or(0, |d| d["undeclared_imports"].as_u64().unwrap_or(0)),
+                drift
+                    .as_ref()
+                    .map_or(0, |d| d["unused_declarations"].as_u64().unwrap_or(0))
+            ));
+        }
+        text.push_str(&format!(
+            "\n  {} {}{}",
+            if status == "ok" { "ok  " } else { "warn" },
+            record.root.display(),
+            if labels.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", labels.join(", "))
+            }
+        ));
+        rows.push(json!({
+            "root": record.root,
+            "name": record.name,
+            "status": status,
+            "issues": issues,
+            "drift": drift,
+        }));
+    }
+
+    if unhealthy > 0 {
+        collector.warning(format!(
+            "{} of {} registered project(s) need attention",
+            unhealthy,
+            records.len()
+        ));
+    }
+    let summary = if records.is_empty() {
+        "no projects registered yet (run `pybun install` in a project)".to_string()
+    } else {
+        format!(
+            "{} project(s), {} need attention:{}",
+            records.len(),
+            unhealthy,
+            text
+        )
+    };
+    Ok(RenderDetail::with_json(
+        summary,
+        json!({
+            "projects": rows,
+            "attention": unhealthy,
+        }),
+    )
+    .with_table(crate::table::TableSpec::new(
+        "projects",
+        &["root", "status", "issues"],
+    )))
+}
+
+// ---------------------------------------------------------------------------
+// pybun graph (dependency graph export)
+// ---------------------------------------------------------------------------
+
+/// The locked dependency graph of the project around the current
+/// directory, annotated with licenses and sizes from `venv` (else PYBUN_ENV
+/// or the project's venv). Without an environment, `no_env_code` is
+/// reported as a warning. `None` when there is no pybun.lockb.
+/// The project in the current directory (if any), its root, and its
+/// `pybun.lockb`. `None` when there is no lockfile.
+fn project_lock() -> Result<Option<(Option<Project>, std::path::PathBuf, Lockfile)>> {
+    let cwd = std::env::current_dir()?;
+    let project = Project::discover(&cwd).ok();
+    let root_dir = project
+        .as_ref()
+        .map(|p| p.root().to_path_buf())
+        .unwrap_or_else(|| cwd.clone());
+    let lock_path = root_dir.join("pybun.lockb");
+    if !lock_path.exists() {
+        return Ok(None);
+    }
+    let lock = Lockfile::load_from_path(&lock_path)
+        .map_err(|e| eyre!("failed to read {}: {}", lock_path.display(), e))?;
+    Ok(Some((project, root_dir, lock)))
+}
+
+/// Name and version the project root is shown with.
+fn root_identity(project: Option<&Project>) -> (String, String) {
+    let metadata = project.map(Project::metadata).unwrap_or_default();
+    (
+        metadata.name.unwrap_or_else(|| "project".to_string()),
+        metadata.version.unwrap_or_else(|| "0.0.0".to_string()),
+    )
+}
+
+/// The locked dependency graph of the project in the current directory, and
+/// the project root. `None` when there is no `pybun.lockb`.
+fn locked_graph() -> Result<Option<(DependencyGraph, std::path::PathBuf)>> {
+    let Some((project, root_dir, lock)) = project_lock()? else {
+        return Ok(None);
+    };
+    let (root, root_version) = root_identity(project.as_ref());
+    let declared = project
+        .as_ref()
+        .map(Project::dependencies)
+        .unwrap_or_default();
+    let graph = DependencyGraph::from_lock(&lock, &root, &root_version, &declared);
+    Ok(Some((graph, root_dir)))
+}
+
+pub(super) fn project_graph(
+    venv: Option<&Path>,
+    no_env_code: &str,
+    collector: &mut EventCollector,
+) -> Result<Option<DependencyGraph>> {
+    let Some((mut graph, root_dir)) = locked_graph()? else {
+        return Ok(None);
+    };
+
+    let venv = venv
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os("PYBUN_ENV").map(std::path::PathBuf::from))
+        .or_else(|| crate::env::find_project_venv(&root_dir));
+    match venv
+        .as_deref()
+        .and_then(crate::env_clean::site_packages_dir)
+    {
+        Some(site_packages) => {
+            let matched = graph.annotate_installed(&site_packages);
+            collector.info(format!(
+                "Read license and size for {} of {} packages from {}",
+                matched,
+                graph.nodes.len(),
+                site_packages.display()
+            ));
+        }
+        None => collector.diagnostic(
+            Diagnostic::warning("no virtual environment found; licenses and sizes are omitted")
+                .with_code(no_env_code)
+                .with_suggestion(
+                    "Run `pybun install` or pass --venv to include installed metadata.",
+                ),
+        ),
+    }
+    Ok(Some(graph))
+}
+
+fn lockfile_required(command: &str, collector: &mut EventCollector) -> Report {
+    let message = "pybun.lockb not found. Run 'pybun install' first.".to_string();
+    collector.error_with_code(
+        "E_LOCKFILE_NOT_FOUND",
+        message.clone(),
+        format!("Run `pybun install` to generate pybun.lockb, then re-run `pybun {command}`."),
+    );
+    eyre!(message)
+}
+
+pub(super) fn run_graph(
+    args: &crate::cli::GraphArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let Some(graph) = project_graph(args.venv.as_deref(), "W_GRAPH_NO_ENV", collector)? else {
+        return Err(lockfile_required("graph", collector));
+    };
+
+    let content = match args.export {
+        GraphExportFormat::Dot => graph.to_dot(),
+        GraphExportFormat::Graphml => graph.to_graphml(),
+        GraphExportFormat::Spdx => {
+            let document = graph.to_spdx(&crate::mcp::utc_timestamp_seconds_now());
+            format!("{}\n", serde_json::to_string_pretty(&document)?)
+        }
+    };
+
+    let mut detail = json!({
+        "format": args.export.as_str(),
+        "root": graph.root,
+        "root_version": graph.root_version,
+        "packages": graph.nodes,
+        "edges": graph.edges,
+    });
+    match &args.output {
+        Some(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, &content)
+                .map_err(|e| eyre!("failed to write {}: {}", path.display(), e))?;
+            detail["path"] = json!(path.display().to_string());
+            Ok(RenderDetail::with_json(
+                format!(
+                    "Wrote {} graph ({} packages, {} edges) to {}",
+                    args.export.as_str(),
+                    graph.nodes.len(),
+                    graph.edges.len(),
+                    path.display()
+                ),
+                detail,
+            ))
+        }
+        None => {
+            detail["content"] = json!(content);
+            Ok(RenderDetail::with_json_raw_text(content, detail))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// pybun tree
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_tree(
+    args: &crate::cli::TreeArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let Some((graph, _)) = locked_graph()? else {
+        return Err(lockfile_required("tree", collector));
+    };
+
+    let tree = match &args.invert {
+        Some(package) => match DependencyTree::inverted(&graph, package, args.depth) {
+            Some(tree) => tree,
+            None => {
+                let message = format!("{} is not in pybun.lockb", package);
+                collector.error_with_code(
+                    "E_TREE_PACKAGE_NOT_LOCKED",
+                    message.clone(),
+                    format!(
+                        "Check the package name, or run `pybun add {}` to depend on it.",
+                        package
+                    ),
+                );
+                return Err(eyre!(message));
+            }
+        },
+        None => DependencyTree::new(&graph, args.depth),
+    };
+
+    let content = match args.export {
+        TreeExportFormat::Text => tree.render(),
+        TreeExportFormat::Dot => tree.subgraph(&graph).to_dot(),
+    };
+    let detail = json!({
+        "format": args.export.as_str(),
+        "root": graph.root,
+        "root_version": graph.root_version,
+        "invert": tree.inverted,
+        "depth": tree.depth,
+        "packages": tree.package_count(),
+        "tree": tree.root,
+        "edges": tree.edges,
+        "content": content,
+    });
+    Ok(RenderDetail::with_json_raw_text(content.trim_end(), detail))
+}
+
+// ---------------------------------------------------------------------------
+// pybun why
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_why(
+    args: &crate::cli::WhyArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let Some((project, _, lock)) = project_lock()? else {
+        return Err(lockfile_required("why", collector));
+    };
+    let (root, root_version) = root_identity(project.as_ref());
+    let mut groups = project
+        .as_ref()
+        .map(Project::dependency_groups)
+        .unwrap_or_default();
+    groups.insert(
+        crate::lockfile::MAIN_GROUP.to_string(),
+        project
+            .as_ref()
+            .map(Project::dependencies)
+            .unwrap_or_default(),
+    );
+
+    let Some(why) = crate::dep_why::explain(&lock, &root, &root_version, &groups, &args.package)
+    else {
+        let message = format!("{} is not in pybun.lockb", args.package);
+        collector.error_with_code(
+            "E_WHY_PACKAGE_NOT_LOCKED",
+            message.clone(),
+            "Check the package name, or run `pybun tree` to list the locked packages.",
+        );
+        return Err(eyre!(message));
+    };
+
+    let mut text = format!("{} {}\n", why.package, why.version);
+    if why.chains.is_empty() {
+        text.push_str("  not required by any declared dependency (run `pybun lock --check`)\n");
+    }
+    for chain in &why.chains {
+        text.push_str(&format!("  {}\n", chain.describe(&root)));
+    }
+    if why.truncated {
+        text.push_str(&format!(
+            "  ... more than {} chains; showing the first {}\n",
+            crate::dep_why::MAX_CHAINS,
+            crate::dep_why::MAX_CHAINS
+        ));
+    }
+    let detail = json!({
+        "root": root,
+        "root_version": root_version,
+        "package": why.package,
+        "version": why.version,
+        "direct": why.direct,
+        "chains": why.chains,
+        "truncated": why.truncated,
+    });
+    Ok(RenderDetail::with_json_raw_text(text.trim_end(), detail))
+}
+
+// ---------------------------------------------------------------------------
+// pybun sbom
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_sbom(
+    args: &crate::cli::SbomArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let Some(graph) = project_graph(args.venv.as_deref(), "W_SBOM_NO_ENV", collector)? else {
+        return Err(lockfile_required("sbom", collector));
+    };
+    let sbom = Sbom::new(graph);
+    let format = args.export;
+
+    let mut detail = json!({
+        "format": format.as_str(),
+        "root": sbom.graph.root,
+        "root_version": sbom.graph.root_version,
+        "components": sbom.component_count(),
+    });
+    match &args.output {
+        Some(path) => {
+            let summary = sbom
+                .write(format, path)
+                .map_err(|e| eyre!("failed to write {}: {}", path.display(), e))?;
+            detail["path"] = json!(path.display().to_string());
+            Ok(RenderDetail::with_json(
+                format!(
+                    "Wrote {} SBOM ({} components) to {}",
+                    summary.format,
+                    summary.component_count,
+                    path.display()
+                ),
+                detail,
+            ))
+        }
+        None => {
+            let document = sbom.render(format, &crate::mcp::utc_timestamp_seconds_now());
+            let content = format!("{}\n", serde_json::to_string_pretty(&document)?);
+            detail["document"] = document;
+            Ok(RenderDetail::with_json_raw_text(content, detail))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// pybun export
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_export(
+    args: &crate::cli::ExportArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let Some((project, _, lock)) = project_lock()? else {
+        return Err(lockfile_required("export", collector));
+    };
+    let mut declared = project
+        .as_ref()
+        .map(Project::dependencies)
+        .unwrap_or_default();
+    for group in &args.group {
+        match project.as_ref().filter(|p| p.has_group(group)) {
+            Some(project) => declared.extend(project.group_dependencies(group)),
+            None => return Err(eyre!("unknown dependency group '{group}'")),
+        }
+    }
+    let groups: Vec<&str> = args.group.iter().map(String::as_str).collect();
+    let exported = match args.export {
+        crate::cli::ExportFormat::RequirementsTxt => {
+            crate::dep_export::requirements_txt(&lock, &declared, &groups, !args.no_hashes)
+        }
+        crate::cli::ExportFormat::UvLock => {
+            let (name, version) = root_identity(project.as_ref());
+            let uv_project = match &project {
+                Some(project) => crate::foreign_lock::UvProject {
+                    name,
+                    version,
+                    requires_python: project.requires_python(),
+                    editable: project.build_system().build_backend.is_some(),
+                    dependencies: project.dependencies(),
+                    optional_dependencies: project.optional_dependencies(),
+                    dependency_groups: project.dependency_groups(),
+                },
+                None => crate::foreign_lock::UvProject {
+                    name,
+                    version,
+                    ..Default::default()
+                },
+            };
+            crate::foreign_lock::uv_lock(&lock, &uv_project, !args.no_hashes)
+        }
+    };
+    if !exported.unlocated.is_empty() {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "no download URL recorded for {}; uv will look them up again",
+                exported.unlocated.join(", ")
+            ))
+            .with_code("W_EXPORT_NO_URL")
+            .with_suggestion("Re-run `pybun lock` against an index that reports file URLs."),
+        );
+    }
+    if !exported.unhashed.is_empty() {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "no hash for {}; `pip install --require-hashes` will refuse the file",
+                exported.unhashed.join(", ")
+            ))
+            .with_code("W_EXPORT_UNHASHED")
+            .with_suggestion("Re-run `pybun lock` to record hashes, or export with --no-hashes."),
+        );
+    }
+
+    let mut detail = json!({
+        "format": args.export.as_str(),
+        "groups": args.group,
+        "hashes": !args.no_hashes,
+        "packages": exported.packages,
+        "unhashed": exported.unhashed,
+        "unlocated": exported.unlocated,
+    });
+    match &args.output {
+        Some(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, &exported.content)
+                .map_err(|e| eyre!("failed to write {}: {}", path.display(), e))?;
+            detail["path"] = json!(path.display().to_string());
+            Ok(RenderDetail::with_json(
+                format!(
+                    "Wrote {} ({} packages) to {}",
+                    args.export.as_str(),
+                    exported.packages.len(),
+                    path.display()
+                ),
+                detail,
+            ))
+        }
+        None => {
+            detail["content"] = json!(exported.content);
+            Ok(RenderDetail::with_json_raw_text(exported.content, detail))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// pybun status (operations started with --max-duration)
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_status(
+    args: &crate::cli::StatusArgs,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    use crate::operation::{OperationError, OperationState, OperationStore};
+
+    let loaded = OperationStore::from_env().and_then(|store| {
+        let record = store.status(&args.operation)?;
+        Ok((store, record))
+    });
+    let (store, record) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            let code = if matches!(e, OperationError::NotFound(_)) {
+                "E_OPERATION_NOT_FOUND"
+            } else {
+                "E_STATUS_FAILED"
+            };
+            collector.error_with_code(
+                code,
+                e.to_string(),
+                "Pass the operation id from the `--max-duration` checkpoint; finished operations are kept for 7 days.",
+            );
+            return RenderDetail::error(e.to_string(), json!({ "error": e.to_string() }));
+        }
+    };
+
+    let checkpoint = record.checkpoint(crate::operation::now_ms());
+    let mut summary = format!(
+        "operation {} ({}): {}",
+        record.id,
+        record.command,
+        record.state.as_str()
+    );
+    match record.state {
+        OperationState::Running => {
+            if let Some(phase) = &record.phase {
+                summary.push_str(&format!(" - {phase}"));
+            }
+            if let Some(percent) = record.percent {
+                summary.push_str(&format!(", {percent}%"));
+            }
+            if let Some(eta) = checkpoint["eta_ms"].as_u64() {
+                summary.push_str(&format!(
+                    ", ETA {}",
+                    crate::units::format_duration(std::time::Duration::from_millis(eta))
+                ));
+            }
+        }
+        _ => {
+            if let Some(code) = record.exit_code {
+                summary.push_str(&format!(" (exit code {code})"));
+            }
+        }
+    }
+
+    let output_path = store.output_path(&record.id);
+    let stderr_path = store.stderr_path(&record.id);
+    let mut detail = json!({
+        "operation": checkpoint,
+        "output_path": output_path.display().to_string(),
+        "stderr_path": stderr_path.display().to_string(),
+    });
+    // The result is the child's own output: its JSON envelope when it ran
+    // with --format=json, otherwise its text.
+    if record.state.is_finished()
+        && let Ok(output) = std::fs::read_to_string(&output_path)
+    {
+        match serde_json::from_str::<Value>(&output) {
+            Ok(result) => detail["result"] = result,
+            Err(_) => {
+                let output = output.trim_end();
+                if !output.is_empty() {
+                    summary.push('\n');
+                    summary.push_str(output);
+                }
+                detail["output"] = json!(output);
+            }
+        }
+    }
+    RenderDetail::with_json(summary, detail)
+}
+
+// ---------------------------------------------------------------------------
+// pybun explain (error catalog)
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_explain(
+    args: &crate::cli::ExplainArgs,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    use crate::error_catalog::{CatalogEntry, ErrorCode, catalog};
+
+    fn entry_json(entry: &CatalogEntry) -> Value {
+        json!({
+            "code": entry.id,
+            "title": entry.title,
+            "description": entry.description,
+            "fixes": entry.fixes,
+            "diagnostic_codes": entry.diagnostic_codes,
+        })
+    }
+
+    let Some(code) = &args.code else {
+        let mut summary = format!("{} error codes", catalog().len());
+        for entry in catalog() {
+            summary.push_str(&format!("\n  {}  {}", entry.id, entry.title));
+        }
+        let codes: Vec<Value> = catalog().iter().map(entry_json).collect();
+        return RenderDetail::with_json(summary, json!({ "codes": codes }))
+            .with_table(crate::table::TableSpec::new("codes", &["code", "title"]));
+    };
+
+    let Some(error_code) = ErrorCode::parse(code) else {
+        let message = format!("unknown error code: {code}");
+        collector.error_with_code(
+            "E_EXPLAIN_UNKNOWN_CODE",
+            message.clone(),
+            "Run `pybun explain` to list the known codes.",
+        );
+        return RenderDetail::error(message.clone(), json!({ "error": message }));
+    };
+    let entry = error_code.entry();
+    let mut text = format!(
+        "{}: {}\n\n{}\n\nCommon fixes:",
+        entry.id, entry.title, entry.description
+    );
+    for fix in entry.fixes {
+        text.push_str(&format!("\n  - {fix}"));
+    }
+    text.push_str(&format!(
+        "\n\nReported as: {}",
+        entry.diagnostic_codes.join(", ")
+    ));
+    RenderDetail::with_json_raw_text(text, entry_json(entry))
+}
@@ -1,32 +1,40 @@
 use crate::build::{BuildBackend, BuildCache};
+use crate::change_diff::{self, FileDiff};
 use crate::cli::{
     Cli, Commands, DriftArgs, InitArgs, InitTemplate, LockArgs, McpCommands, OutdatedArgs,
-    OutputFormat, ProgressMode, PythonCommands, SchemaArgs, SchemaCommands, SelfCommands,
-    TelemetryCommands, UpgradeArgs,
+    OutputFormat, ProfileArgs, ProfileCommands, ProgressMode, PythonCommands, SchemaArgs,
+    SchemaCommands, SelfCommands, TelemetryCommands, UpgradeArgs,
 };
 use crate::env::{EnvSource, find_python_env};
 use crate::index::load_index_from_path;
-use crate::installer;
-use crate::lockfile::{Lockfile, Package, PackageSource};
+use crate::installer::{InstallRecord, InstallStatus};
+use crate::lock_shard;
+use crate::lockfile::{Artifact, Lockfile, MAIN_GROUP, Package, PackageSource, SourceBuild};
+use crate::ndjson::NdjsonStream;
+use crate::network_policy;
 use crate::pep723;
 use crate::pep723_cache::{Pep723Cache, Pep723CacheKey};
 use crate::progress::{ProgressConfig, ProgressDriver};
 use crate::project::Project;
-use crate::pypi::{PyPiClient, PyPiIndex};
+use crate::pypi_index::RemoteIndex;
 use crate::release_manifest::{ReleaseManifest, current_release_target};
+use crate::reproducible;
 use crate::resolver::parse_version_relaxed;
 use crate::resolver::{
-    PackageIndex, Requirement, Resolution, ResolveOptions, compare_versions,
+    PackageIndex, Requirement, Resolution, ResolveOptions, VersionSpec, compare_versions,
     cp_tag_to_dotted_version, current_platform_tags, is_wheel_python_compatible, parse_wheel_tags,
     python_version_to_cp_tag, resolve_with_options, select_artifact_for_platform_with_cp,
 };
+use crate::resolver_strategy::ResolverKind;
 use crate::sandbox;
 use crate::sbom;
 use crate::schema::{
     Diagnostic, DiagnosticLevel, Event, EventCollector, EventType, JsonEnvelope, Status,
 };
 use crate::self_update::apply_update_for_asset;
-use crate::wheel_cache::WheelCache;
+use crate::table::TableSpec;
+use crate::tool_env::{self, ToolEnvCache, ToolEnvInfo, ToolEnvKey};
+use crate::update_channel::{self, HistoryAction, HistoryEntry, RolloutDecision, UpdateStore};
 use crate::workspace::Workspace;
 use color_eyre::eyre::{Result, eyre};
 use console::Style;
@@ -35,7 +43,7 @@ use futures::stream::{self, StreamExt};
 use serde_json::{Value, json};
 use sha2::{Digest, Sha256};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::io::IsTerminal;
 #[cfg(unix)]
@@ -46,19 +54,27 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+mod bench;
+mod env_install;
 mod maintenance;
 mod test;
 mod tooling;
 
 pub async fn execute(cli: Cli) -> Result<()> {
     let mut collector = EventCollector::new();
+    if cli.offline {
+        crate::offline::enable();
+    }
+    if cli.format.is_json() {
+        crate::payload::set_max_inline_bytes(cli.max_inline_bytes);
+    }
 
     let requested_progress = if cli.no_progress {
         ProgressMode::Never
     } else {
         cli.progress
     };
-    let progress_mode = if matches!(cli.format, OutputFormat::Json) {
+    let progress_mode = if cli.format.is_json() || cli.format == OutputFormat::Tsv {
         ProgressMode::Never
     } else {
         requested_progress
@@ -67,9 +83,17 @@ pub async fn execute(cli: Cli) -> Result<()> {
         mode: progress_mode,
         is_tty: std::io::stderr().is_terminal(),
     });
-    if let Some(listener) = progress.listener() {
+    let ndjson = (cli.format == OutputFormat::Ndjson).then(NdjsonStream::new);
+    let listener = match &ndjson {
+        Some(stream) => Some(stream.event_listener()),
+        None => progress.listener(),
+    };
+    if let Some(listener) = crate::operation::with_recorder(listener) {
         collector.set_event_listener(listener);
     }
+    if let Some(stream) = &ndjson {
+        collector.set_diagnostic_listener(stream.diagnostic_listener());
+    }
 
     // Record command start
     collector.event(EventType::CommandStart);
@@ -88,8 +112,16 @@ pub async fn execute(cli: Cli) -> Result<()> {
                     artifacts,
                     workspace,
                     installed_count,
+                    results,
+                    environment,
                 }) => {
                     collector.event(EventType::InstallComplete);
+                    record_project_activity(
+                        "install",
+                        &lockfile,
+                        environment.as_ref(),
+                        &mut collector,
+                    );
                     let detail = json!({
                         "lockfile": lockfile.display().to_string(),
                         "packages": packages,
@@ -97,6 +129,8 @@ pub async fn execute(cli: Cli) -> Result<()> {
                         "artifacts": artifacts,
                         "workspace": workspace,
                         "installed_count": installed_count,
+                        "results": results,
+                        "environment": environment,
                     });
                     (
                         "install".to_string(),
@@ -107,10 +141,13 @@ pub async fn execute(cli: Cli) -> Result<()> {
                     // Only push a generic fallback error if install() did not already
                     // record an error-level diagnostic (e.g. resolve errors).
                     if collector.error_diagnostic_count() == pre_error_count {
-                        collector.error_with_code(
-                            "E_INSTALL_FAILED",
-                            e.to_string(),
-                            "Check --index/--require and network connectivity, then re-run `pybun install`. Use --format=json for full diagnostics.",
+                        collector.diagnostic(
+                            Diagnostic::error(e.to_string())
+                                .with_code("E_INSTALL_FAILED")
+                                .with_suggestion(
+                                    "Check --index/--require and network connectivity, then re-run `pybun install`. Use --format=json for full diagnostics.",
+                                )
+                                .with_tool_failure(e.as_ref()),
                         );
                     }
                     (
@@ -125,90 +162,46 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
-        Commands::Add(args) => {
-            let result = add_package(args);
-            match result {
-                Ok(AddOutcome {
-                    summary,
-                    packages,
-                    added_deps,
-                }) => {
-                    // Chain install to ensure the environment is up-to-date
-                    let names = packages
-                        .iter()
-                        .map(|p| p.name.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    collector.info(format!("Installing dependencies including {}...", names));
-
-                    let install_args = crate::cli::InstallArgs {
-                        offline: args.offline,
-                        system: false,
-                        requirements: Vec::new(), // install from pyproject.toml
-                        index: None,
-                        lock: std::path::PathBuf::from("pybun.lockb"),
-                        workspace: false,
-                        member: None,
-                        group: None,
-                        pre: args.pre,
-                    };
-
-                    let packages_json: Vec<serde_json::Value> = packages
-                        .iter()
-                        .map(|p| json!({ "name": p.name, "version": p.version }))
-                        .collect();
-
-                    let pre_error_count = collector.error_diagnostic_count();
-                    match install(&install_args, &mut collector).await {
-                        Ok(_) => (
-                            "add".to_string(),
-                            RenderDetail::with_json(
-                                format!("{} and installed dependencies.", summary),
-                                json!({
-                                    "package": packages.first().map(|p| p.name.clone()),
-                                    "version": packages.first().and_then(|p| p.version.clone()),
-                                    "packages": packages_json,
-                                    "added_dependencies": added_deps,
-                                    "installed": true,
-                                }),
-                            ),
+        Commands::Add(args) => run_add(args, &mut collector).await,
+        Commands::Remove(args) => run_remove(args, &mut collector),
+        Commands::RenameDep(args) => {
+            let pre_error_count = collector.error_diagnostic_count();
+            match rename_dep(args, &mut collector).await {
+                Ok(outcome) => {
+                    let (diff, changes) =
+                        render_file_changes(&outcome.diffs.iter().collect::<Vec<_>>());
+                    (
+                        "rename-dep".to_string(),
+                        RenderDetail::with_json(
+                            with_diff(outcome.summary, &diff),
+                            json!({
+                                "old": args.old,
+                                "new": args.new,
+                                "applied": args.apply,
+                                "sections": outcome.sections,
+                                "imports": outcome.imports.as_ref().map(|(from, to)| json!({
+                                    "from": from,
+                                    "to": to,
+                                })),
+                                "files": outcome.files,
+                                "relocked": outcome.relocked,
+                                "history": outcome.history.map(|p| p.display().to_string()),
+                                "changes": changes,
+                                "diff": diff,
+                            }),
                         ),
-                        Err(e) => {
-                            let err_msg = format!(
-                                "Added {} to pyproject.toml but failed to install: {}",
-                                names, e
-                            );
-                            // Only push a generic fallback error if install() did not
-                            // already record an error-level diagnostic (e.g. resolve errors).
-                            if collector.error_diagnostic_count() == pre_error_count {
-                                collector.error_with_code(
-                                    "E_ADD_INSTALL_FAILED",
-                                    err_msg.clone(),
-                                    "pyproject.toml was updated; fix the underlying issue (see other diagnostics) and run `pybun install` to finish installing dependencies.",
-                                );
-                            }
-                            (
-                                "add".to_string(),
-                                RenderDetail::error(
-                                    err_msg,
-                                    json!({
-                                        "packages": packages_json,
-                                        "error": e.to_string(),
-                                        "installed": false,
-                                    }),
-                                ),
-                            )
-                        }
-                    }
+                    )
                 }
                 Err(e) => {
-                    collector.error_with_code(
-                        "E_ADD_FAILED",
-                        e.to_string(),
-                        "Verify the package name/version and pyproject.toml, then retry `pybun add <package>`.",
-                    );
+                    if collector.error_diagnostic_count() == pre_error_count {
+                        collector.error_with_code(
+                            "E_RENAME_DEP_FAILED",
+                            e.to_string(),
+                            "Check that OLD is declared in pyproject.toml, then retry `pybun rename-dep OLD NEW`.",
+                        );
+                    }
                     (
-                        "add".to_string(),
+                        "rename-dep".to_string(),
                         RenderDetail::error(
                             e.to_string(),
                             json!({
@@ -219,37 +212,49 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
-        Commands::Remove(args) => {
-            let result = remove_package(args);
-            match result {
-                Ok(RemoveOutcome { summary, packages }) => {
-                    let packages_json: Vec<serde_json::Value> = packages
-                        .iter()
-                        .map(|p| json!({ "name": p.name, "removed": p.removed }))
-                        .collect();
+        Commands::Import(args) => {
+            let pre_error_count = collector.error_diagnostic_count();
+            match import_dependencies(args, &mut collector).await {
+                Ok(outcome) => {
+                    let (diff, changes) =
+                        render_file_changes(&outcome.diffs.iter().collect::<Vec<_>>());
+                    let imported = &outcome.imported;
                     (
-                        "remove".to_string(),
+                        "import".to_string(),
                         RenderDetail::with_json(
-                            summary,
+                            with_diff(outcome.summary, &diff),
                             json!({
-                                "package": packages.first().map(|p| p.name.clone()),
-                                "removed": packages.first().map(|p| p.removed),
-                                "packages": packages_json,
+                                "file": args.file.display().to_string(),
+                                "format": imported.format.as_str(),
+                                "files": imported.files,
+                                "dependencies": imported.dependencies,
+                                "group": args.group,
+                                "groups": imported.groups,
+                                "extras": imported.extras,
+                                "requires_python": outcome.requires_python,
+                                "indexes": imported.indexes,
+                                "skipped": imported.skipped,
+                                "locked": outcome.locked,
+                                "changes": changes,
+                                "diff": diff,
                             }),
                         ),
                     )
                 }
                 Err(e) => {
-                    collector.error_with_code(
-                        "E_REMOVE_FAILED",
-                        e.to_string(),
-                        "Verify the package is listed in pyproject.toml, then retry `pybun remove <package>`.",
-                    );
+                    if collector.error_diagnostic_count() == pre_error_count {
+                        collector.error_with_code(
+                            "E_IMPORT_FAILED",
+                            e.to_string(),
+                            "Check the file path and format, or re-run with --no-lock to only update pyproject.toml.",
+                        );
+                    }
                     (
-                        "remove".to_string(),
+                        "import".to_string(),
                         RenderDetail::error(
                             e.to_string(),
                             json!({
+                                "file": args.file.display().to_string(),
                                 "error": e.to_string(),
                             }),
                         ),
@@ -257,6 +262,27 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
+        Commands::Lock(args) if args.shards || args.check_shards => {
+            ("lock".to_string(), run_lock_shards(args, &mut collector))
+        }
+        Commands::Lock(args) if args.check => ("lock".to_string(), run_lock_check(&mut collector)),
+        Commands::Lock(
+            args @ LockArgs {
+                from: Some(from), ..
+            },
+        ) => (
+            "lock".to_string(),
+            run_lock_from(args, from, &mut collector).await,
+        ),
+        Commands::Lock(
+            args @ LockArgs {
+                update_only: Some(spec),
+                ..
+            },
+        ) => (
+            "lock".to_string(),
+            run_lock_update(args, spec, &mut collector).await,
+        ),
         Commands::Lock(args) => {
             collector.event(EventType::ResolveStart);
             let pre_error_count = collector.error_diagnostic_count();
@@ -268,8 +294,13 @@ pub async fn execute(cli: Cli) -> Result<()> {
                     packages,
                     verified,
                     artifacts,
+                    dynamic_metadata,
+                    resolver,
+                    comparison,
+                    targets,
                 }) => {
                     collector.event(EventType::InstallComplete);
+                    record_project_activity("lock", &lockfile, None, &mut collector);
                     (
                         "lock".to_string(),
                         RenderDetail::with_json(
@@ -279,6 +310,11 @@ pub async fn execute(cli: Cli) -> Result<()> {
                                 "packages": packages,
                                 "verified": verified,
                                 "artifacts": artifacts,
+                                "dynamic_metadata": dynamic_metadata,
+                                "resolver": resolver,
+                                "comparison": comparison,
+                                "target": targets.first(),
+                                "targets": targets,
                             }),
                         ),
                     )
@@ -287,10 +323,13 @@ pub async fn execute(cli: Cli) -> Result<()> {
                     // Only push a generic fallback error if lock_dependencies did not
                     // already record an error-level diagnostic (e.g. resolve errors).
                     if collector.error_diagnostic_count() == pre_error_count {
-                        collector.error_with_code(
-                            "E_LOCK_FAILED",
-                            e.to_string(),
-                            "Check --index/--require and network connectivity, then re-run `pybun lock`.",
+                        collector.diagnostic(
+                            Diagnostic::error(e.to_string())
+                                .with_code("E_LOCK_FAILED")
+                                .with_suggestion(
+                                    "Check --index/--require and network connectivity, then re-run `pybun lock`.",
+                                )
+                                .with_tool_failure(e.as_ref()),
                         );
                     }
                     (
@@ -305,9 +344,55 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
+        Commands::Run(args) if args.list => match tooling::run_task_list() {
+            Ok(detail) => ("run".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_TASK_FAILED",
+                    e.to_string(),
+                    "Check the [tool.pybun.tasks] table in pyproject.toml, then re-run `pybun run --list`.",
+                );
+                (
+                    "run".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Run(args) if args.member.is_none() && tooling::is_task(args) => {
+            let pre_error_count = collector.error_diagnostic_count();
+            match tooling::run_task(args, &mut collector, cli.format) {
+                Ok(detail) => ("run".to_string(), detail),
+                Err(e) => {
+                    if collector.error_diagnostic_count() == pre_error_count {
+                        collector.error_with_code(
+                            "E_TASK_FAILED",
+                            e.to_string(),
+                            "Check the task's `cmd` and `depends` in [tool.pybun.tasks], then re-run `pybun run --list`.",
+                        );
+                    }
+                    (
+                        "run".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
         Commands::Run(args) => {
             collector.event(EventType::ScriptStart);
-            let result = run_script(args, &mut collector, cli.format).await;
+            let pre_error_count = collector.error_diagnostic_count();
+            let mut member_detail = None;
+            let entered = args
+                .member
+                .as_deref()
+                .map(|member| enter_workspace_member(member, &mut collector))
+                .transpose();
+            let result = match entered {
+                Ok(detail) => {
+                    member_detail = detail;
+                    run_script(args, &mut collector, cli.format).await
+                }
+                Err(e) => Err(e),
+            };
             match result {
                 Ok(RunOutcome {
                     summary,
@@ -322,6 +407,10 @@ pub async fn execute(cli: Cli) -> Result<()> {
                     stderr,
                     sandbox,
                     profile,
+                    entry_point,
+                    encoding,
+                    install,
+                    lazy_imports,
                 }) => {
                     collector.event(EventType::ScriptEnd);
 
@@ -331,9 +420,15 @@ pub async fn execute(cli: Cli) -> Result<()> {
                     // `diagnostics[]` is never empty on a failed run (Issue #266) — callers
                     // should not have to fall back to inspecting `detail.exit_code` alone.
                     if exit_code != 0 {
-                        match stderr.as_deref().and_then(crate::traceback::parse) {
+                        match stderr
+                            .as_ref()
+                            .and_then(|stderr| crate::traceback::parse(&stderr.text))
+                        {
                             Some(tb) => {
                                 let mut diag = Diagnostic::error(tb.message.clone());
+                                if tb.code == "E_RUNTIME_ENCODING_ERROR" {
+                                    diag.suggestion = Some(encoding.failure_hint(&tb.message));
+                                }
                                 diag.code = Some(tb.code);
                                 diag.file = tb.location.as_ref().map(|l| l.file.clone());
                                 diag.line = tb.location.as_ref().map(|l| l.line);
@@ -375,20 +470,7 @@ pub async fn execute(cli: Cli) -> Result<()> {
                         }
                     }
 
-                    let sandbox_detail = sandbox.as_ref().map(|s| {
-                        json!({
-                            "enabled": s.enabled,
-                            "allow_network": s.allow_network,
-                            "allow_read": s.allow_read,
-                            "allow_write": s.allow_write,
-                            "allow_env": s.allow_env,
-                            "default_deny_write": s.default_deny_write,
-                            "enforcement": s.enforcement,
-                            "audit": s.audit,
-                            "resource_limits": s.resource_limits,
-                            "timed_out": s.timed_out,
-                        })
-                    });
+                    let sandbox_detail = sandbox.as_ref().map(sandbox_json);
                     let profile_detail = json!({
                         "name": profile.name,
                         "optimization_level": profile.optimization_level,
@@ -410,19 +492,47 @@ pub async fn execute(cli: Cli) -> Result<()> {
                             "stderr": stderr,
                             "sandbox": sandbox_detail,
                             "profile": profile_detail,
+                            "entry_point": entry_point,
+                            "encoding": encoding,
+                            "install": install,
+                            "lazy_imports": lazy_imports,
+                            "workspace": member_detail,
                         }),
                     )
                     .with_process_exit_code(exit_code);
                     ("run".to_string(), detail)
                 }
+                Err(e) => {
+                    if collector.error_diagnostic_count() == pre_error_count {
+                        collector.error_with_code(
+                            "E_RUN_FAILED",
+                            e.to_string(),
+                            "Check the script path and any PEP 723 inline metadata, then re-run `pybun run <script>`.",
+                        );
+                    }
+                    (
+                        "run".to_string(),
+                        RenderDetail::error(
+                            e.to_string(),
+                            json!({
+                                "error": e.to_string(),
+                            }),
+                        ),
+                    )
+                }
+            }
+        }
+        Commands::X(args) if args.list || args.upgrade || args.remove => {
+            match manage_tools(args, &mut collector).await {
+                Ok(detail) => ("x".to_string(), detail),
                 Err(e) => {
                     collector.error_with_code(
-                        "E_RUN_FAILED",
+                        "E_X_FAILED",
                         e.to_string(),
-                        "Check the script path and any PEP 723 inline metadata, then re-run `pybun run <script>`.",
+                        "Check the tool cache under PYBUN_HOME (`pybun x --list`), then retry.",
                     );
                     (
-                        "run".to_string(),
+                        "x".to_string(),
                         RenderDetail::error(
                             e.to_string(),
                             json!({
@@ -435,17 +545,22 @@ pub async fn execute(cli: Cli) -> Result<()> {
         }
         Commands::X(args) => {
             collector.event(EventType::EnvCreate);
-            let result = execute_tool(args, &mut collector);
+            let pre_error_count = collector.error_diagnostic_count();
+            let result = execute_tool(args, &mut collector).await;
             match result {
                 Ok(XOutcome {
                     summary,
                     package,
                     version,
                     passthrough,
-                    temp_env,
                     python_version,
+                    command,
+                    entry_point,
                     exit_code,
-                    cleanup,
+                    tool_env,
+                    env,
+                    install,
+                    sandbox,
                 }) => (
                     "x".to_string(),
                     RenderDetail::with_json(
@@ -454,20 +569,28 @@ pub async fn execute(cli: Cli) -> Result<()> {
                             "package": package,
                             "version": version,
                             "passthrough": passthrough,
-                            "temp_env": temp_env,
                             "python_version": python_version,
+                            "command": command,
+                            "entry_point": entry_point,
                             "exit_code": exit_code,
-                            "cleanup": cleanup,
+                            "tool_env": tool_env,
+                            "env": env,
+                            "install": install,
+                            "sandbox": sandbox.as_ref().map(sandbox_json),
                         }),
                     )
                     .with_process_exit_code(exit_code),
                 ),
                 Err(e) => {
-                    collector.error_with_code(
-                        "E_X_FAILED",
-                        e.to_string(),
-                        "Verify the tool/package name and that it provides a console entry point, then retry `pybun x <tool>`.",
-                    );
+                    // E_X_COMMAND_NOT_FOUND is already recorded with the
+                    // package's commands.
+                    if collector.error_diagnostic_count() == pre_error_count {
+                        collector.error_with_code(
+                            "E_X_FAILED",
+                            e.to_string(),
+                            "Verify the tool/package name and that it provides a console entry point, then retry `pybun x <tool>`.",
+                        );
+                    }
                     (
                         "x".to_string(),
                         RenderDetail::error(
@@ -503,47 +626,117 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
+        Commands::Bench(args) => match bench::run_bench(args, &mut collector) {
+            Ok(detail) => ("bench".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_BENCH_RUN_FAILED",
+                    e.to_string(),
+                    "Check that Python is available and the benchmark paths are valid, then re-run `pybun bench`.",
+                );
+                (
+                    "bench".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
         Commands::Build(args) => {
             let pre_error_count = collector.error_diagnostic_count();
             let result = run_build(args, &mut collector, cli.format);
             let detail = match result {
-                Ok(outcome) => RenderDetail::with_json(outcome.summary, {
-                    let backend = &outcome.backend;
-                    let sbom_detail = if let Some(sbom) = &outcome.sbom {
-                        json!({
-                            "requested": args.sbom,
-                            "path": sbom.path.display().to_string(),
-                            "format": sbom.format,
-                            "components": sbom.component_count,
-                        })
-                    } else {
+                Ok(outcome) => {
+                    let differing: Vec<&str> = outcome
+                        .reproducibility
+                        .iter()
+                        .flatten()
+                        .filter(|artifact| !artifact.identical)
+                        .map(|artifact| artifact.name.as_str())
+                        .collect();
+                    let sources: BTreeSet<reproducible::NondeterminismSource> = outcome
+                        .reproducibility
+                        .iter()
+                        .flatten()
+                        .flat_map(|artifact| artifact.sources.iter().copied())
+                        .collect();
+                    let json = {
+                        let backend = &outcome.backend;
+                        let sbom_detail = if let Some(sbom) = &outcome.sbom {
+                            json!({
+                                "requested": args.sbom.is_some(),
+                                "path": sbom.path.display().to_string(),
+                                "format": sbom.format,
+                                "components": sbom.component_count,
+                            })
+                        } else {
+                            json!({
+                                "requested": args.sbom.is_some(),
+                                "status": if args.sbom.is_some() { "skipped" } else { "not_requested" },
+                            })
+                        };
                         json!({
-                            "requested": args.sbom,
-                            "status": if args.sbom { "skipped" } else { "not_requested" },
+                        "builder": outcome.builder,
+                        "python": outcome.python.display().to_string(),
+                        "dist_dir": outcome.dist_dir.display().to_string(),
+                        "artifacts": outcome.artifacts.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+                        "backend": {
+                            "name": backend.name.clone(),
+                            "kind": backend.kind.as_str(),
+                            "isolated": backend.isolated,
+                            "requires": backend.requires.clone(),
+                        },
+                        "cache": {
+                            "hit": outcome.cache_hit,
+                            "key": outcome.cache_key,
+                            "dir": outcome.cache_dir.display().to_string(),
+                        },
+                        "sbom": sbom_detail,
+                        "reproducible": {
+                            "source_date_epoch": outcome.source_date_epoch,
+                            "epoch_source": outcome.epoch_source,
+                            "env": outcome.build_env.iter().cloned().collect::<BTreeMap<_, _>>(),
+                            "normalized": outcome.normalized.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+                            "check": outcome.reproducibility.as_ref().map(|artifacts| json!({
+                                "identical": differing.is_empty(),
+                                "sources": sources,
+                                "artifacts": artifacts,
+                            })),
+                        },
+                        "stdout": outcome.stdout,
+                        "stderr": outcome.stderr,
+                        "exit_code": outcome.exit_code,
                         })
                     };
-                    json!({
-                    "builder": outcome.builder,
-                    "python": outcome.python.display().to_string(),
-                    "dist_dir": outcome.dist_dir.display().to_string(),
-                    "artifacts": outcome.artifacts.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
-                    "backend": {
-                        "name": backend.name.clone(),
-                        "kind": backend.kind.as_str(),
-                        "isolated": backend.isolated,
-                        "requires": backend.requires.clone(),
-                    },
-                    "cache": {
-                        "hit": outcome.cache_hit,
-                        "key": outcome.cache_key,
-                        "dir": outcome.cache_dir.display().to_string(),
-                    },
-                    "sbom": sbom_detail,
-                    "stdout": outcome.stdout,
-                    "stderr": outcome.stderr,
-                    "exit_code": outcome.exit_code,
-                    })
-                }),
+                    if differing.is_empty() {
+                        let summary = if outcome.reproducibility.is_some() {
+                            format!(
+                                "{} (reproducible: two builds are identical)",
+                                outcome.summary
+                            )
+                        } else {
+                            outcome.summary
+                        };
+                        RenderDetail::with_json(summary, json)
+                    } else {
+                        let message = format!(
+                            "build is not reproducible: {} differ ({})",
+                            differing.join(", "),
+                            sources
+                                .iter()
+                                .map(|source| source.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                        collector.diagnostic(
+                            Diagnostic::error(message.clone())
+                                .with_code("E_BUILD_NOT_REPRODUCIBLE")
+                                .with_suggestion(
+                                    "`detail.reproducible.check.sources` names the likely causes and `detail.reproducible.check.artifacts` the differing members; pin what the backend reads with `--env KEY=VALUE` or `[tool.pybun.build.env]`.",
+                                )
+                                .with_context(json["reproducible"]["check"].clone()),
+                        );
+                        RenderDetail::error(message, json)
+                    }
+                }
                 Err(e) => {
                     // Only push a generic fallback error if run_build did not already
                     // record an error-level diagnostic (e.g. E_BUILD_MISSING_BUILD_PKG).
@@ -564,9 +757,31 @@ pub async fn execute(cli: Cli) -> Result<()> {
             };
             ("build".to_string(), detail)
         }
+        Commands::Publish(args) => {
+            let pre_error_count = collector.error_diagnostic_count();
+            match maintenance::run_publish(args, &mut collector).await {
+                Ok(detail) => ("publish".to_string(), detail),
+                Err(e) => {
+                    if collector.error_diagnostic_count() == pre_error_count {
+                        collector.error_with_code(
+                            "E_PUBLISH_FAILED",
+                            e.to_string(),
+                            "Run `pybun build` first, check --repository, then re-run `pybun publish --dry-run` to validate the files and credentials.",
+                        );
+                    }
+                    (
+                        "publish".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
         Commands::Doctor(args) => {
             collector.info("Running environment diagnostics");
-            let detail = maintenance::run_doctor(args, &mut collector);
+            let mut detail = maintenance::run_doctor(args, &mut collector);
+            if args.score {
+                maintenance::add_health_score(&mut detail).await;
+            }
             ("doctor".to_string(), detail)
         }
         Commands::Mcp(cmd) => match cmd {
@@ -589,18 +804,53 @@ pub async fn execute(cli: Cli) -> Result<()> {
                         // avoid corrupting the stream with non-JSON text.
                         ("mcp serve".to_string(), RenderDetail::silent())
                     }
-                } else {
-                    // HTTP mode (not yet implemented)
+                } else if !args.host.is_loopback() && args.token.is_none() {
+                    let message = format!(
+                        "refusing to serve MCP on {} without authentication",
+                        args.host
+                    );
+                    collector.error_with_code(
+                        "E_MCP_TOKEN_REQUIRED",
+                        message.clone(),
+                        "Pass --token (or set PYBUN_MCP_TOKEN) when binding a non-loopback address.",
+                    );
                     (
                         "mcp serve".to_string(),
-                        stub_detail(
-                            format!(
-                                "port={} (HTTP mode not yet implemented, use --stdio)",
-                                args.port
-                            ),
-                            json!({"port": args.port, "mode": "http", "status": "not_implemented"}),
+                        RenderDetail::error(
+                            message,
+                            json!({"mode": "http", "host": args.host.to_string(), "port": args.port}),
                         ),
                     )
+                } else {
+                    let config = crate::mcp_http::HttpServerConfig {
+                        bind: std::net::SocketAddr::new(args.host, args.port),
+                        token: args.token.clone(),
+                    };
+                    match crate::mcp_http::run_http_server(config).await {
+                        Ok(addr) => (
+                            "mcp serve".to_string(),
+                            RenderDetail::with_json(
+                                format!("MCP HTTP server on {addr} stopped"),
+                                json!({
+                                    "mode": "http",
+                                    "address": addr.to_string(),
+                                    "auth": args.token.is_some(),
+                                    "status": "stopped",
+                                }),
+                            ),
+                        ),
+                        Err(e) => {
+                            collector.error_with_code(
+                                "E_MCP_SERVE_FAILED",
+                                e.to_string(),
+                                "Check that the address is free (or pass another --port) and retry `pybun mcp serve`.",
+                            );
+                            (
+                                "mcp serve".to_string(),
+                                RenderDetail::error(e.to_string(), json!({"error": e.to_string()})),
+                            )
+                        }
+                    }
                 }
             }
         },
@@ -609,6 +859,11 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 let detail = run_self_update(args, &mut collector);
                 ("self update".to_string(), detail)
             }
+            SelfCommands::Rollback(args) => {
+                let detail = run_self_rollback(args, &mut collector);
+                ("self rollback".to_string(), detail)
+            }
+            SelfCommands::History(_) => ("self history".to_string(), run_self_history()),
         },
         Commands::Gc(args) => {
             collector.event(EventType::CacheHit); // Reuse cache event
@@ -633,58 +888,50 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
-        Commands::Python(cmd) => {
-            match handle_python_command(cmd, &mut collector) {
-                Ok((subcmd, detail)) => (format!("python {}", subcmd), detail),
-                Err(e) => {
-                    // Determine subcommand name for error reporting
-                    let subcmd = match cmd {
-                        PythonCommands::List(_) => "list",
-                        PythonCommands::Install(_) => "install",
-                        PythonCommands::Remove(_) => "remove",
-                        PythonCommands::Which(_) => "which",
-                    };
-                    collector.error_with_code(
-                        format!("E_PYTHON_{}_FAILED", subcmd.to_uppercase()),
-                        e.to_string(),
-                        "Run `pybun doctor` to check Python discovery, then retry `pybun python <subcommand>`.",
-                    );
-                    (
-                        format!("python {}", subcmd),
-                        RenderDetail::error(
-                            e.to_string(),
-                            json!({
-                                "error": e.to_string(),
-                            }),
-                        ),
-                    )
-                }
-            }
-        }
-        Commands::ModuleFind(args) => {
-            collector.event(EventType::ModuleFindStart);
-            let result = tooling::run_module_find(args, &mut collector);
-            collector.event(EventType::ModuleFindComplete);
+        Commands::Cache(cmd) => {
+            use crate::cli::CacheCommands;
+            let (name, result) = match cmd {
+                CacheCommands::Dir => ("cache dir", maintenance::run_cache_dir()),
+                CacheCommands::List(args) => ("cache list", maintenance::run_cache_list(args)),
+                CacheCommands::Info(args) => ("cache info", maintenance::run_cache_info(args)),
+                CacheCommands::Verify => (
+                    "cache verify",
+                    maintenance::run_cache_verify(&mut collector),
+                ),
+                CacheCommands::Push(args) => (
+                    "cache push",
+                    maintenance::run_cache_push(args, &mut collector).await,
+                ),
+                CacheCommands::Pull(args) => (
+                    "cache pull",
+                    maintenance::run_cache_pull(args, &mut collector).await,
+                ),
+            };
             match result {
-                Ok(detail) => ("module-find".to_string(), detail),
+                Ok(detail) => (name.to_string(), detail),
                 Err(e) => {
-                    collector.error_with_code(
-                        "E_MODULE_FIND_FAILED",
-                        e.to_string(),
-                        "Verify the module name and that the target environment is set up, then re-run `pybun module-find`.",
-                    );
-                    (
-                        "module-find".to_string(),
-                        RenderDetail::error(
+                    if matches!(cmd, CacheCommands::Push(_) | CacheCommands::Pull(_)) {
+                        collector.error_with_code(
+                            "E_REMOTE_CACHE_FAILED",
                             e.to_string(),
-                            json!({
-                                "error": e.to_string(),
-                            }),
-                        ),
+                            "Check --remote (or PYBUN_CACHE_URL / [tool.pybun.cache.remote]) and its credentials, then re-run; wheels already transferred are skipped.",
+                        );
+                    } else {
+                        collector.error_with_code(
+                            "E_CACHE_INSPECT_FAILED",
+                            e.to_string(),
+                            "Run `pybun cache list` to see the cached entries and their hashes.",
+                        );
+                    }
+                    (
+                        name.to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
                     )
                 }
             }
         }
+        Commands::Python(cmd) => run_python(cmd, &mut collector),
+        Commands::ModuleFind(args) => run_module_find(args, &mut collector),
         Commands::LazyImport(args) => {
             collector.event(EventType::LazyImportStart);
             let result = tooling::run_lazy_import(args, &mut collector);
@@ -732,16 +979,50 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
-        Commands::Profile(args) => {
-            let result = tooling::run_profile(args, &mut collector);
-            match result {
-                Ok(detail) => ("profile".to_string(), detail),
-                Err(e) => {
-                    collector.error_with_code(
-                        "E_PROFILE_FAILED",
-                        e.to_string(),
-                        "Check the profile name and the [tool.pybun.profiles] section of pyproject.toml, then re-run `pybun profile`.",
-                    );
+        Commands::Profile(ProfileArgs {
+            command: Some(ProfileCommands::Imports(args)),
+            ..
+        }) => match tooling::run_profile_imports(args, &mut collector) {
+            Ok(detail) => ("profile imports".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_PROFILE_IMPORTS_FAILED",
+                    e.to_string(),
+                    "Check the script path and that a Python 3.7+ interpreter is available (PYBUN_PYTHON, .pybun/venv or PATH), then re-run `pybun profile imports`.",
+                );
+                (
+                    "profile imports".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Profile(ProfileArgs {
+            command: Some(ProfileCommands::Run(args)),
+            ..
+        }) => match tooling::run_profile_run(args, &mut collector).await {
+            Ok(detail) => ("profile run".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_PROFILE_RUN_FAILED",
+                    e.to_string(),
+                    "Check the script path and the Python interpreter (PYBUN_PYTHON, .pybun/venv or PATH); with --sampler py-spy, py-spy also needs permission to trace the process.",
+                );
+                (
+                    "profile run".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Profile(args) => {
+            let result = tooling::run_profile(args, &mut collector);
+            match result {
+                Ok(detail) => ("profile".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_PROFILE_FAILED",
+                        e.to_string(),
+                        "Check the profile name and the [tool.pybun.profiles] section of pyproject.toml, then re-run `pybun profile`.",
+                    );
                     (
                         "profile".to_string(),
                         RenderDetail::error(
@@ -885,6 +1166,86 @@ pub async fn execute(cli: Cli) -> Result<()> {
                 }
             }
         }
+        Commands::Sbom(args) => match maintenance::run_sbom(args, &mut collector) {
+            Ok(detail) => ("sbom".to_string(), detail),
+            Err(e) => {
+                if collector.error_diagnostic_count() == 0 {
+                    collector.error_with_code(
+                        "E_SBOM_FAILED",
+                        e.to_string(),
+                        "Run `pybun install` to generate pybun.lockb, then re-run `pybun sbom`.",
+                    );
+                }
+                (
+                    "sbom".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Export(args) => match maintenance::run_export(args, &mut collector) {
+            Ok(detail) => ("export".to_string(), detail),
+            Err(e) => {
+                if collector.error_diagnostic_count() == 0 {
+                    collector.error_with_code(
+                        "E_EXPORT_FAILED",
+                        e.to_string(),
+                        "Run `pybun lock` to generate pybun.lockb, then re-run `pybun export`.",
+                    );
+                }
+                (
+                    "export".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Tree(args) => match maintenance::run_tree(args, &mut collector) {
+            Ok(detail) => ("tree".to_string(), detail),
+            Err(e) => {
+                if collector.error_diagnostic_count() == 0 {
+                    collector.error_with_code(
+                        "E_TREE_FAILED",
+                        e.to_string(),
+                        "Run `pybun install` to generate pybun.lockb, then re-run `pybun tree`.",
+                    );
+                }
+                (
+                    "tree".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Why(args) => match maintenance::run_why(args, &mut collector) {
+            Ok(detail) => ("why".to_string(), detail),
+            Err(e) => {
+                if collector.error_diagnostic_count() == 0 {
+                    collector.error_with_code(
+                        "E_WHY_FAILED",
+                        e.to_string(),
+                        "Run `pybun install` to generate pybun.lockb, then re-run `pybun why`.",
+                    );
+                }
+                (
+                    "why".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Graph(args) => match maintenance::run_graph(args, &mut collector) {
+            Ok(detail) => ("graph".to_string(), detail),
+            Err(e) => {
+                if collector.error_diagnostic_count() == 0 {
+                    collector.error_with_code(
+                        "E_GRAPH_FAILED",
+                        e.to_string(),
+                        "Run `pybun install` to generate pybun.lockb, then re-run `pybun graph`.",
+                    );
+                }
+                (
+                    "graph".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
         Commands::Drift(args) => {
             let result = run_drift(args, &mut collector);
             match result {
@@ -905,17 +1266,218 @@ pub async fn execute(cli: Cli) -> Result<()> {
             }
         }
         Commands::Audit(args) => {
-            collector.info("Scanning installed packages for known vulnerabilities");
+            collector.info("Scanning packages for known vulnerabilities");
             let detail = maintenance::run_audit(args, &mut collector).await;
             ("audit".to_string(), detail)
         }
+        Commands::Env(crate::cli::EnvCommands::Clean(args)) => {
+            match maintenance::run_env_clean(args, &mut collector) {
+                Ok(detail) => ("env clean".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_ENV_CLEAN_FAILED",
+                        e.to_string(),
+                        "Point --venv at a virtual environment (a directory containing pyvenv.cfg), then re-run `pybun env clean`.",
+                    );
+                    (
+                        "env clean".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
+        Commands::Env(crate::cli::EnvCommands::Push(args)) => {
+            match maintenance::run_env_push(args, &mut collector).await {
+                Ok(detail) => ("env push".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_ENV_PUSH_FAILED",
+                        e.to_string(),
+                        "Check --remote (or PYBUN_CACHE_URL / [tool.pybun.cache.remote]) and its credentials, and that --venv points at an environment, then re-run `pybun env push`; chunks already uploaded are skipped.",
+                    );
+                    (
+                        "env push".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
+        Commands::Env(crate::cli::EnvCommands::Pull(args)) => {
+            match maintenance::run_env_pull(args, &mut collector).await {
+                Ok(detail) => ("env pull".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_ENV_PULL_FAILED",
+                        e.to_string(),
+                        "Check --remote (or PYBUN_CACHE_URL / [tool.pybun.cache.remote]) and --name (see the remote's snapshots/ prefix), then re-run `pybun env pull`; chunks already fetched are skipped.",
+                    );
+                    (
+                        "env pull".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
+        Commands::Env(crate::cli::EnvCommands::Snapshot(args)) => {
+            match maintenance::run_env_snapshot(args, &mut collector) {
+                Ok(detail) => ("env snapshot".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_ENV_SNAPSHOT_FAILED",
+                        e.to_string(),
+                        "Check that --venv points at an environment and --output ends in .tar.zst, .tar.gz or .tar, then re-run `pybun env snapshot`.",
+                    );
+                    (
+                        "env snapshot".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
+        Commands::Env(crate::cli::EnvCommands::Restore(args)) => {
+            match maintenance::run_env_restore(args, &mut collector) {
+                Ok(detail) => ("env restore".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_ENV_RESTORE_FAILED",
+                        e.to_string(),
+                        "Check that --input is an archive written by `pybun env snapshot`; re-create it if it is damaged, or run `pybun install` instead.",
+                    );
+                    (
+                        "env restore".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
+        Commands::Env(crate::cli::EnvCommands::List(args)) => {
+            match maintenance::run_env_list(args) {
+                Ok(detail) => ("env list".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_ENV_LIST_FAILED",
+                        e.to_string(),
+                        "Point --venv at a virtual environment, or run `pybun install` first.",
+                    );
+                    (
+                        "env list".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
+        Commands::Projects(cmd) => match maintenance::run_projects(cmd, &mut collector) {
+            Ok(detail) => ("projects".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_PROJECTS_FAILED",
+                    e.to_string(),
+                    "Check the project path (see `pybun projects list`) and the cache directory permissions, then re-run the command.",
+                );
+                (
+                    "projects".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Alias(crate::cli::AliasCommands::List(_)) => match tooling::run_alias_list() {
+            Ok(detail) => ("alias list".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_ALIAS_FAILED",
+                    e.to_string(),
+                    "Check the [tool.pybun.alias] table in pyproject.toml, then re-run `pybun alias list`.",
+                );
+                (
+                    "alias list".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Config(crate::cli::ConfigCommands::Validate(_)) => {
+            match tooling::run_config_validate(&mut collector) {
+                Ok(detail) => ("config validate".to_string(), detail),
+                Err(e) => {
+                    collector.error_with_code(
+                        "E_CONFIG_INVALID",
+                        e.to_string(),
+                        "Fix the TOML syntax error, then re-run `pybun config validate`.",
+                    );
+                    (
+                        "config validate".to_string(),
+                        RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                    )
+                }
+            }
+        }
+        Commands::Completions(args) => match tooling::run_completions(args) {
+            Ok(detail) => ("completions".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_COMPLETIONS_FAILED",
+                    e.to_string(),
+                    "Re-run `pybun completions <SHELL>` from a readable directory.",
+                );
+                (
+                    "completions".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Script(crate::cli::ScriptCommands::Lock(args)) => (
+            "script lock".to_string(),
+            run_script_lock(args, &mut collector).await,
+        ),
+        Commands::Auth(cmd) => match tooling::run_auth(cmd, &mut collector) {
+            Ok(detail) => ("auth".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_AUTH_FAILED",
+                    e.to_string(),
+                    "Check the URL and $PYBUN_HOME permissions; on machines without an OS keychain set PYBUN_AUTH_BACKEND=file, then re-run `pybun auth`.",
+                );
+                (
+                    "auth".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
+        Commands::Status(args) => (
+            "status".to_string(),
+            maintenance::run_status(args, &mut collector),
+        ),
+        Commands::Explain(args) => (
+            "explain".to_string(),
+            maintenance::run_explain(args, &mut collector),
+        ),
+        Commands::Hook(cmd) => match tooling::run_hook(cmd, &mut collector) {
+            Ok(detail) => ("hook".to_string(), detail),
+            Err(e) => {
+                collector.error_with_code(
+                    "E_HOOK_FAILED",
+                    e.to_string(),
+                    "Check that the rc file is writable and $PYBUN_HOME is accessible, then re-run `pybun hook`.",
+                );
+                (
+                    "hook".to_string(),
+                    RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+                )
+            }
+        },
     };
 
+    record_deferred_diagnostics(&mut collector);
+
+    let detail = apply_table_output(&command, detail, cli.format, &cli.columns, &mut collector);
+
     // Record command end
     collector.event(EventType::CommandEnd);
 
     let duration = collector.elapsed();
-    let (events, diagnostics, trace_id) = collector.into_parts();
+    let (mut events, mut diagnostics, trace_id) = collector.into_parts();
+    if let Some(stream) = &ndjson {
+        stream.skip_streamed(&mut events, &mut diagnostics);
+    }
 
     let is_error = detail.is_error;
     let process_exit_code = detail.process_exit_code;
@@ -923,6 +1485,7 @@ pub async fn execute(cli: Cli) -> Result<()> {
         &command,
         detail,
         cli.format,
+        cli.max_inline_bytes,
         duration,
         events,
         diagnostics,
@@ -930,8 +1493,24 @@ pub async fn execute(cli: Cli) -> Result<()> {
     );
 
     progress.finish();
-    if let Some(output) = rendered {
-        println!("{output}");
+    match rendered {
+        Some(Rendered::Text(output)) => println!("{output}"),
+        Some(Rendered::Json(envelope)) => {
+            use std::io::Write as _;
+            let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+            if ndjson.is_some() {
+                envelope
+                    .write_ndjson(&mut stdout)
+                    .map_err(|e| eyre!("failed to write NDJSON output: {}", e))?;
+            } else {
+                envelope
+                    .write_json(&mut stdout)
+                    .map_err(|e| eyre!("failed to write JSON output: {}", e))?;
+                writeln!(stdout)?;
+            }
+            stdout.flush()?;
+        }
+        None => {}
     }
 
     // Flush stdout before any std::process::exit call. std::process::exit
@@ -939,6 +1518,12 @@ pub async fn execute(cli: Cli) -> Result<()> {
     // would otherwise silently discard buffered output.
     let _ = std::io::Write::flush(&mut std::io::stdout());
 
+    crate::operation::finish_current(if is_error {
+        1
+    } else {
+        process_exit_code.unwrap_or(0)
+    });
+
     // `is_error` and `process_exit_code` are mutually exclusive: the Err
     // arm of every command sets is_error via RenderDetail::error() which
     // leaves process_exit_code = None, while the Ok arm uses with_json()
@@ -957,27 +1542,46 @@ pub async fn execute(cli: Cli) -> Result<()> {
     Ok(())
 }
 
+/// Output of [`render`]. JSON is kept as an envelope so it can be streamed
+/// to stdout instead of first being built as one string.
+enum Rendered {
+    Text(String),
+    Json(JsonEnvelope),
+}
+
+#[allow(clippy::too_many_arguments)]
 fn render(
     command: &str,
     detail: RenderDetail,
     format: OutputFormat,
+    max_inline_bytes: u64,
     duration: Duration,
     events: Vec<Event>,
-    diagnostics: Vec<Diagnostic>,
+    mut diagnostics: Vec<Diagnostic>,
     trace_id: Option<String>,
-) -> Option<String> {
+) -> Option<Rendered> {
     if detail.silent {
         return None;
     }
     Some(match format {
-        OutputFormat::Text => {
-            if detail.raw_text {
-                detail.text
-            } else {
-                format!("pybun {command}: {}", detail.text)
+        OutputFormat::Text | OutputFormat::Tsv => Rendered::Text(if detail.raw_text {
+            detail.text
+        } else {
+            let mut text = format!("pybun {command}: {}", detail.text);
+            let error_code = diagnostics
+                .iter()
+                .filter(|d| matches!(d.level, DiagnosticLevel::Error))
+                .find_map(|d| d.error_code.as_deref());
+            if detail.is_error
+                && let Some(code) = error_code
+            {
+                text.push_str(&format!(
+                    "\nerror code: {code} (run `pybun explain {code}` for details)"
+                ));
             }
-        }
-        OutputFormat::Json => {
+            text
+        }),
+        OutputFormat::Json | OutputFormat::Ndjson => {
             // child_failed is only set on the Ok arm; is_error covers the Err arm (see execute()).
             let child_failed = detail.process_exit_code.is_some_and(|c| c != 0);
             let status = if detail.is_error || child_failed {
@@ -985,28 +1589,43 @@ fn render(
             } else {
                 Status::Ok
             };
+            let mut json = detail.json;
+            let spilled = crate::payload::SpillOptions::new(max_inline_bytes)
+                .and_then(|options| crate::payload::spill_oversized(&mut json, &options))
+                .map(|sections| {
+                    let mut captured = crate::payload::take_captured();
+                    captured.extend(sections);
+                    captured
+                });
+            match spilled {
+                Ok(sections) if !sections.is_empty() => diagnostics.push(
+                    Diagnostic::info(format!(
+                        "{} oversized output section(s) written to disk (--max-inline-bytes={})",
+                        sections.len(),
+                        max_inline_bytes
+                    ))
+                    .with_code("I_PAYLOAD_SPILLED")
+                    .with_context(json!({ "sections": sections })),
+                ),
+                Ok(_) => {}
+                Err(e) => diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "could not spill oversized output to disk, keeping it inline: {e}"
+                    ))
+                    .with_code("W_PAYLOAD_SPILL_FAILED"),
+                ),
+            }
+            crate::units::normalize(&mut json, crate::units::UnitOptions::from_env());
             let mut envelope =
-                JsonEnvelope::new(format!("pybun {command}"), status, duration, detail.json);
+                JsonEnvelope::new(format!("pybun {command}"), status, duration, json);
             envelope.events = events;
             envelope.diagnostics = diagnostics;
             envelope.trace_id = trace_id;
-            envelope.to_json()
+            Rendered::Json(envelope)
         }
     })
 }
 
-fn stub_detail(message: String, payload: Value) -> RenderDetail {
-    let message = format!("{message} (not implemented yet)");
-    RenderDetail::with_json(
-        message.clone(),
-        json!({
-            "status": "stub",
-            "message": message,
-            "payload": payload,
-        }),
-    )
-}
-
 fn schema_version_from(schema: &Value) -> Option<String> {
     schema
         .get("properties")
@@ -1208,8 +1827,10 @@ fn select_member_or_group_dependencies(
             )
         })?;
         let deps = match group {
-            Some(group_name) => member_project.group_dependencies(group_name),
-            None => member_project.dependencies(),
+            Some(group_name) => {
+                ws.external(member_project.group_dependencies(group_name).into_iter())
+            }
+            None => ws.member_dependencies(member_name),
         };
         collector.info(format!(
             "Selected workspace member '{}' at {} ({} dependencies{})",
@@ -1268,6 +1889,38 @@ fn select_member_or_group_dependencies(
     Ok(None)
 }
 
+/// `pybun run --member`: make the root of workspace member `name` the
+/// working directory, so the target and its project configuration are
+/// looked up there. Returns the workspace JSON detail.
+fn enter_workspace_member(name: &str, collector: &mut EventCollector) -> Result<Value> {
+    let cwd = std::env::current_dir()?;
+    let workspace = Workspace::discover_root(&cwd)
+        .map_err(|e| eyre!(e))?
+        .ok_or_else(|| {
+            eyre!("--member requires a workspace; no [tool.pybun.workspace] configuration found")
+        })?;
+    let member = workspace.member_by_name(name).ok_or_else(|| {
+        eyre!(
+            "workspace member '{name}' not found (available: {})",
+            workspace.member_names().join(", ")
+        )
+    })?;
+    std::env::set_current_dir(member.root())?;
+    collector.info(format!(
+        "Selected workspace member '{}' at {}",
+        name,
+        member.root().display()
+    ));
+    Ok(json!({
+        "scope": "member",
+        "root": workspace.root.root().display().to_string(),
+        "selected_members": [name],
+    }))
+}
+
+/// Requirements keyed by the dependency group that declares them.
+type GroupedDependencies = BTreeMap<String, Vec<String>>;
+
 /// Resolve which dependency specifiers to install based on workspace
 /// selectors (`--workspace`/`--member`/`--group`). Returns the dependency
 /// strings plus an optional JSON blob describing the selection scope for
@@ -1277,12 +1930,16 @@ fn select_member_or_group_dependencies(
 /// priority, then `--group` alone (workspace-wide or project-local), then
 /// `--workspace`/auto-detected workspace merging, finally falling back to the
 /// discovered project's own `[project.dependencies]`.
+///
+/// The dependencies are keyed by the group declaring them: `--group`
+/// installs its group on top of what would be selected without it, under
+/// [`MAIN_GROUP`].
 fn select_install_dependencies(
     project: &Project,
     working_dir: &Path,
     args: &crate::cli::InstallArgs,
     collector: &mut EventCollector,
-) -> Result<(Vec<String>, Option<Value>)> {
+) -> Result<(GroupedDependencies, Option<Value>)> {
     let workspace = if args.workspace {
         Workspace::discover_root(working_dir).map_err(|e| eyre!(e))?
     } else {
@@ -1302,7 +1959,22 @@ fn select_install_dependencies(
         args.group.as_deref(),
         collector,
     )? {
-        return Ok((deps, detail));
+        let mut grouped = BTreeMap::new();
+        match args.group.as_deref() {
+            Some(group) => {
+                let main = match (args.member.as_deref(), &workspace) {
+                    (Some(member), Some(ws)) => ws.member_dependencies(member),
+                    (None, Some(ws)) => ws.merged_dependencies(),
+                    _ => project.dependencies(),
+                };
+                grouped.insert(MAIN_GROUP.to_string(), main);
+                grouped.insert(group.to_string(), deps);
+            }
+            None => {
+                grouped.insert(MAIN_GROUP.to_string(), deps);
+            }
+        }
+        return Ok((grouped, detail));
     }
 
     if let Some(ws) = &workspace {
@@ -1314,7 +1986,7 @@ fn select_install_dependencies(
             merged.len()
         ));
         return Ok((
-            merged,
+            BTreeMap::from([(MAIN_GROUP.to_string(), merged)]),
             Some(json!({
                 "scope": "workspace",
                 "root": ws.root.root().display().to_string(),
@@ -1334,7 +2006,7 @@ fn select_install_dependencies(
             project.path().display()
         ));
     }
-    Ok((deps, None))
+    Ok((BTreeMap::from([(MAIN_GROUP.to_string(), deps)]), None))
 }
 
 /// Resolve dependency specifiers for `pybun outdated`/`pybun upgrade`,
@@ -1443,43 +2115,264 @@ pub(crate) fn resolve_target_python_version() -> Option<String> {
     get_python_version(&probe.python_path).ok()
 }
 
+/// Record the project containing the current directory in the machine-wide
+/// registry (see [`crate::project_registry`]). Best effort: a failure is only
+/// a warning.
+fn record_project_activity(
+    command: &str,
+    lockfile: &Path,
+    environment: Option<&Value>,
+    collector: &mut EventCollector,
+) {
+    let Ok(cwd) = std::env::current_dir() else {
+        return;
+    };
+    let Ok(project) = Project::discover(&cwd) else {
+        return;
+    };
+    let lockfile = cwd.join(lockfile);
+    let env = environment
+        .and_then(|env| env["python"].as_str())
+        .and_then(|python| crate::project_registry::venv_of(Path::new(python)));
+    let result = crate::project_registry::ProjectRegistry::new().and_then(|registry| {
+        registry.record(&crate::project_registry::Activity {
+            root: project.root(),
+            command,
+            lockfile: Some(&lockfile),
+            env: env.as_deref(),
+        })
+    });
+    if let Err(e) = result {
+        collector.warning(format!("could not update the project registry: {}", e));
+    }
+}
+
 pub(crate) async fn install(
     args: &crate::cli::InstallArgs,
     collector: &mut EventCollector,
 ) -> Result<InstallOutcome> {
-    // Gather requirements: either from --require flags or from pyproject.toml
-    let (requirements, workspace_detail): (Vec<Requirement>, Option<Value>) =
-        if !args.requirements.is_empty() {
-            // CLI --require flags take precedence
-            (args.requirements.clone(), None)
-        } else {
-            // Try to load from pyproject.toml
-            let working_dir = std::env::current_dir()?;
-            let project = Project::discover(&working_dir).map_err(|_| {
-                eyre!(
-                    "no requirements provided and no pyproject.toml found. \
-                     Use --require or create a pyproject.toml with [project.dependencies]"
-                )
-            })?;
-
-            let (deps, workspace_detail) =
-                select_install_dependencies(&project, &working_dir, args, collector)?;
+    let working_dir = std::env::current_dir()?;
+    honor_python_pin(&working_dir, args.offline, collector);
+    let members = if args.requirements.is_empty() || args.frozen {
+        editable_members(&working_dir, args)?
+    } else {
+        Vec::new()
+    };
+    let mut outcome = if let Some(member) = args.package.as_deref() {
+        install_package(args, collector, &working_dir, member).await?
+    } else if args.frozen {
+        install_frozen(args, collector, &args.lock, None).await?
+    } else {
+        install_requirements(args, collector).await?
+    };
+    install_editable_members(args, collector, &working_dir, &members, &mut outcome)?;
+    Ok(outcome)
+}
 
-            let requirements = deps
-                .into_iter()
-                .map(|d| {
-                    d.parse::<Requirement>()
-                        .unwrap_or_else(|_| Requirement::any(d.trim()))
-                })
-                .collect();
+/// `pybun install --package NAME`: install the lock shard of workspace
+/// member `member`, once it is known to match the workspace lockfile.
+async fn install_package(
+    args: &crate::cli::InstallArgs,
+    collector: &mut EventCollector,
+    working_dir: &Path,
+    member: &str,
+) -> Result<InstallOutcome> {
+    let ws = Workspace::discover_root(working_dir)
+        .map_err(|e| eyre!(e))?
+        .ok_or_else(|| {
+            eyre!("--package requires a workspace; no [tool.pybun.workspace] configuration found")
+        })?;
+    if ws.member_by_name(member).is_none() {
+        return Err(eyre!(
+            "workspace member '{member}' not found (available: {})",
+            ws.member_names().join(", ")
+        ));
+    }
+    let root = ws.root.root().to_path_buf();
+    let lock_path = root.join(&args.lock);
+    let lock = load_install_lock(&lock_path, collector)?;
+    let drift =
+        lock_shard::check(&ws, &lock, &lock_path, &[member.to_string()]).map_err(|e| eyre!(e))?;
+    if let Some(drift) = drift.first() {
+        let message = format!(
+            "lock shard of '{member}' does not match {}: {}",
+            lock_path.display(),
+            drift.describe()
+        );
+        collector.diagnostic(
+            Diagnostic::error(message.clone())
+                .with_code("E_LOCK_SHARD_DRIFT")
+                .with_suggestion(
+                    "Run `pybun lock --shards` to rederive the shards from the workspace lockfile, or `pybun install` at the workspace root to re-resolve.",
+                )
+                .with_context(json!({ "drift": drift })),
+        );
+        return Err(eyre!(message));
+    }
+    let shard = lock_shard::shard_path(&lock_path, member);
+    let closure: Vec<String> = ws
+        .member_closure(member)
+        .into_iter()
+        .map(crate::workspace::member_display_name)
+        .collect();
+    collector.info(format!(
+        "Selected workspace member '{member}' ({} member(s)); installing {}",
+        closure.len(),
+        shard.display()
+    ));
+    let detail = json!({
+        "scope": "package",
+        "root": root.display().to_string(),
+        "selected_members": closure,
+        "group": Value::Null,
+        "shard": shard.display().to_string(),
+    });
+    install_frozen(args, collector, &shard, Some(detail)).await
+}
+
+/// Workspace members `pybun install` installs editable: the `--member` (or
+/// `--package`) and the members it depends on, otherwise every member.
+fn editable_members(working_dir: &Path, args: &crate::cli::InstallArgs) -> Result<Vec<Project>> {
+    let workspace = if args.workspace || args.package.is_some() {
+        Workspace::discover_root(working_dir)
+    } else {
+        Workspace::discover(working_dir)
+    }
+    .map_err(|e| eyre!(e))?;
+    let Some(ws) = workspace else {
+        return Ok(Vec::new());
+    };
+    Ok(match args.member.as_deref().or(args.package.as_deref()) {
+        Some(member) => ws.member_closure(member).into_iter().cloned().collect(),
+        None => ws.members.clone(),
+    })
+}
 
-            (requirements, workspace_detail)
+/// Install workspace `members` editable into the install environment and
+/// report them in `outcome`.
+fn install_editable_members(
+    args: &crate::cli::InstallArgs,
+    collector: &mut EventCollector,
+    working_dir: &Path,
+    members: &[Project],
+    outcome: &mut InstallOutcome,
+) -> Result<()> {
+    if members.is_empty() {
+        return Ok(());
+    }
+    let (target, environment) = prepare_install_env(args, collector, working_dir)?;
+    let site_packages = target.site_packages;
+    outcome.environment = Some(environment);
+    for member in members {
+        let name = crate::workspace::member_display_name(member);
+        let version = member
+            .metadata()
+            .version
+            .unwrap_or_else(|| "0.0.0".to_string());
+        let requires = member.dependencies();
+        let scripts = member.scripts();
+        let project = crate::installer::EditableProject {
+            name: &name,
+            version: &version,
+            root: member.root(),
+            requires: &requires,
+            scripts: &scripts,
         };
+        let dist_info =
+            crate::installer::install_editable(&project, &site_packages).map_err(|e| {
+                eyre!(
+                    "failed to install workspace member {} editable: {}",
+                    name,
+                    e
+                )
+            })?;
+        let pth = dist_info
+            .file_stem()
+            .map(|stem| format!("__editable__.{}.pth", stem.to_string_lossy()))
+            .unwrap_or_default();
+        let mut record = InstallRecord::new(&name, &version, &pth, InstallStatus::Installed);
+        record.dist_info = Some(dist_info);
+        record.editable = Some(member.root().to_path_buf());
+        outcome.results.push(record);
+        outcome.installed_count += 1;
+    }
+    collector.info(format!(
+        "Installed {} workspace member(s) editable into {}",
+        members.len(),
+        site_packages.display()
+    ));
+    outcome.summary = format!(
+        "{}; {} workspace member(s) editable",
+        outcome.summary,
+        members.len()
+    );
+    Ok(())
+}
+
+/// `pybun install` without `--frozen`: resolve, lock and install the
+/// requirements.
+async fn install_requirements(
+    args: &crate::cli::InstallArgs,
+    collector: &mut EventCollector,
+) -> Result<InstallOutcome> {
+    // Gather requirements: either from --require flags or from pyproject.toml
+    let (requirements, workspace_detail, groups): (
+        Vec<Requirement>,
+        Option<Value>,
+        BTreeMap<String, Vec<String>>,
+    ) = if !args.requirements.is_empty() {
+        // CLI --require flags take precedence
+        let specs = args.requirements.iter().map(ToString::to_string).collect();
+        (
+            args.requirements.clone(),
+            None,
+            BTreeMap::from([(MAIN_GROUP.to_string(), specs)]),
+        )
+    } else {
+        // Try to load from pyproject.toml
+        let working_dir = std::env::current_dir()?;
+        let project = Project::discover(&working_dir).map_err(|_| {
+            eyre!(
+                "no requirements provided and no pyproject.toml found. \
+                     Use --require or create a pyproject.toml with [project.dependencies]"
+            )
+        })?;
+
+        let (groups, workspace_detail) =
+            select_install_dependencies(&project, &working_dir, args, collector)?;
+
+        // A requirement declared by several groups is resolved once.
+        let mut seen = BTreeSet::new();
+        let requirements = groups
+            .values()
+            .flatten()
+            .filter(|d| seen.insert(d.as_str()))
+            .map(|d| {
+                d.parse::<Requirement>()
+                    .unwrap_or_else(|_| Requirement::any(d.trim()))
+            })
+            .collect();
+
+        (requirements, workspace_detail, groups)
+    };
 
     warn_on_ignored_extras(&requirements, collector);
+    let (git_requirements, mut requirements) = split_git_requirements(requirements, collector)?;
+    // Packages an earlier `--allow-suspicious` install acknowledged stay
+    // flagged for container builds.
+    if let Ok(previous) = Lockfile::load_from_path(&args.lock) {
+        crate::suspicious::restore(&previous);
+    }
+    let new_dependencies =
+        new_dependency_names(requirements.iter().map(|r| r.name.as_str()), &args.lock);
+    let typosquats: Vec<_> = new_dependencies
+        .iter()
+        .filter_map(|name| crate::suspicious::typosquat(name))
+        .collect();
+    report_suspicious(&typosquats, args.allow_suspicious, collector)?;
 
     // If no requirements (empty pyproject dependencies), create empty lockfile
-    if requirements.is_empty() {
+    if requirements.is_empty() && git_requirements.is_empty() {
         let lock = Lockfile::new(vec!["3.11".into()], vec!["unknown".into()]);
         lock.save_to_path(&args.lock)?;
         return Ok(InstallOutcome {
@@ -1490,55 +2383,54 @@ pub(crate) async fn install(
             artifacts: Vec::new(),
             workspace: workspace_detail.clone(),
             installed_count: 0,
+            results: Vec::new(),
+            environment: None,
         });
     }
 
-    // Detect the CPython tag of the actual install target (PYBUN_ENV / PYBUN_PYTHON /
-    // project venv / system Python) *before* selecting wheels, so artifact selection
-    // matches the Python interpreter packages will actually be installed into.
-    // Selecting wheels against whatever `python3`/`python` happens to resolve on PATH
-    // (the previous behavior) can silently pick wheels for the wrong CPython ABI
-    // (Issue #291). This is read-only detection only — creating a project-local venv
-    // (and the associated system-Python safe-install-target guard) is deferred to the
-    // later "Install wheels" step below, so a resolve-only or failed install doesn't
-    // have the side effect of mutating the filesystem.
-    //
-    // Detection happens before resolution because the same interpreter version also
-    // drives `requires-python` candidate filtering (Issue #342). The interpreter is
-    // only spawned when no environment override makes it unnecessary.
     let working_dir = std::env::current_dir()?;
-    let target_env_probe = crate::env::find_python_env(&working_dir)?;
-    let python_version_override = python_version_env_override();
-    // PYBUN_FORCE_CP_TAG lets tests (and users) pin the CPython tag deterministically.
-    // Note it no longer bypasses interpreter detection on its own: the detected version
-    // is also needed for `requires-python` filtering, so detection is only skipped when
-    // PYBUN_PYPI_PYTHON_VERSION covers that too.
-    let forced_cp_tag = std::env::var("PYBUN_FORCE_CP_TAG")
-        .ok()
-        .filter(|v| !v.trim().is_empty());
-    let detected_python_version = if python_version_override.is_none() || forced_cp_tag.is_none() {
-        get_python_version(&target_env_probe.python_path).ok()
-    } else {
-        None
-    };
-    let active_cp_tag = forced_cp_tag
-        .or_else(|| {
-            detected_python_version
-                .as_deref()
-                .and_then(python_version_to_cp_tag)
-        })
-        .unwrap_or_else(|| "cp311".to_string());
+    let InstallPython {
+        probe: target_env_probe,
+        version: target_python_version,
+        cp_tag: active_cp_tag,
+    } = detect_install_python(&working_dir)?;
 
-    let source_index_url: String;
+    let targets = wheel_targets(&working_dir, &target_env_probe.python_path, collector)?;
     let offline = args.offline;
+    // Git dependencies are built first: their wheels' requirements are
+    // resolved with the rest.
+    let git_dependencies = build_git_dependencies(
+        git_requirements,
+        &GitBuildTarget {
+            python: &target_env_probe.python_path,
+            cp_tag: &active_cp_tag,
+            platform: targets
+                .tags()
+                .first()
+                .map(String::as_str)
+                .unwrap_or("unknown"),
+            offline,
+        },
+        collector,
+    )?;
+    for dependency in &git_dependencies {
+        requirements.extend(dependency.requires.iter().cloned());
+    }
+
+    let source_index_url: String;
     let resolve_options = ResolveOptions {
         allow_prerelease: args.pre,
-        python_version: python_version_override.or(detected_python_version),
+        python_version: target_python_version,
     };
-    let resolution = if let Some(index_path) = args.index.clone() {
+    let mut dynamic_metadata = BTreeSet::new();
+    let mut resolution = if let Some(index_path) = args.index.clone() {
         source_index_url = index_path.display().to_string();
         let index = load_index_from_path(&index_path).map_err(|e| eyre!(e))?;
-        match resolve_with_options(requirements.clone(), &index, resolve_options).await {
+        match args
+            .resolver
+            .resolve(requirements.clone(), &index, resolve_options, None)
+            .await
+        {
             Ok(r) => r,
             Err(e) => {
                 for d in crate::self_heal::diagnostics_for_resolve_error(&requirements, &e) {
@@ -1548,20 +2440,27 @@ pub(crate) async fn install(
             }
         }
     } else {
-        let client = PyPiClient::from_env(offline)
+        let index = RemoteIndex::from_env(offline)
             .map_err(|e| eyre!("failed to init pypi client: {}", e))?;
-        source_index_url = client.index_url();
+        source_index_url = index.index_url();
         collector.info(format!(
             "Using PyPI index {} (offline: {})",
             source_index_url, offline
         ));
-        let index = PyPiIndex::new(client);
-        let resolve_result =
-            resolve_with_options(requirements.clone(), &index, resolve_options).await;
+        let resolve_result = args
+            .resolver
+            .resolve(
+                requirements.clone(),
+                &index,
+                resolve_options,
+                Some(&source_index_url),
+            )
+            .await;
         for notice in index.take_stale_cache_notices() {
             collector.warning(notice);
         }
-        match resolve_result {
+        dynamic_metadata = index.dynamic_metadata_packages();
+        let resolution = match resolve_result {
             Ok(r) => r,
             Err(e) => {
                 for d in crate::self_heal::diagnostics_for_resolve_error(&requirements, &e) {
@@ -1569,17 +2468,27 @@ pub(crate) async fn install(
                 }
                 return Err(eyre!(e.to_string()));
             }
+        };
+        let today = crate::pypi::now_epoch_seconds() / 86_400;
+        let mut findings = Vec::new();
+        for name in &new_dependencies {
+            if let Ok(Some(history)) = index.project_history(name).await {
+                findings.extend(crate::suspicious::history(name, &history, today));
+            }
         }
+        report_suspicious(&findings, args.allow_suspicious, collector)?;
+        resolution
     };
+    drop_git_packages(&mut resolution, &git_dependencies);
     warn_on_prerelease_fallback(&resolution, collector);
     collector.event_with(EventType::ResolveComplete, |event| {
         event.message = Some("Resolved dependencies".to_string());
         event.progress = Some(40);
     });
 
-    let platform_tags = current_platform_tags();
+    let platform_tags = targets.tags().to_vec();
     let mut lock = Lockfile::new(
-        vec!["3.11".into()],
+        vec![lock_python_version(&active_cp_tag)],
         vec![
             platform_tags
                 .first()
@@ -1589,75 +2498,322 @@ pub(crate) async fn install(
     );
     let mut verified_artifacts = Vec::new();
     for pkg in resolution.packages.values() {
-        let selection = select_artifact_for_platform_with_cp(pkg, &platform_tags, &active_cp_tag);
+        let package_tags = targets.tags_for(&pkg.name);
+        let mut selection = select_artifact_for_platform_with_cp(pkg, package_tags, &active_cp_tag);
         if selection.from_source {
             let message = format!(
-                "no compatible pre-built wheel for {} {} on {}; source distributions are not supported for install",
+                "no compatible pre-built wheel for {} {} on {}; falling back to source build",
                 pkg.name,
                 pkg.version,
-                platform_tags.join(",")
+                package_tags.join(",")
             );
             eprintln!("warning: {}", message);
             collector.warning(message);
         }
-        let (verified_hash, artifact) =
-            ensure_selection_is_verifiable(pkg, &selection, collector, &source_index_url)?;
+        warn_on_arch_mismatch(pkg, &selection, &targets, collector);
+        let hash_missing = !args.require_hashes && !offline;
+        let (verified_hash, artifact) = verify_selection(
+            pkg,
+            &mut selection,
+            hash_missing,
+            &source_index_url,
+            collector,
+        )
+        .await?;
         verified_artifacts.push(artifact);
         lock.add_package(Package {
             name: pkg.name.clone(),
             version: pkg.version.clone(),
             source: registry_source_for_index(&source_index_url),
-            wheel: selection.filename,
-            hash: verified_hash,
+            wheel: selection.filename.clone(),
+            hash: verified_hash.clone(),
             dependencies: pkg.dependencies.iter().map(ToString::to_string).collect(),
+            dynamic_metadata: has_dynamic_metadata(&dynamic_metadata, &pkg.name),
+            groups: Vec::new(),
+            build: None,
+            artifacts: vec![locked_artifact(pkg, &selection, verified_hash)],
         });
     }
-    lock.save_to_path(&args.lock)?;
+    for dependency in &git_dependencies {
+        lock.add_package(dependency.locked_package());
+    }
+    lock.assign_groups(&groups);
+    crate::suspicious::record(&mut lock);
+    let shard_workspace = match &workspace_detail {
+        Some(detail) if detail["scope"] == "workspace" => detail["root"]
+            .as_str()
+            .map(Workspace::discover_root)
+            .transpose()
+            .map_err(|e| eyre!(e))?
+            .flatten(),
+        _ => None,
+    };
+    save_install_lock(&lock, &args.lock, shard_workspace.as_ref(), collector)?;
+    let workspace_detail = workspace_detail.map(|mut detail| {
+        if shard_workspace.is_some() {
+            detail["shards"] = json!(lock_shard::shard_dir(&args.lock).display().to_string());
+        }
+        detail
+    });
+
+    install_locked(
+        args,
+        collector,
+        LockedInstall {
+            lock,
+            lock_path: args.lock.clone(),
+            frozen: false,
+            shard_workspace,
+            resolution,
+            git: git_dependencies,
+            verified_artifacts,
+            workspace: workspace_detail,
+            targets,
+            cp_tag: active_cp_tag,
+            python: target_env_probe.python_path,
+            working_dir,
+        },
+    )
+    .await
+}
+
+/// Save the lockfile `pybun install` resolved, with the lock shards of every
+/// member when it is a workspace's.
+fn save_install_lock(
+    lock: &Lockfile,
+    lock_path: &Path,
+    workspace: Option<&Workspace>,
+    collector: &mut EventCollector,
+) -> Result<()> {
+    lock.save_to_path(lock_path)?;
+    if let Some(ws) = workspace {
+        let shards = lock_shard::write_all(ws, lock, lock_path).map_err(|e| eyre!(e))?;
+        collector.info(format!(
+            "Wrote {} member lock shard(s) to {}",
+            shards.len(),
+            lock_shard::shard_dir(lock_path).display()
+        ));
+    }
+    Ok(())
+}
+
+/// What `pybun install` installs: a lockfile and the packages it pins.
+struct LockedInstall {
+    lock: Lockfile,
+    /// Where `lock` lives.
+    lock_path: PathBuf,
+    /// Install `lock` as it is, without rewriting it.
+    frozen: bool,
+    /// Workspace whose member lock shards are rewritten with `lock`.
+    shard_workspace: Option<Workspace>,
+    resolution: Resolution,
+    /// Git dependencies, already built.
+    git: Vec<GitDependency>,
+    /// Verification records of the selected artifacts.
+    verified_artifacts: Vec<Value>,
+    workspace: Option<Value>,
+    targets: crate::arch::WheelTargets,
+    cp_tag: String,
+    /// Interpreter source builds run with.
+    python: PathBuf,
+    working_dir: PathBuf,
+}
+
+/// Normalized names among `names` that the lockfile at `lock_path` does not
+/// have yet and `pybun-policy.toml` neither approves nor blocks (blocked ones
+/// are refused by the policy itself): the dependencies the
+/// suspicious-package checks look at.
+fn new_dependency_names<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    lock_path: &Path,
+) -> Vec<String> {
+    let locked: BTreeSet<String> = Lockfile::load_from_path(lock_path)
+        .map(|lock| {
+            lock.packages
+                .values()
+                .map(|pkg| crate::pypi::normalize_project_name(&pkg.name))
+                .collect()
+        })
+        .unwrap_or_default();
+    let names: BTreeSet<String> = names
+        .into_iter()
+        .map(crate::pypi::normalize_project_name)
+        .filter(|name| {
+            !locked.contains(name)
+                && !crate::suspicious::approved(name)
+                && crate::execution_policy::current()
+                    .is_none_or(|policy| policy.check_package(name).is_ok())
+        })
+        .collect();
+    names.into_iter().collect()
+}
+
+/// Report suspicious-package findings ([`crate::suspicious`]): warnings once
+/// `allow`ed, which flags the packages for container builds, otherwise
+/// errors that stop the command.
+fn report_suspicious(
+    findings: &[crate::suspicious::Finding],
+    allow: bool,
+    collector: &mut EventCollector,
+) -> Result<()> {
+    for finding in findings {
+        collector.diagnostic(finding.diagnostic(allow));
+        if allow {
+            crate::suspicious::flag(&finding.package);
+        }
+    }
+    if allow || findings.is_empty() {
+        return Ok(());
+    }
+    let packages: BTreeSet<&str> = findings.iter().map(|f| f.package.as_str()).collect();
+    Err(eyre!(
+        "refusing suspicious package(s) {}; re-run with --allow-suspicious if they are intended",
+        packages.into_iter().collect::<Vec<_>>().join(", ")
+    ))
+}
+
+/// Download, build and install the artifacts selected from `plan` for this
+/// machine.
+async fn install_locked(
+    args: &crate::cli::InstallArgs,
+    collector: &mut EventCollector,
+    plan: LockedInstall,
+) -> Result<InstallOutcome> {
+    let LockedInstall {
+        mut lock,
+        lock_path,
+        frozen,
+        shard_workspace,
+        resolution,
+        git,
+        verified_artifacts,
+        workspace: workspace_detail,
+        targets,
+        cp_tag: active_cp_tag,
+        python,
+        working_dir,
+    } = plan;
+
+    if let Some(policy) = crate::execution_policy::current() {
+        let violations: Vec<_> = resolution
+            .packages
+            .values()
+            .filter_map(|pkg| policy.check_package(&pkg.name).err())
+            .collect();
+        if let Some(first) = violations.first() {
+            let message = first.to_string();
+            for violation in &violations {
+                collector.diagnostic(violation.diagnostic());
+            }
+            return Err(eyre!(message));
+        }
+    }
 
     // Download artifacts in parallel.
     // Respect PYBUN_PYPI_CACHE_DIR when present so tests and callers can
     // isolate both index metadata and downloaded wheel artifacts together.
-    let cache_dir = if let Ok(dir) = std::env::var("PYBUN_PYPI_CACHE_DIR") {
-        PathBuf::from(dir).join("artifacts")
-    } else {
-        dirs::cache_dir()
-            .ok_or_else(|| eyre!("failed to determine cache directory"))?
-            .join("pybun")
-            .join("artifacts")
-    };
+    let cache_dir = crate::pypi::artifacts_cache_dir()
+        .ok_or_else(|| eyre!("failed to determine cache directory"))?;
 
     collector.info(format!("Downloading artifacts to {}", cache_dir.display()));
 
     let mut download_items = Vec::new();
+    // Per-package reports: `pending` is parallel to `download_items`.
+    let mut pending = Vec::new();
+    let mut results = Vec::new();
     let mut sdist_only_packages = Vec::new();
+    let mut source_builds = Vec::new();
     for pkg in resolution.packages.values() {
-        let selection = select_artifact_for_platform_with_cp(pkg, &platform_tags, &active_cp_tag);
-        if let Some(url) = selection.url {
+        let mut selection =
+            select_artifact_for_platform_with_cp(pkg, targets.tags_for(&pkg.name), &active_cp_tag);
+        // Hashes computed while locking are in the lockfile, not the index.
+        if is_missing_sha256(selection.hash.as_deref()) {
+            selection.hash = locked_hash(&lock, &pkg.name, &selection.filename);
+        }
+        if selection.from_source {
+            match (selection.url, selection.hash) {
+                (Some(url), Some(hash)) => source_builds.push(PendingSourceBuild {
+                    name: pkg.name.clone(),
+                    version: pkg.version.clone(),
+                    sdist: selection.filename,
+                    url,
+                    hash,
+                }),
+                _ => sdist_only_packages.push(format!("{}=={}", pkg.name, pkg.version)),
+            }
+        } else if let Some(url) = selection.url {
+            let mut record = InstallRecord::new(
+                &pkg.name,
+                &pkg.version,
+                &selection.filename,
+                InstallStatus::Installed,
+            );
+            record.hash = selection.hash.clone();
+            pending.push(record);
             // Construct filename from selection
             let filename = PathBuf::from(selection.filename);
             let dest = cache_dir.join(filename);
             // Include hash when available to verify downloads
             download_items.push((url, dest, selection.hash.clone()));
-        } else if selection.from_source {
-            // sdist-only package - no wheel available
-            sdist_only_packages.push(format!("{}=={}", pkg.name, pkg.version));
+        } else {
+            results.push(InstallRecord::new(
+                &pkg.name,
+                &pkg.version,
+                &selection.filename,
+                InstallStatus::NoDownloadUrl,
+            ));
         }
     }
 
-    // Fail if there are sdist-only packages (source builds not yet supported)
+    // Source builds need the sdist itself; the index only named it.
     if !sdist_only_packages.is_empty() {
         let message = format!(
-            "The following packages have no pre-built wheel for your platform and require source builds (not yet supported): {}",
+            "The following packages have no pre-built wheel for your platform and no downloadable source distribution: {}",
             sdist_only_packages.join(", ")
         );
         collector.error_with_code(
             "E_INSTALL_SDIST_ONLY",
             message.clone(),
-            "Source builds are not yet supported; choose packages/versions with prebuilt wheels for your platform, or use a different index.",
+            "Choose packages/versions with prebuilt wheels for your platform, or use an index that serves the sdist's URL and SHA-256.",
         );
         return Err(eyre!(message));
     }
 
+    let mut built_wheels = if source_builds.is_empty() {
+        Vec::new()
+    } else {
+        let build_config = crate::project::Project::discover(&working_dir)
+            .map(|p| p.pybun_config().build)
+            .unwrap_or_default();
+        let target = SourceBuildTarget {
+            python: &python,
+            cp_tag: &active_cp_tag,
+            platform: targets
+                .tags()
+                .first()
+                .map(String::as_str)
+                .unwrap_or("unknown"),
+            sdist_dir: &cache_dir,
+            config: &build_config,
+            isolation: args.build_isolation,
+        };
+        build_source_distributions(source_builds, &target, collector).await?
+    };
+    let built_from_sdist = !built_wheels.is_empty();
+    built_wheels.extend(
+        git.into_iter()
+            .map(|dependency| (dependency.record, dependency.wheel, dependency.build)),
+    );
+    // A frozen install leaves the lockfile as it is.
+    if built_from_sdist && !frozen {
+        for (record, _, build) in &built_wheels {
+            if let Some(entry) = lock.packages.get_mut(&record.name) {
+                entry.build = Some(build.clone());
+            }
+        }
+        save_install_lock(&lock, &lock_path, shard_workspace.as_ref(), collector)?;
+    }
+
     collector.event_with(EventType::DownloadStart, |event| {
         event.message = Some(format!("Downloading {} artifacts", download_items.len()));
         event.progress = Some(50);
@@ -1665,19 +2821,22 @@ pub(crate) async fn install(
 
     let mut outcome = InstallOutcome {
         summary: format!(
-            "resolved {} packages -> {}",
+            "{} {} packages -> {}",
+            if frozen { "locked" } else { "resolved" },
             lock.packages.len(),
-            args.lock.display()
+            lock_path.display()
         ),
         packages: lock.packages.keys().cloned().collect(),
-        lockfile: args.lock.clone(),
+        lockfile: lock_path.clone(),
         verified: true,
         artifacts: verified_artifacts,
         workspace: workspace_detail,
         installed_count: 0,
+        results,
+        environment: None,
     };
 
-    if download_items.is_empty() {
+    if download_items.is_empty() && built_wheels.is_empty() {
         collector.event_with(EventType::InstallStart, |event| {
             event.message = Some("Installing 0 packages".to_string());
             event.progress = Some(85);
@@ -1685,39 +2844,221 @@ pub(crate) async fn install(
         return Ok(outcome);
     }
 
-    if !download_items.is_empty() {
+    if !download_items.is_empty() || !built_wheels.is_empty() {
         use crate::downloader::{DownloadRequest, Downloader};
         let downloader = Downloader::new();
-        let concurrency = 10; // Default concurrency
+        let concurrency = args
+            .concurrency
+            .unwrap_or(crate::downloader::DEFAULT_CONCURRENCY);
         collector.info(format!(
-            "Starting parallel download of {} artifacts...",
-            download_items.len()
+            "Starting parallel download of {} artifacts ({} at a time)...",
+            download_items.len(),
+            concurrency
         ));
 
         // Keep track of paths to install
-        let wheels_to_install: Vec<PathBuf> = download_items
+        let mut wheels_to_install: Vec<PathBuf> = download_items
             .iter()
             .map(|(_, path, _)| path.clone())
             .collect();
 
-        let download_requests: Vec<DownloadRequest> =
-            download_items.into_iter().map(Into::into).collect();
+        // Link wheels already in the content-addressed store instead of
+        // downloading them again; only hash-pinned wheels are looked up.
+        let wheel_store = match crate::wheel_cache::WheelCache::new() {
+            Ok(store) => Some(store),
+            Err(e) => {
+                collector.warning(format!("wheel store unavailable: {}", e));
+                None
+            }
+        };
+        let mut to_download = Vec::new();
+        let mut mismatches = Vec::new();
+        for (index, (url, dest, hash)) in download_items.into_iter().enumerate() {
+            let linked = match (&wheel_store, hash.as_deref()) {
+                (Some(store), Some(hash)) => store
+                    .link_to(hash, &pending[index].wheel, &dest)
+                    .unwrap_or(false),
+                _ => false,
+            };
+            if linked {
+                pending[index].cached = true;
+                // --require-hashes does not trust the store's index: re-hash
+                // the linked wheel.
+                if args.require_hashes
+                    && let Some(expected) = hash.as_deref()
+                {
+                    let actual = crate::security::sha256_file(&dest).unwrap_or_default();
+                    if actual != expected.trim_start_matches("sha256:") {
+                        let record = &mut pending[index];
+                        record.status = InstallStatus::DownloadFailed;
+                        record.actual_hash = Some(format!("sha256:{actual}"));
+                        record.error = Some(format!(
+                            "wheel store copy of {} does not match {}",
+                            record.wheel, expected
+                        ));
+                        mismatches.push(hash_mismatch_value(record, "wheel_store"));
+                    }
+                }
+            } else {
+                to_download.push((index, (url, dest, hash)));
+            }
+        }
+        if args.offline {
+            // Offline, a wheel is usable only if it already sits at its cache
+            // path (with the locked hash, when there is one).
+            let mut missing = Vec::new();
+            to_download.retain(|(index, (url, dest, hash))| {
+                let cached = dest.exists()
+                    && hash.as_deref().is_none_or(|expected| {
+                        crate::security::sha256_file(dest)
+                            .is_ok_and(|actual| actual == expected.trim_start_matches("sha256:"))
+                    });
+                let record = &mut pending[*index];
+                if cached {
+                    record.cached = true;
+                } else {
+                    missing.push(crate::offline::MissingArtifact::new(
+                        crate::offline::ArtifactKind::Wheel,
+                        format!("{}=={}", record.name, record.version),
+                        Some(url.clone()),
+                    ));
+                }
+                false
+            });
+            if !missing.is_empty() {
+                return Err(crate::offline::NetworkRequired::new(missing).into());
+            }
+        }
+        if to_download.len() < pending.len() {
+            collector.info(format!(
+                "Reused {} wheels from the wheel store",
+                pending.len() - to_download.len()
+            ));
+        }
+
+        let (indices, download_requests): (Vec<usize>, Vec<DownloadRequest>) = to_download
+            .into_iter()
+            .map(|(index, item)| (index, item.into()))
+            .unzip();
+        let labels: Vec<String> = indices
+            .iter()
+            .map(|&index| format!("{} {}", pending[index].name, pending[index].version))
+            .collect();
+        let mut completed = 0;
+        let mut transferred = 0u64;
+        let started = std::time::Instant::now();
         let results = downloader
-            .download_parallel(download_requests, concurrency)
+            .download_parallel_observed(download_requests, concurrency, |item, result| {
+                completed += 1;
+                let verb = if result.is_ok() {
+                    "Downloaded"
+                } else {
+                    "Failed to download"
+                };
+                let mut data = json!({
+                    "artifact": labels[item],
+                    "completed": completed,
+                    "total": labels.len(),
+                });
+                if let Ok(downloaded) = result {
+                    transferred += downloaded.stats.bytes;
+                    data["throughput"] = downloaded.stats.to_json();
+                }
+                collector.event_with(EventType::DownloadProgress, |event| {
+                    event.message = Some(format!(
+                        "{verb} {} ({completed}/{})",
+                        labels[item],
+                        labels.len()
+                    ));
+                    event.progress = Some((50 + 20 * completed / labels.len()) as u8);
+                    event.data = Some(data);
+                });
+            })
             .await;
+        if transferred > 0 {
+            let secs = started.elapsed().as_secs_f64().max(0.001);
+            collector.info(format!(
+                "Downloaded {:.1} MiB in {:.1}s ({:.1} MiB/s)",
+                transferred as f64 / 1_048_576.0,
+                secs,
+                transferred as f64 / 1_048_576.0 / secs
+            ));
+        }
 
-        // Check for failures
+        // Check for failures (results are in request order)
         let mut failures = 0;
-        for res in results {
-            if let Err(e) = res {
-                eprintln!("warning: download failed: {}", e);
-                failures += 1;
+        for (index, res) in indices.into_iter().zip(results) {
+            let record = &mut pending[index];
+            match res {
+                Ok(_) => {
+                    if let Some(store) = &wheel_store
+                        && let Err(e) = store.insert(
+                            &wheels_to_install[index],
+                            &record.wheel,
+                            record.hash.as_deref(),
+                        )
+                    {
+                        collector.warning(format!(
+                            "failed to add {} to the wheel store: {}",
+                            record.wheel, e
+                        ));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("warning: download failed: {}", e);
+                    record.status = InstallStatus::DownloadFailed;
+                    record.error = Some(e.to_string());
+                    if let Some(actual) = checksum_mismatch_actual(&e) {
+                        record.actual_hash = Some(format!("sha256:{actual}"));
+                        mismatches.push(hash_mismatch_value(record, "download"));
+                    }
+                    failures += 1;
+                }
             }
         }
+        failures += mismatches
+            .iter()
+            .filter(|m| m["source"] == "wheel_store")
+            .count();
+
+        if !mismatches.is_empty() {
+            let message = format!(
+                "{} artifact(s) do not match their recorded SHA-256",
+                mismatches.len()
+            );
+            collector.diagnostic(
+                Diagnostic::error(message)
+                    .with_code("E_INSTALL_HASH_MISMATCH")
+                    .with_suggestion(
+                        "The artifact changed since it was locked, or the download was tampered with. Check the index, then re-lock (`pybun lock`) only if the new artifact is trusted; nothing was installed.",
+                    )
+                    .with_context(json!({ "artifacts": mismatches })),
+            );
+        }
 
         if failures > 0 {
-            collector.warning(format!("{} downloads failed", failures));
-            return Err(eyre!("failed to download some artifacts"));
+            let failed: Vec<String> = pending
+                .iter()
+                .filter(|r| r.status == InstallStatus::DownloadFailed)
+                .map(|r| format!("{}=={}", r.name, r.version))
+                .collect();
+            let message = format!(
+                "failed to download {} of {} artifacts: {}",
+                failures,
+                pending.len(),
+                failed.join(", ")
+            );
+            collector.diagnostic(
+                Diagnostic::error(message.clone())
+                    .with_code("E_INSTALL_DOWNLOAD_FAILED")
+                    .with_suggestion(
+                        "Check network access to the index (`pybun doctor`), then re-run `pybun install`; nothing was installed.",
+                    )
+                    .with_context(json!({
+                        "results": outcome.results.iter().chain(&pending).collect::<Vec<_>>(),
+                    })),
+            );
+            return Err(eyre!(message));
         }
 
         collector.event_with(EventType::DownloadComplete, |event| {
@@ -1725,77 +3066,84 @@ pub(crate) async fn install(
             event.progress = Some(70);
         });
 
+        for (record, wheel, _) in built_wheels {
+            pending.push(record);
+            wheels_to_install.push(wheel);
+        }
+
         // Install wheels. Re-resolve the target environment now that we know there is
         // something to install; this is where venv creation / the system-Python guard
         // actually mutates the filesystem (deferred from the cp-tag detection above so
         // a resolve-only or failed install has no such side effect).
-        let mut env = crate::env::find_python_env(&working_dir)?;
+        let (target, environment) = prepare_install_env(args, collector, &working_dir)?;
+        let site_packages = &target.site_packages;
+        outcome.environment = Some(environment);
 
-        if matches!(env.source, crate::env::EnvSource::System) {
-            if args.system {
-                if let Some(marker) = crate::env::externally_managed_marker(&env.python_path) {
-                    let message = format!(
-                        "refusing to install into externally-managed system Python (marker: {})",
-                        marker.display()
-                    );
-                    collector.error_with_code(
-                        "E_INSTALL_EXTERNALLY_MANAGED",
-                        message.clone(),
-                        "This interpreter is marked externally-managed (PEP 668). Create a virtual environment (e.g. `python3 -m venv .venv`) and re-run, or install with a non-managed interpreter.",
-                    );
-                    return Err(eyre!(message));
-                }
+        collector.event_with(EventType::InstallStart, |event| {
+            event.message = Some(format!("Installing {} packages", wheels_to_install.len()));
+            event.progress = Some(85);
+        });
 
-                let warning =
-                    "warning: PyBun is installing into system Python (--system was specified).";
-                eprintln!("{}", warning);
-                collector.warning(warning.to_string());
+        let mut install_error = None;
+        for (mut record, wheel) in pending.into_iter().zip(wheels_to_install) {
+            if let Some(dist_info) =
+                crate::installer::installed_dist_info(site_packages, &record.name, &record.version)
+            {
+                record.status = InstallStatus::AlreadyInstalled;
+                record.dist_info = Some(dist_info);
+            } else if install_error.is_some() {
+                // An earlier wheel failed; leave the rest untouched.
+                record.status = InstallStatus::InstallFailed;
+                record.error = Some("skipped after an earlier install failure".to_string());
             } else {
-                collector.info(
-                    "No virtual environment found; creating project-local environment at .pybun/venv"
-                        .to_string(),
-                );
-                env = crate::env::create_project_venv(&working_dir)?;
+                // An upgrade or downgrade replaces the installed version
+                // instead of unpacking over it.
+                let installed =
+                    crate::installer::remove_other_versions(&target, &record.name, &record.version)
+                        .and_then(|replaced| {
+                            record.replaced = replaced;
+                            crate::installer::install_wheel_into(&wheel, &target)
+                        });
+                match installed {
+                    Ok(installed) => {
+                        record.dist_info = Some(installed.dist_info);
+                        outcome.installed_count += 1;
+                    }
+                    Err(e) => {
+                        let message = format!("failed to install wheel {}: {}", wheel.display(), e);
+                        record.status = InstallStatus::InstallFailed;
+                        record.error = Some(e.to_string());
+                        install_error = Some(message);
+                    }
+                }
             }
+            outcome.results.push(record);
         }
 
-        collector.info(format!(
-            "Installing packages into {}",
-            env.python_path.display()
-        ));
-
-        // Determine site-packages path
-        let output = std::process::Command::new(&env.python_path)
-            .args([
-                "-c",
-                "import sysconfig; print(sysconfig.get_paths()['purelib'], end='')",
-            ])
-            .output()
-            .map_err(|e| eyre!("failed to determine site-packages path: {}", e))?;
-
-        if !output.status.success() {
-            return Err(eyre!(
-                "failed to determine site-packages path (python execution failed)"
-            ));
+        if let Some(message) = install_error {
+            collector.diagnostic(
+                Diagnostic::error(message.clone())
+                    .with_code("E_INSTALL_WHEEL_FAILED")
+                    .with_suggestion(
+                        "Inspect the wheel named above (it may be corrupt or for another platform), then re-run `pybun install`.",
+                    )
+                    .with_context(json!({ "results": outcome.results })),
+            );
+            return Err(eyre!(message));
         }
-        let site_packages_str = String::from_utf8(output.stdout)
-            .map_err(|e| eyre!("invalid utf8 in site-packages path: {}", e))?;
-        let site_packages = PathBuf::from(site_packages_str);
-
-        collector.info(format!("Target site-packages: {}", site_packages.display()));
-
-        collector.event_with(EventType::InstallStart, |event| {
-            event.message = Some(format!("Installing {} packages", wheels_to_install.len()));
-            event.progress = Some(85);
-        });
 
-        for wheel in wheels_to_install {
-            if wheel.exists() {
-                crate::installer::install_wheel(&wheel, &site_packages)
-                    .map_err(|e| eyre!("failed to install wheel {}: {}", wheel.display(), e))?;
-                outcome.installed_count += 1;
-            }
-        }
+        let already = outcome
+            .results
+            .iter()
+            .filter(|r| r.status == InstallStatus::AlreadyInstalled)
+            .count();
+        outcome.summary = format!(
+            "installed {} packages ({} already present) into {} -> {}",
+            outcome.installed_count,
+            already,
+            site_packages.display(),
+            lock_path.display()
+        );
 
         collector.event_with(EventType::InstallComplete, |event| {
             event.message = Some("Installation complete".to_string());
@@ -1806,365 +3154,2686 @@ pub(crate) async fn install(
     Ok(outcome)
 }
 
-#[derive(Debug)]
-pub(crate) struct InstallOutcome {
-    pub(crate) summary: String,
-    pub(crate) packages: Vec<String>,
-    pub(crate) lockfile: PathBuf,
-    pub(crate) verified: bool,
-    pub(crate) artifacts: Vec<Value>,
-    /// Workspace selection details (scope, selected members, group), present
-    /// only when dependencies were gathered from a workspace-aware source.
-    pub(crate) workspace: Option<Value>,
-    /// Number of wheels actually downloaded and installed into a site-packages
-    /// directory during this call. This is distinct from `packages.len()`,
-    /// which counts *resolved* packages regardless of whether any wheel was
-    /// actually fetched and installed (e.g. when no download URL is available
-    /// from the index, or when there was nothing new to install). Callers
-    /// (including the MCP `pybun_install` tool) must not claim packages were
-    /// "installed" unless this count is greater than zero.
-    pub(crate) installed_count: usize,
+/// The environment `pybun install` installs into, creating the project-local
+/// `.pybun/venv` when there is none, and its site-packages directory.
+/// Returns the site-packages path and the `environment` JSON detail.
+/// Record that a virtual environment is about to be created at `path`, so
+/// the progress UI can time the step.
+fn record_env_create(collector: &mut EventCollector, path: &Path) {
+    collector.event_with(EventType::EnvCreate, |event| {
+        event.message = Some(format!(
+            "Creating virtual environment at {}",
+            path.display()
+        ));
+        event.data = Some(json!({ "path": path.display().to_string() }));
+    });
 }
 
-#[derive(Debug)]
-struct LockOutcome {
-    summary: String,
-    lockfile: PathBuf,
-    packages: Vec<String>,
-    verified: bool,
-    artifacts: Vec<Value>,
-}
+fn prepare_install_env(
+    args: &crate::cli::InstallArgs,
+    collector: &mut EventCollector,
+    working_dir: &Path,
+) -> Result<(crate::installer::InstallTarget, Value)> {
+    let mut env = crate::env::find_python_env(working_dir)?;
+
+    // A managed runtime picked by a `.python-version` pin is shared by every
+    // project pinned to it, so it is never installed into directly.
+    if matches!(
+        env.source,
+        crate::env::EnvSource::System | crate::env::EnvSource::ManagedRuntime(_)
+    ) {
+        if args.system && matches!(env.source, crate::env::EnvSource::System) {
+            if let Some(marker) = crate::env::externally_managed_marker(&env.python_path) {
+                let message = format!(
+                    "refusing to install into externally-managed system Python (marker: {})",
+                    marker.display()
+                );
+                collector.error_with_code(
+                    "E_INSTALL_EXTERNALLY_MANAGED",
+                    message.clone(),
+                    "This interpreter is marked externally-managed (PEP 668). Create a virtual environment (e.g. `python3 -m venv .venv`) and re-run, or install with a non-managed interpreter.",
+                );
+                return Err(eyre!(message));
+            }
 
-fn is_missing_sha256(hash: Option<&str>) -> bool {
-    match hash {
-        Some(value) => crate::security::is_placeholder_hash(value),
-        None => true,
+            let warning =
+                "warning: PyBun is installing into system Python (--system was specified).";
+            eprintln!("{}", warning);
+            collector.warning(warning.to_string());
+        } else {
+            collector.info(
+                "No virtual environment found; creating project-local environment at .pybun/venv"
+                    .to_string(),
+            );
+            let seed = if args.no_seed {
+                crate::seed::SeedPlan::disabled()
+            } else {
+                crate::project::Project::discover(working_dir)
+                    .map(|p| crate::seed::SeedPlan::from_config(&p.pybun_config().seed))
+                    .unwrap_or_default()
+            };
+            record_env_create(collector, &working_dir.join(".pybun").join("venv"));
+            env = crate::env::create_project_venv(working_dir, &env.python_path, &seed)?;
+            if let Some(manifest) =
+                crate::seed::EnvManifest::load(&working_dir.join(".pybun").join("venv"))
+            {
+                let seeded: Vec<String> = manifest
+                    .seeded
+                    .iter()
+                    .map(|p| format!("{}=={}", p.name, p.version))
+                    .collect();
+                collector.info(if seeded.is_empty() {
+                    "Seeded no packages into .pybun/venv".to_string()
+                } else {
+                    format!("Seeded .pybun/venv with {}", seeded.join(", "))
+                });
+            }
+        }
     }
-}
 
-fn registry_source_for_index(index_url: &str) -> PackageSource {
-    PackageSource::Registry {
-        index: "pypi".into(),
-        url: index_url.to_string(),
-    }
-}
+    collector.info(format!(
+        "Installing packages into {}",
+        env.python_path.display()
+    ));
 
-fn verification_artifact_value(
-    pkg: &crate::resolver::ResolvedPackage,
-    selection: &crate::resolver::ArtifactSelection,
-    index_url: &str,
-    verified_hash: &str,
-) -> Value {
-    json!({
-        "package": pkg.name,
-        "version": pkg.version,
-        "sha256": verified_hash,
-        "index_url": index_url,
-        "artifact_url": selection.url,
-        "platform_tag": selection.matched_platform,
-        "filename": selection.filename,
-        "from_source": selection.from_source,
-    })
-}
+    // Determine the install scheme: site-packages, scripts and data prefix
+    let output = std::process::Command::new(&env.python_path)
+        .args([
+            "-c",
+            "import sysconfig; p = sysconfig.get_paths(); print(p['purelib'], p['scripts'], p['data'], sep='\\n', end='')",
+        ])
+        .output()
+        .map_err(|e| eyre!("failed to determine site-packages path: {}", e))?;
 
-fn missing_hash_diagnostic(
-    pkg: &crate::resolver::ResolvedPackage,
-    selection: &crate::resolver::ArtifactSelection,
-    index_url: &str,
-) -> Diagnostic {
-    Diagnostic {
-        level: crate::schema::DiagnosticLevel::Error,
-        code: Some("E_VERIFY_MISSING_HASH".to_string()),
-        message: format!(
-            "selected artifact for {} {} ({}) is missing sha256 verification metadata",
-            pkg.name, pkg.version, selection.filename
-        ),
-        file: None,
-        line: None,
-        suggestion: Some(
-            "use an index that provides sha256 digests, then rerun install/lock/upgrade"
-                .to_string(),
-        ),
-        context: Some(json!({
-            "package": pkg.name,
-            "version": pkg.version,
-            "filename": selection.filename,
-            "artifact_url": selection.url,
-            "index_url": index_url,
-            "platform_tag": selection.matched_platform,
-            "from_source": selection.from_source,
-        })),
-        exception_type: None,
-        location: None,
-        next_action: None,
-        fix_candidates: None,
+    if !output.status.success() {
+        return Err(eyre!(
+            "failed to determine site-packages path (python execution failed)"
+        ));
     }
+    let paths = String::from_utf8(output.stdout)
+        .map_err(|e| eyre!("invalid utf8 in site-packages path: {}", e))?;
+    let mut paths = paths.lines().map(PathBuf::from);
+    let (Some(site_packages), Some(scripts), Some(prefix)) =
+        (paths.next(), paths.next(), paths.next())
+    else {
+        return Err(eyre!(
+            "failed to determine site-packages path (unexpected sysconfig output)"
+        ));
+    };
+    let target = crate::installer::InstallTarget {
+        site_packages,
+        scripts,
+        prefix,
+        python: env.python_path.clone(),
+    };
+
+    collector.info(format!(
+        "Target site-packages: {}",
+        target.site_packages.display()
+    ));
+    let environment = json!({
+        "python": env.python_path.display().to_string(),
+        "source": env.source.to_string(),
+        "site_packages": target.site_packages.display().to_string(),
+    });
+    Ok((target, environment))
 }
 
-fn ensure_selection_is_verifiable(
-    pkg: &crate::resolver::ResolvedPackage,
-    selection: &crate::resolver::ArtifactSelection,
+/// `pybun install --frozen`: install the artifacts the lockfile pins for
+/// this interpreter and platform, without resolving.
+async fn install_frozen(
+    args: &crate::cli::InstallArgs,
     collector: &mut EventCollector,
-    index_url: &str,
-) -> Result<(String, Value)> {
-    if is_missing_sha256(selection.hash.as_deref()) {
-        let diagnostic = missing_hash_diagnostic(pkg, selection, index_url);
-        let message = diagnostic.message.clone();
-        collector.diagnostic(diagnostic);
-        return Err(eyre!(message));
+    lock_path: &Path,
+    workspace_detail: Option<Value>,
+) -> Result<InstallOutcome> {
+    let lock = load_install_lock(lock_path, collector)?;
+    crate::suspicious::restore(&lock);
+
+    let working_dir = std::env::current_dir()?;
+    let InstallPython { probe, cp_tag, .. } = detect_install_python(&working_dir)?;
+    let targets = wheel_targets(&working_dir, &probe.python_path, collector)?;
+    let python = lock_python_version(&cp_tag);
+    let platform = targets
+        .tags()
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "unknown".to_string());
+    if !lock.python_versions.is_empty() && !lock.python_versions.contains(&python) {
+        collector.warning(format!(
+            "{} was locked for Python {}, not {}",
+            lock_path.display(),
+            lock.python_versions.join(", "),
+            python
+        ));
     }
 
-    let Some(verified_hash) = selection.hash.clone() else {
+    let mut resolution = resolution_from_lock(&lock);
+    // Packages only other dependency groups need are skipped.
+    let selected: Vec<&str> = args.group.as_deref().into_iter().collect();
+    let skipped = resolution.packages.len();
+    resolution
+        .packages
+        .retain(|name, _| Lockfile::in_groups(&lock.packages[name], &selected));
+    let skipped = skipped - resolution.packages.len();
+    if skipped > 0 {
+        collector.info(format!(
+            "Skipping {skipped} locked package(s) of other dependency groups"
+        ));
+    }
+    // Git dependencies are built from their pinned commits.
+    let mut git_builds = Vec::new();
+    resolution.packages.retain(|name, _| {
+        let Some((source, commit)) =
+            crate::git_source::GitSource::from_locked(&lock.packages[name].source)
+        else {
+            return true;
+        };
+        git_builds.push(PendingGitBuild {
+            name: name.clone(),
+            source,
+            commit: Some(commit),
+        });
+        false
+    });
+    let mut missing = Vec::new();
+    let mut verified_artifacts = Vec::new();
+    for pkg in resolution.packages.values() {
+        let selection =
+            select_artifact_for_platform_with_cp(pkg, targets.tags_for(&pkg.name), &cp_tag);
+        if selection.url.is_none() {
+            missing.push(format!("{}=={}", pkg.name, pkg.version));
+            continue;
+        }
+        warn_on_arch_mismatch(pkg, &selection, &targets, collector);
+        let index_url = lock.packages[&pkg.name]
+            .source
+            .url()
+            .unwrap_or_default()
+            .to_string();
+        let (_, mut artifact) =
+            ensure_selection_is_verifiable(pkg, &selection, collector, &index_url)?;
+        artifact["hash_source"] = json!("lockfile");
+        verified_artifacts.push(artifact);
+    }
+    if !missing.is_empty() {
         let message = format!(
-            "missing SHA-256 hash for {} {} after verification",
-            pkg.name, pkg.version
+            "{} has no artifact for {} on Python {} ({})",
+            lock_path.display(),
+            missing.join(", "),
+            python,
+            platform
+        );
+        collector.diagnostic(
+            Diagnostic::error(message.clone())
+                .with_code("E_INSTALL_LOCK_TARGET_MISSING")
+                .with_suggestion(format!(
+                    "Re-lock with this machine among the targets: `pybun lock --python {python} --platform {platform}`, plus a `--python`/`--platform` for each existing target."
+                ))
+                .with_context(json!({
+                    "python": python,
+                    "platform": platform,
+                    "locked": {
+                        "python": lock.python_versions,
+                        "platforms": lock.platforms,
+                    },
+                    "packages": missing,
+                })),
         );
+        return Err(eyre!(message));
+    }
+
+    let git = build_git_dependencies(
+        git_builds,
+        &GitBuildTarget {
+            python: &probe.python_path,
+            cp_tag: &cp_tag,
+            platform: &platform,
+            offline: args.offline,
+        },
+        collector,
+    )?;
+
+    install_locked(
+        args,
+        collector,
+        LockedInstall {
+            lock,
+            lock_path: lock_path.to_path_buf(),
+            frozen: true,
+            shard_workspace: None,
+            resolution,
+            git,
+            verified_artifacts,
+            workspace: workspace_detail,
+            targets,
+            cp_tag,
+            python: probe.python_path,
+            working_dir,
+        },
+    )
+    .await
+}
+
+/// Load the lockfile a frozen install reads, reporting
+/// `E_INSTALL_LOCK_MISSING` when it cannot be read.
+fn load_install_lock(lock_path: &Path, collector: &mut EventCollector) -> Result<Lockfile> {
+    Lockfile::load_from_path(lock_path).map_err(|e| {
+        let message = format!("cannot read lockfile {}: {}", lock_path.display(), e);
         collector.error_with_code(
-            "E_VERIFY_MISSING_HASH",
+            "E_INSTALL_LOCK_MISSING",
             message.clone(),
-            "Choose a package artifact that includes a SHA-256 digest or use an index that exposes artifact hashes.",
+            "Run `pybun lock` (with `--platform`/`--python` for every machine that installs from it) and commit the lockfile.",
         );
-        return Err(eyre!(message));
-    };
-    Ok((
-        verified_hash.clone(),
-        verification_artifact_value(pkg, selection, index_url, &verified_hash),
-    ))
+        eyre!(message)
+    })
 }
 
-fn emit_lockfile_verification_drift(lockfile: &Lockfile, collector: &mut EventCollector) {
-    let drifted_packages: Vec<Value> = lockfile
+/// The packages `lock` pins, in the shape the resolver returns them, so a
+/// frozen install selects among the locked artifacts like a fresh one.
+fn resolution_from_lock(lock: &Lockfile) -> Resolution {
+    let packages = lock
         .packages
         .values()
-        .filter(|pkg| is_missing_sha256(Some(&pkg.hash)))
         .map(|pkg| {
-            json!({
-                "package": pkg.name,
-                "version": pkg.version,
-                "filename": pkg.wheel,
-                "hash": pkg.hash,
-            })
+            // Lockfiles from before artifacts were recorded only name the
+            // first target's file, without a download URL.
+            let locked = if pkg.artifacts.is_empty() {
+                vec![Artifact {
+                    filename: pkg.wheel.clone(),
+                    url: None,
+                    hash: pkg.hash.clone(),
+                    platforms: Vec::new(),
+                }]
+            } else {
+                pkg.artifacts.clone()
+            };
+            let mut artifacts = crate::resolver::PackageArtifacts::default();
+            for artifact in locked {
+                if artifact.is_wheel() {
+                    let (python_tag, abi_tag) = parse_wheel_tags(&artifact.filename);
+                    artifacts.wheels.push(crate::resolver::Wheel {
+                        file: artifact.filename,
+                        url: artifact.url,
+                        hash: Some(artifact.hash),
+                        platforms: artifact.platforms,
+                        python_tag,
+                        abi_tag,
+                    });
+                } else {
+                    artifacts.sdist = Some(artifact.filename);
+                    artifacts.sdist_url = artifact.url;
+                    artifacts.sdist_hash = Some(artifact.hash);
+                }
+            }
+            let resolved = crate::resolver::ResolvedPackage {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                dependencies: pkg
+                    .dependencies
+                    .iter()
+                    .map(|d| {
+                        d.parse::<Requirement>()
+                            .unwrap_or_else(|_| Requirement::any(d.trim()))
+                    })
+                    .collect(),
+                source: None,
+                artifacts,
+                requires_python: None,
+            };
+            (pkg.name.clone(), resolved)
         })
         .collect();
-
-    if drifted_packages.is_empty() {
-        return;
+    Resolution {
+        packages,
+        prerelease_fallbacks: Vec::new(),
     }
+}
 
-    collector.diagnostic(Diagnostic {
-        level: crate::schema::DiagnosticLevel::Warning,
-        code: Some("W_LOCK_PLACEHOLDER_HASH".to_string()),
-        message: format!(
-            "existing lockfile contains {} package(s) without verified hashes",
-            drifted_packages.len()
-        ),
-        file: None,
-        line: None,
-        suggestion: Some(
-            "rerun 'pybun install' or 'pybun lock' with an index that provides sha256 digests"
-                .to_string(),
-        ),
-        context: Some(json!({ "packages": drifted_packages })),
-        exception_type: None,
-        location: None,
-        next_action: None,
-        fix_candidates: Some(crate::self_heal::fix_candidates_for_lock_drift()),
-    });
+/// Lockfile record of the artifact selected for `pkg`.
+fn locked_artifact(
+    pkg: &crate::resolver::ResolvedPackage,
+    selection: &crate::resolver::ArtifactSelection,
+    hash: String,
+) -> Artifact {
+    let platforms = if selection.from_source {
+        Vec::new()
+    } else {
+        pkg.artifacts
+            .wheels
+            .iter()
+            .find(|wheel| wheel.file == selection.filename)
+            .map(|wheel| wheel.platforms.clone())
+            .unwrap_or_default()
+    };
+    Artifact {
+        filename: selection.filename.clone(),
+        url: selection.url.clone(),
+        hash,
+        platforms,
+    }
 }
 
-async fn lock_dependencies(args: &LockArgs, collector: &mut EventCollector) -> Result<LockOutcome> {
-    let (dep_specs, lock_path): (Vec<String>, PathBuf) =
-        if let Some(script_path) = args.script.as_ref() {
-            if !script_path.exists() {
-                return Err(eyre!("script not found: {}", script_path.display()));
-            }
+/// Interpreter `pybun install` installs into, as far as wheel selection and
+/// `requires-python` filtering are concerned.
+struct InstallPython {
+    probe: crate::env::PythonEnv,
+    /// Version for `requires-python` filtering (`None` disables it).
+    version: Option<String>,
+    cp_tag: String,
+}
 
-            let pep723_metadata = match pep723::parse_script_metadata(script_path) {
-                Ok(metadata) => metadata,
-                Err(e) => {
-                    return Err(eyre!("failed to parse PEP 723 metadata: {}", e));
-                }
+fn detect_install_python(working_dir: &Path) -> Result<InstallPython> {
+    // Detect the CPython tag of the actual install target (PYBUN_ENV / PYBUN_PYTHON /
+    // project venv / system Python) *before* selecting wheels, so artifact selection
+    // matches the Python interpreter packages will actually be installed into.
+    // Selecting wheels against whatever `python3`/`python` happens to resolve on PATH
+    // (the previous behavior) can silently pick wheels for the wrong CPython ABI
+    // (Issue #291). This is read-only detection only — creating a project-local venv
+    // (and the associated system-Python safe-install-target guard) is deferred to the
+    // later "Install wheels" step below, so a resolve-only or failed install doesn't
+    // have the side effect of mutating the filesystem.
+    //
+    // Detection happens before resolution because the same interpreter version also
+    // drives `requires-python` candidate filtering (Issue #342). The interpreter is
+    // only spawned when no environment override makes it unnecessary.
+    let probe = crate::env::find_python_env(working_dir)?;
+    let python_version_override = python_version_env_override();
+    // PYBUN_FORCE_CP_TAG lets tests (and users) pin the CPython tag deterministically.
+    // Note it no longer bypasses interpreter detection on its own: the detected version
+    // is also needed for `requires-python` filtering, so detection is only skipped when
+    // PYBUN_PYPI_PYTHON_VERSION covers that too.
+    let forced_cp_tag = std::env::var("PYBUN_FORCE_CP_TAG")
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    let detected_python_version = if python_version_override.is_none() || forced_cp_tag.is_none() {
+        get_python_version(&probe.python_path).ok()
+    } else {
+        None
+    };
+    let cp_tag = forced_cp_tag
+        .or_else(|| {
+            detected_python_version
+                .as_deref()
+                .and_then(|v| get_interpreter_tag(&probe.python_path, v))
+        })
+        .unwrap_or_else(|| "cp311".to_string());
+    Ok(InstallPython {
+        probe,
+        version: python_version_override.or(detected_python_version),
+        cp_tag,
+    })
+}
+
+/// Wheel platform tags for the architecture `python` runs as, plus the
+/// project's `[tool.pybun.arch]` overrides.
+fn wheel_targets(
+    working_dir: &Path,
+    python: &Path,
+    collector: &mut EventCollector,
+) -> Result<crate::arch::WheelTargets> {
+    let interpreter = crate::arch::detect_interpreter_arch(python);
+    let overrides = crate::project::Project::discover(working_dir)
+        .map(|p| p.pybun_config().arch)
+        .unwrap_or_default();
+    let targets = match crate::arch::WheelTargets::new(interpreter, &overrides) {
+        Ok(targets) => targets,
+        Err(e) => {
+            let message = e.to_string();
+            collector.error_with_code(
+                "E_ARCH_OVERRIDE_INVALID",
+                message.clone(),
+                "Use x86_64, arm64 or x86 in [tool.pybun.arch] (universal2 only on macOS).",
+            );
+            return Err(eyre!(message));
+        }
+    };
+    // An x86_64 interpreter under Rosetta (or any emulated interpreter) needs
+    // wheels for its own architecture, not PyBun's.
+    if let (Some(interpreter), Some(host)) = (interpreter, crate::arch::Arch::host())
+        && interpreter != host
+    {
+        collector.info(format!(
+            "{} runs as {interpreter} (pybun is {host}); selecting {interpreter} wheels",
+            python.display()
+        ));
+    }
+    Ok(targets)
+}
+
+/// Emit a `W_ARCH_MISMATCH` warning when the wheel selected for `pkg` is
+/// built for an architecture the interpreter cannot load, which only
+/// happens when `[tool.pybun.arch]` forces it.
+fn warn_on_arch_mismatch(
+    pkg: &crate::resolver::ResolvedPackage,
+    selection: &crate::resolver::ArtifactSelection,
+    targets: &crate::arch::WheelTargets,
+    collector: &mut EventCollector,
+) {
+    let Some(wheel_arch) = selection
+        .matched_platform
+        .as_deref()
+        .and_then(|tag| targets.mismatch(tag))
+    else {
+        return;
+    };
+    let interpreter = targets
+        .interpreter()
+        .or_else(crate::arch::Arch::host)
+        .map_or("unknown", crate::arch::Arch::as_str);
+    let message = format!(
+        "{} {}: {} is built for {wheel_arch}, but the interpreter runs as {interpreter} and cannot load it",
+        pkg.name, pkg.version, selection.filename
+    );
+    eprintln!("warning: {}", message);
+    let suggestion = match targets.override_for(&pkg.name) {
+        Some(forced) => format!(
+            "[tool.pybun.arch] forces {} to {forced}; remove the override, use universal2 on macOS, or install with an interpreter running as {wheel_arch}.",
+            pkg.name
+        ),
+        None => format!(
+            "Install with an interpreter running as {wheel_arch}, or set {} to match the interpreter.",
+            crate::arch::PYTHON_ARCH_ENV
+        ),
+    };
+    collector.diagnostic(
+        Diagnostic::warning(message)
+            .with_code("W_ARCH_MISMATCH")
+            .with_suggestion(suggestion)
+            .with_context(json!({
+                "package": pkg.name,
+                "wheel": selection.filename,
+                "wheel_arch": wheel_arch.as_str(),
+                "interpreter_arch": interpreter,
+                "override": targets.override_for(&pkg.name).map(crate::arch::Arch::as_str),
+            })),
+    );
+}
+
+/// A package with no compatible wheel whose sdist `pybun install` builds.
+struct PendingSourceBuild {
+    name: String,
+    version: String,
+    sdist: String,
+    url: String,
+    hash: String,
+}
+
+/// Interpreter, platform and isolation settings for source builds.
+struct SourceBuildTarget<'a> {
+    python: &'a Path,
+    cp_tag: &'a str,
+    platform: &'a str,
+    /// Where downloaded sdists are kept (the install artifacts directory).
+    sdist_dir: &'a Path,
+    config: &'a crate::build_isolation::BuildConfig,
+    isolation: Option<crate::build_isolation::BuildIsolation>,
+}
+
+/// Download and build each sdist, reusing wheels already built from the
+/// same sdist for this target. Returns each package's install record,
+/// wheel path and lockfile build record.
+async fn build_source_distributions(
+    builds: Vec<PendingSourceBuild>,
+    target: &SourceBuildTarget<'_>,
+    collector: &mut EventCollector,
+) -> Result<Vec<(InstallRecord, PathBuf, SourceBuild)>> {
+    let fail = |collector: &mut EventCollector,
+                build: &PendingSourceBuild,
+                error: &(dyn std::error::Error + 'static)| {
+        let message = format!(
+            "failed to build {}=={} from source: {}",
+            build.name, build.version, error
+        );
+        collector.diagnostic(
+            Diagnostic::error(message.clone())
+                .with_code("E_INSTALL_SOURCE_BUILD_FAILED")
+                .with_suggestion(
+                    "Install the package's native build dependencies (compiler, headers), choose a version with a prebuilt wheel, or retry with `--build-isolation container`.",
+                )
+                .with_tool_failure(error),
+        );
+        eyre!(message)
+    };
+    let cache = crate::sdist_build::SdistBuildCache::new()
+        .map_err(|e| eyre!("failed to open the wheel build cache: {}", e))?;
+    let downloader = crate::downloader::Downloader::new();
+
+    let mut built = Vec::new();
+    for build in builds {
+        let (wheel, cached) = match cache.get(&build.hash, target.cp_tag, target.platform) {
+            Some(wheel) => (wheel, true),
+            None => {
+                let sdist = target.sdist_dir.join(&build.sdist);
+                downloader
+                    .download_file(&build.url, &sdist, Some(&build.hash))
+                    .await
+                    .map_err(|e| fail(collector, &build, &e))?;
+                let isolation = target.config.isolation_for(
+                    &build.name,
+                    target.isolation,
+                    crate::build_isolation::flagged_for_container(&build.name),
+                );
+                collector.info(format!(
+                    "Building {} {} from source ({} isolation)",
+                    build.name,
+                    build.version,
+                    isolation.as_str()
+                ));
+                let work_dir = tempfile::tempdir()?;
+                let wheel = build_sdist(&sdist, isolation, target, work_dir.path())
+                    .and_then(|wheel| cache.put(&build.hash, target.cp_tag, target.platform, wheel))
+                    .map_err(|e| fail(collector, &build, &e))?;
+                (wheel, false)
+            }
+        };
+        let mut record = InstallRecord::new(
+            &build.name,
+            &build.version,
+            &wheel.filename,
+            InstallStatus::Installed,
+        );
+        record.hash = Some(format!("sha256:{}", wheel.sha256));
+        record.cached = cached;
+        record.built = true;
+        let source_build = wheel.source_build(target.cp_tag, target.platform);
+        built.push((record, wheel.path, source_build));
+    }
+    Ok(built)
+}
+
+/// A `git+` requirement to build, at `commit` when the lockfile pins one.
+struct PendingGitBuild {
+    name: String,
+    source: crate::git_source::GitSource,
+    commit: Option<String>,
+}
+
+/// A git dependency built at the commit its ref resolved to.
+struct GitDependency {
+    name: String,
+    version: String,
+    source: crate::git_source::GitSource,
+    commit: String,
+    /// Requirements of the built wheel that apply to this environment.
+    requires: Vec<Requirement>,
+    record: InstallRecord,
+    wheel: PathBuf,
+    build: SourceBuild,
+}
+
+impl GitDependency {
+    /// Lockfile entry pinning the commit. `wheel` and `hash` name the wheel
+    /// built from it.
+    fn locked_package(&self) -> Package {
+        Package {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            source: self.source.locked(&self.commit),
+            wheel: self.build.wheel.clone(),
+            hash: self.build.wheel_hash.clone(),
+            dependencies: self.requires.iter().map(ToString::to_string).collect(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: Some(self.build.clone()),
+            artifacts: Vec::new(),
+        }
+    }
+}
+
+/// Separate `git+` direct references from the requirements the index
+/// resolves. Other direct references are rejected.
+fn split_git_requirements(
+    requirements: Vec<Requirement>,
+    collector: &mut EventCollector,
+) -> Result<(Vec<PendingGitBuild>, Vec<Requirement>)> {
+    let mut git = Vec::new();
+    let mut index = Vec::new();
+    for req in requirements {
+        let Some(url) = &req.url else {
+            index.push(req);
+            continue;
+        };
+        let Some(source) = crate::git_source::GitSource::parse(url) else {
+            let message = format!("unsupported direct reference for {}: {}", req.name, url);
+            collector.error_with_code(
+                "E_INSTALL_DIRECT_URL_UNSUPPORTED",
+                message.clone(),
+                "Only git sources (`name @ git+https://host/repo@ref`) are supported as direct references; depend on a released version otherwise.",
+            );
+            return Err(eyre!(message));
+        };
+        git.push(PendingGitBuild {
+            name: req.name,
+            source,
+            commit: None,
+        });
+    }
+    Ok((git, index))
+}
+
+/// Interpreter and platform git dependencies are built for. Builds always
+/// use a throwaway venv.
+struct GitBuildTarget<'a> {
+    python: &'a Path,
+    cp_tag: &'a str,
+    platform: &'a str,
+    offline: bool,
+}
+
+/// Resolve each git dependency to a commit (unless pinned) and build it,
+/// reusing the wheel built from the same commit for this target.
+fn build_git_dependencies(
+    builds: Vec<PendingGitBuild>,
+    target: &GitBuildTarget<'_>,
+    collector: &mut EventCollector,
+) -> Result<Vec<GitDependency>> {
+    if builds.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fail = |collector: &mut EventCollector,
+                build: &PendingGitBuild,
+                error: &dyn std::fmt::Display,
+                source: Option<&(dyn std::error::Error + 'static)>| {
+        let message = format!(
+            "failed to install {} from {}: {}",
+            build.name, build.source.url, error
+        );
+        let mut diagnostic = Diagnostic::error(message.clone())
+            .with_code("E_INSTALL_GIT_FAILED")
+            .with_suggestion(
+                "Check that the repository and ref exist and are reachable (`git ls-remote <url>`), and that the project builds with its PEP 517 backend.",
+            );
+        if let Some(source) = source {
+            diagnostic = diagnostic.with_tool_failure(source);
+        }
+        collector.diagnostic(diagnostic);
+        eyre!(message)
+    };
+    let repositories = crate::git_source::GitCache::new()
+        .map_err(|e| eyre!("failed to open the git cache: {}", e))?;
+    let cache = crate::sdist_build::SdistBuildCache::new()
+        .map_err(|e| eyre!("failed to open the wheel build cache: {}", e))?;
+
+    let mut built = Vec::new();
+    for build in builds {
+        let commit = match &build.commit {
+            Some(commit) => build
+                .source
+                .validate()
+                .and_then(|()| build.source.validate_commit(commit))
+                .map(|()| commit.clone())
+                .map_err(|e| fail(collector, &build, &e, Some(&e)))?,
+            None => repositories
+                .resolve(&build.source, target.offline)
+                .map_err(|e| fail(collector, &build, &e, Some(&e)))?,
+        };
+        let key = build.source.build_key(&commit);
+        let (wheel, cached) = match cache.get(&key, target.cp_tag, target.platform) {
+            Some(wheel) => (wheel, true),
+            None => {
+                let project = repositories
+                    .checkout(&build.source, &commit, target.offline)
+                    .map_err(|e| fail(collector, &build, &e, Some(&e)))?;
+                collector.info(format!(
+                    "Building {} from {} at {}",
+                    build.name,
+                    build.source.url,
+                    &commit[..commit.len().min(12)]
+                ));
+                let work_dir = tempfile::tempdir()?;
+                let wheel = crate::sdist_build::build_tree_in_venv(
+                    &project,
+                    target.python,
+                    work_dir.path(),
+                )
+                .and_then(|wheel| cache.put(&key, target.cp_tag, target.platform, wheel))
+                .map_err(|e| fail(collector, &build, &e, Some(&e)))?;
+                (wheel, false)
+            }
+        };
+
+        let metadata = fs::File::open(&wheel.path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| crate::dist_info::wheel_metadata(file).map_err(|e| e.to_string()))
+            .and_then(|headers| headers.ok_or_else(|| "the wheel has no METADATA".to_string()))
+            .map(|headers| crate::sdist_metadata::CoreMetadata::parse(&headers))
+            .map_err(|e| fail(collector, &build, &e, None))?;
+        let name = metadata.name.unwrap_or_default();
+        if crate::pypi::normalize_project_name(&name)
+            != crate::pypi::normalize_project_name(&build.name)
+        {
+            let error = format!("the repository builds '{}', not '{}'", name, build.name);
+            return Err(fail(collector, &build, &error, None));
+        }
+        let version = metadata.version.unwrap_or_default();
+        let requires = metadata
+            .requires_dist
+            .into_iter()
+            .filter_map(crate::pypi::parse_requires_dist)
+            .collect();
+
+        let mut record = InstallRecord::new(
+            &build.name,
+            &version,
+            &wheel.filename,
+            InstallStatus::Installed,
+        );
+        record.hash = Some(format!("sha256:{}", wheel.sha256));
+        record.cached = cached;
+        record.built = true;
+        built.push(GitDependency {
+            build: wheel.source_build(target.cp_tag, target.platform),
+            name: build.name,
+            version,
+            source: build.source,
+            commit,
+            requires,
+            record,
+            wheel: wheel.path,
+        });
+    }
+    Ok(built)
+}
+
+/// Remove packages the resolver pulled from the index that git
+/// dependencies provide.
+fn drop_git_packages(resolution: &mut Resolution, git: &[GitDependency]) {
+    let provided: BTreeSet<String> = git
+        .iter()
+        .map(|dependency| crate::pypi::normalize_project_name(&dependency.name))
+        .collect();
+    resolution
+        .packages
+        .retain(|name, _| !provided.contains(&crate::pypi::normalize_project_name(name)));
+}
+
+/// Build one sdist with the chosen isolation, leaving the wheel in
+/// `work_dir`.
+fn build_sdist(
+    sdist: &Path,
+    isolation: crate::build_isolation::BuildIsolation,
+    target: &SourceBuildTarget<'_>,
+    work_dir: &Path,
+) -> crate::sdist_build::Result<crate::sdist_build::BuiltWheel> {
+    use crate::build_isolation::{BuildIsolation, ContainerRuntime, default_image};
+    match isolation {
+        BuildIsolation::Venv => crate::sdist_build::build_in_venv(sdist, target.python, work_dir),
+        BuildIsolation::Container => {
+            let runtime = ContainerRuntime::detect(target.config.container_runtime.as_deref())?;
+            let image = target.config.container_image.clone().unwrap_or_else(|| {
+                default_image(crate::resolver::cp_tag_to_dotted_version(target.cp_tag).as_deref())
+            });
+            crate::sdist_build::build_in_container(&runtime, &image, sdist, work_dir)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct InstallOutcome {
+    pub(crate) summary: String,
+    pub(crate) packages: Vec<String>,
+    pub(crate) lockfile: PathBuf,
+    pub(crate) verified: bool,
+    pub(crate) artifacts: Vec<Value>,
+    /// Workspace selection details (scope, selected members, group), present
+    /// only when dependencies were gathered from a workspace-aware source.
+    pub(crate) workspace: Option<Value>,
+    /// Number of wheels actually downloaded and installed into a site-packages
+    /// directory during this call. This is distinct from `packages.len()`,
+    /// which counts *resolved* packages regardless of whether any wheel was
+    /// actually fetched and installed (e.g. when no download URL is available
+    /// from the index, or when there was nothing new to install). Callers
+    /// (including the MCP `pybun_install` tool) must not claim packages were
+    /// "installed" unless this count is greater than zero.
+    pub(crate) installed_count: usize,
+    /// Per-package install status, one entry per resolved package that got
+    /// as far as the install phase.
+    pub(crate) results: Vec<InstallRecord>,
+    /// Target interpreter and site-packages, once the install phase ran.
+    pub(crate) environment: Option<Value>,
+}
+
+#[derive(Debug)]
+struct LockOutcome {
+    summary: String,
+    lockfile: PathBuf,
+    packages: Vec<String>,
+    verified: bool,
+    artifacts: Vec<Value>,
+    /// Packages whose dependencies came from dynamic sdist metadata.
+    dynamic_metadata: Vec<String>,
+    resolver: ResolverKind,
+    /// `--compare` report (see [`crate::resolver_strategy::comparison_json`]).
+    comparison: Option<Value>,
+    /// Python/platform combinations wheels were selected for, with the file
+    /// locked for each package
+    /// (`{"python": "3.12", "platform": "manylinux_2_28_x86_64", "packages": {..}}`).
+    targets: Vec<Value>,
+}
+
+fn is_missing_sha256(hash: Option<&str>) -> bool {
+    match hash {
+        Some(value) => crate::security::is_placeholder_hash(value),
+        None => true,
+    }
+}
+
+/// `MAJOR.MINOR` recorded in a lockfile for the CPython tag wheels were
+/// selected for.
+fn lock_python_version(cp_tag: &str) -> String {
+    cp_tag_to_dotted_version(cp_tag).unwrap_or_else(|| "3.11".to_string())
+}
+
+fn has_dynamic_metadata(dynamic_metadata: &BTreeSet<String>, name: &str) -> bool {
+    dynamic_metadata.contains(&crate::pypi::normalize_project_name(name))
+}
+
+fn registry_source_for_index(index_url: &str) -> PackageSource {
+    PackageSource::Registry {
+        index: "pypi".into(),
+        url: index_url.to_string(),
+    }
+}
+
+fn verification_artifact_value(
+    pkg: &crate::resolver::ResolvedPackage,
+    selection: &crate::resolver::ArtifactSelection,
+    index_url: &str,
+    verified_hash: &str,
+) -> Value {
+    json!({
+        "package": pkg.name,
+        "version": pkg.version,
+        "sha256": verified_hash,
+        "index_url": index_url,
+        "artifact_url": selection.url,
+        "platform_tag": selection.matched_platform,
+        "filename": selection.filename,
+        "from_source": selection.from_source,
+    })
+}
+
+fn missing_hash_diagnostic(
+    pkg: &crate::resolver::ResolvedPackage,
+    selection: &crate::resolver::ArtifactSelection,
+    index_url: &str,
+) -> Diagnostic {
+    Diagnostic {
+        level: crate::schema::DiagnosticLevel::Error,
+        code: Some("E_VERIFY_MISSING_HASH".to_string()),
+        error_code: None,
+        message: format!(
+            "selected artifact for {} {} ({}) is missing sha256 verification metadata",
+            pkg.name, pkg.version, selection.filename
+        ),
+        file: None,
+        line: None,
+        suggestion: Some(
+            "use an index that provides sha256 digests, then rerun install/lock/upgrade"
+                .to_string(),
+        ),
+        context: Some(json!({
+            "package": pkg.name,
+            "version": pkg.version,
+            "filename": selection.filename,
+            "artifact_url": selection.url,
+            "index_url": index_url,
+            "platform_tag": selection.matched_platform,
+            "from_source": selection.from_source,
+        })),
+        exception_type: None,
+        location: None,
+        next_action: None,
+        fix_candidates: None,
+    }
+}
+
+fn ensure_selection_is_verifiable(
+    pkg: &crate::resolver::ResolvedPackage,
+    selection: &crate::resolver::ArtifactSelection,
+    collector: &mut EventCollector,
+    index_url: &str,
+) -> Result<(String, Value)> {
+    if is_missing_sha256(selection.hash.as_deref()) {
+        let diagnostic = missing_hash_diagnostic(pkg, selection, index_url);
+        let message = diagnostic.message.clone();
+        collector.diagnostic(diagnostic);
+        return Err(eyre!(message));
+    }
+
+    let Some(verified_hash) = selection.hash.clone() else {
+        let message = format!(
+            "missing SHA-256 hash for {} {} after verification",
+            pkg.name, pkg.version
+        );
+        collector.error_with_code(
+            "E_VERIFY_MISSING_HASH",
+            message.clone(),
+            "Choose a package artifact that includes a SHA-256 digest or use an index that exposes artifact hashes.",
+        );
+        return Err(eyre!(message));
+    };
+    Ok((
+        verified_hash.clone(),
+        verification_artifact_value(pkg, selection, index_url, &verified_hash),
+    ))
+}
+
+/// Hash recorded in `lock` for `name`'s artifact `filename`.
+fn locked_hash(lock: &Lockfile, name: &str, filename: &str) -> Option<String> {
+    let pkg = lock.packages.get(name)?;
+    pkg.artifacts
+        .iter()
+        .find(|a| a.filename == filename)
+        .map(|a| a.hash.clone())
+        .or_else(|| (pkg.wheel == filename).then(|| pkg.hash.clone()))
+        .filter(|hash| !is_missing_sha256(Some(hash)))
+}
+
+/// The hash a download actually had when it failed checksum verification.
+fn checksum_mismatch_actual(error: &crate::downloader::DownloadError) -> Option<&str> {
+    use crate::downloader::DownloadError;
+    match error {
+        DownloadError::ChecksumMismatch { actual, .. } => Some(actual),
+        DownloadError::MaxRetriesExceeded { source, .. } => checksum_mismatch_actual(source),
+        _ => None,
+    }
+}
+
+/// `E_INSTALL_HASH_MISMATCH` context entry for `record`; `source` is where
+/// the mismatching file came from (`download` or `wheel_store`).
+fn hash_mismatch_value(record: &InstallRecord, source: &str) -> Value {
+    json!({
+        "package": record.name,
+        "version": record.version,
+        "filename": record.wheel,
+        "expected": record.hash,
+        "actual": record.actual_hash,
+        "source": source,
+    })
+}
+
+/// Check that `selection` has a usable SHA-256 and return it with its
+/// verification record. When the index published no hash and
+/// `hash_missing` allows it, the artifact is downloaded and hashed first, so
+/// the lockfile records a real digest (`hash_source: "computed"`).
+async fn verify_selection(
+    pkg: &crate::resolver::ResolvedPackage,
+    selection: &mut crate::resolver::ArtifactSelection,
+    hash_missing: bool,
+    index_url: &str,
+    collector: &mut EventCollector,
+) -> Result<(String, Value)> {
+    let computed = hash_missing && hash_unpublished_artifact(pkg, selection, collector).await;
+    let (hash, mut artifact) =
+        ensure_selection_is_verifiable(pkg, selection, collector, index_url)?;
+    artifact["hash_source"] = json!(if computed { "computed" } else { "index" });
+    Ok((hash, artifact))
+}
+
+/// Download a selected artifact the index published no SHA-256 for into the
+/// artifacts cache and record the hash of the file. Returns `false`, leaving
+/// the hash missing, when there is nothing to download or the download fails.
+async fn hash_unpublished_artifact(
+    pkg: &crate::resolver::ResolvedPackage,
+    selection: &mut crate::resolver::ArtifactSelection,
+    collector: &mut EventCollector,
+) -> bool {
+    if !is_missing_sha256(selection.hash.as_deref()) {
+        return false;
+    }
+    let (Some(url), Some(cache_dir)) = (selection.url.clone(), crate::pypi::artifacts_cache_dir())
+    else {
+        return false;
+    };
+    let dest = cache_dir.join(&selection.filename);
+    let hashed = match crate::downloader::Downloader::new()
+        .download_file(&url, &dest, None)
+        .await
+    {
+        Ok(_) => crate::security::sha256_file(&dest).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let digest = match hashed {
+        Ok(digest) => digest,
+        Err(e) => {
+            collector.warning(format!(
+                "could not download {} to hash it: {}",
+                selection.filename, e
+            ));
+            return false;
+        }
+    };
+    let hash = format!("sha256:{digest}");
+    let message = format!(
+        "the index published no SHA-256 for {} {} ({}); recorded the hash of the downloaded file",
+        pkg.name, pkg.version, selection.filename
+    );
+    eprintln!("warning: {}", message);
+    collector.diagnostic(
+        Diagnostic::warning(message)
+            .with_code("W_HASH_COMPUTED")
+            .with_suggestion(
+                "Use an index that publishes artifact hashes, or pass --require-hashes to refuse artifacts without one."
+                    .to_string(),
+            )
+            .with_context(json!({
+                "package": pkg.name,
+                "version": pkg.version,
+                "filename": selection.filename,
+                "artifact_url": url,
+                "sha256": hash,
+            })),
+    );
+    selection.hash = Some(hash);
+    true
+}
+
+fn emit_lockfile_verification_drift(lockfile: &Lockfile, collector: &mut EventCollector) {
+    let drifted_packages: Vec<Value> = lockfile
+        .packages
+        .values()
+        .filter(|pkg| is_missing_sha256(Some(&pkg.hash)))
+        .map(|pkg| {
+            json!({
+                "package": pkg.name,
+                "version": pkg.version,
+                "filename": pkg.wheel,
+                "hash": pkg.hash,
+            })
+        })
+        .collect();
+
+    if drifted_packages.is_empty() {
+        return;
+    }
+
+    collector.diagnostic(Diagnostic {
+        level: crate::schema::DiagnosticLevel::Warning,
+        code: Some("W_LOCK_PLACEHOLDER_HASH".to_string()),
+        error_code: None,
+        message: format!(
+            "existing lockfile contains {} package(s) without verified hashes",
+            drifted_packages.len()
+        ),
+        file: None,
+        line: None,
+        suggestion: Some(
+            "rerun 'pybun install' or 'pybun lock' with an index that provides sha256 digests"
+                .to_string(),
+        ),
+        context: Some(json!({ "packages": drifted_packages })),
+        exception_type: None,
+        location: None,
+        next_action: None,
+        fix_candidates: Some(crate::self_heal::fix_candidates_for_lock_drift()),
+    });
+}
+
+/// Resolve with the `--resolver` strategy. With `compare`, the baseline
+/// strategy runs first on the same index (so the selected strategy may see a
+/// warm metadata cache) and the comparison report is returned as well.
+async fn resolve_with_strategy<I: PackageIndex + Sync>(
+    resolver: ResolverKind,
+    compare: bool,
+    requirements: &[Requirement],
+    index: &I,
+    options: ResolveOptions,
+    index_url: Option<&str>,
+) -> (
+    std::result::Result<Resolution, crate::resolver::ResolveError>,
+    Option<Value>,
+) {
+    if !compare {
+        let result = resolver
+            .resolve(requirements.to_vec(), index, options, index_url)
+            .await;
+        return (result, None);
+    }
+    let baseline = resolver
+        .compare_baseline()
+        .run(requirements.to_vec(), index, options.clone(), index_url)
+        .await;
+    let candidate = resolver
+        .run(requirements.to_vec(), index, options, index_url)
+        .await;
+    let report = crate::resolver_strategy::comparison_json(&baseline, &candidate);
+    (candidate.result, Some(report))
+}
+
+/// One-line summary of a `--compare` report for the event log.
+fn describe_comparison(report: &Value) -> String {
+    let side = |key: &str| {
+        format!(
+            "{} {}ms",
+            report[key]["resolver"].as_str().unwrap_or("?"),
+            report[key]["elapsed_ms"]
+        )
+    };
+    let outcome = match report["diff"].as_array() {
+        Some(diff) if diff.is_empty() => "identical resolutions".to_string(),
+        Some(diff) => format!("{} package(s) differ", diff.len()),
+        None => "one resolver failed".to_string(),
+    };
+    format!(
+        "Resolver comparison: {} vs {}: {}",
+        side("candidate"),
+        side("baseline"),
+        outcome
+    )
+}
+
+/// `pybun add`: edit pyproject.toml, then install so the lock and
+/// environment follow.
+async fn run_add(
+    args: &crate::cli::PackageArgs,
+    collector: &mut EventCollector,
+) -> (String, RenderDetail) {
+    // Typosquats are refused before pyproject.toml is touched.
+    if !args.allow_suspicious {
+        let names: Vec<String> = args
+            .packages
+            .iter()
+            .filter_map(|spec| spec.parse::<Requirement>().ok())
+            .map(|req| req.name)
+            .collect();
+        let findings: Vec<_> =
+            new_dependency_names(names.iter().map(String::as_str), Path::new("pybun.lockb"))
+                .iter()
+                .filter_map(|name| crate::suspicious::typosquat(name))
+                .collect();
+        if let Err(e) = report_suspicious(&findings, false, collector) {
+            return (
+                "add".to_string(),
+                RenderDetail::error(
+                    e.to_string(),
+                    json!({ "error": e.to_string(), "suspicious": findings }),
+                ),
+            );
+        }
+    }
+    let result = add_package(args);
+    match result {
+        Ok(AddOutcome {
+            summary,
+            packages,
+            added_deps,
+            pyproject,
+        }) => {
+            // Chain install to ensure the environment is up-to-date
+            let names = packages
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            collector.info(format!("Installing dependencies including {}...", names));
+
+            let install_args = crate::cli::InstallArgs {
+                offline: args.offline,
+                system: false,
+                no_seed: false,
+                build_isolation: None,
+                requirements: Vec::new(), // install from pyproject.toml
+                index: None,
+                lock: std::path::PathBuf::from("pybun.lockb"),
+                frozen: false,
+                package: None,
+                require_hashes: false,
+                workspace: false,
+                member: None,
+                group: args.group.clone(),
+                pre: args.pre,
+                resolver: Default::default(),
+                concurrency: None,
+                allow_suspicious: args.allow_suspicious,
+            };
+
+            let packages_json: Vec<serde_json::Value> = packages
+                .iter()
+                .map(|p| json!({ "name": p.name, "version": p.version }))
+                .collect();
+
+            let lock_before = Lockfile::load_from_path(&install_args.lock).ok();
+            let pre_error_count = collector.error_diagnostic_count();
+            match install(&install_args, collector).await {
+                Ok(outcome) => {
+                    record_project_activity(
+                        "add",
+                        &outcome.lockfile,
+                        outcome.environment.as_ref(),
+                        collector,
+                    );
+                    let lockfile = Lockfile::load_from_path(&outcome.lockfile)
+                        .map(|after| {
+                            change_diff::lockfile_changes(
+                                &outcome.lockfile.display().to_string(),
+                                lock_before.as_ref(),
+                                &after,
+                            )
+                        })
+                        .unwrap_or_default();
+                    let (diff, changes) = render_file_changes(&[&pyproject, &lockfile]);
+                    (
+                        "add".to_string(),
+                        RenderDetail::with_json(
+                            with_diff(format!("{} and installed dependencies.", summary), &diff),
+                            json!({
+                                "package": packages.first().map(|p| p.name.clone()),
+                                "version": packages.first().and_then(|p| p.version.clone()),
+                                "packages": packages_json,
+                                "added_dependencies": added_deps,
+                                "group": args.group,
+                                "installed": true,
+                                "changes": changes,
+                                "diff": diff,
+                            }),
+                        ),
+                    )
+                }
+                Err(e) => {
+                    let err_msg = format!(
+                        "Added {} to pyproject.toml but failed to install: {}",
+                        names, e
+                    );
+                    // Only push a generic fallback error if install() did not
+                    // already record an error-level diagnostic (e.g. resolve errors).
+                    if collector.error_diagnostic_count() == pre_error_count {
+                        collector.error_with_code(
+                            "E_ADD_INSTALL_FAILED",
+                            err_msg.clone(),
+                            "pyproject.toml was updated; fix the underlying issue (see other diagnostics) and run `pybun install` to finish installing dependencies.",
+                        );
+                    }
+                    let (diff, changes) = render_file_changes(&[&pyproject]);
+                    (
+                        "add".to_string(),
+                        RenderDetail::error(
+                            err_msg,
+                            json!({
+                                "packages": packages_json,
+                                "group": args.group,
+                                "error": e.to_string(),
+                                "installed": false,
+                                "changes": changes,
+                                "diff": diff,
+                            }),
+                        ),
+                    )
+                }
+            }
+        }
+        Err(e) => {
+            collector.error_with_code(
+                "E_ADD_FAILED",
+                e.to_string(),
+                "Verify the package name/version and pyproject.toml, then retry `pybun add <package>`.",
+            );
+            (
+                "add".to_string(),
+                RenderDetail::error(
+                    e.to_string(),
+                    json!({
+                        "error": e.to_string(),
+                    }),
+                ),
+            )
+        }
+    }
+}
+
+fn run_remove(
+    args: &crate::cli::PackageArgs,
+    collector: &mut EventCollector,
+) -> (String, RenderDetail) {
+    let result = remove_package(args);
+    match result {
+        Ok(RemoveOutcome {
+            summary,
+            packages,
+            pyproject,
+        }) => {
+            let packages_json: Vec<serde_json::Value> = packages
+                .iter()
+                .map(|p| json!({ "name": p.name, "removed": p.removed }))
+                .collect();
+            let (diff, changes) = render_file_changes(&[&pyproject]);
+            (
+                "remove".to_string(),
+                RenderDetail::with_json(
+                    with_diff(summary, &diff),
+                    json!({
+                        "package": packages.first().map(|p| p.name.clone()),
+                        "removed": packages.first().map(|p| p.removed),
+                        "packages": packages_json,
+                        "group": args.group,
+                        "changes": changes,
+                        "diff": diff,
+                    }),
+                ),
+            )
+        }
+        Err(e) => {
+            collector.error_with_code(
+                "E_REMOVE_FAILED",
+                e.to_string(),
+                "Verify the package is listed in pyproject.toml, then retry `pybun remove <package>`.",
+            );
+            (
+                "remove".to_string(),
+                RenderDetail::error(
+                    e.to_string(),
+                    json!({
+                        "error": e.to_string(),
+                    }),
+                ),
+            )
+        }
+    }
+}
+
+fn run_python(cmd: &PythonCommands, collector: &mut EventCollector) -> (String, RenderDetail) {
+    match handle_python_command(cmd, collector) {
+        Ok((subcmd, detail)) => (format!("python {}", subcmd), detail),
+        Err(e) => {
+            // Determine subcommand name for error reporting
+            let subcmd = match cmd {
+                PythonCommands::List(_) => "list",
+                PythonCommands::Install(_) => "install",
+                PythonCommands::Remove(_) => "remove",
+                PythonCommands::Which(_) => "which",
+                PythonCommands::Pin(_) => "pin",
+            };
+            collector.diagnostic(
+                Diagnostic::error(e.to_string())
+                    .with_code(format!("E_PYTHON_{}_FAILED", subcmd.to_uppercase()))
+                    .with_suggestion(
+                        "Run `pybun doctor` to check Python discovery, then retry `pybun python <subcommand>`.",
+                    )
+                    .with_tool_failure(e.as_ref()),
+            );
+            (
+                format!("python {}", subcmd),
+                RenderDetail::error(
+                    e.to_string(),
+                    json!({
+                        "error": e.to_string(),
+                    }),
+                ),
+            )
+        }
+    }
+}
+
+fn run_module_find(
+    args: &crate::cli::ModuleFindArgs,
+    collector: &mut EventCollector,
+) -> (String, RenderDetail) {
+    collector.event(EventType::ModuleFindStart);
+    let result = tooling::run_module_find(args, collector);
+    collector.event(EventType::ModuleFindComplete);
+    match result {
+        Ok(detail) => ("module-find".to_string(), detail),
+        Err(e) => {
+            collector.error_with_code(
+                "E_MODULE_FIND_FAILED",
+                e.to_string(),
+                "Verify the module name and that the target environment is set up, then re-run `pybun module-find`.",
+            );
+            (
+                "module-find".to_string(),
+                RenderDetail::error(
+                    e.to_string(),
+                    json!({
+                        "error": e.to_string(),
+                    }),
+                ),
+            )
+        }
+    }
+}
+
+/// Run a command the MCP server exposes as a tool and return the envelope
+/// `--format json` would print. `None` for commands that are not exposed:
+/// they may write to stdout, the stdio transport's channel.
+pub async fn run_envelope(command: &Commands) -> Option<JsonEnvelope> {
+    let mut collector = EventCollector::new();
+    collector.event(EventType::CommandStart);
+    let (command, detail) = match command {
+        Commands::Add(args) => run_add(args, &mut collector).await,
+        Commands::Remove(args) => run_remove(args, &mut collector),
+        Commands::Python(cmd @ (PythonCommands::List(_) | PythonCommands::Install(_))) => {
+            run_python(cmd, &mut collector)
+        }
+        Commands::ModuleFind(args) => run_module_find(args, &mut collector),
+        _ => return None,
+    };
+    record_deferred_diagnostics(&mut collector);
+    collector.event(EventType::CommandEnd);
+    let duration = collector.elapsed();
+    let (events, diagnostics, trace_id) = collector.into_parts();
+    match render(
+        &command,
+        detail,
+        OutputFormat::Json,
+        0,
+        duration,
+        events,
+        diagnostics,
+        trace_id,
+    ) {
+        Some(Rendered::Json(envelope)) => Some(envelope),
+        _ => None,
+    }
+}
+
+/// Diagnostics gathered outside the command handlers: network policy
+/// violations and artifacts offline mode could not fetch.
+fn record_deferred_diagnostics(collector: &mut EventCollector) {
+    for violation in crate::execution_policy::take_violations() {
+        collector.diagnostic(violation.diagnostic());
+    }
+
+    for violation in network_policy::take_violations() {
+        let section = match crate::execution_policy::current() {
+            Some(policy) if policy.source.display().to_string() == violation.policy_file => {
+                "[network]"
+            }
+            _ => "[tool.pybun.network]",
+        };
+        collector.diagnostic(
+            Diagnostic::error(violation.to_string())
+                .with_code("E_NETWORK_POLICY")
+                .with_context(json!(violation))
+                .with_suggestion(format!(
+                    "Add the host to `{}` under {section} in {} if this access is expected.",
+                    violation.operation.as_str(),
+                    violation.policy_file
+                )),
+        );
+    }
+
+    let missing = crate::offline::take_missing();
+    if !missing.is_empty() {
+        collector.diagnostic(crate::offline::NetworkRequired { missing }.diagnostic());
+    }
+}
+
+/// `pybun lock --check`: verify that `pybun.lockb` still matches the
+/// project's declared dependencies, offline.
+fn run_lock_check(collector: &mut EventCollector) -> RenderDetail {
+    let fail = |collector: &mut EventCollector, code: &str, message: String, hint: &str| {
+        collector.diagnostic(
+            Diagnostic::error(message.clone())
+                .with_code(code)
+                .with_suggestion(hint)
+                .with_fix_candidates(crate::self_heal::fix_candidates_for_stale_lockfile()),
+        );
+        RenderDetail::error(message.clone(), json!({ "error": message }))
+    };
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let (root, main) = match Workspace::discover(&cwd) {
+        Ok(Some(ws)) => {
+            let main = ws.merged_dependencies();
+            (ws.root, main)
+        }
+        Ok(None) => match Project::discover(&cwd) {
+            Ok(project) => {
+                let main = project.dependencies();
+                (project, main)
+            }
+            Err(_) => {
+                return fail(
+                    collector,
+                    "E_LOCK_TARGET_REQUIRED",
+                    "no pyproject.toml found in the current directory or any parent directory"
+                        .to_string(),
+                    "Run from inside a project with a pyproject.toml.",
+                );
+            }
+        },
+        Err(e) => {
+            return fail(
+                collector,
+                "E_LOCK_FAILED",
+                e.to_string(),
+                "Fix pyproject.toml and retry.",
+            );
+        }
+    };
+    let lock_path = root.root().join("pybun.lockb");
+    if !lock_path.exists() {
+        return fail(
+            collector,
+            "E_LOCK_MISSING",
+            format!("{} not found", lock_path.display()),
+            "Run `pybun lock` to create it.",
+        );
+    }
+    let lock = match Lockfile::load_from_path(&lock_path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            return fail(
+                collector,
+                "E_LOCK_FAILED",
+                format!("cannot read lockfile {}: {}", lock_path.display(), e),
+                "Run `pybun lock` to regenerate it.",
+            );
+        }
+    };
+
+    let result = crate::lock_check::check(&lock, &main, &root.dependency_groups());
+    for item in &result.items {
+        collector.diagnostic(
+            Diagnostic::error(item.message())
+                .with_code("E_LOCK_DRIFT")
+                .with_context(json!(item))
+                .with_fix_candidates(crate::self_heal::fix_candidates_for_stale_lockfile()),
+        );
+    }
+    let detail = json!({
+        "lockfile": lock_path.display().to_string(),
+        "groups": result.groups,
+        "up_to_date": result.is_clean(),
+        "drift": result.items,
+    });
+    if result.is_clean() {
+        return RenderDetail::with_json(
+            format!(
+                "{} matches pyproject.toml ({} packages)",
+                lock_path.display(),
+                lock.packages.len()
+            ),
+            detail,
+        );
+    }
+    let lines: Vec<String> = result.items.iter().map(|item| item.message()).collect();
+    RenderDetail::error(
+        format!(
+            "{} is out of date ({} item(s)):\n  {}",
+            lock_path.display(),
+            result.items.len(),
+            lines.join("\n  ")
+        ),
+        detail,
+    )
+}
+
+/// `pybun lock --shards` / `--check-shards`: rederive or verify the member
+/// lock shards of the workspace lockfile, without resolving.
+fn run_lock_shards(args: &LockArgs, collector: &mut EventCollector) -> RenderDetail {
+    let fail = |collector: &mut EventCollector, code: &str, message: String, hint: &str| {
+        collector.error_with_code(code, message.clone(), hint);
+        RenderDetail::error(message.clone(), json!({ "error": message }))
+    };
+    let workspace = std::env::current_dir()
+        .map_err(|e| eyre!(e))
+        .and_then(|cwd| Workspace::discover_root(cwd).map_err(|e| eyre!(e)));
+    let ws = match workspace {
+        Ok(Some(ws)) => ws,
+        Ok(None) => {
+            return fail(
+                collector,
+                "E_LOCK_FAILED",
+                "lock shards require a workspace; no [tool.pybun.workspace] configuration found"
+                    .to_string(),
+                "Run from inside a workspace that declares [tool.pybun.workspace].",
+            );
+        }
+        Err(e) => {
+            return fail(
+                collector,
+                "E_LOCK_FAILED",
+                e.to_string(),
+                "Fix the workspace's pyproject.toml files and retry.",
+            );
+        }
+    };
+    let lock_path = ws.root.root().join("pybun.lockb");
+    let lock = match Lockfile::load_from_path(&lock_path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            return fail(
+                collector,
+                "E_INSTALL_LOCK_MISSING",
+                format!("cannot read lockfile {}: {}", lock_path.display(), e),
+                "Run `pybun install` at the workspace root to resolve and lock the workspace.",
+            );
+        }
+    };
+    let shard_dir = lock_shard::shard_dir(&lock_path);
+
+    if args.check_shards {
+        let drift = match lock_shard::check(&ws, &lock, &lock_path, &[]) {
+            Ok(drift) => drift,
+            Err(e) => {
+                return fail(
+                    collector,
+                    "E_LOCK_SHARD_DRIFT",
+                    e.to_string(),
+                    "Run `pybun lock --shards` to rederive the shards.",
+                );
+            }
+        };
+        let detail = json!({
+            "lockfile": lock_path.display().to_string(),
+            "shards": shard_dir.display().to_string(),
+            "members": ws.member_names(),
+            "drift": drift,
+        });
+        if drift.is_empty() {
+            return RenderDetail::with_json(
+                format!(
+                    "{} member lock shard(s) match {}",
+                    ws.members.len(),
+                    lock_path.display()
+                ),
+                detail,
+            );
+        }
+        let lines: Vec<String> = drift.iter().map(|d| d.describe()).collect();
+        let message = format!(
+            "{} lock shard(s) drifted from {}:\n  {}",
+            drift.len(),
+            lock_path.display(),
+            lines.join("\n  ")
+        );
+        collector.diagnostic(
+            Diagnostic::error(message.clone())
+                .with_code("E_LOCK_SHARD_DRIFT")
+                .with_suggestion(
+                    "Run `pybun lock --shards` to rederive the shards from the workspace lockfile.",
+                )
+                .with_context(json!({ "drift": drift })),
+        );
+        return RenderDetail::error(message, detail);
+    }
+
+    match lock_shard::write_all(&ws, &lock, &lock_path) {
+        Ok(written) => RenderDetail::with_json(
+            format!(
+                "wrote {} member lock shard(s) -> {}",
+                written.len(),
+                shard_dir.display()
+            ),
+            json!({
+                "lockfile": lock_path.display().to_string(),
+                "shards": shard_dir.display().to_string(),
+                "members": ws.member_names(),
+                "written": written
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>(),
+            }),
+        ),
+        Err(e) => fail(
+            collector,
+            "E_LOCK_FAILED",
+            e.to_string(),
+            "Check that the shard directory is writable and retry.",
+        ),
+    }
+}
+
+async fn lock_dependencies(args: &LockArgs, collector: &mut EventCollector) -> Result<LockOutcome> {
+    // Validate the lock targets before doing any resolution work.
+    let (platforms, mut python_tags) = lock_targets(args, collector)?;
+    let (dep_specs, lock_path): (Vec<String>, PathBuf) =
+        if let Some(script_path) = args.script.as_ref() {
+            if !script_path.exists() {
+                return Err(eyre!("script not found: {}", script_path.display()));
+            }
+
+            let pep723_metadata = match pep723::parse_script_metadata(script_path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    return Err(eyre!("failed to parse PEP 723 metadata: {}", e));
+                }
+            };
+
+            let pep723_deps = pep723_metadata
+                .as_ref()
+                .map(|m| m.dependencies.clone())
+                .unwrap_or_default();
+
+            (pep723_deps, script_lock_path(script_path))
+        } else {
+            let cwd = std::env::current_dir()?;
+            let Ok(project) = Project::discover(&cwd) else {
+                let message =
+                    "no pyproject.toml found in the current directory or any parent directory"
+                        .to_string();
+                collector.diagnostic(Diagnostic {
+                    level: crate::schema::DiagnosticLevel::Error,
+                    code: Some("E_LOCK_TARGET_REQUIRED".to_string()),
+                    error_code: None,
+                    message: message.clone(),
+                    file: None,
+                    line: None,
+                    suggestion: Some(
+                        "Run 'pybun lock --script <path/to/script.py>' to lock a PEP 723 script, \
+                     or create a pyproject.toml with [project.dependencies] to lock a project"
+                            .to_string(),
+                    ),
+                    context: None,
+                    exception_type: None,
+                    location: None,
+                    next_action: None,
+                    fix_candidates: None,
+                });
+                return Err(eyre!(message));
             };
 
-            let pep723_deps = pep723_metadata
-                .as_ref()
-                .map(|m| m.dependencies.clone())
-                .unwrap_or_default();
+            (project.dependencies(), cwd.join("pybun.lockb"))
+        };
+
+    let requirements: Vec<Requirement> = dep_specs
+        .iter()
+        .map(|d| {
+            d.parse::<Requirement>()
+                .unwrap_or_else(|_| Requirement::any(d.trim()))
+        })
+        .collect();
+
+    if dep_specs.is_empty() {
+        let lock = Lockfile::new(vec!["3.11".into()], vec!["unknown".into()]);
+        lock.save_to_path(&lock_path)?;
+        return Ok(LockOutcome {
+            summary: format!("no dependencies to lock -> {}", lock_path.display()),
+            lockfile: lock_path,
+            packages: Vec::new(),
+            verified: true,
+            artifacts: Vec::new(),
+            dynamic_metadata: Vec::new(),
+            resolver: args.resolver,
+            comparison: None,
+            targets: Vec::new(),
+        });
+    }
+
+    let source_index_url: String;
+    let offline = args.offline;
+    // With several target versions, resolve for the oldest; every locked
+    // version is checked against all of them below.
+    let python_version = match python_tags.iter().min_by_key(|tag| cp_tag_minor(tag)) {
+        Some(tag) => cp_tag_to_dotted_version(tag),
+        None => resolve_target_python_version(),
+    };
+    let resolve_options = ResolveOptions {
+        python_version,
+        ..Default::default()
+    };
+    let mut dynamic_metadata = BTreeSet::new();
+    let comparison;
+    let resolution = if let Some(index_path) = args.index.clone() {
+        source_index_url = index_path.display().to_string();
+        let index = load_index_from_path(&index_path).map_err(|e| eyre!(e))?;
+        let (result, report) = resolve_with_strategy(
+            args.resolver,
+            args.compare,
+            &requirements,
+            &index,
+            resolve_options,
+            None,
+        )
+        .await;
+        comparison = report;
+        match result {
+            Ok(r) => r,
+            Err(e) => {
+                for d in crate::self_heal::diagnostics_for_resolve_error(&requirements, &e) {
+                    collector.diagnostic(d);
+                }
+                return Err(eyre!(e.to_string()));
+            }
+        }
+    } else {
+        let index = RemoteIndex::from_env(offline)
+            .map_err(|e| eyre!("failed to init pypi client: {}", e))?;
+        source_index_url = index.index_url();
+        collector.info(format!(
+            "Using PyPI index {} (offline: {})",
+            source_index_url, offline
+        ));
+        let (resolve_result, report) = resolve_with_strategy(
+            args.resolver,
+            args.compare,
+            &requirements,
+            &index,
+            resolve_options,
+            Some(&source_index_url),
+        )
+        .await;
+        comparison = report;
+        for notice in index.take_stale_cache_notices() {
+            collector.warning(notice);
+        }
+        dynamic_metadata = index.dynamic_metadata_packages();
+        match resolve_result {
+            Ok(r) => r,
+            Err(e) => {
+                for d in crate::self_heal::diagnostics_for_resolve_error(&requirements, &e) {
+                    collector.diagnostic(d);
+                }
+                return Err(eyre!(e.to_string()));
+            }
+        }
+    };
+    warn_on_prerelease_fallback(&resolution, collector);
+    if let Some(report) = &comparison {
+        collector.info(describe_comparison(report));
+    }
+
+    collector.event_with(EventType::ResolveComplete, |event| {
+        event.message = Some("Resolved dependencies".to_string());
+        event.progress = Some(40);
+    });
+
+    // Resolution used the oldest requested version; every locked package must
+    // support the newer ones too.
+    let violations = requires_python_violations(&resolution, &python_tags);
+    if !violations.is_empty() {
+        let message = format!(
+            "locked versions do not support every target Python: {}",
+            violations.join("; ")
+        );
+        collector.error_with_code(
+            "E_LOCK_PYTHON_UNSUPPORTED",
+            message.clone(),
+            "Drop the unsupported `--python` versions, or pin dependency versions that support all of them.",
+        );
+        return Err(eyre!(message));
+    }
+
+    if python_tags.is_empty() {
+        python_tags.push(active_lock_cp_tag()?);
+    }
+
+    let mut lock = Lockfile::new(
+        python_tags
+            .iter()
+            .map(|tag| lock_python_version(tag))
+            .collect(),
+        platforms
+            .iter()
+            .map(|tags| {
+                tags.first()
+                    .cloned()
+                    .unwrap_or_else(|| "unknown".to_string())
+            })
+            .collect(),
+    );
+    let mut verified_artifacts = Vec::new();
+    let mut targets: Vec<LockTarget> = python_tags
+        .iter()
+        .flat_map(|cp_tag| {
+            platforms
+                .iter()
+                .map(move |tags| (cp_tag, tags, serde_json::Map::new()))
+        })
+        .collect();
+
+    for pkg in resolution.packages.values() {
+        let package = lock_resolved_package(
+            pkg,
+            &mut targets,
+            !args.require_hashes && !args.offline,
+            &source_index_url,
+            has_dynamic_metadata(&dynamic_metadata, &pkg.name),
+            &mut verified_artifacts,
+            collector,
+        )
+        .await?;
+        lock.add_package(package);
+    }
+    let targets: Vec<Value> = targets
+        .into_iter()
+        .map(|(cp_tag, tags, selected)| {
+            json!({
+                "python": lock_python_version(cp_tag),
+                "platform": tags.first(),
+                "packages": selected,
+            })
+        })
+        .collect();
 
-            (pep723_deps, script_lock_path(script_path))
-        } else {
-            let cwd = std::env::current_dir()?;
-            let Ok(project) = Project::discover(&cwd) else {
-                let message =
-                    "no pyproject.toml found in the current directory or any parent directory"
-                        .to_string();
-                collector.diagnostic(Diagnostic {
-                    level: crate::schema::DiagnosticLevel::Error,
-                    code: Some("E_LOCK_TARGET_REQUIRED".to_string()),
-                    message: message.clone(),
-                    file: None,
-                    line: None,
-                    suggestion: Some(
-                        "Run 'pybun lock --script <path/to/script.py>' to lock a PEP 723 script, \
-                     or create a pyproject.toml with [project.dependencies] to lock a project"
-                            .to_string(),
-                    ),
-                    context: None,
-                    exception_type: None,
-                    location: None,
-                    next_action: None,
-                    fix_candidates: None,
-                });
+    lock.assign_groups(&BTreeMap::from([(MAIN_GROUP.to_string(), dep_specs)]));
+    if let Ok(previous) = Lockfile::load_from_path(&lock_path) {
+        crate::suspicious::restore(&previous);
+    }
+    crate::suspicious::record(&mut lock);
+    lock.save_to_path(&lock_path)?;
+
+    let dynamic_metadata: Vec<String> = lock
+        .dynamic_metadata_packages()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    if !dynamic_metadata.is_empty() {
+        collector.warning(format!(
+            "dependencies of {} were computed by building the sdist metadata and may differ on other platforms or Python versions",
+            dynamic_metadata.join(", ")
+        ));
+    }
+
+    Ok(LockOutcome {
+        summary: format!(
+            "locked {} packages -> {}",
+            lock.packages.len(),
+            lock_path.display()
+        ),
+        lockfile: lock_path,
+        packages: lock.packages.keys().cloned().collect(),
+        verified: true,
+        artifacts: verified_artifacts,
+        dynamic_metadata,
+        resolver: args.resolver,
+        comparison,
+        targets,
+    })
+}
+
+/// A Python version (CPython tag) and platform (its wheel tags) a lock
+/// targets, with the file locked for each package.
+type LockTarget<'a> = (&'a String, &'a Vec<String>, serde_json::Map<String, Value>);
+
+/// Lock `pkg` for every target: the artifact selected for each, with its
+/// verified hash. Records the selected files in `targets` and the
+/// verifications in `verified_artifacts`.
+async fn lock_resolved_package(
+    pkg: &crate::resolver::ResolvedPackage,
+    targets: &mut [LockTarget<'_>],
+    download_hashes: bool,
+    source_index_url: &str,
+    dynamic_metadata: bool,
+    verified_artifacts: &mut Vec<Value>,
+    collector: &mut EventCollector,
+) -> Result<Package> {
+    let mut artifacts: Vec<Artifact> = Vec::new();
+    for (cp_tag, platform_tags, selected) in targets.iter_mut() {
+        let mut selection = select_artifact_for_platform_with_cp(pkg, platform_tags, cp_tag);
+        if selection.from_source {
+            let message = format!(
+                "no compatible pre-built wheel for {} {} on {}; falling back to source build",
+                pkg.name,
+                pkg.version,
+                platform_tags.join(",")
+            );
+            eprintln!("warning: {}", message);
+            collector.warning(message);
+        }
+        selected.insert(pkg.name.clone(), json!(selection.filename));
+        if artifacts.iter().any(|a| a.filename == selection.filename) {
+            continue;
+        }
+        let (verified_hash, artifact) = verify_selection(
+            pkg,
+            &mut selection,
+            download_hashes,
+            source_index_url,
+            collector,
+        )
+        .await?;
+        verified_artifacts.push(artifact);
+        artifacts.push(locked_artifact(pkg, &selection, verified_hash));
+    }
+    Ok(Package {
+        name: pkg.name.clone(),
+        version: pkg.version.clone(),
+        source: registry_source_for_index(source_index_url),
+        wheel: artifacts[0].filename.clone(),
+        hash: artifacts[0].hash.clone(),
+        dependencies: pkg.dependencies.iter().map(ToString::to_string).collect(),
+        dynamic_metadata,
+        groups: Vec::new(),
+        build: None,
+        artifacts,
+    })
+}
+
+/// Platform tag lists and CPython tags requested with `--platform` and
+/// `--python`; this machine's platform when none is given.
+fn lock_targets(
+    args: &LockArgs,
+    collector: &mut EventCollector,
+) -> Result<(Vec<Vec<String>>, Vec<String>)> {
+    let mut platforms: Vec<Vec<String>> = Vec::new();
+    for target in &args.platform {
+        match crate::tags::platform_tags_for(target) {
+            Ok(tags) => {
+                if !platforms.contains(&tags) {
+                    platforms.push(tags);
+                }
+            }
+            Err(e) => {
+                let message = e.to_string();
+                collector.error_with_code(
+                    "E_LOCK_UNKNOWN_PLATFORM",
+                    message.clone(),
+                    "Pass the target's wheel platform tag, e.g. `--platform manylinux_2_28_x86_64`, `--platform macosx_14_0_arm64` or `--platform win_amd64`.",
+                );
                 return Err(eyre!(message));
-            };
+            }
+        }
+    }
+    if platforms.is_empty() {
+        platforms.push(current_platform_tags());
+    }
+    let mut python_tags: Vec<String> = Vec::new();
+    for version in &args.python {
+        let Some(cp_tag) = python_version_to_cp_tag(version)
+            .filter(|tag| tag.starts_with("cp3") && version.split('.').count() <= 3)
+        else {
+            let message = format!(
+                "unknown Python version '{version}'; expected a CPython 3 version such as 3.12"
+            );
+            collector.error_with_code(
+                "E_LOCK_UNKNOWN_PYTHON",
+                message.clone(),
+                "Pass `--python MAJOR.MINOR`, e.g. `--python 3.12`.",
+            );
+            return Err(eyre!(message));
+        };
+        if !python_tags.contains(&cp_tag) {
+            python_tags.push(cp_tag);
+        }
+    }
+    Ok((platforms, python_tags))
+}
 
-            (project.dependencies(), cwd.join("pybun.lockb"))
+/// CPython tag to lock for when no `--python` is given: the tag of the
+/// interpreter the lock targets.
+fn active_lock_cp_tag() -> Result<String> {
+    // Detect the CPython tag of the actual lock target's Python (PYBUN_ENV / PYBUN_PYTHON /
+    // project venv / system Python) *before* selecting wheels, so the wheel filenames recorded
+    // in the lockfile match the interpreter that will actually install them. Selecting wheels
+    // against whatever `python3`/`python` happens to resolve on PATH (the previous behavior)
+    // could silently record wheels for the wrong CPython ABI, producing the kind of
+    // `ImportError` #172's runtime compatibility check was built to detect after the fact
+    // (Issue #293; same root cause as #291, fixed for `pybun install` in #292). This is
+    // read-only detection only and covers both project-mode and `--script` PEP 723 locking,
+    // since both resolve the target interpreter relative to the current working directory
+    // (honoring PYBUN_ENV/PYBUN_PYTHON regardless of cwd).
+    let working_dir = std::env::current_dir()?;
+    let target_env_probe = crate::env::find_python_env(&working_dir)?;
+
+    // PYBUN_FORCE_CP_TAG lets tests (and users) pin the CPython tag deterministically,
+    // bypassing interpreter detection entirely.
+    Ok(std::env::var("PYBUN_FORCE_CP_TAG")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| {
+            get_python_version(&target_env_probe.python_path)
+                .ok()
+                .and_then(|v| get_interpreter_tag(&target_env_probe.python_path, &v))
+        })
+        .unwrap_or_else(|| "cp311".to_string()))
+}
+
+/// `"{package} requires Python {specifier}, excluding {version}"` for each
+/// resolved package that does not support one of `python_tags`.
+fn requires_python_violations(resolution: &Resolution, python_tags: &[String]) -> Vec<String> {
+    let mut violations = Vec::new();
+    for pkg in resolution.packages.values() {
+        let Some(requires_python) = pkg.requires_python.as_deref() else {
+            continue;
         };
+        let excluded: Vec<String> = python_tags
+            .iter()
+            .filter_map(|tag| cp_tag_to_dotted_version(tag))
+            .filter(|version| !crate::resolver::requires_python_allows(requires_python, version))
+            .collect();
+        if !excluded.is_empty() {
+            violations.push(format!(
+                "{} {} requires Python {}, excluding {}",
+                pkg.name,
+                pkg.version,
+                requires_python,
+                excluded.join(", ")
+            ));
+        }
+    }
+    violations
+}
 
-    let requirements: Vec<Requirement> = dep_specs
+/// Minor version of a `cp3XX` tag, for ordering.
+fn cp_tag_minor(cp_tag: &str) -> u32 {
+    cp_tag
+        .strip_prefix("cp3")
+        .and_then(|minor| minor.parse().ok())
+        .unwrap_or(u32::MAX)
+}
+
+/// `pybun lock --from`: convert a uv.lock or poetry.lock into pybun.lockb
+/// without resolving (see [`crate::foreign_lock`]).
+async fn run_lock_from(
+    args: &LockArgs,
+    from: &Path,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    let fail = |collector: &mut EventCollector, message: String| {
+        collector.error_with_code(
+            "E_LOCK_FROM_FAILED",
+            message.clone(),
+            "Pass a uv.lock or poetry.lock, or run `pybun lock` without --from to resolve pyproject.toml.",
+        );
+        RenderDetail::error(message.clone(), json!({ "error": message }))
+    };
+    let (platforms, mut python_tags) = match lock_targets(args, collector) {
+        Ok(targets) => targets,
+        Err(e) => return RenderDetail::error(e.to_string(), json!({ "error": e.to_string() })),
+    };
+    if python_tags.is_empty() {
+        match active_lock_cp_tag() {
+            Ok(tag) => python_tags.push(tag),
+            Err(e) => return fail(collector, e.to_string()),
+        }
+    }
+    let converted = match crate::foreign_lock::convert(
+        from,
+        python_tags
+            .iter()
+            .map(|tag| lock_python_version(tag))
+            .collect(),
+        platforms
+            .iter()
+            .map(|tags| {
+                tags.first()
+                    .cloned()
+                    .unwrap_or_else(|| "unknown".to_string())
+            })
+            .collect(),
+    ) {
+        Ok(converted) => converted,
+        Err(e) => return fail(collector, e.to_string()),
+    };
+    let mut lock = converted.lock;
+
+    // poetry.lock names files without download URLs; look them up by exact
+    // version on the index, as `pybun install` needs them.
+    let unlocated = if let Some(index_path) = &args.index {
+        match load_index_from_path(index_path) {
+            Ok(index) => fill_artifact_urls(&mut lock, &index).await,
+            Err(e) => return fail(collector, e.to_string()),
+        }
+    } else if lock.packages.values().any(needs_artifact_urls) {
+        match RemoteIndex::from_env(args.offline) {
+            Ok(index) => fill_artifact_urls(&mut lock, &index).await,
+            Err(e) => return fail(collector, format!("failed to init pypi client: {e}")),
+        }
+    } else {
+        Vec::new()
+    };
+
+    for skipped in &converted.skipped {
+        collector.diagnostic(
+            Diagnostic::warning(format!("skipped {}: {}", skipped.entry, skipped.reason))
+                .with_code("W_LOCK_FROM_SKIPPED")
+                .with_suggestion("Add it with `pybun add` if the project still needs it."),
+        );
+    }
+    for forked in &converted.forked {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "{} is locked at {}; pybun.lockb keeps {}",
+                forked.name,
+                forked.versions.join(", "),
+                forked.kept
+            ))
+            .with_code("W_LOCK_FROM_FORKED")
+            .with_suggestion(
+                "Check the kept version works on every target, or pin the package per platform in pyproject.toml and run `pybun lock`.",
+            ),
+        );
+    }
+    if !unlocated.is_empty() {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "no download URL found for {}; `pybun install` cannot fetch them from this lock",
+                unlocated.join(", ")
+            ))
+            .with_code("W_LOCK_FROM_NO_URL")
+            .with_suggestion("Re-run with the index that serves them (--index or PYBUN_INDEX_URL), or run `pybun lock`."),
+        );
+    }
+
+    let lock_path = match std::env::current_dir() {
+        Ok(cwd) => cwd.join("pybun.lockb"),
+        Err(e) => return fail(collector, e.to_string()),
+    };
+    if let Err(e) = lock.save_to_path(&lock_path) {
+        return fail(
+            collector,
+            format!("failed to write {}: {}", lock_path.display(), e),
+        );
+    }
+    record_project_activity("lock", &lock_path, None, collector);
+
+    let groups: BTreeSet<&String> = lock.packages.values().flat_map(|p| &p.groups).collect();
+    RenderDetail::with_json(
+        format!(
+            "converted {} packages from {} -> {}",
+            lock.packages.len(),
+            from.display(),
+            lock_path.display()
+        ),
+        json!({
+            "lockfile": lock_path.display().to_string(),
+            "from": from.display().to_string(),
+            "format": converted.format,
+            "packages": lock.packages.keys().collect::<Vec<_>>(),
+            "groups": groups,
+            "python": lock.python_versions,
+            "platforms": lock.platforms,
+            "requires_python": converted.requires_python,
+            "skipped": converted.skipped,
+            "forked": converted.forked,
+            "unlocated": unlocated,
+        }),
+    )
+}
+
+fn needs_artifact_urls(pkg: &Package) -> bool {
+    matches!(pkg.source, PackageSource::Registry { .. })
+        && pkg.artifacts.iter().any(|a| a.url.is_none())
+}
+
+/// Fill in the download URLs of registry artifacts from `index`; returns the
+/// packages left without any.
+async fn fill_artifact_urls(lock: &mut Lockfile, index: &impl PackageIndex) -> Vec<String> {
+    let mut unlocated = Vec::new();
+    for pkg in lock.packages.values_mut() {
+        if !needs_artifact_urls(pkg) {
+            continue;
+        }
+        if let Ok(Some(published)) = index.get(&pkg.name, &pkg.version).await {
+            for artifact in pkg.artifacts.iter_mut().filter(|a| a.url.is_none()) {
+                if let Some(wheel) = published
+                    .artifacts
+                    .wheels
+                    .iter()
+                    .find(|wheel| wheel.file == artifact.filename)
+                {
+                    artifact.url = wheel.url.clone();
+                    if !wheel.platforms.is_empty() {
+                        artifact.platforms = wheel.platforms.clone();
+                    }
+                } else if published.artifacts.sdist.as_deref() == Some(&artifact.filename) {
+                    artifact.url = published.artifacts.sdist_url.clone();
+                }
+            }
+        }
+        if pkg.artifacts.iter().all(|a| a.url.is_none()) {
+            unlocated.push(pkg.name.clone());
+        }
+    }
+    unlocated
+}
+
+/// `pybun lock --update-only NAME==VERSION`: move one package in
+/// pybun.lockb, with only the bumps its new version needs (see
+/// [`crate::lock_update`]).
+async fn run_lock_update(
+    args: &LockArgs,
+    spec: &str,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    let fail = |collector: &mut EventCollector, code: &str, message: String, hint: &str| {
+        collector.error_with_code(code, message.clone(), hint);
+        RenderDetail::error(message.clone(), json!({ "error": message }))
+    };
+    let failed = |collector: &mut EventCollector, message: String| {
+        fail(
+            collector,
+            "E_LOCK_UPDATE_FAILED",
+            message,
+            "Pass `--update-only NAME==VERSION` for a locked package and a version the index serves.",
+        )
+    };
+    let cwd = match std::env::current_dir() {
+        Ok(cwd) => cwd,
+        Err(e) => return failed(collector, e.to_string()),
+    };
+    let Ok(project) = Project::discover(&cwd) else {
+        return fail(
+            collector,
+            "E_LOCK_TARGET_REQUIRED",
+            "no pyproject.toml found in the current directory or any parent directory".to_string(),
+            "Run from inside a project with a pyproject.toml.",
+        );
+    };
+    let lock_path = cwd.join("pybun.lockb");
+    if !lock_path.exists() {
+        return fail(
+            collector,
+            "E_LOCK_MISSING",
+            format!("{} not found", lock_path.display()),
+            "Run `pybun lock` to create it.",
+        );
+    }
+    let before = match Lockfile::load_from_path(&lock_path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            return failed(
+                collector,
+                format!("cannot read lockfile {}: {}", lock_path.display(), e),
+            );
+        }
+    };
+
+    // The groups already locked stay locked, so their requirements hold the
+    // update back like the main dependencies do.
+    let mut groups = BTreeMap::from([(MAIN_GROUP.to_string(), project.dependencies())]);
+    let locked_groups: BTreeSet<&str> = before
+        .packages
+        .values()
+        .flat_map(|pkg| pkg.groups.iter().map(String::as_str))
+        .filter(|group| *group != MAIN_GROUP)
+        .collect();
+    for group in locked_groups {
+        let deps = project.group_dependencies(group);
+        if !deps.is_empty() {
+            groups.insert(group.to_string(), deps);
+        }
+    }
+    let roots: Vec<String> = groups.values().flatten().cloned().collect();
+
+    let (planned, source_index_url, dynamic_metadata) = if let Some(index_path) = &args.index {
+        let index = match load_index_from_path(index_path) {
+            Ok(index) => index,
+            Err(e) => return failed(collector, e.to_string()),
+        };
+        let planned =
+            crate::lock_update::plan(&before, &roots, spec, &before.python_versions, &index).await;
+        (planned, index_path.display().to_string(), BTreeSet::new())
+    } else {
+        let index = match RemoteIndex::from_env(args.offline) {
+            Ok(index) => index,
+            Err(e) => return failed(collector, format!("failed to init pypi client: {e}")),
+        };
+        let planned =
+            crate::lock_update::plan(&before, &roots, spec, &before.python_versions, &index).await;
+        for notice in index.take_stale_cache_notices() {
+            collector.warning(notice);
+        }
+        (
+            planned,
+            index.index_url(),
+            index.dynamic_metadata_packages(),
+        )
+    };
+    let planned = match planned {
+        Ok(planned) => planned,
+        Err(e @ crate::lock_update::UpdateError::Conflict { .. }) => {
+            return fail(
+                collector,
+                "E_LOCK_UPDATE_CONFLICT",
+                e.to_string(),
+                "Pick a version the listed requirements allow, or run `pybun upgrade PACKAGE` to re-resolve everything that depends on it.",
+            );
+        }
+        Err(e) => return failed(collector, e.to_string()),
+    };
+
+    let mut python_tags: Vec<String> = before
+        .python_versions
         .iter()
-        .map(|d| {
-            d.parse::<Requirement>()
-                .unwrap_or_else(|_| Requirement::any(d.trim()))
+        .filter_map(|version| python_version_to_cp_tag(version))
+        .collect();
+    if python_tags.is_empty() {
+        match active_lock_cp_tag() {
+            Ok(tag) => python_tags.push(tag),
+            Err(e) => return failed(collector, e.to_string()),
+        }
+    }
+    let platforms: Vec<Vec<String>> = before
+        .platforms
+        .iter()
+        .map(|platform| {
+            crate::tags::platform_tags_for(platform).unwrap_or_else(|_| current_platform_tags())
+        })
+        .collect();
+    let mut targets: Vec<LockTarget> = python_tags
+        .iter()
+        .flat_map(|cp_tag| {
+            platforms
+                .iter()
+                .map(move |tags| (cp_tag, tags, serde_json::Map::new()))
         })
         .collect();
+    let mut lock = before.clone();
+    let mut verified_artifacts = Vec::new();
+    for key in &planned.removed {
+        lock.packages.remove(key);
+    }
+    for pkg in &planned.packages {
+        let package = match lock_resolved_package(
+            pkg,
+            &mut targets,
+            !args.require_hashes && !args.offline,
+            &source_index_url,
+            has_dynamic_metadata(&dynamic_metadata, &pkg.name),
+            &mut verified_artifacts,
+            collector,
+        )
+        .await
+        {
+            Ok(package) => package,
+            Err(e) => return failed(collector, e.to_string()),
+        };
+        let name = crate::pypi::normalize_project_name(&pkg.name);
+        lock.packages
+            .retain(|key, _| crate::pypi::normalize_project_name(key) != name);
+        lock.add_package(package);
+    }
+    lock.assign_groups(&groups);
 
-    if dep_specs.is_empty() {
-        let lock = Lockfile::new(vec!["3.11".into()], vec!["unknown".into()]);
-        lock.save_to_path(&lock_path)?;
-        return Ok(LockOutcome {
-            summary: format!("no dependencies to lock -> {}", lock_path.display()),
-            lockfile: lock_path,
-            packages: Vec::new(),
-            verified: true,
-            artifacts: Vec::new(),
-        });
+    if !planned.changes.is_empty() {
+        if let Err(e) = lock.save_to_path(&lock_path) {
+            return failed(
+                collector,
+                format!("failed to write {}: {}", lock_path.display(), e),
+            );
+        }
+        record_project_activity("lock", &lock_path, None, collector);
     }
 
-    let source_index_url: String;
-    let offline = args.offline;
-    let resolve_options = ResolveOptions {
-        python_version: resolve_target_python_version(),
-        ..Default::default()
-    };
-    let resolution = if let Some(index_path) = args.index.clone() {
-        source_index_url = index_path.display().to_string();
-        let index = load_index_from_path(&index_path).map_err(|e| eyre!(e))?;
-        match resolve_with_options(requirements.clone(), &index, resolve_options).await {
-            Ok(r) => r,
-            Err(e) => {
-                for d in crate::self_heal::diagnostics_for_resolve_error(&requirements, &e) {
-                    collector.diagnostic(d);
-                }
-                return Err(eyre!(e.to_string()));
+    let file_diff =
+        change_diff::lockfile_changes(&change_label(&lock_path, &cwd), Some(&before), &lock);
+    let (diff, changes) = render_file_changes(&[&file_diff]);
+    let summary = match planned.changes.iter().find(|c| c.reason == "requested") {
+        Some(requested) => {
+            let mut summary = format!(
+                "updated {} {} -> {} ({} packages changed) -> {}",
+                requested.name,
+                requested.before.as_deref().unwrap_or_default(),
+                requested.after.as_deref().unwrap_or_default(),
+                planned.changes.len(),
+                lock_path.display()
+            );
+            for change in &planned.changes {
+                summary.push_str(&format!(
+                    "\n  {} {} -> {}: {}",
+                    change.name,
+                    change.before.as_deref().unwrap_or("(none)"),
+                    change.after.as_deref().unwrap_or("(removed)"),
+                    change.reason
+                ));
             }
+            summary
         }
-    } else {
-        let client = PyPiClient::from_env(offline)
-            .map_err(|e| eyre!("failed to init pypi client: {}", e))?;
-        source_index_url = client.index_url();
-        collector.info(format!(
-            "Using PyPI index {} (offline: {})",
-            source_index_url, offline
-        ));
-        let index = PyPiIndex::new(client);
-        let resolve_result =
-            resolve_with_options(requirements.clone(), &index, resolve_options).await;
-        for notice in index.take_stale_cache_notices() {
-            collector.warning(notice);
+        None => format!("{spec} is already locked; nothing to update"),
+    };
+    RenderDetail::with_json(
+        with_diff(summary, &diff),
+        json!({
+            "lockfile": lock_path.display().to_string(),
+            "package": spec.split("==").next().map(str::trim),
+            "version": spec.split("==").nth(1).map(str::trim),
+            "updated": !planned.changes.is_empty(),
+            "changeset": planned.changes,
+            "artifacts": verified_artifacts,
+            "changes": changes,
+            "diff": diff,
+        }),
+    )
+}
+
+/// `pybun script lock`: keep `<script>.lock` in step with the script's
+/// declared dependencies.
+async fn run_script_lock(
+    args: &crate::cli::ScriptLockArgs,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    let script = &args.script;
+    let fail = |collector: &mut EventCollector, code: &str, message: String, hint: &str| {
+        collector.error_with_code(code, message.clone(), hint);
+        RenderDetail::error(message.clone(), json!({ "error": message }))
+    };
+    if !script.exists() {
+        return fail(
+            collector,
+            "E_SCRIPT_LOCK_FAILED",
+            format!("script not found: {}", script.display()),
+            "Pass the path to a PEP 723 script.",
+        );
+    }
+    let declared = match pep723::parse_script_metadata(script) {
+        Ok(metadata) => metadata.map(|m| m.dependencies).unwrap_or_default(),
+        Err(e) => {
+            return fail(
+                collector,
+                "E_SCRIPT_LOCK_FAILED",
+                format!("failed to parse PEP 723 metadata: {e}"),
+                "Fix the `# /// script` block and retry.",
+            );
         }
-        match resolve_result {
-            Ok(r) => r,
-            Err(e) => {
-                for d in crate::self_heal::diagnostics_for_resolve_error(&requirements, &e) {
-                    collector.diagnostic(d);
-                }
-                return Err(eyre!(e.to_string()));
-            }
+    };
+    let lock_path = script_lock_path(script);
+    let previous = match load_script_lock(script) {
+        Ok(previous) => previous.map(|info| info.lock),
+        Err(e) => {
+            return fail(
+                collector,
+                "E_SCRIPT_LOCK_FAILED",
+                e.to_string(),
+                "Check permissions on the lockfile and retry.",
+            );
         }
     };
-    warn_on_prerelease_fallback(&resolution, collector);
-
-    collector.event_with(EventType::ResolveComplete, |event| {
-        event.message = Some("Resolved dependencies".to_string());
-        event.progress = Some(40);
+    let drift = previous
+        .as_ref()
+        .map(|lock| crate::script_lock::check(lock, &declared));
+    let mut detail = json!({
+        "script": script.display().to_string(),
+        "lockfile": lock_path.display().to_string(),
+        "declared": declared,
+        "lock_exists": previous.is_some(),
+        "drift": drift,
+        "up_to_date": drift.as_ref().is_some_and(|d| d.is_clean()),
+        "updated": false,
+        "changes": [],
     });
 
-    // Detect the CPython tag of the actual lock target's Python (PYBUN_ENV / PYBUN_PYTHON /
-    // project venv / system Python) *before* selecting wheels, so the wheel filenames recorded
-    // in the lockfile match the interpreter that will actually install them. Selecting wheels
-    // against whatever `python3`/`python` happens to resolve on PATH (the previous behavior)
-    // could silently record wheels for the wrong CPython ABI, producing the kind of
-    // `ImportError` #172's runtime compatibility check was built to detect after the fact
-    // (Issue #293; same root cause as #291, fixed for `pybun install` in #292). This is
-    // read-only detection only and covers both project-mode and `--script` PEP 723 locking,
-    // since both resolve the target interpreter relative to the current working directory
-    // (honoring PYBUN_ENV/PYBUN_PYTHON regardless of cwd).
-    let working_dir = std::env::current_dir()?;
-    let target_env_probe = crate::env::find_python_env(&working_dir)?;
-
-    // PYBUN_FORCE_CP_TAG lets tests (and users) pin the CPython tag deterministically,
-    // bypassing interpreter detection entirely.
-    let active_cp_tag = std::env::var("PYBUN_FORCE_CP_TAG")
-        .ok()
-        .filter(|v| !v.trim().is_empty())
-        .or_else(|| {
-            get_python_version(&target_env_probe.python_path)
-                .ok()
-                .and_then(|v| python_version_to_cp_tag(&v))
-        })
-        .unwrap_or_else(|| "cp311".to_string());
+    if args.check {
+        return match &drift {
+            None => fail(
+                collector,
+                "E_SCRIPT_LOCK_MISSING",
+                format!(
+                    "no lockfile for {} ({})",
+                    script.display(),
+                    lock_path.display()
+                ),
+                &format!("Run `pybun script lock {}` to create it.", script.display()),
+            ),
+            Some(drift) if !drift.is_clean() => {
+                let message = format!("script lock is out of date: {}", drift.describe());
+                collector.diagnostic(
+                    Diagnostic::error(message.clone())
+                        .with_code("E_SCRIPT_LOCK_DRIFT")
+                        .with_suggestion(format!(
+                            "Run `pybun script lock --upgrade {}` to re-resolve.",
+                            script.display()
+                        ))
+                        .with_context(json!(drift)),
+                );
+                RenderDetail::error(message, detail)
+            }
+            Some(_) => RenderDetail::with_json(
+                format!(
+                    "{} satisfies the declared dependencies",
+                    lock_path.display()
+                ),
+                detail,
+            ),
+        };
+    }
 
-    let platform_tags = current_platform_tags();
-    let mut lock = Lockfile::new(
-        vec!["3.11".into()],
-        vec![
-            platform_tags
-                .first()
-                .cloned()
-                .unwrap_or_else(|| "unknown".to_string()),
-        ],
-    );
-    let mut verified_artifacts = Vec::new();
+    if !args.upgrade && drift.as_ref().is_some_and(|d| d.is_clean()) {
+        return RenderDetail::with_json(format!("{} is up to date", lock_path.display()), detail);
+    }
 
-    for pkg in resolution.packages.values() {
-        let selection = select_artifact_for_platform_with_cp(pkg, &platform_tags, &active_cp_tag);
-        if selection.from_source {
-            let message = format!(
-                "no compatible pre-built wheel for {} {} on {}; falling back to source build",
-                pkg.name,
-                pkg.version,
-                platform_tags.join(",")
-            );
-            eprintln!("warning: {}", message);
-            collector.warning(message);
+    let lock_args = LockArgs {
+        script: Some(script.clone()),
+        offline: args.offline,
+        index: args.index.clone(),
+        resolver: Default::default(),
+        compare: false,
+        platform: Vec::new(),
+        python: Vec::new(),
+        require_hashes: false,
+        shards: false,
+        check_shards: false,
+        check: false,
+        from: None,
+        update_only: None,
+    };
+    let pre_error_count = collector.error_diagnostic_count();
+    let outcome = match lock_dependencies(&lock_args, collector).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            if collector.error_diagnostic_count() == pre_error_count {
+                collector.error_with_code(
+                    "E_SCRIPT_LOCK_FAILED",
+                    e.to_string(),
+                    "Check --index and network connectivity, then re-run `pybun script lock`.",
+                );
+            }
+            detail["error"] = json!(e.to_string());
+            return RenderDetail::error(e.to_string(), detail);
         }
-        let (verified_hash, artifact) =
-            ensure_selection_is_verifiable(pkg, &selection, collector, &source_index_url)?;
-        verified_artifacts.push(artifact);
-        lock.add_package(Package {
-            name: pkg.name.clone(),
-            version: pkg.version.clone(),
-            source: registry_source_for_index(&source_index_url),
-            wheel: selection.filename,
-            hash: verified_hash,
-            dependencies: pkg.dependencies.iter().map(ToString::to_string).collect(),
-        });
-    }
+    };
 
-    lock.save_to_path(&lock_path)?;
+    let updated = Lockfile::load_from_path(&outcome.lockfile).ok();
+    let changes = match (&previous, &updated) {
+        (Some(old), Some(new)) => script_lock_changes(old, new),
+        _ => Vec::new(),
+    };
+    detail["updated"] = json!(true);
+    detail["changes"] = json!(changes);
+    detail["packages"] = json!(outcome.packages);
+    detail["dynamic_metadata"] = json!(outcome.dynamic_metadata);
+    let summary = if changes.is_empty() {
+        outcome.summary
+    } else {
+        format!("{} ({} changed)", outcome.summary, changes.len())
+    };
+    RenderDetail::with_json(summary, detail)
+}
 
-    Ok(LockOutcome {
-        summary: format!(
-            "locked {} packages -> {}",
-            lock.packages.len(),
-            lock_path.display()
-        ),
-        lockfile: lock_path,
-        packages: lock.packages.keys().cloned().collect(),
-        verified: true,
-        artifacts: verified_artifacts,
-    })
+/// Per-package version changes between two locks (`from`/`to` are null for
+/// added/removed packages).
+fn script_lock_changes(old: &Lockfile, new: &Lockfile) -> Vec<Value> {
+    let names: BTreeSet<&String> = old.packages.keys().chain(new.packages.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let from = old.packages.get(name).map(|p| p.version.as_str());
+            let to = new.packages.get(name).map(|p| p.version.as_str());
+            (from != to).then(|| json!({ "name": name, "from": from, "to": to }))
+        })
+        .collect()
 }
 
 #[derive(Debug)]
@@ -2181,6 +5850,8 @@ struct RenderDetail {
     /// When set and non-zero, `execute` calls `std::process::exit` with this
     /// code after flushing output, so the shell sees the script's own code.
     process_exit_code: Option<i32>,
+    /// Tabular rows in `json` for `--format tsv` and `--columns`.
+    table: Option<TableSpec>,
 }
 
 impl RenderDetail {
@@ -2192,6 +5863,7 @@ impl RenderDetail {
             raw_text: false,
             silent: false,
             process_exit_code: None,
+            table: None,
         }
     }
 
@@ -2203,6 +5875,7 @@ impl RenderDetail {
             raw_text: false,
             silent: false,
             process_exit_code: None,
+            table: None,
         }
     }
 
@@ -2214,6 +5887,7 @@ impl RenderDetail {
             raw_text: true,
             silent: false,
             process_exit_code: None,
+            table: None,
         }
     }
 
@@ -2228,6 +5902,7 @@ impl RenderDetail {
             raw_text: false,
             silent: true,
             process_exit_code: None,
+            table: None,
         }
     }
 
@@ -2237,6 +5912,65 @@ impl RenderDetail {
         self.process_exit_code = Some(code);
         self
     }
+
+    /// Mark `json[spec.rows_key]` as the command's table so `--format tsv`
+    /// and `--columns` can render it.
+    fn with_table(mut self, spec: TableSpec) -> Self {
+        self.table = Some(spec);
+        self
+    }
+}
+
+/// Apply `--format tsv` / `--columns` to a command's detail: render TSV text
+/// for tsv output and narrow the row objects for JSON output.
+fn apply_table_output(
+    command: &str,
+    mut detail: RenderDetail,
+    format: OutputFormat,
+    columns: &[String],
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    if detail.is_error || detail.silent {
+        return detail;
+    }
+    let Some(spec) = detail.table else {
+        if format == OutputFormat::Tsv {
+            let message = format!("`pybun {command}` does not produce tabular output");
+            collector.error_with_code(
+                "E_FORMAT_UNSUPPORTED",
+                message.clone(),
+                "Use --format text or --format json for this command; tsv is supported by list commands such as `pybun outdated` and `pybun python list`.",
+            );
+            return RenderDetail::error(message.clone(), json!({ "error": message }));
+        }
+        return detail;
+    };
+    if format == OutputFormat::Text {
+        return detail;
+    }
+
+    let rows = detail.json[spec.rows_key]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let selected = match spec.select_columns(&rows, Some(columns)) {
+        Ok(selected) => selected,
+        Err(e) => {
+            collector.error_with_code(
+                "E_UNKNOWN_COLUMN",
+                e.to_string(),
+                "Pick --columns from the listed names; they match the JSON field names.",
+            );
+            return RenderDetail::error(e.to_string(), json!({ "error": e.to_string() }));
+        }
+    };
+    if format == OutputFormat::Tsv {
+        detail.text = crate::table::render_tsv(&rows, &selected);
+        detail.raw_text = true;
+    } else if !columns.is_empty() {
+        detail.json[spec.rows_key] = Value::Array(crate::table::project_rows(&rows, &selected));
+    }
+    detail
 }
 
 // ---------------------------------------------------------------------------
@@ -2258,6 +5992,20 @@ struct BuildOutcome {
     cache_hit: bool,
     cache_key: String,
     cache_dir: PathBuf,
+    source_date_epoch: u64,
+    epoch_source: reproducible::EpochSource,
+    /// Environment the backend ran with.
+    build_env: Vec<(String, String)>,
+    normalized: Vec<PathBuf>,
+    /// Artifacts of the two builds of `--check-reproducible`.
+    reproducibility: Option<Vec<reproducible::ArtifactComparison>>,
+}
+
+#[derive(Debug, Default)]
+struct BuildRun {
+    stdout: String,
+    stderr: String,
+    exit_code: i32,
 }
 
 fn run_build(
@@ -2279,19 +6027,34 @@ fn run_build(
         python_env.source
     ));
 
+    let mut configured_env = project.pybun_config().build.env;
+    configured_env.extend(args.env.iter().cloned());
+    let (source_date_epoch, epoch_source) =
+        reproducible::source_date_epoch(&project_root, &configured_env)
+            .map_err(|e| eyre!("{}", e))?;
+    collector.info(format!(
+        "Using SOURCE_DATE_EPOCH={} ({})",
+        source_date_epoch,
+        epoch_source.as_str()
+    ));
+
+    let build_env = reproducible::build_env(source_date_epoch, &configured_env);
+
     let backend = BuildBackend::from_build_system(project.build_system());
     let build_cache =
         BuildCache::new().map_err(|e| eyre!("failed to initialize build cache: {}", e))?;
     let cache_key = build_cache
-        .compute_cache_key(&project_root, &python_env.python_path, &backend)
+        .compute_cache_key(&project_root, &python_env.python_path, &backend, &build_env)
         .map_err(|e| eyre!("failed to compute build cache key: {}", e))?;
     let cache_dir = build_cache.cache_dir_for_key(&cache_key);
-    let no_cache = std::env::var("PYBUN_BUILD_NO_CACHE").is_ok();
+    // The reproducibility check must run the backend, so it skips the cache.
+    let no_cache = std::env::var("PYBUN_BUILD_NO_CACHE").is_ok() || args.check_reproducible;
+    let dist_dir = project_root.join("dist");
 
     let mut cache_hit = false;
     if !no_cache {
         cache_hit = build_cache
-            .restore_dist(&cache_key, &project_root.join("dist"))
+            .restore_dist(&cache_key, &dist_dir)
             .map_err(|e| eyre!("failed to restore build cache: {}", e))?;
         if cache_hit {
             collector.event(EventType::CacheHit);
@@ -2299,9 +6062,9 @@ fn run_build(
     }
 
     let builder = "python -m build".to_string();
-    let mut stdout = String::new();
-    let mut stderr = String::new();
-    let mut exit_code = 0;
+    let mut run = BuildRun::default();
+    let mut normalized = Vec::new();
+    let mut reproducibility = None;
 
     if !cache_hit {
         if !cache_dir.exists() {
@@ -2313,63 +6076,46 @@ fn run_build(
                 )
             })?;
         }
-        collector.event_with(EventType::Progress, |event| {
-            event.message = Some(format!(
-                "invoking python -m build (backend: {})",
-                backend.kind.as_str()
-            ));
-            event.progress = Some(30);
-        });
-
-        let mut cmd = ProcessCommand::new(&python_env.python_path);
-        cmd.current_dir(&project_root).args(["-m", "build"]);
-        for (key, value) in backend.env_overrides(&cache_dir) {
-            cmd.env(key, value);
-        }
-        let output = cmd
-            .output()
-            .map_err(|e| eyre!("failed to execute python -m build: {}", e))?;
-
-        exit_code = output.status.code().unwrap_or(-1);
-        stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        stderr = String::from_utf8_lossy(&output.stderr).to_string();
-
-        if matches!(format, OutputFormat::Text) {
-            if !stdout.trim().is_empty() {
-                println!("{stdout}");
-            }
-            if !stderr.trim().is_empty() {
-                eprintln!("{stderr}");
-            }
-        }
+        let build = |collector: &mut EventCollector| {
+            invoke_python_build(
+                &python_env.python_path,
+                &project_root,
+                &backend,
+                &cache_dir,
+                &build_env,
+                collector,
+                format,
+            )
+        };
 
-        if !output.status.success() {
-            // CPython 3.x emits "No module named 'build'" (with quotes); older builds may
-            // omit the quotes.  Check both forms to be safe.
-            let missing_build = stderr.contains("No module named 'build'")
-                || stderr.contains("No module named build");
-            if missing_build {
-                collector.diagnostic(
-                    Diagnostic::error("python -m build failed: No module named build")
-                        .with_code("E_BUILD_MISSING_BUILD_PKG")
-                        .with_suggestion("pybun add build --dev\n  or: pip install build"),
-                );
-                if matches!(format, OutputFormat::Text) {
-                    eprintln!("hint: Install the build package first: pybun add build --dev");
-                    eprintln!("      or: pip install build");
+        if args.check_reproducible {
+            // Both builds start from an empty dist/ so that only their own
+            // artifacts are compared.
+            let first_dir = cache_dir.join("reproducible-first");
+            for dir in [&dist_dir, &first_dir] {
+                if dir.exists() {
+                    fs::remove_dir_all(dir)
+                        .map_err(|e| eyre!("failed to clear {}: {}", dir.display(), e))?;
                 }
-                return Err(eyre!("python -m build failed: No module named build"));
             }
-            return Err(eyre!(
-                "python -m build failed with exit code {}.\nstdout:\n{}\nstderr:\n{}",
-                exit_code,
-                stdout,
-                stderr
-            ));
+            build(collector)?;
+            normalize_dist(&dist_dir, source_date_epoch)?;
+            fs::create_dir_all(&dist_dir)
+                .and_then(|_| fs::rename(&dist_dir, &first_dir))
+                .map_err(|e| eyre!("failed to keep the first build: {}", e))?;
+
+            run = build(collector)?;
+            normalized = normalize_dist(&dist_dir, source_date_epoch)?;
+            let comparisons = reproducible::compare_dirs(&first_dir, &dist_dir)
+                .map_err(|e| eyre!("failed to compare builds: {}", e))?;
+            let _ = fs::remove_dir_all(&first_dir);
+            reproducibility = Some(comparisons);
+        } else {
+            run = build(collector)?;
+            normalized = normalize_dist(&dist_dir, source_date_epoch)?;
         }
     }
 
-    let dist_dir = project_root.join("dist");
     let artifacts = collect_artifacts(&dist_dir)?;
     if !cache_hit {
         build_cache
@@ -2377,15 +6123,28 @@ fn run_build(
             .map_err(|e| eyre!("failed to store build cache: {}", e))?;
     }
 
-    let sbom = if args.sbom {
-        fs::create_dir_all(&dist_dir).map_err(|e| eyre!("failed to create dist dir: {}", e))?;
-        let sbom_path = dist_dir.join("pybun-sbom.json");
-        let metadata = project.metadata();
-        let summary = sbom::write_cyclonedx_sbom(&sbom_path, &metadata, &artifacts)
-            .map_err(|e| eyre!("failed to write sbom: {}", e))?;
-        Some(summary)
-    } else {
-        None
+    let sbom = match args.sbom {
+        Some(format) => {
+            // Without a lockfile the SBOM still describes the artifacts.
+            let graph = match maintenance::project_graph(None, "W_SBOM_NO_ENV", collector)? {
+                Some(graph) => graph,
+                None => {
+                    let metadata = project.metadata();
+                    crate::dep_graph::DependencyGraph::from_lock(
+                        &Lockfile::new(Vec::new(), Vec::new()),
+                        metadata.name.as_deref().unwrap_or("project"),
+                        metadata.version.as_deref().unwrap_or("0.0.0"),
+                        &[],
+                    )
+                }
+            };
+            let summary = sbom::Sbom::new(graph)
+                .with_artifacts(&artifacts)
+                .and_then(|sbom| sbom.write(format, &dist_dir.join(format.file_name())))
+                .map_err(|e| eyre!("failed to write sbom: {}", e))?;
+            Some(summary)
+        }
+        None => None,
     };
 
     let summary = if cache_hit {
@@ -2404,23 +6163,117 @@ fn run_build(
         )
     };
 
-    Ok(BuildOutcome {
-        summary,
-        dist_dir,
-        artifacts,
-        sbom,
+    Ok(BuildOutcome {
+        summary,
+        dist_dir,
+        artifacts,
+        sbom,
+        stdout: run.stdout,
+        stderr: run.stderr,
+        exit_code: run.exit_code,
+        builder,
+        python: python_env.python_path,
+        backend,
+        cache_hit,
+        cache_key,
+        cache_dir,
+        source_date_epoch,
+        epoch_source,
+        build_env,
+        normalized,
+        reproducibility,
+    })
+}
+
+/// Run `python -m build` in `project_root`, which writes to `dist/`.
+fn invoke_python_build(
+    python: &Path,
+    project_root: &Path,
+    backend: &BuildBackend,
+    cache_dir: &Path,
+    build_env: &[(String, String)],
+    collector: &mut EventCollector,
+    format: OutputFormat,
+) -> Result<BuildRun> {
+    collector.event_with(EventType::Progress, |event| {
+        event.message = Some(format!(
+            "invoking python -m build (backend: {})",
+            backend.kind.as_str()
+        ));
+        event.progress = Some(30);
+    });
+
+    let mut cmd = ProcessCommand::new(python);
+    cmd.current_dir(project_root).args(["-m", "build"]);
+    for (key, value) in backend.env_overrides(cache_dir) {
+        cmd.env(key, value);
+    }
+    for (key, value) in build_env {
+        cmd.env(key, value);
+    }
+    let output = cmd
+        .output()
+        .map_err(|e| eyre!("failed to execute python -m build: {}", e))?;
+
+    let exit_code = output.status.code().unwrap_or(-1);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    if matches!(format, OutputFormat::Text) {
+        if !stdout.trim().is_empty() {
+            println!("{stdout}");
+        }
+        if !stderr.trim().is_empty() {
+            eprintln!("{stderr}");
+        }
+    }
+
+    if !output.status.success() {
+        // CPython 3.x emits "No module named 'build'" (with quotes); older builds may
+        // omit the quotes.  Check both forms to be safe.
+        let missing_build =
+            stderr.contains("No module named 'build'") || stderr.contains("No module named build");
+        if missing_build {
+            collector.diagnostic(
+                Diagnostic::error("python -m build failed: No module named build")
+                    .with_code("E_BUILD_MISSING_BUILD_PKG")
+                    .with_suggestion("pybun add build --dev\n  or: pip install build"),
+            );
+            if matches!(format, OutputFormat::Text) {
+                eprintln!("hint: Install the build package first: pybun add build --dev");
+                eprintln!("      or: pip install build");
+            }
+            return Err(eyre!("python -m build failed: No module named build"));
+        }
+        return Err(eyre!(
+            "python -m build failed with exit code {}.\nstdout:\n{}\nstderr:\n{}",
+            exit_code,
+            stdout,
+            stderr
+        ));
+    }
+
+    Ok(BuildRun {
         stdout,
         stderr,
         exit_code,
-        builder,
-        python: python_env.python_path,
-        backend,
-        cache_hit,
-        cache_key,
-        cache_dir,
     })
 }
 
+/// Rewrite the archives in `dist_dir` deterministically; returns the ones
+/// rewritten.
+fn normalize_dist(dist_dir: &Path, source_date_epoch: u64) -> Result<Vec<PathBuf>> {
+    let mut normalized = Vec::new();
+    for artifact in collect_artifacts(dist_dir)? {
+        if reproducible::normalize_artifact(&artifact, source_date_epoch)
+            .map_err(|e| eyre!("failed to normalize {}: {}", artifact.display(), e))?
+        {
+            normalized.push(artifact);
+        }
+    }
+    Ok(normalized)
+}
+
 fn collect_artifacts(dist_dir: &Path) -> Result<Vec<PathBuf>> {
     if !dist_dir.exists() {
         return Ok(Vec::new());
@@ -2435,6 +6288,7 @@ fn collect_artifacts(dist_dir: &Path) -> Result<Vec<PathBuf>> {
             artifacts.push(entry.path());
         }
     }
+    artifacts.sort();
 
     Ok(artifacts)
 }
@@ -2454,6 +6308,7 @@ struct AddOutcome {
     summary: String,
     packages: Vec<AddedPackage>,
     added_deps: Vec<String>,
+    pyproject: FileDiff,
 }
 
 fn add_package(args: &crate::cli::PackageArgs) -> Result<AddOutcome> {
@@ -2471,6 +6326,7 @@ fn add_package(args: &crate::cli::PackageArgs) -> Result<AddOutcome> {
             Project::new(&path)
         }
     };
+    let before = fs::read_to_string(project.path()).unwrap_or_default();
 
     let mut packages = Vec::with_capacity(args.packages.len());
     for package_spec in &args.packages {
@@ -2486,7 +6342,10 @@ fn add_package(args: &crate::cli::PackageArgs) -> Result<AddOutcome> {
         // here.
 
         // Add to pyproject.toml
-        project.add_dependency(package_spec);
+        match &args.group {
+            Some(group) => project.add_group_dependency(group, package_spec),
+            None => project.add_dependency(package_spec),
+        }
 
         let version = match req.specs.as_slice() {
             [crate::resolver::VersionSpec::Any] => None,
@@ -2511,15 +6370,32 @@ fn add_package(args: &crate::cli::PackageArgs) -> Result<AddOutcome> {
     }
 
     project.save()?;
-    let added_deps = project.dependencies();
+    let added_deps = match &args.group {
+        Some(group) => project.group_dependencies(group),
+        None => project.dependencies(),
+    };
+    let pyproject = change_diff::pyproject_changes(
+        &change_label(project.path(), &current_dir),
+        &before,
+        &fs::read_to_string(project.path())?,
+    );
 
     let package_list = args.packages.join(", ");
-    let summary = format!("added {} to {}", package_list, project.path().display());
+    let summary = match &args.group {
+        Some(group) => format!(
+            "added {} to group '{}' in {}",
+            package_list,
+            group,
+            project.path().display()
+        ),
+        None => format!("added {} to {}", package_list, project.path().display()),
+    };
 
     Ok(AddOutcome {
         summary,
         packages,
         added_deps,
+        pyproject,
     })
 }
 
@@ -2537,6 +6413,7 @@ struct RemovedPackage {
 struct RemoveOutcome {
     summary: String,
     packages: Vec<RemovedPackage>,
+    pyproject: FileDiff,
 }
 
 fn remove_package(args: &crate::cli::PackageArgs) -> Result<RemoveOutcome> {
@@ -2553,11 +6430,16 @@ fn remove_package(args: &crate::cli::PackageArgs) -> Result<RemoveOutcome> {
         )
     })?;
 
+    let before = fs::read_to_string(project.path())?;
+
     let mut packages = Vec::with_capacity(args.packages.len());
     let mut removed_names = Vec::new();
     let mut not_found_names = Vec::new();
     for package_name in &args.packages {
-        let removed = project.remove_dependency(package_name);
+        let removed = match &args.group {
+            Some(group) => project.remove_group_dependency(group, package_name),
+            None => project.remove_dependency(package_name),
+        };
         if removed {
             removed_names.push(package_name.clone());
         } else {
@@ -2569,10 +6451,20 @@ fn remove_package(args: &crate::cli::PackageArgs) -> Result<RemoveOutcome> {
         });
     }
 
+    let mut pyproject = FileDiff::default();
     if !removed_names.is_empty() {
         project.save()?;
+        pyproject = change_diff::pyproject_changes(
+            &change_label(project.path(), &current_dir),
+            &before,
+            &fs::read_to_string(project.path())?,
+        );
     }
 
+    let section = match &args.group {
+        Some(group) => format!("group '{group}'"),
+        None => "dependencies".to_string(),
+    };
     let summary = match (removed_names.is_empty(), not_found_names.is_empty()) {
         (false, true) => format!(
             "removed {} from {}",
@@ -2580,19 +6472,420 @@ fn remove_package(args: &crate::cli::PackageArgs) -> Result<RemoveOutcome> {
             project.path().display()
         ),
         (true, false) => format!(
-            "{} was not found in dependencies",
-            not_found_names.join(", ")
+            "{} was not found in {}",
+            not_found_names.join(", "),
+            section
         ),
         (false, false) => format!(
-            "removed {} from {}; {} was not found in dependencies",
+            "removed {} from {}; {} was not found in {}",
             removed_names.join(", "),
             project.path().display(),
-            not_found_names.join(", ")
+            not_found_names.join(", "),
+            section
         ),
         (true, true) => unreachable!("at least one package is always processed"),
     };
 
-    Ok(RemoveOutcome { summary, packages })
+    Ok(RemoveOutcome {
+        summary,
+        packages,
+        pyproject,
+    })
+}
+
+#[derive(Debug)]
+struct RenameDepOutcome {
+    summary: String,
+    /// `pyproject.toml` sections the dependency was renamed in.
+    sections: Vec<String>,
+    /// Module rename applied to imports, when requested.
+    imports: Option<(String, String)>,
+    /// Python files whose imports were (or would be) rewritten, with the
+    /// lines that need a manual look.
+    files: Vec<Value>,
+    relocked: bool,
+    history: Option<PathBuf>,
+    diffs: Vec<FileDiff>,
+}
+
+/// `pybun rename-dep`: rename a dependency in `pyproject.toml`, rewrite the
+/// imports found through the project's import index, and re-lock. Every file
+/// is written together and restored if re-locking fails.
+async fn rename_dep(
+    args: &crate::cli::RenameDepArgs,
+    collector: &mut EventCollector,
+) -> Result<RenameDepOutcome> {
+    let cwd = std::env::current_dir()?;
+    let mut project = Project::discover(&cwd).map_err(|_| {
+        eyre!(
+            "pyproject.toml not found in {} or any parent directory",
+            cwd.display()
+        )
+    })?;
+    let root = project.root().to_path_buf();
+    let pyproject_before = fs::read_to_string(project.path())?;
+    let sections = project.rename_dependency(&args.old, &args.new);
+    if sections.is_empty() {
+        return Err(eyre!(
+            "{} is not declared in {}",
+            args.old,
+            project.path().display()
+        ));
+    }
+    let pyproject_after = project.to_toml_string()?;
+    let mut edits = vec![crate::dep_rename::FileEdit {
+        path: project.path().to_path_buf(),
+        before: pyproject_before,
+        after: pyproject_after,
+    }];
+    let mut diffs = vec![change_diff::pyproject_changes(
+        &change_label(project.path(), &cwd),
+        &edits[0].before,
+        &edits[0].after,
+    )];
+
+    let imports = match args.imports.as_deref() {
+        None => None,
+        Some("") => {
+            let from = crate::drift::import_names(&args.old);
+            let to = crate::drift::import_names(&args.new);
+            match (from.as_slice(), to.as_slice()) {
+                ([from], [to]) => Some((from.clone(), to.clone())),
+                _ => {
+                    return Err(eyre!(
+                        "cannot tell which modules {} and {} provide; pass --imports OLD_MODULE=NEW_MODULE",
+                        args.old,
+                        args.new
+                    ));
+                }
+            }
+        }
+        Some(mapping) => match mapping.split_once('=') {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+                Some((from.trim().to_string(), to.trim().to_string()))
+            }
+            _ => {
+                return Err(eyre!(
+                    "invalid --imports value '{mapping}'; expected OLD_MODULE=NEW_MODULE"
+                ));
+            }
+        },
+    };
+
+    let mut files = Vec::new();
+    if let Some((from, to)) = imports.as_ref().filter(|(from, to)| from != to) {
+        let py_files = crate::drift::collect_py_files(&root);
+        let index = crate::drift::scan_imports(&root, &py_files);
+        let affected: BTreeSet<String> = index
+            .get(from)
+            .into_iter()
+            .flatten()
+            .map(|loc| loc.file.clone())
+            .collect();
+        for file in affected {
+            let path = root.join(&file);
+            let before = fs::read_to_string(&path)?;
+            let rewrite = crate::dep_rename::rewrite_imports(&before, from, to);
+            if rewrite.lines.is_empty() {
+                continue;
+            }
+            for line in &rewrite.review {
+                collector.warning(format!(
+                    "{file}:{line}: qualified uses of {from} after this import are not rewritten; review them"
+                ));
+            }
+            files.push(json!({
+                "file": file,
+                "lines": rewrite.lines,
+                "review": rewrite.review,
+            }));
+            diffs.push(FileDiff {
+                changes: Vec::new(),
+                diff: change_diff::unified_diff(&change_label(&path, &cwd), &before, &rewrite.text),
+            });
+            edits.push(crate::dep_rename::FileEdit {
+                path,
+                before,
+                after: rewrite.text,
+            });
+        }
+    }
+
+    let target = match &imports {
+        Some(_) => format!(
+            "{} and {} Python file(s)",
+            change_label(project.path(), &cwd),
+            files.len()
+        ),
+        None => change_label(project.path(), &cwd),
+    };
+    if !args.apply {
+        return Ok(RenameDepOutcome {
+            summary: format!(
+                "would rename {} to {} in {}; re-run with --apply to write the changes",
+                args.old, args.new, target
+            ),
+            sections,
+            imports,
+            files,
+            relocked: false,
+            history: None,
+            diffs,
+        });
+    }
+
+    let lock_path = cwd.join("pybun.lockb");
+    let lock_before = fs::read(&lock_path).ok();
+    crate::dep_rename::apply(&edits)?;
+    let mut relocked = false;
+    if let Some(bytes) = &lock_before {
+        let previous = Lockfile::load_from_path(&lock_path).ok();
+        let lock_args = LockArgs {
+            script: None,
+            offline: args.offline,
+            index: args.index.clone(),
+            resolver: Default::default(),
+            compare: false,
+            platform: previous
+                .iter()
+                .flat_map(|lock| &lock.platforms)
+                .filter(|platform| crate::tags::platform_tags_for(platform).is_ok())
+                .cloned()
+                .collect(),
+            python: previous
+                .iter()
+                .flat_map(|lock| &lock.python_versions)
+                .cloned()
+                .collect(),
+            require_hashes: false,
+            shards: false,
+            check_shards: false,
+            check: false,
+            from: None,
+            update_only: None,
+        };
+        if let Err(e) = lock_dependencies(&lock_args, collector).await {
+            let _ = crate::dep_rename::restore(&edits);
+            let _ = fs::write(&lock_path, bytes);
+            return Err(eyre!("re-locking failed, no files were changed: {e}"));
+        }
+        relocked = true;
+        if let Ok(after) = Lockfile::load_from_path(&lock_path) {
+            diffs.push(change_diff::lockfile_changes(
+                &change_label(&lock_path, &cwd),
+                previous.as_ref(),
+                &after,
+            ));
+        }
+        record_project_activity("rename-dep", &lock_path, None, collector);
+    }
+
+    let mut changed: Vec<String> = edits
+        .iter()
+        .map(|edit| change_label(&edit.path, &root))
+        .collect();
+    if relocked {
+        changed.push(change_label(&lock_path, &root));
+    }
+    let entry = crate::command_history::HistoryEntry::new(
+        "rename-dep",
+        changed,
+        json!({
+            "old": args.old,
+            "new": args.new,
+            "sections": sections,
+            "imports": imports.as_ref().map(|(from, to)| json!({ "from": from, "to": to })),
+        }),
+    );
+    let history = match crate::command_history::append(&root, &entry) {
+        Ok(path) => Some(path),
+        Err(e) => {
+            collector.warning(format!("could not record the rename in history: {e}"));
+            None
+        }
+    };
+
+    Ok(RenameDepOutcome {
+        summary: format!("renamed {} to {} in {}", args.old, args.new, target),
+        sections,
+        imports,
+        files,
+        relocked,
+        history,
+        diffs,
+    })
+}
+
+struct ImportOutcome {
+    summary: String,
+    imported: crate::dep_import::Imported,
+    /// `requires-python` written to pyproject.toml, if it had none.
+    requires_python: Option<String>,
+    locked: bool,
+    diffs: Vec<FileDiff>,
+}
+
+/// `pybun import`: add the requirements of a requirements file, Pipfile or
+/// Poetry pyproject.toml to the project and lock them. pyproject.toml and the
+/// lockfile are restored if locking fails.
+async fn import_dependencies(
+    args: &crate::cli::ImportArgs,
+    collector: &mut EventCollector,
+) -> Result<ImportOutcome> {
+    let cwd = std::env::current_dir()?;
+    let imported = crate::dep_import::import(&args.file)?;
+    if imported.count() == 0 {
+        return Err(eyre!(
+            "no requirements to import from {}",
+            args.file.display()
+        ));
+    }
+    for skipped in &imported.skipped {
+        collector.diagnostic(
+            Diagnostic::warning(format!("skipped `{}`: {}", skipped.entry, skipped.reason))
+                .with_code("W_IMPORT_SKIPPED")
+                .with_suggestion("Add the dependency by hand with `pybun add` if it is needed."),
+        );
+    }
+    if !imported.indexes.is_empty() {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "{} names package indexes ({}) that are not imported",
+                args.file.display(),
+                imported.indexes.join(", ")
+            ))
+            .with_code("W_IMPORT_INDEX")
+            .with_suggestion("Set PYBUN_INDEX_URL to resolve against a different index."),
+        );
+    }
+
+    let mut project =
+        Project::discover(&cwd).unwrap_or_else(|_| Project::new(cwd.join("pyproject.toml")));
+    let pyproject_before = fs::read_to_string(project.path()).ok();
+    match &args.group {
+        Some(group) if !imported.dependencies.is_empty() => {
+            project.add_group_dependencies(group, &imported.dependencies)
+        }
+        Some(_) => {}
+        None if !imported.dependencies.is_empty() => {
+            project.add_dependencies(&imported.dependencies)
+        }
+        None => {}
+    }
+    for (group, deps) in &imported.groups {
+        project.add_group_dependencies(group, deps);
+    }
+    for (extra, deps) in &imported.extras {
+        project.add_optional_dependencies(extra, deps);
+    }
+    let requires_python = match (&imported.requires_python, project.requires_python()) {
+        (Some(wanted), None) => {
+            project.set_requires_python(wanted);
+            Some(wanted.clone())
+        }
+        (Some(wanted), Some(current)) if *wanted != current => {
+            collector.warning(format!(
+                "kept requires-python = \"{current}\"; {} asks for \"{wanted}\"",
+                args.file.display()
+            ));
+            None
+        }
+        _ => None,
+    };
+    project.save()?;
+    let mut diffs = vec![change_diff::pyproject_changes(
+        &change_label(project.path(), &cwd),
+        pyproject_before.as_deref().unwrap_or_default(),
+        &fs::read_to_string(project.path())?,
+    )];
+
+    let mut locked = false;
+    if !args.no_lock {
+        let lock_path = cwd.join("pybun.lockb");
+        let lock_before = fs::read(&lock_path).ok();
+        let previous = Lockfile::load_from_path(&lock_path).ok();
+        let lock_args = LockArgs {
+            script: None,
+            offline: args.offline,
+            index: args.index.clone(),
+            resolver: Default::default(),
+            compare: false,
+            platform: previous
+                .iter()
+                .flat_map(|lock| &lock.platforms)
+                .filter(|platform| crate::tags::platform_tags_for(platform).is_ok())
+                .cloned()
+                .collect(),
+            python: previous
+                .iter()
+                .flat_map(|lock| &lock.python_versions)
+                .cloned()
+                .collect(),
+            require_hashes: false,
+            shards: false,
+            check_shards: false,
+            check: false,
+            from: None,
+            update_only: None,
+        };
+        if let Err(e) = lock_dependencies(&lock_args, collector).await {
+            let _ = match &pyproject_before {
+                Some(content) => fs::write(project.path(), content),
+                None => fs::remove_file(project.path()),
+            };
+            let _ = match &lock_before {
+                Some(bytes) => fs::write(&lock_path, bytes),
+                None => fs::remove_file(&lock_path),
+            };
+            return Err(eyre!("locking failed, no files were changed: {e}"));
+        }
+        locked = true;
+        if let Ok(after) = Lockfile::load_from_path(&lock_path) {
+            diffs.push(change_diff::lockfile_changes(
+                &change_label(&lock_path, &cwd),
+                previous.as_ref(),
+                &after,
+            ));
+        }
+        record_project_activity("import", &lock_path, None, collector);
+    }
+
+    let summary = format!(
+        "imported {} requirement(s) from {} into {}{}",
+        imported.count(),
+        args.file.display(),
+        change_label(project.path(), &cwd),
+        if locked { " and locked them" } else { "" }
+    );
+    Ok(ImportOutcome {
+        summary,
+        imported,
+        requires_python,
+        locked,
+        diffs,
+    })
+}
+
+/// `path` relative to `cwd` when it is inside it, for diff headers.
+fn change_label(path: &Path, cwd: &Path) -> String {
+    path.strip_prefix(cwd).unwrap_or(path).display().to_string()
+}
+
+/// The unified diff (text output) and change list (JSON output) of the
+/// files a command modified.
+fn render_file_changes(diffs: &[&FileDiff]) -> (String, Value) {
+    let diff: String = diffs.iter().map(|d| d.diff.as_str()).collect();
+    let changes: Vec<&change_diff::Change> = diffs.iter().flat_map(|d| &d.changes).collect();
+    (diff, json!(changes))
+}
+
+/// `summary` followed by `diff`, when there is one.
+fn with_diff(summary: String, diff: &str) -> String {
+    if diff.is_empty() {
+        summary
+    } else {
+        format!("{}\n\n{}", summary, diff.trim_end())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -2620,13 +6913,21 @@ pub(crate) struct RunOutcome {
     /// Whether the environment was a cache hit
     pub(crate) cache_hit: bool,
     /// Captured stdout (only when `--format=json`).
-    pub(crate) stdout: Option<String>,
+    pub(crate) stdout: Option<crate::payload::CapturedOutput>,
     /// Captured stderr (only when `--format=json`).
-    pub(crate) stderr: Option<String>,
+    pub(crate) stderr: Option<crate::payload::CapturedOutput>,
     /// Sandbox information when enabled
     pub(crate) sandbox: Option<SandboxInfo>,
     /// Applied launch profile info
     pub(crate) profile: RunProfileInfo,
+    /// Console script the target named, when it was not a file.
+    pub(crate) entry_point: Option<crate::entry_points::EntryPoint>,
+    /// Text encoding environment the child ran with.
+    pub(crate) encoding: crate::encoding::EncodingSetup,
+    /// Dependency install done for a PEP 723 environment.
+    pub(crate) install: Option<env_install::EnvInstall>,
+    /// What the lazy-import hook deferred (only with `--lazy-imports`).
+    pub(crate) lazy_imports: Option<crate::lazy_import::LazyImportReport>,
 }
 
 #[derive(Debug, Clone)]
@@ -2638,6 +6939,43 @@ pub(crate) struct RunProfileInfo {
     pub(crate) timing: bool,
 }
 
+/// Set the text encoding environment of a `pybun run` child (see
+/// [`crate::encoding`]) and warn when it will use a non-UTF-8 locale.
+fn apply_encoding(
+    cmd: &mut ProcessCommand,
+    collector: &mut EventCollector,
+    format: OutputFormat,
+) -> Result<crate::encoding::EncodingSetup> {
+    use crate::encoding::{EncodingPolicy, EncodingSetup, LocaleInfo};
+
+    let configured = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| Project::discover(cwd).ok())
+        .and_then(|project| project.pybun_config().encoding);
+    let policy = EncodingPolicy::resolve(configured)?;
+    let setup = EncodingSetup::new(policy, LocaleInfo::detect(), |name| {
+        std::env::var(name).ok()
+    });
+    for (name, value) in &setup.env {
+        cmd.env(name, value);
+    }
+    if let Some(warning) = setup.warning() {
+        // Text mode usually exec()s into Python, so the collector would never
+        // be rendered.
+        if format.is_json() {
+            collector.diagnostic(
+                Diagnostic::warning(warning)
+                    .with_code("W_LOCALE_NOT_UTF8")
+                    .with_suggestion("Use a UTF-8 locale (e.g. LANG=C.UTF-8), or drop the opt-out so PyBun runs Python in UTF-8 mode.")
+                    .with_context(json!({ "locale": setup.locale, "policy": setup.policy })),
+            );
+        } else {
+            eprintln!("warning: {warning}");
+        }
+    }
+    Ok(setup)
+}
+
 /// Emit a `warn`-level diagnostic for each resource limit that was requested
 /// but cannot be enforced on the current platform (Issue #203).
 fn emit_unsupported_resource_limit_diagnostics(
@@ -2665,10 +7003,167 @@ fn emit_rejected_allow_env_diagnostics(collector: &mut EventCollector, rejected_
     }
 }
 
+/// Directories a `--sandbox` process may write to besides the temp dir: the
+/// project root containing each of `dirs` (else the directory itself) and
+/// the environment of `python`, when it is a virtual environment.
+fn sandbox_write_dirs(dirs: &[&Path], python: &Path) -> Vec<PathBuf> {
+    let mut writable = BTreeSet::new();
+    for dir in dirs {
+        match Project::discover(dir) {
+            Ok(project) => writable.insert(project.root().to_path_buf()),
+            Err(_) => writable.insert(dir.to_path_buf()),
+        };
+    }
+    let prefix = python.parent().and_then(Path::parent);
+    if let Some(prefix) = prefix.filter(|prefix| prefix.join("pyvenv.cfg").is_file()) {
+        writable.insert(prefix.to_path_buf());
+    }
+    writable.into_iter().collect()
+}
+
+/// Network access and host allowlist of a sandboxed run. `--allow-host`
+/// limits the run to the given hosts, minus any the project's network
+/// policy refuses; otherwise `--allow-network` is limited by the policy.
+fn sandbox_network(
+    flags: &crate::cli::SandboxArgs,
+    collector: &mut EventCollector,
+) -> (bool, Option<String>) {
+    let operation = network_policy::Operation::Run;
+    if flags.allow_host.is_empty() {
+        let allow_network = flags.allow_network
+            || std::env::var("PYBUN_SANDBOX_ALLOW_NETWORK")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false);
+        return (allow_network, network_policy::child_allowlist(operation));
+    }
+    let policy = network_policy::current();
+    let (hosts, refused): (Vec<&String>, Vec<&String>) = flags
+        .allow_host
+        .iter()
+        .partition(|host| policy.is_none_or(|policy| policy.allows_host(operation, host)));
+    for host in refused {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "--allow-host={host} was ignored because the project's network policy does not allow it for `run`"
+            ))
+            .with_code("W_SANDBOX_HOST_NOT_ALLOWED")
+            .with_suggestion("Add the host to `run` in [tool.pybun.network] to allow it."),
+        );
+    }
+    (true, serde_json::to_string(&hosts).ok())
+}
+
+/// Apply `--sandbox` to `cmd`, confining writes to `writable` (see
+/// [`sandbox_write_dirs`]) unless `--allow-write` is given. Returns the
+/// guard keeping the sandbox's helper files alive and the policy reported
+/// in `detail.sandbox`.
+fn apply_sandbox_args(
+    cmd: &mut ProcessCommand,
+    flags: &crate::cli::SandboxArgs,
+    writable: Vec<PathBuf>,
+    description: &str,
+    collector: &mut EventCollector,
+) -> Result<(sandbox::SandboxGuard, SandboxInfo)> {
+    let (allow_network, allow_hosts) = sandbox_network(flags, collector);
+    collector.info(format!(
+        "sandbox enabled for {} (allow_network={})",
+        description, allow_network
+    ));
+    let guard = sandbox::apply_python_sandbox(
+        cmd,
+        sandbox::SandboxConfig {
+            allow_network,
+            allow_read: flags.allow_read.clone(),
+            allow_write: flags.allow_write.clone(),
+            allow_env: flags.allow_env.clone(),
+            timeout_secs: flags.timeout,
+            memory_limit_mb: flags.memory,
+            cpu_limit_secs: flags.cpu,
+            allow_hosts,
+            confine_write: writable,
+            ..Default::default()
+        },
+    )?;
+    emit_unsupported_resource_limit_diagnostics(collector, &guard.resource_limits);
+    emit_rejected_allow_env_diagnostics(collector, &guard.rejected_env);
+    let info = SandboxInfo {
+        enabled: true,
+        allow_network,
+        allow_host: flags.allow_host.clone(),
+        allow_read: flags.allow_read.clone(),
+        allow_write: flags.allow_write.clone(),
+        allow_env: guard.allow_env.clone(),
+        default_deny_write: guard.default_deny_write.clone(),
+        enforcement: guard.enforcement().to_string(),
+        audit: None,
+        resource_limits: guard.resource_limits.clone(),
+        timed_out: false,
+    };
+    Ok((guard, info))
+}
+
+/// `detail.sandbox` of `pybun run` and `pybun x`.
+fn sandbox_json(s: &SandboxInfo) -> Value {
+    json!({
+        "enabled": s.enabled,
+        "allow_network": s.allow_network,
+        "allow_host": s.allow_host,
+        "allow_read": s.allow_read,
+        "allow_write": s.allow_write,
+        "allow_env": s.allow_env,
+        "default_deny_write": s.default_deny_write,
+        "enforcement": s.enforcement,
+        "audit": s.audit,
+        "resource_limits": s.resource_limits,
+        "timed_out": s.timed_out,
+    })
+}
+
+/// Record the audit of a finished sandboxed process in `info`, and report
+/// it being killed by the timeout or the CPU limit.
+fn finish_sandbox(
+    guard: sandbox::SandboxGuard,
+    info: &mut SandboxInfo,
+    status: &std::process::ExitStatus,
+    timed_out: bool,
+    collector: &mut EventCollector,
+) {
+    // Read audit before dropping the guard (guard keeps the audit file alive).
+    let audit = guard.read_audit();
+    network_policy::record_blocked_hosts(
+        network_policy::Operation::Run,
+        audit.blocked_hosts.iter().map(String::as_str),
+    );
+    info.audit = Some(audit);
+    info.timed_out = timed_out;
+    let limits = &guard.resource_limits;
+    if timed_out {
+        collector.diagnostic(
+            Diagnostic::error(format!(
+                "sandboxed process killed after exceeding --sandbox-timeout={}s",
+                limits.timeout_secs
+            ))
+            .with_code("E_SANDBOX_TIMEOUT")
+            .with_suggestion("increase --sandbox-timeout, set --sandbox-timeout=0 to disable, or optimize the script to finish sooner"),
+        );
+    } else if limits.cpu_limit_secs > 0 && sandbox::cpu_limit_exceeded(status) {
+        collector.diagnostic(
+            Diagnostic::error(format!(
+                "sandboxed process killed after exceeding --sandbox-cpu={}s of CPU time",
+                limits.cpu_limit_secs
+            ))
+            .with_code("E_SANDBOX_CPU_LIMIT")
+            .with_suggestion("increase --sandbox-cpu, set --sandbox-cpu=0 to disable, or optimize the script to use less CPU time"),
+        );
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct SandboxInfo {
     pub(crate) enabled: bool,
     pub(crate) allow_network: bool,
+    /// Hosts given with `--allow-host`.
+    pub(crate) allow_host: Vec<String>,
     pub(crate) allow_read: Vec<String>,
     pub(crate) allow_write: Vec<String>,
     /// Env var *names* (never values) that were explicitly allowed through the env filter.
@@ -2683,7 +7178,10 @@ pub(crate) struct SandboxInfo {
 #[derive(Debug)]
 enum RunProgram {
     Python(String),
-    Uv { uv_path: PathBuf },
+    Uv {
+        uv_path: PathBuf,
+        python: Option<String>,
+    },
 }
 
 fn script_lock_path(script_path: &Path) -> PathBuf {
@@ -2758,21 +7256,23 @@ fn split_env_list(raw: &str) -> Vec<String> {
 
 const MAX_RUN_STDIO_CAPTURE_BYTES: usize = 64 * 1024;
 
-fn capture_stdio(bytes: &[u8]) -> Option<String> {
-    if bytes.is_empty() {
+/// Keep captured output for the envelope: spilled output stays a reference,
+/// inline output is cut at [`MAX_RUN_STDIO_CAPTURE_BYTES`].
+fn capture_stdio(
+    mut output: crate::payload::CapturedOutput,
+) -> Option<crate::payload::CapturedOutput> {
+    if output.is_empty() {
         return None;
     }
-    let truncated = bytes.len() > MAX_RUN_STDIO_CAPTURE_BYTES;
-    let slice = if truncated {
-        &bytes[..MAX_RUN_STDIO_CAPTURE_BYTES]
-    } else {
-        bytes
-    };
-    let mut out = String::from_utf8_lossy(slice).to_string();
-    if truncated {
-        out.push_str("\n...[truncated]");
+    if !output.is_spilled() && output.text.len() > MAX_RUN_STDIO_CAPTURE_BYTES {
+        let mut end = MAX_RUN_STDIO_CAPTURE_BYTES;
+        while !output.text.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.text.truncate(end);
+        output.text.push_str("\n...[truncated]");
     }
-    Some(out)
+    Some(output)
 }
 
 pub(crate) async fn run_script(
@@ -2786,11 +7286,22 @@ pub(crate) async fn run_script(
         .profile
         .parse()
         .map_err(|e: String| eyre!("invalid --profile value: {}", e))?;
-    let profile_config = ProfileConfig::for_profile(profile);
+    let mut profile_config = ProfileConfig::for_profile(profile);
+    profile_config.lazy_imports |= args.lazy_imports;
 
     // -c/--code: execute inline Python code, like `python -c "..."`.
     if let Some(code) = &args.code {
-        return run_python_code(args, code, collector, format);
+        if let Some(policy) = crate::execution_policy::current()
+            && let Err(violation) = policy.check_code()
+        {
+            collector.diagnostic(violation.diagnostic());
+            return Err(eyre!(violation));
+        }
+        return run_python_code(args, PythonTarget::Code(code), collector, format);
+    }
+    // -m/--module: run a module, like `python -m module`.
+    if let Some(module) = &args.module {
+        return run_python_code(args, PythonTarget::Module(module), collector, format);
     }
 
     let target = args
@@ -2801,9 +7312,19 @@ pub(crate) async fn run_script(
     // Check if it's a Python file
     let script_path = PathBuf::from(target);
 
-    // Ensure the script exists
+    // Ensure the script exists; a bare name may be a console script instead.
     if !script_path.exists() {
-        return Err(eyre!("script not found: {}", script_path.display()));
+        if is_command_name(target) {
+            return run_python_code(args, PythonTarget::Command(target), collector, format);
+        }
+        let message = format!("script not found: {}", script_path.display());
+        collector.diagnostic(
+            Diagnostic::error(message.clone())
+                .with_code("E_RUN_TARGET_NOT_FOUND")
+                .with_suggestion("Check the script path relative to the current directory.")
+                .with_context(json!({ "target": target })),
+        );
+        return Err(eyre!(message));
     }
 
     // Check for PEP 723 metadata
@@ -2822,6 +7343,24 @@ pub(crate) async fn run_script(
         .unwrap_or_default();
 
     let script_lock = load_script_lock(&script_path)?;
+    if let Some(lock_info) = &script_lock {
+        let drift = crate::script_lock::check(&lock_info.lock, &pep723_deps);
+        if !drift.is_clean() {
+            collector.diagnostic(
+                Diagnostic::warning(format!(
+                    "{} does not match the script's declared dependencies ({}); running with the locked versions",
+                    script_lock_path(&script_path).display(),
+                    drift.describe()
+                ))
+                .with_code("W_SCRIPT_LOCK_DRIFT")
+                .with_suggestion(format!(
+                    "Run `pybun script lock --upgrade {}` to re-resolve.",
+                    script_path.display()
+                ))
+                .with_context(json!(drift)),
+            );
+        }
+    }
     let (install_deps, lock_hash) = if let Some(lock_info) = &script_lock {
         let mut locked = lock_info
             .lock
@@ -2838,6 +7377,9 @@ pub(crate) async fn run_script(
     };
 
     let has_pep723_deps = !install_deps.is_empty();
+    let requires_python = pep723_metadata
+        .as_ref()
+        .and_then(|m| m.requires_python.as_deref());
 
     // Shared wheel cache directory for PEP 723 installs (align with uv cache use).
     let wheel_cache_dir = if !has_pep723_deps {
@@ -2860,6 +7402,7 @@ pub(crate) async fn run_script(
 
     let mut pep723_backend = "system".to_string();
     let mut temp_env_dir: Option<tempfile::TempDir> = None;
+    let mut install_report: Option<env_install::EnvInstall> = None;
 
     // If there are PEP 723 dependencies, use cached or create environment
     let (runner, cached_env_path, cache_hit) = if has_pep723_deps {
@@ -2888,7 +7431,7 @@ pub(crate) async fn run_script(
         }
         if !dry_run
             && !no_cache
-            && !args.sandbox
+            && !args.sandbox.enabled
             && pep723_backend_setting != "pybun"
             && script_lock.is_none()
         {
@@ -2904,8 +7447,12 @@ pub(crate) async fn run_script(
                 // reporting `false` (Issue #267).
                 let pep_cache =
                     Pep723Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
-                let (base_python, _env_source) = find_python_interpreter()?;
-                let python_version = get_python_version(Path::new(&base_python))?;
+                let (base_python, env_source) =
+                    find_script_python(args.python.as_deref(), requires_python, collector)?;
+                let python_version = crate::runtime_source::cache_identity(
+                    Path::new(&base_python),
+                    &get_python_version(Path::new(&base_python))?,
+                );
                 let index_settings = pep723_index_settings(pep723_metadata.as_ref());
                 let cache_key = Pep723CacheKey::new(
                     &install_deps,
@@ -2929,6 +7476,7 @@ pub(crate) async fn run_script(
                 pep_cache
                     .record_cache_entry_at(&env_root, &cache_key)
                     .map_err(|e| eyre!("failed to record cache entry: {}", e))?;
+                pep_cache.record_lookup(&cache_key.hash, uv_cache_hit);
 
                 if uv_cache_hit {
                     collector.info(format!(
@@ -2937,7 +7485,10 @@ pub(crate) async fn run_script(
                     ));
                 }
 
-                (RunProgram::Uv { uv_path }, None, uv_cache_hit)
+                // uv picks its own interpreter unless PyBun chose a managed one.
+                let python =
+                    matches!(env_source, EnvSource::ManagedRuntime(_)).then_some(base_python);
+                (RunProgram::Uv { uv_path, python }, None, uv_cache_hit)
             } else if pep723_backend_setting == "uv" {
                 return Err(eyre!(
                     "PYBUN_PEP723_BACKEND=uv requires `uv` to be available in PATH"
@@ -2947,8 +7498,12 @@ pub(crate) async fn run_script(
                 // Continue with the built-in runner below.
                 let pep_cache =
                     Pep723Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
-                let (base_python, env_source) = find_python_interpreter()?;
-                let python_version = get_python_version(Path::new(&base_python))?;
+                let (base_python, env_source) =
+                    find_script_python(args.python.as_deref(), requires_python, collector)?;
+                let python_version = crate::runtime_source::cache_identity(
+                    Path::new(&base_python),
+                    &get_python_version(Path::new(&base_python))?,
+                );
                 let index_settings = pep723_index_settings(pep723_metadata.as_ref());
                 let cache_key = Pep723CacheKey::new(
                     &install_deps,
@@ -2991,6 +7546,7 @@ pub(crate) async fn run_script(
                         let _ = pep_cache.update_last_used_at(&env_root);
                         cache_hit = true;
                     }
+                    pep_cache.record_lookup(&cache_key.hash, cache_hit);
 
                     if cache_hit {
                         collector.info(format!(
@@ -3029,81 +7585,27 @@ pub(crate) async fn run_script(
                             venv_path.display()
                         );
 
-                        let mut venv_cmd = ProcessCommand::new(&base_python);
-                        venv_cmd.args(["-m", "venv"]);
-                        if crate::env::find_uv_executable().is_some() {
-                            venv_cmd.arg("--without-pip");
-                        }
-                        venv_cmd.arg(&venv_path);
-                        let venv_status = venv_cmd
-                            .status()
-                            .map_err(|e| eyre!("failed to create virtual environment: {}", e))?;
-
-                        if !venv_status.success() {
-                            return Err(eyre!("failed to create virtual environment"));
-                        }
-
-                        // Get pip path in venv (for fallback install)
-                        let pip_path = if cfg!(windows) {
-                            venv_path.join("Scripts").join("pip.exe")
-                        } else {
-                            venv_path.join("bin").join("pip")
-                        };
-
-                        // Install dependencies
-                        eprintln!("info: installing {} dependencies...", install_deps.len());
-                        if let Some(uv_path) = crate::env::find_uv_executable() {
-                            eprintln!("info: using uv for fast installation");
-                            let mut install_cmd = ProcessCommand::new(uv_path);
-                            install_cmd.args(["pip", "install", "--quiet"]);
-                            if install_no_deps {
-                                install_cmd.arg("--no-deps");
-                            }
-                            install_cmd.arg("--python");
-                            install_cmd.arg(&venv_path);
-                            if let Some(dir) = &wheel_cache_dir {
-                                if std::env::var_os("UV_CACHE_DIR").is_none() {
-                                    install_cmd.env("UV_CACHE_DIR", dir);
-                                }
-                                if std::env::var_os("PIP_CACHE_DIR").is_none() {
-                                    install_cmd.env("PIP_CACHE_DIR", dir);
-                                }
-                            }
-                            install_cmd.args(&install_deps);
-
-                            let install_status = install_cmd.status().map_err(|e| {
-                                eyre!("failed to install dependencies with uv: {}", e)
-                            })?;
-
-                            if !install_status.success() {
-                                collector
-                                    .warning("failed to install dependencies with uv".to_string());
-                                return Err(eyre!(
-                                    "failed to install PEP 723 dependencies (uv backend)"
-                                ));
-                            }
-                        } else {
-                            // Fallback to standard pip
-                            let mut install_cmd = ProcessCommand::new(&pip_path);
-                            install_cmd.args(["install", "--quiet"]);
-                            if install_no_deps {
-                                install_cmd.arg("--no-deps");
-                            }
-                            if let Some(dir) = &wheel_cache_dir {
-                                install_cmd.arg("--cache-dir");
-                                install_cmd.arg(dir);
-                            }
-                            install_cmd.args(&install_deps);
-
-                            let install_status = install_cmd
-                                .status()
-                                .map_err(|e| eyre!("failed to install dependencies: {}", e))?;
-
-                            if !install_status.success() {
-                                collector.warning("failed to install dependencies".to_string());
-                                return Err(eyre!("failed to install PEP 723 dependencies"));
-                            }
-                        }
+                        record_env_create(collector, &venv_path);
+                        let installer_kind = env_install::InstallerKind::from_env();
+                        env_install::create_venv(
+                            Path::new(&base_python),
+                            &venv_path,
+                            installer_kind,
+                        )?;
+                        install_report = Some(
+                            env_install::install(
+                                env_install::InstallRequest {
+                                    venv: &venv_path,
+                                    python_version: &python_version,
+                                    requirements: &install_deps,
+                                    no_deps: install_no_deps,
+                                    pip_cache_dir: wheel_cache_dir.as_deref(),
+                                },
+                                installer_kind,
+                                collector,
+                            )
+                            .await?,
+                        );
 
                         pep_cache
                             .record_cache_entry_at(&env_root, &cache_key)
@@ -3124,8 +7626,12 @@ pub(crate) async fn run_script(
             // Initialize PEP 723 cache
             let pep_cache =
                 Pep723Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
-            let (base_python, env_source) = find_python_interpreter()?;
-            let python_version = get_python_version(Path::new(&base_python))?;
+            let (base_python, env_source) =
+                find_script_python(args.python.as_deref(), requires_python, collector)?;
+            let python_version = crate::runtime_source::cache_identity(
+                Path::new(&base_python),
+                &get_python_version(Path::new(&base_python))?,
+            );
             let index_settings = pep723_index_settings(pep723_metadata.as_ref());
             let cache_key = Pep723CacheKey::new(
                 &install_deps,
@@ -3206,159 +7712,23 @@ pub(crate) async fn run_script(
                         venv_path.display()
                     );
 
-                    let mut venv_cmd = ProcessCommand::new(&base_python);
-                    venv_cmd.args(["-m", "venv"]);
-                    if crate::env::find_uv_executable().is_some() {
-                        venv_cmd.arg("--without-pip");
-                    }
-                    venv_cmd.arg(&venv_path);
-                    let venv_status = venv_cmd
-                        .status()
-                        .map_err(|e| eyre!("failed to create virtual environment: {}", e))?;
-
-                    if !venv_status.success() {
-                        return Err(eyre!("failed to create virtual environment"));
-                    }
-
-                    // Get pip path in venv (for fallback install)
-                    let _pip_path = if cfg!(windows) {
-                        venv_path.join("Scripts").join("pip.exe")
-                    } else {
-                        venv_path.join("bin").join("pip")
-                    };
-
-                    eprintln!("info: installing {} dependencies...", install_deps.len());
-                    if let Some(uv_path) = crate::env::find_uv_executable() {
-                        eprintln!("info: using uv for fast installation");
-                        let mut install_cmd = ProcessCommand::new(uv_path);
-                        install_cmd.args(["pip", "install", "--quiet"]);
-                        if install_no_deps {
-                            install_cmd.arg("--no-deps");
-                        }
-                        install_cmd.arg("--python");
-                        install_cmd.arg(&venv_path);
-                        if let Some(dir) = &wheel_cache_dir {
-                            if std::env::var_os("UV_CACHE_DIR").is_none() {
-                                install_cmd.env("UV_CACHE_DIR", dir);
-                            }
-                            if std::env::var_os("PIP_CACHE_DIR").is_none() {
-                                install_cmd.env("PIP_CACHE_DIR", dir);
-                            }
-                        }
-                        install_cmd.args(&install_deps);
-
-                        let install_status = install_cmd
-                            .status()
-                            .map_err(|e| eyre!("failed to install dependencies with uv: {}", e))?;
-
-                        if !install_status.success() {
-                            collector.warning("failed to install dependencies with uv".to_string());
-                            return Err(eyre!(
-                                "failed to install PEP 723 dependencies (uv backend)"
-                            ));
-                        }
-                    } else {
-                        // Native PyBun Installation
-                        eprintln!("info: resolving dependencies (native)...");
-
-                        let requirements: Vec<Requirement> = install_deps
-                            .iter()
-                            .map(|d| d.parse().unwrap_or_else(|_| Requirement::any(d)))
-                            .collect();
-
-                        // Use offline flag from args if available?
-                        // run_script args doesn't strictly have offline flag passed down easily unless we parse it?
-                        // But PyPiClient::from_env handles env vars.
-                        let client = PyPiClient::from_env(false).map_err(|e| eyre!(e))?;
-                        let index = PyPiIndex::new(client);
-                        let resolution = resolve_with_options(
-                            requirements,
-                            &index,
-                            ResolveOptions {
-                                python_version: python_version_env_override()
-                                    .or_else(|| Some(python_version.clone())),
-                                ..Default::default()
+                    record_env_create(collector, &venv_path);
+                    let installer_kind = env_install::InstallerKind::from_env();
+                    env_install::create_venv(Path::new(&base_python), &venv_path, installer_kind)?;
+                    install_report = Some(
+                        env_install::install(
+                            env_install::InstallRequest {
+                                venv: &venv_path,
+                                python_version: &python_version,
+                                requirements: &install_deps,
+                                no_deps: install_no_deps,
+                                pip_cache_dir: wheel_cache_dir.as_deref(),
                             },
+                            installer_kind,
+                            collector,
                         )
-                        .await;
-                        for notice in index.take_stale_cache_notices() {
-                            collector.warning(notice);
-                        }
-                        let resolution =
-                            resolution.map_err(|e: crate::resolver::ResolveError| eyre!(e))?;
-                        warn_on_prerelease_fallback(&resolution, collector);
-
-                        // Prepare site-packages path
-                        let major_minor = python_version
-                            .split('.')
-                            .take(2)
-                            .collect::<Vec<_>>()
-                            .join(".");
-                        let site_packages = if cfg!(windows) {
-                            venv_path.join("Lib").join("site-packages")
-                        } else {
-                            venv_path
-                                .join("lib")
-                                .join(format!("python{}", major_minor))
-                                .join("site-packages")
-                        };
-
-                        let wheel_cache = WheelCache::new()
-                            .map_err(|e| eyre!("failed to init wheel cache: {}", e))?;
-                        eprintln!(
-                            "info: downloading {} packages...",
-                            resolution.packages.len()
-                        );
-
-                        let platform_tags = crate::resolver::current_platform_tags();
-                        // Issue #294: select wheels for the *target venv's* Python
-                        // (already resolved above as `python_version`), not whatever
-                        // python3/python happens to resolve on PATH. Same root cause
-                        // as Issue #291, fixed for `pybun install` in #292.
-                        let active_cp_tag = python_version_to_cp_tag(&python_version)
-                            .unwrap_or_else(|| "cp311".to_string());
-                        let mut download_futures = Vec::new();
-
-                        for pkg in resolution.packages.values() {
-                            let selection = crate::resolver::select_artifact_for_platform_with_cp(
-                                pkg,
-                                &platform_tags,
-                                &active_cp_tag,
-                            );
-                            if selection.from_source {
-                                return Err(eyre!(
-                                    "native installer does not support sdist for {}",
-                                    pkg.name
-                                ));
-                            }
-                            if let Some(url) = &selection.url {
-                                let name = pkg.name.clone();
-                                let filename = selection.filename.clone();
-                                let url = url.clone();
-                                let wc = &wheel_cache;
-                                download_futures.push(async move {
-                                    wc.get_wheel(&name, &filename, &url, None).await
-                                });
-                            } else {
-                                return Err(eyre!("no download URL for {}", pkg.name));
-                            }
-                        }
-
-                        let results = futures::future::join_all(download_futures).await;
-                        let mut wheels_to_install = Vec::new();
-                        for res in results {
-                            match res {
-                                Ok(path) => wheels_to_install.push(path),
-                                Err(e) => return Err(eyre!("download failed: {}", e)),
-                            }
-                        }
-
-                        eprintln!("info: installing {} packages...", wheels_to_install.len());
-                        for wheel in wheels_to_install {
-                            installer::install_wheel(&wheel, &site_packages)
-                                .map_err(|e| eyre!("failed to install wheel: {}", e))?;
-                        }
-                    }
+                        .await?,
+                    );
 
                     pep_cache
                         .record_cache_entry_at(&env_root, &cache_key)
@@ -3389,81 +7759,29 @@ pub(crate) async fn run_script(
                     venv_path.display()
                 );
 
-                let mut venv_cmd = ProcessCommand::new(&base_python);
-                venv_cmd.args(["-m", "venv"]);
-                if crate::env::find_uv_executable().is_some() {
-                    venv_cmd.arg("--without-pip");
-                }
-                venv_cmd.arg(&venv_path);
-                let venv_status = venv_cmd
-                    .status()
-                    .map_err(|e| eyre!("failed to create virtual environment: {}", e))?;
-
-                if !venv_status.success() {
-                    return Err(eyre!("failed to create virtual environment"));
-                }
-
-                let pip_path = if cfg!(windows) {
-                    venv_path.join("Scripts").join("pip.exe")
-                } else {
-                    venv_path.join("bin").join("pip")
-                };
                 let venv_python = if cfg!(windows) {
                     venv_path.join("Scripts").join("python.exe")
                 } else {
                     venv_path.join("bin").join("python")
                 };
 
-                eprintln!("info: installing {} dependencies...", install_deps.len());
-
-                if let Some(uv_path) = crate::env::find_uv_executable() {
-                    eprintln!("info: using uv for fast installation (no-cache mode)");
-                    let mut install_cmd = ProcessCommand::new(uv_path);
-                    install_cmd.args(["pip", "install", "--quiet"]);
-                    if install_no_deps {
-                        install_cmd.arg("--no-deps");
-                    }
-                    install_cmd.arg("--python");
-                    install_cmd.arg(&venv_path);
-                    if let Some(dir) = &wheel_cache_dir {
-                        if std::env::var_os("UV_CACHE_DIR").is_none() {
-                            install_cmd.env("UV_CACHE_DIR", dir);
-                        }
-                        if std::env::var_os("PIP_CACHE_DIR").is_none() {
-                            install_cmd.env("PIP_CACHE_DIR", dir);
-                        }
-                    }
-                    install_cmd.args(&install_deps);
-
-                    let install_status = install_cmd
-                        .status()
-                        .map_err(|e| eyre!("failed to install dependencies with uv: {}", e))?;
-
-                    if !install_status.success() {
-                        collector.warning("failed to install dependencies with uv".to_string());
-                        return Err(eyre!("failed to install PEP 723 dependencies (uv)"));
-                    }
-                } else {
-                    let mut install_cmd = ProcessCommand::new(&pip_path);
-                    install_cmd.args(["install", "--quiet"]);
-                    if install_no_deps {
-                        install_cmd.arg("--no-deps");
-                    }
-                    if let Some(dir) = &wheel_cache_dir {
-                        install_cmd.arg("--cache-dir");
-                        install_cmd.arg(dir);
-                    }
-                    install_cmd.args(&install_deps);
-
-                    let install_status = install_cmd
-                        .status()
-                        .map_err(|e| eyre!("failed to install dependencies: {}", e))?;
-
-                    if !install_status.success() {
-                        collector.warning("failed to install dependencies".to_string());
-                        return Err(eyre!("failed to install PEP 723 dependencies"));
-                    }
-                }
+                record_env_create(collector, &venv_path);
+                let installer_kind = env_install::InstallerKind::from_env();
+                env_install::create_venv(Path::new(&base_python), &venv_path, installer_kind)?;
+                install_report = Some(
+                    env_install::install(
+                        env_install::InstallRequest {
+                            venv: &venv_path,
+                            python_version: &python_version,
+                            requirements: &install_deps,
+                            no_deps: install_no_deps,
+                            pip_cache_dir: wheel_cache_dir.as_deref(),
+                        },
+                        installer_kind,
+                        collector,
+                    )
+                    .await?,
+                );
 
                 // Keep the temp dir alive until after execution.
                 temp_env_dir = Some(temp_dir);
@@ -3475,8 +7793,14 @@ pub(crate) async fn run_script(
             }
         }
     } else {
-        // No PEP 723 dependencies, use system/project Python
-        let (python, env_source) = find_python_interpreter()?;
+        // No PEP 723 dependencies, use system/project Python (or the runtime a
+        // PEP 723 block or --python asks for)
+        let (python, env_source) = if pep723_metadata.is_some() || args.python.is_some() {
+            find_script_python(args.python.as_deref(), requires_python, collector)?
+        } else {
+            honor_python_pin(&std::env::current_dir()?, false, collector);
+            find_python_interpreter()?
+        };
 
         check_lockfile_python_compatibility(&python, collector);
 
@@ -3504,9 +7828,12 @@ pub(crate) async fn run_script(
             }
             (cmd, false)
         }
-        RunProgram::Uv { uv_path } => {
+        RunProgram::Uv { uv_path, python } => {
             let mut cmd = ProcessCommand::new(uv_path);
             cmd.args(["run", "--script"]);
+            if let Some(python) = python {
+                cmd.arg("--python").arg(python);
+            }
             cmd.arg(&script_path);
             if let Some(dir) = &wheel_cache_dir {
                 if std::env::var_os("UV_CACHE_DIR").is_none() {
@@ -3529,49 +7856,30 @@ pub(crate) async fn run_script(
     // Enable sandbox if requested.
     let mut sandbox_guard: Option<sandbox::SandboxGuard> = None;
     let mut sandbox_info: Option<SandboxInfo> = None;
-    if args.sandbox {
+    if args.sandbox.enabled {
         if is_uv_runner {
             return Err(eyre!("--sandbox is not supported with uv run backend"));
         }
-        let allow_network = args.allow_network
-            || std::env::var("PYBUN_SANDBOX_ALLOW_NETWORK")
-                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-                .unwrap_or(false);
-        collector.info(format!("sandbox enabled (allow_network={})", allow_network));
-        let guard = sandbox::apply_python_sandbox(
+        let script_dir = script_path.parent().unwrap_or(Path::new("."));
+        let writable = sandbox_write_dirs(
+            &[&std::env::current_dir()?, script_dir],
+            Path::new(cmd.get_program()),
+        );
+        let (guard, info) = apply_sandbox_args(
             &mut cmd,
-            sandbox::SandboxConfig {
-                allow_network,
-                allow_read: args.allow_read.clone(),
-                allow_write: args.allow_write.clone(),
-                allow_env: args.allow_env.clone(),
-                timeout_secs: args.sandbox_timeout,
-                memory_limit_mb: args.sandbox_memory,
-                cpu_limit_secs: args.sandbox_cpu,
-                ..Default::default()
-            },
+            &args.sandbox,
+            writable,
+            &script_path.display().to_string(),
+            collector,
         )?;
-        emit_unsupported_resource_limit_diagnostics(collector, &guard.resource_limits);
-        emit_rejected_allow_env_diagnostics(collector, &guard.rejected_env);
-        sandbox_info = Some(SandboxInfo {
-            enabled: true,
-            allow_network,
-            allow_read: args.allow_read.clone(),
-            allow_write: args.allow_write.clone(),
-            allow_env: guard.allow_env.clone(),
-            default_deny_write: guard.default_deny_write.clone(),
-            enforcement: guard.enforcement().to_string(),
-            audit: None,
-            resource_limits: guard.resource_limits.clone(),
-            timed_out: false,
-        });
         sandbox_guard = Some(guard);
+        sandbox_info = Some(info);
     }
 
+    let encoding = apply_encoding(&mut cmd, collector, format)?;
+
     // Apply launch profile settings to the command.
     // PYTHONOPTIMIZE maps optimization_level to Python's -O/-OO flag semantics.
-    let mut lazy_import_tempdir: Option<tempfile::TempDir> = None;
-    let mut lazy_imports_injected = false;
     if profile_config.optimization_level > 0 && std::env::var_os("PYTHONOPTIMIZE").is_none() {
         cmd.env(
             "PYTHONOPTIMIZE",
@@ -3586,28 +7894,19 @@ pub(crate) async fn run_script(
     }
     // Inject lazy imports via sitecustomize.py when not sandboxed (sandbox has its own
     // sitecustomize.py and merging them is deferred to a later PR).
-    if profile_config.lazy_imports && !args.sandbox && !is_uv_runner {
-        use crate::lazy_import::{LazyImportConfig, generate_lazy_import_python_code};
-        let lazy_config = LazyImportConfig::with_defaults();
-        let python_code = generate_lazy_import_python_code(&lazy_config);
-        match tempfile::tempdir() {
-            Ok(dir) => {
-                let sitecustomize = dir.path().join("sitecustomize.py");
-                if std::fs::write(&sitecustomize, &python_code).is_ok() {
-                    let new_path = join_python_path(dir.path());
-                    cmd.env("PYTHONPATH", new_path);
-                    lazy_imports_injected = true;
-                    lazy_import_tempdir = Some(dir);
-                }
-            }
-            Err(e) => {
-                collector.warning(format!(
-                    "failed to create lazy-import tempdir, skipping injection: {}",
-                    e
-                ));
-            }
-        }
-    }
+    let lazy_import_hook = if is_uv_runner {
+        skip_lazy_imports(args, "the uv run backend", collector);
+        None
+    } else {
+        inject_lazy_imports(&mut cmd, args, &profile_config, collector)
+    };
+    let lazy_imports_injected = lazy_import_hook.is_some();
+
+    let network_guard = if sandbox_guard.is_none() && !is_uv_runner {
+        apply_network_guard(&mut cmd, network_policy::Operation::Run)?
+    } else {
+        None
+    };
 
     let cleanup = temp_env_dir.is_some();
 
@@ -3615,10 +7914,11 @@ pub(crate) async fn run_script(
     // On Unix, use exec to replace the process if cleanup is not needed AND not in JSON mode
     // (JSON mode requires wrapping to emit final summary)
     #[cfg(unix)]
-    if !cleanup && format != OutputFormat::Json && sandbox_guard.is_none() {
-        // leak lazy_import_tempdir intentionally: exec replaces the process before Rust
+    if !cleanup && !format.is_json() && sandbox_guard.is_none() && !args.lazy_imports {
+        // leak lazy_import_hook intentionally: exec replaces the process before Rust
         // drop runs, so the directory remains accessible to the spawned Python process.
-        std::mem::forget(lazy_import_tempdir);
+        std::mem::forget(lazy_import_hook);
+        std::mem::forget(network_guard);
         let err = cmd.exec();
         return Err(eyre!("failed to exec runner: {}", err));
     }
@@ -3628,43 +7928,25 @@ pub(crate) async fn run_script(
         stdout,
         stderr,
         timed_out,
-    } = sandbox::execute_with_optional_sandbox(
-        &mut cmd,
-        sandbox_guard.as_ref(),
-        format == OutputFormat::Json,
-    )
-    .map_err(|e| eyre!("failed to execute runner: {}", e))?;
-    let stdout = stdout.as_deref().and_then(capture_stdio);
-    let stderr = stderr.as_deref().and_then(capture_stdio);
-    // Read audit before dropping the guard (guard keeps the audit file alive).
-    if let (Some(guard), Some(info)) = (&sandbox_guard, &mut sandbox_info) {
-        info.audit = Some(guard.read_audit());
-        info.timed_out = timed_out;
+    } = sandbox::execute_with_optional_sandbox(&mut cmd, sandbox_guard.as_ref(), format.is_json())
+        .map_err(|e| eyre!("failed to execute runner: {}", e))?;
+    let stdout = stdout.and_then(capture_stdio);
+    let stderr = stderr.and_then(capture_stdio);
+    if let (Some(guard), Some(info)) = (sandbox_guard, &mut sandbox_info) {
+        finish_sandbox(guard, info, &status, timed_out, collector);
     }
-    drop(sandbox_guard);
-
-    if timed_out {
-        collector.diagnostic(
-            Diagnostic::error(format!(
-                "sandboxed process killed after exceeding --sandbox-timeout={}s",
-                args.sandbox_timeout
-            ))
-            .with_code("E_SANDBOX_TIMEOUT")
-            .with_suggestion("increase --sandbox-timeout, set --sandbox-timeout=0 to disable, or optimize the script to finish sooner"),
-        );
-    } else if args.sandbox_cpu > 0 && sandbox::cpu_limit_exceeded(&status) {
-        collector.diagnostic(
-            Diagnostic::error(format!(
-                "sandboxed process killed after exceeding --sandbox-cpu={}s of CPU time",
-                args.sandbox_cpu
-            ))
-            .with_code("E_SANDBOX_CPU_LIMIT")
-            .with_suggestion("increase --sandbox-cpu, set --sandbox-cpu=0 to disable, or optimize the script to use less CPU time"),
-        );
+    // The guard stays installed for the lazy-import savings probe.
+    if let Some(guard) = &network_guard {
+        guard.record_blocked();
     }
 
     let exit_code = status.code().unwrap_or(-1);
 
+    let sys_path = script_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let lazy_imports = lazy_import_hook.and_then(|hook| hook.finish(&cmd, sys_path, collector));
     let summary = if status.success() {
         format!("executed {} successfully", script_path.display())
     } else {
@@ -3674,8 +7956,7 @@ pub(crate) async fn run_script(
             exit_code
         )
     };
-
-    drop(lazy_import_tempdir);
+    let summary = lazy_import_summary(summary, lazy_imports.as_ref());
 
     Ok(RunOutcome {
         summary,
@@ -3696,10 +7977,168 @@ pub(crate) async fn run_script(
             lazy_imports_injected,
             timing: profile_config.timing,
         },
+        entry_point: None,
+        encoding,
+        install: install_report,
+        lazy_imports,
     })
 }
 
 /// Build a PYTHONPATH string that prepends `dir` before the existing PYTHONPATH.
+/// Install the network allowlist guard for a child Python process when the
+/// project configures `[tool.pybun.network]`.
+fn apply_network_guard(
+    cmd: &mut ProcessCommand,
+    operation: network_policy::Operation,
+) -> Result<Option<network_policy::NetworkGuard>> {
+    let guard = network_policy::NetworkGuard::for_operation(operation)
+        .map_err(|e| eyre!("failed to prepare network guard: {}", e))?;
+    if let Some(guard) = &guard {
+        guard.apply(cmd);
+    }
+    Ok(guard)
+}
+
+/// The lazy-import hook installed as a `pybun run` child's `sitecustomize.py`.
+struct LazyImportHook {
+    dir: tempfile::TempDir,
+    /// Whether the hook records what it defers (`--lazy-imports`).
+    report: bool,
+    /// Whether to estimate the time it saved (`--measure-lazy-savings`).
+    measure: bool,
+}
+
+/// Install the lazy-import hook for `cmd` when the profile or
+/// `--lazy-imports` asks for it: write it as `sitecustomize.py` to a temp
+/// dir put first on PYTHONPATH.
+fn inject_lazy_imports(
+    cmd: &mut ProcessCommand,
+    args: &crate::cli::RunArgs,
+    profile_config: &crate::profiles::ProfileConfig,
+    collector: &mut EventCollector,
+) -> Option<LazyImportHook> {
+    use crate::lazy_import::{LazyImportConfig, REPORT_ENV, generate_lazy_import_python_code};
+
+    if !profile_config.lazy_imports {
+        return None;
+    }
+    if args.sandbox.enabled {
+        skip_lazy_imports(args, "--sandbox", collector);
+        return None;
+    }
+    let python_code = generate_lazy_import_python_code(&LazyImportConfig::with_defaults());
+    let dir = match tempfile::tempdir().and_then(|dir| {
+        std::fs::write(dir.path().join("sitecustomize.py"), &python_code)?;
+        Ok(dir)
+    }) {
+        Ok(dir) => dir,
+        Err(e) => {
+            collector.warning(format!(
+                "failed to write the lazy-import hook, skipping injection: {}",
+                e
+            ));
+            return None;
+        }
+    };
+    cmd.env("PYTHONPATH", join_python_path(dir.path()));
+    if args.lazy_imports {
+        cmd.env(REPORT_ENV, dir.path().join("report.json"));
+    }
+    Some(LazyImportHook {
+        dir,
+        report: args.lazy_imports,
+        measure: args.measure_lazy_savings,
+    })
+}
+
+fn skip_lazy_imports(args: &crate::cli::RunArgs, reason: &str, collector: &mut EventCollector) {
+    if args.lazy_imports {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "--lazy-imports is not supported with {reason}; running without the hook"
+            ))
+            .with_code("W_LAZY_IMPORTS_SKIPPED"),
+        );
+    }
+}
+
+impl LazyImportHook {
+    /// What the hook deferred during `run`. With `--measure-lazy-savings`,
+    /// the start-up time it saved is estimated by `run`'s interpreter, in
+    /// `run`'s environment (network guard included) minus the hook, with
+    /// `sys_path` first on `sys.path`.
+    fn finish(
+        self,
+        run: &ProcessCommand,
+        sys_path: &Path,
+        collector: &mut EventCollector,
+    ) -> Option<crate::lazy_import::LazyImportReport> {
+        if !self.report {
+            return None;
+        }
+        let Some(mut report) =
+            crate::lazy_import::LazyImportReport::read(&self.dir.path().join("report.json"))
+        else {
+            collector.warning(
+                "the lazy-import hook wrote no report: the interpreter did not load it or did not exit normally",
+            );
+            return None;
+        };
+        if self.measure {
+            report.estimated_saved_ms = report.measure_savings(self.probe(run), sys_path);
+        }
+        Some(report)
+    }
+
+    /// A command for `run`'s interpreter, directory and environment, without
+    /// the hook on PYTHONPATH.
+    fn probe(&self, run: &ProcessCommand) -> ProcessCommand {
+        let mut probe = ProcessCommand::new(run.get_program());
+        if let Some(dir) = run.get_current_dir() {
+            probe.current_dir(dir);
+        }
+        for (key, value) in run.get_envs() {
+            match value {
+                Some(value) if key == "PYTHONPATH" => {
+                    let paths: Vec<PathBuf> = std::env::split_paths(value)
+                        .filter(|path| path != self.dir.path())
+                        .collect();
+                    match std::env::join_paths(paths) {
+                        Ok(joined) if !joined.is_empty() => probe.env(key, joined),
+                        _ => probe.env_remove(key),
+                    };
+                }
+                Some(value) => {
+                    probe.env(key, value);
+                }
+                None => {
+                    probe.env_remove(key);
+                }
+            }
+        }
+        probe
+    }
+}
+
+/// `summary` with what `--lazy-imports` deferred and saved.
+fn lazy_import_summary(
+    summary: String,
+    report: Option<&crate::lazy_import::LazyImportReport>,
+) -> String {
+    let Some(report) = report else {
+        return summary;
+    };
+    let saved = report
+        .estimated_saved_ms
+        .map(|ms| format!(", saved ~{}", crate::units::format_millis(ms)))
+        .unwrap_or_default();
+    format!(
+        "{summary} (lazy imports: deferred {} modules, {} never loaded{saved})",
+        report.deferred.len(),
+        report.never_loaded.len()
+    )
+}
+
 fn join_python_path(dir: &std::path::Path) -> std::ffi::OsString {
     let sep = if cfg!(windows) { ";" } else { ":" };
     let mut paths = vec![dir.as_os_str().to_os_string()];
@@ -3729,6 +8168,14 @@ fn get_python_version(python_path: &std::path::Path) -> Result<String> {
     Ok(version)
 }
 
+/// Interpreter tag of the Python at `python_path` running `version`: `cp312`,
+/// free-threaded `cp313t` or PyPy `pp310`. Interpreters that cannot report
+/// their flavor are taken for regular CPython.
+fn get_interpreter_tag(python_path: &Path, version: &str) -> Option<String> {
+    let flavor = crate::tags::detect_flavor(python_path).unwrap_or_default();
+    crate::tags::Interpreter::from_version(flavor, version).map(|i| i.tag())
+}
+
 /// Validate that the wheels recorded in the project lockfile (`pybun.lockb`)
 /// are compatible with the Python interpreter that is about to execute the
 /// script. A mismatch (e.g. `cp310` wheels locked but running under `cp312`)
@@ -3752,7 +8199,7 @@ fn check_lockfile_python_compatibility(python_path: &str, collector: &mut EventC
     let Ok(active_version) = get_python_version(Path::new(python_path)) else {
         return;
     };
-    let Some(active_cp_tag) = python_version_to_cp_tag(&active_version) else {
+    let Some(active_cp_tag) = get_interpreter_tag(Path::new(python_path), &active_version) else {
         return;
     };
 
@@ -3786,9 +8233,29 @@ fn check_lockfile_python_compatibility(python_path: &str, collector: &mut EventC
     );
 }
 
+/// What [`run_python_code`] hands to the interpreter of the selected
+/// environment.
+enum PythonTarget<'a> {
+    /// `-c CODE`.
+    Code(&'a str),
+    /// `-m MODULE`.
+    Module(&'a str),
+    /// A `[console_scripts]` entry point of an installed distribution.
+    Command(&'a str),
+}
+
+/// Whether a `pybun run` target that is not an existing file can name a
+/// console script: no path separators and no `.py` suffix.
+fn is_command_name(target: &str) -> bool {
+    !target.is_empty()
+        && !target.contains(['/', '\\'])
+        && !target.ends_with(".py")
+        && !target.starts_with('-')
+}
+
 fn run_python_code(
     args: &crate::cli::RunArgs,
-    code: &str,
+    target: PythonTarget<'_>,
     collector: &mut EventCollector,
     format: OutputFormat,
 ) -> Result<RunOutcome> {
@@ -3798,57 +8265,57 @@ fn run_python_code(
         .profile
         .parse()
         .map_err(|e: String| eyre!("invalid --profile value: {}", e))?;
-    let profile_config = ProfileConfig::for_profile(profile);
+    let mut profile_config = ProfileConfig::for_profile(profile);
+    profile_config.lazy_imports |= args.lazy_imports;
 
+    honor_python_pin(&std::env::current_dir()?, false, collector);
     let (python, env_source) = find_python_interpreter()?;
     eprintln!("info: using Python from {}", env_source);
 
     let mut cmd = ProcessCommand::new(&python);
-    cmd.arg("-c").arg(code);
+    let mut entry_point = None;
+    let (label, description) = match target {
+        PythonTarget::Code(code) => {
+            cmd.arg("-c").arg(code);
+            for arg in args.passthrough.iter().skip(1) {
+                cmd.arg(arg);
+            }
+            ("-c".to_string(), "inline code".to_string())
+        }
+        PythonTarget::Module(module) => {
+            ensure_module_exists(&python, module, collector)?;
+            cmd.arg("-m").arg(module);
+            cmd.args(&args.passthrough);
+            (format!("-m {module}"), format!("module {module}"))
+        }
+        PythonTarget::Command(name) => {
+            let entry = find_entry_point(&python, name, collector)?;
+            collector.info(format!(
+                "Running console script {} ({}) from {}",
+                name,
+                entry.target(),
+                entry.distribution
+            ));
+            cmd.arg("-c").arg(entry.python_code());
+            cmd.args(&args.passthrough);
+            entry_point = Some(entry);
+            (name.to_string(), format!("console script {name}"))
+        }
+    };
 
     let mut sandbox_info: Option<SandboxInfo> = None;
     let mut sandbox_guard: Option<sandbox::SandboxGuard> = None;
-    if args.sandbox {
-        let allow_network = args.allow_network
-            || std::env::var("PYBUN_SANDBOX_ALLOW_NETWORK")
-                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-                .unwrap_or(false);
-        collector.info(format!(
-            "sandbox enabled for inline code (allow_network={})",
-            allow_network
-        ));
-        let guard = sandbox::apply_python_sandbox(
-            &mut cmd,
-            sandbox::SandboxConfig {
-                allow_network,
-                allow_read: args.allow_read.clone(),
-                allow_write: args.allow_write.clone(),
-                allow_env: args.allow_env.clone(),
-                timeout_secs: args.sandbox_timeout,
-                memory_limit_mb: args.sandbox_memory,
-                cpu_limit_secs: args.sandbox_cpu,
-                ..Default::default()
-            },
-        )?;
-        emit_unsupported_resource_limit_diagnostics(collector, &guard.resource_limits);
-        emit_rejected_allow_env_diagnostics(collector, &guard.rejected_env);
-        sandbox_info = Some(SandboxInfo {
-            enabled: true,
-            allow_network,
-            allow_read: args.allow_read.clone(),
-            allow_write: args.allow_write.clone(),
-            allow_env: guard.allow_env.clone(),
-            default_deny_write: guard.default_deny_write.clone(),
-            enforcement: guard.enforcement().to_string(),
-            audit: None,
-            resource_limits: guard.resource_limits.clone(),
-            timed_out: false,
-        });
+    if args.sandbox.enabled {
+        let writable = sandbox_write_dirs(&[&std::env::current_dir()?], Path::new(&python));
+        let (guard, info) =
+            apply_sandbox_args(&mut cmd, &args.sandbox, writable, &description, collector)?;
         sandbox_guard = Some(guard);
+        sandbox_info = Some(info);
     }
 
+    let encoding = apply_encoding(&mut cmd, collector, format)?;
+
     // Apply profile settings (optimization, timing, env vars) — same as run_script.
-    let mut lazy_imports_injected = false;
     if profile_config.optimization_level > 0 && std::env::var_os("PYTHONOPTIMIZE").is_none() {
         cmd.env(
             "PYTHONOPTIMIZE",
@@ -3861,30 +8328,19 @@ fn run_python_code(
     for (key, value) in &profile_config.env_vars {
         cmd.env(key, value);
     }
-    let mut lazy_import_tempdir: Option<tempfile::TempDir> = None;
-    if profile_config.lazy_imports && !args.sandbox {
-        use crate::lazy_import::{LazyImportConfig, generate_lazy_import_python_code};
-        let lazy_config = LazyImportConfig::with_defaults();
-        let python_code = generate_lazy_import_python_code(&lazy_config);
-        if let Ok(dir) = tempfile::tempdir() {
-            let sitecustomize = dir.path().join("sitecustomize.py");
-            if std::fs::write(&sitecustomize, &python_code).is_ok() {
-                let new_path = join_python_path(dir.path());
-                cmd.env("PYTHONPATH", new_path);
-                lazy_imports_injected = true;
-                lazy_import_tempdir = Some(dir);
-            }
-        }
-    }
+    let lazy_import_hook = inject_lazy_imports(&mut cmd, args, &profile_config, collector);
+    let lazy_imports_injected = lazy_import_hook.is_some();
 
-    // Add remaining passthrough arguments
-    for arg in args.passthrough.iter().skip(1) {
-        cmd.arg(arg);
-    }
+    let network_guard = if sandbox_guard.is_none() {
+        apply_network_guard(&mut cmd, network_policy::Operation::Run)?
+    } else {
+        None
+    };
 
     #[cfg(unix)]
-    if format != OutputFormat::Json && sandbox_guard.is_none() {
-        std::mem::forget(lazy_import_tempdir);
+    if !format.is_json() && sandbox_guard.is_none() && !args.lazy_imports {
+        std::mem::forget(lazy_import_hook);
+        std::mem::forget(network_guard);
         let err = cmd.exec();
         return Err(eyre!("failed to exec Python: {}", err));
     }
@@ -3894,74 +8350,167 @@ fn run_python_code(
         stdout,
         stderr,
         timed_out,
-    } = sandbox::execute_with_optional_sandbox(
-        &mut cmd,
-        sandbox_guard.as_ref(),
-        format == OutputFormat::Json,
-    )
-    .map_err(|e| eyre!("failed to execute Python: {}", e))?;
-    let stdout = stdout.as_deref().and_then(capture_stdio);
-    let stderr = stderr.as_deref().and_then(capture_stdio);
-    if let (Some(guard), Some(info)) = (&sandbox_guard, &mut sandbox_info) {
-        info.audit = Some(guard.read_audit());
-        info.timed_out = timed_out;
+    } = sandbox::execute_with_optional_sandbox(&mut cmd, sandbox_guard.as_ref(), format.is_json())
+        .map_err(|e| eyre!("failed to execute Python: {}", e))?;
+    let stdout = stdout.and_then(capture_stdio);
+    let stderr = stderr.and_then(capture_stdio);
+    if let (Some(guard), Some(info)) = (sandbox_guard, &mut sandbox_info) {
+        finish_sandbox(guard, info, &status, timed_out, collector);
     }
-    drop(sandbox_guard);
-
-    if timed_out {
-        collector.diagnostic(
-            Diagnostic::error(format!(
-                "sandboxed process killed after exceeding --sandbox-timeout={}s",
-                args.sandbox_timeout
-            ))
-            .with_code("E_SANDBOX_TIMEOUT")
-            .with_suggestion("increase --sandbox-timeout, set --sandbox-timeout=0 to disable, or optimize the script to finish sooner"),
-        );
-    } else if args.sandbox_cpu > 0 && sandbox::cpu_limit_exceeded(&status) {
-        collector.diagnostic(
-            Diagnostic::error(format!(
-                "sandboxed process killed after exceeding --sandbox-cpu={}s of CPU time",
-                args.sandbox_cpu
-            ))
-            .with_code("E_SANDBOX_CPU_LIMIT")
-            .with_suggestion("increase --sandbox-cpu, set --sandbox-cpu=0 to disable, or optimize the script to use less CPU time"),
-        );
+    // The guard stays installed for the lazy-import savings probe.
+    if let Some(guard) = &network_guard {
+        guard.record_blocked();
     }
 
     let exit_code = status.code().unwrap_or(-1);
 
     let summary = if status.success() {
-        if args.sandbox {
-            "executed inline code successfully (sandboxed)".to_string()
+        if args.sandbox.enabled {
+            format!("executed {description} successfully (sandboxed)")
         } else {
-            "executed inline code successfully".to_string()
+            format!("executed {description} successfully")
         }
     } else {
-        format!("inline code exited with code {}", exit_code)
+        format!("{description} exited with code {exit_code}")
     };
+    let cwd = std::env::current_dir()?;
+    let lazy_imports = lazy_import_hook.and_then(|hook| hook.finish(&cmd, &cwd, collector));
+    let summary = lazy_import_summary(summary, lazy_imports.as_ref());
+
+    Ok(RunOutcome {
+        summary,
+        target: Some(label),
+        entry_point,
+        exit_code,
+        pep723_deps: Vec::new(),
+        pep723_backend: "system".to_string(),
+        temp_env: None,
+        cleanup: false,
+        cache_hit: false,
+        stdout,
+        stderr,
+        sandbox: sandbox_info,
+        profile: RunProfileInfo {
+            name: profile_config.profile.to_string(),
+            optimization_level: profile_config.optimization_level,
+            lazy_imports: profile_config.lazy_imports,
+            lazy_imports_injected,
+            timing: profile_config.timing,
+        },
+        encoding,
+        install: None,
+        lazy_imports,
+    })
+}
+
+/// `sys.path` (the working directory standing in for `''`) and the builtin
+/// module names of `python`: where `-m` modules and console scripts of the
+/// selected environment are looked up.
+fn python_import_paths(python: &str) -> Result<(Vec<PathBuf>, Vec<String>)> {
+    let output = ProcessCommand::new(python)
+        .args([
+            "-c",
+            "import json, sys; print(json.dumps([sys.path, sorted(sys.builtin_module_names)]))",
+        ])
+        .output()
+        .map_err(|e| eyre!("failed to inspect Python import paths: {}", e))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "failed to inspect Python import paths: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let (paths, builtins): (Vec<String>, Vec<String>) = serde_json::from_slice(&output.stdout)
+        .map_err(|e| eyre!("failed to inspect Python import paths: {}", e))?;
+    let cwd = std::env::current_dir()?;
+    let paths = paths
+        .into_iter()
+        .map(|path| {
+            if path.is_empty() {
+                cwd.clone()
+            } else {
+                cwd.join(path)
+            }
+        })
+        .collect();
+    Ok((paths, builtins))
+}
+
+/// Fail with `E_RUN_MODULE_NOT_FOUND`, and close matches, when `module`
+/// cannot be imported by `python`. The search paths are scanned with
+/// [`ModuleFinder`]; a miss is confirmed with `importlib.util.find_spec`
+/// because import hooks (editable installs, zipped stdlib) escape the scan.
+fn ensure_module_exists(python: &str, module: &str, collector: &mut EventCollector) -> Result<()> {
+    use crate::module_finder::{ModuleFinder, ModuleFinderConfig};
+
+    let (search_paths, builtins) = python_import_paths(python)?;
+    let top_level = module.split('.').next().unwrap_or(module);
+    if builtins.iter().any(|name| name == top_level) {
+        return Ok(());
+    }
+    let finder = ModuleFinder::new(ModuleFinderConfig {
+        enabled: true,
+        search_paths,
+        cache_enabled: false,
+        ..Default::default()
+    });
+    if finder.find_module(module).module.is_some() {
+        return Ok(());
+    }
+    let found = ProcessCommand::new(python)
+        .args([
+            "-c",
+            "import importlib.util, sys\ntry:\n    sys.exit(0 if importlib.util.find_spec(sys.argv[1]) else 1)\nexcept Exception:\n    sys.exit(1)",
+            module,
+        ])
+        .status()
+        .is_ok_and(|status| status.success());
+    if found {
+        return Ok(());
+    }
 
-    drop(lazy_import_tempdir);
+    let did_you_mean = finder.similar_modules(module, &builtins);
+    let suggestion = match did_you_mean.first() {
+        Some(similar) => format!("Did you mean `pybun run -m {similar}`?"),
+        None => format!(
+            "Install the package providing it (e.g. `pybun add {top_level}`) or check the module name."
+        ),
+    };
+    let message = format!("no module named '{module}' in the selected environment");
+    collector.diagnostic(
+        Diagnostic::error(message.clone())
+            .with_code("E_RUN_MODULE_NOT_FOUND")
+            .with_suggestion(suggestion)
+            .with_context(json!({
+                "module": module,
+                "python": python,
+                "did_you_mean": did_you_mean,
+            })),
+    );
+    Err(eyre!(message))
+}
 
-    Ok(RunOutcome {
-        summary,
-        target: Some("-c".to_string()),
-        exit_code,
-        pep723_deps: Vec::new(),
-        pep723_backend: "system".to_string(),
-        temp_env: None,
-        cleanup: false,
-        cache_hit: false,
-        stdout,
-        stderr,
-        sandbox: sandbox_info,
-        profile: RunProfileInfo {
-            name: profile_config.profile.to_string(),
-            optimization_level: profile_config.optimization_level,
-            lazy_imports: profile_config.lazy_imports,
-            lazy_imports_injected,
-            timing: profile_config.timing,
-        },
-    })
+/// The console script `name` declared by a distribution installed for
+/// `python`; fails with `E_RUN_TARGET_NOT_FOUND` when there is none.
+fn find_entry_point(
+    python: &str,
+    name: &str,
+    collector: &mut EventCollector,
+) -> Result<crate::entry_points::EntryPoint> {
+    let (search_paths, _) = python_import_paths(python)?;
+    if let Some(entry) = crate::entry_points::find_console_script(&search_paths, name) {
+        return Ok(entry);
+    }
+    let message = format!("script not found: {name} (no such file or console script)");
+    collector.diagnostic(
+        Diagnostic::error(message.clone())
+            .with_code("E_RUN_TARGET_NOT_FOUND")
+            .with_suggestion(format!(
+                "Check the path, install the package providing `{name}`, or use `pybun run -m MODULE` for a module."
+            ))
+            .with_context(json!({ "target": name, "python": python })),
+    );
+    Err(eyre!(message))
 }
 
 /// Find the Python interpreter to use.
@@ -3971,7 +8520,7 @@ fn run_python_code(
 /// 1. PYBUN_ENV environment variable (venv path)
 /// 2. PYBUN_PYTHON environment variable (explicit binary)
 /// 3. Project-local .pybun/venv directory
-/// 4. .python-version file (pyenv-style)
+/// 4. .python-version file (pinned managed runtime, else pyenv-style)
 /// 5. System Python (python3/python in PATH)
 fn find_python_interpreter() -> Result<(String, EnvSource)> {
     let working_dir = std::env::current_dir()?;
@@ -3979,6 +8528,98 @@ fn find_python_interpreter() -> Result<(String, EnvSource)> {
     Ok((env.python_path.to_string_lossy().to_string(), env.source))
 }
 
+/// [`find_env_base_python`] for a PEP 723 script. When no interpreter can be
+/// found for its `requires-python`, reports `E_RUN_PYTHON_INCOMPATIBLE` with a
+/// `pybun python install` fix candidate.
+fn find_script_python(
+    requested: Option<&str>,
+    requires_python: Option<&str>,
+    collector: &mut EventCollector,
+) -> Result<(String, EnvSource)> {
+    let result = find_env_base_python(requested, requires_python);
+    let (Err(error), Some(spec)) = (&result, requires_python) else {
+        return result;
+    };
+    let discovered = find_python_interpreter().ok().map(|(python, _)| {
+        let version = get_python_version(Path::new(&python)).ok();
+        (python, version)
+    });
+    let found = match &discovered {
+        Some((python, Some(version))) => format!("{python} is Python {version}"),
+        Some((python, None)) => format!("the version of {python} is unknown"),
+        None => "no Python interpreter was found".to_string(),
+    };
+    let series = crate::runtime::newest_supported(spec).map(|info| {
+        let mut parts = info.version.split('.');
+        format!(
+            "{}.{}",
+            parts.next().unwrap_or_default(),
+            parts.next().unwrap_or_default()
+        )
+    });
+    let (suggestion, fixes) = match &series {
+        Some(series) => (
+            format!(
+                "Run `pybun python install {series}` (or `pybun run --python {series}`), or point PYBUN_PYTHON at a Python that satisfies {spec}."
+            ),
+            crate::self_heal::fix_candidates_for_requires_python(series),
+        ),
+        None => (
+            format!(
+                "No managed runtime satisfies {spec}; point PYBUN_PYTHON at a compatible Python or relax requires-python."
+            ),
+            crate::self_heal::fix_candidates_for_missing_python(),
+        ),
+    };
+    let message = format!(
+        "script requires Python {spec}, but {found} and no compatible managed runtime is available: {error}"
+    );
+    collector.diagnostic(
+        Diagnostic::error(message.clone())
+            .with_code("E_RUN_PYTHON_INCOMPATIBLE")
+        .with_suggestion(suggestion)
+        .with_context(json!({
+            "requires_python": spec,
+            "requested": requested,
+            "interpreter": discovered.as_ref().map(|(python, _)| python),
+            "interpreter_version": discovered.as_ref().and_then(|(_, version)| version.as_ref()),
+            "candidate": series,
+        }))
+        .with_fix_candidates(fixes),
+    );
+    Err(eyre!(message))
+}
+
+/// Find the base interpreter for a new PEP 723 or `pybun x` environment.
+///
+/// `--python` selects a managed runtime, installing it if missing. A
+/// `requires-python` the interpreter from [`find_python_interpreter`] does not
+/// satisfy switches to the newest managed runtime that does. Otherwise this is
+/// [`find_python_interpreter`].
+fn find_env_base_python(
+    requested: Option<&str>,
+    requires_python: Option<&str>,
+) -> Result<(String, EnvSource)> {
+    if requested.is_none() {
+        let Some(spec) = requires_python else {
+            return find_python_interpreter();
+        };
+        if let Ok((python, source)) = find_python_interpreter()
+            && let Ok(version) = get_python_version(Path::new(&python))
+            && crate::resolver::requires_python_allows(spec, &version)
+        {
+            return Ok((python, source));
+        }
+    }
+    let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+    let (version, python) =
+        RuntimeManager::new(cache).select_runtime(requested, requires_python)?;
+    Ok((
+        python.to_string_lossy().to_string(),
+        EnvSource::ManagedRuntime(version),
+    ))
+}
+
 // ---------------------------------------------------------------------------
 // pybun python
 // ---------------------------------------------------------------------------
@@ -3999,67 +8640,224 @@ fn handle_python_command(
         }
         PythonCommands::Install(args) => {
             collector.event(EventType::PythonInstallStart);
-            let result = python_install(args);
+            let result = python_install(args, collector);
             collector.event(EventType::PythonInstallComplete);
             result
         }
         PythonCommands::Remove(args) => {
             collector.event(EventType::PythonRemoveStart);
-            let result = python_remove(args);
+            let result = python_remove(args, collector);
             collector.event(EventType::PythonRemoveComplete);
             result
         }
         PythonCommands::Which(args) => python_which(args),
+        PythonCommands::Pin(args) => python_pin(args, collector),
     }
 }
 
 fn python_list(args: &crate::cli::PythonListArgs) -> Result<(String, RenderDetail)> {
     let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
-    let manager = RuntimeManager::new(cache);
+    let manager = RuntimeManager::new(cache.clone());
 
     let installed = manager.list_installed()?;
     let available = supported_versions();
+    let today = crate::mcp::utc_date_now();
 
-    let mut text_output = String::new();
+    // The current project's requires-python decides
+    // `satisfies_requires_python`; `used_by` lists each runtime's dependents.
+    let cwd = std::env::current_dir()?;
+    let project = Project::discover(&cwd).ok();
+    let requires_python = project.as_ref().and_then(Project::requires_python);
+    let mut dependents = runtime_dependents(&manager, &cache);
+
+    let names: Vec<String> = available.iter().map(|v| v.name()).collect();
+    let listed: Vec<&str> = if args.all {
+        names.iter().map(String::as_str).collect()
+    } else {
+        installed.iter().map(String::as_str).collect()
+    };
+    let mut size_errors = Vec::new();
+    let versions: Vec<Value> = listed
+        .into_iter()
+        .map(|version| {
+            let is_installed = installed.iter().any(|i| i == version);
+            let info = available.iter().find(|v| v.name() == version);
+            let runtime = manager.runtime_info(version);
+            let release = crate::runtime::release_version(version);
+            // PyPy follows its own release cycle, not CPython's.
+            let lifecycle = runtime
+                .as_ref()
+                .filter(|r| r.flavor != crate::tags::Flavor::PyPy)
+                .and_then(|_| crate::runtime::series_lifecycle(release));
+            let mut manifest = info.and_then(|i| manager.cached_manifest_for(i));
+            if manifest.is_none()
+                && args.fetch_sizes
+                && let Some(info) = info
+            {
+                match manager.manifest_for(info) {
+                    Ok(fetched) => manifest = Some(fetched),
+                    Err(e) => size_errors.push(format!("{version}: {e:#}")),
+                }
+            }
+            let asset = info
+                .and_then(crate::runtime::archive_name)
+                .and_then(|archive| manifest.as_ref()?.asset(&archive).cloned());
+            let download_size = asset
+                .as_ref()
+                .map(|asset| asset.size)
+                .filter(|size| *size > 0)
+                .or_else(|| info.and_then(|i| manager.known_download_size(i)));
+            let used_by: std::collections::BTreeSet<String> = dependents
+                .remove(version)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|d| d.project.unwrap_or(d.path).display().to_string())
+                .collect();
+            json!({
+                "version": version,
+                "installed": is_installed,
+                "path": is_installed.then(|| manager.python_binary(version).display().to_string()),
+                "release_date": info.map(|i| i.release_date.clone()),
+                "support_status": lifecycle.map(|l| l.status(&today)),
+                "end_of_life": lifecycle.map(|l| l.end_of_life),
+                "download_size_bytes": download_size,
+                "build_date": manifest.as_ref().and_then(|m| m.release_date()),
+                "archive_sha256": asset.and_then(|asset| asset.sha256),
+                "installed_size_bytes": is_installed.then(|| manager.installed_size(version)),
+                "satisfies_requires_python": requires_python
+                    .as_deref()
+                    .map(|spec| crate::resolver::requires_python_allows(spec, release)),
+                "implementation": runtime.as_ref().map(|r| r.implementation.clone()),
+                "free_threaded": runtime
+                    .as_ref()
+                    .map(|r| r.flavor == crate::tags::Flavor::FreeThreaded),
+                "interpreter_tag": runtime.as_ref().map(|r| r.interpreter_tag.clone()),
+                "abi_tag": runtime.as_ref().map(|r| r.abi_tag.clone()),
+                "used_by": used_by,
+                "source_build": is_installed
+                    .then(|| crate::runtime_source::source_build_info(&manager.python_binary(version)))
+                    .flatten(),
+            })
+        })
+        .collect();
 
+    let mut text_output = String::new();
     if args.all {
         text_output.push_str("Available Python versions:\n");
-        for v in &available {
-            let status = if installed.iter().any(|i| i == &v.version) {
-                " (installed)"
-            } else {
-                ""
-            };
-            text_output.push_str(&format!("  {}{}\n", v.version, status));
-        }
     } else {
         text_output.push_str("Installed Python versions:\n");
-        if installed.is_empty() {
+        if versions.is_empty() {
             text_output.push_str("  (none)\n");
             text_output
                 .push_str("\nUse 'pybun python install <VERSION>' to install a Python version.");
+        }
+    }
+    for v in &versions {
+        let mut notes = Vec::new();
+        if args.all && v["installed"] == true {
+            notes.push("installed".to_string());
+        }
+        if v["free_threaded"] == true {
+            notes.push("free-threaded".to_string());
+        } else if v["implementation"] == "pypy" {
+            notes.push("PyPy".to_string());
+        }
+        if let Some(status) = v["support_status"].as_str() {
+            notes.push(match status {
+                "end-of-life" => format!(
+                    "end-of-life since {}",
+                    v["end_of_life"].as_str().unwrap_or("?")
+                ),
+                _ => format!(
+                    "{status} until {}",
+                    v["end_of_life"].as_str().unwrap_or("?")
+                ),
+            });
+        }
+        if let Some(size) = v["download_size_bytes"].as_u64() {
+            notes.push(format!("{} download", crate::units::format_bytes(size)));
+        }
+        if let Some(date) = v["build_date"].as_str() {
+            notes.push(format!("built {date}"));
+        }
+        if v["satisfies_requires_python"] == false {
+            notes.push("outside requires-python".to_string());
+        }
+        let version = v["version"].as_str().unwrap_or_default();
+        if notes.is_empty() {
+            text_output.push_str(&format!("  {}\n", version));
         } else {
-            for v in &installed {
-                text_output.push_str(&format!("  {}\n", v));
-            }
+            text_output.push_str(&format!("  {} ({})\n", version, notes.join(", ")));
         }
     }
+    for error in &size_errors {
+        text_output.push_str(&format!(
+            "warning: could not fetch download size for {error}\n"
+        ));
+    }
 
     let json = json!({
         "installed": installed,
-        "available": available.iter().map(|v| &v.version).collect::<Vec<_>>(),
+        "available": names,
+        "versions": versions,
+        "requires_python": requires_python,
+        "size_errors": size_errors,
     });
 
     Ok((
         "list".to_string(),
-        RenderDetail::with_json(text_output.trim(), json),
+        RenderDetail::with_json(text_output.trim(), json).with_table(TableSpec::new(
+            "versions",
+            &["version", "installed", "path"],
+        )),
     ))
 }
 
-fn python_install(args: &crate::cli::PythonInstallArgs) -> Result<(String, RenderDetail)> {
+fn python_install(
+    args: &crate::cli::PythonInstallArgs,
+    collector: &mut EventCollector,
+) -> Result<(String, RenderDetail)> {
     let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
     let manager = RuntimeManager::new(cache);
 
+    if args.from_source {
+        let config = crate::runtime_source::SourceBuildConfig {
+            git_ref: args
+                .git_ref
+                .clone()
+                .unwrap_or_else(|| format!("v{}", args.version)),
+            pydebug: args.pydebug,
+            lto: args.lto,
+            configure_args: args.configure_args.clone(),
+        };
+        let install = manager.install_from_source(&args.version, &config)?;
+        let summary = if install.reused {
+            format!(
+                "Python {} is already built from {} at {}",
+                install.name,
+                install.info.commit,
+                install.python.display()
+            )
+        } else {
+            format!(
+                "Built Python {} from {} at {}",
+                install.name,
+                install.info.commit,
+                install.python.display()
+            )
+        };
+        let json = json!({
+            "version": install.name,
+            "path": install.python.display().to_string(),
+            "status": if install.reused { "already_installed" } else { "built" },
+            "source_build": install.info,
+        });
+        return Ok((
+            "install".to_string(),
+            RenderDetail::with_json(summary, json),
+        ));
+    }
+
     // Check if already installed
     if manager.is_installed(&args.version) {
         let path = manager.python_binary(&args.version);
@@ -4080,17 +8878,62 @@ fn python_install(args: &crate::cli::PythonInstallArgs) -> Result<(String, Rende
     }
 
     // Install
-    let python_path = manager.ensure_version(&args.version)?;
+    let mut download = json!({});
+    let mut extracting = false;
+    let python_path = manager.ensure_version_with_progress(&args.version, &mut |progress| {
+        use crate::runtime::InstallProgress;
+        match progress {
+            InstallProgress::Started { version, url } => {
+                download["url"] = json!(url);
+                collector.event_with(EventType::DownloadStart, |event| {
+                    event.message = Some(format!("Downloading Python {version}"));
+                    event.data = Some(json!({ "version": version, "url": url }));
+                });
+            }
+            InstallProgress::Downloading { downloaded, total } => {
+                download["bytes"] = json!(downloaded);
+                collector.event_with(EventType::DownloadProgress, |event| {
+                    event.progress = total
+                        .filter(|total| *total > 0)
+                        .map(|total| (downloaded * 100 / total).min(100) as u8);
+                    event.data = Some(json!({ "downloaded": downloaded, "total": total }));
+                });
+            }
+            InstallProgress::Verified { sha256 } => {
+                download["sha256"] = json!(sha256);
+                collector.event_with(EventType::DownloadComplete, |event| {
+                    event.message = Some(format!("Verified checksum {sha256}"));
+                    event.data = Some(json!({ "sha256": sha256 }));
+                });
+            }
+            InstallProgress::Extracting { .. } => {
+                if !extracting {
+                    extracting = true;
+                    collector.event(EventType::ExtractStart);
+                }
+            }
+            InstallProgress::Warning(message) => collector.warning(message),
+            InstallProgress::Installed { path } => {
+                collector.event_with(EventType::ExtractComplete, |event| {
+                    event.message = Some(format!("Installed to {}", path.display()));
+                });
+            }
+        }
+    })?;
 
     let summary = format!(
         "Installed Python {} at {}",
         args.version,
         python_path.display()
     );
+    let runtime = crate::runtime::find_version(&args.version)
+        .and_then(|info| manager.runtime_info(&info.name()));
     let json = json!({
         "version": args.version,
         "path": python_path.display().to_string(),
         "status": "installed",
+        "download": download,
+        "runtime": runtime,
     });
 
     Ok((
@@ -4099,16 +8942,98 @@ fn python_install(args: &crate::cli::PythonInstallArgs) -> Result<(String, Rende
     ))
 }
 
-fn python_remove(args: &crate::cli::PythonRemoveArgs) -> Result<(String, RenderDetail)> {
+/// Environments and pins using each installed runtime: registered projects
+/// and the current one, cached script and tool environments.
+fn runtime_dependents(
+    manager: &RuntimeManager,
+    cache: &Cache,
+) -> std::collections::BTreeMap<String, Vec<crate::runtime_usage::Dependent>> {
+    let mut projects: Vec<crate::runtime_usage::ProjectRef> =
+        crate::project_registry::ProjectRegistry::new()
+            .and_then(|registry| registry.load())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|record| crate::runtime_usage::ProjectRef {
+                root: record.root,
+                env: record.env,
+            })
+            .collect();
+    if let Ok(cwd) = std::env::current_dir()
+        && let Ok(project) = Project::discover(&cwd)
+        && !projects.iter().any(|p| p.root == project.root())
+    {
+        projects.push(crate::runtime_usage::ProjectRef {
+            root: project.root().to_path_buf(),
+            env: None,
+        });
+    }
+    crate::runtime_usage::dependents(manager, cache, &projects)
+}
+
+fn python_remove(
+    args: &crate::cli::PythonRemoveArgs,
+    collector: &mut EventCollector,
+) -> Result<(String, RenderDetail)> {
     let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
-    let manager = RuntimeManager::new(cache);
+    let manager = RuntimeManager::new(cache.clone());
+
+    let dependents = if manager.is_installed(&args.version) {
+        runtime_dependents(&manager, &cache)
+            .remove(&args.version)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let listing: Vec<String> = dependents
+        .iter()
+        .map(|d| format!("  {} {}", d.kind.label(), d.path.display()))
+        .collect();
+
+    if !dependents.is_empty() && !args.force {
+        let message = format!(
+            "Python {} is still used by {} environment(s) or pin(s)",
+            args.version,
+            dependents.len()
+        );
+        collector.error_with_code(
+            "E_PYTHON_RUNTIME_IN_USE",
+            message.clone(),
+            "Recreate or remove the listed environments first, or pass --force to remove the runtime anyway.",
+        );
+        let json = json!({
+            "version": args.version,
+            "status": "in_use",
+            "dependents": dependents,
+        });
+        return Ok((
+            "remove".to_string(),
+            RenderDetail::error(format!("{message}:\n{}", listing.join("\n")), json),
+        ));
+    }
 
     manager.remove_version(&args.version)?;
 
-    let summary = format!("Removed Python {}", args.version);
+    let mut summary = format!("Removed Python {}", args.version);
+    if !dependents.is_empty() {
+        let warning = format!(
+            "removed Python {} while {} environment(s) or pin(s) still use it",
+            args.version,
+            dependents.len()
+        );
+        collector.diagnostic(
+            Diagnostic::warning(warning.clone())
+                .with_code("W_PYTHON_RUNTIME_IN_USE")
+                .with_suggestion(format!(
+                    "Run `pybun python install {}` to restore it, or recreate the listed environments.",
+                    args.version
+                )),
+        );
+        summary.push_str(&format!("\nwarning: {warning}:\n{}", listing.join("\n")));
+    }
     let json = json!({
         "version": args.version,
         "status": "removed",
+        "dependents": dependents,
     });
 
     Ok(("remove".to_string(), RenderDetail::with_json(summary, json)))
@@ -4124,17 +9049,134 @@ struct XOutcome {
     package: String,
     version: Option<String>,
     passthrough: Vec<String>,
-    temp_env: String,
     python_version: String,
+    /// The command run, with the entry point it calls (`module:attr`).
+    command: Option<String>,
+    entry_point: Option<String>,
     exit_code: i32,
-    cleanup: bool,
+    tool_env: Value,
+    env: sandbox::EnvPassthrough,
+    install: Option<env_install::EnvInstall>,
+    sandbox: Option<SandboxInfo>,
 }
 
-fn execute_tool(args: &crate::cli::ToolArgs, _collector: &mut EventCollector) -> Result<XOutcome> {
-    let package_spec = args
+/// Interpreter for a tool environment: a managed runtime with `--python`,
+/// else the one `pybun run` would use. Returns (path, version).
+fn tool_python(args: &crate::cli::ToolArgs) -> Result<(PathBuf, String)> {
+    match args.python.as_deref() {
+        Some(requested) => {
+            let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+            let (version, python) =
+                RuntimeManager::new(cache).select_runtime(Some(requested), None)?;
+            Ok((python, version))
+        }
+        None => {
+            let working_dir = std::env::current_dir()?;
+            let env = find_python_env(&working_dir)?;
+            let version = env.version.clone().unwrap_or_else(|| "unknown".to_string());
+            Ok((env.python_path, version))
+        }
+    }
+}
+
+/// Create the environment for `key` and install `package_spec` into it.
+/// The caller holds the environment's lock.
+async fn build_tool_env(
+    cache: &ToolEnvCache,
+    key: &ToolEnvKey,
+    python: &Path,
+    python_version: &str,
+    package_spec: &str,
+    collector: &mut EventCollector,
+) -> Result<(ToolEnvInfo, env_install::EnvInstall)> {
+    let venv_path = cache.venv_path(key);
+    eprintln!("info: creating tool environment at {}", venv_path.display());
+    record_env_create(collector, &venv_path);
+    let installer_kind = env_install::InstallerKind::from_env();
+    env_install::create_venv(python, &venv_path, installer_kind)?;
+
+    eprintln!("info: installing {}...", package_spec);
+    let install = env_install::install(
+        env_install::InstallRequest {
+            venv: &venv_path,
+            python_version,
+            requirements: &[package_spec.to_string()],
+            no_deps: false,
+            pip_cache_dir: None,
+        },
+        installer_kind,
+        collector,
+    )
+    .await?;
+
+    let version = tool_env::installed_version(&venv_path, &key.package);
+    let info = cache.record(key, version)?;
+    Ok((info, install))
+}
+
+/// Reuse the cached environment for `key`, or build it; the install is
+/// `None` when reused. The lock is only held while building, not while the
+/// tool runs.
+async fn cached_tool_env(
+    cache: &ToolEnvCache,
+    key: &ToolEnvKey,
+    python: &Path,
+    python_version: &str,
+    package_spec: &str,
+    collector: &mut EventCollector,
+) -> Result<(ToolEnvInfo, Option<env_install::EnvInstall>)> {
+    let _lock = cache.lock(key)?;
+    if let Some(info) = cache.get(key) {
+        eprintln!("info: using cached environment for {}", package_spec);
+        cache.touch(key)?;
+        return Ok((info, None));
+    }
+    cache.clear(key)?;
+    let (info, install) =
+        build_tool_env(cache, key, python, python_version, package_spec, collector)
+            .await
+            .inspect_err(|_| {
+                let _ = cache.clear(key);
+            })?;
+    Ok((info, Some(install)))
+}
+
+fn tool_env_json(path: &Path, info: Option<&ToolEnvInfo>, reused: bool) -> Value {
+    json!({
+        "path": path.display().to_string(),
+        "reused": reused,
+        "version": info.and_then(|info| info.version.clone()),
+    })
+}
+
+/// The executable `name` in a venv's `bin/`. On Windows pip writes `.exe`
+/// launchers and the native installer `.cmd` ones.
+fn tool_executable(venv: &Path, name: &str) -> Option<PathBuf> {
+    let candidates = if cfg!(windows) {
+        let scripts = venv.join("Scripts");
+        vec![
+            scripts.join(format!("{}.exe", name)),
+            scripts.join(format!("{}.cmd", name)),
+        ]
+    } else {
+        vec![venv.join("bin").join(name)]
+    };
+    candidates.into_iter().find(|path| path.exists())
+}
+
+async fn execute_tool(
+    args: &crate::cli::ToolArgs,
+    collector: &mut EventCollector,
+) -> Result<XOutcome> {
+    let positional = args
         .package
         .as_ref()
         .ok_or_else(|| eyre!("package name is required"))?;
+    // `pybun x --from PACKAGE COMMAND` installs PACKAGE and runs COMMAND.
+    let (package_spec, requested_command) = match &args.from {
+        Some(package) => (package, Some(positional.as_str())),
+        None => (positional, None),
+    };
 
     // Parse package name and version
     let (package_name, version) = parse_package_spec(package_spec);
@@ -4142,16 +9184,26 @@ fn execute_tool(args: &crate::cli::ToolArgs, _collector: &mut EventCollector) ->
     // Check for dry-run mode (for testing)
     let dry_run = std::env::var("PYBUN_X_DRY_RUN").is_ok();
 
-    // Find Python interpreter
-    let working_dir = std::env::current_dir()?;
-    let env = find_python_env(&working_dir)?;
-    let python_path = env.python_path.to_string_lossy().to_string();
-    let python_version = env.version.clone().unwrap_or_else(|| "unknown".to_string());
+    let (python_path, python_version) = tool_python(args)?;
 
-    // Create temporary environment
-    let temp_dir =
-        tempfile::tempdir().map_err(|e| eyre!("failed to create temp directory: {}", e))?;
-    let temp_env_path = temp_dir.path().to_string_lossy().to_string();
+    // The environment is cached per (package, requirement, python version).
+    let cache = ToolEnvCache::new()?;
+    let key = ToolEnvKey::new(&package_name, package_spec, &python_version);
+    let venv_path = cache.venv_path(&key);
+
+    // The tool only sees a standard set of inherited variables plus --pass-env.
+    let env = sandbox::EnvPassthrough::from_env(&args.pass_env);
+    if !env.withheld.is_empty() && !args.sandbox.enabled {
+        let message = format!(
+            "withheld {} environment variable{} from {} (pass with --pass-env NAME): {}",
+            env.withheld.len(),
+            if env.withheld.len() == 1 { "" } else { "s" },
+            package_name,
+            env.withheld.join(", ")
+        );
+        eprintln!("info: {}", message);
+        collector.info(message);
+    }
 
     if dry_run {
         // In dry-run mode, just return the planned actions. Tests can set
@@ -4161,127 +9213,296 @@ fn execute_tool(args: &crate::cli::ToolArgs, _collector: &mut EventCollector) ->
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(0);
+        let cached = cache.get(&key);
         return Ok(XOutcome {
             summary: format!("would execute {} (dry-run)", package_name),
             package: package_name,
             version,
             passthrough: args.passthrough.clone(),
-            temp_env: temp_env_path,
             python_version,
+            command: requested_command.map(str::to_string),
+            entry_point: None,
             exit_code,
-            cleanup: true,
+            tool_env: tool_env_json(&venv_path, cached.as_ref(), cached.is_some()),
+            env,
+            install: None,
+            sandbox: None,
         });
     }
 
-    // Create virtual environment in temp directory
-    let venv_path = temp_dir.path().join("venv");
-    eprintln!(
-        "info: creating temporary environment at {}",
-        venv_path.display()
-    );
+    let (info, install) = cached_tool_env(
+        &cache,
+        &key,
+        &python_path,
+        &python_version,
+        package_spec,
+        collector,
+    )
+    .await?;
+    let reused = install.is_none();
 
-    let uv_available = crate::env::find_uv_executable().is_some();
+    // Get python path in venv
+    let venv_python = if cfg!(windows) {
+        venv_path.join("Scripts").join("python.exe")
+    } else {
+        venv_path.join("bin").join("python")
+    };
 
-    let mut venv_cmd = ProcessCommand::new(&python_path);
-    venv_cmd.args(["-m", "venv"]);
-    if uv_available {
-        venv_cmd.arg("--without-pip");
-    }
-    venv_cmd.arg(&venv_path);
-    let venv_status = venv_cmd
-        .status()
-        .map_err(|e| eyre!("failed to create virtual environment: {}", e))?;
+    // The command comes from the installed metadata, not the package name:
+    // `httpie` installs `http`.
+    let command = match tool_env::resolve_command(&venv_path, &package_name, requested_command) {
+        Ok(command) => command,
+        Err(e) => {
+            if let tool_env::ToolEnvError::UnknownCommand { available, .. } = &e {
+                let suggestion = match available.first() {
+                    Some(first) => format!(
+                        "Name the command to run: `pybun x --from {} {}`.",
+                        package_spec, first
+                    ),
+                    None => format!(
+                        "{} installs no commands; check the package name.",
+                        package_name
+                    ),
+                };
+                collector.diagnostic(
+                    Diagnostic::error(e.to_string())
+                        .with_code("E_X_COMMAND_NOT_FOUND")
+                        .with_suggestion(suggestion)
+                        .with_context(json!({
+                            "package": package_name,
+                            "command": requested_command.unwrap_or(&package_name),
+                            "available": available,
+                        })),
+                );
+            }
+            return Err(e.into());
+        }
+    };
 
-    if !venv_status.success() {
-        return Err(eyre!("failed to create virtual environment"));
+    let mut cmd = match &command {
+        tool_env::ToolCommand::Module(module) => {
+            eprintln!("info: executing python -m {}...", module);
+            let mut cmd = ProcessCommand::new(&venv_python);
+            cmd.args(["-m", module]);
+            cmd
+        }
+        _ => match tool_executable(&venv_path, command.name()) {
+            Some(executable) => {
+                eprintln!("info: executing {}...", executable.display());
+                ProcessCommand::new(executable)
+            }
+            // No wrapper script in the environment: call the entry point
+            // the way one would.
+            None => {
+                let tool_env::ToolCommand::EntryPoint(entry) = &command else {
+                    return Err(eyre!(
+                        "{} is missing from {}",
+                        command.name(),
+                        venv_path.display()
+                    ));
+                };
+                eprintln!("info: executing {} ({})...", entry.name, entry.target());
+                let mut cmd = ProcessCommand::new(&venv_python);
+                cmd.args(["-c", &entry.python_code()]);
+                cmd
+            }
+        },
+    };
+    env.apply(&mut cmd);
+    cmd.args(&args.passthrough);
+
+    // Under --sandbox the sandbox's env filter replaces the tool policy.
+    let mut sandbox_guard = None;
+    let mut sandbox_info = None;
+    if args.sandbox.enabled {
+        let writable = sandbox_write_dirs(&[&std::env::current_dir()?], &venv_python);
+        let (guard, info) =
+            apply_sandbox_args(&mut cmd, &args.sandbox, writable, command.name(), collector)?;
+        sandbox_guard = Some(guard);
+        sandbox_info = Some(info);
+    }
+    let sandbox::SandboxedExecution {
+        status, timed_out, ..
+    } = sandbox::execute_with_optional_sandbox(&mut cmd, sandbox_guard.as_ref(), false)
+        .map_err(|e| eyre!("failed to execute {}: {}", command.name(), e))?;
+    if let (Some(guard), Some(info)) = (sandbox_guard, &mut sandbox_info) {
+        finish_sandbox(guard, info, &status, timed_out, collector);
     }
+    let exit_code = status.code().unwrap_or(-1);
 
-    // Get pip path in venv
-    let pip_path = if cfg!(windows) {
-        venv_path.join("Scripts").join("pip.exe")
+    let summary = if exit_code == 0 {
+        format!("executed {} successfully", package_name)
     } else {
-        venv_path.join("bin").join("pip")
+        format!("{} exited with code {}", package_name, exit_code)
     };
 
-    // Get python path in venv
-    let venv_python = if cfg!(windows) {
-        venv_path.join("Scripts").join("python.exe")
-    } else {
-        venv_path.join("bin").join("python")
+    let entry_point = match &command {
+        tool_env::ToolCommand::EntryPoint(entry) => Some(entry.target()),
+        _ => None,
     };
+    Ok(XOutcome {
+        summary,
+        package: package_name,
+        version,
+        passthrough: args.passthrough.clone(),
+        python_version,
+        command: Some(command.name().to_string()),
+        entry_point,
+        exit_code,
+        tool_env: tool_env_json(&venv_path, Some(&info), reused),
+        env,
+        install,
+        sandbox: sandbox_info,
+    })
+}
+
+/// `pybun x --list`, `--upgrade PACKAGE` and `--remove PACKAGE`.
+async fn manage_tools(
+    args: &crate::cli::ToolArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let cache = ToolEnvCache::new()?;
+
+    if args.list {
+        let envs = cache.list()?;
+        let rows: Vec<Value> = envs
+            .iter()
+            .map(|env| serde_json::to_value(env).unwrap_or_else(|_| json!({})))
+            .collect();
+        let summary = if envs.is_empty() {
+            "no cached tool environments".to_string()
+        } else {
+            let width = envs
+                .iter()
+                .map(|env| env.info.requirement.len())
+                .max()
+                .unwrap_or(0);
+            let mut text = format!("{} tool environment(s):", envs.len());
+            for env in &envs {
+                text.push_str(&format!(
+                    "\n  {:<width$}  {:<10}  Python {}  {}",
+                    env.info.requirement,
+                    env.info.version.as_deref().unwrap_or("?"),
+                    env.info.python_version,
+                    crate::units::format_bytes(env.size_bytes),
+                ));
+            }
+            text
+        };
+        return Ok(
+            RenderDetail::with_json(summary, json!({ "tools": rows })).with_table(TableSpec::new(
+                "tools",
+                &["package", "version", "python_version", "requirement"],
+            )),
+        );
+    }
 
-    // Install the package using uv if available, otherwise pip
-    eprintln!("info: installing {}...", package_spec);
-    let install_status = if let Some(uv_path) = crate::env::find_uv_executable() {
-        ProcessCommand::new(uv_path)
-            .args(["pip", "install", "--quiet", "--python"])
-            .arg(&venv_path)
-            .arg(package_spec)
-            .status()
-            .map_err(|e| eyre!("failed to install package with uv: {}", e))?
-    } else {
-        ProcessCommand::new(&pip_path)
-            .args(["install", "--quiet", package_spec])
-            .status()
-            .map_err(|e| eyre!("failed to install package: {}", e))?
-    };
+    let package_spec = args
+        .package
+        .as_ref()
+        .ok_or_else(|| eyre!("package name is required"))?;
+    let (package_name, _) = parse_package_spec(package_spec);
 
-    if !install_status.success() {
-        return Err(eyre!("failed to install package {}", package_spec));
+    if args.remove {
+        let removed = cache.remove(&package_name)?;
+        let summary = if removed.is_empty() {
+            format!("no cached environments for {}", package_name)
+        } else {
+            format!(
+                "removed {} environment(s) of {}",
+                removed.len(),
+                package_name
+            )
+        };
+        return Ok(RenderDetail::with_json(
+            summary,
+            json!({
+                "package": package_name,
+                "removed": removed,
+            }),
+        ));
     }
 
-    // Find and execute the entry point
-    // Most packages have a console script with the same name as the package
-    let entry_point = if cfg!(windows) {
-        venv_path
-            .join("Scripts")
-            .join(format!("{}.exe", package_name))
-    } else {
-        venv_path.join("bin").join(&package_name)
-    };
-
-    let exit_code = if entry_point.exists() {
-        // Execute the console script directly
-        eprintln!("info: executing {}...", entry_point.display());
-        let mut cmd = ProcessCommand::new(&entry_point);
-        for arg in &args.passthrough {
-            cmd.arg(arg);
+    // --upgrade: rebuild the environment `pybun x PACKAGE_SPEC` would use.
+    // The old venv is moved aside (venvs cannot be relocated, so it is moved
+    // back unchanged) and restored if the new install fails.
+    let (python_path, python_version) = tool_python(args)?;
+    let key = ToolEnvKey::new(&package_name, package_spec, &python_version);
+    let _lock = cache.lock(&key)?;
+    let previous = cache.get(&key);
+    let venv_path = cache.venv_path(&key);
+    let backup = venv_path.with_extension("old");
+    if backup.exists() {
+        fs::remove_dir_all(&backup)?;
+    }
+    if previous.is_some() {
+        fs::rename(&venv_path, &backup)?;
+    }
+    cache.clear(&key)?;
+    let built = build_tool_env(
+        &cache,
+        &key,
+        &python_path,
+        &python_version,
+        package_spec,
+        collector,
+    )
+    .await;
+    let (info, install) = match built {
+        Ok(built) => {
+            if backup.exists() {
+                fs::remove_dir_all(&backup)?;
+            }
+            built
         }
-        let status = cmd
-            .status()
-            .map_err(|e| eyre!("failed to execute {}: {}", package_name, e))?;
-        status.code().unwrap_or(-1)
-    } else {
-        // Fallback: try to run as a module
-        eprintln!("info: executing python -m {}...", package_name);
-        let mut cmd = ProcessCommand::new(&venv_python);
-        cmd.args(["-m", &package_name]);
-        for arg in &args.passthrough {
-            cmd.arg(arg);
+        Err(e) => {
+            if venv_path.exists() {
+                fs::remove_dir_all(&venv_path)?;
+            }
+            if let Some(previous) = &previous {
+                fs::rename(&backup, &venv_path)?;
+                cache.record(&key, previous.version.clone())?;
+            }
+            return Err(e);
         }
-        let status = cmd
-            .status()
-            .map_err(|e| eyre!("failed to execute module {}: {}", package_name, e))?;
-        status.code().unwrap_or(-1)
     };
 
-    // Cleanup is automatic when temp_dir is dropped
-    let summary = if exit_code == 0 {
-        format!("executed {} successfully", package_name)
-    } else {
-        format!("{} exited with code {}", package_name, exit_code)
+    let previous_version = previous.and_then(|info| info.version);
+    let version = info.version.clone();
+    let summary = match (&previous_version, &version) {
+        (Some(old), Some(new)) if old == new => {
+            format!("{} is up to date ({})", package_name, new)
+        }
+        (Some(old), Some(new)) => format!("upgraded {} {} -> {}", package_name, old, new),
+        (_, Some(new)) => format!("installed {} {}", package_name, new),
+        _ => format!("installed {}", package_name),
     };
-
-    Ok(XOutcome {
+    Ok(RenderDetail::with_json(
         summary,
-        package: package_name,
-        version,
-        passthrough: args.passthrough.clone(),
-        temp_env: temp_env_path,
-        python_version,
-        exit_code,
-        cleanup: true,
-    })
+        json!({
+            "package": package_name,
+            "previous_version": previous_version,
+            "version": version,
+            "python_version": python_version,
+            "tool_env": tool_env_json(&venv_path, Some(&info), false),
+            "install": install,
+        }),
+    ))
+}
+
+/// Under `--offline`, restrict a `uv pip install` (`uv`) or `pip install` to
+/// the local wheel cache.
+fn offline_install_args(cmd: &mut ProcessCommand, uv: bool) {
+    if !crate::offline::is_enabled() {
+        return;
+    }
+    cmd.arg(if uv { "--offline" } else { "--no-index" });
+    if let Some(dir) = crate::pypi::artifacts_cache_dir() {
+        cmd.arg("--find-links").arg(dir);
+    }
+    if let Some(layers) = crate::pypi::artifacts_cache_layers() {
+        cmd.arg("--find-links").arg(layers.shared);
+    }
 }
 
 /// Parse a package specification like "cowsay==6.1" into (name, version)
@@ -4310,6 +9531,11 @@ fn run_self_update(
 
     collector.info(format!("Checking for updates on {} channel", channel));
 
+    let store = UpdateStore::new();
+    // An installation that cannot persist its cohort is treated as the last
+    // cohort, so it only updates once a release is fully rolled out.
+    let cohort = store.cohort().unwrap_or(99);
+
     let manifest_source_env = std::env::var("PYBUN_SELF_UPDATE_MANIFEST").ok();
     let default_manifest_url = default_manifest_url(channel);
     let manifest_source = manifest_source_env
@@ -4319,7 +9545,10 @@ fn run_self_update(
         || manifest_source_env.is_some()
         || std::env::var("PYBUN_SELF_UPDATE_FETCH").is_ok();
     let manifest_result = if should_fetch_manifest {
-        Some(ReleaseManifest::load(&manifest_source))
+        Some(ReleaseManifest::load_for_cohort(
+            &manifest_source,
+            Some(cohort),
+        ))
     } else {
         None
     };
@@ -4335,6 +9564,10 @@ fn run_self_update(
     let mut rollback_performed = false;
     let mut install_path = None;
     let mut update_error = None;
+    let mut rollout_decision = RolloutDecision::Eligible;
+    let mut rollout_detail = Value::Null;
+    let mut held_back = false;
+    let mut backup_path = None;
 
     match manifest_result {
         Some(Ok(manifest)) => {
@@ -4343,6 +9576,13 @@ fn run_self_update(
                 .compare_version(current_version)
                 .map(|ordering| ordering == Ordering::Greater)
                 .unwrap_or(false);
+            rollout_decision = update_channel::decide(manifest.rollout.as_ref(), cohort);
+            rollout_detail = json!({
+                "decision": rollout_decision.as_str(),
+                "percentage": manifest.rollout.as_ref().map_or(100, |r| r.percentage),
+                "halted": manifest.rollout.as_ref().is_some_and(|r| r.halted),
+            });
+            held_back = store.is_held_back(&manifest.version);
             release_url = manifest
                 .release_url
                 .clone()
@@ -4377,6 +9617,27 @@ fn run_self_update(
         None => {}
     }
 
+    // Why an available update is not being installed, if it is not.
+    let blocked_reason = if !update_available {
+        None
+    } else {
+        match rollout_decision {
+            RolloutDecision::Halted => Some(format!(
+                "release {latest_version} was halted by the publisher"
+            )),
+            RolloutDecision::Deferred { cohort, percentage } if !args.force => Some(format!(
+                "release {latest_version} is rolling out to {percentage}% of installations (this one is cohort {cohort}); use --force to update now"
+            )),
+            _ if held_back && !args.force => Some(format!(
+                "release {latest_version} was rolled back on this machine; use --force to reinstall it"
+            )),
+            _ => None,
+        }
+    };
+    if let Some(reason) = blocked_reason.as_deref() {
+        collector.info(reason.to_string());
+    }
+
     if !args.dry_run {
         if let Some(error) = manifest_error.as_deref() {
             let message = format!("failed to load release manifest: {error}");
@@ -4386,7 +9647,7 @@ fn run_self_update(
                 "Check network connectivity and the release manifest URL (--channel or PYBUN_SELF_UPDATE_MANIFEST_URL), then retry `pybun self update`.",
             );
             update_error = Some(message);
-        } else if update_available {
+        } else if update_available && blocked_reason.is_none() {
             let Some(asset) = selected_asset else {
                 let target_text = target
                     .as_deref()
@@ -4415,12 +9676,26 @@ fn run_self_update(
                     "rollback_performed": false,
                     "install_path": Value::Null,
                     "error": update_error,
+                    "cohort": cohort,
+                    "rollout": rollout_detail,
+                    "held_back": held_back,
+                    "backup_path": Value::Null,
                 });
                 return RenderDetail::error(summary, json_detail);
             };
             let install_override = std::env::var("PYBUN_SELF_UPDATE_BIN")
                 .ok()
                 .map(PathBuf::from);
+            // Keep the running binary so `self rollback` can restore it.
+            if let Ok(current) = crate::self_update::resolve_install_path(install_override.clone())
+            {
+                match store.keep_previous(&current, current_version) {
+                    Ok(path) => backup_path = Some(path),
+                    Err(e) => collector.warning(format!(
+                        "could not keep the current binary for rollback: {e}"
+                    )),
+                }
+            }
             let fail_swap_for_test = std::env::var("PYBUN_SELF_UPDATE_TEST_FAIL_SWAP").is_ok();
             let target_name = target
                 .as_deref()
@@ -4433,6 +9708,17 @@ fn run_self_update(
                     update_applied = true;
                     rollback_performed = outcome.rollback_performed;
                     install_path = Some(outcome.install_path.display().to_string());
+                    if let Err(e) = store.record(HistoryEntry {
+                        action: HistoryAction::Update,
+                        from_version: current_version.to_string(),
+                        to_version: latest_version.clone(),
+                        channel: Some(channel.clone()),
+                        timestamp: update_channel::now_timestamp(),
+                        install_path: outcome.install_path.clone(),
+                        backup_path: backup_path.clone(),
+                    }) {
+                        collector.warning(format!("could not record update history: {e}"));
+                    }
                     collector.info(format!(
                         "Updated binary at {}",
                         outcome.install_path.display()
@@ -4451,7 +9737,14 @@ fn run_self_update(
         }
     }
 
-    let summary = if args.dry_run {
+    let summary = if let Some(reason) = blocked_reason.as_deref()
+        && update_error.is_none()
+    {
+        format!(
+            "Update available: {} -> {} (not installed: {})",
+            current_version, latest_version, reason
+        )
+    } else if args.dry_run {
         if update_available {
             format!(
                 "Update available: {} -> {} (dry-run, no changes made)",
@@ -4493,6 +9786,11 @@ fn run_self_update(
         "rollback_performed": rollback_performed,
         "install_path": install_path,
         "error": update_error,
+        "cohort": cohort,
+        "rollout": rollout_detail,
+        "held_back": held_back,
+        "blocked_reason": blocked_reason,
+        "backup_path": backup_path.map(|p| p.display().to_string()),
     });
 
     if !args.dry_run && update_error.is_some() {
@@ -4502,6 +9800,117 @@ fn run_self_update(
     }
 }
 
+fn run_self_rollback(
+    args: &crate::cli::SelfRollbackArgs,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    let store = UpdateStore::new();
+    let Some(entry) = store.rollback_candidate() else {
+        let message = "no update to roll back (history is empty, the last action was already a rollback, or the kept binary is gone)".to_string();
+        collector.error_with_code(
+            "E_SELF_ROLLBACK_UNAVAILABLE",
+            message.clone(),
+            "Run `pybun self history` to see recorded updates; reinstall PyBun manually if no previous binary was kept.",
+        );
+        return RenderDetail::error(message.clone(), json!({ "error": message }));
+    };
+    let backup = entry.backup_path.clone().unwrap_or_default();
+    let install_path = std::env::var("PYBUN_SELF_UPDATE_BIN")
+        .ok()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| entry.install_path.clone());
+    let mut detail = json!({
+        "from_version": entry.to_version,
+        "to_version": entry.from_version,
+        "install_path": install_path.display().to_string(),
+        "backup_path": backup.display().to_string(),
+        "dry_run": args.dry_run,
+        "rolled_back": false,
+    });
+
+    if args.dry_run {
+        return RenderDetail::with_json(
+            format!(
+                "Would roll back {} -> {} (dry-run, no changes made)",
+                entry.to_version, entry.from_version
+            ),
+            detail,
+        );
+    }
+
+    if let Err(error) = crate::self_update::install_binary(&install_path, &backup) {
+        collector.error_with_code(
+            "E_SELF_ROLLBACK_FAILED",
+            error.to_string(),
+            "Check write permissions to the install path, then retry `pybun self rollback`.",
+        );
+        detail["error"] = json!(error.to_string());
+        return RenderDetail::error(format!("Rollback failed: {error}"), detail);
+    }
+    if let Err(e) = store.record(HistoryEntry {
+        action: HistoryAction::Rollback,
+        from_version: entry.to_version.clone(),
+        to_version: entry.from_version.clone(),
+        channel: entry.channel.clone(),
+        timestamp: update_channel::now_timestamp(),
+        install_path: install_path.clone(),
+        backup_path: None,
+    }) {
+        collector.warning(format!("could not record rollback in history: {e}"));
+    }
+    detail["rolled_back"] = json!(true);
+    RenderDetail::with_json(
+        format!(
+            "Rolled back {} -> {} ({} will be skipped by `pybun self update` until --force)",
+            entry.to_version, entry.from_version, entry.to_version
+        ),
+        detail,
+    )
+}
+
+fn run_self_history() -> RenderDetail {
+    let store = UpdateStore::new();
+    let history = store.history();
+    let held_back = store.held_back();
+    let cohort = store.cohort().ok();
+    let mut text = if history.is_empty() {
+        "no self-update history".to_string()
+    } else {
+        history
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{:<8} {} -> {} (epoch:{})",
+                    serde_json::to_value(entry.action)
+                        .ok()
+                        .and_then(|v| v.as_str().map(str::to_string))
+                        .unwrap_or_default(),
+                    entry.from_version,
+                    entry.to_version,
+                    entry.timestamp
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    if !held_back.is_empty() {
+        text.push_str(&format!("\nheld back: {}", held_back.join(", ")));
+    }
+    RenderDetail::with_json(
+        text,
+        json!({
+            "history": history,
+            "held_back": held_back,
+            "cohort": cohort,
+            "rollback_available": store.rollback_candidate().is_some(),
+        }),
+    )
+    .with_table(TableSpec::new(
+        "history",
+        &["action", "from_version", "to_version", "timestamp"],
+    ))
+}
+
 fn default_manifest_url(channel: &str) -> String {
     if channel == "nightly" {
         "https://github.com/VOID-TECHNOLOGY-INC/PyBun/releases/download/nightly/pybun-release.json"
@@ -4570,12 +9979,177 @@ fn python_which(args: &crate::cli::PythonWhichArgs) -> Result<(String, RenderDet
         "version": env.version,
         "path": env.python_path.display().to_string(),
         "source": format!("{}", env.source),
-        "managed": false,
+        "managed": matches!(env.source, crate::env::EnvSource::ManagedRuntime(_)),
     });
 
     Ok(("which".to_string(), RenderDetail::with_json(summary, json)))
 }
 
+fn pin_conflict_diagnostic(version: &str, requires_python: &str, file: &Path) -> Diagnostic {
+    Diagnostic::warning(format!(
+        "{} pins Python {version}, which does not satisfy requires-python {requires_python}",
+        file.display()
+    ))
+    .with_code("W_PYTHON_PIN_CONFLICT")
+    .with_suggestion("Pin a version requires-python admits with `pybun python pin <VERSION>`, or widen requires-python in pyproject.toml.")
+    .with_context(json!({
+        "pin": version,
+        "pinned_release": crate::python_pin::pinned_release(version),
+        "requires_python": requires_python,
+        "file": file.display().to_string(),
+    }))
+}
+
+fn python_pin(
+    args: &crate::cli::PythonPinArgs,
+    collector: &mut EventCollector,
+) -> Result<(String, RenderDetail)> {
+    let working_dir = std::env::current_dir()?;
+    let project = crate::project::Project::discover(&working_dir).ok();
+    let requires_python = project.as_ref().and_then(|p| p.requires_python());
+
+    let (version, file, pinned) = match &args.version {
+        Some(version) => {
+            let version = version.trim();
+            if version.is_empty() || version.chars().any(char::is_whitespace) {
+                return Err(eyre!("invalid Python version `{version}`"));
+            }
+            if let Some(spec) = &requires_python
+                && crate::python_pin::conflicts(version, spec)
+                && !args.force
+            {
+                return Err(eyre!(
+                    "Python {} does not satisfy requires-python {}; pin a version it admits or pass --force",
+                    version,
+                    spec
+                ));
+            }
+            let dir = project
+                .as_ref()
+                .map(|p| p.root().to_path_buf())
+                .unwrap_or_else(|| working_dir.clone());
+            let file = crate::python_pin::write(&dir, version).map_err(|e| {
+                eyre!(
+                    "failed to write {}: {}",
+                    dir.join(".python-version").display(),
+                    e
+                )
+            })?;
+            (version.to_string(), file, true)
+        }
+        None => {
+            let pin = crate::python_pin::find(&working_dir).ok_or_else(|| {
+                eyre!("no .python-version found; pin a version with `pybun python pin <VERSION>`")
+            })?;
+            (pin.version, pin.file, false)
+        }
+    };
+
+    let conflict = requires_python
+        .as_deref()
+        .is_some_and(|spec| crate::python_pin::conflicts(&version, spec));
+    if let Some(spec) = requires_python.as_deref().filter(|_| conflict) {
+        collector.diagnostic(pin_conflict_diagnostic(&version, spec, &file));
+    }
+
+    let manager =
+        RuntimeManager::new(Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?);
+    let installed = manager.find_installed(&version);
+    let runtime = crate::runtime::find_version(&version).map(|info| info.name());
+
+    let mut summary = if pinned {
+        format!("Pinned Python {} in {}", version, file.display())
+    } else {
+        format!("Python {} (from {})", version, file.display())
+    };
+    match (&installed, &runtime) {
+        (Some(name), _) => summary.push_str(&format!("\nUses managed runtime {name}")),
+        (None, Some(name)) => summary.push_str(&format!(
+            "\nPython {name} is installed on first `pybun run`, `pybun test` or `pybun install`"
+        )),
+        (None, None) => {}
+    }
+
+    let json = json!({
+        "version": version,
+        "file": file.display().to_string(),
+        "pinned": pinned,
+        "requires_python": requires_python,
+        "conflict": conflict,
+        "runtime": installed.as_ref().or(runtime.as_ref()),
+        "installed": installed.is_some(),
+    });
+    Ok(("pin".to_string(), RenderDetail::with_json(summary, json)))
+}
+
+/// Honor a `.python-version` pin before `run`, `test` or `install` look up
+/// their interpreter: warn when `requires-python` excludes it, and install
+/// the managed runtime it names when nothing on this machine provides it.
+/// Explicit environments (`PYBUN_ENV`, `PYBUN_PYTHON`, a project venv) take
+/// precedence over the pin, as in [`find_python_env`].
+fn honor_python_pin(working_dir: &Path, offline: bool, collector: &mut EventCollector) {
+    let Some(pin) = crate::python_pin::find(working_dir) else {
+        return;
+    };
+    if let Some(spec) = crate::project::Project::discover(working_dir)
+        .ok()
+        .and_then(|p| p.requires_python())
+        && crate::python_pin::conflicts(&pin.version, &spec)
+    {
+        let diagnostic = pin_conflict_diagnostic(&pin.version, &spec, &pin.file);
+        eprintln!("warning: {}", diagnostic.message);
+        collector.diagnostic(diagnostic);
+    }
+
+    if std::env::var_os("PYBUN_ENV").is_some()
+        || std::env::var_os("PYBUN_PYTHON").is_some()
+        || crate::env::find_project_venv(working_dir).is_some()
+    {
+        return;
+    }
+    let Ok(cache) = Cache::new() else {
+        return;
+    };
+    let manager = RuntimeManager::new(cache).offline(offline || crate::offline::is_enabled());
+    if manager.find_installed(&pin.version).is_some()
+        || crate::env::pin_satisfied_locally(&pin.version)
+    {
+        return;
+    }
+    // Versions pybun cannot install are left to pyenv-style lookup.
+    let Some(info) = crate::runtime::find_version(&pin.version) else {
+        return;
+    };
+    let name = info.name();
+    if manager.is_offline() {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "{} pins Python {}, which is not installed and cannot be downloaded offline",
+                pin.file.display(),
+                pin.version
+            ))
+            .with_code("W_PYTHON_PIN_NOT_INSTALLED")
+            .with_suggestion(format!("pybun python install {name}")),
+        );
+        return;
+    }
+    collector.info(format!(
+        "Installing Python {} pinned by {}",
+        name,
+        pin.file.display()
+    ));
+    if let Err(e) = manager.ensure_version(&name) {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "failed to install Python {name} pinned by {}: {e}",
+                pin.file.display()
+            ))
+            .with_code("W_PYTHON_PIN_NOT_INSTALLED")
+            .with_suggestion(format!("pybun python install {name}")),
+        );
+    }
+}
+
 // ---------------------------------------------------------------------------
 // pybun init
 // ---------------------------------------------------------------------------
@@ -4624,6 +10198,7 @@ fn init_project(args: &InitArgs, collector: &mut EventCollector) -> Result<Rende
             collector.diagnostic(Diagnostic {
                 level: DiagnosticLevel::Error,
                 code: Some("E_INIT_NOT_INTERACTIVE".to_string()),
+                error_code: None,
                 message: "Interactive prompt requires a terminal".to_string(),
                 file: None,
                 line: None,
@@ -4874,7 +10449,7 @@ async fn run_outdated(args: &OutdatedArgs, collector: &mut EventCollector) -> Re
     let packages_to_check: Vec<(String, Package)> = lockfile.packages.into_iter().collect();
 
     // Setup client
-    let client = PyPiClient::from_env(args.offline)
+    let remote = RemoteIndex::from_env(args.offline)
         .map_err(|e| eyre!("failed to create PyPI client: {}", e))?;
 
     // Setup local index if needed
@@ -4892,14 +10467,13 @@ async fn run_outdated(args: &OutdatedArgs, collector: &mut EventCollector) -> Re
     // Use stream buffering for parallel requests
     let results = stream::iter(packages_to_check)
         .map(|(name, pkg)| {
-            let client = client.clone();
+            let remote = remote.clone();
             let local_index = local_index.clone();
             async move {
                 let all_versions_res = if let Some(index) = local_index {
                     index.all(&name).await
                 } else {
-                    let pypi = PyPiIndex::new(client);
-                    pypi.all(&name).await
+                    remote.all(&name).await
                 };
                 (name, pkg, all_versions_res)
             }
@@ -4908,7 +10482,7 @@ async fn run_outdated(args: &OutdatedArgs, collector: &mut EventCollector) -> Re
         .collect::<Vec<_>>()
         .await;
 
-    for notice in client.take_stale_cache_notices() {
+    for notice in remote.take_stale_cache_notices() {
         collector.warning(notice);
     }
 
@@ -5003,7 +10577,11 @@ async fn run_outdated(args: &OutdatedArgs, collector: &mut EventCollector) -> Re
             "errors": check_errors,
             "workspace": scope_detail,
         }),
-    ))
+    )
+    .with_table(TableSpec::new(
+        "outdated",
+        &["package", "current", "wanted", "latest", "type"],
+    )))
 }
 
 fn classify_update(current: &str, latest: &str) -> &'static str {
@@ -5053,7 +10631,8 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
     }
 
     // Load project to get constraints, optionally scoped by --member/--group
-    let project = Project::discover(&cwd).map_err(|e| eyre!("failed to load project: {}", e))?;
+    let mut project =
+        Project::discover(&cwd).map_err(|e| eyre!("failed to load project: {}", e))?;
     let (dependencies, scope_detail) = select_scoped_dependencies(
         &project,
         &cwd,
@@ -5066,6 +10645,8 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
             "No dependencies to upgrade",
             json!({
                 "upgraded": [],
+                "changes": [],
+                "diff": "",
                 "dry_run": args.dry_run,
                 "verified": true,
                 "artifacts": [],
@@ -5080,46 +10661,36 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
         emit_lockfile_verification_drift(lockfile, collector);
     }
 
-    // Prepare requirements
-    let mut requirements: Vec<Requirement> = Vec::new();
-
-    // Strategy:
-    // 1. If args.packages is empty (upgrade all): Use project dependencies.
-    // 2. If args.packages is distinct:
-    //    - For packages in args.packages: Use project constraints (or Any).
-    //    - For others found in lockfile: Pin to lockfile version (Exact).
-    //    - For others NOT in lockfile (new deps?): Use project constraints.
-
-    for dep_str in &dependencies {
-        if let Ok(req) = dep_str.parse::<Requirement>() {
-            let is_target = if args.packages.is_empty() {
-                true // Upgrade everything
-            } else {
-                // Check if this requirement matches any targeted package
-                args.packages
-                    .iter()
-                    .any(|p| p.eq_ignore_ascii_case(&req.name))
-            };
-
-            if is_target {
-                requirements.push(req);
-            } else {
-                // Not targeted. Check if we should pin it.
-                if let Some(lock) = &current_lock {
-                    if let Some(pkg) = lock.packages.get(&req.name) {
-                        // Pin to currently locked version
-                        requirements.push(Requirement::exact(req.name.clone(), &pkg.version));
-                    } else {
-                        // Not locked yet, strict requirement
-                        requirements.push(req);
-                    }
-                } else {
-                    requirements.push(req);
-                }
+    // A whole-project upgrade keeps the groups a previous `install --group` locked,
+    // so their packages are re-resolved instead of dropped from the lockfile.
+    let mut groups = BTreeMap::from([(
+        args.group.clone().unwrap_or_else(|| MAIN_GROUP.to_string()),
+        dependencies.clone(),
+    )]);
+    let mut dependencies = dependencies;
+    if args.member.is_none()
+        && args.group.is_none()
+        && let Some(lockfile) = &current_lock
+    {
+        let locked: BTreeSet<&str> = lockfile
+            .packages
+            .values()
+            .flat_map(|pkg| pkg.groups.iter().map(String::as_str))
+            .filter(|group| *group != MAIN_GROUP)
+            .collect();
+        for group in locked {
+            let deps = project.group_dependencies(group);
+            if deps.is_empty() {
+                continue;
             }
+            dependencies.extend(deps.iter().cloned());
+            groups.insert(group.to_string(), deps);
         }
     }
 
+    let plan = plan_upgrade(args, &dependencies, current_lock.as_ref(), collector)?;
+    let requirements = plan.requirements.clone();
+
     collector.event(EventType::ResolveStart);
 
     // Re-resolve dependencies
@@ -5128,20 +10699,21 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
         python_version: resolve_target_python_version(),
     };
     let source_index_url: String;
+    let mut dynamic_metadata = BTreeSet::new();
     let resolution = if let Some(index_path) = &args.index {
         source_index_url = index_path.display().to_string();
         let index = load_index_from_path(index_path)?;
         resolve_with_options(requirements.clone(), &index, resolve_options).await?
     } else {
-        let pypi_client = PyPiClient::from_env(args.offline)
+        let pypi_index = RemoteIndex::from_env(args.offline)
             .map_err(|e| eyre!("failed to create PyPI client: {}", e))?;
-        source_index_url = pypi_client.index_url();
-        let pypi_index = PyPiIndex::new(pypi_client);
+        source_index_url = pypi_index.index_url();
         let resolve_result =
             resolve_with_options(requirements.clone(), &pypi_index, resolve_options).await;
         for notice in pypi_index.take_stale_cache_notices() {
             collector.warning(notice);
         }
+        dynamic_metadata = pypi_index.dynamic_metadata_packages();
         resolve_result?
     };
     warn_on_prerelease_fallback(&resolution, collector);
@@ -5150,7 +10722,6 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
 
     let mut upgraded_packages: Vec<Value> = Vec::new();
     let mut verification_artifacts: Vec<Value> = Vec::new();
-    let platform_tags = current_platform_tags();
 
     // Detect the CPython tag of the actual project's Python (PYBUN_ENV / PYBUN_PYTHON /
     // project venv / system Python) *before* re-selecting wheels, so the wheel filenames
@@ -5172,13 +10743,15 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
         .or_else(|| {
             get_python_version(&target_env_probe.python_path)
                 .ok()
-                .and_then(|v| python_version_to_cp_tag(&v))
+                .and_then(|v| get_interpreter_tag(&target_env_probe.python_path, &v))
         })
         .unwrap_or_else(|| "cp311".to_string());
+    let targets = wheel_targets(&cwd, &target_env_probe.python_path, collector)?;
 
     // Use an empty lockfile if none exists for comparison base
-    let base_lock =
-        current_lock.unwrap_or_else(|| Lockfile::new(vec!["3.12".into()], vec!["any".into()]));
+    let base_lock = current_lock
+        .clone()
+        .unwrap_or_else(|| Lockfile::new(vec!["3.12".into()], vec!["any".into()]));
 
     // Build new lockfile
     let mut new_lock = Lockfile::new(
@@ -5187,10 +10760,18 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
     );
 
     for (pkg_name, pkg) in &resolution.packages {
-        let selection = select_artifact_for_platform_with_cp(pkg, &platform_tags, &active_cp_tag);
+        let mut selection =
+            select_artifact_for_platform_with_cp(pkg, targets.tags_for(&pkg.name), &active_cp_tag);
+        warn_on_arch_mismatch(pkg, &selection, &targets, collector);
         let wheel_name = selection.filename.clone();
-        let (hash, artifact) =
-            ensure_selection_is_verifiable(pkg, &selection, collector, &source_index_url)?;
+        let (hash, artifact) = verify_selection(
+            pkg,
+            &mut selection,
+            !args.offline,
+            &source_index_url,
+            collector,
+        )
+        .await?;
 
         let new_pkg = Package {
             name: pkg.name.clone(),
@@ -5200,8 +10781,12 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
                 .clone()
                 .unwrap_or_else(|| registry_source_for_index(&source_index_url)),
             wheel: wheel_name,
+            artifacts: vec![locked_artifact(pkg, &selection, hash.clone())],
             hash,
             dependencies: pkg.dependencies.iter().map(|r| r.to_string()).collect(),
+            dynamic_metadata: has_dynamic_metadata(&dynamic_metadata, &pkg.name),
+            groups: Vec::new(),
+            build: None,
         };
 
         // Track upgrades
@@ -5241,7 +10826,47 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
         }
     }
 
+    // With --latest, raise the pyproject.toml constraints the new versions
+    // no longer satisfy.
+    let mut raised: Vec<Value> = Vec::new();
+    let mut pyproject = FileDiff::default();
+    if !plan.relaxed.is_empty() {
+        let before = fs::read_to_string(project.path())?;
+        for (dep, req) in &plan.relaxed {
+            let Some(locked) = new_lock.packages.get(&req.name) else {
+                continue;
+            };
+            if req.specs.iter().all(|spec| spec.matches(&locked.version)) {
+                continue;
+            }
+            let raised_dep = raised_requirement(req, &locked.version);
+            if project.replace_dependency(dep, &raised_dep) {
+                raised.push(json!({
+                    "package": req.name,
+                    "from": dep,
+                    "to": raised_dep,
+                }));
+            }
+        }
+        if !raised.is_empty() {
+            let after = project.to_toml_string()?;
+            if !args.dry_run {
+                fs::write(project.path(), &after)?;
+            }
+            pyproject = change_diff::pyproject_changes(
+                &change_label(project.path(), &cwd),
+                &before,
+                &after,
+            );
+        }
+    }
+
     // Write lockfile unless dry-run
+    new_lock.assign_groups(&groups);
+    if let Some(current) = &current_lock {
+        crate::suspicious::restore(current);
+    }
+    crate::suspicious::record(&mut new_lock);
     if !args.dry_run {
         new_lock
             .save_to_path(&lock_path)
@@ -5285,19 +10910,198 @@ async fn run_upgrade(args: &UpgradeArgs, collector: &mut EventCollector) -> Resu
         }
     }
 
+    let lockfile = change_diff::lockfile_changes(
+        &change_label(&lock_path, &cwd),
+        current_lock.as_ref(),
+        &new_lock,
+    );
+    let (diff, changes) = render_file_changes(&[&pyproject, &lockfile]);
+
     Ok(RenderDetail::with_json(
-        summary.trim().to_string(),
+        with_diff(summary.trim().to_string(), &diff),
         json!({
             "upgraded": upgraded_packages,
+            "changes": changes,
+            "diff": diff,
             "dry_run": args.dry_run,
             "lockfile": lock_path.display().to_string(),
             "verified": true,
             "artifacts": verification_artifacts,
             "workspace": scope_detail,
+            "plan": {
+                "packages": plan.targets.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "pinned": plan.pinned,
+                "latest": args.latest,
+                "raised": raised,
+            },
         }),
     ))
 }
 
+/// What `pybun upgrade` re-resolves: the requirements, the dependencies
+/// `--latest` relaxed, and the packages held back by `--pin`.
+struct UpgradePlan {
+    requirements: Vec<Requirement>,
+    targets: Vec<Requirement>,
+    /// Original `[project.dependencies]` entries resolved without their
+    /// version constraints.
+    relaxed: Vec<(String, Requirement)>,
+    pinned: Vec<Value>,
+}
+
+/// Turn the project's dependencies into upgrade requirements. Targeted
+/// packages (all of them when none are named) keep their pyproject
+/// constraints, narrowed by the specifier they were named with and dropped
+/// under `--latest`; other locked dependencies and `--pin` packages stay at
+/// their locked (or pinned) version.
+fn plan_upgrade(
+    args: &UpgradeArgs,
+    dependencies: &[String],
+    lock: Option<&Lockfile>,
+    collector: &mut EventCollector,
+) -> Result<UpgradePlan> {
+    let same = |a: &str, b: &str| {
+        crate::pypi::normalize_project_name(a) == crate::pypi::normalize_project_name(b)
+    };
+    let locked_version = |name: &str| {
+        lock.and_then(|lock| {
+            lock.packages
+                .values()
+                .find(|pkg| same(&pkg.name, name))
+                .map(|pkg| pkg.version.clone())
+        })
+    };
+    let direct: Vec<Requirement> = dependencies
+        .iter()
+        .filter_map(|dep| dep.parse::<Requirement>().ok())
+        .collect();
+    let mut fail = |code: &str, message: String, hint: &str| {
+        collector.error_with_code(code, message.clone(), hint);
+        Err(eyre!(message))
+    };
+
+    let mut targets = Vec::new();
+    for spec in &args.packages {
+        let Ok(target) = spec.parse::<Requirement>() else {
+            return fail(
+                "E_UPGRADE_INVALID_PACKAGE",
+                format!("invalid package '{spec}'"),
+                "Name the package, optionally with a specifier such as `requests<3`.",
+            );
+        };
+        if !direct.iter().any(|d| same(&d.name, &target.name))
+            && locked_version(&target.name).is_none()
+        {
+            return fail(
+                "E_UPGRADE_UNKNOWN_PACKAGE",
+                format!("{} is not a dependency of this project", target.name),
+                "Add it with `pybun add` first, or check the name with `pybun outdated`.",
+            );
+        }
+        targets.push(target);
+    }
+
+    let mut pins: Vec<Requirement> = Vec::new();
+    let mut pinned = Vec::new();
+    for spec in &args.pin {
+        let parsed = spec.parse::<Requirement>().ok();
+        let version = match parsed.as_ref().map(|r| r.specs.as_slice()) {
+            Some([VersionSpec::Exact(version)]) => Some(version.clone()),
+            Some([VersionSpec::Any]) => parsed.as_ref().and_then(|r| locked_version(&r.name)),
+            _ => {
+                return fail(
+                    "E_UPGRADE_INVALID_PIN",
+                    format!("invalid pin '{spec}'; expected NAME or NAME==VERSION"),
+                    "Pin a locked package by name (`--pin requests`) or to an exact version (`--pin requests==2.31.0`).",
+                );
+            }
+        };
+        let name = parsed.map(|r| r.name).unwrap_or_default();
+        let Some(version) = version else {
+            return fail(
+                "E_UPGRADE_PIN_UNLOCKED",
+                format!("cannot pin {name}: it is not in the lockfile"),
+                "Pin it to an explicit version instead, e.g. `--pin NAME==VERSION`.",
+            );
+        };
+        if targets.iter().any(|t| same(&t.name, &name)) {
+            return fail(
+                "E_UPGRADE_PIN_CONFLICT",
+                format!("{name} is both upgraded and pinned"),
+                "Drop it from the packages to upgrade or from `--pin`.",
+            );
+        }
+        pinned.push(json!({ "package": name, "version": version }));
+        pins.push(Requirement::exact(name, version));
+    }
+
+    let mut requirements = Vec::new();
+    let mut relaxed = Vec::new();
+    for (dep, mut req) in dependencies
+        .iter()
+        .filter_map(|dep| dep.parse::<Requirement>().ok().map(|req| (dep, req)))
+    {
+        if let Some(pin) = pins.iter().find(|p| same(&p.name, &req.name)) {
+            req.specs = pin.specs.clone();
+            requirements.push(req);
+            continue;
+        }
+        let target = targets.iter().find(|t| same(&t.name, &req.name));
+        if !args.packages.is_empty() && target.is_none() {
+            // Not targeted: hold it at the locked version.
+            if let Some(version) = locked_version(&req.name) {
+                req = Requirement::exact(req.name.clone(), version);
+            }
+            requirements.push(req);
+            continue;
+        }
+        if args.latest {
+            relaxed.push((dep.clone(), req.clone()));
+            req.specs = vec![VersionSpec::Any];
+        }
+        if let Some(target) = target {
+            let narrowed = target.specs.iter().filter(|s| **s != VersionSpec::Any);
+            req.specs.retain(|s| *s != VersionSpec::Any);
+            req.specs.extend(narrowed.cloned());
+            if req.specs.is_empty() {
+                req.specs.push(VersionSpec::Any);
+            }
+        }
+        requirements.push(req);
+    }
+    // Pinned or constrained transitive packages join the resolution as roots.
+    for extra in pins.iter().chain(
+        targets
+            .iter()
+            .filter(|t| t.specs.iter().any(|s| *s != VersionSpec::Any)),
+    ) {
+        if !direct.iter().any(|d| same(&d.name, &extra.name)) {
+            requirements.push(extra.clone());
+        }
+    }
+
+    Ok(UpgradePlan {
+        requirements,
+        targets,
+        relaxed,
+        pinned,
+    })
+}
+
+/// `req` with its version constraints replaced by `>=version`, keeping its
+/// extras and marker.
+fn raised_requirement(req: &Requirement, version: &str) -> String {
+    let mut raised = req.name.clone();
+    if !req.extras.is_empty() {
+        raised.push_str(&format!("[{}]", req.extras.join(",")));
+    }
+    raised.push_str(&format!(">={version}"));
+    if let Some(marker) = &req.marker {
+        raised.push_str(&format!("; {marker}"));
+    }
+    raised
+}
+
 fn run_drift(args: &DriftArgs, collector: &mut EventCollector) -> Result<RenderDetail> {
     use crate::drift;
 
@@ -5343,6 +11147,7 @@ fn run_drift(args: &DriftArgs, collector: &mut EventCollector) -> Result<RenderD
         collector.diagnostic(Diagnostic {
             level: DiagnosticLevel::Warning,
             code: Some("W_DRIFT_UNDECLARED_IMPORT".to_string()),
+            error_code: None,
             message: format!(
                 "Package '{}' is imported but not declared in pyproject.toml",
                 u.package
@@ -5363,6 +11168,7 @@ fn run_drift(args: &DriftArgs, collector: &mut EventCollector) -> Result<RenderD
         collector.diagnostic(Diagnostic {
             level: DiagnosticLevel::Warning,
             code: Some("W_DRIFT_UNUSED_DECLARATION".to_string()),
+            error_code: None,
             message: format!(
                 "Package '{}' is declared in pyproject.toml but never imported",
                 u.package
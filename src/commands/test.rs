@@ -1,11 +1,24 @@
 use super::{RenderDetail, find_python_interpreter};
+use crate::cache::Cache;
 use crate::cli::TestBackend;
-use crate::env::find_python_env;
-use crate::schema::{Diagnostic, EventCollector};
+use crate::env::{EnvSource, find_python_env};
+use crate::network_policy::{NetworkGuard, Operation};
+use crate::payload::{Capture, CapturedOutput};
+use crate::schema::{Diagnostic, EventCollector, EventType};
+use crate::test_coverage::{self, CoverageData};
 use crate::test_discovery::{DiscoveryResult, TestDiscovery, TestItem, TestItemType};
+use crate::test_history::{self, RunRecord, TestHistory, TestRecord};
+use crate::test_impact::{self, ResultStore};
+use crate::test_params::{ParamsPlugin, TestParams};
+use crate::test_plugins::{PluginBatch, PluginRegistry};
+use crate::test_report::{self, TestCaseReport};
+use crate::test_scheduler;
+use crate::test_selection::{KeywordExpr, ResolvedTarget, TestTarget};
+use crate::tool_exec::{ToolError, ToolRun};
 use crate::workspace::Workspace;
 use color_eyre::eyre::{Result, eyre};
 use serde_json::{Value, json};
+use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command as ProcessCommand;
 
@@ -13,6 +26,9 @@ use std::process::Command as ProcessCommand;
 // pybun test (test runner)
 // ---------------------------------------------------------------------------
 
+/// Slowest tests listed by `pybun test --history`.
+const HISTORY_SLOWEST: usize = 10;
+
 /// Get a hint message for a pytest compatibility warning code
 fn get_pytest_compat_hint(code: &str) -> Option<&'static str> {
     match code {
@@ -49,6 +65,7 @@ fn native_backend_compat_diagnostic(warning: &crate::test_discovery::CompatWarni
     Diagnostic {
         level,
         code: Some(format!("W_TEST_BACKEND_COMPAT_{}", warning.code)),
+        error_code: None,
         message: format!(
             "{} (the native --backend=pybun executor may not fully emulate this pytest feature)",
             warning.message
@@ -199,8 +216,7 @@ fn walkdir_recursive(path: &std::path::Path, result: &mut Vec<PathBuf>) -> Resul
 }
 
 /// Use AST-based discovery to find all tests
-fn discover_tests_ast(paths: &[PathBuf]) -> DiscoveryResult {
-    let discovery = TestDiscovery::new();
+fn discover_tests_ast(discovery: &TestDiscovery, paths: &[PathBuf]) -> DiscoveryResult {
     let search_paths = if paths.is_empty() {
         vec![std::env::current_dir().unwrap_or_default()]
     } else {
@@ -209,19 +225,56 @@ fn discover_tests_ast(paths: &[PathBuf]) -> DiscoveryResult {
     discovery.discover(&search_paths)
 }
 
-/// Filter tests by name pattern
-fn filter_tests(tests: Vec<TestItem>, pattern: &str) -> Vec<TestItem> {
-    tests
-        .into_iter()
-        .filter(|t| {
-            t.name.contains(pattern)
-                || t.short_name.contains(pattern)
-                || t.class_name
-                    .as_ref()
-                    .map(|c| c.contains(pattern))
-                    .unwrap_or(false)
+/// Split positional arguments into selection targets plus the de-duplicated
+/// file/directory list that discovery should scan.
+fn parse_test_targets(paths: &[PathBuf]) -> (Vec<TestTarget>, Vec<PathBuf>) {
+    let targets: Vec<TestTarget> = paths.iter().map(|p| TestTarget::parse(p)).collect();
+    let mut search_paths: Vec<PathBuf> = Vec::new();
+    for target in &targets {
+        let path = target.path().to_path_buf();
+        if !search_paths.contains(&path) {
+            search_paths.push(path);
+        }
+    }
+    (targets, search_paths)
+}
+
+/// Arguments identifying the selected tests for the pytest backend. Plain
+/// paths are forwarded unchanged; node ids and `file:line` targets are passed
+/// as the node ids they resolved to, so pytest runs exactly those tests.
+/// Files owned by a discovery plugin are left to that plugin.
+fn pytest_target_args(
+    targets: &[TestTarget],
+    resolved: &[ResolvedTarget],
+    plugins: &PluginRegistry,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    for (target, resolved) in targets.iter().zip(resolved) {
+        if target.path().is_file() && plugins.plugin_for(target.path()).is_some() {
+            continue;
+        }
+        if target.is_precise() {
+            out.extend(resolved.selected.iter().cloned());
+        } else {
+            out.push(target.path().display().to_string());
+        }
+    }
+    out
+}
+
+/// Dotted `package.module.Class.test` name understood by `python -m unittest`.
+fn unittest_test_id(item: &TestItem) -> String {
+    let module = item
+        .path
+        .with_extension("")
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(part) => part.to_str().map(|s| s.to_string()),
+            _ => None,
         })
-        .collect()
+        .collect::<Vec<_>>()
+        .join(".");
+    format!("{}.{}", module, item.name.replace("::", "."))
 }
 
 /// Apply sharding to tests
@@ -296,13 +349,44 @@ pub(crate) fn run_tests(
     args: &crate::cli::TestArgs,
     collector: &mut EventCollector,
 ) -> Result<RenderDetail> {
+    if args.history {
+        return history_report(args, collector);
+    }
+
     // Check for dry-run mode (for testing)
     let dry_run = std::env::var("PYBUN_TEST_DRY_RUN").is_ok();
+    if !dry_run && let Ok(cwd) = std::env::current_dir() {
+        super::honor_python_pin(&cwd, false, collector);
+    }
 
     // Resolve effective search paths, honoring `--member` to scope discovery
     // to a single workspace member directory.
     let (paths, member_detail) = resolve_test_paths(args, collector)?;
 
+    // Positional arguments may be node ids (`file.py::Class::test`) or
+    // `file.py:LINE` references; discovery scans the underlying files.
+    let (targets, paths) = parse_test_targets(&paths);
+    let precise_selection = targets.iter().any(TestTarget::is_precise);
+
+    // Validate the -k expression up front so a typo fails fast.
+    let keyword = args
+        .filter
+        .as_deref()
+        .map(KeywordExpr::parse)
+        .transpose()
+        .map_err(|e| eyre!(e))?;
+
+    // `--env`/`--fixture` values layered over `[tool.pybun.test]`.
+    let test_config = std::env::current_dir()
+        .ok()
+        .and_then(|dir| crate::project::Project::discover(dir).ok())
+        .map(|project| project.pybun_config().test)
+        .unwrap_or_default();
+    let params = TestParams::resolve(&test_config, &args.env, &args.fixtures);
+    let discovery = TestDiscovery::new()
+        .with_plugins(PluginRegistry::from_config(&test_config.plugins).map_err(|e| eyre!(e))?);
+    let plugins = discovery.plugins();
+
     // Parse shard if provided
     let shard_info = if let Some(ref shard_str) = args.shard {
         Some(parse_shard(shard_str)?)
@@ -314,7 +398,7 @@ pub(crate) fn run_tests(
     let backend = args.backend.unwrap_or_else(|| detect_test_backend(&paths));
 
     // Use AST-based discovery
-    let discovery_result = discover_tests_ast(&paths);
+    let discovery_result = discover_tests_ast(&discovery, &paths);
 
     collector.info(format!(
         "AST discovery: found {} tests in {} files ({}µs)",
@@ -327,7 +411,7 @@ pub(crate) fn run_tests(
     // (independent of --pytest-compat): choosing --backend=pybun is itself
     // the signal that compatibility matters, since the native executor may
     // not fully emulate every pytest plugin/fixture pattern (Issue #168).
-    if backend == TestBackend::Pybun {
+    if backend.is_native() {
         for warning in &discovery_result.compat_warnings {
             collector.diagnostic(native_backend_compat_diagnostic(warning));
         }
@@ -346,6 +430,7 @@ pub(crate) fn run_tests(
             let diag = Diagnostic {
                 level,
                 code: Some(warning.code.clone()),
+                error_code: None,
                 message: warning.message.clone(),
                 file: Some(warning.path.display().to_string()),
                 line: Some(warning.line as u32),
@@ -387,17 +472,30 @@ pub(crate) fn run_tests(
         }
     }
 
-    // Get only function/method tests (not class items for running)
-    let mut tests: Vec<TestItem> = discovery_result
-        .tests
-        .iter()
-        .filter(|t| t.item_type != TestItemType::Class)
-        .cloned()
-        .collect();
+    // Get only function/method tests (not class items for running),
+    // narrowed to node id / file:line targets when any were given.
+    let (mut tests, resolved_targets): (Vec<TestItem>, Vec<ResolvedTarget>) = if precise_selection {
+        let (selected, resolved) =
+            crate::test_selection::select_targets(&discovery_result.tests, &targets)
+                .map_err(|e| eyre!(e))?;
+        collector.info(format!("After target selection: {} tests", selected.len()));
+        (selected, resolved)
+    } else {
+        (
+            discovery_result
+                .tests
+                .iter()
+                .filter(|t| t.item_type != TestItemType::Class)
+                .cloned()
+                .collect(),
+            Vec::new(),
+        )
+    };
+    let targets_json = serde_json::to_value(&resolved_targets).unwrap_or(Value::Null);
 
-    // Apply filter if specified
-    if let Some(ref pattern) = args.filter {
-        tests = filter_tests(tests, pattern);
+    // Apply -k expression if specified
+    if let (Some(pattern), Some(expr)) = (&args.filter, &keyword) {
+        tests = crate::test_selection::filter_by_keyword(tests, expr);
         collector.info(format!("After filter '{}': {} tests", pattern, tests.len()));
     }
 
@@ -412,6 +510,26 @@ pub(crate) fn run_tests(
         ));
     }
 
+    // `--changed` (or `[tool.pybun.test] changed`): only tests affected by
+    // files changed since the last run, plus tests that did not pass.
+    let changed_detail = (args.changed || (test_config.changed && !args.all)).then(|| {
+        let cache = result_store().map(|store| store.load()).unwrap_or_default();
+        let selection = test_impact::select(&cache, &history_root(), std::mem::take(&mut tests));
+        tests = selection.tests;
+        collector.info(format!(
+            "After --changed: {} tests ({} unchanged)",
+            tests.len(),
+            selection.unchanged
+        ));
+        json!({
+            "selected": tests.len(),
+            "unchanged": selection.unchanged,
+            "new": selection.new,
+            "failed_last_run": selection.failed,
+            "changed_files": selection.changed_files,
+        })
+    });
+
     // Filter out skipped tests for counting
     let runnable_tests: Vec<&TestItem> = tests.iter().filter(|t| !t.skipped).collect();
 
@@ -446,6 +564,7 @@ pub(crate) fn run_tests(
                         "params": p.params,
                         "case_count": p.case_count,
                     })),
+                    "plugin": t.plugin,
                 })
             })
             .collect();
@@ -533,6 +652,9 @@ pub(crate) fn run_tests(
             json!({
                 "discover": true,
                 "workspace": member_detail,
+                "targets": targets_json,
+                "changed": changed_detail,
+                "plugins": plugins.names(),
                 "tests": tests_json,
                 "fixtures": fixtures_json,
                 "compat_warnings": warnings_json,
@@ -579,7 +701,7 @@ pub(crate) fn run_tests(
             Vec::new()
         };
 
-        let workers = if backend == TestBackend::Pybun {
+        let workers = if backend.is_native() {
             Some(args.parallel.unwrap_or_else(|| {
                 std::thread::available_parallelism()
                     .map(|p| p.get())
@@ -594,6 +716,9 @@ pub(crate) fn run_tests(
             json!({
                 "dry_run": true,
                 "workspace": member_detail,
+                "targets": targets_json,
+                "selected_tests": tests.iter().map(crate::test_selection::node_id).collect::<Vec<_>>(),
+                "changed": changed_detail,
                 "backend": format!("{:?}", backend).to_lowercase(),
                 "test_runner": format!("{:?}", backend).to_lowercase(),
                 "discovered_files": discovered_files.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
@@ -609,6 +734,13 @@ pub(crate) fn run_tests(
                 "retries": args.retries.unwrap_or(0),
                 "snapshot": args.snapshot,
                 "update_snapshots": args.update_snapshots,
+                "coverage": args.coverage,
+                "params": params.to_json(),
+                "plugins": plugins.batches(&tests).iter().map(|batch| json!({
+                    "name": batch.plugin.name(),
+                    "tests": batch.items.iter().map(crate::test_selection::node_id).collect::<Vec<_>>(),
+                    "command": batch.command(),
+                })).collect::<Vec<_>>(),
                 "ast_discovery": {
                     "tests": tests.len(),
                     "fixtures": discovery_result.fixtures.len(),
@@ -620,47 +752,121 @@ pub(crate) fn run_tests(
         ));
     }
 
+    if let Some(changed) = &changed_detail
+        && tests.is_empty()
+    {
+        return Ok(RenderDetail::with_json(
+            "No tests affected by changes since the last run",
+            json!({
+                "workspace": member_detail,
+                "targets": targets_json,
+                "tests_found": 0,
+                "changed": changed,
+            }),
+        ));
+    }
+
     // Find Python interpreter
     let (python, env_source) = find_python_interpreter()?;
     eprintln!("info: using Python from {}", env_source);
 
+    // Plugin-owned tests run through their plugin's execution adapter after
+    // the backend; the backend is skipped when they are all that was selected.
+    let batches = plugins.batches(&tests);
+    tests.retain(|t| t.plugin.is_none());
+    if tests.is_empty() && !batches.is_empty() {
+        let plugin_runs = run_plugin_batches(&batches, &python, &params, args, collector)?;
+        let detail = RenderDetail::with_json(
+            format!(
+                "Ran {} tests with {} plugin{}",
+                batches.iter().map(|b| b.items.len()).sum::<usize>(),
+                batches.len(),
+                if batches.len() == 1 { "" } else { "s" }
+            ),
+            json!({
+                "workspace": member_detail,
+                "targets": targets_json,
+                "test_runner": "plugins",
+                "fail_fast": args.fail_fast,
+                "shard": shard_info.map(|(n, m)| format!("{}/{}", n, m)),
+                "filter": args.filter,
+                "tests_found": batches.iter().map(|b| b.items.len()).sum::<usize>(),
+                "params": params.to_json(),
+            }),
+        );
+        return Ok(with_plugin_runs(detail, plugin_runs));
+    }
+
+    // `--coverage`: every test process records coverage data, combined and
+    // summarized after the run.
+    let coverage = if args.coverage {
+        match prepare_coverage(&python, &env_source, collector) {
+            Ok(data) => Some(data),
+            Err(message) => {
+                return Ok(RenderDetail::error(
+                    message.clone(),
+                    json!({ "error": message, "coverage": null }),
+                ));
+            }
+        }
+    } else {
+        None
+    };
+
     // Native pybun backend: use Rust TestExecutor
-    if backend == TestBackend::Pybun {
-        return run_tests_native(args, tests, shard_info, &python, member_detail, collector);
+    if backend.is_native() {
+        let mut detail = run_tests_native(
+            args,
+            backend,
+            tests,
+            shard_info,
+            &python,
+            &params,
+            coverage.as_ref(),
+            member_detail,
+            targets_json,
+            collector,
+        )?;
+        detail.json["changed"] = json!(changed_detail);
+        let detail = finish_coverage(detail, coverage, &python, args, shard_info, collector);
+        let plugin_runs = if args.fail_fast && detail.is_error {
+            PluginRuns::default()
+        } else {
+            run_plugin_batches(&batches, &python, &params, args, collector)?
+        };
+        return Ok(with_plugin_runs(detail, plugin_runs));
     }
 
+    let params_plugin = ParamsPlugin::new(&params)
+        .map_err(|e| eyre!("failed to prepare test parameters: {}", e))?;
+
     // Build the command based on backend
     let mut cmd = ProcessCommand::new(&python);
-
+    // pytest's JUnit report is read back for `detail.tests`, `--report`
+    // and the test history.
+    let junit_report = (backend == TestBackend::Pytest)
+        .then(|| tempfile::NamedTempFile::new().ok())
+        .flatten();
+
+    if let Some(data) = &coverage {
+        cmd.args(test_coverage::run_args(&data.data_file()));
+    }
     match backend {
         TestBackend::Pytest => {
             cmd.arg("-m").arg("pytest");
+            cmd.args(pytest_options(args, params_plugin.as_ref()));
 
-            // Add fail-fast flag
-            if args.fail_fast {
-                cmd.arg("-x");
+            // Per-test results for the run history
+            if let Some(report) = &junit_report {
+                cmd.arg(format!("--junitxml={}", report.path().display()))
+                    .args(["-o", "junit_family=xunit1"]);
             }
 
-            // Add verbose for better output
-            if args.verbose {
-                cmd.arg("-v");
-            }
-
-            // Add filter (-k option)
-            if let Some(ref pattern) = args.filter {
-                cmd.arg("-k").arg(pattern);
-            }
-
-            // Add parallel option
-            if let Some(workers) = args.parallel {
-                cmd.arg("-n").arg(workers.to_string());
-            }
-
-            // Add test paths
-            if !paths.is_empty() {
-                for path in &paths {
-                    cmd.arg(path);
-                }
+            // Add test paths (node ids for precise targets or `--changed`)
+            if changed_detail.is_some() {
+                cmd.args(tests.iter().map(crate::test_selection::node_id));
+            } else {
+                cmd.args(pytest_target_args(&targets, &resolved_targets, plugins));
             }
 
             // Add passthrough args
@@ -680,8 +886,13 @@ pub(crate) fn run_tests(
                 cmd.arg("-v");
             }
 
-            // For unittest, we need to specify discover or specific files
-            if paths.is_empty() {
+            // For unittest, we need to specify discover or specific files.
+            // Precise targets are passed as dotted test ids.
+            if precise_selection || changed_detail.is_some() {
+                for test in &tests {
+                    cmd.arg(unittest_test_id(test));
+                }
+            } else if paths.is_empty() {
                 cmd.arg("discover");
             } else {
                 // If the first path is a directory, assume the user wants discovery in that dir
@@ -689,7 +900,7 @@ pub(crate) fn run_tests(
                 if paths.len() == 1 && paths[0].is_dir() {
                     cmd.arg("discover").arg("-s").arg(&paths[0]);
                 } else {
-                    for path in &paths {
+                    for path in paths.iter().filter(|p| plugins.plugin_for(p).is_none()) {
                         cmd.arg(path);
                     }
                 }
@@ -700,32 +911,112 @@ pub(crate) fn run_tests(
                 cmd.arg(arg);
             }
         }
-        // The pybun path returns early via run_tests_native() before reaching
-        // this match.  The debug_assert guards against future refactors that
-        // accidentally remove that early return.
-        TestBackend::Pybun => {
-            debug_assert!(
-                false,
-                "TestBackend::Pybun must be handled before this match"
-            );
+        // The native paths return early via run_tests_native() before
+        // reaching this match.  The debug_assert guards against future
+        // refactors that accidentally remove that early return.
+        TestBackend::Pybun | TestBackend::Native => {
+            debug_assert!(false, "native backends must be handled before this match");
             unreachable!("pybun backend handled before this point")
         }
     }
 
     eprintln!("info: running tests with {:?}...", backend);
 
-    // Execute the tests
-    let output = cmd
-        .output()
-        .map_err(|e| eyre!("failed to execute test runner: {}", e))?;
+    if let Some(plugin) = &params_plugin {
+        plugin.apply(&mut cmd);
+    }
+
+    let network_guard = NetworkGuard::for_operation(Operation::Test)
+        .map_err(|e| eyre!("failed to prepare network guard: {}", e))?;
+    if let Some(guard) = &network_guard {
+        guard.apply(&mut cmd);
+    }
 
-    let exit_code = output.status.code().unwrap_or(-1);
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    // Execute the tests. With `-j N` the pytest backend runs as N worker
+    // processes scheduled from here instead of through pytest-xdist.
+    let started = std::time::Instant::now();
+    let scheduled_workers = args
+        .parallel
+        .filter(|&workers| backend == TestBackend::Pytest && workers > 1 && tests.len() > 1);
+    let (exit_code, stdout, stderr, test_cases, scheduler) = match scheduled_workers {
+        Some(workers) => {
+            let run = run_pytest_workers(
+                &python,
+                args,
+                &tests,
+                workers,
+                params_plugin.as_ref(),
+                network_guard.as_ref(),
+                coverage.as_ref(),
+                collector,
+            )?;
+            (
+                run.exit_code,
+                run.stdout,
+                run.stderr,
+                Some(run.tests),
+                Some(run.detail),
+            )
+        }
+        None => {
+            let (status, stdout, stderr) = crate::proc_exec::output_capturing(&mut cmd)
+                .map_err(|e| eyre!("failed to execute test runner: {}", e))?;
+            let test_cases = junit_report
+                .as_ref()
+                .and_then(|report| std::fs::read_to_string(report.path()).ok())
+                .map(|xml| test_report::parse_junit(&xml));
+            (
+                status.code().unwrap_or(-1),
+                stdout,
+                stderr,
+                test_cases,
+                None,
+            )
+        }
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+    if let Some(guard) = &network_guard {
+        guard.record_blocked();
+    }
 
     // Parse test results (simplified)
-    let tests_passed = stdout.contains("passed") || stdout.contains("OK");
-    let tests_failed = !output.status.success();
+    let tests_passed = stdout.text.contains("passed") || stdout.text.contains("OK");
+    let tests_failed = exit_code != 0;
+
+    record_history(RunRecord::now(
+        &format!("{:?}", backend).to_lowercase(),
+        duration_ms,
+        !tests_failed,
+        test_cases
+            .iter()
+            .flatten()
+            .map(|case| TestRecord::new(case.id(), case.outcome.clone(), case.duration_ms))
+            .collect(),
+    ));
+    record_results(
+        &tests,
+        test_cases.as_deref().unwrap_or_default(),
+        !tests_failed,
+    );
+    let report_path = match &test_cases {
+        Some(cases) => write_junit_report(args, "pytest", cases, duration_ms, collector),
+        None => {
+            if let Some(path) = &args.report {
+                collector.diagnostic(
+                    Diagnostic::warning(format!(
+                        "{} does not report individual tests; no report written to {}",
+                        format!("{:?}", backend).to_lowercase(),
+                        path.display()
+                    ))
+                    .with_code("W_TEST_REPORT_UNSUPPORTED")
+                    .with_suggestion(
+                        "Run with --backend=pytest or --backend=native for a per-test JUnit report.",
+                    ),
+                );
+            }
+            None
+        }
+    };
 
     let summary = if tests_failed {
         format!("Tests failed (exit code {})", exit_code)
@@ -734,12 +1025,8 @@ pub(crate) fn run_tests(
     };
 
     // Print output
-    if !stdout.is_empty() {
-        eprintln!("{}", stdout);
-    }
-    if !stderr.is_empty() {
-        eprintln!("{}", stderr);
-    }
+    echo_captured(&stdout);
+    echo_captured(&stderr);
 
     // Build compat_warnings for JSON output
     let run_compat_warnings_json: Vec<Value> = if args.pytest_compat {
@@ -764,6 +1051,7 @@ pub(crate) fn run_tests(
     let detail = json!({
         "backend": format!("{:?}", backend).to_lowercase(),
         "workspace": member_detail,
+        "targets": targets_json,
         "test_runner": format!("{:?}", backend).to_lowercase(),
         "exit_code": exit_code,
         "passed": tests_passed && !tests_failed,
@@ -781,20 +1069,651 @@ pub(crate) fn run_tests(
             "compat_warnings": discovery_result.compat_warnings.len(),
         },
         "compat_warnings": run_compat_warnings_json,
-        "stdout": stdout.to_string(),
-        "stderr": stderr.to_string(),
+        "params": params.to_json(),
+        "tests": test_cases,
+        "report": report_path,
+        "scheduler": scheduler,
+        "changed": changed_detail,
+        "stdout": stdout,
+        "stderr": stderr,
     });
 
-    if tests_failed {
+    let detail = if tests_failed {
         collector.error_with_code(
             "E_TEST_FAILED",
             summary.clone(),
             "Inspect stdout/stderr in the response for failing test output, fix the failing tests, and re-run `pybun test`.",
         );
-        Ok(RenderDetail::error(summary, detail))
+        RenderDetail::error(summary, detail)
+    } else {
+        RenderDetail::with_json(summary, detail)
+    };
+    let detail = finish_coverage(detail, coverage, &python, args, shard_info, collector);
+    let plugin_runs = if args.fail_fast && tests_failed {
+        PluginRuns::default()
     } else {
-        Ok(RenderDetail::with_json(summary, detail))
+        run_plugin_batches(&batches, &python, &params, args, collector)?
+    };
+    Ok(with_plugin_runs(detail, plugin_runs))
+}
+
+/// pytest options shared by the single-process run and scheduled workers.
+fn pytest_options(
+    args: &crate::cli::TestArgs,
+    params_plugin: Option<&ParamsPlugin>,
+) -> Vec<String> {
+    let mut options = Vec::new();
+
+    // Register the `pybun_params` fixture
+    if let Some(plugin) = params_plugin {
+        options.extend(plugin.pytest_args().iter().map(|arg| arg.to_string()));
+    }
+
+    // Add fail-fast flag
+    if args.fail_fast {
+        options.push("-x".to_string());
+    }
+
+    // Add verbose for better output
+    if args.verbose {
+        options.push("-v".to_string());
+    }
+
+    // Add filter (-k option)
+    if let Some(ref pattern) = args.filter {
+        options.push("-k".to_string());
+        options.push(pattern.clone());
+    }
+    options
+}
+
+/// Echo captured test output to stderr, streaming it back from disk when it
+/// was spilled.
+fn echo_captured(output: &CapturedOutput) {
+    if output.is_empty() {
+        return;
+    }
+    let mut stderr = std::io::stderr().lock();
+    let _ = output.copy_to(&mut stderr);
+    let _ = writeln!(stderr);
+}
+
+/// Merged result of a scheduled pytest run.
+struct ScheduledRun {
+    exit_code: i32,
+    stdout: CapturedOutput,
+    stderr: CapturedOutput,
+    tests: Vec<TestCaseReport>,
+    /// `detail.scheduler`: the shards and how each worker did.
+    detail: Value,
+}
+
+/// Run the selected tests as `workers` pytest processes, balanced by the
+/// durations in the test history (see [`crate::test_scheduler`]). Results
+/// are reported as events while the workers run.
+#[allow(clippy::too_many_arguments)]
+fn run_pytest_workers(
+    python: &str,
+    args: &crate::cli::TestArgs,
+    tests: &[TestItem],
+    workers: usize,
+    params_plugin: Option<&ParamsPlugin>,
+    network_guard: Option<&NetworkGuard>,
+    coverage: Option<&CoverageData>,
+    collector: &mut EventCollector,
+) -> Result<ScheduledRun> {
+    let root = history_root();
+    let durations = history_store()
+        .map(|store| test_scheduler::expected_durations(&store.load()))
+        .unwrap_or_default();
+    let shards = test_scheduler::balance(
+        tests
+            .iter()
+            .map(|t| {
+                let id = format!(
+                    "{}::{}",
+                    test_history::relative_id(&root, &root.join(&t.path)),
+                    t.name
+                );
+                (
+                    crate::test_selection::node_id(t),
+                    durations.get(&id).copied(),
+                )
+            })
+            .collect(),
+        workers,
+    );
+
+    let mut options = pytest_options(args, params_plugin);
+    if !args.verbose {
+        // Result lines are what the scheduler streams.
+        options.push("-v".to_string());
+    }
+    let mut worker_commands = Vec::new();
+    for shard in &shards {
+        let report = tempfile::NamedTempFile::new()
+            .map_err(|e| eyre!("failed to create worker report file: {}", e))?;
+        let mut cmd = ProcessCommand::new(python);
+        if let Some(data) = coverage {
+            cmd.args(test_coverage::run_args(&data.data_file()));
+        }
+        cmd.args(["-m", "pytest"])
+            .args(&options)
+            .arg(format!("--junitxml={}", report.path().display()))
+            .args(["-o", "junit_family=xunit1"])
+            .args(&shard.tests)
+            .args(&args.passthrough);
+        if let Some(plugin) = params_plugin {
+            plugin.apply(&mut cmd);
+        }
+        if let Some(guard) = network_guard {
+            guard.apply(&mut cmd);
+        }
+        worker_commands.push(test_scheduler::Worker {
+            command: cmd,
+            report,
+        });
+    }
+
+    let total: usize = tests
+        .iter()
+        .map(|t| t.parametrize.as_ref().map_or(1, |p| p.case_count.max(1)))
+        .sum();
+    collector.info(format!(
+        "Running {} tests across {} pytest workers",
+        tests.len(),
+        shards.len()
+    ));
+    collector.event_with(EventType::TestStart, |event| {
+        event.message = Some(format!("Running {total} tests"));
+        event.progress = Some(30);
+        event.data = Some(json!({ "completed": 0, "total": total, "workers": shards.len() }));
+    });
+    let mut completed = 0;
+    let runs = test_scheduler::run(worker_commands, |worker, node_id, outcome| {
+        completed += 1;
+        let event_type = match outcome {
+            "passed" | "xfail" => EventType::TestPass,
+            "skipped" => EventType::TestSkip,
+            _ => EventType::TestFail,
+        };
+        collector.event_with(event_type, |event| {
+            event.message = Some(node_id.to_string());
+            event.progress = Some((30 + 70 * completed.min(total) / total.max(1)) as u8);
+            event.data = Some(json!({
+                "completed": completed,
+                "total": total,
+                "outcome": outcome,
+                "worker": worker,
+            }));
+        });
+        args.fail_fast && matches!(outcome, "failed" | "error")
+    })
+    .map_err(|e| eyre!("failed to execute test runner: {}", e))?;
+
+    // The run fails with the lowest non-zero worker exit code; workers
+    // stopped by fail-fast have none.
+    let exit_code = if runs.iter().all(|run| run.exit_code == Some(0)) {
+        0
+    } else {
+        runs.iter()
+            .filter_map(|run| run.exit_code)
+            .filter(|&code| code != 0)
+            .min()
+            .unwrap_or(-1)
+    };
+    let mut stdout = Capture::for_output("stdout");
+    let mut stderr = Capture::for_output("stderr");
+    for (index, (shard, run)) in shards.iter().zip(&runs).enumerate() {
+        let header = format!(
+            "==== pybun worker {} ({} tests) ====\n",
+            index + 1,
+            shard.tests.len()
+        );
+        let merge = |merged: &mut Capture, output: &CapturedOutput| {
+            merged.write_all(header.as_bytes())?;
+            output.copy_to(merged)
+        };
+        merge(&mut stdout, &run.stdout)
+            .map_err(|e| eyre!("failed to collect worker output: {}", e))?;
+        if !run.stderr.is_empty() {
+            merge(&mut stderr, &run.stderr)
+                .map_err(|e| eyre!("failed to collect worker output: {}", e))?;
+        }
+    }
+    let stdout = stdout
+        .finish()
+        .map_err(|e| eyre!("failed to collect worker output: {}", e))?;
+    let stderr = stderr
+        .finish()
+        .map_err(|e| eyre!("failed to collect worker output: {}", e))?;
+    let detail = json!({
+        "workers": shards.len(),
+        "shards": shards
+            .iter()
+            .zip(&runs)
+            .enumerate()
+            .map(|(index, (shard, run))| json!({
+                "worker": index,
+                "tests": shard.tests,
+                "expected_ms": shard.expected_ms,
+                "duration_ms": run.duration_ms,
+                "exit_code": run.exit_code,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    Ok(ScheduledRun {
+        exit_code,
+        stdout,
+        stderr,
+        tests: runs.into_iter().flat_map(|run| run.tests).collect(),
+        detail,
+    })
+}
+
+/// Write `cases` as a JUnit XML report to `--report`, returning the path
+/// written. Best effort: a report that cannot be written is a warning, not
+/// a failed run.
+fn write_junit_report(
+    args: &crate::cli::TestArgs,
+    suite: &str,
+    cases: &[TestCaseReport],
+    duration_ms: u64,
+    collector: &mut EventCollector,
+) -> Option<String> {
+    let path = args.report.as_ref()?;
+    let xml = test_report::to_junit(suite, cases, duration_ms);
+    let written = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(path, xml));
+    match written {
+        Ok(()) => {
+            collector.info(format!("JUnit report written to {}", path.display()));
+            Some(path.display().to_string())
+        }
+        Err(e) => {
+            collector.diagnostic(
+                Diagnostic::warning(format!(
+                    "failed to write JUnit report to {}: {}",
+                    path.display(),
+                    e
+                ))
+                .with_code("W_TEST_REPORT_WRITE"),
+            );
+            None
+        }
+    }
+}
+
+/// Root the test history is keyed on: the enclosing project, else the
+/// current directory.
+fn history_root() -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    crate::project::Project::discover(&cwd)
+        .map(|project| project.root().to_path_buf())
+        .unwrap_or(cwd)
+}
+
+pub(super) fn history_store() -> Result<TestHistory> {
+    let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+    Ok(TestHistory::for_project(cache.root(), &history_root()))
+}
+
+/// Record a finished run. Best effort: history must never fail a test run.
+fn record_history(run: RunRecord) {
+    if test_history::enabled()
+        && let Ok(store) = history_store()
+    {
+        let _ = store.record(&run);
+    }
+}
+
+/// Make sure `python` can import coverage.py, installing it into the
+/// project's virtual environment when missing. On failure the error is
+/// recorded and its message returned.
+fn prepare_coverage(
+    python: &str,
+    env_source: &EnvSource,
+    collector: &mut EventCollector,
+) -> std::result::Result<CoverageData, String> {
+    const SUGGESTION: &str = "Add coverage to the project (`pybun add --group dev coverage`) or install it for the interpreter `pybun test` uses.";
+    if !test_coverage::is_installed(python) {
+        if !matches!(env_source, EnvSource::PybunEnv | EnvSource::ProjectLocal) {
+            let message = format!("coverage is not installed for {python}");
+            collector.error_with_code("E_TEST_COVERAGE_UNAVAILABLE", message.clone(), SUGGESTION);
+            return Err(message);
+        }
+        eprintln!("info: installing coverage into the test environment");
+        if let Err(error) = install_coverage(python) {
+            let message = format!("failed to install coverage: {error}");
+            collector.diagnostic(
+                Diagnostic::error(message.clone())
+                    .with_code("E_TEST_COVERAGE_UNAVAILABLE")
+                    .with_suggestion(SUGGESTION)
+                    .with_tool_failure(&error),
+            );
+            return Err(message);
+        }
+    }
+    CoverageData::new().map_err(|e| format!("failed to create a coverage data directory: {e}"))
+}
+
+/// Install coverage.py for `python`, with uv when it is available.
+fn install_coverage(python: &str) -> std::result::Result<(), ToolError> {
+    let uv = crate::env::find_uv_executable();
+    let mut cmd = match &uv {
+        Some(uv) => {
+            let mut cmd = ProcessCommand::new(uv);
+            cmd.args(["pip", "install", "--quiet", "--python", python]);
+            cmd
+        }
+        None => {
+            let mut cmd = ProcessCommand::new(python);
+            cmd.args(["-m", "pip", "install", "--quiet"]);
+            cmd
+        }
+    };
+    super::offline_install_args(&mut cmd, uv.is_some());
+    cmd.arg("coverage");
+    ToolRun::new(if uv.is_some() { "uv" } else { "pip" }, &mut cmd)
+        .idempotent()
+        .run()
+        .map(|_| ())
+}
+
+/// Combine the run's coverage data, write the `--coverage-report` files
+/// and check `--coverage-fail-under`, adding `detail.coverage`.
+fn finish_coverage(
+    mut detail: RenderDetail,
+    data: Option<CoverageData>,
+    python: &str,
+    args: &crate::cli::TestArgs,
+    shard_info: Option<(u32, u32)>,
+    collector: &mut EventCollector,
+) -> RenderDetail {
+    let Some(data) = data else {
+        return detail;
+    };
+    let data_file = test_coverage::combined_data_file(shard_info);
+    let summary = data
+        .combine(python, &data_file)
+        .and_then(|()| data.summary(python, &data_file));
+    let summary = match summary {
+        Ok(Some(summary)) => summary,
+        Ok(None) | Err(_) => {
+            let mut diagnostic = Diagnostic::warning("no coverage data was recorded")
+                .with_code("W_TEST_COVERAGE_NO_DATA")
+                .with_suggestion(
+                    "Check that tests ran; coverage.py's output is in the diagnostic context.",
+                );
+            if let Err(error) = &summary {
+                diagnostic = diagnostic.with_tool_failure(error);
+            }
+            collector.diagnostic(diagnostic);
+            detail.json["coverage"] = Value::Null;
+            return detail;
+        }
+    };
+
+    let mut reports = serde_json::Map::new();
+    for &format in &args.coverage_report {
+        let (name, _) = test_coverage::report_target(format);
+        match test_coverage::write_report(python, &data_file, format) {
+            Ok(path) => {
+                reports.insert(name.to_string(), json!(path.display().to_string()));
+            }
+            Err(error) => collector.diagnostic(
+                Diagnostic::warning(format!("failed to write the {name} coverage report"))
+                    .with_code("W_TEST_COVERAGE_REPORT")
+                    .with_tool_failure(&error),
+            ),
+        }
+    }
+
+    match args.coverage_fail_under {
+        Some(threshold) if summary.percent < threshold => {
+            let message = format!(
+                "coverage {:.1}% is below --coverage-fail-under {}",
+                summary.percent, threshold
+            );
+            collector.error_with_code(
+                "E_TEST_COVERAGE_BELOW",
+                message.clone(),
+                "Cover the missing lines listed in detail.coverage.files, or lower --coverage-fail-under.",
+            );
+            detail.is_error = true;
+            detail.text = format!("{}; {}", detail.text, message);
+        }
+        _ => detail.text = format!("{} (coverage {:.1}%)", detail.text, summary.percent),
     }
+
+    let mut coverage = serde_json::to_value(&summary).unwrap_or(Value::Null);
+    coverage["data_file"] = json!(data_file.display().to_string());
+    coverage["reports"] = Value::Object(reports);
+    coverage["fail_under"] = json!(args.coverage_fail_under);
+    detail.json["coverage"] = coverage;
+    detail
+}
+
+fn result_store() -> Result<ResultStore> {
+    let cache = Cache::new().map_err(|e| eyre!("failed to initialize cache: {}", e))?;
+    Ok(ResultStore::for_project(cache.root(), &history_root()))
+}
+
+/// Record per-test outcomes and dependency hashes for `--changed`. Tests
+/// without a reported outcome take the outcome of the whole run. Best
+/// effort, like the history.
+fn record_results(tests: &[TestItem], cases: &[TestCaseReport], passed: bool) {
+    if !test_history::enabled() {
+        return;
+    }
+    let Ok(store) = result_store() else {
+        return;
+    };
+    let outcomes = cases
+        .iter()
+        .map(|case| (case.id(), case.outcome.clone()))
+        .collect();
+    let mut cache = store.load();
+    test_impact::record(
+        &mut cache,
+        &history_root(),
+        tests,
+        &outcomes,
+        if passed { "passed" } else { "failed" },
+    );
+    let _ = store.save(&cache);
+}
+
+/// `pybun test --history`: report recorded runs instead of running tests.
+fn history_report(
+    args: &crate::cli::TestArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    let store = history_store()?;
+    let runs = store.load();
+    let report = test_history::report(&runs, args.history_runs, HISTORY_SLOWEST);
+    if let Some(path) = &args.history_export
+        && let Err(e) = report.export(path)
+    {
+        collector.error_with_code(
+            "E_TEST_HISTORY_EXPORT",
+            e.to_string(),
+            "Pass a writable path ending in .json or .csv to --history-export.",
+        );
+        return Ok(RenderDetail::error(
+            e.to_string(),
+            json!({ "error": e.to_string() }),
+        ));
+    }
+
+    let mut detail = serde_json::to_value(&report).unwrap_or(Value::Null);
+    detail["history_file"] = json!(store.path().display().to_string());
+    detail["recorded_runs"] = json!(runs.len());
+    detail["export"] = json!(
+        args.history_export
+            .as_ref()
+            .map(|p| p.display().to_string())
+    );
+
+    let Some(latest) = report.runs.last() else {
+        return Ok(RenderDetail::with_json(
+            "No test runs recorded for this project yet; run `pybun test` first".to_string(),
+            detail,
+        ));
+    };
+    let seconds = |ms: u64| format!("{:.2}s", ms as f64 / 1000.0);
+    let mut lines = vec![format!(
+        "Test history: {} run{} (latest {}, {})",
+        report.runs.len(),
+        if report.runs.len() == 1 { "" } else { "s" },
+        if latest.passed { "passed" } else { "failed" },
+        seconds(latest.duration_ms),
+    )];
+    lines.push(format!(
+        "Suite duration: {}",
+        report
+            .runs
+            .iter()
+            .map(|run| seconds(run.duration_ms))
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    ));
+    if !report.slowest.is_empty() {
+        lines.push("Slowest tests:".to_string());
+        for test in &report.slowest {
+            let change = match (test.median_duration_ms, test.change_percent) {
+                (Some(median), Some(percent)) => {
+                    format!(" (median {median} ms, {percent:+}%, {})", test.trend)
+                }
+                _ => format!(" ({})", test.trend),
+            };
+            lines.push(format!(
+                "  {:>6} ms  {}{}",
+                test.last_duration_ms, test.id, change
+            ));
+        }
+    }
+    for (title, tests) in [
+        ("Newly failing", &report.newly_failing),
+        ("Chronically failing", &report.chronically_failing),
+    ] {
+        if !tests.is_empty() {
+            lines.push(format!("{title} ({}):", tests.len()));
+            for test in tests {
+                lines.push(format!(
+                    "  {} (failed {}/{} runs)",
+                    test.id, test.failures, test.runs
+                ));
+            }
+        }
+    }
+    if let Some(path) = &args.history_export {
+        lines.push(format!("Exported to {}", path.display()));
+    }
+    Ok(RenderDetail::with_json(lines.join("\n"), detail))
+}
+
+/// Plugin-owned tests run by [`run_plugin_batches`].
+#[derive(Default)]
+struct PluginRuns {
+    runs: Vec<Value>,
+    failed: Vec<String>,
+}
+
+/// Run each plugin's selected tests through its execution adapter, with the
+/// same `--env`/`--fixture` values and network policy as the backend.
+fn run_plugin_batches(
+    batches: &[PluginBatch<'_>],
+    python: &str,
+    params: &TestParams,
+    args: &crate::cli::TestArgs,
+    collector: &mut EventCollector,
+) -> Result<PluginRuns> {
+    let mut plugin_runs = PluginRuns::default();
+    if batches.is_empty() {
+        return Ok(plugin_runs);
+    }
+
+    let params_plugin =
+        ParamsPlugin::new(params).map_err(|e| eyre!("failed to prepare test parameters: {}", e))?;
+    let network_guard = NetworkGuard::for_operation(Operation::Test)
+        .map_err(|e| eyre!("failed to prepare network guard: {}", e))?;
+
+    for batch in batches {
+        let name = batch.plugin.name();
+        let command = batch.command();
+        let mut cmd = ProcessCommand::new(python);
+        cmd.args(&command);
+        if let Some(plugin) = &params_plugin {
+            plugin.apply(&mut cmd);
+        }
+        if let Some(guard) = &network_guard {
+            guard.apply(&mut cmd);
+        }
+
+        eprintln!(
+            "info: running {} tests with the {} plugin...",
+            batch.items.len(),
+            name
+        );
+        let (status, stdout, stderr) = crate::proc_exec::output_capturing(&mut cmd)
+            .map_err(|e| eyre!("failed to execute the {} test plugin: {}", name, e))?;
+        if let Some(guard) = &network_guard {
+            guard.record_blocked();
+        }
+
+        let exit_code = status.code().unwrap_or(-1);
+        echo_captured(&stdout);
+        echo_captured(&stderr);
+
+        let passed = status.success();
+        if !passed {
+            collector.diagnostic(
+                Diagnostic::error(format!("{} tests failed (exit code {})", name, exit_code))
+                    .with_code("E_TEST_PLUGIN_FAILED")
+                    .with_suggestion(
+                        "Inspect the plugin's stdout/stderr in the response, fix the failing tests, and re-run `pybun test`.",
+                    )
+                    .with_context(json!({ "plugin": name, "command": command })),
+            );
+            plugin_runs.failed.push(name.to_string());
+        }
+        plugin_runs.runs.push(json!({
+            "name": name,
+            "command": command,
+            "tests": batch.items.iter().map(crate::test_selection::node_id).collect::<Vec<_>>(),
+            "exit_code": exit_code,
+            "passed": passed,
+            "stdout": stdout,
+            "stderr": stderr,
+        }));
+
+        if !passed && args.fail_fast {
+            break;
+        }
+    }
+
+    Ok(plugin_runs)
+}
+
+/// Record plugin runs in a backend's result; a failing plugin fails the run.
+fn with_plugin_runs(mut detail: RenderDetail, plugin_runs: PluginRuns) -> RenderDetail {
+    if plugin_runs.runs.is_empty() {
+        return detail;
+    }
+    detail.json["plugins"] = Value::Array(plugin_runs.runs);
+    if !plugin_runs.failed.is_empty() {
+        detail.is_error = true;
+        detail.text = format!(
+            "{}; plugin tests failed: {}",
+            detail.text,
+            plugin_runs.failed.join(", ")
+        );
+    }
+    detail
 }
 
 // ---------------------------------------------------------------------------
@@ -843,15 +1762,20 @@ fn normalize_timing_in_line(line: &str) -> String {
     format!("{}X.XXs{}", &line[..prefix_end], trailer)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_tests_native(
     args: &crate::cli::TestArgs,
+    backend: TestBackend,
     tests: Vec<TestItem>,
     shard_info: Option<(u32, u32)>,
     python: &str,
+    params: &TestParams,
+    coverage: Option<&CoverageData>,
     member_detail: Option<Value>,
+    targets_json: Value,
     collector: &mut EventCollector,
 ) -> Result<RenderDetail> {
-    use crate::test_executor::{ExecutorConfig, TestExecutor, TestOutcome};
+    use crate::test_executor::{ExecutorConfig, TestExecutor, TestOutcome, TestRunner};
 
     let workers = args.parallel.unwrap_or_else(|| {
         std::thread::available_parallelism()
@@ -859,6 +1783,26 @@ fn run_tests_native(
             .unwrap_or(4)
     });
 
+    let params_plugin =
+        ParamsPlugin::new(params).map_err(|e| eyre!("failed to prepare test parameters: {}", e))?;
+    let network_guard = NetworkGuard::for_operation(Operation::Test)
+        .map_err(|e| eyre!("failed to prepare network guard: {}", e))?;
+    let mut env: Vec<(String, std::ffi::OsString)> = params_plugin
+        .iter()
+        .flat_map(|plugin| plugin.env(None))
+        .collect();
+    let python_path = env
+        .iter()
+        .find(|(key, _)| key == "PYTHONPATH")
+        .map(|(_, value)| value.clone());
+    if let Some(guard) = &network_guard {
+        env.extend(
+            guard
+                .env(python_path.as_deref())
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value)),
+        );
+    }
     let config = ExecutorConfig {
         workers,
         fail_fast: args.fail_fast,
@@ -867,18 +1811,92 @@ fn run_tests_native(
         timeout: args.timeout,
         retries: args.retries.unwrap_or(0),
         python: python.to_string(),
+        runner: if backend == TestBackend::Native {
+            TestRunner::Harness
+        } else {
+            TestRunner::Pytest
+        },
+        env,
+        pytest_args: params_plugin
+            .iter()
+            .flat_map(|plugin| plugin.pytest_args())
+            .map(str::to_string)
+            .collect(),
+        cancel: None,
+        coverage: coverage.map(CoverageData::data_file),
     };
 
     let executor = TestExecutor::new(config);
+    let backend_name = format!("{:?}", backend).to_lowercase();
 
     collector.info(format!(
-        "Running {} tests with native pybun executor ({} workers)",
+        "Running {} tests with native pybun executor ({} workers{})",
         tests.len(),
-        workers
+        workers,
+        if backend == TestBackend::Native {
+            ", built-in harness"
+        } else {
+            ""
+        }
     ));
 
-    let result = executor.execute(tests);
+    let total = tests.len();
+    collector.event_with(EventType::TestStart, |event| {
+        event.message = Some(format!("Running {total} tests"));
+        event.progress = Some(30);
+        event.data = Some(json!({ "completed": 0, "total": total }));
+    });
+    let mut completed = 0;
+    let ran = tests.clone();
+    let result = executor.execute_observed(tests, |result| {
+        completed += 1;
+        let event_type = match result.outcome {
+            TestOutcome::Passed | TestOutcome::XFail => EventType::TestPass,
+            TestOutcome::Skipped => EventType::TestSkip,
+            _ => EventType::TestFail,
+        };
+        collector.event_with(event_type, |event| {
+            event.message = Some(format!("{}::{}", result.path.display(), result.name));
+            event.progress = Some((30 + 70 * completed / total.max(1)) as u8);
+            event.data = Some(json!({
+                "completed": completed,
+                "total": total,
+                "outcome": result.outcome,
+                "duration_ms": result.duration_ms,
+            }));
+        });
+    });
+    collector.event_with(EventType::TestComplete, |event| {
+        event.message = Some(format!(
+            "{} passed, {} failed, {} skipped",
+            result.summary.passed, result.summary.failed, result.summary.skipped
+        ));
+    });
+    if let Some(guard) = &network_guard {
+        guard.record_blocked();
+    }
     let summary = &result.summary;
+    let root = history_root();
+    record_history(RunRecord::now(
+        &backend_name,
+        summary.duration_ms,
+        summary.all_passed(),
+        result
+            .results
+            .iter()
+            .map(|r| {
+                let path = test_history::relative_id(&root, &root.join(&r.path));
+                TestRecord::new(
+                    format!("{path}::{}", r.name),
+                    serde_json::to_value(&r.outcome)
+                        .ok()
+                        .and_then(|v| v.as_str().map(str::to_string))
+                        .unwrap_or_default(),
+                    r.duration_ms,
+                )
+            })
+            .collect(),
+    ));
 
     // Native snapshot integration: each passing test's captured stdout is
     // compared against (or written to, in update mode) its stored snapshot
@@ -905,6 +1923,7 @@ fn run_tests_native(
                 collector.diagnostic(Diagnostic {
                     level: crate::schema::DiagnosticLevel::Warning,
                     code: Some("W_SNAPSHOT_FLAKY_RETRY".to_string()),
+                    error_code: None,
                     message: format!(
                         "{}::{} passed only after {} retr{} — its snapshot reflects the final attempt's output, which may not be reproducible",
                         r.path.display(),
@@ -930,6 +1949,7 @@ fn run_tests_native(
             collector.diagnostic(Diagnostic {
                 level: crate::schema::DiagnosticLevel::Warning,
                 code: Some("E_SNAPSHOT_SAVE".to_string()),
+                error_code: None,
                 message: format!("failed to save snapshot updates: {}", e),
                 file: None,
                 line: None,
@@ -1034,6 +2054,7 @@ fn run_tests_native(
         let diag = Diagnostic {
             level: crate::schema::DiagnosticLevel::Error,
             code: Some(code.to_string()),
+            error_code: None,
             message: format!("{prefix} {}", failed.name),
             file: Some(failed.path.display().to_string()),
             line: Some(failed.line as u32),
@@ -1051,6 +2072,20 @@ fn run_tests_native(
         collector.diagnostic(diag);
     }
 
+    let test_cases: Vec<TestCaseReport> = result
+        .results
+        .iter()
+        .map(|r| TestCaseReport::from_result(r, &root))
+        .collect();
+    record_results(&ran, &test_cases, summary.all_passed());
+    let report_path = write_junit_report(
+        args,
+        &backend_name,
+        &test_cases,
+        summary.duration_ms,
+        collector,
+    );
+
     let results_json: Vec<Value> = result
         .results
         .iter()
@@ -1071,9 +2106,10 @@ fn run_tests_native(
         .collect();
 
     let detail = json!({
-        "backend": "pybun",
+        "backend": backend_name,
         "workspace": member_detail,
-        "test_runner": "pybun",
+        "targets": targets_json,
+        "test_runner": backend_name,
         "workers": workers,
         "fail_fast": args.fail_fast,
         "timeout": args.timeout,
@@ -1084,6 +2120,7 @@ fn run_tests_native(
         "snapshot": args.snapshot,
         "update_snapshots": args.update_snapshots,
         "snapshot_config": snapshot_config,
+        "params": params.to_json(),
         "summary": {
             "total": summary.total,
             "passed": summary.passed,
@@ -1097,6 +2134,8 @@ fn run_tests_native(
             "stopped_early": summary.stopped_early,
         },
         "results": results_json,
+        "tests": test_cases,
+        "report": report_path,
     });
 
     if summary.all_passed() {
@@ -1,5 +1,8 @@
 use super::RenderDetail;
-use crate::cli::{LazyImportArgs, ModuleFindArgs, ProfileArgs, WatchArgs};
+use crate::cli::{
+    AuthCommands, CompletionsArgs, HookCommands, LazyImportArgs, ModuleFindArgs, OutputFormat,
+    ProfileArgs, ProfileImportsArgs, ProfileRunArgs, ProfileSampler, RunArgs, WatchArgs,
+};
 #[cfg(feature = "native-watch")]
 use crate::hot_reload::run_native_watch_loop;
 #[cfg(not(feature = "native-watch"))]
@@ -10,9 +13,10 @@ use crate::lazy_import::{
 };
 use crate::module_finder::{ModuleFinder, ModuleFinderConfig};
 use crate::profiles::{Profile, ProfileConfig, ProfileManager};
-use crate::schema::EventCollector;
+use crate::schema::{Diagnostic, EventCollector};
 use color_eyre::eyre::{Result, eyre};
 use serde_json::{Value, json};
+use std::io::IsTerminal;
 
 // ---------------------------------------------------------------------------
 // pybun module-find (Rust-based module finder)
@@ -554,6 +558,8 @@ pub(super) fn run_watch(args: &WatchArgs, collector: &mut EventCollector) -> Res
                     "polling": true,
                     "iterations": outcome.iterations,
                     "runs": outcome.runs,
+                    "skipped_runs": outcome.skipped,
+                    "syntax_error": outcome.syntax_error,
                 }),
             )),
             Err(e) => {
@@ -722,3 +728,911 @@ pub(super) fn run_profile(
         }),
     ))
 }
+
+/// `pybun profile imports`: run a script under `-X importtime` and report
+/// its import costs (see [`crate::import_profile`]).
+pub(super) fn run_profile_imports(
+    args: &ProfileImportsArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    use crate::import_profile;
+    use crate::units::format_millis;
+
+    if !args.script.is_file() {
+        return Err(eyre!("script not found: {}", args.script.display()));
+    }
+    let cwd = std::env::current_dir()?;
+    let python = crate::env::find_python_env(&cwd)?.python_path;
+    let output = std::process::Command::new(&python)
+        .arg("-X")
+        .arg("importtime")
+        .arg(&args.script)
+        .args(&args.passthrough)
+        .output()
+        .map_err(|e| eyre!("failed to run {}: {}", python.display(), e))?;
+    let (profile, stderr) = import_profile::parse(&String::from_utf8_lossy(&output.stderr));
+    if profile.startup.is_empty() && profile.imports.is_empty() {
+        return Err(eyre!(
+            "{} printed no `-X importtime` output; Python 3.7 or newer is required",
+            python.display()
+        ));
+    }
+    let exit_code = output.status.code().unwrap_or(-1);
+    if !output.status.success() {
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "{} exited with status {exit_code}; only the imports before it stopped are profiled{}",
+                args.script.display(),
+                stderr
+                    .last()
+                    .map(|line| format!(": {}", line.trim()))
+                    .unwrap_or_default()
+            ))
+            .with_code("W_PROFILE_SCRIPT_FAILED")
+            .with_suggestion("Fix the error, or pass the arguments the script needs after `--`."),
+        );
+    }
+
+    let top = profile.top_offenders(args.top);
+    let sites = std::fs::read_to_string(&args.script)
+        .map(|source| crate::lazy_import::import_sites(&source))
+        .unwrap_or_default();
+    let suggestion =
+        profile.lazy_candidates(&LazyImportConfig::with_defaults(), args.min_ms, &sites);
+    let command = suggestion.command("lazy_imports.py");
+
+    let mut text = format!(
+        "{}: imported {} modules in {} (interpreter start-up: {})",
+        args.script.display(),
+        profile.module_count(),
+        format_millis(profile.imports_ms()),
+        format_millis(profile.startup_ms())
+    );
+    if !top.is_empty() {
+        text.push_str("\n\nSlowest imports (own time, cumulative):");
+        for offender in &top {
+            text.push_str(&format!(
+                "\n  {:>9}  {:>9}  {}",
+                format_millis(offender.self_ms),
+                format_millis(offender.cumulative_ms),
+                offender.module
+            ));
+            if let Some(parent) = &offender.imported_by {
+                text.push_str(&format!(" <- {parent}"));
+            }
+        }
+    }
+    if !suggestion.allow.is_empty() {
+        text.push_str("\n\nLazy-import candidates:");
+        for candidate in &suggestion.allow {
+            text.push_str(&format!(
+                "\n  {:>9}  {}",
+                format_millis(candidate.cumulative_ms),
+                candidate.module
+            ));
+            if let Some(line) = candidate.line {
+                text.push_str(&format!(" ({}:{line})", args.script.display()));
+            }
+        }
+    }
+    if !suggestion.guarded.is_empty() {
+        let guarded: Vec<String> = suggestion
+            .guarded
+            .iter()
+            .map(|c| format!("{} ({})", c.module, format_millis(c.cumulative_ms)))
+            .collect();
+        text.push_str(&format!(
+            "\n\nKept eager, imported under `except ImportError`: {}",
+            guarded.join(", ")
+        ));
+    }
+    if !suggestion.denied.is_empty() {
+        let denied: Vec<String> = suggestion
+            .denied
+            .iter()
+            .map(|c| format!("{} ({})", c.module, format_millis(c.cumulative_ms)))
+            .collect();
+        text.push_str(&format!(
+            "\n\nKept eager by the lazy-import denylist: {}",
+            denied.join(", ")
+        ));
+    }
+    if let Some(command) = &command {
+        text.push_str(&format!("\n\nGenerate a hook: {command}"));
+    }
+
+    Ok(RenderDetail::with_json(
+        text,
+        json!({
+            "script": args.script.display().to_string(),
+            "python": python.display().to_string(),
+            "exit_code": exit_code,
+            "modules": profile.module_count(),
+            "imports_ms": profile.imports_ms(),
+            "startup_ms": profile.startup_ms(),
+            "top": top,
+            "lazy_candidates": suggestion,
+            "lazy_import_command": command,
+            "tree": profile.imports,
+        }),
+    )
+    .with_table(crate::table::TableSpec::new(
+        "top",
+        &["module", "self_ms", "cumulative_ms", "imported_by"],
+    )))
+}
+
+/// `pybun profile run`: run the script under the chosen profiler, keep the
+/// profile file, and report the hottest functions.
+pub(super) async fn run_profile_run(
+    args: &ProfileRunArgs,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    use crate::runtime_profile::{self, CPROFILE_DRIVER};
+    use crate::tool_env::{ToolEnvCache, ToolEnvKey};
+    use crate::units::{format_bytes, format_millis};
+
+    if !args.script.is_file() {
+        return Err(eyre!("script not found: {}", args.script.display()));
+    }
+    let cwd = std::env::current_dir()?;
+    let env = crate::env::find_python_env(&cwd)?;
+    let python = env.python_path;
+    let (format, extension) = match args.sampler {
+        ProfileSampler::PySpy => ("speedscope", "speedscope.json"),
+        ProfileSampler::CProfile => ("pstats", "prof"),
+    };
+    let artifact = match &args.output {
+        Some(path) => path.clone(),
+        None => {
+            let stem = args
+                .script
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "script".to_string());
+            cwd.join(".pybun")
+                .join("profiles")
+                .join(format!("{stem}.{extension}"))
+        }
+    };
+    if let Some(parent) = artifact.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    // A profile left over from an earlier run must not pass for this one.
+    let _ = std::fs::remove_file(&artifact);
+
+    let (output, profile) = match args.sampler {
+        ProfileSampler::PySpy => {
+            if args.memory {
+                collector.diagnostic(
+                    Diagnostic::warning(
+                        "--memory needs --sampler cProfile; py-spy does not trace allocations",
+                    )
+                    .with_code("W_PROFILE_MEMORY_UNSUPPORTED")
+                    .with_suggestion(
+                        "Re-run with `--sampler cProfile --memory` for a memory report.",
+                    ),
+                );
+            }
+            let py_spy = match crate::env::which_executable("py-spy") {
+                Some(path) => path,
+                None => {
+                    // Installed once into a cached tool environment, as
+                    // `pybun x py-spy` would.
+                    let version = env.version.unwrap_or_else(|| "unknown".to_string());
+                    let cache = ToolEnvCache::new()?;
+                    let key = ToolEnvKey::new("py-spy", "py-spy", &version);
+                    let venv = cache.venv_path(&key);
+                    super::cached_tool_env(&cache, &key, &python, &version, "py-spy", collector)
+                        .await?;
+                    super::tool_executable(&venv, "py-spy")
+                        .ok_or_else(|| eyre!("py-spy is missing from {}", venv.display()))?
+                }
+            };
+            let output = std::process::Command::new(&py_spy)
+                .args(["record", "--format", "speedscope", "--rate"])
+                .arg(args.rate.to_string())
+                .arg("--output")
+                .arg(&artifact)
+                .arg("--")
+                .arg(&python)
+                .arg(&args.script)
+                .args(&args.passthrough)
+                .output()
+                .map_err(|e| eyre!("failed to run {}: {}", py_spy.display(), e))?;
+            let content = std::fs::read_to_string(&artifact).map_err(|_| {
+                eyre!(
+                    "py-spy wrote no profile to {}: {}",
+                    artifact.display(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )
+            })?;
+            let profile =
+                runtime_profile::from_speedscope(&content, args.rate).map_err(|e| eyre!(e))?;
+            (output, profile)
+        }
+        ProfileSampler::CProfile => {
+            let report = tempfile::NamedTempFile::new()?;
+            let output = std::process::Command::new(&python)
+                .arg("-c")
+                .arg(CPROFILE_DRIVER)
+                .arg(&artifact)
+                .arg(report.path())
+                .arg(if args.memory { "1" } else { "0" })
+                .arg(&args.script)
+                .args(&args.passthrough)
+                .output()
+                .map_err(|e| eyre!("failed to run {}: {}", python.display(), e))?;
+            let content = std::fs::read_to_string(report.path())?;
+            if content.is_empty() {
+                return Err(eyre!(
+                    "cProfile wrote no report: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            let profile = runtime_profile::from_cprofile_report(&content).map_err(|e| eyre!(e))?;
+            (output, profile)
+        }
+    };
+
+    let exit_code = output.status.code().unwrap_or(-1);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        collector.diagnostic(
+            Diagnostic::warning(format!(
+                "{} exited with status {exit_code}; the profile covers the run until it stopped{}",
+                args.script.display(),
+                stderr
+                    .lines()
+                    .last()
+                    .map(|line| format!(": {}", line.trim()))
+                    .unwrap_or_default()
+            ))
+            .with_code("W_PROFILE_SCRIPT_FAILED")
+            .with_suggestion("Fix the error, or pass the arguments the script needs after `--`."),
+        );
+    }
+
+    let hot = profile.hot(args.top);
+    let mut text = format!(
+        "{}: profiled {} with {} ({} functions)\nProfile: {} ({format})",
+        args.script.display(),
+        format_millis(profile.duration_ms),
+        args.sampler.as_str(),
+        profile.functions.len(),
+        artifact.display()
+    );
+    if !hot.is_empty() {
+        text.push_str("\n\nHottest functions (own time, total):");
+        for function in hot {
+            text.push_str(&format!(
+                "\n  {:>9}  {:>9}  {}",
+                format_millis(function.self_ms),
+                format_millis(function.total_ms),
+                function.function
+            ));
+            if let Some(file) = &function.file {
+                text.push_str(&format!(" ({file}:{})", function.line.unwrap_or_default()));
+            }
+        }
+    }
+    if let Some(memory) = &profile.memory {
+        text.push_str(&format!(
+            "\n\nPeak traced memory: {}",
+            format_bytes(memory.peak_bytes)
+        ));
+        for site in &memory.retained {
+            text.push_str(&format!(
+                "\n  {:>9}  {}:{} ({} blocks)",
+                format_bytes(site.size_bytes),
+                site.file,
+                site.line,
+                site.count
+            ));
+        }
+    }
+
+    Ok(RenderDetail::with_json(
+        text,
+        json!({
+            "script": args.script.display().to_string(),
+            "sampler": args.sampler.as_str(),
+            "python": python.display().to_string(),
+            "exit_code": exit_code,
+            "artifact": {
+                "path": artifact.display().to_string(),
+                "format": format,
+            },
+            "duration_ms": profile.duration_ms,
+            "functions": profile.functions.len(),
+            "hot": hot,
+            "memory": profile.memory,
+        }),
+    )
+    .with_table(crate::table::TableSpec::new(
+        "hot",
+        &["function", "self_ms", "total_ms", "calls", "file", "line"],
+    )))
+}
+
+// ---------------------------------------------------------------------------
+// pybun hook (shell activation hook)
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_hook(cmd: &HookCommands, collector: &mut EventCollector) -> Result<RenderDetail> {
+    use crate::paths::PyBunPaths;
+    use crate::shell_hook::{self, HookManager, Shell};
+
+    let paths = PyBunPaths::new().map_err(|e| eyre!("failed to get config path: {}", e))?;
+    let manager = HookManager::new(paths.root());
+
+    match cmd {
+        HookCommands::Install(args) => {
+            let exe = std::env::current_exe()?;
+            if args.print {
+                let script = shell_hook::hook_script(args.shell, &exe);
+                return Ok(RenderDetail::with_json_raw_text(
+                    script.clone(),
+                    json!({ "shell": args.shell.as_str(), "script": script }),
+                ));
+            }
+            let rc_file = match &args.rc_file {
+                Some(path) => path.clone(),
+                None => shell_hook::default_rc_file(args.shell)?,
+            };
+            let changed = shell_hook::install(args.shell, &exe, &rc_file)?;
+            let summary = if changed {
+                format!(
+                    "installed {} hook in {} (open a new shell to activate)",
+                    args.shell.as_str(),
+                    rc_file.display()
+                )
+            } else {
+                format!(
+                    "{} hook already installed in {}",
+                    args.shell.as_str(),
+                    rc_file.display()
+                )
+            };
+            collector.info(summary.clone());
+            Ok(RenderDetail::with_json(
+                summary,
+                json!({
+                    "shell": args.shell.as_str(),
+                    "rc_file": rc_file.display().to_string(),
+                    "changed": changed,
+                }),
+            ))
+        }
+        HookCommands::Export(args) => {
+            let cwd = std::env::current_dir()?;
+            let state = shell_hook::HookState::from_env();
+            let (enabled, _) = manager.enabled();
+            let diff = shell_hook::export(&cwd, &state, enabled, &manager.allowed());
+            Ok(RenderDetail::with_json_raw_text(
+                shell_hook::render_diff(args.shell, &diff),
+                json!({
+                    "set": diff.set.iter().map(|(k, v)| (k.clone(), Value::String(v.clone()))).collect::<serde_json::Map<_, _>>(),
+                    "unset": diff.unset,
+                    "messages": diff.messages,
+                }),
+            ))
+        }
+        HookCommands::Status(_) => {
+            let (enabled, source) = manager.enabled();
+            let home = dirs::home_dir();
+            let installed: Vec<Value> = Shell::ALL
+                .iter()
+                .filter_map(|shell| {
+                    let rc_file = shell.rc_file(home.as_deref()?);
+                    Some(json!({
+                        "shell": shell.as_str(),
+                        "rc_file": rc_file.display().to_string(),
+                        "installed": shell_hook::is_installed(&rc_file),
+                    }))
+                })
+                .collect();
+            let cwd = std::env::current_dir()?;
+            let project = shell_hook::find_project_env(&cwd).map(|project| project.root);
+            let allowed = project
+                .as_deref()
+                .is_some_and(|root| manager.is_allowed(root));
+            let active = std::env::var("PYBUN_HOOK_ACTIVE").ok();
+            let installed_shells: Vec<&str> = installed
+                .iter()
+                .filter(|v| v["installed"] == true)
+                .filter_map(|v| v["shell"].as_str())
+                .collect();
+            let summary = format!(
+                "hook {} ({}); installed for: {}; project here: {}{}; active: {}",
+                if enabled { "enabled" } else { "disabled" },
+                source,
+                if installed_shells.is_empty() {
+                    "none".to_string()
+                } else {
+                    installed_shells.join(", ")
+                },
+                project
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+                match (&project, allowed) {
+                    (Some(_), false) => " (not allowed; run `pybun hook allow`)",
+                    _ => "",
+                },
+                active.as_deref().unwrap_or("none"),
+            );
+            Ok(RenderDetail::with_json(
+                summary,
+                json!({
+                    "enabled": enabled,
+                    "source": source.to_string(),
+                    "shells": installed,
+                    "project": project.map(|p| p.display().to_string()),
+                    "allowed": allowed,
+                    "active": active,
+                }),
+            ))
+        }
+        HookCommands::Enable(_) | HookCommands::Disable(_) => {
+            let enabled = matches!(cmd, HookCommands::Enable(_));
+            manager.set_enabled(enabled)?;
+            let summary = if enabled {
+                "Shell hook enabled".to_string()
+            } else {
+                "Shell hook disabled".to_string()
+            };
+            Ok(RenderDetail::with_json(
+                summary,
+                json!({ "enabled": enabled, "source": "config" }),
+            ))
+        }
+        HookCommands::Allow(args) | HookCommands::Deny(args) => {
+            let allow = matches!(cmd, HookCommands::Allow(_));
+            let start = match &args.path {
+                Some(path) => path.clone(),
+                None => std::env::current_dir()?,
+            };
+            let root = shell_hook::find_project_root(&start)
+                .ok_or_else(|| eyre!("no pyproject.toml found in {} or above", start.display()))?;
+            let changed = manager.set_allowed(&root, allow)?;
+            let summary = match (allow, changed) {
+                (true, true) => format!("Allowed the hook to activate {}", root.display()),
+                (true, false) => format!("{} is already allowed", root.display()),
+                (false, true) => format!("Denied the hook for {}", root.display()),
+                (false, false) => format!("{} was not allowed", root.display()),
+            };
+            Ok(RenderDetail::with_json(
+                summary,
+                json!({
+                    "project": root.display().to_string(),
+                    "allowed": allow,
+                    "changed": changed,
+                }),
+            ))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// pybun auth (stored index/publish credentials)
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_auth(cmd: &AuthCommands, collector: &mut EventCollector) -> Result<RenderDetail> {
+    use crate::auth::{AuthStore, TOKEN_USERNAME};
+    use crate::cli::AuthTokenCommands;
+
+    let store = AuthStore::new()?;
+    match cmd {
+        AuthCommands::Login(args) => {
+            let username = match &args.username {
+                Some(username) => username.clone(),
+                None if std::io::stdin().is_terminal() && !args.password_stdin => {
+                    dialoguer::Input::<String>::new()
+                        .with_prompt("Username")
+                        .interact_text()?
+                }
+                None => return Err(eyre!("--username is required when not prompting")),
+            };
+            let secret = read_secret("Password", args.password_stdin, "--password-stdin")?;
+            let (record, note) = store.login(&args.url, &username, &secret, args.backend)?;
+            auth_stored(record, note, collector)
+        }
+        AuthCommands::Token(AuthTokenCommands::Set(args)) => {
+            let secret = read_secret("Token", args.token_stdin, "--token-stdin")?;
+            let (record, note) = store.login(&args.url, TOKEN_USERNAME, &secret, args.backend)?;
+            auth_stored(record, note, collector)
+        }
+        AuthCommands::Logout(args) => {
+            let record = store.logout(&args.url)?;
+            Ok(RenderDetail::with_json(
+                format!("Removed credentials for {}", record.scope),
+                json!({ "removed": record }),
+            ))
+        }
+        AuthCommands::Status(args) => {
+            let mut records = store.list()?;
+            if let Some(url) = &args.url {
+                let parsed = reqwest::Url::parse(url)
+                    .map_err(|_| crate::auth::AuthError::InvalidUrl(url.clone()))?;
+                records = store.find(&parsed)?.into_iter().collect();
+            }
+            let mut rows = Vec::new();
+            let mut text = String::new();
+            for record in records {
+                let readable = match store.secret(&record) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        collector.warning(format!(
+                            "credentials for {} are not readable: {}",
+                            record.scope, e
+                        ));
+                        false
+                    }
+                };
+                text.push_str(&format!(
+                    "\n  {}  {} ({}){}",
+                    record.scope,
+                    record.username,
+                    record.backend.as_str(),
+                    if readable { "" } else { " [unreadable]" }
+                ));
+                let mut row = serde_json::to_value(&record).unwrap_or_default();
+                row["readable"] = json!(readable);
+                rows.push(row);
+            }
+            let summary = match (&args.url, rows.is_empty()) {
+                (Some(url), true) => format!("no stored credentials apply to {}", url),
+                (None, true) => {
+                    "no stored credentials (use `pybun auth login` or `pybun auth token set`)"
+                        .to_string()
+                }
+                _ => format!("{} stored credential(s):{}", rows.len(), text),
+            };
+            Ok(RenderDetail::with_json(
+                summary,
+                json!({
+                    "credentials": rows,
+                    "index_file": store.index_path().display().to_string(),
+                    "keychain_available": crate::auth::keychain_available(),
+                }),
+            )
+            .with_table(crate::table::TableSpec::new(
+                "credentials",
+                &["scope", "username", "backend", "readable"],
+            )))
+        }
+    }
+}
+
+/// Read a secret from stdin (`from_stdin`) or an interactive prompt; never
+/// from the command line, so it stays out of shell history.
+fn read_secret(prompt: &str, from_stdin: bool, stdin_flag: &str) -> Result<String> {
+    if from_stdin {
+        let mut secret = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut secret)?;
+        return Ok(secret.trim().to_string());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(eyre!(
+            "stdin is not a terminal; pass {} and pipe the secret in",
+            stdin_flag
+        ));
+    }
+    Ok(dialoguer::Password::new().with_prompt(prompt).interact()?)
+}
+
+fn auth_stored(
+    record: crate::auth::CredentialRecord,
+    note: Option<String>,
+    collector: &mut EventCollector,
+) -> Result<RenderDetail> {
+    if let Some(note) = &note {
+        collector.warning(note.clone());
+    }
+    Ok(RenderDetail::with_json(
+        format!(
+            "Stored credentials for {} as {} ({})",
+            record.scope,
+            record.username,
+            record.backend.as_str()
+        ),
+        json!({ "credential": record, "fallback": note }),
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// pybun alias / pybun completions
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_alias_list() -> Result<RenderDetail> {
+    let cwd = std::env::current_dir()?;
+    let aliases = crate::alias::load_aliases(&cwd);
+    let builtins = crate::alias::builtin_commands();
+
+    let rows: Vec<Value> = aliases
+        .iter()
+        .map(|(name, value)| {
+            json!({
+                "name": name,
+                "expansion": value.display(),
+                "shadowed": builtins.contains(name),
+            })
+        })
+        .collect();
+
+    let summary = if rows.is_empty() {
+        "no aliases defined in [tool.pybun.alias]".to_string()
+    } else {
+        let width = aliases.keys().map(String::len).max().unwrap_or(0);
+        let mut text = format!("{} alias(es):", rows.len());
+        for (name, value) in &aliases {
+            text.push_str(&format!("\n  {name:<width$}  {}", value.display()));
+            if builtins.contains(name) {
+                text.push_str("  (ignored: shadows a built-in command)");
+            }
+        }
+        text
+    };
+
+    Ok(
+        RenderDetail::with_json(summary, json!({ "aliases": rows })).with_table(
+            crate::table::TableSpec::new("aliases", &["name", "expansion"]),
+        ),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// pybun run <task> / pybun run --list
+// ---------------------------------------------------------------------------
+
+/// Whether `pybun run TARGET` names a task: TARGET is not an existing file
+/// and `[tool.pybun.tasks]` defines it. Files win over tasks, tasks over
+/// console scripts.
+pub(super) fn is_task(args: &RunArgs) -> bool {
+    let Some(target) = args.target.as_deref() else {
+        return false;
+    };
+    if args.code.is_some() || args.module.is_some() || std::path::Path::new(target).exists() {
+        return false;
+    }
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| crate::tasks::load_tasks(&cwd))
+        .is_some_and(|(_, tasks)| tasks.contains_key(target))
+}
+
+pub(super) fn run_task_list() -> Result<RenderDetail> {
+    let cwd = std::env::current_dir()?;
+    let tasks = crate::tasks::load_tasks(&cwd)
+        .map(|(_, tasks)| tasks)
+        .unwrap_or_default();
+
+    let rows: Vec<Value> = tasks
+        .iter()
+        .map(|(name, task)| {
+            json!({
+                "name": name,
+                "command": task.cmd().map(|cmd| cmd.display()),
+                "depends": task.depends(),
+                "help": task.help(),
+            })
+        })
+        .collect();
+
+    let summary = if rows.is_empty() {
+        "no tasks defined in [tool.pybun.tasks]".to_string()
+    } else {
+        let width = tasks.keys().map(String::len).max().unwrap_or(0);
+        let mut text = format!("{} task(s):", rows.len());
+        for (name, task) in &tasks {
+            let description = task
+                .help()
+                .map(str::to_string)
+                .or_else(|| task.cmd().map(|cmd| cmd.display()))
+                .unwrap_or_default();
+            text.push_str(&format!("\n  {name:<width$}  {description}"));
+            if !task.depends().is_empty() {
+                text.push_str(&format!("  (after {})", task.depends().join(", ")));
+            }
+        }
+        text
+    };
+
+    Ok(
+        RenderDetail::with_json(summary, json!({ "tasks": rows })).with_table(
+            crate::table::TableSpec::new("tasks", &["name", "command", "help"]),
+        ),
+    )
+}
+
+/// Run the task `args.target` names after its dependencies. The first task
+/// that fails stops the run and its exit code becomes pybun's.
+pub(super) fn run_task(
+    args: &RunArgs,
+    collector: &mut EventCollector,
+    format: OutputFormat,
+) -> Result<RenderDetail> {
+    let name = args
+        .target
+        .as_deref()
+        .ok_or_else(|| eyre!("task name is required"))?;
+    if args.sandbox.enabled {
+        return Err(eyre!("--sandbox is not supported for tasks"));
+    }
+    let cwd = std::env::current_dir()?;
+    let (root, tasks) = crate::tasks::load_tasks(&cwd)
+        .ok_or_else(|| eyre!("no pyproject.toml found in {}", cwd.display()))?;
+    let order = crate::tasks::plan(&tasks, name).map_err(|e| eyre!(e))?;
+
+    let venv = std::env::var_os("PYBUN_ENV")
+        .map(std::path::PathBuf::from)
+        .or_else(|| crate::env::find_project_venv(&root));
+    match &venv {
+        Some(venv) => collector.info(format!("Running tasks in {}", venv.display())),
+        None => collector.info("No project environment found; running tasks with the current PATH"),
+    }
+
+    let capture = format.is_json();
+    let mut results: Vec<Value> = Vec::new();
+    let mut exit_code = 0;
+    for task_name in &order {
+        let task = &tasks[task_name];
+        let Some(cmd) = task.cmd() else {
+            continue;
+        };
+        let extra: &[String] = if task_name == name {
+            &args.passthrough
+        } else {
+            &[]
+        };
+        let mut command = cmd
+            .command(extra)
+            .ok_or_else(|| eyre!("task '{task_name}' has an empty command"))?;
+        command.current_dir(&root);
+        if let Some(venv) = &venv {
+            crate::tasks::apply_environment(&mut command, venv);
+        }
+        if let Some(env) = task.env() {
+            command.envs(env);
+        }
+        if !capture {
+            eprintln!("> {task_name}: {}", cmd.display());
+        }
+        collector.info(format!("Running task {task_name}: {}", cmd.display()));
+
+        let started = std::time::Instant::now();
+        let execution = crate::sandbox::execute_with_optional_sandbox(&mut command, None, capture)
+            .map_err(|e| eyre!("failed to run task '{task_name}': {e}"))?;
+        let code = execution.status.code().unwrap_or(-1);
+        results.push(json!({
+            "name": task_name,
+            "command": cmd.display(),
+            "exit_code": code,
+            "duration_ms": started.elapsed().as_millis() as u64,
+            "stdout": execution.stdout.and_then(super::capture_stdio),
+            "stderr": execution.stderr.and_then(super::capture_stdio),
+        }));
+        if !execution.status.success() {
+            exit_code = code;
+            collector.error_with_code(
+                "E_TASK_FAILED",
+                format!("task '{task_name}' exited with code {code}"),
+                format!(
+                    "Fix the failure reported by `{}`, then re-run `pybun run {name}`.",
+                    cmd.display()
+                ),
+            );
+            break;
+        }
+    }
+
+    let summary = if exit_code == 0 {
+        format!("task {name} finished ({} command(s))", results.len())
+    } else {
+        format!("task {name} failed with exit code {exit_code}")
+    };
+    Ok(RenderDetail::with_json(
+        summary,
+        json!({
+            "task": name,
+            "exit_code": exit_code,
+            "environment": venv.map(|path| path.display().to_string()),
+            "tasks": results,
+        }),
+    )
+    .with_process_exit_code(exit_code))
+}
+
+// ---------------------------------------------------------------------------
+// pybun config validate
+// ---------------------------------------------------------------------------
+
+pub(super) fn run_config_validate(collector: &mut EventCollector) -> Result<RenderDetail> {
+    let cwd = std::env::current_dir()?;
+    let root = crate::project::Project::discover(&cwd)
+        .ok()
+        .and_then(|project| project.path().parent().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| cwd.clone());
+    let reports = crate::config_schema::validate_project(&root)?;
+    if reports.is_empty() {
+        return Err(eyre!(
+            "no pyproject.toml or {} found in {}",
+            crate::project::PYBUN_TOML,
+            root.display()
+        ));
+    }
+
+    let issues: Vec<&crate::config_schema::ConfigIssue> =
+        reports.iter().flat_map(|r| &r.issues).collect();
+    let mut lines = Vec::new();
+    for issue in &issues {
+        let file = issue
+            .file
+            .as_deref()
+            .map(|f| super::change_label(f, &cwd))
+            .unwrap_or_default();
+        let mut diagnostic = crate::schema::Diagnostic::error(issue.message())
+            .with_code("E_CONFIG_INVALID")
+            .with_file(file.clone())
+            .with_context(json!({ "issue": issue }));
+        if let Some(line) = issue.line {
+            diagnostic = diagnostic.with_line(line as u32);
+        }
+        if let Some(suggestion) = issue.suggestion() {
+            diagnostic = diagnostic.with_suggestion(suggestion);
+        }
+        collector.diagnostic(diagnostic);
+
+        let mut text = format!(
+            "  {file}:{}:{}: {}",
+            issue.line.unwrap_or(0),
+            issue.column.unwrap_or(0),
+            issue.message()
+        );
+        if let Some(suggestion) = issue.suggestion() {
+            text.push_str(&format!(" ({suggestion})"));
+        }
+        lines.push(text);
+    }
+
+    let files: Vec<String> = reports
+        .iter()
+        .map(|r| super::change_label(&r.file, &cwd))
+        .collect();
+    let json = json!({ "files": files, "issues": issues });
+    if issues.is_empty() {
+        Ok(RenderDetail::with_json(
+            format!("config OK ({})", files.join(", ")),
+            json,
+        ))
+    } else {
+        Ok(RenderDetail::error(
+            format!("{} config issue(s):\n{}", issues.len(), lines.join("\n")),
+            json,
+        ))
+    }
+}
+
+pub(super) fn run_completions(args: &CompletionsArgs) -> Result<RenderDetail> {
+    use clap::CommandFactory;
+
+    let cwd = std::env::current_dir()?;
+    let builtins = crate::alias::builtin_commands();
+    let mut command = crate::cli::Cli::command();
+    for (name, value) in crate::alias::load_aliases(&cwd) {
+        if builtins.contains(&name) {
+            continue;
+        }
+        command = command
+            .subcommand(clap::Command::new(name).about(format!("Alias for `{}`", value.display())));
+    }
+
+    let mut script = Vec::new();
+    clap_complete::generate(args.shell, &mut command, "pybun", &mut script);
+    let script = String::from_utf8_lossy(&script).into_owned();
+    Ok(RenderDetail::with_json_raw_text(
+        script.trim_end().to_string(),
+        json!({ "shell": args.shell.to_string(), "script": script }),
+    ))
+}
@@ -0,0 +1,666 @@
+//! Schema validation for `[tool.pybun]` and `pybun.toml`.
+//!
+//! The typed config structs ([`crate::project::PybunConfig`] and the tables it
+//! embeds) default every field, so a typo like `lazy_import = [...]` used to
+//! be ignored without a word, and one wrongly typed value dropped the whole
+//! table. [`PYBUN_SCHEMA`] mirrors those structs; [`validate`] walks a config
+//! against it and reports:
+//!
+//! - unknown keys, with the nearest known key as a suggestion;
+//! - values of the wrong type, with the expected shape.
+//!
+//! [`sanitize`] removes the reported entries so the rest of the config still
+//! deserializes, and [`validate_project`] attaches file, line and column to
+//! each issue for `pybun config validate`.
+
+use crate::project::PYBUN_TOML;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use toml::Value;
+use toml::de::{DeTable, DeValue};
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, ConfigError>;
+
+/// Expected shape of a config value.
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    String,
+    Bool,
+    Integer,
+    /// Any TOML value.
+    Any,
+    /// One of a fixed set of strings.
+    Enum(&'static [&'static str]),
+    Array(&'static Shape),
+    /// Table with a fixed set of keys.
+    Table(&'static [(&'static str, Shape)]),
+    /// Table with arbitrary keys whose values all have the same shape.
+    Map(&'static Shape),
+    /// Either of two shapes (untagged enums).
+    Either(&'static Shape, &'static Shape),
+}
+
+const STRINGS: Shape = Shape::Array(&Shape::String);
+
+/// A task command: a shell command line or an argument vector.
+const TASK_COMMAND: Shape = Shape::Either(&Shape::String, &STRINGS);
+
+/// A `[tool.pybun.tasks]` entry: a bare command or a table.
+const TASK: Shape = Shape::Either(
+    &TASK_COMMAND,
+    &Shape::Table(&[
+        ("cmd", TASK_COMMAND),
+        ("depends", STRINGS),
+        ("help", Shape::String),
+        ("env", Shape::Map(&Shape::String)),
+    ]),
+);
+
+/// Schema of `[tool.pybun]` (and of a `pybun.toml` document).
+pub const PYBUN_SCHEMA: Shape = Shape::Table(&[
+    ("python", Shape::String),
+    ("lazy_imports", STRINGS),
+    (
+        "profiles",
+        Shape::Map(&Shape::Table(&[
+            ("hot_reload", Shape::Bool),
+            ("lazy_import", Shape::Bool),
+            ("log_level", Shape::String),
+        ])),
+    ),
+    (
+        "seed",
+        Shape::Table(&[
+            ("enabled", Shape::Bool),
+            ("pip", Shape::String),
+            ("setuptools", Shape::String),
+            ("wheel", Shape::String),
+        ]),
+    ),
+    (
+        "build",
+        Shape::Table(&[
+            ("isolation", Shape::Enum(&["venv", "container"])),
+            ("container_runtime", Shape::String),
+            ("container_image", Shape::String),
+            ("container_packages", STRINGS),
+            ("env", Shape::Map(&Shape::String)),
+        ]),
+    ),
+    (
+        "alias",
+        Shape::Map(&Shape::Either(&Shape::String, &STRINGS)),
+    ),
+    (
+        "network",
+        Shape::Table(&[
+            ("index", STRINGS),
+            ("self-update", STRINGS),
+            ("python", STRINGS),
+            ("audit", STRINGS),
+            ("run", STRINGS),
+            ("test", STRINGS),
+            ("cache", STRINGS),
+            ("publish", STRINGS),
+        ]),
+    ),
+    (
+        "test",
+        Shape::Table(&[
+            ("env", Shape::Map(&Shape::String)),
+            ("fixtures", Shape::Map(&Shape::Any)),
+            (
+                "plugins",
+                Shape::Map(&Shape::Table(&[
+                    ("file-patterns", STRINGS),
+                    ("item-pattern", Shape::String),
+                    ("command", STRINGS),
+                ])),
+            ),
+            ("changed", Shape::Bool),
+        ]),
+    ),
+    (
+        "http",
+        Shape::Table(&[
+            ("ca-bundle", Shape::String),
+            ("system-certs", Shape::Bool),
+            ("proxy", Shape::String),
+            ("no-proxy", STRINGS),
+        ]),
+    ),
+    (
+        "cache",
+        Shape::Table(&[(
+            "remote",
+            Shape::Table(&[
+                ("url", Shape::String),
+                ("endpoint", Shape::String),
+                ("region", Shape::String),
+                ("push", Shape::Bool),
+                ("allow-http", Shape::Bool),
+            ]),
+        )]),
+    ),
+    ("tasks", Shape::Map(&TASK)),
+    ("arch", Shape::Map(&Shape::String)),
+    ("encoding", Shape::Enum(&["utf8", "locale"])),
+    ("workspace", Shape::Table(&[("members", STRINGS)])),
+    (
+        "mcp",
+        Shape::Table(&[(
+            "audit",
+            Shape::Table(&[
+                ("enabled", Shape::Bool),
+                ("path", Shape::String),
+                ("hash_inputs", Shape::Bool),
+                ("retention_days", Shape::Integer),
+            ]),
+        )]),
+    ),
+]);
+
+impl Shape {
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Shape::Any, _)
+            | (Shape::String, Value::String(_))
+            | (Shape::Bool, Value::Boolean(_))
+            | (Shape::Integer, Value::Integer(_))
+            | (Shape::Array(_), Value::Array(_))
+            | (Shape::Table(_) | Shape::Map(_), Value::Table(_)) => true,
+            (Shape::Enum(values), Value::String(s)) => values.contains(&s.as_str()),
+            (Shape::Either(a, b), value) => a.matches(value) || b.matches(value),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::String => f.write_str("a string"),
+            Shape::Bool => f.write_str("a boolean"),
+            Shape::Integer => f.write_str("an integer"),
+            Shape::Any => f.write_str("any value"),
+            Shape::Enum(values) => {
+                let quoted: Vec<String> = values.iter().map(|v| format!("\"{v}\"")).collect();
+                write!(f, "one of {}", quoted.join(", "))
+            }
+            Shape::Array(item) => write!(f, "an array of {}", plural(item)),
+            Shape::Table(fields) => {
+                let keys: Vec<&str> = fields.iter().map(|(key, _)| *key).collect();
+                write!(f, "a table with keys {}", keys.join(", "))
+            }
+            Shape::Map(value) => write!(f, "a table of {}", plural(value)),
+            Shape::Either(a, b) => write!(f, "{a} or {b}"),
+        }
+    }
+}
+
+fn plural(shape: &Shape) -> String {
+    match shape {
+        Shape::String => "strings".into(),
+        Shape::Bool => "booleans".into(),
+        Shape::Integer => "integers".into(),
+        Shape::Table(_) => "tables".into(),
+        other => format!("({other})"),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Integer(_) => "integer",
+        Value::Float(_) => "float",
+        Value::Boolean(_) => "boolean",
+        Value::Datetime(_) => "datetime",
+        Value::Array(_) => "array",
+        Value::Table(_) => "table",
+    }
+}
+
+/// One step of the path to a value.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum IssueKind {
+    UnknownKey {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        suggestion: Option<String>,
+    },
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+}
+
+/// One problem in a config document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigIssue {
+    /// Dotted path as written in the file, e.g. `tool.pybun.build.isolation`.
+    pub key: String,
+    #[serde(flatten)]
+    pub kind: IssueKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// 1-based line of the offending key (unknown keys) or value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(skip)]
+    pub path: Vec<Segment>,
+}
+
+impl ConfigIssue {
+    fn new(path: Vec<Segment>, kind: IssueKind) -> Self {
+        Self {
+            key: dotted(&path),
+            kind,
+            file: None,
+            line: None,
+            column: None,
+            path,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match &self.kind {
+            IssueKind::UnknownKey { .. } => format!("unknown key `{}`", self.key),
+            IssueKind::TypeMismatch { expected, found } => {
+                format!("`{}` must be {expected}, found {found}", self.key)
+            }
+        }
+    }
+
+    /// `did you mean ...?` hint for unknown keys.
+    pub fn suggestion(&self) -> Option<String> {
+        match &self.kind {
+            IssueKind::UnknownKey {
+                suggestion: Some(key),
+            } => Some(format!("did you mean `{key}`?")),
+            _ => None,
+        }
+    }
+}
+
+fn dotted(path: &[Segment]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !out.is_empty() {
+                    out.push('.');
+                }
+                if !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    out.push_str(key);
+                } else {
+                    out.push_str(&format!("\"{key}\""));
+                }
+            }
+            Segment::Index(idx) => out.push_str(&format!("[{idx}]")),
+        }
+    }
+    out
+}
+
+/// Check `config` against [`PYBUN_SCHEMA`]. Issue paths are relative to
+/// `config`.
+pub fn validate(config: &Value) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    check(&PYBUN_SCHEMA, config, &mut Vec::new(), &mut issues);
+    issues
+}
+
+fn check(shape: &Shape, value: &Value, path: &mut Vec<Segment>, issues: &mut Vec<ConfigIssue>) {
+    if !shape.matches(value) {
+        issues.push(ConfigIssue::new(
+            path.clone(),
+            IssueKind::TypeMismatch {
+                expected: shape.to_string(),
+                found: match value {
+                    Value::String(s) if matches!(shape, Shape::Enum(_)) => format!("\"{s}\""),
+                    other => type_name(other).to_string(),
+                },
+            },
+        ));
+        return;
+    }
+    match (shape, value) {
+        (Shape::Array(item), Value::Array(values)) => {
+            for (idx, value) in values.iter().enumerate() {
+                path.push(Segment::Index(idx));
+                check(item, value, path, issues);
+                path.pop();
+            }
+        }
+        (Shape::Table(fields), Value::Table(table)) => {
+            for (key, value) in table {
+                path.push(Segment::Key(key.clone()));
+                match fields.iter().find(|(name, _)| name == key) {
+                    Some((_, field)) => check(field, value, path, issues),
+                    None => issues.push(ConfigIssue::new(
+                        path.clone(),
+                        IssueKind::UnknownKey {
+                            suggestion: nearest(key, fields),
+                        },
+                    )),
+                }
+                path.pop();
+            }
+        }
+        (Shape::Map(item), Value::Table(table)) => {
+            for (key, value) in table {
+                path.push(Segment::Key(key.clone()));
+                check(item, value, path, issues);
+                path.pop();
+            }
+        }
+        // Descend into the alternative the value matched.
+        (Shape::Either(a, b), value) => {
+            let side = if a.matches(value) { a } else { b };
+            check(side, value, path, issues);
+        }
+        _ => {}
+    }
+}
+
+/// Closest known key within a third of `key`'s length in edits.
+fn nearest(key: &str, fields: &[(&'static str, Shape)]) -> Option<String> {
+    let wanted = key.to_lowercase();
+    let limit = (wanted.chars().count() / 3).max(1);
+    fields
+        .iter()
+        .map(|(name, _)| (crate::module_finder::edit_distance(&wanted, name), *name))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, name)| name.to_string())
+}
+
+/// `config` without the entries [`validate`] reports, so it deserializes
+/// into [`crate::project::PybunConfig`] with everything valid kept.
+pub fn sanitize(mut config: Value) -> Value {
+    let mut paths: Vec<Vec<Segment>> = validate(&config).into_iter().map(|i| i.path).collect();
+    // Later array indices first, so earlier removals don't shift them.
+    paths.sort();
+    for path in paths.iter().rev() {
+        if path.is_empty() {
+            return Value::Table(Default::default());
+        }
+        remove(&mut config, path);
+    }
+    config
+}
+
+fn remove(value: &mut Value, path: &[Segment]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = value;
+    for segment in parents {
+        let next = match (segment, current) {
+            (Segment::Key(key), Value::Table(table)) => table.get_mut(key),
+            (Segment::Index(idx), Value::Array(values)) => values.get_mut(*idx),
+            _ => None,
+        };
+        let Some(next) = next else {
+            return;
+        };
+        current = next;
+    }
+    match (last, current) {
+        (Segment::Key(key), Value::Table(table)) => {
+            table.remove(key);
+        }
+        (Segment::Index(idx), Value::Array(values)) if *idx < values.len() => {
+            values.remove(*idx);
+        }
+        _ => {}
+    }
+}
+
+/// Issues in one file, located by line and column.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReport {
+    pub file: PathBuf,
+    pub issues: Vec<ConfigIssue>,
+}
+
+/// Validate the PyBun config of the project at `root`: `[tool.pybun]` in
+/// `pyproject.toml` and the whole of `pybun.toml`, whichever exist.
+pub fn validate_project(root: &Path) -> Result<Vec<FileReport>> {
+    let mut reports = Vec::new();
+    for (name, prefix) in [
+        ("pyproject.toml", &["tool", "pybun"][..]),
+        (PYBUN_TOML, &[][..]),
+    ] {
+        let path = root.join(name);
+        if path.is_file() {
+            reports.push(validate_file(&path, prefix)?);
+        }
+    }
+    Ok(reports)
+}
+
+/// Validate the table at `prefix` (e.g. `["tool", "pybun"]`) of the TOML file
+/// at `path`. A missing table has no issues.
+pub fn validate_file(path: &Path, prefix: &[&str]) -> Result<FileReport> {
+    let source = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_err = |source| ConfigError::Parse {
+        path: path.to_path_buf(),
+        source,
+    };
+    let document: Value = toml::from_str(&source).map_err(parse_err)?;
+    let spanned = DeTable::parse(&source).map_err(parse_err)?;
+
+    let mut issues = Vec::new();
+    if let Some(config) = prefix.iter().try_fold(&document, |v, key| v.get(key)) {
+        let prefix: Vec<Segment> = prefix.iter().map(|k| Segment::Key(k.to_string())).collect();
+        for mut issue in validate(config) {
+            issue.path = prefix.iter().cloned().chain(issue.path).collect();
+            issue.key = dotted(&issue.path);
+            let key_span = matches!(issue.kind, IssueKind::UnknownKey { .. });
+            if let Some(offset) = locate(spanned.get_ref(), &issue.path, key_span) {
+                let (line, column) = line_column(&source, offset);
+                issue.line = Some(line);
+                issue.column = Some(column);
+            }
+            issue.file = Some(path.to_path_buf());
+            issues.push(issue);
+        }
+    }
+    Ok(FileReport {
+        file: path.to_path_buf(),
+        issues,
+    })
+}
+
+/// Byte offset of the value at `path`, or of its key when `key_span`.
+fn locate(table: &DeTable<'_>, path: &[Segment], key_span: bool) -> Option<usize> {
+    let (Segment::Key(first), rest) = path.split_first()? else {
+        return None;
+    };
+    let (key, mut value) = table.iter().find(|(k, _)| k.get_ref() == first)?;
+    let mut offset = if key_span && rest.is_empty() {
+        key.span().start
+    } else {
+        value.span().start
+    };
+    for (idx, segment) in rest.iter().enumerate() {
+        let last = idx + 1 == rest.len();
+        match (segment, value.get_ref()) {
+            (Segment::Key(name), DeValue::Table(table)) => {
+                let (key, next) = table.iter().find(|(k, _)| k.get_ref() == name)?;
+                offset = if key_span && last {
+                    key.span().start
+                } else {
+                    next.span().start
+                };
+                value = next;
+            }
+            (Segment::Index(i), DeValue::Array(values)) => {
+                value = values.get(*i)?;
+                offset = value.span().start;
+            }
+            _ => return None,
+        }
+    }
+    Some(offset)
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map(|s| s.chars().count())
+        .unwrap_or(0)
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn config(text: &str) -> Value {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn reports_unknown_keys_with_suggestions_and_type_mismatches() {
+        let issues = validate(&config(
+            "lazy_import = [\"numpy\"]\nunrelated = 1\n\
+             [build]\nisolation = \"docker\"\ncontainer_packages = [\"a\", 2]\n\
+             [alias]\nfmt = [\"ruff\", \"format\"]\nbad = 3\n",
+        ));
+        let summary: Vec<(String, IssueKind)> =
+            issues.into_iter().map(|i| (i.key, i.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "alias.bad".into(),
+                    IssueKind::TypeMismatch {
+                        expected: "a string or an array of strings".into(),
+                        found: "integer".into(),
+                    }
+                ),
+                (
+                    "build.container_packages[1]".into(),
+                    IssueKind::TypeMismatch {
+                        expected: "a string".into(),
+                        found: "integer".into(),
+                    }
+                ),
+                (
+                    "build.isolation".into(),
+                    IssueKind::TypeMismatch {
+                        expected: "one of \"venv\", \"container\"".into(),
+                        found: "\"docker\"".into(),
+                    }
+                ),
+                (
+                    "lazy_import".into(),
+                    IssueKind::UnknownKey {
+                        suggestion: Some("lazy_imports".into())
+                    }
+                ),
+                (
+                    "unrelated".into(),
+                    IssueKind::UnknownKey { suggestion: None }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn sanitize_keeps_every_valid_entry() {
+        let cleaned = sanitize(config(
+            "python = \"3.12\"\nlazy_imports = [1, \"a\", 2, \"b\"]\n\
+             [seed]\nenabled = \"yes\"\npip = \"24.0\"\n",
+        ));
+        assert!(validate(&cleaned).is_empty());
+        let parsed: crate::project::PybunConfig = cleaned.try_into().unwrap();
+        assert_eq!(parsed.python.as_deref(), Some("3.12"));
+        assert_eq!(parsed.lazy_imports, vec!["a", "b"]);
+        assert_eq!(parsed.seed.pip.as_deref(), Some("24.0"));
+        assert_eq!(parsed.seed.enabled, None);
+    }
+
+    #[test]
+    fn task_tables_are_checked_field_by_field() {
+        let cleaned = sanitize(config(
+            "[tasks]\nlint = \"ruff check .\"\nbad = 3\n\
+             test = { cmd = [\"pytest\", 1], depends = \"lint\", help = \"Tests\" }\n",
+        ));
+        assert!(validate(&cleaned).is_empty());
+        let parsed: crate::project::PybunConfig = cleaned.try_into().unwrap();
+        assert_eq!(parsed.tasks.len(), 2);
+        assert_eq!(parsed.tasks["test"].cmd().unwrap().display(), "pytest");
+        assert!(parsed.tasks["test"].depends().is_empty());
+        assert_eq!(parsed.tasks["test"].help(), Some("Tests"));
+    }
+
+    #[test]
+    fn validate_file_locates_issues() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("pyproject.toml");
+        fs::write(
+            &path,
+            "[project]\nname = \"app\"\n\n[tool.pybun]\npython = 3.12\n\n\
+             [tool.pybun.http]\n  proxi = \"http://proxy\"\n",
+        )
+        .unwrap();
+
+        let report = validate_file(&path, &["tool", "pybun"]).unwrap();
+        let located: Vec<(&str, Option<usize>, Option<usize>)> = report
+            .issues
+            .iter()
+            .map(|i| (i.key.as_str(), i.line, i.column))
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                ("tool.pybun.http.proxi", Some(8), Some(3)),
+                ("tool.pybun.python", Some(5), Some(10)),
+            ]
+        );
+        assert_eq!(
+            report.issues[0].suggestion().as_deref(),
+            Some("did you mean `proxy`?")
+        );
+    }
+}
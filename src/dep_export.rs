@@ -0,0 +1,260 @@
+//! `pybun export`: the locked packages as a pip requirements file.
+//!
+//! Every package the selected groups need is pinned with `==` (or as a
+//! direct reference for URL and git sources) and followed by a `--hash`
+//! option for each artifact the lock records, so the file works with
+//! `pip install --require-hashes --no-deps`. A package only required under
+//! an environment marker keeps the markers of the requirements that pull it
+//! in. Nothing is re-resolved.
+
+use crate::dep_graph::DependencyGraph;
+use crate::lockfile::{Lockfile, Package, PackageSource};
+use crate::pypi::normalize_project_name;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One exported package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportedPackage {
+    pub name: String,
+    pub version: String,
+    /// The requirement as written, without marker and hashes.
+    pub requirement: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+    pub hashes: Vec<String>,
+}
+
+/// A rendered requirements file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exported {
+    pub content: String,
+    pub packages: Vec<ExportedPackage>,
+    /// Packages written without hashes (git sources, or no recorded hash),
+    /// which `pip install --require-hashes` refuses.
+    pub unhashed: Vec<String>,
+    /// Packages written without any file because the lock records no
+    /// download URL for them (uv.lock only).
+    pub unlocated: Vec<String>,
+}
+
+/// Render the packages of `lock` that [`crate::lockfile::MAIN_GROUP`] and
+/// `groups` need. `declared` are the project requirements of those groups;
+/// they supply the markers of direct dependencies.
+pub fn requirements_txt(
+    lock: &Lockfile,
+    declared: &[String],
+    groups: &[&str],
+    hashes: bool,
+) -> Exported {
+    let selected: BTreeMap<&String, &Package> = lock
+        .packages
+        .iter()
+        .filter(|(_, pkg)| Lockfile::in_groups(pkg, groups))
+        .collect();
+    let markers = markers(lock, declared, &selected);
+
+    let mut content =
+        String::from("# This file was generated by `pybun export` from pybun.lockb.\n");
+    if !lock.python_versions.is_empty() {
+        content.push_str(&format!(
+            "# Locked for Python {} on {}.\n",
+            lock.python_versions.join(", "),
+            lock.platforms.join(", ")
+        ));
+    }
+
+    let mut packages = Vec::new();
+    let mut unhashed = Vec::new();
+    for (key, pkg) in selected {
+        let requirement = match &pkg.source {
+            PackageSource::Registry { .. } => format!("{}=={}", pkg.name, pkg.version),
+            PackageSource::Url { url } => format!("{} @ {}", pkg.name, url),
+            PackageSource::Git {
+                url,
+                commit,
+                subdirectory,
+                ..
+            } => {
+                let url = url.strip_prefix("git+").unwrap_or(url);
+                let subdirectory = subdirectory
+                    .as_ref()
+                    .map(|s| format!("#subdirectory={s}"))
+                    .unwrap_or_default();
+                format!("{} @ git+{url}@{commit}{subdirectory}", pkg.name)
+            }
+        };
+        let pkg_hashes = if matches!(pkg.source, PackageSource::Git { .. }) {
+            Vec::new()
+        } else {
+            artifact_hashes(pkg)
+        };
+        if hashes && pkg_hashes.is_empty() {
+            unhashed.push(pkg.name.clone());
+        }
+        let marker = markers.get(key.as_str()).cloned().flatten();
+
+        content.push_str(&requirement);
+        if let Some(marker) = &marker {
+            content.push_str(&format!(" ; {marker}"));
+        }
+        if hashes {
+            for hash in &pkg_hashes {
+                content.push_str(&format!(" \\\n    --hash={hash}"));
+            }
+        }
+        content.push('\n');
+
+        packages.push(ExportedPackage {
+            name: pkg.name.clone(),
+            version: pkg.version.clone(),
+            requirement,
+            marker,
+            hashes: if hashes { pkg_hashes } else { Vec::new() },
+        });
+    }
+
+    Exported {
+        content,
+        packages,
+        unhashed,
+        unlocated: Vec::new(),
+    }
+}
+
+/// Every `sha256:` hash recorded for `pkg`'s artifacts, sorted.
+fn artifact_hashes(pkg: &Package) -> Vec<String> {
+    let recorded = if pkg.artifacts.is_empty() {
+        vec![pkg.hash.as_str()]
+    } else {
+        pkg.artifacts.iter().map(|a| a.hash.as_str()).collect()
+    };
+    recorded
+        .into_iter()
+        .filter(|hash| hash.starts_with("sha256:"))
+        .filter(|hash| !crate::security::is_placeholder_hash(hash))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// The marker each selected package is needed under: `None` when some
+/// requirement pulls it in unconditionally, else the markers of the
+/// requirements naming it, joined with `or`.
+fn markers<'a>(
+    lock: &'a Lockfile,
+    declared: &[String],
+    selected: &BTreeMap<&'a String, &'a Package>,
+) -> BTreeMap<&'a str, Option<String>> {
+    // Not a valid package name, so it cannot collide with one.
+    const ROOT: &str = "<project>";
+    let graph = DependencyGraph::from_lock(lock, ROOT, "", declared);
+    let names: BTreeMap<String, &str> = selected
+        .keys()
+        .map(|key| (normalize_project_name(key), key.as_str()))
+        .collect();
+
+    let mut incoming: BTreeMap<&str, Option<BTreeSet<String>>> = BTreeMap::new();
+    for edge in &graph.edges {
+        let from_selected =
+            edge.from == ROOT || names.contains_key(&normalize_project_name(&edge.from));
+        let Some(&to) = names.get(&normalize_project_name(&edge.to)) else {
+            continue;
+        };
+        if !from_selected {
+            continue;
+        }
+        let entry = incoming.entry(to).or_insert_with(|| Some(BTreeSet::new()));
+        match (&edge.marker, entry.as_mut()) {
+            (Some(marker), Some(markers)) => {
+                markers.insert(marker.clone());
+            }
+            (None, _) => *entry = None,
+            (Some(_), None) => {}
+        }
+    }
+
+    incoming
+        .into_iter()
+        .map(|(name, markers)| {
+            let marker = markers.filter(|m| !m.is_empty()).map(|markers| {
+                if markers.len() == 1 {
+                    markers.into_iter().next().unwrap_or_default()
+                } else {
+                    markers
+                        .into_iter()
+                        .map(|m| format!("({m})"))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                }
+            });
+            (name, marker)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::Artifact;
+
+    fn package(name: &str, version: &str, dependencies: &[&str], hashes: &[&str]) -> Package {
+        Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            source: PackageSource::Registry {
+                index: "pypi".to_string(),
+                url: "https://pypi.org/simple".to_string(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: hashes.first().copied().unwrap_or_default().to_string(),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            build: None,
+            artifacts: hashes
+                .iter()
+                .enumerate()
+                .map(|(i, hash)| Artifact {
+                    filename: format!("{name}-{version}-{i}.whl"),
+                    url: None,
+                    hash: hash.to_string(),
+                    platforms: Vec::new(),
+                })
+                .collect(),
+            groups: vec!["main".to_string()],
+        }
+    }
+
+    #[test]
+    fn pins_hashes_and_markers() {
+        let mut lock = Lockfile::new(vec!["3.10".into()], vec!["manylinux_2_17_x86_64".into()]);
+        lock.add_package(package(
+            "httpx",
+            "0.27.0",
+            &["idna", "exceptiongroup; python_version < \"3.11\""],
+            &["sha256:bb", "sha256:aa"],
+        ));
+        lock.add_package(package("idna", "3.7", &[], &["sha256:cc"]));
+        lock.add_package(package("exceptiongroup", "1.2.0", &[], &["sha256:dd"]));
+        let mut dev = package("pytest", "8.0.0", &[], &["sha256:ee"]);
+        dev.groups = vec!["dev".to_string()];
+        lock.add_package(dev);
+
+        let exported = requirements_txt(&lock, &["httpx>=0.27".to_string()], &[], true);
+        assert_eq!(
+            exported.content,
+            "# This file was generated by `pybun export` from pybun.lockb.\n\
+             # Locked for Python 3.10 on manylinux_2_17_x86_64.\n\
+             exceptiongroup==1.2.0 ; python_version < \"3.11\" \\\n    --hash=sha256:dd\n\
+             httpx==0.27.0 \\\n    --hash=sha256:aa \\\n    --hash=sha256:bb\n\
+             idna==3.7 \\\n    --hash=sha256:cc\n"
+        );
+        assert!(exported.unhashed.is_empty());
+
+        let exported = requirements_txt(&lock, &["httpx>=0.27".to_string()], &["dev"], false);
+        let names: Vec<&str> = exported.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["exceptiongroup", "httpx", "idna", "pytest"]);
+        assert!(!exported.content.contains("--hash"));
+    }
+}
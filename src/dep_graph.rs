@@ -0,0 +1,522 @@
+//! Locked dependency graph export for external analysis.
+//!
+//! `pybun graph` builds a [`DependencyGraph`] from `pybun.lockb`, the
+//! project's declared dependencies (to tell direct from transitive), and,
+//! when a virtual environment is present, installed `.dist-info` metadata
+//! (license and on-disk size). Nothing is re-resolved: the graph is exactly
+//! what the lock records. It renders as Graphviz DOT, GraphML (for graph
+//! databases and network tools), or an SPDX 2.3 JSON document whose
+//! `DEPENDS_ON` relationships mirror the graph's edges.
+
+use crate::dist_info::{self, Distribution};
+use crate::lockfile::{Lockfile, PackageSource};
+use crate::pypi::normalize_project_name;
+use crate::resolver::Requirement;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// A locked package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub version: String,
+    /// Declared in the project's `[project].dependencies`.
+    pub direct: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Installed size from the distribution's `RECORD`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    pub source: String,
+    pub hash: String,
+}
+
+/// `from` requires `to`. `from` is the project itself for direct edges.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub requirement: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyGraph {
+    pub root: String,
+    pub root_version: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl DependencyGraph {
+    /// Graph of `lock` rooted at a project declaring `declared`.
+    pub fn from_lock(lock: &Lockfile, root: &str, root_version: &str, declared: &[String]) -> Self {
+        let keys: BTreeMap<String, &str> = lock
+            .packages
+            .values()
+            .map(|pkg| (normalize_project_name(&pkg.name), pkg.name.as_str()))
+            .collect();
+        let edge_for = |from: &str, spec: &str| -> Option<GraphEdge> {
+            let requirement = spec.parse::<Requirement>().ok()?;
+            let to = keys.get(&normalize_project_name(&requirement.name))?;
+            let text = spec.split(';').next().unwrap_or(spec).trim().to_string();
+            Some(GraphEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                requirement: text,
+                marker: requirement.marker.map(|m| m.trim().to_string()),
+            })
+        };
+
+        let mut edges: Vec<GraphEdge> = declared
+            .iter()
+            .filter_map(|spec| edge_for(root, spec))
+            .collect();
+        let direct: BTreeSet<String> = edges.iter().map(|e| e.to.clone()).collect();
+        for pkg in lock.packages.values() {
+            edges.extend(
+                pkg.dependencies
+                    .iter()
+                    .filter_map(|spec| edge_for(&pkg.name, spec)),
+            );
+        }
+
+        let nodes = lock
+            .packages
+            .values()
+            .map(|pkg| GraphNode {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                direct: direct.contains(&pkg.name),
+                license: None,
+                size_bytes: None,
+                source: match &pkg.source {
+                    PackageSource::Registry { url, .. } => url.clone(),
+                    PackageSource::Url { url } => url.clone(),
+                    PackageSource::Git { url, commit, .. } => format!("git+{url}@{commit}"),
+                },
+                hash: pkg.hash.clone(),
+            })
+            .collect();
+
+        Self {
+            root: root.to_string(),
+            root_version: root_version.to_string(),
+            nodes,
+            edges,
+        }
+    }
+
+    /// Fill in license and size from `.dist-info` directories under
+    /// `site_packages` whose version matches the lock. Returns how many
+    /// nodes were matched.
+    pub fn annotate_installed(&mut self, site_packages: &Path) -> usize {
+        let installed: BTreeMap<String, Distribution> = dist_info::installed(site_packages)
+            .into_iter()
+            .map(|dist| (normalize_project_name(&dist.name), dist))
+            .collect();
+
+        let mut matched = 0;
+        for node in &mut self.nodes {
+            let Some(dist) = installed.get(&normalize_project_name(&node.name)) else {
+                continue;
+            };
+            if dist.version != node.version {
+                continue;
+            }
+            matched += 1;
+            if let Ok(metadata) = dist.metadata() {
+                node.license = metadata.license_summary();
+            }
+            node.size_bytes = dist.size_bytes().ok();
+        }
+        matched
+    }
+
+    /// Graphviz DOT.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph dependencies {\n  rankdir=LR;\n");
+        out.push_str(&format!(
+            "  {} [label={}, shape=box];\n",
+            dot_quote(&self.root),
+            dot_quote(&format!("{} {}", self.root, self.root_version))
+        ));
+        for node in &self.nodes {
+            let mut label = format!("{} {}", node.name, node.version);
+            if let Some(license) = &node.license {
+                label.push_str(&format!("\n{license}"));
+            }
+            let style = if node.direct { ", style=bold" } else { "" };
+            out.push_str(&format!(
+                "  {} [label={}{}];\n",
+                dot_quote(&node.name),
+                dot_quote(&label),
+                style
+            ));
+        }
+        for edge in &self.edges {
+            let mut label = edge.requirement.clone();
+            if let Some(marker) = &edge.marker {
+                label.push_str(&format!("; {marker}"));
+            }
+            out.push_str(&format!(
+                "  {} -> {} [label={}];\n",
+                dot_quote(&edge.from),
+                dot_quote(&edge.to),
+                dot_quote(&label)
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// GraphML with node keys `version`, `license`, `size_bytes`, `direct`,
+    /// `source`, `hash` and edge keys `requirement`, `marker`.
+    pub fn to_graphml(&self) -> String {
+        const NODE_KEYS: [(&str, &str); 7] = [
+            ("kind", "string"),
+            ("version", "string"),
+            ("license", "string"),
+            ("size_bytes", "long"),
+            ("direct", "boolean"),
+            ("source", "string"),
+            ("hash", "string"),
+        ];
+        const EDGE_KEYS: [(&str, &str); 2] = [("requirement", "string"), ("marker", "string")];
+
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        );
+        for (key, kind) in NODE_KEYS {
+            out.push_str(&format!(
+                "  <key id=\"{key}\" for=\"node\" attr.name=\"{key}\" attr.type=\"{kind}\"/>\n"
+            ));
+        }
+        for (key, kind) in EDGE_KEYS {
+            out.push_str(&format!(
+                "  <key id=\"{key}\" for=\"edge\" attr.name=\"{key}\" attr.type=\"{kind}\"/>\n"
+            ));
+        }
+        out.push_str("  <graph id=\"dependencies\" edgedefault=\"directed\">\n");
+        out.push_str(&graphml_element(
+            "node",
+            &[("id", &self.root)],
+            &[
+                ("kind", Some("project".to_string())),
+                ("version", Some(self.root_version.clone())),
+            ],
+        ));
+        for node in &self.nodes {
+            out.push_str(&graphml_element(
+                "node",
+                &[("id", &node.name)],
+                &[
+                    ("kind", Some("package".to_string())),
+                    ("version", Some(node.version.clone())),
+                    ("license", node.license.clone()),
+                    ("size_bytes", node.size_bytes.map(|s| s.to_string())),
+                    ("direct", Some(node.direct.to_string())),
+                    ("source", Some(node.source.clone())),
+                    ("hash", Some(node.hash.clone())),
+                ],
+            ));
+        }
+        for edge in &self.edges {
+            out.push_str(&graphml_element(
+                "edge",
+                &[("source", &edge.from), ("target", &edge.to)],
+                &[
+                    ("requirement", Some(edge.requirement.clone())),
+                    ("marker", edge.marker.clone()),
+                ],
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    /// SPDX 2.3 JSON document. `created` is an RFC 3339 UTC timestamp.
+    pub fn to_spdx(&self, created: &str) -> Value {
+        let root_id = spdx_id(&self.root);
+        let mut packages = vec![json!({
+            "SPDXID": root_id,
+            "name": self.root,
+            "versionInfo": self.root_version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": "NOASSERTION",
+            "primaryPackagePurpose": "APPLICATION",
+        })];
+        for node in &self.nodes {
+            let mut package = json!({
+                "SPDXID": spdx_id(&node.name),
+                "name": node.name,
+                "versionInfo": node.version,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": "NOASSERTION",
+                "primaryPackagePurpose": "LIBRARY",
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": format!(
+                        "pkg:pypi/{}@{}",
+                        normalize_project_name(&node.name),
+                        node.version
+                    ),
+                }],
+                "comment": spdx_comment(node),
+            });
+            if let Some(license) = &node.license {
+                // Free-text licenses are not valid SPDX expressions, so the
+                // value is kept as text and only declared when it parses as
+                // one.
+                if is_spdx_expression(license) {
+                    package["licenseDeclared"] = json!(license);
+                }
+                package["licenseComments"] = json!(format!("Installed metadata: {license}"));
+            }
+            if let Some(digest) = node.hash.strip_prefix("sha256:")
+                && !crate::security::is_placeholder_hash(&node.hash)
+            {
+                package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": digest }]);
+            }
+            packages.push(package);
+        }
+
+        let mut relationships = vec![json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": root_id,
+        })];
+        for edge in &self.edges {
+            let mut relationship = json!({
+                "spdxElementId": spdx_id(&edge.from),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(&edge.to),
+            });
+            relationship["comment"] = json!(match &edge.marker {
+                Some(marker) => format!("{}; {}", edge.requirement, marker),
+                None => edge.requirement.clone(),
+            });
+            relationships.push(relationship);
+        }
+
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": format!("{}-{}-dependencies", self.root, self.root_version),
+            "documentNamespace": format!(
+                "https://spdx.org/spdxdocs/pybun/{}-{}-{}",
+                normalize_project_name(&self.root),
+                self.root_version,
+                uuid::Uuid::new_v4()
+            ),
+            "creationInfo": {
+                "created": created,
+                "creators": [format!("Tool: pybun-{}", env!("CARGO_PKG_VERSION"))],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+}
+
+fn spdx_id(name: &str) -> String {
+    spdx_ref("Package", name)
+}
+
+/// SPDX element id `SPDXRef-{kind}-{name}`, with characters SPDX ids do not
+/// allow replaced by `-`.
+pub(crate) fn spdx_ref(kind: &str, name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("SPDXRef-{kind}-{sanitized}")
+}
+
+fn spdx_comment(node: &GraphNode) -> String {
+    let mut comment = if node.direct { "direct" } else { "transitive" }.to_string();
+    if let Some(size) = node.size_bytes {
+        comment.push_str(&format!("; installed size {size} bytes"));
+    }
+    comment
+}
+
+/// Whether `license` looks like an SPDX license expression (identifiers
+/// joined by AND/OR/WITH) rather than free text such as "MIT License".
+pub(crate) fn is_spdx_expression(license: &str) -> bool {
+    license
+        .split_whitespace()
+        .map(|token| token.trim_matches(['(', ')']))
+        .enumerate()
+        .all(|(i, token)| {
+            if i % 2 == 1 {
+                matches!(token, "AND" | "OR" | "WITH")
+            } else {
+                !token.is_empty()
+                    && token
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '+' | ':'))
+            }
+        })
+}
+
+fn dot_quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn graphml_element(tag: &str, attrs: &[(&str, &str)], data: &[(&str, Option<String>)]) -> String {
+    let attrs: String = attrs
+        .iter()
+        .map(|(key, value)| format!(" {key}=\"{}\"", xml_escape(value)))
+        .collect();
+    let mut out = format!("    <{tag}{attrs}>\n");
+    for (key, value) in data {
+        if let Some(value) = value {
+            out.push_str(&format!(
+                "      <data key=\"{key}\">{}</data>\n",
+                xml_escape(value)
+            ));
+        }
+    }
+    out.push_str(&format!("    </{tag}>\n"));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::Package;
+    use std::fs;
+
+    fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+        Package {
+            name: name.into(),
+            version: version.into(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: format!("sha256:{}", "ab".repeat(32)),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn graph() -> DependencyGraph {
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        lock.add_package(package(
+            "requests",
+            "2.32.0",
+            &["urllib3>=1.21", "win-inet-pton; sys_platform == \"win32\""],
+        ));
+        lock.add_package(package("urllib3", "2.2.0", &[]));
+        DependencyGraph::from_lock(&lock, "demo", "0.1.0", &["Requests>=2.31".to_string()])
+    }
+
+    #[test]
+    fn direct_and_transitive_edges_come_from_the_lock() {
+        let graph = graph();
+        assert_eq!(
+            graph.edges,
+            vec![
+                GraphEdge {
+                    from: "demo".into(),
+                    to: "requests".into(),
+                    requirement: "Requests>=2.31".into(),
+                    marker: None,
+                },
+                GraphEdge {
+                    from: "requests".into(),
+                    to: "urllib3".into(),
+                    requirement: "urllib3>=1.21".into(),
+                    marker: None,
+                },
+            ]
+        );
+        assert!(graph.nodes[0].direct);
+        assert!(!graph.nodes[1].direct);
+    }
+
+    #[test]
+    fn installed_metadata_adds_license_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let dist_info = dir.path().join("urllib3-2.2.0.dist-info");
+        fs::create_dir_all(&dist_info).unwrap();
+        fs::write(
+            dist_info.join("METADATA"),
+            "Name: urllib3\nLicense-Expression: MIT\n",
+        )
+        .unwrap();
+        fs::write(
+            dist_info.join("RECORD"),
+            "urllib3/__init__.py,sha256=x,1200\nurllib3-2.2.0.dist-info/RECORD,,\n",
+        )
+        .unwrap();
+
+        let mut graph = graph();
+        assert_eq!(graph.annotate_installed(dir.path()), 1);
+        let urllib3 = &graph.nodes[1];
+        assert_eq!(urllib3.license.as_deref(), Some("MIT"));
+        assert_eq!(urllib3.size_bytes, Some(1200));
+    }
+
+    #[test]
+    fn spdx_relationships_mirror_edges() {
+        let mut graph = graph();
+        graph.nodes[1].license = Some("MIT License".into());
+        let doc = graph.to_spdx("2026-01-01T00:00:00Z");
+        let relationships = doc["relationships"].as_array().unwrap();
+        assert_eq!(relationships[0]["relationshipType"], "DESCRIBES");
+        assert_eq!(relationships.len(), 1 + graph.edges.len());
+        assert_eq!(
+            relationships[2]["spdxElementId"],
+            "SPDXRef-Package-requests"
+        );
+        assert_eq!(
+            relationships[2]["relatedSpdxElement"],
+            "SPDXRef-Package-urllib3"
+        );
+        assert_eq!(doc["packages"][2]["licenseDeclared"], "NOASSERTION");
+        assert_eq!(doc["packages"][1]["comment"], "direct");
+        assert!(is_spdx_expression(
+            "MIT OR (Apache-2.0 WITH LLVM-exception)"
+        ));
+    }
+
+    #[test]
+    fn graphml_escapes_attributes() {
+        let mut graph = graph();
+        graph.edges[1].marker = Some("python_version < \"3.12\"".into());
+        let xml = graph.to_graphml();
+        assert!(xml.contains("<data key=\"marker\">python_version &lt; &quot;3.12&quot;</data>"));
+        assert!(xml.contains("<edge source=\"requests\" target=\"urllib3\">"));
+    }
+}
@@ -0,0 +1,878 @@
+//! Dependency import from other tools' manifests for `pybun import`.
+//!
+//! Three formats are read and turned into PEP 508 requirement strings:
+//!
+//! - pip requirements files (`requirements.txt`, `*.in`), following
+//!   `-r` includes. Hash options are dropped (the lock records hashes) and
+//!   `-i`/`--extra-index-url` are reported as indexes;
+//! - `Pipfile`: `[packages]`, `[dev-packages]` (group `dev`) and any other
+//!   package category (a group of that name), plus `[requires]`;
+//! - Poetry's `[tool.poetry]` pyproject sections: dependencies, `dev`
+//!   dependencies and `group.<name>` tables, with `^`/`~` constraints
+//!   rewritten as PEP 440 ranges and optional dependencies assigned to the
+//!   extras that list them.
+//!
+//! Entries that cannot be expressed as a registry or URL requirement
+//! (local paths, editable installs, constraints files, `||` constraints)
+//! are returned in [`Imported::skipped`] instead of failing the import.
+
+use crate::resolver::Requirement;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use toml::Value;
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+    #[error(
+        "cannot tell what {0} is; expected a requirements file (*.txt, *.in), a Pipfile, or a pyproject.toml with [tool.poetry]"
+    )]
+    UnknownFormat(PathBuf),
+    #[error("{0} has no [tool.poetry] section to import")]
+    NotPoetry(PathBuf),
+}
+
+pub type Result<T> = std::result::Result<T, ImportError>;
+
+/// Manifest format of an imported file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SourceFormat {
+    #[serde(rename = "requirements.txt")]
+    Requirements,
+    #[serde(rename = "pipfile")]
+    Pipfile,
+    #[serde(rename = "poetry")]
+    Poetry,
+}
+
+impl SourceFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SourceFormat::Requirements => "requirements.txt",
+            SourceFormat::Pipfile => "pipfile",
+            SourceFormat::Poetry => "poetry",
+        }
+    }
+
+    /// The format of `path`, judged by its file name.
+    pub fn detect(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if name == "Pipfile" {
+            Ok(SourceFormat::Pipfile)
+        } else if name == "pyproject.toml" {
+            Ok(SourceFormat::Poetry)
+        } else if name.ends_with(".txt") || name.ends_with(".in") {
+            Ok(SourceFormat::Requirements)
+        } else {
+            Err(ImportError::UnknownFormat(path.to_path_buf()))
+        }
+    }
+}
+
+/// An entry that was not imported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Skipped {
+    pub entry: String,
+    pub reason: String,
+}
+
+/// Requirements read from a manifest, by destination.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Imported {
+    pub format: SourceFormat,
+    /// Every file read, the given one first.
+    pub files: Vec<PathBuf>,
+    /// For `[project.dependencies]`.
+    pub dependencies: Vec<String>,
+    /// PEP 735 dependency groups.
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// `[project.optional-dependencies]` extras.
+    pub extras: BTreeMap<String, Vec<String>>,
+    pub requires_python: Option<String>,
+    /// Index URLs the manifest names; PyBun takes its index from
+    /// `PYBUN_INDEX_URL` instead.
+    pub indexes: Vec<String>,
+    pub skipped: Vec<Skipped>,
+}
+
+impl Imported {
+    fn new(format: SourceFormat, path: &Path) -> Self {
+        Self {
+            format,
+            files: vec![path.to_path_buf()],
+            dependencies: Vec::new(),
+            groups: BTreeMap::new(),
+            extras: BTreeMap::new(),
+            requires_python: None,
+            indexes: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    fn skip(&mut self, entry: impl Into<String>, reason: impl Into<String>) {
+        self.skipped.push(Skipped {
+            entry: entry.into(),
+            reason: reason.into(),
+        });
+    }
+
+    /// Number of imported requirements across all destinations.
+    pub fn count(&self) -> usize {
+        self.dependencies.len()
+            + self.groups.values().map(Vec::len).sum::<usize>()
+            + self.extras.values().map(Vec::len).sum::<usize>()
+    }
+}
+
+/// Read `path` in the format its name implies.
+pub fn import(path: &Path) -> Result<Imported> {
+    match SourceFormat::detect(path)? {
+        SourceFormat::Requirements => import_requirements(path),
+        SourceFormat::Pipfile => import_pipfile(path),
+        SourceFormat::Poetry => import_poetry(path),
+    }
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|source| ImportError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn parse_toml(path: &Path) -> Result<Value> {
+    toml::from_str(&read(path)?).map_err(|e| ImportError::Parse {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// requirements.txt
+// ---------------------------------------------------------------------------
+
+/// Read a pip requirements file and the files it includes with `-r`.
+pub fn import_requirements(path: &Path) -> Result<Imported> {
+    let mut imported = Imported::new(SourceFormat::Requirements, path);
+    let mut seen = BTreeSet::new();
+    read_requirements(path, &mut imported, &mut seen)?;
+    Ok(imported)
+}
+
+fn read_requirements(
+    path: &Path,
+    imported: &mut Imported,
+    seen: &mut BTreeSet<PathBuf>,
+) -> Result<()> {
+    let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if !seen.insert(key) {
+        return Ok(());
+    }
+    let content = read(path)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    for line in logical_lines(&content) {
+        if let Some(option) = line.strip_prefix('-') {
+            let (flag, value) = split_option(option);
+            match flag {
+                "r" | "-requirement" => {
+                    let include = base.join(value);
+                    if !imported.files.contains(&include) {
+                        imported.files.push(include.clone());
+                    }
+                    read_requirements(&include, imported, seen)?;
+                }
+                "i" | "-index-url" | "-extra-index-url" => {
+                    if !imported.indexes.iter().any(|url| url == value) {
+                        imported.indexes.push(value.to_string());
+                    }
+                }
+                "c" | "-constraint" => imported.skip(&line, "constraints files are not imported"),
+                "e" | "-editable" => imported.skip(&line, "editable installs are not imported"),
+                _ => imported.skip(&line, "pip option has no PyBun equivalent"),
+            }
+            continue;
+        }
+        match requirement_line(&line) {
+            Ok(requirement) => imported.dependencies.push(requirement),
+            Err(reason) => imported.skip(&line, reason),
+        }
+    }
+    Ok(())
+}
+
+/// Lines with comments removed and `\` continuations joined.
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for raw in content.lines() {
+        let line = match raw.find('#') {
+            Some(0) => "",
+            Some(idx) if raw[..idx].ends_with(char::is_whitespace) => &raw[..idx],
+            _ => raw,
+        };
+        match line.trim_end().strip_suffix('\\') {
+            Some(part) => {
+                current.push_str(part);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                let joined = current.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !joined.is_empty() {
+                    lines.push(joined);
+                }
+                current.clear();
+            }
+        }
+    }
+    let joined = current.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !joined.is_empty() {
+        lines.push(joined);
+    }
+    lines
+}
+
+/// `("r", "base.txt")` for `-r base.txt`, `("-requirement", "base.txt")` for
+/// `--requirement=base.txt`.
+fn split_option(option: &str) -> (&str, &str) {
+    let end = option
+        .find(|c: char| c == '=' || c.is_whitespace())
+        .unwrap_or(option.len());
+    let (flag, rest) = option.split_at(end);
+    (flag, rest.trim_start_matches('=').trim())
+}
+
+/// The PEP 508 requirement on a requirements-file line, without trailing
+/// per-requirement options such as `--hash`.
+fn requirement_line(line: &str) -> std::result::Result<String, String> {
+    let spec = match line.find(" -") {
+        Some(idx) => line[..idx].trim(),
+        None => line,
+    };
+    let (head, marker) = match spec.split_once(';') {
+        Some((head, marker)) => (head.trim(), Some(marker.trim())),
+        None => (spec, None),
+    };
+    let requirement = if head.contains(" @ ") || !is_location(head) {
+        head.to_string()
+    } else {
+        let (url, fragment) = head.split_once('#').unwrap_or((head, ""));
+        if !url.contains("://") {
+            return Err("local paths are not imported".to_string());
+        }
+        let egg = fragment
+            .split('&')
+            .find_map(|part| part.strip_prefix("egg="))
+            .filter(|name| !name.is_empty());
+        match egg {
+            Some(name) => format!("{name} @ {url}"),
+            None => return Err("URL has no `#egg=` package name".to_string()),
+        }
+    };
+    finish(requirement, marker.map(str::to_string))
+}
+
+/// Whether a requirements-file entry is a URL or path rather than a name.
+fn is_location(head: &str) -> bool {
+    head.contains("://")
+        || head.starts_with('.')
+        || head.starts_with('/')
+        || head.starts_with('~')
+        || head.ends_with(".whl")
+        || head.ends_with(".tar.gz")
+        || head.ends_with(".zip")
+        || head.contains('\\')
+}
+
+/// `requirement` with `marker` appended, checked to parse.
+fn finish(requirement: String, marker: Option<String>) -> std::result::Result<String, String> {
+    let text = match marker.filter(|m| !m.is_empty()) {
+        Some(marker) => format!("{requirement}; {marker}"),
+        None => requirement,
+    };
+    text.parse::<Requirement>()
+        .map(|_| text.clone())
+        .map_err(|e| format!("not a valid requirement: {e}"))
+}
+
+// ---------------------------------------------------------------------------
+// Pipfile
+// ---------------------------------------------------------------------------
+
+/// Pipfile keys that hold settings rather than package categories.
+const PIPFILE_SETTINGS: [&str; 4] = ["source", "requires", "pipenv", "scripts"];
+
+/// PEP 508 marker variables Pipfile accepts as package keys
+/// (`sys_platform = "== 'win32'"`).
+const MARKER_KEYS: [&str; 11] = [
+    "os_name",
+    "sys_platform",
+    "platform_machine",
+    "platform_python_implementation",
+    "platform_release",
+    "platform_system",
+    "platform_version",
+    "python_version",
+    "python_full_version",
+    "implementation_name",
+    "implementation_version",
+];
+
+/// Read a `Pipfile`.
+pub fn import_pipfile(path: &Path) -> Result<Imported> {
+    let mut imported = Imported::new(SourceFormat::Pipfile, path);
+    let pipfile = parse_toml(path)?;
+    let Some(root) = pipfile.as_table() else {
+        return Ok(imported);
+    };
+
+    for source in root
+        .get("source")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(url) = source.get("url").and_then(Value::as_str) {
+            imported.indexes.push(url.to_string());
+        }
+    }
+    if let Some(requires) = root.get("requires") {
+        imported.requires_python = requires
+            .get("python_full_version")
+            .and_then(Value::as_str)
+            .map(|v| format!("=={v}"))
+            .or_else(|| {
+                requires
+                    .get("python_version")
+                    .and_then(Value::as_str)
+                    .map(|v| format!("=={v}.*"))
+            });
+    }
+
+    for (category, packages) in root {
+        if PIPFILE_SETTINGS.contains(&category.as_str()) {
+            continue;
+        }
+        let Some(packages) = packages.as_table() else {
+            continue;
+        };
+        let mut requirements = Vec::new();
+        for (name, value) in packages {
+            match pipfile_requirement(name, value) {
+                Ok(requirement) => requirements.push(requirement),
+                Err(reason) => imported.skip(format!("{name} = {value}"), reason),
+            }
+        }
+        match category.as_str() {
+            "packages" => imported.dependencies.extend(requirements),
+            "dev-packages" => imported
+                .groups
+                .entry("dev".to_string())
+                .or_default()
+                .extend(requirements),
+            other => imported
+                .groups
+                .entry(other.to_string())
+                .or_default()
+                .extend(requirements),
+        }
+    }
+    Ok(imported)
+}
+
+fn pipfile_requirement(name: &str, value: &Value) -> std::result::Result<String, String> {
+    let Some(table) = value.as_table() else {
+        let version = value.as_str().ok_or("expected a version string or table")?;
+        return finish(format!("{name}{}", pipfile_version(version)), None);
+    };
+    let text = |key: &str| table.get(key).and_then(Value::as_str);
+    let extras = extras_suffix(table.get("extras"));
+
+    let mut markers: Vec<String> = text("markers").map(str::to_string).into_iter().collect();
+    for key in MARKER_KEYS {
+        if let Some(condition) = text(key) {
+            markers.push(format!("{key} {}", condition.trim()));
+        }
+    }
+
+    let requirement = if let Some(git) = text("git") {
+        let reference = text("ref").map(|r| format!("@{r}")).unwrap_or_default();
+        let git = git.strip_prefix("git+").unwrap_or(git);
+        format!("{name}{extras} @ git+{git}{reference}")
+    } else if let Some(file) = text("file").filter(|f| f.contains("://")) {
+        format!("{name}{extras} @ {file}")
+    } else if table.contains_key("path") || table.contains_key("file") {
+        return Err("local paths are not imported".to_string());
+    } else {
+        format!(
+            "{name}{extras}{}",
+            pipfile_version(text("version").unwrap_or("*"))
+        )
+    };
+    finish(requirement, join_markers(&markers))
+}
+
+/// A Pipfile version (`"*"`, `"==1.0"`, `">=1,<2"`) as a specifier suffix.
+fn pipfile_version(version: &str) -> String {
+    let version = version.trim();
+    if version.is_empty() || version == "*" {
+        String::new()
+    } else if version.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("=={version}")
+    } else {
+        version.replace(' ', "")
+    }
+}
+
+fn extras_suffix(extras: Option<&Value>) -> String {
+    let extras: Vec<&str> = extras
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    if extras.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", extras.join(","))
+    }
+}
+
+/// Markers joined with `and`, parenthesizing any that contain `or`.
+fn join_markers(markers: &[String]) -> Option<String> {
+    match markers {
+        [] => None,
+        [single] => Some(single.clone()),
+        many => Some(
+            many.iter()
+                .map(|m| {
+                    if m.contains(" or ") {
+                        format!("({m})")
+                    } else {
+                        m.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" and "),
+        ),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Poetry
+// ---------------------------------------------------------------------------
+
+/// Read the `[tool.poetry]` dependency sections of a pyproject.toml.
+pub fn import_poetry(path: &Path) -> Result<Imported> {
+    let mut imported = Imported::new(SourceFormat::Poetry, path);
+    let pyproject = parse_toml(path)?;
+    let Some(poetry) = pyproject.get("tool").and_then(|t| t.get("poetry")) else {
+        return Err(ImportError::NotPoetry(path.to_path_buf()));
+    };
+
+    // Optional dependencies are installed through the extras naming them.
+    let mut extras_of: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (extra, names) in poetry
+        .get("extras")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flatten()
+    {
+        for name in names
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            extras_of
+                .entry(crate::pypi::normalize_project_name(name))
+                .or_default()
+                .push(extra.clone());
+        }
+    }
+
+    let mut sections: Vec<(Option<String>, &toml::map::Map<String, Value>)> = Vec::new();
+    if let Some(table) = poetry.get("dependencies").and_then(Value::as_table) {
+        sections.push((None, table));
+    }
+    if let Some(table) = poetry.get("dev-dependencies").and_then(Value::as_table) {
+        sections.push((Some("dev".to_string()), table));
+    }
+    for (group, body) in poetry
+        .get("group")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(table) = body.get("dependencies").and_then(Value::as_table) {
+            sections.push((Some(group.clone()), table));
+        }
+    }
+
+    for (group, table) in sections {
+        for (name, value) in table {
+            if group.is_none() && name.eq_ignore_ascii_case("python") {
+                match value.as_str().map(poetry_constraint) {
+                    Some(Ok(constraint)) if !constraint.is_empty() => {
+                        imported.requires_python = Some(constraint)
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(reason)) => imported.skip(format!("python = {value}"), reason),
+                    None => imported.skip(format!("python = {value}"), "expected a string"),
+                }
+                continue;
+            }
+            // A list holds alternatives for different markers.
+            let variants = match value.as_array() {
+                Some(variants) => variants.iter().collect(),
+                None => vec![value],
+            };
+            for variant in variants {
+                let optional = variant
+                    .get("optional")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                let requirement = match poetry_requirement(name, variant) {
+                    Ok(requirement) => requirement,
+                    Err(reason) => {
+                        imported.skip(format!("{name} = {variant}"), reason);
+                        continue;
+                    }
+                };
+                match (&group, optional) {
+                    (None, true) => {
+                        let extras = extras_of
+                            .get(&crate::pypi::normalize_project_name(name))
+                            .cloned()
+                            .unwrap_or_default();
+                        if extras.is_empty() {
+                            imported.skip(
+                                format!("{name} = {variant}"),
+                                "optional dependency is not part of any extra",
+                            );
+                        }
+                        for extra in extras {
+                            imported
+                                .extras
+                                .entry(extra)
+                                .or_default()
+                                .push(requirement.clone());
+                        }
+                    }
+                    (None, false) => imported.dependencies.push(requirement),
+                    (Some(group), _) => imported
+                        .groups
+                        .entry(group.clone())
+                        .or_default()
+                        .push(requirement),
+                }
+            }
+        }
+    }
+    Ok(imported)
+}
+
+/// One Poetry dependency (a constraint string or a table) as a PEP 508
+/// requirement. Also used for the dependencies recorded in `poetry.lock`.
+pub(crate) fn poetry_requirement(name: &str, value: &Value) -> std::result::Result<String, String> {
+    let Some(table) = value.as_table() else {
+        let constraint = value.as_str().ok_or("expected a version string or table")?;
+        return finish(format!("{name}{}", poetry_constraint(constraint)?), None);
+    };
+    let text = |key: &str| table.get(key).and_then(Value::as_str);
+    let extras = extras_suffix(table.get("extras"));
+
+    let mut markers: Vec<String> = text("markers").map(str::to_string).into_iter().collect();
+    if let Some(python) = text("python") {
+        let constraint = poetry_constraint(python)?;
+        markers.extend(
+            constraint
+                .split(',')
+                .filter(|spec| !spec.is_empty())
+                .map(|spec| {
+                    let at = spec
+                        .find(|c: char| c.is_ascii_digit())
+                        .unwrap_or(spec.len());
+                    format!("python_version {} \"{}\"", &spec[..at], &spec[at..])
+                }),
+        );
+    }
+    if let Some(platform) = text("platform") {
+        markers.push(format!("sys_platform == \"{platform}\""));
+    }
+
+    let requirement = if let Some(git) = text("git") {
+        let reference = text("rev")
+            .or_else(|| text("tag"))
+            .or_else(|| text("branch"))
+            .map(|r| format!("@{r}"))
+            .unwrap_or_default();
+        let subdirectory = text("subdirectory")
+            .map(|s| format!("#subdirectory={s}"))
+            .unwrap_or_default();
+        let git = git.strip_prefix("git+").unwrap_or(git);
+        format!("{name}{extras} @ git+{git}{reference}{subdirectory}")
+    } else if let Some(url) = text("url") {
+        format!("{name}{extras} @ {url}")
+    } else if table.contains_key("path") {
+        return Err("local paths are not imported".to_string());
+    } else {
+        format!(
+            "{name}{extras}{}",
+            poetry_constraint(text("version").unwrap_or("*"))?
+        )
+    };
+    finish(requirement, join_markers(&markers))
+}
+
+/// A Poetry version constraint as a PEP 440 specifier: `^1.2.3` becomes
+/// `>=1.2.3,<2.0.0`, `~1.2` becomes `>=1.2,<1.3`, a bare `1.2` becomes
+/// `==1.2`, and `*` becomes the empty string.
+pub fn poetry_constraint(constraint: &str) -> std::result::Result<String, String> {
+    let constraint = constraint.trim();
+    if constraint.contains("||") {
+        return Err(format!(
+            "`{constraint}` has alternatives (`||`), which one PEP 440 specifier cannot express"
+        ));
+    }
+    // Clauses are separated by commas or spaces; an operator may be
+    // separated from its version by a space.
+    let mut clauses: Vec<String> = Vec::new();
+    let mut pending_operator = String::new();
+    for token in constraint.split([',', ' ']).filter(|t| !t.is_empty()) {
+        if token.chars().all(|c| "<>=!~^".contains(c)) {
+            pending_operator.push_str(token);
+            continue;
+        }
+        clauses.push(format!(
+            "{}{}",
+            std::mem::take(&mut pending_operator),
+            token
+        ));
+    }
+
+    let mut specs = Vec::new();
+    for clause in clauses {
+        if clause == "*" {
+            continue;
+        }
+        if let Some(version) = clause.strip_prefix('^') {
+            let upper = bump(version, caret_position(version)?)?;
+            specs.push(format!(">={version},<{upper}"));
+        } else if let Some(version) = clause.strip_prefix('~').filter(|v| !v.starts_with('=')) {
+            let position = if version.split('.').count() == 1 {
+                0
+            } else {
+                1
+            };
+            specs.push(format!(">={version},<{}", bump(version, position)?));
+        } else if clause.starts_with(['<', '>', '!', '~']) || clause.starts_with("==") {
+            specs.push(clause);
+        } else if let Some(version) = clause.strip_prefix('=') {
+            specs.push(format!("=={version}"));
+        } else {
+            specs.push(format!("=={clause}"));
+        }
+    }
+    Ok(specs.join(","))
+}
+
+/// Index of the release component a caret constraint may not change: the
+/// first non-zero one, else the last.
+fn caret_position(version: &str) -> std::result::Result<usize, String> {
+    let parts = release(version)?;
+    Ok(parts
+        .iter()
+        .position(|&p| p != 0)
+        .unwrap_or(parts.len() - 1))
+}
+
+/// `version` with release component `position` incremented and the ones
+/// after it zeroed, e.g. `bump("1.2.3", 1) == "1.3.0"`.
+fn bump(version: &str, position: usize) -> std::result::Result<String, String> {
+    let mut parts = release(version)?;
+    if position >= parts.len() {
+        parts.resize(position + 1, 0);
+    }
+    parts[position] += 1;
+    for part in &mut parts[position + 1..] {
+        *part = 0;
+    }
+    Ok(parts
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+fn release(version: &str) -> std::result::Result<Vec<u64>, String> {
+    version
+        .split('.')
+        .map(|part| {
+            part.parse::<u64>()
+                .map_err(|_| format!("cannot read version `{version}` in a `^`/`~` constraint"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn poetry_constraints_become_pep440_ranges() {
+        let cases = [
+            ("^1.2.3", ">=1.2.3,<2.0.0"),
+            ("^0.2.3", ">=0.2.3,<0.3.0"),
+            ("^0.0.3", ">=0.0.3,<0.0.4"),
+            ("^0", ">=0,<1"),
+            ("~1.2.3", ">=1.2.3,<1.3.0"),
+            ("~1", ">=1,<2"),
+            ("~=1.2", "~=1.2"),
+            ("1.4", "==1.4"),
+            ("1.2.*", "==1.2.*"),
+            (">= 1.2, < 2.0", ">=1.2,<2.0"),
+            (">=1.2 <2.0", ">=1.2,<2.0"),
+            ("*", ""),
+        ];
+        for (poetry, pep440) in cases {
+            assert_eq!(poetry_constraint(poetry).unwrap(), pep440, "{poetry}");
+        }
+        assert!(poetry_constraint("^1.0 || ^2.0").is_err());
+    }
+
+    #[test]
+    fn requirements_lines_keep_markers_and_drop_options() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("base.txt"), "six==1.16.0\n").unwrap();
+        let path = temp.path().join("requirements.txt");
+        fs::write(
+            &path,
+            "# pinned\n\
+             -r base.txt\n\
+             --index-url https://mirror.example/simple\n\
+             requests==2.31.0 \\\n    --hash=sha256:aaa \\\n    --hash=sha256:bbb\n\
+             tomli>=1.1 ; python_version < \"3.11\"  # backport\n\
+             git+https://github.com/org/demo@v1#egg=demo\n\
+             -e .\n\
+             ./vendor/pkg\n",
+        )
+        .unwrap();
+
+        let imported = import(&path).unwrap();
+        assert_eq!(
+            imported.dependencies,
+            [
+                "six==1.16.0",
+                "requests==2.31.0",
+                "tomli>=1.1; python_version < \"3.11\"",
+                "demo @ git+https://github.com/org/demo@v1",
+            ]
+        );
+        assert_eq!(imported.indexes, ["https://mirror.example/simple"]);
+        assert_eq!(imported.files.len(), 2);
+        let skipped: Vec<&str> = imported.skipped.iter().map(|s| s.entry.as_str()).collect();
+        assert_eq!(skipped, ["-e .", "./vendor/pkg"]);
+    }
+
+    #[test]
+    fn pipfile_categories_become_groups() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("Pipfile");
+        fs::write(
+            &path,
+            r#"
+[[source]]
+url = "https://pypi.org/simple"
+name = "pypi"
+
+[packages]
+requests = "*"
+django = "==4.2"
+pywin32 = {version = ">=306", sys_platform = "== 'win32'"}
+httpx = {version = "*", extras = ["http2"], markers = "python_version >= '3.9'"}
+demo = {git = "https://github.com/org/demo.git", ref = "main"}
+local = {path = "./local", editable = true}
+
+[dev-packages]
+pytest = ">=7"
+
+[requires]
+python_version = "3.11"
+"#,
+        )
+        .unwrap();
+
+        let imported = import(&path).unwrap();
+        assert_eq!(
+            imported.dependencies,
+            [
+                "demo @ git+https://github.com/org/demo.git@main",
+                "django==4.2",
+                "httpx[http2]; python_version >= '3.9'",
+                "pywin32>=306; sys_platform == 'win32'",
+                "requests",
+            ]
+        );
+        assert_eq!(imported.groups["dev"], ["pytest>=7"]);
+        assert_eq!(imported.requires_python.as_deref(), Some("==3.11.*"));
+        assert_eq!(imported.skipped.len(), 1);
+    }
+
+    #[test]
+    fn poetry_sections_map_to_dependencies_groups_and_extras() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("pyproject.toml");
+        fs::write(
+            &path,
+            r#"
+[tool.poetry]
+name = "demo"
+
+[tool.poetry.dependencies]
+python = "^3.9"
+requests = "^2.31"
+tomli = { version = "^2.0", python = "<3.11" }
+psycopg = { version = "^3.1", optional = true }
+numpy = [
+  { version = "<1.25", python = "<3.9" },
+  { version = "^1.25", python = ">=3.9" },
+]
+
+[tool.poetry.group.test.dependencies]
+pytest = "~7.4"
+
+[tool.poetry.extras]
+postgres = ["psycopg"]
+"#,
+        )
+        .unwrap();
+
+        let imported = import(&path).unwrap();
+        assert_eq!(imported.requires_python.as_deref(), Some(">=3.9,<4.0"));
+        assert_eq!(
+            imported.dependencies,
+            [
+                "numpy<1.25; python_version < \"3.9\"",
+                "numpy>=1.25,<2.0; python_version >= \"3.9\"",
+                "requests>=2.31,<3.0",
+                "tomli>=2.0,<3.0; python_version < \"3.11\"",
+            ]
+        );
+        assert_eq!(imported.groups["test"], ["pytest>=7.4,<7.5"]);
+        assert_eq!(imported.extras["postgres"], ["psycopg>=3.1,<4.0"]);
+    }
+}
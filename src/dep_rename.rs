@@ -0,0 +1,273 @@
+//! Import rewriting and atomic multi-file edits for `pybun rename-dep`.
+//!
+//! Rewrites are line-based, like the import scanner in [`crate::drift`]:
+//!
+//! - `from old[.sub] import x` becomes `from new[.sub] import x`;
+//! - `import old` becomes `import new as old`, so code using `old.attr`
+//!   keeps working;
+//! - `import old.sub as y` becomes `import new.sub as y`;
+//! - `import old.sub` becomes `import new.sub`, but later `old.sub.attr`
+//!   uses are not rewritten, so such lines are reported as needing review.
+//!
+//! [`apply`] writes a set of edits all-or-nothing: every new content is
+//! staged next to its target first, then the staged files are renamed into
+//! place. If a rename fails, files already replaced are restored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RenameError {
+    #[error("failed to write {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, RenameError>;
+
+/// Result of rewriting one file's imports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRewrite {
+    pub text: String,
+    /// 1-based numbers of the rewritten lines.
+    pub lines: Vec<usize>,
+    /// Rewritten `import old.sub` lines whose qualified uses in the file
+    /// body still spell `old.sub` and need a manual look.
+    pub review: Vec<usize>,
+}
+
+/// A pending whole-file replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+/// Rewrite imports of module `from` (and its submodules) to `to`.
+pub fn rewrite_imports(source: &str, from: &str, to: &str) -> ImportRewrite {
+    let mut out = ImportRewrite {
+        text: String::with_capacity(source.len()),
+        lines: Vec::new(),
+        review: Vec::new(),
+    };
+    for (idx, raw) in source.split_inclusive('\n').enumerate() {
+        let (line, ending) = match raw.strip_suffix("\r\n") {
+            Some(line) => (line, "\r\n"),
+            None => match raw.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (raw, ""),
+            },
+        };
+        match rewrite_line(line, from, to) {
+            Some((rewritten, needs_review)) => {
+                out.text.push_str(&rewritten);
+                out.text.push_str(ending);
+                out.lines.push(idx + 1);
+                if needs_review {
+                    out.review.push(idx + 1);
+                }
+            }
+            None => out.text.push_str(raw),
+        }
+    }
+    out
+}
+
+/// `Some((line, needs_review))` when `line` imports `from`.
+fn rewrite_line(line: &str, from: &str, to: &str) -> Option<(String, bool)> {
+    let body = line.trim_start();
+    let indent = &line[..line.len() - body.len()];
+    let (code, comment) = match body.find('#') {
+        Some(pos) => body.split_at(pos),
+        None => (body, ""),
+    };
+
+    if let Some(rest) = code.strip_prefix("from ") {
+        let spaces = rest.len() - rest.trim_start().len();
+        let rest = rest.trim_start();
+        let module_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let module = rename_module(&rest[..module_len], from, to)?;
+        let line = format!(
+            "{indent}from {}{module}{}{comment}",
+            " ".repeat(spaces),
+            &rest[module_len..]
+        );
+        return Some((line, false));
+    }
+
+    let rest = code.strip_prefix("import ")?;
+    let trailing = &rest[rest.trim_end().len()..];
+    let mut changed = false;
+    let mut needs_review = false;
+    let parts: Vec<String> = rest
+        .trim_end()
+        .split(',')
+        .map(|part| {
+            let item = part.trim();
+            let (module, alias) = match item.split_once(" as ") {
+                Some((module, alias)) => (module.trim(), Some(alias.trim())),
+                None => (item, None),
+            };
+            let Some(renamed) = rename_module(module, from, to) else {
+                return item.to_string();
+            };
+            changed = true;
+            match alias {
+                Some(alias) => format!("{renamed} as {alias}"),
+                None if module == from => format!("{renamed} as {from}"),
+                None => {
+                    needs_review = true;
+                    renamed
+                }
+            }
+        })
+        .collect();
+    changed.then(|| {
+        (
+            format!("{indent}import {}{trailing}{comment}", parts.join(", ")),
+            needs_review,
+        )
+    })
+}
+
+fn rename_module(module: &str, from: &str, to: &str) -> Option<String> {
+    if module == from {
+        return Some(to.to_string());
+    }
+    let sub = module.strip_prefix(from)?.strip_prefix('.')?;
+    Some(format!("{to}.{sub}"))
+}
+
+/// Write every edit or none of them.
+pub fn apply(edits: &[FileEdit]) -> Result<()> {
+    let mut staged = Vec::with_capacity(edits.len());
+    for edit in edits {
+        let tmp = staging_path(&edit.path);
+        if let Err(source) = fs::write(&tmp, &edit.after) {
+            for tmp in &staged {
+                let _ = fs::remove_file(tmp);
+            }
+            return Err(RenameError::Io {
+                path: edit.path.clone(),
+                source,
+            });
+        }
+        staged.push(tmp);
+    }
+    for (idx, (edit, tmp)) in edits.iter().zip(&staged).enumerate() {
+        if let Err(source) = fs::rename(tmp, &edit.path) {
+            let _ = restore(&edits[..idx]);
+            for tmp in &staged[idx..] {
+                let _ = fs::remove_file(tmp);
+            }
+            return Err(RenameError::Io {
+                path: edit.path.clone(),
+                source,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Put back the original contents of already-applied edits.
+pub fn restore(edits: &[FileEdit]) -> Result<()> {
+    for edit in edits {
+        fs::write(&edit.path, &edit.before).map_err(|source| RenameError::Io {
+            path: edit.path.clone(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.pybun-{}.tmp", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn rewrites_from_and_plain_imports() {
+        let source = "import os, PIL\nfrom PIL.Image import open  # load\n\
+                      import PIL.ImageOps as ops\nimport PILLOW\nx = PIL.Image\n";
+        let rewrite = rewrite_imports(source, "PIL", "pillow");
+        assert_eq!(
+            rewrite.text,
+            "import os, pillow as PIL\nfrom pillow.Image import open  # load\n\
+             import pillow.ImageOps as ops\nimport PILLOW\nx = PIL.Image\n"
+        );
+        assert_eq!(rewrite.lines, vec![1, 2, 3]);
+        assert!(rewrite.review.is_empty());
+    }
+
+    #[test]
+    fn bare_submodule_import_needs_review() {
+        let rewrite = rewrite_imports("    import PIL.Image\r\n", "PIL", "pillow");
+        assert_eq!(rewrite.text, "    import pillow.Image\r\n");
+        assert_eq!(rewrite.review, vec![1]);
+    }
+
+    #[test]
+    fn apply_writes_all_files_and_restore_undoes_them() {
+        let temp = tempdir().unwrap();
+        let a = temp.path().join("a.py");
+        let b = temp.path().join("b.py");
+        fs::write(&a, "old a").unwrap();
+        fs::write(&b, "old b").unwrap();
+        let edits = vec![
+            FileEdit {
+                path: a.clone(),
+                before: "old a".into(),
+                after: "new a".into(),
+            },
+            FileEdit {
+                path: b.clone(),
+                before: "old b".into(),
+                after: "new b".into(),
+            },
+        ];
+
+        apply(&edits).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "new a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new b");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+
+        restore(&edits).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "old b");
+    }
+
+    #[test]
+    fn apply_leaves_nothing_behind_when_staging_fails() {
+        let temp = tempdir().unwrap();
+        let a = temp.path().join("a.py");
+        fs::write(&a, "old a").unwrap();
+        let edits = vec![
+            FileEdit {
+                path: a.clone(),
+                before: "old a".into(),
+                after: "new a".into(),
+            },
+            FileEdit {
+                path: temp.path().join("missing/b.py"),
+                before: String::new(),
+                after: "new b".into(),
+            },
+        ];
+
+        assert!(apply(&edits).is_err());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,307 @@
+//! Text rendering of the locked dependency graph for `pybun tree`.
+//!
+//! A [`DependencyTree`] unfolds a [`DependencyGraph`] from the project root,
+//! or, with `--invert`, from one package up through everything that requires
+//! it back to the root ("why is this here?"). A package reachable along
+//! several paths is expanded the first time only; later occurrences are
+//! marked `(*)`, so cycles and diamonds stay finite. `--depth` cuts the tree
+//! below a given level.
+
+use crate::dep_graph::{DependencyGraph, GraphEdge};
+use crate::pypi::normalize_project_name;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One package (or the project) in the tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TreeNode {
+    pub name: String,
+    pub version: String,
+    /// Requirement on the edge to the parent, as written by the requiring
+    /// package. Always the requirement on the *dependency*, also when
+    /// inverted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+    /// Already expanded elsewhere in the tree; children are not repeated.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub repeated: bool,
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyTree {
+    /// Package the tree was inverted on, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inverted: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    pub root: TreeNode,
+    /// Graph edges shown in the tree, in their original direction.
+    #[serde(skip)]
+    pub edges: Vec<GraphEdge>,
+}
+
+impl DependencyTree {
+    /// Dependencies of the project, top down.
+    pub fn new(graph: &DependencyGraph, depth: Option<usize>) -> Self {
+        let mut walker = Walker::new(graph, false, depth);
+        let root = walker.expand(&graph.root, None, 0);
+        Self {
+            inverted: None,
+            depth,
+            root,
+            edges: walker.shown,
+        }
+    }
+
+    /// Everything that requires `package`, bottom up. `None` when the lock
+    /// has no such package.
+    pub fn inverted(graph: &DependencyGraph, package: &str, depth: Option<usize>) -> Option<Self> {
+        let wanted = normalize_project_name(package);
+        let name = graph
+            .nodes
+            .iter()
+            .find(|node| normalize_project_name(&node.name) == wanted)?
+            .name
+            .clone();
+        let mut walker = Walker::new(graph, true, depth);
+        let root = walker.expand(&name, None, 0);
+        Some(Self {
+            inverted: Some(name),
+            depth,
+            root,
+            edges: walker.shown,
+        })
+    }
+
+    /// The packages and edges shown, as a graph (for DOT output).
+    pub fn subgraph(&self, graph: &DependencyGraph) -> DependencyGraph {
+        let shown: BTreeSet<&str> = self
+            .edges
+            .iter()
+            .flat_map(|edge| [edge.from.as_str(), edge.to.as_str()])
+            .chain([self.root.name.as_str()])
+            .collect();
+        DependencyGraph {
+            root: graph.root.clone(),
+            root_version: graph.root_version.clone(),
+            nodes: graph
+                .nodes
+                .iter()
+                .filter(|node| shown.contains(node.name.as_str()))
+                .cloned()
+                .collect(),
+            edges: self.edges.clone(),
+        }
+    }
+
+    /// Number of distinct packages shown, the project excluded.
+    pub fn package_count(&self) -> usize {
+        fn collect<'a>(node: &'a TreeNode, names: &mut BTreeSet<&'a str>) {
+            names.insert(&node.name);
+            for child in &node.children {
+                collect(child, names);
+            }
+        }
+        let mut names = BTreeSet::new();
+        collect(&self.root, &mut names);
+        names.len() - usize::from(self.inverted.is_none())
+    }
+
+    /// Box-drawing text tree, one package per line.
+    pub fn render(&self) -> String {
+        let mut out = format!("{}\n", label(&self.root));
+        render_children(&self.root, "", &mut out);
+        out
+    }
+}
+
+struct Walker<'a> {
+    /// Edges leaving each node: dependencies, or dependents when inverted.
+    adjacent: BTreeMap<&'a str, Vec<&'a GraphEdge>>,
+    versions: BTreeMap<&'a str, &'a str>,
+    inverted: bool,
+    depth: Option<usize>,
+    expanded: BTreeSet<String>,
+    shown: Vec<GraphEdge>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(graph: &'a DependencyGraph, inverted: bool, depth: Option<usize>) -> Self {
+        let mut adjacent: BTreeMap<&str, Vec<&GraphEdge>> = BTreeMap::new();
+        for edge in &graph.edges {
+            let key = if inverted { &edge.to } else { &edge.from };
+            adjacent.entry(key).or_default().push(edge);
+        }
+        for edges in adjacent.values_mut() {
+            edges.sort_by_key(|edge| {
+                let next = if inverted { &edge.from } else { &edge.to };
+                normalize_project_name(next)
+            });
+        }
+        let versions = graph
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node.version.as_str()))
+            .chain([(graph.root.as_str(), graph.root_version.as_str())])
+            .collect();
+        Self {
+            adjacent,
+            versions,
+            inverted,
+            depth,
+            expanded: BTreeSet::new(),
+            shown: Vec::new(),
+        }
+    }
+
+    fn expand(&mut self, name: &str, via: Option<&GraphEdge>, level: usize) -> TreeNode {
+        let mut node = TreeNode {
+            name: name.to_string(),
+            version: self.versions.get(name).copied().unwrap_or("").to_string(),
+            requirement: via.map(|edge| edge.requirement.clone()),
+            marker: via.and_then(|edge| edge.marker.clone()),
+            repeated: false,
+            children: Vec::new(),
+        };
+        if self.depth.is_some_and(|depth| level >= depth) {
+            return node;
+        }
+        let edges = self.adjacent.get(name).cloned().unwrap_or_default();
+        if !edges.is_empty() && !self.expanded.insert(name.to_string()) {
+            node.repeated = true;
+            return node;
+        }
+        for edge in edges {
+            self.shown.push(edge.clone());
+            let next = if self.inverted { &edge.from } else { &edge.to };
+            node.children.push(self.expand(next, Some(edge), level + 1));
+        }
+        node
+    }
+}
+
+fn label(node: &TreeNode) -> String {
+    let mut label = format!("{} {}", node.name, node.version)
+        .trim_end()
+        .to_string();
+    match (&node.requirement, &node.marker) {
+        (Some(requirement), Some(marker)) => label.push_str(&format!(" ({requirement}; {marker})")),
+        (Some(requirement), None) => label.push_str(&format!(" ({requirement})")),
+        _ => {}
+    }
+    if node.repeated {
+        label.push_str(" (*)");
+    }
+    label
+}
+
+fn render_children(node: &TreeNode, prefix: &str, out: &mut String) {
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        out.push_str(&format!("{prefix}{branch}{}\n", label(child)));
+        render_children(child, &format!("{prefix}{indent}"), out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Lockfile, Package, PackageSource};
+
+    fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+        Package {
+            name: name.into(),
+            version: version.into(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: format!("sha256:{}", "ab".repeat(32)),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn graph() -> DependencyGraph {
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        lock.add_package(package(
+            "requests",
+            "2.32.0",
+            &["urllib3>=1.21", "idna<4; python_version >= \"3.8\""],
+        ));
+        lock.add_package(package("httpx", "0.27.0", &["idna"]));
+        lock.add_package(package("urllib3", "2.2.0", &[]));
+        lock.add_package(package("idna", "3.7", &[]));
+        DependencyGraph::from_lock(
+            &lock,
+            "demo",
+            "0.1.0",
+            &["requests>=2.31".to_string(), "httpx".to_string()],
+        )
+    }
+
+    #[test]
+    fn renders_dependencies_top_down_and_marks_repeats() {
+        let tree = DependencyTree::new(&graph(), None);
+        assert_eq!(
+            tree.render(),
+            "demo 0.1.0\n\
+             ├── httpx 0.27.0 (httpx)\n\
+             │   └── idna 3.7 (idna)\n\
+             └── requests 2.32.0 (requests>=2.31)\n    \
+                 ├── idna 3.7 (idna<4; python_version >= \"3.8\")\n    \
+                 └── urllib3 2.2.0 (urllib3>=1.21)\n"
+        );
+        assert_eq!(tree.package_count(), 4);
+        assert_eq!(tree.edges.len(), 5);
+    }
+
+    #[test]
+    fn depth_cuts_the_tree() {
+        let tree = DependencyTree::new(&graph(), Some(1));
+        assert_eq!(tree.root.children.len(), 2);
+        assert!(tree.root.children.iter().all(|c| c.children.is_empty()));
+        assert_eq!(tree.subgraph(&graph()).nodes.len(), 2);
+    }
+
+    #[test]
+    fn inverted_tree_walks_dependents_up_to_the_project() {
+        let graph = graph();
+        let tree = DependencyTree::inverted(&graph, "IDNA", None).unwrap();
+        assert_eq!(
+            tree.render(),
+            "idna 3.7\n\
+             ├── httpx 0.27.0 (idna)\n\
+             │   └── demo 0.1.0 (httpx)\n\
+             └── requests 2.32.0 (idna<4; python_version >= \"3.8\")\n    \
+                 └── demo 0.1.0 (requests>=2.31)\n"
+        );
+        assert_eq!(tree.package_count(), 4);
+        assert!(DependencyTree::inverted(&graph, "flask", None).is_none());
+    }
+
+    #[test]
+    fn cycles_terminate() {
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        lock.add_package(package("a", "1.0", &["b"]));
+        lock.add_package(package("b", "1.0", &["a"]));
+        let graph = DependencyGraph::from_lock(&lock, "demo", "0.1.0", &["a".to_string()]);
+        let tree = DependencyTree::new(&graph, None);
+        assert_eq!(
+            tree.render(),
+            "demo 0.1.0\n└── a 1.0 (a)\n    └── b 1.0 (b)\n        └── a 1.0 (a) (*)\n"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Requirement chains for `pybun why <package>`.
+//!
+//! Each chain starts at a requirement the project declares, in
+//! `[project].dependencies` (group `main`) or a `[dependency-groups]` table,
+//! and follows locked packages' requirements down to the package asked
+//! about. Every link keeps the requirement text and environment marker it
+//! was reached through, so conflicting constraints and marker-only paths are
+//! visible. Like [`crate::dep_graph`], nothing is re-resolved.
+
+use crate::dep_graph::{DependencyGraph, GraphEdge};
+use crate::lockfile::{Lockfile, MAIN_GROUP};
+use crate::pypi::normalize_project_name;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Chains beyond this many are dropped (and `truncated` set): the number of
+/// paths through a dense graph grows exponentially.
+pub const MAX_CHAINS: usize = 100;
+
+/// One step of a chain: `from` requires `package` through `requirement`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainLink {
+    pub from: String,
+    pub package: String,
+    pub version: String,
+    pub requirement: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+}
+
+/// A path from a declared requirement to the package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chain {
+    /// Dependency group declaring the first requirement (`main` for
+    /// `[project].dependencies`).
+    pub group: String,
+    pub links: Vec<ChainLink>,
+}
+
+impl Chain {
+    /// The package is declared by the project itself.
+    pub fn is_direct(&self) -> bool {
+        self.links.len() == 1
+    }
+
+    /// `main: demo → requests 2.32.0 [requests>=2.31] → idna 3.7 [idna<4]`.
+    pub fn describe(&self, root: &str) -> String {
+        let mut out = format!("{}: {}", self.group, root);
+        for link in &self.links {
+            out.push_str(&format!(
+                " → {} {} [{}",
+                link.package, link.version, link.requirement
+            ));
+            if let Some(marker) = &link.marker {
+                out.push_str(&format!("; {marker}"));
+            }
+            out.push(']');
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    pub package: String,
+    pub version: String,
+    /// Declared directly by the project (in any group).
+    pub direct: bool,
+    pub chains: Vec<Chain>,
+    /// More than [`MAX_CHAINS`] chains exist.
+    pub truncated: bool,
+}
+
+/// Explain why `package` is in `lock`, given the requirements each group of
+/// the project declares. `None` when the lock has no such package.
+pub fn explain(
+    lock: &Lockfile,
+    root: &str,
+    root_version: &str,
+    groups: &BTreeMap<String, Vec<String>>,
+    package: &str,
+) -> Option<Explanation> {
+    let wanted = normalize_project_name(package);
+    let target = lock
+        .packages
+        .values()
+        .find(|pkg| normalize_project_name(&pkg.name) == wanted)?;
+
+    // Package-to-package edges do not depend on the declared requirements;
+    // only the edges leaving the root differ per group.
+    let main = groups.get(MAIN_GROUP).cloned().unwrap_or_default();
+    let graph = DependencyGraph::from_lock(lock, root, root_version, &main);
+    let mut dependents: BTreeMap<&str, Vec<&GraphEdge>> = BTreeMap::new();
+    for edge in graph.edges.iter().filter(|edge| edge.from != graph.root) {
+        dependents.entry(&edge.to).or_default().push(edge);
+    }
+    let mut declared: Vec<(String, GraphEdge)> = Vec::new();
+    for (group, requirements) in groups {
+        let edges = if group == MAIN_GROUP {
+            graph.edges.clone()
+        } else {
+            DependencyGraph::from_lock(lock, root, root_version, requirements).edges
+        };
+        declared.extend(
+            edges
+                .into_iter()
+                .filter(|edge| edge.from == graph.root)
+                .map(|edge| (group.clone(), edge)),
+        );
+    }
+    let versions: BTreeMap<&str, &str> = graph
+        .nodes
+        .iter()
+        .map(|node| (node.name.as_str(), node.version.as_str()))
+        .collect();
+
+    let mut search = Search {
+        dependents: &dependents,
+        declared: &declared,
+        versions: &versions,
+        chains: Vec::new(),
+        truncated: false,
+    };
+    search.walk(&target.name, &mut Vec::new());
+
+    let mut chains = search.chains;
+    chains.sort_by(|a, b| {
+        (a.group != MAIN_GROUP, &a.group, a.links.len()).cmp(&(
+            b.group != MAIN_GROUP,
+            &b.group,
+            b.links.len(),
+        ))
+    });
+    Some(Explanation {
+        package: target.name.clone(),
+        version: target.version.clone(),
+        direct: chains.iter().any(Chain::is_direct),
+        chains,
+        truncated: search.truncated,
+    })
+}
+
+struct Search<'a> {
+    dependents: &'a BTreeMap<&'a str, Vec<&'a GraphEdge>>,
+    declared: &'a [(String, GraphEdge)],
+    versions: &'a BTreeMap<&'a str, &'a str>,
+    chains: Vec<Chain>,
+    truncated: bool,
+}
+
+impl Search<'_> {
+    /// Extend `path` (edges from `name` down to the target, innermost last)
+    /// upwards until it reaches a declared requirement.
+    fn walk(&mut self, name: &str, path: &mut Vec<GraphEdge>) {
+        for (group, edge) in self.declared.iter().filter(|(_, edge)| edge.to == name) {
+            if self.chains.len() == MAX_CHAINS {
+                self.truncated = true;
+                return;
+            }
+            let links = std::iter::once(edge)
+                .chain(path.iter().rev())
+                .map(|edge| self.link(edge))
+                .collect();
+            self.chains.push(Chain {
+                group: group.clone(),
+                links,
+            });
+        }
+        let Some(edges) = self.dependents.get(name) else {
+            return;
+        };
+        for edge in edges {
+            let cycle = path.iter().any(|seen| seen.to == edge.from) || edge.from == name;
+            if cycle || self.truncated {
+                continue;
+            }
+            path.push((*edge).clone());
+            self.walk(&edge.from, path);
+            path.pop();
+        }
+    }
+
+    fn link(&self, edge: &GraphEdge) -> ChainLink {
+        ChainLink {
+            from: edge.from.clone(),
+            package: edge.to.clone(),
+            version: self
+                .versions
+                .get(edge.to.as_str())
+                .copied()
+                .unwrap_or("")
+                .to_string(),
+            requirement: edge.requirement.clone(),
+            marker: edge.marker.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Package, PackageSource};
+
+    fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+        Package {
+            name: name.into(),
+            version: version.into(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: format!("sha256:{}", "ab".repeat(32)),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn lock() -> Lockfile {
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        lock.add_package(package(
+            "requests",
+            "2.32.0",
+            &["idna<4,>=2.5; python_version >= \"3.8\"", "urllib3"],
+        ));
+        lock.add_package(package("pytest", "8.0.0", &["requests"]));
+        lock.add_package(package("idna", "3.7", &[]));
+        lock.add_package(package("urllib3", "2.2.0", &["idna"]));
+        lock.add_package(package("click", "8.1.0", &[]));
+        lock
+    }
+
+    fn groups() -> BTreeMap<String, Vec<String>> {
+        BTreeMap::from([
+            ("main".to_string(), vec!["requests>=2.31".to_string()]),
+            ("dev".to_string(), vec!["pytest>=8".to_string()]),
+        ])
+    }
+
+    #[test]
+    fn lists_every_chain_with_requirements_and_markers() {
+        let why = explain(&lock(), "demo", "0.1.0", &groups(), "IDNA").unwrap();
+        assert_eq!(why.package, "idna");
+        assert!(!why.direct);
+        let described: Vec<String> = why.chains.iter().map(|c| c.describe("demo")).collect();
+        assert_eq!(
+            described,
+            [
+                "main: demo → requests 2.32.0 [requests>=2.31] → idna 3.7 [idna<4,>=2.5; python_version >= \"3.8\"]",
+                "main: demo → requests 2.32.0 [requests>=2.31] → urllib3 2.2.0 [urllib3] → idna 3.7 [idna]",
+                "dev: demo → pytest 8.0.0 [pytest>=8] → requests 2.32.0 [requests] → idna 3.7 [idna<4,>=2.5; python_version >= \"3.8\"]",
+                "dev: demo → pytest 8.0.0 [pytest>=8] → requests 2.32.0 [requests] → urllib3 2.2.0 [urllib3] → idna 3.7 [idna]",
+            ]
+        );
+    }
+
+    #[test]
+    fn direct_and_unreachable_packages() {
+        let why = explain(&lock(), "demo", "0.1.0", &groups(), "requests").unwrap();
+        assert!(why.direct);
+        assert_eq!(why.chains[0].links.len(), 1);
+        assert_eq!(why.chains[0].links[0].from, "demo");
+
+        let why = explain(&lock(), "demo", "0.1.0", &groups(), "click").unwrap();
+        assert!(why.chains.is_empty());
+        assert!(explain(&lock(), "demo", "0.1.0", &groups(), "flask").is_none());
+    }
+}
@@ -0,0 +1,189 @@
+//! Fast metadata reads of installed distributions and wheels.
+//!
+//! Inventories (`pybun env list`, `pybun graph`, console-script lookup) touch
+//! every distribution in an environment, so they must not pay for what they
+//! do not use. Listing an environment reads directory names only; names and
+//! versions come from the `<name>-<version>.dist-info` directory itself.
+//! `METADATA` is read up to the end of its header block, never into the
+//! long description body, and `RECORD` is streamed line by line to sum file
+//! sizes. In a wheel only the `.dist-info/METADATA` entry is inflated: the
+//! rest of the archive is located through the zip central directory and
+//! never read. Nothing is unpacked and no interpreter is started.
+
+use crate::pypi::normalize_project_name;
+use crate::sdist_metadata::CoreMetadata;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
+
+/// A `<name>-<version>.dist-info` directory in site-packages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Distribution {
+    /// Name as spelled in the directory name.
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+}
+
+impl Distribution {
+    /// Parse a `.dist-info` directory path; `None` for anything else.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let stem = path.file_name()?.to_str()?.strip_suffix(".dist-info")?;
+        let (name, version) = stem.rsplit_once('-')?;
+        if name.is_empty() || version.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// The header block of `METADATA`, parsed. The description body is not
+    /// read.
+    pub fn metadata(&self) -> io::Result<CoreMetadata> {
+        let file = File::open(self.path.join("METADATA"))?;
+        Ok(CoreMetadata::parse(&read_header_block(BufReader::new(
+            file,
+        ))?))
+    }
+
+    /// Installed size: the sum of the size column of `RECORD`.
+    pub fn size_bytes(&self) -> io::Result<u64> {
+        let file = File::open(self.path.join("RECORD"))?;
+        record_size(BufReader::new(file))
+    }
+}
+
+/// Every distribution installed in `site_packages`, sorted by normalized
+/// name. Only the directory listing is read.
+pub fn installed(site_packages: &Path) -> Vec<Distribution> {
+    let Ok(entries) = std::fs::read_dir(site_packages) else {
+        return Vec::new();
+    };
+    let mut dists: Vec<Distribution> = entries
+        .flatten()
+        .filter_map(|entry| Distribution::from_path(&entry.path()))
+        .collect();
+    dists.sort_by_cached_key(|dist| (normalize_project_name(&dist.name), dist.path.clone()));
+    dists
+}
+
+/// The installed distribution named `name` (compared PEP 503-normalized).
+pub fn find(site_packages: &Path, name: &str) -> Option<Distribution> {
+    let wanted = normalize_project_name(name);
+    installed(site_packages)
+        .into_iter()
+        .find(|dist| normalize_project_name(&dist.name) == wanted)
+}
+
+/// Lines of an RFC 822-style metadata file up to (not including) the first
+/// blank line.
+pub fn read_header_block(mut reader: impl BufRead) -> io::Result<String> {
+    let mut headers = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end_matches(['\r', '\n']).is_empty() {
+            return Ok(headers);
+        }
+        headers.push_str(&line);
+    }
+}
+
+/// Sum of the size column of a `RECORD` file.
+pub fn record_size(reader: impl BufRead) -> io::Result<u64> {
+    let mut total = 0;
+    for line in reader.lines() {
+        if let Some(size) = line?
+            .rsplit(',')
+            .next()
+            .and_then(|size| size.trim().parse::<u64>().ok())
+        {
+            total += size;
+        }
+    }
+    Ok(total)
+}
+
+/// Header block of a wheel's top-level `.dist-info/METADATA`, or `None` when
+/// the archive has none.
+pub fn wheel_metadata(reader: impl Read + Seek) -> zip::result::ZipResult<Option<String>> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let Some(entry) = archive
+        .file_names()
+        .find(|name| {
+            name.strip_suffix("/METADATA")
+                .is_some_and(|dir| dir.ends_with(".dist-info") && !dir.contains('/'))
+        })
+        .map(str::to_string)
+    else {
+        return Ok(None);
+    };
+    let file = archive.by_name(&entry)?;
+    Ok(Some(read_header_block(BufReader::new(file))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn lists_dist_info_directories_and_reads_headers_only() {
+        let temp = tempdir().unwrap();
+        let site = temp.path();
+        let dist_info = site.join("Requests-2.32.0.dist-info");
+        fs::create_dir_all(&dist_info).unwrap();
+        fs::create_dir_all(site.join("idna-3.7.dist-info")).unwrap();
+        fs::create_dir_all(site.join("requests")).unwrap();
+        fs::write(site.join("distutils-precedence.pth"), "").unwrap();
+        fs::write(
+            dist_info.join("METADATA"),
+            "Name: requests\nLicense: Apache 2.0\n\nRequires-Dist: not-a-header\n",
+        )
+        .unwrap();
+        fs::write(
+            dist_info.join("RECORD"),
+            "requests/__init__.py,sha256=x,4000\nrequests/api.py,sha256=y,96\nrequests-2.32.0.dist-info/RECORD,,\n",
+        )
+        .unwrap();
+
+        let dists = installed(site);
+        let names: Vec<&str> = dists.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["idna", "Requests"]);
+
+        let requests = find(site, "requests").unwrap();
+        assert_eq!(requests.version, "2.32.0");
+        let metadata = requests.metadata().unwrap();
+        assert_eq!(metadata.license.as_deref(), Some("Apache 2.0"));
+        assert!(metadata.requires_dist.is_empty());
+        assert_eq!(requests.size_bytes().unwrap(), 4096);
+        assert!(find(site, "idna").unwrap().metadata().is_err());
+    }
+
+    #[test]
+    fn reads_only_the_metadata_entry_of_a_wheel() {
+        let mut bytes = io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut bytes);
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("demo/__init__.py", options).unwrap();
+            zip.write_all(b"").unwrap();
+            zip.start_file("demo/vendored/x-1.0.dist-info/METADATA", options)
+                .unwrap();
+            zip.write_all(b"Name: x\n").unwrap();
+            zip.start_file("demo-1.0.dist-info/METADATA", options)
+                .unwrap();
+            zip.write_all(b"Name: demo\nRequires-Dist: idna\n\nlong description\n")
+                .unwrap();
+            zip.finish().unwrap();
+        }
+        let headers = wheel_metadata(bytes).unwrap().unwrap();
+        assert_eq!(headers, "Name: demo\nRequires-Dist: idna\n");
+    }
+}
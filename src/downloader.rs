@@ -1,19 +1,65 @@
+use crate::network_policy::{self, NetworkPolicyViolation, Operation};
 use crate::once_map::OnceMap;
 use crate::security::verify_ed25519_signature;
 use futures::StreamExt;
 use reqwest::Client;
+use serde_json::{Value, json};
 use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::fs::{self, File};
+use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{AsyncWriteExt, BufWriter};
 
 /// Upper bound on a server-supplied `Retry-After` value, so a malicious or
 /// misconfigured index cannot stall a download indefinitely.
 const MAX_RETRY_AFTER_SECS: u64 = 5;
 
+/// Downloads run at a time unless `--concurrency` (or
+/// `PYBUN_CONCURRENT_DOWNLOADS`) says otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 10;
+
+/// What a single download transferred.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferStats {
+    /// Bytes received over the network; 0 when the file was already in place.
+    pub bytes: u64,
+    /// Bytes kept from an earlier, interrupted attempt.
+    pub resumed_from: u64,
+    /// HTTP requests made, retries included.
+    pub attempts: u32,
+    pub elapsed: Duration,
+}
+
+impl TransferStats {
+    pub fn bytes_per_sec(&self) -> u64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            (self.bytes as f64 / secs) as u64
+        } else {
+            0
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "bytes": self.bytes,
+            "resumed_from": self.resumed_from,
+            "attempts": self.attempts,
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+            "bytes_per_sec": self.bytes_per_sec(),
+        })
+    }
+}
+
+/// A finished download and what it cost.
+#[derive(Debug, Clone)]
+pub struct Downloaded {
+    pub path: PathBuf,
+    pub stats: TransferStats,
+}
+
 #[derive(Debug, Clone)]
 pub struct SignatureSpec {
     pub signature: String,
@@ -52,6 +98,9 @@ pub enum DownloadError {
     Network(String),
     #[error("io error: {0}")]
     Io(String),
+    /// The URL's host is outside the configured network allowlist.
+    #[error("{0}")]
+    NetworkPolicy(String),
     /// An HTTP response with a non-success status code (e.g. 404, 401, 500).
     ///
     /// `retry_after` carries the parsed `Retry-After` header (in seconds), if
@@ -99,6 +148,7 @@ impl DownloadError {
             DownloadError::MissingChecksum { .. }
             | DownloadError::ChecksumMismatch { .. }
             | DownloadError::SignatureVerificationFailed { .. }
+            | DownloadError::NetworkPolicy(_)
             | DownloadError::MaxRetriesExceeded { .. } => false,
         }
     }
@@ -110,6 +160,12 @@ impl From<reqwest::Error> for DownloadError {
     }
 }
 
+impl From<NetworkPolicyViolation> for DownloadError {
+    fn from(value: NetworkPolicyViolation) -> Self {
+        Self::NetworkPolicy(value.to_string())
+    }
+}
+
 impl From<std::io::Error> for DownloadError {
     fn from(value: std::io::Error) -> Self {
         Self::Io(value.to_string())
@@ -119,7 +175,7 @@ impl From<std::io::Error> for DownloadError {
 #[derive(Debug)]
 pub struct Downloader {
     client: Client,
-    inflight: Arc<OnceMap<DownloadKey, PathBuf>>,
+    inflight: Arc<OnceMap<DownloadKey, Downloaded>>,
 }
 
 impl Default for Downloader {
@@ -132,8 +188,10 @@ impl Downloader {
     pub fn new() -> Self {
         Self {
             // Enhanced HTTP client with connection pooling and keepalive
-            // for improved cold start performance
-            client: Client::builder()
+            // for improved cold start performance. HTTPS connections
+            // negotiate HTTP/2 via ALPN, so parallel downloads from one
+            // index share a single multiplexed connection.
+            client: crate::http_config::client_builder()
                 .timeout(Duration::from_secs(300))
                 // Connection pooling: reuse connections for multiple requests
                 .pool_max_idle_per_host(10)
@@ -142,6 +200,7 @@ impl Downloader {
                 .tcp_keepalive(Duration::from_secs(30))
                 // Connection timeout for faster failure detection
                 .connect_timeout(Duration::from_secs(10))
+                .redirect(network_policy::redirect_policy(Operation::Index))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
             inflight: Arc::new(OnceMap::new()),
@@ -170,6 +229,27 @@ impl Downloader {
         checksum: Option<&str>,
         signature: Option<&SignatureSpec>,
     ) -> Result<PathBuf, DownloadError> {
+        self.download_file_with_stats(url, destination, checksum, signature)
+            .await
+            .map(|downloaded| downloaded.path)
+    }
+
+    /// Like [`Self::download_file_with_signature`], also reporting what was
+    /// transferred.
+    ///
+    /// The body is streamed to `<destination>.part` and renamed into place
+    /// once complete. When a checksum is known, a `.part` file left by an
+    /// interrupted attempt (or an earlier run) is resumed with a `Range`
+    /// request; the checksum then catches a server that changed the file in
+    /// between. Without a checksum every attempt starts from scratch.
+    pub async fn download_file_with_stats(
+        &self,
+        url: &str,
+        destination: &Path,
+        checksum: Option<&str>,
+        signature: Option<&SignatureSpec>,
+    ) -> Result<Downloaded, DownloadError> {
+        network_policy::check_url(Operation::Index, url)?;
         if let Some(expected) = checksum
             && crate::security::is_placeholder_hash(expected)
         {
@@ -182,6 +262,9 @@ impl Downloader {
 
         let max_retries = 3;
         let mut attempt = 0;
+        let started = Instant::now();
+        let mut stats = TransferStats::default();
+        let partial = partial_path(destination);
 
         loop {
             // Optimization: check if file exists and matches checksum
@@ -193,7 +276,10 @@ impl Downloader {
                             if let Some(sig) = signature {
                                 self.verify_signature(destination, sig).await?;
                             }
-                            return Ok(destination.to_path_buf());
+                            return Ok(Downloaded {
+                                path: destination.to_path_buf(),
+                                stats,
+                            });
                         }
                         Err(_) => {
                             // Hash mismatch, remove and re-download
@@ -208,15 +294,22 @@ impl Downloader {
                 }
             }
 
-            match self.download_attempt(url, destination).await {
+            let attempt_result = self
+                .download_attempt(url, destination, &partial, checksum.is_some(), &mut stats)
+                .await;
+            match attempt_result {
                 Ok(_) => {
+                    stats.elapsed = started.elapsed();
                     if let Some(expected) = checksum {
                         self.verify_checksum(destination, expected).await?;
                     }
                     if let Some(sig) = signature {
                         self.verify_signature(destination, sig).await?;
                     }
-                    return Ok(destination.to_path_buf());
+                    return Ok(Downloaded {
+                        path: destination.to_path_buf(),
+                        stats,
+                    });
                 }
                 Err(e) => {
                     // Fail fast on non-retryable errors (e.g. 404/401/403):
@@ -252,8 +345,37 @@ impl Downloader {
         }
     }
 
-    async fn download_attempt(&self, url: &str, destination: &Path) -> Result<(), DownloadError> {
-        let response = self.client.get(url).send().await?;
+    /// One request for `url`, streamed into `partial` and renamed to
+    /// `destination` when the body is complete. With `resume`, an existing
+    /// `partial` is continued from its current length.
+    async fn download_attempt(
+        &self,
+        url: &str,
+        destination: &Path,
+        partial: &Path,
+        resume: bool,
+        stats: &mut TransferStats,
+    ) -> Result<(), DownloadError> {
+        let (response, offset) = loop {
+            let offset = if resume {
+                fs::metadata(partial).await.map(|m| m.len()).unwrap_or(0)
+            } else {
+                0
+            };
+            let mut request = self.client.get(url);
+            if offset > 0 {
+                request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+            }
+            stats.attempts += 1;
+            let response = crate::auth::authorize(request, url).send().await?;
+            if offset > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+                // The partial file is not a prefix of what the server has
+                // now; start over.
+                let _ = fs::remove_file(partial).await;
+                continue;
+            }
+            break (response, offset);
+        };
 
         if let Err(status_err) = response.error_for_status_ref() {
             let status = response.status();
@@ -274,16 +396,39 @@ impl Downloader {
             fs::create_dir_all(parent).await?;
         }
 
-        let file = File::create(destination).await?;
+        // A server that ignores `Range` answers 200 with the whole body.
+        let append = offset > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        if append && content_range_start(&response) != Some(offset) {
+            let _ = fs::remove_file(partial).await;
+            return Err(DownloadError::Network(format!(
+                "unexpected Content-Range resuming {url} at byte {offset}"
+            )));
+        }
+        let file = if append {
+            OpenOptions::new().append(true).open(partial).await?
+        } else {
+            File::create(partial).await?
+        };
+        stats.resumed_from = if append { offset } else { 0 };
         let mut writer = BufWriter::new(file);
         let mut stream = response.bytes_stream();
 
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    // Keep what arrived so the next attempt can resume.
+                    let _ = writer.flush().await;
+                    return Err(e.into());
+                }
+            };
             writer.write_all(&chunk).await?;
+            stats.bytes += chunk.len() as u64;
         }
 
         writer.flush().await?;
+        drop(writer);
+        fs::rename(partial, destination).await?;
         Ok(())
     }
 
@@ -348,23 +493,40 @@ impl Downloader {
     ///
     /// items: Vec<(url, destination, checksum, signature)>
     /// concurrency: Maximum number of concurrent downloads
+    ///
+    /// Results are returned in the order of `items`.
     pub async fn download_parallel(
         &self,
         items: Vec<DownloadRequest>,
         concurrency: usize,
     ) -> Vec<Result<PathBuf, DownloadError>> {
-        let stream = futures::stream::iter(items.into_iter().map(|req| {
+        self.download_parallel_observed(items, concurrency, |_, _| {})
+            .await
+            .into_iter()
+            .map(|result| result.map(|downloaded| downloaded.path))
+            .collect()
+    }
+
+    /// Like [`Self::download_parallel`], calling `on_done` with the item
+    /// index as each download finishes, and keeping per-download stats.
+    pub async fn download_parallel_observed(
+        &self,
+        items: Vec<DownloadRequest>,
+        concurrency: usize,
+        mut on_done: impl FnMut(usize, &Result<Downloaded, DownloadError>),
+    ) -> Vec<Result<Downloaded, DownloadError>> {
+        let stream = futures::stream::iter(items.into_iter().enumerate().map(|(index, req)| {
             let client = self;
             async move {
                 let key = DownloadKey {
                     url: req.url.clone(),
                     destination: req.destination.clone(),
                 };
-                client
+                let result = client
                     .inflight
                     .get_or_try_init(key, || async move {
                         client
-                            .download_file_with_signature(
+                            .download_file_with_stats(
                                 &req.url,
                                 &req.destination,
                                 req.checksum.as_deref(),
@@ -372,14 +534,40 @@ impl Downloader {
                             )
                             .await
                     })
-                    .await
+                    .await;
+                (index, result)
             }
         }));
 
-        stream.buffer_unordered(concurrency).collect().await
+        let mut stream = stream.buffer_unordered(concurrency.max(1));
+        let mut results = Vec::new();
+        while let Some((index, result)) = stream.next().await {
+            on_done(index, &result);
+            results.push((index, result));
+        }
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
     }
 }
 
+/// Where an in-progress download of `destination` is written.
+fn partial_path(destination: &Path) -> PathBuf {
+    let mut name = destination.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    destination.with_file_name(name)
+}
+
+/// The first byte of a `Content-Range: bytes START-END/TOTAL` response.
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    let value = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -572,4 +760,108 @@ mod tests {
             "wheel-bytes"
         );
     }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    /// A `.part` file left by an interrupted download is continued with a
+    /// `Range` request instead of being fetched again.
+    #[tokio::test]
+    async fn partial_download_is_resumed() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/big.whl")
+                .header("range", "bytes=6-");
+            then.status(206)
+                .header("Content-Range", "bytes 6-10/11")
+                .body("bytes");
+        });
+
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("big.whl");
+        std::fs::write(partial_path(&dest), "wheel-").unwrap();
+        let downloader = Downloader::new();
+
+        let downloaded = downloader
+            .download_file_with_stats(
+                &server.url("/big.whl"),
+                &dest,
+                Some(&sha256_hex(b"wheel-bytes")),
+                None,
+            )
+            .await
+            .unwrap();
+
+        mock.assert_calls(1);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"wheel-bytes");
+        assert!(!partial_path(&dest).exists());
+        assert_eq!(downloaded.stats.bytes, 5);
+        assert_eq!(downloaded.stats.resumed_from, 6);
+        assert_eq!(downloaded.stats.attempts, 1);
+    }
+
+    /// A server that cannot serve the requested range (the artifact changed
+    /// since the partial was written) gets a fresh, full request.
+    #[tokio::test]
+    async fn unsatisfiable_range_restarts_download() {
+        let server = MockServer::start();
+        let ranged = server.mock(|when, then| {
+            when.method(GET).path("/changed.whl").header_exists("range");
+            then.status(416);
+        });
+        let full = server.mock(|when, then| {
+            when.method(GET)
+                .path("/changed.whl")
+                .header_missing("range");
+            then.status(200).body("wheel-bytes");
+        });
+
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("changed.whl");
+        std::fs::write(partial_path(&dest), "stale contents that are too long").unwrap();
+        let downloader = Downloader::new();
+
+        let downloaded = downloader
+            .download_file_with_stats(
+                &server.url("/changed.whl"),
+                &dest,
+                Some(&sha256_hex(b"wheel-bytes")),
+                None,
+            )
+            .await
+            .unwrap();
+
+        ranged.assert_calls(1);
+        full.assert_calls(1);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"wheel-bytes");
+        assert_eq!(downloaded.stats.resumed_from, 0);
+        assert_eq!(downloaded.stats.attempts, 2);
+    }
+
+    /// Without a checksum a stale partial cannot be trusted, so it is
+    /// overwritten rather than resumed.
+    #[tokio::test]
+    async fn unverified_download_does_not_resume() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/plain.whl").header_missing("range");
+            then.status(200).body("wheel-bytes");
+        });
+
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("plain.whl");
+        std::fs::write(partial_path(&dest), "wheel-").unwrap();
+        let downloader = Downloader::new();
+
+        let downloaded = downloader
+            .download_file_with_stats(&server.url("/plain.whl"), &dest, None, None)
+            .await
+            .unwrap();
+
+        mock.assert_calls(1);
+        assert_eq!(std::fs::read(&dest).unwrap(), b"wheel-bytes");
+        assert_eq!(downloaded.stats.bytes, 11);
+    }
 }
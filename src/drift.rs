@@ -56,30 +56,7 @@ pub fn analyze(root: &Path) -> DriftResult {
     let files_scanned = py_files.len();
 
     // Scan all imports
-    let mut import_map: HashMap<String, Vec<ImportLocation>> = HashMap::new();
-    for py_file in &py_files {
-        let file_label = py_file
-            .strip_prefix(root)
-            .unwrap_or(py_file)
-            .to_string_lossy()
-            .to_string();
-        if let Ok(content) = std::fs::read_to_string(py_file) {
-            for (line_no, line) in content.lines().enumerate() {
-                let line = line.trim();
-                let pkgs = parse_import_packages(line);
-                if !pkgs.is_empty() {
-                    let loc = ImportLocation {
-                        file: file_label.clone(),
-                        line: line_no + 1,
-                        statement: line.to_string(),
-                    };
-                    for pkg in pkgs {
-                        import_map.entry(pkg).or_default().push(loc.clone());
-                    }
-                }
-            }
-        }
-    }
+    let import_map = scan_imports(root, &py_files);
 
     // Resolve import names to PyPI package names
     let aliases = import_aliases();
@@ -193,7 +170,39 @@ pub fn analyze(root: &Path) -> DriftResult {
 }
 
 /// Collect all .py files recursively, skipping hidden dirs and common noise dirs.
-fn collect_py_files(root: &Path) -> Vec<PathBuf> {
+/// Index the imports of `py_files` by top-level module name. File labels are
+/// relative to `root`.
+pub fn scan_imports(root: &Path, py_files: &[PathBuf]) -> HashMap<String, Vec<ImportLocation>> {
+    let mut import_map: HashMap<String, Vec<ImportLocation>> = HashMap::new();
+    for py_file in py_files {
+        let file_label = py_file
+            .strip_prefix(root)
+            .unwrap_or(py_file)
+            .to_string_lossy()
+            .to_string();
+        if let Ok(content) = std::fs::read_to_string(py_file) {
+            for (line_no, line) in content.lines().enumerate() {
+                let line = line.trim();
+                let pkgs = parse_import_packages(line);
+                if !pkgs.is_empty() {
+                    let loc = ImportLocation {
+                        file: file_label.clone(),
+                        line: line_no + 1,
+                        statement: line.to_string(),
+                    };
+                    for pkg in pkgs {
+                        import_map.entry(pkg).or_default().push(loc.clone());
+                    }
+                }
+            }
+        }
+    }
+    import_map
+}
+
+/// Collect the `.py` files under `root`, skipping hidden, virtualenv and build
+/// directories.
+pub fn collect_py_files(root: &Path) -> Vec<PathBuf> {
     let mut files = Vec::new();
     collect_py_files_inner(root, &mut files);
     files
@@ -349,6 +358,21 @@ fn import_aliases_reverse() -> HashMap<&'static str, Vec<&'static str>> {
     m
 }
 
+/// Top-level import names a PyPI distribution is expected to provide.
+pub fn import_names(pypi_name: &str) -> Vec<String> {
+    let normalized = normalize_package_name(pypi_name);
+    let mut names: Vec<String> = import_aliases_reverse()
+        .into_iter()
+        .filter(|(pypi, _)| normalize_package_name(pypi) == normalized)
+        .flat_map(|(_, imports)| imports.into_iter().map(str::to_string))
+        .collect();
+    if names.is_empty() {
+        names.push(normalized);
+    }
+    names.sort();
+    names
+}
+
 /// Set of Python standard library module names to exclude from drift analysis.
 pub fn stdlib_modules() -> HashSet<&'static str> {
     // Python 3.9+ stdlib (comprehensive list)
@@ -673,6 +697,12 @@ mod tests {
         assert_eq!(aliases.get("PIL"), Some(&"Pillow"));
     }
 
+    #[test]
+    fn import_names_prefers_known_aliases() {
+        assert_eq!(import_names("pillow"), vec!["PIL"]);
+        assert_eq!(import_names("Flask-Login"), vec!["flask_login"]);
+    }
+
     #[test]
     fn import_aliases_cv2_maps_to_opencv() {
         let aliases = import_aliases();
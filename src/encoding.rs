@@ -0,0 +1,237 @@
+//! Text encoding of Python processes started by `pybun run`.
+//!
+//! Python picks its default encoding for `open()`, pipes and the standard
+//! streams from the locale: a legacy Windows code page such as cp1252, or
+//! ASCII under the C locale of a minimal Linux container. Scripts that work on
+//! a UTF-8 developer machine then fail with `UnicodeEncodeError` or
+//! `UnicodeDecodeError` elsewhere.
+//!
+//! Under the default [`EncodingPolicy::Utf8`], `pybun run` starts Python in
+//! UTF-8 mode (`PYTHONUTF8=1`, `PYTHONIOENCODING=utf-8`), leaving either
+//! variable alone if the caller already set it. Code that relies on the locale
+//! encoding opts out with `[tool.pybun] encoding = "locale"` or
+//! `PYBUN_ENCODING=locale`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Environment variable overriding `[tool.pybun] encoding`.
+pub const ENCODING_ENV: &str = "PYBUN_ENCODING";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EncodingError {
+    #[error("invalid encoding policy '{0}' (expected 'utf8' or 'locale')")]
+    InvalidPolicy(String),
+}
+
+/// How child Python processes choose their text encoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingPolicy {
+    /// Run Python in UTF-8 mode regardless of the locale.
+    #[default]
+    Utf8,
+    /// Leave the locale encoding in effect (legacy codebases).
+    Locale,
+}
+
+impl FromStr for EncodingPolicy {
+    type Err = EncodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "utf8" | "utf-8" => Ok(Self::Utf8),
+            "locale" => Ok(Self::Locale),
+            _ => Err(EncodingError::InvalidPolicy(s.to_string())),
+        }
+    }
+}
+
+impl EncodingPolicy {
+    /// `PYBUN_ENCODING` if set, else the project setting, else UTF-8.
+    pub fn resolve(configured: Option<EncodingPolicy>) -> Result<Self, EncodingError> {
+        match std::env::var(ENCODING_ENV) {
+            Ok(value) if !value.trim().is_empty() => value.parse(),
+            _ => Ok(configured.unwrap_or_default()),
+        }
+    }
+}
+
+/// The host's character encoding setting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocaleInfo {
+    /// Effective `LC_CTYPE` locale (e.g. `C`, `en_US.UTF-8`) on Unix, the ANSI
+    /// code page (e.g. `cp1252`) on Windows.
+    pub name: String,
+    pub utf8: bool,
+}
+
+impl LocaleInfo {
+    pub fn detect() -> Self {
+        #[cfg(windows)]
+        {
+            #[link(name = "kernel32")]
+            unsafe extern "system" {
+                safe fn GetACP() -> u32;
+            }
+            let code_page = GetACP();
+            Self {
+                name: format!("cp{code_page}"),
+                utf8: code_page == 65001,
+            }
+        }
+        #[cfg(not(windows))]
+        {
+            Self::from_env(|name| std::env::var(name).ok())
+        }
+    }
+
+    /// POSIX precedence: `LC_ALL`, then `LC_CTYPE`, then `LANG`; unset means
+    /// the `C` locale.
+    pub fn from_env(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let name = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .filter_map(&lookup)
+            .find(|value| !value.is_empty())
+            .unwrap_or_else(|| "C".to_string());
+        let lower = name.to_ascii_lowercase();
+        let utf8 = lower.contains("utf-8") || lower.contains("utf8");
+        Self { name, utf8 }
+    }
+}
+
+/// Encoding environment for one child process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EncodingSetup {
+    pub policy: EncodingPolicy,
+    pub locale: LocaleInfo,
+    /// Variables PyBun sets on the child.
+    pub env: BTreeMap<String, String>,
+    /// Whether Python will run in UTF-8 mode (set by PyBun or the caller).
+    pub utf8_mode: bool,
+}
+
+impl EncodingSetup {
+    /// Work out the child environment for `policy`. `lookup` reads the
+    /// caller's environment so explicit `PYTHONUTF8`/`PYTHONIOENCODING` win.
+    pub fn new(
+        policy: EncodingPolicy,
+        locale: LocaleInfo,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let mut env = BTreeMap::new();
+        if policy == EncodingPolicy::Utf8 {
+            for (name, value) in [("PYTHONUTF8", "1"), ("PYTHONIOENCODING", "utf-8")] {
+                if lookup(name).is_none() {
+                    env.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+        let utf8_mode =
+            env.contains_key("PYTHONUTF8") || lookup("PYTHONUTF8").is_some_and(|v| v.trim() == "1");
+        Self {
+            policy,
+            locale,
+            env,
+            utf8_mode,
+        }
+    }
+
+    /// Warning for a child that will use a non-UTF-8 locale encoding.
+    pub fn warning(&self) -> Option<String> {
+        if self.utf8_mode || self.locale.utf8 {
+            return None;
+        }
+        let why = match self.policy {
+            EncodingPolicy::Locale => "the project opted out of UTF-8 mode",
+            EncodingPolicy::Utf8 => "PYTHONUTF8 is set to disable UTF-8 mode",
+        };
+        Some(format!(
+            "locale '{}' is not UTF-8 and {why}; non-ASCII text may fail to encode or decode",
+            self.locale.name
+        ))
+    }
+
+    /// Suggestion for a child that failed with an encoding error.
+    pub fn failure_hint(&self, message: &str) -> String {
+        if message.contains("Non-UTF-8 code") {
+            return "Save the source file as UTF-8, or declare its encoding with a `# -*- coding: <encoding> -*-` line.".to_string();
+        }
+        if self.utf8_mode {
+            format!(
+                "Python ran in UTF-8 mode but the data is not UTF-8. Pass an explicit `encoding=` to open()/bytes.decode(), or set `[tool.pybun] encoding = \"locale\"` (or {ENCODING_ENV}=locale) if the project relies on the '{}' locale encoding.",
+                self.locale.name
+            )
+        } else {
+            format!(
+                "Python used the '{}' locale encoding. Remove the UTF-8 opt-out (`[tool.pybun] encoding = \"locale\"`, {ENCODING_ENV}, or PYTHONUTF8=0) or pass an explicit `encoding=`.",
+                self.locale.name
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn locale_follows_posix_precedence() {
+        let locale = LocaleInfo::from_env(env(&[("LANG", "en_US.UTF-8"), ("LC_ALL", "C")]));
+        assert_eq!(locale.name, "C");
+        assert!(!locale.utf8);
+        assert!(LocaleInfo::from_env(env(&[("LC_CTYPE", "de_DE.utf8")])).utf8);
+        assert_eq!(LocaleInfo::from_env(env(&[("LC_ALL", "")])).name, "C");
+    }
+
+    #[test]
+    fn utf8_policy_sets_variables_the_caller_left_unset() {
+        let c = LocaleInfo::from_env(env(&[]));
+        let setup = EncodingSetup::new(EncodingPolicy::Utf8, c.clone(), env(&[]));
+        assert_eq!(setup.env["PYTHONUTF8"], "1");
+        assert_eq!(setup.env["PYTHONIOENCODING"], "utf-8");
+        assert!(setup.utf8_mode);
+        assert_eq!(setup.warning(), None);
+
+        let opted_out = EncodingSetup::new(EncodingPolicy::Utf8, c, env(&[("PYTHONUTF8", "0")]));
+        assert_eq!(opted_out.env.len(), 1);
+        assert!(!opted_out.utf8_mode);
+        assert!(opted_out.warning().unwrap().contains("PYTHONUTF8"));
+    }
+
+    #[test]
+    fn locale_policy_leaves_environment_and_warns_on_legacy_locale() {
+        let latin1 = LocaleInfo::from_env(env(&[("LANG", "fr_FR.ISO-8859-1")]));
+        let setup = EncodingSetup::new(EncodingPolicy::Locale, latin1, env(&[]));
+        assert!(setup.env.is_empty());
+        assert!(setup.warning().unwrap().contains("fr_FR.ISO-8859-1"));
+        assert!(
+            setup
+                .failure_hint("'ascii' codec")
+                .contains("locale encoding")
+        );
+
+        let utf8 = LocaleInfo::from_env(env(&[("LANG", "C.UTF-8")]));
+        assert_eq!(
+            EncodingSetup::new(EncodingPolicy::Locale, utf8, env(&[])).warning(),
+            None
+        );
+    }
+
+    #[test]
+    fn policy_parses_aliases() {
+        assert_eq!("UTF-8".parse(), Ok(EncodingPolicy::Utf8));
+        assert_eq!("locale".parse(), Ok(EncodingPolicy::Locale));
+        assert!("latin1".parse::<EncodingPolicy>().is_err());
+    }
+}
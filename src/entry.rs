@@ -1,4 +1,4 @@
-use crate::cli::{Cli, Commands};
+use crate::cli::{CacheCommands, Cli, Commands, EnvCommands};
 
 const DEFAULT_STACK_SIZE: usize = 4 * 1024 * 1024;
 const MIN_STACK_SIZE: usize = 1024 * 1024;
@@ -26,19 +26,43 @@ pub fn should_install_color_eyre(cli: &Cli) -> bool {
 }
 
 pub fn requires_tokio_runtime(cli: &Cli) -> bool {
+    // `x` and PEP 723 scripts with dependencies install packages natively;
+    // plain scripts skip the runtime to start faster.
+    if let Commands::Run(args) = &cli.command {
+        return args.target.as_deref().is_some_and(declares_dependencies);
+    }
+    // `doctor --score` queries PyPI and OSV for outdated and vulnerable
+    // packages.
+    if let Commands::Doctor(args) = &cli.command {
+        return args.score;
+    }
     matches!(
         cli.command,
         Commands::Install(_)
             | Commands::Lock(_)
             | Commands::Mcp(_)
             | Commands::Add(_)
+            | Commands::RenameDep(_)
+            | Commands::Import(_)
             | Commands::Outdated(_)
             | Commands::Upgrade(_)
             | Commands::Build(_)
+            | Commands::Publish(_)
             | Commands::Audit(_)
+            | Commands::Script(_)
+            | Commands::X(_)
+            | Commands::Cache(CacheCommands::Push(_) | CacheCommands::Pull(_))
+            | Commands::Env(EnvCommands::Push(_) | EnvCommands::Pull(_))
     )
 }
 
+fn declares_dependencies(script: &str) -> bool {
+    crate::pep723::parse_script_metadata(script)
+        .ok()
+        .flatten()
+        .is_some_and(|metadata| !metadata.dependencies.is_empty())
+}
+
 fn pybun_trace_enabled() -> bool {
     std::env::var_os("PYBUN_TRACE").is_some()
 }
@@ -65,7 +89,8 @@ mod tests {
     use super::{requires_tokio_runtime, runtime_stack_size, should_install_color_eyre};
     use crate::cli::{
         Cli, Commands, DoctorArgs, InstallArgs, LockArgs, McpCommands, McpServeArgs, OutputFormat,
-        ProgressMode, RunArgs, TestArgs,
+        ProgressMode, RunArgs, SandboxArgs, ScriptCommands, ScriptLockArgs, TestArgs,
+        TimeoutAction,
     };
     use std::sync::{LazyLock, Mutex};
 
@@ -102,8 +127,13 @@ mod tests {
     fn test_cli(verbose: bool) -> Cli {
         Cli {
             format: OutputFormat::Text,
+            columns: Vec::new(),
             progress: ProgressMode::Auto,
             no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
             command: Commands::Test(TestArgs {
                 paths: Vec::new(),
                 member: None,
@@ -120,6 +150,17 @@ mod tests {
                 snapshot_dir: None,
                 timeout: None,
                 retries: None,
+                env: Vec::new(),
+                fixtures: Vec::new(),
+                history: false,
+                history_runs: 20,
+                history_export: None,
+                report: None,
+                changed: false,
+                all: false,
+                coverage: false,
+                coverage_fail_under: None,
+                coverage_report: Vec::new(),
                 passthrough: Vec::new(),
             }),
         }
@@ -128,8 +169,13 @@ mod tests {
     fn doctor_cli(verbose: bool) -> Cli {
         Cli {
             format: OutputFormat::Text,
+            columns: Vec::new(),
             progress: ProgressMode::Auto,
             no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
             command: Commands::Doctor(DoctorArgs {
                 verbose,
                 bundle: None,
@@ -137,10 +183,21 @@ mod tests {
                 upload_url: None,
                 fix: false,
                 apply: false,
+                score: false,
             }),
         }
     }
 
+    #[test]
+    fn tokio_runtime_required_only_for_doctor_score() {
+        let mut cli = doctor_cli(false);
+        assert!(!requires_tokio_runtime(&cli));
+        if let Commands::Doctor(args) = &mut cli.command {
+            args.score = true;
+        }
+        assert!(requires_tokio_runtime(&cli));
+    }
+
     #[test]
     fn installs_color_eyre_for_trace_env() {
         let cli = test_cli(false);
@@ -197,18 +254,31 @@ mod tests {
     fn tokio_runtime_required_for_install() {
         let cli = Cli {
             format: OutputFormat::Text,
+            columns: Vec::new(),
             progress: ProgressMode::Auto,
             no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
             command: Commands::Install(InstallArgs {
                 offline: false,
                 system: false,
+                no_seed: false,
+                build_isolation: None,
                 requirements: Vec::new(),
                 index: None,
                 lock: "pybun.lockb".into(),
+                frozen: false,
+                package: None,
+                require_hashes: false,
                 workspace: false,
                 member: None,
                 group: None,
                 pre: false,
+                resolver: Default::default(),
+                concurrency: None,
+                allow_suspicious: false,
             }),
         };
         assert!(requires_tokio_runtime(&cli));
@@ -218,25 +288,69 @@ mod tests {
     fn tokio_runtime_required_for_lock() {
         let cli = Cli {
             format: OutputFormat::Text,
+            columns: Vec::new(),
             progress: ProgressMode::Auto,
             no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
             command: Commands::Lock(LockArgs {
                 script: None,
                 offline: false,
                 index: None,
+                resolver: Default::default(),
+                compare: false,
+                platform: Vec::new(),
+                python: Vec::new(),
+                require_hashes: false,
+                shards: false,
+                check_shards: false,
+                check: false,
+                from: None,
+                update_only: None,
             }),
         };
         assert!(requires_tokio_runtime(&cli));
     }
 
+    #[test]
+    fn tokio_runtime_required_for_script_lock() {
+        let cli = Cli {
+            format: OutputFormat::Text,
+            columns: Vec::new(),
+            progress: ProgressMode::Auto,
+            no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
+            command: Commands::Script(ScriptCommands::Lock(ScriptLockArgs {
+                script: "script.py".into(),
+                upgrade: false,
+                check: false,
+                offline: false,
+                index: None,
+            })),
+        };
+        assert!(requires_tokio_runtime(&cli));
+    }
+
     #[test]
     fn tokio_runtime_required_for_mcp() {
         let cli = Cli {
             format: OutputFormat::Text,
+            columns: Vec::new(),
             progress: ProgressMode::Auto,
             no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
             command: Commands::Mcp(McpCommands::Serve(McpServeArgs {
                 port: 9999,
+                host: std::net::Ipv4Addr::LOCALHOST.into(),
+                token: None,
                 stdio: true,
             })),
         };
@@ -247,23 +361,62 @@ mod tests {
     fn tokio_runtime_not_required_for_run() {
         let cli = Cli {
             format: OutputFormat::Text,
+            columns: Vec::new(),
             progress: ProgressMode::Auto,
             no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
             command: Commands::Run(RunArgs {
                 target: Some("script.py".to_string()),
+                list: false,
                 code: None,
-                sandbox: false,
-                allow_network: false,
-                allow_read: Vec::new(),
-                allow_write: Vec::new(),
-                allow_env: Vec::new(),
-                sandbox_timeout: crate::sandbox::DEFAULT_SANDBOX_TIMEOUT_SECS,
-                sandbox_memory: 0,
-                sandbox_cpu: 0,
+                module: None,
+                member: None,
+                sandbox: SandboxArgs::default(),
                 profile: "dev".to_string(),
+                lazy_imports: false,
+                measure_lazy_savings: false,
+                python: None,
                 passthrough: Vec::new(),
             }),
         };
         assert!(!requires_tokio_runtime(&cli));
     }
+
+    #[test]
+    fn tokio_runtime_required_for_run_with_pep723_dependencies() {
+        let temp = tempfile::tempdir().unwrap();
+        let script = temp.path().join("script.py");
+        std::fs::write(
+            &script,
+            "# /// script\n# dependencies = [\"rich\"]\n# ///\nprint('hi')\n",
+        )
+        .unwrap();
+        let cli = Cli {
+            format: OutputFormat::Text,
+            columns: Vec::new(),
+            progress: ProgressMode::Auto,
+            no_progress: false,
+            max_inline_bytes: 0,
+            max_duration: None,
+            on_timeout: TimeoutAction::Cancel,
+            offline: false,
+            command: Commands::Run(RunArgs {
+                target: Some(script.to_string_lossy().to_string()),
+                list: false,
+                code: None,
+                module: None,
+                member: None,
+                sandbox: SandboxArgs::default(),
+                profile: "dev".to_string(),
+                lazy_imports: false,
+                measure_lazy_savings: false,
+                python: None,
+                passthrough: Vec::new(),
+            }),
+        };
+        assert!(requires_tokio_runtime(&cli));
+    }
 }
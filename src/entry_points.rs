@@ -0,0 +1,249 @@
+//! Console-script entry points of installed distributions.
+//!
+//! `pybun run NAME` runs a `[console_scripts]` entry point declared in a
+//! distribution's `entry_points.txt` without relying on a wrapper script in
+//! the environment's `bin/` (`pybun install` does not create them; the
+//! environments of `pybun run` scripts and `pybun x` tools get them from
+//! [`crate::installer::install_wheel_into`]).
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A `[console_scripts]` entry such as `black = black:patched_main`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryPoint {
+    pub name: String,
+    pub module: String,
+    /// Dotted attribute path inside `module`; `None` calls the module itself.
+    pub attr: Option<String>,
+    /// Distribution declaring the entry point.
+    pub distribution: String,
+    /// The `.dist-info` directory it was read from.
+    pub dist_info: PathBuf,
+}
+
+impl EntryPoint {
+    /// Parse the value of an entry point (`module[:attr] [extras]`). Modules
+    /// and attributes that are not dotted Python identifiers, and script
+    /// names other than letters, digits, `-`, `_` and `.`, are rejected.
+    pub fn parse(name: &str, value: &str, distribution: &str, dist_info: &Path) -> Option<Self> {
+        let value = value.split('[').next().unwrap_or(value).trim();
+        let (module, attr) = match value.split_once(':') {
+            Some((module, attr)) => (module.trim(), Some(attr.trim())),
+            None => (value, None),
+        };
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name
+            || !is_dotted_identifier(module)
+            || attr.is_some_and(|a| !is_dotted_identifier(a))
+        {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            module: module.to_string(),
+            attr: attr.map(str::to_string),
+            distribution: distribution.to_string(),
+            dist_info: dist_info.to_path_buf(),
+        })
+    }
+
+    /// `module:attr` as written in `entry_points.txt`.
+    pub fn target(&self) -> String {
+        match &self.attr {
+            Some(attr) => format!("{}:{}", self.module, attr),
+            None => self.module.clone(),
+        }
+    }
+
+    /// Python code that calls the entry point the way a generated console
+    /// script does: `sys.argv[0]` is the script name and the return value
+    /// becomes the exit status.
+    pub fn python_code(&self) -> String {
+        let attrs = self
+            .attr
+            .as_deref()
+            .map(|attr| {
+                attr.split('.')
+                    .map(|part| format!("target = getattr(target, '{part}')\n"))
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        format!(
+            "import sys\nimport importlib\nsys.argv[0] = '{}'\ntarget = importlib.import_module('{}')\n{}sys.exit(target())\n",
+            self.name, self.module, attrs
+        )
+    }
+}
+
+/// Every console script declared by the distributions installed in
+/// `site_dirs`, in search order. A name declared twice keeps its first entry.
+pub fn console_scripts(site_dirs: &[PathBuf]) -> Vec<EntryPoint> {
+    let mut scripts: Vec<EntryPoint> = Vec::new();
+    for dir in site_dirs {
+        for dist in crate::dist_info::installed(dir) {
+            let Ok(text) = std::fs::read_to_string(dist.path.join("entry_points.txt")) else {
+                continue;
+            };
+            for (name, value) in section(&text, "console_scripts") {
+                if scripts.iter().any(|s| s.name == name) {
+                    continue;
+                }
+                if let Some(entry) = EntryPoint::parse(name, value, &dist.name, &dist.path) {
+                    scripts.push(entry);
+                }
+            }
+        }
+    }
+    scripts
+}
+
+/// The console script called `name`, if an installed distribution declares one.
+pub fn find_console_script(site_dirs: &[PathBuf], name: &str) -> Option<EntryPoint> {
+    console_scripts(site_dirs)
+        .into_iter()
+        .find(|entry| entry.name == name)
+}
+
+/// Entry points in `group` (`console_scripts`, `gui_scripts`) declared by the
+/// distribution installed at `dist_info`. Invalid entries are skipped.
+pub fn declared(dist_info: &Path, distribution: &str, group: &str) -> Vec<EntryPoint> {
+    let Ok(text) = std::fs::read_to_string(dist_info.join("entry_points.txt")) else {
+        return Vec::new();
+    };
+    section(&text, group)
+        .into_iter()
+        .filter_map(|(name, value)| EntryPoint::parse(name, value, distribution, dist_info))
+        .collect()
+}
+
+/// Names of the executables the distribution installed at `dist_info` put in
+/// the environment's `bin/` (`Scripts` on Windows), read from `RECORD`. This
+/// covers distributions that ship plain scripts instead of entry points.
+pub fn installed_scripts(dist_info: &Path) -> Vec<String> {
+    let Ok(text) = std::fs::read_to_string(dist_info.join("RECORD")) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let path = line
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .replace('\\', "/");
+        let Some((dir, file)) = path.rsplit_once('/') else {
+            continue;
+        };
+        if !dir.starts_with("..") || !(dir.ends_with("/bin") || dir.ends_with("/Scripts")) {
+            continue;
+        }
+        let name = file
+            .strip_suffix(".exe")
+            .or_else(|| file.strip_suffix(".cmd"))
+            .unwrap_or(file);
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// `key = value` pairs of one `[section]` of an INI-style `entry_points.txt`.
+fn section<'a>(text: &'a str, wanted: &str) -> Vec<(&'a str, &'a str)> {
+    let mut current = None;
+    let mut pairs = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.trim());
+            continue;
+        }
+        if current == Some(wanted)
+            && let Some((key, value)) = line.split_once('=')
+        {
+            pairs.push((key.trim(), value.trim()));
+        }
+    }
+    pairs
+}
+
+fn is_dotted_identifier(value: &str) -> bool {
+    !value.is_empty()
+        && value.split('.').all(|part| {
+            let mut chars = part.chars();
+            chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                && chars.all(|c| c.is_alphanumeric() || c == '_')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parses_module_attr_and_extras() {
+        let dist_info = Path::new("black-24.1.0.dist-info");
+        let entry =
+            EntryPoint::parse("black", "black:patched_main [d]", "black", dist_info).unwrap();
+        assert_eq!(entry.module, "black");
+        assert_eq!(entry.attr.as_deref(), Some("patched_main"));
+        assert_eq!(entry.target(), "black:patched_main");
+
+        let entry = EntryPoint::parse("tool", "pkg.cli", "pkg", dist_info).unwrap();
+        assert_eq!(entry.attr, None);
+        assert!(EntryPoint::parse("bad", "pkg:main(); import os", "pkg", dist_info).is_none());
+        assert!(EntryPoint::parse("it's", "pkg:main", "pkg", dist_info).is_none());
+    }
+
+    #[test]
+    fn python_code_calls_nested_attribute() {
+        let entry = EntryPoint::parse("tool", "pkg.cli:App.main", "pkg", Path::new("x")).unwrap();
+        let code = entry.python_code();
+        assert!(code.contains("sys.argv[0] = 'tool'"));
+        assert!(code.contains("importlib.import_module('pkg.cli')"));
+        assert!(code.contains("getattr(target, 'App')\ntarget = getattr(target, 'main')"));
+        assert!(code.ends_with("sys.exit(target())\n"));
+    }
+
+    #[test]
+    fn finds_console_scripts_in_dist_info() {
+        let temp = tempdir().unwrap();
+        let dist_info = temp.path().join("demo_tool-1.0.0.dist-info");
+        fs::create_dir(&dist_info).unwrap();
+        fs::write(
+            dist_info.join("entry_points.txt"),
+            "[gui_scripts]\ndemo-gui = demo.gui:main\n\n[console_scripts]\ndemo = demo.cli:main\n",
+        )
+        .unwrap();
+
+        let dirs = vec![temp.path().to_path_buf()];
+        let entry = find_console_script(&dirs, "demo").unwrap();
+        assert_eq!(entry.distribution, "demo_tool");
+        assert_eq!(entry.target(), "demo.cli:main");
+        assert!(find_console_script(&dirs, "demo-gui").is_none());
+        assert_eq!(console_scripts(&dirs).len(), 1);
+    }
+
+    #[test]
+    fn installed_scripts_come_from_record() {
+        let temp = tempdir().unwrap();
+        fs::write(
+            temp.path().join("RECORD"),
+            "httpie/__init__.py,sha256=abc,120\n\
+             ../../../bin/http,sha256=def,220\n\
+             ../../Scripts/https.exe,sha256=ghi,108000\n\
+             ../../../bin/http,,\n\
+             httpie-3.2.2.dist-info/RECORD,,\n",
+        )
+        .unwrap();
+        assert_eq!(installed_scripts(temp.path()), ["http", "https"]);
+        assert!(installed_scripts(&temp.path().join("missing")).is_empty());
+    }
+}
@@ -4,7 +4,8 @@
 //! 1. PYBUN_ENV environment variable (explicit path to venv)
 //! 2. PYBUN_PYTHON environment variable (explicit Python binary)
 //! 3. Project-local `.pybun/venv` directory
-//! 4. `.python-version` file (pyenv-style version selection)
+//! 4. `.python-version` file (a managed runtime installed by `pybun python
+//!    install`, else pyenv-style version selection)
 //! 5. System Python (python3 / python in PATH)
 
 use color_eyre::eyre::{Result, eyre};
@@ -36,6 +37,8 @@ pub enum EnvSource {
     PythonVersionFile(PathBuf),
     /// System Python found in PATH.
     System,
+    /// A runtime installed by `pybun python install` (named by version).
+    ManagedRuntime(String),
 }
 
 impl std::fmt::Display for EnvSource {
@@ -48,6 +51,9 @@ impl std::fmt::Display for EnvSource {
                 write!(f, ".python-version ({}, LOCAL)", p.display())
             }
             EnvSource::System => write!(f, "system PATH (GLOBAL)"),
+            EnvSource::ManagedRuntime(version) => {
+                write!(f, "managed runtime {version} (GLOBAL)")
+            }
         }
     }
 }
@@ -58,7 +64,7 @@ impl std::fmt::Display for EnvSource {
 /// 1. `PYBUN_ENV` - explicit venv path
 /// 2. `PYBUN_PYTHON` - explicit Python binary
 /// 3. `.pybun/venv` - project-local environment
-/// 4. `.python-version` - pyenv-style version file
+/// 4. `.python-version` - pinned managed runtime, else pyenv-style lookup
 /// 5. System Python (python3/python in PATH)
 pub fn find_python_env(working_dir: &Path) -> Result<PythonEnv> {
     // 1. Check PYBUN_ENV (explicit venv path)
@@ -107,19 +113,27 @@ pub fn find_python_env(working_dir: &Path) -> Result<PythonEnv> {
             version: get_python_version_from_venv(&project_venv),
             source: EnvSource::ProjectLocal,
         };
-        cache.put(working_dir, &env);
+        cache.put(working_dir, &env, None);
         let _ = cache.save();
         return Ok(env);
     }
 
-    // Check cache after venv detection
-    if let Some(env) = cache.get(working_dir) {
+    // Check cache after venv detection; a changed pin invalidates it.
+    let pin = find_python_version_file(working_dir);
+    let pinned = pin.as_ref().map(|(_, version)| version.clone());
+    if let Some(env) = cache.get(working_dir, pinned.as_deref()) {
         return Ok(env);
     }
 
     // 4. Check .python-version file
-    let discovered = if let Some((version_file, version)) = find_python_version_file(working_dir) {
-        if let Some((python, is_pyenv_isolated)) = find_python_for_version(&version) {
+    let discovered = if let Some((version_file, version)) = pin {
+        if let Some((name, python)) = find_managed_runtime(&version) {
+            Some(PythonEnv {
+                python_path: python,
+                version: Some(crate::runtime::release_version(&name).to_string()),
+                source: EnvSource::ManagedRuntime(name),
+            })
+        } else if let Some((python, is_pyenv_isolated)) = find_python_for_version(&version) {
             Some(PythonEnv {
                 python_path: python,
                 version: Some(version),
@@ -153,7 +167,7 @@ pub fn find_python_env(working_dir: &Path) -> Result<PythonEnv> {
     };
 
     if let Some(env) = discovered {
-        cache.put(working_dir, &env);
+        cache.put(working_dir, &env, pinned.as_deref());
         let _ = cache.save();
         return Ok(env);
     }
@@ -202,8 +216,25 @@ fn get_python_version_from_venv(venv_path: &Path) -> Option<String> {
     None
 }
 
+/// The `home` directory recorded in a venv's pyvenv.cfg (the bin directory
+/// of the interpreter the venv was created from).
+pub fn venv_home(venv_path: &Path) -> Option<PathBuf> {
+    pyvenv_value(venv_path, "home").map(PathBuf::from)
+}
+
+/// A non-empty `key = value` setting from a venv's pyvenv.cfg.
+pub fn pyvenv_value(venv_path: &Path, key: &str) -> Option<String> {
+    let content = std::fs::read_to_string(venv_path.join("pyvenv.cfg")).ok()?;
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key)
+            .then(|| value.trim().to_string())
+            .filter(|v| !v.is_empty())
+    })
+}
+
 /// Find project-local .pybun/venv directory.
-fn find_project_venv(start_dir: &Path) -> Option<PathBuf> {
+pub fn find_project_venv(start_dir: &Path) -> Option<PathBuf> {
     let mut current = start_dir;
     loop {
         // Check for standard venv names
@@ -223,9 +254,11 @@ fn find_project_venv(start_dir: &Path) -> Option<PathBuf> {
         // Also check for pyproject.toml as project root marker
         let pyproject = current.join("pyproject.toml");
         if pyproject.exists() {
-            // If we found pyproject.toml but no venv in this dir,
-            // we stop searching up, assuming this is the project root.
-            return None;
+            // If we found pyproject.toml but no venv in this dir, this is the
+            // project root: a workspace member continues at its workspace
+            // root, anything else stops searching up.
+            return crate::workspace::member_workspace_root(current)
+                .and_then(|root| find_project_venv(&root));
         }
 
         current = current.parent()?;
@@ -233,7 +266,7 @@ fn find_project_venv(start_dir: &Path) -> Option<PathBuf> {
 }
 
 /// Find .python-version file and read its content.
-fn find_python_version_file(start_dir: &Path) -> Option<(PathBuf, String)> {
+pub(crate) fn find_python_version_file(start_dir: &Path) -> Option<(PathBuf, String)> {
     let mut current = start_dir;
     loop {
         let version_file = current.join(".python-version");
@@ -250,6 +283,28 @@ fn find_python_version_file(start_dir: &Path) -> Option<(PathBuf, String)> {
     }
 }
 
+/// The installed managed runtime a `.python-version` pin names, with its
+/// interpreter.
+fn find_managed_runtime(version: &str) -> Option<(String, PathBuf)> {
+    let manager = crate::runtime::RuntimeManager::new(crate::cache::Cache::new().ok()?);
+    let name = manager.find_installed(version)?;
+    let python = manager.python_binary(&name);
+    Some((name, python))
+}
+
+/// Whether a pin is satisfied without a managed runtime: by a pyenv install
+/// or a versioned interpreter on `PATH` (`python3.12`).
+pub(crate) fn pin_satisfied_locally(version: &str) -> bool {
+    if find_pyenv_python(version).is_some() {
+        return true;
+    }
+    let mut parts = version.split('.');
+    match (parts.next(), parts.next()) {
+        (Some(major), Some(minor)) => which_executable(&format!("python{major}.{minor}")).is_some(),
+        _ => false,
+    }
+}
+
 /// Find Python interpreter for a specific version.
 /// Supports pyenv-style installations and common system paths.
 ///
@@ -339,7 +394,15 @@ fn find_pyenv_python(version: &str) -> Option<PathBuf> {
 ///
 /// Used as the safe default install target when no venv/`PYBUN_ENV` is
 /// configured, instead of silently installing into system Python (Issue #286).
-pub fn create_project_venv(project_root: &Path) -> Result<PythonEnv> {
+/// A newly created environment is based on `base_python` (the interpreter
+/// [`find_python_env`] picked, so a `.python-version` pin carries over) and
+/// seeded according to `seed` (see [`crate::seed`]); an existing one is
+/// reused as-is.
+pub fn create_project_venv(
+    project_root: &Path,
+    base_python: &Path,
+    seed: &crate::seed::SeedPlan,
+) -> Result<PythonEnv> {
     let venv_path = project_root.join(".pybun").join("venv");
 
     if let Some(python) = find_venv_python(&venv_path) {
@@ -350,35 +413,11 @@ pub fn create_project_venv(project_root: &Path) -> Result<PythonEnv> {
         });
     }
 
-    let base_python = find_system_python().ok_or_else(|| {
-        eyre!(
-            "no Python interpreter found to create {}; set PYBUN_PYTHON or install python3",
-            venv_path.display()
-        )
-    })?;
-
     if let Some(parent) = venv_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
-    let status = std::process::Command::new(&base_python)
-        .args(["-m", "venv"])
-        .arg(&venv_path)
-        .status()
-        .map_err(|e| {
-            eyre!(
-                "failed to create virtual environment at {}: {}",
-                venv_path.display(),
-                e
-            )
-        })?;
-
-    if !status.success() {
-        return Err(eyre!(
-            "failed to create virtual environment at {}",
-            venv_path.display()
-        ));
-    }
+    crate::seed::create_seeded_venv(base_python, &venv_path, seed).map_err(|e| eyre!(e))?;
 
     let python = find_venv_python(&venv_path).ok_or_else(|| {
         eyre!(
@@ -430,7 +469,7 @@ fn find_system_python() -> Option<PathBuf> {
 }
 
 /// Check if an executable exists in PATH.
-fn which_executable(name: &str) -> Option<PathBuf> {
+pub(crate) fn which_executable(name: &str) -> Option<PathBuf> {
     std::env::var_os("PATH").and_then(|paths| {
         std::env::split_paths(&paths).find_map(|dir| {
             let full_path = dir.join(name);
@@ -562,12 +601,13 @@ mod tests {
 
     #[test]
     fn create_project_venv_creates_pybun_venv_directory() {
-        if find_system_python().is_none() {
+        let Some(python) = find_system_python() else {
             eprintln!("skipping: no system python available in test environment");
             return;
-        }
+        };
         let temp = tempfile::tempdir().unwrap();
-        let env = create_project_venv(temp.path()).expect("venv creation succeeds");
+        let env = create_project_venv(temp.path(), &python, &crate::seed::SeedPlan::default())
+            .expect("venv creation succeeds");
         assert_eq!(env.source, EnvSource::ProjectLocal);
         assert!(env.python_path.exists());
         assert!(temp.path().join(".pybun").join("venv").is_dir());
@@ -575,13 +615,15 @@ mod tests {
 
     #[test]
     fn create_project_venv_reuses_existing_venv() {
-        if find_system_python().is_none() {
+        let Some(python) = find_system_python() else {
             eprintln!("skipping: no system python available in test environment");
             return;
-        }
+        };
         let temp = tempfile::tempdir().unwrap();
-        let first = create_project_venv(temp.path()).expect("first creation succeeds");
-        let second = create_project_venv(temp.path()).expect("second call reuses venv");
+        let first = create_project_venv(temp.path(), &python, &crate::seed::SeedPlan::default())
+            .expect("first creation succeeds");
+        let second = create_project_venv(temp.path(), &python, &crate::seed::SeedPlan::default())
+            .expect("second call reuses venv");
         assert_eq!(first.python_path, second.python_path);
     }
 
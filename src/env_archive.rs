@@ -0,0 +1,590 @@
+//! Portable, deterministic environment snapshots.
+//!
+//! `pybun env snapshot` writes a resolved virtual environment to a single
+//! archive, and `pybun env restore` unpacks it elsewhere (typically a CI
+//! runner warming its cache) without resolving or installing anything:
+//!
+//! ```text
+//! pybun-snapshot.json        manifest: tree, distributions, interpreter, lock hash
+//! objects/<id[..2]>/<id>     file contents by SHA-256, each stored once
+//! ```
+//!
+//! The archive is deterministic: entries are sorted, tar headers carry no
+//! timestamps or owners, and bytecode caches (`__pycache__`) are left out, so
+//! the same environment always gives a byte-identical archive. The snapshot
+//! id is the SHA-256 of the manifest and so names the environment's content.
+//!
+//! Console scripts and activation files embed the environment's absolute
+//! path. Snapshotting marks the files under `bin/` (`Scripts\`) that contain
+//! it, and restoring rewrites them for the new location. Restores verify every
+//! object against its hash and build the tree next to the target before
+//! swapping it into place, like `pybun env pull`.
+//!
+//! The compression follows the archive name: `.tar.zst`, `.tar.gz` or `.tar`.
+
+use crate::archive::ArchiveFormat;
+use crate::chunking::chunk_id;
+use crate::env_snapshot::{self, SnapshotError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Name of the manifest, the first entry of every snapshot archive.
+pub const MANIFEST_NAME: &str = "pybun-snapshot.json";
+
+/// Manifest layout version; restores refuse newer layouts.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum EnvArchiveError {
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+    #[error("unsupported snapshot archive name '{0}': use a .tar.zst, .tar.gz or .tar file name")]
+    UnsupportedFormat(PathBuf),
+    #[error("{0} is not a virtual environment (no pyvenv.cfg)")]
+    NotVenv(PathBuf),
+    #[error("invalid snapshot archive {path}: {reason}")]
+    Invalid { path: PathBuf, reason: String },
+    #[error("object {id} in {path} does not match its hash")]
+    CorruptObject { id: String, path: PathBuf },
+}
+
+pub type Result<T> = std::result::Result<T, EnvArchiveError>;
+
+fn io_err(path: &Path) -> impl FnOnce(io::Error) -> EnvArchiveError + '_ {
+    move |source| {
+        EnvArchiveError::Snapshot(SnapshotError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+/// Everything needed to rebuild the environment, except file contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    /// Absolute path of the environment when it was snapshotted.
+    pub venv: String,
+    pub python: Interpreter,
+    /// `<os>-<arch>` the environment was built on.
+    pub platform: String,
+    /// SHA-256 of the project's `pybun.lockb`, when there was one.
+    pub lock_sha256: Option<String>,
+    pub distributions: Vec<Distribution>,
+    pub entries: Vec<Entry>,
+}
+
+/// The base interpreter, from `pyvenv.cfg`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interpreter {
+    pub version: Option<String>,
+    /// Directory of the base interpreter (`home` in `pyvenv.cfg`).
+    pub home: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Distribution {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Path relative to the environment root, `/`-separated.
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: EntryKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum EntryKind {
+    Dir,
+    File {
+        size: u64,
+        #[serde(default)]
+        executable: bool,
+        sha256: String,
+        /// Contains the environment's absolute path, rewritten on restore.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        relocate: bool,
+    },
+    Symlink {
+        target: String,
+    },
+}
+
+/// What `snapshot` wrote.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotReport {
+    /// SHA-256 of the manifest.
+    pub id: String,
+    pub output: PathBuf,
+    pub files: usize,
+    /// Distinct file contents stored.
+    pub objects: usize,
+    /// Size of all file contents in the environment.
+    pub total_bytes: u64,
+    pub archive_bytes: u64,
+    pub distributions: usize,
+    pub python: Interpreter,
+}
+
+/// What `restore` rebuilt, and where the snapshot came from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RestoreReport {
+    pub id: String,
+    pub files: usize,
+    pub total_bytes: u64,
+    /// Files rewritten for the new location.
+    pub relocated: usize,
+    pub distributions: usize,
+    pub python: Interpreter,
+    pub platform: String,
+    pub lock_sha256: Option<String>,
+}
+
+/// `<os>-<arch>` of this machine, as recorded in manifests.
+pub fn current_platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Write the environment at `venv` to the archive `output`. `lock_sha256`
+/// records which lockfile the environment was installed from.
+pub fn snapshot(venv: &Path, output: &Path, lock_sha256: Option<String>) -> Result<SnapshotReport> {
+    let format = tar_format(output)?;
+    if !venv.join("pyvenv.cfg").is_file() {
+        return Err(EnvArchiveError::NotVenv(venv.to_path_buf()));
+    }
+    let venv = std::path::absolute(venv).map_err(io_err(venv))?;
+    let venv_str = venv.to_string_lossy().into_owned();
+
+    let mut walked = Vec::new();
+    env_snapshot::walk(&venv, &venv, &mut walked)?;
+    let mut entries = Vec::new();
+    let mut objects: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut total_bytes = 0;
+    for entry in walked {
+        if entry.path.split('/').any(|part| part == "__pycache__") {
+            continue;
+        }
+        let kind = match entry.kind {
+            env_snapshot::EntryKind::Dir => EntryKind::Dir,
+            env_snapshot::EntryKind::Symlink { target } => EntryKind::Symlink { target },
+            env_snapshot::EntryKind::File { executable, .. } => {
+                let path = venv.join(&entry.path);
+                let data = fs::read(&path).map_err(io_err(&path))?;
+                let sha256 = chunk_id(&data);
+                let relocate = is_script(&entry.path) && find(&data, venv_str.as_bytes()).is_some();
+                total_bytes += data.len() as u64;
+                objects.entry(sha256.clone()).or_insert(path);
+                EntryKind::File {
+                    size: data.len() as u64,
+                    executable,
+                    sha256,
+                    relocate,
+                }
+            }
+        };
+        entries.push(Entry {
+            path: entry.path,
+            kind,
+        });
+    }
+
+    let distributions = crate::env_clean::site_packages_dir(&venv)
+        .map(|site_packages| crate::dist_info::installed(&site_packages))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|dist| Distribution {
+            name: dist.name,
+            version: dist.version,
+        })
+        .collect();
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        venv: venv_str,
+        python: Interpreter {
+            version: crate::env::pyvenv_value(&venv, "version")
+                .or_else(|| crate::env::pyvenv_value(&venv, "version_info")),
+            home: crate::env::pyvenv_value(&venv, "home"),
+        },
+        platform: current_platform(),
+        lock_sha256,
+        distributions,
+        entries,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).expect("manifests serialize");
+
+    let dir = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&dir).map_err(io_err(&dir))?;
+    let tar = tempfile::NamedTempFile::new_in(&dir).map_err(io_err(&dir))?;
+    write_tar(tar.as_file(), &json, &objects).map_err(io_err(tar.path()))?;
+    let archive = match format {
+        ArchiveFormat::Tar => tar,
+        _ => {
+            let compressed = tempfile::NamedTempFile::new_in(&dir).map_err(io_err(&dir))?;
+            compress(format, tar.path(), compressed.as_file())
+                .map_err(io_err(compressed.path()))?;
+            compressed
+        }
+    };
+    archive
+        .persist(output)
+        .map_err(|e| io_err(output)(e.error))?;
+
+    Ok(SnapshotReport {
+        id: chunk_id(&json),
+        output: output.to_path_buf(),
+        files: manifest
+            .entries
+            .iter()
+            .filter(|e| matches!(e.kind, EntryKind::File { .. }))
+            .count(),
+        objects: objects.len(),
+        total_bytes,
+        archive_bytes: fs::metadata(output).map(|m| m.len()).unwrap_or(0),
+        distributions: manifest.distributions.len(),
+        python: manifest.python,
+    })
+}
+
+fn tar_format(path: &Path) -> Result<ArchiveFormat> {
+    match ArchiveFormat::from_path(path) {
+        Some(ArchiveFormat::Zip) | None => {
+            Err(EnvArchiveError::UnsupportedFormat(path.to_path_buf()))
+        }
+        Some(format) => Ok(format),
+    }
+}
+
+/// Files under `bin/` (`Scripts\`) may embed the environment's path.
+fn is_script(path: &str) -> bool {
+    matches!(path.split('/').next(), Some("bin" | "Scripts"))
+}
+
+fn write_tar(file: &File, manifest: &[u8], objects: &BTreeMap<String, PathBuf>) -> io::Result<()> {
+    let mut builder = tar::Builder::new(BufWriter::new(file));
+    append(&mut builder, MANIFEST_NAME, manifest)?;
+    for (id, path) in objects {
+        let data = fs::read(path)?;
+        append(&mut builder, &object_path(id), &data)?;
+    }
+    builder.into_inner()?.flush()
+}
+
+/// Append a regular file with a fixed mode and no timestamp or owner.
+fn append<W: Write>(builder: &mut tar::Builder<W>, path: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+    builder.append_data(&mut header, path, data)
+}
+
+fn object_path(id: &str) -> String {
+    format!("objects/{}/{id}", id.get(..2).unwrap_or(id))
+}
+
+fn compress(format: ArchiveFormat, tar: &Path, output: &File) -> io::Result<()> {
+    let source = BufReader::new(File::open(tar)?);
+    let mut target = BufWriter::new(output);
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(&mut target, flate2::Compression::default());
+            io::copy(&mut { source }, &mut encoder)?;
+            encoder.finish()?;
+        }
+        _ => ruzstd::encoding::compress(
+            source,
+            &mut target,
+            ruzstd::encoding::CompressionLevel::Fastest,
+        ),
+    }
+    target.flush()
+}
+
+/// Rebuild the environment in `archive` at `target`, replacing it once the
+/// whole tree is written and verified.
+pub fn restore(archive: &Path, target: &Path) -> Result<RestoreReport> {
+    let format = tar_format(archive)?;
+    let file = File::open(archive).map_err(io_err(archive))?;
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(BufReader::new(file))),
+        ArchiveFormat::TarZst => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(BufReader::new(file)).map_err(|e| {
+                EnvArchiveError::Invalid {
+                    path: archive.to_path_buf(),
+                    reason: e.to_string(),
+                }
+            })?,
+        ),
+        _ => Box::new(BufReader::new(file)),
+    };
+    let invalid = |reason: String| EnvArchiveError::Invalid {
+        path: archive.to_path_buf(),
+        reason,
+    };
+    let mut tar = tar::Archive::new(reader);
+    let mut tar_entries = tar.entries().map_err(io_err(archive))?;
+
+    let mut raw = Vec::new();
+    match tar_entries.next() {
+        Some(Ok(mut entry))
+            if entry.path().map_err(io_err(archive))?.as_ref() == Path::new(MANIFEST_NAME) =>
+        {
+            entry.read_to_end(&mut raw).map_err(io_err(archive))?;
+        }
+        Some(Err(e)) => return Err(io_err(archive)(e)),
+        _ => return Err(invalid(format!("{MANIFEST_NAME} is not the first entry"))),
+    }
+    let manifest: Manifest =
+        serde_json::from_slice(&raw).map_err(|e| invalid(format!("{MANIFEST_NAME}: {e}")))?;
+    if manifest.format > FORMAT_VERSION {
+        return Err(invalid(format!(
+            "snapshot format {} is newer than this PyBun supports ({FORMAT_VERSION}); upgrade PyBun",
+            manifest.format
+        )));
+    }
+    let mut wanted: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for entry in &manifest.entries {
+        env_snapshot::check_path(&entry.path)?;
+        if let EntryKind::File { sha256, .. } = &entry.kind {
+            wanted.entry(sha256.as_str()).or_default().push(entry);
+        }
+    }
+
+    let target = std::path::absolute(target).map_err(io_err(target))?;
+    let new_venv = target.to_string_lossy().into_owned();
+    let staging = env_snapshot::sibling(&target, "pybun-restore");
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging).map_err(io_err(&staging))?;
+    let result = (|| {
+        let mut report = RestoreReport {
+            id: chunk_id(&raw),
+            files: 0,
+            total_bytes: 0,
+            relocated: 0,
+            distributions: manifest.distributions.len(),
+            python: manifest.python.clone(),
+            platform: manifest.platform.clone(),
+            lock_sha256: manifest.lock_sha256.clone(),
+        };
+        for entry in &manifest.entries {
+            if let EntryKind::Dir = entry.kind {
+                let path = staging.join(&entry.path);
+                fs::create_dir_all(&path).map_err(io_err(&path))?;
+            }
+        }
+        for tar_entry in tar_entries {
+            let mut tar_entry = tar_entry.map_err(io_err(archive))?;
+            let name = tar_entry.path().map_err(io_err(archive))?.into_owned();
+            let Some(id) = name
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let Some(users) = wanted.remove(id.as_str()) else {
+                continue;
+            };
+            let mut data = Vec::new();
+            tar_entry.read_to_end(&mut data).map_err(io_err(archive))?;
+            if chunk_id(&data) != id {
+                return Err(EnvArchiveError::CorruptObject {
+                    id,
+                    path: archive.to_path_buf(),
+                });
+            }
+            for entry in users {
+                let EntryKind::File {
+                    executable,
+                    relocate,
+                    ..
+                } = &entry.kind
+                else {
+                    continue;
+                };
+                let path = staging.join(&entry.path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(io_err(parent))?;
+                }
+                let contents = if *relocate {
+                    report.relocated += 1;
+                    replace_all(&data, manifest.venv.as_bytes(), new_venv.as_bytes())
+                } else {
+                    data.clone()
+                };
+                fs::write(&path, &contents).map_err(io_err(&path))?;
+                if *executable {
+                    env_snapshot::set_executable(&path)?;
+                }
+                report.files += 1;
+                report.total_bytes += contents.len() as u64;
+            }
+        }
+        if let Some(id) = wanted.keys().next() {
+            return Err(invalid(format!("object {id} is missing")));
+        }
+        for entry in &manifest.entries {
+            if let EntryKind::Symlink { target: link } = &entry.kind {
+                let link = match Path::new(link).strip_prefix(&manifest.venv) {
+                    Ok(rest) => target.join(rest).to_string_lossy().into_owned(),
+                    Err(_) => link.clone(),
+                };
+                env_snapshot::symlink(&link, &staging.join(&entry.path))?;
+            }
+        }
+        Ok(report)
+    })();
+
+    match result {
+        Ok(report) => {
+            env_snapshot::replace_dir(&staging, &target)?;
+            Ok(report)
+        }
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            Err(e)
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn replace_all(data: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut rest = data;
+    while let Some(pos) = find(rest, from) {
+        out.extend_from_slice(&rest[..pos]);
+        out.extend_from_slice(to);
+        rest = &rest[pos + from.len()..];
+    }
+    out.extend_from_slice(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn venv(dir: &Path) {
+        let site_packages = dir.join("lib/python3.12/site-packages");
+        fs::create_dir_all(site_packages.join("pkg/__pycache__")).unwrap();
+        fs::create_dir_all(site_packages.join("pkg-1.0.dist-info")).unwrap();
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::write(
+            dir.join("pyvenv.cfg"),
+            "home = /usr/bin\nversion_info = 3.12.4\n",
+        )
+        .unwrap();
+        fs::write(site_packages.join("pkg/__init__.py"), "X = 1\n").unwrap();
+        fs::write(site_packages.join("pkg/copy.py"), "X = 1\n").unwrap();
+        fs::write(site_packages.join("pkg/__pycache__/x.pyc"), "bytecode").unwrap();
+        fs::write(
+            dir.join("bin/tool"),
+            format!("#!{}/bin/python\nimport pkg\n", dir.display()),
+        )
+        .unwrap();
+        env_snapshot::set_executable(&dir.join("bin/tool")).unwrap();
+    }
+
+    #[test]
+    fn snapshots_are_deterministic_and_restore_relocates_scripts() {
+        let temp = tempdir().unwrap();
+        let env = temp.path().join("venv");
+        venv(&env);
+
+        let first = snapshot(&env, &temp.path().join("a.tar.zst"), None).unwrap();
+        fs::write(
+            env.join("lib/python3.12/site-packages/pkg/__pycache__/y.pyc"),
+            "more",
+        )
+        .unwrap();
+        let second = snapshot(&env, &temp.path().join("b.tar.zst"), None).unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(
+            fs::read(temp.path().join("a.tar.zst")).unwrap(),
+            fs::read(temp.path().join("b.tar.zst")).unwrap()
+        );
+        assert_eq!(first.files, 4);
+        assert_eq!(first.objects, 3, "identical files are stored once");
+        assert_eq!(first.distributions, 1);
+        assert_eq!(first.python.version.as_deref(), Some("3.12.4"));
+
+        let target = temp.path().join("elsewhere/venv");
+        let restored = restore(&temp.path().join("a.tar.zst"), &target).unwrap();
+        assert_eq!(restored.id, first.id);
+        assert_eq!(restored.relocated, 1);
+        let script = fs::read_to_string(target.join("bin/tool")).unwrap();
+        assert!(
+            script.starts_with(&format!("#!{}/bin/python\n", target.display())),
+            "{script}"
+        );
+        assert!(
+            target
+                .join("lib/python3.12/site-packages/pkg/copy.py")
+                .is_file()
+        );
+        assert!(
+            !target
+                .join("lib/python3.12/site-packages/pkg/__pycache__")
+                .exists()
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(target.join("bin/tool"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+    }
+
+    #[test]
+    fn corrupt_objects_are_rejected_and_the_target_is_kept() {
+        let temp = tempdir().unwrap();
+        let env = temp.path().join("venv");
+        venv(&env);
+        let archive = temp.path().join("env.tar");
+        snapshot(&env, &archive, None).unwrap();
+
+        let mut data = fs::read(&archive).unwrap();
+        let pos = find(&data, b"X = 1").unwrap();
+        data[pos] = b'Y';
+        fs::write(&archive, data).unwrap();
+        let target = temp.path().join("target");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("keep"), "").unwrap();
+        let err = restore(&archive, &target).unwrap_err();
+        assert!(
+            matches!(err, EnvArchiveError::CorruptObject { .. }),
+            "{err}"
+        );
+        assert!(target.join("keep").is_file());
+
+        assert!(matches!(
+            snapshot(&env, &temp.path().join("env.zip"), None),
+            Err(EnvArchiveError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -10,6 +10,9 @@ use std::time::{SystemTime, UNIX_EPOCH};
 struct CacheEntry {
     env: PythonEnv,
     timestamp: u64,
+    /// `.python-version` pin in effect when the entry was cached.
+    #[serde(default)]
+    pin: Option<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -43,10 +46,12 @@ impl EnvCache {
         Ok(())
     }
 
-    pub fn get(&self, cwd: &Path) -> Option<PythonEnv> {
+    /// The cached environment of `cwd`, unless it is gone or was picked
+    /// under a different `.python-version` pin.
+    pub fn get(&self, cwd: &Path, pin: Option<&str>) -> Option<PythonEnv> {
         if let Some(entry) = self.entries.get(cwd) {
             // Check existence
-            if entry.env.python_path.exists() {
+            if entry.env.python_path.exists() && entry.pin.as_deref() == pin {
                 // Invalidate cache if a local venv now exists but cached env is System
                 if matches!(entry.env.source, crate::env::EnvSource::System) {
                     // Check for local venvs that would take priority
@@ -65,7 +70,7 @@ impl EnvCache {
         None
     }
 
-    pub fn put(&mut self, cwd: &Path, env: &PythonEnv) {
+    pub fn put(&mut self, cwd: &Path, env: &PythonEnv, pin: Option<&str>) {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
@@ -76,6 +81,7 @@ impl EnvCache {
             CacheEntry {
                 env: env.clone(),
                 timestamp,
+                pin: pin.map(str::to_string),
             },
         );
     }
@@ -0,0 +1,474 @@
+//! Environment hygiene pass (`pybun env clean`).
+//!
+//! Interrupted installs and manual deletions leave debris in virtual
+//! environments. This module finds and removes:
+//!
+//! - orphaned bytecode: `__pycache__/<module>.<tag>.pyc` whose `<module>.py`
+//!   no longer exists (and `__pycache__` directories left empty),
+//! - broken `.dist-info` directories that have no `RECORD` file,
+//! - dangling entry-point scripts in `bin/` (`Scripts\` on Windows) that are
+//!   not owned by any installed distribution and import a module that is
+//!   no longer installed.
+//!
+//! Anything suspicious that cannot be cleaned safely (for example a
+//! distribution installed twice, or a `RECORD` listing files that are gone)
+//! is reported as an anomaly for manual attention instead of being touched.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum EnvCleanError {
+    #[error("{0} is not a virtual environment (no pyvenv.cfg)")]
+    NotAVenv(PathBuf),
+    #[error("site-packages directory not found in {0}")]
+    NoSitePackages(PathBuf),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, EnvCleanError>;
+
+/// Kind of debris found in an environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    OrphanedBytecode,
+    EmptyPycache,
+    BrokenDistInfo,
+    DanglingScript,
+}
+
+/// A removable item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Something that looks wrong but is left for the user to resolve.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Anomaly {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Result of a scan (and optional cleanup).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanReport {
+    pub venv: PathBuf,
+    pub site_packages: PathBuf,
+    pub findings: Vec<Finding>,
+    pub anomalies: Vec<Anomaly>,
+    /// Bytes freed (or that would be freed in a dry run).
+    pub reclaimed_bytes: u64,
+    pub dry_run: bool,
+}
+
+/// Scan `venv` and, unless `dry_run`, remove everything found.
+pub fn clean(venv: &Path, dry_run: bool) -> Result<CleanReport> {
+    let mut report = scan(venv)?;
+    report.dry_run = dry_run;
+    if !dry_run {
+        for finding in &report.findings {
+            let result = if finding.path.is_dir() {
+                fs::remove_dir_all(&finding.path)
+            } else {
+                fs::remove_file(&finding.path)
+            };
+            if let Err(e) = result
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                report.anomalies.push(Anomaly {
+                    path: finding.path.clone(),
+                    message: format!("failed to remove: {e}"),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Scan `venv` without modifying it.
+pub fn scan(venv: &Path) -> Result<CleanReport> {
+    if !venv.join("pyvenv.cfg").is_file() {
+        return Err(EnvCleanError::NotAVenv(venv.to_path_buf()));
+    }
+    let site_packages =
+        site_packages_dir(venv).ok_or_else(|| EnvCleanError::NoSitePackages(venv.to_path_buf()))?;
+
+    let mut findings = Vec::new();
+    let mut anomalies = Vec::new();
+    scan_bytecode(&site_packages, &mut findings)?;
+    let owned_scripts = scan_dist_info(&site_packages, &mut findings, &mut anomalies)?;
+    scan_scripts(venv, &site_packages, &owned_scripts, &mut findings)?;
+
+    findings.sort_by(|a, b| a.path.cmp(&b.path));
+    let reclaimed_bytes = findings.iter().map(|f| f.bytes).sum();
+    Ok(CleanReport {
+        venv: venv.to_path_buf(),
+        site_packages,
+        findings,
+        anomalies,
+        reclaimed_bytes,
+        dry_run: true,
+    })
+}
+
+pub(crate) fn site_packages_dir(venv: &Path) -> Option<PathBuf> {
+    let windows = venv.join("Lib").join("site-packages");
+    if windows.is_dir() {
+        return Some(windows);
+    }
+    fs::read_dir(venv.join("lib"))
+        .ok()?
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            // `python3.13t` for free-threaded CPython, `pypy3.10` for PyPy.
+            name.starts_with("python") || name.starts_with("pypy")
+        })
+        .map(|e| e.path().join("site-packages"))
+        .find(|p| p.is_dir())
+}
+
+fn scripts_dir(venv: &Path) -> PathBuf {
+    let windows = venv.join("Scripts");
+    if windows.is_dir() {
+        windows
+    } else {
+        venv.join("bin")
+    }
+}
+
+fn size_of(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| size_of(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Walk site-packages looking for `__pycache__` directories.
+fn scan_bytecode(dir: &Path, findings: &mut Vec<Finding>) -> Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if !file_type.is_dir() {
+            continue;
+        }
+        if entry.file_name() == "__pycache__" {
+            scan_pycache(&path, findings)?;
+        } else {
+            scan_bytecode(&path, findings)?;
+        }
+    }
+    Ok(())
+}
+
+fn scan_pycache(pycache: &Path, findings: &mut Vec<Finding>) -> Result<()> {
+    let Some(source_dir) = pycache.parent() else {
+        return Ok(());
+    };
+    let mut orphaned = Vec::new();
+    let mut total = 0usize;
+    for entry in fs::read_dir(pycache)?.flatten() {
+        total += 1;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(stem) = name
+            .strip_suffix(".pyc")
+            .and_then(|rest| rest.split('.').next())
+        else {
+            continue;
+        };
+        if !source_dir.join(format!("{stem}.py")).exists() {
+            orphaned.push(entry.path());
+        }
+    }
+
+    if total == 0 || orphaned.len() == total {
+        // Every entry is orphaned (or there are none): drop the directory.
+        findings.push(Finding {
+            kind: if total == 0 {
+                FindingKind::EmptyPycache
+            } else {
+                FindingKind::OrphanedBytecode
+            },
+            bytes: size_of(pycache),
+            path: pycache.to_path_buf(),
+        });
+    } else {
+        findings.extend(orphaned.into_iter().map(|path| Finding {
+            kind: FindingKind::OrphanedBytecode,
+            bytes: size_of(&path),
+            path,
+        }));
+    }
+    Ok(())
+}
+
+/// Check `.dist-info` directories; returns the script names owned by
+/// installed distributions (from their RECORD files).
+fn scan_dist_info(
+    site_packages: &Path,
+    findings: &mut Vec<Finding>,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<HashSet<String>> {
+    let mut owned_scripts = HashSet::new();
+    let mut projects: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for entry in fs::read_dir(site_packages)?.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(stem) = name.strip_suffix(".dist-info") else {
+            continue;
+        };
+        if !path.is_dir() {
+            continue;
+        }
+        let record = path.join("RECORD");
+        let Ok(content) = fs::read_to_string(&record) else {
+            findings.push(Finding {
+                kind: FindingKind::BrokenDistInfo,
+                bytes: size_of(&path),
+                path,
+            });
+            continue;
+        };
+
+        let project = crate::pypi::normalize_project_name(stem.split('-').next().unwrap_or(stem));
+        projects.entry(project).or_default().push(path.clone());
+
+        let mut missing = 0usize;
+        for line in content.lines() {
+            let Some(file) = line.split(',').next().filter(|f| !f.is_empty()) else {
+                continue;
+            };
+            let file_path = site_packages.join(file);
+            if file.contains("/bin/") || file.contains("/Scripts/") {
+                if let Some(script) = Path::new(file).file_name() {
+                    owned_scripts.insert(script.to_string_lossy().into_owned());
+                }
+            } else if !file.ends_with(".pyc") && !file_path.exists() {
+                missing += 1;
+            }
+        }
+        if missing > 0 {
+            anomalies.push(Anomaly {
+                path: path.clone(),
+                message: format!(
+                    "RECORD lists {missing} missing file(s); reinstall {stem} to repair it"
+                ),
+            });
+        }
+    }
+
+    for (project, dirs) in projects {
+        if dirs.len() > 1 {
+            anomalies.push(Anomaly {
+                path: dirs[0].clone(),
+                message: format!(
+                    "{project} is installed {} times ({}); remove the stale copy",
+                    dirs.len(),
+                    dirs.iter()
+                        .filter_map(|d| d.file_name())
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+        }
+    }
+    Ok(owned_scripts)
+}
+
+/// Flag console scripts generated for entry points whose target module is
+/// gone and which no distribution claims any more.
+fn scan_scripts(
+    venv: &Path,
+    site_packages: &Path,
+    owned: &HashSet<String>,
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let Ok(entries) = fs::read_dir(scripts_dir(venv)) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if owned.contains(&name) || name.starts_with("python") || name.starts_with("activate") {
+            continue;
+        }
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(module) = entry_point_module(&path) else {
+            continue;
+        };
+        if !module_installed(site_packages, &module) {
+            findings.push(Finding {
+                kind: FindingKind::DanglingScript,
+                bytes: size_of(&path),
+                path,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Top-level module imported by a pip/installer-generated console script.
+fn entry_point_module(script: &Path) -> Option<String> {
+    let bytes = fs::read(script).ok()?;
+    if bytes.len() > 16 * 1024 {
+        return None;
+    }
+    let text = String::from_utf8(bytes).ok()?;
+    if !text.starts_with("#!") || !text.contains("sys.exit(") {
+        return None;
+    }
+    text.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("from ")?;
+        let (module, _) = rest.split_once(" import ")?;
+        let top = module.split('.').next()?.trim();
+        (!top.is_empty()).then(|| top.to_string())
+    })
+}
+
+fn module_installed(site_packages: &Path, module: &str) -> bool {
+    if site_packages.join(module).is_dir() || site_packages.join(format!("{module}.py")).exists() {
+        return true;
+    }
+    // Extension modules: `<module>.cpython-312-x86_64-linux-gnu.so`, `.pyd`.
+    fs::read_dir(site_packages)
+        .map(|entries| {
+            entries.flatten().any(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                name.starts_with(&format!("{module}."))
+                    && (name.ends_with(".so") || name.ends_with(".pyd"))
+            })
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn fake_venv(root: &Path) -> PathBuf {
+        let venv = root.join("venv");
+        fs::create_dir_all(venv.join("lib/python3.12/site-packages")).unwrap();
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        venv
+    }
+
+    fn console_script(module: &str) -> String {
+        format!(
+            "#!/venv/bin/python\nimport re\nimport sys\nfrom {module}.cli import main\n\
+             if __name__ == '__main__':\n    sys.exit(main())\n"
+        )
+    }
+
+    #[test]
+    fn finds_orphaned_bytecode() {
+        let temp = tempdir().unwrap();
+        let venv = fake_venv(temp.path());
+        let sp = venv.join("lib/python3.12/site-packages");
+        fs::create_dir_all(sp.join("pkg/__pycache__")).unwrap();
+        fs::write(sp.join("pkg/live.py"), "").unwrap();
+        fs::write(sp.join("pkg/__pycache__/live.cpython-312.pyc"), "x").unwrap();
+        fs::write(sp.join("pkg/__pycache__/gone.cpython-312.pyc"), "xx").unwrap();
+        fs::create_dir_all(sp.join("removed/__pycache__")).unwrap();
+        fs::write(sp.join("removed/__pycache__/mod.cpython-312.pyc"), "xxx").unwrap();
+
+        let report = scan(&venv).unwrap();
+        let paths: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| f.path.strip_prefix(&sp).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("pkg/__pycache__/gone.cpython-312.pyc"),
+                PathBuf::from("removed/__pycache__"),
+            ]
+        );
+        assert_eq!(report.reclaimed_bytes, 5);
+    }
+
+    #[test]
+    fn removes_broken_dist_info_and_dangling_scripts() {
+        let temp = tempdir().unwrap();
+        let venv = fake_venv(temp.path());
+        let sp = venv.join("lib/python3.12/site-packages");
+        fs::create_dir_all(sp.join("broken-1.0.dist-info")).unwrap();
+        fs::write(sp.join("broken-1.0.dist-info/METADATA"), "Name: broken").unwrap();
+
+        fs::create_dir_all(sp.join("tool")).unwrap();
+        fs::write(sp.join("tool/__init__.py"), "").unwrap();
+        fs::create_dir_all(sp.join("tool-2.0.dist-info")).unwrap();
+        fs::write(
+            sp.join("tool-2.0.dist-info/RECORD"),
+            "tool/__init__.py,,\n../../../bin/tool,,\n",
+        )
+        .unwrap();
+        fs::write(venv.join("bin/tool"), console_script("tool")).unwrap();
+        fs::write(venv.join("bin/ghost"), console_script("ghost")).unwrap();
+        fs::write(venv.join("bin/python"), "#!/bin/sh\n").unwrap();
+
+        let report = clean(&venv, false).unwrap();
+        let kinds: Vec<_> = report.findings.iter().map(|f| f.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![FindingKind::DanglingScript, FindingKind::BrokenDistInfo]
+        );
+        assert!(!venv.join("bin/ghost").exists());
+        assert!(venv.join("bin/tool").exists());
+        assert!(!sp.join("broken-1.0.dist-info").exists());
+        assert!(report.anomalies.is_empty());
+    }
+
+    #[test]
+    fn dry_run_leaves_files_and_reports_anomalies() {
+        let temp = tempdir().unwrap();
+        let venv = fake_venv(temp.path());
+        let sp = venv.join("lib/python3.12/site-packages");
+        for dist in ["dup-1.0.dist-info", "dup-2.0.dist-info"] {
+            fs::create_dir_all(sp.join(dist)).unwrap();
+            fs::write(sp.join(dist).join("RECORD"), "dup/missing.py,,\n").unwrap();
+        }
+        fs::create_dir_all(sp.join("empty/__pycache__")).unwrap();
+
+        let report = clean(&venv, true).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].kind, FindingKind::EmptyPycache);
+        assert!(sp.join("empty/__pycache__").exists());
+        // Two missing-file anomalies plus the duplicate install.
+        assert_eq!(report.anomalies.len(), 3);
+        assert!(
+            report
+                .anomalies
+                .iter()
+                .any(|a| a.message.contains("installed 2 times"))
+        );
+    }
+
+    #[test]
+    fn rejects_non_venv() {
+        let temp = tempdir().unwrap();
+        assert!(matches!(scan(temp.path()), Err(EnvCleanError::NotAVenv(_))));
+    }
+}
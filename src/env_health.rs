@@ -0,0 +1,646 @@
+//! Deep environment checks for `pybun doctor`.
+//!
+//! Finding an interpreter says little about whether the project environment
+//! still works. These checks inspect the environment itself, each reported
+//! as its own doctor check with a status of its own:
+//!
+//! - `venv_links`: `pyvenv.cfg`'s `home` and the interpreter symlinks in
+//!   `bin/` still resolve (they dangle after the base Python is upgraded or
+//!   removed);
+//! - `record_hashes`: installed files match the sha256 recorded in each
+//!   distribution's `RECORD`;
+//! - `extension_libraries`: the shared libraries extension modules link
+//!   against can be found (`ldd` on Linux, `otool -L` on macOS);
+//! - `duplicate_distributions`: no project is installed twice;
+//! - `lock_drift`: installed versions match `pybun.lockb`.
+//!
+//! Nothing is modified and no interpreter is started.
+
+use crate::dist_info::{self, Distribution};
+use crate::lockfile::Lockfile;
+use crate::pep440::Pep440Version;
+use crate::pypi::normalize_project_name;
+use crate::schema::Diagnostic;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    /// Nothing wrong, but the check could not run fully (or at all).
+    Info,
+    Warning,
+    Error,
+}
+
+/// One problem a check found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(status: Status, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            package: None,
+            path: None,
+            message: message.into(),
+        }
+    }
+
+    fn package(mut self, package: &str) -> Self {
+        self.package = Some(package.to_string());
+        self
+    }
+
+    fn path(mut self, path: &Path) -> Self {
+        self.path = Some(path.display().to_string());
+        self
+    }
+}
+
+/// The result of one check, shaped like the other doctor checks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    /// The most severe finding's status, or the check's own when it did not
+    /// run.
+    pub status: Status,
+    pub message: String,
+    /// Diagnostic code reported when the status is a warning or an error.
+    #[serde(skip)]
+    pub code: &'static str,
+    pub findings: Vec<Finding>,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, code: &'static str, findings: Vec<Finding>, ok: &str) -> Self {
+        let status = findings
+            .iter()
+            .map(|finding| finding.status)
+            .max()
+            .unwrap_or(Status::Ok);
+        let message = match findings.len() {
+            0 => ok.to_string(),
+            1 => findings[0].message.clone(),
+            n => format!("{} (and {} more)", findings[0].message, n - 1),
+        };
+        Self {
+            name,
+            status,
+            message,
+            code,
+            findings,
+        }
+    }
+
+    fn skipped(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Info,
+            message: message.into(),
+            code: "",
+            findings: Vec::new(),
+        }
+    }
+
+    /// The diagnostic for a failing check; `None` when it passed.
+    pub fn diagnostic(&self) -> Option<Diagnostic> {
+        let diagnostic = match self.status {
+            Status::Error => Diagnostic::error(self.message.clone()),
+            Status::Warning => Diagnostic::warning(self.message.clone()),
+            Status::Ok | Status::Info => return None,
+        };
+        Some(
+            diagnostic
+                .with_code(self.code)
+                .with_context(serde_json::json!({
+                    "check": self.name,
+                    "findings": self.findings,
+                })),
+        )
+    }
+}
+
+/// Run every check against `venv`. `lock` is the project's lockfile, if any.
+pub fn run(venv: &Path, lock: Option<&Lockfile>) -> Vec<HealthCheck> {
+    let site_packages = crate::env_clean::site_packages_dir(venv);
+    let installed = site_packages
+        .as_deref()
+        .map(dist_info::installed)
+        .unwrap_or_default();
+    let mut checks = vec![venv_links(venv)];
+    match &site_packages {
+        Some(site_packages) => {
+            checks.push(record_hashes(site_packages, &installed));
+            checks.push(extension_libraries(site_packages));
+        }
+        None => {
+            let message = format!("No site-packages directory in {}", venv.display());
+            checks.push(HealthCheck::skipped("record_hashes", message.clone()));
+            checks.push(HealthCheck::skipped("extension_libraries", message));
+        }
+    }
+    checks.push(duplicate_distributions(&installed));
+    checks.push(match lock {
+        Some(lock) => lock_drift(lock, &installed),
+        None => HealthCheck::skipped("lock_drift", "No pybun.lockb to compare against"),
+    });
+    checks
+}
+
+/// `home` in `pyvenv.cfg` and the symlinks in `bin/` (`Scripts\`) must
+/// resolve. A dangling interpreter link is an error: nothing in the
+/// environment can run.
+pub fn venv_links(venv: &Path) -> HealthCheck {
+    let mut findings = Vec::new();
+    let cfg = venv.join("pyvenv.cfg");
+    if !cfg.is_file() {
+        findings.push(
+            Finding::new(
+                Status::Warning,
+                format!("{} has no pyvenv.cfg", venv.display()),
+            )
+            .path(&cfg),
+        );
+    } else if let Some(home) = crate::env::venv_home(venv)
+        && !home.is_dir()
+    {
+        findings.push(
+            Finding::new(
+                Status::Error,
+                format!(
+                    "Base interpreter directory {} from pyvenv.cfg no longer exists",
+                    home.display()
+                ),
+            )
+            .path(&cfg),
+        );
+    }
+
+    let scripts = ["bin", "Scripts"]
+        .iter()
+        .map(|dir| venv.join(dir))
+        .find(|dir| dir.is_dir());
+    let mut entries: Vec<PathBuf> = scripts
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    entries.sort();
+    for path in entries {
+        let is_link = fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink());
+        if !is_link || fs::metadata(&path).is_ok() {
+            continue;
+        }
+        let target = fs::read_link(&path)
+            .map(|target| target.display().to_string())
+            .unwrap_or_default();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let status = if name.starts_with("python") {
+            Status::Error
+        } else {
+            Status::Warning
+        };
+        findings.push(
+            Finding::new(
+                status,
+                format!("{} points to missing {}", path.display(), target),
+            )
+            .path(&path),
+        );
+    }
+    HealthCheck::new(
+        "venv_links",
+        "E_DOCTOR_BROKEN_VENV",
+        findings,
+        "Interpreter links resolve",
+    )
+}
+
+/// One `RECORD` row: the path and its `sha256=` digest, when it has one.
+fn record_entry(line: &str) -> Option<(String, &str)> {
+    let mut fields = line.trim_end().rsplitn(3, ',');
+    let _size = fields.next()?;
+    let hash = fields.next()?.strip_prefix("sha256=")?;
+    let path = fields.next()?;
+    let path = match path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => path.to_string(),
+    };
+    (!hash.is_empty()).then_some((path, hash))
+}
+
+fn record_digest(path: &Path) -> std::io::Result<String> {
+    Ok(URL_SAFE_NO_PAD.encode(Sha256::digest(fs::read(path)?)))
+}
+
+/// Every file a distribution's `RECORD` lists with a hash must still exist
+/// and hash to it. Files edited in place or removed behind the installer's
+/// back are warnings: the package may still import.
+pub fn record_hashes(site_packages: &Path, installed: &[Distribution]) -> HealthCheck {
+    let mut findings = Vec::new();
+    let mut verified = 0usize;
+    for dist in installed {
+        let Ok(record) = fs::read_to_string(dist.path.join("RECORD")) else {
+            continue;
+        };
+        for (relative, expected) in record.lines().filter_map(record_entry) {
+            let path = site_packages.join(&relative);
+            match record_digest(&path) {
+                Ok(actual) if actual == expected => verified += 1,
+                Ok(_) => findings.push(
+                    Finding::new(
+                        Status::Warning,
+                        format!("{relative} of {} was modified after install", dist.name),
+                    )
+                    .package(&dist.name)
+                    .path(&path),
+                ),
+                Err(_) => findings.push(
+                    Finding::new(
+                        Status::Warning,
+                        format!("{relative} of {} is missing", dist.name),
+                    )
+                    .package(&dist.name)
+                    .path(&path),
+                ),
+            }
+        }
+    }
+    HealthCheck::new(
+        "record_hashes",
+        "W_DOCTOR_RECORD_MISMATCH",
+        findings,
+        &format!("{verified} recorded files match their hashes"),
+    )
+}
+
+fn extension_modules(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            extension_modules(&path, out);
+        } else if file_type.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext == "so" || ext == "dylib")
+        {
+            out.push(path);
+        }
+    }
+}
+
+/// Libraries `ldd` reports as `not found`.
+fn ldd_missing(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| line.contains("=> not found"))
+        .filter_map(|line| line.split("=>").next())
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+/// Absolute or `@loader_path` install names from `otool -L` that do not
+/// exist. System libraries live in the dyld shared cache rather than on
+/// disk, and `@rpath` needs the loading binary's search path, so both are
+/// skipped.
+fn otool_missing(output: &str, module: &Path) -> Vec<String> {
+    let loader = module.parent().unwrap_or(Path::new(""));
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split(" (").next())
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter(|name| !name.starts_with("/usr/lib/") && !name.starts_with("/System/"))
+        .filter(|name| {
+            let path = match name.strip_prefix("@loader_path/") {
+                Some(relative) => loader.join(relative),
+                None if name.starts_with('/') => PathBuf::from(name),
+                None => return false,
+            };
+            !path.exists()
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Every extension module's shared libraries must be loadable. Skipped
+/// (`info`) where neither `ldd` nor `otool` applies or is installed.
+pub fn extension_libraries(site_packages: &Path) -> HealthCheck {
+    const NAME: &str = "extension_libraries";
+    let tool = if cfg!(target_os = "linux") {
+        "ldd"
+    } else if cfg!(target_os = "macos") {
+        "otool"
+    } else {
+        return HealthCheck::skipped(NAME, "Shared library probing is not supported here");
+    };
+    let mut modules = Vec::new();
+    extension_modules(site_packages, &mut modules);
+    modules.sort();
+
+    let mut findings = Vec::new();
+    for module in &modules {
+        let output = match tool {
+            "ldd" => Command::new("ldd").arg(module).output(),
+            _ => Command::new("otool").arg("-L").arg(module).output(),
+        };
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                return HealthCheck::skipped(NAME, format!("Could not run {tool}: {e}"));
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let missing = match tool {
+            "ldd" => ldd_missing(&stdout),
+            _ => otool_missing(&stdout, module),
+        };
+        let relative = module.strip_prefix(site_packages).unwrap_or(module);
+        for library in missing {
+            findings.push(
+                Finding::new(
+                    Status::Error,
+                    format!(
+                        "{} needs {library}, which cannot be found",
+                        relative.display()
+                    ),
+                )
+                .path(module),
+            );
+        }
+    }
+    HealthCheck::new(
+        NAME,
+        "E_DOCTOR_MISSING_SHARED_LIBRARY",
+        findings,
+        &format!(
+            "Shared libraries of {} extension module(s) resolve",
+            modules.len()
+        ),
+    )
+}
+
+/// A project with two `.dist-info` directories: imports get whichever
+/// files were written last, and uninstalling one breaks the other.
+pub fn duplicate_distributions(installed: &[Distribution]) -> HealthCheck {
+    let mut by_name: BTreeMap<String, Vec<&Distribution>> = BTreeMap::new();
+    for dist in installed {
+        by_name
+            .entry(normalize_project_name(&dist.name))
+            .or_default()
+            .push(dist);
+    }
+    let findings = by_name
+        .values()
+        .filter(|dists| dists.len() > 1)
+        .map(|dists| {
+            let versions: Vec<&str> = dists.iter().map(|dist| dist.version.as_str()).collect();
+            Finding::new(
+                Status::Error,
+                format!(
+                    "{} is installed {} times ({})",
+                    dists[0].name,
+                    dists.len(),
+                    versions.join(", ")
+                ),
+            )
+            .package(&dists[0].name)
+        })
+        .collect();
+    HealthCheck::new(
+        "duplicate_distributions",
+        "E_DOCTOR_DUPLICATE_DISTRIBUTION",
+        findings,
+        "No distribution is installed twice",
+    )
+}
+
+fn same_version(a: &str, b: &str) -> bool {
+    match (Pep440Version::parse(a), Pep440Version::parse(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Locked packages that are not installed, or installed at another version.
+/// Installed packages the lock does not mention (installers, the project
+/// itself) are not drift.
+pub fn lock_drift(lock: &Lockfile, installed: &[Distribution]) -> HealthCheck {
+    let installed: BTreeMap<String, &Distribution> = installed
+        .iter()
+        .map(|dist| (normalize_project_name(&dist.name), dist))
+        .collect();
+    let mut findings = Vec::new();
+    for package in lock.packages.values() {
+        match installed.get(&normalize_project_name(&package.name)) {
+            None => findings.push(
+                Finding::new(
+                    Status::Warning,
+                    format!(
+                        "{} {} is locked but not installed",
+                        package.name, package.version
+                    ),
+                )
+                .package(&package.name),
+            ),
+            Some(dist) if !same_version(&dist.version, &package.version) => findings.push(
+                Finding::new(
+                    Status::Warning,
+                    format!(
+                        "{} {} is installed but {} is locked",
+                        package.name, dist.version, package.version
+                    ),
+                )
+                .package(&package.name),
+            ),
+            Some(_) => {}
+        }
+    }
+    HealthCheck::new(
+        "lock_drift",
+        "W_DOCTOR_LOCK_DRIFT",
+        findings,
+        &format!(
+            "All {} locked package(s) are installed",
+            lock.packages.len()
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Package, PackageSource};
+    use tempfile::tempdir;
+
+    fn dist_info(site_packages: &Path, name: &str, version: &str, record: &str) -> Distribution {
+        let dir = site_packages.join(format!("{name}-{version}.dist-info"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("RECORD"), record).unwrap();
+        Distribution::from_path(&dir).unwrap()
+    }
+
+    fn hash(content: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(content))
+    }
+
+    #[test]
+    fn record_hashes_flag_modified_and_missing_files() {
+        let temp = tempdir().unwrap();
+        let site = temp.path();
+        fs::create_dir_all(site.join("demo")).unwrap();
+        fs::write(site.join("demo/__init__.py"), "x = 1\n").unwrap();
+        fs::write(site.join("demo/core.py"), "edited\n").unwrap();
+        let record = format!(
+            "demo/__init__.py,sha256={},6\ndemo/core.py,sha256={},9\n\"demo/a,b.py\",sha256={},0\ndemo/__init__.pyc,,\ndemo-1.0.dist-info/RECORD,,\n",
+            hash(b"x = 1\n"),
+            hash(b"original\n"),
+            hash(b""),
+        );
+        let installed = vec![dist_info(site, "demo", "1.0", &record)];
+
+        let check = record_hashes(site, &installed);
+        assert_eq!(check.status, Status::Warning);
+        let messages: Vec<&str> = check.findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "demo/core.py of demo was modified after install",
+                "demo/a,b.py of demo is missing",
+            ]
+        );
+        let diagnostic = check.diagnostic().unwrap();
+        assert_eq!(diagnostic.code.as_deref(), Some("W_DOCTOR_RECORD_MISMATCH"));
+        assert_eq!(diagnostic.level, crate::schema::DiagnosticLevel::Warning);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_interpreter_link_and_missing_home_are_errors() {
+        let temp = tempdir().unwrap();
+        let venv = temp.path();
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        fs::write(
+            venv.join("pyvenv.cfg"),
+            "home = /nonexistent/python3.11/bin\n",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(
+            "/nonexistent/python3.11/bin/python3",
+            venv.join("bin/python"),
+        )
+        .unwrap();
+        fs::write(venv.join("bin/activate"), "").unwrap();
+
+        let check = venv_links(venv);
+        assert_eq!(check.status, Status::Error);
+        assert_eq!(check.findings.len(), 2);
+        assert!(
+            check.findings[0]
+                .message
+                .contains("/nonexistent/python3.11/bin")
+        );
+        assert!(
+            check.findings[1]
+                .message
+                .contains("bin/python points to missing")
+        );
+
+        fs::write(
+            venv.join("pyvenv.cfg"),
+            format!("home = {}\n", venv.display()),
+        )
+        .unwrap();
+        fs::remove_file(venv.join("bin/python")).unwrap();
+        assert_eq!(venv_links(venv).status, Status::Ok);
+    }
+
+    #[test]
+    fn duplicates_and_lock_drift() {
+        let temp = tempdir().unwrap();
+        let site = temp.path();
+        let installed = vec![
+            dist_info(site, "Requests", "2.31.0", ""),
+            dist_info(site, "requests", "2.32.0", ""),
+            dist_info(site, "idna", "3.7.0", ""),
+            dist_info(site, "pip", "24.0", ""),
+        ];
+        let duplicates = duplicate_distributions(&installed);
+        assert_eq!(duplicates.status, Status::Error);
+        assert_eq!(
+            duplicates.message,
+            "Requests is installed 2 times (2.31.0, 2.32.0)"
+        );
+
+        let package = |name: &str, version: &str| Package {
+            name: name.into(),
+            version: version.into(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: format!("sha256:{}", "ab".repeat(32)),
+            dependencies: Vec::new(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        };
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        lock.add_package(package("idna", "3.7"));
+        lock.add_package(package("urllib3", "2.2.0"));
+        let drift = lock_drift(&lock, &installed[2..]);
+        assert_eq!(drift.status, Status::Warning);
+        assert_eq!(drift.message, "urllib3 2.2.0 is locked but not installed");
+
+        let clean = lock_drift(
+            &lock,
+            &[
+                installed[2].clone(),
+                dist_info(site, "urllib3", "2.2.0", ""),
+            ],
+        );
+        assert_eq!(clean.status, Status::Ok);
+        assert!(clean.diagnostic().is_none());
+    }
+
+    #[test]
+    fn parses_missing_libraries_from_ldd_and_otool() {
+        let ldd = "\tlinux-vdso.so.1 (0x00007ffc)\n\tlibopenblas.so.0 => not found\n\tlibc.so.6 => /lib/x86_64-linux-gnu/libc.so.6 (0x00007f)\n";
+        assert_eq!(ldd_missing(ldd), ["libopenblas.so.0"]);
+
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("libpresent.dylib"), "").unwrap();
+        let module = temp.path().join("_ext.so");
+        let otool = "_ext.so:\n\t@loader_path/libpresent.dylib (compatibility version 1.0.0)\n\t@loader_path/libgone.dylib (compatibility version 1.0.0)\n\t@rpath/libx.dylib (compatibility version 1.0.0)\n\t/usr/lib/libSystem.B.dylib (compatibility version 1.0.0)\n\t/opt/homebrew/lib/libgone.dylib (compatibility version 1.0.0)\n";
+        assert_eq!(
+            otool_missing(otool, &module),
+            [
+                "@loader_path/libgone.dylib",
+                "/opt/homebrew/lib/libgone.dylib"
+            ]
+        );
+    }
+}
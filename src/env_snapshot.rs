@@ -0,0 +1,677 @@
+//! Chunked environment snapshots in a remote cache.
+//!
+//! `pybun env push` splits every file of a virtual environment into
+//! content-defined chunks ([`crate::chunking`]) and stores them by SHA-256 in
+//! the remote cache of `pybun cache push` ([`crate::remote_cache`]: HTTP(S),
+//! S3 or a directory), next to a manifest describing the tree:
+//!
+//! ```text
+//! <remote>/chunks/<id[..2]>/<id>
+//! <remote>/snapshots/<name>.json
+//! ```
+//!
+//! Only chunks the remote does not hold yet are uploaded, so pushing an
+//! environment again after a small change transfers only the changed chunks,
+//! and environments of different projects share their common chunks.
+//! `pybun env pull` likewise fetches only the chunks missing from the local
+//! chunk store ([`crate::cache::Cache::chunks_dir`]) and then rebuilds the
+//! tree.
+//!
+//! Transfers are resumable: each chunk is stored whole or not at all, and a
+//! push writes its manifest last. Re-running an interrupted push or pull
+//! skips the chunks that made it.
+
+use crate::chunking::{Chunker, chunk_id};
+use crate::fsutil::{PathIoError, manifest_key, write_atomic};
+use crate::remote_cache::{self, CONCURRENCY, Remote, RemoteCacheError};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid snapshot manifest {location}: {source}")]
+    Manifest {
+        location: String,
+        source: serde_json::Error,
+    },
+    #[error("snapshot '{name}' not found in {remote}")]
+    NotFound { name: String, remote: String },
+    #[error("chunk {id} is missing from {remote}")]
+    MissingChunk { id: String, remote: String },
+    #[error("chunk {id} at {location} does not match its hash")]
+    CorruptChunk { id: String, location: String },
+    #[error("invalid path in snapshot manifest: {0}")]
+    UnsafePath(String),
+    #[error(transparent)]
+    Remote(#[from] RemoteCacheError),
+}
+
+pub type Result<T> = std::result::Result<T, SnapshotError>;
+
+impl From<PathIoError> for SnapshotError {
+    fn from(e: PathIoError) -> Self {
+        SnapshotError::Io {
+            path: e.path,
+            source: e.source,
+        }
+    }
+}
+
+pub(crate) fn io_err(path: &Path) -> impl FnOnce(std::io::Error) -> SnapshotError + '_ {
+    move |source| SnapshotError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Content-addressed chunk directory: `<root>/<id[..2]>/<id>`.
+#[derive(Debug, Clone)]
+pub struct ChunkStore {
+    root: PathBuf,
+}
+
+impl ChunkStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.root.join(id.get(..2).unwrap_or(id)).join(id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.path(id).is_file()
+    }
+
+    /// Chunk `id`, checked against its hash.
+    pub fn read(&self, id: &str) -> Result<Vec<u8>> {
+        let path = self.path(id);
+        let data = fs::read(&path).map_err(io_err(&path))?;
+        if chunk_id(&data) != id {
+            return Err(SnapshotError::CorruptChunk {
+                id: id.to_string(),
+                location: path.display().to_string(),
+            });
+        }
+        Ok(data)
+    }
+
+    /// Store `data` as chunk `id`, atomically.
+    pub fn write(&self, id: &str, data: &[u8]) -> Result<()> {
+        let path = self.path(id);
+        Ok(write_atomic(&path, data)?)
+    }
+}
+
+/// Remote key of chunk `id`.
+fn chunk_key(id: &str) -> String {
+    format!("chunks/{}/{id}", id.get(..2).unwrap_or(id))
+}
+
+/// Remote key of snapshot `name`'s manifest.
+fn snapshot_key(name: &str) -> String {
+    manifest_key("snapshots", name)
+}
+
+/// A snapshot: the environment's tree with each file as a list of chunks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    /// Unix timestamp (seconds) of the push.
+    pub created: u64,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Path relative to the environment root, `/`-separated.
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: EntryKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum EntryKind {
+    Dir,
+    File {
+        size: u64,
+        #[serde(default)]
+        executable: bool,
+        chunks: Vec<String>,
+    },
+    Symlink {
+        target: String,
+    },
+}
+
+/// What a push or pull moved, and what it could skip.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransferReport {
+    pub name: String,
+    pub remote: String,
+    pub files: usize,
+    /// Size of all file contents in the snapshot.
+    pub total_bytes: u64,
+    pub chunks: usize,
+    pub transferred_chunks: usize,
+    pub transferred_bytes: u64,
+    /// Chunks already at the destination (from earlier transfers, other
+    /// projects, or repeats within this snapshot).
+    pub reused_chunks: usize,
+    pub reused_bytes: u64,
+    /// Share of `total_bytes` not transferred, in percent.
+    pub savings_percent: f64,
+}
+
+impl TransferReport {
+    fn new(name: &str, remote: &Remote) -> Self {
+        Self {
+            name: name.to_string(),
+            remote: remote.url.clone(),
+            files: 0,
+            total_bytes: 0,
+            chunks: 0,
+            transferred_chunks: 0,
+            transferred_bytes: 0,
+            reused_chunks: 0,
+            reused_bytes: 0,
+            savings_percent: 0.0,
+        }
+    }
+
+    fn record(&mut self, len: usize, transferred: bool) {
+        let len = len as u64;
+        self.chunks += 1;
+        self.total_bytes += len;
+        if transferred {
+            self.transferred_chunks += 1;
+            self.transferred_bytes += len;
+        } else {
+            self.reused_chunks += 1;
+            self.reused_bytes += len;
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        if self.total_bytes > 0 {
+            let percent = self.reused_bytes as f64 * 100.0 / self.total_bytes as f64;
+            self.savings_percent = (percent * 10.0).round() / 10.0;
+        }
+        self
+    }
+}
+
+/// Upload the environment at `venv` to `remote` as snapshot `name`.
+pub async fn push(venv: &Path, remote: &Remote, name: &str) -> Result<TransferReport> {
+    if !remote.push_allowed {
+        return Err(RemoteCacheError::PushDisabled.into());
+    }
+    let mut report = TransferReport::new(name, remote);
+    let mut entries = Vec::new();
+    walk(venv, venv, &mut entries)?;
+
+    let mut seen = HashSet::new();
+    let mut batch = Vec::new();
+    for entry in &mut entries {
+        let EntryKind::File { chunks, .. } = &mut entry.kind else {
+            continue;
+        };
+        report.files += 1;
+        let path = venv.join(&entry.path);
+        let file = File::open(&path).map_err(io_err(&path))?;
+        let mut chunker = Chunker::new(file);
+        while let Some(chunk) = chunker.next_chunk().map_err(io_err(&path))? {
+            let id = chunk_id(&chunk);
+            chunks.push(id.clone());
+            if !seen.insert(id.clone()) {
+                report.record(chunk.len(), false);
+                continue;
+            }
+            batch.push((id, chunk));
+            if batch.len() == CONCURRENCY {
+                upload(remote, std::mem::take(&mut batch), &mut report).await?;
+            }
+        }
+    }
+    upload(remote, batch, &mut report).await?;
+
+    let manifest = Manifest {
+        name: name.to_string(),
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        entries,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).expect("manifests serialize");
+    remote.put(&snapshot_key(name), json).await?;
+    Ok(report.finish())
+}
+
+/// Store the chunks of `batch` the remote does not hold yet.
+async fn upload(
+    remote: &Remote,
+    batch: Vec<(String, Vec<u8>)>,
+    report: &mut TransferReport,
+) -> Result<()> {
+    let results: Vec<remote_cache::Result<(usize, bool)>> = futures::stream::iter(batch)
+        .map(|(id, chunk)| async move {
+            let key = chunk_key(&id);
+            let len = chunk.len();
+            if remote.exists(&key).await? {
+                return Ok((len, false));
+            }
+            remote.put(&key, chunk).await?;
+            Ok((len, true))
+        })
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
+    for result in results {
+        let (len, transferred) = result?;
+        report.record(len, transferred);
+    }
+    Ok(())
+}
+
+/// Directories, files and symlinks under `dir`, sorted, with empty chunk
+/// lists.
+pub(crate) fn walk(root: &Path, dir: &Path, entries: &mut Vec<Entry>) -> Result<()> {
+    let mut children: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(io_err(dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()
+        .map_err(io_err(dir))?;
+    children.sort();
+    for path in children {
+        let meta = fs::symlink_metadata(&path).map_err(io_err(&path))?;
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if meta.file_type().is_symlink() {
+            let target = fs::read_link(&path).map_err(io_err(&path))?;
+            entries.push(Entry {
+                path: relative,
+                kind: EntryKind::Symlink {
+                    target: target.to_string_lossy().into_owned(),
+                },
+            });
+        } else if meta.is_dir() {
+            entries.push(Entry {
+                path: relative,
+                kind: EntryKind::Dir,
+            });
+            walk(root, &path, entries)?;
+        } else {
+            entries.push(Entry {
+                path: relative,
+                kind: EntryKind::File {
+                    size: meta.len(),
+                    executable: is_executable(&meta),
+                    chunks: Vec::new(),
+                },
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+/// Load snapshot `name` from `remote`.
+pub async fn load_manifest(remote: &Remote, name: &str) -> Result<Manifest> {
+    let key = snapshot_key(name);
+    let Some(data) = remote.get(&key).await? else {
+        return Err(SnapshotError::NotFound {
+            name: name.to_string(),
+            remote: remote.url.clone(),
+        });
+    };
+    serde_json::from_slice(&data).map_err(|source| SnapshotError::Manifest {
+        location: remote.object_url(&key),
+        source,
+    })
+}
+
+/// Download snapshot `name` from `remote` into `target`, fetching only the
+/// chunks missing from `local`. `target` is replaced once the whole tree is
+/// rebuilt.
+pub async fn pull(
+    remote: &Remote,
+    name: &str,
+    target: &Path,
+    local: &ChunkStore,
+) -> Result<TransferReport> {
+    let manifest = load_manifest(remote, name).await?;
+    let mut report = TransferReport::new(name, remote);
+
+    // Fetch first, so an interrupted pull keeps every chunk it got.
+    let mut missing = Vec::new();
+    let mut present = Vec::new();
+    let mut seen = HashSet::new();
+    let links: HashSet<&str> = manifest
+        .entries
+        .iter()
+        .filter(|entry| matches!(entry.kind, EntryKind::Symlink { .. }))
+        .map(|entry| entry.path.as_str())
+        .collect();
+    for entry in &manifest.entries {
+        check_path(&entry.path)?;
+        check_outside_links(&entry.path, &links)?;
+        let EntryKind::File { chunks, .. } = &entry.kind else {
+            continue;
+        };
+        report.files += 1;
+        for id in chunks {
+            if local.contains(id) || !seen.insert(id.as_str()) {
+                present.push(id.as_str());
+            } else {
+                missing.push(id.as_str());
+            }
+        }
+    }
+    let fetched: Vec<Result<usize>> = futures::stream::iter(missing)
+        .map(|id| download(remote, id, local))
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
+    for len in fetched {
+        report.record(len?, true);
+    }
+    for id in present {
+        let len = fs::metadata(local.path(id)).map(|m| m.len()).unwrap_or(0);
+        report.record(len as usize, false);
+    }
+
+    let staging = sibling(target, "pybun-pull");
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging).map_err(io_err(&staging))?;
+    for entry in &manifest.entries {
+        let path = staging.join(&entry.path);
+        match &entry.kind {
+            EntryKind::Dir => fs::create_dir_all(&path).map_err(io_err(&path))?,
+            EntryKind::File {
+                executable, chunks, ..
+            } => {
+                let mut data = Vec::new();
+                for id in chunks {
+                    data.extend(local.read(id)?);
+                }
+                fs::write(&path, data).map_err(io_err(&path))?;
+                if *executable {
+                    set_executable(&path)?;
+                }
+            }
+            EntryKind::Symlink { .. } => {}
+        }
+    }
+    // Links go in last so no file is ever written through one.
+    for entry in &manifest.entries {
+        if let EntryKind::Symlink { target } = &entry.kind {
+            symlink(target, &staging.join(&entry.path))?;
+        }
+    }
+
+    replace_dir(&staging, target)?;
+    Ok(report.finish())
+}
+
+/// Fetch chunk `id` from `remote` into `local`, checked against its hash.
+async fn download(remote: &Remote, id: &str, local: &ChunkStore) -> Result<usize> {
+    let key = chunk_key(id);
+    let data = remote
+        .get(&key)
+        .await?
+        .ok_or_else(|| SnapshotError::MissingChunk {
+            id: id.to_string(),
+            remote: remote.url.clone(),
+        })?;
+    if chunk_id(&data) != id {
+        return Err(SnapshotError::CorruptChunk {
+            id: id.to_string(),
+            location: remote.object_url(&key),
+        });
+    }
+    local.write(id, &data)?;
+    Ok(data.len())
+}
+
+/// Move the fully built `staging` tree to `target`, replacing what was
+/// there. The old tree is restored if the move fails.
+pub(crate) fn replace_dir(staging: &Path, target: &Path) -> Result<()> {
+    let old = sibling(target, "pybun-old");
+    let _ = fs::remove_dir_all(&old);
+    if target.exists() {
+        fs::rename(target, &old).map_err(io_err(target))?;
+    }
+    if let Err(source) = fs::rename(staging, target) {
+        let _ = fs::rename(&old, target);
+        return Err(SnapshotError::Io {
+            path: target.to_path_buf(),
+            source,
+        });
+    }
+    let _ = fs::remove_dir_all(&old);
+    Ok(())
+}
+
+/// Reject absolute paths and `..` in manifest entries.
+pub(crate) fn check_path(path: &str) -> Result<()> {
+    let safe = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if safe {
+        Ok(())
+    } else {
+        Err(SnapshotError::UnsafePath(path.to_string()))
+    }
+}
+
+/// Reject an entry nested under a symlink entry, which would otherwise be
+/// created wherever the link points.
+fn check_outside_links(path: &str, links: &HashSet<&str>) -> Result<()> {
+    if path
+        .match_indices('/')
+        .any(|(end, _)| links.contains(&path[..end]))
+    {
+        return Err(SnapshotError::UnsafePath(path.to_string()));
+    }
+    Ok(())
+}
+
+pub(crate) fn sibling(target: &Path, suffix: &str) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{name}.{suffix}"))
+}
+
+#[cfg(unix)]
+pub(crate) fn set_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).map_err(io_err(path))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn set_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn symlink(target: &str, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link).map_err(io_err(link))
+}
+
+#[cfg(windows)]
+pub(crate) fn symlink(target: &str, link: &Path) -> Result<()> {
+    std::os::windows::fs::symlink_file(target, link).map_err(io_err(link))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn dir_remote(path: &Path) -> Remote {
+        Remote::resolve(Some(&format!("file://{}", path.display())), None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn second_push_only_uploads_changed_chunks_and_pull_restores_the_tree() {
+        let temp = tempdir().unwrap();
+        let venv = temp.path().join("venv");
+        fs::create_dir_all(venv.join("lib/site-packages/pkg")).unwrap();
+        fs::create_dir_all(venv.join("empty")).unwrap();
+        let big = noise(1024 * 1024, 3);
+        fs::write(venv.join("lib/site-packages/pkg/core.so"), &big).unwrap();
+        fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        let remote = dir_remote(&temp.path().join("remote"));
+
+        let first = push(&venv, &remote, "app").await.unwrap();
+        assert_eq!(first.files, 2);
+        assert_eq!(first.transferred_bytes, big.len() as u64 + 16);
+
+        let mut edited = big.clone();
+        edited[500_000] ^= 0xff;
+        fs::write(venv.join("lib/site-packages/pkg/core.so"), &edited).unwrap();
+        let second = push(&venv, &remote, "app").await.unwrap();
+        assert!(second.transferred_chunks <= 2, "{second:?}");
+        assert!(second.savings_percent > 80.0, "{second:?}");
+
+        let local = ChunkStore::new(temp.path().join("local"));
+        let target = temp.path().join("restored");
+        let pulled = pull(&remote, "app", &target, &local).await.unwrap();
+        assert_eq!(pulled.transferred_chunks, pulled.chunks);
+        assert_eq!(
+            fs::read(target.join("lib/site-packages/pkg/core.so")).unwrap(),
+            edited
+        );
+        assert!(target.join("empty").is_dir());
+
+        let again = pull(&remote, "app", &target, &local).await.unwrap();
+        assert_eq!(again.transferred_chunks, 0);
+        assert_eq!(again.savings_percent, 100.0);
+    }
+
+    #[tokio::test]
+    async fn corrupt_remote_chunks_are_rejected() {
+        let temp = tempdir().unwrap();
+        let venv = temp.path().join("venv");
+        fs::create_dir_all(&venv).unwrap();
+        fs::write(venv.join("a.py"), "print('a')\n").unwrap();
+        let remote = dir_remote(&temp.path().join("r"));
+        push(&venv, &remote, "app").await.unwrap();
+
+        let id = chunk_id(b"print('a')\n");
+        fs::write(remote.object_url(&chunk_key(&id)), "tampered").unwrap();
+        let local = ChunkStore::new(temp.path().join("local"));
+        let err = pull(&remote, "app", &temp.path().join("out"), &local)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SnapshotError::CorruptChunk { .. }), "{err}");
+        assert!(!temp.path().join("out").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn pull_refuses_entries_under_symlinks() {
+        let temp = tempdir().unwrap();
+        let venv = temp.path().join("venv");
+        fs::create_dir_all(&venv).unwrap();
+        fs::write(venv.join("x"), "pwned").unwrap();
+        let remote = dir_remote(&temp.path().join("r"));
+        push(&venv, &remote, "app").await.unwrap();
+
+        let outside = temp.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        let hostile = Manifest {
+            name: "evil".into(),
+            created: 0,
+            entries: vec![
+                Entry {
+                    path: "lib".into(),
+                    kind: EntryKind::Symlink {
+                        target: outside.display().to_string(),
+                    },
+                },
+                Entry {
+                    path: "lib/x".into(),
+                    kind: EntryKind::File {
+                        size: 5,
+                        executable: false,
+                        chunks: vec![chunk_id(b"pwned")],
+                    },
+                },
+            ],
+        };
+        fs::write(
+            remote.object_url(&snapshot_key("evil")),
+            serde_json::to_vec(&hostile).unwrap(),
+        )
+        .unwrap();
+
+        let local = ChunkStore::new(temp.path().join("local"));
+        let err = pull(&remote, "evil", &temp.path().join("out"), &local)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SnapshotError::UnsafePath(_)), "{err}");
+        assert!(!outside.join("x").exists());
+        assert!(!temp.path().join("out").exists());
+    }
+
+    #[tokio::test]
+    async fn pull_only_remotes_refuse_pushes_and_manifest_paths_are_validated() {
+        let temp = tempdir().unwrap();
+        let config = remote_cache::RemoteConfig {
+            push: Some(false),
+            ..Default::default()
+        };
+        let url = temp.path().join("r").display().to_string();
+        let remote = Remote::resolve(Some(&url), Some(&config)).unwrap();
+        assert!(matches!(
+            push(temp.path(), &remote, "app").await,
+            Err(SnapshotError::Remote(RemoteCacheError::PushDisabled))
+        ));
+        assert!(check_path("lib/x.py").is_ok());
+        assert!(check_path("../x").is_err());
+        assert!(check_path("/etc/passwd").is_err());
+    }
+}
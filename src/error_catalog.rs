@@ -0,0 +1,631 @@
+//! Stable error codes (`PYBUN-RESOLVE-001`) and the catalog behind
+//! `pybun explain`.
+//!
+//! Diagnostics keep their specific `E_*` codes. Each [`ErrorCode`] stands
+//! for one kind of failure and covers the diagnostic codes that report it,
+//! so every error diagnostic with a catalogued code also carries the stable
+//! `error_code`. Codes are never renumbered or reused; a retired failure
+//! keeps its entry.
+
+/// A catalogued failure. The discriminant is the index into the catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    ResolveNoMatch,
+    ResolveConflict,
+    ResolvePythonIncompatible,
+    ResolveIndexUnavailable,
+    InstallDownloadFailed,
+    InstallHashMismatch,
+    InstallWheelFailed,
+    InstallSourceBuildFailed,
+    InstallExternallyManaged,
+    InstallGitFailed,
+    LockMissing,
+    LockDrift,
+    LockTargetUnknown,
+    RunTargetNotFound,
+    RunModuleNotFound,
+    RunPythonIncompatible,
+    RunExitNonzero,
+    RunLimitExceeded,
+    PythonMissing,
+    TestFailed,
+    TestTimeout,
+    TestPluginFailed,
+    TestCoverageBelow,
+    TestCoverageUnavailable,
+    BenchFailed,
+    BenchRegression,
+    NetworkRequired,
+    NetworkPolicy,
+    NetworkAuth,
+    NetworkTlsIntercepted,
+    ConfigInvalid,
+    ConfigOutputUnsupported,
+    EnvBroken,
+    EnvSharedLibraryMissing,
+    BuildFailed,
+    BuildNotReproducible,
+    TaskFailed,
+    OperationTimedOut,
+    PolicyViolation,
+    SuspiciousPackage,
+    PublishFailed,
+    ImportFailed,
+    LockConvertFailed,
+    LockUpdateFailed,
+}
+
+/// What `pybun explain` prints for one code.
+#[derive(Debug)]
+pub struct CatalogEntry {
+    pub code: ErrorCode,
+    /// Stable identifier, `PYBUN-<AREA>-<NNN>`.
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub fixes: &'static [&'static str],
+    /// Diagnostic codes that report this failure.
+    pub diagnostic_codes: &'static [&'static str],
+}
+
+impl ErrorCode {
+    pub fn entry(self) -> &'static CatalogEntry {
+        &CATALOG[self as usize]
+    }
+
+    pub fn as_str(self) -> &'static str {
+        self.entry().id
+    }
+
+    /// Look up a stable id (case-insensitive) or one of the `E_*`
+    /// diagnostic codes it covers.
+    pub fn parse(code: &str) -> Option<Self> {
+        let code = code.trim();
+        CATALOG
+            .iter()
+            .find(|entry| entry.id.eq_ignore_ascii_case(code))
+            .map(|entry| entry.code)
+            .or_else(|| Self::for_diagnostic(code))
+    }
+
+    /// The code covering the diagnostic code `code`, if catalogued.
+    pub fn for_diagnostic(code: &str) -> Option<Self> {
+        CATALOG
+            .iter()
+            .find(|entry| entry.diagnostic_codes.contains(&code))
+            .map(|entry| entry.code)
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Every entry, in code order.
+pub fn catalog() -> &'static [CatalogEntry] {
+    CATALOG
+}
+
+const CATALOG: &[CatalogEntry] = &[
+    CatalogEntry {
+        code: ErrorCode::ResolveNoMatch,
+        id: "PYBUN-RESOLVE-001",
+        title: "No version satisfies a requirement",
+        description: "The resolver found the package on the index, or did not find it at all, but no release matches the requested specifier for the target Python and platforms.",
+        fixes: &[
+            "Check the package name and specifier for typos.",
+            "Relax the version constraint, or request a pre-release explicitly (e.g. `>=2.0rc1`) if only pre-releases match.",
+            "Check that the index (--index, [tool.pybun.indexes]) serves the package.",
+        ],
+        diagnostic_codes: &["E_RESOLVE_MISSING"],
+    },
+    CatalogEntry {
+        code: ErrorCode::ResolveConflict,
+        id: "PYBUN-RESOLVE-002",
+        title: "Conflicting requirements",
+        description: "Two or more requirements ask for versions of the same package that cannot be satisfied together. The diagnostic context lists the requirement chains that conflict.",
+        fixes: &[
+            "Run `pybun why <package>` to see which dependencies pull in each constraint.",
+            "Loosen one of the conflicting constraints, or upgrade the package that pins the old version.",
+        ],
+        diagnostic_codes: &["E_RESOLVE_CONFLICT"],
+    },
+    CatalogEntry {
+        code: ErrorCode::ResolvePythonIncompatible,
+        id: "PYBUN-RESOLVE-003",
+        title: "Requirement does not support the target Python",
+        description: "Every candidate release declares a Requires-Python that excludes the interpreter or the Python versions being locked for.",
+        fixes: &[
+            "Install a compatible interpreter with `pybun python install <version>`.",
+            "Adjust `requires-python` in pyproject.toml, or pick an older release of the package.",
+        ],
+        diagnostic_codes: &["E_RESOLVE_PYTHON_INCOMPATIBLE", "E_LOCK_PYTHON_UNSUPPORTED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::ResolveIndexUnavailable,
+        id: "PYBUN-RESOLVE-004",
+        title: "Package index unavailable",
+        description: "Metadata could not be fetched or read from the package index, so resolution stopped before a decision could be made.",
+        fixes: &[
+            "Check network access and proxy settings (PYBUN_PROXY, HTTPS_PROXY).",
+            "Run `pybun doctor` to check index reachability and TLS trust.",
+            "Retry with --offline if the needed metadata is already cached.",
+        ],
+        diagnostic_codes: &["E_RESOLVE_IO", "E_RESOLVE_BACKEND"],
+    },
+    CatalogEntry {
+        code: ErrorCode::InstallDownloadFailed,
+        id: "PYBUN-INSTALL-001",
+        title: "Artifact download failed",
+        description: "A wheel or sdist could not be downloaded. The install is aborted and the per-package results are in the diagnostic context.",
+        fixes: &[
+            "Retry; transient network errors are common on large downloads.",
+            "Check index credentials with `pybun auth status` for private indexes.",
+        ],
+        diagnostic_codes: &["E_INSTALL_DOWNLOAD_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::InstallHashMismatch,
+        id: "PYBUN-INSTALL-002",
+        title: "Artifact hash mismatch",
+        description: "A downloaded artifact does not match the hash recorded in the lockfile, or the lockfile has no hash to verify against. The file is not installed.",
+        fixes: &[
+            "Re-lock with `pybun lock` if the upstream artifact was legitimately re-published.",
+            "Clear the cached artifact with `pybun gc` and retry if the download was corrupted.",
+        ],
+        diagnostic_codes: &["E_INSTALL_HASH_MISMATCH", "E_VERIFY_MISSING_HASH"],
+    },
+    CatalogEntry {
+        code: ErrorCode::InstallWheelFailed,
+        id: "PYBUN-INSTALL-003",
+        title: "Wheel could not be installed",
+        description: "Extracting or installing a wheel into the environment failed, for example because the archive is invalid or site-packages is not writable.",
+        fixes: &[
+            "Check permissions on the environment directory.",
+            "Run `pybun doctor` to check the environment for damage.",
+        ],
+        diagnostic_codes: &["E_INSTALL_WHEEL_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::InstallSourceBuildFailed,
+        id: "PYBUN-INSTALL-004",
+        title: "No wheel and the source build failed",
+        description: "The package has no compatible wheel, so it has to be built from its sdist, and the build was not possible or failed.",
+        fixes: &[
+            "Install the build requirements (compiler, headers) the package documents.",
+            "Pick a version that ships a wheel for this platform.",
+        ],
+        diagnostic_codes: &["E_INSTALL_SDIST_ONLY", "E_INSTALL_SOURCE_BUILD_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::InstallExternallyManaged,
+        id: "PYBUN-INSTALL-005",
+        title: "Interpreter is externally managed",
+        description: "The target interpreter is marked externally-managed (PEP 668), so packages must not be installed into it directly.",
+        fixes: &[
+            "Create a project environment (`pybun install` does this when none exists).",
+            "Use a PyBun-managed interpreter from `pybun python install`.",
+        ],
+        diagnostic_codes: &["E_INSTALL_EXTERNALLY_MANAGED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::InstallGitFailed,
+        id: "PYBUN-INSTALL-006",
+        title: "Git dependency could not be fetched",
+        description: "Cloning or checking out a git dependency failed, or its ref does not exist.",
+        fixes: &[
+            "Check the repository URL and the ref (branch, tag or commit).",
+            "Check git credentials for private repositories.",
+        ],
+        diagnostic_codes: &["E_INSTALL_GIT_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::LockMissing,
+        id: "PYBUN-LOCK-001",
+        title: "Lockfile missing",
+        description: "The command needs pybun.lockb (or a script lock), but none was found.",
+        fixes: &["Run `pybun lock` (or `pybun script lock <script>`) to create it."],
+        diagnostic_codes: &[
+            "E_LOCK_MISSING",
+            "E_LOCKFILE_NOT_FOUND",
+            "E_INSTALL_LOCK_MISSING",
+            "E_SCRIPT_LOCK_MISSING",
+        ],
+    },
+    CatalogEntry {
+        code: ErrorCode::LockDrift,
+        id: "PYBUN-LOCK-002",
+        title: "Lockfile out of date",
+        description: "The dependencies declared in pyproject.toml (or in a script's PEP 723 block) no longer match what the lockfile was created from.",
+        fixes: &["Run `pybun lock` to refresh the lockfile, then commit it."],
+        diagnostic_codes: &["E_LOCK_DRIFT", "E_LOCK_SHARD_DRIFT", "E_SCRIPT_LOCK_DRIFT"],
+    },
+    CatalogEntry {
+        code: ErrorCode::LockTargetUnknown,
+        id: "PYBUN-LOCK-003",
+        title: "Unknown lock target",
+        description: "A Python version or platform requested for locking is not recognised, or the lockfile has no entry for the current target.",
+        fixes: &[
+            "Check the --python and --platform values.",
+            "Re-lock including the current target.",
+        ],
+        diagnostic_codes: &[
+            "E_LOCK_UNKNOWN_PLATFORM",
+            "E_LOCK_UNKNOWN_PYTHON",
+            "E_LOCK_TARGET_REQUIRED",
+            "E_INSTALL_LOCK_TARGET_MISSING",
+        ],
+    },
+    CatalogEntry {
+        code: ErrorCode::RunTargetNotFound,
+        id: "PYBUN-RUN-001",
+        title: "Script or command not found",
+        description: "`pybun run` was given a path that does not exist and no console script of that name is installed in the environment.",
+        fixes: &[
+            "Check the path relative to the current directory.",
+            "Install the package providing the console script, or use `pybun run -m MODULE`.",
+            "Use `pybun run --list` to see project tasks.",
+        ],
+        diagnostic_codes: &["E_RUN_TARGET_NOT_FOUND", "E_X_COMMAND_NOT_FOUND"],
+    },
+    CatalogEntry {
+        code: ErrorCode::RunModuleNotFound,
+        id: "PYBUN-RUN-002",
+        title: "Module not importable",
+        description: "The module to run, or a module imported by the program, is not installed in the environment.",
+        fixes: &[
+            "Add the package with `pybun add <package>`.",
+            "Check that the right environment is selected (`pybun doctor` shows it).",
+        ],
+        diagnostic_codes: &[
+            "E_RUN_MODULE_NOT_FOUND",
+            "E_RUNTIME_MODULE_NOT_FOUND",
+            "E_RUNTIME_IMPORT_ERROR",
+        ],
+    },
+    CatalogEntry {
+        code: ErrorCode::RunPythonIncompatible,
+        id: "PYBUN-RUN-003",
+        title: "Script needs a different Python",
+        description: "The script's `requires-python` does not match the selected interpreter.",
+        fixes: &[
+            "Install a matching interpreter with `pybun python install <version>`.",
+            "Set PYBUN_PYTHON to a compatible interpreter.",
+        ],
+        diagnostic_codes: &["E_RUN_PYTHON_INCOMPATIBLE"],
+    },
+    CatalogEntry {
+        code: ErrorCode::RunExitNonzero,
+        id: "PYBUN-RUN-004",
+        title: "Program exited with an error",
+        description: "The Python program ran and exited with a non-zero status. Its traceback, when there is one, is reported in the diagnostic.",
+        fixes: &[
+            "Read the traceback in the diagnostic; the failure is in the program, not in PyBun.",
+        ],
+        diagnostic_codes: &["E_SCRIPT_EXIT_NONZERO", "E_RUNTIME_EXIT_NONZERO"],
+    },
+    CatalogEntry {
+        code: ErrorCode::RunLimitExceeded,
+        id: "PYBUN-RUN-005",
+        title: "Run limit exceeded",
+        description: "The program was stopped because it exceeded a sandbox time or CPU limit.",
+        fixes: &["Raise the limit (--sandbox-timeout) or make the program finish sooner."],
+        diagnostic_codes: &[
+            "E_SANDBOX_TIMEOUT",
+            "E_SANDBOX_CPU_LIMIT",
+            "E_RUNTIME_TIMEOUT",
+        ],
+    },
+    CatalogEntry {
+        code: ErrorCode::PythonMissing,
+        id: "PYBUN-PYTHON-001",
+        title: "No suitable Python interpreter",
+        description: "No interpreter matching the requested version was found on PATH, in PyBun's managed runtimes, or via PYBUN_PYTHON.",
+        fixes: &[
+            "Install one with `pybun python install <version>`.",
+            "Point PYBUN_PYTHON at an existing interpreter.",
+        ],
+        diagnostic_codes: &["E_MISSING_RUNTIME", "E_DOCTOR_MISSING_PYTHON"],
+    },
+    CatalogEntry {
+        code: ErrorCode::TestFailed,
+        id: "PYBUN-TEST-001",
+        title: "Tests failed",
+        description: "At least one test failed or errored. The failing tests and their output are in the result detail.",
+        fixes: &[
+            "Re-run a failing test on its own with `pybun test -k <name>`.",
+            "Use `pybun test --history` to tell new failures from chronic ones.",
+        ],
+        diagnostic_codes: &["E_TEST_FAILED", "E_TEST_RUN_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::TestTimeout,
+        id: "PYBUN-TEST-002",
+        title: "Test timed out",
+        description: "A test ran longer than the --timeout limit and was stopped.",
+        fixes: &["Raise --timeout, or look for a hang (network access, deadlock) in the test."],
+        diagnostic_codes: &["E_TEST_TIMEOUT"],
+    },
+    CatalogEntry {
+        code: ErrorCode::TestPluginFailed,
+        id: "PYBUN-TEST-003",
+        title: "Test framework plugin failed",
+        description: "A test framework configured under [tool.pybun.test.plugins] exited with an error.",
+        fixes: &["Check the plugin's command and its output in detail.plugins."],
+        diagnostic_codes: &["E_TEST_PLUGIN_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::TestCoverageBelow,
+        id: "PYBUN-TEST-004",
+        title: "Coverage below threshold",
+        description: "Total coverage of the run is below --coverage-fail-under. Per-file coverage and missing lines are in detail.coverage.",
+        fixes: &[
+            "Add tests for the missing lines listed in detail.coverage.files.",
+            "Lower --coverage-fail-under if the threshold is out of date.",
+        ],
+        diagnostic_codes: &["E_TEST_COVERAGE_BELOW"],
+    },
+    CatalogEntry {
+        code: ErrorCode::TestCoverageUnavailable,
+        id: "PYBUN-TEST-005",
+        title: "coverage.py unavailable",
+        description: "`pybun test --coverage` needs coverage.py. It is installed automatically only into a virtual environment (PYBUN_ENV or .pybun/venv); the install failed or the interpreter is not in one.",
+        fixes: &[
+            "Add coverage to the project with `pybun add --group dev coverage`, or install it for the interpreter `pybun test` uses.",
+        ],
+        diagnostic_codes: &["E_TEST_COVERAGE_UNAVAILABLE"],
+    },
+    CatalogEntry {
+        code: ErrorCode::BenchFailed,
+        id: "PYBUN-BENCH-001",
+        title: "Benchmark failed",
+        description: "A benchmark raised an exception, its module failed to import, or a pytest-benchmark style benchmark never called the `benchmark` fixture. The traceback is in the diagnostic's context.",
+        fixes: &["Fix the benchmark, or run it alone with `pybun bench -k NAME` to reproduce."],
+        diagnostic_codes: &["E_BENCH_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::BenchRegression,
+        id: "PYBUN-BENCH-002",
+        title: "Benchmark regressed",
+        description: "`pybun bench --compare` found a benchmark whose median is slower than the baseline by more than the threshold (10% unless --threshold is given).",
+        fixes: &[
+            "Check the change for a performance regression.",
+            "If the slowdown is expected, save a new baseline with `pybun bench --save-baseline NAME`.",
+        ],
+        diagnostic_codes: &["E_BENCH_REGRESSION"],
+    },
+    CatalogEntry {
+        code: ErrorCode::NetworkRequired,
+        id: "PYBUN-NETWORK-001",
+        title: "Network access needed while offline",
+        description: "The command runs offline (--offline or PYBUN_OFFLINE=1) but needs artifacts or metadata that are not cached. The diagnostic lists what is missing.",
+        fixes: &["Run the command once online to fill the cache, or drop --offline."],
+        diagnostic_codes: &["E_NETWORK_REQUIRED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::NetworkPolicy,
+        id: "PYBUN-NETWORK-002",
+        title: "Host blocked by the network allowlist",
+        description: "A request went to a host that is not allowed by [tool.pybun.network].",
+        fixes: &["Add the host to the allowlist if the access is intended."],
+        diagnostic_codes: &["E_NETWORK_POLICY"],
+    },
+    CatalogEntry {
+        code: ErrorCode::NetworkAuth,
+        id: "PYBUN-NETWORK-003",
+        title: "Index authentication failed",
+        description: "The index rejected the request or no credentials were available for it.",
+        fixes: &[
+            "Store credentials with `pybun auth login <url>`.",
+            "Check that the token has not expired.",
+        ],
+        diagnostic_codes: &["E_AUTH_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::NetworkTlsIntercepted,
+        id: "PYBUN-NETWORK-004",
+        title: "TLS connection intercepted",
+        description: "The index presented a certificate signed by an untrusted authority, typically a corporate proxy re-signing traffic.",
+        fixes: &[
+            "Set PYBUN_CA_BUNDLE to the proxy's root certificate.",
+            "Make sure PYBUN_SYSTEM_CERTS is not 0 if the root is in the system store.",
+        ],
+        diagnostic_codes: &["E_DOCTOR_TLS_INTERCEPTED", "E_DOCTOR_HTTP_CONFIG"],
+    },
+    CatalogEntry {
+        code: ErrorCode::ConfigInvalid,
+        id: "PYBUN-CONFIG-001",
+        title: "Invalid configuration",
+        description: "[tool.pybun] or pybun.toml contains an unknown key or a value of the wrong type. Each issue names the key, file and line.",
+        fixes: &["Run `pybun config validate` and fix the reported keys."],
+        diagnostic_codes: &["E_CONFIG_INVALID"],
+    },
+    CatalogEntry {
+        code: ErrorCode::ConfigOutputUnsupported,
+        id: "PYBUN-CONFIG-002",
+        title: "Unsupported output options",
+        description: "The command does not support the requested --format, or --columns names a column the output does not have.",
+        fixes: &["Use --format text or json, and pick --columns from the listed names."],
+        diagnostic_codes: &["E_FORMAT_UNSUPPORTED", "E_UNKNOWN_COLUMN"],
+    },
+    CatalogEntry {
+        code: ErrorCode::EnvBroken,
+        id: "PYBUN-ENV-001",
+        title: "Broken virtual environment",
+        description: "The environment's base interpreter is gone or its interpreter links are dangling, typically after the base Python was upgraded or removed.",
+        fixes: &["Remove the environment directory and run `pybun install` to recreate it."],
+        diagnostic_codes: &["E_DOCTOR_BROKEN_VENV", "E_DOCTOR_DUPLICATE_DISTRIBUTION"],
+    },
+    CatalogEntry {
+        code: ErrorCode::EnvSharedLibraryMissing,
+        id: "PYBUN-ENV-002",
+        title: "Extension module misses a shared library",
+        description: "A compiled extension in the environment links against a shared library that cannot be found on this system.",
+        fixes: &[
+            "Install the system package providing the library.",
+            "Reinstall the package so a wheel matching this system is chosen.",
+        ],
+        diagnostic_codes: &["E_DOCTOR_MISSING_SHARED_LIBRARY"],
+    },
+    CatalogEntry {
+        code: ErrorCode::BuildFailed,
+        id: "PYBUN-BUILD-001",
+        title: "Build failed",
+        description: "Building the project's sdist or wheel failed, or the build backend is not available.",
+        fixes: &[
+            "Check `[build-system]` in pyproject.toml.",
+            "Read the backend output in the diagnostic.",
+        ],
+        diagnostic_codes: &["E_BUILD_FAILED", "E_BUILD_MISSING_BUILD_PKG"],
+    },
+    CatalogEntry {
+        code: ErrorCode::BuildNotReproducible,
+        id: "PYBUN-BUILD-002",
+        title: "Build is not reproducible",
+        description: "Two builds of the same source produced different artifacts.",
+        fixes: &[
+            "Check `detail.reproducible.check.sources` for the likely cause of each difference.",
+            "Set SOURCE_DATE_EPOCH and remove timestamps or random data from the build.",
+            "Pin what the backend reads with `--env KEY=VALUE` or [tool.pybun.build.env].",
+        ],
+        diagnostic_codes: &["E_BUILD_NOT_REPRODUCIBLE"],
+    },
+    CatalogEntry {
+        code: ErrorCode::TaskFailed,
+        id: "PYBUN-TASK-001",
+        title: "Task failed",
+        description: "A task from [tool.pybun.tasks], or one of its dependencies, exited with a non-zero status. Later tasks were not run.",
+        fixes: &["Run the failing task on its own with `pybun run <task>` and read its output."],
+        diagnostic_codes: &["E_TASK_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::OperationTimedOut,
+        id: "PYBUN-OPERATION-001",
+        title: "Operation exceeded --max-duration",
+        description: "The command did not finish within --max-duration and was cancelled.",
+        fixes: &[
+            "Raise --max-duration.",
+            "Pass --on-timeout=detach and poll `pybun status --operation <id>`.",
+        ],
+        diagnostic_codes: &["E_MAX_DURATION_EXCEEDED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::PolicyViolation,
+        id: "PYBUN-POLICY-001",
+        title: "Refused by the execution policy",
+        description: "pybun-policy.toml (or the file named by PYBUN_POLICY) does not allow the index, package or inline code, or the policy file itself is invalid. The diagnostic names the rule and the policy file.",
+        fixes: &[
+            "Check the package name or index URL for typos.",
+            "Change the policy only if the action is intended.",
+        ],
+        diagnostic_codes: &["E_POLICY_VIOLATION"],
+    },
+    CatalogEntry {
+        code: ErrorCode::SuspiciousPackage,
+        id: "PYBUN-POLICY-002",
+        title: "Suspicious new dependency",
+        description: "A dependency new to the project looks like a misspelling of a popular package, was first released recently, or changed all of its maintainers in the latest release.",
+        fixes: &[
+            "Check the package name for typos.",
+            "If the package is intended, re-run with --allow-suspicious or approve it under [packages] approved in pybun-policy.toml.",
+        ],
+        diagnostic_codes: &["E_SUSPICIOUS_PACKAGE"],
+    },
+    CatalogEntry {
+        code: ErrorCode::PublishFailed,
+        id: "PYBUN-PUBLISH-001",
+        title: "Publishing failed",
+        description: "`pybun publish` could not upload a distribution: the file is invalid, no credentials were found, or the repository refused the upload. Files after the first failure are not attempted.",
+        fixes: &[
+            "Run `pybun publish --dry-run` to check the files and which credentials would be used.",
+            "Set PYBUN_PUBLISH_TOKEN or run `pybun auth token set`, or configure trusted publishing for the CI job.",
+            "Re-run with --skip-existing to finish a partial publish.",
+        ],
+        diagnostic_codes: &[
+            "E_PUBLISH_FAILED",
+            "E_PUBLISH_NO_CREDENTIALS",
+            "E_PUBLISH_UPLOAD_FAILED",
+        ],
+    },
+    CatalogEntry {
+        code: ErrorCode::ImportFailed,
+        id: "PYBUN-IMPORT-001",
+        title: "Dependency import failed",
+        description: "`pybun import` could not read the file, did not recognise its format, found no requirements in it, or could not lock the imported requirements. When locking fails, pyproject.toml and pybun.lockb are left as they were.",
+        fixes: &[
+            "Pass a requirements file (`*.txt`, `*.in`), a `Pipfile`, or a pyproject.toml with `[tool.poetry]` dependencies.",
+            "Re-run with --no-lock to write pyproject.toml only, then fix the requirements the resolver rejects.",
+        ],
+        diagnostic_codes: &["E_IMPORT_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::LockConvertFailed,
+        id: "PYBUN-LOCK-004",
+        title: "Lockfile conversion failed",
+        description: "`pybun lock --from` could not read the uv.lock or poetry.lock, did not recognise its format, or found no packages in it. pybun.lockb is left as it was.",
+        fixes: &[
+            "Pass a `uv.lock` or `poetry.lock` written by a current uv or Poetry release.",
+            "Run `pybun lock` without --from to resolve from pyproject.toml instead.",
+        ],
+        diagnostic_codes: &["E_LOCK_FROM_FAILED"],
+    },
+    CatalogEntry {
+        code: ErrorCode::LockUpdateFailed,
+        id: "PYBUN-LOCK-005",
+        title: "Single-package lock update refused",
+        description: "`pybun lock --update-only` could not move the package: the value is not `NAME==VERSION`, the package is not locked, the version is not on the index, or a package that keeps its version (or pyproject.toml) rules out a version the update needs. pybun.lockb is left as it was.",
+        fixes: &[
+            "Pick a version the conflicting requirements in the error allow, or update the package that holds it back first.",
+            "Run `pybun upgrade PACKAGE` to re-resolve the package and everything that depends on it.",
+        ],
+        diagnostic_codes: &["E_LOCK_UPDATE_FAILED", "E_LOCK_UPDATE_CONFLICT"],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn catalog_order_ids_and_diagnostic_codes_are_consistent() {
+        let mut ids = HashSet::new();
+        let mut diagnostic_codes = HashSet::new();
+        for (index, entry) in CATALOG.iter().enumerate() {
+            assert_eq!(entry.code as usize, index, "{} is out of order", entry.id);
+            let parts: Vec<&str> = entry.id.split('-').collect();
+            assert!(
+                parts.len() == 3
+                    && parts[0] == "PYBUN"
+                    && parts[1].chars().all(|c| c.is_ascii_uppercase())
+                    && parts[2].len() == 3
+                    && parts[2].chars().all(|c| c.is_ascii_digit()),
+                "malformed id {}",
+                entry.id
+            );
+            assert!(ids.insert(entry.id), "duplicate id {}", entry.id);
+            assert!(!entry.fixes.is_empty(), "{} has no fixes", entry.id);
+            for code in entry.diagnostic_codes {
+                assert!(diagnostic_codes.insert(*code), "{code} is catalogued twice");
+            }
+        }
+    }
+
+    #[test]
+    fn parses_ids_and_diagnostic_codes() {
+        assert_eq!(
+            ErrorCode::parse("pybun-resolve-002"),
+            Some(ErrorCode::ResolveConflict)
+        );
+        assert_eq!(
+            ErrorCode::parse("E_RESOLVE_CONFLICT"),
+            Some(ErrorCode::ResolveConflict)
+        );
+        assert_eq!(ErrorCode::ResolveConflict.to_string(), "PYBUN-RESOLVE-002");
+        assert_eq!(ErrorCode::parse("PYBUN-RESOLVE-999"), None);
+        assert_eq!(ErrorCode::for_diagnostic("E_ADD_FAILED"), None);
+    }
+}
@@ -0,0 +1,499 @@
+//! Execution policy: what PyBun itself may do (`pybun-policy.toml`).
+//!
+//! An organisation or an agent harness can constrain PyBun with a policy
+//! file, found through `PYBUN_POLICY` or as the nearest `pybun-policy.toml`
+//! in the current directory or a parent:
+//!
+//! ```toml
+//! [index]
+//! allowed = ["https://pypi.org/*", "https://pypi.corp.example/simple"]
+//!
+//! [packages]
+//! blocked = ["reqeusts", "colourama", "python-*-utils"]   # typosquats
+//! approved = ["internal-*"]   # skip the suspicious-package checks
+//! container-build = ["legacy-*"]   # build sdists only in a container
+//!
+//! [run]
+//! allow-code = false     # refuse `pybun run -c`
+//!
+//! [network]              # same keys as [tool.pybun.network]
+//! index = ["pypi.org", "files.pythonhosted.org"]
+//! run = []
+//! ```
+//!
+//! Index patterns match the whole index URL, with a trailing `*` matching
+//! any suffix. Package patterns are globs over PEP 503 normalized names. The
+//! `[network]` section replaces the project's `[tool.pybun.network]`
+//! allowlist (see [`crate::network_policy`]).
+//!
+//! Violations are [`PolicyViolation`]s, reported as `E_POLICY_VIOLATION`
+//! diagnostics and a non-zero exit. Checks made where no diagnostics
+//! collector is at hand are remembered with [`record`]. A policy file that
+//! cannot be read or parsed fails closed: every check is refused.
+
+use crate::network_policy::NetworkConfig;
+use crate::schema::Diagnostic;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Name of the policy file looked up from the current directory.
+pub const POLICY_FILE: &str = "pybun-policy.toml";
+/// Env var naming the policy file explicitly.
+pub const POLICY_ENV: &str = "PYBUN_POLICY";
+
+/// Contents of `pybun-policy.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub index: IndexRules,
+    #[serde(default)]
+    pub packages: PackageRules,
+    #[serde(default)]
+    pub run: RunRules,
+    #[serde(default)]
+    pub network: Option<NetworkConfig>,
+}
+
+/// `[index]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct IndexRules {
+    /// Index URL patterns; any index is allowed when absent.
+    #[serde(default)]
+    pub allowed: Option<Vec<String>>,
+}
+
+/// `[packages]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PackageRules {
+    #[serde(default)]
+    pub blocked: Vec<String>,
+    /// Packages exempt from the suspicious-package checks
+    /// ([`crate::suspicious`]).
+    #[serde(default)]
+    pub approved: Vec<String>,
+    /// Packages whose sdists may only be built in a container
+    /// ([`crate::build_isolation`]).
+    #[serde(default)]
+    pub container_build: Vec<String>,
+}
+
+/// `[run]`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RunRules {
+    /// Whether `pybun run -c` may execute inline code.
+    #[serde(default = "allow_code_default")]
+    pub allow_code: bool,
+}
+
+impl Default for RunRules {
+    fn default() -> Self {
+        Self {
+            allow_code: allow_code_default(),
+        }
+    }
+}
+
+fn allow_code_default() -> bool {
+    true
+}
+
+/// Which rule a [`PolicyViolation`] broke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// The index is not in `[index] allowed`.
+    Index,
+    /// The package matches `[packages] blocked`.
+    Package,
+    /// `[run] allow-code = false` refused inline code.
+    RunCode,
+    /// The policy file could not be read or parsed.
+    Invalid,
+}
+
+/// Something the execution policy refused.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyViolation {
+    pub rule: Rule,
+    /// The index URL, package name, or (for [`Rule::Invalid`]) the error.
+    pub subject: String,
+    /// The blocking pattern of a [`Rule::Package`] violation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    pub policy_file: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = &self.policy_file;
+        match self.rule {
+            Rule::Index => write!(
+                f,
+                "execution policy ({file}) does not allow index {}",
+                self.subject
+            ),
+            Rule::Package => write!(
+                f,
+                "execution policy ({file}) blocks package {} (matches `{}`)",
+                self.subject,
+                self.pattern.as_deref().unwrap_or_default()
+            ),
+            Rule::RunCode => write!(
+                f,
+                "execution policy ({file}) does not allow inline code (`pybun run -c`)"
+            ),
+            Rule::Invalid => write!(f, "execution policy {file} is invalid: {}", self.subject),
+        }
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+impl PolicyViolation {
+    /// The `E_POLICY_VIOLATION` diagnostic.
+    pub fn diagnostic(&self) -> Diagnostic {
+        let file = &self.policy_file;
+        let suggestion = match self.rule {
+            Rule::Index => {
+                format!("Use an index listed under [index] allowed in {file}, or add this one.")
+            }
+            Rule::Package => format!(
+                "Check the package name for typos. Remove the pattern from [packages] blocked in {file} only if the package is intended."
+            ),
+            Rule::RunCode => format!(
+                "Save the code to a script and run it with `pybun run <script>`, or set allow-code = true under [run] in {file}."
+            ),
+            Rule::Invalid => format!(
+                "Fix {file}, or point {POLICY_ENV} at a valid policy. Nothing is allowed until then."
+            ),
+        };
+        Diagnostic::error(self.to_string())
+            .with_code("E_POLICY_VIOLATION")
+            .with_context(json!(self))
+            .with_suggestion(suggestion)
+    }
+}
+
+/// A loaded policy and the file it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionPolicy {
+    pub config: PolicyConfig,
+    pub source: PathBuf,
+    /// Why the file could not be loaded; every check fails while set.
+    pub invalid: Option<String>,
+}
+
+impl ExecutionPolicy {
+    /// Load `path`. A file that cannot be read or parsed yields a policy
+    /// that refuses everything.
+    pub fn load(path: &Path) -> Self {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| toml::from_str::<PolicyConfig>(&text).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(config) => Self {
+                config,
+                source: path.to_path_buf(),
+                invalid: None,
+            },
+            Err(error) => Self {
+                config: PolicyConfig::default(),
+                source: path.to_path_buf(),
+                invalid: Some(one_line(&error)),
+            },
+        }
+    }
+
+    /// The policy named by `PYBUN_POLICY`, else the nearest
+    /// `pybun-policy.toml` in `dir` or a parent.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        if let Some(path) = std::env::var_os(POLICY_ENV).filter(|p| !p.is_empty()) {
+            return Some(Self::load(Path::new(&path)));
+        }
+        dir.ancestors()
+            .map(|d| d.join(POLICY_FILE))
+            .find(|p| p.is_file())
+            .map(|p| Self::load(&p))
+    }
+
+    fn violation(&self, rule: Rule, subject: &str, pattern: Option<&str>) -> PolicyViolation {
+        PolicyViolation {
+            rule,
+            subject: subject.to_string(),
+            pattern: pattern.map(str::to_string),
+            policy_file: self.source.display().to_string(),
+        }
+    }
+
+    fn check_valid(&self) -> Result<(), PolicyViolation> {
+        match &self.invalid {
+            Some(error) => Err(self.violation(Rule::Invalid, error, None)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that packages may be fetched from the index at `url`.
+    pub fn check_index(&self, url: &str) -> Result<(), PolicyViolation> {
+        self.check_valid()?;
+        match &self.config.index.allowed {
+            Some(allowed) if !allowed.iter().any(|p| url_matches(p, url)) => {
+                Err(self.violation(Rule::Index, url, None))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that package `name` may be resolved or installed.
+    pub fn check_package(&self, name: &str) -> Result<(), PolicyViolation> {
+        self.check_valid()?;
+        let normalized = crate::pypi::normalize_project_name(name);
+        match self
+            .config
+            .packages
+            .blocked
+            .iter()
+            .find(|p| glob_match(&crate::pypi::normalize_project_name(p.trim()), &normalized))
+        {
+            Some(pattern) => Err(self.violation(Rule::Package, &normalized, Some(pattern))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `name` matches `[packages] approved`.
+    pub fn approves(&self, name: &str) -> bool {
+        let normalized = crate::pypi::normalize_project_name(name);
+        self.invalid.is_none()
+            && self
+                .config
+                .packages
+                .approved
+                .iter()
+                .any(|p| glob_match(&crate::pypi::normalize_project_name(p.trim()), &normalized))
+    }
+
+    /// Whether `name` matches `[packages] container-build`. An invalid
+    /// policy sends every build to a container.
+    pub fn requires_container_build(&self, name: &str) -> bool {
+        let normalized = crate::pypi::normalize_project_name(name);
+        self.invalid.is_some()
+            || self
+                .config
+                .packages
+                .container_build
+                .iter()
+                .any(|p| glob_match(&crate::pypi::normalize_project_name(p.trim()), &normalized))
+    }
+
+    /// Check that `pybun run -c` may execute inline code.
+    pub fn check_code(&self) -> Result<(), PolicyViolation> {
+        self.check_valid()?;
+        if self.config.run.allow_code {
+            Ok(())
+        } else {
+            Err(self.violation(Rule::RunCode, "-c", None))
+        }
+    }
+
+    /// The network allowlist this policy imposes. An invalid policy allows
+    /// no hosts at all.
+    pub fn network(&self) -> Option<NetworkConfig> {
+        if self.invalid.is_some() {
+            let none = || Some(Vec::new());
+            return Some(NetworkConfig {
+                index: none(),
+                self_update: none(),
+                python: none(),
+                audit: none(),
+                run: none(),
+                test: none(),
+                cache: none(),
+                publish: none(),
+            });
+        }
+        self.config.network.clone()
+    }
+}
+
+/// First and last line of a (TOML) error, which spans several lines with
+/// a source excerpt in between.
+fn one_line(error: &str) -> String {
+    let mut lines = error.lines().map(str::trim).filter(|l| !l.is_empty());
+    let first = lines.next().unwrap_or_default();
+    match lines.next_back() {
+        Some(last) => format!("{first}: {last}"),
+        None => first.to_string(),
+    }
+}
+
+/// Match an index URL against a pattern (exact, or a prefix ending in `*`).
+fn url_matches(pattern: &str, url: &str) -> bool {
+    let url = url.trim().trim_end_matches('/').to_ascii_lowercase();
+    let pattern = pattern.trim().to_ascii_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => url.starts_with(prefix) || format!("{url}/") == prefix,
+        None => pattern.trim_end_matches('/') == url,
+    }
+}
+
+/// Glob match with `*` standing for any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let [first, middle @ .., last] = parts.as_slice() else {
+        return pattern == text;
+    };
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// The policy for the current directory, loaded once per process.
+pub fn current() -> Option<&'static ExecutionPolicy> {
+    static POLICY: OnceLock<Option<ExecutionPolicy>> = OnceLock::new();
+    POLICY
+        .get_or_init(|| {
+            std::env::current_dir()
+                .ok()
+                .and_then(|dir| ExecutionPolicy::discover(&dir))
+        })
+        .as_ref()
+}
+
+static VIOLATIONS: Mutex<Vec<PolicyViolation>> = Mutex::new(Vec::new());
+
+/// Remember a violation so the command can report it as a diagnostic.
+pub fn record(violation: PolicyViolation) {
+    if let Ok(mut violations) = VIOLATIONS.lock()
+        && !violations.contains(&violation)
+    {
+        violations.push(violation);
+    }
+}
+
+/// Drain violations recorded since the last call.
+pub fn take_violations() -> Vec<PolicyViolation> {
+    VIOLATIONS
+        .lock()
+        .map(|mut v| std::mem::take(&mut *v))
+        .unwrap_or_default()
+}
+
+/// Check `url` against the current policy, recording any violation.
+pub fn check_index(url: &str) -> Result<(), PolicyViolation> {
+    let Some(policy) = current() else {
+        return Ok(());
+    };
+    policy.check_index(url).inspect_err(|v| record(v.clone()))
+}
+
+/// Check package `name` against the current policy, recording any
+/// violation.
+pub fn check_package(name: &str) -> Result<(), PolicyViolation> {
+    let Some(policy) = current() else {
+        return Ok(());
+    };
+    policy
+        .check_package(name)
+        .inspect_err(|v| record(v.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn policy(toml: &str) -> ExecutionPolicy {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(POLICY_FILE);
+        std::fs::write(&path, toml).unwrap();
+        ExecutionPolicy::load(&path)
+    }
+
+    #[test]
+    fn checks_indexes_packages_and_inline_code() {
+        let policy = policy(
+            r#"
+[index]
+allowed = ["https://pypi.org/*", "https://mirror.example/simple/"]
+
+[packages]
+blocked = ["reqeusts", "Python_*.Utils"]
+container-build = ["legacy-*"]
+
+[run]
+allow-code = false
+"#,
+        );
+        assert!(policy.invalid.is_none());
+        assert!(policy.check_index("https://pypi.org/pypi").is_ok());
+        assert!(policy.check_index("https://pypi.org").is_ok());
+        assert!(policy.check_index("https://mirror.example/simple").is_ok());
+        let index = policy
+            .check_index("https://evil.example/simple")
+            .unwrap_err();
+        assert_eq!(index.rule, Rule::Index);
+
+        assert!(policy.check_package("requests").is_ok());
+        let blocked = policy.check_package("Reqeusts").unwrap_err();
+        assert_eq!(blocked.subject, "reqeusts");
+        let glob = policy.check_package("python.date_utils").unwrap_err();
+        assert_eq!(glob.pattern.as_deref(), Some("Python_*.Utils"));
+        assert!(policy.check_package("python-dateutil").is_ok());
+        assert!(policy.requires_container_build("Legacy_Pkg"));
+        assert!(!policy.requires_container_build("requests"));
+
+        let code = policy.check_code().unwrap_err();
+        assert_eq!(code.rule, Rule::RunCode);
+        let diagnostic = code.diagnostic();
+        assert_eq!(diagnostic.code.as_deref(), Some("E_POLICY_VIOLATION"));
+        assert_eq!(diagnostic.context.unwrap()["rule"], "run-code");
+    }
+
+    #[test]
+    fn empty_policy_allows_everything() {
+        let policy = policy("");
+        assert!(
+            policy
+                .check_index("https://anything.example/simple")
+                .is_ok()
+        );
+        assert!(policy.check_package("requests").is_ok());
+        assert!(policy.check_code().is_ok());
+        assert!(policy.network().is_none());
+    }
+
+    #[test]
+    fn invalid_policy_fails_closed() {
+        let policy = policy("[packages]\nblock = [\"x\"]\n");
+        assert!(policy.invalid.as_deref().unwrap().contains("block"));
+        let violation = policy.check_package("requests").unwrap_err();
+        assert_eq!(violation.rule, Rule::Invalid);
+        assert!(policy.check_index("https://pypi.org/pypi").is_err());
+        assert!(policy.check_code().is_err());
+        assert!(policy.requires_container_build("requests"));
+        assert_eq!(policy.network().unwrap().index, Some(Vec::new()));
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("a*b*c", "axxc"));
+        assert!(!glob_match("ab*ba", "aba"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exactly"));
+    }
+}
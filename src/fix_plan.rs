@@ -0,0 +1,516 @@
+//! Dry runs of `pybun doctor --fix` remediations.
+//!
+//! Every fix candidate becomes a [`PlannedFix`]: the candidate, the
+//! preconditions it needs (free disk space, a writable directory, a
+//! reachable host, an installed program), the state it is expected to leave
+//! behind, and the steps that have to run before it. [`simulate`] orders the
+//! steps by those dependencies and checks each precondition with a
+//! [`Probe`] without changing anything:
+//!
+//! - `ready`: every precondition holds and the fix is safe to run unattended;
+//! - `manual`: every precondition holds, but the fix needs a human;
+//! - `blocked`: a precondition failed or a step it depends on is blocked.
+//!
+//! `doctor --fix --apply` only runs `ready` steps, in plan order.
+
+use crate::schema::{FixCandidate, RiskLevel};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Something that has to hold before a fix can succeed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Precondition {
+    /// At least `bytes` free on the filesystem holding `path`.
+    FreeSpace { path: PathBuf, bytes: u64 },
+    /// Files can be created and removed in `path`.
+    Writable { path: PathBuf },
+    /// An HTTPS request to `url` succeeds.
+    Reachable { url: String },
+    /// `name` is on `PATH`.
+    Program { name: String },
+}
+
+/// Read-only view of the machine the preconditions are checked against.
+pub trait Probe {
+    /// Free bytes on the filesystem holding `path`, if it can be measured.
+    fn free_space(&self, path: &Path) -> Option<u64>;
+    /// `Err` says why files cannot be created in `path`.
+    fn writable(&self, path: &Path) -> Result<(), String>;
+    /// `Err` says why `url` cannot be reached.
+    fn reachable(&self, url: &str) -> Result<(), String>;
+    fn program(&self, name: &str) -> bool;
+}
+
+/// One fix candidate with what it needs and what it should achieve.
+#[derive(Debug, Clone)]
+pub struct PlannedFix {
+    pub id: String,
+    pub code: Option<String>,
+    pub candidate: FixCandidate,
+    pub preconditions: Vec<Precondition>,
+    /// Expected state after the fix, e.g. `{"stale_count": 0}`.
+    pub predicted: Map<String, Value>,
+    /// Ids of the steps that must run first.
+    pub after: Vec<String>,
+}
+
+impl PlannedFix {
+    pub fn new(id: impl Into<String>, code: Option<String>, candidate: FixCandidate) -> Self {
+        Self {
+            id: id.into(),
+            code,
+            candidate,
+            preconditions: Vec::new(),
+            predicted: Map::new(),
+            after: Vec::new(),
+        }
+    }
+
+    pub fn with_precondition(mut self, precondition: Precondition) -> Self {
+        self.preconditions.push(precondition);
+        self
+    }
+
+    pub fn with_prediction(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.predicted.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_dependency(mut self, id: impl Into<String>) -> Self {
+        self.after.push(id.into());
+        self
+    }
+
+    fn needs_network(&self) -> bool {
+        self.preconditions
+            .iter()
+            .any(|p| matches!(p, Precondition::Reachable { .. }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixStatus {
+    Ready,
+    Manual,
+    Blocked,
+}
+
+/// Outcome of checking one precondition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckedPrecondition {
+    pub kind: &'static str,
+    pub target: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// A step of the simulated plan, as reported in `detail.fix_steps`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedFix {
+    pub id: String,
+    pub order: usize,
+    pub code: Option<String>,
+    pub command: String,
+    pub risk: RiskLevel,
+    pub auto_applicable: bool,
+    pub status: FixStatus,
+    pub after: Vec<String>,
+    pub preconditions: Vec<CheckedPrecondition>,
+    pub predicted: Map<String, Value>,
+    pub blocked_reasons: Vec<String>,
+}
+
+/// Make every step with a network precondition run after the steps in
+/// `network_fixes` (proxy/TLS remediations), which it cannot succeed
+/// without.
+pub fn order_after_network_fixes(steps: &mut [PlannedFix], network_fixes: &[String]) {
+    for step in steps.iter_mut() {
+        if step.needs_network() {
+            for id in network_fixes {
+                if *id != step.id && !step.after.contains(id) {
+                    step.after.push(id.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Order `steps` by their dependencies and check their preconditions.
+/// Steps caught in a dependency cycle, or depending on an unknown or
+/// blocked step, are blocked.
+pub fn simulate(steps: &[PlannedFix], probe: &dyn Probe) -> Vec<SimulatedFix> {
+    let ids: BTreeSet<&str> = steps.iter().map(|s| s.id.as_str()).collect();
+    let mut done: BTreeSet<String> = BTreeSet::new();
+    let mut blocked: BTreeSet<String> = BTreeSet::new();
+    let mut remaining: Vec<&PlannedFix> = steps.iter().collect();
+    let mut out = Vec::with_capacity(steps.len());
+
+    while !remaining.is_empty() {
+        // The first step, in the given order, whose known dependencies have
+        // all been placed; a cycle leaves none.
+        let next = remaining.iter().position(|step| {
+            step.after
+                .iter()
+                .all(|dep| done.contains(dep) || !ids.contains(dep.as_str()))
+        });
+        let (step, cyclic) = match next {
+            Some(index) => (remaining.remove(index), false),
+            None => (remaining.remove(0), true),
+        };
+
+        let preconditions: Vec<CheckedPrecondition> =
+            step.preconditions.iter().map(|p| check(p, probe)).collect();
+        let mut reasons: Vec<String> = preconditions
+            .iter()
+            .filter(|c| !c.ok)
+            .map(|c| c.detail.clone())
+            .collect();
+        if cyclic {
+            reasons.push(format!("{} is part of a dependency cycle", step.id));
+        }
+        for dep in &step.after {
+            if !ids.contains(dep.as_str()) {
+                reasons.push(format!("depends on unknown step {}", dep));
+            } else if blocked.contains(dep) {
+                reasons.push(format!("depends on blocked step {}", dep));
+            }
+        }
+
+        let status = if !reasons.is_empty() {
+            blocked.insert(step.id.clone());
+            FixStatus::Blocked
+        } else if step.candidate.auto_applicable && step.candidate.risk == RiskLevel::Low {
+            FixStatus::Ready
+        } else {
+            FixStatus::Manual
+        };
+        done.insert(step.id.clone());
+        out.push(SimulatedFix {
+            id: step.id.clone(),
+            order: out.len() + 1,
+            code: step.code.clone(),
+            command: step.candidate.command.clone(),
+            risk: step.candidate.risk,
+            auto_applicable: step.candidate.auto_applicable,
+            status,
+            after: step.after.clone(),
+            preconditions,
+            predicted: step.predicted.clone(),
+            blocked_reasons: reasons,
+        });
+    }
+    out
+}
+
+fn check(precondition: &Precondition, probe: &dyn Probe) -> CheckedPrecondition {
+    match precondition {
+        Precondition::FreeSpace { path, bytes } => {
+            let target = path.display().to_string();
+            match probe.free_space(path) {
+                Some(free) if free >= *bytes => CheckedPrecondition {
+                    kind: "disk_space",
+                    target,
+                    ok: true,
+                    detail: format!("{} free, {} needed", free, bytes),
+                },
+                Some(free) => CheckedPrecondition {
+                    kind: "disk_space",
+                    detail: format!(
+                        "only {} bytes free under {}, {} needed",
+                        free, target, bytes
+                    ),
+                    target,
+                    ok: false,
+                },
+                // Unmeasurable space is not a reason to block the fix.
+                None => CheckedPrecondition {
+                    kind: "disk_space",
+                    target,
+                    ok: true,
+                    detail: "free space could not be measured".to_string(),
+                },
+            }
+        }
+        Precondition::Writable { path } => {
+            let target = path.display().to_string();
+            match probe.writable(path) {
+                Ok(()) => CheckedPrecondition {
+                    kind: "permissions",
+                    target,
+                    ok: true,
+                    detail: "writable".to_string(),
+                },
+                Err(e) => CheckedPrecondition {
+                    kind: "permissions",
+                    detail: format!("{} is not writable: {}", target, e),
+                    target,
+                    ok: false,
+                },
+            }
+        }
+        Precondition::Reachable { url } => match probe.reachable(url) {
+            Ok(()) => CheckedPrecondition {
+                kind: "network",
+                target: url.clone(),
+                ok: true,
+                detail: "reachable".to_string(),
+            },
+            Err(e) => CheckedPrecondition {
+                kind: "network",
+                target: url.clone(),
+                ok: false,
+                detail: format!("{} is not reachable: {}", url, e),
+            },
+        },
+        Precondition::Program { name } => {
+            let ok = probe.program(name);
+            CheckedPrecondition {
+                kind: "program",
+                target: name.clone(),
+                ok,
+                detail: if ok {
+                    format!("{} found on PATH", name)
+                } else {
+                    format!("{} is not on PATH", name)
+                },
+            }
+        }
+    }
+}
+
+/// [`Probe`] of the local machine. `reachable` is supplied by the caller so
+/// it can apply the configured proxy, trust store and network policy.
+pub struct SystemProbe<F: Fn(&str) -> Result<(), String>> {
+    pub reachable: F,
+}
+
+impl<F: Fn(&str) -> Result<(), String>> Probe for SystemProbe<F> {
+    fn free_space(&self, path: &Path) -> Option<u64> {
+        free_space(&existing_ancestor(path)?)
+    }
+
+    fn writable(&self, path: &Path) -> Result<(), String> {
+        let dir =
+            existing_ancestor(path).ok_or_else(|| "no existing parent directory".to_string())?;
+        tempfile::Builder::new()
+            .prefix(".pybun-doctor-probe")
+            .tempfile_in(&dir)
+            .map(drop)
+            .map_err(|e| e.to_string())
+    }
+
+    fn reachable(&self, url: &str) -> Result<(), String> {
+        (self.reachable)(url)
+    }
+
+    fn program(&self, name: &str) -> bool {
+        std::env::var_os("PATH")
+            .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+    }
+}
+
+/// `path`, or its closest ancestor that exists: the directory a fix would
+/// create `path` in.
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find(|p| p.is_dir()).map(Path::to_path_buf)
+}
+
+#[cfg(unix)]
+fn free_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stats` is a valid out-pointer.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn free_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeProbe {
+        free: u64,
+        read_only: Vec<PathBuf>,
+        offline: bool,
+    }
+
+    impl Probe for FakeProbe {
+        fn free_space(&self, _path: &Path) -> Option<u64> {
+            Some(self.free)
+        }
+        fn writable(&self, path: &Path) -> Result<(), String> {
+            if self.read_only.iter().any(|p| p == path) {
+                Err("permission denied".to_string())
+            } else {
+                Ok(())
+            }
+        }
+        fn reachable(&self, _url: &str) -> Result<(), String> {
+            if self.offline {
+                Err("connection refused".to_string())
+            } else {
+                Ok(())
+            }
+        }
+        fn program(&self, name: &str) -> bool {
+            name == "sh"
+        }
+    }
+
+    fn probe() -> FakeProbe {
+        FakeProbe {
+            free: 1 << 30,
+            read_only: Vec::new(),
+            offline: false,
+        }
+    }
+
+    fn step(id: &str, risk: RiskLevel, auto: bool) -> PlannedFix {
+        PlannedFix::new(
+            id,
+            None,
+            FixCandidate::new(format!("fix {id}"), "", risk, auto),
+        )
+    }
+
+    fn statuses(plan: &[SimulatedFix]) -> Vec<(&str, FixStatus)> {
+        plan.iter().map(|s| (s.id.as_str(), s.status)).collect()
+    }
+
+    #[test]
+    fn steps_run_after_their_dependencies() {
+        let steps = vec![
+            step("install", RiskLevel::Low, true).with_dependency("tls"),
+            step("tls", RiskLevel::Medium, false),
+            step("gc", RiskLevel::Low, true),
+        ];
+        let plan = simulate(&steps, &probe());
+        assert_eq!(
+            statuses(&plan),
+            vec![
+                ("tls", FixStatus::Manual),
+                ("install", FixStatus::Ready),
+                ("gc", FixStatus::Ready),
+            ]
+        );
+        assert_eq!(
+            plan.iter().map(|s| s.order).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn failed_preconditions_block_the_step_and_its_dependents() {
+        let cache = PathBuf::from("/cache");
+        let steps = vec![
+            step("gc", RiskLevel::Low, true).with_precondition(Precondition::Writable {
+                path: cache.clone(),
+            }),
+            step("reinstall", RiskLevel::Low, true)
+                .with_dependency("gc")
+                .with_precondition(Precondition::FreeSpace {
+                    path: cache.clone(),
+                    bytes: 10,
+                }),
+            step("download", RiskLevel::Low, true).with_precondition(Precondition::Reachable {
+                url: "https://example.com".into(),
+            }),
+            step("certs", RiskLevel::High, false).with_precondition(Precondition::Program {
+                name: "update-ca-certificates".into(),
+            }),
+        ];
+        let broken = FakeProbe {
+            free: 100,
+            read_only: vec![cache],
+            offline: true,
+        };
+        let plan = simulate(&steps, &broken);
+        assert!(plan.iter().all(|s| s.status == FixStatus::Blocked));
+        assert_eq!(
+            plan[0].blocked_reasons,
+            vec!["/cache is not writable: permission denied"]
+        );
+        assert_eq!(plan[1].blocked_reasons, vec!["depends on blocked step gc"]);
+        assert!(plan[1].preconditions[0].ok);
+        assert_eq!(plan[2].preconditions[0].kind, "network");
+        assert_eq!(
+            plan[3].blocked_reasons,
+            vec!["update-ca-certificates is not on PATH"]
+        );
+
+        let low_disk = FakeProbe { free: 5, ..probe() };
+        let plan = simulate(&steps[1..2], &low_disk);
+        assert_eq!(plan[0].preconditions[0].kind, "disk_space");
+        assert_eq!(plan[0].status, FixStatus::Blocked);
+    }
+
+    #[test]
+    fn cycles_and_unknown_dependencies_are_blocked() {
+        let steps = vec![
+            step("a", RiskLevel::Low, true).with_dependency("b"),
+            step("b", RiskLevel::Low, true).with_dependency("a"),
+            step("c", RiskLevel::Low, true).with_dependency("missing"),
+        ];
+        let plan = simulate(&steps, &probe());
+        assert_eq!(
+            statuses(&plan),
+            vec![
+                ("c", FixStatus::Blocked),
+                ("a", FixStatus::Blocked),
+                ("b", FixStatus::Blocked),
+            ]
+        );
+        assert_eq!(
+            plan[0].blocked_reasons,
+            vec!["depends on unknown step missing"]
+        );
+        assert_eq!(
+            plan[1].blocked_reasons,
+            vec!["a is part of a dependency cycle"]
+        );
+    }
+
+    #[test]
+    fn network_steps_wait_for_network_fixes() {
+        let mut steps = vec![
+            step("python", RiskLevel::Medium, false).with_precondition(Precondition::Reachable {
+                url: "https://example.com".into(),
+            }),
+            step("gc", RiskLevel::Low, true),
+            step("tls", RiskLevel::Medium, false),
+        ];
+        order_after_network_fixes(&mut steps, &["tls".to_string()]);
+        assert_eq!(steps[0].after, vec!["tls"]);
+        assert!(steps[1].after.is_empty());
+        let plan = simulate(&steps, &probe());
+        assert_eq!(
+            plan.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(),
+            vec!["gc", "tls", "python"]
+        );
+    }
+
+    #[test]
+    fn system_probe_checks_the_closest_existing_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let probe = SystemProbe {
+            reachable: |_: &str| Ok(()),
+        };
+        let missing = temp.path().join("not").join("yet");
+        assert!(probe.writable(&missing).is_ok());
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 0);
+        #[cfg(unix)]
+        assert!(probe.free_space(&missing).is_some_and(|free| free > 0));
+    }
+}
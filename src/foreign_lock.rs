@@ -0,0 +1,1047 @@
+//! Lockfiles of other tools, for projects moving to PyBun or running it next
+//! to uv or Poetry for a while.
+//!
+//! [`convert`] reads a `uv.lock` or `poetry.lock` into a [`Lockfile`]
+//! without resolving anything (`pybun lock --from`): every locked package
+//! keeps its version, source, dependencies and file hashes, and dependency
+//! groups come from the source lock. [`uv_lock`] goes the other way and
+//! renders a [`Lockfile`] as a `uv.lock` (`pybun export --export uv.lock`).
+//!
+//! PyBun locks one version per package, so when a lock forks a package by
+//! environment marker only the highest version is kept and the package is
+//! reported in [`Converted::forked`]. Local path and editable packages other
+//! than the project itself are left out ([`Converted::skipped`]).
+
+use crate::dep_export::{Exported, ExportedPackage};
+use crate::dep_import::Skipped;
+use crate::lockfile::{Artifact, Lockfile, MAIN_GROUP, Package, PackageSource};
+use crate::pep440::Pep440Version;
+use crate::pypi::{normalize_project_name, wheel_platforms};
+use crate::resolver::Requirement;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use toml::Value;
+
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+    #[error("cannot tell what {0} is; expected a uv.lock or a poetry.lock")]
+    UnknownFormat(PathBuf),
+}
+
+pub type Result<T> = std::result::Result<T, ConvertError>;
+
+/// Format of a converted lockfile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LockFormat {
+    #[serde(rename = "uv.lock")]
+    Uv,
+    #[serde(rename = "poetry.lock")]
+    Poetry,
+}
+
+impl LockFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LockFormat::Uv => "uv.lock",
+            LockFormat::Poetry => "poetry.lock",
+        }
+    }
+
+    /// Tell the formats apart by content, so renamed files still work:
+    /// Poetry writes `[metadata] lock-version`, uv a top-level `version`.
+    fn detect(doc: &Value) -> Option<Self> {
+        if doc
+            .get("metadata")
+            .and_then(|m| m.get("lock-version"))
+            .is_some()
+        {
+            Some(LockFormat::Poetry)
+        } else if doc.get("version").and_then(Value::as_integer).is_some() {
+            Some(LockFormat::Uv)
+        } else {
+            None
+        }
+    }
+}
+
+/// A package the source lock held at several versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Forked {
+    pub name: String,
+    pub versions: Vec<String>,
+    pub kept: String,
+}
+
+/// A lockfile converted by [`convert`].
+#[derive(Debug)]
+pub struct Converted {
+    pub format: LockFormat,
+    pub lock: Lockfile,
+    /// `requires-python` of the source lock.
+    pub requires_python: Option<String>,
+    pub skipped: Vec<Skipped>,
+    pub forked: Vec<Forked>,
+}
+
+/// Packages, group roots and notes read from a source lock.
+#[derive(Default)]
+struct Read {
+    packages: Vec<Package>,
+    /// Requirements of the project by group, for locks that record the
+    /// project itself (uv). Poetry records groups per package instead.
+    roots: Option<BTreeMap<String, Vec<String>>>,
+    requires_python: Option<String>,
+    skipped: Vec<Skipped>,
+}
+
+/// Read the uv.lock or poetry.lock at `path` into a lockfile for the given
+/// Python versions and platforms.
+pub fn convert(
+    path: &Path,
+    python_versions: Vec<String>,
+    platforms: Vec<String>,
+) -> Result<Converted> {
+    let content = std::fs::read_to_string(path).map_err(|source| ConvertError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let doc: Value = toml::from_str(&content).map_err(|e| ConvertError::Parse {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    let format =
+        LockFormat::detect(&doc).ok_or_else(|| ConvertError::UnknownFormat(path.to_path_buf()))?;
+    let read = match format {
+        LockFormat::Uv => read_uv(&doc),
+        LockFormat::Poetry => read_poetry(&doc),
+    };
+
+    let mut by_name: BTreeMap<String, Vec<Package>> = BTreeMap::new();
+    for pkg in read.packages {
+        by_name
+            .entry(normalize_project_name(&pkg.name))
+            .or_default()
+            .push(pkg);
+    }
+    let mut lock = Lockfile::new(python_versions, platforms);
+    let mut forked = Vec::new();
+    for (_, mut versions) in by_name {
+        versions.sort_by(|a, b| compare_versions(&a.version, &b.version));
+        let Some(kept) = versions.pop() else {
+            continue;
+        };
+        if !versions.is_empty() {
+            forked.push(Forked {
+                name: kept.name.clone(),
+                versions: versions
+                    .iter()
+                    .map(|p| p.version.clone())
+                    .chain([kept.version.clone()])
+                    .collect(),
+                kept: kept.version.clone(),
+            });
+        }
+        lock.add_package(kept);
+    }
+    if let Some(roots) = &read.roots {
+        lock.assign_groups(roots);
+    }
+
+    Ok(Converted {
+        format,
+        lock,
+        requires_python: read.requires_python,
+        skipped: read.skipped,
+        forked,
+    })
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    match (Pep440Version::parse(a), Pep440Version::parse(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+fn text<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(Value::as_str)
+}
+
+/// Last path segment of a file URL, with `%XX` escapes decoded.
+fn url_filename(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let segment = path.rsplit('/').next().unwrap_or(path);
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| segment.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn artifact(filename: String, url: Option<String>, hash: String) -> Artifact {
+    Artifact {
+        platforms: wheel_platforms(&filename),
+        filename,
+        url,
+        hash,
+    }
+}
+
+/// A package as PyBun locks it; the first wheel (else the first file) is
+/// the one [`Package::wheel`] names.
+fn package(
+    name: &str,
+    version: &str,
+    source: PackageSource,
+    dependencies: Vec<String>,
+    artifacts: Vec<Artifact>,
+    groups: Vec<String>,
+) -> Package {
+    let first = artifacts
+        .iter()
+        .find(|a| a.is_wheel())
+        .or_else(|| artifacts.first());
+    Package {
+        name: name.to_string(),
+        version: version.to_string(),
+        source,
+        wheel: first.map(|a| a.filename.clone()).unwrap_or_default(),
+        hash: first.map(|a| a.hash.clone()).unwrap_or_default(),
+        dependencies,
+        dynamic_metadata: false,
+        build: None,
+        artifacts,
+        groups,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// uv.lock
+// ---------------------------------------------------------------------------
+
+/// Source keys uv uses for packages on the local file system.
+const UV_LOCAL_SOURCES: [&str; 4] = ["editable", "virtual", "directory", "path"];
+
+fn read_uv(doc: &Value) -> Read {
+    let mut read = Read {
+        requires_python: text(doc, "requires-python").map(str::to_string),
+        ..Read::default()
+    };
+    let mut roots: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let entries = doc.get("package").and_then(Value::as_array);
+    for entry in entries.into_iter().flatten() {
+        let Some(name) = text(entry, "name") else {
+            continue;
+        };
+        let version = text(entry, "version").unwrap_or_default();
+        let label = format!("{name} {version}").trim_end().to_string();
+        let source = entry
+            .get("source")
+            .cloned()
+            .unwrap_or(Value::Boolean(false));
+        let dependencies = uv_dependencies(entry.get("dependencies"));
+
+        if let Some((kind, location)) = UV_LOCAL_SOURCES
+            .iter()
+            .find_map(|kind| text(&source, kind).map(|location| (*kind, location)))
+        {
+            if location == "." && matches!(kind, "editable" | "virtual") {
+                // The project itself: its requirements root the groups.
+                roots
+                    .entry(MAIN_GROUP.to_string())
+                    .or_default()
+                    .extend(dependencies);
+                for key in ["optional-dependencies", "dev-dependencies"] {
+                    let tables = entry.get(key).and_then(Value::as_table);
+                    for (group, deps) in tables.into_iter().flatten() {
+                        roots
+                            .entry(group.clone())
+                            .or_default()
+                            .extend(uv_dependencies(Some(deps)));
+                    }
+                }
+            } else {
+                read.skipped.push(Skipped {
+                    entry: label,
+                    reason: format!("{kind} source `{location}` is not locked by pybun"),
+                });
+            }
+            continue;
+        }
+
+        let source = if let Some(url) = text(&source, "registry") {
+            PackageSource::Registry {
+                index: "pypi".to_string(),
+                url: url.to_string(),
+            }
+        } else if let Some(git) = text(&source, "git") {
+            match uv_git_source(git) {
+                Some(source) => source,
+                None => {
+                    read.skipped.push(Skipped {
+                        entry: label,
+                        reason: format!("git source `{git}` has no pinned commit"),
+                    });
+                    continue;
+                }
+            }
+        } else if let Some(url) = text(&source, "url") {
+            PackageSource::Url {
+                url: url.to_string(),
+            }
+        } else {
+            read.skipped.push(Skipped {
+                entry: label,
+                reason: "unknown source".to_string(),
+            });
+            continue;
+        };
+
+        let file = |file: &Value| {
+            let url = text(file, "url").map(str::to_string);
+            let filename = text(file, "filename")
+                .map(str::to_string)
+                .or_else(|| url.as_deref().map(url_filename))?;
+            Some(artifact(
+                filename,
+                url,
+                text(file, "hash").unwrap_or_default().to_string(),
+            ))
+        };
+        let mut artifacts: Vec<Artifact> = entry
+            .get("wheels")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(file)
+            .collect();
+        artifacts.extend(entry.get("sdist").and_then(file));
+
+        read.packages.push(package(
+            name,
+            version,
+            source,
+            dependencies,
+            artifacts,
+            Vec::new(),
+        ));
+    }
+    read.roots = Some(roots);
+    read
+}
+
+/// `[{ name, extra, marker }]` dependency entries as requirement strings.
+fn uv_dependencies(entries: Option<&Value>) -> Vec<String> {
+    entries
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|dep| {
+            let name = text(dep, "name")?;
+            let extras: Vec<&str> = dep
+                .get("extra")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            let mut requirement = name.to_string();
+            if !extras.is_empty() {
+                requirement.push_str(&format!("[{}]", extras.join(",")));
+            }
+            if let Some(marker) = text(dep, "marker") {
+                requirement.push_str(&format!("; {marker}"));
+            }
+            Some(requirement)
+        })
+        .collect()
+}
+
+/// uv's `<url>?rev=<ref>&subdirectory=<dir>#<commit>` git source.
+fn uv_git_source(git: &str) -> Option<PackageSource> {
+    let (rest, commit) = git.split_once('#')?;
+    let (url, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut reference = None;
+    let mut subdirectory = None;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "rev" | "tag" | "branch" => reference = Some(value.to_string()),
+            "subdirectory" => subdirectory = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(PackageSource::Git {
+        url: url.strip_prefix("git+").unwrap_or(url).to_string(),
+        reference,
+        subdirectory,
+        commit: commit.to_string(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// poetry.lock
+// ---------------------------------------------------------------------------
+
+const PYPI_SIMPLE: &str = "https://pypi.org/simple";
+
+fn read_poetry(doc: &Value) -> Read {
+    let requires_python = doc
+        .get("metadata")
+        .and_then(|m| text(m, "python-versions"))
+        .and_then(|constraint| crate::dep_import::poetry_constraint(constraint).ok())
+        .filter(|spec| !spec.is_empty());
+    let mut read = Read {
+        requires_python,
+        ..Read::default()
+    };
+    let entries = doc.get("package").and_then(Value::as_array);
+    for entry in entries.into_iter().flatten() {
+        let (Some(name), Some(version)) = (text(entry, "name"), text(entry, "version")) else {
+            continue;
+        };
+        let label = format!("{name} {version}");
+        let source = entry.get("source");
+        let kind = source.and_then(|s| text(s, "type")).unwrap_or("pypi");
+        let source_url = source.and_then(|s| text(s, "url")).unwrap_or(PYPI_SIMPLE);
+        let mut file_url = None;
+        let source = match kind {
+            "pypi" | "legacy" => PackageSource::Registry {
+                index: source
+                    .and_then(|s| text(s, "reference"))
+                    .unwrap_or("pypi")
+                    .to_string(),
+                url: source_url.to_string(),
+            },
+            "git" => {
+                let Some(commit) = source.and_then(|s| text(s, "resolved_reference")) else {
+                    read.skipped.push(Skipped {
+                        entry: label,
+                        reason: format!("git source `{source_url}` has no resolved commit"),
+                    });
+                    continue;
+                };
+                PackageSource::Git {
+                    url: source_url
+                        .strip_prefix("git+")
+                        .unwrap_or(source_url)
+                        .to_string(),
+                    reference: source
+                        .and_then(|s| text(s, "reference"))
+                        .map(str::to_string),
+                    subdirectory: source
+                        .and_then(|s| text(s, "subdirectory"))
+                        .map(str::to_string),
+                    commit: commit.to_string(),
+                }
+            }
+            "url" => {
+                file_url = Some(source_url.to_string());
+                PackageSource::Url {
+                    url: source_url.to_string(),
+                }
+            }
+            other => {
+                read.skipped.push(Skipped {
+                    entry: label,
+                    reason: format!("{other} source `{source_url}` is not locked by pybun"),
+                });
+                continue;
+            }
+        };
+
+        // Poetry records file names and hashes only; registry download URLs
+        // are looked up again when converting.
+        let artifacts = entry
+            .get("files")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|file| {
+                Some(artifact(
+                    text(file, "file")?.to_string(),
+                    file_url.clone(),
+                    text(file, "hash").unwrap_or_default().to_string(),
+                ))
+            })
+            .collect();
+
+        let mut dependencies = Vec::new();
+        let declared = entry.get("dependencies").and_then(Value::as_table);
+        for (dep, value) in declared.into_iter().flatten() {
+            let values = match value.as_array() {
+                Some(values) => values.iter().collect(),
+                None => vec![value],
+            };
+            for value in values {
+                // Optional dependencies are only needed for an extra.
+                if value.get("optional").and_then(Value::as_bool) == Some(true) {
+                    continue;
+                }
+                match crate::dep_import::poetry_requirement(dep, value) {
+                    Ok(requirement) => dependencies.push(requirement),
+                    Err(reason) => read.skipped.push(Skipped {
+                        entry: format!("{label} -> {dep}"),
+                        reason,
+                    }),
+                }
+            }
+        }
+
+        // Poetry 2 lists groups; Poetry 1 a single category.
+        let groups = match entry.get("groups").and_then(Value::as_array) {
+            Some(groups) => groups
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            None => text(entry, "category")
+                .map(|category| vec![category.to_string()])
+                .unwrap_or_default(),
+        };
+
+        read.packages.push(package(
+            name,
+            version,
+            source,
+            dependencies,
+            artifacts,
+            groups,
+        ));
+    }
+    read
+}
+
+// ---------------------------------------------------------------------------
+// uv.lock export
+// ---------------------------------------------------------------------------
+
+/// The project a `uv.lock` is written for.
+#[derive(Debug, Clone, Default)]
+pub struct UvProject {
+    pub name: String,
+    pub version: String,
+    pub requires_python: Option<String>,
+    /// Built as a package (`[build-system]`) rather than a virtual project.
+    pub editable: bool,
+    pub dependencies: Vec<String>,
+    pub optional_dependencies: BTreeMap<String, Vec<String>>,
+    pub dependency_groups: BTreeMap<String, Vec<String>>,
+}
+
+/// Render `lock` as a `uv.lock` for `project`. Files the lock records
+/// without a download URL cannot be written; their packages are listed in
+/// [`Exported::unlocated`].
+pub fn uv_lock(lock: &Lockfile, project: &UvProject, hashes: bool) -> Exported {
+    let locked: BTreeMap<String, &Package> = lock
+        .packages
+        .values()
+        .map(|pkg| (normalize_project_name(&pkg.name), pkg))
+        .collect();
+
+    let mut content = String::from("version = 1\n");
+    let requires_python = project.requires_python.clone().or_else(|| {
+        lock.python_versions
+            .iter()
+            .filter_map(|v| Pep440Version::parse(v).map(|parsed| (parsed, v)))
+            .min()
+            .map(|(_, v)| format!(">={v}"))
+    });
+    if let Some(requires_python) = &requires_python {
+        content.push_str(&format!("requires-python = {}\n", quoted(requires_python)));
+    }
+
+    // Packages and the project, in uv's order: by normalized name.
+    let root = normalize_project_name(&project.name);
+    let mut names: BTreeSet<&String> = locked.keys().collect();
+    names.insert(&root);
+
+    let mut packages = Vec::new();
+    let mut unlocated = Vec::new();
+    for name in names {
+        content.push_str("\n[[package]]\n");
+        if *name == root && !locked.contains_key(name) {
+            write_uv_project(&mut content, project, &locked);
+            continue;
+        }
+        let pkg = locked[name];
+        content.push_str(&format!("name = {}\n", quoted(name)));
+        content.push_str(&format!("version = {}\n", quoted(&pkg.version)));
+        let (source, requirement) = match &pkg.source {
+            PackageSource::Registry { url, .. } => (
+                format!("{{ registry = {} }}", quoted(url)),
+                format!("{}=={}", pkg.name, pkg.version),
+            ),
+            PackageSource::Url { url } => (
+                format!("{{ url = {} }}", quoted(url)),
+                format!("{} @ {}", pkg.name, url),
+            ),
+            PackageSource::Git {
+                url,
+                reference,
+                subdirectory,
+                commit,
+            } => {
+                let mut query: Vec<String> = Vec::new();
+                query.extend(reference.iter().map(|r| format!("rev={r}")));
+                query.extend(subdirectory.iter().map(|s| format!("subdirectory={s}")));
+                let query = if query.is_empty() {
+                    String::new()
+                } else {
+                    format!("?{}", query.join("&"))
+                };
+                (
+                    format!("{{ git = {} }}", quoted(&format!("{url}{query}#{commit}"))),
+                    format!("{} @ git+{url}@{commit}", pkg.name),
+                )
+            }
+        };
+        content.push_str(&format!("source = {source}\n"));
+        write_uv_dependencies(&mut content, "dependencies", &pkg.dependencies, &locked);
+
+        let mut written = Vec::new();
+        if !matches!(pkg.source, PackageSource::Git { .. }) {
+            let file = |artifact: &Artifact| {
+                let url = artifact.url.as_ref()?;
+                let hash = Some(&artifact.hash)
+                    .filter(|hash| hashes && !hash.is_empty())
+                    .map(|hash| format!(", hash = {}", quoted(hash)))
+                    .unwrap_or_default();
+                Some(format!("{{ url = {}{hash} }}", quoted(url)))
+            };
+            let sdist = pkg.artifacts.iter().find(|a| !a.is_wheel());
+            if let Some((sdist, line)) = sdist.and_then(|a| Some((a, file(a)?))) {
+                content.push_str(&format!("sdist = {line}\n"));
+                written.push(sdist);
+            }
+            let wheels: Vec<(&Artifact, String)> = pkg
+                .artifacts
+                .iter()
+                .filter(|a| a.is_wheel())
+                .filter_map(|a| Some((a, file(a)?)))
+                .collect();
+            if !wheels.is_empty() {
+                content.push_str("wheels = [\n");
+                for (artifact, line) in wheels {
+                    content.push_str(&format!("    {line},\n"));
+                    written.push(artifact);
+                }
+                content.push_str("]\n");
+            }
+            if written.is_empty() {
+                unlocated.push(pkg.name.clone());
+            }
+        }
+
+        packages.push(ExportedPackage {
+            name: pkg.name.clone(),
+            version: pkg.version.clone(),
+            requirement,
+            marker: None,
+            hashes: if hashes {
+                written.iter().map(|a| a.hash.clone()).collect()
+            } else {
+                Vec::new()
+            },
+        });
+    }
+
+    Exported {
+        content,
+        packages,
+        unhashed: Vec::new(),
+        unlocated,
+    }
+}
+
+/// The project's own `[[package]]` entry, with the requirements uv checks
+/// against pyproject.toml before trusting the lock.
+fn write_uv_project(
+    content: &mut String,
+    project: &UvProject,
+    locked: &BTreeMap<String, &Package>,
+) {
+    content.push_str(&format!(
+        "name = {}\n",
+        quoted(&normalize_project_name(&project.name))
+    ));
+    content.push_str(&format!("version = {}\n", quoted(&project.version)));
+    let kind = if project.editable {
+        "editable"
+    } else {
+        "virtual"
+    };
+    content.push_str(&format!("source = {{ {kind} = \".\" }}\n"));
+    write_uv_dependencies(content, "dependencies", &project.dependencies, locked);
+
+    for (table, groups) in [
+        ("optional-dependencies", &project.optional_dependencies),
+        ("dev-dependencies", &project.dependency_groups),
+    ] {
+        if groups.is_empty() {
+            continue;
+        }
+        content.push_str(&format!("\n[package.{table}]\n"));
+        for (group, deps) in groups {
+            write_uv_dependencies(content, &quoted_key(group), deps, locked);
+        }
+    }
+
+    content.push_str("\n[package.metadata]\n");
+    let requires_dist: Vec<String> = project
+        .dependencies
+        .iter()
+        .chain(project.optional_dependencies.values().flatten())
+        .filter_map(|spec| requirement_metadata(spec))
+        .collect();
+    write_array(content, "requires-dist", &requires_dist);
+    if !project.dependency_groups.is_empty() {
+        content.push_str("\n[package.metadata.requires-dev]\n");
+        for (group, deps) in &project.dependency_groups {
+            let entries: Vec<String> = deps
+                .iter()
+                .filter_map(|spec| requirement_metadata(spec))
+                .collect();
+            write_array(content, &quoted_key(group), &entries);
+        }
+    }
+}
+
+/// `key = [{ name = .. }, ..]` for the requirements naming locked packages.
+fn write_uv_dependencies(
+    content: &mut String,
+    key: &str,
+    requirements: &[String],
+    locked: &BTreeMap<String, &Package>,
+) {
+    let mut entries: Vec<String> = requirements
+        .iter()
+        .filter_map(|spec| {
+            let requirement = spec.parse::<Requirement>().ok()?;
+            let name = normalize_project_name(&requirement.name);
+            if !locked.contains_key(&name) {
+                return None;
+            }
+            let mut entry = format!("{{ name = {}", quoted(&name));
+            if !requirement.extras.is_empty() {
+                let extras: Vec<String> = requirement.extras.iter().map(|e| quoted(e)).collect();
+                entry.push_str(&format!(", extra = [{}]", extras.join(", ")));
+            }
+            if let Some(marker) = requirement.marker.as_deref().map(str::trim) {
+                entry.push_str(&format!(", marker = {}", quoted(marker)));
+            }
+            entry.push_str(" }");
+            Some(entry)
+        })
+        .collect();
+    entries.sort();
+    entries.dedup();
+    if !entries.is_empty() {
+        write_array(content, key, &entries);
+    }
+}
+
+/// A `requires-dist` entry: name, extras, specifier or URL, and marker.
+fn requirement_metadata(spec: &str) -> Option<String> {
+    let requirement = spec.parse::<Requirement>().ok()?;
+    let mut entry = format!(
+        "{{ name = {}",
+        quoted(&normalize_project_name(&requirement.name))
+    );
+    if !requirement.extras.is_empty() {
+        let extras: Vec<String> = requirement.extras.iter().map(|e| quoted(e)).collect();
+        entry.push_str(&format!(", extras = [{}]", extras.join(", ")));
+    }
+    if let Some(marker) = requirement.marker.as_deref().map(str::trim) {
+        entry.push_str(&format!(", marker = {}", quoted(marker)));
+    }
+    let head = spec.split(';').next().unwrap_or(spec).trim();
+    if let Some(url) = &requirement.url {
+        let key = if url.starts_with("git+") {
+            "git"
+        } else {
+            "url"
+        };
+        let url = url.strip_prefix("git+").unwrap_or(url);
+        entry.push_str(&format!(", {key} = {}", quoted(url)));
+    } else if let Some(at) = head.find(['=', '<', '>', '!', '~']) {
+        entry.push_str(&format!(", specifier = {}", quoted(head[at..].trim())));
+    }
+    entry.push_str(" }");
+    Some(entry)
+}
+
+fn write_array(content: &mut String, key: &str, entries: &[String]) {
+    if entries.is_empty() {
+        content.push_str(&format!("{key} = []\n"));
+        return;
+    }
+    content.push_str(&format!("{key} = [\n"));
+    for entry in entries {
+        content.push_str(&format!("    {entry},\n"));
+    }
+    content.push_str("]\n");
+}
+
+fn quoted(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+/// A bare TOML key when possible, else a quoted one.
+fn quoted_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        key.to_string()
+    } else {
+        quoted(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_uv_lock_packages_groups_and_forks() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = write(
+            temp.path(),
+            "uv.lock",
+            r#"version = 1
+requires-python = ">=3.11"
+
+[[package]]
+name = "app"
+version = "0.1.0"
+source = { virtual = "." }
+dependencies = [{ name = "httpx" }]
+
+[package.dev-dependencies]
+dev = [{ name = "pytest" }]
+
+[[package]]
+name = "httpx"
+version = "0.27.0"
+source = { registry = "https://pypi.org/simple" }
+dependencies = [{ name = "idna" }, { name = "exceptiongroup", marker = "python_full_version < '3.11'" }]
+sdist = { url = "https://files.example/httpx-0.27.0.tar.gz", hash = "sha256:aa" }
+wheels = [{ url = "https://files.example/httpx-0.27.0-py3-none-any.whl", hash = "sha256:bb" }]
+
+[[package]]
+name = "idna"
+version = "3.6"
+source = { registry = "https://pypi.org/simple" }
+wheels = [{ url = "https://files.example/idna-3.6-py3-none-any.whl", hash = "sha256:cc" }]
+
+[[package]]
+name = "idna"
+version = "3.7"
+source = { registry = "https://pypi.org/simple" }
+wheels = [{ url = "https://files.example/idna-3.7-py3-none-any.whl", hash = "sha256:dd" }]
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+source = { git = "https://github.com/pytest-dev/pytest?tag=8.0.0#0123456789abcdef0123456789abcdef01234567" }
+
+[[package]]
+name = "local-lib"
+version = "0.1.0"
+source = { directory = "../local-lib" }
+"#,
+        );
+
+        let converted = convert(&path, vec!["3.12".into()], vec!["any".into()]).unwrap();
+        assert_eq!(converted.format, LockFormat::Uv);
+        assert_eq!(converted.requires_python.as_deref(), Some(">=3.11"));
+        let lock = &converted.lock;
+        let names: Vec<&str> = lock.packages.keys().map(String::as_str).collect();
+        assert_eq!(names, ["httpx", "idna", "pytest"]);
+
+        let httpx = &lock.packages["httpx"];
+        assert_eq!(httpx.wheel, "httpx-0.27.0-py3-none-any.whl");
+        assert_eq!(httpx.hash, "sha256:bb");
+        assert_eq!(httpx.artifacts.len(), 2);
+        assert_eq!(
+            httpx.dependencies[1],
+            "exceptiongroup; python_full_version < '3.11'"
+        );
+        assert_eq!(httpx.groups, ["main"]);
+        assert_eq!(lock.packages["idna"].version, "3.7");
+        assert_eq!(
+            converted.forked,
+            [Forked {
+                name: "idna".into(),
+                versions: vec!["3.6".into(), "3.7".into()],
+                kept: "3.7".into(),
+            }]
+        );
+        assert_eq!(
+            lock.packages["pytest"].source,
+            PackageSource::Git {
+                url: "https://github.com/pytest-dev/pytest".into(),
+                reference: Some("8.0.0".into()),
+                subdirectory: None,
+                commit: "0123456789abcdef0123456789abcdef01234567".into(),
+            }
+        );
+        assert_eq!(lock.packages["pytest"].groups, ["dev"]);
+        assert_eq!(converted.skipped[0].entry, "local-lib 0.1.0");
+    }
+
+    #[test]
+    fn reads_poetry_lock_dependencies_and_groups() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = write(
+            temp.path(),
+            "poetry.lock",
+            r#"[[package]]
+name = "requests"
+version = "2.32.3"
+optional = false
+python-versions = ">=3.8"
+groups = ["main"]
+files = [
+    {file = "requests-2.32.3-py3-none-any.whl", hash = "sha256:aa"},
+    {file = "requests-2.32.3.tar.gz", hash = "sha256:bb"},
+]
+
+[package.dependencies]
+idna = ">=2.5,<4"
+PySocks = {version = ">=1.5.6,!=1.5.7", optional = true}
+urllib3 = {version = ">=1.21.1,<3", markers = "python_version >= \"3.8\""}
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+category = "dev"
+files = []
+
+[metadata]
+lock-version = "2.0"
+python-versions = "^3.10"
+content-hash = "abc"
+"#,
+        );
+
+        let converted = convert(&path, vec!["3.12".into()], vec!["any".into()]).unwrap();
+        assert_eq!(converted.format, LockFormat::Poetry);
+        assert_eq!(converted.requires_python.as_deref(), Some(">=3.10,<4.0"));
+        let requests = &converted.lock.packages["requests"];
+        assert_eq!(
+            requests.dependencies,
+            [
+                "idna>=2.5,<4",
+                "urllib3>=1.21.1,<3; python_version >= \"3.8\""
+            ]
+        );
+        assert_eq!(requests.wheel, "requests-2.32.3-py3-none-any.whl");
+        assert!(requests.artifacts.iter().all(|a| a.url.is_none()));
+        assert_eq!(
+            requests.source,
+            PackageSource::Registry {
+                index: "pypi".into(),
+                url: PYPI_SIMPLE.into(),
+            }
+        );
+        assert_eq!(converted.lock.packages["pytest"].groups, ["dev"]);
+    }
+
+    #[test]
+    fn uv_lock_export_reads_back() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+        lock.add_package(package(
+            "lib-a",
+            "1.0.0",
+            PackageSource::Registry {
+                index: "pypi".into(),
+                url: PYPI_SIMPLE.into(),
+            },
+            vec!["lib-c>=1; python_version >= \"3.8\"".into()],
+            vec![artifact(
+                "lib_a-1.0.0-py3-none-any.whl".into(),
+                Some("https://files.example/lib_a-1.0.0-py3-none-any.whl".into()),
+                "sha256:aa".into(),
+            )],
+            Vec::new(),
+        ));
+        lock.add_package(package(
+            "lib-c",
+            "1.0.0",
+            PackageSource::Registry {
+                index: "pypi".into(),
+                url: PYPI_SIMPLE.into(),
+            },
+            Vec::new(),
+            vec![artifact(
+                "lib_c-1.0.0-py3-none-any.whl".into(),
+                None,
+                "sha256:cc".into(),
+            )],
+            Vec::new(),
+        ));
+        let project = UvProject {
+            name: "App".into(),
+            version: "0.1.0".into(),
+            dependencies: vec!["lib-a>=1.0".into()],
+            ..UvProject::default()
+        };
+
+        let exported = uv_lock(&lock, &project, true);
+        assert!(
+            exported
+                .content
+                .starts_with("version = 1\nrequires-python = \">=3.12\"\n")
+        );
+        assert!(exported.content.contains(
+            "name = \"app\"\nversion = \"0.1.0\"\nsource = { virtual = \".\" }\n\
+             dependencies = [\n    { name = \"lib-a\" },\n]\n"
+        ));
+        assert!(
+            exported.content.contains(
+                "requires-dist = [\n    { name = \"lib-a\", specifier = \">=1.0\" },\n]\n"
+            )
+        );
+        assert_eq!(exported.unlocated, ["lib-c"]);
+
+        let path = write(temp.path(), "uv.lock", &exported.content);
+        let converted = convert(&path, vec!["3.12".into()], vec!["any".into()]).unwrap();
+        let back = &converted.lock.packages["lib-a"];
+        assert_eq!(back.artifacts, lock.packages["lib-a"].artifacts);
+        assert_eq!(back.dependencies, ["lib-c; python_version >= \"3.8\""]);
+        assert_eq!(back.groups, ["main"]);
+    }
+}
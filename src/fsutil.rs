@@ -0,0 +1,41 @@
+//! Filesystem helpers shared by the cache and snapshot modules.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// An I/O error and the path it happened on.
+#[derive(Debug, Error)]
+#[error("failed to access {path}: {source}")]
+pub struct PathIoError {
+    pub path: PathBuf,
+    pub source: std::io::Error,
+}
+
+fn path_err(path: &Path) -> impl FnOnce(std::io::Error) -> PathIoError + '_ {
+    move |source| PathIoError {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Write `data` to `path` through a `.partial` sibling and a rename, so
+/// readers never see a half-written file. Parent directories are created.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), PathIoError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(path_err(parent))?;
+    }
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{name}.partial-{}", std::process::id()));
+    fs::write(&tmp, data).map_err(path_err(&tmp))?;
+    fs::rename(&tmp, path).map_err(path_err(path))
+}
+
+/// Remote key of the JSON manifest `name` under `namespace`, e.g.
+/// `snapshots/<name>.json`.
+pub fn manifest_key(namespace: &str, name: &str) -> String {
+    format!("{namespace}/{name}.json")
+}
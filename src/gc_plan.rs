@@ -0,0 +1,287 @@
+//! Eviction plans for `pybun gc --dry-run --sweep`.
+//!
+//! For each candidate size limit the plan lists what `pybun gc --max-size`
+//! would evict from the wheel/build cache and the PEP 723 environment cache,
+//! annotated with the entry's recorded hits and misses (see
+//! [`crate::cache_stats`]), and recommends a limit. The output contains no
+//! timestamps and is sorted, so the same cache state always produces the
+//! same plan and plans from CI runs can be diffed.
+
+use crate::cache::{Cache, CacheError, parse_size};
+use crate::cache_stats::{self, EntryStats};
+use crate::pep723_cache::{Pep723Cache, Pep723CacheError};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+/// Limits simulated when `--sweep` is not given.
+pub const DEFAULT_SWEEP: [&str; 3] = ["1G", "5G", "10G"];
+
+/// Share of recorded cache hits a recommended limit must keep.
+pub const TARGET_RETAINED_HITS: f64 = 0.95;
+
+const PLAN_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum PlanError {
+    #[error("invalid size {0:?}: {1}")]
+    InvalidSize(String, String),
+    #[error(transparent)]
+    Cache(#[from] CacheError),
+    #[error(transparent)]
+    Pep723(#[from] Pep723CacheError),
+}
+
+pub type Result<T> = std::result::Result<T, PlanError>;
+
+/// Dry-run eviction plan across several size limits.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvictionPlan {
+    pub version: u32,
+    /// Current size of the collectable cache (wheels, builds, PEP 723 envs).
+    pub total_bytes: u64,
+    /// Hits recorded for entries currently in the cache.
+    pub recorded_hits: u64,
+    pub candidates: Vec<CandidatePlan>,
+    pub recommendation: Recommendation,
+    /// What `--max-age` / `--budget` evict before any size limit applies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<crate::gc_policy::PolicyPlan>,
+}
+
+/// What one size limit would evict.
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidatePlan {
+    /// The limit as given (e.g. `5G`).
+    pub limit: String,
+    pub limit_bytes: u64,
+    pub evicted_bytes: u64,
+    pub evicted_entries: usize,
+    /// Share of recorded hits on entries this limit keeps; `None` without
+    /// recorded hits.
+    pub retained_hit_ratio: Option<f64>,
+    pub evicted: Vec<PlannedEviction>,
+}
+
+/// A cache entry a candidate limit would evict.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedEviction {
+    /// `wheels/<ab>/<sha256>/<file>`, `packages/<name>/<file>`, `build/<file>`
+    /// or `pep723/<hash>`.
+    pub key: String,
+    pub size_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: Option<f64>,
+    pub last_hit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Recommendation {
+    pub limit: Option<String>,
+    pub limit_bytes: Option<u64>,
+    pub reason: String,
+}
+
+/// Parse and sort candidate limits, smallest first; duplicates are dropped.
+pub fn parse_limits(limits: &[String]) -> Result<Vec<(String, u64)>> {
+    let mut parsed: Vec<(String, u64)> = Vec::new();
+    for limit in limits {
+        let limit = limit.trim();
+        if limit.is_empty() {
+            continue;
+        }
+        let bytes = parse_size(limit).map_err(|e| PlanError::InvalidSize(limit.to_string(), e))?;
+        if !parsed.iter().any(|(_, b)| *b == bytes) {
+            parsed.push((limit.to_string(), bytes));
+        }
+    }
+    parsed.sort_by_key(|(_, bytes)| *bytes);
+    Ok(parsed)
+}
+
+/// Simulate `pybun gc --max-size` for each of `limits` without deleting anything.
+pub fn build_plan(
+    cache: &Cache,
+    pep723: &Pep723Cache,
+    pinned: &BTreeSet<String>,
+    limits: &[(String, u64)],
+    stats: &BTreeMap<String, EntryStats>,
+) -> Result<EvictionPlan> {
+    let total_bytes = cache.total_size()? + pep723.total_size()?;
+    let present = present_keys(cache, pep723)?;
+    let recorded_hits: u64 = present
+        .iter()
+        .filter_map(|key| stats.get(key))
+        .map(|s| s.hits)
+        .sum();
+
+    let mut candidates = Vec::with_capacity(limits.len());
+    for (limit, limit_bytes) in limits {
+        let files = cache.gc_with_pins(Some(*limit_bytes), true, pinned)?;
+        let envs = pep723.gc(Some(*limit_bytes), true)?;
+
+        let mut evicted = Vec::new();
+        for path in &files.would_remove {
+            let Some(key) = cache_stats::path_key(cache.root(), path) else {
+                continue;
+            };
+            let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            evicted.push(planned(key, size, stats));
+        }
+        for hash in &envs.would_remove {
+            evicted.push(planned(
+                cache_stats::pep723_key(hash),
+                pep723.env_size(hash)?,
+                stats,
+            ));
+        }
+        evicted.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let lost_hits: u64 = evicted.iter().map(|e| e.hits).sum();
+        candidates.push(CandidatePlan {
+            limit: limit.clone(),
+            limit_bytes: *limit_bytes,
+            evicted_bytes: files.freed_bytes + envs.freed_bytes,
+            evicted_entries: evicted.len(),
+            retained_hit_ratio: (recorded_hits > 0)
+                .then(|| round(1.0 - lost_hits as f64 / recorded_hits as f64)),
+            evicted,
+        });
+    }
+
+    let recommendation = recommend(&candidates);
+    Ok(EvictionPlan {
+        version: PLAN_VERSION,
+        total_bytes,
+        recorded_hits,
+        candidates,
+        recommendation,
+        policy: None,
+    })
+}
+
+/// Smallest limit keeping [`TARGET_RETAINED_HITS`] of recorded hits; without
+/// hit data, the smallest limit that evicts nothing.
+pub fn recommend(candidates: &[CandidatePlan]) -> Recommendation {
+    let pick = |candidate: &CandidatePlan, reason: String| Recommendation {
+        limit: Some(candidate.limit.clone()),
+        limit_bytes: Some(candidate.limit_bytes),
+        reason,
+    };
+    let Some(largest) = candidates.last() else {
+        return Recommendation {
+            limit: None,
+            limit_bytes: None,
+            reason: "no candidate limits".to_string(),
+        };
+    };
+    if largest.retained_hit_ratio.is_some() {
+        if let Some(candidate) = candidates.iter().find(|c| {
+            c.retained_hit_ratio
+                .is_some_and(|r| r >= TARGET_RETAINED_HITS)
+        }) {
+            return pick(
+                candidate,
+                format!(
+                    "smallest limit keeping at least {:.0}% of recorded cache hits",
+                    TARGET_RETAINED_HITS * 100.0
+                ),
+            );
+        }
+        return pick(
+            largest,
+            format!(
+                "no candidate keeps {:.0}% of recorded cache hits; consider a larger limit",
+                TARGET_RETAINED_HITS * 100.0
+            ),
+        );
+    }
+    if let Some(candidate) = candidates.iter().find(|c| c.evicted_entries == 0) {
+        return pick(
+            candidate,
+            "no hit history recorded; smallest limit that evicts nothing".to_string(),
+        );
+    }
+    pick(
+        largest,
+        "no hit history recorded and every candidate evicts entries; consider a larger limit"
+            .to_string(),
+    )
+}
+
+fn planned(key: String, size_bytes: u64, stats: &BTreeMap<String, EntryStats>) -> PlannedEviction {
+    let entry = stats.get(&key).cloned().unwrap_or_default();
+    PlannedEviction {
+        size_bytes,
+        hits: entry.hits,
+        misses: entry.misses,
+        hit_rate: entry.hit_rate().map(round),
+        last_hit: entry.last_hit,
+        key,
+    }
+}
+
+fn present_keys(cache: &Cache, pep723: &Pep723Cache) -> Result<BTreeSet<String>> {
+    let mut keys = BTreeSet::new();
+    for path in cache.entry_paths()? {
+        keys.extend(cache_stats::path_key(cache.root(), &path));
+    }
+    for env in pep723.list_cached_envs()? {
+        keys.insert(cache_stats::pep723_key(&env.hash));
+    }
+    Ok(keys)
+}
+
+/// Round ratios so plans diff cleanly.
+fn round(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(limit: &str, bytes: u64, evicted: usize, ratio: Option<f64>) -> CandidatePlan {
+        CandidatePlan {
+            limit: limit.to_string(),
+            limit_bytes: bytes,
+            evicted_bytes: 0,
+            evicted_entries: evicted,
+            retained_hit_ratio: ratio,
+            evicted: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parse_limits_sorts_and_dedupes() {
+        let limits = parse_limits(&["10G".into(), "1G".into(), "1024M".into()]).unwrap();
+        assert_eq!(
+            limits.iter().map(|(l, _)| l.as_str()).collect::<Vec<_>>(),
+            vec!["1G", "10G"]
+        );
+        assert!(parse_limits(&["lots".into()]).is_err());
+    }
+
+    #[test]
+    fn recommends_smallest_limit_keeping_hits() {
+        let candidates = vec![
+            candidate("1G", 1, 3, Some(0.5)),
+            candidate("5G", 5, 1, Some(0.97)),
+            candidate("10G", 10, 0, Some(1.0)),
+        ];
+        assert_eq!(recommend(&candidates).limit.as_deref(), Some("5G"));
+    }
+
+    #[test]
+    fn recommends_smallest_non_evicting_limit_without_history() {
+        let candidates = vec![
+            candidate("1G", 1, 3, None),
+            candidate("5G", 5, 0, None),
+            candidate("10G", 10, 0, None),
+        ];
+        assert_eq!(recommend(&candidates).limit.as_deref(), Some("5G"));
+        let all_evict = vec![candidate("1G", 1, 3, None), candidate("5G", 5, 1, None)];
+        assert_eq!(recommend(&all_evict).limit.as_deref(), Some("5G"));
+    }
+}
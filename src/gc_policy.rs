@@ -0,0 +1,419 @@
+//! Age and budget policies for `pybun gc`.
+//!
+//! `--max-size` trims the cache as a whole. A policy instead works per
+//! category:
+//!
+//! - `--max-age 30d` evicts wheels (and build artifacts) and PEP 723
+//!   environments that were not used for that long. Runtimes are left out
+//!   because nothing records when an interpreter was last used.
+//! - `--budget wheels=5G,envs=2G,runtimes=3G` evicts the least recently used
+//!   entries of each category until it fits its budget.
+//! - Wheels in the keep set (locked by pinned projects, and with
+//!   `--keep-used-by-lockfiles` by any registered project) are never evicted.
+//!
+//! [`plan`] decides everything up front, so a dry run and a real run evict
+//! the same entries and the plan can be printed or saved as JSON first.
+
+use crate::cache::{Cache, parse_size};
+use crate::cache_inspect::{self, EntryKind, Selection};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+const PLAN_VERSION: u32 = 1;
+
+/// What a budget or eviction applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// Wheels, legacy per-package wheels and build artifacts.
+    Wheels,
+    /// PEP 723 script environments.
+    Envs,
+    /// Installed Python runtimes.
+    Runtimes,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Category::Wheels, Category::Envs, Category::Runtimes];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Wheels => "wheels",
+            Category::Envs => "envs",
+            Category::Runtimes => "runtimes",
+        }
+    }
+}
+
+/// Per-category size limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Budgets {
+    pub wheels: Option<u64>,
+    pub envs: Option<u64>,
+    pub runtimes: Option<u64>,
+}
+
+impl Budgets {
+    pub fn get(&self, category: Category) -> Option<u64> {
+        match category {
+            Category::Wheels => self.wheels,
+            Category::Envs => self.envs,
+            Category::Runtimes => self.runtimes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        Category::ALL.iter().all(|c| self.get(*c).is_none())
+    }
+}
+
+/// Parse `--budget` values such as `wheels=5G` (also `envs=`, `runtimes=`).
+pub fn parse_budgets(values: &[String]) -> Result<Budgets, String> {
+    let mut budgets = Budgets::default();
+    for value in values.iter().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        let (category, size) = value
+            .split_once('=')
+            .ok_or_else(|| format!("invalid budget `{value}` (expected CATEGORY=SIZE)"))?;
+        let bytes = parse_size(size).map_err(|e| format!("invalid budget `{value}`: {e}"))?;
+        let slot = match category.trim() {
+            "wheels" => &mut budgets.wheels,
+            "envs" => &mut budgets.envs,
+            "runtimes" => &mut budgets.runtimes,
+            other => {
+                return Err(format!(
+                    "unknown budget category `{other}` (use wheels, envs or runtimes)"
+                ));
+            }
+        };
+        *slot = Some(bytes);
+    }
+    Ok(budgets)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GcPolicy {
+    pub max_age: Option<Duration>,
+    pub budgets: Budgets,
+    /// Wheel filenames that are never evicted.
+    pub keep: BTreeSet<String>,
+}
+
+impl GcPolicy {
+    /// Whether the policy evicts anything at all.
+    pub fn is_active(&self) -> bool {
+        self.max_age.is_some() || !self.budgets.is_empty()
+    }
+}
+
+/// Why an entry is evicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    MaxAge,
+    Budget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyEviction {
+    pub category: Category,
+    /// Cache-relative path of a wheel or build artifact, environment hash or
+    /// runtime version.
+    pub id: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// Unix timestamp (seconds) of the last use.
+    pub last_used: Option<u64>,
+    pub reason: Reason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryPlan {
+    pub category: Category,
+    pub size_before: u64,
+    pub budget: Option<u64>,
+    pub evicted_bytes: u64,
+    pub evicted_entries: usize,
+    pub size_after: u64,
+    /// Entries that would have been evicted but are locked by a project.
+    pub kept_locked: usize,
+    /// Still over budget once everything evictable is gone.
+    pub over_budget: bool,
+}
+
+/// Everything a policy evicts, grouped by category and sorted by last use.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyPlan {
+    pub version: u32,
+    pub max_age_secs: Option<u64>,
+    pub categories: Vec<CategoryPlan>,
+    pub evictions: Vec<PolicyEviction>,
+}
+
+impl PolicyPlan {
+    pub fn evicted_bytes(&self) -> u64 {
+        self.categories.iter().map(|c| c.evicted_bytes).sum()
+    }
+
+    /// Cache files (wheels and build artifacts) the plan evicts.
+    pub fn planned_files(&self) -> BTreeSet<PathBuf> {
+        self.evictions
+            .iter()
+            .filter(|e| e.category == Category::Wheels)
+            .map(|e| e.path.clone())
+            .collect()
+    }
+
+    /// PEP 723 environment hashes the plan evicts.
+    pub fn planned_envs(&self) -> BTreeSet<String> {
+        self.evictions
+            .iter()
+            .filter(|e| e.category == Category::Envs)
+            .map(|e| e.id.clone())
+            .collect()
+    }
+}
+
+struct Candidate {
+    id: String,
+    path: PathBuf,
+    size_bytes: u64,
+    last_used: Option<u64>,
+    keep: bool,
+}
+
+/// Decide what `policy` evicts from `cache`, as of `now` (Unix seconds).
+pub fn plan(cache: &Cache, policy: &GcPolicy, now: u64) -> PolicyPlan {
+    let mut categories = Vec::new();
+    let mut evictions = Vec::new();
+    for category in Category::ALL {
+        let mut candidates = candidates(cache, category, &policy.keep);
+        // Oldest first; ties break on the id so plans are deterministic.
+        candidates.sort_by(|a, b| {
+            (a.last_used.unwrap_or(0), &a.id).cmp(&(b.last_used.unwrap_or(0), &b.id))
+        });
+        let size_before: u64 = candidates.iter().map(|c| c.size_bytes).sum();
+        let budget = policy.budgets.get(category);
+        let cutoff = policy
+            .max_age
+            .filter(|_| category != Category::Runtimes)
+            .map(|age| now.saturating_sub(age.as_secs()));
+
+        let mut size = size_before;
+        let mut entry = CategoryPlan {
+            category,
+            size_before,
+            budget,
+            evicted_bytes: 0,
+            evicted_entries: 0,
+            size_after: size_before,
+            kept_locked: 0,
+            over_budget: false,
+        };
+        for candidate in candidates {
+            let expired = cutoff.is_some_and(|cutoff| candidate.last_used.unwrap_or(0) < cutoff);
+            let over_budget = budget.is_some_and(|budget| size > budget);
+            let reason = match (expired, over_budget) {
+                (true, _) => Reason::MaxAge,
+                (false, true) => Reason::Budget,
+                (false, false) => continue,
+            };
+            if candidate.keep {
+                entry.kept_locked += 1;
+                continue;
+            }
+            size -= candidate.size_bytes;
+            entry.evicted_bytes += candidate.size_bytes;
+            entry.evicted_entries += 1;
+            evictions.push(PolicyEviction {
+                category,
+                id: candidate.id,
+                path: candidate.path,
+                size_bytes: candidate.size_bytes,
+                last_used: candidate.last_used,
+                reason,
+            });
+        }
+        entry.size_after = size;
+        entry.over_budget = budget.is_some_and(|budget| size > budget);
+        categories.push(entry);
+    }
+    PolicyPlan {
+        version: PLAN_VERSION,
+        max_age_secs: policy.max_age.map(|age| age.as_secs()),
+        categories,
+        evictions,
+    }
+}
+
+fn candidates(cache: &Cache, category: Category, keep: &BTreeSet<String>) -> Vec<Candidate> {
+    match category {
+        Category::Wheels => cache
+            .collect_cache_entries()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| Candidate {
+                id: crate::cache_stats::path_key(cache.root(), &entry.path)
+                    .unwrap_or_else(|| entry.path.display().to_string()),
+                keep: entry
+                    .path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| keep.contains(name)),
+                size_bytes: entry.size,
+                last_used: entry
+                    .accessed
+                    .duration_since(UNIX_EPOCH)
+                    .ok()
+                    .map(|d| d.as_secs()),
+                path: entry.path,
+            })
+            .collect(),
+        Category::Envs | Category::Runtimes => {
+            let (kind, selection) = if category == Category::Envs {
+                (
+                    EntryKind::Env,
+                    Selection {
+                        envs: true,
+                        ..Selection::default()
+                    },
+                )
+            } else {
+                (
+                    EntryKind::Runtime,
+                    Selection {
+                        runtimes: true,
+                        ..Selection::default()
+                    },
+                )
+            };
+            cache_inspect::list(cache, selection)
+                .into_iter()
+                .filter(|item| item.kind == kind && !item.shared)
+                .map(|item| Candidate {
+                    id: item.id,
+                    path: item.path,
+                    size_bytes: item.size_bytes,
+                    last_used: item.last_used,
+                    keep: false,
+                })
+                .collect()
+        }
+    }
+}
+
+/// Delete what `plan` evicts. Returns the entries removed and the failures.
+pub fn apply(plan: &PolicyPlan) -> (usize, Vec<(PathBuf, std::io::Error)>) {
+    let mut removed = 0;
+    let mut failures = Vec::new();
+    for eviction in &plan.evictions {
+        let result = if eviction.path.is_dir() {
+            fs::remove_dir_all(&eviction.path)
+        } else {
+            fs::remove_file(&eviction.path)
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => failures.push((eviction.path.clone(), e)),
+        }
+    }
+    (removed, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    const DAY: u64 = 86_400;
+
+    fn wheel(cache: &Cache, name: &str, size: usize, age_days: u64, now: u64) {
+        let path = cache.root().join(Cache::stored_wheel_relative(
+            &format!("{:0>64}", name.len()),
+            name,
+        ));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![0u8; size]).unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(now - age_days * DAY);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn parses_budgets() {
+        let budgets = parse_budgets(&["wheels=5G".into(), "runtimes=1M".into()]).unwrap();
+        assert_eq!(budgets.wheels, Some(5 * 1024 * 1024 * 1024));
+        assert_eq!(budgets.envs, None);
+        assert_eq!(budgets.runtimes, Some(1024 * 1024));
+        assert!(parse_budgets(&["disks=1G".into()]).is_err());
+        assert!(parse_budgets(&["wheels".into()]).is_err());
+    }
+
+    #[test]
+    fn evicts_by_age_then_budget_and_keeps_locked_wheels() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::with_root(temp.path());
+        let now = now();
+        wheel(&cache, "old-1.0-py3-none-any.whl", 100, 60, now);
+        wheel(&cache, "locked-1.0-py3-none-any.whl", 100, 90, now);
+        wheel(&cache, "mid-1.0-py3-none-any.whl", 100, 10, now);
+        wheel(&cache, "new-1.0-py3-none-any.whl", 100, 1, now);
+
+        let policy = GcPolicy {
+            max_age: Some(Duration::from_secs(30 * DAY)),
+            budgets: Budgets {
+                wheels: Some(250),
+                ..Budgets::default()
+            },
+            keep: BTreeSet::from(["locked-1.0-py3-none-any.whl".to_string()]),
+        };
+        let plan = plan(&cache, &policy, now);
+        let evicted: Vec<(&str, Reason)> = plan
+            .evictions
+            .iter()
+            .map(|e| (e.path.file_name().unwrap().to_str().unwrap(), e.reason))
+            .collect();
+        assert_eq!(
+            evicted,
+            [
+                ("old-1.0-py3-none-any.whl", Reason::MaxAge),
+                ("mid-1.0-py3-none-any.whl", Reason::Budget),
+            ]
+        );
+        let wheels = &plan.categories[0];
+        assert_eq!(wheels.kept_locked, 1);
+        assert_eq!(wheels.size_after, 200);
+        assert!(!wheels.over_budget);
+
+        // Only the locked wheel is left, and it does not fit.
+        let tight = GcPolicy {
+            budgets: Budgets {
+                wheels: Some(50),
+                ..Budgets::default()
+            },
+            ..policy.clone()
+        };
+        let tight = super::plan(&cache, &tight, now);
+        assert_eq!(tight.evictions.len(), 3);
+        assert_eq!(tight.categories[0].size_after, 100);
+        assert!(tight.categories[0].over_budget);
+
+        let (removed, failures) = apply(&plan);
+        assert_eq!((removed, failures.len()), (2, 0));
+        assert_eq!(cache.total_size().unwrap(), 200);
+    }
+}
@@ -0,0 +1,510 @@
+//! Dependencies installed from git repositories.
+//!
+//! A PEP 508 direct reference such as
+//! `mypkg @ git+https://github.com/org/repo@v1.2.3#subdirectory=pkg` names a
+//! repository, an optional ref (tag, branch or commit) and an optional
+//! project subdirectory. `pybun install` resolves the ref to a commit, which
+//! the lockfile pins as [`PackageSource::Git`], and builds a wheel from that
+//! commit with the project's PEP 517 backend.
+//!
+//! Layout under the cache root:
+//!
+//! - `git/db/<repo>`: bare repository the refs are fetched into (shallow);
+//!   `refs/pybun/refs/<ref>` remembers what each ref last resolved to, so an
+//!   offline install can still resolve it,
+//! - `git/checkouts/<repo>/<commit>`: the files of a commit, exported once.
+//!
+//! `<repo>` hashes the repository URL. Wheels built from a checkout are kept
+//! in the sdist build cache keyed by commit, so nothing is rebuilt until the
+//! pinned commit changes.
+
+use crate::archive::{self, ArchiveError, ArchiveFormat, ExtractOptions};
+use crate::cache::Cache;
+use crate::lockfile::PackageSource;
+use crate::network_policy::{self, NetworkPolicyViolation, Operation};
+use crate::runtime_source::sanitize;
+use crate::tool_exec::{ToolError, ToolRun};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GitSourceError {
+    #[error("cache error: {0}")]
+    Cache(#[from] crate::cache::CacheError),
+    #[error(transparent)]
+    Network(#[from] NetworkPolicyViolation),
+    #[error("{0}")]
+    Git(#[from] ToolError),
+    #[error("{0} has not been fetched and offline mode is enabled")]
+    Offline(String),
+    #[error("invalid git source {url}: {reason}")]
+    Invalid { url: String, reason: String },
+    #[error("subdirectory {subdirectory} does not exist in {url} at {commit}")]
+    MissingSubdirectory {
+        url: String,
+        commit: String,
+        subdirectory: String,
+    },
+    #[error("archive error: {0}")]
+    Archive(#[from] ArchiveError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, GitSourceError>;
+
+/// A `git+` direct reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+    /// Repository URL, without the `git+` prefix.
+    pub url: String,
+    /// Tag, branch or commit; the remote's `HEAD` when `None`.
+    pub reference: Option<String>,
+    /// Directory of the project inside the repository.
+    pub subdirectory: Option<String>,
+}
+
+impl GitSource {
+    /// Parse `git+<url>[@<ref>][#subdirectory=<dir>]`. `None` for anything
+    /// that is not a `git+` URL.
+    pub fn parse(direct_url: &str) -> Option<Self> {
+        let rest = direct_url.trim().strip_prefix("git+")?;
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        let subdirectory = fragment.and_then(|fragment| {
+            fragment
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("subdirectory="))
+                .filter(|dir| !dir.is_empty())
+                .map(str::to_string)
+        });
+        // The ref follows the last `@` of the path; an `@` in the authority
+        // (`ssh://git@host/...`) belongs to the user name.
+        let path_start = rest
+            .find("://")
+            .map(|scheme_end| {
+                let authority = scheme_end + 3;
+                rest[authority..]
+                    .find('/')
+                    .map_or(rest.len(), |slash| authority + slash)
+            })
+            .unwrap_or(0);
+        let (url, reference) = match rest[path_start..].rfind('@') {
+            Some(at) => {
+                let at = path_start + at;
+                (&rest[..at], Some(rest[at + 1..].to_string()))
+            }
+            None => (rest, None),
+        };
+        if url.is_empty() {
+            return None;
+        }
+        Some(Self {
+            url: url.to_string(),
+            reference: reference.filter(|r| !r.is_empty()),
+            subdirectory,
+        })
+    }
+
+    /// Lockfile source pinning this reference to `commit`.
+    pub fn locked(&self, commit: &str) -> PackageSource {
+        PackageSource::Git {
+            url: self.url.clone(),
+            reference: self.reference.clone(),
+            subdirectory: self.subdirectory.clone(),
+            commit: commit.to_string(),
+        }
+    }
+
+    /// The reference and pinned commit of a locked git source.
+    pub fn from_locked(source: &PackageSource) -> Option<(Self, String)> {
+        match source {
+            PackageSource::Git {
+                url,
+                reference,
+                subdirectory,
+                commit,
+            } => Some((
+                Self {
+                    url: url.clone(),
+                    reference: reference.clone(),
+                    subdirectory: subdirectory.clone(),
+                },
+                commit.clone(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Key of the wheel built from `commit`: the commit, plus the
+    /// subdirectory when the repository holds several projects.
+    pub fn build_key(&self, commit: &str) -> String {
+        match &self.subdirectory {
+            Some(subdirectory) => format!("{}-{}", commit, sanitize(subdirectory)),
+            None => commit.to_string(),
+        }
+    }
+
+    /// The reference names a full commit, which needs no resolving.
+    fn pinned_commit(&self) -> Option<&str> {
+        self.reference.as_deref().filter(|r| is_commit_id(r))
+    }
+
+    /// Refuse references git would read as options and subdirectories that
+    /// leave the checkout. Both come from requirement strings and lockfiles,
+    /// which may not be the user's own.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(reference) = &self.reference
+            && reference.starts_with('-')
+        {
+            return Err(self.invalid(format!("ref '{reference}' starts with '-'")));
+        }
+        if let Some(subdirectory) = &self.subdirectory
+            && !Path::new(subdirectory)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(self.invalid(format!(
+                "subdirectory '{subdirectory}' must be a relative path inside the repository"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Refuse a locked commit that is not a full commit id.
+    pub fn validate_commit(&self, commit: &str) -> Result<()> {
+        if is_commit_id(commit) {
+            Ok(())
+        } else {
+            Err(self.invalid(format!(
+                "locked commit '{commit}' is not a 40-character hex commit id"
+            )))
+        }
+    }
+
+    fn invalid(&self, reason: String) -> GitSourceError {
+        GitSourceError::Invalid {
+            url: self.url.clone(),
+            reason,
+        }
+    }
+}
+
+fn is_commit_id(value: &str) -> bool {
+    value.len() == 40 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Repositories and checkouts of git dependencies.
+#[derive(Debug)]
+pub struct GitCache {
+    root: PathBuf,
+}
+
+impl GitCache {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_cache(&Cache::new()?))
+    }
+
+    /// Git cache inside `cache` (useful for testing).
+    pub fn with_cache(cache: &Cache) -> Self {
+        Self {
+            root: cache.root().join("git"),
+        }
+    }
+
+    /// The commit `source`'s ref points at, fetched into the cache. Offline,
+    /// the commit it resolved to when last fetched.
+    pub fn resolve(&self, source: &GitSource, offline: bool) -> Result<String> {
+        source.validate()?;
+        if let Some(commit) = source.pinned_commit() {
+            return Ok(commit.to_ascii_lowercase());
+        }
+        let db = self.db(&source.url)?;
+        let reference = source.reference.as_deref().unwrap_or("HEAD");
+        let remembered = format!("refs/pybun/refs/{}", sanitize(reference));
+        if offline {
+            return git(&db, &["rev-parse", "--verify", "--quiet", &remembered])
+                .map_err(|_| offline_miss(format!("{}@{}", source.url, reference)));
+        }
+        fetch(&db, &source.url, reference)?;
+        let commit = git(&db, &["rev-parse", "FETCH_HEAD"])?;
+        git(&db, &["update-ref", &remembered, &commit])?;
+        git(
+            &db,
+            &[
+                "update-ref",
+                &format!("refs/pybun/commits/{commit}"),
+                &commit,
+            ],
+        )?;
+        Ok(commit)
+    }
+
+    /// Directory of the project at `commit`: the exported commit, or its
+    /// `subdirectory`. The commit is fetched when the cache lacks it.
+    pub fn checkout(&self, source: &GitSource, commit: &str, offline: bool) -> Result<PathBuf> {
+        source.validate()?;
+        source.validate_commit(commit)?;
+        let dest = self
+            .root
+            .join("checkouts")
+            .join(repo_key(&source.url))
+            .join(commit);
+        if !dest.exists() {
+            let db = self.db(&source.url)?;
+            let object = format!("{commit}^{{commit}}");
+            if git(&db, &["cat-file", "-e", &object]).is_err() {
+                if offline {
+                    return Err(offline_miss(format!("{}@{}", source.url, commit)));
+                }
+                fetch(&db, &source.url, commit)?;
+                git(
+                    &db,
+                    &[
+                        "update-ref",
+                        &format!("refs/pybun/commits/{commit}"),
+                        commit,
+                    ],
+                )?;
+            }
+            export(&db, commit, &dest)?;
+        }
+        match &source.subdirectory {
+            Some(subdirectory) => {
+                let project = dest.join(subdirectory);
+                if project.is_dir() {
+                    Ok(project)
+                } else {
+                    Err(GitSourceError::MissingSubdirectory {
+                        url: source.url.clone(),
+                        commit: commit.to_string(),
+                        subdirectory: subdirectory.clone(),
+                    })
+                }
+            }
+            None => Ok(dest),
+        }
+    }
+
+    /// The bare repository `url` is fetched into, created on first use.
+    fn db(&self, url: &str) -> Result<PathBuf> {
+        let db = self.root.join("db").join(repo_key(url));
+        if !db.join("HEAD").exists() {
+            fs::create_dir_all(&db)?;
+            git(&db, &["init", "--bare", "--quiet"])?;
+        }
+        Ok(db)
+    }
+}
+
+/// Shallow fetch of `reference` from `url` into `db`'s `FETCH_HEAD`.
+fn fetch(db: &Path, url: &str, reference: &str) -> Result<()> {
+    network_policy::check_url(Operation::Index, url)?;
+    git(
+        db,
+        &["fetch", "--quiet", "--depth", "1", "--", url, reference],
+    )?;
+    Ok(())
+}
+
+/// Write the files of `commit` to `dest` (atomically, via a sibling
+/// directory).
+fn export(db: &Path, commit: &str, dest: &Path) -> Result<()> {
+    let parent = dest.parent().expect("checkouts have a parent");
+    fs::create_dir_all(parent)?;
+    let staging = tempfile::tempdir_in(parent)?;
+    let tarball = staging.path().join("tree.tar");
+    let tarball_arg = tarball.display().to_string();
+    git(db, &["archive", "--format=tar", "-o", &tarball_arg, commit])?;
+    let tree = staging.path().join("tree");
+    archive::extract_as(
+        ArchiveFormat::Tar,
+        &tarball,
+        &tree,
+        ExtractOptions::default(),
+    )?;
+    match fs::rename(&tree, dest) {
+        Ok(()) => Ok(()),
+        // Another install exported the same commit first.
+        Err(_) if dest.exists() => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Run `git` in `dir`. Every git command PyBun runs can be repeated, so
+/// network failures are retried.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(dir);
+    let label = format!("git {}", args.first().copied().unwrap_or_default());
+    let output = ToolRun::new(label, &mut cmd).idempotent().run()?;
+    Ok(output.stdout_text())
+}
+
+fn repo_key(url: &str) -> String {
+    let name = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .next()
+        .map(sanitize)
+        .unwrap_or_default();
+    format!(
+        "{}-{}",
+        name,
+        hex::encode(&Sha256::digest(url.as_bytes())[..8])
+    )
+}
+
+/// A ref as a single ref/path component.
+/// An offline cache miss, recorded for the `E_NETWORK_REQUIRED` report.
+fn offline_miss(what: String) -> GitSourceError {
+    crate::offline::require(crate::offline::MissingArtifact::new(
+        crate::offline::ArtifactKind::Git,
+        what.clone(),
+        None,
+    ));
+    GitSourceError::Offline(what)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_git_direct_references() {
+        assert_eq!(
+            GitSource::parse("git+https://github.com/org/repo@v1.2.3"),
+            Some(GitSource {
+                url: "https://github.com/org/repo".to_string(),
+                reference: Some("v1.2.3".to_string()),
+                subdirectory: None,
+            })
+        );
+        assert_eq!(
+            GitSource::parse("git+ssh://git@github.com/org/repo.git#subdirectory=pkg"),
+            Some(GitSource {
+                url: "ssh://git@github.com/org/repo.git".to_string(),
+                reference: None,
+                subdirectory: Some("pkg".to_string()),
+            })
+        );
+        assert_eq!(
+            GitSource::parse("git+https://example.com/repo@feature/x")
+                .unwrap()
+                .reference
+                .as_deref(),
+            Some("feature/x")
+        );
+        assert_eq!(GitSource::parse("https://example.com/demo.whl"), None);
+
+        let source = GitSource::parse("git+https://github.com/org/repo@main").unwrap();
+        let locked = source.locked("abc123");
+        assert_eq!(
+            GitSource::from_locked(&locked),
+            Some((source, "abc123".to_string()))
+        );
+    }
+
+    #[test]
+    fn full_commit_references_need_no_fetch() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = GitCache::with_cache(&Cache::with_root(temp.path()));
+        let commit = "0123456789ABCDEF0123456789abcdef01234567";
+        let source = GitSource {
+            url: "https://example.invalid/repo".to_string(),
+            reference: Some(commit.to_string()),
+            subdirectory: None,
+        };
+        assert_eq!(
+            cache.resolve(&source, true).unwrap(),
+            commit.to_ascii_lowercase()
+        );
+        assert!(!temp.path().join("git").exists());
+    }
+
+    fn source(reference: Option<&str>, subdirectory: Option<&str>) -> GitSource {
+        GitSource {
+            url: "https://example.invalid/repo".to_string(),
+            reference: reference.map(str::to_string),
+            subdirectory: subdirectory.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn refs_that_look_like_options_are_rejected() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = GitCache::with_cache(&Cache::with_root(temp.path()));
+        let source = source(Some("--upload-pack=touch pwned"), None);
+        assert!(matches!(
+            cache.resolve(&source, false),
+            Err(GitSourceError::Invalid { .. })
+        ));
+        assert!(!temp.path().join("git").exists());
+    }
+
+    #[test]
+    fn locked_commits_must_be_full_commit_ids() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = GitCache::with_cache(&Cache::with_root(temp.path()));
+        let source = source(Some("main"), None);
+        for commit in ["--output=/tmp/x", "HEAD", "abc123", "../../escape"] {
+            assert!(
+                matches!(
+                    cache.checkout(&source, commit, true),
+                    Err(GitSourceError::Invalid { .. })
+                ),
+                "{commit}"
+            );
+        }
+        assert!(
+            source
+                .validate_commit("0123456789abcdef0123456789abcdef01234567")
+                .is_ok()
+        );
+        assert!(!temp.path().join("git").exists());
+    }
+
+    #[test]
+    fn subdirectories_must_stay_inside_the_checkout() {
+        for subdirectory in ["../outside", "pkg/../../outside", "/etc"] {
+            assert!(
+                source(None, Some(subdirectory)).validate().is_err(),
+                "{subdirectory}"
+            );
+        }
+        for subdirectory in ["pkg", "./libs/pkg"] {
+            assert!(
+                source(None, Some(subdirectory)).validate().is_ok(),
+                "{subdirectory}"
+            );
+        }
+    }
+
+    #[test]
+    fn urls_are_never_read_as_fetch_options() {
+        let temp = tempfile::tempdir().unwrap();
+        let upstream = temp.path().join("upstream");
+        fs::create_dir_all(&upstream).unwrap();
+        if git(&upstream, &["init", "--quiet"]).is_err() {
+            eprintln!("skipping: git unavailable");
+            return;
+        }
+        let marker = temp.path().join("pwned");
+        let cache = GitCache::with_cache(&Cache::with_root(temp.path().join("cache")));
+        // Without `--`, git would take the URL as `--upload-pack` and run it
+        // against the repository named by the ref.
+        let source = GitSource {
+            url: format!("--upload-pack=touch {}", marker.display()),
+            reference: Some(upstream.display().to_string()),
+            subdirectory: None,
+        };
+        assert!(cache.resolve(&source, false).is_err());
+        assert!(!marker.exists());
+    }
+}
@@ -0,0 +1,645 @@
+//! Project health score (`pybun doctor --score`).
+//!
+//! Doctor checks, outdated packages, audit findings, lock drift, cache health
+//! and test history are each scored from 0 to 100 and combined into one
+//! weighted score. Every deduction is also a [`Recommendation`] naming the
+//! command that fixes it, so the recommendations double as an ordered to-do
+//! list: most severe first, then by how many points fixing it recovers.
+//!
+//! Signals that cannot be measured (no lockfile, no project environment, no
+//! test history, index unreachable) are reported as skipped and left out of
+//! the weighting rather than scored as zero.
+
+use crate::script_lock::ScriptLockDrift;
+use serde::Serialize;
+
+/// Weights of the components; they sum to 100.
+const WEIGHTS: [(Component, u32); 6] = [
+    (Component::Audit, 30),
+    (Component::Lock, 20),
+    (Component::Doctor, 15),
+    (Component::Outdated, 15),
+    (Component::Tests, 15),
+    (Component::Cache, 5),
+];
+
+/// Packages named in a recommendation message before it says "and N more".
+const MAX_NAMED: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    Audit,
+    Lock,
+    Doctor,
+    Outdated,
+    Tests,
+    Cache,
+}
+
+impl Component {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Component::Audit => "audit",
+            Component::Lock => "lock",
+            Component::Doctor => "doctor",
+            Component::Outdated => "outdated",
+            Component::Tests => "tests",
+            Component::Cache => "cache",
+        }
+    }
+}
+
+/// Severity of a recommendation, most severe last so it sorts descending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// Failed and warning checks of `pybun doctor`.
+#[derive(Debug, Clone, Default)]
+pub struct DoctorSignal {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// A locked package with a newer release.
+#[derive(Debug, Clone)]
+pub struct OutdatedPackage {
+    pub package: String,
+    /// `major`, `minor`, `patch`, ... as reported by `pybun outdated`.
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutdatedSignal {
+    pub locked: usize,
+    pub outdated: Vec<OutdatedPackage>,
+}
+
+/// A known vulnerability in an installed package.
+#[derive(Debug, Clone)]
+pub struct AuditFinding {
+    pub package: String,
+    /// `low`, `medium`, `high` or `critical`.
+    pub severity: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditSignal {
+    pub findings: Vec<AuditFinding>,
+}
+
+/// The project lockfile against the declared dependencies; `drift` is
+/// `None` when there is no lockfile.
+#[derive(Debug, Clone, Default)]
+pub struct LockSignal {
+    pub drift: Option<ScriptLockDrift>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CacheSignal {
+    /// PyPI metadata entries written by incompatible pybun versions.
+    pub stale_entries: usize,
+    /// Hit rate over the recorded cache lookups, if any were recorded.
+    pub hit_rate: Option<f64>,
+}
+
+/// Test history over the report window.
+#[derive(Debug, Clone, Default)]
+pub struct TestSignal {
+    pub runs: usize,
+    /// Failing in the latest run.
+    pub failing: Vec<String>,
+    /// Passed and failed within the window, and passing now.
+    pub flaky: Vec<String>,
+}
+
+/// Everything the score is computed from. `Err` carries why a signal could
+/// not be measured.
+#[derive(Debug, Clone)]
+pub struct Signals {
+    pub doctor: Result<DoctorSignal, String>,
+    pub outdated: Result<OutdatedSignal, String>,
+    pub audit: Result<AuditSignal, String>,
+    pub lock: Result<LockSignal, String>,
+    pub cache: Result<CacheSignal, String>,
+    pub tests: Result<TestSignal, String>,
+}
+
+/// One component of the score.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentScore {
+    pub component: Component,
+    pub weight: u32,
+    /// `None` when skipped.
+    pub score: Option<u32>,
+    /// `ok`, `warning`, `critical` or `skipped`.
+    pub status: &'static str,
+    /// Why the component was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A prioritized fix.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Recommendation {
+    /// 1 for the first thing to do.
+    pub priority: usize,
+    pub component: Component,
+    pub severity: Severity,
+    pub message: String,
+    /// Command that fixes (or, for tests, locates) the problem.
+    pub command: String,
+    /// Points of the overall score this fix recovers.
+    pub impact: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    /// Weighted score over the measured components; `None` if none were.
+    pub score: Option<u32>,
+    pub grade: Option<&'static str>,
+    pub components: Vec<ComponentScore>,
+    pub recommendations: Vec<Recommendation>,
+}
+
+/// A deduction from a component's score and the fix that recovers it.
+struct Deduction {
+    points: u32,
+    severity: Severity,
+    message: String,
+    command: String,
+}
+
+impl Deduction {
+    fn new(
+        points: u32,
+        severity: Severity,
+        message: impl Into<String>,
+        command: impl Into<String>,
+    ) -> Self {
+        Self {
+            points,
+            severity,
+            message: message.into(),
+            command: command.into(),
+        }
+    }
+}
+
+/// `a, b, c and 2 more`.
+fn names(items: &[String]) -> String {
+    let shown = items
+        .iter()
+        .take(MAX_NAMED)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if items.len() > MAX_NAMED {
+        format!("{shown} and {} more", items.len() - MAX_NAMED)
+    } else {
+        shown
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("1 {word}")
+    } else {
+        format!("{count} {word}s")
+    }
+}
+
+fn doctor_deductions(signal: &DoctorSignal) -> Vec<Deduction> {
+    let mut out = Vec::new();
+    if !signal.errors.is_empty() {
+        out.push(Deduction::new(
+            40 * signal.errors.len() as u32,
+            Severity::High,
+            format!("doctor checks failing: {}", names(&signal.errors)),
+            "pybun doctor --fix",
+        ));
+    }
+    if !signal.warnings.is_empty() {
+        out.push(Deduction::new(
+            10 * signal.warnings.len() as u32,
+            Severity::Medium,
+            format!("doctor checks with warnings: {}", names(&signal.warnings)),
+            "pybun doctor --fix",
+        ));
+    }
+    out
+}
+
+fn audit_deductions(signal: &AuditSignal) -> Vec<Deduction> {
+    [
+        ("critical", Severity::Critical, 50),
+        ("high", Severity::High, 30),
+        ("medium", Severity::Medium, 10),
+        ("low", Severity::Low, 3),
+    ]
+    .into_iter()
+    .filter_map(|(level, severity, points)| {
+        let mut packages: Vec<String> = signal
+            .findings
+            .iter()
+            .filter(|f| f.severity == level)
+            .map(|f| f.package.clone())
+            .collect();
+        if packages.is_empty() {
+            return None;
+        }
+        let count = packages.len();
+        packages.sort();
+        packages.dedup();
+        Some(Deduction::new(
+            points * count as u32,
+            severity,
+            format!(
+                "{} {level}-severity vulnerabilit{} in {}",
+                count,
+                if count == 1 { "y" } else { "ies" },
+                names(&packages)
+            ),
+            format!("pybun upgrade {}", packages.join(" ")),
+        ))
+    })
+    .collect()
+}
+
+fn outdated_deductions(signal: &OutdatedSignal) -> Vec<Deduction> {
+    if signal.locked == 0 {
+        return Vec::new();
+    }
+    let major: Vec<String> = signal
+        .outdated
+        .iter()
+        .filter(|p| p.kind == "major")
+        .map(|p| p.package.clone())
+        .collect();
+    let other: Vec<String> = signal
+        .outdated
+        .iter()
+        .filter(|p| p.kind != "major")
+        .map(|p| p.package.clone())
+        .collect();
+    // A fully outdated lock costs 100 points with only major updates, 50
+    // with only minor/patch ones.
+    let share = |count: usize, full: u32| {
+        ((count as f64 / signal.locked as f64) * full as f64).round() as u32
+    };
+    let mut out = Vec::new();
+    if !major.is_empty() {
+        out.push(Deduction::new(
+            share(major.len(), 100),
+            Severity::Medium,
+            format!(
+                "{} behind a major release: {}",
+                plural(major.len(), "package"),
+                names(&major)
+            ),
+            format!("pybun upgrade {}", major.join(" ")),
+        ));
+    }
+    if !other.is_empty() {
+        out.push(Deduction::new(
+            share(other.len(), 50),
+            Severity::Low,
+            format!(
+                "{} with newer releases: {}",
+                plural(other.len(), "package"),
+                names(&other)
+            ),
+            "pybun upgrade",
+        ));
+    }
+    out
+}
+
+fn lock_deductions(signal: &LockSignal) -> Vec<Deduction> {
+    let Some(drift) = &signal.drift else {
+        return vec![Deduction::new(
+            100,
+            Severity::High,
+            "no lockfile: installs are not reproducible",
+            "pybun install",
+        )];
+    };
+    let mut out = Vec::new();
+    let unmet: Vec<String> = drift
+        .missing
+        .iter()
+        .cloned()
+        .chain(drift.unsatisfied.iter().map(|u| u.requirement.clone()))
+        .collect();
+    if !unmet.is_empty() {
+        out.push(Deduction::new(
+            25 * unmet.len() as u32,
+            Severity::High,
+            format!(
+                "lockfile does not satisfy {}: {}",
+                plural(unmet.len(), "declared requirement"),
+                names(&unmet)
+            ),
+            "pybun install",
+        ));
+    }
+    if !drift.stale.is_empty() {
+        out.push(Deduction::new(
+            5 * drift.stale.len() as u32,
+            Severity::Low,
+            format!(
+                "lockfile pins {} no longer required: {}",
+                plural(drift.stale.len(), "package"),
+                names(&drift.stale)
+            ),
+            "pybun install",
+        ));
+    }
+    out
+}
+
+fn cache_deductions(signal: &CacheSignal) -> Vec<Deduction> {
+    let mut out = Vec::new();
+    if signal.stale_entries > 0 {
+        out.push(Deduction::new(
+            (2 * signal.stale_entries as u32).min(40),
+            Severity::Low,
+            format!(
+                "{} stale {} in the PyPI metadata cache",
+                signal.stale_entries,
+                if signal.stale_entries == 1 {
+                    "entry"
+                } else {
+                    "entries"
+                }
+            ),
+            "pybun gc",
+        ));
+    }
+    if let Some(rate) = signal.hit_rate.filter(|rate| *rate < 0.5) {
+        out.push(Deduction::new(
+            20,
+            Severity::Low,
+            format!(
+                "cache hit rate is {:.0}%: most installs download again",
+                rate * 100.0
+            ),
+            "pybun gc --dry-run --sweep",
+        ));
+    }
+    out
+}
+
+fn test_deductions(signal: &TestSignal) -> Vec<Deduction> {
+    let mut out = Vec::new();
+    if !signal.failing.is_empty() {
+        out.push(Deduction::new(
+            25 * signal.failing.len() as u32,
+            Severity::High,
+            format!(
+                "{} failing: {}",
+                plural(signal.failing.len(), "test"),
+                names(&signal.failing)
+            ),
+            format!("pybun test {}", signal.failing.join(" ")),
+        ));
+    }
+    if !signal.flaky.is_empty() {
+        out.push(Deduction::new(
+            10 * signal.flaky.len() as u32,
+            Severity::Medium,
+            format!(
+                "{} flaky over the last {}: {}",
+                plural(signal.flaky.len(), "test"),
+                plural(signal.runs, "run"),
+                names(&signal.flaky)
+            ),
+            "pybun test --history",
+        ));
+    }
+    out
+}
+
+pub fn grade(score: u32) -> &'static str {
+    match score {
+        90.. => "A",
+        80..=89 => "B",
+        70..=79 => "C",
+        60..=69 => "D",
+        _ => "F",
+    }
+}
+
+/// Score `signals`.
+pub fn score(signals: &Signals) -> HealthReport {
+    fn measured<T>(
+        signal: &Result<T, String>,
+        deductions: fn(&T) -> Vec<Deduction>,
+    ) -> Result<Vec<Deduction>, String> {
+        signal.as_ref().map(deductions).map_err(Clone::clone)
+    }
+
+    let measured_deductions = |component| match component {
+        Component::Doctor => measured(&signals.doctor, doctor_deductions),
+        Component::Outdated => measured(&signals.outdated, outdated_deductions),
+        Component::Audit => measured(&signals.audit, audit_deductions),
+        Component::Lock => measured(&signals.lock, lock_deductions),
+        Component::Cache => measured(&signals.cache, cache_deductions),
+        Component::Tests => measured(&signals.tests, test_deductions),
+    };
+
+    let results: Vec<(Component, u32, Result<Vec<Deduction>, String>)> = WEIGHTS
+        .iter()
+        .map(|(component, weight)| (*component, *weight, measured_deductions(*component)))
+        .collect();
+    let total_weight: u32 = results
+        .iter()
+        .filter(|(_, _, result)| result.is_ok())
+        .map(|(_, weight, _)| weight)
+        .sum();
+
+    let mut components = Vec::new();
+    let mut recommendations = Vec::new();
+    let mut weighted = 0;
+    for (component, weight, result) in results {
+        match result {
+            Ok(deductions) => {
+                let lost: u32 = deductions.iter().map(|d| d.points).sum::<u32>().min(100);
+                let score = 100 - lost;
+                weighted += score * weight;
+                let worst = deductions.iter().map(|d| d.severity).max();
+                components.push(ComponentScore {
+                    component,
+                    weight,
+                    score: Some(score),
+                    status: match worst {
+                        None => "ok",
+                        Some(Severity::High | Severity::Critical) => "critical",
+                        Some(_) => "warning",
+                    },
+                    reason: None,
+                });
+                for deduction in deductions {
+                    recommendations.push(Recommendation {
+                        priority: 0,
+                        component,
+                        severity: deduction.severity,
+                        message: deduction.message,
+                        command: deduction.command,
+                        impact: (deduction.points.min(100) as f64 * weight as f64
+                            / total_weight as f64)
+                            .round() as u32,
+                    });
+                }
+            }
+            Err(reason) => components.push(ComponentScore {
+                component,
+                weight,
+                score: None,
+                status: "skipped",
+                reason: Some(reason),
+            }),
+        }
+    }
+
+    recommendations.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then(b.impact.cmp(&a.impact))
+            .then(a.component.cmp(&b.component))
+    });
+    for (index, recommendation) in recommendations.iter_mut().enumerate() {
+        recommendation.priority = index + 1;
+    }
+
+    let score = (total_weight > 0).then(|| (weighted as f64 / total_weight as f64).round() as u32);
+    HealthReport {
+        score,
+        grade: score.map(grade),
+        components,
+        recommendations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script_lock::UnsatisfiedRequirement;
+
+    fn healthy() -> Signals {
+        Signals {
+            doctor: Ok(DoctorSignal::default()),
+            outdated: Ok(OutdatedSignal {
+                locked: 4,
+                outdated: Vec::new(),
+            }),
+            audit: Ok(AuditSignal::default()),
+            lock: Ok(LockSignal {
+                drift: Some(ScriptLockDrift::default()),
+            }),
+            cache: Ok(CacheSignal::default()),
+            tests: Ok(TestSignal::default()),
+        }
+    }
+
+    #[test]
+    fn healthy_project_scores_full_marks() {
+        let report = score(&healthy());
+        assert_eq!(report.score, Some(100));
+        assert_eq!(report.grade, Some("A"));
+        assert!(report.recommendations.is_empty());
+        assert!(report.components.iter().all(|c| c.status == "ok"));
+    }
+
+    #[test]
+    fn deductions_become_ordered_recommendations() {
+        let mut signals = healthy();
+        signals.audit = Ok(AuditSignal {
+            findings: vec![AuditFinding {
+                package: "urllib3".into(),
+                severity: "high".into(),
+            }],
+        });
+        signals.outdated = Ok(OutdatedSignal {
+            locked: 4,
+            outdated: vec![
+                OutdatedPackage {
+                    package: "django".into(),
+                    kind: "major".into(),
+                },
+                OutdatedPackage {
+                    package: "rich".into(),
+                    kind: "patch".into(),
+                },
+            ],
+        });
+        signals.lock = Ok(LockSignal {
+            drift: Some(ScriptLockDrift {
+                unsatisfied: vec![UnsatisfiedRequirement {
+                    requirement: "requests>=2.32".into(),
+                    locked_version: "2.31.0".into(),
+                }],
+                ..Default::default()
+            }),
+        });
+        signals.tests = Ok(TestSignal {
+            runs: 10,
+            failing: Vec::new(),
+            flaky: vec!["tests/test_api.py::test_retry".into()],
+        });
+
+        let report = score(&signals);
+        // audit 70*30 + lock 75*20 + doctor 100*15 + outdated 62*15
+        // + tests 90*15 + cache 100*5, over 100.
+        assert_eq!(report.score, Some(79));
+        assert_eq!(report.grade, Some("C"));
+        let order: Vec<(usize, &str, &str)> = report
+            .recommendations
+            .iter()
+            .map(|r| (r.priority, r.component.as_str(), r.command.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (1, "audit", "pybun upgrade urllib3"),
+                (2, "lock", "pybun install"),
+                (3, "outdated", "pybun upgrade django"),
+                (4, "tests", "pybun test --history"),
+                (5, "outdated", "pybun upgrade"),
+            ]
+        );
+        assert_eq!(report.recommendations[0].impact, 9);
+        assert_eq!(report.recommendations[1].impact, 5);
+    }
+
+    #[test]
+    fn skipped_components_are_left_out_of_the_weighting() {
+        let mut signals = healthy();
+        signals.audit = Err("no project environment".into());
+        signals.lock = Ok(LockSignal { drift: None });
+        let report = score(&signals);
+        // The missing lockfile costs 20 of the 70 measured weight.
+        assert_eq!(report.score, Some(71));
+        let audit = &report.components[0];
+        assert_eq!(audit.status, "skipped");
+        assert_eq!(audit.score, None);
+        assert_eq!(audit.reason.as_deref(), Some("no project environment"));
+        assert_eq!(report.recommendations[0].impact, 29);
+
+        let none = Signals {
+            doctor: Err("x".into()),
+            outdated: Err("x".into()),
+            audit: Err("x".into()),
+            lock: Err("x".into()),
+            cache: Err("x".into()),
+            tests: Err("x".into()),
+        };
+        assert_eq!(score(&none).score, None);
+    }
+}
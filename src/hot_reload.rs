@@ -8,10 +8,13 @@
 //! - Configurable watch patterns (include/exclude)
 //! - Debouncing to prevent rapid successive reloads
 //! - Dev profile toggle to enable/disable in production
+//! - Syntax pre-check of changed Python files; the run is skipped while any
+//!   of them fails to parse (see [`crate::syntax_check`])
 //! - Native file watching with `notify` crate (optional feature: `native-watch`)
 
+use crate::syntax_check::{self, PythonSyntaxError};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::{Duration, Instant, SystemTime};
@@ -608,6 +611,51 @@ pub struct PollingWatchOutcome {
     pub iterations: u64,
     /// Number of times the command was run in response to a detected change.
     pub runs: u64,
+    /// Number of changes that did not run the command because a watched
+    /// file had a syntax error.
+    pub skipped: u64,
+    /// The syntax error still blocking runs when the loop stopped.
+    pub syntax_error: Option<PythonSyntaxError>,
+}
+
+/// Python files that failed the syntax pre-check, re-checked as they change.
+#[derive(Debug, Default)]
+pub struct SyntaxGate {
+    broken: BTreeMap<PathBuf, PythonSyntaxError>,
+}
+
+impl SyntaxGate {
+    /// Re-check the changed `.py` files. Returns the errors of every file
+    /// that still fails to parse, including ones changed earlier; the run
+    /// should be skipped unless this is empty.
+    pub fn update(&mut self, events: &[FileChangeEvent]) -> Vec<&PythonSyntaxError> {
+        for event in events {
+            if event.path.extension().is_none_or(|ext| ext != "py") {
+                continue;
+            }
+            let error = match event.change_type {
+                ChangeType::Deleted => None,
+                _ => syntax_check::check_file(&event.path),
+            };
+            match error {
+                Some(error) => self.broken.insert(event.path.clone(), error),
+                None => self.broken.remove(&event.path),
+            };
+        }
+        self.broken.values().collect()
+    }
+}
+
+/// Print syntax errors in place of a run. Returns `true` if there were any.
+fn report_syntax_errors(errors: &[&PythonSyntaxError]) -> bool {
+    if errors.is_empty() {
+        return false;
+    }
+    for error in errors {
+        eprintln!("{}", error.render());
+    }
+    eprintln!("info: skipping run until the file parses");
+    true
 }
 
 /// Run a poll-based watch loop, re-running `command` whenever a watched
@@ -638,6 +686,7 @@ pub fn run_polling_watch_loop(
     let poll_interval = Duration::from_millis(config.debounce_ms.max(MIN_POLL_INTERVAL_MS));
     let mut snapshot = scan_watch_paths(config);
     let mut outcome = PollingWatchOutcome::default();
+    let mut gate = SyntaxGate::default();
     eprintln!("info: watching for changes...");
 
     loop {
@@ -658,30 +707,36 @@ pub fn run_polling_watch_loop(
                 print!("\x1B[2J\x1B[1;1H");
             }
 
-            eprintln!("info: running: {}", command);
-            let status = if cfg!(windows) {
-                Command::new("cmd").args(["/C", command]).status()
+            let errors = gate.update(&events);
+            outcome.syntax_error = errors.first().map(|&error| error.clone());
+            if report_syntax_errors(&errors) {
+                outcome.skipped += 1;
             } else {
-                Command::new("sh").args(["-c", command]).status()
-            };
+                eprintln!("info: running: {}", command);
+                let status = if cfg!(windows) {
+                    Command::new("cmd").args(["/C", command]).status()
+                } else {
+                    Command::new("sh").args(["-c", command]).status()
+                };
 
-            match status {
-                Ok(s) => {
-                    if s.success() {
-                        eprintln!("info: command completed successfully");
-                    } else {
-                        eprintln!(
-                            "warning: command exited with code {}",
-                            s.code().unwrap_or(-1)
-                        );
+                match status {
+                    Ok(s) => {
+                        if s.success() {
+                            eprintln!("info: command completed successfully");
+                        } else {
+                            eprintln!(
+                                "warning: command exited with code {}",
+                                s.code().unwrap_or(-1)
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("error: failed to run command: {}", e);
                     }
                 }
-                Err(e) => {
-                    eprintln!("error: failed to run command: {}", e);
-                }
-            }
 
-            outcome.runs += 1;
+                outcome.runs += 1;
+            }
             eprintln!("info: watching for changes...");
         }
 
@@ -750,6 +805,7 @@ pub fn run_native_watch_loop(
     // Debounce tracking
     let mut last_run = Instant::now();
     let debounce = Duration::from_millis(config.debounce_ms);
+    let mut gate = SyntaxGate::default();
 
     // Process events in a loop
     loop {
@@ -778,6 +834,11 @@ pub fn run_native_watch_loop(
                     print!("\x1B[2J\x1B[1;1H");
                 }
 
+                if report_syntax_errors(&gate.update(std::slice::from_ref(&event))) {
+                    eprintln!("info: watching for changes...");
+                    continue;
+                }
+
                 // Run the command
                 eprintln!("info: running: {}", command);
                 let status = if cfg!(windows) {
@@ -1237,6 +1298,72 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_run_polling_watch_loop_skips_runs_on_syntax_error() {
+            let temp = TempDir::new().unwrap();
+            let py_file = temp.path().join("main.py");
+            File::create(&py_file).unwrap();
+            let marker = temp.path().join("ran");
+
+            let mut config = HotReloadConfig::dev();
+            config.watch_paths = vec![temp.path().to_path_buf()];
+            config.debounce_ms = 50;
+
+            let watched_file = py_file.clone();
+            let writer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(75));
+                let mut file = File::create(&watched_file).unwrap();
+                writeln!(file, "print('changed'").unwrap();
+                file.sync_all().unwrap();
+            });
+
+            let command = format!("touch {}", marker.display());
+            let outcome = run_polling_watch_loop(&config, &command, Some(6)).unwrap();
+
+            writer.join().unwrap();
+
+            assert_eq!(outcome.runs, 0);
+            assert!(outcome.skipped >= 1);
+            let error = outcome.syntax_error.expect("syntax error is reported");
+            assert_eq!(error.file, py_file);
+            assert_eq!((error.line, error.column), (1, 6));
+            assert!(!marker.exists());
+        }
+
+        #[test]
+        fn test_syntax_gate_blocks_until_every_file_parses() {
+            let temp = TempDir::new().unwrap();
+            let broken = temp.path().join("a.py");
+            let other = temp.path().join("b.py");
+            std::fs::write(&broken, "def f(:\n").unwrap();
+            std::fs::write(&other, "x = 1\n").unwrap();
+            let event =
+                |path: &Path, change_type| FileChangeEvent::new(path.to_path_buf(), change_type, 0);
+
+            let mut gate = SyntaxGate::default();
+            assert_eq!(
+                gate.update(&[event(&broken, ChangeType::Modified)]).len(),
+                1
+            );
+            // Editing another file doesn't unblock the run.
+            assert_eq!(gate.update(&[event(&other, ChangeType::Modified)]).len(), 1);
+            std::fs::write(&broken, "def f():\n    pass\n").unwrap();
+            assert!(
+                gate.update(&[event(&broken, ChangeType::Modified)])
+                    .is_empty()
+            );
+
+            std::fs::write(&broken, "x = (\n").unwrap();
+            assert_eq!(
+                gate.update(&[event(&broken, ChangeType::Modified)]).len(),
+                1
+            );
+            assert!(
+                gate.update(&[event(&broken, ChangeType::Deleted)])
+                    .is_empty()
+            );
+        }
+
         #[test]
         fn test_run_polling_watch_loop_respects_max_iterations_without_changes() {
             let temp = TempDir::new().unwrap();
@@ -0,0 +1,467 @@
+//! Proxy and TLS trust settings shared by PyBun's HTTP clients.
+//!
+//! Corporate networks often route HTTPS through an intercepting proxy whose
+//! certificates are signed by an in-house CA. PyBun always trusts its
+//! built-in (Mozilla) roots and adds:
+//!
+//! - the first CA bundle found in `PYBUN_CA_BUNDLE`, `[tool.pybun.http]
+//!   ca-bundle`, `SSL_CERT_FILE`, `REQUESTS_CA_BUNDLE`, or `PIP_CERT`;
+//! - the operating system's CA bundle (the first of the usual
+//!   distribution locations that exists), unless `system-certs = false` or
+//!   `PYBUN_SYSTEM_CERTS=0`. CAs installed with `update-ca-certificates`
+//!   or `update-ca-trust` therefore work without further configuration.
+//!
+//! Proxies from `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`/`NO_PROXY` are honoured
+//! as usual. An explicit proxy replaces them:
+//!
+//! ```toml
+//! [tool.pybun.http]
+//! ca-bundle = "certs/corp-root.pem"   # relative to pyproject.toml
+//! proxy = "http://proxy.corp.example:3128"
+//! no-proxy = ["*.corp.example", "10.0.0.0/8"]
+//! ```
+//!
+//! `PYBUN_PROXY` and `PYBUN_NO_PROXY` (comma-separated) override the file.
+//! Loopback addresses never go through an explicit proxy. `pybun doctor`
+//! reports the effective settings and probes the package index to detect
+//! TLS interception.
+
+use crate::project::Project;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tempfile::NamedTempFile;
+
+/// Environment variables checked (in order) for an extra CA bundle, after
+/// `PYBUN_CA_BUNDLE` and the project setting.
+const CA_BUNDLE_ENV_FALLBACKS: [&str; 3] = ["SSL_CERT_FILE", "REQUESTS_CA_BUNDLE", "PIP_CERT"];
+
+/// Proxy variables reqwest reads when no explicit proxy is configured.
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// Hosts that always bypass an explicit proxy.
+const LOOPBACK_NO_PROXY: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+/// Well-known locations of the system CA bundle.
+pub const SYSTEM_CA_BUNDLES: [&str; 8] = [
+    "/etc/ssl/certs/ca-certificates.crt", // Debian, Ubuntu, Gentoo, Arch
+    "/etc/pki/ca-trust/extracted/pem/tls-ca-bundle.pem", // Fedora, RHEL 7+
+    "/etc/pki/tls/certs/ca-bundle.crt",   // older RHEL/CentOS
+    "/etc/ssl/ca-bundle.pem",             // openSUSE
+    "/etc/ssl/cert.pem",                  // Alpine, macOS, BSDs
+    "/usr/local/share/certs/ca-root-nss.crt", // FreeBSD
+    "/opt/homebrew/etc/ca-certificates/cert.pem", // Homebrew (Apple silicon)
+    "/usr/local/etc/ca-certificates/cert.pem", // Homebrew (Intel)
+];
+
+/// `[tool.pybun.http]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpConfig {
+    /// Extra PEM bundle to trust, relative to `pyproject.toml`.
+    #[serde(default)]
+    pub ca_bundle: Option<PathBuf>,
+    /// Whether to trust the OS CA bundle (default: true).
+    #[serde(default)]
+    pub system_certs: Option<bool>,
+    /// Proxy URL for all requests.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Hosts, domains (`*.corp.example`), or CIDR ranges that bypass `proxy`.
+    #[serde(default)]
+    pub no_proxy: Option<Vec<String>>,
+}
+
+/// A PEM bundle PyBun trusts in addition to its built-in roots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TrustSource {
+    pub path: PathBuf,
+    /// Where the bundle came from (`PYBUN_CA_BUNDLE`, `pyproject.toml`, `system`, ...).
+    pub origin: String,
+    pub certificates: usize,
+}
+
+/// The proxy PyBun will use.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProxySettings {
+    pub url: String,
+    pub origin: String,
+    /// Explicit proxies only; environment proxies use `NO_PROXY` directly.
+    pub no_proxy: Vec<String>,
+    /// Whether PyBun configures the proxy itself (vs. reqwest reading the
+    /// environment).
+    pub explicit: bool,
+}
+
+/// Effective proxy and trust settings.
+#[derive(Debug, Clone, Default)]
+pub struct HttpSettings {
+    pub trust: Vec<TrustSource>,
+    pub system_certs: bool,
+    pub proxy: Option<ProxySettings>,
+    /// Configuration that could not be applied (unreadable bundle, bad URL).
+    pub problems: Vec<String>,
+    certificates: Vec<reqwest::Certificate>,
+}
+
+impl HttpSettings {
+    /// Resolve settings from `config` (declared in `config_file`), the
+    /// environment (`env`), and the first existing `system_bundles` entry.
+    pub fn resolve(
+        config: Option<&HttpConfig>,
+        config_file: Option<&Path>,
+        env: &dyn Fn(&str) -> Option<String>,
+        system_bundles: &[&str],
+    ) -> Self {
+        let default_config = HttpConfig::default();
+        let config = config.unwrap_or(&default_config);
+        let config_origin = config_file
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "pyproject.toml".to_string());
+        let mut settings = HttpSettings {
+            system_certs: config.system_certs.unwrap_or(true),
+            ..Default::default()
+        };
+        if let Some(value) = env("PYBUN_SYSTEM_CERTS").filter(|v| !v.is_empty()) {
+            settings.system_certs = !matches!(value.as_str(), "0" | "false" | "no" | "off");
+        }
+
+        // Extra CA bundle: first configured source wins.
+        let relative_to = config_file.and_then(Path::parent);
+        let extra = env("PYBUN_CA_BUNDLE")
+            .filter(|v| !v.is_empty())
+            .map(|v| (PathBuf::from(v), "PYBUN_CA_BUNDLE".to_string()))
+            .or_else(|| {
+                config.ca_bundle.as_ref().map(|path| {
+                    let path = match relative_to {
+                        Some(dir) if path.is_relative() => dir.join(path),
+                        _ => path.clone(),
+                    };
+                    (path, config_origin.clone())
+                })
+            })
+            .or_else(|| {
+                CA_BUNDLE_ENV_FALLBACKS.iter().find_map(|name| {
+                    env(name)
+                        .filter(|v| !v.is_empty())
+                        .map(|v| (PathBuf::from(v), name.to_string()))
+                })
+            });
+        if let Some((path, origin)) = extra {
+            settings.add_bundle(path, origin);
+        }
+        if settings.system_certs
+            && let Some(path) = system_bundles.iter().map(Path::new).find(|p| p.is_file())
+            && !settings.trust.iter().any(|t| t.path == path)
+        {
+            settings.add_bundle(path.to_path_buf(), "system".to_string());
+        }
+
+        // Proxy: an explicit setting replaces the environment proxies.
+        let explicit = env("PYBUN_PROXY")
+            .filter(|v| !v.is_empty())
+            .map(|url| (url, "PYBUN_PROXY".to_string()))
+            .or_else(|| config.proxy.clone().map(|url| (url, config_origin.clone())));
+        if let Some((url, origin)) = explicit {
+            let mut no_proxy: Vec<String> = env("PYBUN_NO_PROXY")
+                .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
+                .or_else(|| config.no_proxy.clone())
+                .or_else(|| {
+                    env("NO_PROXY")
+                        .or_else(|| env("no_proxy"))
+                        .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
+                })
+                .unwrap_or_default();
+            no_proxy.retain(|entry| !entry.is_empty());
+            for host in LOOPBACK_NO_PROXY {
+                if !no_proxy.iter().any(|entry| entry == host) {
+                    no_proxy.push(host.to_string());
+                }
+            }
+            match reqwest::Proxy::all(&url) {
+                Ok(_) => {
+                    settings.proxy = Some(ProxySettings {
+                        url,
+                        origin,
+                        no_proxy,
+                        explicit: true,
+                    })
+                }
+                Err(e) => settings
+                    .problems
+                    .push(format!("invalid proxy URL `{url}` from {origin}: {e}")),
+            }
+        } else if let Some((name, url)) = PROXY_ENV_VARS
+            .iter()
+            .find_map(|name| env(name).filter(|v| !v.is_empty()).map(|v| (*name, v)))
+        {
+            settings.proxy = Some(ProxySettings {
+                url,
+                origin: name.to_string(),
+                no_proxy: Vec::new(),
+                explicit: false,
+            });
+        }
+        settings
+    }
+
+    fn add_bundle(&mut self, path: PathBuf, origin: String) {
+        let loaded = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|pem| reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| e.to_string()));
+        match loaded {
+            Ok(certs) if !certs.is_empty() => {
+                self.trust.push(TrustSource {
+                    path,
+                    origin,
+                    certificates: certs.len(),
+                });
+                self.certificates.extend(certs);
+            }
+            Ok(_) => self.problems.push(format!(
+                "CA bundle {} (from {origin}) contains no PEM certificates",
+                path.display()
+            )),
+            Err(e) => self.problems.push(format!(
+                "cannot load CA bundle {} (from {origin}): {e}",
+                path.display()
+            )),
+        }
+    }
+
+    /// Whether any CA beyond the built-in roots is trusted.
+    pub fn has_extra_trust(&self) -> bool {
+        !self.certificates.is_empty()
+    }
+
+    fn explicit_proxy(&self) -> Option<reqwest::Proxy> {
+        let proxy = self.proxy.as_ref().filter(|p| p.explicit)?;
+        let no_proxy = proxy
+            .no_proxy
+            .iter()
+            .map(|entry| entry.strip_prefix('*').unwrap_or(entry))
+            .collect::<Vec<_>>()
+            .join(",");
+        reqwest::Proxy::all(&proxy.url)
+            .ok()
+            .map(|p| p.no_proxy(reqwest::NoProxy::from_string(&no_proxy)))
+    }
+
+    /// Apply the settings to an async client builder.
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        for cert in &self.certificates {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if let Some(proxy) = self.explicit_proxy() {
+            builder = builder.proxy(proxy);
+        }
+        builder
+    }
+
+    /// Apply the settings to a blocking client builder.
+    pub fn apply_blocking(
+        &self,
+        mut builder: reqwest::blocking::ClientBuilder,
+    ) -> reqwest::blocking::ClientBuilder {
+        for cert in &self.certificates {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if let Some(proxy) = self.explicit_proxy() {
+            builder = builder.proxy(proxy);
+        }
+        builder
+    }
+
+    /// Settings without the extra CAs, used to tell whether a certificate
+    /// is only trusted because of them.
+    pub fn without_extra_trust(&self) -> Self {
+        HttpSettings {
+            trust: Vec::new(),
+            certificates: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Arguments that give `curl` the same proxy and trust settings. The
+    /// trusted bundles are concatenated into a temporary file, since
+    /// `--cacert` replaces curl's default store; keep it alive for the call.
+    pub fn curl_args(&self) -> std::io::Result<(Vec<OsString>, Option<NamedTempFile>)> {
+        let mut args: Vec<OsString> = Vec::new();
+        if let Some(proxy) = self.proxy.as_ref().filter(|p| p.explicit) {
+            args.extend(["--proxy".into(), proxy.url.clone().into()]);
+            args.extend(["--noproxy".into(), proxy.no_proxy.join(",").into()]);
+        }
+        if !self.has_extra_trust() {
+            return Ok((args, None));
+        }
+        let mut bundle = NamedTempFile::new()?;
+        for source in &self.trust {
+            bundle.write_all(&std::fs::read(&source.path)?)?;
+            bundle.write_all(b"\n")?;
+        }
+        bundle.flush()?;
+        args.extend(["--cacert".into(), bundle.path().into()]);
+        Ok((args, Some(bundle)))
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "trust": self.trust,
+            "system_certs": self.system_certs,
+            "proxy": self.proxy,
+            "problems": self.problems,
+        })
+    }
+}
+
+/// Settings for the current directory, resolved once per process.
+pub fn current() -> &'static HttpSettings {
+    static SETTINGS: OnceLock<HttpSettings> = OnceLock::new();
+    SETTINGS.get_or_init(|| {
+        let project = std::env::current_dir()
+            .ok()
+            .and_then(|dir| Project::discover(dir).ok());
+        let config = project.as_ref().and_then(|p| p.pybun_config().http);
+        HttpSettings::resolve(
+            config.as_ref(),
+            project.as_ref().map(Project::path),
+            &|name| std::env::var(name).ok(),
+            &SYSTEM_CA_BUNDLES,
+        )
+    })
+}
+
+/// An async client builder with the current proxy and trust settings.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    current().apply(reqwest::Client::builder())
+}
+
+/// A blocking client builder with the current proxy and trust settings.
+pub fn blocking_client_builder() -> reqwest::blocking::ClientBuilder {
+    current().apply_blocking(reqwest::blocking::Client::builder())
+}
+
+/// `error` and its causes joined with `: ` (reqwest hides the TLS cause).
+pub fn error_chain(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut messages = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(err) = source {
+        let message = err.to_string();
+        if !messages.iter().any(|m| m.contains(&message)) {
+            messages.push(message);
+        }
+        source = err.source();
+    }
+    messages.join(": ")
+}
+
+/// Whether `error` (or its cause chain) is a certificate validation failure.
+pub fn is_certificate_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let chain = error_chain(error).to_ascii_lowercase();
+    chain.contains("certificate") || chain.contains("unknownissuer")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // Self-signed test CA (CN=PyBun Test Root).
+    const TEST_CA_PEM: &str = include_str!("../tests/fixtures/tls/test-ca.pem");
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn ca_bundle_precedence_and_system_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let corp = dir.path().join("corp.pem");
+        let system = dir.path().join("system.pem");
+        std::fs::write(&corp, TEST_CA_PEM).unwrap();
+        std::fs::write(&system, TEST_CA_PEM).unwrap();
+        let config = HttpConfig {
+            ca_bundle: Some(PathBuf::from("corp.pem")),
+            ..Default::default()
+        };
+        let pyproject = dir.path().join("pyproject.toml");
+        let system_str = system.to_str().unwrap();
+
+        let settings = HttpSettings::resolve(
+            Some(&config),
+            Some(&pyproject),
+            &env(&[("SSL_CERT_FILE", "/ignored.pem")]),
+            &["/nonexistent.pem", system_str],
+        );
+        assert!(settings.problems.is_empty(), "{:?}", settings.problems);
+        assert_eq!(settings.trust.len(), 2);
+        assert_eq!(settings.trust[0].path, corp);
+        assert_eq!(settings.trust[0].origin, pyproject.display().to_string());
+        assert_eq!(settings.trust[0].certificates, 1);
+        assert_eq!(settings.trust[1].origin, "system");
+
+        let settings = HttpSettings::resolve(
+            Some(&config),
+            Some(&pyproject),
+            &env(&[
+                ("PYBUN_CA_BUNDLE", "/missing.pem"),
+                ("PYBUN_SYSTEM_CERTS", "0"),
+            ]),
+            &[system_str],
+        );
+        assert!(settings.trust.is_empty());
+        assert!(settings.problems[0].contains("/missing.pem"));
+        assert!(settings.problems[0].contains("PYBUN_CA_BUNDLE"));
+    }
+
+    #[test]
+    fn explicit_proxy_replaces_environment_and_bypasses_loopback() {
+        let config = HttpConfig {
+            proxy: Some("http://proxy.corp.example:3128".into()),
+            no_proxy: Some(vec!["*.corp.example".into()]),
+            ..Default::default()
+        };
+        let settings = HttpSettings::resolve(
+            Some(&config),
+            None,
+            &env(&[("HTTPS_PROXY", "http://other:8080")]),
+            &[],
+        );
+        let proxy = settings.proxy.unwrap();
+        assert!(proxy.explicit);
+        assert_eq!(proxy.url, "http://proxy.corp.example:3128");
+        assert_eq!(
+            proxy.no_proxy,
+            vec!["*.corp.example", "localhost", "127.0.0.1", "::1"]
+        );
+
+        let settings = HttpSettings::resolve(
+            None,
+            None,
+            &env(&[("HTTPS_PROXY", "http://other:8080")]),
+            &[],
+        );
+        let proxy = settings.proxy.unwrap();
+        assert!(!proxy.explicit);
+        assert_eq!(proxy.origin, "HTTPS_PROXY");
+
+        let settings =
+            HttpSettings::resolve(None, None, &env(&[("PYBUN_PROXY", "not a url")]), &[]);
+        assert!(settings.proxy.is_none());
+        assert!(settings.problems[0].contains("invalid proxy URL"));
+    }
+}
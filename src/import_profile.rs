@@ -0,0 +1,305 @@
+//! `pybun profile imports`: what a script's imports cost.
+//!
+//! The script runs under `python -X importtime`, which writes one stderr
+//! line per module once it has finished importing:
+//!
+//! ```text
+//! import time: self [us] | cumulative | imported package
+//! import time:       458 |       2639 | json
+//! ```
+//!
+//! Each nesting level indents the module name by two spaces, so a module's
+//! children are the deeper lines printed just before it. Modules imported
+//! until `site` finishes belong to interpreter start-up and are kept apart
+//! from the script's own imports. Lazy-import candidates are matched with
+//! the imports parsed from the script's source
+//! ([`crate::lazy_import::import_sites`]).
+
+use crate::lazy_import::{ImportSite, LazyImportConfig};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// One imported module and the imports it triggered.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportNode {
+    pub module: String,
+    /// Time spent in the module's own body.
+    pub self_ms: f64,
+    /// `self_ms` plus the time of every import it triggered.
+    pub cumulative_ms: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ImportNode>,
+}
+
+/// The parsed `-X importtime` output of one run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportProfile {
+    /// Imports made by interpreter start-up (up to and including `site`).
+    pub startup: Vec<ImportNode>,
+    /// Imports made by the script, in import order.
+    pub imports: Vec<ImportNode>,
+}
+
+/// A module ranked by the time spent in its own body.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Offender {
+    pub module: String,
+    pub self_ms: f64,
+    pub cumulative_ms: f64,
+    /// The module whose import pulled this one in; `None` for the script's
+    /// own imports.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imported_by: Option<String>,
+}
+
+/// A top-level package the script imports, with what importing it costs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LazyCandidate {
+    pub module: String,
+    pub cumulative_ms: f64,
+    /// Line of the script that imports the package; `None` when only a
+    /// function or another module imports it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// Packages worth deferring with `pybun lazy-import`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LazySuggestion {
+    /// Candidates for `--allow`, most expensive first.
+    pub allow: Vec<LazyCandidate>,
+    /// Expensive packages the lazy-import denylist keeps eager; drop them
+    /// from the denylist only if the script does not rely on their import
+    /// side effects.
+    pub denied: Vec<LazyCandidate>,
+    /// Expensive packages the script imports inside a `try` that handles
+    /// `ImportError`; deferring them would let a failure while loading
+    /// escape the `try` (see [`ImportSite::guarded`]).
+    pub guarded: Vec<LazyCandidate>,
+}
+
+impl LazySuggestion {
+    /// The `pybun lazy-import` command that generates a hook deferring the
+    /// candidates, or `None` when there are none.
+    pub fn command(&self, output: &str) -> Option<String> {
+        if self.allow.is_empty() {
+            return None;
+        }
+        let allow: Vec<String> = self
+            .allow
+            .iter()
+            .map(|c| format!("--allow {}", c.module))
+            .collect();
+        Some(format!(
+            "pybun lazy-import --generate {} -o {output}",
+            allow.join(" ")
+        ))
+    }
+}
+
+/// Split `stderr` of a `-X importtime` run into the import profile and the
+/// lines the script itself wrote.
+pub fn parse(stderr: &str) -> (ImportProfile, Vec<String>) {
+    let mut other = Vec::new();
+    // Finished modules waiting for their parent, by nesting depth.
+    let mut pending: Vec<Vec<ImportNode>> = Vec::new();
+    for line in stderr.lines() {
+        let Some((depth, mut node)) = parse_line(line) else {
+            if !line.starts_with("import time:") {
+                other.push(line.to_string());
+            }
+            continue;
+        };
+        if pending.len() < depth + 2 {
+            pending.resize_with(depth + 2, Vec::new);
+        }
+        node.children = std::mem::take(&mut pending[depth + 1]);
+        pending[depth].push(node);
+    }
+    // Deeper leftovers come from an import the script died in.
+    let roots: Vec<ImportNode> = pending.into_iter().flatten().collect();
+
+    let split = roots
+        .iter()
+        .position(|node| node.module == "site")
+        .map_or(0, |at| at + 1);
+    let mut startup = roots;
+    let imports = startup.split_off(split);
+    (ImportProfile { startup, imports }, other)
+}
+
+/// `(depth, node)` for an `import time:` line; `None` for the header and
+/// anything else.
+fn parse_line(line: &str) -> Option<(usize, ImportNode)> {
+    let mut fields = line.strip_prefix("import time:")?.splitn(3, '|');
+    let self_us: u64 = fields.next()?.trim().parse().ok()?;
+    let cumulative_us: u64 = fields.next()?.trim().parse().ok()?;
+    let name = fields.next()?.strip_prefix(' ')?;
+    let module = name.trim_start();
+    if module.is_empty() {
+        return None;
+    }
+    Some((
+        (name.len() - module.len()) / 2,
+        ImportNode {
+            module: module.trim_end().to_string(),
+            self_ms: self_us as f64 / 1000.0,
+            cumulative_ms: cumulative_us as f64 / 1000.0,
+            children: Vec::new(),
+        },
+    ))
+}
+
+impl ImportProfile {
+    /// Time the script's imports took.
+    pub fn imports_ms(&self) -> f64 {
+        self.imports.iter().map(|node| node.cumulative_ms).sum()
+    }
+
+    /// Time interpreter start-up spent importing.
+    pub fn startup_ms(&self) -> f64 {
+        self.startup.iter().map(|node| node.cumulative_ms).sum()
+    }
+
+    /// Number of modules the script imported.
+    pub fn module_count(&self) -> usize {
+        fn count(nodes: &[ImportNode]) -> usize {
+            nodes.iter().map(|node| 1 + count(&node.children)).sum()
+        }
+        count(&self.imports)
+    }
+
+    /// The `limit` script imports with the most time in their own body.
+    pub fn top_offenders(&self, limit: usize) -> Vec<Offender> {
+        fn collect(nodes: &[ImportNode], parent: Option<&str>, out: &mut Vec<Offender>) {
+            for node in nodes {
+                out.push(Offender {
+                    module: node.module.clone(),
+                    self_ms: node.self_ms,
+                    cumulative_ms: node.cumulative_ms,
+                    imported_by: parent.map(str::to_string),
+                });
+                collect(&node.children, Some(&node.module), out);
+            }
+        }
+        let mut offenders = Vec::new();
+        collect(&self.imports, None, &mut offenders);
+        offenders.sort_by(|a, b| b.self_ms.total_cmp(&a.self_ms));
+        offenders.truncate(limit);
+        offenders
+    }
+
+    /// The top-level packages the script imports directly that cost at
+    /// least `min_ms`, split by whether `config` would let them be lazy and
+    /// whether the script's `sites` import them under an `ImportError`
+    /// handler.
+    pub fn lazy_candidates(
+        &self,
+        config: &LazyImportConfig,
+        min_ms: f64,
+        sites: &BTreeMap<String, ImportSite>,
+    ) -> LazySuggestion {
+        let mut packages: BTreeMap<&str, f64> = BTreeMap::new();
+        for node in &self.imports {
+            let package = node.module.split('.').next().unwrap_or(&node.module);
+            *packages.entry(package).or_default() += node.cumulative_ms;
+        }
+        let mut ranked: Vec<LazyCandidate> = packages
+            .into_iter()
+            .filter(|(_, ms)| *ms >= min_ms)
+            .map(|(module, cumulative_ms)| LazyCandidate {
+                module: module.to_string(),
+                cumulative_ms,
+                line: sites.get(module).map(|site| site.line),
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.cumulative_ms.total_cmp(&a.cumulative_ms));
+        let mut suggestion = LazySuggestion::default();
+        for candidate in ranked {
+            if config.is_denied(&candidate.module) {
+                suggestion.denied.push(candidate);
+            } else if sites
+                .get(&candidate.module)
+                .is_some_and(|site| site.guarded)
+            {
+                suggestion.guarded.push(candidate);
+            } else {
+                suggestion.allow.push(candidate);
+            }
+        }
+        suggestion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+import time: self [us] | cumulative | imported package
+import time:       324 |        324 |   _io
+import time:       549 |        873 | _frozen_importlib_external
+import time:       120 |        120 |   os
+import time:      1723 |       1843 | site
+hello from the script
+import time:        95 |         95 |   email.charset
+import time:       400 |        495 | email
+import time:      2630 |       4574 |     socket
+import time:       352 |       4926 |   email.utils
+import time:       355 |       5281 | email.parser
+import time:      4000 |       4000 | logging
+import time:      1000 |       1000 | tiny
+";
+
+    #[test]
+    fn builds_the_import_tree_and_separates_startup() {
+        let (profile, other) = parse(OUTPUT);
+        assert_eq!(other, ["hello from the script"]);
+        let startup: Vec<&str> = profile.startup.iter().map(|n| n.module.as_str()).collect();
+        assert_eq!(startup, ["_frozen_importlib_external", "site"]);
+        assert_eq!(profile.startup[1].children[0].module, "os");
+
+        let imports: Vec<&str> = profile.imports.iter().map(|n| n.module.as_str()).collect();
+        assert_eq!(imports, ["email", "email.parser", "logging", "tiny"]);
+        let parser = &profile.imports[1];
+        assert_eq!(parser.children[0].module, "email.utils");
+        assert_eq!(parser.children[0].children[0].module, "socket");
+        assert_eq!(profile.module_count(), 7);
+        assert!((profile.imports_ms() - 10.776).abs() < 1e-9);
+
+        let top = profile.top_offenders(2);
+        assert_eq!(top[0].module, "logging");
+        assert_eq!(top[1].module, "socket");
+        assert_eq!(top[1].imported_by.as_deref(), Some("email.utils"));
+    }
+
+    #[test]
+    fn suggests_expensive_packages_the_denylist_allows() {
+        let (profile, _) = parse(OUTPUT);
+        let suggestion =
+            profile.lazy_candidates(&LazyImportConfig::with_defaults(), 2.0, &BTreeMap::new());
+        let allow: Vec<&str> = suggestion.allow.iter().map(|c| c.module.as_str()).collect();
+        assert_eq!(allow, ["email"]);
+        assert!((suggestion.allow[0].cumulative_ms - 5.776).abs() < 1e-9);
+        assert_eq!(suggestion.allow[0].line, None);
+        assert_eq!(suggestion.denied[0].module, "logging");
+        assert_eq!(
+            suggestion.command("lazy_imports.py").as_deref(),
+            Some("pybun lazy-import --generate --allow email -o lazy_imports.py")
+        );
+    }
+
+    #[test]
+    fn keeps_imports_guarded_by_import_error_handlers_eager() {
+        let (profile, _) = parse(OUTPUT);
+        let sites = crate::lazy_import::import_sites(
+            "import logging\ntry:\n    import email.parser\nexcept ImportError:\n    pass\n",
+        );
+        let suggestion = profile.lazy_candidates(&LazyImportConfig::with_defaults(), 2.0, &sites);
+        assert!(suggestion.allow.is_empty());
+        assert_eq!(suggestion.guarded[0].module, "email");
+        assert_eq!(suggestion.guarded[0].line, Some(3));
+        assert_eq!(suggestion.denied[0].line, Some(1));
+        assert_eq!(suggestion.command("lazy_imports.py"), None);
+    }
+}
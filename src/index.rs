@@ -82,6 +82,7 @@ fn build_index(packages: Vec<IndexPackage>) -> InMemoryIndex {
             PackageArtifacts {
                 wheels,
                 sdist: pkg.sdist.clone(),
+                ..Default::default()
             }
         };
         index.add_entry(
@@ -1,16 +1,26 @@
 //! Native wheel installer.
 //!
 //! Unzips generic wheels into a target directory (site-packages).
-//! Handles `.dist-info` creation and basic script installation if needed.
+//! [`install_wheel_into`] completes the install for a virtual environment:
+//! `.data` directories, `console_scripts`/`gui_scripts` launchers, and the
+//! `INSTALLER`/`RECORD` files, so the environment needs no pip.
 //!
 //! Note: This is a minimal implementation focusing on pure-python wheels or
 //! platform-compatible binary wheels for the current system.
+//!
+//! Workspace members are installed editable without building a wheel: a
+//! `__editable__.<name>-<version>.pth` file puts the member's source
+//! directory (`src/` when present) on `sys.path`, next to a `.dist-info`
+//! whose `direct_url.json` marks the install as editable.
 
-use std::fs;
-use std::io;
-use std::path::Path;
+use crate::archive::{ArchiveFormat, ExtractOptions};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
-use zip::ZipArchive;
 
 #[derive(Debug, Error)]
 pub enum InstallError {
@@ -18,6 +28,8 @@ pub enum InstallError {
     Io(#[from] std::io::Error),
     #[error("zip error: {0}")]
     Zip(#[from] zip::result::ZipError),
+    #[error("archive error: {0}")]
+    Archive(#[from] crate::archive::ArchiveError),
     #[error("invalid wheel: {0}")]
     InvalidWheel(String),
 }
@@ -26,39 +38,602 @@ pub type Result<T> = std::result::Result<T, InstallError>;
 
 /// Install a wheel into the specified site-packages directory.
 pub fn install_wheel(wheel_path: &Path, site_packages: &Path) -> Result<()> {
-    let file = fs::File::open(wheel_path)?;
-    let mut archive = ZipArchive::new(file)?;
-
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let outpath = match file.enclosed_name() {
-            Some(path) => site_packages.join(path),
-            None => continue,
-        };
+    crate::archive::extract_as(
+        ArchiveFormat::Zip,
+        wheel_path,
+        site_packages,
+        ExtractOptions::default(),
+    )?;
+    Ok(())
+}
 
-        if file.is_dir() {
-            fs::create_dir_all(&outpath)?;
+/// Where the files of a wheel go in a virtual environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallTarget {
+    pub site_packages: PathBuf,
+    /// `bin/` (`Scripts\` on Windows): entry-point launchers and
+    /// `.data/scripts`.
+    pub scripts: PathBuf,
+    /// Environment root, for `.data/data` and `.data/headers`.
+    pub prefix: PathBuf,
+    /// Interpreter the generated scripts run with.
+    pub python: PathBuf,
+}
+
+impl InstallTarget {
+    /// Layout of the virtual environment at `venv` for Python `version`
+    /// (`3.12` or `3.12.4`).
+    pub fn for_venv(venv: &Path, version: &str) -> Self {
+        let major_minor = version.split('.').take(2).collect::<Vec<_>>().join(".");
+        if cfg!(windows) {
+            Self {
+                site_packages: venv.join("Lib").join("site-packages"),
+                scripts: venv.join("Scripts"),
+                prefix: venv.to_path_buf(),
+                python: venv.join("Scripts").join("python.exe"),
+            }
         } else {
-            if let Some(p) = outpath.parent().filter(|p| !p.exists()) {
-                fs::create_dir_all(p)?;
+            // Free-threaded (`python3.13t`) and PyPy (`pypy3.10`) venvs name
+            // their lib directory after the interpreter flavor.
+            let site_packages = crate::env_clean::site_packages_dir(venv).unwrap_or_else(|| {
+                venv.join("lib")
+                    .join(format!("python{major_minor}"))
+                    .join("site-packages")
+            });
+            Self {
+                site_packages,
+                scripts: venv.join("bin"),
+                prefix: venv.to_path_buf(),
+                python: venv.join("bin").join("python"),
             }
-            let mut outfile = fs::File::create(&outpath)?;
-            io::copy(&mut file, &mut outfile)?;
         }
+    }
+}
 
-        // Preserve permissions (unix)
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            if let Some(mode) = file.unix_mode() {
-                fs::set_permissions(&outpath, fs::Permissions::from_mode(mode))?;
+/// A wheel installed by [`install_wheel_into`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledWheel {
+    /// The distribution's `.dist-info` directory.
+    pub dist_info: PathBuf,
+    /// Scripts written to [`InstallTarget::scripts`].
+    pub scripts: Vec<PathBuf>,
+}
+
+/// Install a wheel into a virtual environment the way pip does: unpack it
+/// into site-packages, move its `.data` directory into place, generate
+/// entry-point launchers, and list every added file in `RECORD` next to an
+/// `INSTALLER` of `pybun`.
+pub fn install_wheel_into(wheel: &Path, target: &InstallTarget) -> Result<InstalledWheel> {
+    let (dist_info_name, data_name) = wheel_layout(wheel)?;
+    let site_packages = &target.site_packages;
+    std::fs::create_dir_all(site_packages)?;
+    install_wheel(wheel, site_packages)?;
+    let dist_info = site_packages.join(&dist_info_name);
+    let distribution = dist_info_name
+        .trim_end_matches(".dist-info")
+        .rsplit_once('-')
+        .map_or(dist_info_name.as_str(), |(name, _)| name)
+        .to_string();
+    let mut added = Vec::new();
+    let mut scripts = Vec::new();
+
+    if let Some(data_name) = &data_name {
+        let data = site_packages.join(data_name);
+        for entry in std::fs::read_dir(&data)? {
+            let entry = entry?;
+            let kind = entry.file_name().to_string_lossy().to_string();
+            let dest = match kind.as_str() {
+                "scripts" => target.scripts.clone(),
+                "purelib" | "platlib" => site_packages.clone(),
+                "data" => target.prefix.clone(),
+                "headers" => target.prefix.join("include").join(&distribution),
+                other => {
+                    return Err(InstallError::InvalidWheel(format!(
+                        "unknown directory {data_name}/{other}"
+                    )));
+                }
+            };
+            for file in move_tree(&entry.path(), &dest)? {
+                if kind == "scripts" {
+                    fix_script_shebang(&file, &target.python)?;
+                    scripts.push(file.clone());
+                }
+                added.push(file);
+            }
+        }
+        std::fs::remove_dir_all(&data)?;
+    }
+
+    for group in ["console_scripts", "gui_scripts"] {
+        for entry in crate::entry_points::declared(&dist_info, &distribution, group) {
+            for file in write_launcher(&entry, target)? {
+                scripts.push(file.clone());
+                added.push(file);
             }
         }
     }
 
+    let installer = dist_info.join("INSTALLER");
+    std::fs::write(&installer, "pybun\n")?;
+    added.push(installer);
+    rewrite_record(&dist_info_name, site_packages, data_name.as_deref(), &added)?;
+    Ok(InstalledWheel { dist_info, scripts })
+}
+
+/// Names of the `.dist-info` and (optional) `.data` directories of a wheel.
+fn wheel_layout(wheel: &Path) -> Result<(String, Option<String>)> {
+    let archive = zip::ZipArchive::new(std::fs::File::open(wheel)?)?;
+    let mut dist_info = None;
+    let mut data = None;
+    for name in archive.file_names() {
+        let Some((top, _)) = name.split_once('/') else {
+            continue;
+        };
+        if top.ends_with(".dist-info") {
+            dist_info.get_or_insert_with(|| top.to_string());
+        } else if top.ends_with(".data") {
+            data.get_or_insert_with(|| top.to_string());
+        }
+    }
+    let dist_info = dist_info.ok_or_else(|| {
+        InstallError::InvalidWheel(format!("{} has no .dist-info directory", wheel.display()))
+    })?;
+    Ok((dist_info, data))
+}
+
+/// Move every file under `src` to the same relative path under `dest`.
+fn move_tree(src: &Path, dest: &Path) -> Result<Vec<PathBuf>> {
+    if src.is_file() {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if std::fs::rename(src, dest).is_err() {
+            std::fs::copy(src, dest)?;
+            std::fs::remove_file(src)?;
+        }
+        return Ok(vec![dest.to_path_buf()]);
+    }
+    let mut moved = Vec::new();
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        moved.extend(move_tree(&entry.path(), &dest.join(entry.file_name()))?);
+    }
+    Ok(moved)
+}
+
+/// First line(s) of a generated script. Interpreter paths with spaces or
+/// beyond the kernel's shebang limit go through `/bin/sh`, as pip does.
+fn shebang(python: &Path) -> String {
+    let python = python.display().to_string();
+    if python.contains(' ') || python.len() > 127 {
+        format!("#!/bin/sh\n'''exec' \"{python}\" \"$0\" \"$@\"\n' '''\n")
+    } else {
+        format!("#!{python}\n")
+    }
+}
+
+/// Point the `#!python` placeholder of a `.data/scripts` file at the
+/// environment's interpreter.
+fn fix_script_shebang(path: &Path, python: &Path) -> Result<()> {
+    let content = std::fs::read(path)?;
+    if content.starts_with(b"#!python") {
+        let rest = content
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(&[][..], |i| &content[i + 1..]);
+        let mut fixed = shebang(python).into_bytes();
+        fixed.extend_from_slice(rest);
+        std::fs::write(path, fixed)?;
+    }
+    make_executable(path)
+}
+
+fn make_executable(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Write the launcher for an entry point: an executable Python script, or a
+/// `-script.py` file with a `.cmd` wrapper on Windows.
+fn write_launcher(
+    entry: &crate::entry_points::EntryPoint,
+    target: &InstallTarget,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(&target.scripts)?;
+    let code = format!("# -*- coding: utf-8 -*-\n{}", entry.python_code());
+    if cfg!(windows) {
+        let script = target.scripts.join(format!("{}-script.py", entry.name));
+        let wrapper = target.scripts.join(format!("{}.cmd", entry.name));
+        std::fs::write(&script, code)?;
+        std::fs::write(
+            &wrapper,
+            format!(
+                "@\"{}\" \"%~dp0{}-script.py\" %*\r\n",
+                target.python.display(),
+                entry.name
+            ),
+        )?;
+        Ok(vec![script, wrapper])
+    } else {
+        let script = target.scripts.join(&entry.name);
+        std::fs::write(&script, format!("{}{}", shebang(&target.python), code))?;
+        make_executable(&script)?;
+        Ok(vec![script])
+    }
+}
+
+/// `path` relative to `base` with `/` separators, e.g. `../../../bin/black`.
+fn record_path(path: &Path, base: &Path) -> String {
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    std::iter::repeat_n("..".to_string(), base.len() - common)
+        .chain(
+            path[common..]
+                .iter()
+                .map(|c| c.as_os_str().to_string_lossy().to_string()),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Quote a `RECORD` field the way Python's `csv` module does.
+fn record_field(value: &str) -> String {
+    if value.contains([',', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Drop the `.data` entries of the wheel's `RECORD` and list the `added`
+/// files with their hash and size instead.
+fn rewrite_record(
+    dist_info_name: &str,
+    site_packages: &Path,
+    data_name: Option<&str>,
+    added: &[PathBuf],
+) -> Result<()> {
+    let path = site_packages.join(dist_info_name).join("RECORD");
+    let own = [
+        format!("{dist_info_name}/RECORD"),
+        format!("{dist_info_name}/INSTALLER"),
+    ];
+    let mut record: String = std::fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| {
+            let file = line.split(',').next().unwrap_or_default();
+            !line.trim().is_empty()
+                && !own.iter().any(|o| o == file)
+                && data_name.is_none_or(|data| !file.starts_with(&format!("{data}/")))
+        })
+        .map(|line| format!("{line}\n"))
+        .collect();
+    for file in added {
+        let content = std::fs::read(file)?;
+        let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(&content));
+        record.push_str(&format!(
+            "{},sha256={},{}\n",
+            record_field(&record_path(file, site_packages)),
+            hash,
+            content.len()
+        ));
+    }
+    record.push_str(&format!("{dist_info_name}/RECORD,,\n"));
+    std::fs::write(path, record)?;
     Ok(())
 }
 
+/// A local project to install in development mode.
+#[derive(Debug, Clone)]
+pub struct EditableProject<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    /// Directory containing the project's `pyproject.toml`.
+    pub root: &'a Path,
+    /// `Requires-Dist` entries.
+    pub requires: &'a [String],
+    /// `[project.scripts]`, written as console-script entry points.
+    pub scripts: &'a BTreeMap<String, String>,
+}
+
+/// Install `project` editable into `site_packages`, replacing an earlier
+/// editable install of it. Returns the written `.dist-info` directory.
+pub fn install_editable(project: &EditableProject<'_>, site_packages: &Path) -> Result<PathBuf> {
+    let dist = crate::pypi::normalize_project_name(project.name).replace('-', "_");
+    remove_editable(site_packages, &dist)?;
+
+    let root = project.root.canonicalize()?;
+    let source = if root.join("src").is_dir() {
+        root.join("src")
+    } else {
+        root.clone()
+    };
+
+    let pth = format!("__editable__.{}-{}.pth", dist, project.version);
+    std::fs::write(site_packages.join(&pth), format!("{}\n", source.display()))?;
+
+    let dist_info_name = format!("{}-{}.dist-info", dist, project.version);
+    let dist_info = site_packages.join(&dist_info_name);
+    std::fs::create_dir_all(&dist_info)?;
+
+    let mut metadata = format!(
+        "Metadata-Version: 2.1\nName: {}\nVersion: {}\n",
+        project.name, project.version
+    );
+    for requirement in project.requires {
+        metadata.push_str(&format!("Requires-Dist: {}\n", requirement));
+    }
+    let direct_url = serde_json::json!({
+        "url": format!("file://{}", root.display()),
+        "dir_info": { "editable": true },
+    });
+    let mut files = vec![
+        ("METADATA", metadata),
+        ("INSTALLER", "pybun\n".to_string()),
+        ("direct_url.json", direct_url.to_string()),
+    ];
+    if !project.scripts.is_empty() {
+        let mut entry_points = "[console_scripts]\n".to_string();
+        for (name, target) in project.scripts {
+            entry_points.push_str(&format!("{} = {}\n", name, target));
+        }
+        files.push(("entry_points.txt", entry_points));
+    }
+    let mut record = format!("{},,\n", pth);
+    for (file, content) in &files {
+        std::fs::write(dist_info.join(file), content)?;
+        record.push_str(&format!("{}/{},,\n", dist_info_name, file));
+    }
+    record.push_str(&format!("{}/RECORD,,\n", dist_info_name));
+    std::fs::write(dist_info.join("RECORD"), record)?;
+
+    Ok(dist_info)
+}
+
+/// Remove the files of an earlier editable install of `dist` (any version).
+/// Installs from wheels are left alone.
+fn remove_editable(site_packages: &Path, dist: &str) -> Result<()> {
+    let Ok(entries) = std::fs::read_dir(site_packages) else {
+        return Ok(());
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(stem) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(".dist-info"))
+        else {
+            continue;
+        };
+        let same_dist = stem
+            .rsplit_once('-')
+            .is_some_and(|(name, _)| name.eq_ignore_ascii_case(dist));
+        let editable = std::fs::read_to_string(path.join("direct_url.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+            .is_some_and(|url| url["dir_info"]["editable"] == true);
+        if !same_dist || !editable {
+            continue;
+        }
+        let record = std::fs::read_to_string(path.join("RECORD")).unwrap_or_default();
+        for line in record.lines() {
+            let file = line.split(',').next().unwrap_or_default();
+            if file.starts_with("__editable__.") && !file.contains('/') {
+                let _ = std::fs::remove_file(site_packages.join(file));
+            }
+        }
+        std::fs::remove_dir_all(&path)?;
+    }
+    Ok(())
+}
+
+/// Uninstall every version of `name` other than `version` from `target`,
+/// as pip does before an upgrade: the files each `RECORD` lists are deleted
+/// (along with the bytecode of listed `.py` files), directories left empty
+/// are pruned, and the `.dist-info` goes last. Entries that resolve outside
+/// the environment prefix are left alone. Returns the removed versions.
+pub fn remove_other_versions(
+    target: &InstallTarget,
+    name: &str,
+    version: &str,
+) -> Result<Vec<String>> {
+    let wanted = crate::pypi::normalize_project_name(name);
+    let site_packages = &target.site_packages;
+    let mut removed = Vec::new();
+    for dist in crate::dist_info::installed(site_packages) {
+        if dist.version == version || crate::pypi::normalize_project_name(&dist.name) != wanted {
+            continue;
+        }
+        let record = std::fs::read_to_string(dist.path.join("RECORD")).unwrap_or_default();
+        let mut dirs = std::collections::BTreeSet::new();
+        for line in record.lines() {
+            let Some(path) = resolve_record_entry(site_packages, &record_file(line)) else {
+                continue;
+            };
+            if !path.starts_with(&target.prefix) && !path.starts_with(site_packages) {
+                continue;
+            }
+            if path.starts_with(&dist.path) {
+                continue;
+            }
+            remove_file_if_present(&path)?;
+            if path.extension().is_some_and(|ext| ext == "py") {
+                remove_bytecode(&path)?;
+            }
+            if let Some(parent) = path.parent() {
+                dirs.extend(
+                    parent
+                        .ancestors()
+                        .take_while(|dir| *dir != site_packages && *dir != target.prefix)
+                        .map(Path::to_path_buf),
+                );
+            }
+        }
+        // Children sort after their parents, so prune in reverse.
+        for dir in dirs.iter().rev() {
+            let _ = std::fs::remove_dir(dir.join("__pycache__"));
+            let _ = std::fs::remove_dir(dir);
+        }
+        std::fs::remove_dir_all(&dist.path)?;
+        removed.push(dist.version);
+    }
+    Ok(removed)
+}
+
+/// The path column of a `RECORD` line, unquoted the way Python's `csv`
+/// module writes it.
+fn record_file(line: &str) -> String {
+    let Some(quoted) = line.strip_prefix('"') else {
+        return line.split(',').next().unwrap_or_default().to_string();
+    };
+    let mut file = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            if chars.peek() != Some(&'"') {
+                break;
+            }
+            chars.next();
+        }
+        file.push(c);
+    }
+    file
+}
+
+/// `file` (a `RECORD` path, relative to site-packages) resolved lexically.
+/// `None` for empty or absolute paths.
+fn resolve_record_entry(site_packages: &Path, file: &str) -> Option<PathBuf> {
+    use std::path::Component;
+    if file.is_empty() {
+        return None;
+    }
+    let mut path = site_packages.to_path_buf();
+    for component in Path::new(file).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(path)
+}
+
+fn remove_file_if_present(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Delete the `__pycache__/<stem>.*.pyc` files compiled from `source`.
+fn remove_bytecode(source: &Path) -> Result<()> {
+    let (Some(dir), Some(stem)) = (source.parent(), source.file_stem()) else {
+        return Ok(());
+    };
+    let prefix = format!("{}.", stem.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir.join("__pycache__")) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(&prefix) && name.ends_with(".pyc") {
+            remove_file_if_present(&entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Outcome of installing one resolved package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallStatus {
+    /// The wheel was downloaded (or reused from the cache), verified and
+    /// extracted into site-packages.
+    Installed,
+    /// The same name and version is already present in site-packages.
+    AlreadyInstalled,
+    /// Locked only: the index provides no download URL for the artifact.
+    NoDownloadUrl,
+    /// Download or hash verification failed.
+    DownloadFailed,
+    /// The wheel could not be extracted into site-packages.
+    InstallFailed,
+}
+
+/// Per-package install report (`detail.results[]` of `pybun install`).
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallRecord {
+    pub name: String,
+    pub version: String,
+    pub wheel: String,
+    pub status: InstallStatus,
+    /// Hash the downloaded wheel was verified against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Hash the artifact actually had when it did not match `hash`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual_hash: Option<String>,
+    /// The wheel was linked from the content-addressed wheel store instead
+    /// of being downloaded.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+    /// The wheel was built from the package's sdist (PEP 517).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub built: bool,
+    /// The package's `.dist-info` directory in site-packages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dist_info: Option<PathBuf>,
+    /// Versions of the same project uninstalled to make room for this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replaced: Vec<String>,
+    /// Project directory of a workspace member installed editable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editable: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl InstallRecord {
+    pub fn new(name: &str, version: &str, wheel: &str, status: InstallStatus) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            wheel: wheel.to_string(),
+            status,
+            hash: None,
+            actual_hash: None,
+            cached: false,
+            built: false,
+            dist_info: None,
+            replaced: Vec::new(),
+            editable: None,
+            error: None,
+        }
+    }
+}
+
+/// The `.dist-info` directory for `name` `version` in `site_packages`, if
+/// that exact version is installed. Names are compared PEP 503-normalized.
+pub fn installed_dist_info(site_packages: &Path, name: &str, version: &str) -> Option<PathBuf> {
+    let wanted = crate::pypi::normalize_project_name(name);
+    crate::dist_info::installed(site_packages)
+        .into_iter()
+        .find(|dist| {
+            dist.version == version && crate::pypi::normalize_project_name(&dist.name) == wanted
+        })
+        .map(|dist| dist.path)
+}
+
 // Create a direct symlink for the python executable to avoid venv overhead?
 // Or simpler: Just stick to standard venv creation for now, but use `install_wheel` for deps.
 //
@@ -66,3 +641,206 @@ pub fn install_wheel(wheel_path: &Path, site_packages: &Path) -> Result<()> {
 // `python -m venv` is slow because it copies files.
 // We can optimize venv creation later if needed.
 // Focusing on `pip install` replacement first.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn demo_wheel(dir: &Path) -> PathBuf {
+        use std::io::Write;
+        let path = dir.join("demo-1.0-py3-none-any.whl");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in [
+            ("demo/__init__.py", "def main():\n    return 0\n"),
+            (
+                "demo-1.0.dist-info/METADATA",
+                "Metadata-Version: 2.1\nName: demo\nVersion: 1.0\n",
+            ),
+            ("demo-1.0.dist-info/WHEEL", "Wheel-Version: 1.0\n"),
+            (
+                "demo-1.0.dist-info/entry_points.txt",
+                "[console_scripts]\ndemo = demo:main\n\n[gui_scripts]\ndemo-gui = demo:main\n",
+            ),
+            (
+                "demo-1.0.data/scripts/demo-tool",
+                "#!python\nprint('tool')\n",
+            ),
+            ("demo-1.0.data/purelib/demo_extra.py", "X = 1\n"),
+            ("demo-1.0.data/data/share/demo/readme.txt", "hi\n"),
+            (
+                "demo-1.0.dist-info/RECORD",
+                "demo/__init__.py,sha256=abc,27\ndemo-1.0.data/scripts/demo-tool,,\ndemo-1.0.dist-info/RECORD,,\n",
+            ),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn install_wheel_into_completes_a_venv_install() {
+        let temp = tempdir().unwrap();
+        let venv = temp.path().join("venv");
+        let target = InstallTarget::for_venv(&venv, "3.12.4");
+        let installed = install_wheel_into(&demo_wheel(temp.path()), &target).unwrap();
+
+        assert_eq!(
+            installed.dist_info,
+            target.site_packages.join("demo-1.0.dist-info")
+        );
+        assert!(target.site_packages.join("demo/__init__.py").is_file());
+        assert!(target.site_packages.join("demo_extra.py").is_file());
+        assert!(!target.site_packages.join("demo-1.0.data").exists());
+        assert!(venv.join("share/demo/readme.txt").is_file());
+        assert_eq!(
+            std::fs::read_to_string(installed.dist_info.join("INSTALLER")).unwrap(),
+            "pybun\n"
+        );
+
+        let tool = std::fs::read_to_string(target.scripts.join("demo-tool")).unwrap();
+        assert!(tool.starts_with(&shebang(&target.python)), "{tool}");
+        assert!(tool.ends_with("print('tool')\n"));
+        assert_eq!(installed.scripts.len(), if cfg!(windows) { 5 } else { 3 });
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let launcher = target.scripts.join("demo");
+            let mode = std::fs::metadata(&launcher).unwrap().permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+            let code = std::fs::read_to_string(&launcher).unwrap();
+            assert!(code.contains("importlib.import_module('demo')"), "{code}");
+        }
+
+        let record = std::fs::read_to_string(installed.dist_info.join("RECORD")).unwrap();
+        assert!(record.contains("demo/__init__.py,sha256=abc,27\n"));
+        assert!(!record.contains("demo-1.0.data/"));
+        assert!(record.contains("demo_extra.py,sha256="));
+        assert!(record.contains("demo-1.0.dist-info/INSTALLER,sha256="));
+        if cfg!(unix) {
+            assert!(record.contains("\n../../../bin/demo,sha256="), "{record}");
+            assert!(record.contains("\n../../../share/demo/readme.txt,sha256="));
+        }
+        assert!(record.ends_with("demo-1.0.dist-info/RECORD,,\n"));
+    }
+
+    #[test]
+    fn shebang_falls_back_to_sh_for_paths_with_spaces() {
+        assert_eq!(
+            shebang(Path::new("/venv/bin/python")),
+            "#!/venv/bin/python\n"
+        );
+        assert!(
+            shebang(Path::new("/my venv/bin/python"))
+                .starts_with("#!/bin/sh\n'''exec' \"/my venv/bin/python\"")
+        );
+    }
+
+    #[test]
+    fn installed_dist_info_matches_normalized_name_and_exact_version() {
+        let temp = tempdir().unwrap();
+        std::fs::create_dir(temp.path().join("Typing_Extensions-4.12.2.dist-info")).unwrap();
+
+        assert_eq!(
+            installed_dist_info(temp.path(), "typing-extensions", "4.12.2"),
+            Some(temp.path().join("Typing_Extensions-4.12.2.dist-info"))
+        );
+        assert!(installed_dist_info(temp.path(), "typing-extensions", "4.12.1").is_none());
+        assert!(installed_dist_info(temp.path(), "typing", "4.12.2").is_none());
+        assert!(installed_dist_info(&temp.path().join("missing"), "x", "1").is_none());
+    }
+
+    #[test]
+    fn remove_other_versions_uninstalls_by_record() {
+        let temp = tempdir().unwrap();
+        let venv = temp.path().join("venv");
+        let target = InstallTarget::for_venv(&venv, "3.12");
+        let site = &target.site_packages;
+        let old = site.join("Demo_Pkg-1.0.dist-info");
+        std::fs::create_dir_all(&old).unwrap();
+        std::fs::create_dir_all(site.join("demo_pkg/sub/__pycache__")).unwrap();
+        std::fs::create_dir_all(&target.scripts).unwrap();
+        for file in [
+            "demo_pkg/sub/old.py",
+            "demo_pkg/sub/__pycache__/old.cpython-312.pyc",
+            "demo_pkg/keep.txt",
+        ] {
+            std::fs::write(site.join(file), "x").unwrap();
+        }
+        std::fs::write(target.scripts.join("demo"), "x").unwrap();
+        std::fs::write(temp.path().join("outside"), "x").unwrap();
+        std::fs::write(
+            old.join("RECORD"),
+            "demo_pkg/sub/old.py,,\n\"../../../bin/demo\",,\n../../../../outside,,\nDemo_Pkg-1.0.dist-info/RECORD,,\n",
+        )
+        .unwrap();
+        let current = site.join("demo_pkg-2.0.dist-info");
+        std::fs::create_dir_all(&current).unwrap();
+
+        assert_eq!(
+            remove_other_versions(&target, "demo.pkg", "2.0").unwrap(),
+            vec!["1.0".to_string()]
+        );
+        assert!(!old.exists());
+        assert!(current.is_dir());
+        assert!(!site.join("demo_pkg/sub").exists());
+        assert!(site.join("demo_pkg/keep.txt").is_file());
+        assert!(!target.scripts.join("demo").exists());
+        assert!(temp.path().join("outside").is_file());
+        assert!(
+            remove_other_versions(&target, "demo-pkg", "2.0")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn install_editable_links_source_and_replaces_earlier_version() {
+        let temp = tempdir().unwrap();
+        let site = temp.path().join("site");
+        let member = temp.path().join("packages/my-sdk");
+        std::fs::create_dir_all(&site).unwrap();
+        std::fs::create_dir_all(member.join("src/my_sdk")).unwrap();
+
+        let requires = vec!["requests>=2".to_string()];
+        let scripts = BTreeMap::from([("sdk".to_string(), "my_sdk.cli:main".to_string())]);
+        let project = EditableProject {
+            name: "my-sdk",
+            version: "0.1.0",
+            root: &member,
+            requires: &requires,
+            scripts: &scripts,
+        };
+        let dist_info = install_editable(&project, &site).unwrap();
+        assert_eq!(dist_info, site.join("my_sdk-0.1.0.dist-info"));
+        let pth = std::fs::read_to_string(site.join("__editable__.my_sdk-0.1.0.pth")).unwrap();
+        assert_eq!(
+            pth.trim(),
+            member
+                .canonicalize()
+                .unwrap()
+                .join("src")
+                .display()
+                .to_string()
+        );
+        let metadata = std::fs::read_to_string(dist_info.join("METADATA")).unwrap();
+        assert!(metadata.contains("Requires-Dist: requests>=2"));
+        assert!(
+            std::fs::read_to_string(dist_info.join("entry_points.txt"))
+                .unwrap()
+                .contains("sdk = my_sdk.cli:main")
+        );
+
+        let project = EditableProject {
+            version: "0.2.0",
+            ..project
+        };
+        install_editable(&project, &site).unwrap();
+        assert!(!site.join("my_sdk-0.1.0.dist-info").exists());
+        assert!(!site.join("__editable__.my_sdk-0.1.0.pth").exists());
+        assert!(installed_dist_info(&site, "my-sdk", "0.2.0").is_some());
+    }
+}
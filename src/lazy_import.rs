@@ -9,8 +9,9 @@
 //! - Fallback: CPython's native import system when lazy import fails
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 /// Configuration for lazy import behavior.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -296,10 +297,12 @@ This module provides lazy import functionality to reduce startup time.
 Generated by PyBun - do not edit manually.
 """
 
-import sys
-import importlib
+import atexit
 import importlib.abc
+import importlib.machinery
 import importlib.util
+import os
+import sys
 
 # Configuration
 _ENABLED = {enabled}
@@ -312,105 +315,58 @@ _DENYLIST = {{
 
 _ALLOWLIST = {allowlist_py}
 
+# Where `pybun run --lazy-imports` wants the report. Popped so that Python
+# subprocesses of the program do not overwrite it.
+_REPORT_PATH = os.environ.pop("PYBUN_LAZY_IMPORT_REPORT", None)
 
-class LazyModule:
-    """A proxy object that defers module import until first attribute access."""
+# Modules whose import was deferred, in import order.
+_DEFERRED = []
 
-    __slots__ = ('_name', '_module', '_loading')
 
-    def __init__(self, name):
-        object.__setattr__(self, '_name', name)
-        object.__setattr__(self, '_module', None)
-        object.__setattr__(self, '_loading', False)
+class LazyLoader(importlib.util.LazyLoader):
+    """Loader that defers running a module until one of its attributes is used."""
 
-    def _load(self):
-        if object.__getattribute__(self, '_loading'):
-            # Prevent infinite recursion
-            raise ImportError(f"Circular lazy import detected for {{self._name}}")
+    def exec_module(self, module):
+        _DEFERRED.append(module.__name__)
+        if _LOG_IMPORTS:
+            print(f"[pybun] Deferred import: {{module.__name__}}", file=sys.stderr)
+        super().exec_module(module)
 
-        object.__setattr__(self, '_loading', True)
-        try:
-            if _LOG_IMPORTS:
-                print(f"[pybun] Loading lazy module: {{self._name}}")
-            module = importlib.import_module(self._name)
-            object.__setattr__(self, '_module', module)
-            return module
-        finally:
-            object.__setattr__(self, '_loading', False)
-
-    def __getattr__(self, name):
-        module = object.__getattribute__(self, '_module')
-        if module is None:
-            module = self._load()
-        return getattr(module, name)
-
-    def __setattr__(self, name, value):
-        if name in ('_name', '_module', '_loading'):
-            object.__setattr__(self, name, value)
-        else:
-            module = object.__getattribute__(self, '_module')
-            if module is None:
-                module = self._load()
-            setattr(module, name, value)
-
-    def __repr__(self):
-        module = object.__getattribute__(self, '_module')
-        if module is None:
-            return f"<lazy module '{{self._name}}' (not loaded)>"
-        return repr(module)
+
+def _eligible(fullname):
+    parts = fullname.split('.')
+    parents = ['.'.join(parts[:i]) for i in range(1, len(parts) + 1)]
+    if any(name in _DENYLIST for name in parents):
+        return False
+    return _ALLOWLIST is None or any(name in _ALLOWLIST for name in parents)
 
 
 class LazyFinder(importlib.abc.MetaPathFinder):
-    """Meta path finder that returns lazy loaders for eligible modules."""
+    """Meta path finder that defers the import of eligible modules."""
 
     def find_spec(self, fullname, path, target=None):
-        if not _ENABLED:
+        if not _ENABLED or not _eligible(fullname):
             return None
-
-        # Check denylist
-        if fullname in _DENYLIST:
-            return None
-
-        # Check parent modules in denylist
-        parts = fullname.split('.')
-        for i in range(1, len(parts)):
-            parent = '.'.join(parts[:i])
-            if parent in _DENYLIST:
-                return None
-
-        # Check allowlist if specified
-        if _ALLOWLIST is not None:
-            if fullname not in _ALLOWLIST:
-                # Check parent modules
-                allowed = False
-                for i in range(1, len(parts)):
-                    parent = '.'.join(parts[:i])
-                    if parent in _ALLOWLIST:
-                        allowed = True
-                        break
-                if not allowed:
+        for finder in sys.meta_path:
+            if finder is self or not hasattr(finder, "find_spec"):
+                continue
+            try:
+                spec = finder.find_spec(fullname, path, target)
+            except Exception:
+                if _FALLBACK:
                     return None
-
-        # Create a lazy loader spec
-        return importlib.machinery.ModuleSpec(
-            fullname,
-            LazyLoader(fullname),
-            is_package=False
-        )
-
-
-class LazyLoader(importlib.abc.Loader):
-    """Loader that creates lazy module proxies."""
-
-    def __init__(self, name):
-        self.name = name
-
-    def create_module(self, spec):
-        return LazyModule(self.name)
-
-    def exec_module(self, module):
-        # Lazy modules don't execute until accessed
-        pass
+                raise
+            if spec is None:
+                continue
+            # Only Python source and bytecode can be deferred; extension,
+            # built-in and frozen modules load as usual.
+            if isinstance(
+                spec.loader,
+                (importlib.machinery.SourceFileLoader, importlib.machinery.SourcelessFileLoader),
+            ):
+                spec.loader = LazyLoader(spec.loader)
+            return spec
+        return None
 
 
 def install():
@@ -419,22 +375,44 @@ def install():
         # Insert at the beginning, before other finders
         sys.meta_path.insert(0, LazyFinder())
         if _LOG_IMPORTS:
-            print("[pybun] Lazy import finder installed")
+            print("[pybun] Lazy import finder installed", file=sys.stderr)
 
 
 def is_lazy(module):
-    """Check if a module is a lazy proxy."""
-    return isinstance(module, LazyModule)
+    """Check if a module is deferred and not loaded yet."""
+    return type(module) is getattr(importlib.util, "_LazyModule", None)
 
 
 def force_load(module):
-    """Force a lazy module to load immediately."""
-    if isinstance(module, LazyModule):
-        module._load()
-        return module._module
+    """Force a deferred module to load immediately."""
+    if is_lazy(module):
+        module.__dict__
     return module
 
 
+def report():
+    """The modules the finder deferred, and those of them never loaded."""
+    deferred = [name for name in _DEFERRED if name in sys.modules]
+    return {{
+        "deferred": deferred,
+        "never_loaded": [name for name in deferred if is_lazy(sys.modules[name])],
+    }}
+
+
+def _write_report():
+    global _ENABLED
+    result = report()
+    # json is imported here: the report must not defer it.
+    _ENABLED = False
+    import json
+
+    with open(_REPORT_PATH, "w") as out:
+        json.dump(result, out)
+
+
+if _REPORT_PATH:
+    atexit.register(_write_report)
+
 # Auto-install if this module is imported
 install()
 "#,
@@ -458,6 +436,114 @@ pub fn generate_lazy_import_python_code(config: &LazyImportConfig) -> String {
     generate_lazy_import_python_code_with_module_name(config, None)
 }
 
+/// Where a script imports a package while it starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportSite {
+    /// Line of the script's first import of the package.
+    pub line: usize,
+    /// An import of the package is inside a `try` that handles
+    /// `ImportError`. The hook still raises `ImportError` for a package it
+    /// cannot find, but one the package's own code raises while loading
+    /// (e.g. for a missing optional dependency) is raised where the package
+    /// is first used, outside the `try`.
+    pub guarded: bool,
+}
+
+/// The top-level packages a script imports outside functions, with where it
+/// imports them. Relative imports name the script's own modules and are
+/// left out.
+pub fn import_sites(source: &str) -> BTreeMap<String, ImportSite> {
+    let mut sites: BTreeMap<String, ImportSite> = BTreeMap::new();
+    for import in crate::python_ast::imports(source) {
+        if import.in_function || import.level > 0 {
+            continue;
+        }
+        let package = import.module.split('.').next().unwrap_or_default();
+        let site = sites.entry(package.to_string()).or_insert(ImportSite {
+            line: import.line,
+            guarded: false,
+        });
+        site.guarded |= import.guarded;
+    }
+    sites
+}
+
+/// Environment variable naming the file the generated hook writes its
+/// [`LazyImportReport`] to when the interpreter exits.
+pub const REPORT_ENV: &str = "PYBUN_LAZY_IMPORT_REPORT";
+
+/// What the lazy-import hook deferred during one `pybun run --lazy-imports`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LazyImportReport {
+    /// Modules whose import was deferred, in import order.
+    pub deferred: Vec<String>,
+    /// Deferred modules the program used after all, and so loaded later.
+    #[serde(default)]
+    pub loaded_on_use: Vec<String>,
+    /// Deferred modules the program never used.
+    pub never_loaded: Vec<String>,
+    /// Time importing `never_loaded` eagerly takes once `loaded_on_use` is
+    /// loaded, timed in a second interpreter after the run: an estimate of
+    /// the start-up time the hook saved. Only with `--measure-lazy-savings`.
+    #[serde(default)]
+    pub estimated_saved_ms: Option<f64>,
+}
+
+/// Imports the comma-separated modules of `argv[2]`, then times importing
+/// those of `argv[3]`, with `argv[1]` first on `sys.path`.
+const SAVINGS_PROBE: &str = r#"
+import importlib, sys, time
+sys.path.insert(0, sys.argv[1])
+def load(names):
+    for name in filter(None, names.split(",")):
+        try:
+            importlib.import_module(name)
+        except Exception:
+            pass
+load(sys.argv[2])
+start = time.perf_counter()
+load(sys.argv[3])
+print((time.perf_counter() - start) * 1000)
+"#;
+
+impl LazyImportReport {
+    /// Read the report the hook wrote; `None` if it wrote none.
+    pub fn read(path: &Path) -> Option<Self> {
+        let mut report: Self = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+        report.loaded_on_use = report
+            .deferred
+            .iter()
+            .filter(|name| !report.never_loaded.contains(name))
+            .cloned()
+            .collect();
+        Some(report)
+    }
+
+    /// Measure [`Self::estimated_saved_ms`] with `python`, a command for the
+    /// program's interpreter in its environment but without the hook, with
+    /// `sys_path` first on `sys.path` as it was for the program.
+    pub fn measure_savings(&self, mut python: Command, sys_path: &Path) -> Option<f64> {
+        if self.never_loaded.is_empty() {
+            return Some(0.0);
+        }
+        let output = python
+            .arg("-c")
+            .arg(SAVINGS_PROBE)
+            .arg(sys_path)
+            .arg(self.loaded_on_use.join(","))
+            .arg(self.never_loaded.join(","))
+            .env_remove(REPORT_ENV)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -470,6 +556,22 @@ mod tests {
         assert!(!config.denylist.is_empty());
     }
 
+    #[test]
+    fn test_report_splits_deferred_modules_by_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        std::fs::write(
+            &path,
+            r#"{"deferred": ["json", "decimal", "json.decoder"], "never_loaded": ["decimal"]}"#,
+        )
+        .unwrap();
+        let report = LazyImportReport::read(&path).unwrap();
+        assert_eq!(report.loaded_on_use, ["json", "json.decoder"]);
+        assert_eq!(report.never_loaded, ["decimal"]);
+        assert_eq!(report.estimated_saved_ms, None);
+        assert!(LazyImportReport::read(&dir.path().join("missing.json")).is_none());
+    }
+
     #[test]
     fn test_with_defaults_enabled() {
         let config = LazyImportConfig::with_defaults();
@@ -588,6 +690,32 @@ mod tests {
         assert!(!config.is_allowed("pandas"));
     }
 
+    #[test]
+    fn test_import_sites_skip_function_and_relative_imports() {
+        let sites = import_sites(
+            "import numpy.linalg\n\
+             from . import helpers\n\
+             try:\n    import yaml\nexcept ImportError:\n    yaml = None\n\
+             from numpy import array\n\
+             def main():\n    import pandas\n",
+        );
+        assert_eq!(sites.keys().collect::<Vec<_>>(), ["numpy", "yaml"]);
+        assert_eq!(
+            sites["numpy"],
+            ImportSite {
+                line: 1,
+                guarded: false
+            }
+        );
+        assert_eq!(
+            sites["yaml"],
+            ImportSite {
+                line: 4,
+                guarded: true
+            }
+        );
+    }
+
     #[test]
     fn test_lazy_import_stats() {
         let mut stats = LazyImportStats::new();
@@ -616,7 +744,6 @@ mod tests {
         let config = LazyImportConfig::with_defaults();
         let code = generate_lazy_import_python_code(&config);
 
-        assert!(code.contains("class LazyModule"));
         assert!(code.contains("class LazyFinder"));
         assert!(code.contains("class LazyLoader"));
         assert!(code.contains("_ENABLED = True"));
@@ -698,7 +825,7 @@ mod tests {
 
         // Verify the LazyFinder.find_spec will skip lazy_setup
         // This is verified by checking the denylist check is present
-        assert!(code.contains("if fullname in _DENYLIST:"));
+        assert!(code.contains("if any(name in _DENYLIST for name in parents):"));
         assert!(code.contains("return None"));
     }
 }
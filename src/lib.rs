@@ -1,24 +1,72 @@
+pub mod advisory_db;
+pub mod alias;
 #[cfg(feature = "performance-allocator")]
 pub mod allocator;
+pub mod arch;
+pub mod archive;
 pub mod audit;
+pub mod auth;
+pub mod bench;
 pub mod build;
+pub mod build_isolation;
 pub mod cache;
+pub mod cache_inspect;
+pub mod cache_stats;
+pub mod change_diff;
+pub mod chunking;
 pub mod cli;
+pub mod command_history;
 pub mod commands;
+pub mod config_schema;
+pub mod dep_export;
+pub mod dep_graph;
+pub mod dep_import;
+pub mod dep_rename;
+pub mod dep_tree;
+pub mod dep_why;
+pub mod dist_info;
 pub mod downloader;
 pub mod drift;
+pub mod encoding;
 pub mod entry;
+pub mod entry_points;
 pub mod env;
+pub mod env_archive;
 pub mod env_cache;
+pub mod env_clean;
+pub mod env_health;
+pub mod env_snapshot;
+pub mod error_catalog;
+pub mod execution_policy;
+pub mod fix_plan;
+pub mod foreign_lock;
+pub mod fsutil;
+pub mod gc_plan;
+pub mod gc_policy;
+pub mod git_source;
+pub mod health;
 pub mod hot_reload;
+pub mod http_config;
+pub mod import_profile;
 pub mod index;
 pub mod installer;
 pub mod lazy_import;
+pub mod lock_check;
+pub mod lock_shard;
+pub mod lock_update;
 pub mod lockfile;
 pub mod mcp;
+pub mod mcp_http;
+pub mod mcp_progress;
+pub mod mcp_resources;
 pub mod module_finder;
+pub mod ndjson;
+pub mod network_policy;
+pub mod offline;
 pub mod once_map;
+pub mod operation;
 pub mod paths;
+pub mod payload;
 pub mod pep440;
 pub mod pep723;
 pub mod pep723_cache;
@@ -26,21 +74,56 @@ pub mod proc_exec;
 pub mod profiles;
 pub mod progress;
 pub mod project;
+pub mod project_registry;
+pub mod publish;
 pub mod pypi;
+pub mod pypi_index;
+pub mod python_ast;
+pub mod python_pin;
 pub mod release_manifest;
+pub mod remote_cache;
+pub mod reproducible;
 pub mod resolver;
+pub mod resolver_strategy;
 pub mod runtime;
+pub mod runtime_manifest;
+pub mod runtime_profile;
+pub mod runtime_source;
+pub mod runtime_usage;
 pub mod sandbox;
+pub mod sandbox_os;
 pub mod sbom;
 pub mod schema;
+pub mod script_lock;
+pub mod sdist_build;
+pub mod sdist_metadata;
 pub mod security;
+pub mod seed;
 pub mod self_heal;
 pub mod self_update;
+pub mod shell_hook;
 pub mod snapshot;
 pub mod support_bundle;
+pub mod suspicious;
+pub mod syntax_check;
+pub mod table;
+pub mod tags;
+pub mod tasks;
 pub mod telemetry;
+pub mod test_coverage;
 pub mod test_discovery;
 pub mod test_executor;
+pub mod test_history;
+pub mod test_impact;
+pub mod test_params;
+pub mod test_plugins;
+pub mod test_report;
+pub mod test_scheduler;
+pub mod test_selection;
+pub mod tool_env;
+pub mod tool_exec;
 pub mod traceback;
+pub mod units;
+pub mod update_channel;
 pub mod wheel_cache;
 pub mod workspace;
@@ -0,0 +1,262 @@
+//! `pybun lock --check`: whether `pybun.lockb` still matches pyproject.toml.
+//!
+//! Nothing is resolved and the network is never used. Declared requirements
+//! are compared against the lock with [`crate::script_lock::check`] (missing,
+//! out-of-range, and no longer required packages); on top of that every
+//! locked package's own requirements must be satisfied by the versions
+//! locked for its dependencies, which catches hand-edited or half-merged
+//! lockfiles. Dependency groups count as declared only when the lock was
+//! written with them (some locked package records the group).
+
+use crate::lockfile::{Lockfile, MAIN_GROUP};
+use crate::pypi::normalize_project_name;
+use crate::resolver::Requirement;
+use crate::script_lock::{self, ScriptLockDrift};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How a lock entry disagrees with the project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// Declared but not locked.
+    Missing,
+    /// Locked at a version the declared requirement excludes.
+    Unsatisfied,
+    /// Locked but no longer required by anything declared.
+    Extra,
+    /// Locked at a version another locked package's requirement excludes.
+    Inconsistent,
+}
+
+/// One drift item, reported as one diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DriftItem {
+    pub kind: DriftKind,
+    pub package: String,
+    /// The requirement that is not met; `None` for extra packages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_version: Option<String>,
+    /// Locked package declaring `requirement`, for inconsistent entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_by: Option<String>,
+}
+
+impl DriftItem {
+    pub fn message(&self) -> String {
+        let requirement = self.requirement.as_deref().unwrap_or(&self.package);
+        let locked = self.locked_version.as_deref().unwrap_or("");
+        match self.kind {
+            DriftKind::Missing => format!("{requirement} is declared but not locked"),
+            DriftKind::Unsatisfied => {
+                format!(
+                    "{requirement} is declared but {} {locked} is locked",
+                    self.package
+                )
+            }
+            DriftKind::Extra => {
+                format!("{} {locked} is locked but no longer required", self.package)
+            }
+            DriftKind::Inconsistent => format!(
+                "{} requires {requirement} but {} {locked} is locked",
+                self.required_by.as_deref().unwrap_or("a locked package"),
+                self.package
+            ),
+        }
+    }
+}
+
+/// Result of checking a lockfile against the project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LockCheck {
+    /// Groups whose requirements were checked, [`MAIN_GROUP`] first.
+    pub groups: Vec<String>,
+    pub items: Vec<DriftItem>,
+}
+
+impl LockCheck {
+    pub fn is_clean(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// Compare `lock` with the project's `main` requirements and its
+/// `dependency_groups`.
+pub fn check(
+    lock: &Lockfile,
+    main: &[String],
+    dependency_groups: &BTreeMap<String, Vec<String>>,
+) -> LockCheck {
+    let locked_groups: BTreeSet<&str> = lock
+        .packages
+        .values()
+        .flat_map(|pkg| pkg.groups.iter().map(String::as_str))
+        .collect();
+    let mut groups = vec![MAIN_GROUP.to_string()];
+    let mut declared = main.to_vec();
+    for (group, requirements) in dependency_groups {
+        if group != MAIN_GROUP && locked_groups.contains(group.as_str()) {
+            groups.push(group.clone());
+            declared.extend(requirements.iter().cloned());
+        }
+    }
+
+    let ScriptLockDrift {
+        missing,
+        unsatisfied,
+        stale,
+    } = script_lock::check(lock, &declared);
+    let locked: BTreeMap<String, &crate::lockfile::Package> = lock
+        .packages
+        .values()
+        .map(|pkg| (normalize_project_name(&pkg.name), pkg))
+        .collect();
+    let version_of = |name: &str| {
+        locked
+            .get(&normalize_project_name(name))
+            .map(|pkg| pkg.version.clone())
+    };
+
+    let mut items = Vec::new();
+    for requirement in missing {
+        items.push(DriftItem {
+            kind: DriftKind::Missing,
+            package: requirement_name(&requirement),
+            requirement: Some(requirement),
+            locked_version: None,
+            required_by: None,
+        });
+    }
+    for entry in unsatisfied {
+        items.push(DriftItem {
+            kind: DriftKind::Unsatisfied,
+            package: requirement_name(&entry.requirement),
+            requirement: Some(entry.requirement),
+            locked_version: Some(entry.locked_version),
+            required_by: None,
+        });
+    }
+    for package in stale {
+        items.push(DriftItem {
+            kind: DriftKind::Extra,
+            locked_version: version_of(&package),
+            package,
+            requirement: None,
+            required_by: None,
+        });
+    }
+    for pkg in lock.packages.values() {
+        for spec in &pkg.dependencies {
+            let Ok(requirement) = spec.parse::<Requirement>() else {
+                continue;
+            };
+            if !requirement.marker_applies() {
+                continue;
+            }
+            let Some(dependency) = locked.get(&normalize_project_name(&requirement.name)) else {
+                continue;
+            };
+            if !requirement.is_satisfied_by(&dependency.version) {
+                items.push(DriftItem {
+                    kind: DriftKind::Inconsistent,
+                    package: dependency.name.clone(),
+                    requirement: Some(spec.trim().to_string()),
+                    locked_version: Some(dependency.version.clone()),
+                    required_by: Some(pkg.name.clone()),
+                });
+            }
+        }
+    }
+    LockCheck { groups, items }
+}
+
+fn requirement_name(spec: &str) -> String {
+    spec.parse::<Requirement>()
+        .map(|r| r.name)
+        .unwrap_or_else(|_| crate::project::extract_package_name(spec).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Package, PackageSource};
+
+    fn package(name: &str, version: &str, deps: &[&str], groups: &[&str]) -> Package {
+        Package {
+            name: name.into(),
+            version: version.into(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: format!("sha256:{}", "ab".repeat(32)),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+            build: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn lock(packages: Vec<Package>) -> Lockfile {
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        for pkg in packages {
+            lock.add_package(pkg);
+        }
+        lock
+    }
+
+    #[test]
+    fn locked_groups_are_checked_and_unlocked_groups_ignored() {
+        let lock = lock(vec![
+            package("requests", "2.32.0", &[], &["main"]),
+            package("pytest", "8.0.0", &[], &["dev"]),
+        ]);
+        let groups = BTreeMap::from([
+            ("dev".to_string(), vec!["pytest>=8".to_string()]),
+            ("docs".to_string(), vec!["sphinx".to_string()]),
+        ]);
+        let result = check(&lock, &["requests>=2.31".to_string()], &groups);
+        assert!(result.is_clean(), "{result:?}");
+        assert_eq!(result.groups, ["main", "dev"]);
+    }
+
+    #[test]
+    fn reports_missing_unsatisfied_extra_and_inconsistent_entries() {
+        let lock = lock(vec![
+            package("requests", "2.31.0", &["urllib3<2"], &[]),
+            package("urllib3", "2.2.0", &[], &[]),
+            package("click", "8.1.0", &[], &[]),
+        ]);
+        let result = check(
+            &lock,
+            &["requests>=2.32".to_string(), "rich".to_string()],
+            &BTreeMap::new(),
+        );
+        let kinds: Vec<(DriftKind, &str)> = result
+            .items
+            .iter()
+            .map(|item| (item.kind, item.package.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (DriftKind::Missing, "rich"),
+                (DriftKind::Unsatisfied, "requests"),
+                (DriftKind::Extra, "click"),
+                (DriftKind::Inconsistent, "urllib3"),
+            ]
+        );
+        assert_eq!(
+            result.items[3].message(),
+            "requests requires urllib3<2 but urllib3 2.2.0 is locked"
+        );
+        assert_eq!(
+            result.items[2].message(),
+            "click 8.1.0 is locked but no longer required"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Per-member shards of a workspace lockfile.
+//!
+//! Next to a workspace-wide lockfile (`pybun.lockb`), `pybun install` at the
+//! workspace root writes one lockfile per member into `pybun.lock.d/`. A
+//! shard pins exactly the packages the member's dependencies reach in the
+//! workspace resolution, so `pybun install --package NAME` can install one
+//! member without resolving or reading the whole workspace's packages.
+//!
+//! Shards are derived, never resolved: [`derive`] recomputes a member's shard
+//! from the workspace lock, and [`check`] reports every shard that no longer
+//! matches it.
+
+use crate::lockfile::{Lockfile, LockfileError, MAIN_GROUP};
+use crate::pypi::normalize_project_name;
+use crate::workspace::Workspace;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const SHARD_EXTENSION: &str = "lockb";
+
+#[derive(Debug, Error)]
+pub enum ShardError {
+    #[error("failed to read lock shard {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: LockfileError,
+    },
+    #[error("failed to write lock shard {path}: {source}")]
+    Write {
+        path: PathBuf,
+        source: LockfileError,
+    },
+    #[error("failed to update lock shards in {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, ShardError>;
+
+/// Directory holding the shards of `lock`: `pybun.lockb` → `pybun.lock.d`.
+pub fn shard_dir(lock: &Path) -> PathBuf {
+    lock.with_extension("lock.d")
+}
+
+/// Shard of `member` for the workspace lock `lock`.
+pub fn shard_path(lock: &Path, member: &str) -> PathBuf {
+    shard_dir(lock).join(format!(
+        "{}.{}",
+        normalize_project_name(member),
+        SHARD_EXTENSION
+    ))
+}
+
+/// The shard of `member`: the packages of `lock` that the member's
+/// dependencies, and those of the members it depends on, reach.
+pub fn derive(workspace: &Workspace, lock: &Lockfile, member: &str) -> Lockfile {
+    lock.subset(&BTreeMap::from([(
+        MAIN_GROUP.to_string(),
+        workspace.member_dependencies(member),
+    )]))
+}
+
+/// Write the shard of every member of `workspace` for `lock`, and remove
+/// shards of members that no longer exist. Returns the written paths.
+pub fn write_all(workspace: &Workspace, lock: &Lockfile, lock_path: &Path) -> Result<Vec<PathBuf>> {
+    let dir = shard_dir(lock_path);
+    std::fs::create_dir_all(&dir).map_err(|source| ShardError::Io {
+        path: dir.clone(),
+        source,
+    })?;
+    let mut written = Vec::new();
+    for member in workspace.member_names() {
+        let path = shard_path(lock_path, &member);
+        derive(workspace, lock, &member)
+            .save_to_path(&path)
+            .map_err(|source| ShardError::Write {
+                path: path.clone(),
+                source,
+            })?;
+        written.push(path);
+    }
+    for orphan in orphaned(workspace, lock_path) {
+        std::fs::remove_file(&orphan).map_err(|source| ShardError::Io {
+            path: orphan.clone(),
+            source,
+        })?;
+    }
+    Ok(written)
+}
+
+/// How a shard differs from what the workspace lock implies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// The member has no shard.
+    Missing,
+    /// The shard pins other packages, versions or artifacts.
+    Stale,
+    /// The shard belongs to no current member.
+    Orphaned,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShardDrift {
+    /// Member name, or the shard's file stem for orphaned shards.
+    pub member: String,
+    pub shard: PathBuf,
+    pub kind: DriftKind,
+    /// `name==version` pins the workspace lock implies but the shard lacks.
+    pub missing: Vec<String>,
+    /// Pins in the shard that the workspace lock does not imply.
+    pub unexpected: Vec<String>,
+}
+
+impl ShardDrift {
+    pub fn describe(&self) -> String {
+        match self.kind {
+            DriftKind::Missing => format!("{}: no shard at {}", self.member, self.shard.display()),
+            DriftKind::Orphaned => format!("{}: not a workspace member", self.shard.display()),
+            DriftKind::Stale if self.missing.is_empty() && self.unexpected.is_empty() => {
+                format!("{}: artifacts or targets differ", self.member)
+            }
+            DriftKind::Stale => {
+                let mut parts = Vec::new();
+                if !self.missing.is_empty() {
+                    parts.push(format!("missing {}", self.missing.join(", ")));
+                }
+                if !self.unexpected.is_empty() {
+                    parts.push(format!("unexpected {}", self.unexpected.join(", ")));
+                }
+                format!("{}: {}", self.member, parts.join("; "))
+            }
+        }
+    }
+}
+
+/// Shards of `members` (every member when empty) that do not match `lock`,
+/// plus shards left behind by removed members.
+pub fn check(
+    workspace: &Workspace,
+    lock: &Lockfile,
+    lock_path: &Path,
+    members: &[String],
+) -> Result<Vec<ShardDrift>> {
+    let all = members.is_empty();
+    let members = if all {
+        workspace.member_names()
+    } else {
+        members.to_vec()
+    };
+    let mut drift = Vec::new();
+    for member in members {
+        let path = shard_path(lock_path, &member);
+        let expected = derive(workspace, lock, &member);
+        if !path.is_file() {
+            drift.push(ShardDrift {
+                member,
+                shard: path,
+                kind: DriftKind::Missing,
+                missing: pins(&expected),
+                unexpected: Vec::new(),
+            });
+            continue;
+        }
+        let stored = Lockfile::load_from_path(&path).map_err(|source| ShardError::Read {
+            path: path.clone(),
+            source,
+        })?;
+        if stored != expected {
+            let expected_pins: BTreeSet<String> = pins(&expected).into_iter().collect();
+            let stored_pins: BTreeSet<String> = pins(&stored).into_iter().collect();
+            drift.push(ShardDrift {
+                member,
+                shard: path,
+                kind: DriftKind::Stale,
+                missing: expected_pins.difference(&stored_pins).cloned().collect(),
+                unexpected: stored_pins.difference(&expected_pins).cloned().collect(),
+            });
+        }
+    }
+    if all {
+        drift.extend(orphaned(workspace, lock_path).into_iter().map(|shard| {
+            ShardDrift {
+                member: shard
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                shard,
+                kind: DriftKind::Orphaned,
+                missing: Vec::new(),
+                unexpected: Vec::new(),
+            }
+        }));
+    }
+    Ok(drift)
+}
+
+fn pins(lock: &Lockfile) -> Vec<String> {
+    lock.packages
+        .values()
+        .map(|pkg| format!("{}=={}", pkg.name, pkg.version))
+        .collect()
+}
+
+/// Shard files in the shard directory that belong to no member.
+fn orphaned(workspace: &Workspace, lock_path: &Path) -> Vec<PathBuf> {
+    let current: BTreeSet<PathBuf> = workspace
+        .member_names()
+        .iter()
+        .map(|member| shard_path(lock_path, member))
+        .collect();
+    let Ok(entries) = std::fs::read_dir(shard_dir(lock_path)) else {
+        return Vec::new();
+    };
+    let mut orphans: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == SHARD_EXTENSION) && !current.contains(path)
+        })
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Package, PackageSource};
+    use crate::project::Project;
+    use std::fs;
+
+    fn package(name: &str, deps: &[&str]) -> Package {
+        Package {
+            name: name.into(),
+            version: "1.0.0".into(),
+            source: PackageSource::Url {
+                url: format!("https://example.invalid/{name}.whl"),
+            },
+            wheel: format!("{name}-1.0.0-py3-none-any.whl"),
+            hash: "sha256:abc".into(),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn workspace(root: &Path) -> Workspace {
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project]\nname = \"mono\"\n\n[tool.pybun.workspace]\nmembers = [\"svc-api\", \"svc-worker\", \"lib-core\"]\n",
+        )
+        .unwrap();
+        for (name, deps) in [
+            ("svc-api", "[\"fastapi\", \"lib-core\"]"),
+            ("svc-worker", "[\"celery\"]"),
+            ("lib-core", "[\"pydantic\"]"),
+        ] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            fs::write(
+                root.join(name).join("pyproject.toml"),
+                format!("[project]\nname = \"{name}\"\ndependencies = {deps}\n"),
+            )
+            .unwrap();
+        }
+        Workspace::from_root(Project::load(root.join("pyproject.toml")).unwrap()).unwrap()
+    }
+
+    fn workspace_lock() -> Lockfile {
+        let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+        lock.add_package(package("fastapi", &["starlette", "pydantic"]));
+        lock.add_package(package("starlette", &[]));
+        lock.add_package(package("pydantic", &[]));
+        lock.add_package(package("celery", &["kombu"]));
+        lock.add_package(package("kombu", &[]));
+        lock
+    }
+
+    #[test]
+    fn shards_follow_member_and_package_dependencies() {
+        let temp = tempfile::tempdir().unwrap();
+        let ws = workspace(temp.path());
+        let lock = workspace_lock();
+        let names = |member: &str| {
+            derive(&ws, &lock, member)
+                .packages
+                .into_keys()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("svc-api"), ["fastapi", "pydantic", "starlette"]);
+        assert_eq!(names("svc-worker"), ["celery", "kombu"]);
+        assert_eq!(names("lib-core"), ["pydantic"]);
+        assert_eq!(
+            shard_path(Path::new("/ws/pybun.lockb"), "Svc_Api"),
+            Path::new("/ws/pybun.lock.d/svc-api.lockb")
+        );
+    }
+
+    #[test]
+    fn check_reports_stale_missing_and_orphaned_shards() {
+        let temp = tempfile::tempdir().unwrap();
+        let ws = workspace(temp.path());
+        let lock_path = temp.path().join("pybun.lockb");
+        let mut lock = workspace_lock();
+        write_all(&ws, &lock, &lock_path).unwrap();
+        assert!(check(&ws, &lock, &lock_path, &[]).unwrap().is_empty());
+
+        // The workspace re-resolved kombu; the worker shard is now stale.
+        lock.packages.get_mut("kombu").unwrap().version = "2.0.0".into();
+        fs::remove_file(shard_path(&lock_path, "lib-core")).unwrap();
+        fs::write(shard_dir(&lock_path).join("old-svc.lockb"), b"").unwrap();
+
+        let drift = check(&ws, &lock, &lock_path, &[]).unwrap();
+        let kinds: Vec<_> = drift.iter().map(|d| (d.member.as_str(), &d.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("svc-worker", &DriftKind::Stale),
+                ("lib-core", &DriftKind::Missing),
+                ("old-svc", &DriftKind::Orphaned),
+            ]
+        );
+        assert_eq!(drift[0].missing, ["kombu==2.0.0"]);
+        assert_eq!(drift[0].unexpected, ["kombu==1.0.0"]);
+
+        // Checking one member ignores the others.
+        let api = check(&ws, &lock, &lock_path, &["svc-api".to_string()]).unwrap();
+        assert!(api.is_empty());
+
+        write_all(&ws, &lock, &lock_path).unwrap();
+        assert!(check(&ws, &lock, &lock_path, &[]).unwrap().is_empty());
+        assert!(!shard_dir(&lock_path).join("old-svc.lockb").exists());
+    }
+}
@@ -0,0 +1,505 @@
+//! `pybun lock --update-only NAME==VERSION`: move one locked package to a
+//! new version without re-resolving the rest of the lock.
+//!
+//! The dependencies of the requested version are checked against the lock.
+//! A dependency whose locked version no longer fits moves to the closest
+//! version that does (the lowest newer one, else the highest older one); a
+//! new dependency gets the highest version every constraint allows; moved
+//! packages are checked the same way in turn. Packages nothing needs any
+//! more are dropped. Every other package keeps its version: when one of
+//! them, or pyproject.toml, rules out a moved package's new version, the
+//! update is refused with the conflicting requirements instead of widening
+//! to a full resolve.
+
+use crate::change_diff::ChangeKind;
+use crate::lockfile::Lockfile;
+use crate::pypi::normalize_project_name;
+use crate::resolver::{
+    PackageIndex, Requirement, ResolvedPackage, VersionSpec, compare_versions, is_prerelease,
+    requires_python_allows,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum UpdateError {
+    #[error("invalid --update-only value '{0}'; expected NAME==VERSION")]
+    InvalidSpec(String),
+    #[error("{0} is not in the lockfile; add it with `pybun add`")]
+    NotLocked(String),
+    #[error("{name} {version} is not on the index")]
+    NotFound { name: String, version: String },
+    #[error("cannot update {name} to {version}: {}", .conflicts.join("; "))]
+    Conflict {
+        name: String,
+        version: String,
+        conflicts: Vec<String>,
+    },
+    #[error("index lookup failed: {0}")]
+    Index(String),
+}
+
+pub type Result<T> = std::result::Result<T, UpdateError>;
+
+/// One entry of the changeset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageChange {
+    pub name: String,
+    pub change: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Why the package changed: `requested`, `required by NAME VERSION
+    /// (REQUIREMENT)`, `new dependency of NAME VERSION`, or `no longer
+    /// required`.
+    pub reason: String,
+}
+
+/// What an update changes in the lock.
+#[derive(Debug, Default)]
+pub struct UpdatePlan {
+    /// Sorted by name; empty when the package is already at the version.
+    pub changes: Vec<PackageChange>,
+    /// The added and changed packages, to lock in place of the old ones.
+    pub packages: Vec<ResolvedPackage>,
+    /// Lockfile keys of the packages to drop.
+    pub removed: Vec<String>,
+}
+
+/// A package as the update sees it: locked, or moved by the update.
+struct Node {
+    name: String,
+    version: String,
+    dependencies: Vec<Requirement>,
+}
+
+/// A package the update moved, why, and how often.
+struct Moved {
+    pkg: ResolvedPackage,
+    reason: String,
+    times: usize,
+}
+
+fn move_to(
+    state: &mut BTreeMap<String, Node>,
+    moved: &mut BTreeMap<String, Moved>,
+    pkg: ResolvedPackage,
+    reason: String,
+) -> String {
+    let name = normalize_project_name(&pkg.name);
+    state.insert(
+        name.clone(),
+        Node {
+            name: pkg.name.clone(),
+            version: pkg.version.clone(),
+            dependencies: pkg.dependencies.clone(),
+        },
+    );
+    let times = moved.get(&name).map_or(0, |m| m.times) + 1;
+    moved.insert(name.clone(), Moved { pkg, reason, times });
+    name
+}
+
+/// How often one package may move before the update gives up; constraints
+/// only tighten, so needing more means they cannot all be met.
+const MAX_MOVES: usize = 3;
+
+/// Plan moving the package `spec` (`NAME==VERSION`) names to that version.
+/// `roots` are the project's declared requirements (every group), and
+/// `python_versions` the versions the lock targets.
+pub async fn plan(
+    lock: &Lockfile,
+    roots: &[String],
+    spec: &str,
+    python_versions: &[String],
+    index: &impl PackageIndex,
+) -> Result<UpdatePlan> {
+    let invalid = || UpdateError::InvalidSpec(spec.to_string());
+    let requirement: Requirement = spec.parse().map_err(|_| invalid())?;
+    let version = match requirement.specs.as_slice() {
+        [VersionSpec::Exact(version)] if requirement.url.is_none() => version.clone(),
+        _ => return Err(invalid()),
+    };
+    let target = normalize_project_name(&requirement.name);
+
+    let parse = |spec: &String| {
+        spec.parse::<Requirement>()
+            .unwrap_or_else(|_| Requirement::any(crate::project::extract_package_name(spec)))
+    };
+    let locked: BTreeMap<String, (&String, Node)> = lock
+        .packages
+        .iter()
+        .map(|(key, pkg)| {
+            let node = Node {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                dependencies: pkg.dependencies.iter().map(parse).collect(),
+            };
+            (normalize_project_name(key), (key, node))
+        })
+        .collect();
+    let Some((_, current)) = locked.get(&target) else {
+        return Err(UpdateError::NotLocked(requirement.name));
+    };
+    if current.version == version {
+        return Ok(UpdatePlan::default());
+    }
+    let roots: Vec<Requirement> = roots.iter().map(parse).collect();
+
+    let requested = index
+        .get(&current.name, &version)
+        .await
+        .map_err(|e| UpdateError::Index(e.to_string()))?
+        .ok_or_else(|| UpdateError::NotFound {
+            name: current.name.clone(),
+            version: version.clone(),
+        })?;
+    let conflict = |conflicts: Vec<String>| UpdateError::Conflict {
+        name: current.name.clone(),
+        version: version.clone(),
+        conflicts,
+    };
+    if let Some(excluded) = excluded_python(&requested, python_versions) {
+        return Err(conflict(vec![format!(
+            "it requires Python {}, excluding {excluded}",
+            requested.requires_python.as_deref().unwrap_or_default()
+        )]));
+    }
+
+    let mut state: BTreeMap<String, Node> = BTreeMap::new();
+    for (name, (_, node)) in &locked {
+        state.insert(
+            name.clone(),
+            Node {
+                name: node.name.clone(),
+                version: node.version.clone(),
+                dependencies: node.dependencies.clone(),
+            },
+        );
+    }
+    let mut moved: BTreeMap<String, Moved> = BTreeMap::new();
+    let mut pending = vec![move_to(
+        &mut state,
+        &mut moved,
+        requested,
+        "requested".to_string(),
+    )];
+    let mut conflicts = Vec::new();
+    while let Some(name) = pending.pop() {
+        let (parent, parent_version) = (state[&name].name.clone(), state[&name].version.clone());
+        let dependencies: Vec<Requirement> = state[&name]
+            .dependencies
+            .iter()
+            .filter(|dep| dep.marker_applies())
+            .cloned()
+            .collect();
+        for dep in dependencies {
+            let dep_name = normalize_project_name(&dep.name);
+            let current = state.get(&dep_name).map(|node| node.version.clone());
+            if current.as_deref().is_some_and(|v| dep.is_satisfied_by(v)) {
+                continue;
+            }
+            if dep_name == target || moved.get(&dep_name).is_some_and(|m| m.times >= MAX_MOVES) {
+                conflicts.push(format!(
+                    "{parent} {parent_version} requires {dep}, not {}",
+                    current.unwrap_or_default()
+                ));
+                continue;
+            }
+
+            let constraints: Vec<&Requirement> = state
+                .values()
+                .flat_map(|node| &node.dependencies)
+                .chain(&roots)
+                .filter(|r| r.marker_applies() && normalize_project_name(&r.name) == dep_name)
+                .collect();
+            let candidates = index
+                .all(&dep.name)
+                .await
+                .map_err(|e| UpdateError::Index(e.to_string()))?;
+            let mut fitting: Vec<ResolvedPackage> = candidates
+                .into_iter()
+                .filter(|pkg| constraints.iter().all(|r| r.is_satisfied_by(&pkg.version)))
+                .filter(|pkg| {
+                    !is_prerelease(&pkg.version) || current.as_deref().is_some_and(is_prerelease)
+                })
+                .filter(|pkg| excluded_python(pkg, python_versions).is_none())
+                .collect();
+            fitting.sort_by(|a, b| compare_versions(&a.version, &b.version));
+            let closest = match &current {
+                // The smallest move up, else the smallest move down.
+                Some(current) => {
+                    let up = fitting
+                        .iter()
+                        .position(|pkg| compare_versions(&pkg.version, current).is_gt());
+                    match up {
+                        Some(at) => Some(fitting.swap_remove(at)),
+                        None => fitting.pop(),
+                    }
+                }
+                None => fitting.pop(),
+            };
+            let Some(pkg) = closest else {
+                let wanted: Vec<String> = constraints.iter().map(|r| r.to_string()).collect();
+                conflicts.push(format!(
+                    "no version of {} satisfies {}",
+                    dep.name,
+                    wanted.join(", ")
+                ));
+                continue;
+            };
+            let reason = match current {
+                Some(_) => format!("required by {parent} {parent_version} ({dep})"),
+                None => format!("new dependency of {parent} {parent_version}"),
+            };
+            pending.push(move_to(&mut state, &mut moved, pkg, reason));
+        }
+    }
+
+    // Packages and pyproject.toml requirements that stay as they are must
+    // still accept every moved version.
+    for (name, node) in &state {
+        for dep in node.dependencies.iter().filter(|dep| dep.marker_applies()) {
+            let dep_name = normalize_project_name(&dep.name);
+            if name == &dep_name || !moved.contains_key(&dep_name) {
+                continue;
+            }
+            let version = &state[&dep_name].version;
+            if !dep.is_satisfied_by(version) {
+                conflicts.push(format!(
+                    "{} {} requires {dep}, not {version}",
+                    node.name, node.version
+                ));
+            }
+        }
+    }
+    for root in roots.iter().filter(|r| r.marker_applies()) {
+        let root_name = normalize_project_name(&root.name);
+        if let Some(node) = state
+            .get(&root_name)
+            .filter(|node| moved.contains_key(&root_name) && !root.is_satisfied_by(&node.version))
+        {
+            conflicts.push(format!(
+                "pyproject.toml requires {root}, not {}",
+                node.version
+            ));
+        }
+    }
+    if !conflicts.is_empty() {
+        conflicts.sort();
+        conflicts.dedup();
+        return Err(conflict(conflicts));
+    }
+
+    // Without declared requirements nothing is known to be unused; packages
+    // no root reached before the update are left alone either way.
+    let before = reachable(locked.iter().map(|(name, (_, node))| (name, node)), &roots);
+    let after = reachable(state.iter(), &roots);
+    let dropped = |name: &String| {
+        !roots.is_empty()
+            && !after.contains(name)
+            && (before.contains(name) || !locked.contains_key(name))
+    };
+    let mut plan = UpdatePlan::default();
+    for name in before.difference(&after) {
+        if let Some((key, node)) = locked.get(name) {
+            plan.removed.push((*key).clone());
+            plan.changes.push(PackageChange {
+                name: node.name.clone(),
+                change: ChangeKind::Removed,
+                before: Some(node.version.clone()),
+                after: None,
+                reason: "no longer required".to_string(),
+            });
+        }
+    }
+    for (name, Moved { pkg, reason, .. }) in moved {
+        if dropped(&name) {
+            continue;
+        }
+        let before = locked.get(&name).map(|(_, node)| node.version.clone());
+        if before.as_deref() == Some(pkg.version.as_str()) {
+            continue;
+        }
+        plan.changes.push(PackageChange {
+            name: pkg.name.clone(),
+            change: if before.is_some() {
+                ChangeKind::Changed
+            } else {
+                ChangeKind::Added
+            },
+            before,
+            after: Some(pkg.version.clone()),
+            reason,
+        });
+        plan.packages.push(pkg);
+    }
+    plan.changes
+        .sort_by_key(|change| normalize_project_name(&change.name));
+    Ok(plan)
+}
+
+/// The first of `python_versions` `pkg`'s `requires-python` excludes.
+fn excluded_python(pkg: &ResolvedPackage, python_versions: &[String]) -> Option<String> {
+    let requires_python = pkg.requires_python.as_deref()?;
+    python_versions
+        .iter()
+        .find(|version| !requires_python_allows(requires_python, version))
+        .cloned()
+}
+
+/// Normalized names of the packages `roots` reach through `nodes`, markers
+/// ignored so packages for other targets stay.
+fn reachable<'a>(
+    nodes: impl Iterator<Item = (&'a String, &'a Node)>,
+    roots: &[Requirement],
+) -> BTreeSet<String> {
+    let nodes: BTreeMap<&String, &Node> = nodes.collect();
+    let mut seen = BTreeSet::new();
+    let mut pending: Vec<String> = roots
+        .iter()
+        .map(|r| normalize_project_name(&r.name))
+        .collect();
+    while let Some(name) = pending.pop() {
+        let Some(node) = nodes.get(&name) else {
+            continue;
+        };
+        if seen.insert(name) {
+            pending.extend(
+                node.dependencies
+                    .iter()
+                    .map(|dep| normalize_project_name(&dep.name)),
+            );
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Package, PackageSource};
+    use crate::resolver::InMemoryIndex;
+
+    fn locked(name: &str, version: &str, dependencies: &[&str]) -> Package {
+        Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            source: PackageSource::Registry {
+                index: "pypi".to_string(),
+                url: "https://pypi.org/simple".to_string(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: "sha256:00".to_string(),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            build: None,
+            artifacts: Vec::new(),
+            groups: vec!["main".to_string()],
+        }
+    }
+
+    fn index() -> InMemoryIndex {
+        let mut index = InMemoryIndex::default();
+        index.add("web", "1.0", ["core>=1.0", "legacy"]);
+        index.add("web", "2.0", ["core>=1.2", "fresh"]);
+        index.add("core", "1.0", Vec::<&str>::new());
+        index.add("core", "1.2", Vec::<&str>::new());
+        index.add("core", "1.3", Vec::<&str>::new());
+        index.add("core", "2.0", Vec::<&str>::new());
+        index.add("legacy", "1.0", Vec::<&str>::new());
+        index.add("fresh", "0.9", Vec::<&str>::new());
+        index.add("fresh", "1.0", Vec::<&str>::new());
+        index.add("plugin", "1.0", ["core<2"]);
+        index
+    }
+
+    fn lock() -> Lockfile {
+        let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+        lock.add_package(locked("web", "1.0", &["core>=1.0", "legacy"]));
+        lock.add_package(locked("core", "1.0", &[]));
+        lock.add_package(locked("legacy", "1.0", &[]));
+        lock.add_package(locked("plugin", "1.0", &["core<2"]));
+        lock
+    }
+
+    #[tokio::test]
+    async fn moves_the_package_and_only_what_it_needs() {
+        let roots = ["web".to_string(), "plugin".to_string()];
+        let planned = plan(&lock(), &roots, "web==2.0", &["3.12".into()], &index())
+            .await
+            .unwrap();
+        let summary: Vec<(&str, ChangeKind, Option<&str>, &str)> = planned
+            .changes
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.change,
+                    c.after.as_deref(),
+                    c.reason.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "core",
+                    ChangeKind::Changed,
+                    Some("1.2"),
+                    "required by web 2.0 (core>=1.2)"
+                ),
+                (
+                    "fresh",
+                    ChangeKind::Added,
+                    Some("1.0"),
+                    "new dependency of web 2.0"
+                ),
+                ("legacy", ChangeKind::Removed, None, "no longer required"),
+                ("web", ChangeKind::Changed, Some("2.0"), "requested"),
+            ]
+        );
+        assert_eq!(planned.removed, ["legacy"]);
+        assert_eq!(planned.packages.len(), 3);
+
+        // Already there: nothing to do.
+        let planned = plan(&lock(), &roots, "web==1.0", &["3.12".into()], &index())
+            .await
+            .unwrap();
+        assert!(planned.changes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_versions_the_rest_of_the_lock_rules_out() {
+        let roots = ["web".to_string(), "plugin".to_string()];
+        let err = plan(&lock(), &roots, "core==2.0", &["3.12".into()], &index())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot update core to 2.0: plugin 1.0 requires core<2, not 2.0"
+        );
+
+        let roots = ["web".to_string(), "core<1.3".to_string()];
+        let err = plan(&lock(), &roots, "core==1.3", &["3.12".into()], &index())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("pyproject.toml requires core<1.3"));
+
+        assert!(matches!(
+            plan(&lock(), &roots, "web>=2", &[], &index()).await,
+            Err(UpdateError::InvalidSpec(_))
+        ));
+        assert!(matches!(
+            plan(&lock(), &roots, "other==1.0", &[], &index()).await,
+            Err(UpdateError::NotLocked(_))
+        ));
+        assert!(matches!(
+            plan(&lock(), &roots, "web==9.0", &[], &index()).await,
+            Err(UpdateError::NotFound { .. })
+        ));
+    }
+}
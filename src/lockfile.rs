@@ -1,11 +1,25 @@
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::Path;
 use thiserror::Error;
 
 const MAGIC: &[u8; 8] = b"PYBUNLK1";
-const VERSION: u32 = 1;
+const VERSION: u32 = 7;
+/// Lockfiles written before container-build acknowledgements were recorded.
+const VERSION_6: u32 = 6;
+/// Lockfiles written before git sources were recorded. Their encoding is
+/// the current one; the bump only keeps older PyBun releases from
+/// misreading [`PackageSource::Git`].
+const VERSION_5: u32 = 5;
+/// Lockfiles written before dependency groups were recorded.
+const VERSION_4: u32 = 4;
+/// Lockfiles written before per-package artifacts were recorded.
+const VERSION_3: u32 = 3;
+/// Lockfiles written before source builds were recorded.
+const VERSION_2: u32 = 2;
+/// Lockfiles written before `dynamic_metadata` was recorded.
+const VERSION_1: u32 = 1;
 
 #[derive(Debug, Error)]
 pub enum LockfileError {
@@ -26,6 +40,11 @@ pub struct Lockfile {
     pub python_versions: Vec<String>,
     pub platforms: Vec<String>,
     pub packages: BTreeMap<String, Package>,
+    /// Packages (PEP 503 normalized) whose sdists are only built in a
+    /// container: suspicious dependencies let through with
+    /// `--allow-suspicious` (see [`crate::suspicious`]).
+    #[serde(default)]
+    pub container_builds: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -36,12 +55,297 @@ pub struct Package {
     pub wheel: String,
     pub hash: String,
     pub dependencies: Vec<String>,
+    /// Dependencies were computed by the sdist's build backend rather than
+    /// read from static metadata, so they may differ across platforms or
+    /// interpreters.
+    #[serde(default)]
+    pub dynamic_metadata: bool,
+    /// Set when no compatible wheel exists and the package is built from its
+    /// sdist; `wheel` and `hash` then name the sdist.
+    #[serde(default)]
+    pub build: Option<SourceBuild>,
+    /// Every file needed to install the package on the locked targets:
+    /// the wheel selected for each Python/platform combination, plus the
+    /// sdist when a target has to build from source. `wheel` and `hash`
+    /// name the artifact of the first target.
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+    /// Dependency groups that pull the package in, directly or
+    /// transitively: [`MAIN_GROUP`] for `[project.dependencies]`, otherwise
+    /// the name of an optional-dependencies extra or PEP 735 group.
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+/// Group name recorded for packages required by `[project.dependencies]`.
+pub const MAIN_GROUP: &str = "main";
+
+/// A downloadable file of a locked package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub filename: String,
+    pub url: Option<String>,
+    pub hash: String,
+    /// Platform tags the index reported for a wheel; empty for an sdist.
+    pub platforms: Vec<String>,
+}
+
+impl Artifact {
+    pub fn is_wheel(&self) -> bool {
+        self.filename.ends_with(".whl")
+    }
+}
+
+/// Inputs of a wheel built from an sdist (PEP 517), recorded so a later
+/// install can tell whether a cached build still applies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceBuild {
+    /// PEP 517 backend, e.g. `setuptools.build_meta`.
+    pub backend: String,
+    /// Build requirements: `[build-system].requires` plus whatever
+    /// `get_requires_for_build_wheel` asked for.
+    pub requires: Vec<String>,
+    /// Interpreter tag the wheel was built for (e.g. `cp312`).
+    pub python: String,
+    /// Platform tag of the build host.
+    pub platform: String,
+    /// Filename and hash of the built wheel.
+    pub wheel: String,
+    pub wheel_hash: String,
+}
+
+#[derive(Deserialize)]
+struct LockfileV1 {
+    python_versions: Vec<String>,
+    platforms: Vec<String>,
+    packages: BTreeMap<String, PackageV1>,
+}
+
+#[derive(Deserialize)]
+struct PackageV1 {
+    name: String,
+    version: String,
+    source: PackageSource,
+    wheel: String,
+    hash: String,
+    dependencies: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LockfileV6 {
+    python_versions: Vec<String>,
+    platforms: Vec<String>,
+    packages: BTreeMap<String, Package>,
+}
+
+impl From<LockfileV6> for Lockfile {
+    fn from(v6: LockfileV6) -> Self {
+        Self {
+            python_versions: v6.python_versions,
+            platforms: v6.platforms,
+            packages: v6.packages,
+            container_builds: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LockfileV4 {
+    python_versions: Vec<String>,
+    platforms: Vec<String>,
+    packages: BTreeMap<String, PackageV4>,
+}
+
+#[derive(Deserialize)]
+struct PackageV4 {
+    name: String,
+    version: String,
+    source: PackageSource,
+    wheel: String,
+    hash: String,
+    dependencies: Vec<String>,
+    dynamic_metadata: bool,
+    build: Option<SourceBuild>,
+    artifacts: Vec<Artifact>,
+}
+
+impl From<LockfileV4> for Lockfile {
+    fn from(v4: LockfileV4) -> Self {
+        Self {
+            python_versions: v4.python_versions,
+            platforms: v4.platforms,
+            packages: v4
+                .packages
+                .into_iter()
+                .map(|(key, pkg)| {
+                    (
+                        key,
+                        Package {
+                            name: pkg.name,
+                            version: pkg.version,
+                            source: pkg.source,
+                            wheel: pkg.wheel,
+                            hash: pkg.hash,
+                            dependencies: pkg.dependencies,
+                            dynamic_metadata: pkg.dynamic_metadata,
+                            build: pkg.build,
+                            artifacts: pkg.artifacts,
+                            groups: Vec::new(),
+                        },
+                    )
+                })
+                .collect(),
+            container_builds: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LockfileV3 {
+    python_versions: Vec<String>,
+    platforms: Vec<String>,
+    packages: BTreeMap<String, PackageV3>,
+}
+
+#[derive(Deserialize)]
+struct PackageV3 {
+    name: String,
+    version: String,
+    source: PackageSource,
+    wheel: String,
+    hash: String,
+    dependencies: Vec<String>,
+    dynamic_metadata: bool,
+    build: Option<SourceBuild>,
+}
+
+impl From<LockfileV3> for Lockfile {
+    fn from(v3: LockfileV3) -> Self {
+        Self {
+            python_versions: v3.python_versions,
+            platforms: v3.platforms,
+            packages: v3
+                .packages
+                .into_iter()
+                .map(|(key, pkg)| {
+                    (
+                        key,
+                        Package {
+                            name: pkg.name,
+                            version: pkg.version,
+                            source: pkg.source,
+                            wheel: pkg.wheel,
+                            hash: pkg.hash,
+                            dependencies: pkg.dependencies,
+                            dynamic_metadata: pkg.dynamic_metadata,
+                            build: pkg.build,
+                            artifacts: Vec::new(),
+                            groups: Vec::new(),
+                        },
+                    )
+                })
+                .collect(),
+            container_builds: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LockfileV2 {
+    python_versions: Vec<String>,
+    platforms: Vec<String>,
+    packages: BTreeMap<String, PackageV2>,
+}
+
+#[derive(Deserialize)]
+struct PackageV2 {
+    name: String,
+    version: String,
+    source: PackageSource,
+    wheel: String,
+    hash: String,
+    dependencies: Vec<String>,
+    dynamic_metadata: bool,
+}
+
+impl From<LockfileV2> for Lockfile {
+    fn from(v2: LockfileV2) -> Self {
+        Self {
+            python_versions: v2.python_versions,
+            platforms: v2.platforms,
+            packages: v2
+                .packages
+                .into_iter()
+                .map(|(key, pkg)| {
+                    (
+                        key,
+                        Package {
+                            name: pkg.name,
+                            version: pkg.version,
+                            source: pkg.source,
+                            wheel: pkg.wheel,
+                            hash: pkg.hash,
+                            dependencies: pkg.dependencies,
+                            dynamic_metadata: pkg.dynamic_metadata,
+                            build: None,
+                            artifacts: Vec::new(),
+                            groups: Vec::new(),
+                        },
+                    )
+                })
+                .collect(),
+            container_builds: Vec::new(),
+        }
+    }
+}
+
+impl From<LockfileV1> for Lockfile {
+    fn from(v1: LockfileV1) -> Self {
+        Self {
+            python_versions: v1.python_versions,
+            platforms: v1.platforms,
+            packages: v1
+                .packages
+                .into_iter()
+                .map(|(key, pkg)| {
+                    (
+                        key,
+                        Package {
+                            name: pkg.name,
+                            version: pkg.version,
+                            source: pkg.source,
+                            wheel: pkg.wheel,
+                            hash: pkg.hash,
+                            dependencies: pkg.dependencies,
+                            dynamic_metadata: false,
+                            build: None,
+                            artifacts: Vec::new(),
+                            groups: Vec::new(),
+                        },
+                    )
+                })
+                .collect(),
+            container_builds: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PackageSource {
-    Registry { index: String, url: String },
-    Url { url: String },
+    Registry {
+        index: String,
+        url: String,
+    },
+    Url {
+        url: String,
+    },
+    /// A `git+` direct reference, pinned to the commit its ref resolved to.
+    Git {
+        url: String,
+        reference: Option<String>,
+        subdirectory: Option<String>,
+        commit: String,
+    },
 }
 
 impl PackageSource {
@@ -49,6 +353,7 @@ impl PackageSource {
         match self {
             PackageSource::Registry { url, .. } => Some(url), // This is index URL, NOT file URL!
             PackageSource::Url { url } => Some(url),
+            PackageSource::Git { url, .. } => Some(url),
         }
     }
 }
@@ -59,6 +364,7 @@ impl Lockfile {
             python_versions,
             platforms,
             packages: BTreeMap::new(),
+            container_builds: Vec::new(),
         }
     }
 
@@ -89,12 +395,87 @@ impl Lockfile {
             bytes[version_start + 2],
             bytes[version_start + 3],
         ]);
-        if version != VERSION {
-            return Err(LockfileError::UnsupportedVersion(version));
-        }
         let body = &bytes[version_start + 4..];
-        let parsed: Lockfile = bincode::deserialize(body)?;
-        Ok(parsed)
+        match version {
+            VERSION => Ok(bincode::deserialize(body)?),
+            VERSION_6 | VERSION_5 => Ok(bincode::deserialize::<LockfileV6>(body)?.into()),
+            VERSION_4 => Ok(bincode::deserialize::<LockfileV4>(body)?.into()),
+            VERSION_3 => Ok(bincode::deserialize::<LockfileV3>(body)?.into()),
+            VERSION_2 => Ok(bincode::deserialize::<LockfileV2>(body)?.into()),
+            VERSION_1 => Ok(bincode::deserialize::<LockfileV1>(body)?.into()),
+            other => Err(LockfileError::UnsupportedVersion(other)),
+        }
+    }
+
+    /// Names of packages whose dependencies came from dynamic sdist metadata.
+    pub fn dynamic_metadata_packages(&self) -> Vec<&str> {
+        self.packages
+            .values()
+            .filter(|pkg| pkg.dynamic_metadata)
+            .map(|pkg| pkg.name.as_str())
+            .collect()
+    }
+
+    /// Record in each package's `groups` which of `roots` reach it. `roots`
+    /// maps a group name to the requirements it declares; a package belongs
+    /// to a group when one of those requirements, or anything they depend
+    /// on, names it.
+    pub fn assign_groups(&mut self, roots: &BTreeMap<String, Vec<String>>) {
+        let keys: BTreeMap<String, String> = self
+            .packages
+            .keys()
+            .map(|key| (crate::pypi::normalize_project_name(key), key.clone()))
+            .collect();
+        let lookup = |dep: &str| {
+            keys.get(&crate::pypi::normalize_project_name(
+                crate::project::extract_package_name(dep),
+            ))
+            .cloned()
+        };
+
+        let mut groups: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (group, requirements) in roots {
+            let mut pending: Vec<String> = requirements.iter().filter_map(|r| lookup(r)).collect();
+            let mut seen = BTreeSet::new();
+            while let Some(key) = pending.pop() {
+                if !seen.insert(key.clone()) {
+                    continue;
+                }
+                groups.entry(key.clone()).or_default().insert(group.clone());
+                pending.extend(
+                    self.packages[&key]
+                        .dependencies
+                        .iter()
+                        .filter_map(|dep| lookup(dep)),
+                );
+            }
+        }
+        for (key, pkg) in &mut self.packages {
+            pkg.groups = groups
+                .remove(key)
+                .map(|set| set.into_iter().collect())
+                .unwrap_or_default();
+        }
+    }
+
+    /// The packages `roots` reach, with their groups recomputed from
+    /// `roots` alone (see [`Lockfile::assign_groups`]).
+    pub fn subset(&self, roots: &BTreeMap<String, Vec<String>>) -> Lockfile {
+        let mut subset = self.clone();
+        subset.assign_groups(roots);
+        subset.packages.retain(|_, pkg| !pkg.groups.is_empty());
+        subset
+    }
+
+    /// Whether `pkg` is needed when installing `selected` groups on top of
+    /// [`MAIN_GROUP`]. Packages without recorded groups (older lockfiles)
+    /// are always needed.
+    pub fn in_groups(pkg: &Package, selected: &[&str]) -> bool {
+        pkg.groups.is_empty()
+            || pkg
+                .groups
+                .iter()
+                .any(|group| group == MAIN_GROUP || selected.contains(&group.as_str()))
     }
 
     pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
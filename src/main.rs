@@ -6,13 +6,43 @@ fn main() -> Result<()> {
     // Clap intercepts `--help`/`-h` and prints plain text before normal command
     // dispatch runs, which breaks `--format=json --help` for agents that need
     // machine-readable help. Detect that combination up front and short-circuit.
-    let raw_args: Vec<String> = std::env::args().skip(1).collect();
+    let mut argv = std::env::args();
+    let program = argv.next().unwrap_or_else(|| "pybun".to_string());
+    // Expand project aliases (`[tool.pybun.alias]`) before clap sees the
+    // arguments so aliased commands get normal parsing and help.
+    let args: Vec<String> = argv.collect();
+    let raw_args = match pybun::alias::expand_cli_args(args.clone()) {
+        Ok(args) => args,
+        Err(e) => {
+            match pybun::cli::alias_error_envelope(&args, &e) {
+                Some(envelope) => println!("{envelope}"),
+                None => eprintln!("error: {e}"),
+            }
+            std::process::exit(2);
+        }
+    };
     if let Some(envelope) = pybun::cli::json_help_envelope(&raw_args) {
         println!("{envelope}");
         return Ok(());
     }
 
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(std::iter::once(program).chain(raw_args.iter().cloned()));
+
+    // `--max-duration` re-runs the command as a child operation and waits
+    // for it here. The child (marked by PYBUN_OPERATION_ID) runs normally.
+    if let Some(budget) = cli.max_duration
+        && pybun::operation::current_id().is_none()
+    {
+        let supervisor = pybun::operation::Supervisor {
+            args: raw_args,
+            budget,
+            on_timeout: cli.on_timeout,
+            format: cli.format,
+        };
+        let store = pybun::operation::OperationStore::from_env()?;
+        let code = supervisor.run(&store)?;
+        std::process::exit(code);
+    }
     if entry::should_install_color_eyre(&cli) {
         color_eyre::install()?;
     }
@@ -11,15 +11,28 @@
 //! - `tools/list`: List available tools
 //! - `tools/call`: Call a tool
 //! - `resources/list`: List available resources
+//! - `resources/read`: Read a resource
+//! - `resources/subscribe` / `resources/unsubscribe`: Change notifications
+//!   for the project-state resources, see [`crate::mcp_resources`]
+//! - `notifications/cancelled`: Stop an in-flight tool call; progress is
+//!   reported with `notifications/progress`, see [`crate::mcp_progress`]
 //! - `shutdown`: Shutdown the server
 //!
+//! ## Transports
+//! - stdio (`--stdio`): one JSON-RPC message per line
+//! - HTTP (`--port`): streamable HTTP and HTTP + SSE, see [`crate::mcp_http`]
+//!
 //! ## Tools
 //! - `pybun_resolve`: Resolve dependencies
 //! - `pybun_install`: Install packages
 //! - `pybun_run`: Run Python scripts
 //! - `pybun_gc`: Run garbage collection
 //! - `pybun_doctor`: Run environment diagnostics
+//! - `pybun_add`, `pybun_remove`, `pybun_python_install`, `pybun_python_list`,
+//!   `pybun_module_find`: run the CLI command and return its JSON envelope
 
+use crate::mcp_progress::{Cancellation, Cancellations, ProgressReporter};
+use crate::mcp_resources::{self, Subscriptions};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::{BTreeMap, VecDeque};
@@ -75,6 +88,10 @@ pub struct Tool {
     pub description: String,
     #[serde(rename = "inputSchema")]
     pub input_schema: Value,
+    /// Schema of the JSON result; successful calls also return it as
+    /// `structuredContent`.
+    #[serde(rename = "outputSchema", skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
 }
 
 /// MCP Resource definition
@@ -610,7 +627,7 @@ fn path_within(path: &Path, root: &Path) -> bool {
     path == root || path.starts_with(root)
 }
 
-fn utc_date_now() -> String {
+pub(crate) fn utc_date_now() -> String {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default();
@@ -634,7 +651,23 @@ fn utc_timestamp_now() -> String {
     format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{micros:06}Z")
 }
 
-fn civil_from_unix_days(days_since_epoch: i64) -> (i32, u32, u32) {
+/// Current UTC time as `YYYY-MM-DDThh:mm:ssZ` (no fractional seconds).
+pub(crate) fn utc_timestamp_seconds_now() -> String {
+    let total_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_unix_days((total_secs / 86_400) as i64);
+    let secs_of_day = total_secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3_600,
+        (secs_of_day % 3_600) / 60,
+        secs_of_day % 60
+    )
+}
+
+pub(crate) fn civil_from_unix_days(days_since_epoch: i64) -> (i32, u32, u32) {
     let z = days_since_epoch + 719_468;
     let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
     let doe = z - era * 146_097;
@@ -759,6 +792,13 @@ fn sandbox_events_from_result(result: &Result<String, String>) -> Vec<AuditSandb
 }
 
 fn snapshot_for_tool(tool_name: &str, tool_args: &Value) -> FileSnapshot {
+    if matches!(tool_name, "pybun_add" | "pybun_remove") {
+        let mut snapshot = FileSnapshot::new();
+        for file in ["pyproject.toml", "pybun.lockb"] {
+            collect_file_snapshot(&normalized_absolute_path(Path::new(file)), &mut snapshot);
+        }
+        return snapshot;
+    }
     if tool_name != "pybun_run" {
         return FileSnapshot::new();
     }
@@ -965,6 +1005,14 @@ pub struct McpServer {
     initialized: bool,
     session_id: String,
     audit_log: McpAuditLog,
+    subscriptions: Subscriptions,
+    /// Diagnostics of the last tool call, served as
+    /// `pybun://diagnostics/latest`.
+    latest_diagnostics: Value,
+    diagnostics_generation: u64,
+    /// Where `notifications/progress` go; unset, progress tokens are ignored.
+    notifier: Option<tokio::sync::mpsc::UnboundedSender<String>>,
+    cancellations: Cancellations,
 }
 
 impl McpServer {
@@ -973,9 +1021,31 @@ impl McpServer {
             initialized: false,
             session_id: Uuid::new_v4().to_string(),
             audit_log: McpAuditLog::new(),
+            subscriptions: Subscriptions::default(),
+            latest_diagnostics: json!({ "tool": null, "diagnostics": [] }),
+            diagnostics_generation: 0,
+            notifier: None,
+            cancellations: Cancellations::default(),
         }
     }
 
+    /// Deliver progress notifications through `sink`, one serialized
+    /// message per item.
+    pub fn set_notifier(&mut self, sink: tokio::sync::mpsc::UnboundedSender<String>) {
+        self.notifier = Some(sink);
+    }
+
+    /// Handle for cancelling in-flight calls while the server is busy.
+    pub fn cancellations(&self) -> Cancellations {
+        self.cancellations.clone()
+    }
+
+    /// Id recorded in audit log entries; the HTTP transports reuse it as the
+    /// transport session id.
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
     /// Handle a JSON-RPC request
     pub async fn handle_request(&mut self, request: JsonRpcRequest) -> Option<JsonRpcResponse> {
         // Check for notifications that we explicitly handle
@@ -983,6 +1053,10 @@ impl McpServer {
             "initialized" | "notifications/initialized" => {
                 return None;
             }
+            "notifications/cancelled" => {
+                self.cancellations.cancel(&request.params);
+                return None;
+            }
             _ => {}
         }
 
@@ -995,9 +1069,11 @@ impl McpServer {
         match request.method.as_str() {
             "initialize" => Some(self.handle_initialize(id, request.params)),
             "tools/list" => Some(self.handle_tools_list(id)),
-            "tools/call" => Some(self.handle_tools_call(id, request.params).await),
+            "tools/call" => self.handle_tools_call(id, request.params).await,
             "resources/list" => Some(self.handle_resources_list(id)),
             "resources/read" => Some(self.handle_resources_read(id, request.params)),
+            "resources/subscribe" => Some(self.handle_resources_subscribe(id, request.params)),
+            "resources/unsubscribe" => Some(self.handle_resources_unsubscribe(id, request.params)),
             "shutdown" => {
                 eprintln!("MCP server shutting down");
                 Some(JsonRpcResponse::success(id, json!({})))
@@ -1019,7 +1095,7 @@ impl McpServer {
                 "protocolVersion": PROTOCOL_VERSION,
                 "capabilities": {
                     "tools": {},
-                    "resources": {}
+                    "resources": { "subscribe": true, "listChanged": false }
                 },
                 "serverInfo": {
                     "name": SERVER_NAME,
@@ -1049,6 +1125,7 @@ impl McpServer {
                     },
                     "required": ["requirements"]
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_install".to_string(),
@@ -1073,6 +1150,10 @@ impl McpServer {
                             "type": "boolean",
                             "description": "Allow pre-release and dev versions when resolving (PEP 440 excludes them by default unless a specifier mentions one; default: false)"
                         },
+                        "allow_suspicious": {
+                            "type": "boolean",
+                            "description": "Proceed with new dependencies flagged as typosquats, brand-new, or with changed maintainers; findings become W_SUSPICIOUS_PACKAGE warnings instead of E_SUSPICIOUS_PACKAGE errors. Ask the user before setting it (default: false)"
+                        },
                         "index": {
                             "type": "string",
                             "description": "Path to a local index JSON file. If omitted, falls back to PyPI (same as the CLI)."
@@ -1083,6 +1164,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: Some(install_output_schema()),
             },
             Tool {
                 name: "pybun_run".to_string(),
@@ -1142,6 +1224,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_gc".to_string(),
@@ -1159,6 +1242,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: Some(gc_output_schema()),
             },
             Tool {
                 name: "pybun_doctor".to_string(),
@@ -1172,6 +1256,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: Some(doctor_output_schema()),
             },
             Tool {
                 name: "pybun_lint".to_string(),
@@ -1194,6 +1279,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_type_check".to_string(),
@@ -1215,6 +1301,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_profile".to_string(),
@@ -1236,6 +1323,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_fix".to_string(),
@@ -1259,6 +1347,7 @@ impl McpServer {
                     },
                     "required": ["script"]
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_context".to_string(),
@@ -1276,6 +1365,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_test".to_string(),
@@ -1301,6 +1391,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: Some(test_output_schema()),
             },
             Tool {
                 name: "pybun_drift".to_string(),
@@ -1314,6 +1405,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_audit".to_string(),
@@ -1332,6 +1424,7 @@ impl McpServer {
                         }
                     }
                 }),
+                output_schema: None,
             },
             Tool {
                 name: "pybun_upgrade".to_string(),
@@ -1350,39 +1443,178 @@ impl McpServer {
                     },
                     "required": ["package", "version"]
                 }),
+                output_schema: None,
+            },
+            Tool {
+                name: "pybun_add".to_string(),
+                description: "Add dependencies to pyproject.toml and install them, like `pybun add`. Returns the CLI JSON envelope (detail: packages, added_dependencies, group, installed, changes, diff).".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "packages": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "minItems": 1,
+                            "description": "Packages to add, optionally with a version (e.g., ['requests>=2.31', 'flask'])"
+                        },
+                        "group": {
+                            "type": "string",
+                            "description": "Optional-dependencies extra or [dependency-groups] entry to add to instead of [project.dependencies]"
+                        },
+                        "pre": {
+                            "type": "boolean",
+                            "description": "Allow pre-release and dev versions when resolving (default: false)"
+                        },
+                        "offline": {
+                            "type": "boolean",
+                            "description": "Install from the cache only (default: false)"
+                        },
+                        "allow_suspicious": {
+                            "type": "boolean",
+                            "description": "Proceed with new dependencies flagged as typosquats, brand-new, or with changed maintainers; findings become W_SUSPICIOUS_PACKAGE warnings instead of E_SUSPICIOUS_PACKAGE errors. Ask the user before setting it (default: false)"
+                        }
+                    },
+                    "required": ["packages"]
+                }),
+                output_schema: Some(crate::schema::schema_v1_json()),
+            },
+            Tool {
+                name: "pybun_remove".to_string(),
+                description: "Remove dependencies from pyproject.toml, like `pybun remove`. Returns the CLI JSON envelope (detail: packages with removed flags, group, changes, diff).".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "packages": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "minItems": 1,
+                            "description": "Package names to remove"
+                        },
+                        "group": {
+                            "type": "string",
+                            "description": "Optional-dependencies extra or [dependency-groups] entry to remove from"
+                        }
+                    },
+                    "required": ["packages"]
+                }),
+                output_schema: Some(crate::schema::schema_v1_json()),
+            },
+            Tool {
+                name: "pybun_python_install".to_string(),
+                description: "Install a managed Python runtime, like `pybun python install`. Returns the CLI JSON envelope.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "version": {
+                            "type": "string",
+                            "description": "Version to install (e.g., '3.12', '3.12.7', free-threaded '3.13t' or PyPy 'pypy3.10')"
+                        },
+                        "from_source": {
+                            "type": "boolean",
+                            "description": "Build CPython from a git ref instead of downloading a prebuilt runtime (default: false)"
+                        },
+                        "ref": {
+                            "type": "string",
+                            "description": "Tag, branch, or commit to build (requires from_source; default: vVERSION)"
+                        },
+                        "pydebug": {
+                            "type": "boolean",
+                            "description": "Configure a debug build (requires from_source)"
+                        },
+                        "lto": {
+                            "type": "boolean",
+                            "description": "Configure with link-time optimization (requires from_source)"
+                        }
+                    },
+                    "required": ["version"]
+                }),
+                output_schema: Some(crate::schema::schema_v1_json()),
+            },
+            Tool {
+                name: "pybun_python_list".to_string(),
+                description: "List installed (or all available) Python runtimes, like `pybun python list`. Returns the CLI JSON envelope.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "all": {
+                            "type": "boolean",
+                            "description": "Include versions that are available but not installed (default: false)"
+                        },
+                        "fetch_sizes": {
+                            "type": "boolean",
+                            "description": "Fetch the upstream release manifests not cached yet, for archive sizes and build dates (requires network; default: false)"
+                        }
+                    }
+                }),
+                output_schema: Some(crate::schema::schema_v1_json()),
+            },
+            Tool {
+                name: "pybun_module_find".to_string(),
+                description: "Find where a Python module is loaded from, or scan search paths for all modules, like `pybun module-find`. Returns the CLI JSON envelope.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "module": {
+                            "type": "string",
+                            "description": "Module to find (e.g., 'os.path'); omit when scanning"
+                        },
+                        "paths": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Search paths (default: the environment's sys.path)"
+                        },
+                        "scan": {
+                            "type": "boolean",
+                            "description": "List every module under the search paths instead of finding one (default: false)"
+                        },
+                        "threads": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Threads for parallel scanning (default: 4)"
+                        }
+                    }
+                }),
+                output_schema: Some(crate::schema::schema_v1_json()),
             },
         ];
 
         JsonRpcResponse::success(id, json!({ "tools": tools }))
     }
 
-    async fn handle_tools_call(&mut self, id: Value, params: Value) -> JsonRpcResponse {
+    /// `None` when the client cancelled the call.
+    async fn handle_tools_call(&mut self, id: Value, params: Value) -> Option<JsonRpcResponse> {
         let tool_name = params.get("name").and_then(|n| n.as_str()).unwrap_or("");
         let tool_args = params.get("arguments").cloned().unwrap_or(json!({}));
+        let progress = params
+            .pointer("/_meta/progressToken")
+            .cloned()
+            .zip(self.notifier.clone())
+            .map(|(token, sink)| ProgressReporter::new(sink, token));
+        let cancellation = self.cancellations.register(&id);
 
         let start = Instant::now();
         let before_snapshot = snapshot_for_tool(tool_name, &tool_args);
         let result = if let Err(err) = self.audit_log.prepare_for_call(&tool_args) {
             Err(err)
         } else {
-            match tool_name {
-                "pybun_resolve" => self.call_resolve(tool_args.clone()).await,
-                "pybun_install" => self.call_install(tool_args.clone()).await,
-                "pybun_run" => self.call_run(tool_args.clone()).await,
-                "pybun_gc" => self.call_gc(tool_args.clone()),
-                "pybun_doctor" => self.call_doctor(tool_args.clone()),
-                "pybun_lint" => self.call_lint(tool_args.clone()),
-                "pybun_type_check" => self.call_type_check(tool_args.clone()),
-                "pybun_profile" => self.call_profile(tool_args.clone()),
-                "pybun_fix" => self.call_fix(tool_args.clone()),
-                "pybun_context" => self.call_context(tool_args.clone()),
-                "pybun_drift" => self.call_drift(tool_args.clone()),
-                "pybun_test" => self.call_test(tool_args.clone()),
-                "pybun_audit" => self.call_audit(tool_args.clone()).await,
-                "pybun_upgrade" => self.call_upgrade(tool_args.clone()),
-                _ => Err(format!("Unknown tool: {}", tool_name)),
+            let call = self.call_tool(tool_name, &tool_args, progress, cancellation.clone());
+            if tool_name == "pybun_test" {
+                // Stops between tests by itself; the tests that ran were
+                // already reported as progress.
+                call.await
+            } else {
+                tokio::select! {
+                    result = call => result,
+                    _ = cancellation.cancelled() => Err(CANCELLED.to_string()),
+                }
             }
         };
+        self.cancellations.finish(&id);
+        let result = if cancellation.is_cancelled() {
+            Err(CANCELLED.to_string())
+        } else {
+            result
+        };
         let after_snapshot = snapshot_for_tool(tool_name, &tool_args);
         let file_writes = diff_file_writes(&before_snapshot, &after_snapshot);
         let duration_ms = start.elapsed().as_millis().min(u128::from(u64::MAX)) as u64;
@@ -1397,17 +1629,33 @@ impl McpServer {
             duration_ms,
         );
         self.audit_log.record(audit_entry);
+        self.record_diagnostics(tool_name, &result);
+        if cancellation.is_cancelled() {
+            return None;
+        }
 
-        match result {
-            Ok(content) => JsonRpcResponse::success(
-                id,
-                json!({
+        Some(match result {
+            Ok(content) => {
+                let structured = (ENVELOPE_TOOLS.contains(&tool_name)
+                    || STRUCTURED_TOOLS.contains(&tool_name))
+                .then(|| serde_json::from_str::<Value>(&content).ok())
+                .flatten();
+                let mut result = json!({
                     "content": [{
                         "type": "text",
                         "text": content
                     }]
-                }),
-            ),
+                });
+                if let Some(structured) = structured {
+                    // A command that ran but failed is a tool error, with
+                    // its diagnostics in the envelope.
+                    if ENVELOPE_TOOLS.contains(&tool_name) && structured["status"] == "error" {
+                        result["isError"] = json!(true);
+                    }
+                    result["structuredContent"] = structured;
+                }
+                JsonRpcResponse::success(id, result)
+            }
             Err(e) => JsonRpcResponse::success(
                 id,
                 json!({
@@ -1418,6 +1666,39 @@ impl McpServer {
                     "isError": true
                 }),
             ),
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        tool_name: &str,
+        tool_args: &Value,
+        progress: Option<ProgressReporter>,
+        cancellation: std::sync::Arc<Cancellation>,
+    ) -> Result<String, String> {
+        match tool_name {
+            "pybun_resolve" => self.call_resolve(tool_args.clone()).await,
+            "pybun_install" => self.call_install(tool_args.clone(), progress).await,
+            "pybun_run" => self.call_run(tool_args.clone()).await,
+            "pybun_gc" => self.call_gc(tool_args.clone()),
+            "pybun_doctor" => self.call_doctor(tool_args.clone()),
+            "pybun_lint" => self.call_lint(tool_args.clone()),
+            "pybun_type_check" => self.call_type_check(tool_args.clone()),
+            "pybun_profile" => self.call_profile(tool_args.clone()),
+            "pybun_fix" => self.call_fix(tool_args.clone()),
+            "pybun_context" => self.call_context(tool_args.clone()),
+            "pybun_drift" => self.call_drift(tool_args.clone()),
+            "pybun_test" => {
+                self.call_test(tool_args.clone(), progress, &cancellation)
+                    .await
+            }
+            "pybun_audit" => self.call_audit(tool_args.clone()).await,
+            "pybun_upgrade" => self.call_upgrade(tool_args.clone()),
+            name if ENVELOPE_TOOLS.contains(&name) => match command_argv(tool_name, tool_args) {
+                Ok(argv) => self.call_command(argv).await,
+                Err(e) => Err(e),
+            },
+            _ => Err(format!("Unknown tool: {}", tool_name)),
         }
     }
 
@@ -1441,6 +1722,30 @@ impl McpServer {
                 description: Some("Last 20 audited tool calls for this MCP session".to_string()),
                 mime_type: Some("application/json".to_string()),
             },
+            Resource {
+                uri: mcp_resources::PYPROJECT_URI.to_string(),
+                name: "pyproject.toml".to_string(),
+                description: Some("Parsed pyproject.toml with declared dependencies per group (subscribable)".to_string()),
+                mime_type: Some("application/json".to_string()),
+            },
+            Resource {
+                uri: mcp_resources::LOCKFILE_URI.to_string(),
+                name: "Lockfile".to_string(),
+                description: Some("Decoded pybun.lockb contents (subscribable)".to_string()),
+                mime_type: Some("application/json".to_string()),
+            },
+            Resource {
+                uri: mcp_resources::ENVIRONMENT_URI.to_string(),
+                name: "Environment Snapshot".to_string(),
+                description: Some("Interpreter and installed distributions of the project environment (subscribable)".to_string()),
+                mime_type: Some("application/json".to_string()),
+            },
+            Resource {
+                uri: mcp_resources::DIAGNOSTICS_URI.to_string(),
+                name: "Latest Diagnostics".to_string(),
+                description: Some("Diagnostics reported by the last tool call (subscribable)".to_string()),
+                mime_type: Some("application/json".to_string()),
+            },
             Resource {
                 uri: "pybun://project/snapshot".to_string(),
                 name: "Project State Snapshot".to_string(),
@@ -1460,6 +1765,14 @@ impl McpServer {
             "pybun://env/info" => self.read_env_info(),
             "pybun://audit/recent" => Ok(self.read_audit_recent()),
             "pybun://project/snapshot" => self.call_context(json!({})),
+            mcp_resources::PYPROJECT_URI => read_project_resource(mcp_resources::read_pyproject),
+            mcp_resources::LOCKFILE_URI => read_project_resource(mcp_resources::read_lockfile),
+            mcp_resources::ENVIRONMENT_URI => {
+                read_project_resource(mcp_resources::read_environment)
+            }
+            mcp_resources::DIAGNOSTICS_URI => {
+                serde_json::to_string_pretty(&self.latest_diagnostics).map_err(|e| e.to_string())
+            }
             _ => Err(format!("Unknown resource: {}", uri)),
         };
 
@@ -1478,6 +1791,90 @@ impl McpServer {
         }
     }
 
+    fn handle_resources_subscribe(&mut self, id: Value, params: Value) -> JsonRpcResponse {
+        let uri = params.get("uri").and_then(|u| u.as_str()).unwrap_or("");
+        if !SUBSCRIBABLE_RESOURCES.contains(&uri) {
+            return JsonRpcResponse::error(
+                id,
+                -32602,
+                format!("Resource does not support subscriptions: {}", uri),
+            );
+        }
+        let fingerprint = self.resource_fingerprint(uri);
+        self.subscriptions.subscribe(uri, fingerprint);
+        JsonRpcResponse::success(id, json!({}))
+    }
+
+    fn handle_resources_unsubscribe(&mut self, id: Value, params: Value) -> JsonRpcResponse {
+        let uri = params.get("uri").and_then(|u| u.as_str()).unwrap_or("");
+        self.subscriptions.unsubscribe(uri);
+        JsonRpcResponse::success(id, json!({}))
+    }
+
+    fn resource_fingerprint(&self, uri: &str) -> Option<String> {
+        if uri == mcp_resources::DIAGNOSTICS_URI {
+            return Some(self.diagnostics_generation.to_string());
+        }
+        current_dir()
+            .ok()
+            .and_then(|cwd| mcp_resources::fingerprint(uri, &cwd))
+    }
+
+    /// `notifications/resources/updated` messages for subscribed resources
+    /// that changed since the last call. The transports call this after each
+    /// request and every [`mcp_resources::POLL_INTERVAL`].
+    pub fn resource_updates(&mut self) -> Vec<Value> {
+        if self.subscriptions.is_empty() {
+            return Vec::new();
+        }
+        let generation = self.diagnostics_generation.to_string();
+        let cwd = current_dir().ok();
+        self.subscriptions
+            .changed(|uri| {
+                if uri == mcp_resources::DIAGNOSTICS_URI {
+                    Some(generation.clone())
+                } else {
+                    cwd.as_deref()
+                        .and_then(|cwd| mcp_resources::fingerprint(uri, cwd))
+                }
+            })
+            .iter()
+            .map(|uri| mcp_resources::updated_notification(uri))
+            .collect()
+    }
+
+    /// Keep the diagnostics of a tool call for `pybun://diagnostics/latest`:
+    /// the envelope's `diagnostics` for structured results, the error
+    /// message for failed calls.
+    fn record_diagnostics(&mut self, tool_name: &str, result: &Result<String, String>) {
+        let (status, diagnostics) = match result {
+            Ok(content) => {
+                let parsed = serde_json::from_str::<Value>(content).unwrap_or(Value::Null);
+                let status = parsed
+                    .get("status")
+                    .and_then(Value::as_str)
+                    .unwrap_or("ok")
+                    .to_string();
+                let diagnostics = match parsed.get("diagnostics") {
+                    Some(Value::Array(items)) => Value::Array(items.clone()),
+                    _ => json!([]),
+                };
+                (status, diagnostics)
+            }
+            Err(e) => (
+                "error".to_string(),
+                json!([{ "level": "error", "message": e }]),
+            ),
+        };
+        self.diagnostics_generation += 1;
+        self.latest_diagnostics = json!({
+            "tool": tool_name,
+            "recorded_at": utc_timestamp_now(),
+            "status": status,
+            "diagnostics": diagnostics,
+        });
+    }
+
     fn read_audit_recent(&self) -> String {
         self.audit_log.recent_json(&self.session_id)
     }
@@ -1602,7 +1999,11 @@ impl McpServer {
     /// actually fetched and installed); otherwise it reports an honest
     /// `"resolved"` status describing exactly what happened (dependency
     /// resolution and lockfile generation, without any wheel installation).
-    async fn call_install(&self, args: Value) -> Result<String, String> {
+    async fn call_install(
+        &self,
+        args: Value,
+        progress: Option<ProgressReporter>,
+    ) -> Result<String, String> {
         use crate::resolver::Requirement;
         use crate::schema::EventCollector;
 
@@ -1652,28 +2053,50 @@ impl McpServer {
 
         // Opt-in to pre-release versions (mirrors the CLI `--pre` flag).
         let pre = args.get("pre").and_then(|p| p.as_bool()).unwrap_or(false);
+        let allow_suspicious = args
+            .get("allow_suspicious")
+            .and_then(|p| p.as_bool())
+            .unwrap_or(false);
 
         let install_args = crate::cli::InstallArgs {
             offline,
             system,
+            no_seed: false,
+            build_isolation: None,
             requirements: parsed_requirements,
             index,
             lock,
+            frozen: false,
+            package: None,
+            require_hashes: false,
             workspace: false,
             member: None,
             group: None,
             pre,
+            resolver: Default::default(),
+            concurrency: None,
+            allow_suspicious,
         };
 
         let mut collector = EventCollector::new();
+        if let Some(progress) = progress {
+            collector.set_event_listener(progress.event_listener());
+        }
         let result = crate::commands::install(&install_args, &mut collector).await;
         let diagnostics = serde_json::to_value(collector.into_diagnostics()).unwrap_or(Value::Null);
 
         match result {
             Ok(outcome) => {
                 let really_installed = outcome.installed_count > 0;
+                let up_to_date = !outcome.results.is_empty()
+                    && outcome
+                        .results
+                        .iter()
+                        .all(|r| r.status == crate::installer::InstallStatus::AlreadyInstalled);
                 let status = if really_installed {
                     "installed"
+                } else if up_to_date {
+                    "up_to_date"
                 } else {
                     "resolved"
                 };
@@ -1684,6 +2107,12 @@ impl McpServer {
                         outcome.packages.len(),
                         outcome.lockfile.display()
                     )
+                } else if up_to_date {
+                    format!(
+                        "All {} resolved package(s) are already installed -> {}",
+                        outcome.packages.len(),
+                        outcome.lockfile.display()
+                    )
                 } else {
                     format!(
                         "Resolved {} package(s) and wrote {} (no wheels were downloaded or installed)",
@@ -1700,6 +2129,8 @@ impl McpServer {
                     "installed_count": outcome.installed_count,
                     "verified": outcome.verified,
                     "artifacts": outcome.artifacts,
+                    "results": outcome.results,
+                    "environment": outcome.environment,
                     "message": message,
                     "diagnostics": diagnostics,
                 })
@@ -1773,16 +2204,25 @@ impl McpServer {
         // where MCP skipped PEP 723 dependency install entirely.
         let run_args_struct = crate::cli::RunArgs {
             target: script.map(|s| s.to_string()),
+            list: false,
             code: code.map(|s| s.to_string()),
-            sandbox: use_sandbox,
-            allow_network: effective_sandbox_config.allow_network,
-            allow_read: effective_sandbox_config.allow_read.clone(),
-            allow_write: effective_sandbox_config.allow_write.clone(),
-            allow_env: effective_sandbox_config.allow_env.clone(),
-            sandbox_timeout: effective_sandbox_config.timeout_secs,
-            sandbox_memory: effective_sandbox_config.memory_limit_mb,
-            sandbox_cpu: effective_sandbox_config.cpu_limit_secs,
+            module: None,
+            member: None,
+            sandbox: crate::cli::SandboxArgs {
+                enabled: use_sandbox,
+                allow_network: effective_sandbox_config.allow_network,
+                allow_host: Vec::new(),
+                allow_read: effective_sandbox_config.allow_read.clone(),
+                allow_write: effective_sandbox_config.allow_write.clone(),
+                allow_env: effective_sandbox_config.allow_env.clone(),
+                timeout: effective_sandbox_config.timeout_secs,
+                memory: effective_sandbox_config.memory_limit_mb,
+                cpu: effective_sandbox_config.cpu_limit_secs,
+            },
             profile: "dev".to_string(),
+            lazy_imports: false,
+            measure_lazy_savings: false,
+            python: None,
             passthrough: run_args,
         };
 
@@ -1806,7 +2246,10 @@ impl McpServer {
                 // Enrich diagnostics with a structured traceback when the script
                 // failed, mirroring the CLI `pybun run` dispatcher (Issue #266).
                 if outcome.exit_code != 0
-                    && let Some(tb) = outcome.stderr.as_deref().and_then(crate::traceback::parse)
+                    && let Some(tb) = outcome
+                        .stderr
+                        .as_ref()
+                        .and_then(|stderr| crate::traceback::parse(&stderr.text))
                 {
                     let mut diag = crate::schema::Diagnostic::error(tb.message.clone());
                     diag.code = Some(tb.code);
@@ -2801,7 +3244,12 @@ impl McpServer {
         .unwrap())
     }
 
-    fn call_test(&self, args: Value) -> Result<String, String> {
+    async fn call_test(
+        &self,
+        args: Value,
+        progress: Option<ProgressReporter>,
+        cancellation: &Cancellation,
+    ) -> Result<String, String> {
         use crate::test_discovery::{TestDiscovery, TestItemType};
         use crate::test_executor::{ExecutorConfig, TestExecutor, TestOutcome};
 
@@ -2937,10 +3385,41 @@ impl McpServer {
             timeout: None,
             retries: 0,
             python,
+            cancel: Some(cancellation.flag()),
+            ..Default::default()
         };
 
+        // The executor blocks; run it off the runtime so cancellation
+        // notifications are still read while tests run.
         let executor = TestExecutor::new(config);
-        let result = executor.execute(tests);
+        let total = tests.len();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            let mut completed = 0;
+            let result = executor.execute_observed(tests, |result| {
+                completed += 1;
+                if let Some(progress) = &progress {
+                    let status = serde_json::to_value(&result.outcome)
+                        .ok()
+                        .and_then(|status| status.as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    progress.report(
+                        completed as f64,
+                        Some(total as f64),
+                        format!(
+                            "{status} {}::{} ({}ms)",
+                            result.path.display(),
+                            result.name,
+                            result.duration_ms
+                        ),
+                    );
+                }
+            });
+            let _ = tx.send(result);
+        });
+        let result = rx
+            .await
+            .map_err(|_| "test executor thread panicked".to_string())?;
         let summary = &result.summary;
 
         let failures: Vec<Value> = result
@@ -3093,6 +3572,31 @@ impl McpServer {
         .to_string())
     }
 
+    /// Run a CLI command in-process and return its `--format json` envelope.
+    async fn call_command(&self, argv: Vec<String>) -> Result<String, String> {
+        use clap::Parser;
+
+        let cli = crate::cli::Cli::try_parse_from(std::iter::once("pybun".to_string()).chain(argv))
+            .map_err(|e| e.to_string())?;
+        let envelope = if crate::entry::requires_tokio_runtime(&cli) {
+            crate::commands::run_envelope(&cli.command).await
+        } else {
+            // Commands outside the runtime use blocking HTTP clients, which
+            // must not run on it; give them their own thread like `main` does.
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            std::thread::spawn(move || {
+                let _ = tx.send(futures::executor::block_on(crate::commands::run_envelope(
+                    &cli.command,
+                )));
+            });
+            rx.await
+                .map_err(|_| "command thread panicked".to_string())?
+        };
+        envelope
+            .map(|envelope| envelope.to_json())
+            .ok_or_else(|| "command is not available as an MCP tool".to_string())
+    }
+
     fn call_upgrade(&self, args: Value) -> Result<String, String> {
         use crate::env::find_python_env;
 
@@ -3130,12 +3634,210 @@ impl McpServer {
     }
 }
 
+/// Tools that run a CLI command and return its JSON envelope.
+const ENVELOPE_TOOLS: &[&str] = &[
+    "pybun_add",
+    "pybun_remove",
+    "pybun_python_install",
+    "pybun_python_list",
+    "pybun_module_find",
+];
+
+/// Other tools declaring an `outputSchema`.
+const STRUCTURED_TOOLS: &[&str] = &["pybun_install", "pybun_gc", "pybun_doctor", "pybun_test"];
+
+/// CLI arguments (after `pybun`) for an [`ENVELOPE_TOOLS`] call. Values go
+/// after `--` so they are never taken for flags.
+fn command_argv(tool_name: &str, args: &Value) -> Result<Vec<String>, String> {
+    let flag = |argv: &mut Vec<String>, key: &str, flag: &str| {
+        if args.get(key).and_then(Value::as_bool).unwrap_or(false) {
+            argv.push(flag.to_string());
+        }
+    };
+    let option = |argv: &mut Vec<String>, key: &str, flag: &str| {
+        if let Some(value) = args.get(key).and_then(Value::as_str) {
+            argv.push(format!("{flag}={value}"));
+        }
+    };
+    let strings = |key: &str| -> Vec<String> {
+        args.get(key)
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut argv: Vec<String> = Vec::new();
+    let positional = match tool_name {
+        "pybun_add" | "pybun_remove" => {
+            argv.push(tool_name.trim_start_matches("pybun_").to_string());
+            option(&mut argv, "group", "--group");
+            if tool_name == "pybun_add" {
+                flag(&mut argv, "pre", "--pre");
+                flag(&mut argv, "offline", "--offline");
+                flag(&mut argv, "allow_suspicious", "--allow-suspicious");
+            }
+            let packages = strings("packages");
+            if packages.is_empty() {
+                return Err("Missing required argument: packages".to_string());
+            }
+            packages
+        }
+        "pybun_python_install" => {
+            argv.extend(["python".to_string(), "install".to_string()]);
+            flag(&mut argv, "from_source", "--from-source");
+            option(&mut argv, "ref", "--ref");
+            flag(&mut argv, "pydebug", "--pydebug");
+            flag(&mut argv, "lto", "--lto");
+            let version = args
+                .get("version")
+                .and_then(Value::as_str)
+                .ok_or_else(|| "Missing required argument: version".to_string())?;
+            vec![version.to_string()]
+        }
+        "pybun_python_list" => {
+            argv.extend(["python".to_string(), "list".to_string()]);
+            flag(&mut argv, "all", "--all");
+            flag(&mut argv, "fetch_sizes", "--fetch-sizes");
+            Vec::new()
+        }
+        "pybun_module_find" => {
+            argv.push("module-find".to_string());
+            for path in strings("paths") {
+                argv.push(format!("--path={path}"));
+            }
+            flag(&mut argv, "scan", "--scan");
+            if let Some(threads) = args.get("threads").and_then(Value::as_u64) {
+                argv.push(format!("--threads={threads}"));
+            }
+            args.get("module")
+                .and_then(Value::as_str)
+                .map(|module| vec![module.to_string()])
+                .unwrap_or_default()
+        }
+        _ => return Err(format!("Unknown tool: {tool_name}")),
+    };
+    if !positional.is_empty() {
+        argv.push("--".to_string());
+        argv.extend(positional);
+    }
+    Ok(argv)
+}
+
+fn install_output_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "status": {"type": "string", "enum": ["installed", "up_to_date", "resolved"]},
+            "packages": {"type": "array"},
+            "lockfile": {"type": "string"},
+            "count": {"type": "integer"},
+            "installed_count": {"type": "integer"},
+            "verified": {"type": "boolean"},
+            "artifacts": {"type": "array"},
+            "results": {"type": "array"},
+            "environment": {"type": ["object", "null"]},
+            "message": {"type": "string"},
+            "diagnostics": {"type": "array"}
+        },
+        "required": ["status", "packages", "lockfile", "message"]
+    })
+}
+
+fn gc_output_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "status": {"type": "string"},
+            "freed_bytes": {"type": "integer"},
+            "freed_human": {"type": "string"},
+            "files_removed": {"type": "integer"},
+            "dry_run": {"type": "boolean"}
+        },
+        "required": ["status", "freed_bytes", "files_removed", "dry_run"]
+    })
+}
+
+fn doctor_output_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "status": {"type": "string"},
+            "checks": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "status": {"type": "string"},
+                        "message": {"type": "string"}
+                    },
+                    "required": ["name", "status", "message"]
+                }
+            },
+            "verbose": {"type": "boolean"},
+            "message": {"type": "string"}
+        },
+        "required": ["status", "checks", "message"]
+    })
+}
+
+fn test_output_schema() -> Value {
+    let count = json!({"type": "integer", "minimum": 0});
+    json!({
+        "type": "object",
+        "properties": {
+            "summary": {
+                "type": "object",
+                "properties": {
+                    "total": count,
+                    "passed": count,
+                    "failed": count,
+                    "skipped": count,
+                    "errors": count,
+                    "duration_ms": count
+                },
+                "required": ["total", "passed", "failed"]
+            },
+            "failures": {"type": "array"},
+            "passed": {"type": "array"},
+            "analysis_notes": {"type": "array"}
+        },
+        "required": ["summary", "failures", "passed"]
+    })
+}
+
 impl Default for McpServer {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Error recorded for (and never sent back on) a cancelled tool call.
+const CANCELLED: &str = "cancelled by the client";
+
+/// Resources that support `resources/subscribe`.
+const SUBSCRIBABLE_RESOURCES: &[&str] = &[
+    mcp_resources::PYPROJECT_URI,
+    mcp_resources::LOCKFILE_URI,
+    mcp_resources::ENVIRONMENT_URI,
+    mcp_resources::DIAGNOSTICS_URI,
+];
+
+fn current_dir() -> Result<PathBuf, String> {
+    std::env::current_dir().map_err(|e| e.to_string())
+}
+
+fn read_project_resource(read: fn(&Path) -> Result<Value, String>) -> Result<String, String> {
+    let value = read(&current_dir()?)?;
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
 /// Run the MCP server in stdio mode
 pub async fn run_stdio_server() -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("PyBun MCP server starting (stdio mode)...");
@@ -3146,8 +3848,31 @@ pub async fn run_stdio_server() -> Result<(), Box<dyn std::error::Error>> {
     let mut lines = reader.lines();
 
     let mut server = McpServer::new();
+    let (notifier, mut notifications) = tokio::sync::mpsc::unbounded_channel();
+    server.set_notifier(notifier);
+    let cancellations = server.cancellations();
+    let mut poll = tokio::time::interval(mcp_resources::POLL_INTERVAL);
+    // Lines read while a request was running.
+    let mut queued = VecDeque::new();
+    let mut stdin_closed = false;
 
-    while let Ok(Some(line)) = lines.next_line().await {
+    loop {
+        let line = match queued.pop_front() {
+            Some(line) => line,
+            None if stdin_closed => break,
+            None => tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => line,
+                    _ => break,
+                },
+                _ = poll.tick() => {
+                    for update in server.resource_updates() {
+                        write_line(&mut stdout, &update.to_string()).await?;
+                    }
+                    continue;
+                }
+            },
+        };
         if line.trim().is_empty() {
             continue;
         }
@@ -3166,11 +3891,40 @@ pub async fn run_stdio_server() -> Result<(), Box<dyn std::error::Error>> {
             }
         };
 
-        if let Some(response) = server.handle_request(request).await {
-            let response_json = serde_json::to_string(&response)?;
-            stdout.write_all(response_json.as_bytes()).await?;
-            stdout.write_all(b"\n").await?;
-            stdout.flush().await?;
+        // Keep reading while the request runs: progress goes out as it is
+        // reported, and a cancellation has to reach the running call.
+        let response = {
+            let call = server.handle_request(request);
+            tokio::pin!(call);
+            loop {
+                tokio::select! {
+                    // The call registers itself for cancellation on its
+                    // first poll, before any cancellation is read.
+                    biased;
+                    response = &mut call => break response,
+                    Some(progress) = notifications.recv() => {
+                        write_line(&mut stdout, &progress).await?;
+                    }
+                    line = lines.next_line(), if !stdin_closed => match line {
+                        Ok(Some(line)) => {
+                            if !cancel_from_line(&cancellations, &line) {
+                                queued.push_back(line);
+                            }
+                        }
+                        _ => stdin_closed = true,
+                    },
+                }
+            }
+        };
+        while let Ok(progress) = notifications.try_recv() {
+            write_line(&mut stdout, &progress).await?;
+        }
+
+        if let Some(response) = response {
+            write_line(&mut stdout, &serde_json::to_string(&response)?).await?;
+        }
+        for update in server.resource_updates() {
+            write_line(&mut stdout, &update.to_string()).await?;
         }
     }
 
@@ -3178,6 +3932,23 @@ pub async fn run_stdio_server() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+async fn write_line(stdout: &mut tokio::io::Stdout, line: &str) -> std::io::Result<()> {
+    stdout.write_all(line.as_bytes()).await?;
+    stdout.write_all(b"\n").await?;
+    stdout.flush().await
+}
+
+/// Apply `line` if it is a `notifications/cancelled` message.
+fn cancel_from_line(cancellations: &Cancellations, line: &str) -> bool {
+    match serde_json::from_str::<Value>(line) {
+        Ok(message) if message["method"] == "notifications/cancelled" => {
+            cancellations.cancel(&message["params"]);
+            true
+        }
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3281,6 +4052,57 @@ mod tests {
         assert!(response.result.is_some());
     }
 
+    #[test]
+    fn command_argv_keeps_values_out_of_flag_position() {
+        let argv = command_argv(
+            "pybun_add",
+            &json!({"packages": ["--index-url=evil", "requests>=2"], "group": "dev", "pre": true}),
+        )
+        .unwrap();
+        assert_eq!(
+            argv,
+            [
+                "add",
+                "--group=dev",
+                "--pre",
+                "--",
+                "--index-url=evil",
+                "requests>=2"
+            ]
+        );
+        assert_eq!(
+            command_argv("pybun_python_list", &json!({"all": true})).unwrap(),
+            ["python", "list", "--all"]
+        );
+        assert!(command_argv("pybun_remove", &json!({})).is_err());
+        assert!(command_argv("pybun_python_install", &json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_envelope_tools_declare_output_schemas() {
+        let mut server = McpServer::new();
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/list".to_string(),
+            params: json!({}),
+            id: Some(json!(6)),
+        };
+        let result = server
+            .handle_request(request)
+            .await
+            .unwrap()
+            .result
+            .unwrap();
+        let tools = result["tools"].as_array().unwrap();
+        for name in ENVELOPE_TOOLS.iter().chain(STRUCTURED_TOOLS) {
+            let tool = tools
+                .iter()
+                .find(|tool| tool["name"] == *name)
+                .unwrap_or_else(|| panic!("{name} should be listed"));
+            assert_eq!(tool["outputSchema"]["type"], "object", "{name}");
+        }
+    }
+
     #[tokio::test]
     async fn test_notification_handling() {
         let mut server = McpServer::new();
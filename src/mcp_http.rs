@@ -0,0 +1,637 @@
+//! HTTP transports for `pybun mcp serve --port`.
+//!
+//! One listener serves both MCP HTTP transports:
+//! - Streamable HTTP (`POST /mcp`): the body is a JSON-RPC message or batch
+//!   and the replies come back in the response body. `initialize` opens a
+//!   session whose id is returned in `Mcp-Session-Id`; later requests send
+//!   it back, and `DELETE /mcp` closes it.
+//! - HTTP + SSE (protocol 2024-11-05, `GET /sse`): the event stream first
+//!   announces a `/messages?sessionId=…` endpoint. Messages POSTed there are
+//!   acknowledged with `202 Accepted` and answered on the stream, which
+//!   also carries `notifications/resources/updated` for subscribed
+//!   resources. Streamable HTTP has no server-initiated stream here, so its
+//!   clients re-read resources instead of subscribing, and get no
+//!   progress notifications. `notifications/cancelled` works on both.
+//!
+//! With a token, every request must carry `Authorization: Bearer <token>`.
+//! Without one, browser requests from non-local origins are refused (DNS
+//! rebinding). Ctrl-C or SIGTERM stops accepting connections, ends event
+//! streams, and gives in-flight requests [`SHUTDOWN_GRACE`] to finish.
+//!
+//! Each connection carries one request (`Connection: close`); the server runs
+//! on the current thread, so sessions are plain `Rc`s.
+
+use crate::mcp::{JsonRpcRequest, JsonRpcResponse, McpServer};
+use crate::mcp_progress::Cancellations;
+use crate::mcp_resources;
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, mpsc, watch};
+use tokio::task::{JoinSet, LocalSet};
+
+/// Request line plus headers.
+const MAX_HEAD_BYTES: u64 = 16 * 1024;
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+/// How long in-flight requests may run after a shutdown signal.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+/// Comment lines sent on idle event streams so proxies keep them open.
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+#[derive(Debug, Clone)]
+pub struct HttpServerConfig {
+    pub bind: SocketAddr,
+    /// Bearer token required on every request.
+    pub token: Option<String>,
+}
+
+/// Serve MCP over HTTP until Ctrl-C or SIGTERM. Returns the bound address.
+pub async fn run_http_server(
+    config: HttpServerConfig,
+) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(config.bind).await?;
+    let addr = listener.local_addr()?;
+    eprintln!("PyBun MCP server listening on http://{addr}/mcp (SSE: http://{addr}/sse)");
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let state = Rc::new(State {
+        token: config.token,
+        sessions: RefCell::new(HashMap::new()),
+        shutdown: shutdown_rx,
+    });
+
+    LocalSet::new()
+        .run_until(async move {
+            let mut connections = JoinSet::new();
+            let mut signal = std::pin::pin!(shutdown_signal());
+            loop {
+                tokio::select! {
+                    _ = &mut signal => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => {
+                            connections.spawn_local(handle_connection(stream, state.clone()));
+                        }
+                        Err(e) => eprintln!("MCP HTTP accept failed: {e}"),
+                    },
+                    Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                }
+            }
+            eprintln!("PyBun MCP server shutting down...");
+            drop(listener);
+            let _ = shutdown_tx.send(true);
+            let drained = tokio::time::timeout(SHUTDOWN_GRACE, async {
+                while connections.join_next().await.is_some() {}
+            })
+            .await;
+            if drained.is_err() {
+                eprintln!(
+                    "Aborting {} MCP request(s) still running after {}s",
+                    connections.len(),
+                    SHUTDOWN_GRACE.as_secs()
+                );
+                connections.shutdown().await;
+            }
+        })
+        .await;
+
+    eprintln!("PyBun MCP server stopped.");
+    Ok(addr)
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[derive(Clone)]
+struct Session {
+    server: Rc<Mutex<McpServer>>,
+    /// Event stream of an HTTP + SSE session; `None` for streamable HTTP.
+    events: Option<mpsc::UnboundedSender<String>>,
+    /// Reachable while `server` is locked by the call being cancelled.
+    cancellations: Cancellations,
+}
+
+struct State {
+    token: Option<String>,
+    sessions: RefCell<HashMap<String, Session>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl State {
+    fn open_session(&self, events: Option<mpsc::UnboundedSender<String>>) -> (String, Session) {
+        let mut server = McpServer::new();
+        let id = server.session_id().to_string();
+        // Progress can only be pushed on an event stream.
+        if let Some(events) = &events {
+            server.set_notifier(events.clone());
+        }
+        let session = Session {
+            cancellations: server.cancellations(),
+            server: Rc::new(Mutex::new(server)),
+            events,
+        };
+        self.sessions
+            .borrow_mut()
+            .insert(id.clone(), session.clone());
+        (id, session)
+    }
+
+    fn session(&self, id: &str) -> Option<Session> {
+        self.sessions.borrow().get(id).cloned()
+    }
+
+    fn close_session(&self, id: &str) -> bool {
+        self.sessions.borrow_mut().remove(id).is_some()
+    }
+}
+
+#[derive(Debug, Default)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    /// Names lower-cased.
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            (key == name).then_some(value)
+        })
+    }
+}
+
+struct HttpResponse {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    fn empty(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    fn json(status: u16, value: &Value) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type", "application/json".to_string())],
+            body: serde_json::to_vec(value).unwrap_or_default(),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, &json!({"error": message.into()}))
+    }
+
+    fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> std::io::Result<()> {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            reason_phrase(self.status),
+            self.body.len()
+        );
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+        writer.write_all(head.as_bytes()).await?;
+        writer.write_all(&self.body).await?;
+        writer.flush().await
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+async fn handle_connection(stream: TcpStream, state: Rc<State>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let request = match read_request(&mut reader).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return,
+        Err(response) => {
+            let _ = response.write_to(&mut writer).await;
+            return;
+        }
+    };
+    if let Err(response) = authorize(&request, state.token.as_deref()) {
+        let _ = response.write_to(&mut writer).await;
+        return;
+    }
+    let response = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/mcp") => handle_streamable(&request, &state).await,
+        ("DELETE", "/mcp") => match request.header("mcp-session-id") {
+            Some(id) if state.close_session(id) => HttpResponse::empty(204),
+            Some(_) => HttpResponse::error(404, "Unknown MCP session"),
+            None => HttpResponse::error(400, format!("Missing {SESSION_HEADER} header")),
+        },
+        ("GET", "/sse") => {
+            serve_event_stream(&mut writer, &state).await;
+            return;
+        }
+        ("POST", "/messages") => handle_sse_message(&request, &state).await,
+        (_, "/mcp") => {
+            HttpResponse::error(405, "Use POST to send messages or DELETE to end a session")
+                .with_header("Allow", "POST, DELETE")
+        }
+        (_, "/sse") => {
+            HttpResponse::error(405, "Use GET to open the event stream").with_header("Allow", "GET")
+        }
+        (_, "/messages") => {
+            HttpResponse::error(405, "Use POST to send messages").with_header("Allow", "POST")
+        }
+        _ => HttpResponse::error(404, format!("No MCP endpoint at {}", request.path)),
+    };
+    let _ = response.write_to(&mut writer).await;
+}
+
+/// `Ok(None)` when the client closed the connection without a request.
+async fn read_request<R: AsyncBufReadExt + AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<HttpRequest>, HttpResponse> {
+    let too_large = || HttpResponse::error(413, "Request headers too large");
+    let mut head = (&mut *reader).take(MAX_HEAD_BYTES);
+    let mut line = String::new();
+    let read = head
+        .read_line(&mut line)
+        .await
+        .map_err(|_| HttpResponse::error(400, "Malformed request"))?;
+    if read == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(HttpResponse::error(400, "Malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = HttpRequest {
+        method: method.to_ascii_uppercase(),
+        path: path.to_string(),
+        query: query.to_string(),
+        ..HttpRequest::default()
+    };
+    loop {
+        line.clear();
+        let read = head
+            .read_line(&mut line)
+            .await
+            .map_err(|_| HttpResponse::error(400, "Malformed request headers"))?;
+        if read == 0 {
+            return Err(too_large());
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(HttpResponse::error(400, "Malformed request header"));
+        };
+        request
+            .headers
+            .push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    if request.header("transfer-encoding").is_some() {
+        return Err(HttpResponse::error(
+            411,
+            "Chunked request bodies are not supported",
+        ));
+    }
+    let length = match request.header("content-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| HttpResponse::error(400, "Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(HttpResponse::error(413, "Request body too large"));
+    }
+    request.body = vec![0; length];
+    reader
+        .read_exact(&mut request.body)
+        .await
+        .map_err(|_| HttpResponse::error(400, "Request body shorter than Content-Length"))?;
+    Ok(Some(request))
+}
+
+fn authorize(request: &HttpRequest, token: Option<&str>) -> Result<(), HttpResponse> {
+    match token {
+        Some(expected) => {
+            let given = request
+                .header("authorization")
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(str::trim);
+            if given.is_some_and(|given| tokens_match(given, expected)) {
+                Ok(())
+            } else {
+                Err(HttpResponse::error(401, "Missing or invalid bearer token")
+                    .with_header("WWW-Authenticate", "Bearer"))
+            }
+        }
+        None => match request.header("origin") {
+            Some(origin) if !is_local_origin(origin) => Err(HttpResponse::error(
+                403,
+                format!("Origin {origin} is not allowed; start the server with --token"),
+            )),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Compare in time independent of where the tokens differ.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn is_local_origin(origin: &str) -> bool {
+    let host = origin
+        .split_once("://")
+        .map_or(origin, |(_, rest)| rest)
+        .trim_end_matches('/');
+    let host = if let Some(bracketed) = host.strip_prefix('[') {
+        bracketed.split(']').next().unwrap_or("")
+    } else {
+        host.split(':').next().unwrap_or("")
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Parse a JSON-RPC message or batch. `Err` carries the JSON-RPC error.
+fn parse_messages(body: &[u8]) -> Result<(Vec<JsonRpcRequest>, bool), Value> {
+    let parse_error = || {
+        serde_json::to_value(JsonRpcResponse::error(Value::Null, -32700, "Parse error"))
+            .unwrap_or(Value::Null)
+    };
+    let value: Value = serde_json::from_slice(body).map_err(|_| parse_error())?;
+    let batch = value.is_array();
+    let messages = if batch {
+        serde_json::from_value::<Vec<JsonRpcRequest>>(value)
+    } else {
+        serde_json::from_value::<JsonRpcRequest>(value).map(|message| vec![message])
+    }
+    .map_err(|_| parse_error())?;
+    Ok((messages, batch))
+}
+
+async fn dispatch(session: &Session, messages: Vec<JsonRpcRequest>) -> Vec<JsonRpcResponse> {
+    // Cancellations are applied before waiting for the session, which the
+    // call they target may be holding.
+    let messages: Vec<JsonRpcRequest> = messages
+        .into_iter()
+        .filter(|message| {
+            message.method != "notifications/cancelled"
+                || !session.cancellations.cancel(&message.params)
+        })
+        .collect();
+    let mut server = session.server.lock().await;
+    let mut responses = Vec::new();
+    for message in messages {
+        if let Some(response) = server.handle_request(message).await {
+            responses.push(response);
+        }
+    }
+    responses
+}
+
+async fn handle_streamable(request: &HttpRequest, state: &State) -> HttpResponse {
+    let (messages, batch) = match parse_messages(&request.body) {
+        Ok(parsed) => parsed,
+        Err(error) => return HttpResponse::json(400, &error),
+    };
+    let (id, session) = match request.header("mcp-session-id") {
+        Some(id) => match state.session(id) {
+            Some(session) => (id.to_string(), session),
+            None => return HttpResponse::error(404, "Unknown MCP session; send initialize again"),
+        },
+        None if messages.iter().any(|m| m.method == "initialize") => state.open_session(None),
+        None => {
+            return HttpResponse::error(
+                400,
+                format!("Missing {SESSION_HEADER} header; send initialize first"),
+            );
+        }
+    };
+    let responses = dispatch(&session, messages).await;
+    let response = match responses.len() {
+        0 => HttpResponse::empty(202),
+        _ if batch => HttpResponse::json(200, &json!(responses)),
+        _ => HttpResponse::json(200, &json!(responses[0])),
+    };
+    response.with_header(SESSION_HEADER, id)
+}
+
+async fn handle_sse_message(request: &HttpRequest, state: &State) -> HttpResponse {
+    let Some(id) = request.query_param("sessionId") else {
+        return HttpResponse::error(400, "Missing sessionId query parameter");
+    };
+    let Some(session) = state.session(id) else {
+        return HttpResponse::error(404, "Unknown MCP session; reopen the event stream");
+    };
+    let Some(events) = session.events.clone() else {
+        return HttpResponse::error(404, "Unknown MCP session; reopen the event stream");
+    };
+    let (messages, _) = match parse_messages(&request.body) {
+        Ok(parsed) => parsed,
+        Err(error) => return HttpResponse::json(400, &error),
+    };
+    for response in dispatch(&session, messages).await {
+        if let Ok(text) = serde_json::to_string(&response) {
+            let _ = events.send(text);
+        }
+    }
+    for notification in session.server.lock().await.resource_updates() {
+        let _ = events.send(notification.to_string());
+    }
+    HttpResponse::empty(202)
+}
+
+/// Stream responses for one HTTP + SSE session until the client disconnects
+/// or the server shuts down.
+async fn serve_event_stream<W: AsyncWrite + Unpin>(writer: &mut W, state: &State) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let (id, session) = state.open_session(Some(tx));
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+    let endpoint = format!("event: endpoint\ndata: /messages?sessionId={id}\n\n");
+    if write_event(writer, head).await.is_ok() && write_event(writer, &endpoint).await.is_ok() {
+        let mut shutdown = state.shutdown.clone();
+        let mut keepalive = tokio::time::interval(SSE_KEEPALIVE);
+        keepalive.tick().await;
+        let mut poll = tokio::time::interval(mcp_resources::POLL_INTERVAL);
+        loop {
+            let event = tokio::select! {
+                message = rx.recv() => match message {
+                    Some(message) => format!("event: message\ndata: {message}\n\n"),
+                    None => break,
+                },
+                _ = keepalive.tick() => ": keepalive\n\n".to_string(),
+                // Skipped while a request holds the session; it is polled
+                // again after that request.
+                _ = poll.tick() => match session.server.try_lock() {
+                    Ok(mut server) => server
+                        .resource_updates()
+                        .iter()
+                        .map(|notification| format!("event: message\ndata: {notification}\n\n"))
+                        .collect(),
+                    Err(_) => continue,
+                },
+                _ = shutdown.wait_for(|stopping| *stopping) => break,
+            };
+            if event.is_empty() {
+                continue;
+            }
+            if write_event(writer, &event).await.is_err() {
+                break;
+            }
+        }
+    }
+    state.close_session(&id);
+}
+
+async fn write_event<W: AsyncWrite + Unpin>(writer: &mut W, text: &str) -> std::io::Result<()> {
+    writer.write_all(text.as_bytes()).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn parse(raw: &str) -> Result<Option<HttpRequest>, HttpResponse> {
+        let mut reader = BufReader::new(raw.as_bytes());
+        read_request(&mut reader).await
+    }
+
+    #[tokio::test]
+    async fn reads_request_line_headers_and_body() {
+        let request = parse(
+            "POST /messages?sessionId=abc HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\nMcp-Session-Id: s1\r\n\r\n{}",
+        )
+        .await
+        .ok()
+        .flatten()
+        .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/messages");
+        assert_eq!(request.query_param("sessionId"), Some("abc"));
+        assert_eq!(request.header("mcp-session-id"), Some("s1"));
+        assert_eq!(request.body, b"{}");
+
+        assert!(parse("").await.ok().unwrap().is_none());
+        let chunked = parse("POST /mcp HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n").await;
+        assert_eq!(chunked.err().unwrap().status, 411);
+    }
+
+    #[test]
+    fn bearer_token_and_origin_checks() {
+        let request = |headers: &[(&str, &str)]| HttpRequest {
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..HttpRequest::default()
+        };
+        assert!(
+            authorize(
+                &request(&[("authorization", "Bearer s3cret")]),
+                Some("s3cret")
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            authorize(
+                &request(&[("authorization", "Bearer nope")]),
+                Some("s3cret")
+            )
+            .err()
+            .unwrap()
+            .status,
+            401
+        );
+        assert!(authorize(&request(&[]), Some("s3cret")).is_err());
+
+        assert!(authorize(&request(&[]), None).is_ok());
+        assert!(authorize(&request(&[("origin", "http://localhost:3000")]), None).is_ok());
+        assert!(authorize(&request(&[("origin", "http://[::1]:3000")]), None).is_ok());
+        assert_eq!(
+            authorize(&request(&[("origin", "https://evil.example")]), None)
+                .err()
+                .unwrap()
+                .status,
+            403
+        );
+    }
+
+    #[test]
+    fn parses_single_messages_and_batches() {
+        let (messages, batch) =
+            parse_messages(br#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).unwrap();
+        assert!(!batch);
+        assert_eq!(messages[0].method, "tools/list");
+
+        let (messages, batch) = parse_messages(
+            br#"[{"jsonrpc":"2.0","id":1,"method":"ping"},{"jsonrpc":"2.0","method":"notifications/initialized"}]"#,
+        )
+        .unwrap();
+        assert!(batch);
+        assert_eq!(messages.len(), 2);
+
+        let error = parse_messages(b"not json").unwrap_err();
+        assert_eq!(error["error"]["code"], -32700);
+    }
+}
@@ -0,0 +1,201 @@
+//! Progress notifications and cancellation for MCP tool calls.
+//!
+//! A client that puts `_meta.progressToken` in a `tools/call` request gets
+//! `notifications/progress` messages while the call runs: resolve, download
+//! and install phases for `pybun_install`, one message per finished test for
+//! `pybun_test`. Those messages are the partial results of a call that is
+//! later cancelled.
+//!
+//! `notifications/cancelled` names the request to stop. Async tools are
+//! dropped at their next await point; `pybun_test` starts no further tests.
+//! Either way the cancelled request gets no response, as the protocol
+//! requires. Tools that run synchronously finish regardless.
+
+use crate::schema::{Event, EventListener, EventType};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, mpsc};
+
+/// Sends `notifications/progress` for one request.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    sink: mpsc::UnboundedSender<String>,
+    token: Value,
+}
+
+impl ProgressReporter {
+    pub fn new(sink: mpsc::UnboundedSender<String>, token: Value) -> Self {
+        Self { sink, token }
+    }
+
+    /// `progress` must increase from one call to the next.
+    pub fn report(&self, progress: f64, total: Option<f64>, message: impl Into<String>) {
+        let mut params = json!({
+            "progressToken": self.token,
+            "progress": progress,
+            "message": message.into(),
+        });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": params,
+        });
+        let _ = self.sink.send(notification.to_string());
+    }
+
+    /// Forward a command's progress events as percentages of 100. Events
+    /// that would not move the percentage forward are dropped.
+    pub fn event_listener(self) -> EventListener {
+        let mut last = 0.0;
+        Box::new(move |event: &Event| {
+            let Some((message, percent)) = crate::progress::phase_of(event) else {
+                return;
+            };
+            let progress = match (&event.event_type, &event.data) {
+                // Finished downloads fill the 50-70% band exactly, so each
+                // one advances even when the rounded percentage does not.
+                (EventType::DownloadProgress, Some(data)) => {
+                    let completed = data["completed"].as_f64().unwrap_or(0.0);
+                    let total = data["total"].as_f64().unwrap_or(1.0).max(1.0);
+                    50.0 + 20.0 * completed / total
+                }
+                _ => match percent {
+                    Some(percent) => f64::from(percent),
+                    None => return,
+                },
+            };
+            if progress > last {
+                last = progress;
+                self.report(progress, Some(100.0), message);
+            }
+        })
+    }
+}
+
+/// Cancellation state of one in-flight request.
+#[derive(Debug, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    notify: Notify,
+}
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// The flag behind [`Self::is_cancelled`], for work on other threads.
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    /// Resolves once [`Self::cancel`] has been called.
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            self.notify.notified().await;
+        }
+    }
+}
+
+/// In-flight requests by JSON-RPC id. Cloned handles share the registry, so
+/// a transport can cancel a request while the server is busy running it.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellations {
+    inflight: Arc<Mutex<HashMap<String, Arc<Cancellation>>>>,
+}
+
+impl Cancellations {
+    pub fn register(&self, id: &Value) -> Arc<Cancellation> {
+        let cancellation = Arc::new(Cancellation::default());
+        if let Ok(mut inflight) = self.inflight.lock() {
+            inflight.insert(id.to_string(), cancellation.clone());
+        }
+        cancellation
+    }
+
+    pub fn finish(&self, id: &Value) {
+        if let Ok(mut inflight) = self.inflight.lock() {
+            inflight.remove(&id.to_string());
+        }
+    }
+
+    /// Cancel the request named by `notifications/cancelled` params; false
+    /// when it is not (or no longer) running.
+    pub fn cancel(&self, params: &Value) -> bool {
+        let Some(id) = params.get("requestId") else {
+            return false;
+        };
+        let cancellation = self
+            .inflight
+            .lock()
+            .ok()
+            .and_then(|inflight| inflight.get(&id.to_string()).cloned());
+        match cancellation {
+            Some(cancellation) => {
+                cancellation.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn install_events_become_increasing_progress() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut listener = ProgressReporter::new(tx, json!("tok")).event_listener();
+        let event = |event_type, progress: Option<u8>, data: Option<Value>| {
+            let mut event = Event::new(event_type, 0);
+            event.progress = progress;
+            event.data = data;
+            event
+        };
+        listener(&event(EventType::ResolveStart, None, None));
+        listener(&event(EventType::DownloadStart, Some(50), None));
+        for completed in 1..=3 {
+            listener(&event(
+                EventType::DownloadProgress,
+                Some(50),
+                Some(json!({ "completed": completed, "total": 40 })),
+            ));
+        }
+        listener(&event(EventType::DownloadComplete, Some(50), None));
+        listener(&event(EventType::InstallComplete, None, None));
+
+        let mut progress = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            let message: Value = serde_json::from_str(&message).unwrap();
+            assert_eq!(message["method"], "notifications/progress");
+            assert_eq!(message["params"]["progressToken"], "tok");
+            progress.push(message["params"]["progress"].as_f64().unwrap());
+        }
+        assert_eq!(progress, [5.0, 50.0, 50.5, 51.0, 51.5, 100.0]);
+    }
+
+    #[tokio::test]
+    async fn cancel_wakes_the_registered_request_only() {
+        let cancellations = Cancellations::default();
+        let running = cancellations.register(&json!(7));
+        assert!(!cancellations.cancel(&json!({ "requestId": 8 })));
+        assert!(cancellations.cancel(&json!({ "requestId": 7, "reason": "user" })));
+        running.cancelled().await;
+        assert!(running.is_cancelled());
+
+        cancellations.finish(&json!(7));
+        assert!(!cancellations.cancel(&json!({ "requestId": 7 })));
+    }
+}
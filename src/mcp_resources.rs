@@ -0,0 +1,220 @@
+//! Project-state resources for the MCP server.
+//!
+//! A connected agent reads pyproject.toml, the lockfile, and the project
+//! environment as structured JSON instead of re-running commands, and can
+//! `resources/subscribe` to them. Change detection is a cheap fingerprint
+//! per resource: file contents for pyproject.toml and `pybun.lockb`, the
+//! `.dist-info` directory names for the environment (no interpreter is
+//! started). The transports poll subscribed fingerprints every
+//! [`POLL_INTERVAL`] and after each request, and send
+//! `notifications/resources/updated` for the ones that changed.
+
+use crate::dist_info;
+use crate::lockfile::Lockfile;
+use crate::project::Project;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const PYPROJECT_URI: &str = "pybun://project/pyproject";
+pub const LOCKFILE_URI: &str = "pybun://project/lockfile";
+pub const ENVIRONMENT_URI: &str = "pybun://env/snapshot";
+pub const DIAGNOSTICS_URI: &str = "pybun://diagnostics/latest";
+
+/// How often the transports check subscribed resources for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Project root: the directory holding pyproject.toml, else `cwd`.
+fn project_root(cwd: &Path) -> PathBuf {
+    Project::discover(cwd)
+        .map(|project| project.root().to_path_buf())
+        .unwrap_or_else(|_| cwd.to_path_buf())
+}
+
+fn venv(root: &Path) -> Option<PathBuf> {
+    std::env::var_os("PYBUN_ENV")
+        .map(PathBuf::from)
+        .or_else(|| crate::env::find_project_venv(root))
+}
+
+/// `pybun://project/pyproject`: the whole file as JSON plus the declared
+/// requirements per group.
+pub fn read_pyproject(cwd: &Path) -> Result<Value, String> {
+    let Ok(project) = Project::discover(cwd) else {
+        return Ok(json!({ "exists": false, "path": Value::Null }));
+    };
+    let text = std::fs::read_to_string(project.path()).map_err(|e| e.to_string())?;
+    let document: toml::Value = toml::from_str(&text)
+        .map_err(|e| format!("{} is not valid TOML: {e}", project.path().display()))?;
+    Ok(json!({
+        "exists": true,
+        "path": project.path().display().to_string(),
+        "dependencies": project.dependencies(),
+        "optional_dependencies": project.optional_dependencies(),
+        "dependency_groups": project.dependency_groups(),
+        "content": document,
+    }))
+}
+
+/// `pybun://project/lockfile`: the decoded `pybun.lockb`.
+pub fn read_lockfile(cwd: &Path) -> Result<Value, String> {
+    let path = project_root(cwd).join("pybun.lockb");
+    if !path.is_file() {
+        return Ok(json!({ "exists": false, "path": path.display().to_string() }));
+    }
+    let lock = Lockfile::load_from_path(&path).map_err(|e| e.to_string())?;
+    Ok(json!({
+        "exists": true,
+        "path": path.display().to_string(),
+        "package_count": lock.packages.len(),
+        "lockfile": lock,
+    }))
+}
+
+/// `pybun://env/snapshot`: the interpreter and the distributions installed
+/// in the project environment.
+pub fn read_environment(cwd: &Path) -> Result<Value, String> {
+    let root = project_root(cwd);
+    let python = crate::env::find_python_env(&root).ok();
+    let venv = venv(&root);
+    let site_packages = venv
+        .as_deref()
+        .and_then(crate::env_clean::site_packages_dir);
+    let packages: Vec<Value> = site_packages
+        .as_deref()
+        .map(dist_info::installed)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|dist| json!({ "name": dist.name, "version": dist.version }))
+        .collect();
+    Ok(json!({
+        "python_path": python.as_ref().map(|env| env.python_path.display().to_string()),
+        "python_version": python.as_ref().and_then(|env| env.version.clone()),
+        "source": python.as_ref().map(|env| env.source.to_string()),
+        "venv": venv.map(|path| path.display().to_string()),
+        "site_packages": site_packages.map(|path| path.display().to_string()),
+        "package_count": packages.len(),
+        "packages": packages,
+    }))
+}
+
+/// Fingerprint of a file-backed resource; `None` for other URIs.
+pub fn fingerprint(uri: &str, cwd: &Path) -> Option<String> {
+    let file = |path: PathBuf| {
+        crate::security::sha256_file(&path).unwrap_or_else(|_| "missing".to_string())
+    };
+    match uri {
+        PYPROJECT_URI => Some(
+            Project::discover(cwd)
+                .map(|project| file(project.path().to_path_buf()))
+                .unwrap_or_else(|_| "missing".to_string()),
+        ),
+        LOCKFILE_URI => Some(file(project_root(cwd).join("pybun.lockb"))),
+        ENVIRONMENT_URI => {
+            let venv = venv(&project_root(cwd));
+            let names: Vec<String> = venv
+                .as_deref()
+                .and_then(crate::env_clean::site_packages_dir)
+                .map(|site_packages| dist_info::installed(&site_packages))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|dist| format!("{}-{}", dist.name, dist.version))
+                .collect();
+            Some(format!(
+                "{}:{}",
+                venv.map(|path| path.display().to_string())
+                    .unwrap_or_default(),
+                names.join(",")
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Subscribed URIs and the fingerprint each was last reported at.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    seen: BTreeMap<String, Option<String>>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, uri: &str, fingerprint: Option<String>) {
+        self.seen.insert(uri.to_string(), fingerprint);
+    }
+
+    pub fn unsubscribe(&mut self, uri: &str) -> bool {
+        self.seen.remove(uri).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// URIs whose fingerprint differs from the last one seen, remembering
+    /// the new ones.
+    pub fn changed(&mut self, mut current: impl FnMut(&str) -> Option<String>) -> Vec<String> {
+        let mut changed = Vec::new();
+        for (uri, seen) in &mut self.seen {
+            let now = current(uri);
+            if *seen != now {
+                *seen = now;
+                changed.push(uri.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// The `notifications/resources/updated` message for `uri`.
+pub fn updated_notification(uri: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/resources/updated",
+        "params": { "uri": uri },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reads_pyproject_and_tracks_file_changes() {
+        let temp = tempdir().unwrap();
+        let missing = read_pyproject(temp.path()).unwrap();
+        assert_eq!(missing["exists"], false);
+        assert_eq!(read_lockfile(temp.path()).unwrap()["exists"], false);
+
+        let mut subscriptions = Subscriptions::default();
+        subscriptions.subscribe(PYPROJECT_URI, fingerprint(PYPROJECT_URI, temp.path()));
+        assert!(
+            subscriptions
+                .changed(|uri| fingerprint(uri, temp.path()))
+                .is_empty()
+        );
+
+        std::fs::write(
+            temp.path().join("pyproject.toml"),
+            "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"requests>=2.31\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            subscriptions.changed(|uri| fingerprint(uri, temp.path())),
+            [PYPROJECT_URI]
+        );
+        assert!(
+            subscriptions
+                .changed(|uri| fingerprint(uri, temp.path()))
+                .is_empty()
+        );
+
+        let pyproject = read_pyproject(temp.path()).unwrap();
+        assert_eq!(pyproject["exists"], true);
+        assert_eq!(pyproject["dependencies"][0], "requests>=2.31");
+        assert_eq!(pyproject["content"]["project"]["name"], "demo");
+        assert!(subscriptions.unsubscribe(PYPROJECT_URI));
+        assert!(subscriptions.is_empty());
+    }
+}
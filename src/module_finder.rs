@@ -7,7 +7,7 @@
 //! The module finder is opt-in and guarded by a flag to allow fallback
 //! to CPython's native import system when needed.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread;
@@ -275,7 +275,98 @@ impl ModuleFinder {
             }
         }
 
-        None
+        // Extension modules usually carry an ABI tag
+        // (e.g. `_ssl.cpython-312-x86_64-linux-gnu.so`).
+        let tagged = std::fs::read_dir(&current_path)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| module_stem(n) == Some(*last_part) && is_extension(n))
+            })?;
+        Some(ModuleInfo {
+            name: module_name,
+            path: tagged,
+            module_type: ModuleType::Extension,
+            search_path: search_path.to_path_buf(),
+        })
+    }
+
+    /// Names of the modules directly inside the package `parent` (the
+    /// top-level modules when `parent` is empty), across all search paths.
+    pub fn child_modules(&self, parent: &str) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for search_path in &self.config.search_paths {
+            let mut dir = search_path.clone();
+            dir.extend(parent.split('.').filter(|part| !part.is_empty()));
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_name) = entry.file_name().into_string() else {
+                    continue;
+                };
+                let name = if entry.path().is_dir() {
+                    Some(file_name.as_str())
+                } else if self
+                    .config
+                    .extensions
+                    .iter()
+                    .any(|ext| file_name.ends_with(ext.as_str()))
+                {
+                    module_stem(&file_name)
+                } else {
+                    None
+                };
+                if let Some(name) =
+                    name.filter(|n| is_identifier(n) && !matches!(*n, "__init__" | "__pycache__"))
+                {
+                    names.insert(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// Up to three existing modules whose names are close to the missing
+    /// `module_name`, for "did you mean" hints. The first component that
+    /// cannot be found is compared with its siblings; `extra` adds top-level
+    /// names that have no file on the search paths (builtin modules).
+    pub fn similar_modules(&self, module_name: &str, extra: &[String]) -> Vec<String> {
+        let parts: Vec<&str> = module_name.split('.').collect();
+        let Some(missing) =
+            (1..=parts.len()).find(|&i| self.find_module(&parts[..i].join(".")).module.is_none())
+        else {
+            return Vec::new();
+        };
+        let parent = parts[..missing - 1].join(".");
+        let wanted = parts[missing - 1].to_lowercase();
+        let mut candidates = self.child_modules(&parent);
+        if parent.is_empty() {
+            candidates.extend(extra.iter().cloned());
+        }
+        let limit = (wanted.chars().count() / 3).max(1);
+        let mut ranked: Vec<(usize, String)> = candidates
+            .into_iter()
+            .map(|name| (edit_distance(&wanted, &name.to_lowercase()), name))
+            .filter(|(distance, _)| *distance <= limit)
+            .collect();
+        ranked.sort();
+        ranked
+            .into_iter()
+            .take(3)
+            .map(|(_, name)| {
+                parts[..missing - 1]
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(name.as_str()))
+                    .chain(parts[missing..].iter().copied())
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .collect()
     }
 
     /// Scan a directory and return all discovered modules.
@@ -505,6 +596,45 @@ impl ModuleFinder {
     }
 }
 
+/// Module name of a file in a package directory: the part before the first
+/// dot (`foo.py`, `_ssl.cpython-312-x86_64-linux-gnu.so`).
+fn module_stem(file_name: &str) -> Option<&str> {
+    file_name.split_once('.').map(|(stem, _)| stem)
+}
+
+fn is_extension(file_name: &str) -> bool {
+    file_name.ends_with(".so") || file_name.ends_with(".pyd")
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Edit distance between two names, counting an adjacent transposition
+/// (`jsno` for `json`) as a single edit.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
 /// Generate Python code that installs this module finder into sys.meta_path.
 ///
 /// This returns Python code that can be executed to install a custom finder
@@ -1031,4 +1161,52 @@ mod tests {
             "symlinked dir with __init__.py should be Package"
         );
     }
+
+    #[test]
+    fn test_find_abi_tagged_extension() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path()
+                .join("_speedups.cpython-312-x86_64-linux-gnu.so"),
+            "",
+        )
+        .unwrap();
+
+        let finder = ModuleFinder::new(ModuleFinderConfig {
+            enabled: true,
+            search_paths: vec![temp.path().to_path_buf()],
+            ..Default::default()
+        });
+        let module = finder.find_module("_speedups").module.unwrap();
+        assert_eq!(module.module_type, ModuleType::Extension);
+        assert!(finder.child_modules("").contains("_speedups"));
+    }
+
+    #[test]
+    fn test_similar_modules_suggests_siblings_of_missing_component() {
+        let temp = TempDir::new().unwrap();
+        create_test_module_structure(temp.path());
+
+        let finder = ModuleFinder::new(ModuleFinderConfig {
+            enabled: true,
+            search_paths: vec![temp.path().to_path_buf()],
+            ..Default::default()
+        });
+        assert_eq!(
+            finder.child_modules("bar").into_iter().collect::<Vec<_>>(),
+            vec!["baz", "qux"]
+        );
+        assert_eq!(finder.similar_modules("fooo", &[]), vec!["foo"]);
+        assert_eq!(
+            finder.similar_modules("bar.qux.quxx", &[]),
+            vec!["bar.qux.quux"]
+        );
+        assert_eq!(finder.similar_modules("bra.baz", &[]), vec!["bar.baz"]);
+        assert_eq!(
+            finder.similar_modules("sys_", &["sys".to_string()]),
+            vec!["sys"]
+        );
+        assert!(finder.similar_modules("unrelated", &[]).is_empty());
+        assert!(finder.similar_modules("bar.baz", &[]).is_empty());
+    }
 }
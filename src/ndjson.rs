@@ -0,0 +1,53 @@
+//! `--format ndjson`: events and diagnostics are written to stdout as they
+//! are recorded, one JSON record per line, and the command ends with a
+//! `result` record instead of a single envelope.
+
+use crate::schema::{Diagnostic, DiagnosticListener, Event, EventListener, StreamRecord};
+use std::cell::Cell;
+use std::io;
+use std::rc::Rc;
+
+/// Streams a command's records and remembers how many were written, so the
+/// final envelope only adds what was not streamed yet.
+#[derive(Default)]
+pub struct NdjsonStream {
+    events: Rc<Cell<usize>>,
+    diagnostics: Rc<Cell<usize>>,
+}
+
+impl NdjsonStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn event_listener(&self) -> EventListener {
+        let written = self.events.clone();
+        Box::new(move |event: &Event| {
+            if StreamRecord::Event(event)
+                .write_line(io::stdout().lock())
+                .is_ok()
+            {
+                written.set(written.get() + 1);
+            }
+        })
+    }
+
+    pub fn diagnostic_listener(&self) -> DiagnosticListener {
+        let written = self.diagnostics.clone();
+        Box::new(move |diagnostic: &Diagnostic| {
+            if StreamRecord::Diagnostic(diagnostic)
+                .write_line(io::stdout().lock())
+                .is_ok()
+            {
+                written.set(written.get() + 1);
+            }
+        })
+    }
+
+    /// Drop the records already streamed from the collected `events` and
+    /// `diagnostics`.
+    pub fn skip_streamed(&self, events: &mut Vec<Event>, diagnostics: &mut Vec<Diagnostic>) {
+        events.drain(..self.events.get().min(events.len()));
+        diagnostics.drain(..self.diagnostics.get().min(diagnostics.len()));
+    }
+}
@@ -0,0 +1,668 @@
+//! Outbound network allowlist per operation class.
+//!
+//! A project can restrict which hosts PyBun may contact, per kind of
+//! operation, in `[tool.pybun.network]`:
+//!
+//! ```toml
+//! [tool.pybun.network]
+//! index = ["pypi.org", "files.pythonhosted.org", "*.corp.example"]
+//! self-update = ["github.com", "*.githubusercontent.com"]
+//! run = []          # code started by `pybun run` may not connect anywhere
+//! test = ["localhost"]
+//! cache = ["cache.corp.example"]   # remote wheel cache
+//! publish = ["upload.pypi.org", "pypi.org"]
+//! ```
+//!
+//! Without the section there is no policy and nothing is restricted. With it,
+//! every class not listed falls back to [`Operation::default_hosts`]: the
+//! usual public hosts for `index`, `self-update`, `python`, and `audit`, the
+//! hosts of `PYBUN_CACHE_URL` and `AWS_ENDPOINT_URL` for `cache`, PyPI,
+//! TestPyPI and the CI's OIDC token endpoint for `publish`, and nothing at
+//! all for `run` and `test`. Patterns are exact host names, a
+//! `*.` suffix wildcard, or `*` for any host. Loopback addresses are always
+//! allowed and `file://` URLs are never checked.
+//!
+//! PyBun's own HTTP clients call [`check_url`] before every request and use
+//! [`redirect_policy`] so a redirect cannot escape the allowlist. Python
+//! processes started by `pybun run`/`pybun test` get a `sitecustomize`
+//! guard ([`NetworkGuard`]) that refuses to resolve or connect to other
+//! hosts; the `run --sandbox` sitecustomize applies the same guard when
+//! `--allow-network` is given. Blocked attempts are collected with
+//! [`record`] and reported as `E_NETWORK_POLICY` diagnostics.
+//!
+//! `--offline` ([`crate::offline`]) takes precedence: [`check_url`] then
+//! refuses every `http(s)` URL, whatever the policy says.
+
+use crate::project::Project;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use tempfile::TempDir;
+use thiserror::Error;
+
+/// Kind of network activity a host list applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    /// Package index metadata and artifact downloads.
+    Index,
+    /// Release manifests and binaries for `pybun self update`.
+    SelfUpdate,
+    /// Managed CPython runtime downloads.
+    Python,
+    /// Vulnerability database queries (`pybun audit`).
+    Audit,
+    /// Code executed by `pybun run`.
+    Run,
+    /// Code executed by `pybun test`.
+    Test,
+    /// Remote wheel cache (`pybun cache push`/`pull`).
+    Cache,
+    /// Uploads and trusted publishing (`pybun publish`).
+    Publish,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Index => "index",
+            Operation::SelfUpdate => "self-update",
+            Operation::Python => "python",
+            Operation::Audit => "audit",
+            Operation::Run => "run",
+            Operation::Test => "test",
+            Operation::Cache => "cache",
+            Operation::Publish => "publish",
+        }
+    }
+
+    /// Hosts allowed when the policy exists but does not list this class.
+    pub fn default_hosts(self) -> Vec<String> {
+        let hosts: &[&str] = match self {
+            Operation::Index => &["pypi.org", "files.pythonhosted.org"],
+            Operation::SelfUpdate | Operation::Python => &[
+                "github.com",
+                "objects.githubusercontent.com",
+                "release-assets.githubusercontent.com",
+            ],
+            Operation::Audit => &["api.osv.dev"],
+            Operation::Publish => &["upload.pypi.org", "pypi.org", "test.pypi.org"],
+            Operation::Run | Operation::Test | Operation::Cache => &[],
+        };
+        let mut hosts: Vec<String> = hosts.iter().map(|h| h.to_string()).collect();
+        if self == Operation::Index {
+            hosts.extend(
+                ["PYBUN_PYPI_BASE_URL", crate::pypi_index::INDEX_URL_ENV]
+                    .iter()
+                    .filter_map(|var| std::env::var(var).ok())
+                    .filter_map(|url| host_of(&url)),
+            );
+        }
+        if self == Operation::Cache {
+            hosts.extend(
+                [
+                    crate::remote_cache::URL_ENV,
+                    "AWS_ENDPOINT_URL_S3",
+                    "AWS_ENDPOINT_URL",
+                ]
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .filter_map(|url| host_of(&url)),
+            );
+        }
+        if self == Operation::Publish {
+            hosts.extend(
+                ["ACTIONS_ID_TOKEN_REQUEST_URL"]
+                    .iter()
+                    .filter_map(|var| std::env::var(var).ok())
+                    .filter_map(|url| host_of(&url)),
+            );
+        }
+        hosts
+    }
+}
+
+/// `[tool.pybun.network]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkConfig {
+    #[serde(default)]
+    pub index: Option<Vec<String>>,
+    #[serde(default)]
+    pub self_update: Option<Vec<String>>,
+    #[serde(default)]
+    pub python: Option<Vec<String>>,
+    #[serde(default)]
+    pub audit: Option<Vec<String>>,
+    #[serde(default)]
+    pub run: Option<Vec<String>>,
+    #[serde(default)]
+    pub test: Option<Vec<String>>,
+    #[serde(default)]
+    pub cache: Option<Vec<String>>,
+    #[serde(default)]
+    pub publish: Option<Vec<String>>,
+}
+
+/// A configured allowlist and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkPolicy {
+    pub config: NetworkConfig,
+    pub source: PathBuf,
+}
+
+/// A request the policy refused.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Error)]
+#[error(
+    "network policy ({policy_file}) blocks {} access to {host}; allowed: {}",
+    operation.as_str(),
+    if allowed.is_empty() { "none".to_string() } else { allowed.join(", ") }
+)]
+pub struct NetworkPolicyViolation {
+    pub operation: Operation,
+    pub host: String,
+    pub url: Option<String>,
+    pub allowed: Vec<String>,
+    /// pyproject.toml that defined the policy.
+    pub policy_file: String,
+}
+
+impl NetworkPolicy {
+    /// Policy from the project enclosing `dir`, if it has
+    /// `[tool.pybun.network]`.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        let project = Project::discover(dir).ok()?;
+        let config = project.pybun_config().network?;
+        Some(Self {
+            config,
+            source: project.path().to_path_buf(),
+        })
+    }
+
+    pub fn allowed_hosts(&self, operation: Operation) -> Vec<String> {
+        let configured = match operation {
+            Operation::Index => &self.config.index,
+            Operation::SelfUpdate => &self.config.self_update,
+            Operation::Python => &self.config.python,
+            Operation::Audit => &self.config.audit,
+            Operation::Run => &self.config.run,
+            Operation::Test => &self.config.test,
+            Operation::Cache => &self.config.cache,
+            Operation::Publish => &self.config.publish,
+        };
+        configured
+            .clone()
+            .unwrap_or_else(|| operation.default_hosts())
+    }
+
+    pub fn allows_host(&self, operation: Operation, host: &str) -> bool {
+        is_loopback(host)
+            || self
+                .allowed_hosts(operation)
+                .iter()
+                .any(|pattern| host_matches(pattern, host))
+    }
+
+    /// Check `url` for `operation`. Non-network URLs always pass.
+    pub fn check(&self, operation: Operation, url: &str) -> Result<(), NetworkPolicyViolation> {
+        let Some(host) = host_of(url) else {
+            return Ok(());
+        };
+        if self.allows_host(operation, &host) {
+            return Ok(());
+        }
+        Err(NetworkPolicyViolation {
+            operation,
+            host,
+            url: Some(url.to_string()),
+            allowed: self.allowed_hosts(operation),
+            policy_file: self.source.display().to_string(),
+        })
+    }
+
+    pub fn violation(&self, operation: Operation, host: &str) -> NetworkPolicyViolation {
+        NetworkPolicyViolation {
+            operation,
+            host: host.to_string(),
+            url: None,
+            allowed: self.allowed_hosts(operation),
+            policy_file: self.source.display().to_string(),
+        }
+    }
+}
+
+/// Host of an `http(s)://` URL, lowercased; `None` for other schemes.
+fn host_of(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    parsed
+        .host_str()
+        .map(|h| h.trim_matches(['[', ']']).to_ascii_lowercase())
+}
+
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Match `host` against an allowlist pattern (`host`, `*.suffix`, or `*`).
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if pattern == "*" || pattern == host {
+        return true;
+    }
+    pattern
+        .strip_prefix("*.")
+        .is_some_and(|suffix| host.ends_with(&format!(".{suffix}")))
+}
+
+/// The policy for the current directory, loaded once per process. The
+/// `[network]` section of an execution policy ([`crate::execution_policy`])
+/// takes precedence over `[tool.pybun.network]`.
+pub fn current() -> Option<&'static NetworkPolicy> {
+    static POLICY: OnceLock<Option<NetworkPolicy>> = OnceLock::new();
+    POLICY
+        .get_or_init(|| {
+            if let Some(policy) = crate::execution_policy::current()
+                && let Some(config) = policy.network()
+            {
+                return Some(NetworkPolicy {
+                    config,
+                    source: policy.source.clone(),
+                });
+            }
+            std::env::current_dir()
+                .ok()
+                .and_then(|dir| NetworkPolicy::discover(&dir))
+        })
+        .as_ref()
+}
+
+static VIOLATIONS: Mutex<Vec<NetworkPolicyViolation>> = Mutex::new(Vec::new());
+
+/// Remember a violation so the command can report it as a diagnostic.
+pub fn record(violation: NetworkPolicyViolation) {
+    if let Ok(mut violations) = VIOLATIONS.lock()
+        && !violations.contains(&violation)
+    {
+        violations.push(violation);
+    }
+}
+
+/// Drain violations recorded since the last call.
+pub fn take_violations() -> Vec<NetworkPolicyViolation> {
+    VIOLATIONS
+        .lock()
+        .map(|mut v| std::mem::take(&mut *v))
+        .unwrap_or_default()
+}
+
+/// Check `url` against the current policy, recording any violation. In
+/// `--offline` mode every `http(s)` URL, loopback included, is refused and
+/// recorded as a missing artifact instead (see [`crate::offline`]).
+pub fn check_url(operation: Operation, url: &str) -> Result<(), NetworkPolicyViolation> {
+    if crate::offline::is_enabled()
+        && let Some(host) = host_of(url)
+    {
+        crate::offline::require(offline_artifact(operation, url));
+        return Err(NetworkPolicyViolation {
+            operation,
+            host,
+            url: Some(url.to_string()),
+            allowed: Vec::new(),
+            policy_file: "--offline".to_string(),
+        });
+    }
+    let Some(policy) = current() else {
+        return Ok(());
+    };
+    policy.check(operation, url).inspect_err(|violation| {
+        record(violation.clone());
+    })
+}
+
+/// What a refused offline request was for, judged by its URL.
+fn offline_artifact(operation: Operation, url: &str) -> crate::offline::MissingArtifact {
+    use crate::offline::{ArtifactKind, MissingArtifact};
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file = path.rsplit('/').find(|s| !s.is_empty()).unwrap_or(path);
+    let kind = if operation == Operation::Python {
+        ArtifactKind::Runtime
+    } else if file.ends_with(".whl") {
+        ArtifactKind::Wheel
+    } else if [".tar.gz", ".zip", ".tar.bz2"]
+        .iter()
+        .any(|ext| file.ends_with(ext))
+    {
+        ArtifactKind::Sdist
+    } else {
+        ArtifactKind::Download
+    };
+    MissingArtifact::new(kind, file, Some(url.to_string()))
+}
+
+/// Redirect policy that refuses hops to hosts outside the allowlist (and
+/// otherwise follows up to 10 redirects, like reqwest's default).
+pub fn redirect_policy(operation: Operation) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= 10 {
+            return attempt.error("too many redirects");
+        }
+        match check_url(operation, attempt.url().as_str()) {
+            Ok(()) => attempt.follow(),
+            Err(violation) => attempt.error(violation),
+        }
+    })
+}
+
+/// Hosts a child process may reach for `operation`, as the JSON list read
+/// by [`GUARD_MODULE_PY`]; `None` when no policy is configured.
+pub fn child_allowlist(operation: Operation) -> Option<String> {
+    let policy = current()?;
+    serde_json::to_string(&policy.allowed_hosts(operation)).ok()
+}
+
+/// Env var carrying the JSON allowlist into Python processes.
+pub const ALLOW_HOSTS_ENV: &str = "PYBUN_NETWORK_ALLOW_HOSTS";
+/// Env var naming the file the guard appends blocked host names to.
+pub const REPORT_ENV: &str = "PYBUN_NETWORK_REPORT";
+/// Module name of [`GUARD_MODULE_PY`] on the child's `sys.path`.
+pub const GUARD_MODULE: &str = "_pybun_network_guard";
+
+/// Python module enforcing the allowlist at name resolution and connect.
+///
+/// Connections to an IP literal are allowed only if that address came from
+/// resolving an allowed host (or is loopback).
+pub const GUARD_MODULE_PY: &str = r#"
+import ipaddress
+import json
+import os
+import socket
+
+_allowed_ips = set()
+
+
+def _matches(host, patterns):
+    host = host.lower().rstrip(".")
+    for pattern in patterns:
+        pattern = pattern.strip().lower().rstrip(".")
+        if pattern == "*" or host == pattern:
+            return True
+        if pattern.startswith("*.") and host.endswith(pattern[1:]):
+            return True
+    return False
+
+
+def _is_loopback(host):
+    if host.lower() == "localhost":
+        return True
+    try:
+        return ipaddress.ip_address(host.split("%")[0]).is_loopback
+    except ValueError:
+        return False
+
+
+def _is_ip(host):
+    try:
+        ipaddress.ip_address(host.split("%")[0])
+        return True
+    except ValueError:
+        return False
+
+
+def _report(host):
+    path = os.environ.get("PYBUN_NETWORK_REPORT", "")
+    if path:
+        try:
+            with open(path, "a") as handle:
+                handle.write(host + "\n")
+        except OSError:
+            pass
+
+
+def _deny(host):
+    _report(host)
+    raise PermissionError("pybun network policy: connection to {} blocked".format(host))
+
+
+def install(patterns, on_block=None):
+    orig_getaddrinfo = socket.getaddrinfo
+    orig_connect = socket.socket.connect
+    orig_connect_ex = socket.socket.connect_ex
+
+    def _check(host):
+        if host is None:
+            return
+        if isinstance(host, bytes):
+            host = host.decode("ascii", "replace")
+        host = str(host)
+        if _is_loopback(host) or _matches(host, patterns):
+            return
+        if _is_ip(host) and host in _allowed_ips:
+            return
+        if on_block is not None:
+            on_block(host)
+        _deny(host)
+
+    def getaddrinfo(host, *args, **kwargs):
+        _check(host)
+        results = orig_getaddrinfo(host, *args, **kwargs)
+        for entry in results:
+            try:
+                _allowed_ips.add(str(entry[4][0]))
+            except Exception:
+                pass
+        return results
+
+    def _address_host(address):
+        if isinstance(address, tuple) and address:
+            return address[0]
+        return None
+
+    def connect(self, address, *args, **kwargs):
+        if self.family in (socket.AF_INET, socket.AF_INET6):
+            _check(_address_host(address))
+        return orig_connect(self, address, *args, **kwargs)
+
+    def connect_ex(self, address, *args, **kwargs):
+        if self.family in (socket.AF_INET, socket.AF_INET6):
+            _check(_address_host(address))
+        return orig_connect_ex(self, address, *args, **kwargs)
+
+    socket.getaddrinfo = getaddrinfo
+    socket.socket.connect = connect
+    socket.socket.connect_ex = connect_ex
+
+
+def install_from_env(on_block=None):
+    raw = os.environ.get("PYBUN_NETWORK_ALLOW_HOSTS")
+    if raw is None:
+        return False
+    install(json.loads(raw), on_block)
+    return True
+"#;
+
+/// `sitecustomize` shim for unsandboxed children: install the guard, then
+/// hand over to any `sitecustomize` the environment would otherwise load.
+const SITECUSTOMIZE_PY: &str = r#"
+import importlib
+import os
+import sys
+
+import _pybun_network_guard
+
+_pybun_network_guard.install_from_env()
+
+_here = os.path.dirname(os.path.abspath(__file__))
+sys.path[:] = [p for p in sys.path if os.path.abspath(p or ".") != _here]
+_self = sys.modules.pop("sitecustomize")
+try:
+    importlib.import_module("sitecustomize")
+except ImportError:
+    # The import statement running this file expects to find it again.
+    sys.modules["sitecustomize"] = _self
+"#;
+
+/// Write [`GUARD_MODULE_PY`] into `dir`.
+pub fn write_guard_module(dir: &Path) -> io::Result<()> {
+    std::fs::write(dir.join(format!("{GUARD_MODULE}.py")), GUARD_MODULE_PY)
+}
+
+/// Keeps the guard's `sitecustomize` alive for a child Python process.
+#[derive(Debug)]
+pub struct NetworkGuard {
+    dir: TempDir,
+    operation: Operation,
+    hosts: String,
+}
+
+impl NetworkGuard {
+    /// A guard for `operation`, or `None` when no policy is configured.
+    pub fn for_operation(operation: Operation) -> io::Result<Option<Self>> {
+        let Some(hosts) = child_allowlist(operation) else {
+            return Ok(None);
+        };
+        let dir = tempfile::Builder::new()
+            .prefix("pybun-network-guard")
+            .tempdir()?;
+        write_guard_module(dir.path())?;
+        std::fs::write(dir.path().join("sitecustomize.py"), SITECUSTOMIZE_PY)?;
+        Ok(Some(Self {
+            dir,
+            operation,
+            hosts,
+        }))
+    }
+
+    fn report_path(&self) -> PathBuf {
+        self.dir.path().join("blocked-hosts.txt")
+    }
+
+    /// Environment that activates the guard in a child process. The guard
+    /// directory is prepended to `python_path` (or the inherited
+    /// `PYTHONPATH` when `None`).
+    pub fn env(&self, python_path: Option<&OsStr>) -> Vec<(&'static str, OsString)> {
+        let mut paths = vec![self.dir.path().to_path_buf()];
+        let existing = python_path
+            .map(OsStr::to_os_string)
+            .or_else(|| std::env::var_os("PYTHONPATH"));
+        if let Some(existing) = existing.filter(|p| !p.is_empty()) {
+            paths.extend(std::env::split_paths(&existing));
+        }
+        let python_path = std::env::join_paths(paths).unwrap_or_default();
+        vec![
+            ("PYTHONPATH", python_path),
+            (ALLOW_HOSTS_ENV, OsString::from(&self.hosts)),
+            (REPORT_ENV, self.report_path().into_os_string()),
+        ]
+    }
+
+    /// Activate the guard for `cmd`, keeping any `PYTHONPATH` already set on
+    /// it so other `sitecustomize` hooks still load after the guard.
+    pub fn apply(&self, cmd: &mut Command) {
+        let python_path = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "PYTHONPATH")
+            .and_then(|(_, value)| value.map(OsStr::to_os_string));
+        for (key, value) in self.env(python_path.as_deref()) {
+            cmd.env(key, value);
+        }
+    }
+
+    /// Record every host the child was refused as a violation.
+    pub fn record_blocked(&self) {
+        let report = std::fs::read_to_string(self.report_path()).unwrap_or_default();
+        record_blocked_hosts(self.operation, report.lines());
+    }
+}
+
+/// Record hosts a child process was refused for `operation`.
+pub fn record_blocked_hosts<'a>(operation: Operation, hosts: impl IntoIterator<Item = &'a str>) {
+    let Some(policy) = current() else {
+        return;
+    };
+    let blocked: BTreeSet<&str> = hosts
+        .into_iter()
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .collect();
+    for host in blocked {
+        record(policy.violation(operation, host));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(toml_text: &str) -> NetworkPolicy {
+        NetworkPolicy {
+            config: toml::from_str(toml_text).unwrap(),
+            source: PathBuf::from("pyproject.toml"),
+        }
+    }
+
+    #[test]
+    fn offline_artifacts_are_classified_by_url() {
+        use crate::offline::ArtifactKind;
+        let wheel = offline_artifact(
+            Operation::Index,
+            "https://files.example/packages/ab/app-1.0-py3-none-any.whl#sha256=00",
+        );
+        assert_eq!(wheel.kind, ArtifactKind::Wheel);
+        assert_eq!(wheel.name, "app-1.0-py3-none-any.whl");
+        let sdist = offline_artifact(Operation::Index, "https://files.example/app-1.0.tar.gz");
+        assert_eq!(sdist.kind, ArtifactKind::Sdist);
+        let runtime = offline_artifact(Operation::Python, "https://github.com/cpython.tar.gz");
+        assert_eq!(runtime.kind, ArtifactKind::Runtime);
+        let other = offline_artifact(Operation::Audit, "https://api.osv.dev/v1/querybatch");
+        assert_eq!(other.kind, ArtifactKind::Download);
+        assert_eq!(other.name, "querybatch");
+    }
+
+    #[test]
+    fn host_patterns() {
+        assert!(host_matches("pypi.org", "PyPI.org"));
+        assert!(host_matches("*.example.com", "a.b.example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+        assert!(host_matches("*", "anything.test"));
+    }
+
+    #[test]
+    fn configured_and_default_classes() {
+        let policy = policy("index = [\"mirror.corp.example\"]\n");
+        assert!(
+            policy
+                .check(Operation::Index, "https://mirror.corp.example/simple/x")
+                .is_ok()
+        );
+        let violation = policy
+            .check(Operation::Index, "https://pypi.org/pypi/x/json")
+            .unwrap_err();
+        assert_eq!(violation.host, "pypi.org");
+        assert_eq!(violation.allowed, vec!["mirror.corp.example".to_string()]);
+
+        assert!(
+            policy
+                .check(Operation::Audit, "https://api.osv.dev/v1/querybatch")
+                .is_ok()
+        );
+        assert!(policy.allowed_hosts(Operation::Run).is_empty());
+        assert!(
+            policy
+                .check(Operation::Run, "http://127.0.0.1:8000/")
+                .is_ok()
+        );
+        assert!(policy.check(Operation::Run, "file:///tmp/x").is_ok());
+    }
+}
@@ -0,0 +1,144 @@
+//! Process-wide `--offline` mode.
+//!
+//! `pybun --offline <command>` (or `PYBUN_OFFLINE=1`) forbids every network
+//! access for the rest of the process. [`crate::network_policy::check_url`]
+//! refuses every `http(s)` URL, and the index clients serve metadata from their
+//! caches only. Whatever could not be served locally is recorded with
+//! [`require`]; the command then reports it as one `E_NETWORK_REQUIRED`
+//! diagnostic (see [`NetworkRequired`]) listing exactly which artifacts are
+//! missing, so they can be fetched once online (or copied into the cache).
+
+use crate::schema::Diagnostic;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static MISSING: Mutex<Vec<MissingArtifact>> = Mutex::new(Vec::new());
+
+/// Turn offline mode on for the rest of the process.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// What kind of thing was needed from the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArtifactKind {
+    /// Index metadata for a project (versions, files, dependencies).
+    Metadata,
+    /// A wheel not in the wheel cache.
+    Wheel,
+    /// A source distribution to build from.
+    Sdist,
+    /// A git repository or ref not fetched before.
+    Git,
+    /// A managed CPython runtime.
+    Runtime,
+    /// OSV advisories for a package version never audited online.
+    Advisory,
+    /// Any other download.
+    Download,
+}
+
+/// One artifact that offline mode could not serve from local caches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingArtifact {
+    pub kind: ArtifactKind,
+    /// Package (`name==version`), file name, or repository, depending on
+    /// `kind`.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl MissingArtifact {
+    pub fn new(kind: ArtifactKind, name: impl Into<String>, url: Option<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            url,
+        }
+    }
+}
+
+/// Record that offline mode was missing `artifact`. Duplicates are dropped.
+pub fn require(artifact: MissingArtifact) {
+    if let Ok(mut missing) = MISSING.lock()
+        && !missing.contains(&artifact)
+    {
+        missing.push(artifact);
+    }
+}
+
+/// Drain the artifacts recorded since the last call.
+pub fn take_missing() -> Vec<MissingArtifact> {
+    MISSING
+        .lock()
+        .map(|mut missing| std::mem::take(&mut *missing))
+        .unwrap_or_default()
+}
+
+/// A command needed the network while offline.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error(
+    "network access required in offline mode; missing: {}",
+    missing.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(", ")
+)]
+pub struct NetworkRequired {
+    pub missing: Vec<MissingArtifact>,
+}
+
+impl NetworkRequired {
+    /// Record every artifact in `missing` and return the error for them.
+    pub fn new(missing: Vec<MissingArtifact>) -> Self {
+        for artifact in &missing {
+            require(artifact.clone());
+        }
+        Self { missing }
+    }
+
+    /// The `E_NETWORK_REQUIRED` diagnostic.
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.to_string())
+            .with_code("E_NETWORK_REQUIRED")
+            .with_context(json!({ "missing": self.missing }))
+            .with_suggestion(
+                "Run the command once without --offline (or copy the artifacts into the PyBun cache), then retry offline.",
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_required_lists_every_missing_artifact() {
+        let error = NetworkRequired::new(vec![
+            MissingArtifact::new(
+                ArtifactKind::Wheel,
+                "requests==2.32.3",
+                Some("https://files.example/requests.whl".into()),
+            ),
+            MissingArtifact::new(ArtifactKind::Metadata, "idna", None),
+        ]);
+        assert_eq!(
+            error.to_string(),
+            "network access required in offline mode; missing: requests==2.32.3, idna"
+        );
+        let diagnostic = error.diagnostic();
+        assert_eq!(diagnostic.code.as_deref(), Some("E_NETWORK_REQUIRED"));
+        let context = diagnostic.context.unwrap();
+        assert_eq!(context["missing"][0]["kind"], "wheel");
+        assert!(context["missing"][1].get("url").is_none());
+        let recorded = take_missing();
+        assert!(error.missing.iter().all(|a| recorded.contains(a)));
+    }
+}
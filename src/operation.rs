@@ -0,0 +1,705 @@
+//! Bounded waits for long commands (`--max-duration`).
+//!
+//! With `--max-duration`, the invoking process runs the same command line
+//! again as a child *operation* and waits at most that long for it. The child
+//! records its phase and percent complete (from the events that drive the
+//! progress UI) in `$PYBUN_HOME/operations/<id>/state.json`, and writes its
+//! stdout and stderr to `output` and `stderr.log` in the same directory.
+//!
+//! - If the child finishes within the budget, its output and exit code are
+//!   passed through unchanged.
+//! - Otherwise the parent prints a checkpoint (phase, percent, elapsed time,
+//!   ETA) instead of the command output.
+//! - `--on-timeout=cancel` (the default) then stops the child.
+//! - `--on-timeout=detach` leaves it running in the background.
+//!
+//! The checkpoint carries the operation id. `pybun status --operation <id>`
+//! reports the operation's progress and, once it has finished, its result.
+//! Operation directories older than [`RETENTION`] are removed when a new
+//! operation starts.
+
+use crate::cli::{OutputFormat, TimeoutAction};
+use crate::schema::{Diagnostic, Event, EventListener, JsonEnvelope, Status};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Set in the child process to the id of the operation it runs.
+pub const OPERATION_ENV: &str = "PYBUN_OPERATION_ID";
+/// How long finished operation directories are kept.
+pub const RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// How often the parent checks whether the child has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long a cancelled child gets to exit after SIGTERM before it is killed.
+const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+const STATE_FILE: &str = "state.json";
+const OUTPUT_FILE: &str = "output";
+const STDERR_FILE: &str = "stderr.log";
+
+#[derive(Debug, Error)]
+pub enum OperationError {
+    #[error("unknown operation `{0}`")]
+    NotFound(String),
+    #[error("failed to {action} {path}: {source}")]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        source: io::Error,
+    },
+    #[error("invalid operation state in {path}: {source}")]
+    Corrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("failed to start operation: {0}")]
+    Spawn(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, OperationError>;
+
+/// Lifecycle of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    /// Recorded as running, but the process is gone without a result.
+    Lost,
+}
+
+impl OperationState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Lost => "lost",
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        !matches!(self, Self::Running)
+    }
+}
+
+/// What `state.json` holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationRecord {
+    pub id: String,
+    /// e.g. `pybun install`.
+    pub command: String,
+    pub args: Vec<String>,
+    pub pid: Option<u32>,
+    pub state: OperationState,
+    /// Progress UI label of the last phase, e.g. `Downloading artifacts`.
+    pub phase: Option<String>,
+    pub percent: Option<u8>,
+    pub started_at_ms: u64,
+    pub updated_at_ms: u64,
+    pub exit_code: Option<i32>,
+}
+
+impl OperationRecord {
+    /// Progress snapshot as of `now_ms`. The ETA extrapolates linearly from
+    /// the percent complete, so it is only given between 1 and 99%.
+    pub fn checkpoint(&self, now_ms: u64) -> Value {
+        let elapsed_ms = now_ms.saturating_sub(self.started_at_ms);
+        let eta_ms = match (self.state, self.percent) {
+            (OperationState::Running, Some(percent)) if (1..100).contains(&percent) => {
+                Some(elapsed_ms * u64::from(100 - percent) / u64::from(percent))
+            }
+            _ => None,
+        };
+        json!({
+            "id": self.id,
+            "command": self.command,
+            "state": self.state,
+            "phase": self.phase,
+            "percent": self.percent,
+            "elapsed_ms": elapsed_ms,
+            "eta_ms": eta_ms,
+            "pid": self.pid,
+            "exit_code": self.exit_code,
+        })
+    }
+
+    fn touch(&mut self) {
+        self.updated_at_ms = now_ms();
+    }
+}
+
+/// Operation directories under `$PYBUN_HOME/operations`.
+#[derive(Debug, Clone)]
+pub struct OperationStore {
+    root: PathBuf,
+}
+
+impl OperationStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let paths = crate::paths::PyBunPaths::new().map_err(|e| OperationError::Io {
+            action: "locate",
+            path: PathBuf::from("$PYBUN_HOME"),
+            source: io::Error::other(e),
+        })?;
+        Ok(Self::new(paths.operations_dir()))
+    }
+
+    pub fn dir(&self, id: &str) -> PathBuf {
+        self.root.join(id)
+    }
+
+    pub fn output_path(&self, id: &str) -> PathBuf {
+        self.dir(id).join(OUTPUT_FILE)
+    }
+
+    pub fn stderr_path(&self, id: &str) -> PathBuf {
+        self.dir(id).join(STDERR_FILE)
+    }
+
+    /// Create the directory and initial state of a new operation.
+    pub fn create(&self, command: &str, args: &[String]) -> Result<OperationRecord> {
+        let id = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+        let dir = self.dir(&id);
+        fs::create_dir_all(&dir).map_err(|source| OperationError::Io {
+            action: "create",
+            path: dir.clone(),
+            source,
+        })?;
+        let now = now_ms();
+        let record = OperationRecord {
+            id,
+            command: command.to_string(),
+            args: args.to_vec(),
+            pid: None,
+            state: OperationState::Running,
+            phase: None,
+            percent: None,
+            started_at_ms: now,
+            updated_at_ms: now,
+            exit_code: None,
+        };
+        self.save(&record)?;
+        Ok(record)
+    }
+
+    pub fn load(&self, id: &str) -> Result<OperationRecord> {
+        // Ids are generated by `create`; anything with a separator can only
+        // point outside the store.
+        if id.is_empty() || id.contains(['/', '\\', '.']) {
+            return Err(OperationError::NotFound(id.to_string()));
+        }
+        let path = self.dir(id).join(STATE_FILE);
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(OperationError::NotFound(id.to_string()));
+            }
+            Err(source) => {
+                return Err(OperationError::Io {
+                    action: "read",
+                    path,
+                    source,
+                });
+            }
+        };
+        serde_json::from_slice(&content).map_err(|source| OperationError::Corrupt { path, source })
+    }
+
+    /// Write the state atomically, so a concurrent `pybun status` never
+    /// reads a half-written file.
+    pub fn save(&self, record: &OperationRecord) -> Result<()> {
+        let path = self.dir(&record.id).join(STATE_FILE);
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        let content = serde_json::to_vec_pretty(record).expect("operation record serializes");
+        fs::write(&tmp, content)
+            .and_then(|_| fs::rename(&tmp, &path))
+            .map_err(|source| OperationError::Io {
+                action: "write",
+                path,
+                source,
+            })
+    }
+
+    /// Load an operation, marking it [`OperationState::Lost`] when it is
+    /// recorded as running but its process no longer exists.
+    pub fn status(&self, id: &str) -> Result<OperationRecord> {
+        let mut record = self.load(id)?;
+        if record.state == OperationState::Running && !record.pid.is_some_and(process_alive) {
+            record.state = OperationState::Lost;
+        }
+        Ok(record)
+    }
+
+    /// Remove finished operations last updated more than [`RETENTION`] ago.
+    pub fn prune(&self) {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return;
+        };
+        let cutoff = now_ms().saturating_sub(RETENTION.as_millis() as u64);
+        for entry in entries.flatten() {
+            let id = entry.file_name().to_string_lossy().into_owned();
+            if let Ok(record) = self.status(&id)
+                && record.state.is_finished()
+                && record.updated_at_ms < cutoff
+            {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+}
+
+/// Id of the operation this process runs as, if it was started by
+/// `--max-duration`.
+pub fn current_id() -> Option<String> {
+    std::env::var(OPERATION_ENV)
+        .ok()
+        .filter(|id| !id.is_empty())
+}
+
+/// Wrap `listener` so that, inside an operation, every progress event is
+/// also recorded in the operation state. Outside an operation `listener`
+/// is returned unchanged.
+pub fn with_recorder(listener: Option<EventListener>) -> Option<EventListener> {
+    let Some(id) = current_id() else {
+        return listener;
+    };
+    let Ok(store) = OperationStore::from_env() else {
+        return listener;
+    };
+    let Ok(mut record) = store.load(&id) else {
+        return listener;
+    };
+    record.pid = Some(std::process::id());
+    record.touch();
+    let _ = store.save(&record);
+
+    let mut next = listener;
+    Some(Box::new(move |event: &Event| {
+        if let Some((phase, percent)) = crate::progress::phase_of(event) {
+            record.phase = Some(phase);
+            if percent.is_some() {
+                record.percent = percent;
+            }
+            record.touch();
+            let _ = store.save(&record);
+        }
+        if let Some(next) = next.as_mut() {
+            next(event);
+        }
+    }))
+}
+
+/// Record the result of the operation this process runs as, if any.
+pub fn finish_current(exit_code: i32) {
+    let Some(id) = current_id() else {
+        return;
+    };
+    let Ok(store) = OperationStore::from_env() else {
+        return;
+    };
+    if let Ok(mut record) = store.load(&id) {
+        finish(&mut record, exit_code);
+        let _ = store.save(&record);
+    }
+}
+
+fn finish(record: &mut OperationRecord, exit_code: i32) {
+    record.exit_code = Some(exit_code);
+    if exit_code == 0 {
+        record.state = OperationState::Completed;
+        record.percent = Some(100);
+    } else {
+        record.state = OperationState::Failed;
+    }
+    record.touch();
+}
+
+/// How the parent process runs a command under `--max-duration`.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    pub args: Vec<String>,
+    pub budget: Duration,
+    pub on_timeout: TimeoutAction,
+    pub format: OutputFormat,
+}
+
+impl Supervisor {
+    /// Run the command as a child operation and return the exit code for
+    /// this process.
+    pub fn run(&self, store: &OperationStore) -> Result<i32> {
+        store.prune();
+        let path = crate::cli::subcommand_path(&self.args);
+        let command = if path.is_empty() {
+            "pybun".to_string()
+        } else {
+            format!("pybun {}", path.join(" "))
+        };
+        let mut record = store.create(&command, &self.args)?;
+        let started = Instant::now();
+        let mut child = self.spawn(store, &record)?;
+        record.pid = Some(child.id());
+
+        let deadline = started + self.budget;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return self.pass_through(store, &record.id, status),
+                Ok(None) => {}
+                Err(e) => return Err(OperationError::Spawn(e)),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+
+        // Prefer the child's own record; it carries the latest phase.
+        if let Ok(latest) = store.load(&record.id) {
+            record = latest;
+        }
+        let cancelled = self.on_timeout == TimeoutAction::Cancel;
+        if cancelled {
+            terminate(&mut child);
+            record.state = OperationState::Cancelled;
+            record.touch();
+            store.save(&record)?;
+        }
+        self.print_checkpoint(&record, started.elapsed(), cancelled);
+        Ok(if cancelled { 1 } else { 0 })
+    }
+
+    fn spawn(&self, store: &OperationStore, record: &OperationRecord) -> Result<Child> {
+        let create = |path: PathBuf| {
+            fs::File::create(&path).map_err(|source| OperationError::Io {
+                action: "create",
+                path,
+                source,
+            })
+        };
+        let stdout = create(store.output_path(&record.id))?;
+        let stderr = create(store.stderr_path(&record.id))?;
+        let exe = std::env::current_exe().map_err(OperationError::Spawn)?;
+        let mut command = Command::new(exe);
+        command
+            .args(&self.args)
+            .env(OPERATION_ENV, &record.id)
+            .stdin(Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr);
+        // A detached operation must outlive the terminal's job control, so
+        // the child gets its own process group from the start.
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        command.spawn().map_err(OperationError::Spawn)
+    }
+
+    /// The child finished in time: replay its output and exit code.
+    fn pass_through(&self, store: &OperationStore, id: &str, status: ExitStatus) -> Result<i32> {
+        let code = status.code().unwrap_or(1);
+        // A child that crashed never recorded its result.
+        if let Ok(mut record) = store.load(id)
+            && record.state == OperationState::Running
+        {
+            finish(&mut record, code);
+            store.save(&record)?;
+        }
+        copy_file(&store.stderr_path(id), &mut io::stderr())?;
+        copy_file(&store.output_path(id), &mut io::stdout())?;
+        Ok(code)
+    }
+
+    fn print_checkpoint(&self, record: &OperationRecord, elapsed: Duration, cancelled: bool) {
+        let status_command = format!("pybun status --operation {}", record.id);
+        let budget = crate::units::format_duration(self.budget);
+        let mut checkpoint = record.checkpoint(now_ms());
+        let summary = checkpoint_summary(record, &checkpoint, &budget, cancelled);
+        match self.format {
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                checkpoint["detached"] = json!(!cancelled);
+                let mut detail = json!({
+                    "operation": checkpoint,
+                    "max_duration_ms": self.budget.as_millis() as u64,
+                    "on_timeout": if cancelled { "cancel" } else { "detach" },
+                    "status_command": status_command,
+                });
+                crate::units::normalize(&mut detail, crate::units::UnitOptions::from_env());
+                let diagnostic = if cancelled {
+                    Diagnostic::error(summary)
+                        .with_code("E_MAX_DURATION_EXCEEDED")
+                        .with_suggestion(format!(
+                            "Raise --max-duration, or pass --on-timeout=detach to keep the operation running and poll `{status_command}`."
+                        ))
+                } else {
+                    Diagnostic::warning(summary)
+                        .with_code("W_MAX_DURATION_DETACHED")
+                        .with_suggestion(format!(
+                            "Poll `{status_command}` for progress and the result."
+                        ))
+                };
+                let status = if cancelled { Status::Error } else { Status::Ok };
+                let envelope = JsonEnvelope::new(record.command.clone(), status, elapsed, detail)
+                    .with_diagnostics(vec![diagnostic]);
+                if self.format == OutputFormat::Ndjson {
+                    let _ = envelope.write_ndjson(std::io::stdout().lock());
+                } else {
+                    println!("{}", envelope.to_json());
+                }
+            }
+            OutputFormat::Text | OutputFormat::Tsv => {
+                println!("{}: {summary}; see `{status_command}`", record.command);
+            }
+        }
+    }
+}
+
+fn checkpoint_summary(
+    record: &OperationRecord,
+    checkpoint: &Value,
+    budget: &str,
+    cancelled: bool,
+) -> String {
+    let mut progress = record
+        .phase
+        .clone()
+        .unwrap_or_else(|| "starting".to_string());
+    if let Some(percent) = record.percent {
+        progress.push_str(&format!(", {percent}%"));
+    }
+    if let Some(eta) = checkpoint["eta_ms"].as_u64() {
+        progress.push_str(&format!(
+            ", ETA {}",
+            crate::units::format_duration(Duration::from_millis(eta))
+        ));
+    }
+    let outcome = if cancelled {
+        "cancelled"
+    } else {
+        "still running in the background"
+    };
+    format!(
+        "exceeded --max-duration {budget} ({progress}); operation {} {outcome}",
+        record.id
+    )
+}
+
+fn terminate(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // The child leads its own process group; signal the whole group so
+        // that processes it started (e.g. the interpreter under `pybun run`)
+        // stop with it.
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM);
+        }
+        let deadline = Instant::now() + CANCEL_GRACE;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists. EPERM means it exists
+    // but belongs to another user.
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    // Without a cheap liveness check, trust the recorded state.
+    true
+}
+
+fn copy_file(path: &Path, writer: &mut impl io::Write) -> Result<()> {
+    let io_error = |source| OperationError::Io {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut file = fs::File::open(path).map_err(io_error)?;
+    io::copy(&mut file, writer).map_err(io_error)?;
+    writer.flush().map_err(io_error)
+}
+
+/// Milliseconds since the Unix epoch.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Parse a duration such as `500ms`, `90s`, `5m`, `1h30m`, `30d`, `2w` or a
+/// bare number of seconds.
+pub fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    let input = s.trim();
+    if input.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(format!(
+                "invalid duration `{s}` (expected e.g. 90s, 5m or 1h30m)"
+            ));
+        }
+        let value: u64 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid duration `{s}`"))?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ms" => Duration::from_millis(value),
+            "s" => Duration::from_secs(value),
+            "m" => Duration::from_secs(value * 60),
+            "h" => Duration::from_secs(value * 3600),
+            "d" => Duration::from_secs(value * 86_400),
+            "w" => Duration::from_secs(value * 7 * 86_400),
+            other => {
+                return Err(format!(
+                    "invalid duration unit `{other}` in `{s}` (use ms, s, m, h, d or w)"
+                ));
+            }
+        };
+        total += unit;
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(state: OperationState, percent: Option<u8>) -> OperationRecord {
+        OperationRecord {
+            id: "abc123".to_string(),
+            command: "pybun install".to_string(),
+            args: vec!["install".to_string()],
+            pid: None,
+            state,
+            phase: Some("Downloading artifacts".to_string()),
+            percent,
+            started_at_ms: 1_000,
+            updated_at_ms: 1_000,
+            exit_code: None,
+        }
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(30 * 86_400)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(14 * 86_400)
+        );
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("m5").is_err());
+    }
+
+    #[test]
+    fn checkpoint_extrapolates_eta_from_percent() {
+        let checkpoint = record(OperationState::Running, Some(25)).checkpoint(5_000);
+        assert_eq!(checkpoint["elapsed_ms"], 4_000);
+        assert_eq!(checkpoint["eta_ms"], 12_000);
+        assert_eq!(checkpoint["state"], "running");
+
+        let unknown = record(OperationState::Running, None).checkpoint(5_000);
+        assert!(unknown["eta_ms"].is_null());
+        let done = record(OperationState::Completed, Some(100)).checkpoint(5_000);
+        assert!(done["eta_ms"].is_null());
+    }
+
+    #[test]
+    fn store_round_trips_and_rejects_foreign_ids() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = OperationStore::new(temp.path());
+        let mut created = store.create("pybun lock", &["lock".to_string()]).unwrap();
+        assert_eq!(store.load(&created.id).unwrap(), created);
+
+        finish(&mut created, 0);
+        store.save(&created).unwrap();
+        let loaded = store.status(&created.id).unwrap();
+        assert_eq!(loaded.state, OperationState::Completed);
+        assert_eq!(loaded.percent, Some(100));
+
+        assert!(matches!(
+            store.load("../etc"),
+            Err(OperationError::NotFound(_))
+        ));
+        assert!(matches!(
+            store.load("missing"),
+            Err(OperationError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn running_operation_without_process_is_lost() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = OperationStore::new(temp.path());
+        let mut created = store.create("pybun lock", &[]).unwrap();
+        created.pid = None;
+        store.save(&created).unwrap();
+        assert_eq!(
+            store.status(&created.id).unwrap().state,
+            OperationState::Lost
+        );
+    }
+
+    #[test]
+    fn prune_keeps_running_and_recent_operations() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = OperationStore::new(temp.path());
+        let mut old = store.create("pybun lock", &[]).unwrap();
+        finish(&mut old, 1);
+        old.updated_at_ms = 0;
+        store.save(&old).unwrap();
+        let mut recent = store.create("pybun lock", &[]).unwrap();
+        finish(&mut recent, 0);
+        store.save(&recent).unwrap();
+
+        store.prune();
+        assert!(!store.dir(&old.id).exists());
+        assert!(store.dir(&recent.id).exists());
+    }
+}
@@ -17,7 +17,9 @@
 //! │   ├── index/                # Cached package indexes
 //! │   └── build/                # Build artifacts
 //! ├── envs/                     # Virtual environments
-//! └── logs/                     # Structured logs
+//! ├── logs/                     # Structured logs
+//! ├── operations/               # `--max-duration` operation state and output
+//! └── payloads/                 # JSON output sections spilled to disk
 //! ```
 
 use std::env;
@@ -127,6 +129,16 @@ impl PyBunPaths {
         self.root.join("logs")
     }
 
+    /// Oversized JSON output sections (see [`crate::payload`]).
+    pub fn payloads_dir(&self) -> PathBuf {
+        self.root.join("payloads")
+    }
+
+    /// Operations started with `--max-duration` (see [`crate::operation`]).
+    pub fn operations_dir(&self) -> PathBuf {
+        self.root.join("operations")
+    }
+
     /// Ensure all required directories exist.
     pub fn ensure_dirs(&self) -> Result<()> {
         let dirs = [
@@ -0,0 +1,569 @@
+//! Spill oversized sections of a JSON `detail` payload to disk.
+//!
+//! Commands embed captured stdout/stderr, test results, and file lists in
+//! their JSON envelope, which on a big project can run to hundreds of
+//! megabytes. Child output is the bulk of it, so `pybun run` and `pybun test`
+//! capture it through a [`Capture`]: past `--max-inline-bytes` the output is
+//! streamed to a file as it arrives and only a short tail stays in memory.
+//! [`spill_oversized`] then runs just before rendering and replaces every
+//! remaining string or array whose serialized size exceeds the limit with
+//! the same kind of reference object:
+//!
+//! ```json
+//! { "spilled": true, "path": "/…/payloads/<run>/000-stdout.txt",
+//!   "format": "text", "sha256": "…", "size_bytes": 73400320,
+//!   "preview": "first 256 characters…" }
+//! ```
+//!
+//! Strings are written verbatim (`format: "text"`); arrays are written one
+//! JSON value per line (`format: "ndjson"`) so consumers can stream them.
+//! Files live under `$PYBUN_HOME/payloads/<run-id>/`, one directory per
+//! process; run directories older than [`RETENTION`] are removed on the next
+//! spill. `--max-inline-bytes=0` (or `PYBUN_MAX_INLINE_BYTES=0`) keeps
+//! everything inline.
+
+use serde::{Serialize, Serializer};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// How long spilled run directories are kept.
+pub const RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Characters of a spilled string kept inline as `preview`.
+const PREVIEW_CHARS: usize = 256;
+/// Bytes a capture keeps for its preview: enough for [`PREVIEW_CHARS`]
+/// characters of any UTF-8.
+const PREVIEW_HEAD_BYTES: usize = PREVIEW_CHARS * 4;
+/// Bytes of spilled child output kept in memory, for summary-line checks
+/// and traceback parsing.
+pub const TAIL_BYTES: usize = 16 * 1024;
+
+static MAX_INLINE_BYTES: AtomicU64 = AtomicU64::new(0);
+static CAPTURES: AtomicUsize = AtomicUsize::new(0);
+static CAPTURED: Mutex<Vec<SpilledSection>> = Mutex::new(Vec::new());
+
+/// Set the limit [`Capture::for_output`] spills at for the rest of the
+/// process. Only set for JSON output; 0 (the default) keeps output in memory.
+pub fn set_max_inline_bytes(limit: u64) {
+    MAX_INLINE_BYTES.store(limit, Ordering::Relaxed);
+}
+
+pub fn max_inline_bytes() -> u64 {
+    MAX_INLINE_BYTES.load(Ordering::Relaxed)
+}
+
+/// Drain the sections spilled by captures so far.
+pub fn take_captured() -> Vec<SpilledSection> {
+    std::mem::take(&mut *CAPTURED.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// A section moved out of the envelope.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpilledSection {
+    /// Dotted location in `detail`, e.g. `stdout` or `tests`.
+    pub location: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// Where and when to spill.
+#[derive(Debug, Clone)]
+pub struct SpillOptions {
+    /// Sections larger than this (serialized) are spilled; 0 disables.
+    pub max_inline_bytes: u64,
+    /// Parent of the per-run directories (`$PYBUN_HOME/payloads`).
+    pub root: PathBuf,
+}
+
+impl SpillOptions {
+    pub fn new(max_inline_bytes: u64) -> io::Result<Self> {
+        let paths = crate::paths::PyBunPaths::new().map_err(io::Error::other)?;
+        Ok(Self {
+            max_inline_bytes,
+            root: paths.payloads_dir(),
+        })
+    }
+}
+
+struct Spiller<'a> {
+    options: &'a SpillOptions,
+    run_dir: Option<PathBuf>,
+    spilled: Vec<SpilledSection>,
+}
+
+/// Replace oversized strings and arrays in `detail` with references to
+/// files holding their content. Returns what was spilled.
+pub fn spill_oversized(
+    detail: &mut Value,
+    options: &SpillOptions,
+) -> io::Result<Vec<SpilledSection>> {
+    if options.max_inline_bytes == 0 {
+        return Ok(Vec::new());
+    }
+    let mut spiller = Spiller {
+        options,
+        run_dir: None,
+        spilled: Vec::new(),
+    };
+    spiller.visit(detail, &mut Vec::new())?;
+    Ok(spiller.spilled)
+}
+
+impl Spiller<'_> {
+    /// Spill oversized children of `value` bottom-up, then `value` itself if
+    /// it is still too large. Returns its (approximate) serialized size.
+    fn visit(&mut self, value: &mut Value, location: &mut Vec<String>) -> io::Result<u64> {
+        let size = match value {
+            Value::Object(map) => {
+                let mut size = 2;
+                for (key, child) in map.iter_mut() {
+                    location.push(key.clone());
+                    size += key.len() as u64 + 4 + self.visit(child, location)?;
+                    location.pop();
+                }
+                return Ok(size);
+            }
+            Value::Array(items) => {
+                let mut size = 2;
+                for (index, child) in items.iter_mut().enumerate() {
+                    location.push(index.to_string());
+                    size += 1 + self.visit(child, location)?;
+                    location.pop();
+                }
+                size
+            }
+            Value::String(text) => text.len() as u64 + 2,
+            other => return Ok(other.to_string().len() as u64),
+        };
+        if size <= self.options.max_inline_bytes {
+            return Ok(size);
+        }
+        let reference = self.spill(value, &location.join("."))?;
+        let reference_size = reference.to_string().len() as u64;
+        *value = reference;
+        Ok(reference_size)
+    }
+
+    fn run_dir(&mut self) -> io::Result<&Path> {
+        if self.run_dir.is_none() {
+            self.run_dir = Some(run_dir(&self.options.root)?);
+        }
+        Ok(self.run_dir.as_deref().expect("run dir set above"))
+    }
+
+    fn spill(&mut self, value: &Value, location: &str) -> io::Result<Value> {
+        let (extension, format) = match value {
+            Value::String(_) => ("txt", "text"),
+            _ => ("ndjson", "ndjson"),
+        };
+        let name: String = location
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(80)
+            .collect();
+        let index = self.spilled.len();
+        let path = self
+            .run_dir()?
+            .join(format!("{index:03}-{}.{extension}", name.trim_matches('.')));
+
+        let mut writer = HashingWriter::new(BufWriter::new(fs::File::create(&path)?));
+        let preview = match value {
+            Value::String(text) => {
+                writer.write_all(text.as_bytes())?;
+                json!(text.chars().take(PREVIEW_CHARS).collect::<String>())
+            }
+            Value::Array(items) => {
+                for item in items {
+                    serde_json::to_writer(&mut writer, item)?;
+                    writer.write_all(b"\n")?;
+                }
+                json!(items.len())
+            }
+            _ => Value::Null,
+        };
+        writer.flush()?;
+        let (size_bytes, sha256) = writer.finish();
+
+        self.spilled.push(SpilledSection {
+            location: location.to_string(),
+            path: path.clone(),
+            size_bytes,
+        });
+        let mut reference = json!({
+            "spilled": true,
+            "path": path.display().to_string(),
+            "format": format,
+            "sha256": sha256,
+            "size_bytes": size_bytes,
+        });
+        if value.is_string() {
+            reference["preview"] = preview;
+        } else {
+            reference["items"] = preview;
+        }
+        Ok(reference)
+    }
+}
+
+/// Streaming sink for a child process's stdout or stderr.
+///
+/// Output is held in memory up to the limit. Past it, what was buffered and
+/// everything after is written to `<run-dir>/capture-NNN-<label>.txt` as it
+/// arrives, and only the last [`TAIL_BYTES`] stay in memory.
+pub struct Capture {
+    label: &'static str,
+    options: SpillOptions,
+    /// All output while inline; the tail once spilled.
+    buffer: Vec<u8>,
+    spill: Option<SpillFile>,
+}
+
+struct SpillFile {
+    path: PathBuf,
+    writer: HashingWriter<BufWriter<fs::File>>,
+    /// First bytes of the output, for `preview`.
+    head: Vec<u8>,
+}
+
+impl Capture {
+    pub fn new(label: &'static str, options: SpillOptions) -> Self {
+        Self {
+            label,
+            options,
+            buffer: Vec::new(),
+            spill: None,
+        }
+    }
+
+    /// A capture spilling at the process-wide limit ([`set_max_inline_bytes`]).
+    /// Without a usable `$PYBUN_HOME` the output stays in memory.
+    pub fn for_output(label: &'static str) -> Self {
+        let options = SpillOptions::new(max_inline_bytes()).unwrap_or(SpillOptions {
+            max_inline_bytes: 0,
+            root: PathBuf::new(),
+        });
+        Self::new(label, options)
+    }
+
+    /// Copy `reader` to the end into this capture.
+    pub fn drain(mut self, mut reader: impl Read) -> io::Result<CapturedOutput> {
+        io::copy(&mut reader, &mut self)?;
+        self.finish()
+    }
+
+    /// Bytes currently held in memory.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn start_spill(&mut self) -> io::Result<()> {
+        let index = CAPTURES.fetch_add(1, Ordering::Relaxed);
+        let path =
+            run_dir(&self.options.root)?.join(format!("capture-{index:03}-{}.txt", self.label));
+        let mut writer = HashingWriter::new(BufWriter::new(fs::File::create(&path)?));
+        writer.write_all(&self.buffer)?;
+        let mut head = self.buffer.clone();
+        head.truncate(PREVIEW_HEAD_BYTES);
+        self.spill = Some(SpillFile { path, writer, head });
+        self.keep_tail();
+        Ok(())
+    }
+
+    fn keep_tail(&mut self) {
+        if self.buffer.len() > TAIL_BYTES {
+            self.buffer.drain(..self.buffer.len() - TAIL_BYTES);
+        }
+    }
+
+    pub fn finish(self) -> io::Result<CapturedOutput> {
+        let text = String::from_utf8_lossy(&self.buffer).into_owned();
+        let Some(mut spill) = self.spill else {
+            return Ok(CapturedOutput {
+                text,
+                spilled: None,
+            });
+        };
+        spill.writer.flush()?;
+        let (size_bytes, sha256) = spill.writer.finish();
+        CAPTURED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(SpilledSection {
+                location: self.label.to_string(),
+                path: spill.path.clone(),
+                size_bytes,
+            });
+        let reference = json!({
+            "spilled": true,
+            "path": spill.path.display().to_string(),
+            "format": "text",
+            "sha256": sha256,
+            "size_bytes": size_bytes,
+            "preview": String::from_utf8_lossy(&spill.head)
+                .chars()
+                .take(PREVIEW_CHARS)
+                .collect::<String>(),
+        });
+        Ok(CapturedOutput {
+            text,
+            spilled: Some((spill.path, reference)),
+        })
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let limit = self.options.max_inline_bytes;
+        if self.spill.is_none() && limit > 0 && (self.buffer.len() + buf.len()) as u64 > limit {
+            self.start_spill()?;
+        }
+        if let Some(spill) = &mut self.spill {
+            spill.writer.write_all(buf)?;
+            let missing = PREVIEW_HEAD_BYTES.saturating_sub(spill.head.len());
+            spill.head.extend_from_slice(&buf[..missing.min(buf.len())]);
+            self.buffer.extend_from_slice(buf);
+            self.keep_tail();
+        } else {
+            self.buffer.extend_from_slice(buf);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.spill {
+            Some(spill) => spill.writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Output collected by a [`Capture`]. Serializes as the text, or as the
+/// spill reference once the output went to disk.
+#[derive(Debug, Clone, Default)]
+pub struct CapturedOutput {
+    /// Everything captured, or only its last [`TAIL_BYTES`] when spilled.
+    pub text: String,
+    spilled: Option<(PathBuf, Value)>,
+}
+
+impl CapturedOutput {
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.spilled.is_none()
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        self.spilled.is_some()
+    }
+
+    /// Write the whole output to `out`, streaming it back from disk when it
+    /// was spilled.
+    pub fn copy_to(&self, out: &mut impl Write) -> io::Result<()> {
+        match self.spill_path() {
+            Some(path) => io::copy(&mut fs::File::open(path)?, out).map(drop),
+            None => out.write_all(self.text.as_bytes()),
+        }
+    }
+
+    fn spill_path(&self) -> Option<&Path> {
+        self.spilled.as_ref().map(|(path, _)| path.as_path())
+    }
+}
+
+impl Serialize for CapturedOutput {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.spilled {
+            Some((_, reference)) => reference.serialize(serializer),
+            None => self.text.serialize(serializer),
+        }
+    }
+}
+
+/// This process's run directory under `root`, created on first use.
+fn run_dir(root: &Path) -> io::Result<PathBuf> {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    let id = RUN_ID.get_or_init(|| uuid::Uuid::new_v4().simple().to_string());
+    let dir = root.join(id);
+    if !dir.is_dir() {
+        prune_old_runs(root);
+        fs::create_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+/// Remove run directories older than [`RETENTION`]; failures are ignored.
+fn prune_old_runs(root: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > RETENTION);
+        if expired {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+/// Writer that tracks the SHA-256 and length of everything written.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    len: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            len: 0,
+        }
+    }
+
+    fn finish(self) -> (u64, String) {
+        (self.len, hex::encode(self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(dir: &Path, max: u64) -> SpillOptions {
+        SpillOptions {
+            max_inline_bytes: max,
+            root: dir.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn large_strings_are_replaced_by_hashed_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let stdout = "x".repeat(4096);
+        let mut detail = json!({ "stdout": stdout, "exit_code": 0 });
+
+        let spilled = spill_oversized(&mut detail, &options(dir.path(), 1024)).unwrap();
+        assert_eq!(spilled.len(), 1);
+        assert_eq!(spilled[0].location, "stdout");
+        assert_eq!(detail["exit_code"], 0);
+        assert_eq!(detail["stdout"]["spilled"], true);
+        assert_eq!(detail["stdout"]["size_bytes"], 4096);
+        assert_eq!(
+            detail["stdout"]["sha256"],
+            hex::encode(Sha256::digest(stdout.as_bytes()))
+        );
+        assert_eq!(
+            detail["stdout"]["preview"].as_str().unwrap().len(),
+            PREVIEW_CHARS
+        );
+        let path = detail["stdout"]["path"].as_str().unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), stdout);
+    }
+
+    #[test]
+    fn large_arrays_are_written_as_ndjson() {
+        let dir = tempfile::tempdir().unwrap();
+        let tests: Vec<Value> = (0..200)
+            .map(|i| json!({ "name": format!("test_{i}"), "outcome": "passed" }))
+            .collect();
+        let mut detail = json!({ "summary": { "passed": 200 }, "tests": tests });
+
+        spill_oversized(&mut detail, &options(dir.path(), 1024)).unwrap();
+        assert_eq!(detail["summary"]["passed"], 200);
+        assert_eq!(detail["tests"]["format"], "ndjson");
+        assert_eq!(detail["tests"]["items"], 200);
+        let content = fs::read_to_string(detail["tests"]["path"].as_str().unwrap()).unwrap();
+        let first: Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(first["name"], "test_0");
+    }
+
+    #[test]
+    fn captures_stream_past_the_limit_to_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut capture = Capture::new("stdout", options(dir.path(), 1024));
+        let line = b"collected 1 item ................................ PASSED\n";
+        let mut written = Vec::new();
+        for _ in 0..20_000 {
+            capture.write_all(line).unwrap();
+            written.extend_from_slice(line);
+            assert!(capture.buffered_len() <= TAIL_BYTES + line.len());
+        }
+        capture.write_all(b"=== 1 passed ===\n").unwrap();
+        written.extend_from_slice(b"=== 1 passed ===\n");
+
+        let output = capture.finish().unwrap();
+        assert!(output.is_spilled());
+        let preview: String = String::from_utf8_lossy(&written)
+            .chars()
+            .take(PREVIEW_CHARS)
+            .collect();
+        assert!(output.text.len() <= TAIL_BYTES);
+        assert!(output.text.ends_with("=== 1 passed ===\n"));
+        let reference = serde_json::to_value(&output).unwrap();
+        assert_eq!(reference["spilled"], true);
+        assert_eq!(reference["preview"], preview);
+        assert_eq!(reference["size_bytes"], written.len() as u64);
+        assert_eq!(reference["sha256"], hex::encode(Sha256::digest(&written)));
+        let mut copied = Vec::new();
+        output.copy_to(&mut copied).unwrap();
+        assert_eq!(copied, written);
+        assert!(
+            take_captured()
+                .iter()
+                .any(|section| section.location == "stdout")
+        );
+    }
+
+    #[test]
+    fn small_captures_stay_inline() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = Capture::new("stderr", options(dir.path(), 1024))
+            .drain(&b"warning\n"[..])
+            .unwrap();
+        assert!(!output.is_spilled());
+        assert_eq!(serde_json::to_value(&output).unwrap(), json!("warning\n"));
+        assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn zero_limit_keeps_everything_inline() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut detail = json!({ "stdout": "x".repeat(4096) });
+        let before = detail.clone();
+        assert!(
+            spill_oversized(&mut detail, &options(dir.path(), 0))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(detail, before);
+        assert!(fs::read_dir(dir.path()).unwrap().next().is_none());
+    }
+}
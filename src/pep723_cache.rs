@@ -10,9 +10,14 @@
 //!     venv/           # The actual virtual environment
 //!     deps.json       # Dependency list for debugging
 //! ```
+//!
+//! In read-only cache mode (`PYBUN_READONLY_CACHE`) new environments are
+//! created in the overlay, and environments already present in the shared
+//! cache are reused as-is.
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::Write;
@@ -127,23 +132,44 @@ impl Pep723CacheKey {
 #[derive(Debug, Clone)]
 pub struct Pep723Cache {
     root: PathBuf,
+    /// Read-only lower layer in read-only cache mode.
+    shared: Option<PathBuf>,
 }
 
 impl Pep723Cache {
     /// Create a new cache instance using default or env-configured location.
     pub fn new() -> Result<Self> {
-        let root = if let Ok(home) = std::env::var("PYBUN_HOME") {
-            PathBuf::from(home)
-        } else {
-            let home_dir = dirs::home_dir().ok_or(Pep723CacheError::NoHomeDir)?;
-            home_dir.join(".cache/pybun")
-        };
-        Ok(Self { root })
+        let cache = crate::cache::Cache::new().map_err(|_| Pep723CacheError::NoHomeDir)?;
+        Ok(Self {
+            root: cache.root().to_path_buf(),
+            shared: cache.shared_root().map(Path::to_path_buf),
+        })
     }
 
     /// Create a cache instance with a custom root directory (useful for testing).
     pub fn with_root(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self {
+            root: root.into(),
+            shared: None,
+        }
+    }
+
+    /// Create a cache that writes under `overlay` and reuses environments
+    /// from `shared`.
+    pub fn with_layers(layers: crate::cache::CacheLayers) -> Self {
+        Self {
+            root: layers.overlay,
+            shared: Some(layers.shared),
+        }
+    }
+
+    /// The same location as `path` (under the writable root) in the shared
+    /// layer.
+    fn shared_equivalent(&self, path: &Path) -> Option<PathBuf> {
+        let shared = self.shared.as_ref()?;
+        path.strip_prefix(&self.root)
+            .ok()
+            .map(|relative| shared.join(relative))
     }
 
     /// Root directory for PEP 723 venv cache
@@ -238,15 +264,23 @@ impl Pep723Cache {
         if venv_path.exists() && python_path.exists() {
             // Update last_used timestamp
             let _ = self.update_last_used(hash);
-            Some(CachedEnvPath {
+            return Some(CachedEnvPath {
                 hash: hash.to_string(),
                 venv_path,
                 python_path,
                 cache_hit: true,
-            })
-        } else {
-            None
+            });
         }
+
+        // Shared layer: reuse without touching its metadata.
+        let venv_path = self.shared_equivalent(&venv_path)?;
+        let python_path = self.shared_equivalent(&python_path)?;
+        (venv_path.exists() && python_path.exists()).then(|| CachedEnvPath {
+            hash: hash.to_string(),
+            venv_path,
+            python_path,
+            cache_hit: true,
+        })
     }
 
     /// Fast cache validation using mtime comparison.
@@ -267,7 +301,10 @@ impl Pep723Cache {
 
         // Fast path: check if venv and deps.json exist
         if !venv_path.exists() || !python_path.exists() || !deps_json_path.exists() {
-            return None;
+            return self
+                .shared_equivalent(cache_root)
+                .filter(|shared_root| shared_root.exists())
+                .and_then(|shared_root| self.get_cached_env_fast(script_path, &shared_root));
         }
 
         // Mtime-based validation: if script is older than cache, skip hash check
@@ -278,8 +315,10 @@ impl Pep723Cache {
         {
             // Script hasn't been modified since cache was created
             if script_mtime <= cache_mtime {
-                // Update last_used timestamp (throttled)
-                let _ = self.update_last_used_at(cache_root);
+                // Update last_used timestamp (throttled); never in the shared layer.
+                if cache_root.starts_with(&self.root) {
+                    let _ = self.update_last_used_at(cache_root);
+                }
 
                 // Return cached environment
                 return Some(CachedEnvPath {
@@ -535,6 +574,18 @@ impl Pep723Cache {
         Self::dir_size(&envs_dir)
     }
 
+    /// Record a hit or miss for the environment keyed by `hash` in the cache
+    /// stats (see [`crate::cache_stats`]).
+    pub fn record_lookup(&self, hash: &str, hit: bool) {
+        crate::cache_stats::CacheStats::for_root(&self.root)
+            .record(&crate::cache_stats::pep723_key(hash), hit);
+    }
+
+    /// Size of the cached environment for `hash`.
+    pub fn env_size(&self, hash: &str) -> Result<u64> {
+        Self::dir_size(&self.cache_dir_for_hash(hash))
+    }
+
     fn dir_size(path: &Path) -> Result<u64> {
         let mut total = 0;
         if path.is_dir() {
@@ -554,13 +605,35 @@ impl Pep723Cache {
     /// Run garbage collection on PEP 723 venv cache.
     /// Removes least recently used environments until under max_bytes.
     pub fn gc(&self, max_bytes: Option<u64>, dry_run: bool) -> Result<Pep723GcResult> {
+        self.gc_excluding(max_bytes, dry_run, &BTreeSet::new())
+    }
+
+    /// [`Pep723Cache::gc`] treating the environments in `planned` (hashes)
+    /// as already evicted.
+    pub fn gc_excluding(
+        &self,
+        max_bytes: Option<u64>,
+        dry_run: bool,
+        planned: &BTreeSet<String>,
+    ) -> Result<Pep723GcResult> {
         let mut result = Pep723GcResult::default();
 
         let mut envs = self.list_cached_envs()?;
-        // Sort by last_used (oldest first for LRU eviction)
-        envs.sort_by_key(|e| e.last_used);
-
-        result.size_before = self.total_size()?;
+        let planned_bytes: u64 = envs
+            .iter()
+            .filter(|env| planned.contains(&env.hash))
+            .map(|env| Self::dir_size(&self.cache_dir_for_hash(&env.hash)).unwrap_or(0))
+            .sum();
+        envs.retain(|env| !planned.contains(&env.hash));
+        // Sort by last_used (oldest first for LRU eviction), then hash so
+        // dry runs are deterministic.
+        envs.sort_by(|a, b| {
+            a.last_used
+                .cmp(&b.last_used)
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+
+        result.size_before = self.total_size()?.saturating_sub(planned_bytes);
         let max_bytes = max_bytes.unwrap_or(u64::MAX);
         let mut current_size = result.size_before;
 
@@ -728,6 +801,43 @@ mod tests {
         assert!(cache.get_cached_env(&key).is_none());
     }
 
+    #[test]
+    fn get_cached_env_falls_back_to_shared_layer() {
+        let temp = tempdir().unwrap();
+        let layers = crate::cache::CacheLayers {
+            shared: temp.path().join("shared"),
+            overlay: temp.path().join("overlay"),
+        };
+        let deps = vec!["numpy".to_string()];
+        let key = Pep723CacheKey::new(&deps, "3.11.0", &[], None);
+
+        let shared = Pep723Cache::with_root(&layers.shared);
+        let python = shared.python_path_for_hash(&key.hash);
+        fs::create_dir_all(python.parent().unwrap()).unwrap();
+        fs::write(&python, "").unwrap();
+
+        let cache = Pep723Cache::with_layers(layers);
+        let hit = cache.get_cached_env(&key).unwrap();
+        assert_eq!(hit.python_path, python);
+        assert!(cache.envs_dir().starts_with(temp.path().join("overlay")));
+
+        // The script-keyed fast path also reuses shared environments.
+        let script = temp.path().join("script.py");
+        fs::write(&script, "print(1)").unwrap();
+        let shared_root = shared.script_env_root(&script).unwrap();
+        let python = shared.python_path_for_venv(&shared.venv_path_for_root(&shared_root));
+        fs::create_dir_all(python.parent().unwrap()).unwrap();
+        fs::write(&python, "").unwrap();
+        fs::write(shared_root.join("deps.json"), "{}").unwrap();
+        let overlay_root = cache.script_env_root(&script).unwrap();
+        let hit = cache.get_cached_env_fast(&script, &overlay_root).unwrap();
+        assert_eq!(hit.python_path, python);
+        assert_eq!(
+            fs::read_to_string(shared_root.join("deps.json")).unwrap(),
+            "{}"
+        );
+    }
+
     #[test]
     fn prepare_cache_dir_creates_dir() {
         let temp = tempdir().unwrap();
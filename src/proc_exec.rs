@@ -7,20 +7,22 @@
 //! child if it exceeds a wall-clock timeout. This module is the single
 //! implementation both call sites delegate to (Issue #273).
 
-use std::io::Read;
+use crate::payload::{Capture, CapturedOutput};
+use std::io::{Read, Write};
 use std::process::{Command, ExitStatus, Stdio};
 use std::thread;
 use std::time::{Duration, Instant};
 
 /// Outcome of running a command to completion, possibly subject to a
-/// wall-clock timeout.
-pub enum ProcExecOutcome {
+/// wall-clock timeout. `T` is what a captured pipe yields: its bytes, or a
+/// [`CapturedOutput`] for [`spawn_capturing`].
+pub enum ProcExecOutcome<T = Vec<u8>> {
     /// The process exited on its own (or no timeout was configured).
     Completed {
         status: ExitStatus,
         /// `Some` only when `capture` was requested by the caller.
-        stdout: Option<Vec<u8>>,
-        stderr: Option<Vec<u8>>,
+        stdout: Option<T>,
+        stderr: Option<T>,
     },
     /// The process was killed because it exceeded the configured timeout.
     TimedOut,
@@ -38,17 +40,104 @@ pub fn spawn_with_timeout(
     timeout_secs: Option<u64>,
     capture: bool,
 ) -> std::io::Result<ProcExecOutcome> {
+    spawn_with_input(cmd, timeout_secs.map(Duration::from_secs), capture, None)
+}
+
+/// [`spawn_with_timeout`] with a `Duration` timeout, writing `input` (if
+/// any) to the child's stdin from a background thread before closing it.
+pub fn spawn_with_input(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    capture: bool,
+    input: Option<&[u8]>,
+) -> std::io::Result<ProcExecOutcome> {
+    spawn_reading(cmd, timeout, capture, input, |pipe, _| {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// [`spawn_with_input`] capturing into [`Capture`]s, so output past
+/// `--max-inline-bytes` goes to disk as it arrives instead of into memory.
+pub fn spawn_capturing(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    capture: bool,
+) -> std::io::Result<ProcExecOutcome<CapturedOutput>> {
+    let outcome = spawn_reading(cmd, timeout, capture, None, |pipe, label| {
+        Capture::for_output(label).drain(pipe)
+    })?;
+    Ok(match outcome {
+        ProcExecOutcome::Completed {
+            status,
+            stdout,
+            stderr,
+        } => ProcExecOutcome::Completed {
+            status,
+            stdout: stdout.transpose()?,
+            stderr: stderr.transpose()?,
+        },
+        ProcExecOutcome::TimedOut => ProcExecOutcome::TimedOut,
+    })
+}
+
+/// Run `cmd` to completion (no timeout) through [`spawn_capturing`].
+pub fn output_capturing(
+    cmd: &mut Command,
+) -> std::io::Result<(ExitStatus, CapturedOutput, CapturedOutput)> {
+    match spawn_capturing(cmd, None, true)? {
+        ProcExecOutcome::Completed {
+            status,
+            stdout,
+            stderr,
+        } => Ok((
+            status,
+            stdout.unwrap_or_default(),
+            stderr.unwrap_or_default(),
+        )),
+        ProcExecOutcome::TimedOut => unreachable!("no timeout was set"),
+    }
+}
+
+type PipeReader<T> = fn(&mut (dyn Read + Send), &'static str) -> T;
+
+fn spawn_reading<T: Send + 'static>(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    capture: bool,
+    input: Option<&[u8]>,
+    read: PipeReader<T>,
+) -> std::io::Result<ProcExecOutcome<T>> {
     if capture {
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
     }
+    if input.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
 
     let mut child = cmd.spawn()?;
 
-    let stdout_handle = child.stdout.take().map(spawn_pipe_reader);
-    let stderr_handle = child.stderr.take().map(spawn_pipe_reader);
+    let stdin_handle = match (child.stdin.take(), input) {
+        (Some(mut stdin), Some(input)) => {
+            let input = input.to_vec();
+            Some(thread::spawn(move || {
+                let _ = stdin.write_all(&input);
+            }))
+        }
+        _ => None,
+    };
+
+    let stdout_handle = child
+        .stdout
+        .take()
+        .map(|mut pipe| thread::spawn(move || read(&mut pipe, "stdout")));
+    let stderr_handle = child
+        .stderr
+        .take()
+        .map(|mut pipe| thread::spawn(move || read(&mut pipe, "stderr")));
 
-    let timeout = timeout_secs.map(Duration::from_secs);
     let poll_interval = Duration::from_millis(50);
     let start = Instant::now();
 
@@ -62,6 +151,9 @@ pub fn spawn_with_timeout(
         {
             let _ = child.kill();
             let _ = child.wait();
+            if let Some(handle) = stdin_handle {
+                let _ = handle.join();
+            }
             join_pipe_reader(stdout_handle);
             join_pipe_reader(stderr_handle);
             return Ok(ProcExecOutcome::TimedOut);
@@ -70,6 +162,9 @@ pub fn spawn_with_timeout(
         thread::sleep(poll_interval);
     };
 
+    if let Some(handle) = stdin_handle {
+        let _ = handle.join();
+    }
     let stdout = join_pipe_reader(stdout_handle);
     let stderr = join_pipe_reader(stderr_handle);
 
@@ -80,21 +175,9 @@ pub fn spawn_with_timeout(
     })
 }
 
-/// Spawn a thread that reads a child process pipe to completion.
-pub fn spawn_pipe_reader<R>(mut reader: R) -> thread::JoinHandle<Vec<u8>>
-where
-    R: Read + Send + 'static,
-{
-    thread::spawn(move || {
-        let mut buf = Vec::new();
-        let _ = reader.read_to_end(&mut buf);
-        buf
-    })
-}
-
 /// Join a pipe reader thread, discarding the handle. Returns `None` if there
 /// was no pipe to read or the thread panicked.
-pub fn join_pipe_reader(handle: Option<thread::JoinHandle<Vec<u8>>>) -> Option<Vec<u8>> {
+pub fn join_pipe_reader<T>(handle: Option<thread::JoinHandle<T>>) -> Option<T> {
     handle.and_then(|h| h.join().ok())
 }
 
@@ -190,6 +273,20 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn feeds_input_to_stdin() {
+        let mut cmd = Command::new("cat");
+        let outcome = spawn_with_input(&mut cmd, None, true, Some(b"fed through stdin"))
+            .expect("spawn should succeed");
+        match outcome {
+            ProcExecOutcome::Completed { stdout, .. } => {
+                assert_eq!(stdout.unwrap(), b"fed through stdin");
+            }
+            ProcExecOutcome::TimedOut => panic!("expected process to complete, not time out"),
+        }
+    }
+
     #[test]
     fn kills_process_that_exceeds_timeout() {
         let mut cmd = long_running_command();
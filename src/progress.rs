@@ -1,8 +1,19 @@
+//! Terminal progress for long operations (resolve, download, environment
+//! creation, install, test runs).
+//!
+//! The renderer follows the command's event stream. Each event belongs to a
+//! step; when a step finishes (or the next one starts) it is printed as a
+//! permanent line with its duration. On a TTY the current step is redrawn in
+//! place as a spinner with a progress bar; otherwise every new message is
+//! printed as a plain line so logs stay readable. `--format json` disables the
+//! renderer entirely and the same information is only recorded as events.
+
 use crate::cli::ProgressMode;
 use crate::schema::{Event, EventListener, EventType};
 use std::cell::RefCell;
 use std::io::{self, Write};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 pub struct ProgressConfig {
     pub mode: ProgressMode,
@@ -20,13 +31,13 @@ impl ProgressConfig {
 }
 
 pub struct ProgressDriver {
-    inner: Option<Rc<RefCell<ProgressRenderer>>>,
+    inner: Option<Rc<RefCell<ProgressRenderer<io::Stderr>>>>,
 }
 
 impl ProgressDriver {
     pub fn new(config: ProgressConfig) -> Self {
         if config.enabled() {
-            let renderer = ProgressRenderer::new(config.is_tty);
+            let renderer = ProgressRenderer::new(io::stderr(), config.is_tty);
             Self {
                 inner: Some(Rc::new(RefCell::new(renderer))),
             }
@@ -59,84 +70,213 @@ impl ProgressDriver {
     }
 }
 
-struct ProgressRenderer {
+/// A timed stage of a long operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Resolve,
+    Download,
+    Extract,
+    Environment,
+    Install,
+    Tests,
+    Script,
+}
+
+impl Step {
+    /// Summary printed when the step ends without a message of its own.
+    fn done_label(self) -> &'static str {
+        match self {
+            Step::Resolve => "Resolved dependencies",
+            Step::Download => "Downloaded artifacts",
+            Step::Extract => "Extracted artifacts",
+            Step::Environment => "Created virtual environment",
+            Step::Install => "Installed packages",
+            Step::Tests => "Ran tests",
+            Step::Script => "Script finished",
+        }
+    }
+}
+
+struct ProgressRenderer<W: Write> {
+    out: W,
     is_tty: bool,
+    started: Instant,
     spinner_index: usize,
+    step: Option<(Step, Instant)>,
     last_message: Option<String>,
     last_progress: Option<u8>,
+    last_count: Option<(u64, u64)>,
+    /// A TTY status line is currently drawn and must be cleared first.
+    line_drawn: bool,
+    /// Anything was printed, so `finish` owes the user a closing line.
     rendered: bool,
 }
 
-impl ProgressRenderer {
-    fn new(is_tty: bool) -> Self {
+impl<W: Write> ProgressRenderer<W> {
+    fn new(out: W, is_tty: bool) -> Self {
         Self {
+            out,
             is_tty,
+            started: Instant::now(),
             spinner_index: 0,
+            step: None,
             last_message: None,
             last_progress: None,
+            last_count: None,
+            line_drawn: false,
             rendered: false,
         }
     }
 
     fn handle_event(&mut self, event: &Event) {
-        if let Some(update) = ProgressUpdate::from_event(event) {
-            self.last_message = Some(update.message);
-            if update.progress.is_some() {
-                self.last_progress = update.progress;
-            }
-            self.render();
+        let Some(update) = ProgressUpdate::from_event(event) else {
+            return;
+        };
+        if matches!(event.event_type, EventType::CommandEnd) {
+            self.end_step(None);
+            return;
         }
-    }
-
-    fn render(&mut self) {
-        let message = self
-            .last_message
-            .as_deref()
-            .unwrap_or("Working on tasks...");
-        let spinner = SPINNER_FRAMES[self.spinner_index % SPINNER_FRAMES.len()];
-        self.spinner_index = (self.spinner_index + 1) % SPINNER_FRAMES.len();
-
-        let mut line = format!("{spinner} {message}");
-        if let Some(progress) = self.last_progress {
-            line.push_str(&format!(" [{progress}%]"));
+        if let Some(step) = update.step
+            && self.step.map(|(current, _)| current) != Some(step)
+        {
+            self.end_step(None);
+            self.step = Some((step, Instant::now()));
+            self.last_count = None;
+        }
+        if update.done {
+            self.last_progress = update.progress.or(self.last_progress);
+            self.end_step(Some(update.message));
+            return;
+        }
+        let changed = self.last_message.as_deref() != Some(update.message.as_str());
+        self.last_message = Some(update.message);
+        if update.progress.is_some() {
+            self.last_progress = update.progress;
+        }
+        if update.count.is_some() {
+            self.last_count = update.count;
         }
-
-        let mut stderr = io::stderr();
         if self.is_tty {
-            let _ = write!(stderr, "\r\x1b[2K{line}");
-            let _ = stderr.flush();
-        } else {
-            let _ = writeln!(stderr, "{line}");
+            self.draw_status();
+        } else if changed {
+            let line = self.status_text();
+            self.print_line(&line);
         }
-        self.rendered = true;
+    }
+
+    /// Close the current step with a permanent line carrying its duration.
+    fn end_step(&mut self, summary: Option<String>) {
+        let Some((step, started)) = self.step.take() else {
+            return;
+        };
+        let summary = summary.unwrap_or_else(|| step.done_label().to_string());
+        let line = format!(
+            "{} {summary} ({})",
+            self.done_mark(),
+            format_elapsed(started.elapsed())
+        );
+        self.print_line(&line);
+        self.last_message = None;
+        self.last_count = None;
     }
 
     fn finish(&mut self) {
+        self.end_step(None);
         if !self.rendered {
             return;
         }
-        let message = self
+        let line = format!(
+            "{} Finished in {}",
+            self.done_mark(),
+            format_elapsed(self.started.elapsed())
+        );
+        self.print_line(&line);
+        self.rendered = false;
+    }
+
+    fn done_mark(&self) -> &'static str {
+        if self.is_tty { "✓" } else { "[done]" }
+    }
+
+    /// Message, progress bar (TTY only), percent and item count.
+    fn status_text(&self) -> String {
+        let mut line = self
             .last_message
-            .as_deref()
-            .unwrap_or("Done processing tasks");
-        let mut stderr = io::stderr();
-        if self.is_tty {
-            let _ = write!(stderr, "\r\x1b[2K[done] {message}");
-        } else {
-            let _ = write!(stderr, "[done] {message}");
-        }
+            .clone()
+            .unwrap_or_else(|| "Working...".to_string());
         if let Some(progress) = self.last_progress {
-            let _ = write!(stderr, " [{progress}%]");
+            if self.is_tty {
+                line.push(' ');
+                line.push_str(&progress_bar(progress, BAR_WIDTH));
+            }
+            line.push_str(&format!(" {progress}%"));
         }
-        let _ = writeln!(stderr);
-        let _ = stderr.flush();
-        self.rendered = false;
+        if let Some((completed, total)) = self.last_count {
+            let count = format!("({completed}/{total})");
+            if !line.contains(&count) {
+                line.push(' ');
+                line.push_str(&count);
+            }
+        }
+        line
+    }
+
+    fn draw_status(&mut self) {
+        let spinner = SPINNER_FRAMES[self.spinner_index % SPINNER_FRAMES.len()];
+        self.spinner_index = (self.spinner_index + 1) % SPINNER_FRAMES.len();
+        let mut line = format!("{spinner} {}", self.status_text());
+        if let Some((_, started)) = self.step {
+            line.push_str(&format!(" {}", format_elapsed(started.elapsed())));
+        }
+        let _ = write!(self.out, "\r\x1b[2K{line}");
+        let _ = self.out.flush();
+        self.line_drawn = true;
+        self.rendered = true;
+    }
+
+    fn print_line(&mut self, line: &str) {
+        if self.line_drawn {
+            let _ = write!(self.out, "\r\x1b[2K");
+            self.line_drawn = false;
+        }
+        let _ = writeln!(self.out, "{line}");
+        let _ = self.out.flush();
+        self.rendered = true;
+    }
+}
+
+/// Phase label and percent complete the progress UI shows for `event`.
+pub(crate) fn phase_of(event: &Event) -> Option<(String, Option<u8>)> {
+    ProgressUpdate::from_event(event).map(|update| (update.message, update.progress))
+}
+
+/// `[#####---------------]` for `percent` of `width` cells.
+fn progress_bar(percent: u8, width: usize) -> String {
+    let filled = width * usize::from(percent.min(100)) / 100;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+/// Compact duration: `840ms`, `3.2s`, `2m05s`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let ms = elapsed.as_millis();
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    } else {
+        let secs = elapsed.as_secs();
+        format!("{}m{:02}s", secs / 60, secs % 60)
     }
 }
 
 struct ProgressUpdate {
     message: String,
     progress: Option<u8>,
+    step: Option<Step>,
+    /// The event ends its step.
+    done: bool,
+    /// `completed`/`total` items from the event data.
+    count: Option<(u64, u64)>,
 }
 
 impl ProgressUpdate {
@@ -144,45 +284,206 @@ impl ProgressUpdate {
         Self {
             message: message.into(),
             progress: progress.map(|p| p.min(100)),
+            step: None,
+            done: false,
+            count: None,
         }
     }
 
+    fn step(mut self, step: Step) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    fn done(mut self) -> Self {
+        self.done = true;
+        self
+    }
+
     fn from_event(event: &Event) -> Option<Self> {
-        match event.event_type {
-            EventType::ResolveStart => Some(Self::new("Resolving dependencies", Some(5))),
-            EventType::ResolveComplete => Some(Self::new(
-                event.message.as_deref().unwrap_or("Resolved dependencies"),
+        let message = |default: &str| event.message.as_deref().unwrap_or(default).to_string();
+        let update = match event.event_type {
+            EventType::ResolveStart => {
+                Self::new("Resolving dependencies", Some(5)).step(Step::Resolve)
+            }
+            EventType::ResolveComplete => Self::new(
+                message("Resolved dependencies"),
                 event.progress.or(Some(30)),
-            )),
-            EventType::DownloadStart | EventType::DownloadProgress => Some(Self::new(
-                event.message.as_deref().unwrap_or("Downloading artifacts"),
+            )
+            .step(Step::Resolve)
+            .done(),
+            EventType::DownloadStart | EventType::DownloadProgress => Self::new(
+                message("Downloading artifacts"),
                 event.progress.or(Some(50)),
-            )),
-            EventType::DownloadComplete => Some(Self::new(
-                event.message.as_deref().unwrap_or("Downloaded artifacts"),
-                event.progress.or(Some(70)),
-            )),
-            EventType::InstallStart => Some(Self::new(
-                event.message.as_deref().unwrap_or("Installing packages"),
-                event.progress.or(Some(80)),
-            )),
+            )
+            .step(Step::Download),
+            EventType::DownloadComplete => {
+                Self::new(message("Downloaded artifacts"), event.progress.or(Some(70)))
+                    .step(Step::Download)
+                    .done()
+            }
+            EventType::InstallStart => {
+                Self::new(message("Installing packages"), event.progress.or(Some(80)))
+                    .step(Step::Install)
+            }
             EventType::InstallComplete => {
-                Some(Self::new("Install complete", event.progress.or(Some(100))))
+                Self::new("Install complete", event.progress.or(Some(100)))
+                    .step(Step::Install)
+                    .done()
             }
-            EventType::ExtractStart => Some(Self::new("Extracting artifacts", Some(60))),
-            EventType::ExtractComplete => Some(Self::new("Extracted artifacts", Some(65))),
-            EventType::ScriptStart => Some(Self::new("Running script", Some(40))),
-            EventType::ScriptEnd => Some(Self::new("Script finished", Some(100))),
-            EventType::TestStart => Some(Self::new("Running tests", Some(30))),
-            EventType::TestComplete => Some(Self::new("Tests finished", Some(100))),
-            EventType::Progress => Some(Self::new(
-                event.message.as_deref().unwrap_or("Working..."),
-                event.progress,
-            )),
-            EventType::CommandEnd => Some(Self::new("Finished", Some(100))),
-            _ => None,
-        }
+            EventType::ExtractStart => {
+                Self::new("Extracting artifacts", Some(60)).step(Step::Extract)
+            }
+            EventType::ExtractComplete => Self::new("Extracted artifacts", Some(65))
+                .step(Step::Extract)
+                .done(),
+            EventType::EnvCreate => {
+                Self::new(message("Creating virtual environment"), event.progress)
+                    .step(Step::Environment)
+            }
+            EventType::ScriptStart => Self::new("Running script", Some(40)).step(Step::Script),
+            EventType::ScriptEnd => Self::new("Script finished", Some(100))
+                .step(Step::Script)
+                .done(),
+            EventType::TestStart => {
+                Self::new(message("Running tests"), event.progress.or(Some(30))).step(Step::Tests)
+            }
+            EventType::TestPass | EventType::TestFail | EventType::TestSkip => {
+                Self::new(message("Running tests"), event.progress).step(Step::Tests)
+            }
+            EventType::TestComplete => Self::new(message("Tests finished"), Some(100))
+                .step(Step::Tests)
+                .done(),
+            EventType::Progress => Self::new(message("Working..."), event.progress),
+            EventType::CommandEnd => Self::new("Finished", Some(100)),
+            _ => return None,
+        };
+        let count = event
+            .data
+            .as_ref()
+            .and_then(|data| Some((data["completed"].as_u64()?, data["total"].as_u64()?)));
+        Some(Self { count, ..update })
     }
 }
 
 const SPINNER_FRAMES: &[&str] = &["-", "\\", "|", "/"];
+const BAR_WIDTH: usize = 20;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_type: EventType, message: Option<&str>, progress: Option<u8>) -> Event {
+        let mut event = Event::new(event_type, 0);
+        event.message = message.map(str::to_string);
+        event.progress = progress;
+        event
+    }
+
+    fn render(is_tty: bool, events: &[Event]) -> String {
+        let mut renderer = ProgressRenderer::new(Vec::new(), is_tty);
+        for event in events {
+            renderer.handle_event(event);
+        }
+        renderer.finish();
+        String::from_utf8(renderer.out).unwrap()
+    }
+
+    fn install_events() -> Vec<Event> {
+        let mut download = event(
+            EventType::DownloadProgress,
+            Some("Downloaded idna (1/2)"),
+            Some(60),
+        );
+        download.data = Some(json!({ "completed": 1, "total": 2 }));
+        vec![
+            event(EventType::ResolveStart, None, None),
+            event(
+                EventType::ResolveComplete,
+                Some("Resolved 2 packages"),
+                None,
+            ),
+            event(
+                EventType::DownloadStart,
+                Some("Downloading 2 artifacts"),
+                None,
+            ),
+            download,
+            event(EventType::InstallStart, Some("Installing 2 packages"), None),
+            event(EventType::InstallComplete, None, None),
+        ]
+    }
+
+    #[test]
+    fn plain_output_prints_one_line_per_message_and_times_each_step() {
+        let output = render(false, &install_events());
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 8, "{output}");
+        assert_eq!(lines[0], "Resolving dependencies 5%");
+        assert!(lines[1].starts_with("[done] Resolved 2 packages ("));
+        assert_eq!(lines[2], "Downloading 2 artifacts 50%");
+        assert_eq!(lines[3], "Downloaded idna (1/2) 60%");
+        assert!(lines[4].starts_with("[done] Downloaded artifacts ("));
+        assert_eq!(lines[5], "Installing 2 packages 80%");
+        assert!(lines[6].starts_with("[done] Install complete ("));
+        assert!(lines[7].starts_with("[done] Finished in "));
+        assert!(!output.contains('\x1b'), "no escape codes off a TTY");
+    }
+
+    #[test]
+    fn tty_output_redraws_a_bar_in_place_and_keeps_step_lines() {
+        let output = render(true, &install_events());
+        assert!(output.contains("\r\x1b[2K- Resolving dependencies [#-------------------] 5%"));
+        assert!(output.contains("Downloaded idna (1/2) [############--------] 60%"));
+        assert!(output.contains("\r\x1b[2K✓ Resolved 2 packages ("));
+        assert!(output.contains("✓ Install complete ("));
+        assert!(output.contains("\n✓ Finished in "));
+    }
+
+    #[test]
+    fn test_results_show_counts_and_environment_creation_is_timed() {
+        let mut passed = event(
+            EventType::TestPass,
+            Some("tests/test_a.py::test_one"),
+            Some(65),
+        );
+        passed.data = Some(json!({ "completed": 1, "total": 2 }));
+        let output = render(
+            false,
+            &[
+                event(
+                    EventType::EnvCreate,
+                    Some("Creating virtual environment"),
+                    None,
+                ),
+                event(EventType::TestStart, Some("Running 2 tests"), None),
+                passed,
+                event(
+                    EventType::TestComplete,
+                    Some("2 passed, 0 failed, 0 skipped"),
+                    None,
+                ),
+            ],
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Creating virtual environment");
+        assert!(lines[1].starts_with("[done] Created virtual environment ("));
+        assert_eq!(lines[2], "Running 2 tests 30%");
+        assert_eq!(lines[3], "tests/test_a.py::test_one 65% (1/2)");
+        assert!(lines[4].starts_with("[done] 2 passed, 0 failed, 0 skipped ("));
+    }
+
+    #[test]
+    fn nothing_is_printed_without_progress_events() {
+        assert_eq!(render(false, &[event(EventType::CacheHit, None, None)]), "");
+    }
+
+    #[test]
+    fn elapsed_time_is_compact() {
+        assert_eq!(format_elapsed(Duration::from_millis(840)), "840ms");
+        assert_eq!(format_elapsed(Duration::from_millis(3240)), "3.2s");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m05s");
+        assert_eq!(progress_bar(50, 10), "[#####-----]");
+    }
+}
@@ -12,6 +12,10 @@ use toml::Value;
 
 const PYPROJECT_FILENAME: &str = "pyproject.toml";
 
+/// Optional standalone PyBun config next to `pyproject.toml`. Its top-level
+/// keys take the place of the same keys in `[tool.pybun]`.
+pub const PYBUN_TOML: &str = "pybun.toml";
+
 #[derive(Debug, Error)]
 pub enum ProjectError {
     #[error("pyproject.toml not found in {0}")]
@@ -66,6 +70,30 @@ pub struct PybunConfig {
     pub lazy_imports: Vec<String>,
     #[serde(default)]
     pub profiles: BTreeMap<String, ProfileConfig>,
+    #[serde(default)]
+    pub seed: crate::seed::SeedConfig,
+    #[serde(default)]
+    pub build: crate::build_isolation::BuildConfig,
+    #[serde(default)]
+    pub alias: BTreeMap<String, crate::alias::AliasValue>,
+    /// Named tasks for `pybun run <task>` (see [`crate::tasks`]).
+    #[serde(default)]
+    pub tasks: BTreeMap<String, crate::tasks::TaskValue>,
+    #[serde(default)]
+    pub network: Option<crate::network_policy::NetworkConfig>,
+    #[serde(default)]
+    pub test: crate::test_params::TestConfig,
+    #[serde(default)]
+    pub http: Option<crate::http_config::HttpConfig>,
+    /// Remote wheel cache (see [`crate::remote_cache`]).
+    #[serde(default)]
+    pub cache: Option<crate::remote_cache::CacheConfig>,
+    /// Per-package wheel architecture overrides (see [`crate::arch`]).
+    #[serde(default)]
+    pub arch: BTreeMap<String, String>,
+    /// Text encoding of `pybun run` children (see [`crate::encoding`]).
+    #[serde(default)]
+    pub encoding: Option<crate::encoding::EncodingPolicy>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -147,6 +175,15 @@ impl Project {
             .unwrap_or_default()
     }
 
+    /// `[project].requires-python`, if declared.
+    pub fn requires_python(&self) -> Option<String> {
+        self.raw
+            .get("project")?
+            .get("requires-python")?
+            .as_str()
+            .map(str::to_string)
+    }
+
     /// Get build system configuration.
     pub fn build_system(&self) -> BuildSystem {
         if let Some(build_system) = self.raw.get("build-system") {
@@ -186,39 +223,144 @@ impl Project {
             .unwrap_or_default()
     }
 
+    /// Console scripts declared in [project.scripts].
+    pub fn scripts(&self) -> BTreeMap<String, String> {
+        self.raw
+            .get("project")
+            .and_then(|p| p.get("scripts"))
+            .and_then(|s| s.as_table())
+            .map(|table| {
+                table
+                    .iter()
+                    .filter_map(|(name, target)| Some((name.clone(), target.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Add a dependency to [project.dependencies].
     pub fn add_dependency(&mut self, dep: &str) {
+        self.add_dependencies(&[dep.to_string()]);
+    }
+
+    /// Add dependencies to [project.dependencies], replacing the existing
+    /// entries for the same packages. Several of `deps` may name one package
+    /// (with different markers); all of them are kept.
+    pub fn add_dependencies(&mut self, deps: &[String]) {
         if let Value::Table(ref mut root) = self.raw {
             let project = root
                 .entry("project")
                 .or_insert_with(|| Value::Table(toml::map::Map::new()));
 
             if let Value::Table(project_table) = project {
-                let deps = project_table
+                let existing = project_table
                     .entry("dependencies")
                     .or_insert_with(|| Value::Array(vec![]));
 
-                if let Value::Array(arr) = deps {
-                    // Extract package name (before any version specifier)
-                    let pkg_name = extract_package_name(dep);
+                if let Value::Array(arr) = existing {
+                    upsert_dependencies(arr, deps);
+                }
+            }
+        }
+    }
 
-                    // Remove existing entry for same package
-                    arr.retain(|v| {
-                        v.as_str()
-                            .map(|s| extract_package_name(s) != pkg_name)
-                            .unwrap_or(true)
-                    });
+    /// Add a dependency to the group `group`: the
+    /// `[project.optional-dependencies]` extra of that name if it exists,
+    /// else the PEP 735 `[dependency-groups]` entry (created if missing).
+    pub fn add_group_dependency(&mut self, group: &str, dep: &str) {
+        self.add_group_dependencies(group, &[dep.to_string()]);
+    }
 
-                    // Add new entry
-                    arr.push(Value::String(dep.to_string()));
+    /// [`Project::add_dependencies`] for the group `group` (see
+    /// [`Project::add_group_dependency`]).
+    pub fn add_group_dependencies(&mut self, group: &str, deps: &[String]) {
+        let in_extras = self.optional_dependencies().contains_key(group);
+        let Value::Table(ref mut root) = self.raw else {
+            return;
+        };
+        let table = if in_extras {
+            root.get_mut("project")
+                .and_then(|p| p.get_mut("optional-dependencies"))
+        } else {
+            Some(
+                root.entry("dependency-groups")
+                    .or_insert_with(|| Value::Table(toml::map::Map::new())),
+            )
+        };
+        if let Some(Value::Table(table)) = table
+            && let Value::Array(arr) = table.entry(group).or_insert_with(|| Value::Array(vec![]))
+        {
+            upsert_dependencies(arr, deps);
+        }
+    }
 
-                    // Sort for deterministic output
-                    arr.sort_by_key(|v| v.as_str().unwrap_or("").to_string());
-                }
-            }
+    /// [`Project::add_dependencies`] for the `[project.optional-dependencies]`
+    /// extra `extra`, created if missing.
+    pub fn add_optional_dependencies(&mut self, extra: &str, deps: &[String]) {
+        let Value::Table(ref mut root) = self.raw else {
+            return;
+        };
+        if let Value::Table(project) = root
+            .entry("project")
+            .or_insert_with(|| Value::Table(toml::map::Map::new()))
+            && let Value::Table(extras) = project
+                .entry("optional-dependencies")
+                .or_insert_with(|| Value::Table(toml::map::Map::new()))
+            && let Value::Array(arr) = extras.entry(extra).or_insert_with(|| Value::Array(vec![]))
+        {
+            upsert_dependencies(arr, deps);
+        }
+    }
+
+    /// Set `[project].requires-python`.
+    pub fn set_requires_python(&mut self, specifier: &str) {
+        if let Value::Table(ref mut root) = self.raw
+            && let Value::Table(project) = root
+                .entry("project")
+                .or_insert_with(|| Value::Table(toml::map::Map::new()))
+        {
+            project.insert(
+                "requires-python".into(),
+                Value::String(specifier.to_string()),
+            );
         }
     }
 
+    /// Remove a dependency from the group `group` (see
+    /// [`Project::add_group_dependency`]). `{ include-group = ... }` entries
+    /// are left alone.
+    pub fn remove_group_dependency(&mut self, group: &str, name: &str) -> bool {
+        let name_lower = name.to_lowercase();
+        let Value::Table(ref mut root) = self.raw else {
+            return false;
+        };
+        let remove_from = |table: Option<&mut Value>| {
+            let Some(Value::Array(deps)) = table.and_then(|t| t.get_mut(group)) else {
+                return false;
+            };
+            let before = deps.len();
+            deps.retain(|v| {
+                v.as_str()
+                    .map(|s| extract_package_name(s).to_lowercase() != name_lower)
+                    .unwrap_or(true)
+            });
+            deps.len() < before
+        };
+        let from_extras = remove_from(
+            root.get_mut("project")
+                .and_then(|p| p.get_mut("optional-dependencies")),
+        );
+        let from_groups = remove_from(root.get_mut("dependency-groups"));
+        from_extras || from_groups
+    }
+
+    /// Whether `group` names an optional-dependencies extra or a dependency
+    /// group.
+    pub fn has_group(&self, group: &str) -> bool {
+        self.optional_dependencies().contains_key(group)
+            || self.dependency_groups().contains_key(group)
+    }
+
     /// Remove a dependency from [project.dependencies].
     pub fn remove_dependency(&mut self, name: &str) -> bool {
         let mut removed = false;
@@ -240,6 +382,74 @@ impl Project {
         removed
     }
 
+    /// Replace the [project.dependencies] entry `old` with `new` in place.
+    pub fn replace_dependency(&mut self, old: &str, new: &str) -> bool {
+        if let Value::Table(ref mut root) = self.raw
+            && let Some(Value::Table(project)) = root.get_mut("project")
+            && let Some(Value::Array(deps)) = project.get_mut("dependencies")
+            && let Some(entry) = deps.iter_mut().find(|v| v.as_str() == Some(old))
+        {
+            *entry = Value::String(new.to_string());
+            return true;
+        }
+        false
+    }
+
+    /// Rename the dependency `old` to `new` wherever it is declared:
+    /// `[project.dependencies]`, every `[project.optional-dependencies]`
+    /// extra and every `[dependency-groups]` group. Version specifiers,
+    /// extras and markers are kept. Returns the sections that changed, e.g.
+    /// `project.optional-dependencies.imaging`.
+    pub fn rename_dependency(&mut self, old: &str, new: &str) -> Vec<String> {
+        let old = crate::pypi::normalize_project_name(old);
+        let rename = |section: String, deps: &mut Value, changed: &mut Vec<String>| {
+            let Value::Array(deps) = deps else {
+                return;
+            };
+            let mut renamed = false;
+            for entry in deps.iter_mut() {
+                let Some(dep) = entry.as_str() else {
+                    continue;
+                };
+                let name = extract_package_name(dep);
+                if crate::pypi::normalize_project_name(name) != old {
+                    continue;
+                }
+                let rest = &dep.trim_start()[name.len()..];
+                *entry = Value::String(format!("{new}{rest}"));
+                renamed = true;
+            }
+            if renamed {
+                changed.push(section);
+            }
+        };
+
+        let mut changed = Vec::new();
+        let Value::Table(ref mut root) = self.raw else {
+            return changed;
+        };
+        if let Some(Value::Table(project)) = root.get_mut("project") {
+            if let Some(deps) = project.get_mut("dependencies") {
+                rename("project.dependencies".to_string(), deps, &mut changed);
+            }
+            if let Some(Value::Table(extras)) = project.get_mut("optional-dependencies") {
+                for (extra, deps) in extras.iter_mut() {
+                    rename(
+                        format!("project.optional-dependencies.{extra}"),
+                        deps,
+                        &mut changed,
+                    );
+                }
+            }
+        }
+        if let Some(Value::Table(groups)) = root.get_mut("dependency-groups") {
+            for (group, deps) in groups.iter_mut() {
+                rename(format!("dependency-groups.{group}"), deps, &mut changed);
+            }
+        }
+        changed
+    }
+
     /// Check if a dependency exists.
     pub fn has_dependency(&self, name: &str) -> bool {
         let name_lower = name.to_lowercase();
@@ -301,27 +511,51 @@ impl Project {
             .unwrap_or_default()
     }
 
-    /// Get pybun-specific configuration.
+    /// Get pybun-specific configuration from `[tool.pybun]` and `pybun.toml`.
+    ///
+    /// Entries that don't match [`crate::config_schema::PYBUN_SCHEMA`] are
+    /// dropped (`pybun config validate` reports them); the rest still apply.
     pub fn pybun_config(&self) -> PybunConfig {
-        self.raw
-            .get("tool")
-            .and_then(|t| t.get("pybun"))
-            .and_then(|v| v.clone().try_into().ok())
+        crate::config_schema::sanitize(self.pybun_table())
+            .try_into()
             .unwrap_or_default()
     }
 
     /// Get workspace configuration if present.
     pub fn workspace_config(&self) -> Option<WorkspaceConfig> {
-        self.raw
+        self.pybun_table()
+            .get("workspace")
+            .and_then(|v| v.clone().try_into().ok())
+    }
+
+    /// `[tool.pybun]` with the top-level keys of `pybun.toml` laid over it.
+    fn pybun_table(&self) -> Value {
+        let mut table = self
+            .raw
             .get("tool")
             .and_then(|t| t.get("pybun"))
-            .and_then(|p| p.get("workspace"))
-            .and_then(|v| v.clone().try_into().ok())
+            .and_then(Value::as_table)
+            .cloned()
+            .unwrap_or_default();
+        let standalone = self
+            .path
+            .parent()
+            .and_then(|dir| fs::read_to_string(dir.join(PYBUN_TOML)).ok())
+            .and_then(|content| toml::from_str::<toml::Table>(&content).ok());
+        if let Some(standalone) = standalone {
+            table.extend(standalone);
+        }
+        Value::Table(table)
+    }
+
+    /// The project file as [`Project::save`] would write it.
+    pub fn to_toml_string(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(&self.raw)?)
     }
 
     /// Save the project file.
     pub fn save(&self) -> Result<()> {
-        let content = toml::to_string_pretty(&self.raw)?;
+        let content = self.to_toml_string()?;
         fs::write(&self.path, content).map_err(|source| ProjectError::Write {
             path: self.path.clone(),
             source,
@@ -330,6 +564,19 @@ impl Project {
     }
 }
 
+/// Replace any entries for the same packages as `deps` with `deps`, keeping
+/// the array sorted for deterministic output.
+fn upsert_dependencies(arr: &mut Vec<Value>, deps: &[String]) {
+    let names: Vec<&str> = deps.iter().map(|dep| extract_package_name(dep)).collect();
+    arr.retain(|v| {
+        v.as_str()
+            .map(|s| !names.contains(&extract_package_name(s)))
+            .unwrap_or(true)
+    });
+    arr.extend(deps.iter().map(|dep| Value::String(dep.clone())));
+    arr.sort_by_key(|v| v.as_str().unwrap_or("").to_string());
+}
+
 /// Collect string entries from a TOML array value.
 fn string_array(value: &Value) -> Vec<String> {
     value
@@ -372,9 +619,10 @@ fn expand_dependency_group(
 /// e.g., "requests; python_version < '4.0'" -> "requests"
 pub(crate) fn extract_package_name(dep: &str) -> &str {
     let dep = dep.trim();
-    // Find first version specifier or marker character (PEP 508: ';' starts environment markers)
+    // Find first version specifier or marker character (PEP 508: ';' starts
+    // environment markers, '@' a direct reference)
     let end = dep
-        .find(['=', '>', '<', '!', '~', '[', ';'])
+        .find(['=', '>', '<', '!', '~', '[', ';', '@'])
         .unwrap_or(dep.len());
     dep[..end].trim()
 }
@@ -391,6 +639,10 @@ mod tests {
         assert_eq!(extract_package_name("requests==2.28.0"), "requests");
         assert_eq!(extract_package_name("requests[socks]>=2.28.0"), "requests");
         assert_eq!(extract_package_name("  numpy  "), "numpy");
+        assert_eq!(
+            extract_package_name("mypkg @ git+https://github.com/org/repo@v1.2.3"),
+            "mypkg"
+        );
     }
 
     #[test]
@@ -454,6 +706,43 @@ mod tests {
         assert_eq!(deps[0], "requests>=2.28.0");
     }
 
+    #[test]
+    fn rename_dependency_keeps_specifiers_in_every_section() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("pyproject.toml");
+        fs::write(
+            &path,
+            r#"[project]
+name = "app"
+dependencies = ["PIL>=1.1; python_version < '4'", "requests"]
+
+[project.optional-dependencies]
+imaging = ["pil[tk]"]
+
+[dependency-groups]
+test = ["pytest", { include-group = "lint" }]
+lint = ["ruff"]
+"#,
+        )
+        .unwrap();
+        let mut project = Project::load(&path).unwrap();
+
+        let changed = project.rename_dependency("pil", "pillow");
+        assert_eq!(
+            changed,
+            vec![
+                "project.dependencies".to_string(),
+                "project.optional-dependencies.imaging".to_string()
+            ]
+        );
+        assert_eq!(
+            project.dependencies(),
+            vec!["pillow>=1.1; python_version < '4'", "requests"]
+        );
+        assert_eq!(project.group_dependencies("imaging"), vec!["pillow[tk]"]);
+        assert!(project.rename_dependency("numpy", "numpy2").is_empty());
+    }
+
     #[test]
     fn new_project_has_empty_deps() {
         let temp = tempdir().unwrap();
@@ -497,6 +786,19 @@ mod tests {
         assert!(project.has_dependency("numpy"));
     }
 
+    #[test]
+    fn replace_dependency_keeps_position() {
+        let temp = tempdir().unwrap();
+        let mut project = Project::new(temp.path().join("pyproject.toml"));
+
+        project.add_dependency("numpy>=1.24.0");
+        project.add_dependency("requests<3");
+
+        assert!(project.replace_dependency("numpy>=1.24.0", "numpy>=2.1.0"));
+        assert!(!project.replace_dependency("numpy>=1.24.0", "numpy>=2.2.0"));
+        assert_eq!(project.dependencies(), ["numpy>=2.1.0", "requests<3"]);
+    }
+
     #[test]
     fn optional_dependencies_reads_named_groups() {
         let temp = tempdir().unwrap();
@@ -638,6 +940,68 @@ docs = ["mkdocs"]
         assert!(project.group_dependencies("missing").is_empty());
     }
 
+    #[test]
+    fn add_group_dependency_prefers_existing_extra() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("pyproject.toml");
+        fs::write(
+            &path,
+            r#"[project]
+name = "demo"
+version = "0.1.0"
+dependencies = []
+
+[project.optional-dependencies]
+dev = ["ruff"]
+"#,
+        )
+        .unwrap();
+
+        let mut project = Project::load(&path).unwrap();
+        project.add_group_dependency("dev", "pytest>=8");
+        project.add_group_dependency("test", "pytest>=7");
+        project.add_group_dependency("test", "pytest>=8");
+
+        assert_eq!(
+            project.optional_dependencies().get("dev"),
+            Some(&vec!["pytest>=8".to_string(), "ruff".to_string()])
+        );
+        assert_eq!(
+            project.dependency_groups().get("test"),
+            Some(&vec!["pytest>=8".to_string()])
+        );
+        assert!(project.dependencies().is_empty());
+        assert!(project.has_group("dev") && project.has_group("test"));
+    }
+
+    #[test]
+    fn remove_group_dependency_keeps_include_group_entries() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("pyproject.toml");
+        fs::write(
+            &path,
+            r#"[project]
+name = "demo"
+version = "0.1.0"
+dependencies = []
+
+[dependency-groups]
+test = ["pytest"]
+dev = ["Ruff>=0.1.0", { include-group = "test" }]
+"#,
+        )
+        .unwrap();
+
+        let mut project = Project::load(&path).unwrap();
+        assert!(project.remove_group_dependency("dev", "ruff"));
+        assert!(!project.remove_group_dependency("dev", "ruff"));
+        assert!(!project.remove_group_dependency("missing", "pytest"));
+        assert_eq!(
+            project.dependency_groups().get("dev"),
+            Some(&vec!["pytest".to_string()])
+        );
+    }
+
     #[test]
     fn save_and_load_roundtrip() {
         let temp = tempdir().unwrap();
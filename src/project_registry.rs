@@ -0,0 +1,586 @@
+//! Machine-wide registry of PyBun-managed projects.
+//!
+//! `install`, `add` and `lock` record the project they ran in to
+//! `<cache root>/projects.json`: its root, lockfile and that lockfile's hash,
+//! the environment packages went into, and when it was last used. The
+//! registry powers `pybun projects list/status`, gc pinning (wheels locked
+//! by a pinned project survive `pybun gc --max-size`) and per-project disk
+//! attribution. Updates take an exclusive lock on `projects.json.lock` and
+//! replace the file atomically, so concurrent PyBun processes don't corrupt
+//! it.
+
+use crate::lockfile::Lockfile;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Registry file name under the cache root.
+pub const REGISTRY_FILE: &str = "projects.json";
+
+const REGISTRY_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error(transparent)]
+    Cache(#[from] crate::cache::CacheError),
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("project registry {path} is corrupt: {source}")]
+    Corrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("{0} is not a registered project")]
+    NotRegistered(PathBuf),
+}
+
+pub type Result<T> = std::result::Result<T, RegistryError>;
+
+/// One registered project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectRecord {
+    /// Directory containing `pyproject.toml`.
+    pub root: PathBuf,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub lockfile: Option<PathBuf>,
+    /// `sha256:<hex>` of the lockfile as PyBun last wrote or used it.
+    #[serde(default)]
+    pub lock_hash: Option<String>,
+    /// Virtual environment packages were last installed into.
+    #[serde(default)]
+    pub env: Option<PathBuf>,
+    pub last_command: String,
+    /// Unix timestamp (seconds) of the last recorded command.
+    pub last_activity: u64,
+    /// Pinned projects keep their locked wheels through `pybun gc`.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// What a command did in a project, as passed to [`ProjectRegistry::record`].
+#[derive(Debug, Clone)]
+pub struct Activity<'a> {
+    pub root: &'a Path,
+    pub command: &'a str,
+    pub lockfile: Option<&'a Path>,
+    pub env: Option<&'a Path>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryFile {
+    version: u32,
+    projects: Vec<ProjectRecord>,
+}
+
+/// Something `pybun projects status` found wrong with a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectIssue {
+    /// The project directory (or its `pyproject.toml`) no longer exists.
+    Missing,
+    /// The recorded lockfile is gone.
+    LockMissing,
+    /// The lockfile changed since PyBun last used it (e.g. a `git pull`), so
+    /// the environment may not match it.
+    LockChanged,
+    /// `pyproject.toml` is newer than the lockfile.
+    LockStale,
+    /// The recorded environment is gone.
+    EnvMissing,
+}
+
+impl ProjectIssue {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProjectIssue::Missing => "missing",
+            ProjectIssue::LockMissing => "lock_missing",
+            ProjectIssue::LockChanged => "lock_changed",
+            ProjectIssue::LockStale => "lock_stale",
+            ProjectIssue::EnvMissing => "env_missing",
+        }
+    }
+}
+
+/// Bytes on disk attributable to a project.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiskUsage {
+    /// Size of the project's environment.
+    pub env_bytes: u64,
+    /// Cached wheels named in the project's lockfile.
+    pub cache_bytes: u64,
+    /// The part of `cache_bytes` no other registered project locks, i.e.
+    /// what forgetting this project would let `pybun gc` reclaim.
+    pub exclusive_cache_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectRegistry {
+    path: PathBuf,
+}
+
+impl ProjectRegistry {
+    /// Registry under the default cache root (see [`crate::cache::Cache`]).
+    pub fn new() -> Result<Self> {
+        let cache = crate::cache::Cache::new()?;
+        Ok(Self::with_path(cache.root().join(REGISTRY_FILE)))
+    }
+
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All registered projects, most recently active first.
+    pub fn load(&self) -> Result<Vec<ProjectRecord>> {
+        let mut projects = self.read()?.projects;
+        projects.sort_by(|a, b| {
+            b.last_activity
+                .cmp(&a.last_activity)
+                .then_with(|| a.root.cmp(&b.root))
+        });
+        Ok(projects)
+    }
+
+    /// Insert or refresh the project at `activity.root`.
+    pub fn record(&self, activity: &Activity<'_>) -> Result<ProjectRecord> {
+        let root = absolute(activity.root);
+        let name = fs::read_to_string(root.join("pyproject.toml"))
+            .ok()
+            .and_then(|content| toml::from_str::<toml::Value>(&content).ok())
+            .and_then(|raw| {
+                raw.get("project")?
+                    .get("name")?
+                    .as_str()
+                    .map(str::to_string)
+            });
+        let lockfile = activity.lockfile.map(|path| absolute(&root.join(path)));
+        let lock_hash = lockfile.as_deref().and_then(file_hash);
+        let env = activity.env.map(absolute);
+        let command = activity.command.to_string();
+        self.update(move |projects| {
+            let record = match projects.iter_mut().find(|p| p.root == root) {
+                Some(record) => record,
+                None => {
+                    projects.push(ProjectRecord {
+                        root: root.clone(),
+                        name: None,
+                        lockfile: None,
+                        lock_hash: None,
+                        env: None,
+                        last_command: String::new(),
+                        last_activity: 0,
+                        pinned: false,
+                    });
+                    projects.last_mut().expect("just pushed")
+                }
+            };
+            record.name = name.or(record.name.take());
+            if lockfile.is_some() {
+                record.lockfile = lockfile;
+                record.lock_hash = lock_hash;
+            }
+            if env.is_some() {
+                record.env = env;
+            }
+            record.last_command = command;
+            record.last_activity = now_epoch_seconds();
+            record.clone()
+        })
+    }
+
+    /// Pin or unpin a registered project.
+    pub fn set_pinned(&self, root: &Path, pinned: bool) -> Result<ProjectRecord> {
+        let root = absolute(root);
+        self.update(|projects| {
+            let record = projects
+                .iter_mut()
+                .find(|p| p.root == root)
+                .ok_or_else(|| RegistryError::NotRegistered(root.clone()))?;
+            record.pinned = pinned;
+            Ok(record.clone())
+        })?
+    }
+
+    /// Drop the project at `root`; returns whether it was registered.
+    pub fn forget(&self, root: &Path) -> Result<bool> {
+        let root = absolute(root);
+        self.update(|projects| {
+            let before = projects.len();
+            projects.retain(|p| p.root != root);
+            projects.len() != before
+        })
+    }
+
+    /// Drop every project whose directory no longer exists.
+    pub fn forget_missing(&self) -> Result<Vec<ProjectRecord>> {
+        self.update(|projects| {
+            let (missing, kept): (Vec<_>, Vec<_>) = std::mem::take(projects)
+                .into_iter()
+                .partition(|p| !p.root.join("pyproject.toml").is_file());
+            *projects = kept;
+            missing
+        })
+    }
+
+    fn read(&self) -> Result<RegistryFile> {
+        match fs::read(&self.path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(|source| RegistryError::Corrupt {
+                path: self.path.clone(),
+                source,
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(RegistryFile::default()),
+            Err(source) => Err(RegistryError::Io {
+                path: self.path.clone(),
+                source,
+            }),
+        }
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<ProjectRecord>) -> T) -> Result<T> {
+        let io = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| RegistryError::Io { path, source }
+        };
+        let parent = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(parent).map_err(io(parent))?;
+        let lock_path = self.path.with_extension("json.lock");
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(io(&lock_path))?;
+        fs2::FileExt::lock_exclusive(&lock).map_err(io(&lock_path))?;
+
+        let mut file = self.read()?;
+        let result = f(&mut file.projects);
+        file.version = REGISTRY_VERSION;
+        let data = serde_json::to_vec_pretty(&file).map_err(|source| RegistryError::Corrupt {
+            path: self.path.clone(),
+            source,
+        })?;
+        let mut tmp = tempfile::NamedTempFile::new_in(parent).map_err(io(parent))?;
+        tmp.write_all(&data).map_err(io(tmp.path()))?;
+        tmp.persist(&self.path).map_err(|e| RegistryError::Io {
+            path: self.path.clone(),
+            source: e.error,
+        })?;
+        Ok(result)
+    }
+}
+
+/// Problems with `record`; empty when the project looks healthy.
+pub fn check(record: &ProjectRecord) -> Vec<ProjectIssue> {
+    let pyproject = record.root.join("pyproject.toml");
+    if !pyproject.is_file() {
+        return vec![ProjectIssue::Missing];
+    }
+    let mut issues = Vec::new();
+    if let Some(lockfile) = &record.lockfile {
+        if !lockfile.is_file() {
+            issues.push(ProjectIssue::LockMissing);
+        } else {
+            if record.lock_hash.is_some() && file_hash(lockfile) != record.lock_hash {
+                issues.push(ProjectIssue::LockChanged);
+            }
+            let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+            if let (Some(project), Some(lock)) = (modified(&pyproject), modified(lockfile))
+                && project > lock
+            {
+                issues.push(ProjectIssue::LockStale);
+            }
+        }
+    }
+    if record.env.as_ref().is_some_and(|env| !env.exists()) {
+        issues.push(ProjectIssue::EnvMissing);
+    }
+    issues
+}
+
+/// Wheel filenames locked by `record`'s lockfile (empty if it is unreadable).
+pub fn locked_wheels(record: &ProjectRecord) -> BTreeSet<String> {
+    record
+        .lockfile
+        .as_deref()
+        .and_then(|path| Lockfile::load_from_path(path).ok())
+        .map(|lock| {
+            lock.packages
+                .into_values()
+                .map(|pkg| pkg.wheel)
+                .filter(|wheel| !wheel.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Wheel filenames, for every locked target, named by the lockfile of any
+/// project in `records`: its recorded lockfile, else `pybun.lockb` under its
+/// root.
+pub fn lockfile_wheels(records: &[ProjectRecord]) -> BTreeSet<String> {
+    let mut wheels = BTreeSet::new();
+    for record in records {
+        let path = record
+            .lockfile
+            .clone()
+            .unwrap_or_else(|| record.root.join("pybun.lockb"));
+        let Ok(lock) = Lockfile::load_from_path(&path) else {
+            continue;
+        };
+        for package in lock.packages.into_values() {
+            wheels.extend(
+                package
+                    .artifacts
+                    .into_iter()
+                    .filter(|artifact| artifact.is_wheel())
+                    .map(|artifact| artifact.filename),
+            );
+            if package.wheel.ends_with(".whl") {
+                wheels.insert(package.wheel);
+            }
+        }
+    }
+    wheels
+}
+
+/// Wheels locked by any pinned project.
+pub fn pinned_wheels(records: &[ProjectRecord]) -> BTreeSet<String> {
+    records
+        .iter()
+        .filter(|record| record.pinned)
+        .flat_map(locked_wheels)
+        .collect()
+}
+
+/// Disk usage of each project in `records` (same order). Cached wheels are
+/// looked up by filename anywhere under `wheel_dirs`.
+pub fn disk_usage(records: &[ProjectRecord], wheel_dirs: &[PathBuf]) -> Vec<DiskUsage> {
+    let mut wheel_sizes = BTreeMap::new();
+    for dir in wheel_dirs {
+        collect_wheel_sizes(dir, &mut wheel_sizes);
+    }
+    let locked: Vec<BTreeSet<String>> = records.iter().map(locked_wheels).collect();
+    let mut references: BTreeMap<&str, usize> = BTreeMap::new();
+    for wheels in &locked {
+        for wheel in wheels {
+            *references.entry(wheel.as_str()).or_default() += 1;
+        }
+    }
+    records
+        .iter()
+        .zip(&locked)
+        .map(|(record, wheels)| {
+            let mut usage = DiskUsage {
+                env_bytes: record.env.as_deref().map(dir_size).unwrap_or(0),
+                ..DiskUsage::default()
+            };
+            for wheel in wheels {
+                let Some(size) = wheel_sizes.get(wheel) else {
+                    continue;
+                };
+                usage.cache_bytes += size;
+                if references.get(wheel.as_str()) == Some(&1) {
+                    usage.exclusive_cache_bytes += size;
+                }
+            }
+            usage
+        })
+        .collect()
+}
+
+fn collect_wheel_sizes(dir: &Path, sizes: &mut BTreeMap<String, u64>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_wheel_sizes(&path, sizes);
+        } else if let Some(name) = path.file_name().and_then(|n| n.to_str())
+            && name.ends_with(".whl")
+        {
+            sizes.entry(name.to_string()).or_insert(metadata.len());
+        }
+    }
+}
+
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(kind) if kind.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn file_hash(path: &Path) -> Option<String> {
+    fs::read(path)
+        .ok()
+        .map(|data| format!("sha256:{}", hex::encode(Sha256::digest(&data))))
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| std::path::absolute(path).unwrap_or(path.into()))
+}
+
+fn now_epoch_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Virtual environment containing `python` (the nearest ancestor with a
+/// `pyvenv.cfg`), if any.
+pub fn venv_of(python: &Path) -> Option<PathBuf> {
+    python
+        .ancestors()
+        .skip(1)
+        .take(3)
+        .find(|dir| dir.join("pyvenv.cfg").is_file())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Package, PackageSource};
+    use tempfile::tempdir;
+
+    fn project(dir: &Path, name: &str, wheels: &[&str]) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("pyproject.toml"),
+            format!("[project]\nname = \"{name}\"\nversion = \"0.1.0\"\n"),
+        )
+        .unwrap();
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        for wheel in wheels {
+            let pkg = wheel.split('-').next().unwrap().to_string();
+            lock.add_package(Package {
+                name: pkg.clone(),
+                version: "1.0".into(),
+                source: PackageSource::Registry {
+                    index: "pypi".into(),
+                    url: "https://pypi.org/simple".into(),
+                },
+                wheel: wheel.to_string(),
+                hash: "sha256:00".into(),
+                dependencies: Vec::new(),
+                dynamic_metadata: false,
+                groups: Vec::new(),
+                build: None,
+                artifacts: Vec::new(),
+            });
+        }
+        let lock_path = dir.join("pybun.lockb");
+        lock.save_to_path(&lock_path).unwrap();
+        lock_path
+    }
+
+    #[test]
+    fn record_upserts_and_detects_lock_changes() {
+        let temp = tempdir().unwrap();
+        let registry = ProjectRegistry::with_path(temp.path().join("cache/projects.json"));
+        let root = temp.path().join("app");
+        let lock = project(&root, "app", &["six-1.0-py3-none-any.whl"]);
+
+        let activity = Activity {
+            root: &root,
+            command: "install",
+            lockfile: Some(Path::new("pybun.lockb")),
+            env: None,
+        };
+        let first = registry.record(&activity).unwrap();
+        assert_eq!(first.name.as_deref(), Some("app"));
+        assert!(first.lock_hash.is_some());
+        assert!(check(&first).is_empty());
+
+        registry
+            .record(&Activity {
+                command: "lock",
+                ..activity
+            })
+            .unwrap();
+        let projects = registry.load().unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].last_command, "lock");
+
+        fs::write(&lock, b"changed").unwrap();
+        assert!(check(&projects[0]).contains(&ProjectIssue::LockChanged));
+
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(check(&projects[0]), vec![ProjectIssue::Missing]);
+        assert_eq!(registry.forget_missing().unwrap().len(), 1);
+        assert!(registry.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pinning_and_disk_attribution() {
+        let temp = tempdir().unwrap();
+        let registry = ProjectRegistry::with_path(temp.path().join("projects.json"));
+        let a = temp.path().join("a");
+        let b = temp.path().join("b");
+        project(
+            &a,
+            "a",
+            &["six-1.0-py3-none-any.whl", "only_a-1.0-py3-none-any.whl"],
+        );
+        project(&b, "b", &["six-1.0-py3-none-any.whl"]);
+        for root in [&a, &b] {
+            registry
+                .record(&Activity {
+                    root,
+                    command: "install",
+                    lockfile: Some(Path::new("pybun.lockb")),
+                    env: None,
+                })
+                .unwrap();
+        }
+        assert!(matches!(
+            registry.set_pinned(&temp.path().join("nope"), true),
+            Err(RegistryError::NotRegistered(_))
+        ));
+        registry.set_pinned(&a, true).unwrap();
+
+        let records = registry.load().unwrap();
+        assert_eq!(
+            pinned_wheels(&records),
+            BTreeSet::from([
+                "only_a-1.0-py3-none-any.whl".to_string(),
+                "six-1.0-py3-none-any.whl".to_string(),
+            ])
+        );
+
+        let wheels = temp.path().join("wheels");
+        fs::create_dir_all(wheels.join("six")).unwrap();
+        fs::write(wheels.join("six/six-1.0-py3-none-any.whl"), [0u8; 10]).unwrap();
+        fs::write(wheels.join("only_a-1.0-py3-none-any.whl"), [0u8; 5]).unwrap();
+        let usage = disk_usage(&records, &[wheels]);
+        let by_root: BTreeMap<_, _> = records.iter().map(|r| &r.root).zip(usage).collect();
+        let a_usage = by_root[&absolute(&a)];
+        assert_eq!(a_usage.cache_bytes, 15);
+        assert_eq!(a_usage.exclusive_cache_bytes, 5);
+        assert_eq!(by_root[&absolute(&b)].exclusive_cache_bytes, 0);
+    }
+}
@@ -0,0 +1,842 @@
+//! `pybun publish`: upload built distributions to a package index.
+//!
+//! Wheels and sdists (by default everything in `dist/`) are uploaded one at
+//! a time with the Warehouse "legacy" upload API that PyPI, TestPyPI and
+//! most private registries implement: a `multipart/form-data` POST carrying
+//! the file, its SHA-256 and the fields of its core metadata.
+//!
+//! Credentials are taken from the first of:
+//!
+//! 1. `PYBUN_PUBLISH_TOKEN` (an API token, sent as user `__token__`), or
+//!    `PYBUN_PUBLISH_USERNAME` and `PYBUN_PUBLISH_PASSWORD`;
+//! 2. credentials stored for the upload URL by `pybun auth token set` or
+//!    `pybun auth login` (see [`crate::auth`]);
+//! 3. trusted publishing: in a CI job that can issue OIDC ID tokens (GitHub
+//!    Actions, or any CI that puts one in `PYBUN_OIDC_TOKEN`), the ID token
+//!    is exchanged at the index for a short-lived API token.
+//!
+//! Uploads obey the `publish` class of `[tool.pybun.network]` and never
+//! follow redirects, which would drop the upload body. After the first
+//! failed upload the remaining files are not attempted, so a bad token does
+//! not produce one error per file; `--skip-existing` treats files the index
+//! already has as done, which makes an interrupted publish safe to re-run.
+
+use crate::network_policy::{self, NetworkPolicyViolation, Operation};
+use crate::pypi::normalize_project_name;
+use crate::security::sha256_bytes;
+use reqwest::StatusCode;
+use serde::Serialize;
+use serde_json::json;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// API token for the repository.
+pub const TOKEN_ENV: &str = "PYBUN_PUBLISH_TOKEN";
+/// Username for the repository (with [`PASSWORD_ENV`]).
+pub const USERNAME_ENV: &str = "PYBUN_PUBLISH_USERNAME";
+pub const PASSWORD_ENV: &str = "PYBUN_PUBLISH_PASSWORD";
+/// An OIDC ID token issued by the CI system, for trusted publishing outside
+/// GitHub Actions.
+pub const OIDC_TOKEN_ENV: &str = "PYBUN_OIDC_TOKEN";
+
+/// GitHub Actions' ID token endpoint and the bearer token for it.
+const GITHUB_OIDC_URL_ENV: &str = "ACTIONS_ID_TOKEN_REQUEST_URL";
+const GITHUB_OIDC_TOKEN_ENV: &str = "ACTIONS_ID_TOKEN_REQUEST_TOKEN";
+
+/// Repositories known by name.
+const REPOSITORIES: &[(&str, &str)] = &[
+    ("pypi", "https://upload.pypi.org/legacy/"),
+    ("testpypi", "https://test.pypi.org/legacy/"),
+];
+
+#[derive(Debug, Error)]
+pub enum PublishError {
+    #[error("unknown repository '{0}': use pypi, testpypi, or an http(s) upload URL")]
+    UnknownRepository(String),
+    #[error("no distributions to upload in {0}; run `pybun build` first")]
+    NoFiles(PathBuf),
+    #[error("{0} is not a wheel or .tar.gz sdist")]
+    NotADistribution(PathBuf),
+    #[error("invalid distribution {path}: {message}")]
+    Metadata { path: PathBuf, message: String },
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Policy(#[from] NetworkPolicyViolation),
+    #[error("trusted publishing failed: {0}")]
+    TrustedPublishing(String),
+    #[error(
+        "trusted publishing needs a CI job that can issue OIDC ID tokens (or {OIDC_TOKEN_ENV})"
+    )]
+    TrustedPublishingUnavailable,
+}
+
+pub type Result<T> = std::result::Result<T, PublishError>;
+
+/// Whether to use trusted publishing (OIDC).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustedPublishing {
+    /// When no other credentials are found and the job can issue ID tokens.
+    #[default]
+    Auto,
+    /// Always; fail if the job cannot issue ID tokens.
+    Always,
+    Never,
+}
+
+/// Where an upload should go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    /// `pypi`, `testpypi`, or the URL as given.
+    pub name: String,
+    pub upload_url: String,
+}
+
+impl Repository {
+    /// A repository by name (`pypi`, `testpypi`) or upload URL.
+    pub fn resolve(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if let Some((name, url)) = REPOSITORIES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        {
+            return Ok(Self {
+                name: name.to_string(),
+                upload_url: url.to_string(),
+            });
+        }
+        match reqwest::Url::parse(value) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => Ok(Self {
+                name: value.to_string(),
+                upload_url: value.to_string(),
+            }),
+            _ => Err(PublishError::UnknownRepository(value.to_string())),
+        }
+    }
+
+    /// Base URL of the index's trusted publishing endpoints: PyPI and
+    /// TestPyPI serve them from the main site, other indexes from the
+    /// upload host.
+    fn index_url(&self) -> String {
+        let Ok(url) = reqwest::Url::parse(&self.upload_url) else {
+            return self.upload_url.clone();
+        };
+        match url.host_str() {
+            Some("upload.pypi.org") => "https://pypi.org".to_string(),
+            Some(host) => {
+                let port = url.port().map(|p| format!(":{p}")).unwrap_or_default();
+                format!("{}://{host}{port}", url.scheme())
+            }
+            None => self.upload_url.clone(),
+        }
+    }
+}
+
+/// Kind of a distribution file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filetype {
+    BdistWheel,
+    Sdist,
+}
+
+impl Filetype {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Filetype::BdistWheel => "bdist_wheel",
+            Filetype::Sdist => "sdist",
+        }
+    }
+}
+
+/// A distribution ready to upload.
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    pub path: PathBuf,
+    pub filename: String,
+    pub filetype: Filetype,
+    pub name: String,
+    pub version: String,
+    /// Python tag of a wheel, `source` for an sdist.
+    pub pyversion: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Core metadata as upload form fields.
+    metadata: Vec<(String, String)>,
+}
+
+/// The distributions in `paths` (files, or directories whose wheels and
+/// sdists are taken), sorted by file name with sdists first.
+pub fn collect(paths: &[PathBuf]) -> Result<Vec<Distribution>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let entries = fs::read_dir(path).map_err(|source| PublishError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            let before = files.len();
+            for entry in entries.flatten() {
+                let file = entry.path();
+                if file.is_file() && filetype_of(&file).is_some() {
+                    files.push(file);
+                }
+            }
+            if files.len() == before {
+                return Err(PublishError::NoFiles(path.clone()));
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    let mut distributions = files
+        .iter()
+        .map(|path| read_distribution(path))
+        .collect::<Result<Vec<_>>>()?;
+    distributions.sort_by(|a, b| {
+        (a.filetype != Filetype::Sdist, &a.filename)
+            .cmp(&(b.filetype != Filetype::Sdist, &b.filename))
+    });
+    distributions.dedup_by(|a, b| a.filename == b.filename);
+    Ok(distributions)
+}
+
+fn filetype_of(path: &Path) -> Option<Filetype> {
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(".whl") {
+        Some(Filetype::BdistWheel)
+    } else if name.ends_with(".tar.gz") {
+        Some(Filetype::Sdist)
+    } else {
+        None
+    }
+}
+
+/// Read the metadata of the wheel or sdist at `path` and check that it
+/// matches the file name.
+pub fn read_distribution(path: &Path) -> Result<Distribution> {
+    let filetype =
+        filetype_of(path).ok_or_else(|| PublishError::NotADistribution(path.to_path_buf()))?;
+    let invalid = |message: String| PublishError::Metadata {
+        path: path.to_path_buf(),
+        message,
+    };
+    let data = fs::read(path).map_err(|source| PublishError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let (file_name, file_version, pyversion) = match filetype {
+        Filetype::BdistWheel => {
+            let stem = filename.trim_end_matches(".whl");
+            let parts: Vec<&str> = stem.split('-').collect();
+            if !(5..=6).contains(&parts.len()) {
+                return Err(invalid(
+                    "wheel file name is not NAME-VERSION-PY-ABI-PLATFORM.whl".into(),
+                ));
+            }
+            (parts[0], parts[1], parts[parts.len() - 3].to_string())
+        }
+        Filetype::Sdist => {
+            let stem = filename.trim_end_matches(".tar.gz");
+            let (name, version) = stem
+                .rsplit_once('-')
+                .ok_or_else(|| invalid("sdist file name is not NAME-VERSION.tar.gz".into()))?;
+            (name, version, "source".to_string())
+        }
+    };
+    let text = match filetype {
+        Filetype::BdistWheel => wheel_metadata(&data),
+        Filetype::Sdist => sdist_metadata(&data),
+    }
+    .map_err(invalid)?;
+    let metadata = form_fields(&text);
+    let field = |key: &str| {
+        metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+    let name = field("name").ok_or_else(|| invalid("metadata has no Name".into()))?;
+    let version = field("version").ok_or_else(|| invalid("metadata has no Version".into()))?;
+    if field("metadata_version").is_none() {
+        return Err(invalid("metadata has no Metadata-Version".into()));
+    }
+    if normalize_project_name(&name) != normalize_project_name(file_name) || version != file_version
+    {
+        return Err(invalid(format!(
+            "metadata names {name} {version}, which does not match the file name"
+        )));
+    }
+    Ok(Distribution {
+        path: path.to_path_buf(),
+        filename,
+        filetype,
+        name,
+        version,
+        pyversion,
+        size: data.len() as u64,
+        sha256: sha256_bytes(&data),
+        metadata,
+    })
+}
+
+/// `METADATA` of the wheel's top-level `.dist-info` directory.
+fn wheel_metadata(data: &[u8]) -> std::result::Result<String, String> {
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(data)).map_err(|e| e.to_string())?;
+    let entry = archive
+        .file_names()
+        .find(|name| {
+            name.strip_suffix("/METADATA")
+                .is_some_and(|dir| dir.ends_with(".dist-info") && !dir.contains('/'))
+        })
+        .map(str::to_string)
+        .ok_or("wheel has no .dist-info/METADATA")?;
+    let mut text = String::new();
+    archive
+        .by_name(&entry)
+        .map_err(|e| e.to_string())?
+        .read_to_string(&mut text)
+        .map_err(|e| e.to_string())?;
+    Ok(text)
+}
+
+/// `PKG-INFO` of the sdist's top-level directory.
+fn sdist_metadata(data: &[u8]) -> std::result::Result<String, String> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(data));
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path().map_err(|e| e.to_string())?;
+        if path.components().count() == 2 && path.ends_with("PKG-INFO") {
+            let mut text = String::new();
+            entry.read_to_string(&mut text).map_err(|e| e.to_string())?;
+            return Ok(text);
+        }
+    }
+    Err("sdist has no PKG-INFO".to_string())
+}
+
+/// Core metadata as the upload API's form fields: header names lowercased
+/// with `_` for `-` (and the plural names Warehouse expects for repeatable
+/// ones), the body as `description`.
+fn form_fields(text: &str) -> Vec<(String, String)> {
+    let text = text.replace("\r\n", "\n");
+    let (headers, body) = text.split_once("\n\n").unwrap_or((&text, ""));
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in headers.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim_start().trim_start_matches('|'));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = match key.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "classifier" => "classifiers".to_string(),
+            "project_url" => "project_urls".to_string(),
+            "license_file" => "license_files".to_string(),
+            other => other.to_string(),
+        };
+        fields.push((key, value.trim().to_string()));
+    }
+    if !body.trim().is_empty() && !fields.iter().any(|(k, _)| k == "description") {
+        fields.push(("description".to_string(), body.to_string()));
+    }
+    fields
+}
+
+/// Where the upload credentials come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSource {
+    /// [`TOKEN_ENV`], or [`USERNAME_ENV`] and [`PASSWORD_ENV`].
+    Env,
+    /// Stored by `pybun auth`.
+    Keyring,
+    TrustedPublishing,
+}
+
+#[derive(Clone)]
+pub struct Credentials {
+    pub username: String,
+    password: String,
+    pub source: CredentialSource,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An ID token source available to this process, if any.
+fn oidc_available() -> bool {
+    std::env::var(OIDC_TOKEN_ENV).is_ok_and(|t| !t.is_empty())
+        || (std::env::var(GITHUB_OIDC_URL_ENV).is_ok()
+            && std::env::var(GITHUB_OIDC_TOKEN_ENV).is_ok())
+}
+
+/// Credentials that do not need the network: the environment, then stored
+/// credentials. `None` when none are configured.
+fn static_credentials(repository: &Repository) -> Option<Credentials> {
+    let env = |var: &str| std::env::var(var).ok().filter(|v| !v.is_empty());
+    if let Some(token) = env(TOKEN_ENV) {
+        return Some(Credentials {
+            username: crate::auth::TOKEN_USERNAME.to_string(),
+            password: token,
+            source: CredentialSource::Env,
+        });
+    }
+    if let (Some(username), Some(password)) = (env(USERNAME_ENV), env(PASSWORD_ENV)) {
+        return Some(Credentials {
+            username,
+            password,
+            source: CredentialSource::Env,
+        });
+    }
+    crate::auth::credentials_for(&repository.upload_url).map(|(username, password)| Credentials {
+        username,
+        password,
+        source: CredentialSource::Keyring,
+    })
+}
+
+/// The credential source `credentials` would use, without contacting
+/// anything (for `--dry-run`).
+pub fn credential_source(
+    repository: &Repository,
+    trusted: TrustedPublishing,
+) -> Option<CredentialSource> {
+    let oidc = oidc_available().then_some(CredentialSource::TrustedPublishing);
+    match trusted {
+        TrustedPublishing::Always => oidc,
+        TrustedPublishing::Never => static_credentials(repository).map(|c| c.source),
+        TrustedPublishing::Auto => static_credentials(repository).map(|c| c.source).or(oidc),
+    }
+}
+
+/// Credentials for uploading to `repository`; trusted publishing mints an
+/// API token at the index. `None` when nothing is configured.
+pub async fn credentials(
+    repository: &Repository,
+    trusted: TrustedPublishing,
+) -> Result<Option<Credentials>> {
+    let use_oidc = match trusted {
+        TrustedPublishing::Always => true,
+        TrustedPublishing::Never => false,
+        TrustedPublishing::Auto => {
+            if let Some(credentials) = static_credentials(repository) {
+                return Ok(Some(credentials));
+            }
+            oidc_available()
+        }
+    };
+    if !use_oidc {
+        return Ok(static_credentials(repository));
+    }
+    if !oidc_available() {
+        return Err(PublishError::TrustedPublishingUnavailable);
+    }
+    let client = client()?;
+    let token = mint_token(&client, &repository.index_url()).await?;
+    Ok(Some(Credentials {
+        username: crate::auth::TOKEN_USERNAME.to_string(),
+        password: token,
+        source: CredentialSource::TrustedPublishing,
+    }))
+}
+
+fn client() -> Result<reqwest::Client> {
+    crate::http_config::client_builder()
+        .timeout(Duration::from_secs(600))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| PublishError::TrustedPublishing(e.to_string()))
+}
+
+/// Exchange an OIDC ID token for a short-lived API token at `index`.
+async fn mint_token(client: &reqwest::Client, index: &str) -> Result<String> {
+    let failed = |what: &str, e: &dyn std::fmt::Display| {
+        PublishError::TrustedPublishing(format!("{what}: {e}"))
+    };
+    let get_json = |request: reqwest::RequestBuilder, what: &'static str| async move {
+        let response = request.send().await.map_err(|e| failed(what, &e))?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let message = body["message"]
+                .as_str()
+                .or_else(|| body["errors"][0]["description"].as_str())
+                .map(|m| format!("HTTP {status}: {m}"))
+                .unwrap_or_else(|| format!("HTTP {status}"));
+            return Err(failed(what, &message));
+        }
+        Ok(body)
+    };
+
+    let audience_url = format!("{index}/_/oidc/audience");
+    network_policy::check_url(Operation::Publish, &audience_url)?;
+    let audience = get_json(client.get(&audience_url), "audience request").await?["audience"]
+        .as_str()
+        .ok_or_else(|| failed("audience request", &"response has no `audience`"))?
+        .to_string();
+
+    let id_token = match std::env::var(OIDC_TOKEN_ENV).ok().filter(|t| !t.is_empty()) {
+        Some(token) => token,
+        None => {
+            let request_url = std::env::var(GITHUB_OIDC_URL_ENV).unwrap_or_default();
+            let bearer = std::env::var(GITHUB_OIDC_TOKEN_ENV).unwrap_or_default();
+            let mut url =
+                reqwest::Url::parse(&request_url).map_err(|e| failed("ID token request", &e))?;
+            url.query_pairs_mut().append_pair("audience", &audience);
+            network_policy::check_url(Operation::Publish, url.as_str())?;
+            get_json(client.get(url).bearer_auth(bearer), "ID token request").await?["value"]
+                .as_str()
+                .ok_or_else(|| failed("ID token request", &"response has no `value`"))?
+                .to_string()
+        }
+    };
+
+    let mint_url = format!("{index}/_/oidc/mint-token");
+    network_policy::check_url(Operation::Publish, &mint_url)?;
+    get_json(
+        client.post(&mint_url).json(&json!({ "token": id_token })),
+        "token exchange",
+    )
+    .await?["token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| failed("token exchange", &"response has no `token`"))
+}
+
+/// Outcome of one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadStatus {
+    Uploaded,
+    /// The index already has the file (`--skip-existing`).
+    Exists,
+    Failed,
+    /// Not tried because an earlier upload failed.
+    NotAttempted,
+    /// Checked only (`--dry-run`).
+    DryRun,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReport {
+    pub filename: String,
+    pub path: PathBuf,
+    pub name: String,
+    pub version: String,
+    pub filetype: Filetype,
+    pub size: u64,
+    pub sha256: String,
+    pub status: UploadStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl FileReport {
+    pub fn new(dist: &Distribution, status: UploadStatus) -> Self {
+        Self {
+            filename: dist.filename.clone(),
+            path: dist.path.clone(),
+            name: dist.name.clone(),
+            version: dist.version.clone(),
+            filetype: dist.filetype,
+            size: dist.size,
+            sha256: dist.sha256.clone(),
+            status,
+            http_status: None,
+            message: None,
+        }
+    }
+}
+
+/// Upload `dists` in order. Stops at the first failure; the rest are
+/// reported as [`UploadStatus::NotAttempted`].
+pub async fn upload(
+    repository: &Repository,
+    dists: &[Distribution],
+    credentials: &Credentials,
+    skip_existing: bool,
+) -> Result<Vec<FileReport>> {
+    network_policy::check_url(Operation::Publish, &repository.upload_url)?;
+    let client = client()?;
+    let mut reports = Vec::with_capacity(dists.len());
+    let mut failed = false;
+    for dist in dists {
+        if failed {
+            reports.push(FileReport::new(dist, UploadStatus::NotAttempted));
+            continue;
+        }
+        let report = upload_one(&client, repository, dist, credentials, skip_existing).await?;
+        failed = report.status == UploadStatus::Failed;
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+async fn upload_one(
+    client: &reqwest::Client,
+    repository: &Repository,
+    dist: &Distribution,
+    credentials: &Credentials,
+    skip_existing: bool,
+) -> Result<FileReport> {
+    let content = fs::read(&dist.path).map_err(|source| PublishError::Io {
+        path: dist.path.clone(),
+        source,
+    })?;
+    let boundary = format!("pybun-{}", &dist.sha256[..32]);
+    let body = multipart_body(&boundary, dist, &content);
+    let mut report = FileReport::new(dist, UploadStatus::Failed);
+    let response = client
+        .post(&repository.upload_url)
+        .basic_auth(&credentials.username, Some(&credentials.password))
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(body)
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            report.message = Some(crate::http_config::error_chain(&e));
+            return Ok(report);
+        }
+    };
+    let status = response.status();
+    report.http_status = Some(status.as_u16());
+    let location = response
+        .headers()
+        .get("location")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let text = response_text(&response.text().await.unwrap_or_default());
+    let already_exists = status == StatusCode::CONFLICT
+        || (status == StatusCode::BAD_REQUEST
+            && text.to_ascii_lowercase().contains("already exist"));
+    if status.is_success() {
+        report.status = UploadStatus::Uploaded;
+    } else if already_exists && skip_existing {
+        report.status = UploadStatus::Exists;
+    } else {
+        report.message = Some(if status.is_redirection() {
+            format!(
+                "HTTP {status}: redirected to {}; use the upload URL itself",
+                location.as_deref().unwrap_or("another URL")
+            )
+        } else if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            format!(
+                "HTTP {status}: the index rejected the {} credentials{}",
+                match credentials.source {
+                    CredentialSource::Env => "environment",
+                    CredentialSource::Keyring => "stored",
+                    CredentialSource::TrustedPublishing => "trusted publishing",
+                },
+                if text.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {text}")
+                }
+            )
+        } else if text.is_empty() {
+            format!("HTTP {status}")
+        } else {
+            format!("HTTP {status}: {text}")
+        });
+    }
+    Ok(report)
+}
+
+/// The upload form: protocol fields, metadata, digest, then the file.
+fn multipart_body(boundary: &str, dist: &Distribution, content: &[u8]) -> Vec<u8> {
+    let mut fields: Vec<(&str, &str)> = vec![
+        (":action", "file_upload"),
+        ("protocol_version", "1"),
+        ("filetype", dist.filetype.as_str()),
+        ("pyversion", &dist.pyversion),
+        ("sha256_digest", &dist.sha256),
+    ];
+    fields.extend(dist.metadata.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+    let mut body = Vec::with_capacity(content.len() + 4096);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"content\"; filename=\"{}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n",
+            dist.filename
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    body
+}
+
+/// The readable part of an error response: tags stripped, whitespace
+/// collapsed, at most 300 characters.
+fn response_text(body: &str) -> String {
+    let mut text = String::with_capacity(body.len());
+    let mut in_tag = false;
+    for c in body.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(300) {
+        Some((index, _)) => format!("{}...", &text[..index]),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    const METADATA: &str = "Metadata-Version: 2.1\nName: demo-pkg\nVersion: 1.0\n\
+        Summary: A demo\nClassifier: A :: B\nClassifier: C :: D\n\
+        Project-URL: Home, https://example.com\n\n# Demo\n\nLong text.\n";
+
+    fn wheel(dir: &Path, filename: &str, metadata: &str) -> PathBuf {
+        let path = dir.join(filename);
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file(
+            "demo_pkg-1.0.dist-info/METADATA",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(metadata.as_bytes()).unwrap();
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn repositories_resolve_by_name_or_url() {
+        let pypi = Repository::resolve("PyPI").unwrap();
+        assert_eq!(pypi.upload_url, "https://upload.pypi.org/legacy/");
+        assert_eq!(pypi.index_url(), "https://pypi.org");
+        let test = Repository::resolve("testpypi").unwrap();
+        assert_eq!(test.index_url(), "https://test.pypi.org");
+        let custom = Repository::resolve("http://127.0.0.1:8080/legacy/").unwrap();
+        assert_eq!(custom.index_url(), "http://127.0.0.1:8080");
+        assert!(matches!(
+            Repository::resolve("nexus"),
+            Err(PublishError::UnknownRepository(_))
+        ));
+    }
+
+    #[test]
+    fn metadata_becomes_upload_fields() {
+        let fields = form_fields(METADATA);
+        let get = |key: &str| -> Vec<&str> {
+            fields
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .collect()
+        };
+        assert_eq!(get("metadata_version"), ["2.1"]);
+        assert_eq!(get("classifiers"), ["A :: B", "C :: D"]);
+        assert_eq!(get("project_urls"), ["Home, https://example.com"]);
+        assert_eq!(get("description"), ["# Demo\n\nLong text.\n"]);
+    }
+
+    #[test]
+    fn distributions_are_read_and_checked_against_their_file_names() {
+        let temp = tempdir().unwrap();
+        let path = wheel(temp.path(), "demo_pkg-1.0-py3-none-any.whl", METADATA);
+        let dist = read_distribution(&path).unwrap();
+        assert_eq!(
+            (
+                dist.name.as_str(),
+                dist.version.as_str(),
+                dist.pyversion.as_str()
+            ),
+            ("demo-pkg", "1.0", "py3")
+        );
+        assert_eq!(dist.filetype, Filetype::BdistWheel);
+
+        let wrong = wheel(temp.path(), "demo_pkg-2.0-py3-none-any.whl", METADATA);
+        assert!(matches!(
+            read_distribution(&wrong),
+            Err(PublishError::Metadata { .. })
+        ));
+        fs::write(temp.path().join("notes.txt"), "x").unwrap();
+        assert!(matches!(
+            read_distribution(&temp.path().join("notes.txt")),
+            Err(PublishError::NotADistribution(_))
+        ));
+    }
+
+    #[test]
+    fn multipart_body_carries_fields_and_file() {
+        let temp = tempdir().unwrap();
+        let path = wheel(temp.path(), "demo_pkg-1.0-py3-none-any.whl", METADATA);
+        let dist = read_distribution(&path).unwrap();
+        let body = multipart_body("B", &dist, b"WHEEL");
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.starts_with(
+            "--B\r\nContent-Disposition: form-data; name=\":action\"\r\n\r\nfile_upload\r\n"
+        ));
+        assert!(body.contains("name=\"filetype\"\r\n\r\nbdist_wheel\r\n"));
+        assert!(body.contains(&format!(
+            "name=\"sha256_digest\"\r\n\r\n{}\r\n",
+            dist.sha256
+        )));
+        assert!(body.ends_with(
+            "filename=\"demo_pkg-1.0-py3-none-any.whl\"\r\nContent-Type: application/octet-stream\r\n\r\nWHEEL\r\n--B--\r\n"
+        ));
+    }
+
+    #[test]
+    fn error_pages_are_reduced_to_text() {
+        assert_eq!(
+            response_text(
+                "<html><title>400 File already exists</title><body>\n  <h1>Oops</h1></body></html>"
+            ),
+            "400 File already exists Oops"
+        );
+    }
+}
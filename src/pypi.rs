@@ -1,10 +1,11 @@
 use crate::lockfile::PackageSource;
+use crate::network_policy::{self, Operation};
 use crate::once_map::OnceMap;
 use crate::resolver::{PackageArtifacts, PackageIndex, Requirement, ResolvedPackage, Wheel};
 use dashmap::DashMap;
 use reqwest::{StatusCode, Url, header};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -25,6 +26,38 @@ pub enum PyPiError {
     Io(String),
     #[error("parse error: {0}")]
     Parse(String),
+    #[error("sdist metadata for {package}: {message}")]
+    SdistMetadata { package: String, message: String },
+    #[error("{0}")]
+    NetworkPolicy(String),
+    #[error("{0}")]
+    Policy(String),
+}
+
+impl PyPiError {
+    /// Cache miss for `what` while offline, recorded as missing metadata for
+    /// the `E_NETWORK_REQUIRED` report.
+    pub(crate) fn offline_miss(what: impl Into<String>) -> Self {
+        let what = what.into();
+        crate::offline::require(crate::offline::MissingArtifact::new(
+            crate::offline::ArtifactKind::Metadata,
+            what.clone(),
+            None,
+        ));
+        Self::OfflineCacheMiss(what)
+    }
+}
+
+impl From<crate::network_policy::NetworkPolicyViolation> for PyPiError {
+    fn from(value: crate::network_policy::NetworkPolicyViolation) -> Self {
+        Self::NetworkPolicy(value.to_string())
+    }
+}
+
+impl From<crate::execution_policy::PolicyViolation> for PyPiError {
+    fn from(value: crate::execution_policy::PolicyViolation) -> Self {
+        Self::Policy(value.to_string())
+    }
 }
 
 impl From<reqwest::Error> for PyPiError {
@@ -54,11 +87,26 @@ impl PyPiIndex {
         }
     }
 
+    pub fn index_url(&self) -> String {
+        self.client.index_url()
+    }
+
     /// Drain and return any notices about stale/corrupt cache entries that
     /// were discarded (treated as cache misses) since the last call.
     pub fn take_stale_cache_notices(&self) -> Vec<String> {
         self.client.take_stale_cache_notices()
     }
+
+    /// Normalized names of packages whose dependencies were computed by
+    /// running their sdist's build backend (see [`crate::sdist_metadata`]).
+    pub fn dynamic_metadata_packages(&self) -> BTreeSet<String> {
+        self.client.dynamic_metadata_packages()
+    }
+
+    /// See [`PyPiClient::project_history`].
+    pub async fn project_history(&self, name: &str) -> Result<Option<ProjectHistory>, PyPiError> {
+        self.client.project_history(name).await
+    }
 }
 
 impl PackageIndex for PyPiIndex {
@@ -132,11 +180,15 @@ impl PackageIndex for PyPiIndex {
 pub struct PyPiClient {
     base: Url,
     cache_dir: PathBuf,
+    /// Read-only lower layer of the metadata cache (read-only cache mode).
+    shared_cache_dir: Option<PathBuf>,
     http: reqwest::Client,
     offline: bool,
     package_once: Arc<OnceMap<String, Vec<CachedPackage>>>,
     deps_once: Arc<OnceMap<String, Vec<String>>>,
     stale_cache_notices: Arc<Mutex<Vec<String>>>,
+    sdist_python: Option<PathBuf>,
+    dynamic_metadata: Arc<Mutex<BTreeSet<String>>>,
 }
 
 fn resolve_pypi_cache_dir(
@@ -165,14 +217,35 @@ impl PyPiClient {
         Ok(Self {
             base: normalized,
             cache_dir,
-            http: reqwest::Client::builder().user_agent("pybun/0.1").build()?,
+            shared_cache_dir: None,
+            http: crate::http_config::client_builder()
+                .user_agent("pybun/0.1")
+                .redirect(network_policy::redirect_policy(Operation::Index))
+                .build()?,
             offline,
             package_once: Arc::new(OnceMap::new()),
             deps_once: Arc::new(OnceMap::new()),
             stale_cache_notices: Arc::new(Mutex::new(Vec::new())),
+            sdist_python: None,
+            dynamic_metadata: Arc::new(Mutex::new(BTreeSet::new())),
         })
     }
 
+    /// Also read cache entries from `dir` when `cache_dir` lacks them; new
+    /// entries are still only written to `cache_dir`.
+    pub fn with_shared_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.shared_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Interpreter used to run sdist build backends when a release has no
+    /// static dependency metadata. Defaults to the project's Python (see
+    /// [`crate::env::find_python_env`]).
+    pub fn with_sdist_python(mut self, python: impl Into<PathBuf>) -> Self {
+        self.sdist_python = Some(python.into());
+        self
+    }
+
     /// Production wrapper over [`PyPiClient::with_config`] that reads
     /// `PYBUN_PYPI_BASE_URL` / `PYBUN_PYPI_CACHE_DIR` from the environment.
     pub fn from_env(offline: bool) -> Result<Self, PyPiError> {
@@ -180,7 +253,15 @@ impl PyPiClient {
             std::env::var("PYBUN_PYPI_BASE_URL").unwrap_or_else(|_| "https://pypi.org".to_string());
         let cache_dir_override = std::env::var("PYBUN_PYPI_CACHE_DIR").ok();
         let cache_dir = resolve_pypi_cache_dir(cache_dir_override.as_deref(), dirs::cache_dir())?;
-        Self::with_config(&base, cache_dir, offline)
+        let layers =
+            readonly_layers(cache_dir.clone(), "pypi").map_err(|e| PyPiError::Io(e.to_string()))?;
+        match layers {
+            Some(layers) => {
+                Ok(Self::with_config(&base, layers.overlay, offline)?
+                    .with_shared_cache(layers.shared))
+            }
+            None => Self::with_config(&base, cache_dir, offline),
+        }
     }
 
     /// Drain and return any notices about stale/corrupt cache entries that
@@ -193,6 +274,14 @@ impl PyPiClient {
         std::mem::take(&mut *notices)
     }
 
+    /// Names of packages whose dependencies came from dynamic sdist metadata.
+    pub fn dynamic_metadata_packages(&self) -> BTreeSet<String> {
+        self.dynamic_metadata
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
     async fn get_or_fetch(
         &self,
         name: &str,
@@ -229,8 +318,7 @@ impl PyPiClient {
         let cached_entry = self.load_cache(name).await?;
 
         if self.offline {
-            let entry =
-                cached_entry.ok_or_else(|| PyPiError::OfflineCacheMiss(name.to_string()))?;
+            let entry = cached_entry.ok_or_else(|| PyPiError::offline_miss(name))?;
             return Ok(entry.packages);
         }
 
@@ -240,11 +328,12 @@ impl PyPiClient {
             return Ok(entry.packages.clone());
         }
 
-        let mut req = self.http.get(
-            self.base
-                .join(&format!("pypi/{name}/json"))
-                .map_err(|e| PyPiError::Parse(e.to_string()))?,
-        );
+        let url = self
+            .base
+            .join(&format!("pypi/{name}/json"))
+            .map_err(|e| PyPiError::Parse(e.to_string()))?;
+        network_policy::check_url(Operation::Index, url.as_str())?;
+        let mut req = crate::auth::authorize(self.http.get(url.clone()), url.as_str());
         if let Some(entry) = &cached_entry {
             if let Some(etag) = &entry.policy.etag {
                 req = req.header(header::IF_NONE_MATCH, etag.as_str());
@@ -257,8 +346,7 @@ impl PyPiClient {
         let resp = req.send().await?;
 
         if resp.status() == StatusCode::NOT_MODIFIED {
-            let entry =
-                cached_entry.ok_or_else(|| PyPiError::OfflineCacheMiss(name.to_string()))?;
+            let entry = cached_entry.ok_or_else(|| PyPiError::offline_miss(name))?;
             return Ok(entry.packages);
         }
 
@@ -304,21 +392,141 @@ impl PyPiClient {
         Ok(entry.packages)
     }
 
-    async fn fetch_requires_dist(
+    async fn fetch_version_info(
         &self,
         name: &str,
         version: &str,
-    ) -> Result<Vec<String>, PyPiError> {
+    ) -> Result<Option<VersionResponse>, PyPiError> {
         let url = self
             .base
             .join(&format!("pypi/{}/{}/json", name, version))
             .map_err(|e| PyPiError::Parse(e.to_string()))?;
-        let resp = self.http.get(url).send().await?;
+        network_policy::check_url(Operation::Index, url.as_str())?;
+        let resp = crate::auth::authorize(self.http.get(url.clone()), url.as_str())
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+        Ok(Some(resp.json().await?))
+    }
+
+    /// Release history of `name` from its cached project JSON (fetched while
+    /// resolving). Online, the previous release's metadata is fetched for
+    /// its maintainers. `None` when the project JSON is not cached.
+    pub async fn project_history(&self, name: &str) -> Result<Option<ProjectHistory>, PyPiError> {
+        let Some(entry) = self.load_cache(name).await? else {
+            return Ok(None);
+        };
+        let parsed: HistoryResponse = serde_json::from_slice(&entry.body)
+            .map_err(|e| PyPiError::Parse(format!("json decode error: {}", e)))?;
+        let mut releases: Vec<(&str, &str)> = parsed
+            .releases
+            .iter()
+            .filter_map(|(version, files)| {
+                let first = files.iter().filter_map(UploadedFile::uploaded).min()?;
+                Some((first, version.as_str()))
+            })
+            .collect();
+        releases.sort();
+        let mut history = ProjectHistory {
+            first_upload: releases.first().map(|(time, _)| time.to_string()),
+            maintainers: maintainer_emails([
+                &parsed.info.author_email,
+                &parsed.info.maintainer_email,
+            ]),
+            ..ProjectHistory::default()
+        };
+        let Some(latest) = parsed.info.version else {
+            return Ok(Some(history));
+        };
+        let previous = releases
+            .iter()
+            .position(|(_, version)| *version == latest)
+            .and_then(|at| at.checked_sub(1))
+            .map(|at| releases[at].1.to_string());
+        history.latest_version = Some(latest);
+        if let Some(previous) = previous
+            && !self.offline
+            && !history.maintainers.is_empty()
+            && let Some(info) = self.fetch_version_info(name, &previous).await?
+        {
+            history.previous_maintainers = info.info.maintainers();
+            history.previous_version = Some(previous);
+        }
+        Ok(Some(history))
+    }
+
+    /// Dependencies of an sdist-only release, read from the sdist itself.
+    /// Results are cached by sdist hash so each archive is processed once.
+    async fn sdist_requires_dist(
+        &self,
+        name: &str,
+        sdist: &ReleaseFile,
+    ) -> Result<crate::sdist_metadata::SdistMetadata, PyPiError> {
+        let fail = |message: String| PyPiError::SdistMetadata {
+            package: name.to_string(),
+            message,
+        };
+        let sha256 = sdist
+            .digests
+            .as_ref()
+            .and_then(|d| d.get("sha256"))
+            .cloned()
+            .ok_or_else(|| fail(format!("{} has no sha256 digest", sdist.filename)))?;
+        let cache = crate::sdist_metadata::SdistMetadataCache::with_root(
+            self.cache_dir.join("sdist-metadata"),
+        );
+        if let Some(metadata) = cache.get(&sha256) {
+            return Ok(metadata);
+        }
+
+        network_policy::check_url(Operation::Index, &sdist.url)?;
+        let resp = crate::auth::authorize(self.http.get(&sdist.url), &sdist.url)
+            .send()
+            .await?;
         if !resp.status().is_success() {
-            return Ok(Vec::new());
+            return Err(fail(format!(
+                "download of {} failed with {}",
+                sdist.filename,
+                resp.status()
+            )));
         }
-        let body: VersionResponse = resp.json().await?;
-        Ok(body.info.requires_dist.unwrap_or_default())
+        let bytes = resp.bytes().await?;
+        let python = match &self.sdist_python {
+            Some(python) => python.clone(),
+            None => {
+                let cwd = std::env::current_dir()?;
+                crate::env::find_python_env(&cwd)
+                    .map_err(|e| fail(e.to_string()))?
+                    .python_path
+            }
+        };
+        let filename = sdist.filename.clone();
+        let expected = sha256.clone();
+        let metadata = tokio::task::spawn_blocking(move || {
+            let work = tempfile::tempdir()?;
+            let archive = work.path().join(&filename);
+            fs::write(&archive, &bytes)?;
+            let actual = crate::archive::sha256_file(&archive)?;
+            if !actual.eq_ignore_ascii_case(&expected) {
+                return Err(crate::sdist_metadata::SdistMetadataError::Archive(
+                    crate::archive::ArchiveError::ChecksumMismatch {
+                        path: archive,
+                        expected,
+                        actual,
+                    },
+                ));
+            }
+            crate::sdist_metadata::extract(&archive, &python, work.path())
+        })
+        .await
+        .map_err(|e| fail(format!("join error: {}", e)))?
+        .map_err(|e| fail(e.to_string()))?;
+        cache
+            .put(&sha256, &metadata)
+            .map_err(|e| fail(e.to_string()))?;
+        Ok(metadata)
     }
 
     fn cache_path(&self, name: &str) -> PathBuf {
@@ -330,8 +538,9 @@ impl PyPiClient {
     }
 
     async fn load_cache(&self, name: &str) -> Result<Option<CacheEntry>, PyPiError> {
-        let path = self.cache_path(name);
-        let legacy_path = self.legacy_cache_path(name);
+        let shared = self.shared_cache_dir.as_deref();
+        let path = layered_path(&self.cache_dir, shared, self.cache_path(name));
+        let legacy_path = layered_path(&self.cache_dir, shared, self.legacy_cache_path(name));
         let now = now_epoch_seconds();
         let (entry, stale_notice) =
             tokio::task::spawn_blocking(move || load_cache_from_paths(&path, &legacy_path, now))
@@ -395,6 +604,8 @@ impl PyPiClient {
                 })
                 .collect(),
             sdist: pkg.sdist.clone(),
+            sdist_url: pkg.sdist_url.clone(),
+            sdist_hash: pkg.sdist_hash.clone(),
         };
         ResolvedPackage {
             name: pkg.name.clone(),
@@ -427,26 +638,64 @@ impl PyPiClient {
                 let memory = Arc::clone(&memory);
                 async move {
                     if client.offline {
-                        return Err(PyPiError::OfflineCacheMiss(format!(
+                        return Err(PyPiError::offline_miss(format!(
                             "{}=={}",
                             name_owned, version_owned
                         )));
                     }
 
-                    let raw_deps = client
-                        .fetch_requires_dist(&name_owned, &version_owned)
+                    let info = client
+                        .fetch_version_info(&name_owned, &version_owned)
                         .await?;
+                    let sdist_only = memory.get(&name_owned).is_some_and(|packages| {
+                        packages
+                            .iter()
+                            .any(|pkg| pkg.version == version_owned && pkg.wheels.is_empty())
+                    });
+                    let (raw_deps, dynamic) = match info {
+                        Some(VersionResponse {
+                            info:
+                                VersionInfo {
+                                    requires_dist: None,
+                                    ..
+                                },
+                            urls,
+                        }) if sdist_only => {
+                            match urls.iter().find(|file| file.packagetype == "sdist") {
+                                Some(sdist) => {
+                                    let metadata =
+                                        client.sdist_requires_dist(&name_owned, sdist).await?;
+                                    let dynamic = metadata.is_dynamic();
+                                    (metadata.requires_dist, dynamic)
+                                }
+                                None => (Vec::new(), false),
+                            }
+                        }
+                        Some(response) => (response.info.requires_dist.unwrap_or_default(), false),
+                        None => (Vec::new(), false),
+                    };
+                    if dynamic {
+                        client
+                            .dynamic_metadata
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner())
+                            .insert(normalize_project_name(&name_owned));
+                    }
                     let deps = raw_deps
                         .into_iter()
                         .filter_map(parse_requires_dist)
                         .map(|req| req.to_string())
                         .collect::<Vec<_>>();
+                    // Dynamic results are not written to the index cache: the
+                    // sdist-hash cache keeps them cheap while the
+                    // dynamic-metadata flag is re-established on every run.
                     client
                         .update_cached_dependencies(
                             &name_owned,
                             &version_owned,
                             deps.clone(),
                             &memory,
+                            !dynamic,
                         )
                         .await?;
                     Ok::<Vec<String>, PyPiError>(deps)
@@ -476,12 +725,16 @@ impl PyPiClient {
         version: &str,
         deps: Vec<String>,
         memory: &Arc<DashMap<String, Vec<CachedPackage>>>,
+        persist: bool,
     ) -> Result<(), PyPiError> {
         if let Some(mut packages) = memory.get_mut(name)
             && let Some(pkg) = packages.iter_mut().find(|pkg| pkg.version == version)
         {
             pkg.dependencies = Some(deps.clone());
         }
+        if !persist {
+            return Ok(());
+        }
 
         if let Some(mut entry) = self.load_cache(name).await? {
             if let Some(pkg) = entry.packages.iter_mut().find(|pkg| pkg.version == version) {
@@ -525,12 +778,94 @@ struct ReleaseFile {
 #[derive(Debug, Deserialize)]
 struct VersionResponse {
     info: VersionInfo,
+    #[serde(default)]
+    urls: Vec<ReleaseFile>,
 }
 
 #[derive(Debug, Deserialize)]
 struct VersionInfo {
     #[serde(default)]
     requires_dist: Option<Vec<String>>,
+    #[serde(default)]
+    author_email: Option<String>,
+    #[serde(default)]
+    maintainer_email: Option<String>,
+}
+
+impl VersionInfo {
+    fn maintainers(&self) -> Vec<String> {
+        maintainer_emails([&self.author_email, &self.maintainer_email])
+    }
+}
+
+/// Project JSON fields read by [`PyPiClient::project_history`].
+#[derive(Debug, Deserialize)]
+struct HistoryResponse {
+    info: HistoryInfo,
+    #[serde(default)]
+    releases: HashMap<String, Vec<UploadedFile>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryInfo {
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    author_email: Option<String>,
+    #[serde(default)]
+    maintainer_email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadedFile {
+    #[serde(default)]
+    upload_time_iso_8601: Option<String>,
+    #[serde(default)]
+    upload_time: Option<String>,
+}
+
+impl UploadedFile {
+    fn uploaded(&self) -> Option<&str> {
+        self.upload_time_iso_8601
+            .as_deref()
+            .or(self.upload_time.as_deref())
+    }
+}
+
+/// Release history of a project, as judged by [`crate::suspicious`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectHistory {
+    /// Earliest upload of any release file (ISO 8601).
+    pub first_upload: Option<String>,
+    pub latest_version: Option<String>,
+    /// Author and maintainer emails of the latest release, lowercased.
+    pub maintainers: Vec<String>,
+    /// The release uploaded before the latest one.
+    pub previous_version: Option<String>,
+    pub previous_maintainers: Vec<String>,
+}
+
+/// Lowercased addresses from `author_email`/`maintainer_email` fields, which
+/// may list several (`"A <a@x.org>, b@y.org"`).
+fn maintainer_emails<'a>(fields: impl IntoIterator<Item = &'a Option<String>>) -> Vec<String> {
+    let mut emails: Vec<String> = fields
+        .into_iter()
+        .flatten()
+        .flat_map(|field| field.split(','))
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            let address = match (entry.find('<'), entry.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &entry[start + 1..end],
+                _ => entry,
+            };
+            address
+                .contains('@')
+                .then(|| address.trim().to_ascii_lowercase())
+        })
+        .collect();
+    emails.sort();
+    emails.dedup();
+    emails
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -556,26 +891,30 @@ struct CachedPackage {
     sdist: Option<String>,
     #[serde(default)]
     requires_python: Option<String>,
+    #[serde(default)]
+    sdist_url: Option<String>,
+    #[serde(default)]
+    sdist_hash: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct HttpCachePolicy {
+pub(crate) struct HttpCachePolicy {
     #[serde(default)]
-    etag: Option<String>,
+    pub(crate) etag: Option<String>,
     #[serde(default)]
-    last_modified: Option<String>,
+    pub(crate) last_modified: Option<String>,
     #[serde(default)]
     max_age: Option<u64>,
     #[serde(default)]
     no_cache: bool,
     #[serde(default)]
-    no_store: bool,
+    pub(crate) no_store: bool,
     #[serde(default)]
     fetched_at: u64,
 }
 
 impl HttpCachePolicy {
-    fn from_headers(headers: &header::HeaderMap, fetched_at: u64) -> Self {
+    pub(crate) fn from_headers(headers: &header::HeaderMap, fetched_at: u64) -> Self {
         let cache_control = headers
             .get(header::CACHE_CONTROL)
             .and_then(|h| h.to_str().ok())
@@ -597,7 +936,7 @@ impl HttpCachePolicy {
         }
     }
 
-    fn is_fresh(&self, now: u64) -> bool {
+    pub(crate) fn is_fresh(&self, now: u64) -> bool {
         if self.no_cache {
             return false;
         }
@@ -632,6 +971,22 @@ struct CacheControlDirectives {
     no_store: bool,
 }
 
+/// PEP 503 normalized project name, as used in
+/// [`PyPiIndex::dynamic_metadata_packages`].
+pub fn normalize_project_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for ch in name.chars() {
+        if matches!(ch, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(ch.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
 fn normalize_base(input: &str) -> Result<Url, PyPiError> {
     let trimmed = input.trim_end_matches('/');
     let normalized = if trimmed.ends_with("/simple") {
@@ -642,7 +997,7 @@ fn normalize_base(input: &str) -> Result<Url, PyPiError> {
     Url::parse(normalized).map_err(|_| PyPiError::InvalidBaseUrl(input.to_string()))
 }
 
-fn parse_requires_dist(raw: String) -> Option<Requirement> {
+pub(crate) fn parse_requires_dist(raw: String) -> Option<Requirement> {
     let py_version = std::env::var("PYBUN_PYPI_PYTHON_VERSION").unwrap_or_else(|_| "3.11".into());
 
     // Split marker and requirement
@@ -727,7 +1082,7 @@ fn version_tuple(s: &str) -> (u64, u64, u64) {
     (parts[0], parts[1], parts[2])
 }
 
-fn wheel_platforms(filename: &str) -> Vec<String> {
+pub(crate) fn wheel_platforms(filename: &str) -> Vec<String> {
     if !filename.ends_with(".whl") {
         return Vec::new();
     }
@@ -761,7 +1116,7 @@ fn parse_cache_control(raw: &str) -> CacheControlDirectives {
     directives
 }
 
-fn now_epoch_seconds() -> u64 {
+pub(crate) fn now_epoch_seconds() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
@@ -841,13 +1196,87 @@ fn load_cache_from_paths(
 /// 1. `PYBUN_PYPI_CACHE_DIR` environment variable
 /// 2. `dirs::cache_dir()/pybun/pypi` (e.g. `~/Library/Caches/pybun/pypi` on
 ///    macOS, `~/.cache/pybun/pypi` on Linux)
+///
+/// In read-only cache mode this is the writable `pypi` directory of the
+/// overlay (`None` when the overlay is unusable); see [`pypi_cache_layers`].
 pub fn pypi_cache_dir() -> Option<PathBuf> {
+    let dir = shared_pypi_cache_dir()?;
+    Some(
+        readonly_layers(dir.clone(), "pypi")
+            .ok()?
+            .map_or(dir, |layers| layers.overlay),
+    )
+}
+
+/// The PyPI metadata cache in read-only cache mode (`PYBUN_READONLY_CACHE`):
+/// the directory above is the shared layer, `<overlay>/pypi` takes writes.
+pub fn pypi_cache_layers() -> Option<crate::cache::CacheLayers> {
+    readonly_layers(shared_pypi_cache_dir()?, "pypi")
+        .ok()
+        .flatten()
+}
+
+fn shared_pypi_cache_dir() -> Option<PathBuf> {
     if let Ok(dir) = std::env::var("PYBUN_PYPI_CACHE_DIR") {
         return Some(PathBuf::from(dir));
     }
     dirs::cache_dir().map(|p| p.join("pybun").join("pypi"))
 }
 
+/// Directory `pybun install` downloads wheels into: `artifacts` next to the
+/// PyPI metadata cache (see [`pypi_cache_dir`]). In read-only cache mode,
+/// `<overlay>/artifacts` (`None` when the overlay is unusable); see
+/// [`artifacts_cache_layers`].
+pub fn artifacts_cache_dir() -> Option<PathBuf> {
+    let dir = shared_artifacts_cache_dir()?;
+    Some(
+        readonly_layers(dir.clone(), "artifacts")
+            .ok()?
+            .map_or(dir, |layers| layers.overlay),
+    )
+}
+
+/// The downloaded-wheel directory in read-only cache mode.
+pub fn artifacts_cache_layers() -> Option<crate::cache::CacheLayers> {
+    readonly_layers(shared_artifacts_cache_dir()?, "artifacts")
+        .ok()
+        .flatten()
+}
+
+fn shared_artifacts_cache_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("PYBUN_PYPI_CACHE_DIR") {
+        return Some(PathBuf::from(dir).join("artifacts"));
+    }
+    dirs::cache_dir().map(|p| p.join("pybun").join("artifacts"))
+}
+
+/// `shared` as the read-only layer under `<overlay>/<name>`, when the cache
+/// is read-only.
+fn readonly_layers(
+    shared: PathBuf,
+    name: &str,
+) -> Result<Option<crate::cache::CacheLayers>, crate::cache::CacheError> {
+    Ok(
+        crate::cache::CacheLayers::from_env(&shared)?.map(|layers| crate::cache::CacheLayers {
+            overlay: layers.overlay.join(name),
+            shared,
+        }),
+    )
+}
+
+/// `path` (inside `cache_dir`), or the same entry in the `shared` layer when
+/// only that one has it.
+pub(crate) fn layered_path(cache_dir: &Path, shared: Option<&Path>, path: PathBuf) -> PathBuf {
+    if path.exists() {
+        return path;
+    }
+    shared
+        .zip(path.strip_prefix(cache_dir).ok())
+        .map(|(shared, relative)| shared.join(relative))
+        .filter(|candidate| candidate.exists())
+        .unwrap_or(path)
+}
+
 /// Returns `true` if `path` is a `.bin` PyPI cache entry that fails to
 /// deserialize as the current [`CacheEntry`] layout (e.g. left over from a
 /// pre-v0.1.19 pybun install, see issue #202), or a legacy `.json` cache
@@ -955,6 +1384,8 @@ fn build_cached_packages(
         }
         let mut wheels = Vec::new();
         let mut sdist = None;
+        let mut sdist_url = None;
+        let mut sdist_hash = None;
         // PyPI publishes the same requires_python for every file of a
         // release; take it from the first file that carries it (Issue #342).
         let requires_python = files
@@ -990,6 +1421,12 @@ fn build_cached_packages(
                 }
                 "sdist" => {
                     sdist = Some(file.filename.clone());
+                    sdist_url = Some(file.url.clone());
+                    sdist_hash = file
+                        .digests
+                        .as_ref()
+                        .and_then(|d| d.get("sha256"))
+                        .map(|h| format!("sha256:{}", h));
                 }
                 _ => {}
             }
@@ -1003,6 +1440,8 @@ fn build_cached_packages(
             wheels,
             sdist,
             requires_python,
+            sdist_url,
+            sdist_hash,
         });
     }
     packages
@@ -1085,17 +1524,39 @@ mod tests {
         assert!(matches!(err, PyPiError::CacheDirUnavailable));
     }
 
+    #[test]
+    fn layered_path_falls_back_to_the_shared_layer() {
+        let temp = tempdir().unwrap();
+        let overlay = temp.path().join("overlay");
+        let shared = temp.path().join("shared");
+        fs::create_dir_all(&overlay).unwrap();
+        fs::create_dir_all(&shared).unwrap();
+        fs::write(shared.join("demo.bin"), b"shared").unwrap();
+
+        let path = layered_path(&overlay, Some(&shared), overlay.join("demo.bin"));
+        assert_eq!(path, shared.join("demo.bin"));
+        fs::write(overlay.join("demo.bin"), b"overlay").unwrap();
+        let path = layered_path(&overlay, Some(&shared), overlay.join("demo.bin"));
+        assert_eq!(path, overlay.join("demo.bin"));
+        // Misses stay in the writable layer.
+        let path = layered_path(&overlay, Some(&shared), overlay.join("other.bin"));
+        assert_eq!(path, overlay.join("other.bin"));
+    }
+
     #[tokio::test]
     async fn binary_cache_roundtrip() {
         let temp = tempdir().unwrap();
         let client = PyPiClient {
             base: Url::parse("https://pypi.org").unwrap(),
             cache_dir: temp.path().join("cache"),
+            shared_cache_dir: None,
             http: reqwest::Client::new(),
             offline: false,
             package_once: Arc::new(OnceMap::new()),
             deps_once: Arc::new(OnceMap::new()),
             stale_cache_notices: Arc::new(Mutex::new(Vec::new())),
+            sdist_python: None,
+            dynamic_metadata: Arc::new(Mutex::new(BTreeSet::new())),
         };
         let entry = CacheEntry {
             policy: HttpCachePolicy {
@@ -1129,11 +1590,14 @@ mod tests {
         let client = PyPiClient {
             base: Url::parse("https://pypi.org").unwrap(),
             cache_dir,
+            shared_cache_dir: None,
             http: reqwest::Client::new(),
             offline: false,
             package_once: Arc::new(OnceMap::new()),
             deps_once: Arc::new(OnceMap::new()),
             stale_cache_notices: Arc::new(Mutex::new(Vec::new())),
+            sdist_python: None,
+            dynamic_metadata: Arc::new(Mutex::new(BTreeSet::new())),
         };
 
         // Must not error - the stale entry is discarded and treated as a
@@ -1166,11 +1630,14 @@ mod tests {
         let client = PyPiClient {
             base: Url::parse("https://pypi.org").unwrap(),
             cache_dir,
+            shared_cache_dir: None,
             http: reqwest::Client::new(),
             offline: false,
             package_once: Arc::new(OnceMap::new()),
             deps_once: Arc::new(OnceMap::new()),
             stale_cache_notices: Arc::new(Mutex::new(Vec::new())),
+            sdist_python: None,
+            dynamic_metadata: Arc::new(Mutex::new(BTreeSet::new())),
         };
 
         // Must not error - the unreadable legacy entry is discarded and
@@ -1229,11 +1696,14 @@ mod tests {
         let client = PyPiClient {
             base: Url::parse("https://pypi.org").unwrap(),
             cache_dir: temp.path().join("cache"),
+            shared_cache_dir: None,
             http: reqwest::Client::new(),
             offline: false,
             package_once: Arc::new(OnceMap::new()),
             deps_once: Arc::new(OnceMap::new()),
             stale_cache_notices: Arc::new(Mutex::new(Vec::new())),
+            sdist_python: None,
+            dynamic_metadata: Arc::new(Mutex::new(BTreeSet::new())),
         };
 
         let notices = Arc::clone(&client.stale_cache_notices);
@@ -0,0 +1,1113 @@
+//! Package index client for the PEP 503 / PEP 691 "simple" repository API.
+//!
+//! [`crate::pypi::PyPiIndex`] reads PyPI's legacy `/pypi/<name>/json` API,
+//! which mirrors and private registries (devpi, Artifactory, Nexus, static
+//! file servers) often do not serve. [`SimpleIndex`] reads
+//! `<index>/<project>/` pages instead, asking for the PEP 691 JSON form and
+//! falling back to PEP 503 HTML. Dependencies come from PEP 658 `.metadata`
+//! files, or from the wheel's own `METADATA` when the index publishes none.
+//!
+//! Project pages are cached on disk together with their `ETag` /
+//! `Cache-Control` validators, extracted `Requires-Dist` lists are cached by
+//! file hash, and transient failures (transport errors, 408/429/5xx) are
+//! retried with backoff.
+//!
+//! [`RemoteIndex::from_env`] picks the backend: `PYBUN_INDEX_URL` selects a
+//! simple index (e.g. `https://pypi.org/simple`); otherwise the JSON API at
+//! `PYBUN_PYPI_BASE_URL` is used.
+
+use crate::lockfile::PackageSource;
+use crate::network_policy::{self, Operation};
+use crate::once_map::OnceMap;
+use crate::pypi::{
+    HttpCachePolicy, PyPiClient, PyPiError, PyPiIndex, normalize_project_name, now_epoch_seconds,
+    parse_requires_dist, wheel_platforms,
+};
+use crate::resolver::{
+    PackageArtifacts, PackageIndex, ResolveError, ResolvedPackage, Wheel, parse_wheel_tags,
+};
+use dashmap::DashMap;
+use reqwest::{StatusCode, Url, header};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Environment variable selecting a PEP 503 simple index for resolution.
+pub const INDEX_URL_ENV: &str = "PYBUN_INDEX_URL";
+
+/// Total attempts per request, including the first one.
+const MAX_ATTEMPTS: u32 = 3;
+/// Upper bound on a server-provided `Retry-After`.
+const MAX_RETRY_AFTER_SECS: u64 = 30;
+
+const SIMPLE_ACCEPT: &str = "application/vnd.pypi.simple.v1+json, \
+     application/vnd.pypi.simple.v1+html;q=0.2, text/html;q=0.01";
+
+/// One distribution file listed on a project page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleFile {
+    pub filename: String,
+    /// Absolute download URL without the hash fragment.
+    pub url: String,
+    /// Hash name → hex digest, from PEP 691 `hashes` or the PEP 503 URL fragment.
+    #[serde(default)]
+    pub hashes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub requires_python: Option<String>,
+    #[serde(default)]
+    pub yanked: bool,
+    /// Whether the index serves PEP 658 core metadata at `<url>.metadata`.
+    #[serde(default)]
+    pub core_metadata: bool,
+}
+
+impl SimpleFile {
+    fn sha256(&self) -> Option<&str> {
+        self.hashes.get("sha256").map(String::as_str)
+    }
+
+    fn is_wheel(&self) -> bool {
+        self.filename.ends_with(".whl")
+    }
+
+    /// Version encoded in the filename of a wheel or sdist of `project`.
+    fn version(&self, project: &str) -> Option<String> {
+        if let Some(stem) = self.filename.strip_suffix(".whl") {
+            return stem.split('-').nth(1).map(str::to_string);
+        }
+        let stem = [".tar.gz", ".tar.bz2", ".tgz", ".zip"]
+            .iter()
+            .find_map(|ext| self.filename.strip_suffix(ext))?;
+        // Older sdists keep the project's display name, which may itself
+        // contain dashes, so split where the prefix matches the project.
+        let project = normalize_project_name(project);
+        stem.match_indices('-')
+            .find(|(idx, _)| normalize_project_name(&stem[..*idx]) == project)
+            .map(|(idx, _)| stem[idx + 1..].to_string())
+            .or_else(|| stem.rsplit_once('-').map(|(_, v)| v.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProjectCacheEntry {
+    policy: HttpCachePolicy,
+    files: Vec<SimpleFile>,
+}
+
+/// HTTP client for a single simple index.
+#[derive(Clone)]
+pub struct SimpleIndexClient {
+    base: Url,
+    cache_dir: PathBuf,
+    /// Read-only lower layer of the metadata cache (read-only cache mode).
+    shared_cache_dir: Option<PathBuf>,
+    http: reqwest::Client,
+    offline: bool,
+    retry_backoff: Duration,
+    notices: Arc<Mutex<Vec<String>>>,
+}
+
+impl SimpleIndexClient {
+    /// Build a client for the simple index at `index_url` (the URL that
+    /// `<project>/` is appended to, e.g. `https://pypi.org/simple`).
+    pub fn with_config(
+        index_url: &str,
+        cache_dir: PathBuf,
+        offline: bool,
+    ) -> Result<Self, PyPiError> {
+        let base = Url::parse(&format!("{}/", index_url.trim_end_matches('/')))
+            .map_err(|_| PyPiError::InvalidBaseUrl(index_url.to_string()))?;
+        Ok(Self {
+            base,
+            cache_dir,
+            shared_cache_dir: None,
+            http: crate::http_config::client_builder()
+                .user_agent("pybun/0.1")
+                .redirect(network_policy::redirect_policy(Operation::Index))
+                .build()?,
+            offline,
+            retry_backoff: Duration::from_secs(1),
+            notices: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// Client for `PYBUN_INDEX_URL`, caching under the PyPI metadata cache
+    /// directory. `None` when the variable is unset or empty.
+    pub fn from_env(offline: bool) -> Result<Option<Self>, PyPiError> {
+        let Some(index_url) = std::env::var(INDEX_URL_ENV)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let cache_dir = crate::pypi::pypi_cache_dir().ok_or(PyPiError::CacheDirUnavailable)?;
+        let mut client = Self::with_config(&index_url, cache_dir, offline)?;
+        client.shared_cache_dir = crate::pypi::pypi_cache_layers().map(|layers| layers.shared);
+        Ok(Some(client))
+    }
+
+    pub fn index_url(&self) -> String {
+        self.base.as_str().trim_end_matches('/').to_string()
+    }
+
+    /// Drain notices about discarded cache entries and releases whose
+    /// dependencies could not be read.
+    pub fn take_notices(&self) -> Vec<String> {
+        let mut notices = self
+            .notices
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::mem::take(&mut *notices)
+    }
+
+    fn notice(&self, message: String) {
+        self.notices
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(message);
+    }
+
+    fn project_cache_path(&self, project: &str) -> PathBuf {
+        let index_key = hex::encode(Sha256::digest(self.base.as_str().as_bytes()));
+        self.cache_dir
+            .join("simple")
+            .join(&index_key[..16])
+            .join(format!("{project}.json"))
+    }
+
+    /// Where to read the cache entry at `path` from: the shared layer when
+    /// only that one has it.
+    fn readable(&self, path: PathBuf) -> PathBuf {
+        crate::pypi::layered_path(&self.cache_dir, self.shared_cache_dir.as_deref(), path)
+    }
+
+    fn metadata_cache_path(&self, file: &SimpleFile) -> PathBuf {
+        let key = match file.sha256() {
+            Some(sha256) => sha256.to_ascii_lowercase(),
+            None => hex::encode(Sha256::digest(file.url.as_bytes())),
+        };
+        self.cache_dir
+            .join("simple-metadata")
+            .join(format!("{key}.json"))
+    }
+
+    /// GET `url`, retrying transport errors and 408/429/5xx responses.
+    async fn get(
+        &self,
+        url: &Url,
+        headers: header::HeaderMap,
+    ) -> Result<reqwest::Response, PyPiError> {
+        network_policy::check_url(Operation::Index, url.as_str())?;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let request = crate::auth::authorize(
+                self.http.get(url.clone()).headers(headers.clone()),
+                url.as_str(),
+            );
+            let (error, retry_after) = match request.send().await {
+                Ok(resp) if !is_retryable_status(resp.status()) => return Ok(resp),
+                Ok(resp) => {
+                    let retry_after = resp
+                        .headers()
+                        .get(header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok());
+                    (format!("{} returned {}", url, resp.status()), retry_after)
+                }
+                Err(e) => (e.to_string(), None),
+            };
+            if attempt >= MAX_ATTEMPTS {
+                return Err(PyPiError::Http(format!(
+                    "{error} (gave up after {attempt} attempts)"
+                )));
+            }
+            let backoff = match retry_after {
+                Some(secs) => Duration::from_secs(secs.min(MAX_RETRY_AFTER_SECS)),
+                None => self.retry_backoff * (1 << (attempt - 1)),
+            };
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    /// Files listed on the project page for `name`. An unknown project (404)
+    /// has no files.
+    pub async fn project_files(&self, name: &str) -> Result<Vec<SimpleFile>, PyPiError> {
+        let project = normalize_project_name(name);
+        let path = self.project_cache_path(&project);
+        let cached = {
+            let path = self.readable(path.clone());
+            tokio::task::spawn_blocking(move || load_project_cache(&path))
+                .await
+                .map_err(|e| PyPiError::Parse(format!("cache join error: {}", e)))?
+        };
+        let cached = match cached {
+            Ok(entry) => entry,
+            Err(notice) => {
+                self.notice(notice);
+                None
+            }
+        };
+
+        if self.offline {
+            return cached
+                .map(|entry| entry.files)
+                .ok_or_else(|| PyPiError::offline_miss(project));
+        }
+        if let Some(entry) = &cached
+            && entry.policy.is_fresh(now_epoch_seconds())
+        {
+            return Ok(entry.files.clone());
+        }
+
+        let url = self
+            .base
+            .join(&format!("{project}/"))
+            .map_err(|e| PyPiError::Parse(e.to_string()))?;
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static(SIMPLE_ACCEPT),
+        );
+        if let Some(entry) = &cached {
+            if let Some(etag) = entry
+                .policy
+                .etag
+                .as_deref()
+                .and_then(|v| header::HeaderValue::from_str(v).ok())
+            {
+                headers.insert(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = entry
+                .policy
+                .last_modified
+                .as_deref()
+                .and_then(|v| header::HeaderValue::from_str(v).ok())
+            {
+                headers.insert(header::IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let resp = self.get(&url, headers).await?;
+        match resp.status() {
+            StatusCode::NOT_MODIFIED => {
+                return cached
+                    .map(|entry| entry.files)
+                    .ok_or_else(|| PyPiError::offline_miss(project));
+            }
+            StatusCode::NOT_FOUND => return Ok(Vec::new()),
+            status if !status.is_success() => {
+                return Err(PyPiError::Http(format!("{} returned {}", url, status)));
+            }
+            _ => {}
+        }
+
+        let page_url = resp.url().clone();
+        let headers = resp.headers().clone();
+        let is_json = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("json"));
+        let body = resp.text().await?;
+        let files = if is_json {
+            parse_json_project(&body, &page_url)?
+        } else {
+            parse_html_project(&body, &page_url)
+        };
+
+        let policy = HttpCachePolicy::from_headers(&headers, now_epoch_seconds());
+        if !policy.no_store {
+            let entry = ProjectCacheEntry {
+                policy,
+                files: files.clone(),
+            };
+            tokio::task::spawn_blocking(move || save_json(&path, &entry))
+                .await
+                .map_err(|e| PyPiError::Parse(format!("cache join error: {}", e)))??;
+        }
+        Ok(files)
+    }
+
+    /// Raw `Requires-Dist` entries of `file`, from its PEP 658 metadata file
+    /// or, failing that, from the wheel itself. `None` for an sdist without
+    /// published metadata.
+    pub async fn requires_dist(&self, file: &SimpleFile) -> Result<Option<Vec<String>>, PyPiError> {
+        let path = self.metadata_cache_path(file);
+        if let Some(cached) = fs::read(self.readable(path.clone()))
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Vec<String>>(&data).ok())
+        {
+            return Ok(Some(cached));
+        }
+        if self.offline {
+            return Err(PyPiError::offline_miss(file.filename.clone()));
+        }
+
+        let requires = if file.core_metadata {
+            let url = Url::parse(&format!("{}.metadata", file.url))
+                .map_err(|e| PyPiError::Parse(e.to_string()))?;
+            let resp = self.get(&url, header::HeaderMap::new()).await?;
+            if !resp.status().is_success() {
+                return Err(PyPiError::Http(format!(
+                    "{} returned {}",
+                    url,
+                    resp.status()
+                )));
+            }
+            parse_metadata_requires_dist(&resp.text().await?)
+        } else if file.is_wheel() {
+            let url = Url::parse(&file.url).map_err(|e| PyPiError::Parse(e.to_string()))?;
+            let resp = self.get(&url, header::HeaderMap::new()).await?;
+            if !resp.status().is_success() {
+                return Err(PyPiError::Http(format!(
+                    "{} returned {}",
+                    url,
+                    resp.status()
+                )));
+            }
+            let bytes = resp.bytes().await?;
+            if let Some(expected) = file.sha256() {
+                let actual = hex::encode(Sha256::digest(&bytes));
+                if !actual.eq_ignore_ascii_case(expected) {
+                    return Err(PyPiError::Parse(format!(
+                        "{}: sha256 mismatch (expected {}, got {})",
+                        file.filename, expected, actual
+                    )));
+                }
+            }
+            let filename = file.filename.clone();
+            tokio::task::spawn_blocking(move || wheel_metadata(&bytes, &filename))
+                .await
+                .map_err(|e| PyPiError::Parse(format!("metadata join error: {}", e)))??
+        } else {
+            return Ok(None);
+        };
+
+        save_json(&path, &requires)?;
+        Ok(Some(requires))
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 429 | 500..=599)
+}
+
+/// Cached project page at `path`; `Err` carries a notice for an unreadable
+/// entry, which is then treated as a cache miss.
+fn load_project_cache(path: &Path) -> Result<Option<ProjectCacheEntry>, String> {
+    let Ok(data) = fs::read(path) else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&data).map(Some).map_err(|e| {
+        format!(
+            "discarded unreadable index cache entry at {} ({}); re-fetching from index",
+            path.display(),
+            e
+        )
+    })
+}
+
+fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), PyPiError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let data = serde_json::to_vec(value).map_err(|e| PyPiError::Parse(e.to_string()))?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonProject {
+    files: Vec<JsonFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFile {
+    filename: String,
+    url: String,
+    #[serde(default)]
+    hashes: BTreeMap<String, String>,
+    #[serde(default, rename = "requires-python")]
+    requires_python: Option<String>,
+    #[serde(default)]
+    yanked: Option<serde_json::Value>,
+    #[serde(default, rename = "core-metadata")]
+    core_metadata: Option<serde_json::Value>,
+    /// Pre-PEP 714 spelling of `core-metadata`.
+    #[serde(default, rename = "dist-info-metadata")]
+    dist_info_metadata: Option<serde_json::Value>,
+}
+
+/// `yanked` / `core-metadata` are either a boolean or a string/hash object
+/// that counts as `true`.
+fn json_flag(value: &Option<serde_json::Value>) -> bool {
+    !matches!(
+        value,
+        None | Some(serde_json::Value::Null) | Some(serde_json::Value::Bool(false))
+    )
+}
+
+/// Parse a PEP 691 `application/vnd.pypi.simple.v1+json` project page.
+fn parse_json_project(body: &str, page: &Url) -> Result<Vec<SimpleFile>, PyPiError> {
+    let project: JsonProject = serde_json::from_str(body)
+        .map_err(|e| PyPiError::Parse(format!("json decode error: {}", e)))?;
+    Ok(project
+        .files
+        .into_iter()
+        .filter_map(|file| {
+            let url = page.join(&file.url).ok()?;
+            Some(SimpleFile {
+                filename: file.filename,
+                url: url.to_string(),
+                hashes: file.hashes,
+                requires_python: file.requires_python,
+                yanked: json_flag(&file.yanked),
+                core_metadata: json_flag(&file.core_metadata)
+                    || json_flag(&file.dist_info_metadata),
+            })
+        })
+        .collect())
+}
+
+/// Parse a PEP 503 HTML project page: one `<a>` per file, with the hash in
+/// the URL fragment and PEP 592/658/503 `data-*` attributes.
+fn parse_html_project(html: &str, page: &Url) -> Vec<SimpleFile> {
+    let lower = html.to_ascii_lowercase();
+    let mut files = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = lower[pos..].find("<a") {
+        let attrs_start = pos + found + 2;
+        pos = attrs_start;
+        if !lower[attrs_start..].starts_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+        let Some(tag_end) = find_tag_end(html, attrs_start) else {
+            break;
+        };
+        let attrs = parse_attributes(&html[attrs_start..tag_end]);
+        let text_end = lower[tag_end + 1..]
+            .find("</a")
+            .map(|idx| tag_end + 1 + idx)
+            .unwrap_or(tag_end + 1);
+        let text = unescape_html(html[tag_end + 1..text_end].trim());
+        pos = text_end;
+
+        let Some(href) = attrs.get("href") else {
+            continue;
+        };
+        let Ok(mut url) = page.join(href) else {
+            continue;
+        };
+        let mut hashes = BTreeMap::new();
+        if let Some((algorithm, digest)) = url.fragment().and_then(|f| f.split_once('=')) {
+            hashes.insert(algorithm.to_ascii_lowercase(), digest.to_string());
+        }
+        url.set_fragment(None);
+        let filename = if text.is_empty() {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .unwrap_or_default()
+                .to_string()
+        } else {
+            text
+        };
+        let metadata_flag = |key: &str| {
+            attrs
+                .get(key)
+                .is_some_and(|value| !value.eq_ignore_ascii_case("false"))
+        };
+        files.push(SimpleFile {
+            filename,
+            url: url.to_string(),
+            hashes,
+            requires_python: attrs
+                .get("data-requires-python")
+                .filter(|v| !v.is_empty())
+                .cloned(),
+            yanked: attrs.contains_key("data-yanked"),
+            core_metadata: metadata_flag("data-core-metadata")
+                || metadata_flag("data-dist-info-metadata"),
+        });
+    }
+    files
+}
+
+/// Index of the `>` closing a tag whose attributes start at `from`, skipping
+/// quoted attribute values.
+fn find_tag_end(html: &str, from: usize) -> Option<usize> {
+    let mut quote = None;
+    for (idx, byte) in html.bytes().enumerate().skip(from) {
+        match (quote, byte) {
+            (Some(q), b) if b == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(byte),
+            (None, b'>') => return Some(idx),
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(raw: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = raw.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (raw_value, remaining) = match after_eq.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let inner = &after_eq[1..];
+                    let end = inner.find(q).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_eq
+                        .find(|c: char| c.is_ascii_whitespace())
+                        .unwrap_or(after_eq.len());
+                    (&after_eq[..end], &after_eq[end..])
+                }
+            };
+            value = unescape_html(raw_value);
+            rest = remaining;
+        } else if name.is_empty() {
+            // Stray `/` of a self-closing tag.
+            rest = &rest[1.min(rest.len())..];
+        }
+        if !name.is_empty() {
+            attrs.insert(name, value);
+        }
+        rest = rest.trim_start();
+    }
+    attrs
+}
+
+fn unescape_html(raw: &str) -> String {
+    raw.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+/// `Requires-Dist` values from the header block of a core metadata file.
+fn parse_metadata_requires_dist(metadata: &str) -> Vec<String> {
+    let mut requires: Vec<String> = Vec::new();
+    let mut in_requires = false;
+    for line in metadata.lines() {
+        if line.trim().is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if in_requires && let Some(last) = requires.last_mut() {
+                last.push(' ');
+                last.push_str(line.trim());
+            }
+            continue;
+        }
+        in_requires = false;
+        if let Some((key, value)) = line.split_once(':')
+            && key.trim().eq_ignore_ascii_case("requires-dist")
+        {
+            requires.push(value.trim().to_string());
+            in_requires = true;
+        }
+    }
+    requires
+}
+
+/// `Requires-Dist` of a wheel, read from its top-level `.dist-info/METADATA`.
+fn wheel_metadata(bytes: &[u8], filename: &str) -> Result<Vec<String>, PyPiError> {
+    let fail = |message: String| PyPiError::Parse(format!("{filename}: {message}"));
+    let metadata = crate::dist_info::wheel_metadata(std::io::Cursor::new(bytes))
+        .map_err(|e| fail(e.to_string()))?
+        .ok_or_else(|| fail("no .dist-info/METADATA in wheel".into()))?;
+    Ok(parse_metadata_requires_dist(&metadata))
+}
+
+/// [`PackageIndex`] over a [`SimpleIndexClient`], memoizing project pages
+/// and dependency lists for the duration of a resolution.
+#[derive(Clone)]
+pub struct SimpleIndex {
+    client: SimpleIndexClient,
+    projects: Arc<DashMap<String, Arc<Vec<SimpleFile>>>>,
+    project_once: Arc<OnceMap<String, Arc<Vec<SimpleFile>>>>,
+    deps_once: Arc<OnceMap<String, Vec<String>>>,
+}
+
+impl SimpleIndex {
+    pub fn new(client: SimpleIndexClient) -> Self {
+        Self {
+            client,
+            projects: Arc::new(DashMap::new()),
+            project_once: Arc::new(OnceMap::new()),
+            deps_once: Arc::new(OnceMap::new()),
+        }
+    }
+
+    pub fn index_url(&self) -> String {
+        self.client.index_url()
+    }
+
+    pub fn take_notices(&self) -> Vec<String> {
+        self.client.take_notices()
+    }
+
+    async fn files(&self, name: &str) -> Result<Arc<Vec<SimpleFile>>, PyPiError> {
+        let project = normalize_project_name(name);
+        if let Some(files) = self.projects.get(&project).map(|entry| entry.clone()) {
+            return Ok(files);
+        }
+        self.project_once
+            .get_or_try_init(project.clone(), || async {
+                let files = Arc::new(self.client.project_files(&project).await?);
+                self.projects.insert(project.clone(), files.clone());
+                Ok::<_, PyPiError>(files)
+            })
+            .await
+    }
+
+    /// Non-yanked files of `name`, grouped by the version in their filename.
+    fn releases<'a>(name: &str, files: &'a [SimpleFile]) -> BTreeMap<String, Vec<&'a SimpleFile>> {
+        let mut releases: BTreeMap<String, Vec<&SimpleFile>> = BTreeMap::new();
+        for file in files.iter().filter(|f| !f.yanked) {
+            if let Some(version) = file.version(name) {
+                releases.entry(version).or_default().push(file);
+            }
+        }
+        releases
+    }
+
+    async fn dependencies(
+        &self,
+        name: &str,
+        version: &str,
+        files: &[&SimpleFile],
+    ) -> Result<Vec<String>, PyPiError> {
+        // Prefer published metadata, then a wheel we can open ourselves.
+        let source = files
+            .iter()
+            .find(|f| f.core_metadata && f.is_wheel())
+            .or_else(|| files.iter().find(|f| f.core_metadata))
+            .or_else(|| files.iter().find(|f| f.is_wheel()))
+            .copied()
+            .cloned();
+        let key = format!("{}=={}", normalize_project_name(name), version);
+        self.deps_once
+            .get_or_try_init(key, || async {
+                let requires = match &source {
+                    Some(file) => self.client.requires_dist(file).await?,
+                    None => None,
+                };
+                Ok::<_, PyPiError>(requires.unwrap_or_else(|| {
+                    self.client.notice(format!(
+                        "{name}=={version} has no wheel or published metadata on {}; \
+                         its dependencies were not resolved",
+                        self.client.index_url()
+                    ));
+                    Vec::new()
+                }))
+            })
+            .await
+    }
+
+    fn build_resolved(
+        &self,
+        name: &str,
+        version: &str,
+        files: &[&SimpleFile],
+        dependencies: &[String],
+    ) -> ResolvedPackage {
+        let wheels = files
+            .iter()
+            .filter(|f| f.is_wheel())
+            .map(|f| {
+                let (python_tag, abi_tag) = parse_wheel_tags(&f.filename);
+                let platforms = wheel_platforms(&f.filename);
+                Wheel {
+                    file: f.filename.clone(),
+                    url: Some(f.url.clone()),
+                    hash: f.sha256().map(|h| format!("sha256:{h}")),
+                    platforms: if platforms.is_empty() {
+                        vec!["any".into()]
+                    } else {
+                        platforms
+                    },
+                    python_tag,
+                    abi_tag,
+                }
+            })
+            .collect();
+        let sdist = files.iter().find(|f| !f.is_wheel());
+        ResolvedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            dependencies: dependencies
+                .iter()
+                .filter_map(|raw| parse_requires_dist(raw.clone()))
+                .collect(),
+            source: Some(PackageSource::Registry {
+                index: "simple".into(),
+                url: self.client.index_url(),
+            }),
+            artifacts: PackageArtifacts {
+                wheels,
+                sdist: sdist.map(|f| f.filename.clone()),
+                sdist_url: sdist.map(|f| f.url.clone()),
+                sdist_hash: sdist
+                    .and_then(|f| f.sha256())
+                    .map(|h| format!("sha256:{h}")),
+            },
+            requires_python: files.iter().find_map(|f| f.requires_python.clone()),
+        }
+    }
+}
+
+impl PackageIndex for SimpleIndex {
+    fn get(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> impl std::future::Future<Output = Result<Option<ResolvedPackage>, ResolveError>> + Send
+    {
+        let name = name.to_string();
+        let version = version.to_string();
+        let this = self.clone();
+        async move {
+            let files = this
+                .files(&name)
+                .await
+                .map_err(|e| ResolveError::Io(e.to_string()))?;
+            let releases = Self::releases(&name, &files);
+            let Some(release) = releases.get(&version) else {
+                return Ok(None);
+            };
+            let dependencies = this
+                .dependencies(&name, &version, release)
+                .await
+                .map_err(|e| ResolveError::Io(e.to_string()))?;
+            Ok(Some(this.build_resolved(
+                &name,
+                &version,
+                release,
+                &dependencies,
+            )))
+        }
+    }
+
+    fn all(
+        &self,
+        name: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<ResolvedPackage>, ResolveError>> + Send {
+        let name = name.to_string();
+        let this = self.clone();
+        async move {
+            let files = this
+                .files(&name)
+                .await
+                .map_err(|e| ResolveError::Io(e.to_string()))?;
+            // Dependencies are only fetched for the versions the resolver
+            // actually picks (via `get`).
+            Ok(Self::releases(&name, &files)
+                .iter()
+                .map(|(version, release)| this.build_resolved(&name, version, release, &[]))
+                .collect())
+        }
+    }
+}
+
+/// The index backend used for registry resolution: a PEP 503 simple index
+/// when `PYBUN_INDEX_URL` is set, otherwise the PyPI JSON API.
+#[derive(Clone)]
+pub enum RemoteIndex {
+    Json(PyPiIndex),
+    Simple(SimpleIndex),
+}
+
+impl RemoteIndex {
+    pub fn from_env(offline: bool) -> Result<Self, PyPiError> {
+        let index = match SimpleIndexClient::from_env(offline)? {
+            Some(client) => Self::Simple(SimpleIndex::new(client)),
+            None => Self::Json(PyPiIndex::new(PyPiClient::from_env(offline)?)),
+        };
+        crate::execution_policy::check_index(&index.index_url())?;
+        Ok(index)
+    }
+
+    pub fn index_url(&self) -> String {
+        match self {
+            Self::Json(index) => index.index_url(),
+            Self::Simple(index) => index.index_url(),
+        }
+    }
+
+    /// Drain notices about discarded cache entries (and, for a simple index,
+    /// releases whose dependencies could not be read).
+    pub fn take_stale_cache_notices(&self) -> Vec<String> {
+        match self {
+            Self::Json(index) => index.take_stale_cache_notices(),
+            Self::Simple(index) => index.take_notices(),
+        }
+    }
+
+    /// See [`PyPiIndex::project_history`]; a simple index publishes no
+    /// maintainers, so there is none.
+    pub async fn project_history(
+        &self,
+        name: &str,
+    ) -> Result<Option<crate::pypi::ProjectHistory>, PyPiError> {
+        match self {
+            Self::Json(index) => index.project_history(name).await,
+            Self::Simple(_) => Ok(None),
+        }
+    }
+
+    /// See [`PyPiIndex::dynamic_metadata_packages`]; a simple index never
+    /// builds sdists for metadata.
+    pub fn dynamic_metadata_packages(&self) -> BTreeSet<String> {
+        match self {
+            Self::Json(index) => index.dynamic_metadata_packages(),
+            Self::Simple(_) => BTreeSet::new(),
+        }
+    }
+}
+
+impl PackageIndex for RemoteIndex {
+    fn get(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> impl std::future::Future<Output = Result<Option<ResolvedPackage>, ResolveError>> + Send
+    {
+        let name = name.to_string();
+        let version = version.to_string();
+        let this = self.clone();
+        async move {
+            crate::execution_policy::check_package(&name)
+                .map_err(|v| ResolveError::Policy(v.to_string()))?;
+            match this {
+                Self::Json(index) => index.get(&name, &version).await,
+                Self::Simple(index) => index.get(&name, &version).await,
+            }
+        }
+    }
+
+    fn all(
+        &self,
+        name: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<ResolvedPackage>, ResolveError>> + Send {
+        let name = name.to_string();
+        let this = self.clone();
+        async move {
+            crate::execution_policy::check_package(&name)
+                .map_err(|v| ResolveError::Policy(v.to_string()))?;
+            match this {
+                Self::Json(index) => index.all(&name).await,
+                Self::Simple(index) => index.all(&name).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use tempfile::tempdir;
+
+    fn page() -> Url {
+        Url::parse("https://mirror.example/simple/demo-pkg/").unwrap()
+    }
+
+    #[test]
+    fn parses_pep503_html_page() {
+        let html = r#"<!DOCTYPE html><html><body>
+            <a href="../../files/demo_pkg-1.0-py3-none-any.whl#sha256=abc123"
+               data-requires-python="&gt;=3.8" data-dist-info-metadata="sha256=def">demo_pkg-1.0-py3-none-any.whl</a><br/>
+            <A HREF='https://files.example/demo-pkg-0.9.tar.gz' data-yanked>demo-pkg-0.9.tar.gz</A>
+            <a href="demo-pkg-1.1.tar.gz" data-requires-python=">=3.9" data-core-metadata="false"></a>
+        </body></html>"#;
+        let files = parse_html_project(html, &page());
+        assert_eq!(files.len(), 3);
+
+        assert_eq!(files[0].filename, "demo_pkg-1.0-py3-none-any.whl");
+        assert_eq!(
+            files[0].url,
+            "https://mirror.example/files/demo_pkg-1.0-py3-none-any.whl"
+        );
+        assert_eq!(files[0].sha256(), Some("abc123"));
+        assert_eq!(files[0].requires_python.as_deref(), Some(">=3.8"));
+        assert!(files[0].core_metadata);
+        assert!(!files[0].yanked);
+
+        assert!(files[1].yanked);
+        assert_eq!(files[1].url, "https://files.example/demo-pkg-0.9.tar.gz");
+
+        assert_eq!(files[2].filename, "demo-pkg-1.1.tar.gz");
+        assert_eq!(files[2].requires_python.as_deref(), Some(">=3.9"));
+        assert!(!files[2].core_metadata);
+    }
+
+    #[test]
+    fn parses_pep691_json_page() {
+        let body = serde_json::json!({
+            "meta": { "api-version": "1.1" },
+            "name": "demo-pkg",
+            "files": [
+                {
+                    "filename": "demo_pkg-1.0-py3-none-any.whl",
+                    "url": "/files/demo_pkg-1.0-py3-none-any.whl",
+                    "hashes": { "sha256": "abc" },
+                    "requires-python": ">=3.8",
+                    "core-metadata": { "sha256": "def" },
+                    "yanked": false
+                },
+                {
+                    "filename": "demo-pkg-0.9.tar.gz",
+                    "url": "https://files.example/demo-pkg-0.9.tar.gz",
+                    "hashes": {},
+                    "yanked": "broken build"
+                }
+            ]
+        })
+        .to_string();
+        let files = parse_json_project(&body, &page()).unwrap();
+        assert_eq!(
+            files[0].url,
+            "https://mirror.example/files/demo_pkg-1.0-py3-none-any.whl"
+        );
+        assert!(files[0].core_metadata);
+        assert_eq!(files[0].requires_python.as_deref(), Some(">=3.8"));
+        assert!(files[1].yanked);
+        assert!(!files[1].core_metadata);
+    }
+
+    #[test]
+    fn versions_come_from_filenames() {
+        let file = |filename: &str| SimpleFile {
+            filename: filename.into(),
+            url: String::new(),
+            hashes: BTreeMap::new(),
+            requires_python: None,
+            yanked: false,
+            core_metadata: false,
+        };
+        assert_eq!(
+            file("demo_pkg-1.0-py3-none-any.whl").version("demo-pkg"),
+            Some("1.0".into())
+        );
+        assert_eq!(
+            file("demo-pkg-2.0rc1.tar.gz").version("demo_pkg"),
+            Some("2.0rc1".into())
+        );
+        assert_eq!(file("demo_pkg-3.zip").version("demo-pkg"), Some("3".into()));
+        assert_eq!(file("demo-pkg-1.0.exe").version("demo-pkg"), None);
+    }
+
+    #[test]
+    fn reads_requires_dist_headers_only() {
+        let metadata = "Metadata-Version: 2.1\nName: demo\nRequires-Dist: idna>=2.5\n\
+                        requires-dist: urllib3<3,\n  >=1.21\nRequires-Python: >=3.8\n\n\
+                        Requires-Dist: not-a-header\n";
+        assert_eq!(
+            parse_metadata_requires_dist(metadata),
+            vec!["idna>=2.5".to_string(), "urllib3<3, >=1.21".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn resolves_from_simple_index_and_serves_cache_offline() {
+        let server = MockServer::start();
+        let html = format!(
+            r#"<a href="{0}/files/app-1.0-py3-none-any.whl#sha256=aa" data-core-metadata="true">app-1.0-py3-none-any.whl</a>
+               <a href="{0}/files/app-0.5.tar.gz">app-0.5.tar.gz</a>"#,
+            server.base_url()
+        );
+        let page = server.mock(|when, then| {
+            when.method(GET).path("/simple/app/");
+            then.status(200)
+                .header("Content-Type", "text/html")
+                .header("ETag", "\"p1\"")
+                .body(html.clone());
+        });
+        let metadata = server.mock(|when, then| {
+            when.method(GET)
+                .path("/files/app-1.0-py3-none-any.whl.metadata");
+            then.status(200).body(
+                "Name: app\nRequires-Dist: dep==2.0\nRequires-Dist: extra-only; extra == \"x\"\n",
+            );
+        });
+
+        let temp = tempdir().unwrap();
+        let url = server.url("/simple");
+        let index = SimpleIndex::new(
+            SimpleIndexClient::with_config(&url, temp.path().to_path_buf(), false).unwrap(),
+        );
+        let all = index.all("app").await.unwrap();
+        assert_eq!(
+            all.iter().map(|p| p.version.as_str()).collect::<Vec<_>>(),
+            vec!["0.5", "1.0"]
+        );
+        let app = index.get("app", "1.0").await.unwrap().unwrap();
+        assert_eq!(app.artifacts.wheels[0].hash.as_deref(), Some("sha256:aa"));
+        assert_eq!(
+            app.dependencies
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<_>>(),
+            vec!["dep==2.0".to_string()]
+        );
+        assert!(index.get("app", "3.0").await.unwrap().is_none());
+        page.assert_calls(1);
+        metadata.assert_calls(1);
+
+        let offline = SimpleIndex::new(
+            SimpleIndexClient::with_config(&url, temp.path().to_path_buf(), true).unwrap(),
+        );
+        let app = offline.get("app", "1.0").await.unwrap().unwrap();
+        assert_eq!(app.dependencies.len(), 1);
+        page.assert_calls(1);
+        metadata.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let server = MockServer::start();
+        let mut failing = server.mock(|when, then| {
+            when.method(GET).path("/simple/app/");
+            then.status(503);
+        });
+        let temp = tempdir().unwrap();
+        let mut client = SimpleIndexClient::with_config(
+            &server.url("/simple"),
+            temp.path().to_path_buf(),
+            false,
+        )
+        .unwrap();
+        client.retry_backoff = Duration::from_millis(1);
+
+        let err = client.project_files("app").await.unwrap_err();
+        assert!(
+            err.to_string().contains("gave up after 3 attempts"),
+            "{err}"
+        );
+        failing.assert_calls(3);
+        failing.delete();
+
+        server.mock(|when, then| {
+            when.method(GET).path("/simple/app/");
+            then.status(404);
+        });
+        assert!(client.project_files("app").await.unwrap().is_empty());
+    }
+}
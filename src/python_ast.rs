@@ -0,0 +1,1049 @@
+//! Structural parser for Python source, built on the tree-sitter Python
+//! grammar.
+//!
+//! [`parse_module`] reads the syntax tree into statements: `def`, `async
+//! def` and `class` statements carry their decorators, parameters and body,
+//! other compound statements (`if`, `try`, `with`, `for`, ...) carry their
+//! body, with every `elif`, `else`, `except` and `finally` clause as a
+//! statement of its own, and everything else is kept as text. [`imports`]
+//! lists the import statements at any nesting level, with whether they run
+//! at import time. Statement text has its comments dropped and its line
+//! breaks outside string literals replaced by a space, so a multi-line
+//! signature or decorator is one line. Expressions are not parsed further;
+//! the helpers at the bottom split argument lists, sequence literals and
+//! string literals on demand.
+//!
+//! Parsing never fails: tree-sitter recovers from syntax errors and the
+//! statements around them are still read, which is good enough for
+//! discovery and import analysis, where a file with a syntax error is also
+//! reported by Python.
+
+use tree_sitter::{Node, Parser, Tree};
+
+/// A statement of a module, class or compound statement body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stmt {
+    Function(FunctionDef),
+    Class(ClassDef),
+    /// Any other compound statement, e.g. `if`, `try:` or `with`.
+    Compound {
+        line: usize,
+        /// Header up to (not including) the colon, e.g. `if sys.platform == "linux"`.
+        header: String,
+        body: Vec<Stmt>,
+    },
+    Simple {
+        line: usize,
+        text: String,
+    },
+}
+
+/// A `def` or `async def` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDef {
+    pub name: String,
+    pub is_async: bool,
+    /// Line of the `def` keyword (decorators come before it).
+    pub line: usize,
+    pub decorators: Vec<Decorator>,
+    /// Header up to (not including) the colon, e.g. `def test(a, b) -> None`.
+    pub signature: String,
+    pub params: Vec<Param>,
+    pub body: Vec<Stmt>,
+}
+
+/// A `class` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassDef {
+    pub name: String,
+    pub line: usize,
+    pub decorators: Vec<Decorator>,
+    /// Base class and keyword expressions, e.g. `["unittest.TestCase"]`.
+    pub bases: Vec<String>,
+    pub body: Vec<Stmt>,
+}
+
+/// A decorator expression, without the `@`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decorator {
+    pub line: usize,
+    pub expr: String,
+}
+
+impl Decorator {
+    /// Dotted name of the decorator, e.g. `pytest.mark.parametrize`.
+    pub fn name(&self) -> &str {
+        call_parts(&self.expr).0
+    }
+
+    /// Argument text between the parentheses of a call decorator.
+    pub fn args(&self) -> Option<&str> {
+        call_parts(&self.expr).1
+    }
+}
+
+/// Kind of a function parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// Positional-or-keyword (or positional-only, before `/`).
+    Regular,
+    /// `*args`
+    VarPositional,
+    /// After `*` or `*args`.
+    KeywordOnly,
+    /// `**kwargs`
+    VarKeyword,
+}
+
+/// A function parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub kind: ParamKind,
+    pub annotation: Option<String>,
+    pub default: Option<String>,
+}
+
+/// One module imported by an `import` or `from ... import` statement;
+/// `import a, b` yields one per module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub line: usize,
+    /// Leading dots of a relative import; 0 for absolute imports.
+    pub level: usize,
+    /// Dotted module name, empty for `from . import x`.
+    pub module: String,
+    /// Names imported with `from`, which may be submodules.
+    pub names: Vec<String>,
+    /// The statement is inside a function, so it runs when the function is
+    /// called rather than when the module is imported.
+    pub in_function: bool,
+    /// The statement is inside a `try` whose handlers catch `ImportError`.
+    pub guarded: bool,
+}
+
+fn parse_tree(source: &str) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_python::LANGUAGE.into())
+        .ok()?;
+    parser.parse(source, None)
+}
+
+/// Parse a module into its top-level statements.
+pub fn parse_module(source: &str) -> Vec<Stmt> {
+    parse_tree(source)
+        .map(|tree| statements(tree.root_node(), source))
+        .unwrap_or_default()
+}
+
+/// Parameters of a `def` header such as `def test(a, b=1) -> None`.
+pub fn def_params(header: &str) -> Vec<Param> {
+    let source = format!("{}:\n    pass\n", header.trim());
+    match parse_module(&source).into_iter().next() {
+        Some(Stmt::Function(function)) => function.params,
+        _ => Vec::new(),
+    }
+}
+
+/// Import statements of a module, at any nesting level, in source order.
+pub fn imports(source: &str) -> Vec<Import> {
+    let mut imports = Vec::new();
+    if let Some(tree) = parse_tree(source) {
+        collect_imports(tree.root_node(), source, false, false, &mut imports);
+    }
+    imports
+}
+
+/// The statements of a module or block. Error nodes tree-sitter recovered
+/// are read as if their contents were statements of the block.
+fn statements(node: Node, source: &str) -> Vec<Stmt> {
+    let mut stmts = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        statement(child, source, &mut stmts);
+    }
+    stmts
+}
+
+fn statement(node: Node, source: &str, out: &mut Vec<Stmt>) {
+    match node.kind() {
+        "comment" => {}
+        "ERROR" => out.extend(statements(node, source)),
+        "decorated_definition" => {
+            let mut cursor = node.walk();
+            let decorators = node
+                .named_children(&mut cursor)
+                .filter(|child| child.kind() == "decorator")
+                .map(|decorator| Decorator {
+                    line: line_of(decorator),
+                    expr: text(decorator, source)
+                        .trim_start_matches('@')
+                        .trim()
+                        .to_string(),
+                })
+                .collect();
+            if let Some(definition) = node.child_by_field_name("definition") {
+                out.extend(definition_stmt(definition, source, decorators));
+            }
+        }
+        "function_definition" | "class_definition" => {
+            out.extend(definition_stmt(node, source, Vec::new()))
+        }
+        kind if (kind.ends_with("_statement") || kind.ends_with("_clause"))
+            && colon(node).is_some() =>
+        {
+            out.push(Stmt::Compound {
+                line: line_of(node),
+                header: header(node, source),
+                body: body(node, source),
+            });
+            // `elif`, `else`, `except` and `finally` follow as statements.
+            let mut cursor = node.walk();
+            for clause in node.named_children(&mut cursor) {
+                if clause.kind().ends_with("_clause") && colon(clause).is_some() {
+                    statement(clause, source, out);
+                }
+            }
+        }
+        _ => out.push(Stmt::Simple {
+            line: line_of(node),
+            text: text(node, source),
+        }),
+    }
+}
+
+fn definition_stmt(node: Node, source: &str, decorators: Vec<Decorator>) -> Option<Stmt> {
+    let name = text(node.child_by_field_name("name")?, source);
+    let line = line_of(node);
+    let body = body(node, source);
+    if node.kind() == "class_definition" {
+        let bases = node
+            .child_by_field_name("superclasses")
+            .map(|list| {
+                let mut cursor = list.walk();
+                list.named_children(&mut cursor)
+                    .filter(|base| base.kind() != "comment")
+                    .map(|base| text(base, source))
+                    .collect()
+            })
+            .unwrap_or_default();
+        return Some(Stmt::Class(ClassDef {
+            name,
+            line,
+            decorators,
+            bases,
+            body,
+        }));
+    }
+    let mut cursor = node.walk();
+    let is_async = node.children(&mut cursor).any(|c| c.kind() == "async");
+    Some(Stmt::Function(FunctionDef {
+        name,
+        is_async,
+        line,
+        decorators,
+        signature: header(node, source),
+        params: node
+            .child_by_field_name("parameters")
+            .map(|list| params(list, source))
+            .unwrap_or_default(),
+        body,
+    }))
+}
+
+fn params(list: Node, source: &str) -> Vec<Param> {
+    let mut params = Vec::new();
+    let mut keyword_only = false;
+    let mut cursor = list.walk();
+    for item in list.named_children(&mut cursor) {
+        let (pattern, default) = match item.kind() {
+            "keyword_separator" => {
+                keyword_only = true;
+                continue;
+            }
+            "typed_parameter" => (item.named_child(0), None),
+            "default_parameter" | "typed_default_parameter" => (
+                item.child_by_field_name("name"),
+                item.child_by_field_name("value"),
+            ),
+            _ => (Some(item), None),
+        };
+        let Some(pattern) = pattern else {
+            continue;
+        };
+        let (kind, name) = match pattern.kind() {
+            "list_splat_pattern" => {
+                keyword_only = true;
+                (ParamKind::VarPositional, pattern.named_child(0))
+            }
+            "dictionary_splat_pattern" => (ParamKind::VarKeyword, pattern.named_child(0)),
+            "identifier" if keyword_only => (ParamKind::KeywordOnly, Some(pattern)),
+            "identifier" => (ParamKind::Regular, Some(pattern)),
+            // Comments, `/`, and Python 2 tuple parameters.
+            _ => continue,
+        };
+        let Some(name) = name else {
+            continue;
+        };
+        params.push(Param {
+            name: text(name, source),
+            kind,
+            annotation: item
+                .child_by_field_name("type")
+                .map(|annotation| text(annotation, source)),
+            default: default.map(|value| text(value, source)),
+        });
+    }
+    params
+}
+
+fn collect_imports(
+    node: Node,
+    source: &str,
+    in_function: bool,
+    guarded: bool,
+    out: &mut Vec<Import>,
+) {
+    let line = line_of(node);
+    let mut cursor = node.walk();
+    match node.kind() {
+        "import_statement" => {
+            for name in node.children_by_field_name("name", &mut cursor) {
+                out.push(Import {
+                    line,
+                    level: 0,
+                    module: imported_name(name, source),
+                    names: Vec::new(),
+                    in_function,
+                    guarded,
+                });
+            }
+        }
+        "import_from_statement" => {
+            let (level, module) = match node.child_by_field_name("module_name") {
+                Some(relative) if relative.kind() == "relative_import" => {
+                    let mut inner = relative.walk();
+                    let mut level = 0;
+                    let mut module = String::new();
+                    for part in relative.named_children(&mut inner) {
+                        match part.kind() {
+                            "import_prefix" => level = text(part, source).matches('.').count(),
+                            "dotted_name" => module = imported_name(part, source),
+                            _ => {}
+                        }
+                    }
+                    (level, module)
+                }
+                Some(module) => (0, imported_name(module, source)),
+                None => (0, String::new()),
+            };
+            out.push(Import {
+                line,
+                level,
+                module,
+                names: node
+                    .children_by_field_name("name", &mut cursor)
+                    .map(|name| imported_name(name, source))
+                    .collect(),
+                in_function,
+                guarded,
+            });
+        }
+        "function_definition" => {
+            for child in node.named_children(&mut cursor) {
+                collect_imports(child, source, true, guarded, out);
+            }
+        }
+        "try_statement" => {
+            let catches = node
+                .named_children(&mut cursor)
+                .any(|clause| catches_import_error(clause, source));
+            for child in node.named_children(&mut cursor) {
+                let body = node.child_by_field_name("body") == Some(child);
+                collect_imports(
+                    child,
+                    source,
+                    in_function,
+                    guarded || (body && catches),
+                    out,
+                );
+            }
+        }
+        _ => {
+            for child in node.named_children(&mut cursor) {
+                collect_imports(child, source, in_function, guarded, out);
+            }
+        }
+    }
+}
+
+/// Whether an `except` clause handles `ImportError`: a bare `except`, or
+/// one naming `ImportError`, a subclass, or a base class of it.
+fn catches_import_error(clause: Node, source: &str) -> bool {
+    if !matches!(clause.kind(), "except_clause" | "except_group_clause") {
+        return false;
+    }
+    let mut cursor = clause.walk();
+    let Some(caught) = clause
+        .named_children(&mut cursor)
+        .find(|child| !matches!(child.kind(), "block" | "comment"))
+    else {
+        return true;
+    };
+    text(caught, source)
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|name| {
+            matches!(
+                name,
+                "ImportError" | "ModuleNotFoundError" | "Exception" | "BaseException"
+            )
+        })
+}
+
+/// `a.b` out of the dotted name or `a.b as c` an import names.
+fn imported_name(node: Node, source: &str) -> String {
+    let node = match node.kind() {
+        "aliased_import" => node.child_by_field_name("name").unwrap_or(node),
+        _ => node,
+    };
+    let mut cursor = node.walk();
+    let parts: Vec<String> = node
+        .named_children(&mut cursor)
+        .filter(|part| part.kind() == "identifier")
+        .map(|part| text(part, source))
+        .collect();
+    if parts.is_empty() {
+        text(node, source)
+    } else {
+        parts.join(".")
+    }
+}
+
+fn line_of(node: Node) -> usize {
+    node.start_position().row + 1
+}
+
+/// The colon ending the header of a compound statement or clause.
+fn colon(node: Node) -> Option<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor).find(|child| child.kind() == ":")
+}
+
+/// Header of a compound statement, up to (not including) its colon.
+fn header(node: Node, source: &str) -> String {
+    let end = colon(node).map_or(node.end_byte(), |colon| colon.start_byte());
+    let mut out = String::new();
+    let mut pos = node.start_byte();
+    push_text(node, end, source, &mut pos, &mut out);
+    push_joined(&source[pos.min(end)..end], &mut out);
+    out.trim_end().to_string()
+}
+
+/// Statements of the block a compound statement or clause ends with.
+fn body(node: Node, source: &str) -> Vec<Stmt> {
+    let mut cursor = node.walk();
+    let block = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "block");
+    block
+        .map(|block| statements(block, source))
+        .unwrap_or_default()
+}
+
+/// Source of `node` without comments, with line breaks outside string
+/// literals replaced by a space.
+fn text(node: Node, source: &str) -> String {
+    let end = node.end_byte();
+    let mut out = String::new();
+    let mut pos = node.start_byte();
+    push_text(node, end, source, &mut pos, &mut out);
+    push_joined(&source[pos.min(end)..end], &mut out);
+    out.trim().to_string()
+}
+
+/// Append the source of `node`'s descendants before `end`, from `pos` on.
+fn push_text(node: Node, end: usize, source: &str, pos: &mut usize, out: &mut String) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.start_byte() >= end {
+            break;
+        }
+        match child.kind() {
+            "comment" => {
+                push_joined(&source[*pos..child.start_byte()], out);
+                *pos = child.end_byte();
+            }
+            "string" => {
+                push_joined(&source[*pos..child.start_byte()], out);
+                let string_end = child.end_byte().min(end);
+                out.push_str(&source[child.start_byte()..string_end]);
+                *pos = string_end;
+            }
+            _ => push_text(child, end, source, pos, out),
+        }
+    }
+}
+
+/// Append source outside string literals with its backslash continuations
+/// and line breaks replaced by a space.
+fn push_joined(text: &str, out: &mut String) {
+    out.push_str(
+        &text
+            .replace("\\\r\n", " ")
+            .replace("\\\n", " ")
+            .replace("\r\n", " ")
+            .replace('\n', " "),
+    );
+}
+
+/// Byte index and character of every character of `text` outside string
+/// literals and brackets. Opening and closing brackets themselves count as
+/// top level.
+pub fn top_level(text: &str) -> Vec<(usize, char)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let plain: Vec<char> = chars.iter().map(|&(_, c)| c).collect();
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let (index, c) = chars[i];
+        match c {
+            '"' | '\'' => {
+                i = string_end(&plain, i);
+                continue;
+            }
+            '(' | '[' | '{' => {
+                if depth == 0 {
+                    result.push((index, c));
+                }
+                depth += 1;
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    result.push((index, c));
+                }
+            }
+            _ if depth == 0 => result.push((index, c)),
+            _ => {}
+        }
+        i += 1;
+    }
+    result
+}
+
+/// Split `text` at top-level `separator`s into trimmed parts. A trailing
+/// separator does not produce an empty last part.
+pub fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in top_level(text) {
+        if c == separator {
+            parts.push(text[start..i].trim());
+            start = i + c.len_utf8();
+        }
+    }
+    let last = text[start..].trim();
+    if !last.is_empty() || parts.is_empty() {
+        parts.push(last);
+    }
+    parts
+}
+
+/// `name` and `value` of a `name=value` item; `None` for `a == b` and
+/// other expressions.
+pub fn keyword_split(item: &str) -> Option<(&str, &str)> {
+    let bytes = item.as_bytes();
+    let (i, _) = top_level(item).into_iter().find(|&(i, c)| {
+        c == '='
+            && bytes.get(i + 1) != Some(&b'=')
+            && !matches!(
+                i.checked_sub(1).map(|p| bytes[p]),
+                Some(b'=' | b'!' | b'<' | b'>')
+            )
+    })?;
+    Some((item[..i].trim(), item[i + 1..].trim()))
+}
+
+/// Callee and argument text of a call expression such as
+/// `pytest.mark.skip(reason="x")`; the arguments are `None` when `expr` is
+/// not a call.
+pub fn call_parts(expr: &str) -> (&str, Option<&str>) {
+    let expr = expr.trim();
+    let open = top_level(expr).into_iter().find(|&(_, c)| c == '(');
+    match open {
+        Some((i, _)) if expr.ends_with(')') => {
+            (expr[..i].trim(), Some(&expr[i + 1..expr.len() - 1]))
+        }
+        _ => (expr, None),
+    }
+}
+
+/// One argument of a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argument<'a> {
+    /// Keyword of a `name=value` argument.
+    pub keyword: Option<&'a str>,
+    pub value: &'a str,
+}
+
+/// Arguments of the argument text of a call.
+pub fn call_arguments(args: &str) -> Vec<Argument<'_>> {
+    split_top_level(args, ',')
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .map(|item| match keyword_split(item) {
+            Some((keyword, value)) if is_identifier(keyword) => Argument {
+                keyword: Some(keyword),
+                value,
+            },
+            _ => Argument {
+                keyword: None,
+                value: item,
+            },
+        })
+        .collect()
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Items of a list, tuple or set literal; `None` for any other expression.
+pub fn sequence_items(expr: &str) -> Option<Vec<&str>> {
+    let expr = expr.trim();
+    let close = match expr.chars().next()? {
+        '[' => ']',
+        '(' => ')',
+        '{' => '}',
+        _ => return None,
+    };
+    let inner = bracket_contents(&expr[1..], close)?;
+    // The closing bracket must end the expression: `(a)[0]` is not a tuple.
+    if expr[1 + inner.len()..].trim() != close.to_string() {
+        return None;
+    }
+    let items = split_top_level(inner, ',');
+    if items == [""] {
+        return Some(Vec::new());
+    }
+    // `(x)` is a parenthesized expression, `(x,)` a tuple.
+    if close == ')' && items.len() == 1 && !inner.trim_end().ends_with(',') {
+        return None;
+    }
+    // Comprehensions produce an unknown number of items.
+    if items.len() == 1 && is_comprehension(items[0]) {
+        return None;
+    }
+    Some(items)
+}
+
+fn is_comprehension(item: &str) -> bool {
+    let outside: String = top_level(item).into_iter().map(|(_, c)| c).collect();
+    outside.split_whitespace().any(|word| word == "for")
+}
+
+/// Value of a string literal, including implicitly concatenated ones such as
+/// `"a" 'b'`. Escape sequences of non-raw strings are decoded; f-strings are
+/// returned as written. `None` when `expr` is not a string literal.
+pub fn string_literal(expr: &str) -> Option<String> {
+    let chars: Vec<char> = expr.trim().chars().collect();
+    let mut value = String::new();
+    let mut i = 0;
+    let mut any = false;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let prefix_start = i;
+        while i < chars.len() && chars[i].is_ascii_alphabetic() && i - prefix_start < 2 {
+            i += 1;
+        }
+        let prefix: String = chars[prefix_start..i]
+            .iter()
+            .collect::<String>()
+            .to_lowercase();
+        if !matches!(
+            prefix.as_str(),
+            "" | "r" | "u" | "b" | "f" | "rb" | "br" | "fr" | "rf"
+        ) || !matches!(chars.get(i), Some('"' | '\''))
+        {
+            return None;
+        }
+        let end = string_end(&chars, i);
+        let quote = chars[i];
+        let triple = chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote);
+        let width = if triple { 3 } else { 1 };
+        if end < i + 2 * width || chars[end - 1] != quote {
+            return None;
+        }
+        let body = &chars[i + width..end - width];
+        if prefix.contains('r') {
+            value.extend(body);
+        } else {
+            unescape(body, &mut value);
+        }
+        any = true;
+        i = end;
+    }
+    any.then_some(value)
+}
+
+fn unescape(body: &[char], out: &mut String) {
+    let mut i = 0;
+    while i < body.len() {
+        if body[i] == '\\' && i + 1 < body.len() {
+            match body[i + 1] {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                '0' => out.push('\0'),
+                '\\' | '\'' | '"' => out.push(body[i + 1]),
+                '\n' => {}
+                other => {
+                    out.push('\\');
+                    out.push(other);
+                }
+            }
+            i += 2;
+        } else {
+            out.push(body[i]);
+            i += 1;
+        }
+    }
+}
+
+/// Text up to the bracket closing an already opened one.
+fn bracket_contents(after_open: &str, close: char) -> Option<&str> {
+    top_level(after_open)
+        .into_iter()
+        .find(|&(_, c)| c == close)
+        .map(|(i, _)| &after_open[..i])
+}
+
+/// Index just past the string literal whose opening quote is at `start`.
+fn string_end(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let triple = chars.get(start + 1) == Some(&quote) && chars.get(start + 2) == Some(&quote);
+    let mut i = start + if triple { 3 } else { 1 };
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '\n' if !triple => return i,
+            c if c == quote => {
+                if !triple {
+                    return i + 1;
+                }
+                if chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote) {
+                    return i + 3;
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn functions(stmts: &[Stmt]) -> Vec<&FunctionDef> {
+        stmts
+            .iter()
+            .filter_map(|s| match s {
+                Stmt::Function(f) => Some(f),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn joins_brackets_continuations_and_triple_quoted_strings() {
+        let source = "x = (1,\n     2)  # comment\ny = 1 + \\\n    2\ns = \"\"\"a\n# not a comment\ndef fake(): pass\n\"\"\"\nz = 'it''s'; w = \"#\"\n";
+        let module = parse_module(source);
+        let texts: Vec<(usize, &str)> = module
+            .iter()
+            .map(|s| match s {
+                Stmt::Simple { line, text } => (*line, text.as_str()),
+                _ => panic!("simple statement expected: {s:?}"),
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                (1, "x = (1,      2)"),
+                (3, "y = 1 +      2"),
+                (5, "s = \"\"\"a\n# not a comment\ndef fake(): pass\n\"\"\""),
+                (9, "z = 'it''s'"),
+                (9, "w = \"#\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_definitions_with_decorators_and_multiline_signatures() {
+        let source = r#"
+@pytest.mark.parametrize(
+    "a, b",
+    [(1, 2), (3, 4)],
+)
+async def test_add(
+    a: int,
+    b: Dict[str, int] = {"x": 1, "y": 2},
+    *args,
+    key,
+    **kwargs,
+) -> None:
+    def helper(): return 1
+    assert helper()
+
+class TestThing(Base, metaclass=Meta):
+    def test_one(self): pass
+"#;
+        let module = parse_module(source);
+        let function = functions(&module)[0];
+        assert_eq!(function.name, "test_add");
+        assert!(function.is_async);
+        assert_eq!(function.line, 6);
+        assert_eq!(function.decorators[0].name(), "pytest.mark.parametrize");
+        assert_eq!(function.decorators[0].line, 2);
+        let params: Vec<(&str, ParamKind, bool)> = function
+            .params
+            .iter()
+            .map(|p| (p.name.as_str(), p.kind, p.default.is_some()))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                ("a", ParamKind::Regular, false),
+                ("b", ParamKind::Regular, true),
+                ("args", ParamKind::VarPositional, false),
+                ("key", ParamKind::KeywordOnly, false),
+                ("kwargs", ParamKind::VarKeyword, false),
+            ]
+        );
+        assert_eq!(
+            function.params[1].annotation.as_deref(),
+            Some("Dict[str, int]")
+        );
+        assert_eq!(functions(&function.body)[0].name, "helper");
+
+        let Stmt::Class(class) = &module[1] else {
+            panic!("class expected: {module:?}");
+        };
+        assert_eq!(class.bases, ["Base", "metaclass=Meta"]);
+        assert_eq!(functions(&class.body)[0].name, "test_one");
+        assert!(matches!(
+            functions(&class.body)[0].body[..],
+            [Stmt::Simple { .. }]
+        ));
+    }
+
+    #[test]
+    fn compound_statements_nest_their_bodies() {
+        let source = "if sys.version_info >= (3, 8):\n    def test_new(): pass\nelse:\n    match = 1\ntry: import x\nexcept ImportError: x = None\n";
+        let module = parse_module(source);
+        let headers: Vec<&str> = module
+            .iter()
+            .map(|s| match s {
+                Stmt::Compound { header, .. } => header.as_str(),
+                _ => "",
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            [
+                "if sys.version_info >= (3, 8)",
+                "else",
+                "try",
+                "except ImportError"
+            ]
+        );
+        let Stmt::Compound { body, .. } = &module[0] else {
+            unreachable!()
+        };
+        assert_eq!(functions(body)[0].name, "test_new");
+        let Stmt::Compound { body, .. } = &module[1] else {
+            unreachable!()
+        };
+        assert!(matches!(&body[0], Stmt::Simple { text, .. } if text == "match = 1"));
+    }
+
+    #[test]
+    fn reads_nested_decorated_and_conditional_definitions() {
+        let source = r#"
+class TestOuter:
+    class TestInner:
+        @pytest.mark.skipif(
+            sys.platform == "win32",  # not on Windows
+            reason="posix only",
+        )
+        @pytest.mark.slow
+        def test_nested(
+            self,
+            tmp_path,  # fixture
+            *, flag: bool = False,
+        ): pass
+
+if HAS_NUMPY:
+    @pytest.fixture
+    def arr(): return [1]
+elif sys.version_info < (3, 9):
+    pass
+else:
+    try:
+        def test_fallback(): ...
+    finally:
+        pass
+"#;
+        let module = parse_module(source);
+        let Stmt::Class(outer) = &module[0] else {
+            panic!("class expected: {module:?}");
+        };
+        let Stmt::Class(inner) = &outer.body[0] else {
+            panic!("nested class expected: {outer:?}");
+        };
+        let nested = functions(&inner.body)[0];
+        assert_eq!((nested.name.as_str(), nested.line), ("test_nested", 9));
+        let decorators: Vec<(usize, &str)> = nested
+            .decorators
+            .iter()
+            .map(|d| (d.line, d.name()))
+            .collect();
+        assert_eq!(
+            decorators,
+            [(4, "pytest.mark.skipif"), (8, "pytest.mark.slow")]
+        );
+        assert_eq!(
+            call_arguments(nested.decorators[0].args().unwrap())[1].keyword,
+            Some("reason")
+        );
+        let params: Vec<(&str, ParamKind)> = nested
+            .params
+            .iter()
+            .map(|p| (p.name.as_str(), p.kind))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                ("self", ParamKind::Regular),
+                ("tmp_path", ParamKind::Regular),
+                ("flag", ParamKind::KeywordOnly),
+            ]
+        );
+        assert_eq!(def_params(&nested.signature), nested.params);
+
+        let headers: Vec<&str> = module[1..]
+            .iter()
+            .map(|s| match s {
+                Stmt::Compound { header, .. } => header.as_str(),
+                _ => "",
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            ["if HAS_NUMPY", "elif sys.version_info < (3, 9)", "else"]
+        );
+        let Stmt::Compound { body, .. } = &module[1] else {
+            unreachable!()
+        };
+        assert_eq!(functions(body)[0].decorators[0].name(), "pytest.fixture");
+        let Stmt::Compound { body, .. } = &module[3] else {
+            unreachable!()
+        };
+        let Stmt::Compound { body, .. } = &body[0] else {
+            panic!("try expected: {body:?}");
+        };
+        assert_eq!(functions(body)[0].name, "test_fallback");
+    }
+
+    #[test]
+    fn lists_imports_with_where_they_run() {
+        let imports = imports(
+            "import os, app.models as m\n\
+             from . import helpers\n\
+             from ..core.db import (\n    Session,  # comment\n    engine,\n)\n\
+             from app import *\n\
+             \"\"\"\nimport not_code\n\"\"\"\n\
+             if TYPE_CHECKING:\n    from app.types import \\\n        Model\n\
+             try:\n    import ujson as json\nexcept ImportError:\n    import json\n\
+             def main():\n    import numpy\n",
+        );
+        let modules: Vec<(usize, &str, Vec<&str>)> = imports
+            .iter()
+            .map(|i| {
+                (
+                    i.level,
+                    i.module.as_str(),
+                    i.names.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            modules,
+            [
+                (0, "os", vec![]),
+                (0, "app.models", vec![]),
+                (1, "", vec!["helpers"]),
+                (2, "core.db", vec!["Session", "engine"]),
+                (0, "app", vec![]),
+                (0, "app.types", vec!["Model"]),
+                (0, "ujson", vec![]),
+                (0, "json", vec![]),
+                (0, "numpy", vec![]),
+            ]
+        );
+        let sites: Vec<(usize, bool, bool)> = imports
+            .iter()
+            .map(|i| (i.line, i.in_function, i.guarded))
+            .collect();
+        assert_eq!(
+            sites,
+            [
+                (1, false, false),
+                (1, false, false),
+                (2, false, false),
+                (3, false, false),
+                (7, false, false),
+                (12, false, false),
+                (15, false, true),
+                (17, false, false),
+                (19, true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_calls_sequences_and_strings() {
+        let (callee, args) = call_parts(r#"pytest.mark.skip(reason="a, (b)")"#);
+        assert_eq!(callee, "pytest.mark.skip");
+        let args = call_arguments(args.unwrap());
+        assert_eq!(args[0].keyword, Some("reason"));
+        assert_eq!(string_literal(args[0].value).as_deref(), Some("a, (b)"));
+        assert_eq!(call_parts("pytest.fixture"), ("pytest.fixture", None));
+
+        assert_eq!(call_arguments("a == b, x=1")[0].keyword, None);
+        assert_eq!(
+            sequence_items("[(1, 2), pytest.param(3, 4),]")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(sequence_items("(1,)").unwrap(), ["1"]);
+        assert_eq!(sequence_items("[]").unwrap().len(), 0);
+        assert_eq!(sequence_items("(1)"), None);
+        assert_eq!(sequence_items("[x for x in range(3)]"), None);
+        assert_eq!(sequence_items("CASES"), None);
+
+        assert_eq!(string_literal(r#"'a\'b' "c""#).as_deref(), Some("a'bc"));
+        assert_eq!(string_literal(r#"r"\d+""#).as_deref(), Some(r"\d+"));
+        assert_eq!(string_literal("name"), None);
+    }
+}
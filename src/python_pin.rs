@@ -0,0 +1,85 @@
+//! Project interpreter pins (`.python-version`).
+//!
+//! `pybun python pin 3.12` records the project's Python version in
+//! `.python-version` at the project root, the file pyenv and other tools
+//! read too. Environment discovery ([`crate::env::find_python_env`]) prefers
+//! a managed runtime matching the pin, and `pybun run`, `pybun test` and
+//! `pybun install` install that runtime first when it is missing. A pin the
+//! project's `requires-python` excludes is reported as a conflict.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the pin file.
+pub const PIN_FILE: &str = ".python-version";
+
+/// A `.python-version` pin and the file it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pin {
+    pub file: PathBuf,
+    pub version: String,
+}
+
+/// The pin in effect for `start_dir`: the nearest `.python-version` in it or
+/// a parent directory.
+pub fn find(start_dir: &Path) -> Option<Pin> {
+    crate::env::find_python_version_file(start_dir).map(|(file, version)| Pin { file, version })
+}
+
+/// Write `version` to `dir/.python-version`, replacing an existing pin.
+pub fn write(dir: &Path, version: &str) -> io::Result<PathBuf> {
+    let path = dir.join(PIN_FILE);
+    fs::write(&path, format!("{version}\n"))?;
+    Ok(path)
+}
+
+/// The Python version a pin stands for when checking `requires-python`: the
+/// release of the managed runtime it names (`3.12` → `3.12.7`,
+/// `pypy3.10` → `3.10.14`), else the pin itself.
+pub fn pinned_release(version: &str) -> String {
+    match crate::runtime::find_version(version) {
+        Some(info) => info.version,
+        None => crate::runtime::release_version(version).to_string(),
+    }
+}
+
+/// Whether `requires_python` excludes the pinned `version`.
+pub fn conflicts(version: &str, requires_python: &str) -> bool {
+    !crate::resolver::requires_python_allows(requires_python, &pinned_release(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn writes_and_finds_pins_from_subdirectories() {
+        let temp = TempDir::new().unwrap();
+        let nested = temp.path().join("src").join("pkg");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(find(&nested).is_none());
+
+        let file = write(temp.path(), "3.12").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "3.12\n");
+        assert_eq!(
+            find(&nested),
+            Some(Pin {
+                file,
+                version: "3.12".into()
+            })
+        );
+    }
+
+    #[test]
+    fn checks_pins_against_requires_python() {
+        assert_eq!(pinned_release("3.12"), "3.12.7");
+        assert_eq!(pinned_release("pypy3.10"), "3.10.14");
+        assert_eq!(pinned_release("3.8.18"), "3.8.18");
+        assert!(!conflicts("3.12", ">=3.10"));
+        assert!(conflicts("3.9", ">=3.10"));
+        assert!(conflicts("3.13t", "<3.13"));
+        assert!(conflicts("3.8.18", ">=3.9"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::network_policy::{self, NetworkPolicyViolation, Operation};
 use crate::runtime::Platform;
 use semver::Version;
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,8 @@ pub enum ReleaseManifestError {
     Parse(#[from] serde_json::Error),
     #[error("failed to fetch manifest from {url}: {source}")]
     Network { url: String, source: reqwest::Error },
+    #[error(transparent)]
+    NetworkPolicy(#[from] NetworkPolicyViolation),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +36,9 @@ pub struct ReleaseManifest {
     pub sbom: Option<ReleaseAttachment>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub provenance: Option<ReleaseAttachment>,
+    /// Staged rollout controls (see [`crate::update_channel`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<crate::update_channel::ReleaseRollout>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,15 +84,29 @@ impl ReleaseManifest {
     }
 
     pub fn from_url(url: &str) -> Result<Self, ReleaseManifestError> {
-        let client = reqwest::blocking::Client::builder()
+        Self::from_url_for_cohort(url, None)
+    }
+
+    /// Fetch the manifest, reporting `cohort` to the server in the
+    /// [`COHORT_HEADER`](crate::update_channel::COHORT_HEADER) header.
+    pub fn from_url_for_cohort(
+        url: &str,
+        cohort: Option<u8>,
+    ) -> Result<Self, ReleaseManifestError> {
+        network_policy::check_url(Operation::SelfUpdate, url)?;
+        let client = crate::http_config::blocking_client_builder()
             .timeout(Duration::from_secs(30))
+            .redirect(network_policy::redirect_policy(Operation::SelfUpdate))
             .build()
             .map_err(|source| ReleaseManifestError::Network {
                 url: url.to_string(),
                 source,
             })?;
-        let response = client
-            .get(url)
+        let mut request = client.get(url);
+        if let Some(cohort) = cohort {
+            request = request.header(crate::update_channel::COHORT_HEADER, cohort.to_string());
+        }
+        let response = request
             .send()
             .and_then(|resp| resp.error_for_status())
             .map_err(|source| ReleaseManifestError::Network {
@@ -103,11 +123,16 @@ impl ReleaseManifest {
     }
 
     pub fn load(source: &str) -> Result<Self, ReleaseManifestError> {
+        Self::load_for_cohort(source, None)
+    }
+
+    /// Like [`ReleaseManifest::load`], reporting `cohort` for remote sources.
+    pub fn load_for_cohort(source: &str, cohort: Option<u8>) -> Result<Self, ReleaseManifestError> {
         if let Some(path) = source.strip_prefix("file://") {
             return Self::from_path(Path::new(path));
         }
         if source.starts_with("http://") || source.starts_with("https://") {
-            return Self::from_url(source);
+            return Self::from_url_for_cohort(source, cohort);
         }
         Self::from_path(Path::new(source))
     }
@@ -0,0 +1,857 @@
+//! Reproducible build artifacts.
+//!
+//! `pybun build` makes its wheels and sdists byte-identical across machines
+//! and runs:
+//!
+//! - the build backend runs with `SOURCE_DATE_EPOCH` set (from
+//!   `[tool.pybun.build.env]` or `--env`, else the environment, else the time
+//!   of the last git commit, else 1980-01-01), `PYTHONHASHSEED=0`, `TZ=UTC`
+//!   and `LC_ALL=C.UTF-8`, plus the configured build environment;
+//! - afterwards every archive is rewritten with its entries in a fixed order,
+//!   all timestamps set to `SOURCE_DATE_EPOCH`, permissions reduced to
+//!   `0644`/`0755`, owners cleared, and zip extra fields, comments and gzip
+//!   header metadata dropped.
+//!
+//! [`compare_dirs`] diffs the artifacts of two builds for
+//! `pybun build --check-reproducible` and names the likely source of each
+//! difference ([`NondeterminismSource`]).
+
+use crate::security::sha256_bytes;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 1980-01-01T00:00:00Z, the earliest time a zip archive can record.
+pub const DEFAULT_SOURCE_DATE_EPOCH: u64 = 315_532_800;
+
+#[derive(Debug, Error)]
+pub enum ReproducibleError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("zip error in {path}: {source}")]
+    Zip {
+        path: PathBuf,
+        source: zip::result::ZipError,
+    },
+    #[error("invalid SOURCE_DATE_EPOCH '{0}': expected seconds since 1970-01-01")]
+    InvalidEpoch(String),
+}
+
+pub type Result<T> = std::result::Result<T, ReproducibleError>;
+
+/// Where the build timestamp came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpochSource {
+    /// `SOURCE_DATE_EPOCH` in the build environment (`--env` or
+    /// `[tool.pybun.build.env]`).
+    Config,
+    Env,
+    GitCommit,
+    Default,
+}
+
+impl EpochSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Config => "config",
+            Self::Env => "env",
+            Self::GitCommit => "git_commit",
+            Self::Default => "default",
+        }
+    }
+}
+
+/// The `SOURCE_DATE_EPOCH` for building `project_root`: the value in the
+/// configured build environment `configured`, else the environment variable,
+/// else the commit time of `HEAD`, else [`DEFAULT_SOURCE_DATE_EPOCH`].
+pub fn source_date_epoch(
+    project_root: &Path,
+    configured: &BTreeMap<String, String>,
+) -> Result<(u64, EpochSource)> {
+    let explicit = match configured.get("SOURCE_DATE_EPOCH") {
+        Some(value) => Some((value.clone(), EpochSource::Config)),
+        None => std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .map(|value| (value, EpochSource::Env)),
+    };
+    if let Some((value, source)) = explicit {
+        let epoch = value
+            .trim()
+            .parse()
+            .map_err(|_| ReproducibleError::InvalidEpoch(value.clone()))?;
+        return Ok((epoch, source));
+    }
+    let commit_time = Command::new("git")
+        .args(["log", "-1", "--format=%ct"])
+        .current_dir(project_root)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok());
+    Ok(match commit_time {
+        Some(epoch) => (epoch, EpochSource::GitCommit),
+        None => (DEFAULT_SOURCE_DATE_EPOCH, EpochSource::Default),
+    })
+}
+
+/// Environment for the build backend, sorted by name: fixed values for the
+/// usual sources of nondeterminism, with `configured` laid over them.
+pub fn build_env(epoch: u64, configured: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    let mut env: BTreeMap<String, String> = [
+        ("SOURCE_DATE_EPOCH", epoch.to_string()),
+        ("PYTHONHASHSEED", "0".to_string()),
+        ("TZ", "UTC".to_string()),
+        ("LC_ALL", "C.UTF-8".to_string()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    env.extend(configured.clone());
+    env.insert("SOURCE_DATE_EPOCH".to_string(), epoch.to_string());
+    env.into_iter().collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArtifactKind {
+    Zip,
+    TarGz,
+}
+
+fn artifact_kind(path: &Path) -> Option<ArtifactKind> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    let kind = if name.ends_with(".whl") || name.ends_with(".zip") {
+        ArtifactKind::Zip
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        ArtifactKind::TarGz
+    } else {
+        return None;
+    };
+    // Only rewrite files that really are archives of that kind.
+    let mut magic = [0u8; 4];
+    let read = fs::File::open(path)
+        .and_then(|mut f| f.read(&mut magic))
+        .ok()?;
+    let expected: &[u8] = match kind {
+        ArtifactKind::Zip => b"PK\x03\x04",
+        ArtifactKind::TarGz => b"\x1f\x8b",
+    };
+    (read >= expected.len() && magic.starts_with(expected)).then_some(kind)
+}
+
+/// Rewrite the archive at `path` deterministically. Returns `false` for
+/// files that are not wheels, zips or gzipped tarballs, which are left alone.
+pub fn normalize_artifact(path: &Path, epoch: u64) -> Result<bool> {
+    let data = match artifact_kind(path) {
+        Some(ArtifactKind::Zip) => normalize_zip(path, epoch)?,
+        Some(ArtifactKind::TarGz) => normalize_tar_gz(path, epoch)?,
+        None => return Ok(false),
+    };
+    let tmp = path.with_extension("normalized.tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path)?;
+    Ok(true)
+}
+
+/// Kind of an archive member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MemberKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// An archive member read into memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Member {
+    name: String,
+    kind: MemberKind,
+    mode: u32,
+    mtime: u64,
+    /// File contents, or the target of a symlink.
+    data: Vec<u8>,
+    compression: Option<CompressionMethod>,
+}
+
+impl Member {
+    fn normalized_mode(&self) -> u32 {
+        if self.kind == MemberKind::Dir || self.mode & 0o111 != 0 {
+            0o755
+        } else {
+            0o644
+        }
+    }
+}
+
+/// Wheel order: the package first, then the `.dist-info` directory with
+/// `RECORD` last, each sorted by name.
+fn member_order(name: &str) -> (bool, bool, &str) {
+    let dist_info = name
+        .split('/')
+        .next()
+        .is_some_and(|top| top.ends_with(".dist-info"));
+    let record = dist_info && name.ends_with("/RECORD");
+    (dist_info, record, name)
+}
+
+fn zip_error(path: &Path) -> impl FnOnce(zip::result::ZipError) -> ReproducibleError + '_ {
+    move |source| ReproducibleError::Zip {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn read_zip_members(path: &Path) -> Result<Vec<Member>> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?).map_err(zip_error(path))?;
+    let mut members = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(zip_error(path))?;
+        let kind = if file.is_dir() {
+            MemberKind::Dir
+        } else if file.is_symlink() {
+            MemberKind::Symlink
+        } else {
+            MemberKind::File
+        };
+        let mtime = file
+            .last_modified()
+            .map(|t| u64::from(t.datepart()) << 16 | u64::from(t.timepart()))
+            .unwrap_or_default();
+        let mut data = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut data)?;
+        members.push(Member {
+            name: file.name().to_string(),
+            kind,
+            mode: file.unix_mode().unwrap_or(0o644) & 0o7777,
+            mtime,
+            data,
+            compression: Some(file.compression()),
+        });
+    }
+    Ok(members)
+}
+
+fn normalize_zip(path: &Path, epoch: u64) -> Result<Vec<u8>> {
+    let mut members = read_zip_members(path)?;
+    members.sort_by(|a, b| member_order(&a.name).cmp(&member_order(&b.name)));
+
+    let timestamp = zip_datetime(epoch);
+    let mut writer = ZipWriter::new(io::Cursor::new(Vec::new()));
+    for member in &members {
+        let compression = match member.compression {
+            Some(CompressionMethod::Stored) => CompressionMethod::Stored,
+            _ => CompressionMethod::Deflated,
+        };
+        let options = SimpleFileOptions::default()
+            .compression_method(compression)
+            .compression_level(None)
+            .last_modified_time(timestamp)
+            .unix_permissions(member.normalized_mode())
+            .large_file(member.data.len() as u64 >= u32::MAX as u64);
+        match member.kind {
+            MemberKind::Dir => writer
+                .add_directory(member.name.as_str(), options)
+                .map_err(zip_error(path))?,
+            MemberKind::Symlink => writer
+                .add_symlink(
+                    member.name.as_str(),
+                    String::from_utf8_lossy(&member.data),
+                    options,
+                )
+                .map_err(zip_error(path))?,
+            MemberKind::File => {
+                writer
+                    .start_file(member.name.as_str(), options)
+                    .map_err(zip_error(path))?;
+                writer.write_all(&member.data)?;
+            }
+        }
+    }
+    Ok(writer.finish().map_err(zip_error(path))?.into_inner())
+}
+
+/// `epoch` as a zip timestamp, clamped to the range zip can record
+/// (1980–2107).
+fn zip_datetime(epoch: u64) -> zip::DateTime {
+    let epoch = epoch.max(DEFAULT_SOURCE_DATE_EPOCH);
+    let (year, month, day) = crate::mcp::civil_from_unix_days((epoch / 86_400) as i64);
+    let secs = epoch % 86_400;
+    zip::DateTime::from_date_and_time(
+        year.clamp(1980, 2107) as u16,
+        month as u8,
+        day as u8,
+        (secs / 3_600) as u8,
+        ((secs % 3_600) / 60) as u8,
+        (secs % 60) as u8,
+    )
+    .unwrap_or_default()
+}
+
+fn read_tar_gz_members(path: &Path) -> Result<Vec<Member>> {
+    let decoder = flate2::read::GzDecoder::new(fs::File::open(path)?);
+    let mut archive = tar::Archive::new(decoder);
+    let mut members = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => MemberKind::File,
+            tar::EntryType::Directory => MemberKind::Dir,
+            tar::EntryType::Symlink => MemberKind::Symlink,
+            // Hard links, devices and FIFOs do not occur in sdists.
+            _ => continue,
+        };
+        let mode = header.mode().unwrap_or(0o644) & 0o7777;
+        let mtime = header.mtime().unwrap_or_default();
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let data = if kind == MemberKind::Symlink {
+            entry
+                .link_name()?
+                .map(|target| target.to_string_lossy().into_owned().into_bytes())
+                .unwrap_or_default()
+        } else {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            data
+        };
+        members.push(Member {
+            name,
+            kind,
+            mode,
+            mtime,
+            data,
+            compression: None,
+        });
+    }
+    Ok(members)
+}
+
+fn normalize_tar_gz(path: &Path, epoch: u64) -> Result<Vec<u8>> {
+    let mut members = read_tar_gz_members(path)?;
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // A zero gzip mtime and no file name keep the gzip header fixed.
+    let encoder = flate2::GzBuilder::new()
+        .mtime(0)
+        .write(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for member in &members {
+        let mut header = tar::Header::new_gnu();
+        header.set_mtime(epoch);
+        header.set_mode(member.normalized_mode());
+        header.set_uid(0);
+        header.set_gid(0);
+        match member.kind {
+            MemberKind::Dir => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_size(0);
+                builder.append_data(&mut header, &member.name, io::empty())?;
+            }
+            MemberKind::Symlink => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                let target = String::from_utf8_lossy(&member.data).into_owned();
+                builder.append_link(&mut header, &member.name, target)?;
+            }
+            MemberKind::File => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_size(member.data.len() as u64);
+                builder.append_data(&mut header, &member.name, member.data.as_slice())?;
+            }
+        }
+    }
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// How an archive member differs between two builds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberChange {
+    /// Only in the second build.
+    Added,
+    /// Only in the first build.
+    Removed,
+    /// Contents differ.
+    Content,
+    /// Same contents; timestamp, permissions or kind differ.
+    Metadata,
+}
+
+/// The likely cause of a difference between two builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NondeterminismSource {
+    /// Only one build produced the artifact (e.g. a version or tag derived
+    /// from the clock or the checkout).
+    ArtifactSet,
+    /// Only one build produced a member (e.g. generated or globbed files).
+    FileSet,
+    /// Same members, different archive bytes: entry order, compression or
+    /// archive headers.
+    EntryOrder,
+    /// Member timestamps, or a date, time or other number written into a
+    /// file.
+    Timestamp,
+    /// Member permissions or file kind.
+    Permissions,
+    /// A temporary directory path written into a file.
+    TempPath,
+    /// Any other difference in file contents.
+    Content,
+}
+
+impl NondeterminismSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ArtifactSet => "artifact_set",
+            Self::FileSet => "file_set",
+            Self::EntryOrder => "entry_order",
+            Self::Timestamp => "timestamp",
+            Self::Permissions => "permissions",
+            Self::TempPath => "temp_path",
+            Self::Content => "content",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemberDiff {
+    pub name: String,
+    pub change: MemberChange,
+    pub source: NondeterminismSource,
+}
+
+/// One artifact of `pybun build --check-reproducible`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArtifactComparison {
+    pub name: String,
+    /// Hex SHA-256 in the first build (`None` if it was not produced).
+    pub first_sha256: Option<String>,
+    pub second_sha256: Option<String>,
+    pub identical: bool,
+    /// Archive members that differ. Empty when the archive bytes differ but
+    /// every member matches (entry order or compression).
+    pub members: Vec<MemberDiff>,
+    /// Causes of the differences, sorted and deduplicated.
+    pub sources: Vec<NondeterminismSource>,
+}
+
+/// Compare the artifacts in `first` and `second` by name, sorted by name.
+pub fn compare_dirs(first: &Path, second: &Path) -> Result<Vec<ArtifactComparison>> {
+    let first_files = list_files(first)?;
+    let second_files = list_files(second)?;
+    let mut names: Vec<&String> = first_files.keys().chain(second_files.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut comparisons = Vec::with_capacity(names.len());
+    for name in names {
+        let first_path = first_files.get(name);
+        let second_path = second_files.get(name);
+        let first_sha256 = first_path
+            .map(|p| fs::read(p).map(|d| sha256_bytes(&d)))
+            .transpose()?;
+        let second_sha256 = second_path
+            .map(|p| fs::read(p).map(|d| sha256_bytes(&d)))
+            .transpose()?;
+        let identical = first_sha256.is_some() && first_sha256 == second_sha256;
+        let members = match (first_path, second_path) {
+            (Some(a), Some(b)) if !identical => diff_members(a, b)?,
+            _ => None,
+        };
+        let mut sources: Vec<NondeterminismSource> = match &members {
+            _ if identical => Vec::new(),
+            _ if first_path.is_none() || second_path.is_none() => {
+                vec![NondeterminismSource::ArtifactSet]
+            }
+            Some(members) if members.is_empty() => vec![NondeterminismSource::EntryOrder],
+            Some(members) => members.iter().map(|m| m.source).collect(),
+            // Not an archive: classify the file itself.
+            None => {
+                let (a, b) = (
+                    fs::read(first_path.unwrap())?,
+                    fs::read(second_path.unwrap())?,
+                );
+                vec![content_source(&a, &b)]
+            }
+        };
+        sources.sort();
+        sources.dedup();
+        comparisons.push(ArtifactComparison {
+            name: name.clone(),
+            first_sha256,
+            second_sha256,
+            identical,
+            members: members.unwrap_or_default(),
+            sources,
+        });
+    }
+    Ok(comparisons)
+}
+
+fn list_files(dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.insert(
+                entry.file_name().to_string_lossy().into_owned(),
+                entry.path(),
+            );
+        }
+    }
+    Ok(files)
+}
+
+fn read_members(path: &Path) -> Result<Option<BTreeMap<String, Member>>> {
+    let members = match artifact_kind(path) {
+        Some(ArtifactKind::Zip) => read_zip_members(path)?,
+        Some(ArtifactKind::TarGz) => read_tar_gz_members(path)?,
+        None => return Ok(None),
+    };
+    Ok(Some(
+        members.into_iter().map(|m| (m.name.clone(), m)).collect(),
+    ))
+}
+
+/// Differing members of two archives, or `None` if either is not an archive.
+fn diff_members(first: &Path, second: &Path) -> Result<Option<Vec<MemberDiff>>> {
+    let (Some(first), Some(second)) = (read_members(first)?, read_members(second)?) else {
+        return Ok(None);
+    };
+    let mut diffs = Vec::new();
+    for (name, a) in &first {
+        let (change, source) = match second.get(name) {
+            None => (MemberChange::Removed, NondeterminismSource::FileSet),
+            Some(b) if a.data != b.data => {
+                (MemberChange::Content, content_source(&a.data, &b.data))
+            }
+            Some(b) if (a.kind, a.mode) != (b.kind, b.mode) => {
+                (MemberChange::Metadata, NondeterminismSource::Permissions)
+            }
+            Some(b) if a.mtime != b.mtime => {
+                (MemberChange::Metadata, NondeterminismSource::Timestamp)
+            }
+            Some(_) => continue,
+        };
+        diffs.push(MemberDiff {
+            name: name.clone(),
+            change,
+            source,
+        });
+    }
+    for name in second.keys().filter(|name| !first.contains_key(*name)) {
+        diffs.push(MemberDiff {
+            name: name.clone(),
+            change: MemberChange::Added,
+            source: NondeterminismSource::FileSet,
+        });
+    }
+    diffs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Some(diffs))
+}
+
+/// Classify differing file contents by the token around the first
+/// difference: a temporary path, else a run of digits (a date or time),
+/// else generic content.
+fn content_source(first: &[u8], second: &[u8]) -> NondeterminismSource {
+    let start = first.iter().zip(second).take_while(|(a, b)| a == b).count();
+    let (a, b) = (token_at(first, start), token_at(second, start));
+    // `tempfile` names start with `tmp`; macOS keeps them under
+    // `/var/folders`.
+    let temp = |token: &str| {
+        token.contains("/var/folders/")
+            || token
+                .split(['/', '\\'])
+                .any(|part| part.to_ascii_lowercase().starts_with("tmp"))
+    };
+    if temp(a) || temp(b) {
+        return NondeterminismSource::TempPath;
+    }
+    let digits = |token: &str| -> String { token.chars().filter(char::is_ascii_digit).collect() };
+    let (da, db) = (digits(a), digits(b));
+    if !da.is_empty() && !db.is_empty() && da != db && {
+        let strip = |token: &str| token.replace(|c: char| c.is_ascii_digit(), "");
+        strip(a) == strip(b)
+    } {
+        return NondeterminismSource::Timestamp;
+    }
+    NondeterminismSource::Content
+}
+
+/// The whitespace- or quote-delimited token of `data` containing `index`.
+fn token_at(data: &[u8], index: usize) -> &str {
+    let boundary = |c: &u8| c.is_ascii_whitespace() || matches!(c, b'"' | b'\'' | b',' | b'=');
+    let index = index.min(data.len());
+    let start = data[..index]
+        .iter()
+        .rposition(boundary)
+        .map_or(0, |i| i + 1);
+    let end = data[index..]
+        .iter()
+        .position(boundary)
+        .map_or(data.len(), |i| index + i);
+    std::str::from_utf8(&data[start..end]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8], u32)], time: zip::DateTime) {
+        let mut writer = ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, data, mode) in entries {
+            writer
+                .start_file(
+                    *name,
+                    SimpleFileOptions::default()
+                        .last_modified_time(time)
+                        .unix_permissions(*mode),
+                )
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn write_tar_gz(path: &Path, entries: &[(&str, &[u8])], mtime: u64, owner: &str) {
+        let encoder = flate2::GzBuilder::new()
+            .mtime(mtime as u32)
+            .filename("demo.tar")
+            .write(fs::File::create(path).unwrap(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mtime(mtime);
+            header.set_mode(0o664);
+            header.set_username(owner).unwrap();
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn wheels_normalize_to_the_same_bytes() {
+        let temp = tempdir().unwrap();
+        let first = temp.path().join("a.whl");
+        let second = temp.path().join("b.whl");
+        write_zip(
+            &first,
+            &[
+                ("demo-1.0.dist-info/RECORD", b"record", 0o600),
+                ("demo/__init__.py", b"x = 1\n", 0o664),
+                ("demo-1.0.dist-info/METADATA", b"meta", 0o644),
+            ],
+            zip::DateTime::from_date_and_time(2024, 5, 1, 12, 0, 0).unwrap(),
+        );
+        write_zip(
+            &second,
+            &[
+                ("demo-1.0.dist-info/METADATA", b"meta", 0o644),
+                ("demo/__init__.py", b"x = 1\n", 0o644),
+                ("demo-1.0.dist-info/RECORD", b"record", 0o644),
+            ],
+            zip::DateTime::from_date_and_time(2025, 1, 2, 3, 4, 6).unwrap(),
+        );
+
+        assert!(normalize_artifact(&first, 1_700_000_000).unwrap());
+        assert!(normalize_artifact(&second, 1_700_000_000).unwrap());
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+        let names: Vec<String> = read_zip_members(&first)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "demo/__init__.py",
+                "demo-1.0.dist-info/METADATA",
+                "demo-1.0.dist-info/RECORD"
+            ]
+        );
+        let mut archive = ZipArchive::new(fs::File::open(&first).unwrap()).unwrap();
+        let time = archive.by_index_raw(0).unwrap().last_modified().unwrap();
+        // 1_700_000_000 is 2023-11-14T22:13:20Z.
+        assert_eq!(
+            (
+                time.year(),
+                time.month(),
+                time.day(),
+                time.hour(),
+                time.minute()
+            ),
+            (2023, 11, 14, 22, 13)
+        );
+    }
+
+    #[test]
+    fn sdists_normalize_to_the_same_bytes() {
+        let temp = tempdir().unwrap();
+        let first = temp.path().join("a.tar.gz");
+        let second = temp.path().join("b.tar.gz");
+        write_tar_gz(
+            &first,
+            &[
+                ("demo-1.0/PKG-INFO", b"info"),
+                ("demo-1.0/demo.py", b"x = 1\n"),
+            ],
+            1_600_000_000,
+            "alice",
+        );
+        write_tar_gz(
+            &second,
+            &[
+                ("demo-1.0/demo.py", b"x = 1\n"),
+                ("demo-1.0/PKG-INFO", b"info"),
+            ],
+            1_650_000_000,
+            "bob",
+        );
+
+        assert!(normalize_artifact(&first, 1_700_000_000).unwrap());
+        assert!(normalize_artifact(&second, 1_700_000_000).unwrap());
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+        let members = read_tar_gz_members(&first).unwrap();
+        assert_eq!(members[0].name, "demo-1.0/PKG-INFO");
+        assert!(
+            members
+                .iter()
+                .all(|m| m.mtime == 1_700_000_000 && m.mode == 0o644)
+        );
+    }
+
+    #[test]
+    fn files_that_are_not_archives_are_left_alone() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("demo-1.0-py3-none-any.whl");
+        fs::write(&path, "not a zip").unwrap();
+        assert!(!normalize_artifact(&path, 0).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not a zip");
+    }
+
+    #[test]
+    fn comparison_reports_differing_members() {
+        let temp = tempdir().unwrap();
+        let (first, second) = (temp.path().join("first"), temp.path().join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        let time = zip::DateTime::from_date_and_time(2024, 1, 1, 0, 0, 0).unwrap();
+        write_zip(
+            &first.join("demo.whl"),
+            &[
+                ("a.py", b"1", 0o644),
+                ("b.py", b"same", 0o644),
+                ("gone.py", b"", 0o644),
+            ],
+            time,
+        );
+        write_zip(
+            &second.join("demo.whl"),
+            &[
+                ("a.py", b"2", 0o644),
+                ("b.py", b"same", 0o755),
+                ("new.py", b"", 0o644),
+            ],
+            time,
+        );
+        fs::write(first.join("notes.txt"), "same").unwrap();
+        fs::write(second.join("notes.txt"), "same").unwrap();
+        fs::write(first.join("only-first.txt"), "x").unwrap();
+
+        let comparisons = compare_dirs(&first, &second).unwrap();
+        let names: Vec<&str> = comparisons.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["demo.whl", "notes.txt", "only-first.txt"]);
+
+        assert!(!comparisons[0].identical);
+        let changes: Vec<(&str, &MemberChange)> = comparisons[0]
+            .members
+            .iter()
+            .map(|m| (m.name.as_str(), &m.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("a.py", &MemberChange::Content),
+                ("b.py", &MemberChange::Metadata),
+                ("gone.py", &MemberChange::Removed),
+                ("new.py", &MemberChange::Added),
+            ]
+        );
+        assert_eq!(
+            comparisons[0].sources,
+            [
+                NondeterminismSource::FileSet,
+                NondeterminismSource::Timestamp,
+                NondeterminismSource::Permissions,
+            ]
+        );
+        assert!(comparisons[1].identical);
+        assert!(comparisons[1].sources.is_empty());
+        assert!(!comparisons[2].identical);
+        assert!(comparisons[2].second_sha256.is_none());
+        assert_eq!(comparisons[2].sources, [NondeterminismSource::ArtifactSet]);
+    }
+
+    #[test]
+    fn content_differences_are_classified() {
+        assert_eq!(
+            content_source(
+                b"built = \"2024-05-01T12:00:03\"\n",
+                b"built = \"2024-05-01T12:00:09\"\n"
+            ),
+            NondeterminismSource::Timestamp
+        );
+        assert_eq!(
+            content_source(
+                b"ROOT = '/tmp/tmpab12cd/src'\n",
+                b"ROOT = '/tmp/tmpzz98xy/src'\n"
+            ),
+            NondeterminismSource::TempPath
+        );
+        assert_eq!(
+            content_source(b"ORDER = ['a', 'b']\n", b"ORDER = ['b', 'a']\n"),
+            NondeterminismSource::Content
+        );
+    }
+
+    #[test]
+    fn build_env_pins_defaults_and_applies_configured_values() {
+        let configured = BTreeMap::from([
+            ("LC_ALL".to_string(), "en_US.UTF-8".to_string()),
+            ("SOURCE_DATE_EPOCH".to_string(), "1".to_string()),
+            ("CFLAGS".to_string(), "-O2".to_string()),
+        ]);
+        let env = build_env(1_700_000_000, &configured);
+        assert_eq!(
+            env,
+            [
+                ("CFLAGS", "-O2"),
+                ("LC_ALL", "en_US.UTF-8"),
+                ("PYTHONHASHSEED", "0"),
+                ("SOURCE_DATE_EPOCH", "1700000000"),
+                ("TZ", "UTC"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+
+        let temp = tempdir().unwrap();
+        assert_eq!(
+            source_date_epoch(temp.path(), &configured).unwrap(),
+            (1, EpochSource::Config)
+        );
+    }
+}
@@ -79,6 +79,10 @@ pub struct Requirement {
     /// diagnostics should warn when this is non-empty so the omission is not
     /// silent. See `docs/PLAN.md`.
     pub extras: Vec<String>,
+    /// PEP 508 direct reference (`name @ url`), e.g.
+    /// `git+https://github.com/org/repo@v1.2.3`. Only `git+` URLs are
+    /// installed (see [`crate::git_source`]); `specs` is `[Any]` then.
+    pub url: Option<String>,
 }
 
 impl Requirement {
@@ -88,6 +92,7 @@ impl Requirement {
             specs: vec![VersionSpec::Exact(version.into())],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
@@ -97,6 +102,7 @@ impl Requirement {
             specs: vec![VersionSpec::Minimum(version.into())],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
@@ -106,6 +112,7 @@ impl Requirement {
             specs: vec![VersionSpec::MinimumExclusive(version.into())],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
@@ -115,6 +122,7 @@ impl Requirement {
             specs: vec![VersionSpec::MaximumInclusive(version.into())],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
@@ -124,6 +132,7 @@ impl Requirement {
             specs: vec![VersionSpec::Maximum(version.into())],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
@@ -133,6 +142,7 @@ impl Requirement {
             specs: vec![VersionSpec::NotEqual(version.into())],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
@@ -142,6 +152,7 @@ impl Requirement {
             specs: vec![VersionSpec::Compatible(version.into())],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
@@ -151,10 +162,11 @@ impl Requirement {
             specs: vec![VersionSpec::Any],
             marker: None,
             extras: Vec::new(),
+            url: None,
         }
     }
 
-    fn constraint_display(&self) -> String {
+    pub(crate) fn constraint_display(&self) -> String {
         if self.specs.iter().all(|s| *s == VersionSpec::Any) {
             return "*".to_string();
         }
@@ -194,7 +206,9 @@ impl Requirement {
 
 impl fmt::Display for Requirement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.specs.iter().all(|s| *s == VersionSpec::Any) {
+        if let Some(url) = &self.url {
+            write!(f, "{} @ {}", self.name, url)
+        } else if self.specs.iter().all(|s| *s == VersionSpec::Any) {
             write!(f, "{}", self.name)
         } else {
             write!(f, "{}{}", self.name, self.constraint_display())
@@ -277,6 +291,16 @@ impl FromStr for Requirement {
             (normalized, None)
         };
 
+        // A direct reference (`name @ url`) has no version constraints; the
+        // URL may itself contain `@`, so split at the first one.
+        let (requirement_part, url) = match requirement_part.split_once('@') {
+            Some((name, url)) => (name.trim(), Some(url.trim().to_string())),
+            None => (requirement_part, None),
+        };
+        if url.as_deref().is_some_and(str::is_empty) {
+            return Err("direct reference is missing a URL".into());
+        }
+
         // Extract PEP 508 extras (e.g. `typer[all]`) before parsing the name
         // and version constraints, so the brackets don't leak into `name`
         // (which would otherwise corrupt PyPI metadata lookups). Extras are
@@ -326,6 +350,7 @@ impl FromStr for Requirement {
             specs,
             marker: marker_part,
             extras,
+            url,
         })
     }
 }
@@ -347,6 +372,9 @@ pub struct ResolvedPackage {
 pub struct PackageArtifacts {
     pub wheels: Vec<Wheel>,
     pub sdist: Option<String>,
+    /// Download URL of `sdist`, for source builds.
+    pub sdist_url: Option<String>,
+    pub sdist_hash: Option<String>,
 }
 
 impl PackageArtifacts {
@@ -360,7 +388,7 @@ impl PackageArtifacts {
                 python_tag: Some("py3".into()),
                 abi_tag: Some("none".into()),
             }],
-            sdist: None,
+            ..Default::default()
         }
     }
 }
@@ -422,18 +450,14 @@ pub fn python_version_to_cp_tag(version: &str) -> Option<String> {
     }
 }
 
-/// Convert a CPython wheel tag (e.g., `"cp310"`) back to a dotted `MAJOR.MINOR`
-/// version string (e.g., `"3.10"`). Returns `None` for non-CPython tags
-/// (e.g., `"py3"`, `"abi3"`) or tags that don't fit the `cp{major}{minor}` shape.
+/// Convert an interpreter tag (e.g., `"cp310"`, `"cp313t"`, `"pp310"`) back to a
+/// dotted `MAJOR.MINOR` version string (e.g., `"3.10"`). Returns `None` for
+/// tags that don't name an interpreter (e.g., `"py3"`, `"abi3"`) or don't fit
+/// the `cp{major}{minor}` shape.
 ///
 /// The major version is always exactly one digit, mirroring [`cp_tag_ge`].
 pub fn cp_tag_to_dotted_version(tag: &str) -> Option<String> {
-    let digits = tag.strip_prefix("cp")?;
-    if digits.len() < 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
-        return None;
-    }
-    let (major, minor) = digits.split_at(1);
-    Some(format!("{major}.{minor}"))
+    crate::tags::Interpreter::parse(tag).map(|interpreter| interpreter.dotted_version())
 }
 
 /// Compare two CPython tags version-wise: returns true if `a` >= `b`.
@@ -458,18 +482,20 @@ fn cp_tag_ge(a: &str, b: &str) -> bool {
     }
 }
 
-/// Check whether a wheel is compatible with the given active CPython tag (e.g., `"cp311"`).
+/// Check whether a wheel is compatible with the given active interpreter tag (e.g.,
+/// `"cp311"`, free-threaded `"cp313t"` or PyPy `"pp310"`).
 ///
 /// Compatibility rules (PEP 425):
 /// - `py2`, `py3`, or `py2.py3` python tags: compatible with any Python 3.
 /// - Wheels with `abi3` ABI tag: stable ABI, compatible with any CPython >= the version
 ///   encoded in the python tag. The python tag may be a compressed set (e.g., `cp37.cp38`);
-///   the minimum version is taken as the oldest component.
-/// - CPython-specific wheels (e.g., `cp311` or compressed `cp310.cp311`): compatible if
-///   the active CPython tag matches any component in the set.
+///   the minimum version is taken as the oldest component. Free-threaded CPython and PyPy
+///   cannot load them.
+/// - Interpreter-specific wheels (e.g., `cp311` or compressed `cp310.cp311`): compatible if
+///   the active python tag matches any component in the set and the ABI is the
+///   interpreter's own (`cp313t` for free-threaded CPython, `pypy310_pp73` for PyPy).
 /// - `None` python tag (unparseable filename): treated as compatible to avoid breaking
-///   older index formats. **Known limitation**: an unparseable tag scores the same as
-///   an `abi3` wheel, which may allow a malformed wheel to supersede a pure-Python one.
+///   older index formats.
 pub fn is_wheel_python_compatible(
     python_tag: Option<&str>,
     abi_tag: Option<&str>,
@@ -489,14 +515,28 @@ pub fn is_wheel_python_compatible(
         return true;
     }
 
+    let Some(interpreter) = crate::tags::Interpreter::parse(active_cp_tag) else {
+        return components.contains(&active_cp_tag);
+    };
+    let active_python = interpreter.python_tag();
+
     // Stable ABI (abi3): compatible if active CPython >= the *oldest* version in the set
     if abi_tag == Some("abi3") {
-        return components.iter().all(|c| c.starts_with("cp"))
-            && components.iter().any(|c| cp_tag_ge(active_cp_tag, c));
-    }
-
-    // CPython-specific: compatible if active_cp_tag matches any component
-    components.contains(&active_cp_tag)
+        return interpreter.supports_abi3()
+            && components.iter().all(|c| c.starts_with("cp"))
+            && components.iter().any(|c| cp_tag_ge(&active_python, c));
+    }
+
+    // Interpreter-specific: the python tag must match, and the ABI must not be
+    // another flavor's (a GIL build cannot load `cp313t` extensions and vice versa).
+    let own_abi = interpreter.abi_tag();
+    components.contains(&active_python.as_str())
+        && abi_tag.is_none_or(|abi| {
+            abi.split('.').any(|abi| match interpreter.flavor {
+                crate::tags::Flavor::CPython => !abi.ends_with('t'),
+                _ => abi == own_abi || abi == "none",
+            })
+        })
 }
 
 /// Return the CPython tag for the active Python interpreter, e.g. `"cp311"`.
@@ -523,8 +563,11 @@ fn active_python_cp_tag() -> &'static str {
 
 /// Select the best artifact for a given Python version — exposed for testing.
 ///
-/// This is the canonical selection logic; [`select_artifact_for_platform`] is a
-/// thin wrapper that supplies the auto-detected CP tag.
+/// Wheels are ranked by the PEP 425 preference order of [`crate::tags`]: the
+/// best-ranked supported tag of each wheel wins, and the sdist is used when no
+/// wheel is installable. This is the canonical selection logic;
+/// [`select_artifact_for_platform`] is a thin wrapper that supplies the
+/// auto-detected CP tag.
 pub fn select_artifact_for_platform_with_cp(
     pkg: &ResolvedPackage,
     platform_tags: &[String],
@@ -535,46 +578,59 @@ pub fn select_artifact_for_platform_with_cp(
         tags.push("any".into());
     }
 
-    if !pkg.artifacts.wheels.is_empty() {
-        let mut scored_wheels: Vec<_> = pkg
-            .artifacts
-            .wheels
-            .iter()
-            .filter_map(|w| {
-                let matches_platform = w.platforms.is_empty()
-                    || tags.iter().any(|t| w.platforms.iter().any(|p| p == t));
-                let matches_python = is_wheel_python_compatible(
-                    w.python_tag.as_deref(),
-                    w.abi_tag.as_deref(),
-                    active_cp_tag,
-                );
-                if matches_platform && matches_python {
-                    Some((rank_wheel(w, &tags, active_cp_tag), w))
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        scored_wheels.sort_by_key(|w| std::cmp::Reverse(w.0));
-
-        if let Some((_, wheel)) = scored_wheels.first() {
-            let matched_platform = if wheel.platforms.is_empty() {
-                None
+    let priority = crate::tags::TagPriority::new(active_cp_tag, &tags);
+    let active_python = crate::tags::Interpreter::parse(active_cp_tag)
+        .map_or_else(|| active_cp_tag.to_string(), |i| i.python_tag());
+    let any = ["any".to_string()];
+    let best = pkg
+        .artifacts
+        .wheels
+        .iter()
+        .filter_map(|w| {
+            // Wheels without a parseable filename are ranked as pure Python.
+            let mut python = w.python_tag.as_deref().unwrap_or("py3");
+            let mut abi = w.abi_tag.as_deref().unwrap_or("none");
+            let platforms = if w.platforms.is_empty() {
+                &any[..]
             } else {
-                tags.iter()
-                    .find(|t| wheel.platforms.iter().any(|p| p == *t))
-                    .cloned()
-            };
-            return ArtifactSelection {
-                filename: wheel.file.clone(),
-                url: wheel.url.clone(),
-                hash: wheel.hash.clone(),
-                matched_platform,
-                from_source: false,
-                available_wheels: pkg.artifacts.wheels.len(),
+                &w.platforms[..]
             };
-        }
+            // JSON indexes list ABI-specific wheels as `any` (`cp312-cp312-any`),
+            // which PEP 425 has no tag for: rank them as `cp312-none-any` when
+            // the interpreter can load them.
+            if abi != "none" && platforms.iter().all(|p| p == "any") {
+                if !is_wheel_python_compatible(Some(python), Some(abi), active_cp_tag) {
+                    return None;
+                }
+                python = &active_python;
+                abi = "none";
+            }
+            priority.rank(python, abi, platforms).map(|rank| (rank, w))
+        })
+        .min_by_key(|(rank, _)| *rank);
+
+    if let Some((_, wheel)) = best {
+        let matched_platform = if wheel.platforms.is_empty() {
+            None
+        } else {
+            tags.iter()
+                .find(|t| {
+                    wheel
+                        .platforms
+                        .iter()
+                        .flat_map(|p| p.split('.'))
+                        .any(|p| p == t.as_str())
+                })
+                .cloned()
+        };
+        return ArtifactSelection {
+            filename: wheel.file.clone(),
+            url: wheel.url.clone(),
+            hash: wheel.hash.clone(),
+            matched_platform,
+            from_source: false,
+            available_wheels: pkg.artifacts.wheels.len(),
+        };
     }
 
     // Fallback: sdist
@@ -584,8 +640,8 @@ pub fn select_artifact_for_platform_with_cp(
             .sdist
             .clone()
             .unwrap_or_else(|| format!("{}-{}.tar.gz", pkg.name, pkg.version)),
-        url: None,
-        hash: None,
+        url: pkg.artifacts.sdist_url.clone(),
+        hash: pkg.artifacts.sdist_hash.clone(),
         matched_platform: None,
         from_source: true,
         available_wheels: pkg.artifacts.wheels.len(),
@@ -708,7 +764,7 @@ fn spec_version(spec: &VersionSpec) -> Option<&str> {
 
 /// PEP 440: a specifier that itself mentions a pre-release version opts the
 /// package into pre-release candidates (e.g. `pkg>=2.0rc1`).
-fn constraints_mention_prerelease(reqs: &[Requirement]) -> bool {
+pub(crate) fn constraints_mention_prerelease(reqs: &[Requirement]) -> bool {
     reqs.iter().any(|r| {
         r.specs
             .iter()
@@ -735,42 +791,6 @@ pub fn current_platform_tags() -> Vec<String> {
     tags
 }
 
-/// Rank a wheel for selection priority.
-/// Higher score = better preference.
-/// Prefers: native platform + exact Python version > abi3 > pure-Python > any-platform.
-fn rank_wheel(wheel: &Wheel, platform_tags: &[String], active_cp_tag: &str) -> u32 {
-    let mut score: u32 = 100; // base score for being a wheel (vs sdist)
-
-    // Platform scoring
-    if wheel.platforms.is_empty() {
-        score += 10; // universal wheel
-    } else {
-        for (priority, tag) in platform_tags.iter().enumerate() {
-            if wheel.platforms.iter().any(|p| p == tag) {
-                score += 50 - (priority as u32).min(40);
-                break;
-            }
-        }
-    }
-
-    // Python version scoring
-    match (wheel.python_tag.as_deref(), wheel.abi_tag.as_deref()) {
-        (Some(ptag), _) if ptag == active_cp_tag => score += 30, // exact match
-        (_, Some("abi3")) => score += 15,                        // stable ABI
-        (Some(ptag), _) if ptag.starts_with("py") => score += 10, // pure Python
-        (None, _) => score += 15, // unknown tag — treat like abi3 for legacy-index compat
-        // Non-CPython interpreters (e.g., PyPy "pp311") that passed compatibility
-        // checking have no specific bonus; they rank below abi3 and pure-Python.
-        _ => {}
-    }
-
-    if wheel.platforms.len() <= 1 {
-        score += 5;
-    }
-
-    score
-}
-
 /// Select the best artifact for the current platform.
 ///
 /// Delegates to [`select_artifact_for_platform_with_cp`] using the auto-detected
@@ -810,6 +830,14 @@ pub enum ResolveError {
         .0.name, .0.constraint, .0.python_version, .0.rejected_version, .0.rejected_requires_python
     )]
     PythonIncompatible(Box<PythonIncompatibility>),
+    /// A non-default resolver strategy (`--resolver`) failed for a reason
+    /// other than an unsatisfiable graph, e.g. `uv` is not installed.
+    #[error("{resolver} resolver failed: {message}")]
+    Backend { resolver: String, message: String },
+    /// The execution policy (`pybun-policy.toml`) blocks the package; the
+    /// violation is recorded for an `E_POLICY_VIOLATION` diagnostic.
+    #[error("{0}")]
+    Policy(String),
 }
 
 /// Details of a `requires-python` resolution failure (Issue #342). Boxed in
@@ -1133,7 +1161,7 @@ pub async fn resolve_with_options(
     })
 }
 
-fn select_with_constraints(
+pub(crate) fn select_with_constraints(
     reqs: &BTreeMap<String, Vec<Requirement>>,
     name: &str,
     candidates: &[ResolvedPackage],
@@ -2180,6 +2208,26 @@ mod tests {
     fn test_parse_requirement_without_extras_has_empty_vec() {
         let req = Requirement::from_str("requests>=2.28.0").unwrap();
         assert!(req.extras.is_empty());
+        assert_eq!(req.url, None);
+    }
+
+    #[test]
+    fn test_parse_requirement_with_direct_reference() {
+        let req =
+            Requirement::from_str("mypkg[cli] @ git+https://github.com/org/repo@v1.2.3").unwrap();
+
+        assert_eq!(req.name, "mypkg");
+        assert_eq!(req.extras, vec!["cli".to_string()]);
+        assert_eq!(req.specs, vec![VersionSpec::Any]);
+        assert_eq!(
+            req.url.as_deref(),
+            Some("git+https://github.com/org/repo@v1.2.3")
+        );
+        assert_eq!(
+            req.to_string(),
+            "mypkg @ git+https://github.com/org/repo@v1.2.3"
+        );
+        assert!(Requirement::from_str("mypkg @ ").is_err());
     }
 
     // ====================================================================
@@ -2704,6 +2752,41 @@ mod tests {
         assert!(is_wheel_python_compatible(None, None, "cp311"));
     }
 
+    #[test]
+    fn is_wheel_python_compatible_respects_interpreter_flavor() {
+        assert!(is_wheel_python_compatible(
+            Some("cp313"),
+            Some("cp313t"),
+            "cp313t"
+        ));
+        assert!(!is_wheel_python_compatible(
+            Some("cp313"),
+            Some("cp313"),
+            "cp313t"
+        ));
+        assert!(!is_wheel_python_compatible(
+            Some("cp313"),
+            Some("cp313t"),
+            "cp313"
+        ));
+        assert!(!is_wheel_python_compatible(
+            Some("cp39"),
+            Some("abi3"),
+            "cp313t"
+        ));
+        assert!(is_wheel_python_compatible(
+            Some("pp310"),
+            Some("pypy310_pp73"),
+            "pp310"
+        ));
+        assert!(!is_wheel_python_compatible(
+            Some("cp310"),
+            Some("cp310"),
+            "pp310"
+        ));
+        assert_eq!(cp_tag_to_dotted_version("pp310").as_deref(), Some("3.10"));
+    }
+
     #[test]
     fn select_artifact_prefers_cp311_wheel_over_cp310_on_python_311() {
         let pkg = ResolvedPackage {
@@ -2731,7 +2814,7 @@ mod tests {
                         abi_tag: Some("cp311".to_string()),
                     },
                 ],
-                sdist: None,
+                ..Default::default()
             },
         };
         let platform_tags = vec!["macosx_14_0_arm64".to_string(), "any".to_string()];
@@ -2769,7 +2852,7 @@ mod tests {
                         abi_tag: Some("cp311".to_string()),
                     },
                 ],
-                sdist: None,
+                ..Default::default()
             },
         };
         let platform_tags = vec!["macosx_14_0_arm64".to_string(), "any".to_string()];
@@ -2799,6 +2882,8 @@ mod tests {
                     abi_tag: Some("cp310".to_string()),
                 }],
                 sdist: Some("pyarrow-14.0.0.tar.gz".to_string()),
+                sdist_url: Some("https://files.example/pyarrow-14.0.0.tar.gz".to_string()),
+                sdist_hash: Some("sha256:abc".to_string()),
             },
         };
         let platform_tags = vec!["macosx_14_0_arm64".to_string(), "any".to_string()];
@@ -2807,6 +2892,12 @@ mod tests {
             selection.from_source,
             "should fall back to sdist when no compatible wheel is available"
         );
+        assert_eq!(selection.filename, "pyarrow-14.0.0.tar.gz");
+        assert_eq!(
+            selection.url.as_deref(),
+            Some("https://files.example/pyarrow-14.0.0.tar.gz")
+        );
+        assert_eq!(selection.hash.as_deref(), Some("sha256:abc"));
     }
 
     #[test]
@@ -2837,7 +2928,7 @@ mod tests {
                         abi_tag: Some("cp311".to_string()),
                     },
                 ],
-                sdist: None,
+                ..Default::default()
             },
         };
         let platform_tags = vec!["macosx_14_0_arm64".to_string(), "any".to_string()];
@@ -2872,7 +2963,7 @@ mod tests {
                     python_tag: Some("py3".to_string()),
                     abi_tag: Some("none".to_string()),
                 }],
-                sdist: None,
+                ..Default::default()
             },
         };
         let platform_tags = vec!["macosx_14_0_arm64".to_string(), "any".to_string()];
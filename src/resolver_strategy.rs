@@ -0,0 +1,692 @@
+//! Selectable resolver strategies (`--resolver`).
+//!
+//! The resolver is exposed through the [`Resolver`] trait so alternative
+//! implementations can be compared on real projects before any of them
+//! replaces the default:
+//!
+//! - `default`: the single-pass, parallel-fetch resolver in
+//!   [`crate::resolver::resolve_with_options`]. Newest matching version wins
+//!   and a later conflicting constraint is an error.
+//! - `pubgrub`: a backtracking solver in the spirit of PubGrub. It decides
+//!   one package at a time (newest version first) and, on a conflict, backs
+//!   out to the most recent decision that still has untried versions. Graphs
+//!   that the default resolver reports as conflicts can still resolve.
+//! - `external:uv`: hands the root requirements to `uv pip compile` and
+//!   reads back the pins; metadata for the pinned versions still comes from
+//!   PyBun's index so the lockfile is built the same way.
+//!
+//! `pybun lock --resolver <kind> --compare` runs the selected strategy and a
+//! baseline and reports per-package differences plus timings.
+
+use crate::resolver::{
+    PackageIndex, PrereleaseFallback, Requirement, Resolution, ResolveError, ResolveOptions,
+    ResolvedPackage, compare_versions, constraints_mention_prerelease, is_prerelease,
+    requires_python_allows, resolve_with_options, select_with_constraints,
+};
+use crate::tool_exec::ToolRun;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Upper bound on `pubgrub` decisions before giving up, so pathological
+/// graphs fail with a clear error instead of backtracking indefinitely.
+pub const DEFAULT_MAX_DECISIONS: usize = 10_000;
+
+/// Resolver implementation selected with `--resolver`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, Serialize)]
+pub enum ResolverKind {
+    #[default]
+    #[serde(rename = "default")]
+    Default,
+    #[serde(rename = "pubgrub")]
+    Pubgrub,
+    #[value(name = "external:uv")]
+    #[serde(rename = "external:uv")]
+    ExternalUv,
+}
+
+impl ResolverKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResolverKind::Default => "default",
+            ResolverKind::Pubgrub => "pubgrub",
+            ResolverKind::ExternalUv => "external:uv",
+        }
+    }
+
+    /// The strategy `--compare` measures this one against: `default`, or
+    /// `pubgrub` when `default` itself is selected.
+    pub fn compare_baseline(&self) -> ResolverKind {
+        match self {
+            ResolverKind::Default => ResolverKind::Pubgrub,
+            _ => ResolverKind::Default,
+        }
+    }
+
+    /// Resolve with this strategy. `index_url` is the PyPI-compatible index
+    /// handed to external resolvers (`None` for a local JSON index).
+    pub async fn resolve<I: PackageIndex + Sync>(
+        &self,
+        requirements: Vec<Requirement>,
+        index: &I,
+        options: ResolveOptions,
+        index_url: Option<&str>,
+    ) -> Result<Resolution, ResolveError> {
+        match self {
+            ResolverKind::Default => DefaultResolver.resolve(requirements, index, options).await,
+            ResolverKind::Pubgrub => {
+                PubgrubResolver::default()
+                    .resolve(requirements, index, options)
+                    .await
+            }
+            ResolverKind::ExternalUv => {
+                UvResolver {
+                    index_url: index_url.map(str::to_string),
+                }
+                .resolve(requirements, index, options)
+                .await
+            }
+        }
+    }
+
+    /// [`Self::resolve`], timed.
+    pub async fn run<I: PackageIndex + Sync>(
+        &self,
+        requirements: Vec<Requirement>,
+        index: &I,
+        options: ResolveOptions,
+        index_url: Option<&str>,
+    ) -> StrategyRun {
+        let start = Instant::now();
+        let result = self.resolve(requirements, index, options, index_url).await;
+        StrategyRun {
+            resolver: *self,
+            elapsed: start.elapsed(),
+            result,
+        }
+    }
+}
+
+impl std::fmt::Display for ResolverKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A dependency resolver strategy.
+pub trait Resolver {
+    fn name(&self) -> &'static str;
+    fn resolve<I: PackageIndex + Sync>(
+        &self,
+        requirements: Vec<Requirement>,
+        index: &I,
+        options: ResolveOptions,
+    ) -> impl Future<Output = Result<Resolution, ResolveError>>;
+}
+
+/// The built-in single-pass resolver.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultResolver;
+
+impl Resolver for DefaultResolver {
+    fn name(&self) -> &'static str {
+        "default"
+    }
+
+    fn resolve<I: PackageIndex + Sync>(
+        &self,
+        requirements: Vec<Requirement>,
+        index: &I,
+        options: ResolveOptions,
+    ) -> impl Future<Output = Result<Resolution, ResolveError>> {
+        resolve_with_options(requirements, index, options)
+    }
+}
+
+/// Backtracking resolver (see the module docs).
+#[derive(Debug, Clone, Copy)]
+pub struct PubgrubResolver {
+    pub max_decisions: usize,
+}
+
+impl Default for PubgrubResolver {
+    fn default() -> Self {
+        Self {
+            max_decisions: DEFAULT_MAX_DECISIONS,
+        }
+    }
+}
+
+/// Requirements collected for one package, with who asked for each.
+type Constraints = BTreeMap<String, Vec<(Requirement, Option<String>)>>;
+
+/// One decision point: the package being decided, the versions not tried
+/// yet, and the state from before the decision (restored on backtrack).
+struct Frame {
+    name: String,
+    remaining: VecDeque<ResolvedPackage>,
+    assigned: BTreeMap<String, ResolvedPackage>,
+    constraints: Constraints,
+}
+
+impl Resolver for PubgrubResolver {
+    fn name(&self) -> &'static str {
+        "pubgrub"
+    }
+
+    fn resolve<I: PackageIndex + Sync>(
+        &self,
+        requirements: Vec<Requirement>,
+        index: &I,
+        options: ResolveOptions,
+    ) -> impl Future<Output = Result<Resolution, ResolveError>> {
+        let max_decisions = self.max_decisions;
+        async move {
+            let mut versions: BTreeMap<String, Vec<ResolvedPackage>> = BTreeMap::new();
+            let mut assigned: BTreeMap<String, ResolvedPackage> = BTreeMap::new();
+            let mut constraints: Constraints = BTreeMap::new();
+            for req in requirements.into_iter().filter(|r| r.marker_applies()) {
+                constraints
+                    .entry(req.name.clone())
+                    .or_default()
+                    .push((req, None));
+            }
+            let mut stack: Vec<Frame> = Vec::new();
+            let mut last_error: Option<ResolveError> = None;
+            let mut decisions = 0usize;
+
+            // Open a decision for the first undecided package, if any.
+            while let Some(name) = constraints
+                .keys()
+                .find(|name| !assigned.contains_key(*name))
+                .cloned()
+            {
+                if !versions.contains_key(&name) {
+                    versions.insert(name.clone(), index.all(&name).await?);
+                }
+                let reqs = &constraints[&name];
+                let remaining = candidates(&versions[&name], reqs, &options);
+                if remaining.is_empty() {
+                    last_error = Some(no_candidate_error(&name, reqs, &versions[&name], &options));
+                }
+                stack.push(Frame {
+                    name,
+                    remaining,
+                    assigned: assigned.clone(),
+                    constraints: constraints.clone(),
+                });
+
+                // Try versions, backtracking until one applies cleanly.
+                loop {
+                    let Some(frame) = stack.last_mut() else {
+                        return Err(last_error.unwrap_or_else(|| ResolveError::Backend {
+                            resolver: "pubgrub".to_string(),
+                            message: "no solution".to_string(),
+                        }));
+                    };
+                    let Some(candidate) = frame.remaining.pop_front() else {
+                        stack.pop();
+                        continue;
+                    };
+                    assigned = frame.assigned.clone();
+                    constraints = frame.constraints.clone();
+                    let name = frame.name.clone();
+
+                    decisions += 1;
+                    if decisions > max_decisions {
+                        return Err(ResolveError::Backend {
+                            resolver: "pubgrub".to_string(),
+                            message: format!("gave up after {max_decisions} decisions"),
+                        });
+                    }
+
+                    let pkg = index
+                        .get(&candidate.name, &candidate.version)
+                        .await?
+                        .unwrap_or(candidate);
+                    match apply(&name, &pkg, &assigned, &mut constraints) {
+                        Ok(()) => {
+                            assigned.insert(name, pkg);
+                            break;
+                        }
+                        Err(conflict) => last_error = Some(conflict),
+                    }
+                }
+            }
+
+            let prerelease_fallbacks = if options.allow_prerelease {
+                Vec::new()
+            } else {
+                assigned
+                    .iter()
+                    .filter(|(name, pkg)| {
+                        is_prerelease(&pkg.version)
+                            && !constraints_mention_prerelease(&requirements_for(
+                                &constraints,
+                                name,
+                            ))
+                    })
+                    .map(|(name, pkg)| PrereleaseFallback {
+                        name: name.clone(),
+                        version: pkg.version.clone(),
+                    })
+                    .collect()
+            };
+            Ok(Resolution {
+                packages: assigned,
+                prerelease_fallbacks,
+            })
+        }
+    }
+}
+
+fn requirements_for(constraints: &Constraints, name: &str) -> Vec<Requirement> {
+    constraints
+        .get(name)
+        .map(|reqs| reqs.iter().map(|(req, _)| req.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Versions of one package allowed by `reqs`, in trial order: newest first,
+/// stable releases before pre-releases unless pre-releases are opted into.
+fn candidates(
+    versions: &[ResolvedPackage],
+    reqs: &[(Requirement, Option<String>)],
+    options: &ResolveOptions,
+) -> VecDeque<ResolvedPackage> {
+    let plain: Vec<Requirement> = reqs.iter().map(|(req, _)| req.clone()).collect();
+    let mut matching: Vec<&ResolvedPackage> = versions
+        .iter()
+        .filter(|pkg| plain.iter().all(|r| r.is_satisfied_by(&pkg.version)))
+        .filter(|pkg| {
+            options.python_version.as_deref().is_none_or(|py| {
+                pkg.requires_python
+                    .as_deref()
+                    .is_none_or(|spec| requires_python_allows(spec, py))
+            })
+        })
+        .collect();
+    matching.sort_by(|a, b| compare_versions(&b.version, &a.version));
+    if !(options.allow_prerelease || constraints_mention_prerelease(&plain)) {
+        // Stable first; pre-releases stay as the last resort.
+        matching.sort_by_key(|pkg| is_prerelease(&pkg.version));
+    }
+    matching.into_iter().cloned().collect()
+}
+
+/// The error the default resolver would report for a package with no
+/// acceptable version.
+fn no_candidate_error(
+    name: &str,
+    reqs: &[(Requirement, Option<String>)],
+    versions: &[ResolvedPackage],
+    options: &ResolveOptions,
+) -> ResolveError {
+    let mut by_name = BTreeMap::new();
+    by_name.insert(
+        name.to_string(),
+        reqs.iter().map(|(req, _)| req.clone()).collect(),
+    );
+    let requested_by = reqs.iter().find_map(|(_, by)| by.clone());
+    select_with_constraints(
+        &by_name,
+        name,
+        versions,
+        requested_by.as_deref(),
+        options.allow_prerelease,
+        options.python_version.as_deref(),
+    )
+    .err()
+    .unwrap_or_else(|| ResolveError::Missing {
+        name: name.to_string(),
+        constraint: "*".to_string(),
+        requested_by,
+        available_versions: Vec::new(),
+    })
+}
+
+/// Record `pkg`'s dependencies as constraints, failing if one of them rules
+/// out a version that is already decided.
+fn apply(
+    name: &str,
+    pkg: &ResolvedPackage,
+    assigned: &BTreeMap<String, ResolvedPackage>,
+    constraints: &mut Constraints,
+) -> Result<(), ResolveError> {
+    for dep in pkg.dependencies.iter().filter(|d| d.marker_applies()) {
+        if let Some(existing) = assigned.get(&dep.name)
+            && !dep.is_satisfied_by(&existing.version)
+        {
+            let existing_by = constraints
+                .get(&dep.name)
+                .and_then(|reqs| reqs.iter().find_map(|(_, by)| by.clone()));
+            return Err(ResolveError::Conflict {
+                name: dep.name.clone(),
+                existing: existing.version.clone(),
+                requested: dep.constraint_display(),
+                existing_chain: existing_by.into_iter().chain([dep.name.clone()]).collect(),
+                requested_chain: vec![name.to_string(), dep.name.clone()],
+            });
+        }
+        constraints
+            .entry(dep.name.clone())
+            .or_default()
+            .push((dep.clone(), Some(name.to_string())));
+    }
+    Ok(())
+}
+
+/// Delegates to `uv pip compile`.
+#[derive(Debug, Clone, Default)]
+pub struct UvResolver {
+    /// PyPI-compatible index URL; `None` means the index is a local JSON
+    /// file that uv cannot read.
+    pub index_url: Option<String>,
+}
+
+impl Resolver for UvResolver {
+    fn name(&self) -> &'static str {
+        "external:uv"
+    }
+
+    fn resolve<I: PackageIndex + Sync>(
+        &self,
+        requirements: Vec<Requirement>,
+        index: &I,
+        options: ResolveOptions,
+    ) -> impl Future<Output = Result<Resolution, ResolveError>> {
+        let pins = self.compile(&requirements, &options);
+        async move {
+            let mut packages = BTreeMap::new();
+            for (name, version) in pins? {
+                let pkg =
+                    index
+                        .get(&name, &version)
+                        .await?
+                        .ok_or_else(|| ResolveError::Missing {
+                            name: name.clone(),
+                            constraint: format!("=={version}"),
+                            requested_by: Some("uv".to_string()),
+                            available_versions: Vec::new(),
+                        })?;
+                packages.insert(pkg.name.clone(), pkg);
+            }
+            Ok(Resolution {
+                packages,
+                prerelease_fallbacks: Vec::new(),
+            })
+        }
+    }
+}
+
+impl UvResolver {
+    fn backend_error(message: impl Into<String>) -> ResolveError {
+        ResolveError::Backend {
+            resolver: "external:uv".to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Run `uv pip compile` and return the `(name, version)` pins.
+    fn compile(
+        &self,
+        requirements: &[Requirement],
+        options: &ResolveOptions,
+    ) -> Result<Vec<(String, String)>, ResolveError> {
+        let index_url = self.index_url.as_deref().ok_or_else(|| {
+            Self::backend_error("a PyPI-compatible index is required (not a local --index file)")
+        })?;
+        let uv = crate::env::find_uv_executable()
+            .ok_or_else(|| Self::backend_error("uv was not found on PATH"))?;
+
+        let mut cmd = Command::new(uv);
+        cmd.args([
+            "pip",
+            "compile",
+            "-",
+            "--quiet",
+            "--no-header",
+            "--no-annotate",
+        ])
+        .args(["--index-url", &simple_index_url(index_url)]);
+        if let Some(python) = options.python_version.as_deref() {
+            cmd.args(["--python-version", python]);
+        }
+        if options.allow_prerelease {
+            cmd.args(["--prerelease", "allow"]);
+        }
+        let input: Vec<String> = requirements.iter().map(ToString::to_string).collect();
+        let output = ToolRun::new("uv pip compile", &mut cmd)
+            .idempotent()
+            .with_input(input.join("\n"))
+            .run()
+            .map_err(|e| Self::backend_error(e.to_string()))?;
+        Ok(parse_pins(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// uv wants the PEP 503 simple API; PyBun's base URL may point at the JSON
+/// API root (`https://pypi.org/pypi`).
+fn simple_index_url(index_url: &str) -> String {
+    let trimmed = index_url.trim_end_matches('/');
+    match trimmed.strip_suffix("/pypi") {
+        Some(base) => format!("{base}/simple"),
+        None => trimmed.to_string(),
+    }
+}
+
+/// Parse `name==version` lines from `pip compile` output.
+fn parse_pins(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .map(|line| line.split(';').next().unwrap_or_default())
+        .map(|line| line.trim().trim_end_matches('\\').trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('-'))
+        .filter_map(|line| line.split_once("=="))
+        .map(|(name, version)| {
+            let name = name.split('[').next().unwrap_or(name);
+            (name.trim().to_string(), version.trim().to_string())
+        })
+        .collect()
+}
+
+/// One timed strategy run.
+#[derive(Debug)]
+pub struct StrategyRun {
+    pub resolver: ResolverKind,
+    pub elapsed: Duration,
+    pub result: Result<Resolution, ResolveError>,
+}
+
+impl StrategyRun {
+    fn summary_json(&self) -> Value {
+        let mut value = json!({
+            "resolver": self.resolver,
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+            "ok": self.result.is_ok(),
+        });
+        match &self.result {
+            Ok(resolution) => value["packages"] = json!(resolution.packages.len()),
+            Err(e) => value["error"] = json!(e.to_string()),
+        }
+        value
+    }
+}
+
+/// A package whose resolution differs between two strategies. `None` means
+/// the package is absent from that side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolutionChange {
+    pub name: String,
+    pub baseline: Option<String>,
+    pub candidate: Option<String>,
+}
+
+/// Per-package differences between two resolutions, sorted by name.
+pub fn diff_resolutions(baseline: &Resolution, candidate: &Resolution) -> Vec<ResolutionChange> {
+    let mut names: Vec<&String> = baseline
+        .packages
+        .keys()
+        .chain(candidate.packages.keys())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let left = baseline.packages.get(name).map(|p| p.version.clone());
+            let right = candidate.packages.get(name).map(|p| p.version.clone());
+            (left != right).then(|| ResolutionChange {
+                name: name.clone(),
+                baseline: left,
+                candidate: right,
+            })
+        })
+        .collect()
+}
+
+/// `detail.comparison` for `pybun lock --compare`.
+pub fn comparison_json(baseline: &StrategyRun, candidate: &StrategyRun) -> Value {
+    let diff = match (&baseline.result, &candidate.result) {
+        (Ok(left), Ok(right)) => Some(diff_resolutions(left, right)),
+        _ => None,
+    };
+    json!({
+        "baseline": baseline.summary_json(),
+        "candidate": candidate.summary_json(),
+        "identical": diff.as_ref().map(Vec::is_empty),
+        "diff": diff,
+        "speedup": (candidate.elapsed.as_secs_f64() > 0.0)
+            .then(|| baseline.elapsed.as_secs_f64() / candidate.elapsed.as_secs_f64()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::InMemoryIndex;
+
+    fn index() -> InMemoryIndex {
+        // `a 2.0` needs `c==2.0` but `b` only works with `c==1.0`. The
+        // default resolver commits to `a 2.0` and reports a conflict on `c`;
+        // backtracking falls back to `a 1.0`.
+        let mut index = InMemoryIndex::default();
+        index.add("a", "1.0.0", ["c==1.0.0"]);
+        index.add("a", "2.0.0", ["c==2.0.0"]);
+        index.add("b", "1.0.0", ["c==1.0.0"]);
+        index.add("c", "1.0.0", Vec::<&str>::new());
+        index.add("c", "2.0.0", Vec::<&str>::new());
+        index
+    }
+
+    #[tokio::test]
+    async fn pubgrub_backtracks_out_of_a_conflict_default_reports() {
+        let reqs = vec![Requirement::any("a"), Requirement::any("b")];
+        let index = index();
+
+        let default = ResolverKind::Default
+            .resolve(reqs.clone(), &index, ResolveOptions::default(), None)
+            .await;
+        assert!(matches!(default, Err(ResolveError::Conflict { .. })));
+
+        let resolution = ResolverKind::Pubgrub
+            .resolve(reqs, &index, ResolveOptions::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(resolution.packages["a"].version, "1.0.0");
+        assert_eq!(resolution.packages["c"].version, "1.0.0");
+    }
+
+    #[tokio::test]
+    async fn pubgrub_matches_default_on_simple_graphs() {
+        let mut index = InMemoryIndex::default();
+        index.add("a", "1.0.0", ["b>=1.0"]);
+        index.add("b", "1.0.0", Vec::<&str>::new());
+        index.add("b", "1.1.0", Vec::<&str>::new());
+        index.add("b", "2.0.0b1", Vec::<&str>::new());
+        let reqs = vec![Requirement::any("a")];
+
+        let default = ResolverKind::Default
+            .run(reqs.clone(), &index, ResolveOptions::default(), None)
+            .await;
+        let pubgrub = ResolverKind::Pubgrub
+            .run(reqs, &index, ResolveOptions::default(), None)
+            .await;
+        let report = comparison_json(&default, &pubgrub);
+        assert_eq!(report["identical"], true, "{report}");
+        assert_eq!(pubgrub.result.unwrap().packages["b"].version, "1.1.0");
+    }
+
+    #[tokio::test]
+    async fn pubgrub_reports_missing_packages_like_default() {
+        let index = InMemoryIndex::default();
+        let err = ResolverKind::Pubgrub
+            .resolve(
+                vec![Requirement::exact("ghost", "1.0")],
+                &index,
+                ResolveOptions::default(),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ResolveError::Missing { ref name, .. } if name == "ghost"));
+    }
+
+    #[test]
+    fn diff_lists_changed_added_and_removed_packages() {
+        let pkg = |name: &str, version: &str| ResolvedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            dependencies: Vec::new(),
+            source: None,
+            artifacts: Default::default(),
+            requires_python: None,
+        };
+        let left = Resolution {
+            packages: [("a", "1.0"), ("b", "1.0")]
+                .map(|(n, v)| (n.to_string(), pkg(n, v)))
+                .into(),
+            prerelease_fallbacks: Vec::new(),
+        };
+        let right = Resolution {
+            packages: [("a", "1.0"), ("b", "2.0"), ("c", "1.0")]
+                .map(|(n, v)| (n.to_string(), pkg(n, v)))
+                .into(),
+            prerelease_fallbacks: Vec::new(),
+        };
+        let diff = diff_resolutions(&left, &right);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].name, "b");
+        assert_eq!(diff[0].candidate.as_deref(), Some("2.0"));
+        assert_eq!(diff[1].baseline, None);
+    }
+
+    #[test]
+    fn parses_pip_compile_output() {
+        let output = "\
+# comment
+requests==2.31.0
+    # via app
+urllib3==2.2.1 ; python_version >= \"3.8\"
+Typing_Extensions[foo]==4.12.2 \\
+    --hash=sha256:abc
+";
+        assert_eq!(
+            parse_pins(output),
+            vec![
+                ("requests".to_string(), "2.31.0".to_string()),
+                ("urllib3".to_string(), "2.2.1".to_string()),
+                ("Typing_Extensions".to_string(), "4.12.2".to_string()),
+            ]
+        );
+        assert_eq!(
+            simple_index_url("https://pypi.org/pypi/"),
+            "https://pypi.org/simple"
+        );
+    }
+}
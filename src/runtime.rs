@@ -1,24 +1,51 @@
 //! CPython runtime management.
 //!
 //! This module handles:
-//! - Embedded version table for supported Python versions
-//! - Download and verification of missing Python versions
+//! - Embedded version table for supported Python versions, including
+//!   free-threaded CPython (`3.13t`) and PyPy (`pypy3.10`)
+//! - Download and verification of missing Python versions (checksums are
+//!   cross-checked against the release manifest, see
+//!   [`crate::runtime_manifest`])
 //! - Data directory layout for installed runtimes
 //! - ABI compatibility checking
 //!
-//! Uses python-build-standalone releases for portable CPython distributions.
+//! Uses python-build-standalone releases for portable CPython distributions
+//! and the official PyPy binaries from downloads.python.org. Each installed
+//! runtime records its implementation and ABI in `runtime.json`.
+//! Builds from a CPython git ref live in [`crate::runtime_source`].
 
 use crate::cache::Cache;
+use crate::network_policy::{self, Operation};
+use crate::runtime_manifest::{self, ReleaseManifest};
+use crate::tags::{Flavor, Interpreter};
 use color_eyre::eyre::{Result, WrapErr, eyre};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Base URL for python-build-standalone releases.
-const PBS_RELEASE_BASE: &str =
+pub(crate) const PBS_RELEASE_BASE: &str =
     "https://github.com/indygreg/python-build-standalone/releases/download";
 
+/// Base URL of the PyPy binary releases.
+pub const PYPY_RELEASE_BASE: &str = "https://downloads.python.org/pypy";
+
+/// Overrides [`PYPY_RELEASE_BASE`].
+pub const PYPY_MIRROR_ENV: &str = "PYBUN_PYPY_MIRROR";
+
+/// Page listing the SHA-256 of every PyPy release file.
+pub const PYPY_CHECKSUMS_URL: &str = "https://www.pypy.org/checksums.html";
+
+/// Overrides [`PYPY_CHECKSUMS_URL`].
+pub const PYPY_CHECKSUMS_ENV: &str = "PYBUN_PYPY_CHECKSUMS";
+
+/// Implementation and ABI of an installed runtime.
+const RUNTIME_INFO_FILE: &str = "runtime.json";
+
 /// Supported Python version information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PythonVersion {
@@ -28,6 +55,152 @@ pub struct PythonVersion {
     pub release_tag: String,
     /// SHA256 checksums for each platform
     pub checksums: HashMap<String, String>,
+    /// CPython release date (YYYY-MM-DD)
+    #[serde(default)]
+    pub release_date: String,
+    /// Interpreter flavor; `version` is the Python language version and,
+    /// for PyPy, `release_tag` the PyPy release (`v7.3.17`).
+    #[serde(default)]
+    pub flavor: Flavor,
+}
+
+impl PythonVersion {
+    /// Runtime name: `3.12.7`, free-threaded `3.13.0t` or PyPy `pypy3.10.14`.
+    pub fn name(&self) -> String {
+        match self.flavor {
+            Flavor::CPython => self.version.clone(),
+            Flavor::FreeThreaded => format!("{}t", self.version),
+            Flavor::PyPy => format!("pypy{}", self.version),
+        }
+    }
+
+    /// Wheel interpreter tag (`cp312`, `cp313t`, `pp310`).
+    pub fn interpreter_tag(&self) -> Option<String> {
+        Interpreter::from_version(self.flavor, &self.version).map(|i| i.tag())
+    }
+}
+
+/// Flavor and release of a runtime name (see [`PythonVersion::name`]).
+/// Source builds keep their local part (`3.12.7+main`).
+pub fn parse_runtime_name(name: &str) -> (Flavor, &str) {
+    if let Some(release) = name.strip_prefix("pypy") {
+        return (Flavor::PyPy, release);
+    }
+    match name.strip_suffix('t') {
+        Some(release) if !release.contains('+') => (Flavor::FreeThreaded, release),
+        _ => (Flavor::CPython, name),
+    }
+}
+
+/// Python language version of a runtime name (`3.13.0t` → `3.13.0`).
+pub fn release_version(name: &str) -> &str {
+    let (_, release) = parse_runtime_name(name);
+    release.split('+').next().unwrap_or(release)
+}
+
+/// Implementation and ABI of an installed runtime, stored as
+/// `<runtime>/runtime.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeInfo {
+    /// `sys.implementation.name` (`cpython`, `pypy`).
+    pub implementation: String,
+    pub flavor: Flavor,
+    /// Python language version.
+    pub python_version: String,
+    /// Release of the implementation itself (PyPy's `v7.3.17`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implementation_version: Option<String>,
+    /// Wheel interpreter tag (`cp313t`, `pp310`).
+    pub interpreter_tag: String,
+    /// Wheel ABI tag (`cp313t`, `pypy310_pp73`).
+    pub abi_tag: String,
+}
+
+impl RuntimeInfo {
+    fn new(
+        flavor: Flavor,
+        python_version: &str,
+        implementation_version: Option<String>,
+    ) -> Option<Self> {
+        let interpreter = Interpreter::from_version(flavor, python_version)?;
+        Some(Self {
+            implementation: flavor.implementation().to_string(),
+            flavor,
+            python_version: python_version.to_string(),
+            implementation_version,
+            interpreter_tag: interpreter.tag(),
+            abi_tag: interpreter.abi_tag(),
+        })
+    }
+}
+
+/// Support window of a CPython minor series (PEP 602).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SeriesLifecycle {
+    /// Minor series (e.g., "3.11")
+    pub series: &'static str,
+    /// Last day of regular bugfix releases
+    pub bugfix_until: &'static str,
+    /// Last day of security fixes
+    pub end_of_life: &'static str,
+}
+
+/// Where a series is in its support window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SupportStatus {
+    Bugfix,
+    Security,
+    EndOfLife,
+}
+
+impl SeriesLifecycle {
+    /// Status on `today` (YYYY-MM-DD).
+    pub fn status(&self, today: &str) -> SupportStatus {
+        if today > self.end_of_life {
+            SupportStatus::EndOfLife
+        } else if today > self.bugfix_until {
+            SupportStatus::Security
+        } else {
+            SupportStatus::Bugfix
+        }
+    }
+}
+
+/// Embedded support windows for CPython series.
+pub const SERIES_LIFECYCLE: &[SeriesLifecycle] = &[
+    SeriesLifecycle {
+        series: "3.9",
+        bugfix_until: "2022-05-17",
+        end_of_life: "2025-10-31",
+    },
+    SeriesLifecycle {
+        series: "3.10",
+        bugfix_until: "2023-04-05",
+        end_of_life: "2026-10-31",
+    },
+    SeriesLifecycle {
+        series: "3.11",
+        bugfix_until: "2024-04-02",
+        end_of_life: "2027-10-31",
+    },
+    SeriesLifecycle {
+        series: "3.12",
+        bugfix_until: "2025-04-08",
+        end_of_life: "2028-10-31",
+    },
+    SeriesLifecycle {
+        series: "3.13",
+        bugfix_until: "2026-10-31",
+        end_of_life: "2029-10-31",
+    },
+];
+
+/// Support window for the series `version` belongs to.
+pub fn series_lifecycle(version: &str) -> Option<&'static SeriesLifecycle> {
+    let mut parts = version.split('.');
+    let series = format!("{}.{}", parts.next()?, parts.next()?);
+    SERIES_LIFECYCLE.iter().find(|l| l.series == series)
 }
 
 /// Platform identifier for runtime downloads.
@@ -81,6 +254,18 @@ impl Platform {
         }
     }
 
+    /// Platform part of PyPy archive names; PyPy publishes no musl builds.
+    pub fn pypy_suffix(&self) -> Option<&'static str> {
+        match self {
+            Platform::MacOSArm64 => Some("macos_arm64.tar.bz2"),
+            Platform::MacOSX64 => Some("macos_x86_64.tar.bz2"),
+            Platform::LinuxX64Gnu => Some("linux64.tar.bz2"),
+            Platform::LinuxArm64Gnu => Some("aarch64.tar.bz2"),
+            Platform::LinuxX64Musl => None,
+            Platform::WindowsX64 => Some("win64.zip"),
+        }
+    }
+
     /// Get the target triple used for PyBun release artifacts.
     pub fn release_target(&self) -> &'static str {
         match self {
@@ -207,87 +392,13 @@ pub fn pep425_macos_x86_64_tags(cur_major: u32, cur_minor: u32) -> Vec<String> {
     tags
 }
 
-/// Generate PEP 600 manylinux wheel tags for Linux x86_64.
-///
-/// Covers glibc versions from 2.35 down to 2.17 (manylinux2014 minimum), plus
-/// legacy compatibility aliases. Floored at 2.17 because pip >= 22.0 dropped
-/// install support for manylinux1 (glibc < 2.17) targets.
-pub fn manylinux_tags_x86_64() -> Vec<String> {
-    let mut tags = Vec::new();
-    // PEP 600 numeric tags: descending glibc minor from 35 to 17 (manylinux2014 floor)
-    for minor in (17..=35u32).rev() {
-        tags.push(format!("manylinux_2_{minor}_x86_64"));
-    }
-    // Legacy compatibility aliases
-    tags.push("manylinux2014_x86_64".into());
-    tags.push("manylinux1_x86_64".into());
-    tags.push("linux_x86_64".into());
-    tags
-}
-
-/// Generate PEP 600 manylinux wheel tags for Linux aarch64.
-///
-/// Covers glibc versions from 2.35 down to 2.17 (manylinux2014 minimum), plus
-/// legacy compatibility aliases. Matches the x86_64 floor (pip >= 22.0).
-pub fn manylinux_tags_aarch64() -> Vec<String> {
-    let mut tags = Vec::new();
-    // PEP 600 numeric tags: descending glibc minor from 35 to 17
-    for minor in (17..=35u32).rev() {
-        tags.push(format!("manylinux_2_{minor}_aarch64"));
-    }
-    // Legacy compatibility aliases
-    tags.push("manylinux2014_aarch64".into());
-    tags.push("linux_aarch64".into());
-    tags
-}
-
 /// Wheel tags for the current platform.
 ///
-/// Returns PEP 425/600 standard tags (most specific first) followed by legacy
-/// custom tags for backward compatibility with JSON index fixtures.
+/// Returns PEP 425/600/656 standard tags (most specific first, see
+/// [`crate::tags`]) followed by legacy custom tags for backward compatibility
+/// with JSON index fixtures.
 pub fn current_wheel_tags() -> Vec<String> {
-    let mut tags: Vec<String> = Vec::new();
-
-    // Add PEP 425/600 standard tags first (highest priority)
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-    {
-        let (major, minor) = macos_version();
-        tags.extend(pep425_macos_arm64_tags(major, minor));
-    }
-
-    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
-    {
-        let (major, minor) = macos_version();
-        tags.extend(pep425_macos_x86_64_tags(major, minor));
-    }
-
-    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-    {
-        tags.extend(manylinux_tags_x86_64());
-    }
-
-    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
-    {
-        tags.extend(manylinux_tags_aarch64());
-        // "manylinux_aarch64" is a legacy internal tag included via Platform::wheel_tags() below
-    }
-
-    // Add legacy custom tags (for backward compat with JSON index fixtures)
-    if let Some(platform) = Platform::current() {
-        for tag in platform.wheel_tags() {
-            let s = tag.to_string();
-            if !tags.contains(&s) {
-                tags.push(s);
-            }
-        }
-    }
-
-    // Windows-specific tags (win_amd64 already included via wheel_tags)
-
-    if !tags.iter().any(|t| t == "any") {
-        tags.push("any".into());
-    }
-    tags
+    crate::tags::with_legacy_tags(crate::tags::host_platform_tags(), Platform::current())
 }
 
 impl std::fmt::Display for Platform {
@@ -320,6 +431,8 @@ pub fn supported_versions() -> Vec<PythonVersion> {
         PythonVersion {
             version: "3.12.7".to_string(),
             release_tag: "20241016".to_string(),
+            release_date: "2024-10-01".to_string(),
+            flavor: Flavor::CPython,
             checksums: [
                 (
                     "macos_arm64",
@@ -349,6 +462,8 @@ pub fn supported_versions() -> Vec<PythonVersion> {
         PythonVersion {
             version: "3.11.10".to_string(),
             release_tag: "20241016".to_string(),
+            release_date: "2024-09-07".to_string(),
+            flavor: Flavor::CPython,
             checksums: [
                 (
                     "macos_arm64",
@@ -378,6 +493,8 @@ pub fn supported_versions() -> Vec<PythonVersion> {
         PythonVersion {
             version: "3.10.15".to_string(),
             release_tag: "20241016".to_string(),
+            release_date: "2024-09-07".to_string(),
+            flavor: Flavor::CPython,
             checksums: [
                 (
                     "macos_arm64",
@@ -407,6 +524,8 @@ pub fn supported_versions() -> Vec<PythonVersion> {
         PythonVersion {
             version: "3.9.20".to_string(),
             release_tag: "20241016".to_string(),
+            release_date: "2024-09-06".to_string(),
+            flavor: Flavor::CPython,
             checksums: [
                 (
                     "macos_arm64",
@@ -433,12 +552,48 @@ pub fn supported_versions() -> Vec<PythonVersion> {
             .map(|(k, v)| (k.to_string(), v.to_string()))
             .collect(),
         },
+        // Free-threaded builds ship as `full` archives; their checksums come
+        // from the release manifest.
+        PythonVersion {
+            version: "3.13.0".to_string(),
+            release_tag: "20241016".to_string(),
+            release_date: "2024-10-07".to_string(),
+            flavor: Flavor::FreeThreaded,
+            checksums: HashMap::new(),
+        },
+        // PyPy checksums come from the checksum page of pypy.org.
+        PythonVersion {
+            version: "3.10.14".to_string(),
+            release_tag: "v7.3.17".to_string(),
+            release_date: "2024-08-29".to_string(),
+            flavor: Flavor::PyPy,
+            checksums: HashMap::new(),
+        },
     ]
 }
 
-/// Find a supported version matching the request.
+/// Newest supported version admitted by a `requires-python` specifier.
+/// Only regular CPython is picked implicitly.
+pub fn newest_supported(requires_python: &str) -> Option<PythonVersion> {
+    supported_versions()
+        .into_iter()
+        .filter(|info| info.flavor == Flavor::CPython)
+        .filter(|info| crate::resolver::requires_python_allows(requires_python, &info.version))
+        .max_by(|a, b| version_cmp(&a.version, &b.version))
+}
+
+/// Names a `pybun python install` request can use, for error messages.
+pub const SUPPORTED_SUMMARY: &str = "3.9, 3.10, 3.11, 3.12, 3.13t, pypy3.10";
+
+/// Find a supported version matching the request. `3.13t` asks for a
+/// free-threaded build and `pypy3.10` for PyPy; other requests only match
+/// regular CPython.
 pub fn find_version(requested: &str) -> Option<PythonVersion> {
-    let versions = supported_versions();
+    let (flavor, requested) = parse_runtime_name(requested);
+    let versions: Vec<PythonVersion> = supported_versions()
+        .into_iter()
+        .filter(|v| v.flavor == flavor)
+        .collect();
 
     // Exact match first
     if let Some(v) = versions.iter().find(|v| v.version == requested) {
@@ -458,6 +613,117 @@ pub fn find_version(requested: &str) -> Option<PythonVersion> {
         .cloned()
 }
 
+/// Archive name of `version_info` for the current platform:
+/// python-build-standalone's `install_only` archive, its `full` archive for
+/// free-threaded builds, or the PyPy binary release.
+pub fn archive_name(version_info: &PythonVersion) -> Option<String> {
+    let platform = Platform::current()?;
+    Some(match version_info.flavor {
+        Flavor::CPython => format!(
+            "cpython-{}+{}-{}",
+            version_info.version,
+            version_info.release_tag,
+            platform.archive_suffix()
+        ),
+        Flavor::FreeThreaded => format!(
+            "cpython-{}+{}-{}-freethreaded+{}-full.tar.zst",
+            version_info.version,
+            version_info.release_tag,
+            platform.release_target(),
+            if platform == Platform::WindowsX64 {
+                "pgo"
+            } else {
+                "pgo+lto"
+            }
+        ),
+        Flavor::PyPy => format!(
+            "pypy{}-{}-{}",
+            Interpreter::from_version(Flavor::PyPy, &version_info.version)?.dotted_version(),
+            version_info.release_tag,
+            platform.pypy_suffix()?
+        ),
+    })
+}
+
+/// Progress of a runtime download, reported by
+/// [`RuntimeManager::ensure_version_with_progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallProgress {
+    /// The archive download is starting.
+    Started {
+        version: String,
+        url: String,
+    },
+    /// Bytes received so far; `total` when the server sent a length.
+    Downloading {
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    /// The archive matched its checksum.
+    Verified {
+        sha256: String,
+    },
+    /// Share of the archive unpacked so far.
+    Extracting {
+        percent: u8,
+    },
+    Warning(String),
+    Installed {
+        path: PathBuf,
+    },
+}
+
+/// Prints progress to stderr, as `pybun python install` always has.
+fn stderr_progress() -> impl FnMut(InstallProgress) {
+    let mut last_download = 0;
+    let mut last_extract = None::<u8>;
+    move |progress| match progress {
+        InstallProgress::Started { version, url } => {
+            eprintln!("Downloading Python {}...", version);
+            eprintln!("  URL: {}", url);
+        }
+        InstallProgress::Downloading {
+            downloaded,
+            total: Some(total),
+        } if total > 0 => {
+            let percent = downloaded * 100 / total;
+            if percent >= last_download + 10 {
+                last_download = percent;
+                eprintln!("  Downloading... {}%", percent);
+            }
+        }
+        InstallProgress::Downloading { .. } => {}
+        InstallProgress::Verified { sha256 } => eprintln!("  Verified checksum {}", sha256),
+        InstallProgress::Extracting { percent } => match last_extract {
+            None => {
+                last_extract = Some(0);
+                eprintln!("  Extracting...");
+            }
+            Some(last) if percent >= last.saturating_add(10) => {
+                last_extract = Some(percent);
+                eprintln!("  Extracting... {}%", percent);
+            }
+            Some(_) => {}
+        },
+        InstallProgress::Warning(message) => eprintln!("  warning: {}", message),
+        InstallProgress::Installed { path } => eprintln!("  Installed to {}", path.display()),
+    }
+}
+
+/// Run blocking network I/O on its own thread: reqwest's blocking client
+/// panics when called from inside the tokio runtime (`pybun run`/`x`).
+fn off_runtime<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(f)
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// Bytes between two [`InstallProgress::Downloading`] reports.
+const PROGRESS_STEP_BYTES: u64 = 1 << 20;
+
 /// Compare two version strings.
 fn version_cmp(a: &str, b: &str) -> std::cmp::Ordering {
     let parse = |s: &str| -> Vec<u32> { s.split('.').filter_map(|p| p.parse().ok()).collect() };
@@ -468,6 +734,21 @@ fn version_cmp(a: &str, b: &str) -> std::cmp::Ordering {
 pub struct RuntimeManager {
     cache: Cache,
     offline: bool,
+    /// Base URL of the release downloads.
+    mirror: String,
+    /// GitHub-compatible releases API the manifests come from.
+    releases_api: String,
+    /// Base URL of the PyPy downloads.
+    pypy_mirror: String,
+    /// Checksum listing of the PyPy downloads.
+    pypy_checksums: String,
+}
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
 }
 
 impl RuntimeManager {
@@ -476,6 +757,10 @@ impl RuntimeManager {
         Self {
             cache,
             offline: false,
+            mirror: env_or(runtime_manifest::MIRROR_ENV, PBS_RELEASE_BASE),
+            releases_api: runtime_manifest::releases_api(),
+            pypy_mirror: env_or(PYPY_MIRROR_ENV, PYPY_RELEASE_BASE),
+            pypy_checksums: env_or(PYPY_CHECKSUMS_ENV, PYPY_CHECKSUMS_URL),
         }
     }
 
@@ -485,6 +770,44 @@ impl RuntimeManager {
         self
     }
 
+    /// Download release archives from `base` instead of GitHub.
+    pub fn mirror(mut self, base: impl Into<String>) -> Self {
+        self.mirror = base.into();
+        self
+    }
+
+    /// Read release manifests from another releases API.
+    pub fn releases_api(mut self, api: impl Into<String>) -> Self {
+        self.releases_api = api.into();
+        self
+    }
+
+    /// Download PyPy from `base` and read its checksums from `checksums`.
+    pub fn pypy_mirror(mut self, base: impl Into<String>, checksums: impl Into<String>) -> Self {
+        self.pypy_mirror = base.into();
+        self.pypy_checksums = checksums.into();
+        self
+    }
+
+    /// Archive URL of `version_info` for the current platform.
+    pub fn download_url(&self, version_info: &PythonVersion) -> Option<String> {
+        let archive = archive_name(version_info)?;
+        Some(match version_info.flavor {
+            Flavor::PyPy => format!("{}/{}", self.pypy_mirror.trim_end_matches('/'), archive),
+            _ => format!(
+                "{}/{}/{}",
+                self.mirror.trim_end_matches('/'),
+                version_info.release_tag,
+                archive
+            ),
+        })
+    }
+
+    /// Whether downloads are disabled.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
     /// Get the directory where Python runtimes are stored.
     pub fn runtimes_dir(&self) -> PathBuf {
         self.cache.root().join("python")
@@ -497,12 +820,19 @@ impl RuntimeManager {
 
     /// Get the Python binary path for an installed version.
     pub fn python_binary(&self, version: &str) -> PathBuf {
-        let base = self.version_dir(version);
-        if cfg!(windows) {
-            base.join("python").join("python.exe")
-        } else {
-            base.join("python").join("bin").join("python3")
-        }
+        self.version_dir(version).join(python_relative(version))
+    }
+
+    /// Implementation and ABI of an installed runtime. Runtimes installed
+    /// before `runtime.json` was written are described by their name.
+    pub fn runtime_info(&self, version: &str) -> Option<RuntimeInfo> {
+        fs::read(self.version_dir(version).join(RUNTIME_INFO_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .or_else(|| {
+                let (flavor, _) = parse_runtime_name(version);
+                RuntimeInfo::new(flavor, release_version(version), None)
+            })
     }
 
     /// Check if a version is installed.
@@ -529,20 +859,49 @@ impl RuntimeManager {
             }
         }
 
-        versions.sort_by(|a, b| version_cmp(b, a)); // Descending
+        versions.sort_by(|a, b| version_cmp(release_version(b), release_version(a))); // Descending
         Ok(versions)
     }
 
+    /// The installed runtime a request names: the runtime of that exact
+    /// name, else the newest release of the same flavor in that series
+    /// (`3.12` matches `3.12.7`, `3.13t` matches `3.13.0t`). Source builds
+    /// are only matched by their full name.
+    pub fn find_installed(&self, requested: &str) -> Option<String> {
+        if self.is_installed(requested) {
+            return Some(requested.to_string());
+        }
+        let (flavor, release) = parse_runtime_name(requested);
+        let series = format!("{release}.");
+        self.list_installed().ok()?.into_iter().find(|name| {
+            let (installed_flavor, installed_release) = parse_runtime_name(name);
+            !name.contains('+')
+                && installed_flavor == flavor
+                && (installed_release == release || installed_release.starts_with(&series))
+        })
+    }
+
     /// Ensure a Python version is installed, downloading if necessary.
     pub fn ensure_version(&self, requested: &str) -> Result<PathBuf> {
+        self.ensure_version_with_progress(requested, &mut stderr_progress())
+    }
+
+    /// Like [`Self::ensure_version`], reporting download progress to
+    /// `on_progress` instead of stderr.
+    pub fn ensure_version_with_progress(
+        &self,
+        requested: &str,
+        on_progress: &mut dyn FnMut(InstallProgress),
+    ) -> Result<PathBuf> {
         let version_info = find_version(requested).ok_or_else(|| {
             eyre!(
-                "Python {} is not supported. Supported versions: 3.9, 3.10, 3.11, 3.12",
-                requested
+                "Python {} is not supported. Supported versions: {}",
+                requested,
+                SUPPORTED_SUMMARY
             )
         })?;
 
-        let version = &version_info.version;
+        let version = &version_info.name();
 
         // Check if already installed
         if self.is_installed(version) {
@@ -559,85 +918,453 @@ impl RuntimeManager {
         }
 
         // Download and install
-        self.download_and_install(&version_info)?;
+        self.download_and_install(&version_info, on_progress)?;
 
         Ok(self.python_binary(version))
     }
 
-    /// Download and install a Python version.
-    fn download_and_install(&self, version_info: &PythonVersion) -> Result<()> {
-        let platform = Platform::current().ok_or_else(|| eyre!("Unsupported platform"))?;
-
-        let url = format!(
-            "{}/{}/cpython-{}+{}-{}",
-            PBS_RELEASE_BASE,
-            version_info.release_tag,
-            version_info.version,
-            version_info.release_tag,
-            platform.archive_suffix()
-        );
-
-        let dest_dir = self.version_dir(&version_info.version);
-        fs::create_dir_all(&dest_dir)?;
-
-        let archive_path = dest_dir.join("python.tar.gz");
-
-        eprintln!("Downloading Python {}...", version_info.version);
-        eprintln!("  URL: {}", url);
-
-        // Download the archive
-        download_file(&url, &archive_path)
-            .wrap_err_with(|| format!("Failed to download Python {}", version_info.version))?;
-
-        // Verify checksum (if available)
-        if let Some(expected) = version_info.checksums.get(platform.checksum_key()) {
-            eprintln!("  Verifying checksum...");
-            let actual = compute_sha256(&archive_path)?;
-            if actual != *expected {
-                fs::remove_file(&archive_path)?;
+    /// Pick the runtime for a new environment, installing it if necessary.
+    ///
+    /// `requested` (`--python 3.12`) names a supported version or an
+    /// installed runtime such as a source build (`3.12.7+main`). Without it,
+    /// the newest installed runtime admitted by `requires_python` is used,
+    /// else the newest supported version admitted by it is downloaded.
+    /// Returns the runtime name and its interpreter.
+    pub fn select_runtime(
+        &self,
+        requested: Option<&str>,
+        requires_python: Option<&str>,
+    ) -> Result<(String, PathBuf)> {
+        let admits = |version: &str| {
+            requires_python.is_none_or(|spec| {
+                crate::resolver::requires_python_allows(spec, release_version(version))
+            })
+        };
+
+        if let Some(requested) = requested {
+            let version = if self.is_installed(requested) {
+                requested.to_string()
+            } else {
+                find_version(requested)
+                    .map(|info| info.name())
+                    .unwrap_or_else(|| requested.to_string())
+            };
+            if !admits(&version) {
                 return Err(eyre!(
-                    "Checksum mismatch for Python {} (expected {}, got {})",
-                    version_info.version,
-                    expected,
-                    actual
+                    "Python {} does not satisfy requires-python {}",
+                    version,
+                    requires_python.unwrap_or_default()
                 ));
             }
+            if self.is_installed(&version) {
+                return Ok((version.clone(), self.python_binary(&version)));
+            }
+            let python = self.ensure_version(&version)?;
+            return Ok((version, python));
+        }
+
+        // Source builds and other flavors are only used when asked for by name.
+        if let Some(version) = self.list_installed()?.into_iter().find(|version| {
+            !version.contains('+')
+                && parse_runtime_name(version).0 == Flavor::CPython
+                && admits(version)
+        }) {
+            let python = self.python_binary(&version);
+            return Ok((version, python));
         }
 
-        // Extract the archive
-        eprintln!("  Extracting...");
-        extract_tar_gz(&archive_path, &dest_dir)?;
+        let version = newest_supported(requires_python.unwrap_or_default())
+            .map(|info| info.version)
+            .ok_or_else(|| {
+                eyre!(
+                    "no supported Python version satisfies requires-python {} (supported: {})",
+                    requires_python.unwrap_or_default(),
+                    SUPPORTED_SUMMARY
+                )
+            })?;
+        let python = self.ensure_version(&version)?;
+        Ok((version, python))
+    }
 
-        // Clean up archive
-        fs::remove_file(&archive_path)?;
+    /// Download, verify and install a Python version.
+    ///
+    /// The archive is unpacked into a staging directory next to the
+    /// runtimes and renamed into place, so an interrupted install never
+    /// leaves a half-extracted runtime that looks installed.
+    fn download_and_install(
+        &self,
+        version_info: &PythonVersion,
+        on_progress: &mut dyn FnMut(InstallProgress),
+    ) -> Result<()> {
+        let platform = Platform::current().ok_or_else(|| eyre!("Unsupported platform"))?;
+        let archive = archive_name(version_info).ok_or_else(|| eyre!("Unsupported platform"))?;
+        let url = self
+            .download_url(version_info)
+            .ok_or_else(|| eyre!("Unsupported platform"))?;
+        let expected = self.expected_checksum(version_info, platform, &archive, on_progress)?;
+        let name = version_info.name();
+
+        let downloads = self.runtimes_dir().join(".downloads");
+        fs::create_dir_all(&downloads)?;
+        let partial = downloads.join(format!("{archive}.part"));
+        let archive_path = downloads.join(&archive);
+
+        on_progress(InstallProgress::Started {
+            version: name.clone(),
+            url: url.clone(),
+        });
+        let downloaded = self.download_archive(&url, &partial, on_progress);
+        let (actual, size) = match downloaded {
+            Ok(downloaded) => downloaded,
+            Err(e) => {
+                let _ = fs::remove_file(&partial);
+                return Err(e).wrap_err_with(|| format!("Failed to download Python {name}"));
+            }
+        };
+        if actual != expected {
+            fs::remove_file(&partial)?;
+            return Err(eyre!(
+                "Checksum mismatch for Python {} (expected {}, got {})",
+                name,
+                expected,
+                actual
+            ));
+        }
+        on_progress(InstallProgress::Verified { sha256: actual });
+        fs::rename(&partial, &archive_path)?;
+        self.record_download_size(&url, size);
+
+        let staging = self
+            .runtimes_dir()
+            .join(format!(".staging-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&staging);
+        let mut last_percent = None;
+        let mut on_extract = |progress: crate::archive::ExtractProgress| {
+            let percent = progress.percent();
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                on_progress(InstallProgress::Extracting { percent });
+            }
+        };
+        let extracted = crate::archive::extract(
+            &archive_path,
+            &staging,
+            crate::archive::ExtractOptions {
+                expected_sha256: None,
+                on_progress: Some(&mut on_extract),
+            },
+        );
+        let _ = fs::remove_file(&archive_path);
+        if let Err(e) = extracted
+            .map_err(color_eyre::Report::from)
+            .and_then(|_| normalize_layout(&staging, version_info.flavor))
+        {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e).wrap_err_with(|| format!("Failed to extract Python {name}"));
+        }
 
         // Verify installation
-        let python_bin = self.python_binary(&version_info.version);
-        if !python_bin.exists() {
+        let staged_python = staging.join(python_relative(&name));
+        if !staged_python.exists() {
+            let _ = fs::remove_dir_all(&staging);
             return Err(eyre!(
                 "Installation failed: Python binary not found at {}",
-                python_bin.display()
+                self.python_binary(&name).display()
             ));
         }
+        let implementation_version =
+            (version_info.flavor == Flavor::PyPy).then(|| version_info.release_tag.clone());
+        if let Some(info) = RuntimeInfo::new(
+            version_info.flavor,
+            &version_info.version,
+            implementation_version,
+        ) {
+            fs::write(
+                staging.join(RUNTIME_INFO_FILE),
+                serde_json::to_vec_pretty(&info)?,
+            )?;
+        }
 
         // Make binary executable on Unix
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&python_bin)?.permissions();
+            let mut perms = fs::metadata(&staged_python)?.permissions();
             perms.set_mode(0o755);
-            fs::set_permissions(&python_bin, perms)?;
+            fs::set_permissions(&staged_python, perms)?;
         }
 
-        eprintln!(
-            "  Installed Python {} to {}",
-            version_info.version,
-            dest_dir.display()
-        );
+        let dest_dir = self.version_dir(&name);
+        if self.is_installed(&name) {
+            // Another process installed it meanwhile.
+            let _ = fs::remove_dir_all(&staging);
+        } else {
+            // Left over from an install interrupted before this layout.
+            if dest_dir.exists() {
+                fs::remove_dir_all(&dest_dir)?;
+            }
+            fs::rename(&staging, &dest_dir)?;
+        }
+        on_progress(InstallProgress::Installed { path: dest_dir });
 
         Ok(())
     }
 
+    /// Checksum a download of `archive` must match: PyBun's built-in one,
+    /// cross-checked against the release manifest, or the published one for
+    /// archives without a built-in checksum (e.g. musl).
+    fn expected_checksum(
+        &self,
+        version_info: &PythonVersion,
+        platform: Platform,
+        archive: &str,
+        on_progress: &mut dyn FnMut(InstallProgress),
+    ) -> Result<String> {
+        let built_in = version_info
+            .checksums
+            .get(platform.checksum_key())
+            .map(|sha| sha.to_ascii_lowercase());
+        let published = match self.manifest_for(version_info) {
+            Ok(manifest) => manifest.asset(archive).and_then(|a| a.sha256.clone()),
+            Err(e) if built_in.is_some() => {
+                on_progress(InstallProgress::Warning(format!(
+                    "{e:#}; verifying against the built-in checksum only"
+                )));
+                None
+            }
+            Err(e) => {
+                return Err(e.wrap_err(format!("no checksum known for {archive}")));
+            }
+        };
+        match (built_in, published) {
+            (Some(built_in), Some(published)) if built_in != published => Err(eyre!(
+                "release {} publishes checksum {} for {}, but PyBun expects {}; refusing to install",
+                version_info.release_tag,
+                published,
+                archive,
+                built_in
+            )),
+            (Some(sha), _) | (None, Some(sha)) => Ok(sha),
+            (None, None) => Err(eyre!(
+                "release {} publishes no checksum for {}",
+                version_info.release_tag,
+                archive
+            )),
+        }
+    }
+
+    /// Stream `url` into `dest`, returning its SHA-256 and size.
+    fn download_archive(
+        &self,
+        url: &str,
+        dest: &Path,
+        on_progress: &mut dyn FnMut(InstallProgress),
+    ) -> Result<(String, u64)> {
+        network_policy::check_url(Operation::Python, url)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            let download = scope.spawn(move || -> Result<(String, u64)> {
+                let client = crate::http_config::blocking_client_builder()
+                    .connect_timeout(Duration::from_secs(10))
+                    .timeout(None)
+                    .redirect(network_policy::redirect_policy(Operation::Python))
+                    .build()?;
+                let mut response = client.get(url).send()?.error_for_status()?;
+                let total = response.content_length();
+                let mut file = std::io::BufWriter::new(fs::File::create(dest)?);
+                let mut hasher = Sha256::new();
+                let mut buffer = vec![0; 64 * 1024];
+                let mut downloaded = 0u64;
+                let mut reported = 0u64;
+                loop {
+                    let n = response.read(&mut buffer)?;
+                    if n == 0 {
+                        break;
+                    }
+                    file.write_all(&buffer[..n])?;
+                    hasher.update(&buffer[..n]);
+                    downloaded += n as u64;
+                    if downloaded - reported >= PROGRESS_STEP_BYTES {
+                        reported = downloaded;
+                        let _ = sender.send((downloaded, total));
+                    }
+                }
+                file.flush()?;
+                let _ = sender.send((downloaded, total));
+                Ok((hex::encode(hasher.finalize()), downloaded))
+            });
+            for (downloaded, total) in receiver {
+                on_progress(InstallProgress::Downloading { downloaded, total });
+            }
+            download
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    fn manifests_dir(&self) -> PathBuf {
+        self.runtimes_dir().join("manifests")
+    }
+
+    /// The manifest of release `tag`, if cached by an earlier fetch.
+    pub fn cached_release_manifest(&self, tag: &str) -> Option<ReleaseManifest> {
+        runtime_manifest::load_cached(&self.manifests_dir(), tag)
+    }
+
+    /// The manifest of release `tag`, fetched and cached unless it already
+    /// is. Fails in offline mode when it is not cached.
+    pub fn release_manifest(&self, tag: &str) -> Result<ReleaseManifest> {
+        if let Some(manifest) = self.cached_release_manifest(tag) {
+            return Ok(manifest);
+        }
+        if self.offline {
+            return Err(eyre!(
+                "offline mode: release manifest {} is not cached",
+                tag
+            ));
+        }
+        let manifest_url = format!("{}/tags/{}", self.releases_api, tag);
+        network_policy::check_url(Operation::Python, &manifest_url)?;
+        let manifest = off_runtime(|| -> Result<ReleaseManifest> {
+            let client = crate::http_config::blocking_client_builder()
+                .timeout(Duration::from_secs(30))
+                .user_agent(concat!("pybun/", env!("CARGO_PKG_VERSION")))
+                .redirect(network_policy::redirect_policy(Operation::Python))
+                .build()?;
+            runtime_manifest::fetch(&client, &self.releases_api, &self.mirror, tag)
+        })?;
+        runtime_manifest::save(&self.manifests_dir(), &manifest)?;
+        Ok(manifest)
+    }
+
+    /// Cache key of the checksums of `version_info`'s release.
+    fn manifest_tag(version_info: &PythonVersion) -> String {
+        match version_info.flavor {
+            Flavor::PyPy => format!("pypy-{}", version_info.release_tag),
+            _ => version_info.release_tag.clone(),
+        }
+    }
+
+    /// The cached manifest `version_info`'s archive is verified against.
+    pub fn cached_manifest_for(&self, version_info: &PythonVersion) -> Option<ReleaseManifest> {
+        self.cached_release_manifest(&Self::manifest_tag(version_info))
+    }
+
+    /// The manifest `version_info`'s archive is verified against: the
+    /// python-build-standalone release, or for PyPy the files of its release
+    /// on the PyPy checksum page (their sizes are not published).
+    pub fn manifest_for(&self, version_info: &PythonVersion) -> Result<ReleaseManifest> {
+        if version_info.flavor != Flavor::PyPy {
+            return self.release_manifest(&version_info.release_tag);
+        }
+        let tag = Self::manifest_tag(version_info);
+        if let Some(manifest) = self.cached_release_manifest(&tag) {
+            return Ok(manifest);
+        }
+        if self.offline {
+            return Err(eyre!(
+                "offline mode: PyPy checksums for {} are not cached",
+                version_info.release_tag
+            ));
+        }
+        network_policy::check_url(Operation::Python, &self.pypy_checksums)?;
+        let page = off_runtime(|| -> Result<String> {
+            let client = crate::http_config::blocking_client_builder()
+                .timeout(Duration::from_secs(30))
+                .redirect(network_policy::redirect_policy(Operation::Python))
+                .build()?;
+            client
+                .get(&self.pypy_checksums)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text())
+                .wrap_err_with(|| format!("failed to fetch {}", self.pypy_checksums))
+        })?;
+        let release = format!("-{}-", version_info.release_tag);
+        let mut manifest = runtime_manifest::from_sha256_listing(&tag, &page);
+        manifest.assets.retain(|name, _| name.contains(&release));
+        if manifest.assets.is_empty() {
+            return Err(eyre!(
+                "{} lists no checksums for PyPy {}",
+                self.pypy_checksums,
+                version_info.release_tag
+            ));
+        }
+        runtime_manifest::save(&self.manifests_dir(), &manifest)?;
+        Ok(manifest)
+    }
+
+    fn download_sizes_path(&self) -> PathBuf {
+        self.runtimes_dir().join("download-sizes.json")
+    }
+
+    fn load_download_sizes(&self) -> BTreeMap<String, u64> {
+        fs::read(self.download_sizes_path())
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn record_download_size(&self, url: &str, size: u64) {
+        let mut sizes = self.load_download_sizes();
+        sizes.insert(url.to_string(), size);
+        if let Ok(data) = serde_json::to_vec_pretty(&sizes) {
+            let _ = fs::create_dir_all(self.runtimes_dir());
+            let _ = fs::write(self.download_sizes_path(), data);
+        }
+    }
+
+    /// Archive size for `version_info` on this platform, if known from a
+    /// cached release manifest or a previous download.
+    pub fn known_download_size(&self, version_info: &PythonVersion) -> Option<u64> {
+        let archive = archive_name(version_info)?;
+        if let Some(asset) = self
+            .cached_manifest_for(version_info)
+            .and_then(|manifest| manifest.asset(&archive).cloned())
+            .filter(|asset| asset.size > 0)
+        {
+            return Some(asset.size);
+        }
+        let url = self.download_url(version_info)?;
+        self.load_download_sizes().get(&url).copied()
+    }
+
+    /// Archive size for `version_info` from its release manifest, fetching
+    /// the manifest if needed. Fails in offline mode.
+    pub fn fetch_download_size(&self, version_info: &PythonVersion) -> Result<u64> {
+        let archive = archive_name(version_info).ok_or_else(|| eyre!("Unsupported platform"))?;
+        let manifest = self.manifest_for(version_info)?;
+        manifest
+            .asset(&archive)
+            .map(|asset| asset.size)
+            .filter(|size| *size > 0)
+            .ok_or_else(|| {
+                eyre!(
+                    "release {} has no size for {}",
+                    version_info.release_tag,
+                    archive
+                )
+            })
+    }
+
+    /// Disk space used by an installed version.
+    pub fn installed_size(&self, version: &str) -> u64 {
+        fn walk(path: &Path) -> u64 {
+            let Ok(entries) = fs::read_dir(path) else {
+                return 0;
+            };
+            entries
+                .flatten()
+                .map(|entry| match entry.file_type() {
+                    Ok(kind) if kind.is_dir() => walk(&entry.path()),
+                    Ok(kind) if kind.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    _ => 0,
+                })
+                .sum()
+        }
+        walk(&self.version_dir(version))
+    }
+
     /// Remove an installed Python version.
     pub fn remove_version(&self, version: &str) -> Result<()> {
         let dir = self.version_dir(version);
@@ -723,65 +1450,58 @@ pub enum AbiCheck {
     },
 }
 
-/// Download a file from a URL.
-fn download_file(url: &str, dest: &Path) -> Result<()> {
-    // Use system curl for downloads (to be replaced with reqwest in production)
-    let status = std::process::Command::new("curl")
-        .args(["-fSL", "-o"])
-        .arg(dest)
-        .arg(url)
-        .status()
-        .wrap_err("Failed to run curl")?;
-
-    if !status.success() {
-        return Err(eyre!("Download failed with status {}", status));
-    }
-
-    Ok(())
-}
-
-/// Compute SHA256 hash of a file.
-fn compute_sha256(path: &Path) -> Result<String> {
-    use std::process::Command;
-
-    // Use system sha256sum or shasum
-    let output = if cfg!(target_os = "macos") {
-        Command::new("shasum")
-            .args(["-a", "256"])
-            .arg(path)
-            .output()
+/// Interpreter path inside the directory of runtime `name`.
+fn python_relative(name: &str) -> PathBuf {
+    let (flavor, _) = parse_runtime_name(name);
+    let executable = match flavor {
+        Flavor::CPython => "python3".to_string(),
+        // `make install` of a free-threaded build only installs `python3.13t`.
+        Flavor::FreeThreaded => {
+            let version = release_version(name);
+            match Interpreter::from_version(flavor, version) {
+                Some(i) => format!("python{}t", i.dotted_version()),
+                None => "python3".to_string(),
+            }
+        }
+        Flavor::PyPy => "pypy3".to_string(),
+    };
+    if cfg!(windows) {
+        let executable = if flavor == Flavor::CPython {
+            "python"
+        } else {
+            &executable
+        };
+        PathBuf::from("python").join(format!("{executable}.exe"))
     } else {
-        Command::new("sha256sum").arg(path).output()
-    }
-    .wrap_err("Failed to compute checksum")?;
-
-    if !output.status.success() {
-        return Err(eyre!("Checksum computation failed"));
+        PathBuf::from("python").join("bin").join(executable)
     }
-
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    let hash = output_str
-        .split_whitespace()
-        .next()
-        .ok_or_else(|| eyre!("Invalid checksum output"))?;
-
-    Ok(hash.to_string())
 }
 
-/// Extract a .tar.gz archive.
-fn extract_tar_gz(archive: &Path, dest: &Path) -> Result<()> {
-    let status = std::process::Command::new("tar")
-        .args(["-xzf"])
-        .arg(archive)
-        .arg("-C")
-        .arg(dest)
-        .status()
-        .wrap_err("Failed to run tar")?;
-
-    if !status.success() {
-        return Err(eyre!("Extraction failed with status {}", status));
+/// Move an unpacked archive's interpreter tree to `staging/python`, the
+/// layout of python-build-standalone's `install_only` archives.
+fn normalize_layout(staging: &Path, flavor: Flavor) -> Result<()> {
+    match flavor {
+        Flavor::CPython => {}
+        // `full` archives keep the installed tree in `python/install`.
+        Flavor::FreeThreaded => {
+            let full = staging.join("python");
+            if full.join("install").is_dir() {
+                let moved = staging.join(".full");
+                fs::rename(&full, &moved)?;
+                fs::rename(moved.join("install"), &full)?;
+                fs::remove_dir_all(&moved)?;
+            }
+        }
+        // PyPy archives unpack to `pypy3.10-v7.3.17-<platform>/`.
+        Flavor::PyPy => {
+            let top = fs::read_dir(staging)?.flatten().find(|entry| {
+                entry.path().is_dir() && entry.file_name().to_string_lossy().starts_with("pypy")
+            });
+            if let Some(top) = top {
+                fs::rename(top.path(), staging.join("python"))?;
+            }
+        }
     }
-
     Ok(())
 }
 
@@ -823,6 +1543,54 @@ mod tests {
         assert!(v.is_none());
     }
 
+    #[test]
+    fn find_version_matches_the_requested_flavor() {
+        let free_threaded = find_version("3.13t").unwrap();
+        assert_eq!(free_threaded.flavor, Flavor::FreeThreaded);
+        assert_eq!(free_threaded.name(), "3.13.0t");
+        assert_eq!(free_threaded.interpreter_tag().as_deref(), Some("cp313t"));
+        let pypy = find_version("pypy3.10").unwrap();
+        assert_eq!(pypy.name(), "pypy3.10.14");
+        assert_eq!(pypy.interpreter_tag().as_deref(), Some("pp310"));
+        assert_eq!(find_version("pypy").unwrap().name(), "pypy3.10.14");
+        assert!(find_version("3.13").is_none());
+        assert_eq!(find_version("3.10").unwrap().flavor, Flavor::CPython);
+        assert_eq!(newest_supported(">=3.10").unwrap().version, "3.12.7");
+
+        assert_eq!(
+            parse_runtime_name("3.12.7+main"),
+            (Flavor::CPython, "3.12.7+main")
+        );
+        assert_eq!(release_version("3.13.0t"), "3.13.0");
+        assert_eq!(release_version("pypy3.10.14"), "3.10.14");
+        if Platform::current() == Some(Platform::LinuxX64Gnu) {
+            assert_eq!(
+                archive_name(&free_threaded).unwrap(),
+                "cpython-3.13.0+20241016-x86_64-unknown-linux-gnu-freethreaded+pgo+lto-full.tar.zst"
+            );
+            assert_eq!(
+                archive_name(&pypy).unwrap(),
+                "pypy3.10-v7.3.17-linux64.tar.bz2"
+            );
+        }
+    }
+
+    #[test]
+    fn test_series_lifecycle_status() {
+        let lifecycle = series_lifecycle("3.11.10").unwrap();
+        assert_eq!(lifecycle.status("2024-01-01"), SupportStatus::Bugfix);
+        assert_eq!(lifecycle.status("2025-06-01"), SupportStatus::Security);
+        assert_eq!(lifecycle.status("2027-11-01"), SupportStatus::EndOfLife);
+        assert!(series_lifecycle("2.7.18").is_none());
+        for version in supported_versions() {
+            if version.flavor == Flavor::PyPy {
+                continue;
+            }
+            assert!(series_lifecycle(&version.version).is_some());
+            assert_eq!(version.release_date.len(), 10);
+        }
+    }
+
     #[test]
     fn test_platform_detection() {
         // This should not panic on any supported platform
@@ -892,6 +1660,76 @@ mod tests {
         assert!(installed.is_empty());
     }
 
+    /// Create a fake installed runtime (just the interpreter file).
+    fn install_fake(manager: &RuntimeManager, version: &str) {
+        let python = manager.python_binary(version);
+        fs::create_dir_all(python.parent().unwrap()).unwrap();
+        fs::write(&python, "").unwrap();
+    }
+
+    #[test]
+    fn test_select_runtime_prefers_installed_match() {
+        let temp = TempDir::new().unwrap();
+        let manager = RuntimeManager::new(Cache::with_root(temp.path())).offline(true);
+        install_fake(&manager, "3.10.15");
+        install_fake(&manager, "3.12.7");
+        install_fake(&manager, "3.12.7+main");
+
+        let (version, python) = manager.select_runtime(None, Some("<3.12")).unwrap();
+        assert_eq!(version, "3.10.15");
+        assert_eq!(python, manager.python_binary("3.10.15"));
+        let (version, _) = manager.select_runtime(None, Some(">=3.10")).unwrap();
+        assert_eq!(version, "3.12.7");
+        let (version, _) = manager.select_runtime(Some("3.12"), None).unwrap();
+        assert_eq!(version, "3.12.7");
+        let (version, _) = manager
+            .select_runtime(Some("3.12.7+main"), Some(">=3.12"))
+            .unwrap();
+        assert_eq!(version, "3.12.7+main");
+    }
+
+    #[test]
+    fn find_installed_matches_series_and_flavor() {
+        let temp = TempDir::new().unwrap();
+        let manager = RuntimeManager::new(Cache::with_root(temp.path()));
+        install_fake(&manager, "3.1.5");
+        install_fake(&manager, "3.12.6");
+        install_fake(&manager, "3.12.7");
+        install_fake(&manager, "3.13.0t");
+        install_fake(&manager, "3.13.1+main");
+
+        assert_eq!(manager.find_installed("3.12").as_deref(), Some("3.12.7"));
+        assert_eq!(manager.find_installed("3.12.6").as_deref(), Some("3.12.6"));
+        assert_eq!(manager.find_installed("3.1").as_deref(), Some("3.1.5"));
+        assert_eq!(manager.find_installed("3.13t").as_deref(), Some("3.13.0t"));
+        assert_eq!(manager.find_installed("3.13"), None);
+        assert_eq!(
+            manager.find_installed("3.13.1+main").as_deref(),
+            Some("3.13.1+main")
+        );
+        assert_eq!(manager.find_installed("pypy3.10"), None);
+    }
+
+    #[test]
+    fn test_select_runtime_rejects_conflicts() {
+        let temp = TempDir::new().unwrap();
+        let manager = RuntimeManager::new(Cache::with_root(temp.path())).offline(true);
+        install_fake(&manager, "3.10.15");
+
+        let err = manager
+            .select_runtime(Some("3.10"), Some(">=3.11"))
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not satisfy requires-python >=3.11")
+        );
+        let err = manager.select_runtime(None, Some(">=4")).unwrap_err();
+        assert!(err.to_string().contains("no supported Python version"));
+        // A missing match is downloaded, which offline mode refuses.
+        let err = manager.select_runtime(None, Some(">=3.11")).unwrap_err();
+        assert!(err.to_string().contains("offline mode"), "{err}");
+    }
+
     #[test]
     fn test_version_cmp() {
         assert_eq!(version_cmp("3.11.0", "3.11.0"), std::cmp::Ordering::Equal);
@@ -900,6 +1738,176 @@ mod tests {
         assert_eq!(version_cmp("3.12.0", "3.9.0"), std::cmp::Ordering::Greater);
     }
 
+    /// A gzipped tarball laid out like a python-build-standalone
+    /// `install_only` archive.
+    fn fake_runtime_archive() -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::fast(),
+        ));
+        let body = b"#!/bin/sh\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(body.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, python_relative("3.99.1"), &body[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// A release `20990101` of Python 3.99.1 served by `server`, published
+    /// with checksum `sha256` and without one built into PyBun.
+    fn serve_release(server: &httpmock::MockServer, archive: &[u8], sha256: &str) -> PythonVersion {
+        use httpmock::prelude::*;
+        let info = PythonVersion {
+            version: "3.99.1".into(),
+            release_tag: "20990101".into(),
+            checksums: HashMap::new(),
+            release_date: String::new(),
+            flavor: Flavor::CPython,
+        };
+        let name = archive_name(&info).unwrap();
+        server.mock(|when, then| {
+            when.method(GET).path("/releases/tags/20990101");
+            then.status(200).json_body(serde_json::json!({
+                "published_at": "2099-01-01T00:00:00Z",
+                "assets": [{ "name": name, "size": archive.len(), "digest": format!("sha256:{sha256}") }]
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/download/20990101/{name}"));
+            then.status(200).body(archive);
+        });
+        info
+    }
+
+    fn manager_for(server: &httpmock::MockServer, root: &Path) -> RuntimeManager {
+        RuntimeManager::new(Cache::with_root(root))
+            .mirror(server.url("/download"))
+            .releases_api(server.url("/releases"))
+    }
+
+    #[test]
+    fn download_verifies_published_checksum_and_installs_atomically() {
+        let server = httpmock::MockServer::start();
+        let archive = fake_runtime_archive();
+        let info = serve_release(&server, &archive, &hex::encode(Sha256::digest(&archive)));
+        let temp = TempDir::new().unwrap();
+        let manager = manager_for(&server, temp.path());
+
+        let mut events = Vec::new();
+        manager
+            .download_and_install(&info, &mut |progress| events.push(progress))
+            .unwrap();
+
+        assert!(manager.is_installed("3.99.1"));
+        assert!(events.contains(&InstallProgress::Verified {
+            sha256: hex::encode(Sha256::digest(&archive))
+        }));
+        assert!(events.contains(&InstallProgress::Downloading {
+            downloaded: archive.len() as u64,
+            total: Some(archive.len() as u64),
+        }));
+        let manifest = manager.cached_release_manifest("20990101").unwrap();
+        assert_eq!(manifest.release_date(), Some("2099-01-01"));
+        assert_eq!(
+            manager.known_download_size(&info),
+            Some(archive.len() as u64)
+        );
+        // Nothing but the runtime and the cached manifest is left behind.
+        let mut entries: Vec<String> = fs::read_dir(manager.runtimes_dir())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            [".downloads", "3.99.1", "download-sizes.json", "manifests"]
+        );
+        assert_eq!(
+            fs::read_dir(manager.runtimes_dir().join(".downloads"))
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn download_not_matching_published_checksum_is_discarded() {
+        let server = httpmock::MockServer::start();
+        let info = serve_release(&server, &fake_runtime_archive(), &"0".repeat(64));
+        let temp = TempDir::new().unwrap();
+        let manager = manager_for(&server, temp.path());
+
+        let err = manager
+            .download_and_install(&info, &mut |_| {})
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"), "{err}");
+        assert!(!manager.version_dir("3.99.1").exists());
+        assert_eq!(
+            fs::read_dir(manager.runtimes_dir().join(".downloads"))
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn pypy_download_is_verified_against_the_checksum_page() {
+        use httpmock::prelude::*;
+        let info = PythonVersion {
+            version: "3.10.99".into(),
+            release_tag: "v7.3.99".into(),
+            checksums: HashMap::new(),
+            release_date: String::new(),
+            flavor: Flavor::PyPy,
+        };
+        let Some(archive) = archive_name(&info).filter(|name| name.ends_with(".tar.bz2")) else {
+            return;
+        };
+        let top = archive.trim_end_matches(".tar.bz2");
+        let mut builder = tar::Builder::new(bzip2::write::BzEncoder::new(
+            Vec::new(),
+            bzip2::Compression::fast(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{top}/bin/pypy3"), &b""[..])
+            .unwrap();
+        let body = builder.into_inner().unwrap().finish().unwrap();
+        let sha256 = hex::encode(Sha256::digest(&body));
+
+        let server = httpmock::MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/checksums.html");
+            then.status(200).body(format!(
+                "<pre>{}  pypy3.10-v7.3.98-linux64.tar.bz2\n{sha256}  {archive}\n</pre>",
+                "e".repeat(64)
+            ));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/pypy/{archive}"));
+            then.status(200).body(&body);
+        });
+        let temp = TempDir::new().unwrap();
+        let manager = RuntimeManager::new(Cache::with_root(temp.path()))
+            .pypy_mirror(server.url("/pypy"), server.url("/checksums.html"));
+
+        manager.download_and_install(&info, &mut |_| {}).unwrap();
+        assert!(manager.is_installed("pypy3.10.99"));
+        let runtime = manager.runtime_info("pypy3.10.99").unwrap();
+        assert_eq!(runtime.implementation, "pypy");
+        assert_eq!(runtime.abi_tag, "pypy310_pp73");
+        assert_eq!(runtime.implementation_version.as_deref(), Some("v7.3.99"));
+        // Only this release's checksums are cached.
+        let manifest = manager.cached_manifest_for(&info).unwrap();
+        assert_eq!(manifest.assets.len(), 1);
+    }
+
     /// Test that ensure_version successfully downloads and verifies a Python runtime.
     /// This validates that checksums are correct and the download/verification flow works.
     #[test]
@@ -1048,49 +2056,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn manylinux_x86_64_tags_includes_standard_formats() {
-        let tags = manylinux_tags_x86_64();
-        assert!(
-            tags.iter().any(|t| t == "manylinux_2_17_x86_64"),
-            "should include manylinux_2_17 (manylinux2014)"
-        );
-        assert!(
-            tags.iter().any(|t| t == "manylinux_2_28_x86_64"),
-            "should include manylinux_2_28"
-        );
-        assert!(
-            tags.iter().any(|t| t == "manylinux2014_x86_64"),
-            "should include legacy manylinux2014_x86_64 tag"
-        );
-        assert!(
-            tags.iter().any(|t| t == "linux_x86_64"),
-            "should include plain linux_x86_64 tag"
-        );
-        // pip >= 22.0 dropped manylinux1 (glibc < 2.17); we floor at 2.17
-        assert!(
-            !tags.iter().any(|t| t == "manylinux_2_5_x86_64"),
-            "should not include glibc 2.5 tags (below manylinux2014 floor)"
-        );
-    }
-
-    #[test]
-    fn manylinux_aarch64_tags_includes_standard_formats() {
-        let tags = manylinux_tags_aarch64();
-        assert!(
-            tags.iter().any(|t| t == "manylinux_2_17_aarch64"),
-            "should include manylinux_2_17_aarch64"
-        );
-        assert!(
-            tags.iter().any(|t| t == "manylinux2014_aarch64"),
-            "should include legacy manylinux2014_aarch64 tag"
-        );
-        assert!(
-            tags.iter().any(|t| t == "linux_aarch64"),
-            "should include plain linux_aarch64 tag"
-        );
-    }
-
     #[test]
     #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
     fn current_wheel_tags_on_macos_arm64_includes_pep425_tags() {
@@ -0,0 +1,287 @@
+//! Upstream release manifests of python-build-standalone.
+//!
+//! Every python-build-standalone release is a GitHub release whose assets
+//! are the runtime archives plus a `SHA256SUMS` file. The manifest of a
+//! release tag records each asset's size and published SHA-256 and the date
+//! the release was published. `pybun python install` verifies downloads
+//! against it, and `pybun python list --all` reports sizes and dates from it.
+//!
+//! Manifests are cached as `python/manifests/<tag>.json` under the cache
+//! root, so listing works offline once a manifest has been fetched.
+//! `PYBUN_PYTHON_RELEASES_API` points at another GitHub-compatible releases
+//! API, and `PYBUN_PYTHON_MIRROR` at another download base (for example an
+//! internal mirror of the release assets).
+
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// GitHub releases API of python-build-standalone.
+pub const DEFAULT_RELEASES_API: &str =
+    "https://api.github.com/repos/indygreg/python-build-standalone/releases";
+
+/// Overrides [`DEFAULT_RELEASES_API`].
+pub const RELEASES_API_ENV: &str = "PYBUN_PYTHON_RELEASES_API";
+
+/// Overrides the base URL runtime archives are downloaded from.
+pub const MIRROR_ENV: &str = "PYBUN_PYTHON_MIRROR";
+
+/// Name of the checksum file published with each release.
+pub const SHA256SUMS: &str = "SHA256SUMS";
+
+/// One downloadable file of a release.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseAsset {
+    pub size: u64,
+    /// Published SHA-256 (hex), from the asset digest or `SHA256SUMS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// The assets of one release tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+    pub tag: String,
+    /// RFC 3339 timestamp the release was published at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub assets: BTreeMap<String, ReleaseAsset>,
+}
+
+impl ReleaseManifest {
+    /// Read a GitHub release object (`GET /releases/tags/<tag>`).
+    pub fn from_github_release(tag: &str, release: &Value) -> Self {
+        let assets = release["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|asset| {
+                let name = asset["name"].as_str()?;
+                let sha256 = asset["digest"]
+                    .as_str()
+                    .and_then(|digest| digest.strip_prefix("sha256:"))
+                    .map(str::to_ascii_lowercase);
+                Some((
+                    name.to_string(),
+                    ReleaseAsset {
+                        size: asset["size"].as_u64().unwrap_or(0),
+                        sha256,
+                    },
+                ))
+            })
+            .collect();
+        Self {
+            tag: tag.to_string(),
+            published_at: release["published_at"].as_str().map(str::to_string),
+            assets,
+        }
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.get(name)
+    }
+
+    /// The day the release was published (`YYYY-MM-DD`).
+    pub fn release_date(&self) -> Option<&str> {
+        self.published_at.as_deref().and_then(|at| at.get(..10))
+    }
+
+    /// Fill in checksums from a `SHA256SUMS` file. A checksum already known
+    /// from the asset digest is kept.
+    pub fn apply_sha256sums(&mut self, text: &str) {
+        for (name, sha256) in parse_sha256sums(text) {
+            if let Some(asset) = self.assets.get_mut(&name) {
+                asset.sha256.get_or_insert(sha256);
+            }
+        }
+    }
+
+    fn missing_checksums(&self) -> bool {
+        self.assets
+            .iter()
+            .any(|(name, asset)| name != SHA256SUMS && asset.sha256.is_none())
+    }
+}
+
+/// `<sha256>  <file name>` lines (`sha256sum` output, binary mode `*` too).
+pub fn parse_sha256sums(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (sha256, name) = line.trim().split_once(char::is_whitespace)?;
+            let name = name.trim_start().trim_start_matches('*');
+            (sha256.len() == 64 && sha256.bytes().all(|b| b.is_ascii_hexdigit()))
+                .then(|| (name.to_string(), sha256.to_ascii_lowercase()))
+        })
+        .collect()
+}
+
+/// Manifest of a release whose checksums are only published as a
+/// `sha256sum` listing, possibly inside an HTML page (PyPy's checksum page).
+/// Asset sizes are unknown and left at 0.
+pub fn from_sha256_listing(tag: &str, text: &str) -> ReleaseManifest {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                plain.push('\n');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    ReleaseManifest {
+        tag: tag.to_string(),
+        published_at: None,
+        assets: parse_sha256sums(&plain)
+            .into_iter()
+            .map(|(name, sha256)| {
+                let asset = ReleaseAsset {
+                    size: 0,
+                    sha256: Some(sha256),
+                };
+                (name, asset)
+            })
+            .collect(),
+    }
+}
+
+/// Releases API in effect (`PYBUN_PYTHON_RELEASES_API` or the default).
+pub fn releases_api() -> String {
+    std::env::var(RELEASES_API_ENV)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| DEFAULT_RELEASES_API.to_string())
+}
+
+/// Fetch the manifest of `tag`. Checksums missing from the asset digests
+/// are read from the release's `SHA256SUMS`, downloaded from `release_base`.
+pub fn fetch(
+    client: &reqwest::blocking::Client,
+    api: &str,
+    release_base: &str,
+    tag: &str,
+) -> Result<ReleaseManifest> {
+    let url = format!("{}/tags/{}", api.trim_end_matches('/'), tag);
+    let release: Value = client
+        .get(&url)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.json())
+        .wrap_err_with(|| format!("failed to fetch release manifest {url}"))?;
+    let mut manifest = ReleaseManifest::from_github_release(tag, &release);
+    if manifest.assets.is_empty() {
+        return Err(eyre!("release {tag} lists no assets at {url}"));
+    }
+    if manifest.missing_checksums() && manifest.assets.contains_key(SHA256SUMS) {
+        let url = format!(
+            "{}/{}/{}",
+            release_base.trim_end_matches('/'),
+            tag,
+            SHA256SUMS
+        );
+        let sums = client
+            .get(&url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .wrap_err_with(|| format!("failed to fetch {url}"))?;
+        manifest.apply_sha256sums(&sums);
+    }
+    Ok(manifest)
+}
+
+fn cache_path(dir: &Path, tag: &str) -> PathBuf {
+    dir.join(format!("{tag}.json"))
+}
+
+/// The cached manifest of `tag`, if any.
+pub fn load_cached(dir: &Path, tag: &str) -> Option<ReleaseManifest> {
+    let data = fs::read(cache_path(dir, tag)).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Cache `manifest` in `dir`.
+pub fn save(dir: &Path, manifest: &ReleaseManifest) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = cache_path(dir, &manifest.tag);
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(manifest)?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ARCHIVE: &str = "cpython-3.12.7+20241016-x86_64-unknown-linux-gnu-install_only.tar.gz";
+
+    #[test]
+    fn parses_release_assets_and_sha256sums() {
+        let digest = "a".repeat(64);
+        let mut manifest = ReleaseManifest::from_github_release(
+            "20241016",
+            &json!({
+                "published_at": "2024-10-16T21:04:15Z",
+                "assets": [
+                    { "name": ARCHIVE, "size": 1234 },
+                    { "name": "other.tar.gz", "size": 5, "digest": format!("sha256:{digest}") },
+                    { "name": SHA256SUMS, "size": 99 },
+                ]
+            }),
+        );
+        assert_eq!(manifest.release_date(), Some("2024-10-16"));
+        assert_eq!(manifest.asset(ARCHIVE).unwrap().size, 1234);
+        assert!(manifest.missing_checksums());
+
+        let sum = "B".repeat(64);
+        manifest.apply_sha256sums(&format!(
+            "{sum}  {ARCHIVE}\n{}  other.tar.gz\nnot a checksum line\n",
+            "c".repeat(64)
+        ));
+        assert_eq!(
+            manifest.asset(ARCHIVE).unwrap().sha256.as_deref(),
+            Some("b".repeat(64).as_str())
+        );
+        // The asset digest wins over SHA256SUMS.
+        assert_eq!(
+            manifest.asset("other.tar.gz").unwrap().sha256.as_deref(),
+            Some(digest.as_str())
+        );
+        assert!(!manifest.missing_checksums());
+    }
+
+    #[test]
+    fn reads_checksums_from_an_html_listing() {
+        let sum = "d".repeat(64);
+        let page = format!(
+            "<h2>pypy3.10-v7.3.17 sha256:</h2>\n<pre class=\"literal-block\">{sum}  pypy3.10-v7.3.17-linux64.tar.bz2\n</pre>"
+        );
+        let manifest = from_sha256_listing("pypy-v7.3.17", &page);
+        let asset = manifest.asset("pypy3.10-v7.3.17-linux64.tar.bz2").unwrap();
+        assert_eq!(asset.sha256.as_deref(), Some(sum.as_str()));
+        assert_eq!(manifest.assets.len(), 1);
+    }
+
+    #[test]
+    fn caches_manifests_by_tag() {
+        let temp = tempfile::tempdir().unwrap();
+        assert!(load_cached(temp.path(), "20241016").is_none());
+        let manifest = ReleaseManifest {
+            tag: "20241016".into(),
+            published_at: Some("2024-10-16T21:04:15Z".into()),
+            assets: BTreeMap::from([(ARCHIVE.to_string(), ReleaseAsset::default())]),
+        };
+        save(temp.path(), &manifest).unwrap();
+        assert_eq!(load_cached(temp.path(), "20241016"), Some(manifest));
+    }
+}
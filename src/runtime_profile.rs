@@ -0,0 +1,307 @@
+//! `pybun profile run`: where a script spends its time.
+//!
+//! Two samplers are supported:
+//!
+//! - `py-spy` samples the running interpreter from outside and writes a
+//!   speedscope file; the hot functions are counted from its samples (the
+//!   last frame of a sample is the one running, every frame in it is on the
+//!   stack).
+//! - `cProfile` instruments every call. [`CPROFILE_DRIVER`] runs the script
+//!   under it, writes the standard pstats file, and dumps per-function
+//!   times as JSON. With `--memory` it also traces allocations with
+//!   `tracemalloc`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A function and the time the profile attributes to it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HotFunction {
+    pub function: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    /// Time spent in the function's own code.
+    pub self_ms: f64,
+    /// Time spent with the function on the stack.
+    pub total_ms: f64,
+    /// Number of calls (cProfile only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calls: Option<u64>,
+}
+
+/// Peak and retained memory traced by `tracemalloc`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MemoryReport {
+    pub peak_bytes: u64,
+    /// The allocation sites holding the most memory when the script ended.
+    pub retained: Vec<AllocationSite>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AllocationSite {
+    pub file: String,
+    pub line: u32,
+    pub size_bytes: u64,
+    pub count: u64,
+}
+
+/// A parsed profile: every function with the time it took, hottest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RuntimeProfile {
+    /// Time the profile covers.
+    pub duration_ms: f64,
+    pub functions: Vec<HotFunction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryReport>,
+}
+
+impl RuntimeProfile {
+    /// The `limit` functions with the most time in their own code.
+    pub fn hot(&self, limit: usize) -> &[HotFunction] {
+        &self.functions[..limit.min(self.functions.len())]
+    }
+}
+
+fn sort_hottest(functions: &mut [HotFunction]) {
+    functions.sort_by(|a, b| {
+        b.self_ms
+            .total_cmp(&a.self_ms)
+            .then(b.total_ms.total_cmp(&a.total_ms))
+    });
+}
+
+#[derive(Deserialize)]
+struct Speedscope {
+    shared: SpeedscopeShared,
+    profiles: Vec<SpeedscopeProfile>,
+}
+
+#[derive(Deserialize)]
+struct SpeedscopeShared {
+    frames: Vec<SpeedscopeFrame>,
+}
+
+#[derive(Deserialize)]
+struct SpeedscopeFrame {
+    name: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SpeedscopeProfile {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    samples: Vec<Vec<usize>>,
+    #[serde(default)]
+    weights: Vec<f64>,
+}
+
+/// Read the sampled profiles of a speedscope file (one per thread) taken at
+/// `rate_hz` samples per second.
+pub fn from_speedscope(content: &str, rate_hz: u32) -> Result<RuntimeProfile, String> {
+    let file: Speedscope =
+        serde_json::from_str(content).map_err(|e| format!("invalid speedscope file: {e}"))?;
+    let sample_ms = 1000.0 / f64::from(rate_hz.max(1));
+    let frames = &file.shared.frames;
+    let mut self_samples: BTreeMap<usize, f64> = BTreeMap::new();
+    let mut total_samples: BTreeMap<usize, f64> = BTreeMap::new();
+    let mut samples = 0.0;
+    for profile in file.profiles.iter().filter(|p| p.kind == "sampled") {
+        for (at, stack) in profile.samples.iter().enumerate() {
+            let weight = profile.weights.get(at).copied().unwrap_or(1.0);
+            if stack.iter().any(|&frame| frame >= frames.len()) {
+                return Err(format!(
+                    "sample {at} names a frame the file does not define"
+                ));
+            }
+            samples += weight;
+            if let Some(&leaf) = stack.last() {
+                *self_samples.entry(leaf).or_default() += weight;
+            }
+            // Recursive frames count once per sample.
+            for frame in stack.iter().collect::<BTreeSet<_>>() {
+                *total_samples.entry(*frame).or_default() += weight;
+            }
+        }
+    }
+    let mut functions: Vec<HotFunction> = total_samples
+        .into_iter()
+        .map(|(frame, total)| HotFunction {
+            function: frames[frame].name.clone(),
+            file: frames[frame].file.clone(),
+            line: frames[frame].line,
+            self_ms: self_samples.get(&frame).copied().unwrap_or_default() * sample_ms,
+            total_ms: total * sample_ms,
+            calls: None,
+        })
+        .collect();
+    sort_hottest(&mut functions);
+    Ok(RuntimeProfile {
+        duration_ms: samples * sample_ms,
+        functions,
+        memory: None,
+    })
+}
+
+#[derive(Deserialize)]
+struct CProfileReport {
+    functions: Vec<CProfileRow>,
+    memory: Option<MemoryReport>,
+}
+
+#[derive(Deserialize)]
+struct CProfileRow {
+    function: String,
+    file: String,
+    line: u32,
+    calls: u64,
+    self_s: f64,
+    total_s: f64,
+}
+
+/// Read the report [`CPROFILE_DRIVER`] writes.
+pub fn from_cprofile_report(content: &str) -> Result<RuntimeProfile, String> {
+    let report: CProfileReport =
+        serde_json::from_str(content).map_err(|e| format!("invalid cProfile report: {e}"))?;
+    let mut functions: Vec<HotFunction> = report
+        .functions
+        .into_iter()
+        // The profiler's own bookkeeping.
+        .filter(|row| !row.function.contains("_lsprof.Profiler"))
+        .map(|row| {
+            // Built-ins have no source: cProfile files them under "~", line 0.
+            let builtin = row.file == "~";
+            HotFunction {
+                function: row.function,
+                file: (!builtin).then_some(row.file),
+                line: (!builtin).then_some(row.line),
+                self_ms: row.self_s * 1000.0,
+                total_ms: row.total_s * 1000.0,
+                calls: Some(row.calls),
+            }
+        })
+        .collect();
+    sort_hottest(&mut functions);
+    Ok(RuntimeProfile {
+        duration_ms: functions.iter().map(|f| f.self_ms).sum(),
+        functions,
+        memory: report.memory,
+    })
+}
+
+/// Python program run as `python -c CPROFILE_DRIVER STATS REPORT MEMORY
+/// SCRIPT [ARGS...]`: profiles SCRIPT as `__main__`, writes the pstats file
+/// to STATS and the JSON report to REPORT, and exits with the script's
+/// status. MEMORY is `1` to trace allocations.
+pub const CPROFILE_DRIVER: &str = r#"
+import cProfile, json, os, runpy, sys
+stats_path, report_path, memory, script = sys.argv[1], sys.argv[2], sys.argv[3] == "1", sys.argv[4]
+sys.argv = sys.argv[4:]
+sys.path.insert(0, os.path.dirname(os.path.abspath(script)))
+if memory:
+    import tracemalloc
+    tracemalloc.start()
+profiler = cProfile.Profile()
+code = 0
+try:
+    profiler.runcall(runpy.run_path, script, run_name="__main__")
+except SystemExit as exc:
+    if exc.code is None or isinstance(exc.code, int):
+        code = exc.code or 0
+    else:
+        print(exc.code, file=sys.stderr)
+        code = 1
+except BaseException:
+    import traceback
+    traceback.print_exc()
+    code = 1
+report = {"functions": [], "memory": None}
+if memory:
+    snapshot = tracemalloc.take_snapshot()
+    _, peak = tracemalloc.get_traced_memory()
+    tracemalloc.stop()
+    report["memory"] = {
+        "peak_bytes": peak,
+        "retained": [
+            {
+                "file": stat.traceback[0].filename,
+                "line": stat.traceback[0].lineno,
+                "size_bytes": stat.size,
+                "count": stat.count,
+            }
+            for stat in snapshot.statistics("lineno")[:10]
+        ],
+    }
+profiler.create_stats()
+profiler.dump_stats(stats_path)
+for (file, line, function), (_, calls, self_s, total_s, _) in profiler.stats.items():
+    report["functions"].append(
+        {"function": function, "file": file, "line": line, "calls": calls, "self_s": self_s, "total_s": total_s}
+    )
+with open(report_path, "w") as out:
+    json.dump(report, out)
+sys.exit(code)
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_speedscope_samples_per_frame() {
+        let file = r#"{
+            "$schema": "https://www.speedscope.app/file-format-schema.json",
+            "shared": {"frames": [
+                {"name": "<module>", "file": "app.py", "line": 10},
+                {"name": "work", "file": "app.py", "line": 3},
+                {"name": "parse", "file": "lib.py", "line": 7}
+            ]},
+            "profiles": [
+                {"type": "sampled", "unit": "none", "samples": [[0, 1], [0, 1, 2], [0, 1, 2]], "weights": [1, 1, 2]},
+                {"type": "evented", "events": []}
+            ]
+        }"#;
+        let profile = from_speedscope(file, 100).unwrap();
+        assert_eq!(profile.duration_ms, 40.0);
+        let hot: Vec<(&str, f64, f64)> = profile
+            .functions
+            .iter()
+            .map(|f| (f.function.as_str(), f.self_ms, f.total_ms))
+            .collect();
+        assert_eq!(
+            hot,
+            [
+                ("parse", 30.0, 30.0),
+                ("work", 10.0, 40.0),
+                ("<module>", 0.0, 40.0)
+            ]
+        );
+        assert_eq!(profile.hot(1)[0].file.as_deref(), Some("lib.py"));
+
+        assert!(from_speedscope(r#"{"shared": {"frames": []}, "profiles": [{"type": "sampled", "samples": [[0]]}]}"#, 100).is_err());
+    }
+
+    #[test]
+    fn reads_the_cprofile_report() {
+        let report = r#"{
+            "functions": [
+                {"function": "work", "file": "app.py", "line": 3, "calls": 2, "self_s": 0.01, "total_s": 0.05},
+                {"function": "<built-in method time.sleep>", "file": "~", "line": 0, "calls": 4, "self_s": 0.04, "total_s": 0.04},
+                {"function": "<method 'disable' of '_lsprof.Profiler' objects>", "file": "~", "line": 0, "calls": 1, "self_s": 0.0, "total_s": 0.0}
+            ],
+            "memory": {"peak_bytes": 2048, "retained": [{"file": "app.py", "line": 4, "size_bytes": 512, "count": 3}]}
+        }"#;
+        let profile = from_cprofile_report(report).unwrap();
+        assert_eq!(profile.functions.len(), 2);
+        let sleep = &profile.functions[0];
+        assert_eq!(sleep.function, "<built-in method time.sleep>");
+        assert_eq!((sleep.file.as_deref(), sleep.calls), (None, Some(4)));
+        assert_eq!(profile.functions[1].line, Some(3));
+        assert!((profile.duration_ms - 50.0).abs() < 1e-9);
+        assert_eq!(profile.memory.unwrap().peak_bytes, 2048);
+    }
+}
@@ -0,0 +1,391 @@
+//! CPython runtimes built from source (`pybun python install --from-source`).
+//!
+//! Core contributors and projects testing against unreleased Pythons need
+//! interpreters that python-build-standalone does not ship. A source build
+//! checks out a git ref of the CPython repository, configures it with the
+//! requested flags (`--with-pydebug`, `--with-lto`, extra `configure`
+//! arguments), and installs it next to the prebuilt runtimes, so `python
+//! list`/`which`/`remove` treat it like any other version.
+//!
+//! Layout under the cache root:
+//!
+//! - `python-build/src/<ref>`: shallow checkout of the ref,
+//! - `python-build/build/<key>`: out-of-tree build directory, kept so a
+//!   rebuild of the same commit and configuration is incremental,
+//! - `python/<version>+<ref>[.debug][.lto]`: the installed runtime, with a
+//!   `build-info.json` describing the build.
+//!
+//! `<key>` hashes the commit, the configure flags and the platform. It is
+//! also part of the identity PEP 723 environment caches key on, so a debug
+//! and a release build of the same version never share a cached venv.
+
+use crate::network_policy::{self, Operation};
+use crate::runtime::RuntimeManager;
+use crate::tool_exec::ToolRun;
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Repository source builds check out, overridable with `PYBUN_CPYTHON_REPO`.
+pub const DEFAULT_CPYTHON_REPO: &str = "https://github.com/python/cpython";
+
+/// File written into the runtime directory of a source build.
+pub const BUILD_INFO_FILE: &str = "build-info.json";
+
+/// What to build.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceBuildConfig {
+    /// Tag, branch or commit of the CPython repository.
+    pub git_ref: String,
+    pub pydebug: bool,
+    pub lto: bool,
+    /// Extra arguments passed to `configure` as given.
+    pub configure_args: Vec<String>,
+}
+
+impl SourceBuildConfig {
+    /// Arguments passed to `configure`, besides `--prefix`.
+    pub fn configure_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if self.pydebug {
+            flags.push("--with-pydebug".to_string());
+        }
+        if self.lto {
+            flags.push("--with-lto".to_string());
+        }
+        flags.extend(self.configure_args.iter().cloned());
+        flags
+    }
+
+    /// Name the build is installed under, e.g. `3.14+main.debug`. Extra
+    /// configure arguments add a short hash of themselves.
+    pub fn install_name(&self, version: &str) -> String {
+        let mut name = format!("{}+{}", version, sanitize(&self.git_ref));
+        if self.pydebug {
+            name.push_str(".debug");
+        }
+        if self.lto {
+            name.push_str(".lto");
+        }
+        if !self.configure_args.is_empty() {
+            name.push('.');
+            name.push_str(&short_hash(&self.configure_args.join("\n"))[..8]);
+        }
+        name
+    }
+
+    /// Identity of a build of `commit` with this configuration on this
+    /// platform.
+    pub fn build_key(&self, commit: &str) -> String {
+        short_hash(&format!(
+            "commit={}\nflags={}\nplatform={}-{}",
+            commit,
+            self.configure_flags().join("\n"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ))
+    }
+}
+
+/// Contents of `build-info.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceBuildInfo {
+    pub repository: String,
+    pub git_ref: String,
+    pub commit: String,
+    pub configure_flags: Vec<String>,
+    pub build_key: String,
+}
+
+/// Result of [`RuntimeManager::install_from_source`].
+#[derive(Debug, Clone)]
+pub struct SourceInstall {
+    /// Name the runtime is registered under.
+    pub name: String,
+    pub python: PathBuf,
+    pub info: SourceBuildInfo,
+    /// An existing install of the same commit and configuration was kept.
+    pub reused: bool,
+}
+
+impl RuntimeManager {
+    /// Check out `config.git_ref`, build it, and install it as
+    /// [`SourceBuildConfig::install_name`]. Nothing is rebuilt when that
+    /// runtime already holds the same commit and configuration.
+    pub fn install_from_source(
+        &self,
+        version: &str,
+        config: &SourceBuildConfig,
+    ) -> Result<SourceInstall> {
+        // git would read a ref starting with `-` as an option.
+        if config.git_ref.starts_with('-') {
+            return Err(eyre!(
+                "invalid CPython ref '{}': refs cannot start with '-'",
+                config.git_ref
+            ));
+        }
+        let repository =
+            std::env::var("PYBUN_CPYTHON_REPO").unwrap_or_else(|_| DEFAULT_CPYTHON_REPO.into());
+        let build_root = self.runtimes_dir().with_file_name("python-build");
+        let src = build_root.join("src").join(sanitize(&config.git_ref));
+        self.checkout(&repository, &config.git_ref, &src)?;
+        let commit = git(&src, &["rev-parse", "HEAD"])?;
+
+        let info = SourceBuildInfo {
+            repository,
+            git_ref: config.git_ref.clone(),
+            commit: commit.clone(),
+            configure_flags: config.configure_flags(),
+            build_key: config.build_key(&commit),
+        };
+        let name = config.install_name(version);
+        let dest = self.version_dir(&name);
+        let python = self.python_binary(&name);
+        if python.exists()
+            && read_build_info(&dest).is_some_and(|existing| existing.build_key == info.build_key)
+        {
+            return Ok(SourceInstall {
+                name,
+                python,
+                info,
+                reused: true,
+            });
+        }
+
+        let build = build_root.join("build").join(&info.build_key);
+        fs::create_dir_all(&build)?;
+        if !build.join("Makefile").exists() {
+            eprintln!(
+                "Configuring CPython {} ({})...",
+                config.git_ref,
+                &commit[..commit.len().min(12)]
+            );
+            let mut configure = Command::new(src.join("configure"));
+            configure
+                .arg(format!("--prefix={}", dest.join("python").display()))
+                .args(&info.configure_flags)
+                .current_dir(&build);
+            run(&mut configure, "configure")?;
+        }
+        let jobs = std::thread::available_parallelism().map_or(1, |n| n.get());
+        eprintln!("Building CPython with {} jobs...", jobs);
+        run(
+            Command::new("make")
+                .arg(format!("-j{jobs}"))
+                .current_dir(&build),
+            "make",
+        )?;
+        if dest.exists() {
+            fs::remove_dir_all(&dest)?;
+        }
+        run(
+            Command::new("make").arg("install").current_dir(&build),
+            "make install",
+        )?;
+        if !python.exists() {
+            return Err(eyre!(
+                "Installation failed: Python binary not found at {}",
+                python.display()
+            ));
+        }
+        fs::write(
+            dest.join(BUILD_INFO_FILE),
+            serde_json::to_vec_pretty(&info)?,
+        )?;
+        eprintln!("  Installed Python {} to {}", name, dest.display());
+
+        Ok(SourceInstall {
+            name,
+            python,
+            info,
+            reused: false,
+        })
+    }
+
+    /// Fetch `git_ref` into `src` (a shallow checkout). Offline, an existing
+    /// checkout is used as it is.
+    fn checkout(&self, repository: &str, git_ref: &str, src: &Path) -> Result<()> {
+        let cloned = src.join(".git").exists();
+        if self.is_offline() {
+            return if cloned {
+                Ok(())
+            } else {
+                Err(eyre!(
+                    "CPython {} is not checked out and offline mode is enabled",
+                    git_ref
+                ))
+            };
+        }
+        network_policy::check_url(Operation::Python, repository)?;
+        if !cloned {
+            fs::create_dir_all(src)?;
+            git(src, &["init", "--quiet"])?;
+        }
+        eprintln!("Fetching CPython {} from {}...", git_ref, repository);
+        git(
+            src,
+            &[
+                "fetch", "--quiet", "--depth", "1", "--", repository, git_ref,
+            ],
+        )
+        .wrap_err_with(|| format!("failed to fetch {git_ref} from {repository}"))?;
+        git(src, &["checkout", "--quiet", "--force", "FETCH_HEAD"])?;
+        Ok(())
+    }
+}
+
+/// Build information of the source-built runtime `python` belongs to, if
+/// it is one. Symlinks (such as a venv's interpreter) are followed.
+pub fn source_build_info(python: &Path) -> Option<SourceBuildInfo> {
+    let python = python.canonicalize().ok()?;
+    // <runtime>/python/bin/python3
+    python.ancestors().nth(3).and_then(read_build_info)
+}
+
+/// Interpreter identity for environment cache keys: the version, plus the
+/// build key of a source build.
+pub fn cache_identity(python: &Path, version: &str) -> String {
+    match source_build_info(python) {
+        Some(info) => format!("{}+{}", version, info.build_key),
+        None => version.to_string(),
+    }
+}
+
+fn read_build_info(runtime_dir: &Path) -> Option<SourceBuildInfo> {
+    let data = fs::read(runtime_dir.join(BUILD_INFO_FILE)).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(dir);
+    let label = format!("git {}", args.first().copied().unwrap_or_default());
+    let output = ToolRun::new(label, &mut cmd).idempotent().run()?;
+    Ok(output.stdout_text())
+}
+
+/// Run a build step, reporting the end of its output when it fails.
+fn run(command: &mut Command, step: &str) -> Result<()> {
+    let output = command
+        .output()
+        .wrap_err_with(|| format!("failed to run {step}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+    Err(eyre!(
+        "{} failed ({}):\n{}",
+        step,
+        output.status,
+        tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+    ))
+}
+
+/// A git ref as a single path component.
+pub(crate) fn sanitize(git_ref: &str) -> String {
+    git_ref
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn short_hash(text: &str) -> String {
+    hex::encode(&Sha256::digest(text.as_bytes())[..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SourceBuildConfig {
+        SourceBuildConfig {
+            git_ref: "feature/jit".to_string(),
+            pydebug: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn install_name_reflects_ref_and_flags() {
+        assert_eq!(config().install_name("3.14"), "3.14+feature-jit.debug");
+        let lto = SourceBuildConfig {
+            git_ref: "v3.13.1".to_string(),
+            lto: true,
+            configure_args: vec!["--enable-shared".to_string()],
+            ..Default::default()
+        };
+        let name = lto.install_name("3.13.1");
+        assert!(name.starts_with("3.13.1+v3.13.1.lto."), "{name}");
+        assert_eq!(
+            lto.configure_flags(),
+            vec!["--with-lto".to_string(), "--enable-shared".to_string()]
+        );
+    }
+
+    #[test]
+    fn build_key_changes_with_commit_and_flags() {
+        let debug = config();
+        let release = SourceBuildConfig {
+            pydebug: false,
+            ..config()
+        };
+        assert_eq!(debug.build_key("abc"), debug.build_key("abc"));
+        assert_ne!(debug.build_key("abc"), debug.build_key("def"));
+        assert_ne!(debug.build_key("abc"), release.build_key("abc"));
+    }
+
+    #[test]
+    fn cache_identity_includes_build_key_of_source_builds() {
+        let temp = tempfile::tempdir().unwrap();
+        let runtime = temp.path().join("3.14+main.debug");
+        let bin = runtime.join("python").join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        fs::write(bin.join("python3"), "").unwrap();
+        let python = bin.join("python3");
+        assert_eq!(cache_identity(&python, "3.14.0a1"), "3.14.0a1");
+
+        let info = SourceBuildInfo {
+            repository: DEFAULT_CPYTHON_REPO.to_string(),
+            git_ref: "main".to_string(),
+            commit: "abc".to_string(),
+            configure_flags: vec!["--with-pydebug".to_string()],
+            build_key: "0123456789abcdef".to_string(),
+        };
+        fs::write(
+            runtime.join(BUILD_INFO_FILE),
+            serde_json::to_vec(&info).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(source_build_info(&python), Some(info));
+        assert_eq!(
+            cache_identity(&python, "3.14.0a1"),
+            "3.14.0a1+0123456789abcdef"
+        );
+    }
+
+    #[test]
+    fn refs_that_look_like_options_are_rejected() {
+        let temp = tempfile::tempdir().unwrap();
+        let manager = RuntimeManager::new(crate::cache::Cache::with_root(temp.path()));
+        let config = SourceBuildConfig {
+            git_ref: "--upload-pack=touch pwned".to_string(),
+            ..Default::default()
+        };
+        let error = manager.install_from_source("3.14", &config).unwrap_err();
+        assert!(
+            error.to_string().contains("cannot start with '-'"),
+            "{error}"
+        );
+        assert!(!temp.path().join("python-build").exists());
+    }
+}
@@ -0,0 +1,217 @@
+//! Which environments use each managed runtime.
+//!
+//! A virtual environment created from a managed runtime records the
+//! runtime's `bin` directory as `home` in its `pyvenv.cfg`, and stops
+//! working once the runtime is removed. [`dependents`] finds everything that
+//! relies on an installed runtime:
+//!
+//! - project venvs of registered projects (see [`crate::project_registry`])
+//!   and of the current project,
+//! - cached PEP 723 script environments (`pep723-envs/<dir>/venv`),
+//! - `pybun x` tool environments (`tools/<dir>/venv`),
+//! - projects whose `.python-version` pin selects the runtime.
+//!
+//! `pybun python remove` refuses to remove a runtime that still has
+//! dependents unless `--force` is given, and `pybun python list` reports them
+//! as `used_by`.
+
+use crate::cache::Cache;
+use crate::pep723_cache::Pep723Cache;
+use crate::runtime::RuntimeManager;
+use crate::tool_env::ToolEnvCache;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How something depends on a runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependentKind {
+    ProjectVenv,
+    ScriptEnv,
+    ToolEnv,
+    /// A `.python-version` pin (see [`crate::python_pin`]).
+    Pin,
+}
+
+impl DependentKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DependentKind::ProjectVenv => "project venv",
+            DependentKind::ScriptEnv => "script env",
+            DependentKind::ToolEnv => "tool env",
+            DependentKind::Pin => "pin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Dependent {
+    pub kind: DependentKind,
+    /// The environment, or the `.python-version` file of a pin.
+    pub path: PathBuf,
+    /// Project root, for project venvs and pins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<PathBuf>,
+}
+
+/// A project to check: its root and the environment it was installed into,
+/// if known. The project's `.pybun/venv` or `.venv` is checked either way.
+#[derive(Debug, Clone)]
+pub struct ProjectRef {
+    pub root: PathBuf,
+    pub env: Option<PathBuf>,
+}
+
+/// Dependents of every installed runtime of `manager`, keyed by runtime
+/// name. Runtimes nothing depends on are absent.
+pub fn dependents(
+    manager: &RuntimeManager,
+    cache: &Cache,
+    projects: &[ProjectRef],
+) -> BTreeMap<String, Vec<Dependent>> {
+    let installed = manager.list_installed().unwrap_or_default();
+    let runtime_of_venv = |venv: &Path| -> Option<String> {
+        let home = crate::env::venv_home(venv)?;
+        installed
+            .iter()
+            .find(|name| home.starts_with(manager.version_dir(name)))
+            .cloned()
+    };
+
+    let mut found: BTreeMap<String, BTreeSet<Dependent>> = BTreeMap::new();
+    let mut add = |runtime: String, dependent: Dependent| {
+        found.entry(runtime).or_default().insert(dependent);
+    };
+
+    for project in projects {
+        let venvs = project
+            .env
+            .iter()
+            .cloned()
+            .chain(crate::env::find_project_venv(&project.root));
+        for venv in venvs.collect::<BTreeSet<_>>() {
+            if let Some(runtime) = runtime_of_venv(&venv) {
+                let dependent = Dependent {
+                    kind: DependentKind::ProjectVenv,
+                    path: venv,
+                    project: Some(project.root.clone()),
+                };
+                add(runtime, dependent);
+            }
+        }
+        if let Some(pin) = crate::python_pin::find(&project.root)
+            && let Some(runtime) = manager.find_installed(&pin.version)
+        {
+            let dependent = Dependent {
+                kind: DependentKind::Pin,
+                path: pin.file,
+                project: Some(project.root.clone()),
+            };
+            add(runtime, dependent);
+        }
+    }
+
+    let cached = [
+        (
+            DependentKind::ScriptEnv,
+            Pep723Cache::with_root(cache.root()).envs_dir(),
+        ),
+        (
+            DependentKind::ToolEnv,
+            ToolEnvCache::with_root(cache.root()).tools_dir(),
+        ),
+    ];
+    for (kind, dir) in cached {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let venv = entry.path().join("venv");
+            if let Some(runtime) = runtime_of_venv(&venv) {
+                let dependent = Dependent {
+                    kind,
+                    path: venv,
+                    project: None,
+                };
+                add(runtime, dependent);
+            }
+        }
+    }
+
+    found
+        .into_iter()
+        .map(|(runtime, dependents)| (runtime, dependents.into_iter().collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn venv(path: &Path, home: &Path) {
+        fs::create_dir_all(path.join("bin")).unwrap();
+        fs::write(path.join("bin").join("python"), "").unwrap();
+        fs::write(
+            path.join("pyvenv.cfg"),
+            format!("home = {}\n", home.display()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn finds_venvs_and_pins_using_each_runtime() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::with_root(temp.path().join("cache"));
+        let manager = RuntimeManager::new(cache.clone());
+        for name in ["3.11.10", "3.12.7"] {
+            let python = manager.python_binary(name);
+            fs::create_dir_all(python.parent().unwrap()).unwrap();
+            fs::write(&python, "").unwrap();
+        }
+        let home_312 = manager
+            .python_binary("3.12.7")
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        let project = temp.path().join("app");
+        venv(&project.join(".pybun").join("venv"), &home_312);
+        fs::write(project.join(".python-version"), "3.11\n").unwrap();
+        let script_env = cache
+            .root()
+            .join("pep723-envs")
+            .join("report-0123")
+            .join("venv");
+        venv(&script_env, &home_312);
+        venv(
+            &cache.root().join("tools").join("ruff-ab12").join("venv"),
+            Path::new("/usr/bin"),
+        );
+
+        let found = dependents(
+            &manager,
+            &cache,
+            &[ProjectRef {
+                root: project.clone(),
+                env: None,
+            }],
+        );
+        let kinds = |runtime: &str| -> Vec<DependentKind> {
+            found[runtime].iter().map(|d| d.kind).collect()
+        };
+        assert_eq!(
+            kinds("3.12.7"),
+            [DependentKind::ProjectVenv, DependentKind::ScriptEnv]
+        );
+        assert_eq!(found["3.12.7"][1].path, script_env);
+        assert_eq!(kinds("3.11.10"), [DependentKind::Pin]);
+        assert_eq!(
+            found["3.11.10"][0].project.as_deref(),
+            Some(project.as_path())
+        );
+        assert_eq!(found.len(), 2);
+    }
+}
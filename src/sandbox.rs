@@ -1,9 +1,12 @@
+use crate::network_policy;
+use crate::payload::CapturedOutput;
 use color_eyre::eyre::{Result, eyre};
 use std::fs;
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::{Command, ExitStatus};
+use std::time::Duration;
 use tempfile::TempDir;
 
 /// Default wall-clock timeout (in seconds) applied to sandboxed runs.
@@ -40,6 +43,13 @@ pub struct SandboxConfig {
     pub max_processes: u64,
     /// Maximum file size that may be written, in megabytes (Unix only; 0 = unlimited).
     pub file_size_limit_mb: u64,
+    /// JSON host allowlist from the project's network policy, enforced when
+    /// `allow_network` is set. `None` = any host.
+    pub allow_hosts: Option<String>,
+    /// Directories the kernel confines writes to when `allow_write` is empty,
+    /// besides the temp dir: the project directory and the environment (see
+    /// [`crate::sandbox_os`]). Empty = only the shim's default deny applies.
+    pub confine_write: Vec<PathBuf>,
 }
 
 impl Default for SandboxConfig {
@@ -54,6 +64,8 @@ impl Default for SandboxConfig {
             cpu_limit_secs: 0,
             max_processes: DEFAULT_SANDBOX_MAX_PROCESSES,
             file_size_limit_mb: DEFAULT_SANDBOX_FILE_SIZE_LIMIT_MB,
+            allow_hosts: None,
+            confine_write: Vec::new(),
         }
     }
 }
@@ -135,6 +147,121 @@ pub fn is_credential_env_name(name: &str) -> bool {
         || upper.ends_with("_KEY")
 }
 
+/// Environment variable names `pybun x` passes to a tool on top of
+/// [`default_safe_env_vars`]: proxy and CA settings so tools can reach the
+/// network the way the host does, terminal hints, XDG base directories and
+/// the Windows variables processes need to start.
+pub fn tool_env_vars() -> &'static [&'static str] {
+    &[
+        "HTTP_PROXY",
+        "HTTPS_PROXY",
+        "NO_PROXY",
+        "ALL_PROXY",
+        "http_proxy",
+        "https_proxy",
+        "no_proxy",
+        "all_proxy",
+        "SSL_CERT_FILE",
+        "SSL_CERT_DIR",
+        "REQUESTS_CA_BUNDLE",
+        "COLUMNS",
+        "LINES",
+        "NO_COLOR",
+        "FORCE_COLOR",
+        "CLICOLOR",
+        "XDG_CONFIG_HOME",
+        "XDG_CACHE_HOME",
+        "XDG_DATA_HOME",
+        "XDG_RUNTIME_DIR",
+        "SYSTEMROOT",
+        "SYSTEMDRIVE",
+        "WINDIR",
+        "COMSPEC",
+        "PATHEXT",
+        "USERPROFILE",
+        "APPDATA",
+        "LOCALAPPDATA",
+    ]
+}
+
+/// Which inherited environment variables a `pybun x` tool receives.
+///
+/// Tools only get [`default_safe_env_vars`] and [`tool_env_vars`] plus the
+/// names the user opts in with `--pass-env` (`NAME`, a `PREFIX*` pattern, or
+/// `*` for everything). Every other variable is withheld. Only names are
+/// recorded here, never values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct EnvPassthrough {
+    /// Inherited variables passed to the tool, sorted.
+    pub passed: Vec<String>,
+    /// Inherited variables withheld from the tool, sorted.
+    pub withheld: Vec<String>,
+    /// The `--pass-env` patterns in effect.
+    pub pass_env: Vec<String>,
+}
+
+fn env_name_eq(a: &str, b: &str) -> bool {
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+fn pass_env_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => {
+            name.len() >= prefix.len()
+                && name.is_char_boundary(prefix.len())
+                && env_name_eq(&name[..prefix.len()], prefix)
+        }
+        None => env_name_eq(pattern, name),
+    }
+}
+
+impl EnvPassthrough {
+    /// Split the variable names in `names` by the tool policy.
+    pub fn from_names(names: impl IntoIterator<Item = String>, pass_env: &[String]) -> Self {
+        let mut policy = Self {
+            pass_env: pass_env.to_vec(),
+            ..Self::default()
+        };
+        for name in names {
+            let allowed = default_safe_env_vars()
+                .iter()
+                .chain(tool_env_vars())
+                .any(|safe| env_name_eq(safe, &name))
+                || pass_env.iter().any(|p| pass_env_matches(p, &name));
+            if allowed {
+                policy.passed.push(name);
+            } else {
+                policy.withheld.push(name);
+            }
+        }
+        policy.passed.sort();
+        policy.withheld.sort();
+        policy
+    }
+
+    /// Apply the policy to the current process environment.
+    pub fn from_env(pass_env: &[String]) -> Self {
+        Self::from_names(
+            std::env::vars_os().filter_map(|(name, _)| name.into_string().ok()),
+            pass_env,
+        )
+    }
+
+    /// Replace `cmd`'s inherited environment with the passed variables.
+    pub fn apply(&self, cmd: &mut Command) {
+        cmd.env_clear();
+        for name in &self.passed {
+            if let Some(value) = std::env::var_os(name) {
+                cmd.env(name, value);
+            }
+        }
+    }
+}
+
 /// Returns the default system-critical paths that should be denied for writes
 /// when sandbox mode is active and no explicit `--allow-write` policy is set.
 pub fn default_system_deny_write_paths() -> Vec<String> {
@@ -174,6 +301,9 @@ pub struct SandboxAudit {
     pub blocked_network: u64,
     pub blocked_file_reads: u64,
     pub blocked_file_writes: u64,
+    /// Hosts refused by the network allowlist (see `SandboxConfig::allow_hosts`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_hosts: Vec<String>,
 }
 
 /// Guard that keeps sandbox assets (sitecustomize) alive for the child process.
@@ -181,7 +311,7 @@ pub struct SandboxAudit {
 pub struct SandboxGuard {
     _tempdir: TempDir,
     _audit_tempdir: TempDir,
-    enforcement: &'static str,
+    enforcement: String,
     audit_file: PathBuf,
     /// Default system-critical paths denied for writes (empty when explicit allow_write is set).
     pub default_deny_write: Vec<String>,
@@ -197,7 +327,7 @@ pub struct SandboxGuard {
 impl SandboxGuard {
     /// Name of the sandbox enforcement strategy used.
     pub fn enforcement(&self) -> &str {
-        self.enforcement
+        &self.enforcement
     }
 
     /// Read the audit report written by the sandboxed process on exit.
@@ -212,12 +342,45 @@ impl SandboxGuard {
 
 /// Apply a lightweight sandbox to a Python command by injecting a `sitecustomize`
 /// module that blocks subprocess creation, network access, and enforces
-/// filesystem read/write policies.
+/// filesystem read/write policies. Where the OS supports it, the kernel
+/// enforces the network and write policies too (see [`crate::sandbox_os`]).
 pub fn apply_python_sandbox(cmd: &mut Command, config: SandboxConfig) -> Result<SandboxGuard> {
     let tempdir = tempfile::tempdir()?;
     let audit_tempdir = tempfile::Builder::new()
         .prefix("pybun-sandbox-audit-")
         .tempdir()?;
+
+    // Kernel confinement goes first: on macOS it replaces `cmd` with a
+    // `sandbox-exec` wrapper, which the settings below then apply to.
+    let writable = if !config.allow_write.is_empty() {
+        let allowed = config.allow_write.iter().map(PathBuf::from);
+        Some(
+            allowed
+                .chain(helper_dirs(&tempdir, &audit_tempdir))
+                .collect(),
+        )
+    } else if !config.confine_write.is_empty() {
+        let mut writable = config.confine_write.clone();
+        writable.push(std::env::temp_dir());
+        writable.extend(helper_dirs(&tempdir, &audit_tempdir));
+        Some(writable)
+    } else {
+        None
+    };
+    let os_policy = crate::sandbox_os::OsPolicy {
+        deny_network: !network_allowed(config.allow_network),
+        writable,
+    };
+    let mut enforcement = String::from("python-sitecustomize");
+    for mechanism in crate::sandbox_os::confine(cmd, &os_policy) {
+        enforcement.push('+');
+        enforcement.push_str(mechanism);
+    }
+    // No kernel mechanism confines reads, so an `allow_read` policy holds
+    // against Python code only.
+    if !config.allow_read.is_empty() {
+        enforcement.push_str("+reads-shim-only");
+    }
     let audit_file = audit_tempdir.path().join("audit.json");
     fs::write(&audit_file, "{}")
         .map_err(|e| eyre!("failed to initialize sandbox audit file: {e}"))?;
@@ -265,17 +428,16 @@ pub fn apply_python_sandbox(cmd: &mut Command, config: SandboxConfig) -> Result<
         .map_err(|e| eyre!("failed to join PYTHONPATH entries for sandbox: {e}"))?;
     cmd.env("PYTHONPATH", joined);
 
-    // Note: std::env::var reads from the *parent process* environment, not from `cmd`.
-    // env_clear() above only strips the *child's* inherited env, so reading
-    // PYBUN_SANDBOX_ALLOW_NETWORK here correctly reflects the caller's intent.
-    let allow_network = config.allow_network
-        || std::env::var("PYBUN_SANDBOX_ALLOW_NETWORK")
-            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-            .unwrap_or(false);
+    let allow_network = network_allowed(config.allow_network);
 
     cmd.env("PYBUN_SANDBOX", "1");
     if allow_network {
         cmd.env("PYBUN_SANDBOX_ALLOW_NETWORK", "1");
+        if let Some(hosts) = &config.allow_hosts {
+            network_policy::write_guard_module(tempdir.path())
+                .map_err(|e| eyre!("failed to write network guard: {e}"))?;
+            cmd.env(network_policy::ALLOW_HOSTS_ENV, hosts);
+        }
     } else {
         cmd.env_remove("PYBUN_SANDBOX_ALLOW_NETWORK");
     }
@@ -401,7 +563,7 @@ pub fn apply_python_sandbox(cmd: &mut Command, config: SandboxConfig) -> Result<
     Ok(SandboxGuard {
         _tempdir: tempdir,
         _audit_tempdir: audit_tempdir,
-        enforcement: "python-sitecustomize",
+        enforcement,
         audit_file,
         default_deny_write: default_deny,
         allow_env: filtered_allow_env,
@@ -410,13 +572,33 @@ pub fn apply_python_sandbox(cmd: &mut Command, config: SandboxConfig) -> Result<
     })
 }
 
+/// Whether a sandboxed process may use the network: `allow_network` or
+/// `PYBUN_SANDBOX_ALLOW_NETWORK`.
+///
+/// Note: std::env::var reads from the *parent process* environment, so this
+/// reflects the caller's intent even after the child's env was cleared.
+fn network_allowed(allow_network: bool) -> bool {
+    allow_network
+        || std::env::var("PYBUN_SANDBOX_ALLOW_NETWORK")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+}
+
+/// The shim and audit directories, which the sandboxed process writes to.
+fn helper_dirs(tempdir: &TempDir, audit_tempdir: &TempDir) -> [PathBuf; 2] {
+    [
+        tempdir.path().to_path_buf(),
+        audit_tempdir.path().to_path_buf(),
+    ]
+}
+
 /// Outcome of executing a sandboxed command, possibly subject to a wall-clock timeout.
 pub enum SandboxExecOutcome {
     /// The process exited on its own (or no timeout was configured).
     Completed {
         status: ExitStatus,
-        stdout: Option<Vec<u8>>,
-        stderr: Option<Vec<u8>>,
+        stdout: Option<CapturedOutput>,
+        stderr: Option<CapturedOutput>,
     },
     /// The process was killed because it exceeded the configured timeout.
     TimedOut,
@@ -424,7 +606,7 @@ pub enum SandboxExecOutcome {
 
 /// Run `cmd` to completion, optionally capturing stdout/stderr, killing it if
 /// it exceeds `timeout_secs` (0 = unlimited). Delegates to
-/// [`crate::proc_exec::spawn_with_timeout`], the shared spawn/poll/kill helper
+/// [`crate::proc_exec::spawn_capturing`], the shared spawn/poll/kill helper
 /// also used by `test_executor::run_with_timeout` (Issue #273), so a chatty
 /// process can't deadlock on a full pipe buffer while we wait for exit.
 pub fn execute_sandboxed(
@@ -432,8 +614,8 @@ pub fn execute_sandboxed(
     timeout_secs: u64,
     capture: bool,
 ) -> std::io::Result<SandboxExecOutcome> {
-    let timeout = (timeout_secs > 0).then_some(timeout_secs);
-    match crate::proc_exec::spawn_with_timeout(cmd, timeout, capture)? {
+    let timeout = (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs));
+    match crate::proc_exec::spawn_capturing(cmd, timeout, capture)? {
         crate::proc_exec::ProcExecOutcome::Completed {
             status,
             stdout,
@@ -481,8 +663,8 @@ pub fn cpu_limit_exceeded(_status: &ExitStatus) -> bool {
 /// Outcome of [`execute_with_optional_sandbox`]: the finished (or timed-out) process.
 pub struct SandboxedExecution {
     pub status: ExitStatus,
-    pub stdout: Option<Vec<u8>>,
-    pub stderr: Option<Vec<u8>>,
+    pub stdout: Option<CapturedOutput>,
+    pub stderr: Option<CapturedOutput>,
     pub timed_out: bool,
 }
 
@@ -517,8 +699,8 @@ pub fn execute_with_optional_sandbox(
             }
         }
     } else if capture_output {
-        let output = cmd.output()?;
-        (output.status, Some(output.stdout), Some(output.stderr))
+        let (status, stdout, stderr) = crate::proc_exec::output_capturing(cmd)?;
+        (status, Some(stdout), Some(stderr))
     } else {
         let status = cmd.status()?;
         (status, None, None)
@@ -599,6 +781,7 @@ _audit = {
     "blocked_network": 0,
     "blocked_file_reads": 0,
     "blocked_file_writes": 0,
+    "blocked_hosts": [],
 }
 
 
@@ -717,6 +900,17 @@ def _block_network():
         socket.socketpair = _blocked
 
 
+def _restrict_network():
+    import _pybun_network_guard
+
+    def _blocked(host):
+        _audit["blocked_network"] += 1
+        if host not in _audit["blocked_hosts"]:
+            _audit["blocked_hosts"].append(host)
+
+    _pybun_network_guard.install_from_env(_blocked)
+
+
 def _patch_filesystem():
     def _check_write_path(path, action):
         if not isinstance(path, (str, bytes, os.PathLike)):
@@ -808,6 +1002,8 @@ try:
     _block_subprocesses()
     if not ALLOW_NETWORK:
         _block_network()
+    elif "PYBUN_NETWORK_ALLOW_HOSTS" in os.environ:
+        _restrict_network()
     if _HAS_READ_POLICY or _HAS_WRITE_POLICY or _HAS_DEFAULT_DENY_WRITE:
         _patch_filesystem()
     sys.stderr.write(
@@ -896,6 +1092,32 @@ mod tests {
         assert!(!safe.contains(&"DATABASE_URL"));
     }
 
+    #[test]
+    fn tool_env_passes_standard_vars_and_withholds_the_rest() {
+        let names = ["PATH", "HTTPS_PROXY", "GITHUB_TOKEN", "DATABASE_URL", "CI"].map(String::from);
+        let policy = EnvPassthrough::from_names(names.clone(), &[]);
+        assert_eq!(policy.passed, ["HTTPS_PROXY", "PATH"]);
+        assert_eq!(policy.withheld, ["CI", "DATABASE_URL", "GITHUB_TOKEN"]);
+
+        // --pass-env opts in by name or prefix, credential-shaped or not.
+        let policy = EnvPassthrough::from_names(names.clone(), &["CI".into(), "GITHUB_*".into()]);
+        assert_eq!(policy.withheld, ["DATABASE_URL"]);
+        assert_eq!(policy.pass_env, ["CI", "GITHUB_*"]);
+
+        let policy = EnvPassthrough::from_names(names, &["*".into()]);
+        assert!(policy.withheld.is_empty());
+        assert_eq!(policy.passed.len(), 5);
+    }
+
+    #[test]
+    fn tool_env_vars_exclude_credential_shaped_names() {
+        assert!(
+            !tool_env_vars()
+                .iter()
+                .any(|name| is_credential_env_name(name))
+        );
+    }
+
     #[test]
     fn sandbox_config_allow_env_defaults_to_empty() {
         let config = SandboxConfig::default();
@@ -952,10 +1174,7 @@ mod tests {
         let result = execute_with_optional_sandbox(&mut cmd, None, true)
             .expect("plain command should execute");
         assert!(result.status.success());
-        assert_eq!(
-            String::from_utf8_lossy(result.stdout.as_deref().unwrap_or_default()).trim(),
-            "hello"
-        );
+        assert_eq!(result.stdout.unwrap_or_default().text.trim(), "hello");
         assert!(!result.timed_out);
     }
 
@@ -986,7 +1205,7 @@ mod tests {
         assert!(!result.timed_out);
         assert!(result.status.success());
         assert_eq!(
-            String::from_utf8_lossy(result.stdout.as_deref().unwrap_or_default()).trim(),
+            result.stdout.unwrap_or_default().text.trim(),
             "from-sandbox"
         );
     }
@@ -0,0 +1,432 @@
+//! Kernel-level confinement for `--sandbox`.
+//!
+//! The `sitecustomize` shim of [`crate::sandbox`] blocks subprocesses,
+//! sockets and file access from Python code, but native extensions and
+//! `ctypes` can call the OS directly. Where the OS offers a mechanism, the
+//! sandboxed process is confined by the kernel as well:
+//!
+//! - Linux: a seccomp filter makes `socket()` fail with `EACCES` for every
+//!   address family but `AF_UNIX` (and refuses `io_uring`) when network
+//!   access is off, and Landlock (Linux 5.13+) limits writes to the
+//!   writable directories.
+//! - macOS: the command runs under `sandbox-exec` with a Seatbelt profile
+//!   denying IP networking and writes outside the writable directories.
+//!
+//! Reads are not confined by the kernel: `--allow-read` is enforced by the
+//! shim alone, which `sandbox.enforcement` reports as `reads-shim-only`.
+//!
+//! A mechanism the running kernel lacks is skipped and left to the shim;
+//! [`confine`] returns the ones that apply, which `--format=json` reports
+//! in `sandbox.enforcement`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// What the kernel should enforce for a sandboxed process.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsPolicy {
+    /// Refuse IP sockets. Unset when network access (or a host allowlist,
+    /// which only the shim can enforce) is requested.
+    pub deny_network: bool,
+    /// Paths writes are confined to. `None` leaves writes unrestricted.
+    /// `/dev/null`, `/dev/tty` and `/dev/shm` are always writable.
+    pub writable: Option<Vec<PathBuf>>,
+}
+
+/// Device paths a confined process may always write to.
+const DEVICE_PATHS: &[&str] = &["/dev/null", "/dev/tty", "/dev/shm"];
+
+/// The existing path a write rule for `path` is attached to: `path` itself,
+/// or for a file that does not exist yet, its nearest existing ancestor.
+/// Relative paths are resolved against the current directory, symlinks
+/// resolved.
+fn rule_path(path: &Path) -> Option<PathBuf> {
+    let absolute = std::path::absolute(path).ok()?;
+    absolute
+        .ancestors()
+        .find(|candidate| candidate.exists())
+        .and_then(|existing| existing.canonicalize().ok())
+}
+
+/// Confine `cmd` according to `policy`. Must run before the command's
+/// environment and `pre_exec` hooks are set up: on macOS `cmd` is replaced
+/// by a `sandbox-exec` command running the original program. Returns the
+/// mechanisms applied (`seccomp`, `landlock`, `sandbox-exec`).
+pub fn confine(cmd: &mut Command, policy: &OsPolicy) -> Vec<&'static str> {
+    imp::confine(cmd, policy)
+}
+
+/// Seatbelt (SBPL) profile enforcing `policy`, for `sandbox-exec -p`.
+pub fn seatbelt_profile(policy: &OsPolicy) -> String {
+    let mut profile = String::from("(version 1)\n(allow default)\n");
+    if policy.deny_network {
+        profile.push_str("(deny network-outbound (remote ip))\n");
+        profile.push_str("(deny network-bind (local ip))\n");
+    }
+    if let Some(writable) = &policy.writable {
+        profile.push_str("(deny file-write*)\n(allow file-write*");
+        for path in writable.iter().filter_map(|path| rule_path(path)) {
+            profile.push_str(&format!("\n  (subpath {})", sbpl_string(&path)));
+        }
+        for device in DEVICE_PATHS {
+            profile.push_str(&format!("\n  (subpath {})", sbpl_string(Path::new(device))));
+        }
+        profile.push_str("\n  (regex #\"^/dev/fd/\"))\n");
+    }
+    profile
+}
+
+fn sbpl_string(path: &Path) -> String {
+    let escaped = path
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::OsPolicy;
+    use std::path::Path;
+    use std::process::Command;
+
+    const SANDBOX_EXEC: &str = "/usr/bin/sandbox-exec";
+
+    pub fn confine(cmd: &mut Command, policy: &OsPolicy) -> Vec<&'static str> {
+        if (!policy.deny_network && policy.writable.is_none()) || !Path::new(SANDBOX_EXEC).exists()
+        {
+            return Vec::new();
+        }
+        let mut wrapped = Command::new(SANDBOX_EXEC);
+        wrapped
+            .arg("-p")
+            .arg(super::seatbelt_profile(policy))
+            .arg(cmd.get_program())
+            .args(cmd.get_args());
+        if let Some(dir) = cmd.get_current_dir() {
+            wrapped.current_dir(dir);
+        }
+        for (name, value) in cmd.get_envs() {
+            match value {
+                Some(value) => wrapped.env(name, value),
+                None => wrapped.env_remove(name),
+            };
+        }
+        *cmd = wrapped;
+        vec!["sandbox-exec"]
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::{DEVICE_PATHS, OsPolicy};
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+    const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+    const LANDLOCK_ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    /// `REMOVE_DIR` through `MAKE_SYM` (ABI 1).
+    const LANDLOCK_ACCESS_FS_MODIFY_DIR: u64 = 0b1_1111_1111 << 4;
+    const LANDLOCK_ACCESS_FS_REFER: u64 = 1 << 13;
+    const LANDLOCK_ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+
+    #[repr(C)]
+    struct LandlockRulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct LandlockPathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    /// Landlock ABI version of the running kernel, if Landlock is enabled.
+    fn landlock_abi() -> Option<i64> {
+        // SAFETY: querying the ABI version takes no pointers.
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<LandlockRulesetAttr>(),
+                0usize,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        (abi > 0).then_some(abi)
+    }
+
+    /// Write rights handled at `abi`: for directories, and for single files.
+    fn write_access(abi: i64) -> (u64, u64) {
+        let mut file = LANDLOCK_ACCESS_FS_WRITE_FILE;
+        if abi >= 3 {
+            file |= LANDLOCK_ACCESS_FS_TRUNCATE;
+        }
+        let mut dir = file | LANDLOCK_ACCESS_FS_MODIFY_DIR;
+        if abi >= 2 {
+            dir |= LANDLOCK_ACCESS_FS_REFER;
+        }
+        (dir, file)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xC000_003E;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xC000_00B7;
+    /// System calls of the x32 ABI have this bit set on x86_64.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    /// BPF program refusing non-`AF_UNIX` sockets, `io_uring` (which can
+    /// open sockets itself) and system calls of other ABIs.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn socket_filter() -> Vec<libc::sock_filter> {
+        use libc::{BPF_ABS, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W};
+        fn stmt(code: u16, k: u32) -> libc::sock_filter {
+            jump(code, k, 0, 0)
+        }
+        fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+            libc::sock_filter { code, jt, jf, k }
+        }
+        let load = (BPF_LD | BPF_W | BPF_ABS) as u16;
+        let jeq = (BPF_JMP | BPF_JEQ | BPF_K) as u16;
+        let jge = (BPF_JMP | BPF_JGE | BPF_K) as u16;
+        let ret = (BPF_RET | BPF_K) as u16;
+        let deny = libc::SECCOMP_RET_ERRNO | (libc::EACCES as u32 & libc::SECCOMP_RET_DATA);
+        // Offsets into `struct seccomp_data`: nr, arch, args[0] (low half).
+        let (nr, arch, arg0) = (0, 4, 16);
+        vec![
+            stmt(load, arch),
+            jump(jeq, AUDIT_ARCH, 1, 0),
+            stmt(ret, deny),
+            stmt(load, nr),
+            jump(jge, X32_SYSCALL_BIT, 5, 0),
+            jump(jeq, libc::SYS_io_uring_setup as u32, 4, 0),
+            jump(jeq, libc::SYS_socket as u32, 0, 2),
+            stmt(load, arg0),
+            jump(jeq, libc::AF_UNIX as u32, 0, 1),
+            stmt(ret, libc::SECCOMP_RET_ALLOW),
+            stmt(ret, deny),
+        ]
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn socket_filter() -> Vec<libc::sock_filter> {
+        Vec::new()
+    }
+
+    fn seccomp_supported() -> bool {
+        // SAFETY: PR_GET_SECCOMP takes no pointers; it fails with EINVAL on
+        // kernels built without seccomp.
+        unsafe { libc::prctl(libc::PR_GET_SECCOMP) >= 0 }
+    }
+
+    pub fn confine(cmd: &mut Command, policy: &OsPolicy) -> Vec<&'static str> {
+        let mut applied = Vec::new();
+        let filter = if policy.deny_network && seccomp_supported() {
+            socket_filter()
+        } else {
+            Vec::new()
+        };
+        if !filter.is_empty() {
+            applied.push("seccomp");
+        }
+
+        // (handled rights, [(path, allowed rights)]), prepared before the
+        // fork: the child may only make system calls.
+        let landlock = policy.writable.as_ref().and_then(|writable| {
+            let (dir_access, file_access) = write_access(landlock_abi()?);
+            let rules: Vec<(CString, u64)> = writable
+                .iter()
+                .filter_map(|path| super::rule_path(path))
+                .chain(DEVICE_PATHS.iter().map(Into::into))
+                .filter_map(|path| {
+                    let access = if path.is_dir() {
+                        dir_access
+                    } else {
+                        file_access
+                    };
+                    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+                    Some((path, access))
+                })
+                .collect();
+            Some((dir_access, rules))
+        });
+        if landlock.is_some() {
+            applied.push("landlock");
+        }
+        if applied.is_empty() {
+            return applied;
+        }
+
+        // SAFETY: the closure only makes system calls (prctl, open, close,
+        // the Landlock calls and seccomp) on data prepared before the fork,
+        // as required by `pre_exec`.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if let Some((handled, rules)) = &landlock {
+                    restrict_writes(*handled, rules)?;
+                }
+                if !filter.is_empty() {
+                    let program = libc::sock_fprog {
+                        len: filter.len() as libc::c_ushort,
+                        filter: filter.as_ptr() as *mut libc::sock_filter,
+                    };
+                    if libc::prctl(
+                        libc::PR_SET_SECCOMP,
+                        libc::SECCOMP_MODE_FILTER,
+                        &program as *const libc::sock_fprog,
+                    ) != 0
+                    {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+        applied
+    }
+
+    /// Restrict the calling process to writing beneath `rules`. Paths that
+    /// cannot be opened are skipped; failing to enforce the ruleset is an
+    /// error, so the command never runs less confined than reported.
+    unsafe fn restrict_writes(handled: u64, rules: &[(CString, u64)]) -> std::io::Result<()> {
+        let attr = LandlockRulesetAttr {
+            handled_access_fs: handled,
+        };
+        // SAFETY (whole function): pointers passed to the kernel point at
+        // live locals or at `rules`, and every fd opened here is closed.
+        let ruleset = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const LandlockRulesetAttr,
+                std::mem::size_of::<LandlockRulesetAttr>(),
+                0u32,
+            )
+        } as libc::c_int;
+        if ruleset < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        for (path, access) in rules {
+            let fd = unsafe { libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+            if fd < 0 {
+                continue;
+            }
+            let rule = LandlockPathBeneathAttr {
+                allowed_access: *access,
+                parent_fd: fd,
+            };
+            unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset,
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule as *const LandlockPathBeneathAttr,
+                    0u32,
+                );
+                libc::close(fd);
+            }
+        }
+        let restricted = unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0u32) };
+        let error = std::io::Error::last_os_error();
+        unsafe { libc::close(ruleset) };
+        if restricted != 0 {
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod imp {
+    use super::OsPolicy;
+    use std::process::Command;
+
+    pub fn confine(_cmd: &mut Command, _policy: &OsPolicy) -> Vec<&'static str> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seatbelt_profile_denies_network_and_confines_writes() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().canonicalize().unwrap();
+        let missing = project.join("out").join("report.txt");
+        let profile = seatbelt_profile(&OsPolicy {
+            deny_network: true,
+            writable: Some(vec![missing]),
+        });
+        assert!(profile.contains("(deny network-outbound (remote ip))"));
+        assert!(profile.contains("(deny file-write*)"));
+        // A file that does not exist yet is granted through its nearest
+        // existing ancestor.
+        assert!(profile.contains(&format!("(subpath \"{}\")", project.display())));
+        assert!(profile.contains("(subpath \"/dev/null\")"));
+
+        let open = seatbelt_profile(&OsPolicy::default());
+        assert_eq!(open, "(version 1)\n(allow default)\n");
+    }
+
+    #[test]
+    fn confine_without_restrictions_leaves_the_command_alone() {
+        let mut cmd = Command::new("python3");
+        assert!(confine(&mut cmd, &OsPolicy::default()).is_empty());
+        assert_eq!(cmd.get_program(), "python3");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn seccomp_refuses_ip_sockets_but_allows_unix_sockets() {
+        let mut cmd = Command::new("python3");
+        cmd.args([
+            "-c",
+            "import socket\n\
+             socket.socket(socket.AF_UNIX).close()\n\
+             try:\n    socket.socket()\nexcept PermissionError:\n    print('blocked')\n",
+        ]);
+        let policy = OsPolicy {
+            deny_network: true,
+            writable: None,
+        };
+        if !confine(&mut cmd, &policy).contains(&"seccomp") {
+            return;
+        }
+        let Ok(output) = cmd.output() else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "blocked");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn landlock_confines_writes_to_writable_paths() {
+        let allowed = tempfile::tempdir().unwrap();
+        let denied = tempfile::tempdir().unwrap();
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(format!(
+            "echo ok > {}/a && (echo no > {}/b) 2>/dev/null; echo done",
+            allowed.path().display(),
+            denied.path().display()
+        ));
+        let policy = OsPolicy {
+            deny_network: false,
+            writable: Some(vec![allowed.path().to_path_buf()]),
+        };
+        if !confine(&mut cmd, &policy).contains(&"landlock") {
+            return;
+        }
+        let output = cmd.output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "done");
+        assert!(allowed.path().join("a").exists());
+        assert!(!denied.path().join("b").exists());
+    }
+}
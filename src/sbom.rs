@@ -1,6 +1,18 @@
-use crate::project::ProjectMetadata;
+//! Software bills of materials.
+//!
+//! `pybun sbom` and `pybun build --sbom` describe a project through its
+//! locked [`DependencyGraph`]: versions, hashes and dependency relationships
+//! come from `pybun.lockb`, licenses from the installed environment. A build
+//! adds the artifacts it produced. Documents are CycloneDX 1.5 JSON or
+//! SPDX 2.3 JSON, both accepted by the usual CI upload targets.
+
+use crate::dep_graph::{self, DependencyGraph};
+use crate::pypi::normalize_project_name;
 use crate::security::sha256_file;
+use clap::ValueEnum;
 use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use uuid::Uuid;
@@ -13,62 +25,46 @@ pub enum SbomError {
     Serialize(#[from] serde_json::Error),
 }
 
-#[derive(Debug, Serialize)]
-struct Tool {
-    vendor: String,
-    name: String,
-    version: String,
-}
-
-#[derive(Debug, Serialize)]
-struct Property {
-    name: String,
-    value: String,
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON.
+    Cyclonedx,
+    /// SPDX 2.3 JSON.
+    Spdx,
 }
 
-#[derive(Debug, Serialize)]
-struct HashEntry {
-    #[serde(rename = "alg")]
-    algorithm: String,
-    content: String,
-}
+impl SbomFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SbomFormat::Cyclonedx => "cyclonedx",
+            SbomFormat::Spdx => "spdx",
+        }
+    }
 
-#[derive(Debug, Serialize)]
-struct Component {
-    #[serde(rename = "bom-ref")]
-    bom_ref: String,
-    name: String,
-    #[serde(rename = "type")]
-    type_field: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    version: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    purl: Option<String>,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    hashes: Vec<HashEntry>,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    properties: Vec<Property>,
-}
+    /// Name of the standard, as reported in `detail.sbom.format`.
+    pub fn standard(self) -> &'static str {
+        match self {
+            SbomFormat::Cyclonedx => "CycloneDX",
+            SbomFormat::Spdx => "SPDX",
+        }
+    }
 
-#[derive(Debug, Serialize)]
-struct Metadata {
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    tools: Vec<Tool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    component: Option<Component>,
+    /// File name `pybun build --sbom` writes into `dist/`.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            SbomFormat::Cyclonedx => "pybun-sbom.json",
+            SbomFormat::Spdx => "pybun-sbom.spdx.json",
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-pub struct CycloneDxBom {
-    #[serde(rename = "bomFormat")]
-    bom_format: String,
-    #[serde(rename = "specVersion")]
-    spec_version: String,
-    version: u32,
-    #[serde(rename = "serialNumber")]
-    serial_number: String,
-    metadata: Metadata,
-    components: Vec<Component>,
+/// A file produced by `pybun build`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SbomArtifact {
+    pub name: String,
+    pub path: PathBuf,
+    pub sha256: String,
 }
 
 #[derive(Debug, Clone)]
@@ -78,88 +74,290 @@ pub struct SbomSummary {
     pub component_count: usize,
 }
 
-impl CycloneDxBom {
-    pub fn new(project: &ProjectMetadata, artifacts: &[PathBuf]) -> Result<Self, SbomError> {
-        let project_name = project
-            .name
-            .clone()
-            .unwrap_or_else(|| "unknown-project".to_string());
-        let project_version = project
-            .version
-            .clone()
-            .unwrap_or_else(|| "0.0.0".to_string());
-        let metadata_component = Component {
-            bom_ref: project_name.clone(),
-            name: project_name.clone(),
-            type_field: "application".to_string(),
-            version: Some(project_version.clone()),
-            purl: Some(format!("pkg:generic/{project_name}@{project_version}")),
-            hashes: vec![],
-            properties: vec![],
-        };
+/// The project, its locked dependencies and its build artifacts.
+#[derive(Debug, Clone)]
+pub struct Sbom {
+    pub graph: DependencyGraph,
+    pub artifacts: Vec<SbomArtifact>,
+}
+
+impl Sbom {
+    pub fn new(graph: DependencyGraph) -> Self {
+        Self {
+            graph,
+            artifacts: Vec::new(),
+        }
+    }
 
-        let mut components = Vec::new();
-        for artifact in artifacts {
-            let name = artifact
+    /// Add build artifacts, hashing each file.
+    pub fn with_artifacts(mut self, artifacts: &[PathBuf]) -> Result<Self, SbomError> {
+        for path in artifacts {
+            let name = path
                 .file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("artifact")
                 .to_string();
-            let hash = sha256_file(artifact)?;
-            components.push(Component {
-                bom_ref: format!("artifact:{name}"),
+            self.artifacts.push(SbomArtifact {
                 name,
-                type_field: "file".to_string(),
-                version: project.version.clone(),
-                purl: None,
-                hashes: vec![HashEntry {
-                    algorithm: "SHA-256".to_string(),
-                    content: hash,
-                }],
-                properties: vec![Property {
-                    name: "path".to_string(),
-                    value: artifact.display().to_string(),
-                }],
+                path: path.clone(),
+                sha256: sha256_file(path)?,
             });
         }
+        Ok(self)
+    }
+
+    /// Locked packages plus artifacts; the project itself is not counted.
+    pub fn component_count(&self) -> usize {
+        self.graph.nodes.len() + self.artifacts.len()
+    }
+
+    /// The document in `format`. `created` is an RFC 3339 UTC timestamp.
+    pub fn render(&self, format: SbomFormat, created: &str) -> Value {
+        match format {
+            SbomFormat::Cyclonedx => self.to_cyclonedx(created),
+            SbomFormat::Spdx => self.to_spdx(created),
+        }
+    }
+
+    /// CycloneDX 1.5 JSON. Packages are `library` components referenced by
+    /// their purl, and `dependencies` mirrors the graph's edges.
+    pub fn to_cyclonedx(&self, created: &str) -> Value {
+        let graph = &self.graph;
+        let root_ref = purl(&graph.root, &graph.root_version);
+        let refs: BTreeMap<&str, String> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), purl(&node.name, &node.version)))
+            .chain(std::iter::once((graph.root.as_str(), root_ref.clone())))
+            .collect();
 
-        Ok(Self {
-            bom_format: "CycloneDX".to_string(),
-            spec_version: "1.5".to_string(),
-            version: 1,
-            serial_number: format!("urn:uuid:{}", Uuid::new_v4()),
-            metadata: Metadata {
-                tools: vec![Tool {
-                    vendor: "PyBun".to_string(),
-                    name: "pybun".to_string(),
-                    version: env!("CARGO_PKG_VERSION").to_string(),
+        let mut components = Vec::new();
+        for node in &graph.nodes {
+            let bom_ref = &refs[node.name.as_str()];
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": bom_ref,
+                "name": node.name,
+                "version": node.version,
+                "purl": bom_ref,
+                "scope": "required",
+            });
+            if let Some(digest) = sha256_digest(&node.hash) {
+                component["hashes"] = json!([{ "alg": "SHA-256", "content": digest }]);
+            }
+            if let Some(license) = &node.license {
+                component["licenses"] = if dep_graph::is_spdx_expression(license) {
+                    json!([{ "expression": license }])
+                } else {
+                    json!([{ "license": { "name": license } }])
+                };
+            }
+            let mut properties = vec![json!({
+                "name": "pybun:direct",
+                "value": node.direct.to_string(),
+            })];
+            if let Some(size) = node.size_bytes {
+                properties
+                    .push(json!({ "name": "pybun:installed_size", "value": size.to_string() }));
+            }
+            component["properties"] = json!(properties);
+            components.push(component);
+        }
+        for artifact in &self.artifacts {
+            components.push(json!({
+                "type": "file",
+                "bom-ref": format!("artifact:{}", artifact.name),
+                "name": artifact.name,
+                "version": graph.root_version,
+                "hashes": [{ "alg": "SHA-256", "content": artifact.sha256 }],
+                "properties": [{ "name": "path", "value": artifact.path.display().to_string() }],
+            }));
+        }
+
+        // Every package gets an entry, so leaves are listed with no
+        // dependencies rather than left unknown.
+        let mut depends_on: BTreeMap<&str, Vec<&str>> = std::iter::once(graph.root.as_str())
+            .chain(graph.nodes.iter().map(|node| node.name.as_str()))
+            .map(|name| (name, Vec::new()))
+            .collect();
+        for edge in &graph.edges {
+            if let (Some(targets), Some(to)) = (
+                depends_on.get_mut(edge.from.as_str()),
+                refs.get(edge.to.as_str()),
+            ) && !targets.contains(&to.as_str())
+            {
+                targets.push(to);
+            }
+        }
+        let dependencies: Vec<Value> = depends_on
+            .into_iter()
+            .map(|(name, targets)| json!({ "ref": refs[name], "dependsOn": targets }))
+            .collect();
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{}", Uuid::new_v4()),
+            "version": 1,
+            "metadata": {
+                "timestamp": created,
+                "tools": [{
+                    "vendor": "PyBun",
+                    "name": "pybun",
+                    "version": env!("CARGO_PKG_VERSION"),
                 }],
-                component: Some(metadata_component),
+                "component": {
+                    "type": "application",
+                    "bom-ref": root_ref,
+                    "name": graph.root,
+                    "version": graph.root_version,
+                    "purl": root_ref,
+                },
             },
-            components,
+            "components": components,
+            "dependencies": dependencies,
         })
     }
 
-    pub fn to_pretty_json(&self) -> Result<String, SbomError> {
-        Ok(serde_json::to_string_pretty(self)?)
+    /// SPDX 2.3 JSON: the graph's document, with build artifacts as files
+    /// the project `GENERATES`.
+    pub fn to_spdx(&self, created: &str) -> Value {
+        let mut document = self.graph.to_spdx(created);
+        if self.artifacts.is_empty() {
+            return document;
+        }
+        let root_id = dep_graph::spdx_ref("Package", &self.graph.root);
+        let mut files = Vec::new();
+        for artifact in &self.artifacts {
+            let file_id = dep_graph::spdx_ref("File", &artifact.name);
+            files.push(json!({
+                "SPDXID": file_id,
+                "fileName": format!("./{}", artifact.name),
+                "checksums": [{ "algorithm": "SHA256", "checksumValue": artifact.sha256 }],
+                "licenseConcluded": "NOASSERTION",
+                "copyrightText": "NOASSERTION",
+            }));
+            if let Some(relationships) = document["relationships"].as_array_mut() {
+                relationships.push(json!({
+                    "spdxElementId": root_id,
+                    "relationshipType": "GENERATES",
+                    "relatedSpdxElement": file_id,
+                }));
+            }
+        }
+        document["files"] = json!(files);
+        document
+    }
+
+    /// Render the document in `format` and write it to `output`.
+    pub fn write(&self, format: SbomFormat, output: &Path) -> Result<SbomSummary, SbomError> {
+        let document = self.render(format, &crate::mcp::utc_timestamp_seconds_now());
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            output,
+            format!("{}\n", serde_json::to_string_pretty(&document)?),
+        )?;
+        Ok(SbomSummary {
+            path: output.to_path_buf(),
+            format: format.standard().to_string(),
+            component_count: self.component_count(),
+        })
     }
 }
 
-/// Generate a CycloneDX SBOM and write it to the given path.
-pub fn write_cyclonedx_sbom(
-    output: &Path,
-    project: &ProjectMetadata,
-    artifacts: &[PathBuf],
-) -> Result<SbomSummary, SbomError> {
-    let bom = CycloneDxBom::new(project, artifacts)?;
-    let json = bom.to_pretty_json()?;
-    if let Some(parent) = output.parent() {
-        std::fs::create_dir_all(parent)?;
+fn purl(name: &str, version: &str) -> String {
+    format!("pkg:pypi/{}@{}", normalize_project_name(name), version)
+}
+
+fn sha256_digest(hash: &str) -> Option<&str> {
+    hash.strip_prefix("sha256:")
+        .filter(|_| !crate::security::is_placeholder_hash(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Lockfile, Package, PackageSource};
+    use std::fs;
+
+    fn sbom() -> Sbom {
+        let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+        for (name, deps) in [("Requests", vec!["urllib3>=1.21"]), ("urllib3", vec![])] {
+            lock.add_package(Package {
+                name: name.into(),
+                version: "2.0.0".into(),
+                source: PackageSource::Registry {
+                    index: "pypi".into(),
+                    url: "https://pypi.org/simple".into(),
+                },
+                wheel: format!("{name}-2.0.0-py3-none-any.whl"),
+                hash: format!("sha256:{}", "cd".repeat(32)),
+                dependencies: deps.into_iter().map(str::to_string).collect(),
+                dynamic_metadata: false,
+                groups: Vec::new(),
+                build: None,
+                artifacts: Vec::new(),
+            });
+        }
+        let mut graph =
+            DependencyGraph::from_lock(&lock, "demo", "0.1.0", &["requests".to_string()]);
+        graph.nodes[0].license = Some("Apache-2.0".into());
+        graph.nodes[1].license = Some("MIT License".into());
+        Sbom::new(graph)
+    }
+
+    #[test]
+    fn cyclonedx_lists_hashes_licenses_and_dependencies() {
+        let bom = sbom().to_cyclonedx("2026-01-01T00:00:00Z");
+        assert_eq!(bom["specVersion"], "1.5");
+        assert_eq!(bom["metadata"]["component"]["purl"], "pkg:pypi/demo@0.1.0");
+
+        let requests = &bom["components"][0];
+        assert_eq!(requests["purl"], "pkg:pypi/requests@2.0.0");
+        assert_eq!(requests["hashes"][0]["content"], "cd".repeat(32));
+        assert_eq!(requests["licenses"][0]["expression"], "Apache-2.0");
+        assert_eq!(
+            bom["components"][1]["licenses"][0]["license"]["name"],
+            "MIT License"
+        );
+
+        assert_eq!(
+            bom["dependencies"],
+            json!([
+                { "ref": "pkg:pypi/requests@2.0.0", "dependsOn": ["pkg:pypi/urllib3@2.0.0"] },
+                { "ref": "pkg:pypi/demo@0.1.0", "dependsOn": ["pkg:pypi/requests@2.0.0"] },
+                { "ref": "pkg:pypi/urllib3@2.0.0", "dependsOn": [] },
+            ])
+        );
+    }
+
+    #[test]
+    fn artifacts_are_hashed_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let wheel = temp.path().join("demo-0.1.0-py3-none-any.whl");
+        fs::write(&wheel, b"wheel").unwrap();
+        let sbom = sbom().with_artifacts(std::slice::from_ref(&wheel)).unwrap();
+        assert_eq!(sbom.component_count(), 3);
+
+        let bom = sbom.to_cyclonedx("2026-01-01T00:00:00Z");
+        let file = &bom["components"][2];
+        assert_eq!(file["type"], "file");
+        assert_eq!(file["hashes"][0]["content"], sbom.artifacts[0].sha256);
+
+        let doc = sbom.to_spdx("2026-01-01T00:00:00Z");
+        assert_eq!(doc["files"][0]["fileName"], "./demo-0.1.0-py3-none-any.whl");
+        let generates = doc["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["relationshipType"] == "GENERATES")
+            .unwrap();
+        assert_eq!(generates["spdxElementId"], "SPDXRef-Package-demo");
+        assert_eq!(
+            generates["relatedSpdxElement"],
+            "SPDXRef-File-demo-0-1-0-py3-none-any-whl"
+        );
     }
-    std::fs::write(output, json)?;
-    Ok(SbomSummary {
-        path: output.to_path_buf(),
-        format: "CycloneDX".to_string(),
-        component_count: bom.components.len(),
-    })
 }
@@ -20,6 +20,9 @@
 //!   "trace_id": "uuid" (optional, present when PYBUN_TRACE=1)
 //! }
 //! ```
+//!
+//! Sizes and durations inside `detail` follow the `<name>_bytes` /
+//! `<name>_ms` + `<name>_human` convention described in [`crate::units`].
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -27,6 +30,7 @@ use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 pub type EventListener = Box<dyn FnMut(&Event) + 'static>;
+pub type DiagnosticListener = Box<dyn FnMut(&Diagnostic) + 'static>;
 
 /// Schema version - bump when breaking changes occur
 pub const SCHEMA_VERSION: &str = "1";
@@ -109,6 +113,10 @@ pub struct Diagnostic {
     /// Diagnostic code (e.g., "E001", "W002")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
+    /// Stable catalogued error code (`PYBUN-<AREA>-<NNN>`) covering `code`;
+    /// `pybun explain` describes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
     /// Human-readable message
     pub message: String,
     /// Related file path (if applicable)
@@ -142,6 +150,7 @@ impl Diagnostic {
         Self {
             level: DiagnosticLevel::Error,
             code: None,
+            error_code: None,
             message: message.into(),
             file: None,
             line: None,
@@ -158,6 +167,7 @@ impl Diagnostic {
         Self {
             level: DiagnosticLevel::Warning,
             code: None,
+            error_code: None,
             message: message.into(),
             file: None,
             line: None,
@@ -174,6 +184,7 @@ impl Diagnostic {
         Self {
             level: DiagnosticLevel::Info,
             code: None,
+            error_code: None,
             message: message.into(),
             file: None,
             line: None,
@@ -190,6 +201,7 @@ impl Diagnostic {
         Self {
             level: DiagnosticLevel::Hint,
             code: None,
+            error_code: None,
             message: message.into(),
             file: None,
             line: None,
@@ -227,6 +239,20 @@ impl Diagnostic {
         self
     }
 
+    /// Attach the log of the external tool run behind `error`, if there is
+    /// one, as `context.tool` (see [`crate::tool_exec::ToolError::context`]).
+    pub fn with_tool_failure(mut self, error: &(dyn std::error::Error + 'static)) -> Self {
+        if let Some(tool) = crate::tool_exec::ToolError::find(error) {
+            let context = self
+                .context
+                .get_or_insert_with(|| Value::Object(Default::default()));
+            if let Value::Object(map) = context {
+                map.insert("tool".to_string(), tool.context());
+            }
+        }
+        self
+    }
+
     pub fn with_exception_type(mut self, exception_type: impl Into<String>) -> Self {
         self.exception_type = Some(exception_type.into());
         self
@@ -439,9 +465,65 @@ impl JsonEnvelope {
         serde_json::to_string(self).expect("failed to serialize JSON envelope")
     }
 
+    /// Serialize straight into `writer` without building the whole
+    /// document as a string first.
+    pub fn write_json(&self, writer: impl std::io::Write) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+
     pub fn to_json_pretty(&self) -> String {
         serde_json::to_string_pretty(self).expect("failed to serialize JSON envelope")
     }
+
+    /// Write the envelope as `--format ndjson` records: each event, each
+    /// diagnostic, then a `result` record with the rest of the envelope.
+    pub fn write_ndjson(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        let records = self
+            .events
+            .iter()
+            .map(StreamRecord::Event)
+            .chain(self.diagnostics.iter().map(StreamRecord::Diagnostic))
+            .chain(std::iter::once(StreamRecord::Result {
+                version: &self.version,
+                command: &self.command,
+                status: &self.status,
+                duration_ms: self.duration_ms,
+                detail: &self.detail,
+                trace_id: self.trace_id.as_deref(),
+            }));
+        for record in records {
+            record.write_line(&mut writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line of `--format ndjson` output, tagged by `kind`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StreamRecord<'a> {
+    Event(&'a Event),
+    Diagnostic(&'a Diagnostic),
+    /// The envelope without `events` and `diagnostics`, which were streamed
+    /// as their own records.
+    Result {
+        version: &'a str,
+        command: &'a str,
+        status: &'a Status,
+        duration_ms: u64,
+        detail: &'a Value,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        trace_id: Option<&'a str>,
+    },
+}
+
+impl StreamRecord<'_> {
+    /// Write the record as one line and flush, so readers see it at once.
+    pub fn write_line(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        serde_json::to_writer(&mut writer, self)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
 }
 
 /// Event collector that tracks events during command execution
@@ -451,6 +533,7 @@ pub struct EventCollector {
     diagnostics: Vec<Diagnostic>,
     trace_id: Option<String>,
     event_listener: Option<EventListener>,
+    diagnostic_listener: Option<DiagnosticListener>,
 }
 
 impl EventCollector {
@@ -467,6 +550,7 @@ impl EventCollector {
             diagnostics: Vec::new(),
             trace_id,
             event_listener: None,
+            diagnostic_listener: None,
         }
     }
 
@@ -477,6 +561,7 @@ impl EventCollector {
             diagnostics: Vec::new(),
             trace_id: Some(trace_id.into()),
             event_listener: None,
+            diagnostic_listener: None,
         }
     }
 
@@ -485,6 +570,11 @@ impl EventCollector {
         self.event_listener = Some(listener);
     }
 
+    /// Attach a listener that is notified whenever a diagnostic is recorded.
+    pub fn set_diagnostic_listener(&mut self, listener: DiagnosticListener) {
+        self.diagnostic_listener = Some(listener);
+    }
+
     fn notify_listener(&mut self) {
         if let (Some(listener), Some(event)) = (self.event_listener.as_mut(), self.events.last()) {
             listener(event);
@@ -517,7 +607,17 @@ impl EventCollector {
     }
 
     /// Record a diagnostic
-    pub fn diagnostic(&mut self, diagnostic: Diagnostic) {
+    pub fn diagnostic(&mut self, mut diagnostic: Diagnostic) {
+        if diagnostic.error_code.is_none() {
+            diagnostic.error_code = diagnostic
+                .code
+                .as_deref()
+                .and_then(crate::error_catalog::ErrorCode::for_diagnostic)
+                .map(|code| code.as_str().to_string());
+        }
+        if let Some(listener) = self.diagnostic_listener.as_mut() {
+            listener(&diagnostic);
+        }
         self.diagnostics.push(diagnostic);
     }
 
@@ -541,7 +641,7 @@ impl EventCollector {
 
     /// Record an error diagnostic
     pub fn error(&mut self, message: impl Into<String>) {
-        self.diagnostics.push(Diagnostic::error(message));
+        self.diagnostic(Diagnostic::error(message));
     }
 
     /// Record an error diagnostic with a stable `E_*` code and a suggested
@@ -553,7 +653,7 @@ impl EventCollector {
         message: impl Into<String>,
         suggestion: impl Into<String>,
     ) {
-        self.diagnostics.push(
+        self.diagnostic(
             Diagnostic::error(message)
                 .with_code(code)
                 .with_suggestion(suggestion),
@@ -562,12 +662,12 @@ impl EventCollector {
 
     /// Record a warning diagnostic
     pub fn warning(&mut self, message: impl Into<String>) {
-        self.diagnostics.push(Diagnostic::warning(message));
+        self.diagnostic(Diagnostic::warning(message));
     }
 
     /// Record an info diagnostic
     pub fn info(&mut self, message: impl Into<String>) {
-        self.diagnostics.push(Diagnostic::info(message));
+        self.diagnostic(Diagnostic::info(message));
     }
 
     /// Get elapsed time since collector was created
@@ -636,6 +736,31 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn ndjson_writes_events_diagnostics_then_result() {
+        let mut event = Event::new(EventType::ResolveStart, 3);
+        event.message = Some("Resolving".into());
+        let envelope = JsonEnvelope::ok("pybun install", Duration::from_millis(7), json!({}))
+            .with_events(vec![event])
+            .with_diagnostics(vec![Diagnostic::warning("careful").with_code("W_TEST")]);
+        let mut out = Vec::new();
+        envelope.write_ndjson(&mut out).unwrap();
+
+        let records: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            [
+                json!({"kind": "event", "type": "resolve_start", "timestamp_ms": 3, "message": "Resolving"}),
+                json!({"kind": "diagnostic", "level": "warning", "code": "W_TEST", "message": "careful"}),
+                json!({"kind": "result", "version": SCHEMA_VERSION, "command": "pybun install", "status": "ok", "duration_ms": 7, "detail": {}}),
+            ]
+        );
+    }
+
     #[test]
     fn test_diagnostic_builder() {
         let diag = Diagnostic::error("something went wrong")
@@ -0,0 +1,174 @@
+//! Drift between a PEP 723 script's declared dependencies and its lock.
+//!
+//! `pybun lock --script` writes `<script>.lock` next to the script and
+//! `pybun run` installs exactly the locked versions. When the script's
+//! `# /// script` block is edited afterwards the lock silently goes stale;
+//! [`check`] compares the two so `pybun script lock --check` can fail and
+//! `pybun run` can warn.
+
+use crate::lockfile::Lockfile;
+use crate::pypi::normalize_project_name;
+use crate::resolver::Requirement;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// A declared requirement whose locked version does not satisfy it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnsatisfiedRequirement {
+    pub requirement: String,
+    pub locked_version: String,
+}
+
+/// Differences between declared dependencies and a script lock.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ScriptLockDrift {
+    /// Declared requirements with no locked package.
+    pub missing: Vec<String>,
+    /// Declared requirements whose locked version is out of range.
+    pub unsatisfied: Vec<UnsatisfiedRequirement>,
+    /// Locked packages no longer reachable from any declared requirement.
+    pub stale: Vec<String>,
+}
+
+impl ScriptLockDrift {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.unsatisfied.is_empty() && self.stale.is_empty()
+    }
+
+    /// One-line description for diagnostics, e.g.
+    /// `missing: rich; unsatisfied: requests>=2.32 (locked 2.31.0)`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.missing.is_empty() {
+            parts.push(format!("missing: {}", self.missing.join(", ")));
+        }
+        if !self.unsatisfied.is_empty() {
+            let items: Vec<String> = self
+                .unsatisfied
+                .iter()
+                .map(|u| format!("{} (locked {})", u.requirement, u.locked_version))
+                .collect();
+            parts.push(format!("unsatisfied: {}", items.join(", ")));
+        }
+        if !self.stale.is_empty() {
+            parts.push(format!("no longer required: {}", self.stale.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+fn parse_requirement(spec: &str) -> Requirement {
+    spec.parse::<Requirement>()
+        .unwrap_or_else(|_| Requirement::any(spec.trim()))
+}
+
+/// Compare `declared` PEP 508 requirement strings against `lock`.
+///
+/// Requirements whose environment marker does not apply to the current
+/// interpreter are ignored, matching what the resolver would lock.
+pub fn check(lock: &Lockfile, declared: &[String]) -> ScriptLockDrift {
+    let locked: BTreeMap<String, &crate::lockfile::Package> = lock
+        .packages
+        .values()
+        .map(|pkg| (normalize_project_name(&pkg.name), pkg))
+        .collect();
+
+    let mut drift = ScriptLockDrift::default();
+    let mut queue = VecDeque::new();
+    for spec in declared {
+        let requirement = parse_requirement(spec);
+        if !requirement.marker_applies() {
+            continue;
+        }
+        let key = normalize_project_name(&requirement.name);
+        match locked.get(&key) {
+            None => drift.missing.push(spec.trim().to_string()),
+            Some(pkg) => {
+                if !requirement.is_satisfied_by(&pkg.version) {
+                    drift.unsatisfied.push(UnsatisfiedRequirement {
+                        requirement: spec.trim().to_string(),
+                        locked_version: pkg.version.clone(),
+                    });
+                }
+                queue.push_back(key);
+            }
+        }
+    }
+
+    let mut reachable = BTreeSet::new();
+    while let Some(key) = queue.pop_front() {
+        if !reachable.insert(key.clone()) {
+            continue;
+        }
+        let Some(pkg) = locked.get(&key) else {
+            continue;
+        };
+        for dep in &pkg.dependencies {
+            let dep_key = normalize_project_name(&parse_requirement(dep).name);
+            if locked.contains_key(&dep_key) {
+                queue.push_back(dep_key);
+            }
+        }
+    }
+    drift.stale = locked
+        .iter()
+        .filter(|(key, _)| !reachable.contains(*key))
+        .map(|(_, pkg)| pkg.name.clone())
+        .collect();
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Package, PackageSource};
+
+    fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+        Package {
+            name: name.into(),
+            version: version.into(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: "sha256:placeholder".into(),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn lock(packages: Vec<Package>) -> Lockfile {
+        let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+        for pkg in packages {
+            lock.add_package(pkg);
+        }
+        lock
+    }
+
+    #[test]
+    fn clean_when_declared_requirements_are_locked() {
+        let lock = lock(vec![
+            package("requests", "2.32.0", &["urllib3>=1.21"]),
+            package("urllib3", "2.2.0", &[]),
+        ]);
+        let drift = check(&lock, &["Requests>=2.31".to_string()]);
+        assert!(drift.is_clean(), "{drift:?}");
+    }
+
+    #[test]
+    fn reports_missing_unsatisfied_and_stale_packages() {
+        let lock = lock(vec![
+            package("requests", "2.31.0", &[]),
+            package("click", "8.1.0", &[]),
+        ]);
+        let drift = check(&lock, &["requests>=2.32".to_string(), "rich".to_string()]);
+        assert_eq!(drift.missing, vec!["rich".to_string()]);
+        assert_eq!(drift.unsatisfied[0].locked_version, "2.31.0");
+        assert_eq!(drift.stale, vec!["click".to_string()]);
+        assert!(drift.describe().contains("requests>=2.32 (locked 2.31.0)"));
+    }
+}
@@ -0,0 +1,256 @@
+//! Wheels built from source distributions.
+//!
+//! When a release has no wheel compatible with the target interpreter and
+//! platform, `pybun install` downloads its sdist and builds a wheel with the
+//! sdist's PEP 517 backend:
+//!
+//! - `venv` isolation (the default): the `[build-system].requires` are
+//!   installed into a throwaway venv, followed by whatever
+//!   `get_requires_for_build_wheel` asks for, and `build_wheel` is called;
+//! - `container` isolation: the build runs in a Podman/Docker container
+//!   (see [`crate::build_isolation`]).
+//!
+//! Built wheels go into the shared wheel store ([`crate::wheel_cache`]) and
+//! are indexed by sdist SHA-256, CPython tag and platform:
+//!
+//! ```text
+//! ~/.cache/pybun/build/sdist-wheels/{sdist_sha256}/{cp_tag}-{platform}.json
+//! ```
+//!
+//! so a given sdist is only built once per target.
+
+use crate::build_isolation::{self, BuildIsolationError, ContainerRuntime};
+use crate::cache::Cache;
+use crate::lockfile::SourceBuild;
+use crate::sdist_metadata::{self, SdistMetadataError};
+use crate::security::sha256_file;
+use crate::wheel_cache::{WheelCache, WheelCacheError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SdistBuildError {
+    #[error(transparent)]
+    Backend(#[from] SdistMetadataError),
+    #[error(transparent)]
+    Container(#[from] BuildIsolationError),
+    #[error(transparent)]
+    Store(#[from] WheelCacheError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("build of {0} produced no wheel")]
+    NoWheel(String),
+}
+
+pub type Result<T> = std::result::Result<T, SdistBuildError>;
+
+/// A wheel built from an sdist, with the inputs of the build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuiltWheel {
+    /// Where the wheel currently is: the build directory until the wheel is
+    /// cached, the wheel store afterwards.
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub filename: String,
+    /// Hex SHA-256 of the wheel.
+    pub sha256: String,
+    pub backend: String,
+    pub requires: Vec<String>,
+}
+
+impl BuiltWheel {
+    /// Lockfile record of this build for `python` (a CPython tag) on
+    /// `platform`.
+    pub fn source_build(&self, python: &str, platform: &str) -> SourceBuild {
+        SourceBuild {
+            backend: self.backend.clone(),
+            requires: self.requires.clone(),
+            python: python.to_string(),
+            platform: platform.to_string(),
+            wheel: self.filename.clone(),
+            wheel_hash: format!("sha256:{}", self.sha256),
+        }
+    }
+}
+
+/// Build a wheel from `sdist` in a fresh venv created by `python`.
+/// `work_dir` holds the unpacked sources, the venv and the built wheel, and
+/// may be removed once the wheel has been cached.
+pub fn build_in_venv(sdist: &Path, python: &Path, work_dir: &Path) -> Result<BuiltWheel> {
+    let project = sdist_metadata::unpack(sdist, work_dir)?;
+    build_tree_in_venv(&project, python, work_dir)
+}
+
+/// Build a wheel from the source tree `project` (such as a git checkout)
+/// in a fresh venv created by `python`; `work_dir` is used as in
+/// [`build_in_venv`].
+pub fn build_tree_in_venv(project: &Path, python: &Path, work_dir: &Path) -> Result<BuiltWheel> {
+    let project = project.to_path_buf();
+    let build_system = sdist_metadata::read_build_system(&project)?;
+    let env_python =
+        sdist_metadata::create_build_env(python, &work_dir.join("env"), &build_system.requires)?;
+    let extra =
+        sdist_metadata::install_wheel_requires(&env_python, &project, &build_system, work_dir)?;
+
+    let out_dir = work_dir.join("dist");
+    fs::create_dir_all(&out_dir)?;
+    let result = sdist_metadata::run_hook(&env_python, &project, &build_system, "wheel", &out_dir)?;
+    let path = result
+        .get("wheel")
+        .and_then(|v| v.as_str())
+        .map(|name| out_dir.join(name))
+        .filter(|path| path.is_file())
+        .ok_or_else(|| SdistBuildError::NoWheel(display_name(&project)))?;
+
+    let mut requires = build_system.requires;
+    requires.extend(extra);
+    built_wheel(path, build_system.backend, requires)
+}
+
+/// Build a wheel from `sdist` inside a container. The build inputs are read
+/// from the sdist's `pyproject.toml`; the requirements the backend adds
+/// dynamically are resolved by pip inside the container and not recorded.
+pub fn build_in_container(
+    runtime: &ContainerRuntime,
+    image: &str,
+    sdist: &Path,
+    work_dir: &Path,
+) -> Result<BuiltWheel> {
+    let project = sdist_metadata::unpack(sdist, work_dir)?;
+    let build_system = sdist_metadata::read_build_system(&project)?;
+    let wheels =
+        build_isolation::build_sdist_in_container(runtime, image, sdist, &work_dir.join("dist"))?;
+    let path = wheels
+        .into_iter()
+        .next()
+        .ok_or_else(|| SdistBuildError::NoWheel(display_name(sdist)))?;
+    built_wheel(path, build_system.backend, build_system.requires)
+}
+
+fn built_wheel(path: PathBuf, backend: String, requires: Vec<String>) -> Result<BuiltWheel> {
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_string();
+    Ok(BuiltWheel {
+        sha256: sha256_file(&path)?,
+        path,
+        filename,
+        backend,
+        requires,
+    })
+}
+
+fn display_name(sdist: &Path) -> String {
+    sdist
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| sdist.display().to_string())
+}
+
+/// Index of built wheels, keyed by sdist hash and build target. The wheels
+/// themselves live in the wheel store.
+#[derive(Debug)]
+pub struct SdistBuildCache {
+    root: PathBuf,
+    wheels: WheelCache,
+}
+
+impl SdistBuildCache {
+    pub fn new() -> Result<Self> {
+        let cache = Cache::new().map_err(WheelCacheError::Cache)?;
+        Self::with_cache(cache)
+    }
+
+    /// Build cache inside `cache` (useful for testing).
+    pub fn with_cache(cache: Cache) -> Result<Self> {
+        Ok(Self {
+            root: cache.build_dir().join("sdist-wheels"),
+            wheels: WheelCache::with_cache(cache)?,
+        })
+    }
+
+    fn entry_path(&self, sdist_sha256: &str, python: &str, platform: &str) -> PathBuf {
+        let key = sdist_sha256
+            .trim_start_matches("sha256:")
+            .to_ascii_lowercase();
+        self.root
+            .join(key)
+            .join(format!("{python}-{platform}.json"))
+    }
+
+    /// The wheel previously built from this sdist for `python` on
+    /// `platform`. Entries whose wheel has left the store are misses.
+    pub fn get(&self, sdist_sha256: &str, python: &str, platform: &str) -> Option<BuiltWheel> {
+        let data = fs::read(self.entry_path(sdist_sha256, python, platform)).ok()?;
+        let mut built: BuiltWheel = serde_json::from_slice(&data).ok()?;
+        built.path = self.wheels.find(&built.sha256, &built.filename)?;
+        Some(built)
+    }
+
+    /// Move `built` into the wheel store and index it; the returned wheel
+    /// points into the store.
+    pub fn put(
+        &self,
+        sdist_sha256: &str,
+        python: &str,
+        platform: &str,
+        built: BuiltWheel,
+    ) -> Result<BuiltWheel> {
+        let path = self
+            .wheels
+            .insert(&built.path, &built.filename, Some(&built.sha256))?;
+        let built = BuiltWheel { path, ..built };
+
+        let entry = self.entry_path(sdist_sha256, python, platform);
+        fs::create_dir_all(entry.parent().expect("entries have a parent"))?;
+        let tmp = entry.with_extension("json.tmp");
+        fs::write(
+            &tmp,
+            serde_json::to_vec_pretty(&built).map_err(std::io::Error::other)?,
+        )?;
+        fs::rename(tmp, entry)?;
+        Ok(built)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_builds_are_keyed_by_sdist_and_target() {
+        let temp = tempfile::tempdir().unwrap();
+        let builds =
+            SdistBuildCache::with_cache(Cache::with_root(temp.path().join("cache"))).unwrap();
+        let wheel = temp.path().join("demo-1.0-cp311-cp311-linux_x86_64.whl");
+        fs::write(&wheel, b"built wheel").unwrap();
+        let built = built_wheel(wheel, "backend".into(), vec!["flit_core".into()]).unwrap();
+
+        let stored = builds
+            .put("sha256:ABCD", "cp311", "linux_x86_64", built.clone())
+            .unwrap();
+        assert!(
+            stored
+                .path
+                .starts_with(temp.path().join("cache").join("wheels"))
+        );
+        assert_eq!(
+            builds.get("abcd", "cp311", "linux_x86_64"),
+            Some(stored.clone())
+        );
+        assert_eq!(builds.get("abcd", "cp312", "linux_x86_64"), None);
+        assert_eq!(builds.get("ef01", "cp311", "linux_x86_64"), None);
+
+        let record = stored.source_build("cp311", "linux_x86_64");
+        assert_eq!(record.wheel, built.filename);
+        assert_eq!(record.wheel_hash, format!("sha256:{}", built.sha256));
+        assert_eq!(record.requires, vec!["flit_core"]);
+
+        fs::remove_file(&stored.path).unwrap();
+        assert_eq!(builds.get("abcd", "cp311", "linux_x86_64"), None);
+    }
+}
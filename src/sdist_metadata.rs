@@ -0,0 +1,526 @@
+//! Resolution metadata for sdist-only releases.
+//!
+//! When a release ships no wheels and the index has no `requires_dist` for
+//! it, dependencies can only be learned from the sdist itself:
+//!
+//! 1. If the sdist's `PKG-INFO` is Metadata 2.2+ and does not list
+//!    `Requires-Dist` as `Dynamic` (PEP 643), it is authoritative and used
+//!    as-is without running any build code.
+//! 2. Otherwise the PEP 517 backend is asked for metadata: the build
+//!    requirements are installed into a throwaway venv and
+//!    `prepare_metadata_for_build_wheel` is called (falling back to
+//!    `build_wheel` when the backend lacks the optional hook). Such packages
+//!    are reported as having *dynamic* metadata, since the result may differ
+//!    between platforms or interpreters.
+//!
+//! Results are cached by sdist SHA-256 under `<cache>/sdist-metadata/`, so a
+//! given archive is only ever unpacked and built once.
+
+use crate::archive::{self, ArchiveError, ArchiveFormat, ExtractOptions};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+/// Backend used when the sdist has no `[build-system]` table (PEP 517).
+const LEGACY_BACKEND: &str = "setuptools.build_meta:__legacy__";
+const LEGACY_REQUIRES: &[&str] = &["setuptools>=40.8.0"];
+
+/// Calls a PEP 517 hook and prints its result as JSON on the last line.
+const HOOK_SCRIPT: &str = r#"
+import importlib, json, os, sys
+spec, hook, out_dir = sys.argv[1:4]
+sys.path[:0] = [os.path.abspath(p) for p in json.loads(os.environ.get("PYBUN_BACKEND_PATH", "[]"))]
+module, _, attr = spec.partition(":")
+backend = importlib.import_module(module)
+for part in filter(None, attr.split(".")):
+    backend = getattr(backend, part)
+if hook == "requires":
+    fn = getattr(backend, "get_requires_for_build_wheel", None)
+    result = {"requires": list(fn()) if fn else []}
+elif hook == "wheel":
+    result = {"wheel": backend.build_wheel(out_dir)}
+else:
+    fn = getattr(backend, "prepare_metadata_for_build_wheel", None)
+    result = {"dist_info": fn(out_dir)} if fn else {"wheel": backend.build_wheel(out_dir)}
+print()
+print(json.dumps(result))
+"#;
+
+#[derive(Debug, Error)]
+pub enum SdistMetadataError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Archive(#[from] ArchiveError),
+    #[error("sdist {0} does not contain a project directory")]
+    MissingProject(PathBuf),
+    #[error("invalid pyproject.toml in sdist: {0}")]
+    InvalidPyproject(String),
+    #[error("{step} failed: {stderr}")]
+    Backend { step: &'static str, stderr: String },
+    #[error("build backend produced no METADATA file")]
+    MissingMetadata,
+}
+
+pub type Result<T> = std::result::Result<T, SdistMetadataError>;
+
+/// Where the metadata came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetadataSource {
+    /// Static `PKG-INFO` (Metadata 2.2+, PEP 643).
+    PkgInfo,
+    /// `prepare_metadata_for_build_wheel` / `build_wheel`.
+    Backend,
+}
+
+/// Dependency metadata extracted from an sdist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SdistMetadata {
+    pub name: String,
+    pub version: String,
+    pub requires_dist: Vec<String>,
+    pub requires_python: Option<String>,
+    pub source: MetadataSource,
+}
+
+impl SdistMetadata {
+    /// Whether the metadata had to be computed by the build backend and may
+    /// therefore vary by platform or interpreter.
+    pub fn is_dynamic(&self) -> bool {
+        self.source == MetadataSource::Backend
+    }
+}
+
+/// Parsed core metadata (`PKG-INFO` / `METADATA`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoreMetadata {
+    pub metadata_version: Option<String>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub requires_dist: Vec<String>,
+    pub requires_python: Option<String>,
+    pub dynamic: Vec<String>,
+    pub license_expression: Option<String>,
+    pub license: Option<String>,
+    pub classifiers: Vec<String>,
+}
+
+impl CoreMetadata {
+    /// Parse the RFC 822-style header block of a core metadata file.
+    pub fn parse(text: &str) -> Self {
+        let mut meta = CoreMetadata::default();
+        let mut current: Option<(String, String)> = None;
+        let mut fields = Vec::new();
+        for line in text.lines() {
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = current.as_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some(field) = current.take() {
+                fields.push(field);
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                current = Some((key.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        fields.extend(current);
+
+        for (key, value) in fields {
+            match key.as_str() {
+                "metadata-version" => meta.metadata_version = Some(value),
+                "name" => meta.name = Some(value),
+                "version" => meta.version = Some(value),
+                "requires-dist" => meta.requires_dist.push(value),
+                "requires-python" => meta.requires_python = Some(value),
+                "dynamic" => meta.dynamic.push(value.to_ascii_lowercase()),
+                "license-expression" => meta.license_expression = Some(value),
+                "license" => meta.license = Some(value),
+                "classifier" => meta.classifiers.push(value),
+                _ => {}
+            }
+        }
+        meta
+    }
+
+    /// Best short license identifier: `License-Expression` (PEP 639), a
+    /// one-line `License` field, or the last `License ::` classifier.
+    pub fn license_summary(&self) -> Option<String> {
+        if let Some(expression) = self.license_expression.as_deref().map(str::trim)
+            && !expression.is_empty()
+        {
+            return Some(expression.to_string());
+        }
+        if let Some(license) = self.license.as_deref().map(str::trim)
+            && !license.is_empty()
+            && license.len() <= 64
+            && !license.eq_ignore_ascii_case("unknown")
+        {
+            return Some(license.to_string());
+        }
+        self.classifiers
+            .iter()
+            .filter(|c| c.starts_with("License ::"))
+            .filter_map(|c| c.rsplit("::").next())
+            .map(str::trim)
+            .rfind(|name| !name.is_empty() && *name != "OSI Approved")
+            .map(str::to_string)
+    }
+
+    /// Whether `Requires-Dist` can be trusted without building (PEP 643).
+    pub fn has_static_requirements(&self) -> bool {
+        let Some(version) = self.metadata_version.as_deref() else {
+            return false;
+        };
+        let mut parts = version.split('.').map(|p| p.parse::<u32>().unwrap_or(0));
+        let (major, minor) = (parts.next().unwrap_or(0), parts.next().unwrap_or(0));
+        (major, minor) >= (2, 2)
+            && !self
+                .dynamic
+                .iter()
+                .any(|field| field == "requires-dist" || field == "requires-python")
+    }
+
+    fn into_metadata(self, source: MetadataSource) -> SdistMetadata {
+        SdistMetadata {
+            name: self.name.unwrap_or_default(),
+            version: self.version.unwrap_or_default(),
+            requires_dist: self.requires_dist,
+            requires_python: self.requires_python,
+            source,
+        }
+    }
+}
+
+/// On-disk cache of extracted metadata, keyed by sdist SHA-256.
+#[derive(Debug, Clone)]
+pub struct SdistMetadataCache {
+    root: PathBuf,
+}
+
+impl SdistMetadataCache {
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_path(&self, sha256: &str) -> PathBuf {
+        let key = sha256.trim_start_matches("sha256:").to_ascii_lowercase();
+        self.root.join(format!("{key}.json"))
+    }
+
+    /// Cached metadata for the sdist with digest `sha256`, if any. Unreadable
+    /// entries are treated as misses.
+    pub fn get(&self, sha256: &str) -> Option<SdistMetadata> {
+        let data = fs::read(self.entry_path(sha256)).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    pub fn put(&self, sha256: &str, metadata: &SdistMetadata) -> Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let path = self.entry_path(sha256);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(
+            &tmp,
+            serde_json::to_vec_pretty(metadata).map_err(std::io::Error::other)?,
+        )?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Extract dependency metadata from the sdist at `sdist`, using `python` to
+/// run the build backend if needed. `work_dir` is used for scratch files and
+/// may be removed afterwards.
+pub fn extract(sdist: &Path, python: &Path, work_dir: &Path) -> Result<SdistMetadata> {
+    let project = unpack(sdist, work_dir)?;
+    if let Ok(text) = fs::read_to_string(project.join("PKG-INFO")) {
+        let pkg_info = CoreMetadata::parse(&text);
+        if pkg_info.has_static_requirements() {
+            return Ok(pkg_info.into_metadata(MetadataSource::PkgInfo));
+        }
+    }
+
+    let build_system = read_build_system(&project)?;
+    let env_python = create_build_env(python, &work_dir.join("env"), &build_system.requires)?;
+    install_wheel_requires(&env_python, &project, &build_system, work_dir)?;
+
+    let out_dir = work_dir.join("metadata");
+    fs::create_dir_all(&out_dir)?;
+    let result = run_hook(&env_python, &project, &build_system, "metadata", &out_dir)?;
+    let metadata_path = if let Some(dist_info) = result.get("dist_info").and_then(|v| v.as_str()) {
+        out_dir.join(dist_info).join("METADATA")
+    } else if let Some(wheel) = result.get("wheel").and_then(|v| v.as_str()) {
+        let wheel_dir = work_dir.join("wheel");
+        archive::extract_as(
+            ArchiveFormat::Zip,
+            &out_dir.join(wheel),
+            &wheel_dir,
+            ExtractOptions::default(),
+        )?;
+        find_dist_info_metadata(&wheel_dir).ok_or(SdistMetadataError::MissingMetadata)?
+    } else {
+        return Err(SdistMetadataError::MissingMetadata);
+    };
+    let text =
+        fs::read_to_string(&metadata_path).map_err(|_| SdistMetadataError::MissingMetadata)?;
+    Ok(CoreMetadata::parse(&text).into_metadata(MetadataSource::Backend))
+}
+
+/// Unpack `sdist` under `work_dir` and return its project directory.
+pub(crate) fn unpack(sdist: &Path, work_dir: &Path) -> Result<PathBuf> {
+    let unpacked = work_dir.join("src");
+    let format = ArchiveFormat::from_path(sdist).unwrap_or(ArchiveFormat::TarGz);
+    archive::extract_as(format, sdist, &unpacked, ExtractOptions::default())?;
+    project_root(&unpacked).ok_or_else(|| SdistMetadataError::MissingProject(sdist.to_path_buf()))
+}
+
+/// The single top-level directory of an unpacked sdist (or the directory
+/// itself when the archive has no wrapper directory).
+fn project_root(unpacked: &Path) -> Option<PathBuf> {
+    if unpacked.join("PKG-INFO").exists() || unpacked.join("pyproject.toml").exists() {
+        return Some(unpacked.to_path_buf());
+    }
+    let mut dirs = fs::read_dir(unpacked)
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().is_dir());
+    let first = dirs.next()?;
+    dirs.next().is_none().then(|| first.path())
+}
+
+fn find_dist_info_metadata(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+        let path = entry.path();
+        let is_dist_info = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.ends_with(".dist-info"));
+        (is_dist_info && path.join("METADATA").exists()).then(|| path.join("METADATA"))
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SdistBuildSystem {
+    pub(crate) backend: String,
+    pub(crate) backend_path: Vec<String>,
+    pub(crate) requires: Vec<String>,
+}
+
+pub(crate) fn read_build_system(project: &Path) -> Result<SdistBuildSystem> {
+    let Ok(text) = fs::read_to_string(project.join("pyproject.toml")) else {
+        return Ok(SdistBuildSystem {
+            backend: LEGACY_BACKEND.to_string(),
+            backend_path: Vec::new(),
+            requires: LEGACY_REQUIRES.iter().map(|s| s.to_string()).collect(),
+        });
+    };
+    let doc: toml::Value =
+        toml::from_str(&text).map_err(|e| SdistMetadataError::InvalidPyproject(e.to_string()))?;
+    let table = doc.get("build-system");
+    let strings = |key: &str| -> Option<Vec<String>> {
+        table?.get(key)?.as_array().map(|items| {
+            items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+    };
+    let backend = table
+        .and_then(|t| t.get("build-backend"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+    Ok(SdistBuildSystem {
+        requires: strings("requires").unwrap_or_else(|| {
+            if backend.is_none() {
+                LEGACY_REQUIRES.iter().map(|s| s.to_string()).collect()
+            } else {
+                Vec::new()
+            }
+        }),
+        backend_path: strings("backend-path").unwrap_or_default(),
+        backend: backend.unwrap_or_else(|| LEGACY_BACKEND.to_string()),
+    })
+}
+
+pub(crate) fn create_build_env(python: &Path, venv: &Path, requires: &[String]) -> Result<PathBuf> {
+    let mut cmd = Command::new(python);
+    cmd.args(["-m", "venv"]);
+    if requires.is_empty() {
+        cmd.arg("--without-pip");
+    }
+    run_checked(cmd.arg(venv), "creating build environment")?;
+    let env_python = if cfg!(windows) {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    };
+    pip_install(&env_python, requires)?;
+    Ok(env_python)
+}
+
+/// Install what `get_requires_for_build_wheel` asks for into the build env
+/// and return it.
+pub(crate) fn install_wheel_requires(
+    python: &Path,
+    project: &Path,
+    build_system: &SdistBuildSystem,
+    work_dir: &Path,
+) -> Result<Vec<String>> {
+    let result = run_hook(python, project, build_system, "requires", work_dir)?;
+    let requires: Vec<String> = result
+        .get("requires")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    pip_install(python, &requires)?;
+    Ok(requires)
+}
+
+fn pip_install(python: &Path, requires: &[String]) -> Result<()> {
+    if requires.is_empty() {
+        return Ok(());
+    }
+    let mut cmd = Command::new(python);
+    cmd.args([
+        "-m",
+        "pip",
+        "install",
+        "--quiet",
+        "--disable-pip-version-check",
+    ])
+    .args(requires);
+    run_checked(&mut cmd, "installing build requirements")?;
+    Ok(())
+}
+
+pub(crate) fn run_hook(
+    python: &Path,
+    project: &Path,
+    build_system: &SdistBuildSystem,
+    hook: &'static str,
+    out_dir: &Path,
+) -> Result<serde_json::Value> {
+    let mut cmd = Command::new(python);
+    cmd.arg("-c")
+        .arg(HOOK_SCRIPT)
+        .arg(&build_system.backend)
+        .arg(hook)
+        .arg(out_dir)
+        .current_dir(project)
+        .env(
+            "PYBUN_BACKEND_PATH",
+            serde_json::to_string(&build_system.backend_path).unwrap_or_default(),
+        );
+    let step = match hook {
+        "requires" => "get_requires_for_build_wheel",
+        "wheel" => "build_wheel",
+        _ => "prepare_metadata_for_build_wheel",
+    };
+    let stdout = run_checked(&mut cmd, step)?;
+    stdout
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str(line).ok())
+        .ok_or(SdistMetadataError::Backend {
+            step,
+            stderr: "hook produced no result".to_string(),
+        })
+}
+
+fn run_checked(cmd: &mut Command, step: &'static str) -> Result<String> {
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+        return Err(SdistMetadataError::Backend {
+            step,
+            stderr: tail.into_iter().rev().collect::<Vec<_>>().join("\n"),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_core_metadata_with_continuations() {
+        let meta = CoreMetadata::parse(
+            "Metadata-Version: 2.2\nName: demo\nVersion: 1.0\nRequires-Dist: a>=1\n\
+             Requires-Dist: b; extra == \"x\"\nSummary: long\n  summary\nDynamic: Classifier\n\n\
+             Requires-Dist: body-is-ignored\n",
+        );
+        assert_eq!(meta.name.as_deref(), Some("demo"));
+        assert_eq!(meta.requires_dist, vec!["a>=1", "b; extra == \"x\""]);
+        assert_eq!(meta.dynamic, vec!["classifier"]);
+        assert!(meta.has_static_requirements());
+    }
+
+    #[test]
+    fn old_or_dynamic_pkg_info_is_not_static() {
+        assert!(!CoreMetadata::parse("Metadata-Version: 2.1\nName: x\n").has_static_requirements());
+        assert!(
+            !CoreMetadata::parse("Metadata-Version: 2.3\nDynamic: Requires-Dist\n")
+                .has_static_requirements()
+        );
+    }
+
+    #[test]
+    fn license_summary_prefers_expression_then_field_then_classifier() {
+        let meta = CoreMetadata::parse("License-Expression: MIT OR Apache-2.0\nLicense: BSD\n");
+        assert_eq!(meta.license_summary().as_deref(), Some("MIT OR Apache-2.0"));
+        let meta = CoreMetadata::parse("License: BSD-3-Clause\n");
+        assert_eq!(meta.license_summary().as_deref(), Some("BSD-3-Clause"));
+        let meta = CoreMetadata::parse(
+            "License: UNKNOWN\nClassifier: License :: OSI Approved :: MIT License\n",
+        );
+        assert_eq!(meta.license_summary().as_deref(), Some("MIT License"));
+    }
+
+    #[test]
+    fn build_system_defaults_to_legacy_setuptools() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = read_build_system(dir.path()).unwrap();
+        assert_eq!(legacy.backend, LEGACY_BACKEND);
+        assert_eq!(legacy.requires, vec!["setuptools>=40.8.0"]);
+
+        fs::write(
+            dir.path().join("pyproject.toml"),
+            "[build-system]\nrequires = []\nbuild-backend = \"backend\"\nbackend-path = [\".\"]\n",
+        )
+        .unwrap();
+        let custom = read_build_system(dir.path()).unwrap();
+        assert_eq!(custom.backend, "backend");
+        assert_eq!(custom.backend_path, vec!["."]);
+        assert!(custom.requires.is_empty());
+    }
+
+    #[test]
+    fn cache_roundtrip_is_keyed_by_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = SdistMetadataCache::with_root(dir.path());
+        let meta = SdistMetadata {
+            name: "demo".into(),
+            version: "1.0".into(),
+            requires_dist: vec!["a".into()],
+            requires_python: None,
+            source: MetadataSource::Backend,
+        };
+        cache.put("sha256:ABC", &meta).unwrap();
+        assert_eq!(cache.get("abc"), Some(meta));
+        assert_eq!(cache.get("def"), None);
+    }
+}
@@ -0,0 +1,484 @@
+//! Virtual environment seeding (pip/setuptools/wheel).
+//!
+//! By default `python -m venv` seeds whatever pip version the base
+//! interpreter's `ensurepip` bundles, so two machines can end up with
+//! different tooling in "identical" environments. `[tool.pybun.seed]` makes
+//! this explicit:
+//!
+//! ```toml
+//! [tool.pybun.seed]
+//! enabled = true          # false creates the venv without pip
+//! pip = "24.2"            # pin pip instead of using ensurepip's bundle
+//! setuptools = "75.1.0"   # also seed setuptools (omitted = not seeded)
+//! wheel = "latest"        # newest release, cached after the first fetch
+//! ```
+//!
+//! Pinned seed packages are installed from a cached seed bundle
+//! (`$PYBUN_HOME/seed/*.whl`) with the native wheel installer, so repeated
+//! environment creation never re-downloads them. The seeded versions are
+//! recorded in the environment manifest (`<venv>/pybun-env.json`).
+
+use crate::pypi::normalize_project_name;
+use crate::tool_exec::ToolRun;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Filename of the environment manifest written into each seeded venv.
+pub const ENV_MANIFEST_FILENAME: &str = "pybun-env.json";
+
+/// Seed packages in installation order.
+const SEED_PACKAGES: [&str; 3] = ["pip", "setuptools", "wheel"];
+
+#[derive(Debug, Error)]
+pub enum SeedError {
+    #[error("failed to create virtual environment at {path}: {message}")]
+    Venv { path: PathBuf, message: String },
+    #[error(
+        "seed package {spec} is not in the seed bundle at {bundle} and could not be fetched: {message}"
+    )]
+    Fetch {
+        spec: String,
+        bundle: PathBuf,
+        message: String,
+    },
+    #[error("failed to install seed wheel {path}: {source}")]
+    Install {
+        path: PathBuf,
+        source: crate::installer::InstallError,
+    },
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, SeedError>;
+
+/// Seeding configuration from `[tool.pybun.seed]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedConfig {
+    /// Whether to seed any packages at all (default: true).
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    /// pip version (`"latest"` or an exact version). Unset uses ensurepip.
+    #[serde(default)]
+    pub pip: Option<String>,
+    /// setuptools version; unset means setuptools is not seeded.
+    #[serde(default)]
+    pub setuptools: Option<String>,
+    /// wheel version; unset means wheel is not seeded.
+    #[serde(default)]
+    pub wheel: Option<String>,
+}
+
+/// A single package to seed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedPackage {
+    pub name: String,
+    /// Requested version; `None` means "whatever ensurepip bundles".
+    pub version: Option<String>,
+}
+
+impl SeedPackage {
+    /// Whether this package must come from the seed bundle.
+    fn is_bundled(&self) -> bool {
+        self.version.is_some()
+    }
+
+    fn spec(&self) -> String {
+        match self.version.as_deref() {
+            None | Some("latest") => self.name.clone(),
+            Some(version) => format!("{}=={}", self.name, version),
+        }
+    }
+}
+
+/// Concrete seeding plan derived from [`SeedConfig`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedPlan {
+    pub enabled: bool,
+    pub packages: Vec<SeedPackage>,
+}
+
+impl Default for SeedPlan {
+    fn default() -> Self {
+        SeedPlan::from_config(&SeedConfig::default())
+    }
+}
+
+impl SeedPlan {
+    pub fn from_config(config: &SeedConfig) -> Self {
+        let enabled = config.enabled.unwrap_or(true);
+        if !enabled {
+            return SeedPlan {
+                enabled,
+                packages: Vec::new(),
+            };
+        }
+
+        let mut packages = vec![SeedPackage {
+            name: "pip".to_string(),
+            version: config.pip.clone(),
+        }];
+        for (name, version) in [("setuptools", &config.setuptools), ("wheel", &config.wheel)] {
+            if let Some(version) = version {
+                packages.push(SeedPackage {
+                    name: name.to_string(),
+                    version: Some(version.clone()),
+                });
+            }
+        }
+        SeedPlan { enabled, packages }
+    }
+
+    /// Plan that creates the venv without any seed packages.
+    pub fn disabled() -> Self {
+        SeedPlan {
+            enabled: false,
+            packages: Vec::new(),
+        }
+    }
+
+    /// Whether `python -m venv` should bootstrap pip itself via ensurepip.
+    fn uses_ensurepip(&self) -> bool {
+        self.packages
+            .iter()
+            .any(|p| p.name == "pip" && p.version.is_none())
+    }
+}
+
+/// A seeded package as recorded in the environment manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeededPackage {
+    pub name: String,
+    pub version: String,
+}
+
+/// Environment manifest stored at `<venv>/pybun-env.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvManifest {
+    /// PyBun version that created the environment.
+    pub created_by: String,
+    /// Interpreter the environment was created from.
+    pub base_python: String,
+    /// Seeding plan that was requested.
+    pub seed: SeedPlan,
+    /// Seed packages actually present after creation.
+    pub seeded: Vec<SeededPackage>,
+}
+
+impl EnvManifest {
+    /// Load the manifest of an existing venv, if any.
+    pub fn load(venv_path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(venv_path.join(ENV_MANIFEST_FILENAME)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, venv_path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(venv_path.join(ENV_MANIFEST_FILENAME), content)?;
+        Ok(())
+    }
+}
+
+/// Directory holding cached seed wheels.
+pub fn seed_bundle_dir() -> PathBuf {
+    crate::env::pybun_home().join("seed")
+}
+
+/// Create a venv at `venv_path` from `base_python` according to `plan`,
+/// then write its environment manifest.
+pub fn create_seeded_venv(
+    base_python: &Path,
+    venv_path: &Path,
+    plan: &SeedPlan,
+) -> Result<EnvManifest> {
+    let mut cmd = std::process::Command::new(base_python);
+    cmd.args(["-m", "venv"]);
+    if !plan.uses_ensurepip() {
+        cmd.arg("--without-pip");
+    }
+    cmd.arg(venv_path);
+    let output = cmd.output().map_err(|e| SeedError::Venv {
+        path: venv_path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    if !output.status.success() {
+        return Err(SeedError::Venv {
+            path: venv_path.to_path_buf(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    let bundled: Vec<&SeedPackage> = plan.packages.iter().filter(|p| p.is_bundled()).collect();
+    if !bundled.is_empty() {
+        let target = crate::installer::InstallTarget {
+            site_packages: venv_site_packages(venv_path)?,
+            ..crate::installer::InstallTarget::for_venv(venv_path, "")
+        };
+        let bundle = seed_bundle_dir();
+        for package in bundled {
+            let wheel = ensure_bundled_wheel(base_python, &bundle, package)?;
+            crate::installer::install_wheel_into(&wheel, &target).map_err(|source| {
+                SeedError::Install {
+                    path: wheel.clone(),
+                    source,
+                }
+            })?;
+        }
+    }
+
+    let manifest = EnvManifest {
+        created_by: format!("pybun {}", env!("CARGO_PKG_VERSION")),
+        base_python: base_python.display().to_string(),
+        seed: plan.clone(),
+        seeded: installed_seed_packages(&venv_site_packages(venv_path)?),
+    };
+    manifest.save(venv_path)?;
+    Ok(manifest)
+}
+
+/// Locate `site-packages` inside a venv without spawning its interpreter.
+fn venv_site_packages(venv_path: &Path) -> Result<PathBuf> {
+    let windows = venv_path.join("Lib").join("site-packages");
+    if windows.is_dir() {
+        return Ok(windows);
+    }
+    let lib = venv_path.join("lib");
+    for entry in std::fs::read_dir(&lib)?.flatten() {
+        let candidate = entry.path().join("site-packages");
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if (name.starts_with("python") || name.starts_with("pypy")) && candidate.is_dir() {
+            return Ok(candidate);
+        }
+    }
+    Err(SeedError::Venv {
+        path: venv_path.to_path_buf(),
+        message: "site-packages directory not found".to_string(),
+    })
+}
+
+/// Return the cached wheel for `package`, fetching it into the bundle with
+/// the base interpreter's pip on a cache miss.
+fn ensure_bundled_wheel(
+    base_python: &Path,
+    bundle: &Path,
+    package: &SeedPackage,
+) -> Result<PathBuf> {
+    if let Some(wheel) = find_bundled_wheel(bundle, package) {
+        return Ok(wheel);
+    }
+
+    std::fs::create_dir_all(bundle)?;
+    let spec = package.spec();
+    let fetch_error = |message: String| SeedError::Fetch {
+        spec: spec.clone(),
+        bundle: bundle.to_path_buf(),
+        message,
+    };
+    let mut cmd = std::process::Command::new(base_python);
+    cmd.args([
+        "-m",
+        "pip",
+        "download",
+        "--quiet",
+        "--no-deps",
+        "--only-binary=:all:",
+        "--dest",
+    ])
+    .arg(bundle)
+    .arg(&spec);
+    ToolRun::new("pip download", &mut cmd)
+        .idempotent()
+        .run()
+        .map_err(|e| fetch_error(e.to_string()))?;
+
+    find_bundled_wheel(bundle, package)
+        .ok_or_else(|| fetch_error("pip download produced no wheel".to_string()))
+}
+
+/// Find a cached wheel matching `package`. `"latest"` picks the highest
+/// cached version.
+fn find_bundled_wheel(bundle: &Path, package: &SeedPackage) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(bundle).ok()?;
+    let mut candidates: Vec<(crate::pep440::Pep440Version, PathBuf)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter_map(|path| {
+            let (name, version) = wheel_name_version(&path)?;
+            if name != package.name {
+                return None;
+            }
+            match package.version.as_deref() {
+                Some("latest") | None => {}
+                Some(wanted) if wanted == version => {}
+                Some(_) => return None,
+            }
+            Some((crate::pep440::Pep440Version::parse(&version)?, path))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+    candidates.pop().map(|(_, path)| path)
+}
+
+/// Parse `(normalized_name, version)` from a wheel filename.
+fn wheel_name_version(path: &Path) -> Option<(String, String)> {
+    let stem = path.file_name()?.to_str()?.strip_suffix(".whl")?;
+    let mut parts = stem.split('-');
+    let name = normalize_project_name(parts.next()?);
+    let version = parts.next()?.to_string();
+    Some((name, version))
+}
+
+/// Scan `site-packages` for installed seed distributions.
+fn installed_seed_packages(site_packages: &Path) -> Vec<SeededPackage> {
+    let Ok(entries) = std::fs::read_dir(site_packages) else {
+        return Vec::new();
+    };
+    let mut found: Vec<SeededPackage> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let stem = file_name.to_str()?.strip_suffix(".dist-info")?;
+            let (name, version) = stem.split_once('-')?;
+            let name = normalize_project_name(name);
+            SEED_PACKAGES
+                .contains(&name.as_str())
+                .then(|| SeededPackage {
+                    name,
+                    version: version.to_string(),
+                })
+        })
+        .collect();
+    found.sort_by_key(|p| SEED_PACKAGES.iter().position(|s| *s == p.name));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_plan_uses_ensurepip_pip_only() {
+        let plan = SeedPlan::default();
+        assert!(plan.enabled);
+        assert_eq!(
+            plan.packages,
+            vec![SeedPackage {
+                name: "pip".to_string(),
+                version: None
+            }]
+        );
+        assert!(plan.uses_ensurepip());
+    }
+
+    #[test]
+    fn disabled_plan_seeds_nothing() {
+        let plan = SeedPlan::from_config(&SeedConfig {
+            enabled: Some(false),
+            pip: Some("24.2".to_string()),
+            ..Default::default()
+        });
+        assert!(!plan.enabled);
+        assert!(plan.packages.is_empty());
+        assert!(!plan.uses_ensurepip());
+    }
+
+    #[test]
+    fn pinned_plan_uses_bundle() {
+        let plan = SeedPlan::from_config(&SeedConfig {
+            enabled: None,
+            pip: Some("24.2".to_string()),
+            setuptools: Some("75.1.0".to_string()),
+            wheel: None,
+        });
+        assert!(!plan.uses_ensurepip());
+        let specs: Vec<String> = plan.packages.iter().map(SeedPackage::spec).collect();
+        assert_eq!(specs, ["pip==24.2", "setuptools==75.1.0"]);
+    }
+
+    #[test]
+    fn finds_exact_and_latest_bundled_wheels() {
+        let temp = tempfile::tempdir().unwrap();
+        for name in [
+            "pip-23.3-py3-none-any.whl",
+            "pip-24.2-py3-none-any.whl",
+            "setuptools-75.1.0-py3-none-any.whl",
+        ] {
+            std::fs::write(temp.path().join(name), b"").unwrap();
+        }
+
+        let exact = SeedPackage {
+            name: "pip".to_string(),
+            version: Some("23.3".to_string()),
+        };
+        assert!(
+            find_bundled_wheel(temp.path(), &exact)
+                .unwrap()
+                .ends_with("pip-23.3-py3-none-any.whl")
+        );
+
+        let latest = SeedPackage {
+            name: "pip".to_string(),
+            version: Some("latest".to_string()),
+        };
+        assert!(
+            find_bundled_wheel(temp.path(), &latest)
+                .unwrap()
+                .ends_with("pip-24.2-py3-none-any.whl")
+        );
+
+        let missing = SeedPackage {
+            name: "wheel".to_string(),
+            version: Some("0.44.0".to_string()),
+        };
+        assert!(find_bundled_wheel(temp.path(), &missing).is_none());
+    }
+
+    #[test]
+    fn scans_seeded_versions_from_dist_info() {
+        let temp = tempfile::tempdir().unwrap();
+        for dir in [
+            "wheel-0.44.0.dist-info",
+            "pip-24.2.dist-info",
+            "requests-2.32.0.dist-info",
+        ] {
+            std::fs::create_dir(temp.path().join(dir)).unwrap();
+        }
+        let seeded = installed_seed_packages(temp.path());
+        assert_eq!(
+            seeded,
+            vec![
+                SeededPackage {
+                    name: "pip".to_string(),
+                    version: "24.2".to_string()
+                },
+                SeededPackage {
+                    name: "wheel".to_string(),
+                    version: "0.44.0".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn creates_unseeded_venv_and_records_manifest() {
+        let Some(python) = crate::env::find_python_env(Path::new("."))
+            .ok()
+            .map(|env| env.python_path)
+        else {
+            return;
+        };
+        let temp = tempfile::tempdir().unwrap();
+        let venv = temp.path().join("venv");
+        let manifest = match create_seeded_venv(&python, &venv, &SeedPlan::disabled()) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                eprintln!("skipping: venv creation unavailable: {e}");
+                return;
+            }
+        };
+        assert!(manifest.seeded.is_empty());
+        assert_eq!(EnvManifest::load(&venv), Some(manifest));
+    }
+}
@@ -16,6 +16,19 @@ pub fn fix_candidates_for_missing_python() -> Vec<FixCandidate> {
     )]
 }
 
+/// Build the fix candidate for a script whose `requires-python` no available
+/// interpreter satisfies. `series` is the newest supported series that does
+/// (e.g. `3.12`); installing a runtime does not touch the project, so it is
+/// safe to run unattended.
+pub fn fix_candidates_for_requires_python(series: &str) -> Vec<FixCandidate> {
+    vec![FixCandidate::new(
+        format!("pybun python install {series}"),
+        format!("Install a managed Python {series} runtime, which `pybun run` then selects"),
+        RiskLevel::Low,
+        true,
+    )]
+}
+
 /// Build the fix candidate for stale PyPI metadata cache entries
 /// (see Issue #202). Safe to run unattended: it only deletes cache
 /// files that are already known to be stale.
@@ -40,6 +53,67 @@ pub fn fix_candidates_for_lock_drift() -> Vec<FixCandidate> {
     )]
 }
 
+/// Build the fix candidates for a lockfile that no longer matches
+/// pyproject.toml. `pybun lock` only rewrites the lockfile; `pybun install`
+/// also syncs the environment. Both resolve against the index, so neither
+/// is auto-applied.
+pub fn fix_candidates_for_stale_lockfile() -> Vec<FixCandidate> {
+    vec![
+        FixCandidate::new(
+            "pybun lock",
+            "Re-resolve and rewrite pybun.lockb from pyproject.toml",
+            RiskLevel::Medium,
+            false,
+        ),
+        FixCandidate::new(
+            "pybun install",
+            "Re-lock and install the updated dependencies into the project environment",
+            RiskLevel::Medium,
+            false,
+        ),
+    ]
+}
+
+/// Build the fix candidates for HTTPS requests failing certificate
+/// validation, typically because a corporate proxy re-signs traffic with
+/// an in-house CA. Neither is auto-applied: the CA file has to come from
+/// the organization, and changing the system trust store needs privileges.
+pub fn fix_candidates_for_tls_interception(system_certs: bool) -> Vec<FixCandidate> {
+    let mut candidates = vec![FixCandidate::new(
+        "export PYBUN_CA_BUNDLE=/path/to/corporate-root-ca.pem",
+        "Trust your organization's root CA (PEM) for PyBun's HTTPS requests, or set `ca-bundle` in [tool.pybun.http]",
+        RiskLevel::Medium,
+        false,
+    )];
+    if system_certs {
+        candidates.push(FixCandidate::new(
+            "sudo update-ca-certificates",
+            "Install the corporate root CA into the system trust store (Debian/Ubuntu; `update-ca-trust` on Fedora/RHEL); PyBun reads it by default",
+            RiskLevel::High,
+            false,
+        ));
+    } else {
+        candidates.push(FixCandidate::new(
+            "export PYBUN_SYSTEM_CERTS=1",
+            "Trust the system CA bundle again (disabled by PYBUN_SYSTEM_CERTS or `system-certs = false`)",
+            RiskLevel::Low,
+            false,
+        ));
+    }
+    candidates
+}
+
+/// Build the fix candidate for a configured CA bundle or proxy that could
+/// not be applied (missing/unreadable file, malformed URL).
+pub fn fix_candidates_for_http_config() -> Vec<FixCandidate> {
+    vec![FixCandidate::new(
+        "export PYBUN_CA_BUNDLE=/path/to/ca-bundle.pem",
+        "Point PyBun at a readable PEM bundle, or fix `ca-bundle`/`proxy` in [tool.pybun.http]",
+        RiskLevel::Medium,
+        false,
+    )]
+}
+
 pub fn diagnostics_for_resolve_error(
     requirements: &[Requirement],
     err: &ResolveError,
@@ -136,6 +210,18 @@ pub fn diagnostics_for_resolve_error(
                 .with_context(json!({ "error": msg })),
             ]
         }
+        ResolveError::Backend { resolver, message } => {
+            vec![
+                Diagnostic::error(format!("The {resolver} resolver failed: {message}"))
+                    .with_code("E_RESOLVE_BACKEND")
+                    .with_suggestion(
+                        "Fix the resolver setup (e.g. install uv for `--resolver external:uv`), or re-run with `--resolver default`.",
+                    )
+                    .with_context(json!({ "resolver": resolver, "error": message })),
+            ]
+        }
+        // Reported as `E_POLICY_VIOLATION` from the recorded violation.
+        ResolveError::Policy(_) => Vec::new(),
         ResolveError::PythonIncompatible(details) => {
             let crate::resolver::PythonIncompatibility {
                 name,
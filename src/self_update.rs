@@ -1,6 +1,6 @@
+use crate::network_policy::{self, Operation};
 use crate::release_manifest::{ReleaseAsset, ReleaseSignature};
 use crate::security::{sha256_file, verify_ed25519_signature};
-use reqwest::blocking::Client;
 use std::fmt::{Display, Formatter};
 use std::fs;
 use std::io::{self, Write};
@@ -8,7 +8,6 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tempfile::TempDir;
-use zip::ZipArchive;
 
 #[derive(Debug, Clone)]
 pub struct ApplyOutcome {
@@ -68,6 +67,16 @@ pub fn apply_update_for_asset(
     })
 }
 
+/// Atomically replace the binary at `install_path` with `binary` (used by
+/// `self rollback` to restore a kept copy).
+pub fn install_binary(install_path: &Path, binary: &Path) -> ApplyResult<ApplyOutcome> {
+    let swap = atomic_replace_binary(install_path, binary, false)?;
+    Ok(ApplyOutcome {
+        install_path: install_path.to_path_buf(),
+        rollback_performed: swap.rollback_performed,
+    })
+}
+
 fn err(message: impl Into<String>) -> ApplyError {
     ApplyError {
         message: message.into(),
@@ -75,7 +84,9 @@ fn err(message: impl Into<String>) -> ApplyError {
     }
 }
 
-fn resolve_install_path(install_path_override: Option<PathBuf>) -> ApplyResult<PathBuf> {
+/// The binary `self update` replaces: `install_path_override` or the
+/// running executable.
+pub fn resolve_install_path(install_path_override: Option<PathBuf>) -> ApplyResult<PathBuf> {
     let install_path = if let Some(path) = install_path_override {
         path
     } else {
@@ -101,8 +112,10 @@ fn download_asset(url: &str, destination: &Path) -> ApplyResult<()> {
     }
 
     if url.starts_with("http://") || url.starts_with("https://") {
-        let client = Client::builder()
+        network_policy::check_url(Operation::SelfUpdate, url).map_err(|e| err(e.to_string()))?;
+        let client = crate::http_config::blocking_client_builder()
             .timeout(Duration::from_secs(300))
+            .redirect(network_policy::redirect_policy(Operation::SelfUpdate))
             .build()
             .map_err(|e| err(format!("failed to build http client: {e}")))?;
         let mut response = client
@@ -226,83 +239,18 @@ fn write_text_with_newline(path: &Path, value: &str) -> ApplyResult<()> {
 }
 
 fn extract_archive(archive_path: &Path, destination: &Path) -> ApplyResult<()> {
-    let name = archive_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or_default()
-        .to_ascii_lowercase();
-    if name.ends_with(".zip") {
-        return extract_zip(archive_path, destination);
-    }
-    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
-        return extract_tar_gz(archive_path, destination);
-    }
-    Err(err(format!(
-        "unsupported archive format: {}",
-        archive_path.display()
-    )))
-}
-
-fn extract_tar_gz(archive_path: &Path, destination: &Path) -> ApplyResult<()> {
-    let output = Command::new("tar")
-        .arg("-xzf")
-        .arg(archive_path)
-        .arg("-C")
-        .arg(destination)
-        .output()
-        .map_err(|e| err(format!("failed to run tar: {e}")))?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(err(format!(
-            "failed to extract archive {}: {}",
-            archive_path.display(),
-            stderr.trim()
-        )));
-    }
-    Ok(())
-}
-
-fn extract_zip(archive_path: &Path, destination: &Path) -> ApplyResult<()> {
-    let file = fs::File::open(archive_path).map_err(|e| {
+    crate::archive::extract(
+        archive_path,
+        destination,
+        crate::archive::ExtractOptions::default(),
+    )
+    .map(|_| ())
+    .map_err(|e| {
         err(format!(
-            "failed to open zip archive {}: {e}",
+            "failed to extract archive {}: {e}",
             archive_path.display()
         ))
-    })?;
-    let mut archive =
-        ZipArchive::new(file).map_err(|e| err(format!("failed to parse zip archive: {e}")))?;
-    for index in 0..archive.len() {
-        let mut entry = archive
-            .by_index(index)
-            .map_err(|e| err(format!("failed to read zip entry: {e}")))?;
-        let enclosed = match entry.enclosed_name() {
-            Some(path) => path.to_path_buf(),
-            None => continue,
-        };
-        let out_path = destination.join(enclosed);
-        if entry.is_dir() {
-            fs::create_dir_all(&out_path).map_err(|e| {
-                err(format!(
-                    "failed to create directory {}: {e}",
-                    out_path.display()
-                ))
-            })?;
-            continue;
-        }
-        if let Some(parent) = out_path.parent() {
-            fs::create_dir_all(parent).map_err(|e| {
-                err(format!(
-                    "failed to create directory {}: {e}",
-                    parent.display()
-                ))
-            })?;
-        }
-        let mut out_file = fs::File::create(&out_path)
-            .map_err(|e| err(format!("failed to create file {}: {e}", out_path.display())))?;
-        io::copy(&mut entry, &mut out_file)
-            .map_err(|e| err(format!("failed to extract {}: {e}", out_path.display())))?;
-    }
-    Ok(())
+    })
 }
 
 fn locate_binary(extracted_root: &Path, target: &str) -> ApplyResult<PathBuf> {
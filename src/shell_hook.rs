@@ -0,0 +1,723 @@
+//! Shell hook for automatic environment activation (direnv-style).
+//!
+//! `pybun hook install <shell>` writes a marked block into the shell's rc
+//! file. The block defines a prompt/cd hook that runs `pybun hook export
+//! <shell>` and evaluates its output. `export` compares the current directory
+//! with the project the hook last activated (tracked in `PYBUN_HOOK_ACTIVE`)
+//! and emits only the changes needed:
+//!
+//! - entering a project with a venv (found like every other command finds
+//!   it: `.pybun/venv`, `.venv` or `venv`): prepend the venv `bin` directory
+//!   to `PATH`, set `VIRTUAL_ENV`, and set the prompt marker `PYBUN_PROMPT`,
+//! - leaving it: remove the venv from `PATH`, restore the `VIRTUAL_ENV` the
+//!   shell had before, and unset the hook's variables.
+//!
+//! ## Trust
+//! Activating a project puts its `bin` directory first on `PATH`, so a
+//! cloned repository could shadow any command. The hook only activates
+//! projects the user allowed with `pybun hook allow` (kept by path in
+//! `~/.pybun/hook-trust.json`); for others it prints a one-line notice.
+//!
+//! ## Kill switch
+//! 1. Environment variable: `PYBUN_HOOK=0|1`
+//! 2. Config file: `~/.pybun/hook.json` (`pybun hook disable|enable`)
+//! 3. Default: enabled
+//!
+//! When disabled, `export` still deactivates an already-active project so
+//! turning the hook off never leaves a stale `PATH` behind.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Marker lines delimiting the block written into rc files.
+pub const BLOCK_START: &str = "# >>> pybun hook >>>";
+pub const BLOCK_END: &str = "# <<< pybun hook <<<";
+
+/// Variables owned by the hook.
+const ACTIVE_VAR: &str = "PYBUN_HOOK_ACTIVE";
+const VENV_VAR: &str = "PYBUN_HOOK_VENV";
+const PREVIOUS_VENV_VAR: &str = "PYBUN_HOOK_PREVIOUS_VIRTUAL_ENV";
+const BLOCKED_VAR: &str = "PYBUN_HOOK_BLOCKED";
+const PROMPT_VAR: &str = "PYBUN_PROMPT";
+
+#[derive(Debug, Error)]
+pub enum HookError {
+    #[error("failed to determine home directory")]
+    NoHomeDir,
+    #[error("failed to update {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to write hook config: {0}")]
+    Config(String),
+}
+
+pub type Result<T> = std::result::Result<T, HookError>;
+
+/// Shells supported by the hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    /// Default rc file for this shell under `home`.
+    pub fn rc_file(self, home: &Path) -> PathBuf {
+        match self {
+            Shell::Bash => home.join(".bashrc"),
+            Shell::Zsh => home.join(".zshrc"),
+            Shell::Fish => home.join(".config").join("fish").join("config.fish"),
+        }
+    }
+}
+
+/// Source of the hook enablement setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookSource {
+    Default,
+    Config,
+    Environment,
+}
+
+impl std::fmt::Display for HookSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookSource::Default => write!(f, "default"),
+            HookSource::Config => write!(f, "config"),
+            HookSource::Environment => write!(f, "environment"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HookConfig {
+    enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TrustConfig {
+    allowed: Vec<PathBuf>,
+}
+
+/// Loads and saves the hook kill switch (`hook.json`) and the projects the
+/// hook may activate (`hook-trust.json`).
+#[derive(Debug, Clone)]
+pub struct HookManager {
+    config_path: PathBuf,
+    trust_path: PathBuf,
+}
+
+impl HookManager {
+    /// Create a manager rooted at the PyBun data directory.
+    pub fn new(config_dir: &Path) -> Self {
+        Self {
+            config_path: config_dir.join("hook.json"),
+            trust_path: config_dir.join("hook-trust.json"),
+        }
+    }
+
+    /// Project roots the hook may activate.
+    pub fn allowed(&self) -> Vec<PathBuf> {
+        std::fs::read_to_string(&self.trust_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<TrustConfig>(&content).ok())
+            .map(|config| config.allowed)
+            .unwrap_or_default()
+    }
+
+    /// Whether the hook may activate the project at `root`.
+    pub fn is_allowed(&self, root: &Path) -> bool {
+        is_allowed(&self.allowed(), root)
+    }
+
+    /// Allow (`true`) or deny the project at `root`. Returns `true` when the
+    /// trust list changed.
+    pub fn set_allowed(&self, root: &Path, allowed: bool) -> Result<bool> {
+        let root = canonical(root);
+        let mut config = TrustConfig {
+            allowed: self.allowed(),
+        };
+        let before = config.allowed.len();
+        config.allowed.retain(|path| *path != root);
+        if allowed {
+            config.allowed.push(root);
+            config.allowed.sort();
+        }
+        if before == config.allowed.len() {
+            // Allowing an allowed project, or denying an unknown one.
+            return Ok(false);
+        }
+        write_json(&self.trust_path, &config)?;
+        Ok(true)
+    }
+
+    /// Whether the hook is enabled, and where that setting came from.
+    pub fn enabled(&self) -> (bool, HookSource) {
+        if let Ok(value) = std::env::var("PYBUN_HOOK") {
+            let enabled = !matches!(value.trim(), "0" | "false" | "no" | "off");
+            return (enabled, HookSource::Environment);
+        }
+        if let Ok(content) = std::fs::read_to_string(&self.config_path)
+            && let Ok(config) = serde_json::from_str::<HookConfig>(&content)
+        {
+            return (config.enabled, HookSource::Config);
+        }
+        (true, HookSource::Default)
+    }
+
+    /// Persist the kill switch.
+    pub fn set_enabled(&self, enabled: bool) -> Result<()> {
+        write_json(&self.config_path, &HookConfig { enabled })
+    }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| HookError::Config(e.to_string()))?;
+    }
+    let content =
+        serde_json::to_string_pretty(value).map_err(|e| HookError::Config(e.to_string()))?;
+    std::fs::write(path, content).map_err(|e| HookError::Config(e.to_string()))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn is_allowed(allowed: &[PathBuf], root: &Path) -> bool {
+    allowed.contains(&canonical(root))
+}
+
+/// A project the hook can activate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectEnv {
+    /// Directory with the project's `pyproject.toml`.
+    pub root: PathBuf,
+    /// The project's venv, as [`crate::env::find_project_venv`] finds it.
+    pub venv: PathBuf,
+}
+
+/// The project root above `start`: the nearest directory with a
+/// `pyproject.toml`.
+pub fn find_project_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join("pyproject.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// The nearest project above `start`, if it has a venv.
+pub fn find_project_env(start: &Path) -> Option<ProjectEnv> {
+    let root = find_project_root(start)?;
+    let venv = crate::env::find_project_venv(&root)?;
+    Some(ProjectEnv { root, venv })
+}
+
+fn venv_bin(venv: &Path) -> PathBuf {
+    venv.join("bin")
+}
+
+/// Prompt marker shown while a project is active, e.g. `(pybun:myapp) `.
+fn prompt_marker(root: &Path) -> String {
+    let name = root
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("project");
+    format!("(pybun:{name}) ")
+}
+
+/// The shell environment as the hook left it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookState {
+    /// Root of the project the hook activated (`PYBUN_HOOK_ACTIVE`).
+    pub active: Option<PathBuf>,
+    /// Venv it activated (`PYBUN_HOOK_VENV`).
+    pub venv: Option<PathBuf>,
+    /// The user's `VIRTUAL_ENV` from before the activation, restored when
+    /// leaving the project.
+    pub previous_virtual_env: Option<String>,
+    /// Current `VIRTUAL_ENV`.
+    pub virtual_env: Option<String>,
+    /// Untrusted project the hook last printed a notice for.
+    pub blocked: Option<PathBuf>,
+    pub path: String,
+}
+
+impl HookState {
+    /// Read the state from the process environment.
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        Self {
+            active: var(ACTIVE_VAR).map(PathBuf::from),
+            venv: var(VENV_VAR).map(PathBuf::from),
+            previous_virtual_env: std::env::var(PREVIOUS_VENV_VAR).ok(),
+            virtual_env: std::env::var("VIRTUAL_ENV").ok(),
+            blocked: var(BLOCKED_VAR).map(PathBuf::from),
+            path: std::env::var("PATH").unwrap_or_default(),
+        }
+    }
+}
+
+/// Environment changes computed by [`export`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookDiff {
+    /// Variables to set.
+    pub set: Vec<(String, String)>,
+    /// Variables to unset.
+    pub unset: Vec<String>,
+    /// Notices for the user, printed to stderr.
+    pub messages: Vec<String>,
+}
+
+impl HookDiff {
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty() && self.messages.is_empty()
+    }
+}
+
+/// Compute the environment changes for `cwd` from the hook's last `state`.
+/// Only projects in `allowed` are activated.
+pub fn export(cwd: &Path, state: &HookState, enabled: bool, allowed: &[PathBuf]) -> HookDiff {
+    let found = if enabled { find_project_env(cwd) } else { None };
+    let (target, untrusted) = match found {
+        Some(project) if is_allowed(allowed, &project.root) => (Some(project), None),
+        Some(project) => (None, Some(project.root)),
+        None => (None, None),
+    };
+    let mut diff = HookDiff::default();
+
+    if target.as_ref().map(|t| t.root.as_path()) != state.active.as_deref() {
+        let mut entries: Vec<String> = std::env::split_paths(&state.path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        if let Some(old) = &state.venv {
+            let old_bin = venv_bin(old).to_string_lossy().into_owned();
+            entries.retain(|entry| *entry != old_bin);
+        }
+
+        match &target {
+            Some(project) => {
+                entries.insert(0, venv_bin(&project.venv).to_string_lossy().into_owned());
+                // Remember the user's own venv when the hook first takes
+                // over `VIRTUAL_ENV`.
+                if state.active.is_none()
+                    && let Some(virtual_env) = &state.virtual_env
+                {
+                    diff.set
+                        .push((PREVIOUS_VENV_VAR.to_string(), virtual_env.clone()));
+                }
+                let venv = project.venv.to_string_lossy().into_owned();
+                diff.set.push(("VIRTUAL_ENV".to_string(), venv.clone()));
+                diff.set.push((VENV_VAR.to_string(), venv));
+                diff.set.push((
+                    ACTIVE_VAR.to_string(),
+                    project.root.to_string_lossy().into_owned(),
+                ));
+                diff.set
+                    .push((PROMPT_VAR.to_string(), prompt_marker(&project.root)));
+            }
+            None => {
+                match &state.previous_virtual_env {
+                    Some(previous) => {
+                        diff.set.push(("VIRTUAL_ENV".to_string(), previous.clone()));
+                        diff.unset.push(PREVIOUS_VENV_VAR.to_string());
+                    }
+                    None => diff.unset.push("VIRTUAL_ENV".to_string()),
+                }
+                diff.unset.extend([
+                    VENV_VAR.to_string(),
+                    ACTIVE_VAR.to_string(),
+                    PROMPT_VAR.to_string(),
+                ]);
+            }
+        }
+        diff.set.insert(0, ("PATH".to_string(), entries.join(":")));
+    }
+
+    // One notice per untrusted project, not one per prompt.
+    if untrusted != state.blocked {
+        match untrusted {
+            Some(root) => {
+                diff.messages.push(format!(
+                    "pybun: {} is not allowed to activate its environment; run `pybun hook allow` there to trust it",
+                    root.display()
+                ));
+                diff.set
+                    .push((BLOCKED_VAR.to_string(), root.to_string_lossy().into_owned()));
+            }
+            None => diff.unset.push(BLOCKED_VAR.to_string()),
+        }
+    }
+    diff
+}
+
+/// Render a [`HookDiff`] as shell code to be evaluated by the hook.
+pub fn render_diff(shell: Shell, diff: &HookDiff) -> String {
+    let mut lines = Vec::new();
+    for (name, value) in &diff.set {
+        lines.push(match shell {
+            Shell::Bash | Shell::Zsh => format!("export {name}={};", quote(value)),
+            Shell::Fish if name == "PATH" => {
+                let parts: Vec<String> = value.split(':').map(quote).collect();
+                format!("set -gx PATH {};", parts.join(" "))
+            }
+            Shell::Fish => format!("set -gx {name} {};", quote(value)),
+        });
+    }
+    for name in &diff.unset {
+        lines.push(match shell {
+            Shell::Bash | Shell::Zsh => format!("unset {name};"),
+            Shell::Fish => format!("set -e {name};"),
+        });
+    }
+    for message in &diff.messages {
+        lines.push(format!("echo {} >&2;", quote(message)));
+    }
+    lines.join("\n")
+}
+
+/// Single-quote `value` for POSIX shells and fish.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Hook script for `shell`, invoking `exe` to compute changes.
+pub fn hook_script(shell: Shell, exe: &Path) -> String {
+    let exe = quote(&exe.to_string_lossy());
+    match shell {
+        Shell::Bash => format!(
+            r#"_pybun_hook() {{
+  local previous_exit_status=$?
+  eval "$({exe} hook export bash)"
+  if [ -n "${{_PYBUN_OLD_PS1+x}}" ]; then PS1="$_PYBUN_OLD_PS1"; unset _PYBUN_OLD_PS1; fi
+  if [ -n "${{PYBUN_PROMPT-}}" ]; then _PYBUN_OLD_PS1="$PS1"; PS1="$PYBUN_PROMPT$PS1"; fi
+  return $previous_exit_status
+}}
+if [[ ";${{PROMPT_COMMAND[*]:-}};" != *";_pybun_hook;"* ]]; then
+  PROMPT_COMMAND="_pybun_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi"#
+        ),
+        Shell::Zsh => format!(
+            r#"_pybun_hook() {{
+  eval "$({exe} hook export zsh)"
+  if [[ -n "${{_PYBUN_OLD_PS1+x}}" ]]; then PS1="$_PYBUN_OLD_PS1"; unset _PYBUN_OLD_PS1; fi
+  if [[ -n "${{PYBUN_PROMPT-}}" ]]; then _PYBUN_OLD_PS1="$PS1"; PS1="$PYBUN_PROMPT$PS1"; fi
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd _pybun_hook
+add-zsh-hook chpwd _pybun_hook"#
+        ),
+        Shell::Fish => format!(
+            r#"function __pybun_hook --on-variable PWD
+  {exe} hook export fish | source
+end
+if not functions -q __pybun_original_prompt; and functions -q fish_prompt
+  functions -c fish_prompt __pybun_original_prompt
+  function fish_prompt
+    set -q PYBUN_PROMPT; and echo -n $PYBUN_PROMPT
+    __pybun_original_prompt
+  end
+end
+__pybun_hook"#
+        ),
+    }
+}
+
+/// Marked rc-file block wrapping the hook script.
+pub fn rc_block(shell: Shell, exe: &Path) -> String {
+    format!("{BLOCK_START}\n{}\n{BLOCK_END}\n", hook_script(shell, exe))
+}
+
+/// Whether `rc_file` contains the PyBun hook block.
+pub fn is_installed(rc_file: &Path) -> bool {
+    std::fs::read_to_string(rc_file)
+        .map(|content| content.contains(BLOCK_START))
+        .unwrap_or(false)
+}
+
+/// Write (or replace) the hook block in `rc_file`. Returns `true` when the
+/// file changed.
+pub fn install(shell: Shell, exe: &Path, rc_file: &Path) -> Result<bool> {
+    let io_err = |source| HookError::Io {
+        path: rc_file.to_path_buf(),
+        source,
+    };
+    let existing = match std::fs::read_to_string(rc_file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(io_err(e)),
+    };
+    let block = rc_block(shell, exe);
+
+    let updated = match (existing.find(BLOCK_START), existing.find(BLOCK_END)) {
+        (Some(start), Some(end)) if end > start => {
+            let end = existing[end..]
+                .find('\n')
+                .map(|i| end + i + 1)
+                .unwrap_or(existing.len());
+            format!("{}{}{}", &existing[..start], block, &existing[end..])
+        }
+        _ if existing.is_empty() || existing.ends_with('\n') => format!("{existing}{block}"),
+        _ => format!("{existing}\n{block}"),
+    };
+    if updated == existing {
+        return Ok(false);
+    }
+    if let Some(parent) = rc_file.parent() {
+        std::fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    std::fs::write(rc_file, updated).map_err(io_err)?;
+    Ok(true)
+}
+
+/// Default rc file for `shell` in the user's home directory.
+pub fn default_rc_file(shell: Shell) -> Result<PathBuf> {
+    let home = dirs::home_dir().ok_or(HookError::NoHomeDir)?;
+    Ok(shell.rc_file(&home))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// A project at `root` with a `.pybun/venv`; returns the venv.
+    fn project_with_venv(root: &Path) -> PathBuf {
+        project_with_named_venv(root, ".pybun/venv")
+    }
+
+    fn project_with_named_venv(root: &Path, name: &str) -> PathBuf {
+        std::fs::write(root.join("pyproject.toml"), "[project]\nname = \"demo\"\n").unwrap();
+        let venv = root.join(name);
+        std::fs::create_dir_all(venv_bin(&venv)).unwrap();
+        std::fs::write(venv_bin(&venv).join("python"), "").unwrap();
+        venv
+    }
+
+    fn state(path: &str) -> HookState {
+        HookState {
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn active(root: &Path, venv: &Path, path: &str) -> HookState {
+        HookState {
+            active: Some(root.to_path_buf()),
+            venv: Some(venv.to_path_buf()),
+            virtual_env: Some(venv.display().to_string()),
+            ..state(path)
+        }
+    }
+
+    #[test]
+    fn export_activates_project_and_sets_prompt() {
+        let temp = tempdir().unwrap();
+        let root = temp.path().join("demo");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let venv = project_with_venv(&root);
+
+        let diff = export(
+            &root.join("src"),
+            &state("/usr/bin:/bin"),
+            true,
+            std::slice::from_ref(&root),
+        );
+        let path = &diff.set[0].1;
+        assert_eq!(
+            path,
+            &format!("{}:/usr/bin:/bin", venv_bin(&venv).display())
+        );
+        assert!(
+            diff.set
+                .contains(&(PROMPT_VAR.to_string(), "(pybun:demo) ".to_string()))
+        );
+        assert!(diff.unset.is_empty());
+    }
+
+    #[test]
+    fn export_uses_the_venv_env_discovery_finds() {
+        let temp = tempdir().unwrap();
+        let venv = project_with_named_venv(temp.path(), ".venv");
+        let diff = export(
+            temp.path(),
+            &state("/bin"),
+            true,
+            &[temp.path().to_path_buf()],
+        );
+        assert!(
+            diff.set
+                .contains(&("VIRTUAL_ENV".to_string(), venv.display().to_string()))
+        );
+        assert_eq!(diff.set[0].1, format!("{}:/bin", venv_bin(&venv).display()));
+    }
+
+    #[test]
+    fn export_is_noop_when_project_already_active() {
+        let temp = tempdir().unwrap();
+        let venv = project_with_venv(temp.path());
+        let diff = export(
+            temp.path(),
+            &active(temp.path(), &venv, "/bin"),
+            true,
+            &[temp.path().to_path_buf()],
+        );
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn export_deactivates_when_leaving_or_disabled() {
+        let temp = tempdir().unwrap();
+        let root = temp.path().join("demo");
+        std::fs::create_dir_all(&root).unwrap();
+        let venv = project_with_venv(&root);
+        let allowed = std::slice::from_ref(&root);
+        let path = format!("{}:/usr/bin", venv_bin(&venv).display());
+
+        let diff = export(temp.path(), &active(&root, &venv, &path), true, allowed);
+        assert_eq!(diff.set, vec![("PATH".to_string(), "/usr/bin".to_string())]);
+        assert!(diff.unset.contains(&ACTIVE_VAR.to_string()));
+        assert!(diff.unset.contains(&"VIRTUAL_ENV".to_string()));
+
+        let diff = export(&root, &active(&root, &venv, &path), false, allowed);
+        assert_eq!(diff.set[0].1, "/usr/bin");
+    }
+
+    #[test]
+    fn export_restores_the_users_virtual_env() {
+        let temp = tempdir().unwrap();
+        let root = temp.path().join("demo");
+        std::fs::create_dir_all(&root).unwrap();
+        let venv = project_with_venv(&root);
+        let allowed = std::slice::from_ref(&root);
+        let own = "/home/me/envs/tools".to_string();
+
+        let entering = HookState {
+            virtual_env: Some(own.clone()),
+            ..state("/home/me/envs/tools/bin:/usr/bin")
+        };
+        let diff = export(&root, &entering, true, allowed);
+        assert!(
+            diff.set
+                .contains(&(PREVIOUS_VENV_VAR.to_string(), own.clone()))
+        );
+
+        let leaving = HookState {
+            previous_virtual_env: Some(own.clone()),
+            ..active(
+                &root,
+                &venv,
+                &format!(
+                    "{}:/home/me/envs/tools/bin:/usr/bin",
+                    venv_bin(&venv).display()
+                ),
+            )
+        };
+        let diff = export(temp.path(), &leaving, true, allowed);
+        assert_eq!(diff.set[0].1, "/home/me/envs/tools/bin:/usr/bin");
+        assert!(diff.set.contains(&("VIRTUAL_ENV".to_string(), own)));
+        assert!(diff.unset.contains(&PREVIOUS_VENV_VAR.to_string()));
+        assert!(!diff.unset.contains(&"VIRTUAL_ENV".to_string()));
+    }
+
+    #[test]
+    fn untrusted_projects_are_not_activated() {
+        let temp = tempdir().unwrap();
+        project_with_venv(temp.path());
+
+        let diff = export(temp.path(), &state("/bin"), true, &[]);
+        assert!(diff.set.iter().all(|(name, _)| name == BLOCKED_VAR));
+        assert_eq!(diff.messages.len(), 1);
+        assert!(diff.messages[0].contains("pybun hook allow"));
+
+        // The notice is printed once per project.
+        let blocked = HookState {
+            blocked: Some(temp.path().to_path_buf()),
+            ..state("/bin")
+        };
+        assert!(export(temp.path(), &blocked, true, &[]).is_empty());
+    }
+
+    #[test]
+    fn trust_list_allows_and_denies_projects() {
+        let temp = tempdir().unwrap();
+        let project = temp.path().join("demo");
+        std::fs::create_dir_all(&project).unwrap();
+        let manager = HookManager::new(&temp.path().join("config"));
+
+        assert!(!manager.is_allowed(&project));
+        assert!(manager.set_allowed(&project, true).unwrap());
+        assert!(!manager.set_allowed(&project, true).unwrap());
+        assert!(manager.is_allowed(&project));
+        assert!(manager.set_allowed(&project, false).unwrap());
+        assert!(!manager.set_allowed(&project, false).unwrap());
+        assert!(!manager.is_allowed(&project));
+    }
+
+    #[test]
+    fn project_without_venv_is_ignored() {
+        let temp = tempdir().unwrap();
+        std::fs::write(temp.path().join("pyproject.toml"), "").unwrap();
+        assert!(find_project_env(temp.path()).is_none());
+        assert!(export(temp.path(), &state("/bin"), true, &[]).is_empty());
+    }
+
+    #[test]
+    fn render_diff_per_shell() {
+        let diff = HookDiff {
+            set: vec![("PATH".to_string(), "/a b:/c".to_string())],
+            unset: vec!["VIRTUAL_ENV".to_string()],
+            messages: vec!["pybun: it's blocked".to_string()],
+        };
+        assert_eq!(
+            render_diff(Shell::Bash, &diff),
+            "export PATH='/a b:/c';\nunset VIRTUAL_ENV;\necho 'pybun: it'\\''s blocked' >&2;"
+        );
+        assert_eq!(
+            render_diff(Shell::Fish, &diff),
+            "set -gx PATH '/a b' '/c';\nset -e VIRTUAL_ENV;\necho 'pybun: it'\\''s blocked' >&2;"
+        );
+    }
+    #[test]
+    fn install_is_idempotent_and_replaces_block() {
+        let temp = tempdir().unwrap();
+        let rc = temp.path().join(".bashrc");
+        std::fs::write(&rc, "alias ll='ls -l'").unwrap();
+
+        assert!(install(Shell::Bash, Path::new("/opt/pybun"), &rc).unwrap());
+        assert!(!install(Shell::Bash, Path::new("/opt/pybun"), &rc).unwrap());
+        assert!(install(Shell::Bash, Path::new("/usr/bin/pybun"), &rc).unwrap());
+
+        let content = std::fs::read_to_string(&rc).unwrap();
+        assert!(content.starts_with("alias ll='ls -l'\n"));
+        assert_eq!(content.matches(BLOCK_START).count(), 1);
+        assert!(content.contains("'/usr/bin/pybun' hook export bash"));
+        assert!(!content.contains("/opt/pybun"));
+    }
+
+    #[test]
+    fn kill_switch_persists() {
+        let temp = tempdir().unwrap();
+        let manager = HookManager::new(temp.path());
+        if std::env::var_os("PYBUN_HOOK").is_none() {
+            assert_eq!(manager.enabled(), (true, HookSource::Default));
+            manager.set_enabled(false).unwrap();
+            assert_eq!(manager.enabled(), (false, HookSource::Config));
+        }
+    }
+}
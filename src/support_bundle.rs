@@ -283,7 +283,9 @@ pub fn upload_bundle(bundle: &BundleCollection, upload_url: &str) -> UploadOutco
     let upload_url_thread = upload_url.clone();
 
     std::thread::spawn(move || {
-        let client = reqwest::blocking::Client::new();
+        let client = crate::http_config::blocking_client_builder()
+            .build()
+            .unwrap_or_else(|_| reqwest::blocking::Client::new());
         match client.post(&upload_url_thread).json(&payload).send() {
             Ok(response) => UploadOutcome {
                 url: upload_url_thread.clone(),
@@ -0,0 +1,717 @@
+//! Heuristics flagging new dependencies that may be typosquats or hijacked.
+//!
+//! When `pybun add` or `pybun install` brings in a direct dependency the
+//! project's lockfile does not have yet, it is checked for:
+//!
+//! - **typosquat**: the name is a small edit (insertion, deletion,
+//!   substitution, transposition, or only separators) away from a popular
+//!   PyPI package in [`POPULAR`]. The list is ordered by downloads and the
+//!   allowed distance is weighted by popularity: names close to the most
+//!   popular packages are matched at two edits, the rest at one.
+//!   Established packages that happen to sit that close to a popular one
+//!   (`scapy`/`scipy`, `moto`/`boto`) are listed in [`ESTABLISHED`].
+//! - **new-package**: its first release is less than [`NEW_PACKAGE_DAYS`]
+//!   days old.
+//! - **maintainer-change**: none of the author/maintainer emails of its
+//!   latest release appear on the previous release.
+//!
+//! Typosquats are judged from the name alone, before anything is written or
+//! resolved; the other two use the index metadata fetched while resolving
+//! (PyPI JSON API only). Findings are `E_SUSPICIOUS_PACKAGE` errors that
+//! stop the command unless `--allow-suspicious` is given, which turns them
+//! into `W_SUSPICIOUS_PACKAGE` warnings. Packages let through that way are
+//! [`flag`]ged, and their sdists are only built in a container (see
+//! [`crate::build_isolation`]). The flags are [`record`]ed in the lockfile's
+//! `container_builds` and [`restore`]d by later installs, which no longer
+//! check the package once it is locked. Packages matching `[packages] approved` in
+//! `pybun-policy.toml` (see [`crate::execution_policy`]) are not checked.
+
+use crate::lockfile::Lockfile;
+use crate::schema::Diagnostic;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Mutex;
+
+static FLAGGED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Releases younger than this many days make a package "new".
+pub const NEW_PACKAGE_DAYS: u64 = 30;
+
+/// Packages among the first this many of [`POPULAR`] are matched at two
+/// edits when the name is long enough.
+const TOP_TIER: usize = 50;
+
+/// Well-known packages within matching distance of a [`POPULAR`] name that
+/// are not typosquats of it (PEP 503 normalized).
+pub const ESTABLISHED: &[&str] = &["cattrs", "moto", "pyaml", "scapy", "scrypt"];
+
+/// Popular PyPI packages, most downloaded first (PEP 503 normalized).
+pub const POPULAR: &[&str] = &[
+    "boto3",
+    "urllib3",
+    "botocore",
+    "requests",
+    "setuptools",
+    "certifi",
+    "charset-normalizer",
+    "idna",
+    "typing-extensions",
+    "python-dateutil",
+    "s3transfer",
+    "packaging",
+    "aiobotocore",
+    "six",
+    "numpy",
+    "grpcio-status",
+    "pyyaml",
+    "s3fs",
+    "fsspec",
+    "pip",
+    "cryptography",
+    "pydantic",
+    "cffi",
+    "attrs",
+    "google-api-core",
+    "pycparser",
+    "pandas",
+    "importlib-metadata",
+    "pydantic-core",
+    "protobuf",
+    "jmespath",
+    "rsa",
+    "zipp",
+    "pyasn1",
+    "wheel",
+    "click",
+    "platformdirs",
+    "jinja2",
+    "markupsafe",
+    "awscli",
+    "colorama",
+    "pytz",
+    "filelock",
+    "googleapis-common-protos",
+    "tomli",
+    "cachetools",
+    "virtualenv",
+    "pluggy",
+    "pytest",
+    "pyjwt",
+    "wrapt",
+    "jsonschema",
+    "annotated-types",
+    "google-auth",
+    "pyasn1-modules",
+    "aiohttp",
+    "requests-oauthlib",
+    "oauthlib",
+    "multidict",
+    "yarl",
+    "sqlalchemy",
+    "frozenlist",
+    "aiosignal",
+    "greenlet",
+    "iniconfig",
+    "psutil",
+    "docutils",
+    "tzdata",
+    "exceptiongroup",
+    "pyarrow",
+    "grpcio",
+    "isodate",
+    "pygments",
+    "soupsieve",
+    "beautifulsoup4",
+    "decorator",
+    "werkzeug",
+    "lxml",
+    "openpyxl",
+    "et-xmlfile",
+    "distlib",
+    "tqdm",
+    "httpx",
+    "httpcore",
+    "h11",
+    "anyio",
+    "sniffio",
+    "flask",
+    "itsdangerous",
+    "rich",
+    "markdown-it-py",
+    "mdurl",
+    "scipy",
+    "requests-toolbelt",
+    "more-itertools",
+    "pillow",
+    "tomlkit",
+    "coverage",
+    "pyparsing",
+    "azure-core",
+    "msal",
+    "azure-storage-blob",
+    "websocket-client",
+    "gitpython",
+    "gitdb",
+    "smmap",
+    "matplotlib",
+    "kiwisolver",
+    "cycler",
+    "fonttools",
+    "contourpy",
+    "scikit-learn",
+    "joblib",
+    "threadpoolctl",
+    "sortedcontainers",
+    "psycopg2-binary",
+    "psycopg2",
+    "pymysql",
+    "redis",
+    "async-timeout",
+    "asn1crypto",
+    "paramiko",
+    "pynacl",
+    "bcrypt",
+    "fastapi",
+    "starlette",
+    "uvicorn",
+    "gunicorn",
+    "django",
+    "sqlparse",
+    "asgiref",
+    "alembic",
+    "mako",
+    "networkx",
+    "sympy",
+    "mpmath",
+    "regex",
+    "tokenizers",
+    "transformers",
+    "huggingface-hub",
+    "safetensors",
+    "torch",
+    "torchvision",
+    "tensorflow",
+    "keras",
+    "tensorboard",
+    "absl-py",
+    "opencv-python",
+    "nltk",
+    "openai",
+    "tiktoken",
+    "langchain",
+    "langchain-core",
+    "dill",
+    "cloudpickle",
+    "multiprocess",
+    "xlrd",
+    "xlsxwriter",
+    "chardet",
+    "simplejson",
+    "ujson",
+    "orjson",
+    "marshmallow",
+    "babel",
+    "sphinx",
+    "black",
+    "mypy",
+    "mypy-extensions",
+    "pathspec",
+    "flake8",
+    "pycodestyle",
+    "pyflakes",
+    "mccabe",
+    "pylint",
+    "astroid",
+    "isort",
+    "ruff",
+    "pre-commit",
+    "nodeenv",
+    "identify",
+    "cfgv",
+    "tox",
+    "nox",
+    "poetry",
+    "poetry-core",
+    "hatchling",
+    "setuptools-scm",
+    "build",
+    "twine",
+    "pkginfo",
+    "keyring",
+    "jaraco-classes",
+    "secretstorage",
+    "jeepney",
+    "readme-renderer",
+    "pytest-cov",
+    "pytest-mock",
+    "pytest-xdist",
+    "pytest-asyncio",
+    "execnet",
+    "hypothesis",
+    "mock",
+    "responses",
+    "freezegun",
+    "faker",
+    "arrow",
+    "pendulum",
+    "python-dotenv",
+    "pyopenssl",
+    "google-cloud-storage",
+    "google-cloud-core",
+    "google-resumable-media",
+    "google-crc32c",
+    "google-cloud-bigquery",
+    "proto-plus",
+    "grpcio-tools",
+    "azure-identity",
+    "msrest",
+    "adal",
+    "kubernetes",
+    "docker",
+    "prometheus-client",
+    "opentelemetry-api",
+    "opentelemetry-sdk",
+    "deprecated",
+    "tenacity",
+    "backoff",
+    "retry",
+    "toml",
+    "termcolor",
+    "tabulate",
+    "prompt-toolkit",
+    "wcwidth",
+    "ipython",
+    "jedi",
+    "parso",
+    "traitlets",
+    "jupyter-core",
+    "jupyter-client",
+    "ipykernel",
+    "notebook",
+    "nbformat",
+    "tornado",
+    "pyzmq",
+    "debugpy",
+    "pexpect",
+    "ptyprocess",
+    "matplotlib-inline",
+    "executing",
+    "asttokens",
+    "pure-eval",
+    "stack-data",
+    "seaborn",
+    "plotly",
+    "statsmodels",
+    "patsy",
+    "xgboost",
+    "lightgbm",
+    "numba",
+    "llvmlite",
+    "shapely",
+    "pyproj",
+    "selenium",
+    "scrapy",
+    "twisted",
+    "celery",
+    "kombu",
+    "billiard",
+    "vine",
+    "amqp",
+    "pymongo",
+    "elasticsearch",
+    "boto",
+    "dnspython",
+    "email-validator",
+    "pycryptodome",
+    "pycryptodomex",
+    "argon2-cffi",
+    "passlib",
+    "markdown",
+    "jsonpointer",
+    "jsonpatch",
+    "pyrsistent",
+    "referencing",
+    "rpds-py",
+    "jsonschema-specifications",
+    "typer",
+    "shellingham",
+    "structlog",
+    "loguru",
+    "sentry-sdk",
+    "datadog",
+    "slack-sdk",
+    "pygithub",
+    "jira",
+    "stripe",
+    "twilio",
+];
+
+/// What made a package suspicious.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Signal {
+    /// The name is close to a popular package's.
+    Typosquat {
+        similar_to: String,
+        distance: usize,
+        /// 1-based download rank of `similar_to`.
+        rank: usize,
+    },
+    /// The first release is recent.
+    NewPackage {
+        first_release: String,
+        age_days: u64,
+    },
+    /// The latest release shares no maintainer with the previous one.
+    MaintainerChange {
+        version: String,
+        previous_version: String,
+        maintainers: Vec<String>,
+        previous_maintainers: Vec<String>,
+    },
+}
+
+/// A suspicious dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub package: String,
+    #[serde(flatten)]
+    pub signal: Signal,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let package = &self.package;
+        match &self.signal {
+            Signal::Typosquat { similar_to, .. } => write!(
+                f,
+                "`{package}` looks like a misspelling of the popular package `{similar_to}`"
+            ),
+            Signal::NewPackage {
+                first_release,
+                age_days,
+            } => write!(
+                f,
+                "`{package}` is new: its first release was {age_days} day(s) ago ({first_release})"
+            ),
+            Signal::MaintainerChange {
+                version,
+                previous_version,
+                maintainers,
+                previous_maintainers,
+            } => write!(
+                f,
+                "`{package}` {version} is maintained by {} but {previous_version} was maintained by {}",
+                maintainers.join(", "),
+                previous_maintainers.join(", ")
+            ),
+        }
+    }
+}
+
+impl Finding {
+    /// `E_SUSPICIOUS_PACKAGE`, or `W_SUSPICIOUS_PACKAGE` once `allowed`.
+    pub fn diagnostic(&self, allowed: bool) -> Diagnostic {
+        let approve = format!(
+            "re-run with --allow-suspicious, or approve it under [packages] approved in {}",
+            crate::execution_policy::POLICY_FILE
+        );
+        let suggestion = match &self.signal {
+            Signal::Typosquat { similar_to, .. } => format!(
+                "Did you mean `{similar_to}`? If `{}` is intended, {approve}.",
+                self.package
+            ),
+            Signal::NewPackage { .. } | Signal::MaintainerChange { .. } => format!(
+                "Review the package and its maintainers on the index before installing it. If it is trusted, {approve}."
+            ),
+        };
+        let diagnostic = if allowed {
+            Diagnostic::warning(self.to_string()).with_code("W_SUSPICIOUS_PACKAGE")
+        } else {
+            Diagnostic::error(self.to_string())
+                .with_code("E_SUSPICIOUS_PACKAGE")
+                .with_suggestion(suggestion)
+        };
+        diagnostic.with_context(json!(self))
+    }
+}
+
+/// Remember that `name` was installed despite a finding.
+pub fn flag(name: &str) {
+    if let Ok(mut flagged) = FLAGGED.lock() {
+        flagged.insert(crate::pypi::normalize_project_name(name));
+    }
+}
+
+/// Whether `name` was [`flag`]ged in this process.
+pub fn flagged(name: &str) -> bool {
+    FLAGGED
+        .lock()
+        .is_ok_and(|flagged| flagged.contains(&crate::pypi::normalize_project_name(name)))
+}
+
+/// [`flag`] the packages `lock` records in `container_builds`, so an
+/// acknowledgement made by an earlier command still applies.
+pub fn restore(lock: &Lockfile) {
+    for name in &lock.container_builds {
+        flag(name);
+    }
+}
+
+/// Record in `lock.container_builds` which of its packages are [`flag`]ged.
+pub fn record(lock: &mut Lockfile) {
+    let Ok(flagged) = FLAGGED.lock() else {
+        return;
+    };
+    let locked: BTreeSet<String> = lock
+        .packages
+        .values()
+        .map(|pkg| crate::pypi::normalize_project_name(&pkg.name))
+        .collect();
+    lock.container_builds = flagged.intersection(&locked).cloned().collect();
+}
+
+/// Whether `pybun-policy.toml` approves `name`, exempting it from checks.
+pub fn approved(name: &str) -> bool {
+    crate::execution_policy::current().is_some_and(|policy| policy.approves(name))
+}
+
+/// The popular package `name` may be a misspelling of.
+pub fn typosquat(name: &str) -> Option<Finding> {
+    let name = crate::pypi::normalize_project_name(name);
+    if name.len() < 4 || POPULAR.contains(&name.as_str()) || ESTABLISHED.contains(&name.as_str()) {
+        return None;
+    }
+    let squashed = name.replace('-', "");
+    let mut best: Option<(usize, usize, &str)> = None;
+    for (index, popular) in POPULAR.iter().enumerate() {
+        let distance = if squashed == popular.replace('-', "") {
+            1
+        } else {
+            edit_distance(&name, popular)
+        };
+        let max = if index < TOP_TIER && popular.len() >= 8 {
+            2
+        } else {
+            1
+        };
+        if distance <= max && best.is_none_or(|(d, _, _)| distance < d) {
+            best = Some((distance, index, popular));
+        }
+    }
+    let (distance, index, popular) = best?;
+    Some(Finding {
+        package: name,
+        signal: Signal::Typosquat {
+            similar_to: popular.to_string(),
+            distance,
+            rank: index + 1,
+        },
+    })
+}
+
+/// Release-history findings for `name` (see [`crate::pypi::ProjectHistory`]).
+pub fn history(name: &str, history: &crate::pypi::ProjectHistory, today: u64) -> Vec<Finding> {
+    let package = crate::pypi::normalize_project_name(name);
+    let mut findings = Vec::new();
+    if let Some(first) = &history.first_upload
+        && let Some(day) = unix_day(first)
+        && today >= day
+        && today - day < NEW_PACKAGE_DAYS
+    {
+        findings.push(Finding {
+            package: package.clone(),
+            signal: Signal::NewPackage {
+                first_release: first.get(..10).unwrap_or(first).to_string(),
+                age_days: today - day,
+            },
+        });
+    }
+    if let (Some(version), Some(previous_version)) =
+        (&history.latest_version, &history.previous_version)
+        && !history.maintainers.is_empty()
+        && !history.previous_maintainers.is_empty()
+        && !history
+            .maintainers
+            .iter()
+            .any(|m| history.previous_maintainers.contains(m))
+    {
+        findings.push(Finding {
+            package,
+            signal: Signal::MaintainerChange {
+                version: version.clone(),
+                previous_version: previous_version.clone(),
+                maintainers: history.maintainers.clone(),
+                previous_maintainers: history.previous_maintainers.clone(),
+            },
+        });
+    }
+    findings
+}
+
+/// Optimal string alignment distance: edits are insertions, deletions,
+/// substitutions and swaps of adjacent characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Days since the Unix epoch of an ISO 8601 timestamp (`2024-05-29T...`).
+fn unix_day(timestamp: &str) -> Option<u64> {
+    let date = timestamp.get(..10)?;
+    let mut parts = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    u64::try_from(era * 146_097 + doe - 719_468).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pypi::ProjectHistory;
+
+    fn similar_to(name: &str) -> Option<String> {
+        typosquat(name).map(|finding| match finding.signal {
+            Signal::Typosquat { similar_to, .. } => similar_to,
+            other => panic!("unexpected {other:?}"),
+        })
+    }
+
+    #[test]
+    fn flags_names_close_to_popular_packages() {
+        assert_eq!(similar_to("reqeusts").as_deref(), Some("requests"));
+        assert_eq!(similar_to("Requestss").as_deref(), Some("requests"));
+        assert_eq!(similar_to("colourama").as_deref(), Some("colorama"));
+        assert_eq!(
+            similar_to("python_dateutils").as_deref(),
+            Some("python-dateutil")
+        );
+        assert_eq!(similar_to("scikitlearn").as_deref(), Some("scikit-learn"));
+        // Two edits only count for the most popular packages.
+        assert_eq!(similar_to("setuptoolz2").as_deref(), Some("setuptools"));
+        assert_eq!(similar_to("hypothesys2"), None);
+
+        assert_eq!(similar_to("requests"), None);
+        assert_eq!(similar_to("Typing_Extensions"), None);
+        assert_eq!(similar_to("app"), None);
+        assert_eq!(similar_to("my-internal-lib"), None);
+    }
+
+    #[test]
+    fn established_near_neighbours_are_not_flagged() {
+        for name in ESTABLISHED {
+            assert_eq!(similar_to(name), None, "{name}");
+        }
+        assert_eq!(similar_to("Scapy"), None);
+        assert_eq!(similar_to("moto"), None);
+        // Misspellings of the established names' neighbours still are.
+        assert_eq!(similar_to("scipyy").as_deref(), Some("scipy"));
+        assert_eq!(similar_to("b0to").as_deref(), Some("boto"));
+    }
+
+    #[test]
+    fn flags_new_packages_and_replaced_maintainers() {
+        let today = unix_day("2026-10-16").unwrap();
+        let history_of = |first: &str, maintainers: &[&str], previous: &[&str]| ProjectHistory {
+            first_upload: Some(first.to_string()),
+            latest_version: Some("2.0".into()),
+            maintainers: maintainers.iter().map(|m| m.to_string()).collect(),
+            previous_version: Some("1.9".into()),
+            previous_maintainers: previous.iter().map(|m| m.to_string()).collect(),
+        };
+
+        let found = history(
+            "Fresh_Pkg",
+            &history_of("2026-10-10T08:00:00Z", &[], &[]),
+            today,
+        );
+        assert_eq!(
+            found,
+            [Finding {
+                package: "fresh-pkg".into(),
+                signal: Signal::NewPackage {
+                    first_release: "2026-10-10".into(),
+                    age_days: 6,
+                },
+            }]
+        );
+
+        let old = "2019-01-01T00:00:00Z";
+        assert!(
+            history(
+                "pkg",
+                &history_of(old, &["a@x.org", "b@y.org"], &["b@y.org"]),
+                today
+            )
+            .is_empty()
+        );
+        let found = history(
+            "pkg",
+            &history_of(old, &["evil@x.org"], &["b@y.org"]),
+            today,
+        );
+        assert!(matches!(found[0].signal, Signal::MaintainerChange { .. }));
+        assert!(
+            found[0]
+                .to_string()
+                .contains("2.0 is maintained by evil@x.org")
+        );
+    }
+
+    #[test]
+    fn diagnostics_are_errors_until_allowed() {
+        let finding = typosquat("reqeusts").unwrap();
+        let error = finding.diagnostic(false);
+        assert_eq!(error.code.as_deref(), Some("E_SUSPICIOUS_PACKAGE"));
+        assert!(
+            error
+                .suggestion
+                .unwrap()
+                .contains("Did you mean `requests`?")
+        );
+        let context = error.context.unwrap();
+        assert_eq!(context["kind"], "typosquat");
+        assert_eq!(context["similar_to"], "requests");
+        assert_eq!(
+            finding.diagnostic(true).code.as_deref(),
+            Some("W_SUSPICIOUS_PACKAGE")
+        );
+    }
+
+    #[test]
+    fn converts_dates_to_unix_days() {
+        assert_eq!(unix_day("1970-01-01"), Some(0));
+        assert_eq!(unix_day("2000-03-01T00:00:00Z"), Some(11_017));
+        assert_eq!(unix_day("2024-02-29"), Some(19_782));
+        assert_eq!(unix_day("garbage"), None);
+    }
+}
@@ -0,0 +1,708 @@
+//! Fast Python syntax pre-check for watch mode.
+//!
+//! `pybun watch` checks changed files with this module before re-running
+//! the target, so a syntax error is reported the moment the file is saved,
+//! without spawning Python, and the run is skipped until the file parses.
+//!
+//! This is a tokenizer of its own rather than a check of the tree-sitter
+//! parse [`crate::python_ast`] uses. tree-sitter recovers from errors into
+//! `ERROR`/`MISSING` nodes that do not say what went wrong, so CPython's
+//! message and error class cannot be derived from them, and its grammar
+//! accepts indentation CPython rejects: a `def` with an unindented body, an
+//! unexpected indent or a bad unindent all parse without an error node.
+//! The tokenizer catches the errors that make up most broken saves, with
+//! CPython's wording:
+//! - unterminated string literals (single, triple-quoted and f-strings)
+//! - unclosed, unmatched and mismatched brackets
+//! - inconsistent indentation (unexpected indent, bad unindent, missing
+//!   indented block)
+//! - compound statements missing their `:`
+//! - stray characters after `\` and invalid non-ASCII characters
+//!
+//! Anything it accepts is left for Python to judge, so it never reports an
+//! error for a file Python would run.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Tab stops used for indentation, as in CPython's tokenizer.
+const TAB_SIZE: usize = 8;
+
+/// Statements that open an indented block.
+const COMPOUND_KEYWORDS: &[&str] = &[
+    "if", "elif", "else", "while", "for", "try", "except", "finally", "with", "def", "class",
+];
+
+/// A syntax error found without running Python.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PythonSyntaxError {
+    /// Stable error code, shared with runtime syntax errors.
+    pub code: &'static str,
+    /// `SyntaxError` or `IndentationError`.
+    pub kind: &'static str,
+    pub message: String,
+    /// Empty when checking a string.
+    pub file: PathBuf,
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column (in characters).
+    pub column: usize,
+    /// The offending source line, if it exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl PythonSyntaxError {
+    /// Python-style report with a caret under the offending column.
+    pub fn render(&self) -> String {
+        let mut out = format!("  File \"{}\", line {}\n", self.file.display(), self.line);
+        if let Some(text) = &self.text {
+            let trimmed = text.trim_start();
+            let skipped = text.chars().count() - trimmed.chars().count();
+            out.push_str(&format!("    {}\n", trimmed.trim_end()));
+            let caret = self.column.saturating_sub(1 + skipped);
+            out.push_str(&format!("    {}^\n", " ".repeat(caret)));
+        }
+        out.push_str(&format!("{}: {}", self.kind, self.message));
+        out
+    }
+}
+
+/// Check a Python file. Returns `None` when it looks valid or can't be
+/// read (a missing file is not a syntax error).
+pub fn check_file(path: &Path) -> Option<PythonSyntaxError> {
+    let source = std::fs::read_to_string(path).ok()?;
+    check_source(&source).map(|mut error| {
+        error.file = path.to_path_buf();
+        error
+    })
+}
+
+/// Check Python source text.
+pub fn check_source(source: &str) -> Option<PythonSyntaxError> {
+    let lines: Vec<&str> = source.lines().collect();
+    Scanner::new(source)
+        .run()
+        .err()
+        .map(|(kind, message, line, column)| PythonSyntaxError {
+            code: "E_RUNTIME_SYNTAX_ERROR",
+            kind,
+            message,
+            file: PathBuf::new(),
+            line,
+            column,
+            text: lines.get(line.wrapping_sub(1)).map(|text| text.to_string()),
+        })
+}
+
+/// `(kind, message, line, column)`
+type ScanError = (&'static str, String, usize, usize);
+
+struct Scanner {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+    /// Open brackets with their positions.
+    brackets: Vec<(char, usize, usize)>,
+    indents: Vec<usize>,
+    /// Header line of a block whose body hasn't started yet.
+    pending_block: Option<(String, usize)>,
+    /// First word of the current logical line.
+    first_word: Option<String>,
+    has_top_colon: bool,
+    ends_with_colon: bool,
+    /// Whether the current logical line has any token yet.
+    in_line: bool,
+}
+
+impl Scanner {
+    fn new(source: &str) -> Self {
+        Self {
+            chars: source
+                .strip_prefix('\u{feff}')
+                .unwrap_or(source)
+                .chars()
+                .collect(),
+            pos: 0,
+            line: 1,
+            column: 1,
+            brackets: Vec::new(),
+            indents: vec![0],
+            pending_block: None,
+            first_word: None,
+            has_top_colon: false,
+            ends_with_colon: false,
+            in_line: false,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn run(mut self) -> Result<(), ScanError> {
+        let mut line_start = true;
+        loop {
+            if line_start {
+                line_start = false;
+                if !self.start_line()? {
+                    break;
+                }
+            }
+            let Some(c) = self.peek(0) else {
+                break;
+            };
+            match c {
+                '\n' => {
+                    if self.brackets.is_empty() {
+                        self.end_logical_line()?;
+                        line_start = true;
+                    }
+                    self.bump();
+                }
+                '\\' => {
+                    let (line, column) = (self.line, self.column);
+                    self.bump();
+                    if self.peek(0) == Some('\r') {
+                        self.bump();
+                    }
+                    if self.bump() != Some('\n') {
+                        return Err((
+                            "SyntaxError",
+                            "unexpected character after line continuation character".to_string(),
+                            line,
+                            column + 1,
+                        ));
+                    }
+                }
+                '#' => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                ' ' | '\t' | '\r' | '\x0c' => {
+                    self.bump();
+                }
+                '\'' | '"' => {
+                    self.token(None);
+                    self.string("")?;
+                }
+                '(' | '[' | '{' => {
+                    self.token(None);
+                    self.brackets.push((c, self.line, self.column));
+                    self.bump();
+                }
+                ')' | ']' | '}' => {
+                    self.token(None);
+                    self.close_bracket(c)?;
+                    self.bump();
+                }
+                ':' => {
+                    self.bump();
+                    if self.peek(0) == Some('=') {
+                        self.bump();
+                        self.token(None);
+                    } else {
+                        self.token(None);
+                        if self.brackets.is_empty() {
+                            self.has_top_colon = true;
+                            self.ends_with_colon = true;
+                        }
+                    }
+                }
+                c if c == '_' || c.is_alphanumeric() => {
+                    let word = self.word();
+                    if matches!(self.peek(0), Some('\'' | '"')) && is_string_prefix(&word) {
+                        self.token(None);
+                        self.string(&word)?;
+                    } else {
+                        self.token(Some(word));
+                    }
+                }
+                c if is_invalid_character(c) => {
+                    return Err((
+                        "SyntaxError",
+                        format!("invalid character '{c}' (U+{:04X})", c as u32),
+                        self.line,
+                        self.column,
+                    ));
+                }
+                _ => {
+                    self.token(None);
+                    self.bump();
+                }
+            }
+        }
+
+        if let Some(&(open, line, column)) = self.brackets.first() {
+            return Err((
+                "SyntaxError",
+                format!("'{open}' was never closed"),
+                line,
+                column,
+            ));
+        }
+        self.end_logical_line()?;
+        if let Some((header, line)) = self.pending_block.take() {
+            return Err((
+                "IndentationError",
+                format!("expected an indented block after {header} on line {line}"),
+                self.last_line(),
+                1,
+            ));
+        }
+        Ok(())
+    }
+
+    /// The line scanning stopped on, not counting a final newline.
+    fn last_line(&self) -> usize {
+        if self.column == 1 && self.line > 1 {
+            self.line - 1
+        } else {
+            self.line
+        }
+    }
+
+    /// Skip blank and comment-only lines, then check the indentation of the
+    /// next logical line. Returns `false` at end of input.
+    fn start_line(&mut self) -> Result<bool, ScanError> {
+        loop {
+            let mut width = 0;
+            while let Some(c) = self.peek(0) {
+                match c {
+                    ' ' => width += 1,
+                    '\t' => width = (width / TAB_SIZE + 1) * TAB_SIZE,
+                    '\x0c' => width = 0,
+                    _ => break,
+                }
+                self.bump();
+            }
+            match self.peek(0) {
+                None => return Ok(false),
+                Some('\n' | '\r' | '#') => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                    self.bump();
+                }
+                Some(_) => {
+                    self.indent(width)?;
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    fn indent(&mut self, width: usize) -> Result<(), ScanError> {
+        let current = *self.indents.last().expect("indent stack is never empty");
+        if let Some((header, line)) = self.pending_block.take() {
+            if width <= current {
+                return Err((
+                    "IndentationError",
+                    format!("expected an indented block after {header} on line {line}"),
+                    self.line,
+                    self.column,
+                ));
+            }
+            self.indents.push(width);
+        } else if width > current {
+            return Err((
+                "IndentationError",
+                "unexpected indent".to_string(),
+                self.line,
+                self.column,
+            ));
+        } else if width < current {
+            while self.indents.last().is_some_and(|&level| level > width) {
+                self.indents.pop();
+            }
+            if self.indents.last() != Some(&width) {
+                return Err((
+                    "IndentationError",
+                    "unindent does not match any outer indentation level".to_string(),
+                    self.line,
+                    self.column,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn end_logical_line(&mut self) -> Result<(), ScanError> {
+        if !self.in_line {
+            return Ok(());
+        }
+        let first = self.first_word.take();
+        let compound = first
+            .as_deref()
+            .is_some_and(|word| COMPOUND_KEYWORDS.contains(&word));
+        if compound && !self.has_top_colon {
+            return Err((
+                "SyntaxError",
+                "expected ':'".to_string(),
+                self.line,
+                self.column,
+            ));
+        }
+        if self.ends_with_colon {
+            let header = match first.as_deref() {
+                Some("def") => "function definition".to_string(),
+                Some("class") => "class definition".to_string(),
+                Some(word) => format!("'{word}' statement"),
+                None => "':'".to_string(),
+            };
+            self.pending_block = Some((header, self.line));
+        }
+        self.in_line = false;
+        self.has_top_colon = false;
+        self.ends_with_colon = false;
+        Ok(())
+    }
+
+    /// Record a token; `word` is the identifier or keyword, if it is one.
+    fn token(&mut self, word: Option<String>) {
+        if !self.in_line {
+            self.in_line = true;
+            self.first_word = word;
+        } else if self.first_word.as_deref() == Some("async") && word.is_some() {
+            // `async def` / `async for` / `async with`
+            self.first_word = word;
+        }
+        self.ends_with_colon = false;
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek(0).filter(|&c| c == '_' || c.is_alphanumeric()) {
+            word.push(c);
+            self.bump();
+        }
+        word
+    }
+
+    fn close_bracket(&mut self, close: char) -> Result<(), ScanError> {
+        let expected = match close {
+            ')' => '(',
+            ']' => '[',
+            _ => '{',
+        };
+        match self.brackets.pop() {
+            None => Err((
+                "SyntaxError",
+                format!("unmatched '{close}'"),
+                self.line,
+                self.column,
+            )),
+            Some((open, line, _)) if open != expected => {
+                let mut message = format!(
+                    "closing parenthesis '{close}' does not match opening parenthesis '{open}'"
+                );
+                if line != self.line {
+                    message.push_str(&format!(" on line {line}"));
+                }
+                Err(("SyntaxError", message, self.line, self.column))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Scan a string literal starting at the opening quote.
+    fn string(&mut self, prefix: &str) -> Result<(), ScanError> {
+        let (line, column) = (self.line, self.column - prefix.chars().count());
+        let quote = self.bump().expect("called at a quote");
+        let triple = self.peek(0) == Some(quote) && self.peek(1) == Some(quote);
+        if triple {
+            self.bump();
+            self.bump();
+        }
+        let formatted = prefix
+            .chars()
+            .any(|c| matches!(c.to_ascii_lowercase(), 'f' | 't'));
+        let unterminated = |scanner: &Self| {
+            let kind = if triple {
+                "triple-quoted string literal"
+            } else if formatted {
+                "f-string literal"
+            } else {
+                "string literal"
+            };
+            Err((
+                "SyntaxError",
+                format!(
+                    "unterminated {kind} (detected at line {})",
+                    scanner.last_line()
+                ),
+                line,
+                column,
+            ))
+        };
+        loop {
+            let Some(c) = self.peek(0) else {
+                return unterminated(self);
+            };
+            match c {
+                '\\' => {
+                    self.bump();
+                    self.bump();
+                }
+                '\n' if !triple => return unterminated(self),
+                c if c == quote => {
+                    if !triple {
+                        self.bump();
+                        return Ok(());
+                    }
+                    if self.peek(1) == Some(quote) && self.peek(2) == Some(quote) {
+                        self.bump();
+                        self.bump();
+                        self.bump();
+                        return Ok(());
+                    }
+                    self.bump();
+                }
+                '{' if formatted => {
+                    self.bump();
+                    if self.peek(0) == Some('{') {
+                        self.bump();
+                    } else {
+                        self.replacement_field()?;
+                    }
+                }
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    /// Skip an f-string replacement field, after its `{`. Nested strings
+    /// may reuse the outer quote (PEP 701).
+    fn replacement_field(&mut self) -> Result<(), ScanError> {
+        let mut depth = 1;
+        while let Some(c) = self.peek(0) {
+            match c {
+                '{' | '(' | '[' => {
+                    depth += 1;
+                    self.bump();
+                }
+                '}' | ')' | ']' => {
+                    depth -= 1;
+                    self.bump();
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                '\'' | '"' => self.string("")?,
+                c if c == '_' || c.is_alphanumeric() => {
+                    let word = self.word();
+                    if matches!(self.peek(0), Some('\'' | '"')) && is_string_prefix(&word) {
+                        self.string(&word)?;
+                    }
+                }
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Non-ASCII punctuation and symbols that can't appear outside strings and
+/// comments; typically smart quotes or dashes pasted from a document. Kept
+/// to ranges with no identifier characters, since the exact identifier
+/// rules need Unicode tables.
+fn is_invalid_character(c: char) -> bool {
+    matches!(
+        c,
+        '\u{a1}'..='\u{b6}'
+            | '\u{b8}'..='\u{bf}'
+            | '\u{2010}'..='\u{203e}'
+            | '\u{2041}'..='\u{2053}'
+            | '\u{2055}'..='\u{205e}'
+            | '\u{2190}'..='\u{2bff}'
+            | '\u{3001}'..='\u{3003}'
+            | '\u{3008}'..='\u{3011}'
+            | '\u{ff01}'..='\u{ff0f}'
+            | '\u{ff1a}'..='\u{ff20}'
+    ) && !c.is_alphanumeric()
+}
+
+fn is_string_prefix(word: &str) -> bool {
+    matches!(
+        word.to_ascii_lowercase().as_str(),
+        "r" | "u" | "b" | "f" | "t" | "br" | "rb" | "fr" | "rf" | "tr" | "rt"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> PythonSyntaxError {
+        check_source(source).unwrap_or_else(|| panic!("no error for:\n{source}"))
+    }
+
+    #[test]
+    fn accepts_valid_python() {
+        let source = r#"
+import os
+
+@decorator(arg=1)
+async def main(a: int, *args, **kwargs) -> dict[str, int]:
+    """Docstring with 'quotes' and "more".
+    """
+    if (n := len(args)) > 1: return {}
+    data = {"a": 1, 'b': [1, 2,
+                          3]}
+    total = sum(x for x in range(10)
+                if x % 2)
+    path = r"C:\dir" + '\\' + r"\""
+    msg = f"{data["a"]!r:>{n}} {{literal}} {f'{total}'}"
+    f = lambda x: x[1:2]
+    while chunk := read():
+        pass
+    match command:
+        case [x, *_]:
+            pass
+    try:
+        pass
+    except (ValueError, KeyError) as e:  # comment (
+        raise
+    else:
+        x = 1 \
+            + 2
+    return data
+
+class Empty: pass
+"#;
+        assert_eq!(check_source(source), None);
+        assert_eq!(check_source(""), None);
+        assert_eq!(check_source("\tif x:\n\t\tpass\n".trim_start()), None);
+    }
+
+    #[test]
+    fn reports_unclosed_bracket_at_the_opening() {
+        let error = error("x = 1\nprint(\"hi\"\ny = 2\n");
+        assert_eq!(error.message, "'(' was never closed");
+        assert_eq!((error.line, error.column), (2, 6));
+        assert_eq!(error.kind, "SyntaxError");
+        assert_eq!(error.code, "E_RUNTIME_SYNTAX_ERROR");
+        assert_eq!(error.text.as_deref(), Some("print(\"hi\""));
+    }
+
+    #[test]
+    fn reports_mismatched_and_unmatched_brackets() {
+        assert_eq!(
+            error("x = [1, 2)\n").message,
+            "closing parenthesis ')' does not match opening parenthesis '['"
+        );
+        assert_eq!(
+            error("x = [1,\n2)\n").message,
+            "closing parenthesis ')' does not match opening parenthesis '[' on line 1"
+        );
+        assert_eq!(error("x = 1)\n").message, "unmatched ')'");
+    }
+
+    #[test]
+    fn reports_unterminated_strings() {
+        let single = error("x = 'abc\ny = 1\n");
+        assert_eq!(
+            single.message,
+            "unterminated string literal (detected at line 1)"
+        );
+        assert_eq!((single.line, single.column), (1, 5));
+
+        let triple = error("x = 1\ndoc = \"\"\"never\nclosed\n");
+        assert_eq!(
+            triple.message,
+            "unterminated triple-quoted string literal (detected at line 3)"
+        );
+        assert_eq!((triple.line, triple.column), (2, 7));
+
+        assert_eq!(
+            error("x = f'{y}\n").message,
+            "unterminated f-string literal (detected at line 1)"
+        );
+    }
+
+    #[test]
+    fn reports_indentation_errors() {
+        let error_at = |source| {
+            let e = error(source);
+            assert_eq!(e.kind, "IndentationError");
+            (e.message, e.line)
+        };
+        assert_eq!(
+            error_at("x = 1\n    y = 2\n"),
+            ("unexpected indent".to_string(), 2)
+        );
+        assert_eq!(
+            error_at("if x:\n        a = 1\n    b = 2\n"),
+            (
+                "unindent does not match any outer indentation level".to_string(),
+                3
+            )
+        );
+        assert_eq!(
+            error_at("def f():\nreturn 1\n"),
+            (
+                "expected an indented block after function definition on line 1".to_string(),
+                2
+            )
+        );
+        assert_eq!(
+            error_at("for x in y:\n    # only a comment\n"),
+            (
+                "expected an indented block after 'for' statement on line 1".to_string(),
+                2
+            )
+        );
+    }
+
+    #[test]
+    fn reports_missing_colon_and_stray_characters() {
+        let missing = error("def f()\n    return 1\n");
+        assert_eq!(missing.message, "expected ':'");
+        assert_eq!(missing.line, 1);
+        assert_eq!(
+            error("x = 1 \\ 2\n").message,
+            "unexpected character after line continuation character"
+        );
+        assert_eq!(
+            error("print(\u{201c}hi\u{201d})\n").message,
+            "invalid character '\u{201c}' (U+201C)"
+        );
+    }
+
+    #[test]
+    fn renders_a_caret_under_the_column() {
+        let mut error = error("if True:\n    print(\"hi\"\n");
+        error.file = PathBuf::from("app.py");
+        assert_eq!(
+            error.render(),
+            "  File \"app.py\", line 2\n    print(\"hi\"\n         ^\nSyntaxError: '(' was never closed"
+        );
+    }
+
+    #[test]
+    fn check_file_sets_the_path() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("broken.py");
+        std::fs::write(&path, "x = (\n").unwrap();
+        assert_eq!(check_file(&path).unwrap().file, path);
+        assert_eq!(check_file(&temp.path().join("missing.py")), None);
+    }
+}
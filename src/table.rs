@@ -0,0 +1,187 @@
+//! Script-friendly tabular output (`--format tsv`, `--columns`).
+//!
+//! List-style commands expose their rows as an array of JSON objects. The
+//! TSV renderer reuses those objects directly, so column names are exactly
+//! the JSON field names and stay stable across both formats:
+//!
+//! ```text
+//! $ pybun --format tsv --columns package,latest outdated
+//! package<TAB>latest
+//! requests<TAB>2.32.3
+//! ```
+//!
+//! Output rules: one header line, one line per row, fields separated by a
+//! single tab. Tabs, newlines, and backslashes inside values are escaped as
+//! `\t`, `\n`, and `\\` so every row is exactly one line; `null` renders as an
+//! empty field and arrays are joined with commas.
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TableError {
+    #[error("unknown column '{column}' (available: {})", available.join(", "))]
+    UnknownColumn {
+        column: String,
+        available: Vec<String>,
+    },
+}
+
+/// Describes which part of a command's JSON detail is tabular.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableSpec {
+    /// Key in the JSON detail holding the array of row objects.
+    pub rows_key: &'static str,
+    /// Columns shown when `--columns` is not given.
+    pub default_columns: &'static [&'static str],
+}
+
+impl TableSpec {
+    pub const fn new(rows_key: &'static str, default_columns: &'static [&'static str]) -> Self {
+        Self {
+            rows_key,
+            default_columns,
+        }
+    }
+
+    /// Every column that may be selected: the defaults followed by any
+    /// additional fields present on the rows, in first-seen order.
+    pub fn available_columns(&self, rows: &[Value]) -> Vec<String> {
+        let mut columns: Vec<String> = self.default_columns.iter().map(|c| c.to_string()).collect();
+        for row in rows {
+            if let Some(object) = row.as_object() {
+                for key in object.keys() {
+                    if !columns.iter().any(|c| c == key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+        }
+        columns
+    }
+
+    /// Resolve `requested` (from `--columns`) against the rows, falling back
+    /// to the default columns.
+    pub fn select_columns(
+        &self,
+        rows: &[Value],
+        requested: Option<&[String]>,
+    ) -> Result<Vec<String>, TableError> {
+        let Some(requested) = requested.filter(|r| !r.is_empty()) else {
+            return Ok(self.default_columns.iter().map(|c| c.to_string()).collect());
+        };
+        let available = self.available_columns(rows);
+        requested
+            .iter()
+            .map(|column| {
+                let column = column.trim();
+                if available.iter().any(|c| c == column) {
+                    Ok(column.to_string())
+                } else {
+                    Err(TableError::UnknownColumn {
+                        column: column.to_string(),
+                        available: available.clone(),
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+/// Keep only `columns` on each row object.
+pub fn project_rows(rows: &[Value], columns: &[String]) -> Vec<Value> {
+    rows.iter()
+        .map(|row| {
+            let mut object = Map::new();
+            for column in columns {
+                object.insert(
+                    column.clone(),
+                    row.get(column).cloned().unwrap_or(Value::Null),
+                );
+            }
+            Value::Object(object)
+        })
+        .collect()
+}
+
+/// Render rows as TSV with a header line.
+pub fn render_tsv(rows: &[Value], columns: &[String]) -> String {
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    lines.push(columns.join("\t"));
+    for row in rows {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| field(row.get(column).unwrap_or(&Value::Null)))
+            .collect();
+        lines.push(fields.join("\t"));
+    }
+    lines.join("\n")
+}
+
+fn field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => escape(s),
+        Value::Array(items) => items.iter().map(field).collect::<Vec<_>>().join(","),
+        other => escape(&other.to_string()),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SPEC: TableSpec = TableSpec::new("packages", &["name", "version"]);
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"name": "requests", "version": "2.32.3", "size": 1024, "extras": ["socks"]}),
+            json!({"name": "odd\tname", "version": null, "size": 0, "extras": []}),
+        ]
+    }
+
+    #[test]
+    fn renders_default_columns_with_header() {
+        let rows = rows();
+        let columns = SPEC.select_columns(&rows, None).unwrap();
+        assert_eq!(
+            render_tsv(&rows, &columns),
+            "name\tversion\nrequests\t2.32.3\nodd\\tname\t"
+        );
+    }
+
+    #[test]
+    fn selects_requested_columns_in_order() {
+        let rows = rows();
+        let requested = vec!["size".to_string(), "name".to_string(), "extras".to_string()];
+        let columns = SPEC.select_columns(&rows, Some(&requested)).unwrap();
+        assert_eq!(
+            render_tsv(&rows, &columns),
+            "size\tname\textras\n1024\trequests\tsocks\n0\todd\\tname\t"
+        );
+        assert_eq!(
+            project_rows(&rows[..1], &columns),
+            vec![json!({"size": 1024, "name": "requests", "extras": ["socks"]})]
+        );
+    }
+
+    #[test]
+    fn unknown_column_lists_available_columns() {
+        let err = SPEC
+            .select_columns(&rows(), Some(&["sizes".to_string()]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown column 'sizes' (available: name, version, extras, size)"
+        );
+    }
+}
@@ -0,0 +1,757 @@
+//! PEP 425 compatibility tags.
+//!
+//! A wheel can be installed when one of the `{python}-{abi}-{platform}` tags
+//! in its filename (compressed sets like `py2.py3` or
+//! `manylinux_2_17_x86_64.manylinux2014_x86_64` expand to several tags) is
+//! supported by the target. [`TagPriority`] lists the supported tags in the
+//! order `pip` prefers them, following `packaging.tags.sys_tags`:
+//!
+//! 1. `cp311-cp311-{platform}`, then `cp311-abi3-{platform}`, then
+//!    `cp311-none-{platform}`,
+//! 2. stable-ABI wheels for older CPythons (`cp310-abi3-…` … `cp32-abi3-…`),
+//! 3. pure-Python platform wheels (`py311-none-…`, `py3-none-…`, …),
+//! 4. `cp311-none-any`, then `py311-none-any`, `py3-none-any`, … `py30-none-any`.
+//!
+//! Free-threaded CPython (`cp313t`) cannot load stable-ABI wheels and only
+//! accepts `cp313-cp313t-…`; PyPy (`pp310`) accepts `pp310-pypy310_pp73-…`.
+//! Both fall back to pure-Python wheels like CPython does ([`Interpreter`]).
+//!
+//! Within each group the platform tags are tried most specific first.
+//! Platform tags come from the host ([`host_platform_tags`]): the detected
+//! glibc or musl version decides the manylinux (PEP 600) and musllinux
+//! (PEP 656) tags on Linux. When locking for another machine, `--platform`
+//! names the target instead ([`platform_tags_for`]).
+
+use crate::runtime::Platform;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Oldest glibc minor version with manylinux tags. PyBun does not select
+/// wheels for glibc older than manylinux2014 (2.17), matching pip >= 22.0.
+const MANYLINUX_FLOOR: u32 = 17;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TagError {
+    #[error(
+        "unknown platform '{0}'; expected a wheel platform tag such as manylinux_2_28_x86_64, musllinux_1_2_aarch64, macosx_11_0_arm64 or win_amd64"
+    )]
+    UnknownPlatform(String),
+}
+
+pub type Result<T> = std::result::Result<T, TagError>;
+
+/// The C library of a Linux system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Libc {
+    Glibc { major: u32, minor: u32 },
+    Musl { major: u32, minor: u32 },
+}
+
+/// Detect the host's C library (cached). `None` off Linux or when the
+/// version cannot be determined.
+pub fn host_libc() -> Option<Libc> {
+    static LIBC: OnceLock<Option<Libc>> = OnceLock::new();
+    *LIBC.get_or_init(detect_libc)
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn detect_libc() -> Option<Libc> {
+    // SAFETY: gnu_get_libc_version returns a static NUL-terminated string.
+    let version = unsafe { std::ffi::CStr::from_ptr(libc::gnu_get_libc_version()) };
+    let (major, minor) = parse_major_minor(version.to_str().ok()?)?;
+    Some(Libc::Glibc { major, minor })
+}
+
+/// Statically linked (musl) builds cannot ask glibc directly; `ldd
+/// --version` identifies either library.
+#[cfg(all(target_os = "linux", not(target_env = "gnu")))]
+fn detect_libc() -> Option<Libc> {
+    let output = std::process::Command::new("ldd")
+        .arg("--version")
+        .output()
+        .ok()?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    parse_ldd_version(&text)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_libc() -> Option<Libc> {
+    None
+}
+
+/// Parse `ldd --version` output: glibc prints `ldd (GNU libc) 2.36` on its
+/// first line, musl prints `musl libc (x86_64)` followed by `Version 1.2.4`.
+pub fn parse_ldd_version(output: &str) -> Option<Libc> {
+    let mut lines = output.lines().filter(|l| !l.trim().is_empty());
+    let first = lines.next()?;
+    if first.contains("musl") {
+        let version = lines.find_map(|l| l.trim().strip_prefix("Version "))?;
+        let (major, minor) = parse_major_minor(version)?;
+        return Some(Libc::Musl { major, minor });
+    }
+    let lower = first.to_ascii_lowercase();
+    if lower.contains("glibc") || lower.contains("gnu libc") {
+        let (major, minor) = parse_major_minor(first.rsplit(' ').next()?)?;
+        return Some(Libc::Glibc { major, minor });
+    }
+    None
+}
+
+fn parse_major_minor(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// PEP 600 tags for a glibc 2.`glibc_minor` system, newest first, down to
+/// manylinux2014, with the legacy aliases at their equivalent positions.
+pub fn manylinux_tags(arch: &str, glibc_minor: u32) -> Vec<String> {
+    let mut tags = Vec::new();
+    for minor in (MANYLINUX_FLOOR..=glibc_minor).rev() {
+        tags.push(format!("manylinux_2_{minor}_{arch}"));
+    }
+    if glibc_minor >= MANYLINUX_FLOOR {
+        tags.push(format!("manylinux2014_{arch}"));
+        if matches!(arch, "x86_64" | "i686") {
+            tags.push(format!("manylinux1_{arch}"));
+        }
+    }
+    tags
+}
+
+/// PEP 656 tags for a musl 1.`musl_minor` system, newest first.
+pub fn musllinux_tags(arch: &str, musl_minor: u32) -> Vec<String> {
+    (0..=musl_minor)
+        .rev()
+        .map(|minor| format!("musllinux_1_{minor}_{arch}"))
+        .collect()
+}
+
+/// Platform tags of a Linux system; an undetectable C library is treated
+/// as glibc 2.17 (manylinux2014).
+pub fn linux_platform_tags(arch: &str, libc: Option<Libc>) -> Vec<String> {
+    let mut tags = match libc {
+        Some(Libc::Musl { major: 1, minor }) => musllinux_tags(arch, minor),
+        Some(Libc::Musl { .. }) => Vec::new(),
+        Some(Libc::Glibc { major: 2, minor }) => manylinux_tags(arch, minor),
+        Some(Libc::Glibc { .. }) | None => manylinux_tags(arch, MANYLINUX_FLOOR),
+    };
+    tags.push(format!("linux_{arch}"));
+    tags
+}
+
+/// Standard platform tags of the host, most specific first.
+pub fn host_platform_tags() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut tags = Vec::new();
+
+    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+    {
+        let (major, minor) = crate::runtime::macos_version();
+        tags.extend(crate::runtime::pep425_macos_arm64_tags(major, minor));
+    }
+
+    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+    {
+        let (major, minor) = crate::runtime::macos_version();
+        tags.extend(crate::runtime::pep425_macos_x86_64_tags(major, minor));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        tags.extend(linux_platform_tags(std::env::consts::ARCH, host_libc()));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        tags.push(
+            match std::env::consts::ARCH {
+                "x86" => "win32",
+                "aarch64" => "win_arm64",
+                _ => "win_amd64",
+            }
+            .to_string(),
+        );
+    }
+
+    tags
+}
+
+/// Append the legacy tags of `platform` (used by JSON index fixtures) and
+/// `any` to standard platform tags.
+pub fn with_legacy_tags(mut tags: Vec<String>, platform: Option<Platform>) -> Vec<String> {
+    if let Some(platform) = platform {
+        for tag in platform.wheel_tags() {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+    if !tags.iter().any(|t| t == "any") {
+        tags.push("any".into());
+    }
+    tags
+}
+
+/// Platform tags for locking against `target` instead of the host.
+///
+/// `target` is the most specific platform tag the target machine supports
+/// (`manylinux_2_28_x86_64`, `musllinux_1_2_aarch64`, `macosx_14_0_arm64`,
+/// `win_amd64`, …); older compatible tags are added below it. The legacy
+/// aliases `manylinux2014_*` and `manylinux1_*` are accepted, as are the
+/// short forms `linux-x86_64`, `linux-aarch64`, `macos-arm64`,
+/// `macos-x86_64` and `windows-x86_64`, which target the oldest supported
+/// release of that OS.
+pub fn platform_tags_for(target: &str) -> Result<Vec<String>> {
+    let unknown = || TagError::UnknownPlatform(target.to_string());
+    let target = target.trim();
+    let canonical = match target {
+        "linux-x86_64" => "manylinux_2_17_x86_64",
+        "linux-aarch64" => "manylinux_2_17_aarch64",
+        "macos-arm64" => "macosx_11_0_arm64",
+        "macos-x86_64" => "macosx_10_9_x86_64",
+        "windows-x86_64" => "win_amd64",
+        other => other,
+    };
+
+    let (tags, platform) = if let Some(rest) = canonical.strip_prefix("manylinux_") {
+        let (major, minor, arch) = split_version_arch(rest).ok_or_else(unknown)?;
+        if major != 2 || minor < MANYLINUX_FLOOR {
+            return Err(unknown());
+        }
+        (manylinux_tags(arch, minor), linux_platform(arch, false))
+    } else if let Some(arch) = canonical.strip_prefix("manylinux2014_") {
+        (
+            manylinux_tags(arch, MANYLINUX_FLOOR),
+            linux_platform(arch, false),
+        )
+    } else if let Some(rest) = canonical.strip_prefix("musllinux_") {
+        let (major, minor, arch) = split_version_arch(rest).ok_or_else(unknown)?;
+        if major != 1 {
+            return Err(unknown());
+        }
+        (musllinux_tags(arch, minor), linux_platform(arch, true))
+    } else if let Some(rest) = canonical.strip_prefix("macosx_") {
+        let (major, minor, arch) = split_version_arch(rest).ok_or_else(unknown)?;
+        match arch {
+            "arm64" => (
+                crate::runtime::pep425_macos_arm64_tags(major, minor),
+                Some(Platform::MacOSArm64),
+            ),
+            "x86_64" => (
+                crate::runtime::pep425_macos_x86_64_tags(major, minor),
+                Some(Platform::MacOSX64),
+            ),
+            _ => return Err(unknown()),
+        }
+    } else if matches!(canonical, "win_amd64" | "win32" | "win_arm64") {
+        let platform = (canonical == "win_amd64").then_some(Platform::WindowsX64);
+        (vec![canonical.to_string()], platform)
+    } else if let Some(arch) = canonical.strip_prefix("linux_") {
+        (vec![canonical.to_string()], linux_platform(arch, false))
+    } else {
+        return Err(unknown());
+    };
+    if tags.is_empty() {
+        return Err(unknown());
+    }
+    Ok(with_legacy_tags(tags, platform))
+}
+
+/// Split `{major}_{minor}_{arch}` (the arch may contain underscores).
+fn split_version_arch(rest: &str) -> Option<(u32, u32, &str)> {
+    let mut parts = rest.splitn(3, '_');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let arch = parts.next().filter(|a| !a.is_empty())?;
+    Some((major, minor, arch))
+}
+
+fn linux_platform(arch: &str, musl: bool) -> Option<Platform> {
+    match (arch, musl) {
+        ("x86_64", false) => Some(Platform::LinuxX64Gnu),
+        ("x86_64", true) => Some(Platform::LinuxX64Musl),
+        ("aarch64", false) => Some(Platform::LinuxArm64Gnu),
+        _ => None,
+    }
+}
+
+/// A `{python}-{abi}-{platform}` tag.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tag {
+    pub python: String,
+    pub abi: String,
+    pub platform: String,
+}
+
+impl Tag {
+    fn new(python: &str, abi: &str, platform: &str) -> Self {
+        Self {
+            python: python.to_string(),
+            abi: abi.to_string(),
+            platform: platform.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}-{}", self.python, self.abi, self.platform)
+    }
+}
+
+/// Interpreter flavor a wheel has to be built for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Flavor {
+    #[default]
+    CPython,
+    /// CPython built with the GIL disabled (PEP 703).
+    FreeThreaded,
+    PyPy,
+}
+
+impl Flavor {
+    /// `sys.implementation.name` of the flavor.
+    pub fn implementation(&self) -> &'static str {
+        match self {
+            Flavor::CPython | Flavor::FreeThreaded => "cpython",
+            Flavor::PyPy => "pypy",
+        }
+    }
+}
+
+/// Prints `sys.implementation.name` and whether the GIL is disabled.
+const FLAVOR_PROBE: &str = "import sys, sysconfig; print(sys.implementation.name); \
+     print(sysconfig.get_config_var('Py_GIL_DISABLED') or 0)";
+
+/// Flavor of the interpreter at `python`, or `None` when it cannot be asked.
+pub fn detect_flavor(python: &std::path::Path) -> Option<Flavor> {
+    let output = std::process::Command::new(python)
+        .args(["-c", FLAVOR_PROBE])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_flavor_probe(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the output of [`FLAVOR_PROBE`].
+pub fn parse_flavor_probe(output: &str) -> Option<Flavor> {
+    let mut lines = output.lines().map(str::trim);
+    match (lines.next()?, lines.next()) {
+        ("pypy", _) => Some(Flavor::PyPy),
+        ("cpython", Some("1")) => Some(Flavor::FreeThreaded),
+        ("cpython", _) => Some(Flavor::CPython),
+        _ => None,
+    }
+}
+
+/// ABI version suffix of every PyPy 7.3 release (`pypy310_pp73`).
+const PYPY_ABI: &str = "pp73";
+
+/// The interpreter wheels are selected for, as written in its interpreter
+/// tag: `cp312`, free-threaded `cp313t` or PyPy `pp310`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interpreter {
+    pub flavor: Flavor,
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Interpreter {
+    pub fn new(flavor: Flavor, major: u32, minor: u32) -> Self {
+        Self {
+            flavor,
+            major,
+            minor,
+        }
+    }
+
+    /// Parse an interpreter tag. The major version is one digit, as in
+    /// [`crate::resolver::cp_tag_to_dotted_version`].
+    pub fn parse(tag: &str) -> Option<Self> {
+        let (flavor, digits) = if let Some(rest) = tag.strip_prefix("pp") {
+            (Flavor::PyPy, rest)
+        } else {
+            let rest = tag.strip_prefix("cp")?;
+            match rest.strip_suffix('t') {
+                Some(rest) => (Flavor::FreeThreaded, rest),
+                None => (Flavor::CPython, rest),
+            }
+        };
+        if digits.len() < 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let (major, minor) = digits.split_at(1);
+        Some(Self::new(flavor, major.parse().ok()?, minor.parse().ok()?))
+    }
+
+    /// Interpreter of `flavor` running Python `version` (`3.13.1`).
+    pub fn from_version(flavor: Flavor, version: &str) -> Option<Self> {
+        let (major, minor) = parse_major_minor(version)?;
+        Some(Self::new(flavor, major, minor))
+    }
+
+    /// The interpreter tag this was parsed from.
+    pub fn tag(&self) -> String {
+        match self.flavor {
+            Flavor::FreeThreaded => format!("{}t", self.python_tag()),
+            _ => self.python_tag(),
+        }
+    }
+
+    /// Python tag of the wheels built for this interpreter.
+    pub fn python_tag(&self) -> String {
+        let prefix = match self.flavor {
+            Flavor::PyPy => "pp",
+            _ => "cp",
+        };
+        format!("{prefix}{}{}", self.major, self.minor)
+    }
+
+    /// ABI tag of the wheels built for this interpreter.
+    pub fn abi_tag(&self) -> String {
+        match self.flavor {
+            Flavor::CPython => self.python_tag(),
+            Flavor::FreeThreaded => self.tag(),
+            Flavor::PyPy => format!("pypy{}{}_{PYPY_ABI}", self.major, self.minor),
+        }
+    }
+
+    /// Whether stable-ABI (`abi3`) wheels load; free-threaded builds and
+    /// PyPy do not implement the limited API.
+    pub fn supports_abi3(&self) -> bool {
+        self.flavor == Flavor::CPython
+    }
+
+    /// `MAJOR.MINOR` of the Python language version.
+    pub fn dotted_version(&self) -> String {
+        format!("{}.{}", self.major, self.minor)
+    }
+}
+
+/// Tags supported by the interpreter `interpreter_tag` (e.g. `cp311`,
+/// `cp313t`, `pp310`) on `platforms`, most preferred first. `any` in
+/// `platforms` is ignored; universal wheels are always supported and come
+/// last. Unparseable tags are treated as CPython 3.11.
+pub fn supported_tags(interpreter_tag: &str, platforms: &[String]) -> Vec<Tag> {
+    let platforms: Vec<&str> = platforms
+        .iter()
+        .map(String::as_str)
+        .filter(|p| *p != "any")
+        .collect();
+    let interpreter = Interpreter::parse(interpreter_tag)
+        .unwrap_or_else(|| Interpreter::new(Flavor::CPython, 3, 11));
+    let Interpreter { major, minor, .. } = interpreter;
+    let python = interpreter.python_tag();
+
+    let mut tags = Vec::new();
+    let mut abis = vec![interpreter.abi_tag()];
+    if interpreter.supports_abi3() {
+        abis.push("abi3".to_string());
+    }
+    abis.push("none".to_string());
+    for abi in &abis {
+        tags.extend(platforms.iter().map(|p| Tag::new(&python, abi, p)));
+    }
+    if interpreter.supports_abi3() {
+        for older in (2..minor).rev() {
+            let python = format!("cp{major}{older}");
+            tags.extend(platforms.iter().map(|p| Tag::new(&python, "abi3", p)));
+        }
+    }
+
+    let mut pure = vec![format!("py{major}{minor}"), format!("py{major}")];
+    pure.extend((0..minor).rev().map(|older| format!("py{major}{older}")));
+    for python in &pure {
+        tags.extend(platforms.iter().map(|p| Tag::new(python, "none", p)));
+    }
+    tags.push(Tag::new(&python, "none", "any"));
+    tags.extend(pure.iter().map(|python| Tag::new(python, "none", "any")));
+    tags
+}
+
+/// Preference order of the tags a target supports; lower ranks are better.
+#[derive(Debug, Clone)]
+pub struct TagPriority {
+    ranks: HashMap<Tag, usize>,
+}
+
+impl TagPriority {
+    pub fn new(interpreter_tag: &str, platforms: &[String]) -> Self {
+        let mut ranks = HashMap::new();
+        for (rank, tag) in supported_tags(interpreter_tag, platforms)
+            .into_iter()
+            .enumerate()
+        {
+            ranks.entry(tag).or_insert(rank);
+        }
+        Self { ranks }
+    }
+
+    /// Best rank among the tags of a wheel whose filename carries the
+    /// (possibly compressed) `python` and `abi` tags, available on
+    /// `platforms`. `None` when the wheel is not installable.
+    pub fn rank(&self, python: &str, abi: &str, platforms: &[String]) -> Option<usize> {
+        let mut best = None;
+        for py in python.split('.') {
+            for abi in abi.split('.') {
+                for platform in platforms.iter().flat_map(|p| p.split('.')) {
+                    let tag = Tag::new(py, abi, platform);
+                    if let Some(&rank) = self.ranks.get(&tag) {
+                        best = Some(best.map_or(rank, |b: usize| b.min(rank)));
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn manylinux_x86_64_tags_includes_standard_formats() {
+        let tags = manylinux_tags("x86_64", 35);
+        assert!(
+            tags.iter().any(|t| t == "manylinux_2_17_x86_64"),
+            "should include manylinux_2_17 (manylinux2014)"
+        );
+        assert!(
+            tags.iter().any(|t| t == "manylinux_2_28_x86_64"),
+            "should include manylinux_2_28"
+        );
+        assert!(
+            tags.iter().any(|t| t == "manylinux2014_x86_64"),
+            "should include legacy manylinux2014_x86_64 tag"
+        );
+        // pip >= 22.0 dropped manylinux1 (glibc < 2.17); we floor at 2.17
+        assert!(
+            !tags.iter().any(|t| t == "manylinux_2_5_x86_64"),
+            "should not include glibc 2.5 tags (below manylinux2014 floor)"
+        );
+        assert!(linux_platform_tags("x86_64", None).contains(&"linux_x86_64".to_string()));
+    }
+
+    #[test]
+    fn manylinux_aarch64_tags_includes_standard_formats() {
+        let tags = linux_platform_tags(
+            "aarch64",
+            Some(Libc::Glibc {
+                major: 2,
+                minor: 35,
+            }),
+        );
+        assert!(
+            tags.iter().any(|t| t == "manylinux_2_17_aarch64"),
+            "should include manylinux_2_17_aarch64"
+        );
+        assert!(
+            tags.iter().any(|t| t == "manylinux2014_aarch64"),
+            "should include legacy manylinux2014_aarch64 tag"
+        );
+        assert!(
+            tags.iter().any(|t| t == "linux_aarch64"),
+            "should include plain linux_aarch64 tag"
+        );
+        assert!(!tags.iter().any(|t| t.starts_with("manylinux1_")));
+    }
+
+    #[test]
+    fn linux_tags_follow_the_detected_libc() {
+        let glibc = linux_platform_tags(
+            "x86_64",
+            Some(Libc::Glibc {
+                major: 2,
+                minor: 28,
+            }),
+        );
+        assert_eq!(glibc.first().unwrap(), "manylinux_2_28_x86_64");
+        assert!(!glibc.iter().any(|t| t == "manylinux_2_31_x86_64"));
+        assert!(!glibc.iter().any(|t| t.starts_with("musllinux")));
+
+        let musl = linux_platform_tags("aarch64", Some(Libc::Musl { major: 1, minor: 2 }));
+        assert_eq!(
+            musl,
+            strings(&[
+                "musllinux_1_2_aarch64",
+                "musllinux_1_1_aarch64",
+                "musllinux_1_0_aarch64",
+                "linux_aarch64",
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_ldd_version_output() {
+        assert_eq!(
+            parse_ldd_version("ldd (Debian GLIBC 2.36-9+deb12u4) 2.36\nCopyright (C) 2022\n"),
+            Some(Libc::Glibc {
+                major: 2,
+                minor: 36
+            })
+        );
+        assert_eq!(
+            parse_ldd_version("musl libc (x86_64)\nVersion 1.2.4\nDynamic Program Loader\n"),
+            Some(Libc::Musl { major: 1, minor: 2 })
+        );
+        assert_eq!(parse_ldd_version("something else"), None);
+    }
+
+    #[test]
+    fn platform_override_expands_to_older_compatible_tags() {
+        let tags = platform_tags_for("manylinux_2_28_aarch64").unwrap();
+        assert_eq!(tags[0], "manylinux_2_28_aarch64");
+        assert!(tags.contains(&"manylinux2014_aarch64".to_string()));
+        assert!(tags.contains(&"linux_aarch64".to_string()));
+        assert_eq!(tags.last().unwrap(), "any");
+        assert!(!tags.iter().any(|t| t.contains("x86_64")));
+
+        let alias = platform_tags_for("linux-x86_64").unwrap();
+        assert_eq!(alias[0], "manylinux_2_17_x86_64");
+
+        let mac = platform_tags_for("macosx_13_0_arm64").unwrap();
+        assert_eq!(mac[0], "macosx_13_0_arm64");
+        assert!(mac.contains(&"macosx_11_0_universal2".to_string()));
+        assert!(mac.contains(&"macos_arm64".to_string()));
+
+        assert_eq!(
+            platform_tags_for("musllinux_1_1_x86_64").unwrap()[..2],
+            strings(&["musllinux_1_1_x86_64", "musllinux_1_0_x86_64"])[..]
+        );
+        assert_eq!(platform_tags_for("win_amd64").unwrap()[0], "win_amd64");
+
+        for bad in [
+            "solaris",
+            "manylinux_2_5_x86_64",
+            "musllinux_2_0_x86_64",
+            "macosx_14_0_ppc",
+        ] {
+            assert_eq!(
+                platform_tags_for(bad),
+                Err(TagError::UnknownPlatform(bad.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn supported_tags_follow_pip_preference_order() {
+        let tags: Vec<String> =
+            supported_tags("cp312", &strings(&["manylinux_2_17_x86_64", "any"]))
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+        let position = |tag: &str| tags.iter().position(|t| t == tag).unwrap();
+        assert_eq!(tags[0], "cp312-cp312-manylinux_2_17_x86_64");
+        assert!(
+            position("cp312-abi3-manylinux_2_17_x86_64")
+                < position("cp312-none-manylinux_2_17_x86_64")
+        );
+        assert!(
+            position("cp312-none-manylinux_2_17_x86_64")
+                < position("cp38-abi3-manylinux_2_17_x86_64")
+        );
+        assert!(
+            position("cp38-abi3-manylinux_2_17_x86_64")
+                < position("py3-none-manylinux_2_17_x86_64")
+        );
+        assert!(position("py3-none-manylinux_2_17_x86_64") < position("cp312-none-any"));
+        assert!(position("cp312-none-any") < position("py3-none-any"));
+        assert!(!tags.iter().any(|t| t == "cp313-abi3-manylinux_2_17_x86_64"));
+        assert!(!tags.iter().any(|t| t.starts_with("py2")));
+    }
+
+    #[test]
+    fn rank_expands_compressed_tag_sets() {
+        let platforms = strings(&["manylinux_2_28_x86_64", "manylinux_2_17_x86_64", "any"]);
+        let priority = TagPriority::new("cp311", &platforms);
+        let compressed = priority
+            .rank(
+                "cp311",
+                "cp311",
+                &strings(&["manylinux_2_17_x86_64.manylinux2014_x86_64"]),
+            )
+            .unwrap();
+        let newer = priority
+            .rank("cp311", "cp311", &strings(&["manylinux_2_28_x86_64"]))
+            .unwrap();
+        assert!(newer < compressed);
+        assert!(
+            priority
+                .rank("py2.py3", "none", &strings(&["any"]))
+                .is_some()
+        );
+        assert_eq!(
+            priority.rank("cp312", "cp312", &strings(&["manylinux_2_17_x86_64"])),
+            None
+        );
+        assert_eq!(
+            priority.rank("cp311", "cp311", &strings(&["win_amd64"])),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_interpreter_tags() {
+        let free_threaded = Interpreter::parse("cp313t").unwrap();
+        assert_eq!(free_threaded.flavor, Flavor::FreeThreaded);
+        assert_eq!(free_threaded.python_tag(), "cp313");
+        assert_eq!(free_threaded.abi_tag(), "cp313t");
+        let pypy = Interpreter::parse("pp310").unwrap();
+        assert_eq!(pypy.abi_tag(), "pypy310_pp73");
+        assert_eq!(pypy.dotted_version(), "3.10");
+        assert_eq!(Interpreter::parse("cp312").unwrap().tag(), "cp312");
+        for invalid in ["py3", "abi3", "cp", "cpt", "ppXY"] {
+            assert_eq!(Interpreter::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn free_threaded_and_pypy_tags_skip_the_stable_abi() {
+        let platforms = strings(&["manylinux_2_17_x86_64"]);
+        let tags: Vec<String> = supported_tags("cp313t", &platforms)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(tags[0], "cp313-cp313t-manylinux_2_17_x86_64");
+        assert!(tags.contains(&"cp313-none-any".to_string()));
+        assert!(
+            !tags
+                .iter()
+                .any(|t| t.contains("abi3") || t.contains("-cp313-"))
+        );
+
+        let priority = TagPriority::new("pp310", &platforms);
+        assert!(priority.rank("pp310", "pypy310_pp73", &platforms).is_some());
+        assert_eq!(priority.rank("cp310", "cp310", &platforms), None);
+        assert_eq!(priority.rank("cp38", "abi3", &platforms), None);
+        assert!(priority.rank("py3", "none", &strings(&["any"])).is_some());
+    }
+
+    #[test]
+    fn parses_flavor_probe_output() {
+        assert_eq!(parse_flavor_probe("cpython\n0\n"), Some(Flavor::CPython));
+        assert_eq!(parse_flavor_probe("cpython\nNone\n"), Some(Flavor::CPython));
+        assert_eq!(
+            parse_flavor_probe("cpython\n1\n"),
+            Some(Flavor::FreeThreaded)
+        );
+        assert_eq!(parse_flavor_probe("pypy\n0\n"), Some(Flavor::PyPy));
+        assert_eq!(parse_flavor_probe("x86_64\nlinux-x86_64\n64\n"), None);
+    }
+}
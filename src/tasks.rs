@@ -0,0 +1,269 @@
+//! Named project tasks (`[tool.pybun.tasks]`), run with `pybun run <task>`.
+//!
+//! ```toml
+//! [tool.pybun.tasks]
+//! lint = "ruff check ."
+//! typecheck = ["mypy", "src"]
+//! test = { cmd = "pytest -q", depends = ["lint"], help = "Run the test suite" }
+//! check = { depends = ["lint", "typecheck", "test"] }
+//! ```
+//!
+//! String commands run through the platform shell (`sh -c`, `cmd /C`), so
+//! pipes and `&&` work as in npm scripts; arrays run the program directly.
+//! A task's `depends` run first, in order, each at most once per
+//! invocation; a task without `cmd` only groups its dependencies. Every
+//! command runs from the project root with the project environment's
+//! scripts directory first on `PATH` and `VIRTUAL_ENV` set, so tools
+//! installed into the environment are found without activating it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TaskError {
+    #[error("no task named '{0}' in [tool.pybun.tasks]")]
+    Unknown(String),
+    #[error("task '{name}' depends on unknown task '{dependency}'")]
+    UnknownDependency { name: String, dependency: String },
+    #[error("task dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+    #[error("task '{0}' has neither a command nor dependencies")]
+    Empty(String),
+}
+
+/// A command as written in `pyproject.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TaskCommand {
+    Shell(String),
+    Args(Vec<String>),
+}
+
+impl TaskCommand {
+    /// The process for this command, with `extra` arguments appended.
+    pub fn command(&self, extra: &[String]) -> Option<Command> {
+        match self {
+            TaskCommand::Shell(line) => {
+                let mut line = line.clone();
+                for arg in extra {
+                    line.push(' ');
+                    line.push_str(&shlex::try_quote(arg).ok()?);
+                }
+                let mut command = if cfg!(windows) {
+                    let mut command = Command::new("cmd");
+                    command.arg("/C");
+                    command
+                } else {
+                    let mut command = Command::new("sh");
+                    command.arg("-c");
+                    command
+                };
+                command.arg(line);
+                Some(command)
+            }
+            TaskCommand::Args(args) => {
+                let (program, rest) = args.split_first()?;
+                let mut command = Command::new(program);
+                command.args(rest).args(extra);
+                Some(command)
+            }
+        }
+    }
+
+    /// Human-readable command line.
+    pub fn display(&self) -> String {
+        match self {
+            TaskCommand::Shell(line) => line.clone(),
+            TaskCommand::Args(args) => {
+                shlex::try_join(args.iter().map(String::as_str)).unwrap_or_else(|_| args.join(" "))
+            }
+        }
+    }
+}
+
+/// Task definition as written in `pyproject.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TaskValue {
+    Command(TaskCommand),
+    Table(TaskTable),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskTable {
+    #[serde(default)]
+    pub cmd: Option<TaskCommand>,
+    #[serde(default)]
+    pub depends: Vec<String>,
+    #[serde(default)]
+    pub help: Option<String>,
+    /// Extra environment variables for this task's command.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl TaskValue {
+    pub fn cmd(&self) -> Option<&TaskCommand> {
+        match self {
+            TaskValue::Command(command) => Some(command),
+            TaskValue::Table(table) => table.cmd.as_ref(),
+        }
+    }
+
+    pub fn depends(&self) -> &[String] {
+        match self {
+            TaskValue::Command(_) => &[],
+            TaskValue::Table(table) => &table.depends,
+        }
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        match self {
+            TaskValue::Command(_) => None,
+            TaskValue::Table(table) => table.help.as_deref(),
+        }
+    }
+
+    pub fn env(&self) -> Option<&BTreeMap<String, String>> {
+        match self {
+            TaskValue::Command(_) => None,
+            TaskValue::Table(table) => Some(&table.env),
+        }
+    }
+}
+
+/// Tasks defined by the project containing `cwd`, with the project root
+/// (`None` when there is no project).
+pub fn load_tasks(cwd: &Path) -> Option<(PathBuf, BTreeMap<String, TaskValue>)> {
+    let project = crate::project::Project::discover(cwd).ok()?;
+    Some((project.root().to_path_buf(), project.pybun_config().tasks))
+}
+
+/// `name` and its dependencies in the order they run: dependencies first,
+/// in declaration order, each once.
+pub fn plan(tasks: &BTreeMap<String, TaskValue>, name: &str) -> Result<Vec<String>, TaskError> {
+    fn visit(
+        tasks: &BTreeMap<String, TaskValue>,
+        name: &str,
+        stack: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), TaskError> {
+        if order.iter().any(|done| done == name) {
+            return Ok(());
+        }
+        if stack.iter().any(|open| open == name) {
+            let mut cycle = stack.clone();
+            cycle.push(name.to_string());
+            return Err(TaskError::Cycle(cycle));
+        }
+        let Some(task) = tasks.get(name) else {
+            return Err(TaskError::Unknown(name.to_string()));
+        };
+        if task.cmd().is_none() && task.depends().is_empty() {
+            return Err(TaskError::Empty(name.to_string()));
+        }
+        stack.push(name.to_string());
+        for dependency in task.depends() {
+            if !tasks.contains_key(dependency) {
+                return Err(TaskError::UnknownDependency {
+                    name: name.to_string(),
+                    dependency: dependency.clone(),
+                });
+            }
+            visit(tasks, dependency, stack, order)?;
+        }
+        stack.pop();
+        order.push(name.to_string());
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(tasks, name, &mut Vec::new(), &mut order)?;
+    Ok(order)
+}
+
+/// Run `command` inside `venv`: its scripts directory first on `PATH` and
+/// `VIRTUAL_ENV` pointing at it.
+pub fn apply_environment(command: &mut Command, venv: &Path) {
+    let scripts = if venv.join("Scripts").is_dir() {
+        venv.join("Scripts")
+    } else {
+        venv.join("bin")
+    };
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let joined = std::env::join_paths(std::iter::once(scripts).chain(std::env::split_paths(&path)));
+    if let Ok(joined) = joined {
+        command.env("PATH", joined);
+    }
+    command.env("VIRTUAL_ENV", venv);
+    command.env_remove("PYTHONHOME");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(toml: &str) -> BTreeMap<String, TaskValue> {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn dependencies_run_first_and_once() {
+        let tasks = tasks(
+            r#"
+lint = "ruff check ."
+typecheck = ["mypy", "src"]
+test = { cmd = "pytest -q", depends = ["lint"], help = "Run the tests" }
+check = { depends = ["lint", "typecheck", "test"] }
+"#,
+        );
+        assert_eq!(
+            plan(&tasks, "check").unwrap(),
+            ["lint", "typecheck", "test", "check"]
+        );
+        assert_eq!(plan(&tasks, "lint").unwrap(), ["lint"]);
+        assert_eq!(tasks["test"].help(), Some("Run the tests"));
+        assert_eq!(tasks["typecheck"].cmd().unwrap().display(), "mypy src");
+    }
+
+    #[test]
+    fn rejects_cycles_unknown_dependencies_and_empty_tasks() {
+        let tasks = tasks(
+            r#"
+a = { cmd = "true", depends = ["b"] }
+b = { depends = ["a"] }
+c = { cmd = "true", depends = ["missing"] }
+d = {}
+"#,
+        );
+        assert_eq!(
+            plan(&tasks, "a").unwrap_err().to_string(),
+            "task dependency cycle: a -> b -> a"
+        );
+        assert_eq!(
+            plan(&tasks, "c").unwrap_err(),
+            TaskError::UnknownDependency {
+                name: "c".into(),
+                dependency: "missing".into()
+            }
+        );
+        assert_eq!(plan(&tasks, "d").unwrap_err(), TaskError::Empty("d".into()));
+        assert_eq!(
+            plan(&tasks, "e").unwrap_err(),
+            TaskError::Unknown("e".into())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shell_commands_quote_extra_arguments() {
+        let command = TaskCommand::Shell("echo".into())
+            .command(&["a b".into(), "$HOME".into()])
+            .unwrap();
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["-c", "echo 'a b' '$HOME'"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! Coverage for `pybun test --coverage`, measured with coverage.py.
+//!
+//! Every test process records into its own data file in a private
+//! directory: pytest and unittest runs (including each `-j` worker) through
+//! `coverage run --parallel-mode`, native harness processes by starting
+//! coverage themselves when [`DATA_ENV`] is set. After the run the files
+//! are combined into `.coverage` in the working directory
+//! (`.coverage.shard-N-of-M` for `--shard N/M`, so `coverage combine` can
+//! merge the shards' data later). The totals and per-file numbers come from
+//! `coverage json`; lcov, XML and JSON reports are written on request.
+
+use crate::cli::CoverageFormat;
+use crate::tool_exec::{ToolError, ToolRun};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Data file prefix for native harness processes; each writes a suffixed
+/// file next to it.
+pub const DATA_ENV: &str = "PYBUN_COVERAGE_DATA";
+
+/// Coverage totals and per-file numbers, as reported in `detail.coverage`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageSummary {
+    /// Percent covered, branches included when branch coverage is on.
+    pub percent: f64,
+    pub covered_lines: u64,
+    pub num_statements: u64,
+    pub missing_lines: u64,
+    pub files: Vec<FileCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileCoverage {
+    pub path: String,
+    pub percent: f64,
+    pub covered_lines: u64,
+    pub num_statements: u64,
+    /// Line numbers of statements that never ran.
+    pub missing: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+struct JsonReport {
+    #[serde(default)]
+    files: BTreeMap<String, JsonFile>,
+    totals: JsonSummary,
+}
+
+#[derive(Deserialize)]
+struct JsonFile {
+    summary: JsonSummary,
+    #[serde(default)]
+    missing_lines: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+struct JsonSummary {
+    covered_lines: u64,
+    num_statements: u64,
+    percent_covered: f64,
+    #[serde(default)]
+    missing_lines: u64,
+}
+
+/// Summary of a `coverage json` report; `None` when it is not one.
+pub fn parse_json(json: &str) -> Option<CoverageSummary> {
+    let report: JsonReport = serde_json::from_str(json).ok()?;
+    Some(CoverageSummary {
+        percent: report.totals.percent_covered,
+        covered_lines: report.totals.covered_lines,
+        num_statements: report.totals.num_statements,
+        missing_lines: report.totals.missing_lines,
+        files: report
+            .files
+            .into_iter()
+            .map(|(path, file)| FileCoverage {
+                path: path.replace('\\', "/"),
+                percent: file.summary.percent_covered,
+                covered_lines: file.summary.covered_lines,
+                num_statements: file.summary.num_statements,
+                missing: file.missing_lines,
+            })
+            .collect(),
+    })
+}
+
+/// Where the combined data of a run goes.
+pub fn combined_data_file(shard: Option<(u32, u32)>) -> PathBuf {
+    match shard {
+        Some((n, m)) => PathBuf::from(format!(".coverage.shard-{n}-of-{m}")),
+        None => PathBuf::from(".coverage"),
+    }
+}
+
+/// Report file and `coverage` subcommand of a report format.
+pub fn report_target(format: CoverageFormat) -> (&'static str, &'static str) {
+    match format {
+        CoverageFormat::Lcov => ("lcov", "coverage.lcov"),
+        CoverageFormat::Xml => ("xml", "coverage.xml"),
+        CoverageFormat::Json => ("json", "coverage.json"),
+    }
+}
+
+/// Per-process coverage data of one run, in a private directory.
+pub struct CoverageData {
+    dir: tempfile::TempDir,
+}
+
+impl CoverageData {
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            dir: tempfile::tempdir()?,
+        })
+    }
+
+    /// Data file prefix every process writes a suffixed file next to.
+    pub fn data_file(&self) -> PathBuf {
+        self.dir.path().join(".coverage")
+    }
+
+    /// Combine the processes' data into `output`.
+    pub fn combine(&self, python: &str, output: &Path) -> Result<(), ToolError> {
+        let mut cmd = coverage(python, "combine", output);
+        cmd.arg(self.dir.path());
+        ToolRun::new("coverage", &mut cmd).run().map(|_| ())
+    }
+
+    /// Totals and per-file numbers of the combined data in `data_file`;
+    /// `None` when coverage wrote no readable report.
+    pub fn summary(
+        &self,
+        python: &str,
+        data_file: &Path,
+    ) -> Result<Option<CoverageSummary>, ToolError> {
+        let json = self.dir.path().join("coverage.json");
+        report(python, data_file, "json", &json)?;
+        Ok(std::fs::read_to_string(&json)
+            .ok()
+            .and_then(|content| parse_json(&content)))
+    }
+}
+
+/// Interpreter arguments that run a module (`-m pytest ...` follows) under
+/// coverage, writing a suffixed data file next to `data_file`.
+pub fn run_args(data_file: &Path) -> Vec<OsString> {
+    let mut data = OsString::from("--data-file=");
+    data.push(data_file);
+    vec![
+        "-m".into(),
+        "coverage".into(),
+        "run".into(),
+        "--parallel-mode".into(),
+        data,
+    ]
+}
+
+/// Whether `python` can import coverage.
+pub fn is_installed(python: &str) -> bool {
+    Command::new(python)
+        .args(["-c", "import coverage"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Write a `format` report of `data_file` to its default file, returning
+/// the path.
+pub fn write_report(
+    python: &str,
+    data_file: &Path,
+    format: CoverageFormat,
+) -> Result<PathBuf, ToolError> {
+    let (command, path) = report_target(format);
+    report(python, data_file, command, Path::new(path))?;
+    Ok(PathBuf::from(path))
+}
+
+fn report(python: &str, data_file: &Path, command: &str, output: &Path) -> Result<(), ToolError> {
+    let mut cmd = coverage(python, command, data_file);
+    // `--coverage-fail-under` is checked by pybun, not by each report.
+    cmd.arg("--fail-under=0").arg("-o").arg(output);
+    ToolRun::new("coverage", &mut cmd).run().map(|_| ())
+}
+
+fn coverage(python: &str, command: &str, data_file: &Path) -> Command {
+    let mut data = OsString::from("--data-file=");
+    data.push(data_file);
+    let mut cmd = Command::new(python);
+    cmd.args(["-m", "coverage", command, "--quiet"]).arg(data);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_totals_and_files_of_a_json_report() {
+        let json = r#"{
+            "meta": {"version": "7.6.1", "branch_coverage": false},
+            "files": {
+                "src\\app\\calc.py": {
+                    "executed_lines": [1, 2, 4],
+                    "summary": {"covered_lines": 3, "num_statements": 4, "percent_covered": 75.0, "missing_lines": 1, "excluded_lines": 0},
+                    "missing_lines": [5],
+                    "excluded_lines": []
+                },
+                "tests/test_calc.py": {
+                    "executed_lines": [1, 3, 4],
+                    "summary": {"covered_lines": 3, "num_statements": 3, "percent_covered": 100.0, "missing_lines": 0, "excluded_lines": 0},
+                    "missing_lines": [],
+                    "excluded_lines": []
+                }
+            },
+            "totals": {"covered_lines": 6, "num_statements": 7, "percent_covered": 85.71428571428571, "missing_lines": 1, "excluded_lines": 0}
+        }"#;
+        let summary = parse_json(json).unwrap();
+        assert_eq!(summary.covered_lines, 6);
+        assert_eq!(summary.num_statements, 7);
+        assert!((summary.percent - 85.714).abs() < 0.001);
+        assert_eq!(summary.files[0].path, "src/app/calc.py");
+        assert_eq!(summary.files[0].missing, [5]);
+        assert_eq!(summary.files[1].percent, 100.0);
+        assert_eq!(parse_json("{}"), None);
+    }
+
+    #[test]
+    fn shards_get_their_own_combined_data_file() {
+        assert_eq!(combined_data_file(None), PathBuf::from(".coverage"));
+        assert_eq!(
+            combined_data_file(Some((2, 3))),
+            PathBuf::from(".coverage.shard-2-of-3")
+        );
+    }
+}
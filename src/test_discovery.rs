@@ -6,9 +6,13 @@
 //! - Identifies fixtures and their usage
 //! - Provides compatibility shims for pytest patterns
 //!
-//! The discovery uses a lightweight AST-like parsing approach without requiring
-//! a full Python parser, focusing on common test patterns.
+//! Files are parsed with the structural parser in [`crate::python_ast`], so
+//! multi-line signatures and decorators, tests defined under `if`/`try`, and
+//! `def`s nested in other functions (which are never collected) are handled
+//! the way Python sees them.
 
+use crate::python_ast::{self, ClassDef, Decorator, FunctionDef, ParamKind, Stmt};
+use crate::test_plugins::PluginRegistry;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -40,6 +44,9 @@ pub struct TestItem {
     pub xfail: bool,
     /// Parametrized values if any
     pub parametrize: Option<ParametrizeInfo>,
+    /// Discovery plugin that found this item (`None` for Python tests)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
 }
 
 /// Type of test item
@@ -218,19 +225,27 @@ impl Default for DiscoveryConfig {
 /// Test discovery engine
 pub struct TestDiscovery {
     config: DiscoveryConfig,
+    plugins: PluginRegistry,
 }
 
 impl TestDiscovery {
     /// Create a new test discovery engine with default configuration
     pub fn new() -> Self {
-        Self {
-            config: DiscoveryConfig::default(),
-        }
+        Self::with_config(DiscoveryConfig::default())
     }
 
     /// Create a new test discovery engine with custom configuration
     pub fn with_config(config: DiscoveryConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            plugins: PluginRegistry::default(),
+        }
+    }
+
+    /// Hand files claimed by a discovery plugin to that plugin's parser
+    pub fn with_plugins(mut self, plugins: PluginRegistry) -> Self {
+        self.plugins = plugins;
+        self
     }
 
     /// Get the configuration
@@ -238,6 +253,11 @@ impl TestDiscovery {
         &self.config
     }
 
+    /// Get the registered discovery plugins
+    pub fn plugins(&self) -> &PluginRegistry {
+        &self.plugins
+    }
+
     /// Discover tests in the given paths
     pub fn discover(&self, paths: &[PathBuf]) -> DiscoveryResult {
         let start = std::time::Instant::now();
@@ -257,7 +277,7 @@ impl TestDiscovery {
             match std::fs::read_to_string(&file_path) {
                 Ok(content) => {
                     let (file_tests, file_fixtures, file_warnings) =
-                        self.parse_file(&file_path, &content);
+                        self.parse_any(&file_path, &content);
                     tests.extend(file_tests);
                     fixtures.extend(file_fixtures);
                     compat_warnings.extend(file_warnings);
@@ -293,7 +313,7 @@ impl TestDiscovery {
 
         match std::fs::read_to_string(path) {
             Ok(content) => {
-                let (tests, fixtures, warnings) = self.parse_file(path, &content);
+                let (tests, fixtures, warnings) = self.parse_any(path, &content);
                 result.tests = tests;
                 result.fixtures = fixtures;
                 result.compat_warnings = warnings;
@@ -313,9 +333,11 @@ impl TestDiscovery {
 
         for path in paths {
             if path.is_file() {
-                // For explicitly specified files, accept any .py file
-                // For pattern matching, use is_test_file
-                if path.extension().map(|e| e == "py").unwrap_or(false) {
+                // For explicitly specified files, accept any .py file or a
+                // file claimed by a plugin. For pattern matching, use is_test_file
+                if path.extension().map(|e| e == "py").unwrap_or(false)
+                    || self.plugins.plugin_for(path).is_some()
+                {
                     files.push(path.clone());
                 }
             } else if path.is_dir() {
@@ -355,6 +377,10 @@ impl TestDiscovery {
             None => return false,
         };
 
+        if self.plugins.plugin_for(path).is_some() {
+            return true;
+        }
+
         if !name.ends_with(".py") {
             return false;
         }
@@ -368,230 +394,196 @@ impl TestDiscovery {
         false
     }
 
+    /// Parse a file with the plugin claiming it, or as Python otherwise
+    fn parse_any(
+        &self,
+        path: &Path,
+        content: &str,
+    ) -> (Vec<TestItem>, Vec<FixtureInfo>, Vec<CompatWarning>) {
+        match self.plugins.plugin_for(path) {
+            Some(plugin) => (plugin.parse_file(path, content), Vec::new(), Vec::new()),
+            None => self.parse_file(path, content),
+        }
+    }
+
     /// Parse a Python file and extract test items, fixtures, and warnings
     fn parse_file(
         &self,
         path: &Path,
         content: &str,
     ) -> (Vec<TestItem>, Vec<FixtureInfo>, Vec<CompatWarning>) {
-        let mut tests = Vec::new();
-        let mut fixtures = Vec::new();
-        let mut warnings = Vec::new();
-
-        // Track current class context
-        let mut current_class: Option<ClassContext> = None;
-        let mut pending_decorators: Vec<DecoratorInfo> = Vec::new();
-
-        for (line_num, line) in content.lines().enumerate() {
-            let line_number = line_num + 1;
-            let trimmed = line.trim();
+        let module = python_ast::parse_module(content);
+        let mut found = Found {
+            constants: module_constants(&module),
+            ..Found::default()
+        };
+        self.collect(path, &module, None, &mut found);
+        (found.tests, found.fixtures, found.warnings)
+    }
 
-            // Skip empty lines and comments
-            if trimmed.is_empty() || trimmed.starts_with('#') {
-                continue;
+    /// Collect the tests and fixtures of a module or class body. Bodies of
+    /// `if`, `try`, `with` and other compound statements are searched too,
+    /// function bodies are not: a nested `def` is never collected.
+    fn collect(
+        &self,
+        path: &Path,
+        body: &[Stmt],
+        class: Option<&ClassContext>,
+        found: &mut Found<'_>,
+    ) {
+        for stmt in body {
+            match stmt {
+                Stmt::Compound { body, .. } => self.collect(path, body, class, found),
+                Stmt::Class(def) => self.collect_class(path, def, class, found),
+                Stmt::Function(def) => self.collect_function(path, def, class, found),
+                Stmt::Simple { .. } => {}
             }
+        }
+    }
 
-            // Track decorators
-            if trimmed.starts_with('@') {
-                if let Some(decorator) = self.parse_decorator(trimmed, path, line_number) {
-                    pending_decorators.push(decorator);
-                }
-                continue;
-            }
+    fn collect_class(
+        &self,
+        path: &Path,
+        def: &ClassDef,
+        parent: Option<&ClassContext>,
+        found: &mut Found<'_>,
+    ) {
+        // Nested classes are only collected from classes that are collected.
+        if parent.is_some_and(|p| !p.collects_methods()) {
+            return;
+        }
+        let own: Vec<DecoratorInfo> = def.decorators.iter().map(DecoratorInfo::from).collect();
+        // Marks on a class apply to everything in it.
+        let mut decorators = parent.map(|p| p.decorators.clone()).unwrap_or_default();
+        decorators.extend(own.iter().cloned());
+
+        let is_test_class = self
+            .config
+            .class_patterns
+            .iter()
+            .any(|p| matches_pattern(&def.name, p));
+        let context = ClassContext {
+            name: match parent {
+                Some(p) => format!("{}::{}", p.name, def.name),
+                None => def.name.clone(),
+            },
+            is_test_class,
+            has_bases: !def.bases.is_empty(),
+            decorators,
+        };
 
-            // Track class definitions
-            if trimmed.starts_with("class ") {
-                if let Some(class_name) = self.parse_class_name(trimmed) {
-                    // Check if it's a test class
-                    let is_test_class = self
-                        .config
-                        .class_patterns
-                        .iter()
-                        .any(|p| matches_pattern(&class_name, p));
-
-                    current_class = Some(ClassContext {
-                        name: class_name.clone(),
-                        is_test_class,
-                        line: line_number,
-                        decorators: pending_decorators.clone(),
-                    });
-
-                    if is_test_class {
-                        tests.push(TestItem {
-                            name: class_name.clone(),
-                            short_name: class_name.clone(),
-                            path: path.to_path_buf(),
-                            line: line_number,
-                            item_type: TestItemType::Class,
-                            markers: self.extract_markers(&pending_decorators),
-                            fixtures: Vec::new(),
-                            class_name: None,
-                            skipped: self.is_skipped(&pending_decorators),
-                            skip_reason: self.get_skip_reason(&pending_decorators),
-                            xfail: self.is_xfail(&pending_decorators),
-                            parametrize: self.get_parametrize(&pending_decorators),
-                        });
-                    }
-                }
-                pending_decorators.clear();
-                continue;
+        if is_test_class {
+            found.push_test(TestItem {
+                name: context.name.clone(),
+                short_name: def.name.clone(),
+                path: path.to_path_buf(),
+                line: def.line,
+                item_type: TestItemType::Class,
+                markers: self.extract_markers(&context.decorators),
+                fixtures: Vec::new(),
+                class_name: parent.map(|p| p.name.clone()),
+                skipped: self.is_skipped(&context.decorators),
+                skip_reason: self.get_skip_reason(&context.decorators),
+                xfail: self.is_xfail(&context.decorators),
+                parametrize: self.get_parametrize(&context.decorators, &found.constants),
+                plugin: None,
+            });
+            if self.config.compat_warnings {
+                found
+                    .warnings
+                    .extend(self.check_compat_warnings(path, def.line, &own, &def.name));
             }
+        }
 
-            // Track function/method definitions
-            if trimmed.starts_with("def ") || trimmed.starts_with("async def ") {
-                if let Some(func_name) = self.parse_function_name(trimmed) {
-                    let indent = line.len() - line.trim_start().len();
-                    let is_method = indent > 0 && current_class.is_some();
-
-                    // Check for fixture decorator
-                    let is_fixture = pending_decorators
-                        .iter()
-                        .any(|d| d.name == "fixture" || d.name == "pytest.fixture");
-
-                    if is_fixture && self.config.discover_fixtures {
-                        let fixture = self.create_fixture_info(
-                            &func_name,
-                            path,
-                            line_number,
-                            &pending_decorators,
-                            trimmed,
-                        );
-                        fixtures.push(fixture);
-
-                        // Check for compatibility warnings on fixtures too
-                        if self.config.compat_warnings {
-                            warnings.extend(self.check_compat_warnings(
-                                path,
-                                line_number,
-                                &pending_decorators,
-                                trimmed,
-                            ));
-                        }
-
-                        pending_decorators.clear();
-                        continue;
-                    }
+        self.collect(path, &def.body, Some(&context), found);
+    }
 
-                    // Check if it's a test function/method
-                    let is_test = self
-                        .config
-                        .function_patterns
-                        .iter()
-                        .any(|p| matches_pattern(&func_name, p));
-
-                    if is_test {
-                        let (full_name, class_name) = if is_method {
-                            if let Some(ref class_ctx) = current_class {
-                                (
-                                    format!("{}::{}", class_ctx.name, func_name),
-                                    Some(class_ctx.name.clone()),
-                                )
-                            } else {
-                                (func_name.clone(), None)
-                            }
-                        } else {
-                            // Reset class context if we're at top level
-                            if indent == 0 {
-                                current_class = None;
-                            }
-                            (func_name.clone(), None)
-                        };
-
-                        // Extract fixture dependencies from function signature
-                        let fixture_deps = self.extract_fixture_dependencies(trimmed);
-
-                        tests.push(TestItem {
-                            name: full_name,
-                            short_name: func_name.clone(),
-                            path: path.to_path_buf(),
-                            line: line_number,
-                            item_type: if is_method {
-                                TestItemType::Method
-                            } else {
-                                TestItemType::Function
-                            },
-                            markers: self.extract_markers(&pending_decorators),
-                            fixtures: fixture_deps,
-                            class_name,
-                            skipped: self.is_skipped(&pending_decorators),
-                            skip_reason: self.get_skip_reason(&pending_decorators),
-                            xfail: self.is_xfail(&pending_decorators),
-                            parametrize: self.get_parametrize(&pending_decorators),
-                        });
-
-                        // Check for compatibility warnings
-                        if self.config.compat_warnings {
-                            warnings.extend(self.check_compat_warnings(
-                                path,
-                                line_number,
-                                &pending_decorators,
-                                trimmed,
-                            ));
-                        }
-                    }
+    fn collect_function(
+        &self,
+        path: &Path,
+        def: &FunctionDef,
+        class: Option<&ClassContext>,
+        found: &mut Found<'_>,
+    ) {
+        let own: Vec<DecoratorInfo> = def.decorators.iter().map(DecoratorInfo::from).collect();
+
+        // Check for fixture decorator
+        let is_fixture = own
+            .iter()
+            .any(|d| d.name == "fixture" || d.name == "pytest.fixture");
+
+        if is_fixture {
+            if self.config.discover_fixtures {
+                found.fixtures.push(self.create_fixture_info(
+                    &def.name,
+                    path,
+                    def.line,
+                    &own,
+                    &def.signature,
+                ));
+
+                // Check for compatibility warnings on fixtures too
+                if self.config.compat_warnings {
+                    found.warnings.extend(self.check_compat_warnings(
+                        path,
+                        def.line,
+                        &own,
+                        &def.signature,
+                    ));
                 }
-                pending_decorators.clear();
-                continue;
-            }
-
-            // Reset class context if we encounter a top-level definition
-            if !line.starts_with(' ')
-                && !line.starts_with('\t')
-                && !trimmed.is_empty()
-                && !trimmed.starts_with('@')
-            {
-                current_class = None;
             }
+            return;
         }
 
-        (tests, fixtures, warnings)
-    }
-
-    /// Parse a decorator line
-    fn parse_decorator(&self, line: &str, _path: &Path, _line_num: usize) -> Option<DecoratorInfo> {
-        let line = line.trim_start_matches('@');
-
-        // Handle decorators with arguments: @pytest.mark.skip(reason="...")
-        let (name, args) = if let Some(paren_idx) = line.find('(') {
-            let name = line[..paren_idx].trim();
-            let args_part = &line[paren_idx + 1..];
-            let args = args_part.trim_end_matches(')').to_string();
-            (name.to_string(), Some(args))
-        } else {
-            (line.trim().to_string(), None)
-        };
+        // Check if it's a test function/method
+        let is_test = self
+            .config
+            .function_patterns
+            .iter()
+            .any(|p| matches_pattern(&def.name, p))
+            && class.is_none_or(ClassContext::collects_methods);
+        if !is_test {
+            return;
+        }
 
-        Some(DecoratorInfo { name, args })
-    }
+        let mut decorators = class.map(|c| c.decorators.clone()).unwrap_or_default();
+        decorators.extend(own.iter().cloned());
+        let parametrize = self.get_parametrize(&decorators, &found.constants);
 
-    /// Parse class name from a class definition line
-    fn parse_class_name(&self, line: &str) -> Option<String> {
-        let line = line.trim_start_matches("class ");
-        // Handle "class TestFoo:" or "class TestFoo(Base):"
-        let name_end = line.find(['(', ':']).unwrap_or(line.len());
-        let name = line[..name_end].trim();
-        if !name.is_empty() {
-            Some(name.to_string())
-        } else {
-            None
+        // Parametrized arguments are passed directly, not as fixtures
+        let mut fixture_deps = self.extract_fixture_dependencies(&def.signature);
+        if let Some(info) = &parametrize {
+            fixture_deps.retain(|name| !info.params.contains(name));
         }
-    }
 
-    /// Parse function name from a function definition line
-    fn parse_function_name(&self, line: &str) -> Option<String> {
-        let line = if line.starts_with("async def ") {
-            line.trim_start_matches("async def ")
-        } else {
-            line.trim_start_matches("def ")
-        };
-
-        // Handle "def test_foo(...):"
-        let name_end = line.find('(').unwrap_or(line.len());
-        let name = line[..name_end].trim();
-        if !name.is_empty() {
-            Some(name.to_string())
-        } else {
-            None
+        found.push_test(TestItem {
+            name: match class {
+                Some(c) => format!("{}::{}", c.name, def.name),
+                None => def.name.clone(),
+            },
+            short_name: def.name.clone(),
+            path: path.to_path_buf(),
+            line: def.line,
+            item_type: if class.is_some() {
+                TestItemType::Method
+            } else {
+                TestItemType::Function
+            },
+            markers: self.extract_markers(&decorators),
+            fixtures: fixture_deps,
+            class_name: class.map(|c| c.name.clone()),
+            skipped: self.is_skipped(&decorators),
+            skip_reason: self.get_skip_reason(&decorators),
+            xfail: self.is_xfail(&decorators),
+            parametrize,
+            plugin: None,
+        });
+
+        // Check for compatibility warnings
+        if self.config.compat_warnings {
+            found
+                .warnings
+                .extend(self.check_compat_warnings(path, def.line, &own, &def.signature));
         }
     }
 
@@ -627,27 +619,19 @@ impl TestDiscovery {
         markers
     }
 
-    /// Parse marker arguments
+    /// Parse marker arguments; string literals are reported by value, any
+    /// other expression as written
     fn parse_marker_args(&self, args_str: &str) -> (Vec<String>, HashMap<String, String>) {
         let mut args = Vec::new();
         let mut kwargs = HashMap::new();
 
-        // Simple parsing of comma-separated args
-        // This is a simplified parser that handles common cases
-        for part in split_args(args_str) {
-            let part = part.trim();
-            if part.is_empty() {
-                continue;
-            }
-
-            if let Some(eq_idx) = part.find('=') {
-                // keyword argument
-                let key = part[..eq_idx].trim().to_string();
-                let value = part[eq_idx + 1..].trim().trim_matches('"').to_string();
-                kwargs.insert(key, value);
-            } else {
-                // positional argument
-                args.push(part.trim_matches('"').to_string());
+        for arg in python_ast::call_arguments(args_str) {
+            let value = literal_or_expr(arg.value);
+            match arg.keyword {
+                Some(key) => {
+                    kwargs.insert(key.to_string(), value);
+                }
+                None => args.push(value),
             }
         }
 
@@ -672,20 +656,19 @@ impl TestDiscovery {
             if dec.name.contains("skip")
                 && let Some(ref args) = dec.args
             {
-                // Look for reason= argument
-                for part in split_args(args) {
-                    let part = part.trim();
-                    if part.starts_with("reason=") {
-                        return Some(
-                            part.trim_start_matches("reason=")
-                                .trim_matches('"')
-                                .to_string(),
-                        );
-                    }
-                    // First positional arg for @skip("reason")
-                    if !part.contains('=') && !part.is_empty() {
-                        return Some(part.trim_matches('"').to_string());
-                    }
+                let args = python_ast::call_arguments(args);
+                if let Some(reason) = args.iter().find(|a| a.keyword == Some("reason")) {
+                    return Some(literal_or_expr(reason.value));
+                }
+                // First positional arg for @skip("reason"); the first
+                // argument of skipif is its condition
+                if !dec.name.ends_with("skipif")
+                    && let Some(reason) = args
+                        .first()
+                        .filter(|a| a.keyword.is_none())
+                        .and_then(|a| python_ast::string_literal(a.value))
+                {
+                    return Some(reason);
                 }
             }
         }
@@ -702,67 +685,77 @@ impl TestDiscovery {
         })
     }
 
-    /// Get parametrize info if available
-    fn get_parametrize(&self, decorators: &[DecoratorInfo]) -> Option<ParametrizeInfo> {
+    /// Get parametrize info if available. Stacked parametrize decorators
+    /// multiply: every combination of their cases runs.
+    fn get_parametrize(
+        &self,
+        decorators: &[DecoratorInfo],
+        constants: &HashMap<&str, &str>,
+    ) -> Option<ParametrizeInfo> {
+        let mut combined: Option<ParametrizeInfo> = None;
         for dec in decorators {
-            if dec.name.contains("parametrize")
+            if dec.name.ends_with("parametrize")
                 && let Some(ref args) = dec.args
+                && let Some(info) = self.parse_parametrize_args(args, constants)
             {
-                return self.parse_parametrize_args(args);
+                combined = Some(match combined {
+                    Some(mut all) => {
+                        all.params.extend(info.params);
+                        all.case_count *= info.case_count;
+                        all
+                    }
+                    None => info,
+                });
             }
         }
-        None
+        combined
     }
 
-    /// Parse parametrize decorator arguments
-    fn parse_parametrize_args(&self, args_str: &str) -> Option<ParametrizeInfo> {
-        // Format: "param1,param2", [values...]
-        // We just need to extract param names and count cases
-
-        let parts: Vec<&str> = split_args(args_str).collect();
-        if parts.is_empty() {
-            return None;
-        }
+    /// Parse parametrize decorator arguments: the parameter names and the
+    /// number of cases. Argument values given as a module-level constant
+    /// are looked up; values computed at runtime count as one case.
+    fn parse_parametrize_args(
+        &self,
+        args_str: &str,
+        constants: &HashMap<&str, &str>,
+    ) -> Option<ParametrizeInfo> {
+        let args = python_ast::call_arguments(args_str);
+        let argument = |keyword: &str, position: usize| {
+            args.iter()
+                .find(|a| a.keyword == Some(keyword))
+                .or_else(|| args.get(position).filter(|a| a.keyword.is_none()))
+                .map(|a| a.value)
+        };
 
-        // First arg is comma-separated param names
-        let params_str = parts[0].trim().trim_matches('"');
-        let params: Vec<String> = params_str
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+        // "x, y", ("x", "y") or ["x", "y"]
+        let names = argument("argnames", 0)?;
+        let params: Vec<String> = match python_ast::string_literal(names) {
+            Some(names) => names
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            None => python_ast::sequence_items(names)?
+                .into_iter()
+                .filter_map(python_ast::string_literal)
+                .collect(),
+        };
 
-        // Estimate case count from the rest (look for [ ] brackets)
-        let rest = parts.get(1).unwrap_or(&"");
-        let case_count = rest.matches('[').count().max(1);
+        let values = argument("argvalues", 1).unwrap_or("");
+        let values = constants.get(values).copied().unwrap_or(values);
+        let case_count = python_ast::sequence_items(values).map_or(1, |items| items.len());
 
         Some(ParametrizeInfo { params, case_count })
     }
 
-    /// Extract fixture dependencies from function signature
-    fn extract_fixture_dependencies(&self, line: &str) -> Vec<String> {
-        // Extract parameters from def foo(self, fixture1, fixture2):
-        let start = match line.find('(') {
-            Some(idx) => idx + 1,
-            None => return Vec::new(),
-        };
-        let end = match line.find(')') {
-            Some(idx) => idx,
-            None => return Vec::new(),
-        };
-
-        let params_str = &line[start..end];
-
-        params_str
-            .split(',')
-            .map(|s| {
-                // Handle type annotations: param: Type
-                let param = s.split(':').next().unwrap_or(s);
-                // Handle default values: param=value
-                let param = param.split('=').next().unwrap_or(param);
-                param.trim().to_string()
-            })
-            .filter(|s| !s.is_empty() && s != "self" && s != "cls")
+    /// Extract fixture dependencies from a function signature: parameters
+    /// without a default value, except `self`/`cls` and `*args`/`**kwargs`
+    fn extract_fixture_dependencies(&self, signature: &str) -> Vec<String> {
+        python_ast::def_params(signature)
+            .into_iter()
+            .filter(|p| matches!(p.kind, ParamKind::Regular | ParamKind::KeywordOnly))
+            .filter(|p| p.default.is_none() && p.name != "self" && p.name != "cls")
+            .map(|p| p.name)
             .collect()
     }
 
@@ -773,8 +766,9 @@ impl TestDiscovery {
         path: &Path,
         line: usize,
         decorators: &[DecoratorInfo],
-        func_line: &str,
+        signature: &str,
     ) -> FixtureInfo {
+        let mut name = name.to_string();
         let mut scope = FixtureScope::Function;
         let mut autouse = false;
 
@@ -783,31 +777,33 @@ impl TestDiscovery {
             if (dec.name == "fixture" || dec.name == "pytest.fixture")
                 && let Some(ref args) = dec.args
             {
-                for part in split_args(args) {
-                    let part = part.trim();
-                    if part.starts_with("scope=") {
-                        let scope_str = part
-                            .trim_start_matches("scope=")
-                            .trim_matches('"')
-                            .trim_matches('\'');
-                        scope = match scope_str {
-                            "class" => FixtureScope::Class,
-                            "module" => FixtureScope::Module,
-                            "package" => FixtureScope::Package,
-                            "session" => FixtureScope::Session,
-                            _ => FixtureScope::Function,
-                        };
-                    } else if part.starts_with("autouse=") {
-                        autouse = part.trim_start_matches("autouse=").trim() == "True";
+                for arg in python_ast::call_arguments(args) {
+                    match arg.keyword {
+                        Some("scope") => {
+                            scope = match literal_or_expr(arg.value).as_str() {
+                                "class" => FixtureScope::Class,
+                                "module" => FixtureScope::Module,
+                                "package" => FixtureScope::Package,
+                                "session" => FixtureScope::Session,
+                                _ => FixtureScope::Function,
+                            };
+                        }
+                        Some("autouse") => autouse = arg.value == "True",
+                        Some("name") => {
+                            if let Some(alias) = python_ast::string_literal(arg.value) {
+                                name = alias;
+                            }
+                        }
+                        _ => {}
                     }
                 }
             }
         }
 
-        let dependencies = self.extract_fixture_dependencies(func_line);
+        let dependencies = self.extract_fixture_dependencies(signature);
 
         FixtureInfo {
-            name: name.to_string(),
+            name,
             path: path.to_path_buf(),
             line,
             scope,
@@ -891,19 +887,82 @@ struct DecoratorInfo {
     args: Option<String>,
 }
 
+impl From<&Decorator> for DecoratorInfo {
+    fn from(decorator: &Decorator) -> Self {
+        Self {
+            name: decorator.name().to_string(),
+            args: decorator.args().map(str::to_string),
+        }
+    }
+}
+
 /// Class context for tracking test methods
 #[derive(Debug)]
-#[allow(dead_code)]
 struct ClassContext {
+    /// Qualified name, e.g. `TestOuter::TestInner`
     name: String,
     is_test_class: bool,
-    line: usize,
+    has_bases: bool,
+    /// Decorators of the class and the classes around it
     decorators: Vec<DecoratorInfo>,
 }
 
+impl ClassContext {
+    /// Methods are collected from test classes and from classes with a base
+    /// class, which may be `unittest.TestCase`
+    fn collects_methods(&self) -> bool {
+        self.is_test_class || self.has_bases
+    }
+}
+
+/// What parsing one file found
+#[derive(Default)]
+struct Found<'a> {
+    tests: Vec<TestItem>,
+    fixtures: Vec<FixtureInfo>,
+    warnings: Vec<CompatWarning>,
+    /// Module-level `NAME = value` assignments
+    constants: HashMap<&'a str, &'a str>,
+}
+
+impl Found<'_> {
+    /// A later definition of the same name replaces the earlier one, as it
+    /// does in the module namespace
+    fn push_test(&mut self, item: TestItem) {
+        match self.tests.iter_mut().find(|t| t.name == item.name) {
+            Some(existing) => *existing = item,
+            None => self.tests.push(item),
+        }
+    }
+}
+
+/// Module-level assignments of a plain name, e.g. `CASES = [(1, 2)]`
+fn module_constants(module: &[Stmt]) -> HashMap<&str, &str> {
+    module
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Simple { text, .. } => python_ast::keyword_split(text),
+            _ => None,
+        })
+        .filter_map(|(target, value)| {
+            // `CASES: list = [...]`
+            let target = target.split(':').next().unwrap_or(target).trim();
+            target
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_')
+                .then_some((target, value))
+        })
+        .collect()
+}
+
+/// Value of a string literal, or the expression as written
+fn literal_or_expr(expr: &str) -> String {
+    python_ast::string_literal(expr).unwrap_or_else(|| expr.to_string())
+}
+
 /// Simple pattern matching (supports * wildcard)
 /// Supports patterns like: test_*.py, *_test.py, Test*, etc.
-fn matches_pattern(name: &str, pattern: &str) -> bool {
+pub(crate) fn matches_pattern(name: &str, pattern: &str) -> bool {
     if pattern == "*" {
         return true;
     }
@@ -931,49 +990,6 @@ fn matches_pattern(name: &str, pattern: &str) -> bool {
     }
 }
 
-/// Split arguments respecting brackets and quotes
-fn split_args(args: &str) -> impl Iterator<Item = &str> {
-    // Simple split that handles top-level commas
-    // For a more robust solution, we'd need a proper parser
-    let mut depth: i32 = 0;
-    let mut in_string = false;
-    let mut string_char = ' ';
-    let mut last_split = 0;
-    let mut result = Vec::new();
-
-    let bytes = args.as_bytes();
-    for (i, &b) in bytes.iter().enumerate() {
-        let c = b as char;
-
-        if in_string {
-            if c == string_char && (i == 0 || bytes[i - 1] != b'\\') {
-                in_string = false;
-            }
-            continue;
-        }
-
-        match c {
-            '"' | '\'' => {
-                in_string = true;
-                string_char = c;
-            }
-            '[' | '(' | '{' => depth += 1,
-            ']' | ')' | '}' => depth = depth.saturating_sub(1),
-            ',' if depth == 0 => {
-                result.push(&args[last_split..i]);
-                last_split = i + 1;
-            }
-            _ => {}
-        }
-    }
-
-    if last_split < args.len() {
-        result.push(&args[last_split..]);
-    }
-
-    result.into_iter()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1149,10 +1165,39 @@ async def test_async_func():
         assert!(!discovery.is_test_file(Path::new("test_example.txt")));
     }
 
+    #[test]
+    fn test_plugin_files_bypass_python_parser() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("test_math.py"),
+            "def test_add():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("login.feature"),
+            "Feature: Login\n  Scenario: Works\n    Given a user\n",
+        )
+        .unwrap();
+        std::fs::write(temp.path().join("notes.txt"), "Scenario: ignored\n").unwrap();
+
+        let mut plugins = PluginRegistry::new();
+        plugins.register(Box::new(crate::test_plugins::BehavePlugin::new(None)));
+        let discovery = TestDiscovery::new().with_plugins(plugins);
+        let mut result = discovery.discover(&[temp.path().to_path_buf()]);
+        result.tests.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(result.scanned_files.len(), 2);
+        assert_eq!(result.tests.len(), 2);
+        assert_eq!(result.tests[0].name, "Login::Works");
+        assert_eq!(result.tests[0].plugin.as_deref(), Some("behave"));
+        assert_eq!(result.tests[1].name, "test_add");
+        assert_eq!(result.tests[1].plugin, None);
+    }
+
     #[test]
     fn test_split_args() {
         let args = r#""x,y", [(1, 2), (3, 4)]"#;
-        let parts: Vec<_> = split_args(args).collect();
+        let parts = python_ast::split_top_level(args, ',');
         assert_eq!(parts.len(), 2);
         assert_eq!(parts[0].trim(), "\"x,y\"");
     }
@@ -1193,6 +1238,140 @@ def test_param(x):
         assert!(warnings.iter().any(|w| w.code == "I001"));
     }
 
+    #[test]
+    fn test_nested_and_conditional_definitions() {
+        let discovery = TestDiscovery::new();
+        let content = r#"
+import sys
+import pytest
+
+def test_outer():
+    @pytest.mark.skip
+    def test_inner():
+        pass
+    test_inner()
+
+if sys.version_info >= (3, 8):
+    def test_modern():
+        pass
+else:
+    def test_modern():
+        pass
+
+try:
+    import numpy
+except ImportError:
+    numpy = None
+else:
+    def test_numpy():
+        """
+        def test_in_docstring():
+        """
+
+class Helper:
+    def test_not_collected(self):
+        pass
+
+@pytest.mark.slow
+class TestOuter:
+    class TestInner:
+        @pytest.mark.xfail
+        def test_deep(self):
+            pass
+
+def test_after_class(): pass
+"#;
+
+        let (tests, _, _) = discovery.parse_file(Path::new("test_nested.py"), content);
+        let names: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "test_outer",
+                "test_modern",
+                "test_numpy",
+                "TestOuter",
+                "TestOuter::TestInner",
+                "TestOuter::TestInner::test_deep",
+                "test_after_class",
+            ]
+        );
+
+        let outer = &tests[0];
+        assert!(!outer.skipped, "decorators of nested defs stay nested");
+        // The later definition wins, as in the module namespace
+        assert_eq!(tests[1].line, 15);
+
+        let deep = &tests[5];
+        assert_eq!(deep.item_type, TestItemType::Method);
+        assert_eq!(deep.class_name.as_deref(), Some("TestOuter::TestInner"));
+        assert!(deep.xfail);
+        assert!(deep.markers.iter().any(|m| m.name == "slow"));
+        assert_eq!(tests[6].item_type, TestItemType::Function);
+    }
+
+    #[test]
+    fn test_multiline_signatures_and_decorators() {
+        let discovery = TestDiscovery::new();
+        let content = r#"
+import pytest
+
+CASES = [
+    (1, 2),
+    (3, 4),
+    pytest.param(5, 6, marks=pytest.mark.slow),
+]
+
+@pytest.fixture(
+    scope='module',
+    name="db",
+)
+def database_fixture(
+    tmp_path,
+    settings: dict[str, int],
+):
+    yield
+
+@pytest.mark.skipif(
+    sys.platform == "win32",
+    reason='needs "fork"',
+)
+@pytest.mark.parametrize(("a", "b"), CASES)
+@pytest.mark.parametrize("flag", [True, False])
+def test_combined(
+    a,
+    b,
+    flag,
+    db,
+    retries=3,
+    *args,
+    **kwargs,
+):
+    pass
+"#;
+
+        let (tests, fixtures, _) = discovery.parse_file(Path::new("test_multi.py"), content);
+
+        assert_eq!(fixtures.len(), 1);
+        assert_eq!(fixtures[0].name, "db");
+        assert_eq!(fixtures[0].line, 14);
+        assert_eq!(fixtures[0].scope, FixtureScope::Module);
+        assert_eq!(fixtures[0].dependencies, vec!["tmp_path", "settings"]);
+
+        assert_eq!(tests.len(), 1);
+        let test = &tests[0];
+        assert_eq!(test.line, 26);
+        assert!(test.skipped);
+        assert_eq!(test.skip_reason.as_deref(), Some("needs \"fork\""));
+        assert_eq!(test.fixtures, vec!["db"]);
+        let params = test.parametrize.as_ref().unwrap();
+        assert_eq!(params.params, vec!["a", "b", "flag"]);
+        assert_eq!(params.case_count, 6);
+        let skipif = &test.markers[0];
+        assert_eq!(skipif.args, vec![r#"sys.platform == "win32""#]);
+        assert_eq!(skipif.kwargs["reason"], "needs \"fork\"");
+    }
+
     #[test]
     fn test_unittest_style() {
         let discovery = TestDiscovery::new();
@@ -5,11 +5,18 @@
 //! - Supports sharding for distributed test runs
 //! - Implements fail-fast behavior
 //! - Collects and aggregates test results
+//!
+//! Each test runs in its own interpreter, either through pytest or through
+//! PyBun's built-in harness (`test_harness.py`), which imports the test
+//! module directly, resolves fixtures and reports a structured outcome
+//! without needing pytest installed.
 
 use crate::test_discovery::{TestItem, TestItemType};
 use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
 use std::path::PathBuf;
 use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -34,6 +41,18 @@ pub struct ExecutorConfig {
     pub retries: usize,
     /// Python executable path
     pub python: String,
+    /// How each test is run.
+    pub runner: TestRunner,
+    /// Extra environment variables for every test process.
+    pub env: Vec<(String, OsString)>,
+    /// Extra pytest arguments placed before the test node id (e.g. `-p plugin`).
+    pub pytest_args: Vec<String>,
+    /// Once set, no further tests are started; tests already running finish
+    /// and the summary reports `stopped_early`.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Measure each test process with coverage.py, writing suffixed data
+    /// files next to this prefix (see [`crate::test_coverage`]).
+    pub coverage: Option<PathBuf>,
 }
 
 impl Default for ExecutorConfig {
@@ -46,10 +65,52 @@ impl Default for ExecutorConfig {
             timeout: None,
             retries: 0,
             python: "python3".to_string(),
+            runner: TestRunner::default(),
+            env: Vec::new(),
+            pytest_args: Vec::new(),
+            cancel: None,
+            coverage: None,
         }
     }
 }
 
+impl ExecutorConfig {
+    fn cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+}
+
+/// How the executor runs a single test.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TestRunner {
+    /// `python -m pytest -xvs <path>::<name>`.
+    #[default]
+    Pytest,
+    /// The built-in harness: no pytest needed, outcome read from the JSON
+    /// result file the harness writes.
+    Harness,
+}
+
+/// Source of the built-in harness, run with `python -c`.
+const HARNESS_SOURCE: &str = include_str!("test_harness.py");
+
+/// Result file written by the built-in harness.
+#[derive(Debug, Deserialize)]
+struct HarnessReport {
+    outcome: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    longrepr: Option<String>,
+    /// Id of the parametrized case that decided the outcome.
+    #[serde(default)]
+    case: Option<String>,
+    #[serde(default)]
+    duration_ms: Option<u64>,
+}
+
 /// Get number of CPUs (simple cross-platform approximation)
 fn num_cpus() -> usize {
     // Default to 4 if we can't determine
@@ -237,6 +298,16 @@ impl TestExecutor {
 
     /// Execute tests in parallel
     pub fn execute(&self, tests: Vec<TestItem>) -> ExecutionResult {
+        self.execute_observed(tests, |_| {})
+    }
+
+    /// Like [`Self::execute`], calling `on_result` on the calling thread as
+    /// each test finishes.
+    pub fn execute_observed(
+        &self,
+        tests: Vec<TestItem>,
+        mut on_result: impl FnMut(&TestResult),
+    ) -> ExecutionResult {
         let start = Instant::now();
 
         // Filter to only runnable tests (functions and methods, not classes)
@@ -265,20 +336,29 @@ impl TestExecutor {
 
         // For single-threaded or small test counts, run sequentially
         if self.config.workers <= 1 || total_tests <= 2 {
-            return self.execute_sequential(sharded_tests, start);
+            return self.execute_sequential(sharded_tests, start, &mut on_result);
         }
 
         // Parallel execution
-        self.execute_parallel(sharded_tests, start)
+        self.execute_parallel(sharded_tests, start, &mut on_result)
     }
 
     /// Execute tests sequentially (simpler, used for small test counts)
-    fn execute_sequential(&self, tests: Vec<TestItem>, start: Instant) -> ExecutionResult {
+    fn execute_sequential(
+        &self,
+        tests: Vec<TestItem>,
+        start: Instant,
+        on_result: &mut dyn FnMut(&TestResult),
+    ) -> ExecutionResult {
         let mut results = Vec::new();
         let mut stopped_early = false;
         let total = tests.len();
 
         for test in tests {
+            if self.config.cancelled() {
+                stopped_early = true;
+                break;
+            }
             if self.config.verbose {
                 eprintln!("Running: {}...", test.name);
             }
@@ -290,6 +370,7 @@ impl TestExecutor {
                 TestOutcome::Failed | TestOutcome::Error | TestOutcome::Timeout
             );
 
+            on_result(&result);
             results.push(result);
 
             // Fail-fast check
@@ -306,7 +387,12 @@ impl TestExecutor {
     }
 
     /// Execute tests in parallel using worker threads
-    fn execute_parallel(&self, tests: Vec<TestItem>, start: Instant) -> ExecutionResult {
+    fn execute_parallel(
+        &self,
+        tests: Vec<TestItem>,
+        start: Instant,
+        on_result: &mut dyn FnMut(&TestResult),
+    ) -> ExecutionResult {
         let total_tests = tests.len();
         let workers = self.config.workers.min(total_tests);
 
@@ -329,7 +415,7 @@ impl TestExecutor {
             let handle = thread::spawn(move || {
                 loop {
                     // Check stop flag
-                    if *stop.lock().unwrap() {
+                    if *stop.lock().unwrap() || config.cancelled() {
                         break;
                     }
 
@@ -384,6 +470,7 @@ impl TestExecutor {
                         eprintln!("{} {} ({}ms)", status, result.name, result.duration_ms);
                     }
 
+                    on_result(&result);
                     results.push(result);
 
                     // Fail-fast
@@ -405,6 +492,7 @@ impl TestExecutor {
         for handle in handles {
             let _ = handle.join();
         }
+        stopped_early |= self.config.cancelled() && results.len() < total_tests;
 
         let duration_ms = start.elapsed().as_millis() as u64;
         let summary = self.compute_summary(&results, duration_ms, stopped_early, total_tests);
@@ -461,16 +549,50 @@ impl TestExecutor {
     fn run_test_attempt(config: &ExecutorConfig, test: &TestItem, worker_id: usize) -> TestResult {
         let start = Instant::now();
 
-        // Build the pytest command to run this specific test
-        let test_spec = format!("{}::{}", test.path.display(), test.name);
-
         let mut command = Command::new(&config.python);
-        command.args(["-m", "pytest", "-xvs", &test_spec]);
+        let report = match config.runner {
+            TestRunner::Pytest => {
+                // Build the pytest command to run this specific test
+                let test_spec = format!("{}::{}", test.path.display(), test.name);
+                if let Some(data_file) = &config.coverage {
+                    command.args(crate::test_coverage::run_args(data_file));
+                }
+                command.args(["-m", "pytest", "-xvs"]);
+                command.args(&config.pytest_args);
+                command.arg(&test_spec);
+                None
+            }
+            TestRunner::Harness => match tempfile::NamedTempFile::new() {
+                Ok(report) => {
+                    command.args(["-c", HARNESS_SOURCE]);
+                    command.arg(report.path()).arg(&test.path).arg(&test.name);
+                    Some(report)
+                }
+                Err(e) => {
+                    return Self::error_result(
+                        test,
+                        start,
+                        worker_id,
+                        format!("Failed to create harness result file: {}", e),
+                    );
+                }
+            },
+        };
+        command.envs(config.env.iter().map(|(k, v)| (k, v)));
+        if let (TestRunner::Harness, Some(data_file)) = (config.runner, &config.coverage) {
+            command.env(crate::test_coverage::DATA_ENV, data_file);
+        }
 
         match run_with_timeout(command, config.timeout) {
             Ok(RunOutcome::Completed(output)) => {
                 let duration_ms = start.elapsed().as_millis() as u64;
-                Self::parse_test_output(test, output, duration_ms, worker_id)
+                match report {
+                    Some(report) => {
+                        let report = std::fs::read(report.path()).unwrap_or_default();
+                        Self::parse_harness_report(test, output, &report, duration_ms, worker_id)
+                    }
+                    None => Self::parse_test_output(test, output, duration_ms, worker_id),
+                }
             }
             Ok(RunOutcome::TimedOut) => TestResult {
                 name: test.name.clone(),
@@ -487,18 +609,33 @@ impl TestExecutor {
                 skip_reason: None,
                 retries: 0,
             },
-            Err(e) => TestResult {
-                name: test.name.clone(),
-                path: test.path.clone(),
-                line: test.line,
-                outcome: TestOutcome::Error,
-                duration_ms: start.elapsed().as_millis() as u64,
-                stdout: String::new(),
-                stderr: format!("Failed to execute test: {}", e),
+            Err(e) => Self::error_result(
+                test,
+                start,
                 worker_id,
-                skip_reason: None,
-                retries: 0,
-            },
+                format!("Failed to execute test: {}", e),
+            ),
+        }
+    }
+
+    /// An `Error` result for a test that could not be started.
+    fn error_result(
+        test: &TestItem,
+        start: Instant,
+        worker_id: usize,
+        stderr: String,
+    ) -> TestResult {
+        TestResult {
+            name: test.name.clone(),
+            path: test.path.clone(),
+            line: test.line,
+            outcome: TestOutcome::Error,
+            duration_ms: start.elapsed().as_millis() as u64,
+            stdout: String::new(),
+            stderr,
+            worker_id,
+            skip_reason: None,
+            retries: 0,
         }
     }
 
@@ -552,6 +689,82 @@ impl TestExecutor {
         }
     }
 
+    /// Build the result of a harness run from the JSON report it wrote.
+    ///
+    /// A missing or unreadable report means the interpreter died before the
+    /// harness finished (a crash or `os._exit` in the test), which counts as
+    /// an error. The reported duration covers the test itself, without
+    /// interpreter startup.
+    fn parse_harness_report(
+        test: &TestItem,
+        output: Output,
+        report: &[u8],
+        duration_ms: u64,
+        worker_id: usize,
+    ) -> TestResult {
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let mut stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+        let Ok(report) = serde_json::from_slice::<HarnessReport>(report) else {
+            if stderr.is_empty() {
+                stderr = format!(
+                    "test process exited with {} without a result",
+                    output.status
+                );
+            }
+            return TestResult {
+                name: test.name.clone(),
+                path: test.path.clone(),
+                line: test.line,
+                outcome: TestOutcome::Error,
+                duration_ms,
+                stdout,
+                stderr,
+                worker_id,
+                skip_reason: None,
+                retries: 0,
+            };
+        };
+
+        let outcome = match report.outcome.as_str() {
+            "passed" => TestOutcome::Passed,
+            "skipped" => TestOutcome::Skipped,
+            "xfail" => TestOutcome::XFail,
+            "xpass" => TestOutcome::XPass,
+            "failed" => TestOutcome::Failed,
+            _ => TestOutcome::Error,
+        };
+        let skip_reason = (outcome == TestOutcome::Skipped)
+            .then(|| report.message.clone())
+            .flatten();
+        if matches!(outcome, TestOutcome::Failed | TestOutcome::Error) {
+            let detail = report
+                .longrepr
+                .or(report.message)
+                .unwrap_or_else(|| report.outcome.clone());
+            if !stderr.is_empty() && !stderr.ends_with('\n') {
+                stderr.push('\n');
+            }
+            if let Some(case) = report.case {
+                stderr.push_str(&format!("[{case}] "));
+            }
+            stderr.push_str(&detail);
+        }
+
+        TestResult {
+            name: test.name.clone(),
+            path: test.path.clone(),
+            line: test.line,
+            outcome,
+            duration_ms: report.duration_ms.unwrap_or(duration_ms),
+            stdout,
+            stderr,
+            worker_id,
+            skip_reason,
+            retries: 0,
+        }
+    }
+
     /// Compute summary from results
     fn compute_summary(
         &self,
@@ -701,6 +914,7 @@ mod tests {
             skip_reason: None,
             xfail: false,
             parametrize: None,
+            plugin: None,
         }
     }
 
@@ -987,4 +1201,101 @@ def test_flaky():
             "should report 2 retries before passing on the 3rd attempt"
         );
     }
+
+    #[test]
+    fn harness_runs_fixtures_and_parametrized_cases_without_pytest() {
+        if Command::new("python3").arg("--version").output().is_err() {
+            eprintln!("Skipping harness test: python3 not installed");
+            return;
+        }
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let test_file = temp.path().join("test_native.py");
+        std::fs::write(
+            &test_file,
+            r#"import pytest
+
+@pytest.fixture
+def number():
+    yield 41
+
+def test_fixture(number):
+    assert number + 1 == 42
+
+@pytest.mark.parametrize("value", [1, 2])
+def test_cases(value):
+    assert value == 1
+
+@pytest.mark.skip(reason="not today")
+def test_skipped():
+    pass
+"#,
+        )
+        .unwrap();
+
+        let executor = TestExecutor::new(ExecutorConfig {
+            workers: 1,
+            python: "python3".to_string(),
+            runner: TestRunner::Harness,
+            ..Default::default()
+        });
+        let tests = ["test_fixture", "test_cases", "test_skipped"]
+            .into_iter()
+            .map(|name| TestItem {
+                path: test_file.clone(),
+                ..make_test(name)
+            })
+            .collect();
+        let result = executor.execute(tests);
+
+        let outcome = |name: &str| {
+            result
+                .results
+                .iter()
+                .find(|r| r.name == name)
+                .unwrap_or_else(|| panic!("{name} missing"))
+        };
+        assert_eq!(outcome("test_fixture").outcome, TestOutcome::Passed);
+        let cases = outcome("test_cases");
+        assert_eq!(cases.outcome, TestOutcome::Failed);
+        assert!(cases.stderr.contains("[2] "), "{}", cases.stderr);
+        assert!(
+            cases.stderr.contains("assert value == 1"),
+            "{}",
+            cases.stderr
+        );
+        assert_eq!(outcome("test_skipped").outcome, TestOutcome::Skipped);
+        assert_eq!(
+            outcome("test_skipped").skip_reason.as_deref(),
+            Some("not today")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn harness_without_a_report_is_an_error() {
+        let output = Command::new("false").output().unwrap();
+        let result = TestExecutor::parse_harness_report(&make_test("test_a"), output, b"", 5, 0);
+        assert_eq!(result.outcome, TestOutcome::Error);
+        assert!(
+            result.stderr.contains("without a result"),
+            "{}",
+            result.stderr
+        );
+    }
+
+    #[test]
+    fn cancelled_run_starts_no_tests() {
+        let cancel = Arc::new(AtomicBool::new(true));
+        let executor = TestExecutor::new(ExecutorConfig {
+            cancel: Some(cancel),
+            ..Default::default()
+        });
+        let tests = vec![make_test("test_one")];
+        let mut seen = 0;
+        let result = executor.execute_observed(tests, |_| seen += 1);
+        assert_eq!(seen, 0);
+        assert!(result.results.is_empty());
+        assert!(result.summary.stopped_early);
+    }
 }
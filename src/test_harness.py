@@ -0,0 +1,749 @@
+"""PyBun native test harness.
+
+Runs one discovered test without pytest:
+
+    python -c <this file> RESULT_FILE TEST_FILE TEST_NAME
+
+TEST_NAME is the discovered name (``test_fn`` or ``TestClass::test_method``).
+The outcome is written as JSON to RESULT_FILE; anything the test prints goes
+to the process's stdout/stderr as usual.
+
+A minimal ``pytest`` stand-in is installed when pytest itself is not
+importable, covering what plain test modules use: ``fixture`` (with
+``yield`` teardown and ``autouse``), ``mark.skip``/``skipif``/``xfail``/
+``parametrize``, ``param``, ``raises``, ``skip``, ``fail``, ``xfail`` and
+``importorskip``. Fixtures are resolved from the test class, the module and
+every ``conftest.py`` between the working directory and the test file, plus
+the built-ins ``tmp_path``, ``monkeypatch``, ``capsys`` and ``request``.
+"""
+
+import importlib.util
+import inspect
+import io
+import itertools
+import json
+import os
+import re
+import shutil
+import sys
+import tempfile
+import time
+import traceback
+import types
+import unittest
+
+
+class Skipped(Exception):
+    pass
+
+
+class Failed(AssertionError):
+    pass
+
+
+class XFailed(Exception):
+    pass
+
+
+# -- pytest stand-in ---------------------------------------------------------
+
+
+class Mark:
+    def __init__(self, name, args=(), kwargs=None):
+        self.name = name
+        self.args = args
+        self.kwargs = kwargs or {}
+
+
+class MarkDecorator:
+    def __init__(self, name, args=(), kwargs=None):
+        self.mark = Mark(name, args, kwargs)
+
+    def __call__(self, *args, **kwargs):
+        if len(args) == 1 and not kwargs and (inspect.isfunction(args[0]) or inspect.isclass(args[0])):
+            target = args[0]
+            marks = list(getattr(target, "pytestmark", []))
+            marks.append(self.mark)
+            target.pytestmark = marks
+            return target
+        return MarkDecorator(self.mark.name, args, kwargs)
+
+
+class MarkGenerator:
+    def __getattr__(self, name):
+        if name.startswith("_"):
+            raise AttributeError(name)
+        return MarkDecorator(name)
+
+
+class ParameterSet:
+    def __init__(self, values, marks, id):
+        self.values = values
+        self.marks = marks
+        self.id = id
+
+
+def param(*values, marks=(), id=None):
+    if isinstance(marks, MarkDecorator):
+        marks = (marks,)
+    return ParameterSet(values, [m.mark for m in marks], id)
+
+
+def fixture(function=None, *, scope="function", params=None, autouse=False, name=None, ids=None):
+    def decorate(func):
+        func._pybun_fixture = {"name": name or func.__name__, "autouse": autouse}
+        return func
+
+    if function is not None:
+        return decorate(function)
+    return decorate
+
+
+def skip(reason=""):
+    raise Skipped(reason)
+
+
+def fail(reason="", pytrace=True):
+    raise Failed(reason)
+
+
+def xfail(reason=""):
+    raise XFailed(reason)
+
+
+def importorskip(modname, minversion=None, reason=None):
+    try:
+        return __import__(modname, fromlist=["__name__"])
+    except ImportError:
+        raise Skipped(reason or "could not import {!r}".format(modname))
+
+
+class ExceptionInfo:
+    def __init__(self):
+        self.type = None
+        self.value = None
+        self.tb = None
+
+    def match(self, pattern):
+        assert re.search(pattern, str(self.value)), "pattern {!r} not found in {!r}".format(
+            pattern, str(self.value)
+        )
+        return True
+
+
+class RaisesContext:
+    def __init__(self, expected, match):
+        self.expected = expected
+        self.match_expr = match
+        self.excinfo = ExceptionInfo()
+
+    def __enter__(self):
+        return self.excinfo
+
+    def __exit__(self, exc_type, exc, tb):
+        if exc_type is None:
+            raise Failed("DID NOT RAISE {}".format(self.expected))
+        if not issubclass(exc_type, self.expected):
+            return False
+        self.excinfo.type, self.excinfo.value, self.excinfo.tb = exc_type, exc, tb
+        if self.match_expr is not None:
+            self.excinfo.match(self.match_expr)
+        return True
+
+
+def raises(expected, *args, match=None, **kwargs):
+    context = RaisesContext(expected, match)
+    if not args:
+        return context
+    with context:
+        args[0](*args[1:], **kwargs)
+    return context.excinfo
+
+
+def install_pytest_shim():
+    try:
+        import pytest  # noqa: F401
+
+        return sys.modules["pytest"]
+    except ImportError:
+        pass
+    shim = types.ModuleType("pytest")
+    shim.__pybun_shim__ = True
+    shim.mark = MarkGenerator()
+    shim.param = param
+    shim.fixture = fixture
+    shim.skip = skip
+    shim.fail = fail
+    shim.xfail = xfail
+    shim.importorskip = importorskip
+    shim.raises = raises
+    shim.skip.Exception = Skipped
+    shim.fail.Exception = Failed
+    sys.modules["pytest"] = shim
+    return shim
+
+
+def outcome_exceptions(pytest):
+    """Skip/fail/xfail exception types, from real pytest when it is present."""
+    if getattr(pytest, "__pybun_shim__", False):
+        return Skipped, Failed, XFailed
+    return (
+        (Skipped, pytest.skip.Exception),
+        (Failed, pytest.fail.Exception),
+        (XFailed, pytest.xfail.Exception),
+    )
+
+
+# -- built-in fixtures -------------------------------------------------------
+
+
+class MonkeyPatch:
+    _missing = object()
+
+    def __init__(self):
+        self._undo = []
+
+    def setattr(self, target, name, value=_missing, raising=True):
+        if value is MonkeyPatch._missing:
+            module, _, attr = target.rpartition(".")
+            target, name, value = __import__(module, fromlist=[attr]), attr, name
+        old = getattr(target, name, MonkeyPatch._missing)
+        if old is MonkeyPatch._missing and raising:
+            raise AttributeError("{!r} has no attribute {!r}".format(target, name))
+        self._undo.append(lambda: self._restore(target, name, old))
+        setattr(target, name, value)
+
+    def delattr(self, target, name, raising=True):
+        if not hasattr(target, name):
+            if raising:
+                raise AttributeError(name)
+            return
+        old = getattr(target, name)
+        self._undo.append(lambda: setattr(target, name, old))
+        delattr(target, name)
+
+    def setenv(self, name, value, prepend=None):
+        value = str(value)
+        if prepend and name in os.environ:
+            value = value + prepend + os.environ[name]
+        old = os.environ.get(name)
+        self._undo.append(lambda: self._restore_env(name, old))
+        os.environ[name] = value
+
+    def delenv(self, name, raising=True):
+        if name not in os.environ:
+            if raising:
+                raise KeyError(name)
+            return
+        old = os.environ[name]
+        self._undo.append(lambda: self._restore_env(name, old))
+        del os.environ[name]
+
+    def setitem(self, mapping, key, value):
+        old = mapping.get(key, MonkeyPatch._missing)
+        self._undo.append(lambda: self._restore_item(mapping, key, old))
+        mapping[key] = value
+
+    def delitem(self, mapping, key, raising=True):
+        if key not in mapping:
+            if raising:
+                raise KeyError(key)
+            return
+        old = mapping[key]
+        self._undo.append(lambda: self._restore_item(mapping, key, old))
+        del mapping[key]
+
+    def syspath_prepend(self, path):
+        old = list(sys.path)
+        self._undo.append(lambda: sys.path.__setitem__(slice(None), old))
+        sys.path.insert(0, str(path))
+
+    def chdir(self, path):
+        old = os.getcwd()
+        self._undo.append(lambda: os.chdir(old))
+        os.chdir(path)
+
+    def undo(self):
+        while self._undo:
+            self._undo.pop()()
+
+    @staticmethod
+    def _restore(target, name, old):
+        if old is MonkeyPatch._missing:
+            delattr(target, name)
+        else:
+            setattr(target, name, old)
+
+    @staticmethod
+    def _restore_env(name, old):
+        if old is None:
+            os.environ.pop(name, None)
+        else:
+            os.environ[name] = old
+
+    @staticmethod
+    def _restore_item(mapping, key, old):
+        if old is MonkeyPatch._missing:
+            mapping.pop(key, None)
+        else:
+            mapping[key] = old
+
+
+class CaptureResult(tuple):
+    def __new__(cls, out, err):
+        result = tuple.__new__(cls, (out, err))
+        result.out = out
+        result.err = err
+        return result
+
+
+class CaptureFixture:
+    def __init__(self):
+        self._out = io.StringIO()
+        self._err = io.StringIO()
+        self._saved = sys.stdout, sys.stderr
+        sys.stdout, sys.stderr = self._out, self._err
+
+    def readouterr(self):
+        out, err = self._out.getvalue(), self._err.getvalue()
+        for stream in (self._out, self._err):
+            stream.seek(0)
+            stream.truncate()
+        return CaptureResult(out, err)
+
+    def close(self):
+        sys.stdout, sys.stderr = self._saved
+
+
+class FixtureRequest:
+    def __init__(self, function, module, instance):
+        self.function = function
+        self.module = module
+        self.instance = instance
+        self.cls = type(instance) if instance is not None else None
+        self.node = types.SimpleNamespace(name=function.__name__)
+        self.fixturename = None
+        self.param = None
+        self._finalizers = []
+
+    def addfinalizer(self, finalizer):
+        self._finalizers.append(finalizer)
+
+
+def builtin_tmp_path(request):
+    path = tempfile.mkdtemp(prefix="pybun-{}-".format(request.function.__name__))
+    from pathlib import Path
+
+    yield Path(path)
+    shutil.rmtree(path, ignore_errors=True)
+
+
+def builtin_monkeypatch():
+    patch = MonkeyPatch()
+    yield patch
+    patch.undo()
+
+
+def builtin_capsys():
+    capture = CaptureFixture()
+    yield capture
+    capture.close()
+
+
+BUILTINS = {
+    "tmp_path": builtin_tmp_path,
+    "monkeypatch": builtin_monkeypatch,
+    "capsys": builtin_capsys,
+}
+
+
+# -- fixture resolution ------------------------------------------------------
+
+
+def collect_fixtures(namespace, into, instance=None):
+    for value in list(vars(namespace).values()):
+        func = getattr(value, "__func__", value)
+        info = getattr(func, "_pybun_fixture", None)
+        if info is None:
+            # Real pytest's fixture decorator wraps the function.
+            marker = getattr(func, "_pytestfixturefunction", None) or getattr(
+                func, "_fixture_function_marker", None
+            )
+            if marker is None:
+                continue
+            wrapped = getattr(func, "__pytest_wrapped__", None)
+            func = getattr(wrapped, "obj", None) or getattr(func, "_fixture_function", func)
+            info = {
+                "name": getattr(marker, "name", None) or func.__name__,
+                "autouse": getattr(marker, "autouse", False),
+            }
+        if instance is not None:
+            func = func.__get__(instance)
+        into[info["name"]] = (func, info["autouse"])
+
+
+def load_module(path, name):
+    spec = importlib.util.spec_from_file_location(name, path)
+    module = importlib.util.module_from_spec(spec)
+    sys.modules[name] = module
+    spec.loader.exec_module(module)
+    return module
+
+
+def conftest_fixtures(test_file):
+    fixtures = {}
+    root = os.path.abspath(os.getcwd())
+    directory = os.path.dirname(os.path.abspath(test_file))
+    chain = []
+    while True:
+        chain.append(directory)
+        if directory == root or os.path.dirname(directory) == directory:
+            break
+        directory = os.path.dirname(directory)
+    if not chain[-1].startswith(root):
+        chain = chain[:1]
+    for index, directory in enumerate(reversed(chain)):
+        conftest = os.path.join(directory, "conftest.py")
+        if os.path.isfile(conftest):
+            module = load_module(conftest, "conftest_pybun_{}".format(index))
+            collect_fixtures(module, fixtures)
+    return fixtures
+
+
+class FixtureResolver:
+    def __init__(self, definitions, request):
+        self.definitions = definitions
+        self.request = request
+        self.values = {"request": request}
+        self.teardowns = []
+
+    def get(self, name, stack=()):
+        if name in self.values:
+            return self.values[name]
+        if name in stack:
+            raise RuntimeError("fixture cycle: {}".format(" -> ".join(stack + (name,))))
+        if name in self.definitions:
+            func = self.definitions[name][0]
+        elif name in BUILTINS:
+            func = BUILTINS[name]
+        else:
+            raise LookupError("fixture {!r} not found".format(name))
+        kwargs = {arg: self.get(arg, stack + (name,)) for arg in arguments(func)}
+        value = func(**kwargs)
+        if inspect.isgenerator(value):
+            generator = value
+            value = next(generator)
+            self.teardowns.append(lambda: next(generator, None))
+        self.values[name] = value
+        return value
+
+    def teardown(self):
+        errors = []
+        for finalizer in reversed(self.teardowns + self.request._finalizers):
+            try:
+                finalizer()
+            except Exception:
+                errors.append(format_failure())
+        return errors
+
+
+def arguments(func):
+    try:
+        params = inspect.signature(func).parameters.values()
+    except (TypeError, ValueError):
+        return []
+    return [
+        p.name
+        for p in params
+        if p.kind in (p.POSITIONAL_OR_KEYWORD, p.KEYWORD_ONLY) and p.default is p.empty
+    ]
+
+
+# -- marks -------------------------------------------------------------------
+
+
+def marks_of(*targets):
+    marks = []
+    for target in targets:
+        if target is None:
+            continue
+        found = getattr(target, "pytestmark", [])
+        if not isinstance(found, list):
+            found = [found]
+        marks.extend(getattr(mark, "mark", mark) for mark in found)
+    return marks
+
+
+def condition_holds(condition, module):
+    if isinstance(condition, str):
+        return bool(eval(condition, vars(module)))
+    return bool(condition)
+
+
+def skip_reason(marks, module):
+    for mark in marks:
+        if mark.name == "skip":
+            return mark.kwargs.get("reason", mark.args[0] if mark.args else "")
+        if mark.name == "skipif":
+            conditions = mark.args or (mark.kwargs.get("condition", True),)
+            if any(condition_holds(c, module) for c in conditions):
+                return mark.kwargs.get("reason", "")
+    return None
+
+
+def xfail_mark(marks, module):
+    for mark in marks:
+        if mark.name == "xfail":
+            conditions = mark.args or (mark.kwargs.get("condition", True),)
+            if all(condition_holds(c, module) for c in conditions):
+                return mark
+    return None
+
+
+def parametrize_cases(marks):
+    """(id, kwargs, marks) for every parametrize combination."""
+    axes = []
+    for mark in reversed([m for m in marks if m.name == "parametrize"]):
+        names, values = mark.args[0], mark.args[1] if len(mark.args) > 1 else mark.kwargs["argvalues"]
+        if isinstance(names, str):
+            names = [n.strip() for n in names.split(",") if n.strip()]
+        ids = mark.kwargs.get("ids")
+        axis = []
+        for index, value in enumerate(values):
+            case_marks = []
+            case_id = None
+            if isinstance(value, ParameterSet) or hasattr(value, "values") and hasattr(value, "marks"):
+                case_marks = [getattr(m, "mark", m) for m in value.marks]
+                case_id = value.id
+                value = value.values if len(names) > 1 else value.values[0]
+            if isinstance(ids, (list, tuple)) and index < len(ids):
+                case_id = ids[index]
+            kwargs = dict(zip(names, value)) if len(names) > 1 else {names[0]: value}
+            axis.append((case_id or "-".join(repr_id(v) for v in kwargs.values()), kwargs, case_marks))
+        axes.append(axis)
+    if not axes:
+        return [(None, {}, [])]
+    cases = []
+    for combination in itertools.product(*axes):
+        kwargs, case_marks = {}, []
+        for _, values, extra in combination:
+            kwargs.update(values)
+            case_marks.extend(extra)
+        cases.append(("-".join(c[0] for c in combination), kwargs, case_marks))
+    return cases
+
+
+def describe(exc):
+    message = str(exc)
+    return "{}: {}".format(type(exc).__name__, message) if message else type(exc).__name__
+
+
+def repr_id(value):
+    if isinstance(value, (str, int, float, bool)) or value is None:
+        return str(value)
+    return type(value).__name__
+
+
+# -- running -----------------------------------------------------------------
+
+
+def run_unittest(cls, method):
+    result = unittest.TestResult()
+    cls(method).run(result)
+    if result.skipped:
+        return "skipped", result.skipped[0][1], None
+    if result.expectedFailures:
+        return "xfail", None, result.expectedFailures[0][1]
+    if result.unexpectedSuccesses:
+        return "xpass", None, None
+    if result.errors:
+        return "failed", None, result.errors[0][1]
+    if result.failures:
+        return "failed", None, result.failures[0][1]
+    return "passed", None, None
+
+
+def format_failure():
+    """The current exception's traceback without the harness's own frames."""
+    exc_type, exc, tb = sys.exc_info()
+    while tb is not None and tb.tb_frame.f_globals is globals():
+        tb = tb.tb_next
+    return "".join(traceback.format_exception(exc_type, exc, tb))
+
+
+def call(func, kwargs):
+    value = func(**kwargs)
+    if inspect.iscoroutine(value):
+        import asyncio
+
+        value = asyncio.run(value)
+    return value
+
+
+def call_hook(hook, func):
+    """Call a setup_/teardown_ hook, passing the test only if it takes it."""
+    if arguments(hook):
+        hook(func)
+    else:
+        hook()
+
+
+def run_case(module, cls, method_name, kwargs, marks, fixtures, exceptions):
+    """Run one call of the test; returns (outcome, reason, longrepr)."""
+    skip_exc, fail_exc, xfail_exc = exceptions
+    reason = skip_reason(marks, module)
+    if reason is not None:
+        return "skipped", reason, None
+    expected = xfail_mark(marks, module)
+
+    instance = cls() if cls is not None else None
+    func = getattr(instance if instance is not None else module, method_name)
+    request = FixtureRequest(getattr(func, "__func__", func), module, instance)
+    definitions = dict(fixtures)
+    if cls is not None:
+        collect_fixtures(cls, definitions, instance)
+    resolver = FixtureResolver(definitions, request)
+
+    setup = getattr(instance, "setup_method", None) if instance is not None else getattr(
+        module, "setup_function", None
+    )
+    teardown = getattr(instance, "teardown_method", None) if instance is not None else getattr(
+        module, "teardown_function", None
+    )
+    try:
+        for name, (_, autouse) in definitions.items():
+            if autouse:
+                resolver.get(name)
+        values = {}
+        for name in arguments(func):
+            values[name] = kwargs[name] if name in kwargs else resolver.get(name)
+        if setup is not None:
+            call_hook(setup, func)
+    except skip_exc as exc:
+        resolver.teardown()
+        return "skipped", str(exc), None
+    except Exception:
+        longrepr = format_failure()
+        resolver.teardown()
+        return "error", "error in fixture setup", longrepr
+
+    outcome, reason, longrepr = "passed", None, None
+    try:
+        call(func, values)
+    except skip_exc as exc:
+        outcome, reason = "skipped", str(exc)
+    except xfail_exc as exc:
+        outcome, reason = "xfail", str(exc)
+    except Exception as exc:
+        raises = expected.kwargs.get("raises") if expected is not None else None
+        if expected is not None and (raises is None or isinstance(exc, raises)):
+            outcome, reason = "xfail", expected.kwargs.get("reason", "")
+        else:
+            outcome = "failed"
+            reason = str(exc) if isinstance(exc, fail_exc) else describe(exc)
+            longrepr = format_failure()
+    else:
+        if expected is not None:
+            if expected.kwargs.get("strict", False):
+                outcome, reason = "failed", "[XPASS(strict)] " + expected.kwargs.get("reason", "")
+            else:
+                outcome = "xpass"
+
+    if teardown is not None:
+        try:
+            call_hook(teardown, func)
+        except Exception:
+            if outcome in ("passed", "xpass"):
+                outcome, reason, longrepr = "error", "error in teardown", format_failure()
+    errors = resolver.teardown()
+    if errors and outcome in ("passed", "xpass"):
+        outcome, reason, longrepr = "error", "error in fixture teardown", errors[0]
+    return outcome, reason, longrepr
+
+
+# Outcome precedence when several parametrized cases run as one item.
+RANK = ["error", "failed", "xpass", "passed", "xfail", "skipped"]
+
+
+def run(test_file, test_name):
+    test_file = os.path.abspath(test_file)
+    sys.path.insert(0, os.path.dirname(test_file))
+    if os.getcwd() not in sys.path:
+        sys.path.insert(1, os.getcwd())
+    pytest = install_pytest_shim()
+    exceptions = outcome_exceptions(pytest)
+    try:
+        fixtures = conftest_fixtures(test_file)
+        module_name = os.path.splitext(os.path.basename(test_file))[0]
+        module = load_module(test_file, module_name)
+    except exceptions[0] as exc:
+        return {"outcome": "skipped", "message": str(exc), "cases": []}
+    except Exception:
+        return {"outcome": "error", "message": "error importing test module", "longrepr": format_failure(), "cases": []}
+    collect_fixtures(module, fixtures)
+
+    class_name, _, method_name = test_name.rpartition("::")
+    cls = getattr(module, class_name) if class_name else None
+    if cls is not None and isinstance(cls, type) and issubclass(cls, unittest.TestCase):
+        outcome, reason, longrepr = run_unittest(cls, method_name)
+        return {"outcome": outcome, "message": reason, "longrepr": longrepr, "cases": []}
+
+    func = getattr(cls if cls is not None else module, method_name)
+    base_marks = marks_of(module, cls, func)
+    cases = []
+    for case_id, kwargs, case_marks in parametrize_cases(base_marks):
+        start = time.perf_counter()
+        outcome, reason, longrepr = run_case(
+            module, cls, method_name, kwargs, base_marks + case_marks, fixtures, exceptions
+        )
+        cases.append(
+            {
+                "id": case_id,
+                "outcome": outcome,
+                "message": reason,
+                "longrepr": longrepr,
+                "duration_ms": int((time.perf_counter() - start) * 1000),
+            }
+        )
+    worst = min(cases, key=lambda case: RANK.index(case["outcome"]))
+    return {
+        "outcome": worst["outcome"],
+        "message": worst["message"],
+        "longrepr": worst["longrepr"],
+        "case": worst["id"] if len(cases) > 1 else None,
+        "cases": cases if len(cases) > 1 else [],
+    }
+
+
+def start_coverage():
+    """Measure the test with coverage.py under `pybun test --coverage`."""
+    data_file = os.environ.get("PYBUN_COVERAGE_DATA")
+    if not data_file:
+        return None
+    import coverage
+
+    cov = coverage.Coverage(data_file=data_file, data_suffix=True)
+    cov.start()
+    return cov
+
+
+def main():
+    result_file, test_file, test_name = sys.argv[1:4]
+    start = time.perf_counter()
+    cov = start_coverage()
+    try:
+        result = run(test_file, test_name)
+    except Exception:
+        result = {"outcome": "error", "message": "harness error", "longrepr": traceback.format_exc(), "cases": []}
+    if cov is not None:
+        cov.stop()
+        cov.save()
+    result["duration_ms"] = int((time.perf_counter() - start) * 1000)
+    sys.stdout.flush()
+    sys.stderr.flush()
+    with open(result_file, "w", encoding="utf-8") as handle:
+        json.dump(result, handle)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,550 @@
+//! Per-project history of `pybun test` runs.
+//!
+//! Every completed run appends one record to
+//! `<cache root>/test-history/<project key>.jsonl`: when it ran, which
+//! backend ran it, how long the suite took, and each test's outcome and
+//! duration keyed by its node id relative to the project root. The native
+//! backend reports every test; the pytest backend is read back from a JUnit
+//! XML report (see [`crate::test_report`]); unittest runs only record the suite. The newest [`MAX_RUNS`]
+//! runs are kept. `pybun test --history` turns the file into a
+//! [`HistoryReport`]. Set `PYBUN_TEST_HISTORY=0` to stop recording.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Directory under the cache root holding one history file per project.
+pub const HISTORY_DIR: &str = "test-history";
+
+/// Set to `0`/`false`/`off`/`no` to disable recording.
+pub const HISTORY_ENV: &str = "PYBUN_TEST_HISTORY";
+
+/// Runs kept per project.
+pub const MAX_RUNS: usize = 200;
+
+/// A test whose latest duration is this much slower (or faster) than its
+/// median over earlier runs is reported as `slower` (or `faster`).
+pub const TREND_THRESHOLD_PERCENT: f64 = 20.0;
+
+/// Failures within the window before a failing test counts as chronic.
+pub const CHRONIC_MIN_FAILURES: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum TestHistoryError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("unsupported history export format for {0} (use a .json or .csv file)")]
+    UnsupportedExport(PathBuf),
+}
+
+pub type Result<T> = std::result::Result<T, TestHistoryError>;
+
+/// One recorded `pybun test` run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Unix timestamp (seconds).
+    pub timestamp: u64,
+    /// Backend that ran the suite (`pybun`, `pytest`, `unittest`).
+    pub backend: String,
+    pub duration_ms: u64,
+    /// Whether the run as a whole passed.
+    pub passed: bool,
+    /// Per-test results; empty when the backend does not report them.
+    #[serde(default)]
+    pub tests: Vec<TestRecord>,
+}
+
+impl RunRecord {
+    /// Record for a run that finished just now.
+    pub fn now(backend: &str, duration_ms: u64, passed: bool, tests: Vec<TestRecord>) -> Self {
+        Self {
+            timestamp: unix_now(),
+            backend: backend.to_string(),
+            duration_ms,
+            passed,
+            tests,
+        }
+    }
+
+    fn failed_tests(&self) -> usize {
+        self.tests.iter().filter(|t| t.failed()).count()
+    }
+}
+
+/// Outcome of a single test in one run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestRecord {
+    /// Node id relative to the project root (`tests/test_api.py::test_get`).
+    pub id: String,
+    /// `passed`, `failed`, `error`, `timeout`, `skipped`, `xfail` or `xpass`.
+    pub outcome: String,
+    pub duration_ms: u64,
+}
+
+impl TestRecord {
+    pub fn new(id: impl Into<String>, outcome: impl Into<String>, duration_ms: u64) -> Self {
+        Self {
+            id: id.into(),
+            outcome: outcome.into(),
+            duration_ms,
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome.as_str(), "failed" | "error" | "timeout")
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Whether runs should be recorded (see [`HISTORY_ENV`]).
+pub fn enabled() -> bool {
+    !matches!(
+        std::env::var(HISTORY_ENV)
+            .map(|v| v.trim().to_ascii_lowercase())
+            .as_deref(),
+        Ok("0" | "false" | "off" | "no")
+    )
+}
+
+/// `path` as a node id prefix relative to `root`, with `/` separators.
+pub fn relative_id(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Short key naming a project's files under the cache root.
+pub fn project_key(project_root: &Path) -> String {
+    hex::encode(&Sha256::digest(project_root.to_string_lossy().as_bytes())[..8])
+}
+
+#[derive(Debug, Clone)]
+pub struct TestHistory {
+    path: PathBuf,
+}
+
+impl TestHistory {
+    /// History of the project rooted at `project_root`, stored under the
+    /// cache rooted at `cache_root`.
+    pub fn for_project(cache_root: &Path, project_root: &Path) -> Self {
+        Self::with_path(
+            cache_root
+                .join(HISTORY_DIR)
+                .join(format!("{}.jsonl", project_key(project_root))),
+        )
+    }
+
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Recorded runs, oldest first. Unreadable lines are skipped.
+    pub fn load(&self) -> Vec<RunRecord> {
+        fs::read_to_string(&self.path)
+            .map(|text| {
+                text.lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Append `run`, dropping the oldest runs beyond [`MAX_RUNS`].
+    pub fn record(&self, run: &RunRecord) -> Result<()> {
+        let io = |source| TestHistoryError::Io {
+            path: self.path.clone(),
+            source,
+        };
+        let parent = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(parent).map_err(io)?;
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("jsonl.lock"))
+            .map_err(io)?;
+        fs2::FileExt::lock_exclusive(&lock).map_err(io)?;
+
+        let mut runs = self.load();
+        runs.push(run.clone());
+        let skip = runs.len().saturating_sub(MAX_RUNS);
+        let mut data = String::new();
+        for run in &runs[skip..] {
+            data.push_str(&serde_json::to_string(run).map_err(|e| io(e.into()))?);
+            data.push('\n');
+        }
+        let mut tmp = tempfile::NamedTempFile::new_in(parent).map_err(io)?;
+        tmp.write_all(data.as_bytes()).map_err(io)?;
+        tmp.persist(&self.path).map_err(|e| io(e.error))?;
+        Ok(())
+    }
+}
+
+/// Suite-level view of one run in a report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub timestamp: u64,
+    pub backend: String,
+    pub duration_ms: u64,
+    pub passed: bool,
+    pub tests: usize,
+    pub failed_tests: usize,
+}
+
+/// Aggregate history of one test over the report window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestStats {
+    pub id: String,
+    /// Runs in the window that included the test.
+    pub runs: usize,
+    pub failures: usize,
+    pub fail_rate: f64,
+    /// Consecutive failing runs ending with the latest one.
+    pub failure_streak: usize,
+    pub last_outcome: String,
+    pub last_duration_ms: u64,
+    /// Median duration over the earlier runs (excluding the latest).
+    pub median_duration_ms: Option<u64>,
+    /// Latest duration relative to the earlier median.
+    pub change_percent: Option<f64>,
+    /// `slower`, `faster`, `stable`, or `new` without earlier runs.
+    pub trend: &'static str,
+    /// `newly_failing`, `chronically_failing`, `failing` or `passing`.
+    pub status: &'static str,
+    /// Durations, oldest first.
+    pub durations_ms: Vec<u64>,
+}
+
+/// What `pybun test --history` reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryReport {
+    /// Runs in the window, oldest first.
+    pub runs: Vec<RunSummary>,
+    /// Tests of the latest run, slowest first.
+    pub slowest: Vec<TestStats>,
+    /// Failing in the latest run after passing the previous time (or a first run).
+    pub newly_failing: Vec<TestStats>,
+    /// Failing in the latest run and in at least half of the window, with at
+    /// least [`CHRONIC_MIN_FAILURES`] failures.
+    pub chronically_failing: Vec<TestStats>,
+    /// Every test seen in the window, by id.
+    pub tests: Vec<TestStats>,
+}
+
+fn median(values: &mut [u64]) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    })
+}
+
+/// Build a report over the newest `window` runs, listing the `top` slowest tests.
+pub fn report(runs: &[RunRecord], window: usize, top: usize) -> HistoryReport {
+    let runs = &runs[runs.len().saturating_sub(window.max(1))..];
+    let mut by_id: BTreeMap<&str, Vec<&TestRecord>> = BTreeMap::new();
+    for run in runs {
+        for test in &run.tests {
+            by_id.entry(&test.id).or_default().push(test);
+        }
+    }
+
+    let latest: BTreeMap<&str, &TestRecord> = runs
+        .last()
+        .map(|run| run.tests.iter().map(|t| (t.id.as_str(), t)).collect())
+        .unwrap_or_default();
+
+    let tests: Vec<TestStats> = by_id
+        .into_iter()
+        .map(|(id, records)| {
+            let last = records.last().expect("at least one record");
+            let failures = records.iter().filter(|r| r.failed()).count();
+            let failure_streak = records.iter().rev().take_while(|r| r.failed()).count();
+            let durations_ms: Vec<u64> = records.iter().map(|r| r.duration_ms).collect();
+            let mut earlier = durations_ms[..durations_ms.len() - 1].to_vec();
+            let median_duration_ms = median(&mut earlier);
+            let change_percent = median_duration_ms
+                .filter(|m| *m > 0)
+                .map(|m| ((last.duration_ms as f64 - m as f64) / m as f64 * 1000.0).round() / 10.0);
+            let trend = match (median_duration_ms, change_percent) {
+                (None, _) => "new",
+                (_, Some(p)) if p > TREND_THRESHOLD_PERCENT => "slower",
+                (_, Some(p)) if p < -TREND_THRESHOLD_PERCENT => "faster",
+                _ => "stable",
+            };
+            let in_latest = latest.contains_key(id);
+            let status = if !in_latest || !last.failed() {
+                "passing"
+            } else if failure_streak == 1 {
+                "newly_failing"
+            } else if failures >= CHRONIC_MIN_FAILURES && failures * 2 >= records.len() {
+                "chronically_failing"
+            } else {
+                "failing"
+            };
+            TestStats {
+                id: id.to_string(),
+                runs: records.len(),
+                failures,
+                fail_rate: (failures as f64 / records.len() as f64 * 1000.0).round() / 1000.0,
+                failure_streak,
+                last_outcome: last.outcome.clone(),
+                last_duration_ms: last.duration_ms,
+                median_duration_ms,
+                change_percent,
+                trend,
+                status,
+                durations_ms,
+            }
+        })
+        .collect();
+
+    let mut slowest: Vec<TestStats> = tests
+        .iter()
+        .filter(|t| latest.contains_key(t.id.as_str()))
+        .cloned()
+        .collect();
+    slowest.sort_by_key(|t| std::cmp::Reverse(t.last_duration_ms));
+    slowest.truncate(top);
+    let with_status = |status| {
+        tests
+            .iter()
+            .filter(|t| t.status == status)
+            .cloned()
+            .collect()
+    };
+
+    HistoryReport {
+        runs: runs
+            .iter()
+            .map(|run| RunSummary {
+                timestamp: run.timestamp,
+                backend: run.backend.clone(),
+                duration_ms: run.duration_ms,
+                passed: run.passed,
+                tests: run.tests.len(),
+                failed_tests: run.failed_tests(),
+            })
+            .collect(),
+        slowest,
+        newly_failing: with_status("newly_failing"),
+        chronically_failing: with_status("chronically_failing"),
+        tests,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl HistoryReport {
+    /// One row per test with its aggregate stats.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "test_id,runs,failures,fail_rate,failure_streak,last_outcome,last_duration_ms,median_duration_ms,change_percent,trend,status\n",
+        );
+        for t in &self.tests {
+            let optional = |v: Option<String>| v.unwrap_or_default();
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{}\n",
+                csv_field(&t.id),
+                t.runs,
+                t.failures,
+                t.fail_rate,
+                t.failure_streak,
+                t.last_outcome,
+                t.last_duration_ms,
+                optional(t.median_duration_ms.map(|m| m.to_string())),
+                optional(t.change_percent.map(|p| p.to_string())),
+                t.trend,
+                t.status,
+            ));
+        }
+        out
+    }
+
+    /// Write the report to `path`: `.csv` writes [`Self::to_csv`], `.json`
+    /// the full report.
+    pub fn export(&self, path: &Path) -> Result<()> {
+        let data = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => self.to_csv(),
+            Some(ext) if ext.eq_ignore_ascii_case("json") => {
+                serde_json::to_string_pretty(self).expect("report serializes")
+            }
+            _ => return Err(TestHistoryError::UnsupportedExport(path.to_path_buf())),
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|source| TestHistoryError::Io {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+        fs::write(path, data).map_err(|source| TestHistoryError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn run(timestamp: u64, tests: &[(&str, &str, u64)]) -> RunRecord {
+        let tests: Vec<TestRecord> = tests
+            .iter()
+            .map(|(id, outcome, ms)| TestRecord::new(*id, *outcome, *ms))
+            .collect();
+        RunRecord {
+            timestamp,
+            backend: "pybun".to_string(),
+            duration_ms: tests.iter().map(|t| t.duration_ms).sum(),
+            passed: tests.iter().all(|t| !t.failed()),
+            tests,
+        }
+    }
+
+    #[test]
+    fn record_keeps_newest_runs() {
+        let temp = tempdir().unwrap();
+        let history = TestHistory::for_project(temp.path(), Path::new("/work/app"));
+        for i in 0..(MAX_RUNS as u64 + 3) {
+            history
+                .record(&run(i, &[("t.py::test_a", "passed", i)]))
+                .unwrap();
+        }
+        let runs = history.load();
+        assert_eq!(runs.len(), MAX_RUNS);
+        assert_eq!(runs[0].timestamp, 3);
+        assert!(history.path().starts_with(temp.path().join(HISTORY_DIR)));
+        assert_ne!(
+            history.path(),
+            TestHistory::for_project(temp.path(), Path::new("/work/other")).path()
+        );
+    }
+
+    #[test]
+    fn report_classifies_failures_and_trends() {
+        let runs = vec![
+            run(
+                1,
+                &[
+                    ("a", "failed", 100),
+                    ("b", "passed", 10),
+                    ("c", "passed", 50),
+                ],
+            ),
+            run(
+                2,
+                &[
+                    ("a", "failed", 100),
+                    ("b", "passed", 10),
+                    ("c", "passed", 50),
+                ],
+            ),
+            run(
+                3,
+                &[
+                    ("a", "error", 100),
+                    ("b", "passed", 12),
+                    ("c", "passed", 50),
+                ],
+            ),
+            run(
+                4,
+                &[
+                    ("a", "failed", 90),
+                    ("b", "failed", 30),
+                    ("c", "passed", 51),
+                ],
+            ),
+        ];
+        let report = report(&runs, 20, 2);
+
+        assert_eq!(report.runs.len(), 4);
+        assert_eq!(report.runs[3].failed_tests, 2);
+        let ids = |stats: &[TestStats]| stats.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&report.newly_failing), ["b"]);
+        assert_eq!(ids(&report.chronically_failing), ["a"]);
+        assert_eq!(ids(&report.slowest), ["a", "c"]);
+
+        let b = report.tests.iter().find(|t| t.id == "b").unwrap();
+        assert_eq!(b.median_duration_ms, Some(10));
+        assert_eq!(b.change_percent, Some(200.0));
+        assert_eq!(b.trend, "slower");
+        let c = report.tests.iter().find(|t| t.id == "c").unwrap();
+        assert_eq!((c.trend, c.status), ("stable", "passing"));
+
+        // A narrower window forgets the older failures.
+        let recent = super::report(&runs, 2, 10);
+        assert_eq!(recent.runs.len(), 2);
+        assert!(recent.chronically_failing.is_empty());
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("test_id,runs,failures"));
+        assert!(csv.contains("\nb,4,1,0.25,1,failed,30,10,200,slower,newly_failing\n"));
+    }
+
+    #[test]
+    fn export_picks_format_from_extension() {
+        let temp = tempdir().unwrap();
+        let report = report(&[run(1, &[("x, y", "passed", 5)])], 20, 10);
+        report.export(&temp.path().join("out/history.csv")).unwrap();
+        let csv = fs::read_to_string(temp.path().join("out/history.csv")).unwrap();
+        assert!(csv.contains("\"x, y\",1,0,0,0,passed,5,,,new,passing"));
+        report.export(&temp.path().join("history.json")).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&fs::read(temp.path().join("history.json")).unwrap()).unwrap();
+        assert_eq!(json["tests"][0]["id"], "x, y");
+        assert!(matches!(
+            report.export(&temp.path().join("history.txt")),
+            Err(TestHistoryError::UnsupportedExport(_))
+        ));
+    }
+
+    #[test]
+    fn relative_id_strips_project_root() {
+        assert_eq!(
+            relative_id(
+                Path::new("/work/app"),
+                Path::new("/work/app/tests/test_a.py")
+            ),
+            "tests/test_a.py"
+        );
+        assert_eq!(
+            relative_id(Path::new("/work/app"), Path::new("tests/test_b.py")),
+            "tests/test_b.py"
+        );
+    }
+}
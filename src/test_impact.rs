@@ -0,0 +1,413 @@
+//! Change-based test selection (`pybun test --changed`).
+//!
+//! After every run, each test file's outcomes are stored together with a
+//! content hash of every project file the tests depend on: the test file,
+//! the `conftest.py` and `__init__.py` files between it and the project
+//! root, and the project modules it imports, followed transitively and
+//! resolved with the [`ModuleFinder`]. Only files inside the project are
+//! tracked; installed packages are not. The cache lives in
+//! `<cache root>/test-results/<project key>.json`.
+//!
+//! `--changed` then re-runs a test only when its file has not been
+//! recorded yet, one of its dependencies changed (or disappeared), or the
+//! test did not pass last time.
+
+use crate::module_finder::{ModuleFinder, ModuleFinderConfig};
+use crate::python_ast;
+use crate::test_discovery::TestItem;
+use crate::test_history::{project_key, relative_id};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory under the cache root holding one result file per project.
+pub const RESULTS_DIR: &str = "test-results";
+
+/// Recorded state of one test file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileRecord {
+    /// Content hash of each dependency, keyed by path relative to the
+    /// project root.
+    pub deps: BTreeMap<String, String>,
+    /// Last outcome of each test, keyed by name within the file.
+    pub tests: BTreeMap<String, String>,
+}
+
+/// Recorded outcomes of a project's test files.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResultCache {
+    /// Keyed by test file path relative to the project root.
+    pub files: BTreeMap<String, FileRecord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResultStore {
+    path: PathBuf,
+}
+
+impl ResultStore {
+    /// Results of the project rooted at `project_root`, stored under the
+    /// cache rooted at `cache_root`.
+    pub fn for_project(cache_root: &Path, project_root: &Path) -> Self {
+        Self::with_path(
+            cache_root
+                .join(RESULTS_DIR)
+                .join(format!("{}.json", project_key(project_root))),
+        )
+    }
+
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Recorded results; empty when nothing was recorded or the file is
+    /// unreadable.
+    pub fn load(&self) -> ResultCache {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, cache: &ResultCache) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(cache).map_err(std::io::Error::other)?;
+        fs::write(&self.path, json)
+    }
+}
+
+/// Whether an outcome needs no re-run while nothing changes.
+pub fn is_settled(outcome: &str) -> bool {
+    matches!(outcome, "passed" | "skipped" | "xfail" | "xpass")
+}
+
+/// Tests picked by [`select`] and why the others were left out.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    pub tests: Vec<TestItem>,
+    /// Changed or removed dependencies, relative to the project root.
+    pub changed_files: BTreeSet<String>,
+    /// Selected because their file was never recorded.
+    pub new: usize,
+    /// Selected because they did not pass last time.
+    pub failed: usize,
+    /// Left out: recorded as passing and nothing they depend on changed.
+    pub unchanged: usize,
+}
+
+/// Narrow `tests` to the ones affected by changes since `cache` was
+/// recorded.
+pub fn select(cache: &ResultCache, root: &Path, tests: Vec<TestItem>) -> Selection {
+    let mut hashes = FileHashes::default();
+    let mut selection = Selection::default();
+    // Per test file: `None` when unrecorded, else whether a dependency changed.
+    let mut file_state: HashMap<String, Option<bool>> = HashMap::new();
+    for test in tests {
+        let file = relative_id(root, &root.join(&test.path));
+        let record = cache.files.get(&file);
+        let state = *file_state.entry(file).or_insert_with(|| {
+            record.map(|record| {
+                let mut changed = false;
+                for (dep, hash) in &record.deps {
+                    if hashes.get(&root.join(dep)).as_deref() != Some(hash.as_str()) {
+                        selection.changed_files.insert(dep.clone());
+                        changed = true;
+                    }
+                }
+                changed
+            })
+        });
+        match (record, state) {
+            (None, _) => selection.new += 1,
+            (Some(_), Some(true)) => {}
+            (Some(record), _) => match record.tests.get(&test.name) {
+                Some(outcome) if is_settled(outcome) => {
+                    selection.unchanged += 1;
+                    continue;
+                }
+                Some(_) => selection.failed += 1,
+                None => selection.new += 1,
+            },
+        }
+        selection.tests.push(test);
+    }
+    selection
+}
+
+/// Record the outcomes of a finished run. `outcomes` maps node ids relative
+/// to `root` (parametrized cases included) to outcomes; tests without one
+/// are recorded as `fallback`, the outcome of the run as a whole.
+pub fn record(
+    cache: &mut ResultCache,
+    root: &Path,
+    tests: &[TestItem],
+    outcomes: &HashMap<String, String>,
+    fallback: &str,
+) {
+    // A parametrized test is as good as its worst case.
+    let mut by_test: HashMap<&str, &str> = HashMap::new();
+    for (id, outcome) in outcomes {
+        let base = match id.find('[') {
+            Some(index) if id.ends_with(']') => &id[..index],
+            _ => id.as_str(),
+        };
+        let entry = by_test.entry(base).or_insert(outcome.as_str());
+        if is_settled(entry) {
+            *entry = outcome;
+        }
+    }
+
+    let mut by_file: BTreeMap<String, (PathBuf, Vec<&TestItem>)> = BTreeMap::new();
+    for test in tests {
+        let path = root.join(&test.path);
+        by_file
+            .entry(relative_id(root, &path))
+            .or_insert_with(|| (path, Vec::new()))
+            .1
+            .push(test);
+    }
+
+    let mut hashes = FileHashes::default();
+    for (file, (path, file_tests)) in by_file {
+        let deps: BTreeMap<String, String> = dependencies(root, &path)
+            .into_iter()
+            .filter_map(|dep| Some((relative_id(root, &dep), hashes.get(&dep)?)))
+            .collect();
+        let record = cache.files.entry(file.clone()).or_default();
+        // Outcomes of tests that did not run are only still valid when
+        // nothing they depend on changed.
+        if record.deps != deps {
+            record.tests.clear();
+            record.deps = deps;
+        }
+        for test in file_tests {
+            let outcome = by_test
+                .get(format!("{file}::{}", test.name).as_str())
+                .copied()
+                .unwrap_or(fallback);
+            record.tests.insert(test.name.clone(), outcome.to_string());
+        }
+    }
+}
+
+/// Project files `test_file` depends on, itself included.
+pub fn dependencies(root: &Path, test_file: &Path) -> BTreeSet<PathBuf> {
+    let mut deps = BTreeSet::new();
+    let Some(test_dir) = test_file.parent() else {
+        return deps;
+    };
+    // pytest puts the test's directory (rootdir-relative imports) ahead of
+    // the project root; `src/` layouts are importable once installed.
+    let finder = python_finder(&[test_dir.to_path_buf(), root.to_path_buf(), root.join("src")]);
+
+    let mut queue = vec![test_file.to_path_buf()];
+    if let Ok(relative) = test_dir.strip_prefix(root) {
+        let mut dir = root.to_path_buf();
+        for name in ["conftest.py", "__init__.py"] {
+            queue.push(dir.join(name));
+        }
+        for component in relative.components() {
+            dir.push(component);
+            for name in ["conftest.py", "__init__.py"] {
+                queue.push(dir.join(name));
+            }
+        }
+    }
+
+    while let Some(path) = queue.pop() {
+        if !path.is_file() || !path.starts_with(root) || !deps.insert(path.clone()) {
+            continue;
+        }
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+        for import in python_ast::imports(&source) {
+            queue.extend(resolve(&finder, &path, &import));
+        }
+    }
+    deps
+}
+
+fn python_finder(search_paths: &[PathBuf]) -> ModuleFinder {
+    ModuleFinder::new(ModuleFinderConfig {
+        enabled: true,
+        search_paths: search_paths.to_vec(),
+        extensions: vec![".py".to_string()],
+        ..ModuleFinderConfig::default()
+    })
+}
+
+/// Files an import may load: the module, its parent packages and, for
+/// `from` imports, submodules named in the import list.
+fn resolve(finder: &ModuleFinder, importer: &Path, import: &python_ast::Import) -> Vec<PathBuf> {
+    let mut candidates: Vec<String> = Vec::new();
+    let parts: Vec<&str> = import.module.split('.').filter(|p| !p.is_empty()).collect();
+    for end in 1..=parts.len() {
+        candidates.push(parts[..end].join("."));
+    }
+    for name in &import.names {
+        candidates.push(
+            parts
+                .iter()
+                .copied()
+                .chain(std::iter::once(name.as_str()))
+                .collect::<Vec<_>>()
+                .join("."),
+        );
+    }
+
+    let mut files = Vec::new();
+    let relative_finder;
+    let finder = if import.level > 0 {
+        let Some(mut base) = importer.parent().map(Path::to_path_buf) else {
+            return files;
+        };
+        for _ in 1..import.level {
+            if !base.pop() {
+                return files;
+            }
+        }
+        files.push(base.join("__init__.py"));
+        relative_finder = python_finder(&[base]);
+        &relative_finder
+    } else {
+        finder
+    };
+    for candidate in candidates {
+        if let Some(module) = finder.find_module(&candidate).module {
+            files.push(module.path);
+        }
+    }
+    files
+}
+
+/// Content hashes, each file read at most once.
+#[derive(Default)]
+struct FileHashes {
+    hashes: HashMap<PathBuf, Option<String>>,
+}
+
+impl FileHashes {
+    fn get(&mut self, path: &Path) -> Option<String> {
+        self.hashes
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                fs::read(path)
+                    .ok()
+                    .map(|bytes| hex::encode(Sha256::digest(&bytes)))
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_discovery::TestItemType;
+    use tempfile::TempDir;
+
+    fn item(path: &str, name: &str) -> TestItem {
+        TestItem {
+            name: name.to_string(),
+            short_name: name.rsplit("::").next().unwrap().to_string(),
+            path: PathBuf::from(path),
+            line: 1,
+            item_type: TestItemType::Function,
+            markers: Vec::new(),
+            fixtures: Vec::new(),
+            class_name: None,
+            skipped: false,
+            skip_reason: None,
+            xfail: false,
+            parametrize: None,
+            plugin: None,
+        }
+    }
+
+    #[test]
+    fn follows_project_imports_transitively() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/app")).unwrap();
+        fs::create_dir_all(root.join("tests")).unwrap();
+        fs::write(root.join("src/app/__init__.py"), "").unwrap();
+        fs::write(root.join("src/app/api.py"), "from .db import query\n").unwrap();
+        fs::write(root.join("src/app/db.py"), "import sqlite3\n").unwrap();
+        fs::write(root.join("src/app/unused.py"), "").unwrap();
+        fs::write(root.join("conftest.py"), "").unwrap();
+        fs::write(root.join("tests/helpers.py"), "").unwrap();
+        fs::write(
+            root.join("tests/test_api.py"),
+            "import json\nfrom app import api\nimport helpers\n",
+        )
+        .unwrap();
+
+        let deps: Vec<String> = dependencies(root, &root.join("tests/test_api.py"))
+            .iter()
+            .map(|dep| relative_id(root, dep))
+            .collect();
+        assert_eq!(
+            deps,
+            [
+                "conftest.py",
+                "src/app/__init__.py",
+                "src/app/api.py",
+                "src/app/db.py",
+                "tests/helpers.py",
+                "tests/test_api.py",
+            ]
+        );
+    }
+
+    #[test]
+    fn selects_tests_affected_by_changes_or_failing_last_time() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(root.join("lib.py"), "X = 1\n").unwrap();
+        fs::write(root.join("test_a.py"), "import lib\n").unwrap();
+        fs::write(root.join("test_b.py"), "").unwrap();
+        let tests = vec![
+            item("test_a.py", "test_one"),
+            item("test_a.py", "test_two"),
+            item("test_b.py", "test_ok"),
+            item("test_b.py", "test_bad"),
+        ];
+        let outcomes: HashMap<String, String> = [
+            ("test_b.py::test_ok[1]", "passed"),
+            ("test_b.py::test_bad[1]", "passed"),
+            ("test_b.py::test_bad[2]", "failed"),
+        ]
+        .into_iter()
+        .map(|(id, outcome)| (id.to_string(), outcome.to_string()))
+        .collect();
+        let mut cache = ResultCache::default();
+        record(&mut cache, root, &tests, &outcomes, "passed");
+        assert_eq!(cache.files["test_a.py"].deps.len(), 2);
+        assert_eq!(cache.files["test_b.py"].tests["test_bad"], "failed");
+
+        let selection = select(&cache, root, tests.clone());
+        let names: Vec<&str> = selection.tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["test_bad"]);
+        assert_eq!((selection.failed, selection.unchanged), (1, 3));
+
+        fs::write(root.join("lib.py"), "X = 2\n").unwrap();
+        let selection = select(&cache, root, tests.clone());
+        let names: Vec<&str> = selection.tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["test_one", "test_two", "test_bad"]);
+        assert_eq!(
+            selection.changed_files.into_iter().collect::<Vec<_>>(),
+            ["lib.py"]
+        );
+
+        let selection = select(&ResultCache::default(), root, tests);
+        assert_eq!((selection.tests.len(), selection.new), (4, 4));
+    }
+}
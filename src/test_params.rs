@@ -0,0 +1,243 @@
+//! Per-run test parameters (`pybun test --env KEY=VALUE --fixture KEY=VALUE`).
+//!
+//! Parameterized CI jobs usually edit `conftest.py` or export variables in a
+//! wrapper script, neither of which shows up in the run's output. Defaults
+//! can live in `pyproject.toml` and are overridden key-by-key from the CLI:
+//!
+//! ```toml
+//! [tool.pybun.test.env]
+//! DATABASE_URL = "sqlite:///:memory:"
+//!
+//! [tool.pybun.test.fixtures]
+//! region = "eu-west-1"
+//! replicas = 3
+//! ```
+//!
+//! Environment variables are set only on the test subprocesses. Fixture
+//! values are exposed through the session-scoped `pybun_params` fixture
+//! (a dict), registered by a small pytest plugin that is put on the test
+//! processes' `PYTHONPATH`; the same module (`pybun_test_params.params`)
+//! is importable from unittest suites. Both sets are recorded in the JSON
+//! result so a run can be reproduced from the envelope alone.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Name of the plugin module, passed to pytest as `-p pybun_test_params`.
+pub const PLUGIN_MODULE: &str = "pybun_test_params";
+/// Environment variable carrying the fixture values (JSON object).
+pub const PARAMS_ENV: &str = "PYBUN_TEST_PARAMS";
+
+const PLUGIN_PY: &str = r#"# Generated by pybun: exposes `pybun test --fixture` values.
+import json
+import os
+
+params = json.loads(os.environ.get("PYBUN_TEST_PARAMS") or "{}")
+
+try:
+    import pytest
+except ImportError:  # unittest-only environments
+    pytest = None
+
+if pytest is not None:
+
+    @pytest.fixture(scope="session")
+    def pybun_params():
+        return dict(params)
+"#;
+
+/// `[tool.pybun.test]` defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestConfig {
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub fixtures: BTreeMap<String, Value>,
+    /// `[tool.pybun.test.plugins.NAME]` discovery plugins.
+    #[serde(default)]
+    pub plugins: BTreeMap<String, crate::test_plugins::PluginConfig>,
+    /// Make `--changed` the default; `--all` runs everything.
+    #[serde(default)]
+    pub changed: bool,
+}
+
+/// Effective parameters for one `pybun test` run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestParams {
+    pub env: BTreeMap<String, String>,
+    pub fixtures: BTreeMap<String, Value>,
+}
+
+impl TestParams {
+    /// Merge config defaults with CLI values; the CLI wins per key.
+    pub fn resolve(
+        config: &TestConfig,
+        cli_env: &[(String, String)],
+        cli_fixtures: &[(String, String)],
+    ) -> Self {
+        let mut params = TestParams {
+            env: config.env.clone(),
+            fixtures: config.fixtures.clone(),
+        };
+        params.env.extend(cli_env.iter().cloned());
+        params.fixtures.extend(
+            cli_fixtures
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone()))),
+        );
+        params
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty() && self.fixtures.is_empty()
+    }
+
+    /// `{"env": {...}, "fixtures": {...}}` for the JSON envelope.
+    pub fn to_json(&self) -> Value {
+        json!({ "env": self.env, "fixtures": self.fixtures })
+    }
+}
+
+/// Parse a `KEY=VALUE` argument.
+pub fn parse_key_value(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got `{raw}`")),
+    }
+}
+
+/// Keeps the generated `pybun_test_params` plugin alive for the test
+/// processes of one run.
+#[derive(Debug)]
+pub struct ParamsPlugin {
+    dir: TempDir,
+    params: TestParams,
+}
+
+impl ParamsPlugin {
+    /// Write the plugin for `params`, or `None` when there is nothing to inject.
+    pub fn new(params: &TestParams) -> io::Result<Option<Self>> {
+        if params.is_empty() {
+            return Ok(None);
+        }
+        let dir = tempfile::Builder::new()
+            .prefix("pybun-test-params")
+            .tempdir()?;
+        std::fs::write(dir.path().join(format!("{PLUGIN_MODULE}.py")), PLUGIN_PY)?;
+        Ok(Some(Self {
+            dir,
+            params: params.clone(),
+        }))
+    }
+
+    /// pytest arguments that load the plugin.
+    pub fn pytest_args(&self) -> [&'static str; 2] {
+        ["-p", PLUGIN_MODULE]
+    }
+
+    /// Environment for a test process: the `--env` values, the fixture
+    /// values, and the plugin directory prepended to the `PYTHONPATH` from
+    /// `--env`, `python_path`, or the inherited environment, in that order.
+    pub fn env(&self, python_path: Option<&OsStr>) -> Vec<(String, OsString)> {
+        let mut paths = vec![self.dir.path().to_path_buf()];
+        let existing = self
+            .params
+            .env
+            .get("PYTHONPATH")
+            .map(OsString::from)
+            .or_else(|| python_path.map(OsStr::to_os_string))
+            .or_else(|| std::env::var_os("PYTHONPATH"));
+        if let Some(existing) = existing.filter(|p| !p.is_empty()) {
+            paths.extend(std::env::split_paths(&existing));
+        }
+        let mut env: Vec<(String, OsString)> = self
+            .params
+            .env
+            .iter()
+            .filter(|(key, _)| *key != "PYTHONPATH")
+            .map(|(key, value)| (key.clone(), OsString::from(value)))
+            .collect();
+        env.push((
+            "PYTHONPATH".to_string(),
+            std::env::join_paths(paths).unwrap_or_default(),
+        ));
+        env.push((
+            PARAMS_ENV.to_string(),
+            OsString::from(json!(self.params.fixtures).to_string()),
+        ));
+        env
+    }
+
+    /// Apply [`Self::env`] to `cmd`, keeping any `PYTHONPATH` already set on it.
+    pub fn apply(&self, cmd: &mut Command) {
+        let python_path = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "PYTHONPATH")
+            .and_then(|(_, value)| value.map(OsStr::to_os_string));
+        for (key, value) in self.env(python_path.as_deref()) {
+            cmd.env(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn cli_values_override_config_per_key() {
+        let config: TestConfig = toml::from_str(
+            "[env]\nMODE = \"config\"\nKEEP = \"1\"\n[fixtures]\nreplicas = 3\nregion = \"us\"\n",
+        )
+        .unwrap();
+        let params = TestParams::resolve(
+            &config,
+            &[pair("MODE", "cli")],
+            &[pair("region", "eu-west-1")],
+        );
+        assert_eq!(params.env["MODE"], "cli");
+        assert_eq!(params.env["KEEP"], "1");
+        assert_eq!(params.fixtures["replicas"], json!(3));
+        assert_eq!(params.fixtures["region"], json!("eu-west-1"));
+    }
+
+    #[test]
+    fn key_value_arguments_require_a_key() {
+        assert_eq!(parse_key_value("A=b=c").unwrap(), pair("A", "b=c"));
+        assert_eq!(parse_key_value("EMPTY=").unwrap(), pair("EMPTY", ""));
+        assert!(parse_key_value("=value").is_err());
+        assert!(parse_key_value("novalue").is_err());
+    }
+
+    #[test]
+    fn plugin_env_carries_params_and_chains_python_path() {
+        let params = TestParams::resolve(
+            &TestConfig::default(),
+            &[pair("MODE", "ci")],
+            &[pair("region", "eu")],
+        );
+        let plugin = ParamsPlugin::new(&params).unwrap().unwrap();
+        let env: BTreeMap<String, OsString> = plugin
+            .env(Some(OsStr::new("/existing")))
+            .into_iter()
+            .collect();
+        assert_eq!(env["MODE"], "ci");
+        assert_eq!(env[PARAMS_ENV], r#"{"region":"eu"}"#);
+        let paths: Vec<_> = std::env::split_paths(&env["PYTHONPATH"]).collect();
+        assert!(paths[0].join("pybun_test_params.py").is_file());
+        assert_eq!(paths[1], std::path::PathBuf::from("/existing"));
+
+        assert!(ParamsPlugin::new(&TestParams::default()).unwrap().is_none());
+    }
+}
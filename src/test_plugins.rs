@@ -0,0 +1,701 @@
+//! Discovery plugins for test frameworks other than pytest/unittest.
+//!
+//! A [`DiscoveryPlugin`] claims test files by name pattern, parses the items
+//! they declare, and builds the interpreter arguments that run a selection
+//! of those items (its execution adapter). Claimed files bypass the Python
+//! parser in [`crate::test_discovery`]; their items are listed, filtered and
+//! sharded with the rest, and `pybun test` runs each plugin's share through
+//! its adapter and reports it next to the main backend's results.
+//!
+//! Plugins are enabled per project:
+//!
+//! ```toml
+//! [tool.pybun.test.plugins.behave]            # Gherkin `.feature` files
+//!
+//! [tool.pybun.test.plugins.ward]
+//! file-patterns = ["test_*.py"]
+//!
+//! [tool.pybun.test.plugins.nose2]             # any other framework
+//! file-patterns = ["test_*.py"]
+//! item-pattern = '^\s*def (test_\w+)'
+//! command = ["-m", "nose2"]
+//! ```
+//!
+//! `behave` and `ward` are built in. Any other name defines a command plugin:
+//! `item-pattern` is a regex whose first capture group (or whole match) names
+//! an item, defaulting to one item per file, and `{files}` in `command` expands
+//! to the selected files.
+
+use crate::test_discovery::{
+    ParametrizeInfo, PytestMarker, TestItem, TestItemType, matches_pattern,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Names of the plugins that need no `command`.
+pub const BUILTIN_PLUGINS: &[&str] = &["behave", "ward"];
+
+/// One `[tool.pybun.test.plugins.NAME]` table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PluginConfig {
+    /// File name patterns (`*` wildcard) the plugin claims.
+    #[serde(default)]
+    pub file_patterns: Option<Vec<String>>,
+    /// Regex naming the items of a claimed file (command plugins only).
+    #[serde(default)]
+    pub item_pattern: Option<String>,
+    /// Interpreter arguments running the plugin's tests (command plugins only).
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+}
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("test plugin '{plugin}' requires `{field}`")]
+    MissingField { plugin: String, field: &'static str },
+    #[error("`{field}` cannot be set for the built-in test plugin '{plugin}'")]
+    UnsupportedField { plugin: String, field: &'static str },
+    #[error("invalid item-pattern for test plugin '{plugin}': {source}")]
+    InvalidPattern {
+        plugin: String,
+        #[source]
+        source: regex::Error,
+    },
+}
+
+/// Discovery and execution support for one test framework.
+pub trait DiscoveryPlugin: Send + Sync {
+    /// Name recorded on the plugin's items and in the test report.
+    fn name(&self) -> &str;
+
+    /// File name patterns (`*` wildcard) of the files this plugin owns.
+    fn file_patterns(&self) -> &[String];
+
+    /// Test items declared in a claimed file.
+    fn parse_file(&self, path: &Path, content: &str) -> Vec<TestItem>;
+
+    /// Interpreter arguments that run `items`, all of which came from this
+    /// plugin's [`Self::parse_file`].
+    fn command(&self, items: &[TestItem]) -> Vec<String>;
+
+    /// Whether this plugin owns `path`.
+    fn claims(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        self.file_patterns()
+            .iter()
+            .any(|pattern| matches_pattern(name, pattern))
+    }
+}
+
+/// The plugins enabled for a run, in registration order. The first plugin
+/// claiming a file owns it.
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Box<dyn DiscoveryPlugin>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the registry described by `[tool.pybun.test.plugins]`.
+    pub fn from_config(config: &BTreeMap<String, PluginConfig>) -> Result<Self, PluginError> {
+        let mut registry = Self::new();
+        for (name, plugin) in config {
+            if BUILTIN_PLUGINS.contains(&name.as_str()) {
+                if plugin.item_pattern.is_some() {
+                    return Err(PluginError::UnsupportedField {
+                        plugin: name.clone(),
+                        field: "item-pattern",
+                    });
+                }
+                if plugin.command.is_some() {
+                    return Err(PluginError::UnsupportedField {
+                        plugin: name.clone(),
+                        field: "command",
+                    });
+                }
+            }
+            let patterns = plugin.file_patterns.clone();
+            match name.as_str() {
+                "behave" => registry.register(Box::new(BehavePlugin::new(patterns))),
+                "ward" => registry.register(Box::new(WardPlugin::new(patterns))),
+                _ => registry.register(Box::new(CommandPlugin::from_config(name, plugin)?)),
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, plugin: Box<dyn DiscoveryPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|p| p.name()).collect()
+    }
+
+    /// The plugin owning `path`, if any.
+    pub fn plugin_for(&self, path: &Path) -> Option<&dyn DiscoveryPlugin> {
+        self.plugins
+            .iter()
+            .find(|plugin| plugin.claims(path))
+            .map(|plugin| plugin.as_ref())
+    }
+
+    /// Group the plugin-owned, non-skipped `items` by plugin, in registration
+    /// order. Plugins without selected items are left out.
+    pub fn batches(&self, items: &[TestItem]) -> Vec<PluginBatch<'_>> {
+        self.plugins
+            .iter()
+            .filter_map(|plugin| {
+                let items: Vec<TestItem> = items
+                    .iter()
+                    .filter(|t| !t.skipped && t.plugin.as_deref() == Some(plugin.name()))
+                    .cloned()
+                    .collect();
+                (!items.is_empty()).then(|| PluginBatch {
+                    plugin: plugin.as_ref(),
+                    items,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The selected items of one plugin.
+pub struct PluginBatch<'a> {
+    pub plugin: &'a dyn DiscoveryPlugin,
+    pub items: Vec<TestItem>,
+}
+
+impl PluginBatch<'_> {
+    /// Interpreter arguments running this batch.
+    pub fn command(&self) -> Vec<String> {
+        self.plugin.command(&self.items)
+    }
+}
+
+/// behave: scenarios of Gherkin `.feature` files, run by `file:line`.
+pub struct BehavePlugin {
+    file_patterns: Vec<String>,
+}
+
+impl BehavePlugin {
+    pub fn new(file_patterns: Option<Vec<String>>) -> Self {
+        Self {
+            file_patterns: file_patterns.unwrap_or_else(|| vec!["*.feature".to_string()]),
+        }
+    }
+}
+
+impl DiscoveryPlugin for BehavePlugin {
+    fn name(&self) -> &str {
+        "behave"
+    }
+
+    fn file_patterns(&self) -> &[String] {
+        &self.file_patterns
+    }
+
+    fn parse_file(&self, path: &Path, content: &str) -> Vec<TestItem> {
+        let mut items: Vec<TestItem> = Vec::new();
+        let mut feature: Option<String> = None;
+        let mut feature_tags: Vec<String> = Vec::new();
+        let mut pending_tags: Vec<String> = Vec::new();
+        // Index of the scenario outline whose `Examples:` rows are being counted,
+        // and whether the current table's header row has been seen.
+        let mut outline: Option<usize> = None;
+        let mut in_examples = false;
+        let mut header_seen = false;
+        let mut doc_string: Option<&str> = None;
+
+        for (index, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if let Some(fence) = doc_string {
+                if trimmed.starts_with(fence) {
+                    doc_string = None;
+                }
+                continue;
+            }
+            if trimmed.starts_with("\"\"\"") || trimmed.starts_with("```") {
+                doc_string = Some(&trimmed[..3]);
+                continue;
+            }
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if trimmed.starts_with('@') {
+                pending_tags.extend(
+                    trimmed
+                        .split_whitespace()
+                        .filter_map(|tag| tag.strip_prefix('@'))
+                        .map(str::to_string),
+                );
+                continue;
+            }
+            if trimmed.starts_with('|') {
+                if in_examples && let Some(item) = outline.and_then(|i| items.get_mut(i)) {
+                    let cells: Vec<String> = trimmed
+                        .trim_matches('|')
+                        .split('|')
+                        .map(|cell| cell.trim().to_string())
+                        .collect();
+                    let info = item.parametrize.get_or_insert(ParametrizeInfo {
+                        params: Vec::new(),
+                        case_count: 0,
+                    });
+                    if header_seen {
+                        info.case_count += 1;
+                    } else {
+                        header_seen = true;
+                        if info.params.is_empty() {
+                            info.params = cells;
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let Some((keyword, title)) = trimmed.split_once(':') else {
+                continue;
+            };
+            let title = title.trim().to_string();
+            match keyword.trim() {
+                "Feature" => {
+                    feature = Some(title);
+                    feature_tags = std::mem::take(&mut pending_tags);
+                    outline = None;
+                    in_examples = false;
+                }
+                "Scenario" | "Example" | "Scenario Outline" | "Scenario Template" => {
+                    let is_outline = keyword.trim().starts_with("Scenario ");
+                    let mut tags = feature_tags.clone();
+                    tags.append(&mut pending_tags);
+                    let name = match &feature {
+                        Some(feature) => format!("{}::{}", feature, title),
+                        None => title.clone(),
+                    };
+                    items.push(TestItem {
+                        name,
+                        short_name: title,
+                        path: path.to_path_buf(),
+                        line: index + 1,
+                        item_type: if feature.is_some() {
+                            TestItemType::Method
+                        } else {
+                            TestItemType::Function
+                        },
+                        skipped: tags.iter().any(|tag| tag == "skip"),
+                        skip_reason: None,
+                        xfail: false,
+                        markers: tags
+                            .into_iter()
+                            .map(|tag| PytestMarker {
+                                name: tag,
+                                args: Vec::new(),
+                                kwargs: HashMap::new(),
+                            })
+                            .collect(),
+                        fixtures: Vec::new(),
+                        class_name: feature.clone(),
+                        parametrize: None,
+                        plugin: Some(self.name().to_string()),
+                    });
+                    outline = is_outline.then(|| items.len() - 1);
+                    in_examples = false;
+                }
+                "Examples" | "Scenarios" => {
+                    pending_tags.clear();
+                    in_examples = true;
+                    header_seen = false;
+                }
+                "Background" | "Rule" => {
+                    pending_tags.clear();
+                    outline = None;
+                    in_examples = false;
+                }
+                _ => {}
+            }
+        }
+        items
+    }
+
+    fn command(&self, items: &[TestItem]) -> Vec<String> {
+        let mut args = vec!["-m".to_string(), "behave".to_string()];
+        args.extend(
+            items
+                .iter()
+                .map(|item| format!("{}:{}", item.path.display(), item.line)),
+        );
+        args
+    }
+}
+
+/// ward: `@test("description")` functions, run file by file.
+pub struct WardPlugin {
+    file_patterns: Vec<String>,
+}
+
+impl WardPlugin {
+    pub fn new(file_patterns: Option<Vec<String>>) -> Self {
+        Self {
+            file_patterns: file_patterns
+                .unwrap_or_else(|| vec!["test_*.py".to_string(), "*_test.py".to_string()]),
+        }
+    }
+}
+
+impl DiscoveryPlugin for WardPlugin {
+    fn name(&self) -> &str {
+        "ward"
+    }
+
+    fn file_patterns(&self) -> &[String] {
+        &self.file_patterns
+    }
+
+    fn parse_file(&self, path: &Path, content: &str) -> Vec<TestItem> {
+        let mut items = Vec::new();
+        let mut description: Option<(String, usize)> = None;
+        let mut skip: Option<Option<String>> = None;
+        let mut xfail = false;
+
+        for (index, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if let Some(args) = decorator_args(trimmed, "test") {
+                description = Some((
+                    first_string(args).unwrap_or_default().to_string(),
+                    index + 1,
+                ));
+            } else if let Some(args) = decorator_args(trimmed, "skip") {
+                skip = Some(first_string(args).map(str::to_string));
+            } else if decorator_args(trimmed, "xfail").is_some() {
+                xfail = true;
+            } else if trimmed.starts_with("def ") || trimmed.starts_with("async def ") {
+                if let Some((description, line)) = description.take() {
+                    items.push(TestItem {
+                        name: description.clone(),
+                        short_name: description,
+                        path: path.to_path_buf(),
+                        line,
+                        item_type: TestItemType::Function,
+                        markers: Vec::new(),
+                        fixtures: Vec::new(),
+                        class_name: None,
+                        skipped: skip.is_some(),
+                        skip_reason: skip.clone().flatten(),
+                        xfail,
+                        parametrize: None,
+                        plugin: Some(self.name().to_string()),
+                    });
+                }
+                skip = None;
+                xfail = false;
+            }
+        }
+        items
+    }
+
+    fn command(&self, items: &[TestItem]) -> Vec<String> {
+        let mut args = vec!["-m".to_string(), "ward".to_string()];
+        for file in unique_files(items) {
+            args.push("--path".to_string());
+            args.push(file.display().to_string());
+        }
+        args
+    }
+}
+
+/// A framework described entirely by configuration.
+pub struct CommandPlugin {
+    name: String,
+    file_patterns: Vec<String>,
+    item_pattern: Option<Regex>,
+    command: Vec<String>,
+}
+
+impl CommandPlugin {
+    pub fn from_config(name: &str, config: &PluginConfig) -> Result<Self, PluginError> {
+        let missing = |field| PluginError::MissingField {
+            plugin: name.to_string(),
+            field,
+        };
+        let file_patterns = config
+            .file_patterns
+            .clone()
+            .filter(|patterns| !patterns.is_empty())
+            .ok_or_else(|| missing("file-patterns"))?;
+        let command = config
+            .command
+            .clone()
+            .filter(|command| !command.is_empty())
+            .ok_or_else(|| missing("command"))?;
+        let item_pattern = config
+            .item_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|source| PluginError::InvalidPattern {
+                plugin: name.to_string(),
+                source,
+            })?;
+        Ok(Self {
+            name: name.to_string(),
+            file_patterns,
+            item_pattern,
+            command,
+        })
+    }
+
+    fn item(&self, path: &Path, name: String, line: usize) -> TestItem {
+        TestItem {
+            short_name: name.clone(),
+            name,
+            path: path.to_path_buf(),
+            line,
+            item_type: TestItemType::Function,
+            markers: Vec::new(),
+            fixtures: Vec::new(),
+            class_name: None,
+            skipped: false,
+            skip_reason: None,
+            xfail: false,
+            parametrize: None,
+            plugin: Some(self.name.clone()),
+        }
+    }
+}
+
+impl DiscoveryPlugin for CommandPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn file_patterns(&self) -> &[String] {
+        &self.file_patterns
+    }
+
+    fn parse_file(&self, path: &Path, content: &str) -> Vec<TestItem> {
+        let Some(pattern) = &self.item_pattern else {
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            return vec![self.item(path, stem, 1)];
+        };
+        content
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let captures = pattern.captures(line)?;
+                let name = captures.get(1).or_else(|| captures.get(0))?;
+                Some(self.item(path, name.as_str().to_string(), index + 1))
+            })
+            .collect()
+    }
+
+    fn command(&self, items: &[TestItem]) -> Vec<String> {
+        let files = unique_files(items);
+        let mut args = Vec::new();
+        for arg in &self.command {
+            if arg == "{files}" {
+                args.extend(files.iter().map(|f| f.display().to_string()));
+            } else {
+                args.push(arg.clone());
+            }
+        }
+        args
+    }
+}
+
+/// Files of `items`, in first-seen order.
+fn unique_files(items: &[TestItem]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for item in items {
+        if !files.contains(&item.path) {
+            files.push(item.path.clone());
+        }
+    }
+    files
+}
+
+/// Argument text of `@name(...)`, or `""` for a bare `@name`.
+fn decorator_args<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix('@')?.strip_prefix(name)?;
+    if rest.is_empty() {
+        return Some("");
+    }
+    rest.strip_prefix('(')
+        .map(|args| args.strip_suffix(')').unwrap_or(args))
+}
+
+/// Contents of the first quoted string in `args` (`f`/`r` prefixes allowed).
+fn first_string(args: &str) -> Option<&str> {
+    let start = args.find(['"', '\''])?;
+    let quote = args[start..].chars().next()?;
+    let body = &args[start + 1..];
+    body.find(quote).map(|end| &body[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEATURE: &str = r#"@web
+Feature: Login
+
+  Background:
+    Given a user
+
+  Scenario: Successful login
+    When I log in
+    Then I see the dashboard
+
+  @skip
+  Scenario: Locked account
+    Given a doc string
+      """
+      Scenario: not a scenario
+      """
+
+  Scenario Outline: Bad passwords
+    When I log in with <password>
+
+    Examples:
+      | password |
+      | empty    |
+      | wrong    |
+"#;
+
+    #[test]
+    fn behave_parses_scenarios_tags_and_outlines() {
+        let plugin = BehavePlugin::new(None);
+        let path = Path::new("features/login.feature");
+        assert!(plugin.claims(path));
+        assert!(!plugin.claims(Path::new("test_login.py")));
+
+        let items = plugin.parse_file(path, FEATURE);
+        let names: Vec<&str> = items.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Login::Successful login",
+                "Login::Locked account",
+                "Login::Bad passwords"
+            ]
+        );
+        assert_eq!(items[0].line, 7);
+        assert_eq!(items[0].markers[0].name, "web");
+        assert!(!items[0].skipped);
+        assert!(items[1].skipped);
+        let outline = items[2].parametrize.as_ref().unwrap();
+        assert_eq!(outline.params, ["password"]);
+        assert_eq!(outline.case_count, 2);
+        assert_eq!(items[2].plugin.as_deref(), Some("behave"));
+
+        assert_eq!(
+            plugin.command(&items[..1]),
+            ["-m", "behave", "features/login.feature:7"]
+        );
+    }
+
+    #[test]
+    fn ward_parses_described_tests() {
+        let plugin = WardPlugin::new(None);
+        let content = r#"from ward import test, skip
+
+@test("addition works")
+def _():
+    assert 1 + 1 == 2
+
+@skip("not yet")
+@test('subtraction works')
+async def _():
+    pass
+
+def helper():
+    pass
+"#;
+        let items = plugin.parse_file(Path::new("test_math.py"), content);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "addition works");
+        assert_eq!(items[0].line, 3);
+        assert!(items[1].skipped);
+        assert_eq!(items[1].skip_reason.as_deref(), Some("not yet"));
+        assert_eq!(
+            plugin.command(&items),
+            ["-m", "ward", "--path", "test_math.py"]
+        );
+    }
+
+    #[test]
+    fn command_plugins_come_from_config() {
+        let config: BTreeMap<String, PluginConfig> = toml::from_str(
+            r#"
+[nose2]
+file-patterns = ["check_*.py"]
+item-pattern = '^\s*def (test_\w+)'
+command = ["-m", "nose2", "{files}"]
+
+[behave]
+"#,
+        )
+        .unwrap();
+        let registry = PluginRegistry::from_config(&config).unwrap();
+        assert_eq!(registry.names(), ["behave", "nose2"]);
+
+        let plugin = registry.plugin_for(Path::new("check_io.py")).unwrap();
+        let items = plugin.parse_file(
+            Path::new("check_io.py"),
+            "def test_read():\n    pass\n\ndef test_write():\n    pass\n",
+        );
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].name, "test_write");
+        assert_eq!(items[1].line, 4);
+
+        let batches = registry.batches(&items);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].command(), ["-m", "nose2", "check_io.py"]);
+    }
+
+    #[test]
+    fn invalid_plugin_config_is_rejected() {
+        let config =
+            |toml: &str| -> BTreeMap<String, PluginConfig> { toml::from_str(toml).unwrap() };
+
+        let err =
+            PluginRegistry::from_config(&config("[mamba]\nfile-patterns = [\"*_spec.py\"]\n"))
+                .err()
+                .unwrap();
+        assert!(matches!(
+            err,
+            PluginError::MissingField {
+                field: "command",
+                ..
+            }
+        ));
+
+        let err = PluginRegistry::from_config(&config("[ward]\ncommand = [\"-m\", \"ward\"]\n"))
+            .err()
+            .unwrap();
+        assert!(matches!(err, PluginError::UnsupportedField { .. }));
+
+        let err = PluginRegistry::from_config(&config(
+            "[x]\nfile-patterns = [\"*.x\"]\ncommand = [\"-m\", \"x\"]\nitem-pattern = \"(\"\n",
+        ))
+        .err()
+        .unwrap();
+        assert!(matches!(err, PluginError::InvalidPattern { .. }));
+    }
+}
@@ -0,0 +1,322 @@
+//! Per-test reports of a `pybun test` run.
+//!
+//! Every backend that knows its individual tests produces a list of
+//! [`TestCaseReport`]s: the native backend from its executor results, the
+//! pytest backend by reading back the JUnit XML report pytest writes
+//! (`junit_family=xunit1`, which carries each test's `file` and `line`).
+//! The list becomes `detail.tests` in the JSON envelope and, with
+//! `--report PATH`, a JUnit XML file CI systems can annotate PRs from.
+
+use crate::test_executor::{TestOutcome, TestResult};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Outcome of one test, as reported in `detail.tests`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestCaseReport {
+    /// Name within its file, classes included (`TestUser::test_create`).
+    pub name: String,
+    /// Path of the test file relative to the project (or pytest) root.
+    pub file: Option<String>,
+    /// 1-based line of the test definition.
+    pub line: Option<usize>,
+    /// `passed`, `failed`, `error`, `timeout`, `skipped`, `xfail` or `xpass`.
+    pub outcome: String,
+    pub duration_ms: u64,
+    /// One-line failure (or skip) message.
+    pub message: Option<String>,
+    /// Dotted class path from JUnit, used for the id when there is no file.
+    #[serde(skip)]
+    pub classname: String,
+    /// Full failure output (traceback), written into the JUnit report.
+    #[serde(skip)]
+    pub details: Option<String>,
+}
+
+impl TestCaseReport {
+    /// Node id (`tests/test_api.py::TestUser::test_create`).
+    pub fn id(&self) -> String {
+        match &self.file {
+            Some(file) => format!("{file}::{}", self.name),
+            None => format!("{}::{}", self.classname, self.name),
+        }
+    }
+
+    /// Report for a native executor result, with its file relative to `root`.
+    pub fn from_result(result: &TestResult, root: &Path) -> Self {
+        let outcome = serde_json::to_value(&result.outcome)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let failed = matches!(
+            result.outcome,
+            TestOutcome::Failed | TestOutcome::Error | TestOutcome::Timeout
+        );
+        let message = if failed {
+            result
+                .stderr
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .map(|line| line.trim().to_string())
+        } else {
+            result.skip_reason.clone()
+        };
+        Self {
+            name: result.name.clone(),
+            file: Some(crate::test_history::relative_id(
+                root,
+                &root.join(&result.path),
+            )),
+            line: Some(result.line),
+            outcome,
+            duration_ms: result.duration_ms,
+            message,
+            classname: String::new(),
+            details: (failed && !result.stderr.is_empty()).then(|| result.stderr.clone()),
+        }
+    }
+}
+
+static TESTCASE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<testcase\b([^>]*?)(?:/>|>(.*?)</testcase>)").expect("valid regex")
+});
+static RESULT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<(failure|error|skipped)\b([^>]*?)(?:/>|>(.*?)</(?:failure|error|skipped)>)")
+        .expect("valid regex")
+});
+static ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([\w-]+)="([^"]*)""#).expect("valid regex"));
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Other control characters are not allowed in XML 1.0.
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn attributes(tag: &str) -> BTreeMap<&str, String> {
+    ATTRIBUTE
+        .captures_iter(tag)
+        .map(|a| (a.get(1).unwrap().as_str(), unescape(&a[2])))
+        .collect()
+}
+
+/// Per-test results from a pytest JUnit XML report. pytest writes 0-based
+/// `line` attributes; they are reported 1-based like the native backend.
+pub fn parse_junit(xml: &str) -> Vec<TestCaseReport> {
+    TESTCASE
+        .captures_iter(xml)
+        .map(|caps| {
+            let attrs = attributes(&caps[1]);
+            let mut name = attrs.get("name").cloned().unwrap_or_default();
+            let classname = attrs.get("classname").cloned().unwrap_or_default();
+            let file = attrs.get("file").map(|file| file.replace('\\', "/"));
+            if let Some(file) = &file {
+                let module = file.trim_end_matches(".py").replace('/', ".");
+                if let Some(classes) = classname.strip_prefix(&format!("{module}.")) {
+                    name = format!("{}::{name}", classes.replace('.', "::"));
+                }
+            }
+            let body = caps.get(2).map_or("", |m| m.as_str());
+            let (outcome, message, details) = match RESULT.captures(body) {
+                Some(result) => {
+                    let attrs = attributes(&result[2]);
+                    let outcome = match &result[1] {
+                        "failure" => "failed",
+                        "error" => "error",
+                        _ if attrs.get("type").map(String::as_str) == Some("pytest.xfail") => {
+                            "xfail"
+                        }
+                        _ => "skipped",
+                    };
+                    let details = result
+                        .get(3)
+                        .map(|m| unescape(m.as_str()))
+                        .filter(|d| !d.trim().is_empty());
+                    (outcome, attrs.get("message").cloned(), details)
+                }
+                None => ("passed", None, None),
+            };
+            let seconds: f64 = attrs
+                .get("time")
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0.0);
+            TestCaseReport {
+                name,
+                file,
+                line: attrs
+                    .get("line")
+                    .and_then(|l| l.parse::<usize>().ok())
+                    .map(|l| l + 1),
+                outcome: outcome.to_string(),
+                duration_ms: (seconds * 1000.0).round() as u64,
+                message,
+                classname,
+                details,
+            }
+        })
+        .collect()
+}
+
+/// JUnit XML for `cases`, as one `<testsuite>` named `suite`.
+pub fn to_junit(suite: &str, cases: &[TestCaseReport], duration_ms: u64) -> String {
+    let count = |outcomes: &[&str]| {
+        cases
+            .iter()
+            .filter(|c| outcomes.contains(&c.outcome.as_str()))
+            .count()
+    };
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<testsuites>\n");
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        escape(suite),
+        cases.len(),
+        count(&["failed", "timeout"]),
+        count(&["error"]),
+        count(&["skipped", "xfail"]),
+        duration_ms as f64 / 1000.0
+    );
+    for case in cases {
+        let (classes, name) = match case.name.rsplit_once("::") {
+            Some((classes, name)) => (Some(classes), name),
+            None => (None, case.name.as_str()),
+        };
+        let classname = if !case.classname.is_empty() {
+            case.classname.clone()
+        } else {
+            let module = case
+                .file
+                .as_deref()
+                .map(|file| file.trim_end_matches(".py").replace('/', "."));
+            [module, classes.map(|c| c.replace("::", "."))]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(".")
+        };
+        let _ = write!(
+            xml,
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            escape(&classname),
+            escape(name)
+        );
+        if let Some(file) = &case.file {
+            let _ = write!(xml, " file=\"{}\"", escape(file));
+        }
+        if let Some(line) = case.line {
+            let _ = write!(xml, " line=\"{line}\"");
+        }
+        let _ = write!(xml, " time=\"{:.3}\"", case.duration_ms as f64 / 1000.0);
+        let element = match case.outcome.as_str() {
+            "failed" | "timeout" => Some("failure"),
+            "error" => Some("error"),
+            "skipped" | "xfail" => Some("skipped"),
+            _ => None,
+        };
+        match element {
+            None => xml.push_str(" />\n"),
+            Some(element) => {
+                xml.push_str(">\n");
+                let message = case.message.as_deref().unwrap_or(&case.outcome);
+                let _ = write!(xml, "      <{element} message=\"{}\"", escape(message));
+                match &case.details {
+                    Some(details) => {
+                        let _ = writeln!(xml, ">{}</{element}>", escape(details));
+                    }
+                    None => xml.push_str(" />\n"),
+                }
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYTEST_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?><testsuites><testsuite name="pytest">
+<testcase classname="tests.test_api" file="tests/test_api.py" line="3" name="test_get" time="0.012" />
+<testcase classname="tests.test_api.TestUser" file="tests/test_api.py" line="9" name="test_create[a&amp;b]" time="1.5"><failure message="assert 1 == 2">def test_create():
+&gt;       assert 1 == 2</failure></testcase>
+<testcase classname="tests.test_api" file="tests/test_api.py" line="20" name="test_skip" time="0.000"><skipped type="pytest.skip" message="later" /></testcase>
+<testcase classname="tests.test_api" file="tests/test_api.py" line="24" name="test_known" time="0.001"><skipped type="pytest.xfail" message="bug" /></testcase>
+<testcase classname="test_mod" name="test_plain" time="0.1"><error message="setup" /></testcase>
+</testsuite></testsuites>"#;
+
+    #[test]
+    fn parses_names_lines_outcomes_and_messages() {
+        let cases = parse_junit(PYTEST_XML);
+        let ids: Vec<String> = cases.iter().map(TestCaseReport::id).collect();
+        assert_eq!(
+            ids,
+            [
+                "tests/test_api.py::test_get",
+                "tests/test_api.py::TestUser::test_create[a&b]",
+                "tests/test_api.py::test_skip",
+                "tests/test_api.py::test_known",
+                "test_mod::test_plain",
+            ]
+        );
+        let outcomes: Vec<&str> = cases.iter().map(|c| c.outcome.as_str()).collect();
+        assert_eq!(outcomes, ["passed", "failed", "skipped", "xfail", "error"]);
+        assert_eq!(cases[0].line, Some(4));
+        assert_eq!(cases[1].duration_ms, 1500);
+        assert_eq!(cases[1].message.as_deref(), Some("assert 1 == 2"));
+        assert!(
+            cases[1]
+                .details
+                .as_deref()
+                .unwrap()
+                .contains(">       assert")
+        );
+        assert_eq!(cases[4].file, None);
+    }
+
+    #[test]
+    fn junit_output_round_trips() {
+        let cases = parse_junit(PYTEST_XML);
+        let xml = to_junit("pybun", &cases, 1613);
+        assert!(xml.contains(
+            r#"<testsuite name="pybun" tests="5" failures="1" errors="1" skipped="2" time="1.613">"#
+        ));
+        assert!(xml.contains(
+            r#"<testcase classname="tests.test_api.TestUser" name="test_create[a&amp;b]" file="tests/test_api.py" line="10" time="1.500">"#
+        ));
+        let reparsed = parse_junit(&xml);
+        assert_eq!(
+            reparsed.iter().map(TestCaseReport::id).collect::<Vec<_>>(),
+            cases.iter().map(TestCaseReport::id).collect::<Vec<_>>()
+        );
+        assert_eq!(reparsed[1].message, cases[1].message);
+        assert_eq!(reparsed[2].outcome, "skipped");
+    }
+}
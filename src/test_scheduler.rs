@@ -0,0 +1,303 @@
+//! Parallel scheduling for the pytest backend (`pybun test -j N`).
+//!
+//! Instead of handing `-n` to pytest-xdist, which may not be installed, the
+//! discovered tests are split across N pytest worker processes. Tests are
+//! balanced by their duration in the project's test history: longest first,
+//! each onto the least-loaded worker. Tests without history count as the
+//! median known duration. Each worker's output is read line by line while
+//! it runs, so per-test results are reported as they finish, and the
+//! workers' JUnit reports are merged into one result. Output is kept in
+//! [`Capture`]s, so a chatty worker spills to disk rather than into memory.
+
+use crate::payload::{Capture, CapturedOutput};
+use crate::test_history::RunRecord;
+use crate::test_report::{self, TestCaseReport};
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::LazyLock;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Instant;
+
+/// Expected duration of a test nothing is known about.
+pub const DEFAULT_DURATION_MS: u64 = 100;
+
+/// Tests assigned to one worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shard {
+    /// Node ids, longest expected first.
+    pub tests: Vec<String>,
+    /// Sum of the tests' expected durations.
+    pub expected_ms: u64,
+}
+
+/// Latest recorded duration of each test, keyed by node id relative to the
+/// project root. Parametrized cases are summed under their base id, which
+/// is what discovery reports and what a worker is given.
+pub fn expected_durations(runs: &[RunRecord]) -> HashMap<String, u64> {
+    let mut durations = HashMap::new();
+    for run in runs {
+        let mut latest: HashMap<&str, u64> = HashMap::new();
+        for test in &run.tests {
+            let base = match test.id.find('[') {
+                Some(index) if test.id.ends_with(']') => &test.id[..index],
+                _ => test.id.as_str(),
+            };
+            *latest.entry(base).or_default() += test.duration_ms;
+        }
+        for (id, duration) in latest {
+            durations.insert(id.to_string(), duration);
+        }
+    }
+    durations
+}
+
+/// Split `tests` (node id, expected duration or `None` when unknown) into at
+/// most `workers` shards of similar total duration. Empty shards are
+/// dropped, so there are never more shards than tests.
+pub fn balance(tests: Vec<(String, Option<u64>)>, workers: usize) -> Vec<Shard> {
+    let mut known: Vec<u64> = tests.iter().filter_map(|(_, d)| *d).collect();
+    known.sort_unstable();
+    let fallback = known
+        .get(known.len() / 2)
+        .copied()
+        .unwrap_or(DEFAULT_DURATION_MS);
+
+    let mut tests: Vec<(String, u64)> = tests
+        .into_iter()
+        .map(|(id, duration)| (id, duration.unwrap_or(fallback)))
+        .collect();
+    tests.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut shards = vec![
+        Shard {
+            tests: Vec::new(),
+            expected_ms: 0,
+        };
+        workers.max(1)
+    ];
+    for (id, duration) in tests {
+        let lightest = shards
+            .iter_mut()
+            .min_by_key(|shard| (shard.expected_ms, shard.tests.len()))
+            .expect("at least one shard");
+        lightest.tests.push(id);
+        lightest.expected_ms += duration;
+    }
+    shards.retain(|shard| !shard.tests.is_empty());
+    shards
+}
+
+static RESULT_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\S+::.+?) (PASSED|FAILED|ERROR|SKIPPED|XFAIL|XPASS)\b").expect("valid regex")
+});
+
+/// Node id and outcome (`passed`, `failed`, ...) from a `pytest -v` result
+/// line such as `tests/test_api.py::test_get PASSED  [ 50%]`.
+pub fn parse_result_line(line: &str) -> Option<(&str, String)> {
+    let caps = RESULT_LINE.captures(line)?;
+    Some((
+        caps.get(1)?.as_str(),
+        caps.get(2)?.as_str().to_ascii_lowercase(),
+    ))
+}
+
+/// One pytest worker process to run.
+pub struct Worker {
+    /// Fully prepared command; stdout and stderr are piped by [`run`].
+    pub command: Command,
+    /// Where the worker writes its JUnit XML report.
+    pub report: tempfile::NamedTempFile,
+}
+
+/// What one worker produced.
+#[derive(Debug, Clone)]
+pub struct WorkerRun {
+    /// Exit code; `None` when the worker was stopped or killed by a signal.
+    pub exit_code: Option<i32>,
+    pub stdout: CapturedOutput,
+    pub stderr: CapturedOutput,
+    pub duration_ms: u64,
+    /// Per-test results from the worker's JUnit report.
+    pub tests: Vec<TestCaseReport>,
+}
+
+enum Message {
+    Stdout(usize, String),
+    Stderr(usize, std::io::Result<CapturedOutput>),
+    Exited(usize),
+}
+
+/// Run all `workers` at once. `on_result` is called with the worker index,
+/// node id and outcome of every result line as it arrives; returning `true`
+/// kills every other worker (fail-fast). Killed workers write no report.
+pub fn run(
+    workers: Vec<Worker>,
+    mut on_result: impl FnMut(usize, &str, &str) -> bool,
+) -> std::io::Result<Vec<WorkerRun>> {
+    let (tx, rx) = channel();
+    let mut children: Vec<(Child, Instant, tempfile::NamedTempFile)> = Vec::new();
+    for (index, mut worker) in workers.into_iter().enumerate() {
+        worker
+            .command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = match worker.command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                for (mut child, _, _) in children {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                return Err(e);
+            }
+        };
+        let stdout = child.stdout.take().expect("piped stdout");
+        let stderr = child.stderr.take().expect("piped stderr");
+        let out_tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if out_tx.send(Message::Stdout(index, line)).is_err() {
+                    break;
+                }
+            }
+            let _ = out_tx.send(Message::Exited(index));
+        });
+        let err_tx = tx.clone();
+        thread::spawn(move || {
+            let output = Capture::for_output("worker-stderr").drain(stderr);
+            let _ = err_tx.send(Message::Stderr(index, output));
+        });
+        children.push((child, Instant::now(), worker.report));
+    }
+    drop(tx);
+
+    let mut stdout: Vec<Capture> = (0..children.len())
+        .map(|_| Capture::for_output("worker-stdout"))
+        .collect();
+    let mut stderr = vec![CapturedOutput::default(); children.len()];
+    let mut durations = vec![0; children.len()];
+    let mut stopped = false;
+    for message in rx {
+        match message {
+            Message::Stdout(index, line) => {
+                if !stopped
+                    && let Some((node_id, outcome)) = parse_result_line(&line)
+                    && on_result(index, node_id, &outcome)
+                {
+                    // The reporting worker stops on its own (`-x`) and
+                    // still writes its report; the others are cut short.
+                    stopped = true;
+                    for (other, (child, _, _)) in children.iter_mut().enumerate() {
+                        if other != index {
+                            let _ = child.kill();
+                        }
+                    }
+                }
+                writeln!(stdout[index], "{line}")?;
+            }
+            Message::Stderr(index, output) => {
+                stderr[index] = output?;
+            }
+            Message::Exited(index) => {
+                durations[index] = children[index].1.elapsed().as_millis() as u64;
+            }
+        }
+    }
+
+    let mut runs = Vec::new();
+    let outputs = stdout.into_iter().zip(stderr);
+    for (index, ((mut child, _, report), (stdout, stderr))) in
+        children.into_iter().zip(outputs).enumerate()
+    {
+        let status = child.wait()?;
+        let tests = std::fs::read_to_string(report.path())
+            .map(|xml| test_report::parse_junit(&xml))
+            .unwrap_or_default();
+        runs.push(WorkerRun {
+            exit_code: status.code(),
+            stdout: stdout.finish()?,
+            stderr,
+            duration_ms: durations[index],
+            tests,
+        });
+    }
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_history::TestRecord;
+
+    #[test]
+    fn balances_longest_first_onto_the_lightest_worker() {
+        let tests = vec![
+            ("a".to_string(), Some(500)),
+            ("b".to_string(), Some(400)),
+            ("c".to_string(), Some(300)),
+            ("d".to_string(), Some(200)),
+            ("e".to_string(), None),
+        ];
+        let shards = balance(tests, 2);
+        assert_eq!(shards.len(), 2);
+        assert_eq!(shards[0].tests, ["a", "c", "d"]);
+        assert_eq!(shards[1].tests, ["b", "e"]);
+        // `e` has no history and counts as the median known duration.
+        assert_eq!(shards[0].expected_ms, 1000);
+        assert_eq!(shards[1].expected_ms, 400 + 400);
+
+        let shards = balance(vec![("only".to_string(), None)], 4);
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].expected_ms, DEFAULT_DURATION_MS);
+    }
+
+    #[test]
+    fn latest_run_wins_and_parametrized_cases_are_summed() {
+        let runs = vec![
+            RunRecord::now(
+                "pytest",
+                0,
+                true,
+                vec![TestRecord::new("t.py::a", "passed", 900)],
+            ),
+            RunRecord::now(
+                "pytest",
+                0,
+                true,
+                vec![
+                    TestRecord::new("t.py::a", "passed", 10),
+                    TestRecord::new("t.py::p[1]", "passed", 20),
+                    TestRecord::new("t.py::p[2]", "passed", 30),
+                ],
+            ),
+        ];
+        let durations = expected_durations(&runs);
+        assert_eq!(durations["t.py::a"], 10);
+        assert_eq!(durations["t.py::p"], 50);
+    }
+
+    #[test]
+    fn parses_verbose_result_lines() {
+        assert_eq!(
+            parse_result_line("tests/test_api.py::TestUser::test_get[a b] PASSED    [ 50%]"),
+            Some((
+                "tests/test_api.py::TestUser::test_get[a b]",
+                "passed".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_result_line("tests/test_api.py::test_get[1-2] FAILED          [100%]"),
+            Some(("tests/test_api.py::test_get[1-2]", "failed".to_string()))
+        );
+        assert_eq!(
+            parse_result_line("t.py::test_skip SKIPPED (later)"),
+            Some(("t.py::test_skip", "skipped".to_string()))
+        );
+        assert_eq!(parse_result_line("collected 3 items"), None);
+    }
+}
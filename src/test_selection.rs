@@ -0,0 +1,650 @@
+//! Precise test selection for `pybun test`.
+//!
+//! Two complementary mechanisms narrow the discovered test set:
+//!
+//! - **Targets** (positional `PATH` arguments) may be a plain file/directory,
+//!   a pytest-style node id (`tests/test_api.py::TestUser::test_create`), or a
+//!   `file:line` reference (`tests/test_api.py:87`) that resolves to the test
+//!   enclosing (or nearest preceding) that line.
+//! - **Keyword expressions** (`-k`) are boolean expressions over test names,
+//!   class names, module names and marker names, e.g.
+//!   `-k "api and not slow"`, evaluated against discovered items with the same
+//!   case-insensitive substring semantics as `pytest -k`.
+//!
+//! Both are evaluated against [`TestItem`]s produced by
+//! [`crate::test_discovery`], so selection behaves the same regardless of the
+//! chosen backend and does not require pytest-specific syntax knowledge.
+
+use crate::test_discovery::{TestItem, TestItemType};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A single positional selection target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestTarget {
+    /// A file or directory to discover tests in.
+    Path(PathBuf),
+    /// A node id: file path plus `::`-separated class/function components.
+    NodeId { path: PathBuf, parts: Vec<String> },
+    /// A `file:line` reference resolved to the nearest test.
+    Line { path: PathBuf, line: usize },
+}
+
+impl TestTarget {
+    /// Parse a raw positional argument into a target.
+    ///
+    /// A trailing `:<digits>` is only treated as a line reference when the
+    /// prefix names a `.py` or `.feature` file, so Windows drive letters (`C:\...`) and
+    /// directories are never misread.
+    pub fn parse(raw: &Path) -> Self {
+        let text = raw.to_string_lossy();
+
+        if let Some((file, rest)) = text.split_once("::") {
+            let parts = rest
+                .split("::")
+                .filter(|p| !p.is_empty())
+                .map(|p| p.to_string())
+                .collect();
+            return TestTarget::NodeId {
+                path: PathBuf::from(file),
+                parts,
+            };
+        }
+
+        if let Some((file, line)) = text.rsplit_once(':')
+            && (file.ends_with(".py") || file.ends_with(".feature"))
+            && !line.is_empty()
+            && line.chars().all(|c| c.is_ascii_digit())
+            && let Ok(line) = line.parse::<usize>()
+        {
+            return TestTarget::Line {
+                path: PathBuf::from(file),
+                line,
+            };
+        }
+
+        TestTarget::Path(raw.to_path_buf())
+    }
+
+    /// The file or directory this target discovers tests in.
+    pub fn path(&self) -> &Path {
+        match self {
+            TestTarget::Path(path)
+            | TestTarget::NodeId { path, .. }
+            | TestTarget::Line { path, .. } => path,
+        }
+    }
+
+    /// Whether this target narrows a file down to specific tests.
+    pub fn is_precise(&self) -> bool {
+        !matches!(self, TestTarget::Path(_))
+    }
+
+    /// Short identifier of the target kind for JSON output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TestTarget::Path(_) => "path",
+            TestTarget::NodeId { .. } => "node_id",
+            TestTarget::Line { .. } => "line",
+        }
+    }
+}
+
+/// Outcome of resolving one target against the discovered items.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedTarget {
+    /// Target as given on the command line.
+    pub target: String,
+    /// Target kind (`path`, `node_id`, `line`).
+    pub kind: &'static str,
+    /// Node ids (`path::Class::test`) of the tests it selected.
+    pub selected: Vec<String>,
+}
+
+/// Apply positional targets to the discovered items.
+///
+/// `items` must include class items (they are used to resolve `file:line`
+/// references that point inside a class body); the returned list never
+/// contains class items. Plain path targets keep every test under them.
+/// Returns an error for a precise target that selects nothing, since silently
+/// running zero tests would hide a typo.
+pub fn select_targets(
+    items: &[TestItem],
+    targets: &[TestTarget],
+) -> Result<(Vec<TestItem>, Vec<ResolvedTarget>), String> {
+    let mut selected: Vec<TestItem> = Vec::new();
+    let mut resolved = Vec::new();
+
+    for target in targets {
+        let matches: Vec<&TestItem> = match target {
+            TestTarget::Path(path) => items
+                .iter()
+                .filter(|t| t.item_type != TestItemType::Class && is_under(&t.path, path))
+                .collect(),
+            TestTarget::NodeId { path, parts } => items
+                .iter()
+                .filter(|t| {
+                    t.item_type != TestItemType::Class
+                        && same_file(&t.path, path)
+                        && node_matches(t, parts)
+                })
+                .collect(),
+            TestTarget::Line { path, line } => resolve_line(items, path, *line),
+        };
+
+        if target.is_precise() && matches.is_empty() {
+            return Err(match target {
+                TestTarget::Line { path, line } => {
+                    format!("no test found at or before {}:{}", path.display(), line)
+                }
+                _ => format!(
+                    "no test matches '{}'",
+                    display_target(target).trim_end_matches("::")
+                ),
+            });
+        }
+
+        resolved.push(ResolvedTarget {
+            target: display_target(target),
+            kind: target.kind(),
+            selected: matches.iter().map(|t| node_id(t)).collect(),
+        });
+
+        for item in matches {
+            if !selected
+                .iter()
+                .any(|s| s.path == item.path && s.name == item.name && s.line == item.line)
+            {
+                selected.push(item.clone());
+            }
+        }
+    }
+
+    Ok((selected, resolved))
+}
+
+/// Node id for a selected test in pytest's `path::Class::test` form.
+pub fn node_id(item: &TestItem) -> String {
+    format!("{}::{}", item.path.display(), item.name)
+}
+
+fn display_target(target: &TestTarget) -> String {
+    match target {
+        TestTarget::Path(path) => path.display().to_string(),
+        TestTarget::NodeId { path, parts } => {
+            format!("{}::{}", path.display(), parts.join("::"))
+        }
+        TestTarget::Line { path, line } => format!("{}:{}", path.display(), line),
+    }
+}
+
+/// Pick the test enclosing `line`: the last test (or test class) defined at
+/// or before it. A class hit selects every method in that class. When the
+/// line precedes every test in the file, the first test after it is used.
+fn resolve_line<'a>(items: &'a [TestItem], path: &Path, line: usize) -> Vec<&'a TestItem> {
+    let in_file: Vec<&TestItem> = items.iter().filter(|t| same_file(&t.path, path)).collect();
+
+    let enclosing = in_file
+        .iter()
+        .filter(|t| t.line <= line)
+        .max_by_key(|t| t.line)
+        .or_else(|| in_file.iter().min_by_key(|t| t.line));
+
+    let Some(hit) = enclosing else {
+        return Vec::new();
+    };
+
+    if hit.item_type == TestItemType::Class {
+        return in_file
+            .iter()
+            .filter(|t| t.class_name.as_deref() == Some(hit.name.as_str()))
+            .copied()
+            .collect();
+    }
+    vec![*hit]
+}
+
+/// Match node id components against an item, ignoring any `[param]` suffix
+/// on the final component (parametrized cases are expanded at run time).
+fn node_matches(item: &TestItem, parts: &[String]) -> bool {
+    let parts: Vec<&str> = parts.iter().map(|p| strip_param_id(p)).collect();
+    match parts.as_slice() {
+        [] => true,
+        [single] => {
+            item.name == *single
+                || item.short_name == *single
+                || item.class_name.as_deref() == Some(*single)
+        }
+        _ => item.name == parts.join("::"),
+    }
+}
+
+fn strip_param_id(part: &str) -> &str {
+    part.split_once('[').map(|(name, _)| name).unwrap_or(part)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    let (a, b) = normalize_pair(a, b);
+    a == b
+}
+
+fn is_under(item_path: &Path, target: &Path) -> bool {
+    let (item, target) = normalize_pair(item_path, target);
+    item.starts_with(&target)
+}
+
+/// Normalize two paths for comparison: canonical when both exist on disk,
+/// otherwise lexically absolute against the working directory.
+fn normalize_pair(a: &Path, b: &Path) -> (PathBuf, PathBuf) {
+    if let (Ok(a), Ok(b)) = (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        return (a, b);
+    }
+    (lexical_absolute(a), lexical_absolute(b))
+}
+
+fn lexical_absolute(path: &Path) -> PathBuf {
+    let base = if path.is_absolute() {
+        PathBuf::new()
+    } else {
+        std::env::current_dir().unwrap_or_default()
+    };
+    base.join(path)
+        .components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// -k keyword expressions
+// ---------------------------------------------------------------------------
+
+/// A parsed `-k` expression.
+///
+/// Grammar (matching `pytest -k`):
+///
+/// ```text
+/// expr     := and_expr ("or" and_expr)*
+/// and_expr := not_expr ("and" not_expr)*
+/// not_expr := "not" not_expr | "(" expr ")" | IDENT
+/// ```
+///
+/// An identifier matches an item when it is a case-insensitive substring of
+/// the test name, its class name, its module (file stem) or any marker name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeywordExpr {
+    Ident(String),
+    Not(Box<KeywordExpr>),
+    And(Box<KeywordExpr>, Box<KeywordExpr>),
+    Or(Box<KeywordExpr>, Box<KeywordExpr>),
+}
+
+impl KeywordExpr {
+    /// Parse an expression, reporting the offending token on error.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input);
+        if tokens.is_empty() {
+            return Err("empty -k expression".to_string());
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!(
+                "invalid -k expression '{}': unexpected '{}'",
+                input,
+                token.as_str()
+            ));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluate the expression against a discovered test item.
+    pub fn matches(&self, item: &TestItem) -> bool {
+        match self {
+            KeywordExpr::Ident(word) => keywords(item).iter().any(|k| k.contains(word.as_str())),
+            KeywordExpr::Not(inner) => !inner.matches(item),
+            KeywordExpr::And(a, b) => a.matches(item) && b.matches(item),
+            KeywordExpr::Or(a, b) => a.matches(item) || b.matches(item),
+        }
+    }
+}
+
+/// Filter items with a `-k` expression.
+pub fn filter_by_keyword(tests: Vec<TestItem>, expr: &KeywordExpr) -> Vec<TestItem> {
+    tests.into_iter().filter(|t| expr.matches(t)).collect()
+}
+
+/// Lowercased keywords an identifier can match against.
+fn keywords(item: &TestItem) -> Vec<String> {
+    let mut words = vec![item.name.to_lowercase(), item.short_name.to_lowercase()];
+    if let Some(class) = &item.class_name {
+        words.push(class.to_lowercase());
+    }
+    if let Some(stem) = item.path.file_stem().and_then(|s| s.to_str()) {
+        words.push(stem.to_lowercase());
+    }
+    words.extend(item.markers.iter().map(|m| m.name.to_lowercase()));
+    words
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Ident(String),
+}
+
+impl Token {
+    fn as_str(&self) -> &str {
+        match self {
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::And => "and",
+            Token::Or => "or",
+            Token::Not => "not",
+            Token::Ident(s) => s,
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+
+    let flush = |current: &mut String, tokens: &mut Vec<Token>| {
+        if current.is_empty() {
+            return;
+        }
+        let token = match current.as_str() {
+            "and" => Token::And,
+            "or" => Token::Or,
+            "not" => Token::Not,
+            word => Token::Ident(word.to_lowercase()),
+        };
+        tokens.push(token);
+        current.clear();
+    };
+
+    for c in input.chars() {
+        match c {
+            '(' | ')' => {
+                flush(&mut current, &mut tokens);
+                tokens.push(if c == '(' {
+                    Token::LParen
+                } else {
+                    Token::RParen
+                });
+            }
+            c if c.is_whitespace() => flush(&mut current, &mut tokens),
+            c => current.push(c),
+        }
+    }
+    flush(&mut current, &mut tokens);
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<KeywordExpr, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = KeywordExpr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<KeywordExpr, String> {
+        let mut left = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.parse_not()?;
+            left = KeywordExpr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<KeywordExpr, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(KeywordExpr::Not(Box::new(self.parse_not()?))),
+            Some(Token::LParen) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("invalid -k expression: missing ')'".to_string()),
+                }
+            }
+            Some(Token::Ident(word)) => Ok(KeywordExpr::Ident(word)),
+            Some(token) => Err(format!(
+                "invalid -k expression: expected a name, found '{}'",
+                token.as_str()
+            )),
+            None => Err("invalid -k expression: unexpected end of input".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_discovery::PytestMarker;
+    use std::collections::HashMap;
+
+    fn item(
+        path: &str,
+        name: &str,
+        line: usize,
+        class: Option<&str>,
+        markers: &[&str],
+    ) -> TestItem {
+        let item_type = if class.is_some() {
+            TestItemType::Method
+        } else {
+            TestItemType::Function
+        };
+        TestItem {
+            name: name.to_string(),
+            short_name: name.rsplit("::").next().unwrap().to_string(),
+            path: PathBuf::from(path),
+            line,
+            item_type,
+            markers: markers
+                .iter()
+                .map(|m| PytestMarker {
+                    name: m.to_string(),
+                    args: Vec::new(),
+                    kwargs: HashMap::new(),
+                })
+                .collect(),
+            fixtures: Vec::new(),
+            class_name: class.map(|c| c.to_string()),
+            skipped: false,
+            skip_reason: None,
+            xfail: false,
+            parametrize: None,
+            plugin: None,
+        }
+    }
+
+    fn class_item(path: &str, name: &str, line: usize) -> TestItem {
+        TestItem {
+            item_type: TestItemType::Class,
+            ..item(path, name, line, None, &[])
+        }
+    }
+
+    fn sample() -> Vec<TestItem> {
+        vec![
+            item("tests/test_api.py", "test_health", 3, None, &[]),
+            class_item("tests/test_api.py", "TestUser", 6),
+            item(
+                "tests/test_api.py",
+                "TestUser::test_create",
+                7,
+                Some("TestUser"),
+                &[],
+            ),
+            item(
+                "tests/test_api.py",
+                "TestUser::test_delete",
+                12,
+                Some("TestUser"),
+                &["slow"],
+            ),
+            item("tests/test_db.py", "test_connect", 1, None, &["slow"]),
+        ]
+    }
+
+    #[test]
+    fn parses_target_kinds() {
+        assert_eq!(
+            TestTarget::parse(Path::new("tests/test_api.py::TestUser::test_create")),
+            TestTarget::NodeId {
+                path: PathBuf::from("tests/test_api.py"),
+                parts: vec!["TestUser".to_string(), "test_create".to_string()],
+            }
+        );
+        assert_eq!(
+            TestTarget::parse(Path::new("tests/test_api.py:87")),
+            TestTarget::Line {
+                path: PathBuf::from("tests/test_api.py"),
+                line: 87,
+            }
+        );
+        assert_eq!(
+            TestTarget::parse(Path::new("tests")),
+            TestTarget::Path(PathBuf::from("tests"))
+        );
+        assert_eq!(
+            TestTarget::parse(Path::new("C:tests")),
+            TestTarget::Path(PathBuf::from("C:tests"))
+        );
+    }
+
+    #[test]
+    fn node_id_selects_single_method() {
+        let targets = [TestTarget::parse(Path::new(
+            "tests/test_api.py::TestUser::test_create",
+        ))];
+        let (selected, resolved) = select_targets(&sample(), &targets).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "TestUser::test_create");
+        assert_eq!(resolved[0].kind, "node_id");
+    }
+
+    #[test]
+    fn node_id_with_class_selects_all_methods() {
+        let targets = [TestTarget::parse(Path::new("tests/test_api.py::TestUser"))];
+        let (selected, _) = select_targets(&sample(), &targets).unwrap();
+        let names: Vec<_> = selected.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["TestUser::test_create", "TestUser::test_delete"]);
+    }
+
+    #[test]
+    fn node_id_ignores_parametrize_suffix() {
+        let targets = [TestTarget::parse(Path::new(
+            "tests/test_api.py::test_health[1-2]",
+        ))];
+        let (selected, _) = select_targets(&sample(), &targets).unwrap();
+        assert_eq!(selected[0].name, "test_health");
+    }
+
+    #[test]
+    fn line_target_selects_enclosing_test() {
+        let targets = [TestTarget::parse(Path::new("tests/test_api.py:9"))];
+        let (selected, resolved) = select_targets(&sample(), &targets).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "TestUser::test_create");
+        assert_eq!(
+            resolved[0].selected,
+            vec!["tests/test_api.py::TestUser::test_create"]
+        );
+    }
+
+    #[test]
+    fn line_target_on_class_selects_its_methods() {
+        let targets = [TestTarget::parse(Path::new("tests/test_api.py:6"))];
+        let (selected, _) = select_targets(&sample(), &targets).unwrap();
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn line_target_before_first_test_uses_first_test() {
+        let targets = [TestTarget::parse(Path::new("tests/test_api.py:1"))];
+        let (selected, _) = select_targets(&sample(), &targets).unwrap();
+        assert_eq!(selected[0].name, "test_health");
+    }
+
+    #[test]
+    fn unmatched_precise_target_is_an_error() {
+        let targets = [TestTarget::parse(Path::new(
+            "tests/test_api.py::test_missing",
+        ))];
+        let err = select_targets(&sample(), &targets).unwrap_err();
+        assert!(err.contains("test_missing"), "{err}");
+    }
+
+    #[test]
+    fn path_target_keeps_everything_under_it() {
+        let targets = [TestTarget::parse(Path::new("tests"))];
+        let (selected, _) = select_targets(&sample(), &targets).unwrap();
+        assert_eq!(selected.len(), 4);
+    }
+
+    #[test]
+    fn keyword_plain_substring_matches_names() {
+        let expr = KeywordExpr::parse("create").unwrap();
+        let selected = filter_by_keyword(sample(), &expr);
+        assert_eq!(selected.len(), 1);
+    }
+
+    #[test]
+    fn keyword_boolean_expression_over_names_and_markers() {
+        let expr = KeywordExpr::parse("TestUser and not slow").unwrap();
+        let selected: Vec<_> = filter_by_keyword(sample(), &expr)
+            .into_iter()
+            .filter(|t| t.item_type != TestItemType::Class)
+            .collect();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "TestUser::test_create");
+
+        let expr = KeywordExpr::parse("slow").unwrap();
+        assert_eq!(filter_by_keyword(sample(), &expr).len(), 2);
+    }
+
+    #[test]
+    fn keyword_matches_module_name_and_parentheses() {
+        let expr = KeywordExpr::parse("test_db or (api and health)").unwrap();
+        let names: Vec<_> = filter_by_keyword(sample(), &expr)
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, ["test_health", "test_connect"]);
+    }
+
+    #[test]
+    fn keyword_parse_errors() {
+        assert!(KeywordExpr::parse("").is_err());
+        assert!(KeywordExpr::parse("foo and").is_err());
+        assert!(KeywordExpr::parse("(foo").is_err());
+        assert!(KeywordExpr::parse("foo bar").is_err());
+        assert!(KeywordExpr::parse("or foo").is_err());
+    }
+}
@@ -0,0 +1,528 @@
+//! Persistent `pybun x` tool environments.
+//!
+//! `pybun x ruff` installs ruff once and reuses the environment on later
+//! runs. Environments are keyed by the package, the requirement it was
+//! requested with (`ruff`, `ruff==0.5.0`) and the interpreter version, so
+//! `pybun x ruff` and `pybun x --python 3.11 ruff` keep separate ones.
+//!
+//! Cache layout:
+//! ```text
+//! ~/.cache/pybun/tools/
+//!   {package}-{hash}/
+//!     venv/           # The tool's virtual environment
+//!     tool.json       # Key, installed version and timestamps
+//! ```
+//!
+//! An environment only counts as cached once `tool.json` is written, after
+//! the install succeeded; a half-built directory is rebuilt.
+
+use crate::entry_points::{self, EntryPoint};
+use crate::project_registry::dir_size;
+use crate::pypi::normalize_project_name;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+const TOOLS_DIR: &str = "tools";
+const INFO_FILE: &str = "tool.json";
+
+#[derive(Debug, Error)]
+pub enum ToolEnvError {
+    #[error("failed to determine home directory")]
+    NoHomeDir,
+    #[error("failed to update tool environment {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{package} has no command `{command}` ({})", available_commands(.available))]
+    UnknownCommand {
+        package: String,
+        command: String,
+        available: Vec<String>,
+    },
+}
+
+fn available_commands(available: &[String]) -> String {
+    if available.is_empty() {
+        "it installs no commands".to_string()
+    } else {
+        format!("available: {}", available.join(", "))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ToolEnvError>;
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> ToolEnvError + '_ {
+    move |source| ToolEnvError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// What a tool environment is cached under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolEnvKey {
+    /// Normalized project name (`ruff`).
+    pub package: String,
+    /// The requirement as requested, normalized (`ruff`, `ruff==0.5.0`,
+    /// `black[d]`).
+    pub requirement: String,
+    pub python_version: String,
+    /// Directory name under `tools/`.
+    pub id: String,
+}
+
+impl ToolEnvKey {
+    /// Key for `requirement` (a PEP 508 requirement naming `package`) on
+    /// `python_version`.
+    pub fn new(package: &str, requirement: &str, python_version: &str) -> Self {
+        let package = normalize_project_name(package_name(package));
+        let requirement: String = requirement
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        let python_version = python_version.trim().to_string();
+
+        let mut hasher = Sha256::new();
+        hasher.update(format!("requirement={requirement}\npython={python_version}").as_bytes());
+        let id = format!("{package}-{}", hex::encode(&hasher.finalize()[..8]));
+
+        Self {
+            package,
+            requirement,
+            python_version,
+            id,
+        }
+    }
+}
+
+/// `name` without extras (`black[d]` → `black`).
+fn package_name(name: &str) -> &str {
+    name.split('[').next().unwrap_or(name).trim()
+}
+
+/// Metadata stored next to a tool environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolEnvInfo {
+    pub package: String,
+    pub requirement: String,
+    pub python_version: String,
+    /// Installed version of the package, when it could be read.
+    pub version: Option<String>,
+    pub created_at: u64,
+    pub last_used: u64,
+}
+
+impl ToolEnvInfo {
+    fn matches(&self, key: &ToolEnvKey) -> bool {
+        self.package == key.package
+            && self.requirement == key.requirement
+            && self.python_version == key.python_version
+    }
+}
+
+/// A cached tool environment, as listed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolEnv {
+    #[serde(flatten)]
+    pub info: ToolEnvInfo,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// Tool environment cache manager.
+#[derive(Debug, Clone)]
+pub struct ToolEnvCache {
+    root: PathBuf,
+}
+
+impl ToolEnvCache {
+    /// Create a new cache instance using default or env-configured location.
+    pub fn new() -> Result<Self> {
+        let cache = crate::cache::Cache::new().map_err(|_| ToolEnvError::NoHomeDir)?;
+        Ok(Self::with_root(cache.root()))
+    }
+
+    /// Create a cache instance with a custom root directory (useful for testing).
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Root directory for tool environments.
+    pub fn tools_dir(&self) -> PathBuf {
+        self.root.join(TOOLS_DIR)
+    }
+
+    /// Directory of the environment for `key`.
+    pub fn env_dir(&self, key: &ToolEnvKey) -> PathBuf {
+        self.tools_dir().join(&key.id)
+    }
+
+    /// The virtual environment for `key`.
+    pub fn venv_path(&self, key: &ToolEnvKey) -> PathBuf {
+        self.env_dir(key).join("venv")
+    }
+
+    /// The cached environment for `key`, if it was fully installed and its
+    /// interpreter still exists.
+    pub fn get(&self, key: &ToolEnvKey) -> Option<ToolEnvInfo> {
+        let info = read_info(&self.env_dir(key))?;
+        let python = if cfg!(windows) {
+            self.venv_path(key).join("Scripts").join("python.exe")
+        } else {
+            self.venv_path(key).join("bin").join("python")
+        };
+        (info.matches(key) && python.exists()).then_some(info)
+    }
+
+    /// Mark the environment for `key` as installed, with `version` of the
+    /// package in it.
+    pub fn record(&self, key: &ToolEnvKey, version: Option<String>) -> Result<ToolEnvInfo> {
+        let now = now_secs();
+        let info = ToolEnvInfo {
+            package: key.package.clone(),
+            requirement: key.requirement.clone(),
+            python_version: key.python_version.clone(),
+            version,
+            created_at: now,
+            last_used: now,
+        };
+        write_info(&self.env_dir(key), &info)?;
+        Ok(info)
+    }
+
+    /// Update the last-used time of the environment for `key`.
+    pub fn touch(&self, key: &ToolEnvKey) -> Result<()> {
+        let dir = self.env_dir(key);
+        if let Some(mut info) = read_info(&dir) {
+            info.last_used = now_secs();
+            write_info(&dir, &info)?;
+        }
+        Ok(())
+    }
+
+    /// Acquire an exclusive lock on the environment for `key`, so concurrent
+    /// runs of the same tool do not build it twice.
+    pub fn lock(&self, key: &ToolEnvKey) -> Result<ToolEnvLock> {
+        let dir = self.env_dir(key);
+        fs::create_dir_all(&dir).map_err(io_error(&dir))?;
+        let lock_path = dir.join(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .open(&lock_path)
+            .map_err(io_error(&lock_path))?;
+        fs2::FileExt::lock_exclusive(&file).map_err(io_error(&lock_path))?;
+        Ok(ToolEnvLock { _file: file })
+    }
+
+    /// Delete the virtual environment and metadata for `key`, keeping the
+    /// directory (and its lock file) for a rebuild.
+    pub fn clear(&self, key: &ToolEnvKey) -> Result<()> {
+        let dir = self.env_dir(key);
+        let info = dir.join(INFO_FILE);
+        if info.exists() {
+            fs::remove_file(&info).map_err(io_error(&info))?;
+        }
+        let venv = self.venv_path(key);
+        if venv.exists() {
+            fs::remove_dir_all(&venv).map_err(io_error(&venv))?;
+        }
+        Ok(())
+    }
+
+    /// Every installed tool environment, sorted by package, requirement and
+    /// Python version.
+    pub fn list(&self) -> Result<Vec<ToolEnv>> {
+        let dir = self.tools_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut envs = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(io_error(&dir))? {
+            let path = entry.map_err(io_error(&dir))?.path();
+            if let Some(info) = read_info(&path) {
+                envs.push(ToolEnv {
+                    info,
+                    size_bytes: dir_size(&path),
+                    path,
+                });
+            }
+        }
+        envs.sort_by(|a, b| {
+            (&a.info.package, &a.info.requirement, &a.info.python_version).cmp(&(
+                &b.info.package,
+                &b.info.requirement,
+                &b.info.python_version,
+            ))
+        });
+        Ok(envs)
+    }
+
+    /// Remove every environment of `package` (all requirements and Python
+    /// versions). Returns the removed environments.
+    pub fn remove(&self, package: &str) -> Result<Vec<ToolEnv>> {
+        let package = normalize_project_name(package_name(package));
+        let removed: Vec<ToolEnv> = self
+            .list()?
+            .into_iter()
+            .filter(|env| env.info.package == package)
+            .collect();
+        for env in &removed {
+            fs::remove_dir_all(&env.path).map_err(io_error(&env.path))?;
+        }
+        Ok(removed)
+    }
+}
+
+/// Guard for a tool environment lock file.
+#[derive(Debug)]
+pub struct ToolEnvLock {
+    _file: fs::File,
+}
+
+/// Installed version of `package` in `venv`.
+pub fn installed_version(venv: &Path, package: &str) -> Option<String> {
+    let site_packages = crate::env_clean::site_packages_dir(venv)?;
+    crate::dist_info::find(&site_packages, package_name(package)).map(|dist| dist.version)
+}
+
+/// What `pybun x` runs in a tool environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolCommand {
+    /// A `[console_scripts]` entry point declared by the package.
+    EntryPoint(EntryPoint),
+    /// An executable the package installed without declaring an entry point.
+    Script(String),
+    /// `python -m MODULE`, for packages that install no commands.
+    Module(String),
+}
+
+impl ToolCommand {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::EntryPoint(entry) => &entry.name,
+            Self::Script(name) | Self::Module(name) => name,
+        }
+    }
+}
+
+/// The command of `package` to run in `venv`, read from the installed
+/// `entry_points.txt` and `RECORD`.
+///
+/// An explicit `command` (`pybun x --from httpie http`) must be one of the
+/// package's commands. Without one, the command named after the package is
+/// used, else the package's only command; a package without commands runs
+/// as `python -m PACKAGE`. Anything else is ambiguous and fails with
+/// [`ToolEnvError::UnknownCommand`] listing the available commands.
+pub fn resolve_command(venv: &Path, package: &str, command: Option<&str>) -> Result<ToolCommand> {
+    let package = package_name(package);
+    let dist = crate::env_clean::site_packages_dir(venv)
+        .and_then(|site_packages| crate::dist_info::find(&site_packages, package));
+    let (entry_points, scripts) = match &dist {
+        Some(dist) => (
+            entry_points::declared(&dist.path, &dist.name, "console_scripts"),
+            entry_points::installed_scripts(&dist.path),
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
+
+    let wanted = command.unwrap_or(package);
+    let matches = |name: &str| match command {
+        Some(command) => name == command,
+        None => normalize_project_name(name) == normalize_project_name(package),
+    };
+    if let Some(entry) = entry_points.iter().find(|entry| matches(&entry.name)) {
+        return Ok(ToolCommand::EntryPoint(entry.clone()));
+    }
+    if let Some(script) = scripts.iter().find(|script| matches(script)) {
+        return Ok(ToolCommand::Script(script.clone()));
+    }
+
+    let mut available: Vec<String> = entry_points.iter().map(|e| e.name.clone()).collect();
+    for script in &scripts {
+        if !available.contains(script) {
+            available.push(script.clone());
+        }
+    }
+    if command.is_none() {
+        match (entry_points.as_slice(), available.as_slice()) {
+            (_, []) => return Ok(ToolCommand::Module(package.to_string())),
+            ([entry], [_]) => return Ok(ToolCommand::EntryPoint(entry.clone())),
+            ([], [script]) => return Ok(ToolCommand::Script(script.clone())),
+            _ => {}
+        }
+    }
+    Err(ToolEnvError::UnknownCommand {
+        package: package.to_string(),
+        command: wanted.to_string(),
+        available,
+    })
+}
+
+fn read_info(dir: &Path) -> Option<ToolEnvInfo> {
+    let content = fs::read_to_string(dir.join(INFO_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_info(dir: &Path, info: &ToolEnvInfo) -> Result<()> {
+    let path = dir.join(INFO_FILE);
+    fs::write(&path, serde_json::to_string_pretty(info)?).map_err(io_error(&path))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(cache: &ToolEnvCache, key: &ToolEnvKey, version: &str) {
+        let bin = if cfg!(windows) { "Scripts" } else { "bin" };
+        let python = if cfg!(windows) {
+            "python.exe"
+        } else {
+            "python"
+        };
+        let bin_dir = cache.venv_path(key).join(bin);
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join(python), b"").unwrap();
+        cache.record(key, Some(version.to_string())).unwrap();
+    }
+
+    #[test]
+    fn keys_separate_requirements_and_pythons() {
+        let ruff = ToolEnvKey::new("Ruff", "Ruff", "3.12.4");
+        assert_eq!(ruff.package, "ruff");
+        assert!(ruff.id.starts_with("ruff-"));
+        assert_eq!(ruff, ToolEnvKey::new("ruff", " ruff ", "3.12.4"));
+        assert_ne!(ruff.id, ToolEnvKey::new("ruff", "ruff==0.5.0", "3.12.4").id);
+        assert_ne!(ruff.id, ToolEnvKey::new("ruff", "ruff", "3.11.9").id);
+        assert_eq!(
+            ToolEnvKey::new("black[d]", "black[d]", "3.12").package,
+            "black"
+        );
+    }
+
+    #[test]
+    fn only_recorded_environments_are_cached() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = ToolEnvCache::with_root(temp.path());
+        let key = ToolEnvKey::new("ruff", "ruff", "3.12.4");
+        assert!(cache.get(&key).is_none());
+
+        // A venv without tool.json is a build that did not finish.
+        let _lock = cache.lock(&key).unwrap();
+        fs::create_dir_all(cache.venv_path(&key).join("bin")).unwrap();
+        assert!(cache.get(&key).is_none());
+
+        install(&cache, &key, "0.5.0");
+        assert_eq!(cache.get(&key).unwrap().version.as_deref(), Some("0.5.0"));
+
+        cache.clear(&key).unwrap();
+        assert!(cache.get(&key).is_none());
+        assert!(cache.env_dir(&key).join(".lock").exists());
+    }
+
+    #[test]
+    fn list_and_remove_by_package() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = ToolEnvCache::with_root(temp.path());
+        let ruff = ToolEnvKey::new("ruff", "ruff", "3.12.4");
+        let ruff_311 = ToolEnvKey::new("ruff", "ruff", "3.11.9");
+        let black = ToolEnvKey::new("black", "black==24.1.0", "3.12.4");
+        for (key, version) in [(&ruff, "0.5.0"), (&ruff_311, "0.4.0"), (&black, "24.1.0")] {
+            install(&cache, key, version);
+        }
+
+        let listed: Vec<_> = cache
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|env| (env.info.package, env.info.python_version))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("black".to_string(), "3.12.4".to_string()),
+                ("ruff".to_string(), "3.11.9".to_string()),
+                ("ruff".to_string(), "3.12.4".to_string()),
+            ]
+        );
+
+        let removed = cache.remove("Ruff").unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(!cache.env_dir(&ruff).exists());
+        assert!(cache.get(&black).is_some());
+        assert!(cache.remove("ruff").unwrap().is_empty());
+    }
+
+    /// A venv with `package` installed, declaring `entry_points` and
+    /// recording `scripts` in `bin/`.
+    fn venv_with(dir: &Path, package: &str, entry_points: &str, scripts: &[&str]) -> PathBuf {
+        let dist_info = dir
+            .join("lib/python3.12/site-packages")
+            .join(format!("{package}-1.0.0.dist-info"));
+        fs::create_dir_all(&dist_info).unwrap();
+        if !entry_points.is_empty() {
+            fs::write(dist_info.join("entry_points.txt"), entry_points).unwrap();
+        }
+        let record: String = scripts
+            .iter()
+            .map(|script| format!("../../../bin/{script},,\n"))
+            .collect();
+        fs::write(dist_info.join("RECORD"), record).unwrap();
+        dir.to_path_buf()
+    }
+
+    #[test]
+    fn resolves_commands_from_installed_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let httpie = venv_with(
+            &temp.path().join("httpie"),
+            "httpie",
+            "[console_scripts]\nhttp = httpie.__main__:main\nhttps = httpie.__main__:main\n",
+            &["http", "https"],
+        );
+        let http = resolve_command(&httpie, "httpie", Some("http")).unwrap();
+        assert!(
+            matches!(&http, ToolCommand::EntryPoint(e) if e.target() == "httpie.__main__:main")
+        );
+        assert_eq!(http.name(), "http");
+
+        // No command named after the package, and more than one to pick from.
+        let err = resolve_command(&httpie, "httpie", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "httpie has no command `httpie` (available: http, https)"
+        );
+        assert!(resolve_command(&httpie, "httpie", Some("httpx")).is_err());
+
+        // A single command is used whatever its name; plain scripts count.
+        let single = venv_with(&temp.path().join("single"), "Demo_Tool", "", &["demo"]);
+        assert_eq!(
+            resolve_command(&single, "demo-tool", None).unwrap(),
+            ToolCommand::Script("demo".into())
+        );
+
+        let module = venv_with(&temp.path().join("module"), "cowsay", "", &[]);
+        assert_eq!(
+            resolve_command(&module, "cowsay[fun]", None).unwrap(),
+            ToolCommand::Module("cowsay".into())
+        );
+    }
+}
@@ -0,0 +1,562 @@
+//! Supervised runs of the external tools PyBun shells out to (pip, uv, git,
+//! podman/docker).
+//!
+//! Every run is captured and timed. A failure is classified as:
+//!
+//! - `not_found`: the program does not exist;
+//! - `timed_out`: it ran longer than the timeout and was killed;
+//! - `transient`: its stderr reports a network or registry hiccup
+//!   (connection reset, DNS failure, HTTP 5xx/429, ...);
+//! - `failed`: anything else.
+//!
+//! Idempotent runs are retried on `transient` and `timed_out` failures, with
+//! exponential backoff. The attempts are recorded in a [`ToolLog`] that
+//! [`ToolError::context`] turns into the `context` of a diagnostic.
+//!
+//! `PYBUN_TOOL_RETRIES` (default 2) sets the number of retries and
+//! `PYBUN_TOOL_TIMEOUT` the per-attempt timeout in seconds (default: none).
+
+use crate::proc_exec::{ProcExecOutcome, spawn_with_input};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+pub const RETRIES_ENV: &str = "PYBUN_TOOL_RETRIES";
+pub const TIMEOUT_ENV: &str = "PYBUN_TOOL_TIMEOUT";
+
+const DEFAULT_RETRIES: u32 = 2;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Lines of stderr kept per attempt in the log.
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Stderr fragments (lowercase) of failures worth retrying.
+const TRANSIENT_MARKERS: [&str; 22] = [
+    "connection reset",
+    "connection refused",
+    "connection timed out",
+    "connection aborted",
+    "connection was closed",
+    "connecttimeout",
+    "read timed out",
+    "timed out",
+    "i/o timeout",
+    "tls handshake timeout",
+    "temporary failure in name resolution",
+    "could not resolve host",
+    "name or service not known",
+    "network is unreachable",
+    "remote end hung up unexpectedly",
+    "early eof",
+    "502 bad gateway",
+    "503 service unavailable",
+    "504 gateway timeout",
+    "429 too many requests",
+    "toomanyrequests",
+    "max retries exceeded",
+];
+
+/// Why a run failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureClass {
+    NotFound,
+    TimedOut,
+    Transient,
+    Failed,
+}
+
+impl FailureClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailureClass::NotFound => "not_found",
+            FailureClass::TimedOut => "timed_out",
+            FailureClass::Transient => "transient",
+            FailureClass::Failed => "failed",
+        }
+    }
+
+    fn is_retryable(self) -> bool {
+        matches!(self, FailureClass::TimedOut | FailureClass::Transient)
+    }
+}
+
+/// Classify a failed run from its stderr.
+pub fn classify(stderr: &str) -> FailureClass {
+    let stderr = stderr.to_ascii_lowercase();
+    if TRANSIENT_MARKERS
+        .iter()
+        .any(|marker| stderr.contains(marker))
+    {
+        FailureClass::Transient
+    } else {
+        FailureClass::Failed
+    }
+}
+
+/// One attempt of a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolAttempt {
+    pub attempt: u32,
+    pub duration_ms: u64,
+    /// `None` when the process was killed or never started.
+    pub exit_code: Option<i32>,
+    /// `None` for a successful attempt.
+    pub class: Option<FailureClass>,
+    /// The last lines of stderr.
+    pub stderr: String,
+}
+
+/// The attempts of a run, attached to diagnostics when it fails.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolLog {
+    pub tool: String,
+    pub command: Vec<String>,
+    pub attempts: Vec<ToolAttempt>,
+}
+
+impl ToolLog {
+    fn last_stderr(&self) -> &str {
+        self.attempts
+            .last()
+            .map(|attempt| attempt.stderr.as_str())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ToolError {
+    #[error("{tool} was not found ({program})")]
+    NotFound {
+        tool: String,
+        program: String,
+        log: Box<ToolLog>,
+    },
+    #[error("failed to run {tool}: {source}")]
+    Spawn {
+        tool: String,
+        source: std::io::Error,
+        log: Box<ToolLog>,
+    },
+    #[error("{tool} timed out after {}s{}", timeout.as_secs(), attempts_suffix(log))]
+    TimedOut {
+        tool: String,
+        timeout: Duration,
+        log: Box<ToolLog>,
+    },
+    #[error("{tool} failed (exit code {code}){}: {}", attempts_suffix(log), log.last_stderr())]
+    Failed {
+        tool: String,
+        code: i32,
+        class: FailureClass,
+        log: Box<ToolLog>,
+    },
+}
+
+fn attempts_suffix(log: &ToolLog) -> String {
+    match log.attempts.len() {
+        0 | 1 => String::new(),
+        n => format!(" after {n} attempts"),
+    }
+}
+
+impl ToolError {
+    pub fn class(&self) -> FailureClass {
+        match self {
+            ToolError::NotFound { .. } => FailureClass::NotFound,
+            ToolError::Spawn { .. } => FailureClass::Failed,
+            ToolError::TimedOut { .. } => FailureClass::TimedOut,
+            ToolError::Failed { class, .. } => *class,
+        }
+    }
+
+    pub fn log(&self) -> &ToolLog {
+        match self {
+            ToolError::NotFound { log, .. }
+            | ToolError::Spawn { log, .. }
+            | ToolError::TimedOut { log, .. }
+            | ToolError::Failed { log, .. } => log,
+        }
+    }
+
+    /// Stderr of the last attempt.
+    pub fn stderr(&self) -> &str {
+        self.log().last_stderr()
+    }
+
+    /// Diagnostic `context` describing the run: the class, the command and
+    /// every attempt.
+    pub fn context(&self) -> Value {
+        let log = self.log();
+        json!({
+            "tool": log.tool,
+            "class": self.class(),
+            "command": log.command,
+            "attempts": log.attempts,
+        })
+    }
+
+    /// The first `ToolError` in the source chain of `error`.
+    pub fn find<'a>(error: &'a (dyn std::error::Error + 'static)) -> Option<&'a ToolError> {
+        let mut current = Some(error);
+        while let Some(error) = current {
+            if let Some(tool) = error.downcast_ref::<ToolError>() {
+                return Some(tool);
+            }
+            current = error.source();
+        }
+        None
+    }
+}
+
+/// Successful run.
+#[derive(Debug)]
+pub struct ToolOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub log: ToolLog,
+}
+
+impl ToolOutput {
+    pub fn stdout_text(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).trim().to_string()
+    }
+}
+
+/// Retry and timeout settings of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolPolicy {
+    /// Extra attempts after the first, for idempotent runs.
+    pub retries: u32,
+    /// Per-attempt wall-clock timeout.
+    pub timeout: Option<Duration>,
+    /// Wait before the first retry; doubled for every further one.
+    pub backoff: Duration,
+}
+
+impl Default for ToolPolicy {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_RETRIES,
+            timeout: None,
+            backoff: DEFAULT_BACKOFF,
+        }
+    }
+}
+
+impl ToolPolicy {
+    /// The default policy with `PYBUN_TOOL_RETRIES`/`PYBUN_TOOL_TIMEOUT`
+    /// applied.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Some(retries) = env_number(RETRIES_ENV) {
+            policy.retries = retries as u32;
+        }
+        if let Some(secs) = env_number(TIMEOUT_ENV) {
+            policy.timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        policy
+    }
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
+
+/// A supervised run of an external tool.
+pub struct ToolRun<'a> {
+    tool: String,
+    command: &'a mut Command,
+    idempotent: bool,
+    policy: ToolPolicy,
+    input: Option<Vec<u8>>,
+}
+
+impl<'a> ToolRun<'a> {
+    /// Run `command` as `tool` (the name shown in errors), once, under
+    /// [`ToolPolicy::from_env`].
+    pub fn new(tool: impl Into<String>, command: &'a mut Command) -> Self {
+        Self {
+            tool: tool.into(),
+            command,
+            idempotent: false,
+            policy: ToolPolicy::from_env(),
+            input: None,
+        }
+    }
+
+    /// Retry transient failures and timeouts: running the command again
+    /// after a partial run is harmless.
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ToolPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Use `timeout` unless `PYBUN_TOOL_TIMEOUT` sets one.
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        if std::env::var_os(TIMEOUT_ENV).is_none() {
+            self.policy.timeout = Some(timeout);
+        }
+        self
+    }
+
+    /// Write `input` to the tool's stdin.
+    pub fn with_input(mut self, input: impl Into<Vec<u8>>) -> Self {
+        self.input = Some(input.into());
+        self
+    }
+
+    /// Run to success, or until a failure is not retryable or the retries
+    /// are used up.
+    pub fn run(self) -> Result<ToolOutput, ToolError> {
+        let mut log = ToolLog {
+            tool: self.tool.clone(),
+            command: command_line(self.command),
+            attempts: Vec::new(),
+        };
+        let attempts = if self.idempotent {
+            self.policy.retries + 1
+        } else {
+            1
+        };
+        let mut backoff = self.policy.backoff;
+        for attempt in 1..=attempts {
+            let started = Instant::now();
+            let outcome = spawn_with_input(
+                self.command,
+                self.policy.timeout,
+                true,
+                self.input.as_deref(),
+            );
+            let duration_ms = started.elapsed().as_millis() as u64;
+            let failure = match outcome {
+                Err(source) => {
+                    log.attempts.push(ToolAttempt {
+                        attempt,
+                        duration_ms,
+                        exit_code: None,
+                        class: Some(FailureClass::NotFound),
+                        stderr: source.to_string(),
+                    });
+                    if source.kind() == std::io::ErrorKind::NotFound {
+                        return Err(ToolError::NotFound {
+                            tool: self.tool,
+                            program: log.command.first().cloned().unwrap_or_default(),
+                            log: Box::new(log),
+                        });
+                    }
+                    if let Some(last) = log.attempts.last_mut() {
+                        last.class = Some(FailureClass::Failed);
+                    }
+                    return Err(ToolError::Spawn {
+                        tool: self.tool,
+                        source,
+                        log: Box::new(log),
+                    });
+                }
+                Ok(ProcExecOutcome::TimedOut) => {
+                    log.attempts.push(ToolAttempt {
+                        attempt,
+                        duration_ms,
+                        exit_code: None,
+                        class: Some(FailureClass::TimedOut),
+                        stderr: String::new(),
+                    });
+                    FailureClass::TimedOut
+                }
+                Ok(ProcExecOutcome::Completed {
+                    status,
+                    stdout,
+                    stderr,
+                }) => {
+                    let stdout = stdout.unwrap_or_default();
+                    let stderr = stderr.unwrap_or_default();
+                    let text = String::from_utf8_lossy(&stderr);
+                    let class = (!status.success()).then(|| classify(&text));
+                    log.attempts.push(ToolAttempt {
+                        attempt,
+                        duration_ms,
+                        exit_code: status.code(),
+                        class,
+                        stderr: tail(&text),
+                    });
+                    match class {
+                        None => {
+                            return Ok(ToolOutput {
+                                stdout,
+                                stderr,
+                                log,
+                            });
+                        }
+                        Some(class) => class,
+                    }
+                }
+            };
+            if !failure.is_retryable() || attempt == attempts {
+                break;
+            }
+            std::thread::sleep(backoff);
+            backoff *= 2;
+        }
+
+        let last = log.attempts.last().cloned();
+        Err(match last {
+            Some(ToolAttempt {
+                class: Some(FailureClass::TimedOut),
+                ..
+            }) => ToolError::TimedOut {
+                tool: self.tool,
+                timeout: self.policy.timeout.unwrap_or_default(),
+                log: Box::new(log),
+            },
+            last => ToolError::Failed {
+                tool: self.tool,
+                code: last.as_ref().and_then(|a| a.exit_code).unwrap_or(-1),
+                class: last.and_then(|a| a.class).unwrap_or(FailureClass::Failed),
+                log: Box::new(log),
+            },
+        })
+    }
+}
+
+fn command_line(command: &Command) -> Vec<String> {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect()
+}
+
+fn tail(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr.trim().lines().collect();
+    lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn quick() -> ToolPolicy {
+        ToolPolicy {
+            retries: 2,
+            timeout: None,
+            backoff: Duration::from_millis(1),
+        }
+    }
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    #[test]
+    fn classifies_network_failures_as_transient() {
+        assert_eq!(
+            classify("fatal: unable to access 'https://x/': Could not resolve host: x"),
+            FailureClass::Transient
+        );
+        assert_eq!(
+            classify("ERROR: HTTP error 503 Service Unavailable"),
+            FailureClass::Transient
+        );
+        assert_eq!(
+            classify("ERROR: No matching distribution found for nope"),
+            FailureClass::Failed
+        );
+    }
+
+    #[test]
+    fn retries_transient_failures_of_idempotent_runs() {
+        let temp = tempfile::tempdir().unwrap();
+        let marker = temp.path().join("attempted");
+        // Fails with a connection reset the first time, then succeeds.
+        let mut cmd = sh(&format!(
+            "if [ -e {0} ]; then echo ok; else touch {0}; echo 'Connection reset by peer' >&2; exit 1; fi",
+            marker.display()
+        ));
+        let output = ToolRun::new("flaky", &mut cmd)
+            .idempotent()
+            .with_policy(quick())
+            .run()
+            .unwrap();
+        assert_eq!(output.stdout_text(), "ok");
+        let classes: Vec<_> = output.log.attempts.iter().map(|a| a.class).collect();
+        assert_eq!(classes, [Some(FailureClass::Transient), None]);
+    }
+
+    #[test]
+    fn does_not_retry_permanent_or_non_idempotent_failures() {
+        let mut cmd = sh("echo 'No matching distribution' >&2; exit 1");
+        let error = ToolRun::new("pip", &mut cmd)
+            .idempotent()
+            .with_policy(quick())
+            .run()
+            .unwrap_err();
+        assert_eq!(error.class(), FailureClass::Failed);
+        assert_eq!(error.log().attempts.len(), 1);
+        assert_eq!(error.stderr(), "No matching distribution");
+
+        let mut cmd = sh("echo 'Connection reset by peer' >&2; exit 1");
+        let error = ToolRun::new("docker", &mut cmd)
+            .with_policy(quick())
+            .run()
+            .unwrap_err();
+        assert_eq!(error.class(), FailureClass::Transient);
+        assert_eq!(error.log().attempts.len(), 1);
+    }
+
+    #[test]
+    fn gives_up_after_the_retries() {
+        let mut cmd = sh("echo 'Temporary failure in name resolution' >&2; exit 128");
+        let error = ToolRun::new("git", &mut cmd)
+            .idempotent()
+            .with_policy(quick())
+            .run()
+            .unwrap_err();
+        assert_eq!(error.log().attempts.len(), 3);
+        assert!(
+            error
+                .to_string()
+                .starts_with("git failed (exit code 128) after 3 attempts"),
+            "{error}"
+        );
+        let context = error.context();
+        assert_eq!(context["class"], "transient");
+        assert_eq!(context["command"][0], "sh");
+        assert_eq!(context["attempts"][2]["exit_code"], 128);
+    }
+
+    #[test]
+    fn enforces_the_timeout() {
+        let mut cmd = sh("exec sleep 30");
+        let started = Instant::now();
+        let error = ToolRun::new("slow", &mut cmd)
+            .idempotent()
+            .with_policy(ToolPolicy {
+                retries: 1,
+                timeout: Some(Duration::from_millis(200)),
+                backoff: Duration::from_millis(1),
+            })
+            .run()
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(matches!(error, ToolError::TimedOut { .. }));
+        assert_eq!(error.log().attempts.len(), 2);
+    }
+
+    #[test]
+    fn reports_missing_programs() {
+        let mut cmd = Command::new("pybun-no-such-tool");
+        let error = ToolRun::new("uv", &mut cmd).run().unwrap_err();
+        assert_eq!(error.class(), FailureClass::NotFound);
+        let wrapped: Box<dyn std::error::Error> = Box::new(error);
+        assert!(ToolError::find(wrapped.as_ref()).is_some());
+    }
+}
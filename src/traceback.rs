@@ -201,7 +201,13 @@ fn map_exception_to_code(exception_type: &str, message: &str) -> String {
                 "E_RUNTIME_IMPORT_ERROR".to_string()
             }
         }
+        "SyntaxError" if message.contains("Non-UTF-8 code") => {
+            "E_RUNTIME_ENCODING_ERROR".to_string()
+        }
         "SyntaxError" | "IndentationError" | "TabError" => "E_RUNTIME_SYNTAX_ERROR".to_string(),
+        "UnicodeEncodeError" | "UnicodeDecodeError" | "UnicodeError" => {
+            "E_RUNTIME_ENCODING_ERROR".to_string()
+        }
         "TypeError" => "E_RUNTIME_TYPE_ERROR".to_string(),
         "AttributeError" => "E_RUNTIME_ATTRIBUTE_ERROR".to_string(),
         "PermissionError" => "E_RUNTIME_PERMISSION_DENIED".to_string(),
@@ -518,4 +524,22 @@ ValueError: deep error"#;
         let code = map_exception_to_code("ImportError", "cannot import name 'foo' from 'bar'");
         assert_eq!(code, "E_RUNTIME_IMPORT_ERROR");
     }
+
+    #[test]
+    fn map_unicode_errors_and_undeclared_source_encoding() {
+        assert_eq!(
+            map_exception_to_code(
+                "UnicodeEncodeError",
+                "'ascii' codec can't encode character '\\xe9' in position 0"
+            ),
+            "E_RUNTIME_ENCODING_ERROR"
+        );
+        assert_eq!(
+            map_exception_to_code(
+                "SyntaxError",
+                "Non-UTF-8 code starting with '\\xe9' in file app.py on line 1, but no encoding declared"
+            ),
+            "E_RUNTIME_ENCODING_ERROR"
+        );
+    }
 }
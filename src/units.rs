@@ -0,0 +1,326 @@
+//! Canonical units and humanized siblings for JSON output.
+//!
+//! Every size and duration in a JSON `detail` payload uses one canonical,
+//! machine-readable field:
+//!
+//! | quantity | canonical field  | value                           |
+//! |----------|------------------|---------------------------------|
+//! | size     | `<name>_bytes`   | integer bytes                   |
+//! | duration | `<name>_ms`      | milliseconds (may be fractional)|
+//!
+//! [`normalize`] runs over the payload just before the envelope is printed
+//! and adds a `<name>_human` string next to each canonical field (e.g.
+//! `freed_bytes` -> `freed_human`, `duration_ms` -> `duration_human`).
+//! Humanized strings are for display only: they always use `.` as the
+//! decimal separator and English unit names regardless of `LANG`/`LC_ALL`,
+//! so parsers must read the canonical field instead. Fields ending in
+//! `_at_ms` are timestamps and are never humanized.
+//!
+//! Field names used before this convention are listed in [`LEGACY_FIELDS`].
+//! During the deprecation window commands keep emitting them and
+//! [`normalize`] derives the canonical field from them, so both are present.
+//! `gc` printed `freed_human`, `size_before_human` and `size_after_human`
+//! before humanized siblings existed; they are legacy fields too and are
+//! kept even when `PYBUN_HUMANIZE=0` omits the other siblings.
+//! Environment switches:
+//!
+//! - `PYBUN_HUMANIZE=0` omits the `_human` siblings.
+//! - `PYBUN_LEGACY_FIELDS=0` omits the legacy names, to test consumers
+//!   against the post-deprecation output.
+
+use serde_json::{Map, Number, Value};
+use std::time::Duration;
+
+/// How a legacy field relates to its canonical replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyUnit {
+    /// Same value, new name (e.g. `size_before` -> `size_before_bytes`).
+    Rename,
+    /// Integer microseconds (legacy) vs. milliseconds (canonical).
+    Micros,
+    /// Humanized size string (legacy) of the canonical byte count.
+    Human,
+}
+
+/// A deprecated field name and its canonical replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyField {
+    pub legacy: &'static str,
+    pub canonical: &'static str,
+    pub unit: LegacyUnit,
+}
+
+/// Deprecated field names still emitted for compatibility.
+pub const LEGACY_FIELDS: &[LegacyField] = &[
+    LegacyField {
+        legacy: "duration_us",
+        canonical: "duration_ms",
+        unit: LegacyUnit::Micros,
+    },
+    LegacyField {
+        legacy: "size_before",
+        canonical: "size_before_bytes",
+        unit: LegacyUnit::Rename,
+    },
+    LegacyField {
+        legacy: "size_after",
+        canonical: "size_after_bytes",
+        unit: LegacyUnit::Rename,
+    },
+    LegacyField {
+        legacy: "freed_human",
+        canonical: "freed_bytes",
+        unit: LegacyUnit::Human,
+    },
+    LegacyField {
+        legacy: "size_before_human",
+        canonical: "size_before_bytes",
+        unit: LegacyUnit::Human,
+    },
+    LegacyField {
+        legacy: "size_after_human",
+        canonical: "size_after_bytes",
+        unit: LegacyUnit::Human,
+    },
+];
+
+/// Controls what [`normalize`] adds to a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitOptions {
+    /// Add `<name>_human` siblings.
+    pub humanize: bool,
+    /// Keep emitting [`LEGACY_FIELDS`].
+    pub legacy_fields: bool,
+}
+
+impl Default for UnitOptions {
+    fn default() -> Self {
+        Self {
+            humanize: true,
+            legacy_fields: true,
+        }
+    }
+}
+
+impl UnitOptions {
+    /// Read `PYBUN_HUMANIZE` / `PYBUN_LEGACY_FIELDS`.
+    pub fn from_env() -> Self {
+        let enabled = |key: &str| {
+            std::env::var(key)
+                .map(|v| !matches!(v.trim(), "0" | "false" | "no" | "off"))
+                .unwrap_or(true)
+        };
+        Self {
+            humanize: enabled("PYBUN_HUMANIZE"),
+            legacy_fields: enabled("PYBUN_LEGACY_FIELDS"),
+        }
+    }
+}
+
+/// Format a byte count with binary multiples (`1.50 MB` = 1.5 * 1024^2).
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 4] = [
+        ("TB", 1 << 40),
+        ("GB", 1 << 30),
+        ("MB", 1 << 20),
+        ("KB", 1 << 10),
+    ];
+    for (unit, size) in UNITS {
+        if bytes >= size {
+            return format!("{:.2} {unit}", bytes as f64 / size as f64);
+        }
+    }
+    format!("{bytes} B")
+}
+
+/// Format a duration with a unit matched to its magnitude.
+pub fn format_duration(duration: Duration) -> String {
+    format_millis(duration.as_secs_f64() * 1000.0)
+}
+
+/// Format a millisecond value (`850 µs`, `12.3 ms`, `4.20 s`, `2m 05s`).
+pub fn format_millis(ms: f64) -> String {
+    let ms = ms.max(0.0);
+    if ms < 1.0 {
+        format!("{:.0} µs", ms * 1000.0)
+    } else if ms < 100.0 {
+        format!("{ms:.1} ms")
+    } else if ms < 1000.0 {
+        format!("{ms:.0} ms")
+    } else if ms < 60_000.0 {
+        format!("{:.2} s", ms / 1000.0)
+    } else {
+        let secs = (ms / 1000.0).round() as u64;
+        let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+        if hours > 0 {
+            format!("{hours}h {minutes:02}m {seconds:02}s")
+        } else {
+            format!("{minutes}m {seconds:02}s")
+        }
+    }
+}
+
+fn millis_value(ms: f64) -> Value {
+    if ms.fract() == 0.0 && ms <= u64::MAX as f64 {
+        Value::from(ms as u64)
+    } else {
+        Number::from_f64(ms)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Add canonical counterparts for legacy fields and humanized siblings
+/// throughout `value` (see the module docs). Existing fields are never overwritten.
+pub fn normalize(value: &mut Value, options: UnitOptions) {
+    match value {
+        Value::Object(object) => {
+            for child in object.values_mut() {
+                normalize(child, options);
+            }
+            normalize_object(object, options);
+        }
+        Value::Array(items) => {
+            for item in items {
+                normalize(item, options);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn normalize_object(object: &mut Map<String, Value>, options: UnitOptions) {
+    for field in LEGACY_FIELDS {
+        // Derived from the canonical field rather than the other way round,
+        // and only while legacy fields are on; otherwise it is an ordinary
+        // humanized sibling.
+        if field.unit == LegacyUnit::Human {
+            if options.legacy_fields
+                && !object.contains_key(field.legacy)
+                && let Some(bytes) = object.get(field.canonical).and_then(Value::as_u64)
+            {
+                object.insert(field.legacy.to_string(), Value::String(format_bytes(bytes)));
+            }
+            continue;
+        }
+        let Some(legacy) = object.get(field.legacy) else {
+            continue;
+        };
+        if !object.contains_key(field.canonical) {
+            let canonical = match field.unit {
+                LegacyUnit::Rename => legacy.clone(),
+                LegacyUnit::Micros => match legacy.as_f64() {
+                    Some(micros) => millis_value(micros / 1000.0),
+                    None => continue,
+                },
+                // Derived from the canonical field above.
+                LegacyUnit::Human => continue,
+            };
+            object.insert(field.canonical.to_string(), canonical);
+        }
+        if !options.legacy_fields {
+            object.remove(field.legacy);
+        }
+    }
+
+    if !options.humanize {
+        return;
+    }
+    let additions: Vec<(String, String)> = object
+        .iter()
+        .filter_map(|(key, value)| {
+            if let Some(stem) = key.strip_suffix("_bytes") {
+                Some((stem, format_bytes(value.as_u64()?)))
+            } else if let Some(stem) = key.strip_suffix("_ms") {
+                if stem.ends_with("_at") {
+                    return None;
+                }
+                Some((stem, format_millis(value.as_f64()?)))
+            } else {
+                None
+            }
+        })
+        .map(|(stem, human)| (format!("{stem}_human"), human))
+        .filter(|(key, _)| !object.contains_key(key))
+        .collect();
+    object.extend(additions.into_iter().map(|(k, v)| (k, Value::String(v))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn formats_sizes_and_durations() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.50 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.00 GB");
+        assert_eq!(format_millis(0.85), "850 µs");
+        assert_eq!(format_millis(12.34), "12.3 ms");
+        assert_eq!(format_millis(4200.0), "4.20 s");
+        assert_eq!(format_millis(125_000.0), "2m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1h 02m 05s");
+    }
+
+    #[test]
+    fn adds_human_siblings_without_overwriting() {
+        let mut detail = json!({
+            "freed_bytes": 2048,
+            "freed_human": "custom",
+            "nested": [{ "duration_ms": 1.5, "snapshot_at_ms": 1_700_000_000_000u64 }],
+        });
+        normalize(&mut detail, UnitOptions::default());
+        assert_eq!(detail["freed_human"], "custom");
+        assert_eq!(detail["nested"][0]["duration_human"], "1.5 ms");
+        assert!(detail["nested"][0].get("snapshot_at_human").is_none());
+    }
+
+    #[test]
+    fn legacy_fields_gain_canonical_counterparts() {
+        let mut legacy = json!({ "duration_us": 1500, "size_before": 10 });
+        normalize(&mut legacy, UnitOptions::default());
+        assert_eq!(legacy["duration_us"], 1500);
+        assert_eq!(legacy["duration_ms"], 1.5);
+        assert_eq!(legacy["duration_human"], "1.5 ms");
+        assert_eq!(legacy["size_before_bytes"], 10);
+        assert_eq!(legacy["size_before_human"], "10 B");
+
+        let mut canonical = json!({ "duration_ms": 2.25 });
+        normalize(&mut canonical, UnitOptions::default());
+        assert!(canonical.get("duration_us").is_none());
+
+        let mut strict = json!({ "duration_us": 1500 });
+        normalize(
+            &mut strict,
+            UnitOptions {
+                humanize: false,
+                legacy_fields: false,
+            },
+        );
+        assert_eq!(strict, json!({ "duration_ms": 1.5 }));
+    }
+
+    #[test]
+    fn legacy_gc_sizes_stay_humanized_until_legacy_fields_are_off() {
+        let plain = UnitOptions {
+            humanize: false,
+            legacy_fields: true,
+        };
+        let mut gc = json!({ "freed_bytes": 2048, "size_after_bytes": 1, "download_bytes": 5 });
+        normalize(&mut gc, plain);
+        assert_eq!(gc["freed_human"], "2.00 KB");
+        assert_eq!(gc["size_after_human"], "1 B");
+        assert!(gc.get("download_human").is_none());
+
+        let mut strict = json!({ "freed_bytes": 2048 });
+        normalize(
+            &mut strict,
+            UnitOptions {
+                legacy_fields: false,
+                ..plain
+            },
+        );
+        assert_eq!(strict, json!({ "freed_bytes": 2048 }));
+    }
+}
@@ -0,0 +1,326 @@
+//! Staged rollout, local update history, and rollback for `pybun self`.
+//!
+//! Every installation has a stable *cohort* in `0..100`, generated once and
+//! stored in `$PYBUN_HOME/self-update/cohort`. A release manifest may carry
+//! a `rollout` section:
+//!
+//! ```json
+//! "rollout": { "percentage": 25, "halted": false }
+//! ```
+//!
+//! Installations whose cohort is below `percentage` take the update; the
+//! rest defer until the percentage grows. `halted: true` is a kill switch
+//! that stops all updates to that release. The cohort is sent to the
+//! manifest server as the `X-PyBun-Cohort` header.
+//!
+//! Before a binary is replaced, the running binary is copied to
+//! `$PYBUN_HOME/self-update/previous/` and the update is appended to
+//! `history.json`, so `pybun self rollback` can restore it without network
+//! access. A version that was rolled back is held back from future updates
+//! until `pybun self update --force` is used.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Header carrying the installation's cohort on manifest requests.
+pub const COHORT_HEADER: &str = "X-PyBun-Cohort";
+
+/// Rollout controls published with a release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseRollout {
+    /// Share of cohorts (0-100) that should receive the release.
+    #[serde(default = "default_percentage")]
+    pub percentage: u8,
+    /// Kill switch: no installation should update to this release.
+    #[serde(default)]
+    pub halted: bool,
+}
+
+fn default_percentage() -> u8 {
+    100
+}
+
+/// Whether this installation should take a release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RolloutDecision {
+    Eligible,
+    /// Cohort is outside the current rollout percentage.
+    Deferred {
+        cohort: u8,
+        percentage: u8,
+    },
+    /// The release was halted by the kill switch.
+    Halted,
+}
+
+impl RolloutDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RolloutDecision::Eligible => "eligible",
+            RolloutDecision::Deferred { .. } => "deferred",
+            RolloutDecision::Halted => "halted",
+        }
+    }
+}
+
+impl ReleaseRollout {
+    pub fn decide(&self, cohort: u8) -> RolloutDecision {
+        if self.halted {
+            RolloutDecision::Halted
+        } else if cohort < self.percentage.min(100) {
+            RolloutDecision::Eligible
+        } else {
+            RolloutDecision::Deferred {
+                cohort,
+                percentage: self.percentage,
+            }
+        }
+    }
+}
+
+/// Decide for an optional rollout section (absent means fully rolled out).
+pub fn decide(rollout: Option<&ReleaseRollout>, cohort: u8) -> RolloutDecision {
+    rollout.map_or(RolloutDecision::Eligible, |rollout| rollout.decide(cohort))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryAction {
+    Update,
+    Rollback,
+}
+
+/// One binary replacement performed by `pybun self`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub action: HistoryAction,
+    pub from_version: String,
+    pub to_version: String,
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub install_path: PathBuf,
+    /// Copy of the binary that was replaced, if kept.
+    #[serde(default)]
+    pub backup_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    #[serde(default)]
+    entries: Vec<HistoryEntry>,
+    #[serde(default)]
+    held_back: Vec<String>,
+}
+
+/// Local self-update state under `$PYBUN_HOME/self-update`.
+#[derive(Debug, Clone)]
+pub struct UpdateStore {
+    root: PathBuf,
+}
+
+impl Default for UpdateStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UpdateStore {
+    pub fn new() -> Self {
+        Self::with_root(crate::env::pybun_home().join("self-update"))
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.root.join("history.json")
+    }
+
+    /// This installation's cohort in `0..100`. `PYBUN_UPDATE_COHORT`
+    /// overrides the stored value.
+    pub fn cohort(&self) -> io::Result<u8> {
+        if let Some(cohort) = std::env::var("PYBUN_UPDATE_COHORT")
+            .ok()
+            .and_then(|v| v.trim().parse::<u8>().ok())
+        {
+            return Ok(cohort.min(99));
+        }
+        let path = self.root.join("cohort");
+        if let Some(cohort) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|v| v.trim().parse::<u8>().ok())
+            .filter(|c| *c < 100)
+        {
+            return Ok(cohort);
+        }
+        let cohort = (uuid::Uuid::new_v4().as_u128() % 100) as u8;
+        fs::create_dir_all(&self.root)?;
+        fs::write(&path, format!("{cohort}\n"))?;
+        Ok(cohort)
+    }
+
+    fn load(&self) -> HistoryFile {
+        fs::read(self.history_path())
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, file: &HistoryFile) -> io::Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let path = self.history_path();
+        let tmp = path.with_extension("json.tmp");
+        fs::write(
+            &tmp,
+            serde_json::to_vec_pretty(file).map_err(io::Error::other)?,
+        )?;
+        fs::rename(tmp, path)
+    }
+
+    /// Update history, oldest first.
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        self.load().entries
+    }
+
+    /// Versions skipped by `self update` because they were rolled back.
+    pub fn held_back(&self) -> Vec<String> {
+        self.load().held_back
+    }
+
+    pub fn is_held_back(&self, version: &str) -> bool {
+        self.held_back().iter().any(|v| v == version)
+    }
+
+    /// Copy the binary at `install_path` (running `version`) aside so it
+    /// can be restored later. Only the most recent copy is kept.
+    pub fn keep_previous(&self, install_path: &Path, version: &str) -> io::Result<PathBuf> {
+        let dir = self.root.join("previous");
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        let dest_dir = dir.join(version);
+        fs::create_dir_all(&dest_dir)?;
+        let file_name = install_path
+            .file_name()
+            .ok_or_else(|| io::Error::other("install path has no file name"))?;
+        let dest = dest_dir.join(file_name);
+        fs::copy(install_path, &dest)?;
+        Ok(dest)
+    }
+
+    /// Append an entry to the history. Recording a rollback holds back the
+    /// version that was rolled away from; recording an update releases it.
+    pub fn record(&self, entry: HistoryEntry) -> io::Result<()> {
+        let mut file = self.load();
+        match entry.action {
+            HistoryAction::Rollback => {
+                if !file.held_back.contains(&entry.from_version) {
+                    file.held_back.push(entry.from_version.clone());
+                }
+            }
+            HistoryAction::Update => file.held_back.retain(|v| v != &entry.to_version),
+        }
+        file.entries.push(entry);
+        self.save(&file)
+    }
+
+    /// The update `self rollback` would revert: the latest entry, if it is
+    /// an update whose backup still exists.
+    pub fn rollback_candidate(&self) -> Option<HistoryEntry> {
+        let entry = self.history().pop()?;
+        let backup_exists = entry.backup_path.as_deref().is_some_and(Path::exists);
+        (entry.action == HistoryAction::Update && backup_exists).then_some(entry)
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollout_decision_respects_percentage_and_kill_switch() {
+        let rollout = ReleaseRollout {
+            percentage: 25,
+            halted: false,
+        };
+        assert_eq!(rollout.decide(0), RolloutDecision::Eligible);
+        assert_eq!(rollout.decide(24), RolloutDecision::Eligible);
+        assert_eq!(
+            rollout.decide(25),
+            RolloutDecision::Deferred {
+                cohort: 25,
+                percentage: 25
+            }
+        );
+        let halted = ReleaseRollout {
+            percentage: 100,
+            halted: true,
+        };
+        assert_eq!(halted.decide(0), RolloutDecision::Halted);
+        assert_eq!(decide(None, 99), RolloutDecision::Eligible);
+    }
+
+    #[test]
+    fn cohort_is_generated_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = UpdateStore::with_root(temp.path());
+        let first = store.cohort().unwrap();
+        assert!(first < 100);
+        assert_eq!(store.cohort().unwrap(), first);
+    }
+
+    #[test]
+    fn rollback_candidate_and_held_back_versions() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = UpdateStore::with_root(temp.path().join("state"));
+        let binary = temp.path().join("pybun");
+        fs::write(&binary, b"v1").unwrap();
+        let backup = store.keep_previous(&binary, "1.0.0").unwrap();
+        assert_eq!(fs::read(&backup).unwrap(), b"v1");
+
+        let update = HistoryEntry {
+            action: HistoryAction::Update,
+            from_version: "1.0.0".into(),
+            to_version: "2.0.0".into(),
+            channel: Some("stable".into()),
+            timestamp: 1,
+            install_path: binary.clone(),
+            backup_path: Some(backup),
+        };
+        store.record(update.clone()).unwrap();
+        assert_eq!(store.rollback_candidate(), Some(update));
+
+        store
+            .record(HistoryEntry {
+                action: HistoryAction::Rollback,
+                from_version: "2.0.0".into(),
+                to_version: "1.0.0".into(),
+                channel: None,
+                timestamp: 2,
+                install_path: binary,
+                backup_path: None,
+            })
+            .unwrap();
+        assert!(store.rollback_candidate().is_none());
+        assert!(store.is_held_back("2.0.0"));
+        assert_eq!(store.history().len(), 2);
+    }
+}
@@ -1,15 +1,23 @@
-//! Cache for downloaded wheels, organized by package name.
+//! Content-addressed store for downloaded wheels.
 //!
 //! Layout:
-//! ~/.cache/pybun/packages/
-//!   {package_name}/
-//!     {filename}.whl
+//! ~/.cache/pybun/wheels/
+//!   {sha256[..2]}/{sha256}/{filename}.whl
 //!
-//! Uses content-addressable storage concepts where possible (SHA256 check).
+//! Wheels are keyed by the SHA-256 of their contents, checked when they are
+//! fetched, so a stored wheel is trusted without re-hashing. Callers that
+//! need a wheel somewhere else (`pybun install`'s artifacts directory)
+//! hard-link it out of the store, falling back to a copy across
+//! filesystems, so each wheel is downloaded once per machine. Wheels in the
+//! older per-package layout (`packages/{name}/{filename}`) are moved into
+//! the store the first time their hash is verified.
 
 use crate::cache::Cache;
+use crate::cache_stats::{self, CacheStats};
 use crate::downloader::Downloader;
-use std::path::PathBuf;
+use crate::security::sha256_file as file_sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -33,6 +41,11 @@ pub struct WheelCache {
 impl WheelCache {
     pub fn new() -> Result<Self> {
         let cache = Cache::new().map_err(WheelCacheError::Cache)?;
+        Self::with_cache(cache)
+    }
+
+    /// Wheel store inside `cache` (useful for testing).
+    pub fn with_cache(cache: Cache) -> Result<Self> {
         cache.ensure_dirs().map_err(WheelCacheError::Cache)?;
         Ok(Self {
             cache,
@@ -40,12 +53,24 @@ impl WheelCache {
         })
     }
 
-    /// Get a wheel from the cache, downloading it if necessary.
+    /// The stored wheel with this hash, in the writable root or the
+    /// read-only shared layer.
+    pub fn find(&self, sha256: &str, filename: &str) -> Option<PathBuf> {
+        self.cache
+            .find(Cache::stored_wheel_relative(
+                &normalize_hash(sha256),
+                filename,
+            ))
+            .filter(|path| path.is_file())
+    }
+
+    /// Get a wheel from the store, downloading it if necessary.
     ///
-    /// - `name`: Package name (used for directory organization)
+    /// - `name`: Package name (for the legacy per-package layout)
     /// - `filename`: Wheel filename (e.g. `requests-2.31.0-py3-none-any.whl`)
     /// - `url`: Download URL
-    /// - `sha256`: Optional SHA256 checksum for verification
+    /// - `sha256`: Expected SHA256 checksum; without one the wheel is always
+    ///   downloaded and stored under the hash of what arrived
     pub async fn get_wheel(
         &self,
         name: &str,
@@ -53,37 +78,102 @@ impl WheelCache {
         url: &str,
         sha256: Option<&str>,
     ) -> Result<PathBuf> {
-        let package_dir = self
-            .cache
-            .ensure_package_dir(name)
-            .map_err(WheelCacheError::Cache)?;
-        let wheel_path = package_dir.join(filename);
-
-        // Optimization: If file exists and we have a hash, check it first?
-        // Downloader::download_file handles verification, but we might want to skip network req entirely
-        // if we trust the fs. For now, rely on Downloader logic or add simple check here.
-
-        // Actually Downloader doesn't skip if exists unless we optimize it.
-        // Let's optimize here: check existence + size > 0.
-        // If hash is provided, ideally we verify (or assume trusted if recently written).
-        // For cold start speed, we assume "if it exists with correct name, it's good"
-        // OR we trust the downloader to not re-download if verified.
-        // Let's delegate to downloader but hint it to skip if verified?
-        // Currently Downloader overwrites. We should update Downloader or logic here.
-
-        // Logic:
-        // 1. If file exists:
-        //    - if sha256 provided: verify it. If matches, return path. If not, delete and download.
-        //    - if no sha256: return path (optimistic) or re-download?
-        //      Safety: re-download if no hash (unless we trust name uniqueness).
-        //      PyPI wheels are immutable by filename usually.
-
-        // For this PR, we rely on Downloader which is smart enough to skip redundant
-        // downloads if the file exists and the checksum matches.
+        if let Some(expected) = sha256 {
+            if let Some(stored) = self.find(expected, filename) {
+                self.record(&stored, true);
+                return Ok(stored);
+            }
+            // Read-only shared caches may still hold the legacy layout;
+            // reuse a verified wheel there without copying it.
+            if let Some(existing) = self.cache.existing_wheel(name, filename)
+                && matches_sha256(&existing, expected)
+            {
+                self.record(&existing, true);
+                if self.cache.is_shared(&existing) {
+                    return Ok(existing);
+                }
+                return self.adopt(&existing, expected, filename);
+            }
+        }
+
+        // Download next to the store so the final move is a rename.
+        let store = self.cache.wheel_store_dir();
+        fs::create_dir_all(&store)?;
+        let staging = tempfile::Builder::new()
+            .prefix(".download-")
+            .tempdir_in(&store)?;
+        let downloaded = staging.path().join(filename);
         self.downloader
-            .download_file(url, &wheel_path, sha256)
-            .await
-            .map_err(WheelCacheError::from)
+            .download_file(url, &downloaded, sha256)
+            .await?;
+        let hash = match sha256 {
+            Some(expected) => normalize_hash(expected),
+            None => file_sha256(&downloaded)?,
+        };
+        let stored = self.adopt(&downloaded, &hash, filename)?;
+        self.record(&stored, false);
+        Ok(stored)
+    }
+
+    /// Hard-link (or copy) the stored wheel with this hash to `dest`.
+    /// Returns `false`, leaving `dest` alone, when the store lacks it.
+    pub fn link_to(&self, sha256: &str, filename: &str, dest: &Path) -> Result<bool> {
+        let Some(stored) = self.find(sha256, filename) else {
+            return Ok(false);
+        };
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        link_or_copy(&stored, dest)?;
+        self.record(&stored, true);
+        Ok(true)
+    }
+
+    /// Add a wheel that was downloaded and verified elsewhere to the store
+    /// by hard-linking (or copying) it. The hash is computed when not given.
+    pub fn insert(&self, path: &Path, filename: &str, sha256: Option<&str>) -> Result<PathBuf> {
+        let hash = match sha256 {
+            Some(expected) => normalize_hash(expected),
+            None => file_sha256(path)?,
+        };
+        let stored = self
+            .cache
+            .root()
+            .join(Cache::stored_wheel_relative(&hash, filename));
+        if !stored.is_file() {
+            let parent = stored.parent().expect("stored wheels have a parent");
+            fs::create_dir_all(parent)?;
+            // Stage then rename, so readers never see a partial wheel.
+            let staged = tempfile::Builder::new()
+                .prefix(".insert-")
+                .tempdir_in(parent)?;
+            link_or_copy(path, &staged.path().join(filename))?;
+            fs::rename(staged.path().join(filename), &stored)?;
+        }
+        self.record(&stored, false);
+        Ok(stored)
+    }
+
+    /// Move a verified wheel into the store.
+    fn adopt(&self, path: &Path, sha256: &str, filename: &str) -> Result<PathBuf> {
+        let stored = self.cache.root().join(Cache::stored_wheel_relative(
+            &normalize_hash(sha256),
+            filename,
+        ));
+        fs::create_dir_all(stored.parent().expect("stored wheels have a parent"))?;
+        fs::rename(path, &stored)?;
+        Ok(stored)
+    }
+
+    fn record(&self, path: &Path, hit: bool) {
+        let key = cache_stats::path_key(self.cache.root(), path).or_else(|| {
+            self.cache
+                .shared_root()
+                .and_then(|root| cache_stats::path_key(root, path))
+        });
+        if let Some(key) = key {
+            CacheStats::for_root(self.cache.root()).record(&key, hit);
+        }
     }
 
     /// Get the Downloader instance reference
@@ -91,3 +181,116 @@ impl WheelCache {
         &self.downloader
     }
 }
+
+/// Lowercase hex digest without a `sha256:` prefix.
+fn normalize_hash(sha256: &str) -> String {
+    sha256
+        .strip_prefix("sha256:")
+        .unwrap_or(sha256)
+        .to_ascii_lowercase()
+}
+
+/// Whether `path` hashes to `expected` (optionally `sha256:`-prefixed).
+/// Never modifies the file, unlike the downloader's verification.
+fn matches_sha256(path: &Path, expected: &str) -> bool {
+    file_sha256(path).is_ok_and(|actual| actual == normalize_hash(expected))
+}
+
+/// Hard-link `src` to `dest`, replacing `dest`; copy when linking fails
+/// (different filesystems, or a filesystem without hard links).
+fn link_or_copy(src: &Path, dest: &Path) -> std::io::Result<()> {
+    match fs::remove_file(dest) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    if fs::hard_link(src, dest).is_err() {
+        fs::copy(src, dest)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn insert_and_link_share_one_copy() {
+        let temp = tempdir().unwrap();
+        let wheels = WheelCache::with_cache(Cache::with_root(temp.path().join("cache"))).unwrap();
+        let source = temp.path().join("demo-1.0-py3-none-any.whl");
+        fs::write(&source, b"wheel bytes").unwrap();
+        let hash = file_sha256(&source).unwrap();
+
+        let stored = wheels
+            .insert(&source, "demo-1.0-py3-none-any.whl", None)
+            .unwrap();
+        assert!(
+            stored.ends_with(
+                Path::new(&hash[..2])
+                    .join(&hash)
+                    .join("demo-1.0-py3-none-any.whl")
+            )
+        );
+        assert_eq!(
+            wheels.find(
+                &format!("sha256:{}", hash.to_ascii_uppercase()),
+                "demo-1.0-py3-none-any.whl"
+            ),
+            Some(stored.clone())
+        );
+
+        let dest = temp
+            .path()
+            .join("artifacts")
+            .join("demo-1.0-py3-none-any.whl");
+        assert!(
+            wheels
+                .link_to(&hash, "demo-1.0-py3-none-any.whl", &dest)
+                .unwrap()
+        );
+        assert_eq!(fs::read(&dest).unwrap(), b"wheel bytes");
+        assert!(
+            !wheels
+                .link_to(&"0".repeat(64), "demo-1.0-py3-none-any.whl", &dest)
+                .unwrap()
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            assert_eq!(
+                fs::metadata(&dest).unwrap().ino(),
+                fs::metadata(&stored).unwrap().ino()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn legacy_wheels_are_adopted_without_download() {
+        let temp = tempdir().unwrap();
+        let cache = Cache::with_root(temp.path());
+        let wheels = WheelCache::with_cache(cache.clone()).unwrap();
+        let legacy = cache
+            .ensure_package_dir("demo")
+            .unwrap()
+            .join("demo-1.0-py3-none-any.whl");
+        fs::write(&legacy, b"wheel bytes").unwrap();
+        let hash = file_sha256(&legacy).unwrap();
+
+        // The URL is unreachable; a download attempt would fail.
+        let stored = wheels
+            .get_wheel(
+                "demo",
+                "demo-1.0-py3-none-any.whl",
+                "http://127.0.0.1:9/demo-1.0-py3-none-any.whl",
+                Some(&hash),
+            )
+            .await
+            .unwrap();
+        assert!(stored.starts_with(cache.wheel_store_dir()));
+        assert!(!legacy.exists());
+        assert_eq!(fs::read(&stored).unwrap(), b"wheel bytes");
+    }
+}
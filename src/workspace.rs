@@ -1,4 +1,5 @@
 use crate::project::{Project, ProjectError, extract_package_name};
+use crate::pypi::normalize_project_name;
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -108,9 +109,58 @@ impl Workspace {
             .find(|member| member_display_name(member) == name)
     }
 
-    /// Merge dependencies from root and all members, de-duplicating by package name.
+    /// Whether `requirement` names a workspace member. Members are installed
+    /// from the workspace, so they are never resolved against an index.
+    pub fn is_member_requirement(&self, requirement: &str) -> bool {
+        self.member_named_by(requirement).is_some()
+    }
+
+    /// The member a requirement such as `sdk>=0.1` or `sdk @ file:../sdk` names.
+    fn member_named_by(&self, requirement: &str) -> Option<&Project> {
+        let name = extract_package_name(requirement)
+            .split(['@', ' '])
+            .next()
+            .unwrap_or_default();
+        let name = normalize_project_name(name);
+        self.members
+            .iter()
+            .find(|member| normalize_project_name(&member_display_name(member)) == name)
+    }
+
+    /// Member `name` followed by the members it depends on, directly or
+    /// through other members. Empty when `name` is not a member.
+    pub fn member_closure(&self, name: &str) -> Vec<&Project> {
+        let mut closure: Vec<&Project> = Vec::new();
+        let mut pending: Vec<&Project> = self.member_by_name(name).into_iter().collect();
+        while let Some(member) = pending.pop() {
+            if closure.iter().any(|seen| seen.path() == member.path()) {
+                continue;
+            }
+            closure.push(member);
+            pending.extend(
+                member
+                    .dependencies()
+                    .iter()
+                    .filter_map(|dep| self.member_named_by(dep)),
+            );
+        }
+        closure
+    }
+
+    /// Dependencies of member `name` and of the members it depends on, with
+    /// the members themselves left out, de-duplicated by package name.
+    pub fn member_dependencies(&self, name: &str) -> Vec<String> {
+        self.external(
+            self.member_closure(name)
+                .into_iter()
+                .flat_map(|p| p.dependencies()),
+        )
+    }
+
+    /// Merge dependencies from root and all members, de-duplicating by package
+    /// name. Requirements on other members are left out.
     pub fn merged_dependencies(&self) -> Vec<String> {
-        merge_dependencies(
+        self.external(
             self.root
                 .dependencies()
                 .into_iter()
@@ -120,9 +170,9 @@ impl Workspace {
 
     /// Merge dependencies for a named group (checked via `[project.optional-dependencies]`
     /// then `[dependency-groups]`) across the root and all members,
-    /// de-duplicating by package name.
+    /// de-duplicating by package name. Requirements on members are left out.
     pub fn dependencies_for_group(&self, group: &str) -> Vec<String> {
-        merge_dependencies(
+        self.external(
             self.root.group_dependencies(group).into_iter().chain(
                 self.members
                     .iter()
@@ -130,11 +180,27 @@ impl Workspace {
             ),
         )
     }
+
+    /// De-duplicated `deps` without requirements on workspace members.
+    pub fn external(&self, deps: impl Iterator<Item = String>) -> Vec<String> {
+        merge_dependencies(deps.filter(|dep| !self.is_member_requirement(dep)))
+    }
+}
+
+/// Root of the workspace that lists `dir` as a member, if any. Members share
+/// the workspace root's environment.
+pub fn member_workspace_root(dir: &Path) -> Option<PathBuf> {
+    let workspace = Workspace::discover_root(dir.parent()?).ok()??;
+    workspace
+        .members
+        .iter()
+        .any(|member| member.root() == dir)
+        .then(|| workspace.root.root().to_path_buf())
 }
 
 /// Display name for a member project: its declared `[project.name]`, falling
 /// back to the containing directory name.
-fn member_display_name(member: &Project) -> String {
+pub fn member_display_name(member: &Project) -> String {
     member.metadata().name.unwrap_or_else(|| {
         member
             .root()
@@ -437,4 +503,75 @@ members = ["packages/api"]
                 .is_none()
         );
     }
+
+    #[test]
+    fn member_requirements_stay_out_of_resolution() {
+        let temp = tempdir().unwrap();
+        let root_dir = temp.path();
+        fs::write(
+            root_dir.join("pyproject.toml"),
+            r#"[project]
+name = "root"
+version = "0.1.0"
+dependencies = ["My_SDK>=0.1"]
+
+[tool.pybun.workspace]
+members = ["packages/*"]
+"#,
+        )
+        .unwrap();
+        write_project(
+            &root_dir.join("packages/api/pyproject.toml"),
+            "api",
+            &["my-sdk", "lib-a==1.0.0"],
+        );
+        write_project(
+            &root_dir.join("packages/sdk/pyproject.toml"),
+            "my-sdk",
+            &["lib-b==2.0.0"],
+        );
+        write_project(
+            &root_dir.join("packages/web/pyproject.toml"),
+            "web",
+            &["lib-c"],
+        );
+
+        let workspace = Workspace::discover_root(root_dir).unwrap().unwrap();
+        let closure: Vec<String> = workspace
+            .member_closure("api")
+            .into_iter()
+            .map(member_display_name)
+            .collect();
+        assert_eq!(closure, vec!["api".to_string(), "my-sdk".to_string()]);
+        assert_eq!(
+            workspace.member_dependencies("api"),
+            vec!["lib-a==1.0.0".to_string(), "lib-b==2.0.0".to_string()]
+        );
+        assert!(
+            !workspace
+                .merged_dependencies()
+                .iter()
+                .any(|dep| workspace.is_member_requirement(dep))
+        );
+        assert!(workspace.member_closure("missing").is_empty());
+    }
+
+    #[test]
+    fn member_workspace_root_finds_the_listing_workspace() {
+        let temp = tempdir().unwrap();
+        let root_dir = temp.path();
+        fs::write(
+            root_dir.join("pyproject.toml"),
+            "[tool.pybun.workspace]\nmembers = [\"packages/sdk\"]\n",
+        )
+        .unwrap();
+        write_project(&root_dir.join("packages/sdk/pyproject.toml"), "sdk", &[]);
+        write_project(&root_dir.join("other/pyproject.toml"), "other", &[]);
+
+        assert_eq!(
+            member_workspace_root(&root_dir.join("packages/sdk")).as_deref(),
+            Some(root_dir)
+        );
+        assert!(member_workspace_root(&root_dir.join("other")).is_none());
+    }
 }
@@ -0,0 +1,106 @@
+//! E2E tests for project-scoped command aliases.
+//!
+//! Tests for `[tool.pybun.alias]` expansion, `pybun alias list`, and alias
+//! entries in `pybun completions`.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+fn project_with_aliases(temp: &TempDir, aliases: &str) -> std::path::PathBuf {
+    let project = temp.path().join("demo");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(
+        project.join("pyproject.toml"),
+        format!("[project]\nname = \"demo\"\n\n[tool.pybun.alias]\n{aliases}"),
+    )
+    .unwrap();
+    project
+}
+
+fn pybun_in(dir: &std::path::Path) -> Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".pybun-home"));
+    cmd
+}
+
+#[test]
+fn test_alias_expands_before_parsing() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_aliases(&temp, "pl = \"python list\"\nls = [\"pl\"]\n");
+
+    let output = pybun_in(&project)
+        .args(["--format=json", "ls"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["command"], "pybun python list");
+}
+
+#[test]
+fn test_alias_list_reports_expansions() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_aliases(&temp, "fix = [\"lint\", \"--fix\"]\ntest = \"run nope\"\n");
+
+    let output = pybun_in(&project)
+        .args(["--format=json", "alias", "list"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let aliases = json["detail"]["aliases"].as_array().unwrap();
+    assert_eq!(aliases.len(), 2);
+    assert_eq!(aliases[0]["name"], "fix");
+    assert_eq!(aliases[0]["expansion"], "lint --fix");
+    assert_eq!(aliases[0]["shadowed"], false);
+    assert_eq!(aliases[1]["shadowed"], true);
+}
+
+#[test]
+fn test_alias_recursion_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_aliases(&temp, "a = \"b\"\nb = \"a --x\"\n");
+
+    pybun_in(&project)
+        .arg("a")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "alias recursion detected: a -> b -> a",
+        ));
+}
+
+#[test]
+fn test_alias_recursion_reports_a_json_envelope() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_aliases(&temp, "a = \"b\"\nb = \"a\"\n");
+
+    let output = pybun_in(&project)
+        .args(["--format=json", "a"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["status"], "error");
+    assert_eq!(json["diagnostics"][0]["code"], "E_ALIAS_RECURSION");
+    assert_eq!(
+        json["detail"]["error"],
+        "alias recursion detected: a -> b -> a"
+    );
+}
+
+#[test]
+fn test_completions_include_aliases() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_aliases(&temp, "ci = \"test --fail-fast\"\n");
+
+    pybun_in(&project)
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ci"))
+        .stdout(predicate::str::contains("complete -F _pybun"));
+}
@@ -0,0 +1,161 @@
+//! Tests for stored index credentials (`pybun auth`).
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn bin(home: &Path) -> Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.env("PYBUN_HOME", home);
+    cmd.env("PYBUN_PYPI_CACHE_DIR", home.join("pypi"));
+    cmd.env("PYBUN_AUTH_BACKEND", "file");
+    cmd.env_remove("PYBUN_AUTH_PASSPHRASE");
+    cmd
+}
+
+fn json_detail(cmd: &mut Command) -> Value {
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: Value = serde_json::from_str(&stdout).unwrap_or_else(|e| {
+        panic!("invalid json ({e}): {stdout}");
+    });
+    value["detail"].clone()
+}
+
+#[test]
+fn token_set_status_and_logout() {
+    let temp = tempdir().unwrap();
+    let home = temp.path();
+
+    let detail = json_detail(
+        bin(home)
+            .args([
+                "--format=json",
+                "auth",
+                "token",
+                "set",
+                "https://pypi.corp.example/simple/",
+                "--token-stdin",
+            ])
+            .write_stdin("pypi-t0ken-value\n"),
+    );
+    assert_eq!(
+        detail["credential"]["scope"],
+        "https://pypi.corp.example/simple"
+    );
+    assert_eq!(detail["credential"]["username"], "__token__");
+    assert_eq!(detail["credential"]["backend"], "file");
+
+    for file in ["auth.json", "credentials.enc"] {
+        let content = fs::read_to_string(home.join(file)).unwrap();
+        assert!(!content.contains("pypi-t0ken-value"), "{file}: {content}");
+    }
+
+    let detail = json_detail(bin(home).args([
+        "--format=json",
+        "auth",
+        "status",
+        "https://pypi.corp.example/simple/six/",
+    ]));
+    let credentials = detail["credentials"].as_array().unwrap();
+    assert_eq!(credentials.len(), 1);
+    assert_eq!(credentials[0]["readable"], true);
+    assert!(!detail.to_string().contains("pypi-t0ken-value"));
+
+    let detail = json_detail(bin(home).args([
+        "--format=json",
+        "auth",
+        "status",
+        "https://other.example/simple/",
+    ]));
+    assert!(detail["credentials"].as_array().unwrap().is_empty());
+
+    bin(home)
+        .args(["auth", "logout", "https://pypi.corp.example/simple"])
+        .assert()
+        .success();
+    bin(home)
+        .args(["auth", "logout", "https://pypi.corp.example/simple"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn login_without_terminal_requires_stdin_flag() {
+    let temp = tempdir().unwrap();
+    bin(temp.path())
+        .args(["auth", "login", "https://pypi.corp.example", "-u", "me"])
+        .write_stdin("secret\n")
+        .assert()
+        .failure();
+    assert!(!temp.path().join("auth.json").exists());
+}
+
+#[test]
+fn stored_credentials_are_sent_to_the_index() {
+    let temp = tempdir().unwrap();
+    let home = temp.path().join("home");
+    let project = temp.path().join("demo");
+    let server = MockServer::start();
+    let base = server.base_url();
+
+    bin(&home)
+        .args(["auth", "login", &format!("{base}/simple"), "-u", "ci"])
+        .arg("--password-stdin")
+        .write_stdin("hunter2\n")
+        .assert()
+        .success();
+
+    // "ci:hunter2"
+    let authorized = server.mock(|when, then| {
+        when.method(GET)
+            .path("/simple/app/")
+            .header("authorization", "Basic Y2k6aHVudGVyMg==");
+        then.status(200)
+            .header("Content-Type", "application/vnd.pypi.simple.v1+json")
+            .body(
+                json!({
+                    "meta": { "api-version": "1.1" },
+                    "name": "app",
+                    "files": [{
+                        "filename": "app-1.0.0-py3-none-any.whl",
+                        "url": "/files/app-1.0.0-py3-none-any.whl",
+                        "hashes": { "sha256": "00" },
+                        "core-metadata": true
+                    }]
+                })
+                .to_string(),
+            );
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/files/app-1.0.0-py3-none-any.whl.metadata")
+            .header("authorization", "Basic Y2k6aHVudGVyMg==");
+        then.status(200)
+            .body("Metadata-Version: 2.1\nName: app\nVersion: 1.0.0\n");
+    });
+
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"app\"]\n",
+    )
+    .unwrap();
+    let output = bin(&home)
+        .current_dir(&project)
+        .env("PYBUN_INDEX_URL", format!("{base}/simple"))
+        .args(["--format=json", "lock"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    authorized.assert();
+}
@@ -0,0 +1,132 @@
+//! E2E tests for `pybun bench`: discovery of `bench_*` functions and
+//! pytest-benchmark style tests, statistics, history and baselines.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn pybun_bench(dir: &Path, args: &[&str]) -> (Value, bool) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".pybun-home"))
+        .env_remove("PYBUN_ENV")
+        .args([
+            "--format=json",
+            "bench",
+            "--max-time=0.05",
+            "--min-rounds=3",
+        ])
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    (json, output.status.success())
+}
+
+fn codes(json: &Value) -> Vec<&str> {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect()
+}
+
+fn project(temp: &TempDir) -> std::path::PathBuf {
+    let dir = temp.path().join("app");
+    fs::create_dir_all(dir.join("benchmarks")).unwrap();
+    fs::write(dir.join("delay.txt"), "0.002").unwrap();
+    fs::write(
+        dir.join("benchmarks/bench_app.py"),
+        "import os, time\n\n\
+         DELAY = float(open(os.path.join(os.getcwd(), 'delay.txt')).read())\n\n\
+         def bench_sleep():\n    time.sleep(DELAY)\n\n\
+         def test_sum(benchmark):\n    assert benchmark(sum, range(100)) == 4950\n\n\
+         def bench_broken():\n    raise ValueError('boom')\n\n\
+         def bench_needs_fixture(tmp_path):\n    pass\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn bench_measures_functions_and_fixture_benchmarks() {
+    let temp = TempDir::new().unwrap();
+    let dir = project(&temp);
+
+    let (json, ok) = pybun_bench(&dir, &[]);
+    assert!(!ok, "a failing benchmark fails the run");
+    let benchmarks = json["detail"]["benchmarks"].as_array().unwrap();
+    let ids: Vec<&str> = benchmarks
+        .iter()
+        .map(|b| b["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            "benchmarks/bench_app.py::bench_sleep",
+            "benchmarks/bench_app.py::test_sum"
+        ],
+        "{json}"
+    );
+    let sleep = &benchmarks[0];
+    assert_eq!(sleep["style"], "function");
+    assert!(
+        sleep["median_ns"].as_f64().unwrap() >= 2_000_000.0,
+        "{sleep}"
+    );
+    assert!(sleep["rounds"].as_u64().unwrap() >= 3);
+    assert_eq!(benchmarks[1]["style"], "fixture");
+    assert!(benchmarks[1]["iterations"].as_u64().unwrap() > 1);
+
+    assert_eq!(json["detail"]["failed"], 1);
+    let codes = codes(&json);
+    assert!(codes.contains(&"E_BENCH_FAILED"));
+    assert!(codes.contains(&"W_BENCH_UNSUPPORTED_FIXTURES"));
+    let failure = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_BENCH_FAILED")
+        .unwrap();
+    assert!(
+        failure["context"]["traceback"]
+            .as_str()
+            .unwrap()
+            .contains("ValueError: boom")
+    );
+}
+
+#[test]
+fn bench_compares_with_baselines_and_previous_runs() {
+    let temp = TempDir::new().unwrap();
+    let dir = project(&temp);
+    let args = ["-k", "bench_sleep"];
+
+    let (json, ok) = pybun_bench(&dir, &[&args[..], &["--save-baseline", "main"]].concat());
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["saved_baseline"], "main");
+
+    let (json, _) = pybun_bench(&dir, &[&args[..], &["--compare=nope"]].concat());
+    assert!(codes(&json).contains(&"W_BENCH_NO_BASELINE"), "{json}");
+
+    fs::write(dir.join("delay.txt"), "0.01").unwrap();
+    let (json, ok) = pybun_bench(&dir, &[&args[..], &["--compare=main"]].concat());
+    assert!(!ok, "{json}");
+    assert!(codes(&json).contains(&"E_BENCH_REGRESSION"));
+    let sleep = &json["detail"]["benchmarks"][0];
+    assert_eq!(sleep["verdict"], "regressed");
+    assert!(sleep["change_percent"].as_f64().unwrap() > 100.0);
+    assert_eq!(json["detail"]["baseline"]["name"], "baseline 'main'");
+
+    // The previous run was just as slow.
+    let (json, ok) = pybun_bench(
+        &dir,
+        &[&args[..], &["--compare", "--threshold", "50"]].concat(),
+    );
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["benchmarks"][0]["verdict"], "unchanged");
+    assert_eq!(json["detail"]["baseline"]["name"], "previous run");
+}
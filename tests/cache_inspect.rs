@@ -0,0 +1,115 @@
+//! E2E tests for `pybun cache dir`, `list`, `info` and `verify`.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::cache::Cache;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn pybun_cache(home: &Path, args: &[&str]) -> (Value, bool) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(home)
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_READONLY_CACHE")
+        .args(["--format=json", "cache"])
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    (json, output.status.success())
+}
+
+fn store_wheel(home: &Path, contents: &[u8], filename: &str) -> (String, PathBuf) {
+    let sha256 = hex::encode(Sha256::digest(contents));
+    let path = home.join(Cache::stored_wheel_relative(&sha256, filename));
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, contents).unwrap();
+    (sha256, path)
+}
+
+fn script_env(home: &Path) {
+    let dir = home.join("pep723-envs").join("report-0123abcd");
+    fs::create_dir_all(dir.join("venv")).unwrap();
+    fs::write(
+        dir.join("deps.json"),
+        json!({
+            "hash": "feedc0de00112233",
+            "dependencies": ["requests>=2"],
+            "python_version": "3.12",
+            "created_at": 1,
+            "last_used": 2,
+        })
+        .to_string(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn cache_lists_and_describes_entries() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+    let (sha, _) = store_wheel(home, b"wheel", "alpha-1.0-py3-none-any.whl");
+    script_env(home);
+
+    let (json, ok) = pybun_cache(home, &["dir"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["root"], home.display().to_string());
+
+    let (json, ok) = pybun_cache(home, &["list"]);
+    assert!(ok, "{json}");
+    let entries = json["detail"]["entries"].as_array().unwrap();
+    let kinds: Vec<&str> = entries
+        .iter()
+        .map(|e| e["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["wheel", "env"], "{json}");
+    assert_eq!(entries[0]["id"], sha.as_str());
+    assert_eq!(entries[0]["size_bytes"], 5);
+    assert_eq!(entries[1]["name"], "requests>=2");
+
+    let (json, _) = pybun_cache(home, &["list", "--envs"]);
+    assert_eq!(json["detail"]["entries"].as_array().unwrap().len(), 1);
+
+    let (json, ok) = pybun_cache(home, &["info", &sha[..10]]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["name"], "alpha-1.0-py3-none-any.whl");
+    assert_eq!(json["detail"]["kind"], "wheel");
+
+    let (json, ok) = pybun_cache(home, &["info", "feedc0de"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["python_version"], "3.12");
+
+    let (json, ok) = pybun_cache(home, &["info", "99999999"]);
+    assert!(!ok);
+    assert_eq!(json["diagnostics"][0]["code"], "E_CACHE_INSPECT_FAILED");
+}
+
+#[test]
+fn cache_verify_reports_corrupted_wheels() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+    store_wheel(home, b"good", "good-1.0-py3-none-any.whl");
+
+    let (json, ok) = pybun_cache(home, &["verify"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["checked"], 1);
+
+    let (sha, path) = store_wheel(home, b"bad", "bad-1.0-py3-none-any.whl");
+    fs::write(&path, b"flipped bits").unwrap();
+    let (json, ok) = pybun_cache(home, &["verify"]);
+    assert!(!ok, "{json}");
+    assert_eq!(json["detail"]["checked"], 2);
+    let failures = json["detail"]["failures"].as_array().unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0]["sha256"], sha.as_str());
+    assert_eq!(failures[0]["problem"], "hash_mismatch");
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_CACHE_CORRUPT")
+        .expect("corruption diagnostic");
+    assert_eq!(diagnostic["context"]["expected"], sha.as_str());
+}
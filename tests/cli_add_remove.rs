@@ -324,3 +324,138 @@ dependencies = ["requests>=2.28.0"]
     let content = fs::read_to_string(temp.path().join("pyproject.toml")).unwrap();
     assert!(!content.contains("requests"), "requests should be removed");
 }
+
+#[test]
+fn remove_reports_pyproject_diff() {
+    let temp = tempdir().unwrap();
+
+    let pyproject = r#"[project]
+name = "test-project"
+dependencies = ["click>=2.0.0", "requests>=2.28.0"]
+"#;
+    fs::write(temp.path().join("pyproject.toml"), pyproject).unwrap();
+
+    let output = bin()
+        .current_dir(temp.path())
+        .args(["--format=json", "remove", "requests"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let changes = json["detail"]["changes"].as_array().expect("changes");
+    assert_eq!(changes.len(), 1, "{json}");
+    assert_eq!(changes[0]["file"], "pyproject.toml");
+    assert_eq!(changes[0]["section"], "project.dependencies");
+    assert_eq!(changes[0]["name"], "requests");
+    assert_eq!(changes[0]["change"], "removed");
+    assert_eq!(changes[0]["before"], "requests>=2.28.0");
+    assert!(changes[0]["after"].is_null());
+    let diff = json["detail"]["diff"].as_str().unwrap();
+    assert!(diff.starts_with("--- a/pyproject.toml\n+++ b/pyproject.toml\n@@ "));
+    assert!(
+        diff.contains("\n+dependencies = [\"click>=2.0.0\"]\n"),
+        "{diff}"
+    );
+
+    // Text output carries the same diff after the summary; nothing is shown
+    // when nothing changed.
+    fs::write(temp.path().join("pyproject.toml"), pyproject).unwrap();
+    bin()
+        .current_dir(temp.path())
+        .args(["remove", "requests"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--- a/pyproject.toml"))
+        .stdout(predicate::str::contains("-dependencies = ["));
+    bin()
+        .current_dir(temp.path())
+        .args(["remove", "requests"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("---").not());
+}
+
+#[test]
+fn add_reports_pyproject_changes_even_when_install_fails() {
+    let temp = tempdir().unwrap();
+
+    let pyproject = r#"[project]
+name = "test-project"
+dependencies = ["numpy>=1.24.0"]
+"#;
+    fs::write(temp.path().join("pyproject.toml"), pyproject).unwrap();
+
+    // No venv and no reachable index: the chained install fails after
+    // pyproject.toml has been written.
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_PYPI_BASE_URL", "http://127.0.0.1:9")
+        .args(["--format=json", "add", "numpy==2.0.0", "rich"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let changes: Vec<(&str, &str)> = json["detail"]["changes"]
+        .as_array()
+        .expect("changes")
+        .iter()
+        .map(|c| (c["name"].as_str().unwrap(), c["change"].as_str().unwrap()))
+        .collect();
+    assert_eq!(changes, vec![("numpy", "changed"), ("rich", "added")]);
+    assert!(
+        json["detail"]["diff"]
+            .as_str()
+            .is_some_and(|d| d.contains("-dependencies = [\"numpy>=1.24.0\"]\n")
+                && d.contains("+    \"rich\",\n")),
+        "{json}"
+    );
+}
+
+#[test]
+fn add_and_remove_group_dependencies() {
+    let temp = tempdir().unwrap();
+
+    let pyproject = r#"[project]
+name = "test-project"
+dependencies = ["click>=2.0.0"]
+"#;
+    fs::write(temp.path().join("pyproject.toml"), pyproject).unwrap();
+
+    // The chained install fails without a reachable index; pyproject.toml
+    // is written first.
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_PYPI_BASE_URL", "http://127.0.0.1:9")
+        .args(["--format=json", "add", "--group", "test", "pytest>=8"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["group"], "test", "{json}");
+    let changes = json["detail"]["changes"].as_array().expect("changes");
+    assert_eq!(changes[0]["section"], "dependency-groups.test", "{json}");
+    assert_eq!(changes[0]["name"], "pytest");
+    assert_eq!(changes[0]["change"], "added");
+    let content = fs::read_to_string(temp.path().join("pyproject.toml")).unwrap();
+    assert!(content.contains("[dependency-groups]"), "{content}");
+    assert!(
+        content.contains("dependencies = [\"click>=2.0.0\"]"),
+        "{content}"
+    );
+
+    bin()
+        .current_dir(temp.path())
+        .args(["remove", "--group", "test", "click"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not found in group 'test'"));
+    let output = bin()
+        .current_dir(temp.path())
+        .args(["--format=json", "remove", "--group", "test", "pytest"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["changes"][0]["change"], "removed", "{json}");
+    let content = fs::read_to_string(temp.path().join("pyproject.toml")).unwrap();
+    assert!(!content.contains("pytest"), "{content}");
+    assert!(content.contains("click"), "{content}");
+}
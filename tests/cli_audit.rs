@@ -5,6 +5,7 @@
 use assert_cmd::Command;
 use assert_cmd::cargo::cargo_bin_cmd;
 use httpmock::prelude::*;
+use pybun::lockfile::{Lockfile, Package, PackageSource};
 use serde_json::Value;
 use tempfile::tempdir;
 
@@ -563,3 +564,123 @@ fn audit_help_documents_severity_threshold_and_fail_on() {
         .stdout(predicates::str::contains("--severity-threshold"))
         .stdout(predicates::str::contains("--fail-on"));
 }
+
+fn write_locked_project(dir: &std::path::Path, packages: &[(&str, &str)]) {
+    std::fs::write(
+        dir.join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+    for (name, version) in packages {
+        lock.add_package(Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            source: PackageSource::Registry {
+                index: "pypi".into(),
+                url: "https://pypi.org/simple".into(),
+            },
+            wheel: format!("{name}-{version}-py3-none-any.whl"),
+            hash: format!("sha256:{}", "ab".repeat(32)),
+            dependencies: Vec::new(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        });
+    }
+    lock.save_to_path(dir.join("pybun.lockb")).unwrap();
+}
+
+#[test]
+fn audit_scans_locked_packages_and_reuses_cached_advisories_offline() {
+    let server = MockServer::start();
+    let osv_body = serde_json::json!({
+        "results": [
+            {
+                "vulns": [{
+                    "id": "GHSA-j8r2-6x86-q33q",
+                    "summary": "Requests SSRF vulnerability",
+                    "affected": [{
+                        "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "0"}, {"fixed": "2.31.0"}]}]
+                    }],
+                    "database_specific": {"severity": "HIGH"}
+                }]
+            }
+        ]
+    });
+    let osv = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/querybatch")
+            .body_includes("requests");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .json_body(osv_body);
+    });
+
+    let project = tempdir().unwrap();
+    let home = tempdir().unwrap();
+    write_locked_project(project.path(), &[("requests", "2.27.0")]);
+    let osv_url = format!("{}/v1/querybatch", server.base_url());
+
+    // No Python environment is needed: the lockfile is scanned.
+    let output = bin()
+        .current_dir(project.path())
+        .env("PYBUN_HOME", home.path())
+        .env("PYBUN_OSV_URL", &osv_url)
+        .args(["--format=json", "audit", "--fail-on=high"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let value: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["detail"]["source"], "lockfile");
+    assert_eq!(value["detail"]["summary"]["high"], 1);
+    assert_eq!(
+        value["detail"]["vulnerabilities"][0]["fix_version"],
+        "2.31.0"
+    );
+    osv.assert_calls(1);
+
+    // Offline, the cached answer is reused without contacting OSV.
+    let output = bin()
+        .current_dir(project.path())
+        .env("PYBUN_HOME", home.path())
+        .env("PYBUN_OSV_URL", &osv_url)
+        .args(["--offline", "--format=json", "audit", "--fail-on=high"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let value: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["detail"]["offline"], true);
+    assert_eq!(value["detail"]["summary"]["high"], 1);
+    assert_eq!(value["detail"]["summary"]["unscanned"], 0);
+    osv.assert_calls(1);
+}
+
+#[test]
+fn offline_audit_reports_packages_without_cached_advisories_as_unscanned() {
+    let project = tempdir().unwrap();
+    let home = tempdir().unwrap();
+    write_locked_project(project.path(), &[("idna", "3.7")]);
+
+    let output = bin()
+        .current_dir(project.path())
+        .env("PYBUN_HOME", home.path())
+        .args(["--offline", "--format=json", "audit", "--fail-on=high"])
+        .output()
+        .unwrap();
+    assert!(
+        !output.status.success(),
+        "an unscanned package must not pass a --fail-on gate"
+    );
+    let value: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["detail"]["unscanned_packages"][0], "idna==3.7");
+    let codes: Vec<&str> = value["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect();
+    assert!(codes.contains(&"E_AUDIT_UNSCANNED_OFFLINE"), "{codes:?}");
+    assert!(codes.contains(&"E_NETWORK_REQUIRED"), "{codes:?}");
+}
@@ -11,27 +11,7 @@ fn bin() -> Command {
 }
 
 fn setup_fake_build_project() -> (TempDir, PathBuf, std::ffi::OsString) {
-    let temp = tempfile::tempdir().unwrap();
-    let project_dir = temp.path().join("project");
-    fs::create_dir_all(&project_dir).unwrap();
-
-    // Minimal pyproject for build backends
-    fs::write(
-        project_dir.join("pyproject.toml"),
-        r#"[project]
-name = "demo-build"
-version = "0.1.0"
-"#,
-    )
-    .unwrap();
-
-    // Stub `build` module so tests don't depend on external packages.
-    let fake_build_dir = temp.path().join("fake_build");
-    let package_dir = fake_build_dir.join("build");
-    fs::create_dir_all(&package_dir).unwrap();
-    fs::write(package_dir.join("__init__.py"), "").unwrap();
-    fs::write(
-        package_dir.join("__main__.py"),
+    setup_build_project(
         r#"
 import pathlib
 import sys
@@ -51,10 +31,33 @@ def main():
 
 if __name__ == "__main__":
     main()
+"#,
+    )
+}
+
+/// Project whose `python -m build` runs `build_main` as `build/__main__.py`.
+fn setup_build_project(build_main: &str) -> (TempDir, PathBuf, std::ffi::OsString) {
+    let temp = tempfile::tempdir().unwrap();
+    let project_dir = temp.path().join("project");
+    fs::create_dir_all(&project_dir).unwrap();
+
+    // Minimal pyproject for build backends
+    fs::write(
+        project_dir.join("pyproject.toml"),
+        r#"[project]
+name = "demo-build"
+version = "0.1.0"
 "#,
     )
     .unwrap();
 
+    // Stub `build` module so tests don't depend on external packages.
+    let fake_build_dir = temp.path().join("fake_build");
+    let package_dir = fake_build_dir.join("build");
+    fs::create_dir_all(&package_dir).unwrap();
+    fs::write(package_dir.join("__init__.py"), "").unwrap();
+    fs::write(package_dir.join("__main__.py"), build_main).unwrap();
+
     // Compose PYTHONPATH that ensures our stub takes precedence.
     let mut paths = vec![fake_build_dir.into_os_string()];
     if let Some(existing) = std::env::var_os("PYTHONPATH") {
@@ -324,3 +327,185 @@ fn build_cache_reuses_artifacts_on_hit_and_misses_on_change() {
     missed.assert().success();
     assert!(marker_path.exists(), "cache miss should rebuild artifacts");
 }
+
+/// `python -m build` stub writing a real wheel and sdist whose timestamps,
+/// member order and permissions change on every run; with `nondeterministic`
+/// the wheel's `_build.py` also records the build time. `__init__.py`
+/// records `BUILD_FLAVOR` and `TZ` from the backend environment.
+fn archive_build_main(nondeterministic: bool) -> String {
+    format!(
+        r#"
+import io, os, pathlib, random, tarfile, time, zipfile
+
+NONDETERMINISTIC = {nondeterministic}
+
+def main():
+    dist = pathlib.Path.cwd() / "dist"
+    dist.mkdir(exist_ok=True)
+    members = [
+        ("demo_build/__init__.py", "VERSION = '0.1.0'\nFLAVOR = {{!r}}\nTZ = {{!r}}\n".format(os.environ.get("BUILD_FLAVOR"), os.environ.get("TZ")).encode()),
+        ("demo_build/_build.py", repr(time.time_ns()).encode() if NONDETERMINISTIC else b"STAMP = None\n"),
+        ("demo_build-0.1.0.dist-info/METADATA", b"Name: demo-build\nVersion: 0.1.0\n"),
+        ("demo_build-0.1.0.dist-info/RECORD", b""),
+    ]
+    random.shuffle(members)
+    now = time.localtime(time.time() - random.randint(0, 10**6))[:6]
+    with zipfile.ZipFile(dist / "demo_build-0.1.0-py3-none-any.whl", "w", zipfile.ZIP_DEFLATED) as whl:
+        for name, data in members:
+            info = zipfile.ZipInfo(name, date_time=now)
+            info.external_attr = random.choice([0o644, 0o664]) << 16
+            whl.writestr(info, data)
+    with tarfile.open(dist / "demo-build-0.1.0.tar.gz", "w:gz") as sdist:
+        for name, data in members:
+            info = tarfile.TarInfo("demo-build-0.1.0/" + name)
+            info.size = len(data)
+            info.mtime = time.time()
+            info.uname = random.choice(["alice", "bob"])
+            sdist.addfile(info, io.BytesIO(data))
+
+if __name__ == "__main__":
+    main()
+"#,
+        nondeterministic = if nondeterministic { "True" } else { "False" },
+    )
+}
+
+fn run_json_build(
+    project_dir: &Path,
+    pythonpath: &std::ffi::OsStr,
+    cache_home: &Path,
+    extra_args: &[&str],
+) -> (bool, serde_json::Value) {
+    let output = bin()
+        .current_dir(project_dir)
+        .env("PYTHONPATH", pythonpath)
+        .env("PYBUN_HOME", cache_home)
+        .env("SOURCE_DATE_EPOCH", "1700000000")
+        .args(["--format=json", "build", "--check-reproducible"])
+        .args(extra_args)
+        .output()
+        .expect("failed to run pybun build");
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+        panic!(
+            "invalid JSON ({e}):\n{}\nstderr:\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+#[test]
+fn build_normalizes_archives_so_two_builds_are_identical() {
+    let (temp, project_dir, pythonpath) = setup_build_project(&archive_build_main(false));
+    let cache_home = temp.path().join("cache_home");
+
+    let (success, json) = run_json_build(&project_dir, &pythonpath, &cache_home, &[]);
+    assert!(success, "check should pass: {json}");
+
+    let reproducible = &json["detail"]["reproducible"];
+    assert_eq!(reproducible["source_date_epoch"], 1_700_000_000);
+    assert_eq!(reproducible["epoch_source"], "env");
+    assert_eq!(reproducible["normalized"].as_array().unwrap().len(), 2);
+    assert_eq!(reproducible["check"]["identical"], true);
+    let artifacts = reproducible["check"]["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts.len(), 2);
+    assert!(
+        artifacts
+            .iter()
+            .all(|a| a["identical"] == true && a["first_sha256"] == a["second_sha256"])
+    );
+
+    // The wheel keeps RECORD last and every entry carries SOURCE_DATE_EPOCH.
+    let wheel = project_dir
+        .join("dist")
+        .join("demo_build-0.1.0-py3-none-any.whl");
+    let mut archive = zip::ZipArchive::new(fs::File::open(wheel).unwrap()).unwrap();
+    let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    assert_eq!(names.last().unwrap(), "demo_build-0.1.0.dist-info/RECORD");
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).unwrap();
+        let time = entry.last_modified().unwrap();
+        assert_eq!((time.year(), time.month(), time.day()), (2023, 11, 14));
+        assert_eq!(entry.unix_mode().unwrap() & 0o777, 0o644);
+    }
+}
+
+#[test]
+fn build_check_reproducible_reports_differing_members() {
+    let (temp, project_dir, pythonpath) = setup_build_project(&archive_build_main(true));
+    let cache_home = temp.path().join("cache_home");
+
+    let (success, json) = run_json_build(&project_dir, &pythonpath, &cache_home, &[]);
+    assert!(!success, "check should fail: {json}");
+    assert_eq!(json["status"], "error");
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_BUILD_NOT_REPRODUCIBLE")
+        .unwrap_or_else(|| panic!("missing E_BUILD_NOT_REPRODUCIBLE: {json}"));
+    let message = diagnostic["message"].as_str().unwrap();
+    assert!(message.contains("demo_build-0.1.0-py3-none-any.whl"));
+    assert!(message.contains("(timestamp)"), "{message}");
+
+    let check = &json["detail"]["reproducible"]["check"];
+    assert_eq!(check["identical"], false);
+    assert_eq!(check["sources"], serde_json::json!(["timestamp"]));
+    let wheel = check["artifacts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|a| a["name"] == "demo_build-0.1.0-py3-none-any.whl")
+        .unwrap();
+    assert_eq!(wheel["identical"], false);
+    assert_eq!(
+        wheel["members"],
+        serde_json::json!([{
+            "name": "demo_build/_build.py",
+            "change": "content",
+            "source": "timestamp"
+        }])
+    );
+    assert_eq!(wheel["sources"], serde_json::json!(["timestamp"]));
+}
+
+#[test]
+fn build_env_reaches_the_backend_and_cli_overrides_config() {
+    let (temp, project_dir, pythonpath) = setup_build_project(&archive_build_main(false));
+    let cache_home = temp.path().join("cache_home");
+    let pyproject = project_dir.join("pyproject.toml");
+    let mut content = fs::read_to_string(&pyproject).unwrap();
+    content.push_str(
+        "\n[tool.pybun.build.env]\nBUILD_FLAVOR = \"debug\"\nSOURCE_DATE_EPOCH = \"1600000000\"\n",
+    );
+    fs::write(&pyproject, content).unwrap();
+
+    let (success, json) = run_json_build(
+        &project_dir,
+        &pythonpath,
+        &cache_home,
+        &["--env", "BUILD_FLAVOR=release"],
+    );
+    assert!(success, "check should pass: {json}");
+
+    let reproducible = &json["detail"]["reproducible"];
+    assert_eq!(reproducible["source_date_epoch"], 1_600_000_000);
+    assert_eq!(reproducible["epoch_source"], "config");
+    assert_eq!(reproducible["env"]["BUILD_FLAVOR"], "release");
+    assert_eq!(reproducible["env"]["TZ"], "UTC");
+    assert_eq!(reproducible["env"]["PYTHONHASHSEED"], "0");
+
+    let wheel = project_dir
+        .join("dist")
+        .join("demo_build-0.1.0-py3-none-any.whl");
+    let mut archive = zip::ZipArchive::new(fs::File::open(wheel).unwrap()).unwrap();
+    let mut init = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("demo_build/__init__.py").unwrap(),
+        &mut init,
+    )
+    .unwrap();
+    assert!(init.contains("FLAVOR = 'release'"), "{init}");
+    assert!(init.contains("TZ = 'UTC'"), "{init}");
+}
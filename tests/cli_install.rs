@@ -626,10 +626,12 @@ fn install_warns_and_errors_when_no_wheel_matches() {
             d["level"] == "warning"
                 && d["message"]
                     .as_str()
-                    .map(|m| m.contains("source-only") && m.contains("source distributions"))
+                    .map(|m| {
+                        m.contains("source-only") && m.contains("falling back to source build")
+                    })
                     .unwrap_or(false)
         }),
-        "should emit warning diagnostic about source build limitation: {stdout}"
+        "should warn about falling back to a source build: {stdout}"
     );
     assert!(
         diagnostics.iter().any(|d| {
@@ -1102,3 +1104,1058 @@ fn install_exact_match_ignores_local_label_and_prefers_it() {
     let pkg = lock.packages.get("pep440-local").expect("entry");
     assert_eq!(pkg.version, "1.0.0+cpu");
 }
+
+// =============================================================================
+// Install phase: wheels are downloaded, verified and extracted into the env
+// =============================================================================
+
+fn demo_wheel_bytes() -> Vec<u8> {
+    use std::io::Write;
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("demo_pkg/__init__.py", options).unwrap();
+    zip.write_all(b"VALUE = 1\n").unwrap();
+    zip.start_file("demo_pkg-1.0.0.dist-info/METADATA", options)
+        .unwrap();
+    zip.write_all(b"Metadata-Version: 2.1\nName: demo-pkg\nVersion: 1.0.0\n")
+        .unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+/// Fake PyPI JSON API serving `demo-pkg 1.0.0` with the given digest.
+fn demo_pypi(server: &httpmock::MockServer, sha256: &str) -> String {
+    demo_pypi_with_digests(server, serde_json::json!({ "sha256": sha256 }))
+}
+
+/// [`demo_pypi`] publishing `digests` for the wheel (`{}` for none).
+fn demo_pypi_with_digests(server: &httpmock::MockServer, digests: Value) -> String {
+    use httpmock::Method::GET;
+    let base = server.base_url();
+    let project = serde_json::json!({
+        "info": { "name": "demo-pkg", "version": "1.0.0" },
+        "releases": { "1.0.0": [{
+            "filename": "demo_pkg-1.0.0-py3-none-any.whl",
+            "packagetype": "bdist_wheel",
+            "url": format!("{base}/files/demo_pkg-1.0.0-py3-none-any.whl"),
+            "yanked": false,
+            "digests": digests
+        }]}
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/demo-pkg/json");
+        then.status(200).body(project.clone());
+    });
+    let meta = serde_json::json!({
+        "info": { "name": "demo-pkg", "version": "1.0.0", "requires_dist": [] }
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/demo-pkg/1.0.0/json");
+        then.status(200).body(meta.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/files/demo_pkg-1.0.0-py3-none-any.whl");
+        then.status(200).body(demo_wheel_bytes());
+    });
+    base
+}
+
+fn install_demo(dir: &std::path::Path, base_url: &str, venv: &std::path::Path) -> (bool, Value) {
+    let output = bin()
+        .current_dir(dir)
+        .env("PYBUN_PYPI_BASE_URL", base_url)
+        .env("PYBUN_PYPI_CACHE_DIR", dir.join("cache"))
+        .env("PYBUN_ENV", venv)
+        .args(["--format=json", "install", "--require", "demo-pkg==1.0.0"])
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+fn create_venv(dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    let venv = dir.join(".venv");
+    let ok = std::process::Command::new("python3")
+        .args(["-m", "venv", "--without-pip"])
+        .arg(&venv)
+        .status()
+        .is_ok_and(|s| s.success());
+    ok.then_some(venv)
+}
+
+#[test]
+fn install_reports_per_package_status_and_skips_installed_packages() {
+    let temp = tempdir().unwrap();
+    let Some(venv) = create_venv(temp.path()) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let server = httpmock::MockServer::start();
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(demo_wheel_bytes()));
+    let base = demo_pypi(&server, &sha256);
+
+    let (ok, json) = install_demo(temp.path(), &base, &venv);
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["installed_count"], 1);
+    let record = &detail["results"][0];
+    assert_eq!(record["name"], "demo-pkg");
+    assert_eq!(record["status"], "installed");
+    assert_eq!(record["hash"], format!("sha256:{sha256}"));
+    let dist_info = std::path::PathBuf::from(record["dist_info"].as_str().unwrap());
+    assert!(dist_info.join("METADATA").is_file());
+    let site_packages = detail["environment"]["site_packages"].as_str().unwrap();
+    assert!(
+        std::path::Path::new(site_packages)
+            .join("demo_pkg/__init__.py")
+            .is_file()
+    );
+
+    let (ok, json) = install_demo(temp.path(), &base, &venv);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["installed_count"], 0);
+    assert_eq!(json["detail"]["results"][0]["status"], "already_installed");
+}
+
+/// A `tool-pkg` wheel at `version` with a `tool-pkg` console script, its
+/// own `files` and a `RECORD` listing them.
+fn tool_wheel_bytes(version: &str, files: &[(&str, &str)]) -> Vec<u8> {
+    use std::io::Write;
+    let dist_info = format!("tool_pkg-{version}.dist-info");
+    let metadata = format!("Metadata-Version: 2.1\nName: tool-pkg\nVersion: {version}\n");
+    let entry_points = "[console_scripts]\ntool-pkg = tool_pkg:main\n".to_string();
+    let mut entries: Vec<(String, String)> = files
+        .iter()
+        .map(|(name, content)| (name.to_string(), content.to_string()))
+        .collect();
+    entries.push((format!("{dist_info}/METADATA"), metadata));
+    entries.push((format!("{dist_info}/entry_points.txt"), entry_points));
+    let mut record: String = entries
+        .iter()
+        .map(|(name, _)| format!("{name},,\n"))
+        .collect();
+    record.push_str(&format!("{dist_info}/RECORD,,\n"));
+    entries.push((format!("{dist_info}/RECORD"), record));
+
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, content) in entries {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Fake PyPI JSON API serving every `(version, wheel)` of `tool-pkg`.
+fn tool_pypi(server: &httpmock::MockServer, releases: &[(&str, Vec<u8>)]) -> String {
+    use httpmock::Method::GET;
+    let base = server.base_url();
+    let mut files = serde_json::Map::new();
+    for (version, wheel) in releases {
+        let filename = format!("tool_pkg-{version}-py3-none-any.whl");
+        let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(wheel));
+        files.insert(
+            version.to_string(),
+            serde_json::json!([{
+                "filename": filename,
+                "packagetype": "bdist_wheel",
+                "url": format!("{base}/files/{filename}"),
+                "yanked": false,
+                "digests": { "sha256": sha256 }
+            }]),
+        );
+        let meta = serde_json::json!({
+            "info": { "name": "tool-pkg", "version": version, "requires_dist": [] }
+        })
+        .to_string();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/pypi/tool-pkg/{version}/json"));
+            then.status(200).body(meta);
+        });
+        let wheel = wheel.clone();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/files/{filename}"));
+            then.status(200).body(wheel);
+        });
+    }
+    let latest = releases.last().map_or("", |(version, _)| *version);
+    let project = serde_json::json!({
+        "info": { "name": "tool-pkg", "version": latest },
+        "releases": files
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/tool-pkg/json");
+        then.status(200).body(project);
+    });
+    base
+}
+
+#[test]
+fn install_writes_entry_point_launchers_into_the_project_venv() {
+    let temp = tempdir().unwrap();
+    let python = std::process::Command::new("python3")
+        .arg("--version")
+        .output();
+    if !python.is_ok_and(|o| o.status.success()) {
+        eprintln!("skipping: python3 unavailable");
+        return;
+    }
+    let server = httpmock::MockServer::start();
+    let wheel = tool_wheel_bytes(
+        "1.0.0",
+        &[(
+            "tool_pkg/__init__.py",
+            "def main():\n    print('tool ran')\n",
+        )],
+    );
+    let base = tool_pypi(&server, &[("1.0.0", wheel)]);
+
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_PYPI_BASE_URL", &base)
+        .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("cache"))
+        .env_remove("PYBUN_ENV")
+        .args([
+            "--format=json",
+            "install",
+            "--no-seed",
+            "--require",
+            "tool-pkg==1.0.0",
+        ])
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(output.status.success(), "{json}");
+
+    let venv = temp.path().join(".pybun/venv");
+    let scripts = venv.join(if cfg!(windows) { "Scripts" } else { "bin" });
+    let dist_info =
+        std::path::PathBuf::from(json["detail"]["results"][0]["dist_info"].as_str().unwrap());
+    assert_eq!(
+        fs::read_to_string(dist_info.join("INSTALLER")).unwrap(),
+        "pybun\n"
+    );
+    let record = fs::read_to_string(dist_info.join("RECORD")).unwrap();
+    assert!(
+        record.contains("tool_pkg-1.0.0.dist-info/INSTALLER,sha256="),
+        "{record}"
+    );
+    if cfg!(unix) {
+        let launcher = scripts.join("tool-pkg");
+        assert!(record.contains("/bin/tool-pkg,sha256="), "{record}");
+        let ran = std::process::Command::new(&launcher).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&ran.stdout).trim(), "tool ran");
+    } else {
+        assert!(scripts.join("tool-pkg-script.py").is_file());
+    }
+}
+
+#[test]
+fn install_upgrade_removes_the_previous_version() {
+    let temp = tempdir().unwrap();
+    let Some(venv) = create_venv(temp.path()) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let server = httpmock::MockServer::start();
+    let base = tool_pypi(
+        &server,
+        &[
+            (
+                "1.0.0",
+                tool_wheel_bytes(
+                    "1.0.0",
+                    &[
+                        ("tool_pkg/__init__.py", "def main():\n    pass\n"),
+                        ("tool_pkg/old.py", "GONE = True\n"),
+                    ],
+                ),
+            ),
+            (
+                "2.0.0",
+                tool_wheel_bytes(
+                    "2.0.0",
+                    &[("tool_pkg/__init__.py", "def main():\n    pass\n")],
+                ),
+            ),
+        ],
+    );
+    let install = |spec: &str| {
+        let output = bin()
+            .current_dir(temp.path())
+            .env("PYBUN_PYPI_BASE_URL", &base)
+            .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("cache"))
+            .env("PYBUN_ENV", &venv)
+            .args(["--format=json", "install", "--require", spec])
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(output.status.success(), "{json}");
+        json
+    };
+
+    let json = install("tool-pkg==1.0.0");
+    let site_packages = std::path::PathBuf::from(
+        json["detail"]["environment"]["site_packages"]
+            .as_str()
+            .unwrap(),
+    );
+    assert!(site_packages.join("tool_pkg/old.py").is_file());
+
+    let json = install("tool-pkg==2.0.0");
+    let record = &json["detail"]["results"][0];
+    assert_eq!(record["status"], "installed");
+    assert_eq!(record["replaced"], serde_json::json!(["1.0.0"]));
+    assert!(!site_packages.join("tool_pkg-1.0.0.dist-info").exists());
+    assert!(site_packages.join("tool_pkg-2.0.0.dist-info").is_dir());
+    assert!(!site_packages.join("tool_pkg/old.py").exists());
+    assert!(site_packages.join("tool_pkg/__init__.py").is_file());
+}
+
+#[test]
+fn install_reports_download_failures_per_package() {
+    let temp = tempdir().unwrap();
+    let Some(venv) = create_venv(temp.path()) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let server = httpmock::MockServer::start();
+    let base = demo_pypi(&server, &"0".repeat(64));
+
+    let (ok, json) = install_demo(temp.path(), &base, &venv);
+    assert!(!ok);
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_INSTALL_DOWNLOAD_FAILED")
+        .unwrap_or_else(|| panic!("no E_INSTALL_DOWNLOAD_FAILED in {json}"));
+    let record = &diagnostic["context"]["results"][0];
+    assert_eq!(record["name"], "demo-pkg");
+    assert_eq!(record["status"], "download_failed");
+    assert!(record["error"].as_str().unwrap().contains("checksum"));
+}
+
+#[test]
+fn install_links_wheels_from_the_shared_store() {
+    let temp = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(demo_wheel_bytes()));
+    let base = demo_pypi(&server, &sha256);
+    let home = temp.path().join("home");
+
+    let mut records = Vec::new();
+    for project in ["a", "b"] {
+        let dir = temp.path().join(project);
+        fs::create_dir_all(&dir).unwrap();
+        let Some(venv) = create_venv(&dir) else {
+            eprintln!("skipping: python3 -m venv unavailable");
+            return;
+        };
+        let output = bin()
+            .current_dir(&dir)
+            .env("PYBUN_HOME", &home)
+            .env("PYBUN_PYPI_BASE_URL", &base)
+            .env("PYBUN_PYPI_CACHE_DIR", dir.join("cache"))
+            .env("PYBUN_ENV", &venv)
+            .args(["--format=json", "install", "--require", "demo-pkg==1.0.0"])
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(output.status.success(), "{json}");
+        assert!(
+            dir.join("cache/artifacts/demo_pkg-1.0.0-py3-none-any.whl")
+                .is_file()
+        );
+        records.push(json["detail"]["results"][0].clone());
+    }
+
+    assert_eq!(records[0]["status"], "installed");
+    assert!(records[0].get("cached").is_none(), "{}", records[0]);
+    assert_eq!(records[1]["status"], "installed");
+    assert_eq!(records[1]["cached"], true);
+    assert!(
+        home.join("wheels")
+            .join(&sha256[..2])
+            .join(&sha256)
+            .join("demo_pkg-1.0.0-py3-none-any.whl")
+            .is_file()
+    );
+}
+
+/// Every file under `dir` with its size and modification time.
+fn tree_snapshot(dir: &std::path::Path) -> Vec<(std::path::PathBuf, u64, std::time::SystemTime)> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).unwrap().flatten() {
+            let meta = fs::symlink_metadata(entry.path()).unwrap();
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else {
+                files.push((entry.path(), meta.len(), meta.modified().unwrap()));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[test]
+fn install_with_readonly_cache_writes_only_to_the_overlay() {
+    let temp = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(demo_wheel_bytes()));
+    let base = demo_pypi(&server, &sha256);
+    let shared = temp.path().join("shared");
+    let overlay = temp.path().join("overlay");
+
+    for (project, readonly) in [("warm", false), ("job", true)] {
+        let dir = temp.path().join(project);
+        fs::create_dir_all(&dir).unwrap();
+        let Some(venv) = create_venv(&dir) else {
+            eprintln!("skipping: python3 -m venv unavailable");
+            return;
+        };
+        let before = readonly.then(|| tree_snapshot(&shared));
+        let mut cmd = bin();
+        cmd.current_dir(&dir)
+            .env("PYBUN_HOME", &shared)
+            .env("PYBUN_PYPI_BASE_URL", &base)
+            .env("PYBUN_PYPI_CACHE_DIR", shared.join("pypi"))
+            .env("PYBUN_ENV", &venv)
+            .env("PYBUN_CACHE_OVERLAY", &overlay)
+            .args(["--format=json", "install", "--require", "demo-pkg==1.0.0"]);
+        if readonly {
+            cmd.env("PYBUN_READONLY_CACHE", "1");
+        } else {
+            cmd.env_remove("PYBUN_READONLY_CACHE");
+        }
+        let output = cmd.output().unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(output.status.success(), "{json}");
+        if let Some(before) = before {
+            assert_eq!(tree_snapshot(&shared), before, "the shared cache changed");
+        }
+    }
+    assert!(
+        overlay
+            .join("artifacts/demo_pkg-1.0.0-py3-none-any.whl")
+            .is_file()
+    );
+}
+
+#[test]
+fn install_frozen_installs_the_lockfile_without_resolving() {
+    let temp = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(demo_wheel_bytes()));
+    let base = demo_pypi(&server, &sha256);
+    let dir = temp.path().join("app");
+    fs::create_dir_all(&dir).unwrap();
+    let Some(venv) = create_venv(&dir) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let (ok, json) = install_demo(&dir, &base, &venv);
+    assert!(ok, "{json}");
+    let lock_path = dir.join("pybun.lockb");
+    let locked = fs::read(&lock_path).unwrap();
+    let lock = Lockfile::load_from_path(&lock_path).unwrap();
+    let artifact = &lock.packages["demo-pkg"].artifacts[0];
+    assert_eq!(
+        artifact.url.as_deref(),
+        Some(format!("{base}/files/demo_pkg-1.0.0-py3-none-any.whl").as_str())
+    );
+
+    // A fresh venv and cache, with an index that cannot be reached: the
+    // frozen install only downloads the locked files.
+    let frozen = temp.path().join("frozen");
+    fs::create_dir_all(&frozen).unwrap();
+    let venv = create_venv(&frozen).unwrap();
+    fs::copy(&lock_path, frozen.join("pybun.lockb")).unwrap();
+    let output = bin()
+        .current_dir(&frozen)
+        .env("PYBUN_PYPI_BASE_URL", "http://127.0.0.1:9")
+        .env("PYBUN_PYPI_CACHE_DIR", frozen.join("cache"))
+        .env("PYBUN_ENV", &venv)
+        .args(["--format=json", "install", "--frozen"])
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(output.status.success(), "{json}");
+    assert_eq!(
+        json["detail"]["results"][0]["status"], "installed",
+        "{json}"
+    );
+    assert!(
+        json["detail"]["results"][0]["dist_info"]
+            .as_str()
+            .is_some_and(|p| std::path::Path::new(p).is_dir()),
+        "{json}"
+    );
+    assert_eq!(fs::read(frozen.join("pybun.lockb")).unwrap(), locked);
+}
+
+#[test]
+fn install_frozen_reports_targets_missing_from_the_lockfile() {
+    let temp = tempdir().unwrap();
+    let index = index_pypi_wheels_path();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\"pypi-native==1.0.0\"]\n",
+    )
+    .unwrap();
+    bin()
+        .current_dir(temp.path())
+        .env("PYBUN_FORCE_CP_TAG", "cp311")
+        .args([
+            "lock",
+            "--index",
+            index.to_str().unwrap(),
+            "--platform",
+            "win_amd64",
+        ])
+        .assert()
+        .success();
+
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_FORCE_CP_TAG", "cp311")
+        .args(["--format=json", "install", "--frozen"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_INSTALL_LOCK_TARGET_MISSING")
+        .unwrap_or_else(|| panic!("{json}"));
+    assert_eq!(diagnostic["context"]["locked"]["platforms"][0], "win_amd64");
+    assert_eq!(diagnostic["context"]["packages"][0], "pypi-native==1.0.0");
+}
+
+#[test]
+fn install_frozen_requires_a_lockfile() {
+    let temp = tempdir().unwrap();
+    let output = bin()
+        .current_dir(temp.path())
+        .args(["--format=json", "install", "--frozen"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("E_INSTALL_LOCK_MISSING"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+fn targz(root: &str, files: &[(&str, &str)]) -> Vec<u8> {
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for (path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{root}/{path}"), content.as_bytes())
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+/// Fake PyPI serving `src-pkg 1.0` as an sdist only. Its in-tree backend
+/// writes a pure-Python wheel without needing any build requirements.
+fn sdist_only_pypi(server: &httpmock::MockServer) -> (String, String) {
+    use httpmock::Method::GET;
+    let backend = r#"
+import os, zipfile
+
+def build_wheel(wheel_directory, config_settings=None, metadata_directory=None):
+    name = "src_pkg-1.0-py3-none-any.whl"
+    with zipfile.ZipFile(os.path.join(wheel_directory, name), "w") as whl:
+        whl.writestr("src_pkg/__init__.py", "BUILT = True\n")
+        whl.writestr("src_pkg-1.0.dist-info/METADATA", "Metadata-Version: 2.1\nName: src-pkg\nVersion: 1.0\n")
+    return name
+"#;
+    let sdist = targz(
+        "src_pkg-1.0",
+        &[
+            (
+                "pyproject.toml",
+                "[build-system]\nrequires = []\nbuild-backend = \"backend\"\nbackend-path = [\".\"]\n",
+            ),
+            ("backend.py", backend),
+        ],
+    );
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(&sdist));
+    let base = server.base_url();
+    let project = serde_json::json!({
+        "info": { "name": "src-pkg", "version": "1.0" },
+        "releases": { "1.0": [{
+            "filename": "src_pkg-1.0.tar.gz",
+            "packagetype": "sdist",
+            "url": format!("{base}/files/src_pkg-1.0.tar.gz"),
+            "yanked": false,
+            "digests": { "sha256": sha256 }
+        }]}
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/src-pkg/json");
+        then.status(200).body(project.clone());
+    });
+    let meta = serde_json::json!({
+        "info": { "name": "src-pkg", "version": "1.0", "requires_dist": [] }
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/src-pkg/1.0/json");
+        then.status(200).body(meta.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/files/src_pkg-1.0.tar.gz");
+        then.status(200).body(sdist.clone());
+    });
+    (base, sha256)
+}
+
+#[test]
+fn install_builds_sdist_only_packages_and_caches_the_wheel() {
+    let temp = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    let (base, sdist_sha256) = sdist_only_pypi(&server);
+    let home = temp.path().join("home");
+
+    let mut records = Vec::new();
+    for project in ["a", "b"] {
+        let dir = temp.path().join(project);
+        fs::create_dir_all(&dir).unwrap();
+        let Some(venv) = create_venv(&dir) else {
+            eprintln!("skipping: python3 -m venv unavailable");
+            return;
+        };
+        let output = bin()
+            .current_dir(&dir)
+            .env("PYBUN_HOME", &home)
+            .env("PYBUN_PYPI_BASE_URL", &base)
+            .env("PYBUN_PYPI_CACHE_DIR", dir.join("cache"))
+            .env("PYBUN_ENV", &venv)
+            .args(["--format=json", "install", "--require", "src-pkg==1.0"])
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(output.status.success(), "{json}");
+        let site_packages = json["detail"]["environment"]["site_packages"]
+            .as_str()
+            .unwrap();
+        assert!(
+            std::path::Path::new(site_packages)
+                .join("src_pkg/__init__.py")
+                .is_file()
+        );
+
+        let lock = Lockfile::load_from_path(dir.join("pybun.lockb")).unwrap();
+        let pkg = &lock.packages["src-pkg"];
+        assert_eq!(pkg.wheel, "src_pkg-1.0.tar.gz");
+        assert_eq!(pkg.hash, format!("sha256:{sdist_sha256}"));
+        let build = pkg.build.as_ref().expect("build inputs are locked");
+        assert_eq!(build.backend, "backend");
+        assert!(build.requires.is_empty());
+        assert_eq!(build.wheel, "src_pkg-1.0-py3-none-any.whl");
+
+        records.push(json["detail"]["results"][0].clone());
+    }
+
+    assert_eq!(records[0]["status"], "installed");
+    assert_eq!(records[0]["built"], true);
+    assert!(records[0].get("cached").is_none(), "{}", records[0]);
+    assert_eq!(records[1]["built"], true);
+    assert_eq!(
+        records[1]["cached"], true,
+        "second project reuses the build"
+    );
+    assert_eq!(records[0]["hash"], records[1]["hash"]);
+}
+
+// =============================================================================
+// Wheels follow the interpreter's architecture (e.g. x86_64 Python under
+// Rosetta), with per-package overrides in [tool.pybun.arch].
+// =============================================================================
+
+/// A fake venv whose `python` reports Python 3.11.7 and answers the
+/// architecture probe as an aarch64 Linux interpreter.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn fake_aarch64_venv(root: &std::path::Path) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let venv_dir = root.join(".fake-venv");
+    let bin_dir = venv_dir.join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    let python = bin_dir.join("python");
+    fs::write(
+        &python,
+        "#!/bin/sh\nif [ \"$1\" = \"--version\" ]; then echo 'Python 3.11.7'; else printf 'aarch64\\nlinux-aarch64\\n64\\n'; fi\n",
+    )
+    .unwrap();
+    fs::set_permissions(&python, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(venv_dir.join("pyvenv.cfg"), "version = 3.11.7\n").unwrap();
+    venv_dir
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn install_native_json(dir: &std::path::Path, venv: &std::path::Path) -> (bool, Value) {
+    let output = bin()
+        .current_dir(dir)
+        .env("PYBUN_ENV", venv)
+        .env_remove("PYBUN_FORCE_CP_TAG")
+        .env_remove("PYBUN_PYTHON_ARCH")
+        .args([
+            "--format=json",
+            "install",
+            "--index",
+            index_pypi_wheels_path().to_str().unwrap(),
+            "--require",
+            "pypi-native==1.0.0",
+        ])
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        serde_json::from_slice(&output.stdout).unwrap(),
+    )
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn install_selects_wheels_for_the_interpreter_arch() {
+    let temp = tempdir().unwrap();
+    let venv = fake_aarch64_venv(temp.path());
+
+    let (ok, json) = install_native_json(temp.path(), &venv);
+    assert!(ok, "{json}");
+    let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+    assert_eq!(
+        lock.packages["pypi-native"].wheel,
+        "pypi-native-1.0.0-cp311-cp311-manylinux_2_17_aarch64.manylinux2014_aarch64.whl"
+    );
+    assert!(
+        !json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "W_ARCH_MISMATCH"),
+        "{json}"
+    );
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn install_warns_when_an_arch_override_forces_an_unloadable_wheel() {
+    let temp = tempdir().unwrap();
+    let venv = fake_aarch64_venv(temp.path());
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\n\n[tool.pybun.arch]\npypi-native = \"x86_64\"\n",
+    )
+    .unwrap();
+
+    let (ok, json) = install_native_json(temp.path(), &venv);
+    assert!(ok, "{json}");
+    let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+    assert_eq!(
+        lock.packages["pypi-native"].wheel,
+        "pypi-native-1.0.0-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl"
+    );
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "W_ARCH_MISMATCH")
+        .unwrap_or_else(|| panic!("no W_ARCH_MISMATCH in {json}"));
+    assert_eq!(diagnostic["context"]["package"], "pypi-native");
+    assert_eq!(diagnostic["context"]["wheel_arch"], "x86_64");
+    assert_eq!(diagnostic["context"]["interpreter_arch"], "arm64");
+    assert_eq!(diagnostic["context"]["override"], "x86_64");
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn install_rejects_arch_overrides_the_os_has_no_wheels_for() {
+    let temp = tempdir().unwrap();
+    let venv = fake_aarch64_venv(temp.path());
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\n\n[tool.pybun.arch]\npypi-native = \"universal2\"\n",
+    )
+    .unwrap();
+
+    let (ok, json) = install_native_json(temp.path(), &venv);
+    assert!(!ok);
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "E_ARCH_OVERRIDE_INVALID"),
+        "{json}"
+    );
+}
+
+// =============================================================================
+// Hash enforcement: artifacts without a published SHA-256 are hashed while
+// locking unless `--require-hashes`, and mismatches are reported per artifact.
+// =============================================================================
+
+#[test]
+fn install_hashes_artifacts_the_index_publishes_no_digest_for() {
+    let temp = tempdir().unwrap();
+    let Some(venv) = create_venv(temp.path()) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let server = httpmock::MockServer::start();
+    let base = demo_pypi_with_digests(&server, serde_json::json!({}));
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(demo_wheel_bytes()));
+
+    let (ok, json) = install_demo(temp.path(), &base, &venv);
+    assert!(ok, "{json}");
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "W_HASH_COMPUTED"),
+        "{json}"
+    );
+    assert_eq!(json["detail"]["artifacts"][0]["hash_source"], "computed");
+    assert_eq!(json["detail"]["results"][0]["status"], "installed");
+    let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+    assert_eq!(lock.packages["demo-pkg"].hash, format!("sha256:{sha256}"));
+}
+
+#[test]
+fn install_require_hashes_rejects_artifacts_without_a_published_digest() {
+    let temp = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    let base = demo_pypi_with_digests(&server, serde_json::json!({}));
+
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_PYPI_BASE_URL", &base)
+        .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("cache"))
+        .args([
+            "--format=json",
+            "install",
+            "--require-hashes",
+            "--require",
+            "demo-pkg==1.0.0",
+        ])
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!output.status.success());
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "E_VERIFY_MISSING_HASH"),
+        "{json}"
+    );
+    assert!(!temp.path().join("pybun.lockb").exists());
+}
+
+#[test]
+fn install_reports_hash_mismatches_with_expected_and_actual_digests() {
+    let temp = tempdir().unwrap();
+    let Some(venv) = create_venv(temp.path()) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let server = httpmock::MockServer::start();
+    let base = demo_pypi(&server, &"0".repeat(64));
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(demo_wheel_bytes()));
+
+    let (ok, json) = install_demo(temp.path(), &base, &venv);
+    assert!(!ok);
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_INSTALL_HASH_MISMATCH")
+        .unwrap_or_else(|| panic!("no E_INSTALL_HASH_MISMATCH in {json}"));
+    let artifact = &diagnostic["context"]["artifacts"][0];
+    assert_eq!(artifact["package"], "demo-pkg");
+    assert_eq!(artifact["expected"], format!("sha256:{}", "0".repeat(64)));
+    assert_eq!(artifact["actual"], format!("sha256:{sha256}"));
+    assert_eq!(artifact["source"], "download");
+}
+
+#[test]
+fn install_require_hashes_reverifies_wheels_from_the_store() {
+    let temp = tempdir().unwrap();
+    let server = httpmock::MockServer::start();
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(demo_wheel_bytes()));
+    let base = demo_pypi(&server, &sha256);
+    let home = temp.path().join("home");
+
+    let install = |project: &str, extra: &[&str]| {
+        let dir = temp.path().join(project);
+        fs::create_dir_all(&dir).unwrap();
+        let venv = create_venv(&dir)?;
+        let output = bin()
+            .current_dir(&dir)
+            .env("PYBUN_HOME", &home)
+            .env("PYBUN_PYPI_BASE_URL", &base)
+            .env("PYBUN_PYPI_CACHE_DIR", dir.join("cache"))
+            .env("PYBUN_ENV", &venv)
+            .args(["--format=json", "install", "--require", "demo-pkg==1.0.0"])
+            .args(extra)
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        Some((output.status.success(), json))
+    };
+    let Some((ok, json)) = install("a", &[]) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    assert!(ok, "{json}");
+
+    let stored = home
+        .join("wheels")
+        .join(&sha256[..2])
+        .join(&sha256)
+        .join("demo_pkg-1.0.0-py3-none-any.whl");
+    let mut tampered = fs::read(&stored).unwrap();
+    tampered.extend_from_slice(b"tampered");
+    fs::remove_file(&stored).unwrap();
+    fs::write(&stored, tampered).unwrap();
+
+    let (ok, json) = install("b", &["--require-hashes"]).unwrap();
+    assert!(!ok);
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_INSTALL_HASH_MISMATCH")
+        .unwrap_or_else(|| panic!("no E_INSTALL_HASH_MISMATCH in {json}"));
+    assert_eq!(
+        diagnostic["context"]["artifacts"][0]["source"],
+        "wheel_store"
+    );
+}
+
+fn write_grouped_project(dir: &std::path::Path) {
+    fs::write(
+        dir.join("pyproject.toml"),
+        r#"[project]
+name = "grouped"
+version = "0.1.0"
+dependencies = ["lib-a==1.0.0"]
+
+[dependency-groups]
+test = ["lib-b==2.0.0"]
+"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn install_group_records_groups_in_the_lockfile() {
+    let temp = tempdir().unwrap();
+    write_grouped_project(temp.path());
+    let index = index_path();
+
+    bin()
+        .current_dir(temp.path())
+        .args([
+            "install",
+            "--index",
+            index.to_str().unwrap(),
+            "--group",
+            "test",
+        ])
+        .assert()
+        .success();
+
+    let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+    let groups: Vec<(&str, Vec<&str>)> = lock
+        .packages
+        .values()
+        .map(|pkg| {
+            (
+                pkg.name.as_str(),
+                pkg.groups.iter().map(String::as_str).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            ("lib-a", vec!["main"]),
+            ("lib-b", vec!["test"]),
+            ("lib-c", vec!["main"]),
+        ]
+    );
+}
+
+#[test]
+fn install_frozen_only_installs_selected_groups() {
+    let temp = tempdir().unwrap();
+    write_grouped_project(temp.path());
+    let index = index_path();
+    bin()
+        .current_dir(temp.path())
+        .args([
+            "install",
+            "--index",
+            index.to_str().unwrap(),
+            "--group",
+            "test",
+        ])
+        .assert()
+        .success();
+
+    // The fixture index has no download URLs, so every package the frozen
+    // install would fetch is reported as missing.
+    let frozen = |extra: &[&str]| -> Value {
+        let output = bin()
+            .current_dir(temp.path())
+            .args(["--format=json", "install", "--frozen"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["code"] == "E_INSTALL_LOCK_TARGET_MISSING")
+            .unwrap_or_else(|| panic!("{json}"))["context"]["packages"]
+            .clone()
+    };
+    assert_eq!(
+        frozen(&[]),
+        serde_json::json!(["lib-a==1.0.0", "lib-c==1.0.0"])
+    );
+    assert_eq!(
+        frozen(&["--group", "test"]),
+        serde_json::json!(["lib-a==1.0.0", "lib-b==2.0.0", "lib-c==1.0.0"])
+    );
+}
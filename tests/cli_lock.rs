@@ -329,3 +329,421 @@ fn lock_without_script_or_pyproject_fails_with_actionable_error() {
         "expected an actionable error diagnostic mentioning --script and pyproject.toml: {diagnostics:?}"
     );
 }
+
+fn lock_script_json(dir: &Path, deps: &str, index: &str, extra: &[&str]) -> (bool, Value) {
+    let script = dir.join("resolve.py");
+    fs::write(
+        &script,
+        format!("# /// script\n# dependencies = [{deps}]\n# ///\nprint('hi')\n"),
+    )
+    .unwrap();
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("manifest dir");
+    let index = Path::new(&manifest_dir).join("tests/fixtures").join(index);
+    let output = bin()
+        .args(["--format=json", "lock", "--script"])
+        .arg(&script)
+        .arg("--index")
+        .arg(&index)
+        .args(extra)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    (output.status.success(), json)
+}
+
+#[test]
+fn lock_pubgrub_resolver_backtracks_where_default_conflicts() {
+    let temp = tempdir().unwrap();
+
+    let (ok, json) = lock_script_json(temp.path(), r#""a", "b""#, "index_backtrack.json", &[]);
+    assert!(!ok);
+    assert!(json.to_string().contains("E_RESOLVE_CONFLICT"), "{json}");
+
+    let (ok, json) = lock_script_json(
+        temp.path(),
+        r#""a", "b""#,
+        "index_backtrack.json",
+        &["--resolver", "pubgrub", "--compare"],
+    );
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["resolver"], "pubgrub");
+    let comparison = &detail["comparison"];
+    assert_eq!(comparison["candidate"]["resolver"], "pubgrub");
+    assert_eq!(comparison["candidate"]["ok"], true);
+    assert_eq!(comparison["baseline"]["resolver"], "default");
+    assert_eq!(comparison["baseline"]["ok"], false);
+    assert!(comparison["baseline"]["error"].is_string());
+
+    let lock = Lockfile::load_from_path(script_lock_path(&temp.path().join("resolve.py"))).unwrap();
+    assert_eq!(lock.packages["a"].version, "1.0.0");
+    assert_eq!(lock.packages["c"].version, "1.0.0");
+}
+
+#[test]
+fn lock_compare_reports_identical_resolutions() {
+    let temp = tempdir().unwrap();
+    let (ok, json) = lock_script_json(
+        temp.path(),
+        r#""app==1.0.0""#,
+        "index.json",
+        &["--resolver", "pubgrub", "--compare"],
+    );
+    assert!(ok, "{json}");
+    let comparison = &json["detail"]["comparison"];
+    assert_eq!(comparison["identical"], true, "{json}");
+    assert_eq!(comparison["diff"], serde_json::json!([]));
+    assert!(comparison["baseline"]["elapsed_ms"].is_u64());
+}
+
+#[test]
+fn external_uv_resolver_needs_a_pypi_index() {
+    let temp = tempdir().unwrap();
+    let (ok, json) = lock_script_json(
+        temp.path(),
+        r#""app==1.0.0""#,
+        "index.json",
+        &["--resolver", "external:uv"],
+    );
+    assert!(!ok);
+    assert!(json.to_string().contains("E_RESOLVE_BACKEND"), "{json}");
+}
+
+#[test]
+fn lock_platform_override_selects_wheels_for_the_target() {
+    let temp = tempdir().unwrap();
+    let deps = r#""pypi-native==1.0.0", "manylinux28-only==3.0.0""#;
+    let lock_for = |platform: &str| {
+        let script = temp.path().join("resolve.py");
+        fs::write(
+            &script,
+            format!("# /// script\n# dependencies = [{deps}]\n# ///\n"),
+        )
+        .unwrap();
+        let output = bin()
+            .env("PYBUN_FORCE_CP_TAG", "cp311")
+            .args(["--format=json", "lock", "--script"])
+            .arg(&script)
+            .args(["--index", "tests/fixtures/index_pypi_wheels.json"])
+            .args(["--platform", platform])
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+        (output.status.success(), json, script_lock_path(&script))
+    };
+
+    let (ok, json, lock_path) = lock_for("manylinux_2_28_aarch64");
+    assert!(ok, "{json}");
+    assert_eq!(
+        json["detail"]["target"]["platform"],
+        "manylinux_2_28_aarch64"
+    );
+    assert_eq!(json["detail"]["target"]["python"], "3.11");
+    let lock = Lockfile::load_from_path(&lock_path).unwrap();
+    assert_eq!(lock.platforms, vec!["manylinux_2_28_aarch64"]);
+    assert_eq!(lock.python_versions, vec!["3.11"]);
+    assert_eq!(
+        lock.packages["pypi-native"].wheel,
+        "pypi-native-1.0.0-cp311-cp311-manylinux_2_17_aarch64.manylinux2014_aarch64.whl"
+    );
+    assert_eq!(
+        lock.packages["manylinux28-only"].wheel,
+        "manylinux28-only-3.0.0-cp311-cp311-manylinux_2_28_aarch64.whl"
+    );
+
+    let script = temp.path().join("resolve.py");
+    fs::write(
+        &script,
+        "# /// script\n# dependencies = [\"pypi-native==1.0.0\"]\n# ///\n",
+    )
+    .unwrap();
+    for (platform, wheel) in [
+        (
+            "macosx_14_0_arm64",
+            "pypi-native-1.0.0-cp311-cp311-macosx_11_0_arm64.whl",
+        ),
+        ("win_amd64", "pypi-native-1.0.0-cp311-cp311-win_amd64.whl"),
+        ("win32", "pypi-native-1.0.0-py3-none-any.whl"),
+    ] {
+        bin()
+            .env("PYBUN_FORCE_CP_TAG", "cp311")
+            .args(["lock", "--script"])
+            .arg(&script)
+            .args(["--index", "tests/fixtures/index_pypi_wheels.json"])
+            .args(["--platform", platform])
+            .assert()
+            .success();
+        let lock = Lockfile::load_from_path(script_lock_path(&script)).unwrap();
+        assert_eq!(lock.packages["pypi-native"].wheel, wheel, "{platform}");
+    }
+}
+
+#[test]
+fn lock_rejects_unknown_platform_before_resolving() {
+    let temp = tempdir().unwrap();
+    let (ok, json) = lock_script_json(
+        temp.path(),
+        r#""app==1.0.0""#,
+        "index.json",
+        &["--platform", "solaris_sparc"],
+    );
+    assert!(!ok);
+    assert!(
+        json.to_string().contains("E_LOCK_UNKNOWN_PLATFORM"),
+        "{json}"
+    );
+    assert!(!script_lock_path(&temp.path().join("resolve.py")).exists());
+}
+
+#[test]
+fn lock_records_artifacts_for_every_target() {
+    let temp = tempdir().unwrap();
+    let (ok, json) = lock_script_json(
+        temp.path(),
+        r#""pypi-native==1.0.0""#,
+        "index_pypi_wheels.json",
+        &[
+            "--platform",
+            "manylinux_2_28_x86_64",
+            "--platform",
+            "macosx_14_0_arm64",
+            "--python",
+            "3.11",
+            "--python",
+            "3.12",
+        ],
+    );
+    assert!(ok, "{json}");
+    let targets = json["detail"]["targets"].as_array().expect("targets");
+    assert_eq!(targets.len(), 4, "{json}");
+    assert_eq!(targets[0]["python"], "3.11");
+    assert_eq!(targets[0]["platform"], "manylinux_2_28_x86_64");
+    assert_eq!(
+        targets[1]["packages"]["pypi-native"],
+        "pypi-native-1.0.0-cp311-cp311-macosx_11_0_arm64.whl"
+    );
+
+    let lock = Lockfile::load_from_path(script_lock_path(&temp.path().join("resolve.py"))).unwrap();
+    assert_eq!(
+        lock.platforms,
+        vec!["manylinux_2_28_x86_64", "macosx_14_0_arm64"]
+    );
+    assert_eq!(lock.python_versions, vec!["3.11", "3.12"]);
+    let native = &lock.packages["pypi-native"];
+    let mut files: Vec<&str> = native
+        .artifacts
+        .iter()
+        .map(|a| a.filename.as_str())
+        .collect();
+    files.sort();
+    files.dedup();
+    assert_eq!(files.len(), native.artifacts.len(), "artifacts are unique");
+    assert!(
+        files.contains(&"pypi-native-1.0.0-cp311-cp311-macosx_11_0_arm64.whl"),
+        "{files:?}"
+    );
+    assert!(
+        files.iter().any(|f| f.contains("manylinux_2_17_x86_64")),
+        "{files:?}"
+    );
+    assert_eq!(native.wheel, native.artifacts[0].filename);
+}
+
+#[test]
+fn lock_rejects_unknown_python_version() {
+    let temp = tempdir().unwrap();
+    let (ok, json) = lock_script_json(
+        temp.path(),
+        r#""app==1.0.0""#,
+        "index.json",
+        &["--python", "2.7"],
+    );
+    assert!(!ok);
+    assert!(json.to_string().contains("E_LOCK_UNKNOWN_PYTHON"), "{json}");
+    assert!(!script_lock_path(&temp.path().join("resolve.py")).exists());
+}
+
+#[test]
+fn lock_check_detects_drift_from_pyproject_without_resolving() {
+    let temp = tempdir().unwrap();
+    let pyproject_path = temp.path().join("pyproject.toml");
+    fs::write(
+        &pyproject_path,
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"app==1.0.0\"]\n",
+    )
+    .unwrap();
+    bin()
+        .current_dir(temp.path())
+        .args(["lock", "--index", index_path().to_str().unwrap()])
+        .assert()
+        .success();
+
+    let check = || {
+        let output = bin()
+            .current_dir(temp.path())
+            .args(["--format=json", "lock", "--check"])
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        (output.status.success(), json)
+    };
+    let (ok, json) = check();
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["up_to_date"], true);
+
+    fs::write(
+        &pyproject_path,
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"app>=2\", \"rich\"]\n",
+    )
+    .unwrap();
+    let (ok, json) = check();
+    assert!(!ok);
+    let kinds: Vec<&str> = json["detail"]["drift"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["missing", "unsatisfied"]);
+    let diagnostic = &json["diagnostics"][0];
+    assert_eq!(diagnostic["code"], "E_LOCK_DRIFT");
+    assert_eq!(diagnostic["message"], "rich is declared but not locked");
+    assert_eq!(diagnostic["fix_candidates"][0]["command"], "pybun lock");
+    assert_eq!(diagnostic["fix_candidates"][1]["command"], "pybun install");
+}
+
+#[test]
+fn lock_check_fails_when_the_lockfile_is_missing() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"app\"]\n",
+    )
+    .unwrap();
+    let output = bin()
+        .current_dir(temp.path())
+        .args(["--format=json", "lock", "--check"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["diagnostics"][0]["code"], "E_LOCK_MISSING");
+}
+
+#[test]
+fn lock_update_only_moves_one_package_with_minimal_bumps() {
+    let temp = tempdir().unwrap();
+    let package = |name: &str, version: &str, deps: &[&str]| {
+        serde_json::json!({
+            "name": name,
+            "version": version,
+            "dependencies": deps,
+            "wheels": [{
+                "file": format!("{name}-{version}-py3-none-any.whl"),
+                "hash": format!("sha256:{name}{}", version.replace('.', "")),
+            }],
+        })
+    };
+    let index = temp.path().join("index.json");
+    fs::write(
+        &index,
+        serde_json::to_string(&[
+            package("web", "1.0", &["core>=1.0", "legacy"]),
+            package("web", "2.0", &["core>=1.3", "fresh"]),
+            package("core", "1.0", &[]),
+            package("core", "1.2", &[]),
+            package("core", "1.3", &[]),
+            package("core", "1.4", &[]),
+            package("core", "2.0", &[]),
+            package("legacy", "1.0", &[]),
+            package("fresh", "1.0", &[]),
+            package("plugin", "1.0", &["core<2"]),
+        ])
+        .unwrap(),
+    )
+    .unwrap();
+    let pyproject = |deps: &str| {
+        fs::write(
+            temp.path().join("pyproject.toml"),
+            format!("[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [{deps}]\n"),
+        )
+        .unwrap();
+    };
+    let lock = |extra: &[&str]| {
+        let output = bin()
+            .current_dir(temp.path())
+            .env("PYBUN_HOME", temp.path().join(".home"))
+            .env("PYBUN_FORCE_CP_TAG", "cp312")
+            .args(["--format=json", "lock", "--index"])
+            .arg(&index)
+            .args(extra)
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        (output.status.success(), json)
+    };
+    let versions = || {
+        let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+        lock.packages
+            .values()
+            .map(|pkg| format!("{} {}", pkg.name, pkg.version))
+            .collect::<Vec<_>>()
+    };
+
+    pyproject("\"web<2\", \"plugin\", \"core<1.3\"");
+    let (ok, json) = lock(&[]);
+    assert!(ok, "{json}");
+    assert_eq!(
+        versions(),
+        ["core 1.2", "legacy 1.0", "plugin 1.0", "web 1.0"]
+    );
+
+    pyproject("\"web\", \"plugin\"");
+    let (ok, json) = lock(&["--update-only", "web==2.0"]);
+    assert!(ok, "{json}");
+    assert_eq!(
+        versions(),
+        ["core 1.3", "fresh 1.0", "plugin 1.0", "web 2.0"]
+    );
+    let detail = &json["detail"];
+    assert_eq!(detail["updated"], true);
+    assert_eq!(
+        detail["changeset"],
+        serde_json::json!([
+            {"name": "core", "change": "changed", "before": "1.2", "after": "1.3",
+             "reason": "required by web 2.0 (core>=1.3)"},
+            {"name": "fresh", "change": "added", "after": "1.0",
+             "reason": "new dependency of web 2.0"},
+            {"name": "legacy", "change": "removed", "before": "1.0",
+             "reason": "no longer required"},
+            {"name": "web", "change": "changed", "before": "1.0", "after": "2.0",
+             "reason": "requested"},
+        ])
+    );
+    let lockfile = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+    assert_eq!(lockfile.packages["fresh"].groups, ["main"]);
+    assert_eq!(lockfile.packages["web"].hash, "sha256:web20");
+
+    // plugin keeps its version and rules core 2.0 out; nothing is written.
+    let (ok, json) = lock(&["--update-only", "core==2.0"]);
+    assert!(!ok);
+    assert_eq!(json["diagnostics"][0]["code"], "E_LOCK_UPDATE_CONFLICT");
+    assert!(
+        json["diagnostics"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("plugin 1.0 requires core<2, not 2.0"),
+        "{json}"
+    );
+    assert_eq!(
+        versions(),
+        ["core 1.3", "fresh 1.0", "plugin 1.0", "web 2.0"]
+    );
+
+    let (ok, json) = lock(&["--update-only", "web==2.0"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["updated"], false);
+    let (ok, json) = lock(&["--update-only", "requests==2.0"]);
+    assert!(!ok);
+    assert_eq!(json["diagnostics"][0]["code"], "E_LOCK_UPDATE_FAILED");
+}
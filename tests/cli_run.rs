@@ -70,6 +70,117 @@ fn run_no_target_error() {
     bin().args(["run"]).assert().failure();
 }
 
+fn run_json(temp: &std::path::Path, args: &[&str]) -> (Option<i32>, Value) {
+    let output = bin()
+        .current_dir(temp)
+        .env("PYTHONPATH", temp.join("site"))
+        .arg("--format=json")
+        .arg("run")
+        .args(args)
+        .output()
+        .expect("run pybun");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = serde_json::from_str(&stdout)
+        .unwrap_or_else(|_| panic!("expected valid JSON, got: {stdout}"));
+    (output.status.code(), value)
+}
+
+/// A `site` directory with a `demo` package and a `demo-tool` distribution
+/// declaring the `demo` console script.
+fn write_demo_site(temp: &std::path::Path) {
+    let site = temp.join("site");
+    fs::create_dir_all(site.join("demo")).unwrap();
+    fs::write(site.join("demo").join("__init__.py"), "").unwrap();
+    fs::write(
+        site.join("demo").join("cli.py"),
+        "import sys\n\ndef main():\n    print('demo', sys.argv)\n    return 3\n",
+    )
+    .unwrap();
+    fs::write(
+        site.join("demo").join("__main__.py"),
+        "import sys\nprint('main', sys.argv[1:])\n",
+    )
+    .unwrap();
+    let dist_info = site.join("demo_tool-1.0.0.dist-info");
+    fs::create_dir_all(&dist_info).unwrap();
+    fs::write(
+        dist_info.join("entry_points.txt"),
+        "[console_scripts]\ndemo = demo.cli:main\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn run_module_like_python_m() {
+    let temp = tempdir().unwrap();
+    write_demo_site(temp.path());
+
+    let (code, value) = run_json(temp.path(), &["-m", "demo", "--", "x", "y"]);
+    assert_eq!(code, Some(0), "{value}");
+    assert_eq!(value["detail"]["target"], "-m demo");
+    assert_eq!(value["detail"]["stdout"], "main ['x', 'y']\n");
+
+    bin()
+        .args(["run", "-m", "json.tool", "--", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("usage"));
+}
+
+#[test]
+fn run_module_typo_suggests_similar_module() {
+    let temp = tempdir().unwrap();
+    write_demo_site(temp.path());
+
+    let (code, value) = run_json(temp.path(), &["-m", "demo.clii"]);
+    assert_eq!(code, Some(1));
+    let diagnostics = value["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{value}");
+    assert_eq!(diagnostics[0]["code"], "E_RUN_MODULE_NOT_FOUND");
+    assert_eq!(diagnostics[0]["context"]["did_you_mean"][0], "demo.cli");
+    assert_eq!(
+        diagnostics[0]["suggestion"],
+        "Did you mean `pybun run -m demo.cli`?"
+    );
+
+    let (_, value) = run_json(temp.path(), &["-m", "jsno"]);
+    assert_eq!(
+        value["diagnostics"][0]["context"]["did_you_mean"][0],
+        "json"
+    );
+}
+
+#[test]
+fn run_module_conflicts_with_code_and_target() {
+    bin()
+        .args(["run", "-m", "json", "-c", "pass"])
+        .assert()
+        .failure();
+    bin()
+        .args(["run", "-m", "json", "script.py"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn run_console_script_entry_point() {
+    let temp = tempdir().unwrap();
+    write_demo_site(temp.path());
+
+    let (code, value) = run_json(temp.path(), &["demo", "--", "a"]);
+    assert_eq!(code, Some(3), "{value}");
+    assert_eq!(value["detail"]["target"], "demo");
+    assert_eq!(value["detail"]["stdout"], "demo ['demo', 'a']\n");
+    let entry_point = &value["detail"]["entry_point"];
+    assert_eq!(entry_point["distribution"], "demo_tool");
+    assert_eq!(entry_point["module"], "demo.cli");
+    assert_eq!(entry_point["attr"], "main");
+
+    let (code, value) = run_json(temp.path(), &["no-such-tool"]);
+    assert_eq!(code, Some(1));
+    assert_eq!(value["diagnostics"][0]["code"], "E_RUN_TARGET_NOT_FOUND");
+}
+
 #[test]
 fn run_with_pep723_metadata() {
     let temp = tempdir().unwrap();
@@ -764,6 +875,10 @@ fn write_lockfile_with_wheel(path: &std::path::Path, wheel_filename: &str) {
         wheel: wheel_filename.to_string(),
         hash: "sha256:deadbeef".to_string(),
         dependencies: Vec::new(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
     });
     lock.save_to_path(path).unwrap();
 }
@@ -1010,6 +1125,65 @@ fn run_with_prod_profile_lazy_imports_injected() {
     );
 }
 
+#[test]
+fn run_with_lazy_imports_reports_deferred_modules_and_savings() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("heavy.py"),
+        "import time\ntime.sleep(0.05)\nVALUE = 1\n",
+    )
+    .unwrap();
+    fs::write(temp.path().join("used.py"), "VALUE = 2\n").unwrap();
+    let script = temp.path().join("app.py");
+    fs::write(&script, "import heavy, used\nprint(used.VALUE)\n").unwrap();
+
+    let output = bin()
+        .current_dir(temp.path())
+        .args([
+            "--format=json",
+            "run",
+            "--lazy-imports",
+            "--measure-lazy-savings",
+            "app.py",
+        ])
+        .output()
+        .expect("run pybun");
+    assert!(output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let detail = &json["detail"];
+    assert_eq!(detail["stdout"], "2\n");
+    assert_eq!(detail["profile"]["lazy_imports_injected"], true);
+    let lazy = &detail["lazy_imports"];
+    assert_eq!(lazy["deferred"], serde_json::json!(["heavy", "used"]));
+    assert_eq!(lazy["loaded_on_use"], serde_json::json!(["used"]));
+    assert_eq!(lazy["never_loaded"], serde_json::json!(["heavy"]));
+    assert!(
+        lazy["estimated_saved_ms"].as_f64().unwrap() >= 50.0,
+        "{lazy}"
+    );
+
+    // The estimate re-imports the unused modules, so it is opt-in.
+    let output = bin()
+        .current_dir(temp.path())
+        .args(["--format=json", "run", "--lazy-imports", "app.py"])
+        .output()
+        .expect("run pybun");
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let lazy = &json["detail"]["lazy_imports"];
+    assert_eq!(lazy["never_loaded"], serde_json::json!(["heavy"]));
+    assert_eq!(lazy["estimated_saved_ms"], Value::Null, "{lazy}");
+
+    // Without the flag nothing is injected or reported.
+    let output = bin()
+        .current_dir(temp.path())
+        .args(["--format=json", "run", "app.py"])
+        .output()
+        .expect("run pybun");
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["lazy_imports"], Value::Null);
+    assert_eq!(json["detail"]["profile"]["lazy_imports_injected"], false);
+}
+
 #[test]
 fn run_with_dev_profile_no_lazy_imports() {
     let temp = tempdir().unwrap();
@@ -1052,8 +1226,8 @@ print("hello locked")
     fs::write(&script, lock_script_content).unwrap();
 
     // Fake uv that fails only when called as "uv run" (the collision case).
-    // It must succeed for "uv pip install" so the pybun backend can use it for
-    // fast package installation after bypassing the uv run backend.
+    // It must succeed for "uv pip install", which the pybun backend uses with
+    // PYBUN_INSTALLER=pip after bypassing the uv run backend.
     let uv_dir = temp.path().join("uv-bin");
     fs::create_dir_all(&uv_dir).unwrap();
     let uv_path = if cfg!(windows) {
@@ -1116,6 +1290,8 @@ print("hello locked")
     // must NOT call "uv run" even though fake uv is on PATH with PYBUN_PEP723_BACKEND=auto.
     let output = bin()
         .env("PATH", &new_path)
+        // The fake uv stands in for `uv pip install`.
+        .env("PYBUN_INSTALLER", "pip")
         .args(["--format=json", "run", script.to_str().unwrap()])
         .output()
         .expect("run pybun");
@@ -1205,6 +1381,8 @@ print("hello explicit uv lockfile")
     let output = bin()
         .env("PYBUN_PEP723_BACKEND", "uv")
         .env("PATH", new_path)
+        // The fake uv stands in for `uv pip install`.
+        .env("PYBUN_INSTALLER", "pip")
         .args(["--format=json", "run", script.to_str().unwrap()])
         .output()
         .expect("run pybun with explicit uv backend and lockfile");
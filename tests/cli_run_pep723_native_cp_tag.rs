@@ -34,23 +34,30 @@
 //! downloaded after the child exits.
 
 use httpmock::prelude::*;
-use pybun::cli::{Cli, Commands, OutputFormat, ProgressMode, RunArgs};
+use pybun::cli::{Cli, Commands, OutputFormat, ProgressMode, RunArgs, SandboxArgs, TimeoutAction};
 use pybun::commands::execute;
-use pybun::sandbox::DEFAULT_SANDBOX_TIMEOUT_SECS;
 use serde_json::json;
 use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use tempfile::tempdir;
 
-/// Minimal but valid (openable-as-zip) wheel body — content doesn't matter,
-/// only that `installer::install_wheel` can extract it as a zip archive.
+/// Minimal but valid wheel body — only a `.dist-info` directory, which
+/// `installer::install_wheel_into` needs to complete the install.
 fn fake_wheel_bytes() -> Vec<u8> {
+    use std::io::Write;
     let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
     let options = zip::write::SimpleFileOptions::default();
-    zip.start_file("dummy.txt", options)
-        .expect("start wheel entry");
-    use std::io::Write;
-    zip.write_all(b"ok").expect("write wheel entry");
+    for (name, content) in [
+        (
+            "cptagpkg-1.0.0.dist-info/METADATA",
+            "Metadata-Version: 2.1\nName: cptagpkg\nVersion: 1.0.0\n",
+        ),
+        ("cptagpkg-1.0.0.dist-info/RECORD", ""),
+    ] {
+        zip.start_file(name, options).expect("start wheel entry");
+        zip.write_all(content.as_bytes())
+            .expect("write wheel entry");
+    }
     zip.finish().expect("finish wheel zip").into_inner()
 }
 
@@ -127,20 +134,24 @@ async fn run_pep723_native_cp_tag_child() {
 
     let cli = Cli {
         format: OutputFormat::Json,
+        columns: Vec::new(),
         progress: ProgressMode::Never,
         no_progress: true,
+        max_inline_bytes: 0,
+        max_duration: None,
+        on_timeout: TimeoutAction::Cancel,
+        offline: false,
         command: Commands::Run(RunArgs {
             target: Some(script),
+            list: false,
             code: None,
-            sandbox: false,
-            allow_network: false,
-            allow_read: Vec::new(),
-            allow_write: Vec::new(),
-            allow_env: Vec::new(),
-            sandbox_timeout: DEFAULT_SANDBOX_TIMEOUT_SECS,
-            sandbox_memory: 0,
-            sandbox_cpu: 0,
+            module: None,
+            member: None,
+            sandbox: SandboxArgs::default(),
             profile: "dev".to_string(),
+            lazy_imports: false,
+            measure_lazy_savings: false,
+            python: None,
             passthrough: Vec::new(),
         }),
     };
@@ -197,6 +208,8 @@ fn run_pep723_native_installer_selects_wheel_for_target_venv_python_not_path_pyt
     let base = server.base_url();
 
     let wheel_body = fake_wheel_bytes();
+    // Wheels are verified against the index digest when fetched.
+    let wheel_sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(&wheel_body));
     let real_wheel_filename = format!("cptagpkg-1.0.0-{real_cp_tag}-{real_cp_tag}-any.whl");
     let fake_wheel_filename = format!("cptagpkg-1.0.0-{fake_cp_tag}-{fake_cp_tag}-any.whl");
 
@@ -209,14 +222,14 @@ fn run_pep723_native_installer_selects_wheel_for_target_venv_python_not_path_pyt
                     "packagetype": "bdist_wheel",
                     "url": format!("{base}/files/{real_wheel_filename}"),
                     "yanked": false,
-                    "digests": { "sha256": "0".repeat(64) }
+                    "digests": { "sha256": wheel_sha256 }
                 },
                 {
                     "filename": fake_wheel_filename,
                     "packagetype": "bdist_wheel",
                     "url": format!("{base}/files/{fake_wheel_filename}"),
                     "yanked": false,
-                    "digests": { "sha256": "1".repeat(64) }
+                    "digests": { "sha256": wheel_sha256 }
                 }
             ]
         }
@@ -264,6 +264,9 @@ dependencies = [
     let lock = Lockfile::load_from_path(project_root.join("pybun.lockb")).unwrap();
     let pkg = lock.packages.get("pkg-a").expect("pkg-a in lockfile");
     assert_eq!(pkg.version, "2.0.0");
+    assert_eq!(pkg.artifacts.len(), 1);
+    assert_eq!(pkg.artifacts[0].filename, "pkg_a-2.0.0-py3-none-any.whl");
+    assert_eq!(pkg.artifacts[0].hash, "sha256:hash2");
     match &pkg.source {
         PackageSource::Registry { index, url } => {
             assert_eq!(index, "pypi");
@@ -558,6 +561,10 @@ dependencies = [
         wheel: "verpkg-1.0.0-cp311-cp311-any.whl".to_string(),
         hash: "sha256:verpkgcp311".to_string(),
         dependencies: vec![],
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
     });
     seed_lock.save_to_path(&lock_path).unwrap();
 
@@ -582,3 +589,216 @@ dependencies = [
          not whatever python3/python resolves to on PATH"
     );
 }
+
+#[test]
+fn upgrade_reports_lockfile_diff() {
+    let temp = TempDir::new().unwrap();
+    let project_root = temp.path();
+    fs::write(
+        project_root.join("pyproject.toml"),
+        "[project]\nname = \"test-project\"\ndependencies = [\"pkg-a>=1.0.0\"]\n",
+    )
+    .unwrap();
+    let index_path = project_root.join("index.json");
+    let entry = |version: &str| {
+        format!(
+            r#"{{"name":"pkg-a","version":"{version}","dependencies":[],"wheels":[{{"file":"pkg_a-{version}-py3-none-any.whl","hash":"sha256:{version}"}}]}}"#
+        )
+    };
+    fs::write(&index_path, format!("[{}]", entry("1.0.0"))).unwrap();
+    bin()
+        .current_dir(project_root)
+        .args(["install", "--index", index_path.to_str().unwrap()])
+        .assert()
+        .success();
+
+    fs::write(
+        &index_path,
+        format!("[{},{}]", entry("1.0.0"), entry("2.0.0")),
+    )
+    .unwrap();
+    let output = bin()
+        .current_dir(project_root)
+        .args(["--format=json", "upgrade", "--dry-run", "--index"])
+        .arg(&index_path)
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(output.status.success(), "{json}");
+    let changes = json["detail"]["changes"].as_array().expect("changes");
+    assert_eq!(changes.len(), 1, "{json}");
+    assert_eq!(changes[0]["file"], "pybun.lockb");
+    assert_eq!(changes[0]["section"], "packages");
+    assert_eq!(changes[0]["name"], "pkg-a");
+    assert_eq!(changes[0]["change"], "changed");
+    assert_eq!(changes[0]["before"], "1.0.0");
+    assert_eq!(changes[0]["after"], "2.0.0");
+    let diff = json["detail"]["diff"].as_str().unwrap();
+    assert!(diff.starts_with("--- a/pybun.lockb\n+++ b/pybun.lockb\n"));
+    assert!(diff.contains("\n-version = \"1.0.0\"\n"), "{diff}");
+    assert!(diff.contains("\n+version = \"2.0.0\"\n"), "{diff}");
+
+    bin()
+        .current_dir(project_root)
+        .args(["upgrade", "--index"])
+        .arg(&index_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pkg-a 1.0.0 -> 2.0.0"))
+        .stdout(predicate::str::contains(
+            "+wheel = \"pkg_a-2.0.0-py3-none-any.whl\"",
+        ));
+    let output = bin()
+        .current_dir(project_root)
+        .args(["--format=json", "upgrade", "--index"])
+        .arg(&index_path)
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["changes"], serde_json::json!([]), "{json}");
+    assert_eq!(json["detail"]["diff"], "");
+}
+
+/// A project depending on `dependencies`, installed from an index holding
+/// version 1.0.0 of each package; the index then gains `newer` versions.
+fn installed_project(dependencies: &[&str], newer: &[&str]) -> (TempDir, PathBuf) {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    let deps: Vec<String> = dependencies.iter().map(|d| format!("\"{d}\"")).collect();
+    fs::write(
+        root.join("pyproject.toml"),
+        format!(
+            "[project]\nname = \"test-project\"\ndependencies = [{}]\n",
+            deps.join(", ")
+        ),
+    )
+    .unwrap();
+    let entry = |name: &str, version: &str| {
+        let file = name.replace('-', "_");
+        format!(
+            r#"{{"name":"{name}","version":"{version}","dependencies":[],"wheels":[{{"file":"{file}-{version}-py3-none-any.whl","hash":"sha256:{file}{version}"}}]}}"#
+        )
+    };
+    let entries = |versions: &[&str]| {
+        let mut entries = Vec::new();
+        for name in ["pkg-a", "pkg-b"] {
+            for version in versions {
+                entries.push(entry(name, version));
+            }
+        }
+        format!("[{}]", entries.join(","))
+    };
+    let index_path = root.join("index.json");
+    fs::write(&index_path, entries(&["1.0.0"])).unwrap();
+    bin()
+        .current_dir(root)
+        .args(["install", "--index"])
+        .arg(&index_path)
+        .assert()
+        .success();
+    let mut versions = vec!["1.0.0"];
+    versions.extend(newer);
+    fs::write(&index_path, entries(&versions)).unwrap();
+    (temp, index_path)
+}
+
+fn upgrade_json(root: &Path, index: &Path, args: &[&str]) -> (bool, Value) {
+    let output = bin()
+        .current_dir(root)
+        .args(["--format=json", "upgrade", "--index"])
+        .arg(index)
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        serde_json::from_slice(&output.stdout).unwrap(),
+    )
+}
+
+fn locked_version(root: &Path, name: &str) -> String {
+    let lock = Lockfile::load_from_path(root.join("pybun.lockb")).unwrap();
+    lock.packages[name].version.clone()
+}
+
+#[test]
+fn upgrade_latest_raises_pyproject_constraints() {
+    let (temp, index) = installed_project(&["pkg-a<2"], &["2.0.0"]);
+    let root = temp.path();
+
+    let (ok, json) = upgrade_json(root, &index, &[]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["upgraded"], serde_json::json!([]));
+
+    let (ok, json) = upgrade_json(root, &index, &["--latest", "--dry-run"]);
+    assert!(ok, "{json}");
+    let plan = &json["detail"]["plan"];
+    assert_eq!(plan["latest"], true);
+    assert_eq!(plan["raised"][0]["from"], "pkg-a<2");
+    assert_eq!(plan["raised"][0]["to"], "pkg-a>=2.0.0");
+    let diff = json["detail"]["diff"].as_str().unwrap();
+    assert!(
+        diff.contains("+dependencies = [\"pkg-a>=2.0.0\"]"),
+        "{diff}"
+    );
+    assert!(
+        fs::read_to_string(root.join("pyproject.toml"))
+            .unwrap()
+            .contains("pkg-a<2"),
+        "dry run leaves pyproject.toml alone"
+    );
+    assert_eq!(locked_version(root, "pkg-a"), "1.0.0");
+
+    let (ok, json) = upgrade_json(root, &index, &["--latest"]);
+    assert!(ok, "{json}");
+    assert!(
+        fs::read_to_string(root.join("pyproject.toml"))
+            .unwrap()
+            .contains("pkg-a>=2.0.0")
+    );
+    assert_eq!(locked_version(root, "pkg-a"), "2.0.0");
+}
+
+#[test]
+fn upgrade_pin_holds_packages_back() {
+    let (temp, index) = installed_project(&["pkg-a", "pkg-b"], &["2.0.0"]);
+    let root = temp.path();
+
+    let (ok, json) = upgrade_json(root, &index, &["--pin", "pkg-b"]);
+    assert!(ok, "{json}");
+    assert_eq!(locked_version(root, "pkg-a"), "2.0.0");
+    assert_eq!(locked_version(root, "pkg-b"), "1.0.0");
+    assert_eq!(
+        json["detail"]["plan"]["pinned"],
+        serde_json::json!([{"package": "pkg-b", "version": "1.0.0"}])
+    );
+
+    let (ok, json) = upgrade_json(root, &index, &["pkg-b", "--pin", "pkg-b==1.0.0"]);
+    assert!(!ok);
+    assert_eq!(
+        json["diagnostics"][0]["code"], "E_UPGRADE_PIN_CONFLICT",
+        "{json}"
+    );
+}
+
+#[test]
+fn upgrade_target_specifier_constrains_the_new_version() {
+    let (temp, index) = installed_project(&["pkg-a", "pkg-b"], &["1.5.0", "2.0.0"]);
+    let root = temp.path();
+
+    let (ok, json) = upgrade_json(root, &index, &["pkg-a<2"]);
+    assert!(ok, "{json}");
+    assert_eq!(locked_version(root, "pkg-a"), "1.5.0");
+    assert_eq!(locked_version(root, "pkg-b"), "1.0.0");
+    assert_eq!(
+        json["detail"]["plan"]["packages"],
+        serde_json::json!(["pkg-a<2"])
+    );
+
+    let (ok, json) = upgrade_json(root, &index, &["pkg-z"]);
+    assert!(!ok);
+    assert_eq!(
+        json["diagnostics"][0]["code"], "E_UPGRADE_UNKNOWN_PACKAGE",
+        "{json}"
+    );
+}
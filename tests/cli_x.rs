@@ -54,13 +54,26 @@ fn x_passthrough_args() {
 }
 
 #[test]
-fn x_creates_temp_environment() {
-    // In dry-run mode, should report temp environment creation
-    bin()
+fn x_reports_tool_environment() {
+    // In dry-run mode, should report the cached tool environment it would use
+    let temp = tempfile::tempdir().unwrap();
+    let output = bin()
         .args(["--format=json", "x", "httpie"])
         .env("PYBUN_X_DRY_RUN", "1")
-        .assert()
-        .stdout(predicate::str::contains("temp_env"));
+        .env("PYBUN_HOME", temp.path())
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let tool_env = &json["detail"]["tool_env"];
+    assert!(
+        tool_env["path"]
+            .as_str()
+            .is_some_and(|path| path.starts_with(temp.path().join("tools").to_str().unwrap())),
+        "{json}"
+    );
+    assert_eq!(tool_env["reused"], false);
+    // A dry run creates nothing.
+    assert!(!temp.path().join("tools").exists());
 }
 
 #[test]
@@ -97,19 +110,23 @@ fn x_execute_real_package() {
         .stdout(predicate::str::contains("Hello PyBun!"));
 }
 
-// Test that pybun x cleans up temp directory after execution
 #[test]
-fn x_cleanup_temp_env() {
+fn x_list_without_cached_tools() {
+    let temp = tempfile::tempdir().unwrap();
     let output = bin()
-        .args(["--format=json", "x", "cowsay"])
-        .env("PYBUN_X_DRY_RUN", "1")
+        .args(["--format=json", "x", "--list"])
+        .env("PYBUN_HOME", temp.path())
         .output()
-        .expect("failed to execute command");
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
+        .unwrap();
+    assert!(output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["tools"], serde_json::json!([]));
+}
 
-    // Check that cleanup is mentioned in output
-    assert!(stdout.contains("cleanup") || stdout.contains("temp_env"));
+#[test]
+fn x_management_flags_need_a_package() {
+    bin().args(["x", "--upgrade"]).assert().failure();
+    bin().args(["x", "--list", "ruff"]).assert().failure();
 }
 
 #[test]
@@ -180,3 +197,37 @@ fn x_json_mode_zero_exit_reports_ok_status() {
     assert_eq!(value["status"], "ok");
     assert_eq!(value["detail"]["exit_code"], 0);
 }
+
+#[test]
+fn x_withholds_environment_unless_passed() {
+    let output = bin()
+        .args([
+            "--format=json",
+            "x",
+            "cowsay",
+            "--pass-env",
+            "PYBUN_TEST_PASSED",
+        ])
+        .env("PYBUN_X_DRY_RUN", "1")
+        .env("PYBUN_TEST_SECRET_TOKEN", "hunter2")
+        .env("PYBUN_TEST_PASSED", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("hunter2"), "values must never be reported");
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let env = &json["detail"]["env"];
+    let names = |key: &str| -> Vec<String> {
+        env[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect()
+    };
+    assert!(names("withheld").contains(&"PYBUN_TEST_SECRET_TOKEN".to_string()));
+    assert!(names("passed").contains(&"PYBUN_TEST_PASSED".to_string()));
+    assert!(names("passed").contains(&"PATH".to_string()));
+    assert_eq!(env["pass_env"], serde_json::json!(["PYBUN_TEST_PASSED"]));
+}
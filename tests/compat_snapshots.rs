@@ -101,16 +101,23 @@ fn help_snapshots() {
         ("help_install", &["install", "--help"]),
         ("help_add", &["add", "--help"]),
         ("help_remove", &["remove", "--help"]),
+        ("help_rename_dep", &["rename-dep", "--help"]),
         ("help_lock", &["lock", "--help"]),
         ("help_run", &["run", "--help"]),
         ("help_x", &["x", "--help"]),
         ("help_test", &["test", "--help"]),
+        ("help_bench", &["bench", "--help"]),
+        ("help_import", &["import", "--help"]),
+        ("help_export", &["export", "--help"]),
         ("help_build", &["build", "--help"]),
+        ("help_publish", &["publish", "--help"]),
         ("help_doctor", &["doctor", "--help"]),
         ("help_mcp", &["mcp", "--help"]),
         ("help_mcp_serve", &["mcp", "serve", "--help"]),
         ("help_self", &["self", "--help"]),
         ("help_self_update", &["self", "update", "--help"]),
+        ("help_self_rollback", &["self", "rollback", "--help"]),
+        ("help_self_history", &["self", "history", "--help"]),
         ("help_gc", &["gc", "--help"]),
         ("help_python", &["python", "--help"]),
         ("help_python_list", &["python", "list", "--help"]),
@@ -125,6 +132,25 @@ fn help_snapshots() {
         ("help_schema_print", &["schema", "print", "--help"]),
         ("help_schema_check", &["schema", "check", "--help"]),
         ("help_audit", &["audit", "--help"]),
+        ("help_hook", &["hook", "--help"]),
+        ("help_alias", &["alias", "--help"]),
+        ("help_config", &["config", "--help"]),
+        ("help_config_validate", &["config", "validate", "--help"]),
+        ("help_completions", &["completions", "--help"]),
+        ("help_script", &["script", "--help"]),
+        ("help_script_lock", &["script", "lock", "--help"]),
+        ("help_env_clean", &["env", "clean", "--help"]),
+        ("help_env_list", &["env", "list", "--help"]),
+        ("help_env_push", &["env", "push", "--help"]),
+        ("help_env_pull", &["env", "pull", "--help"]),
+        ("help_env_snapshot", &["env", "snapshot", "--help"]),
+        ("help_env_restore", &["env", "restore", "--help"]),
+        ("help_cache_list", &["cache", "list", "--help"]),
+        ("help_cache_info", &["cache", "info", "--help"]),
+        ("help_cache_push", &["cache", "push", "--help"]),
+        ("help_cache_pull", &["cache", "pull", "--help"]),
+        ("help_graph", &["graph", "--help"]),
+        ("help_sbom", &["sbom", "--help"]),
     ];
 
     for (name, args) in cases {
@@ -151,6 +177,7 @@ fn json_snapshots() {
 
     for (name, args) in cases {
         let output = pybun()
+            .env("PYBUN_UPDATE_COHORT", "0")
             .args(*args)
             .output()
             .expect("failed to run pybun json");
@@ -0,0 +1,138 @@
+//! `pybun config validate`: unknown keys and type mismatches in
+//! `[tool.pybun]` / `pybun.toml`, reported with file and line.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn validate(dir: &Path) -> (bool, Value) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".home"))
+        .args(["--format=json", "config", "validate"])
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+fn pybun() -> Command {
+    cargo_bin_cmd!("pybun")
+}
+
+#[test]
+fn valid_config_passes() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\n\n[tool.pybun]\npython = \"3.12\"\n\
+         [tool.pybun.alias]\nlint = [\"ruff\", \"check\"]\n",
+    )
+    .unwrap();
+
+    let (ok, json) = validate(temp.path());
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["files"][0], "pyproject.toml");
+    assert!(json["detail"]["issues"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn issues_point_at_file_and_line_in_both_config_files() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\n\n[tool.pybun]\nlazy_import = [\"numpy\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        temp.path().join("pybun.toml"),
+        "[build]\nisolation = \"docker\"\n",
+    )
+    .unwrap();
+
+    let (ok, json) = validate(temp.path());
+    assert!(!ok, "{json}");
+    let issues = json["detail"]["issues"].as_array().unwrap();
+    assert_eq!(issues.len(), 2, "{json}");
+    assert_eq!(issues[0]["key"], "tool.pybun.lazy_import");
+    assert_eq!(issues[0]["kind"], "unknown-key");
+    assert_eq!(issues[0]["suggestion"], "lazy_imports");
+    assert_eq!(issues[0]["line"], 5);
+    assert_eq!(issues[1]["key"], "build.isolation");
+    assert_eq!(issues[1]["kind"], "type-mismatch");
+    assert_eq!(issues[1]["expected"], "one of \"venv\", \"container\"");
+    assert_eq!(issues[1]["line"], 2);
+
+    let diagnostics = json["diagnostics"].as_array().unwrap();
+    assert!(diagnostics.iter().all(|d| d["code"] == "E_CONFIG_INVALID"));
+    assert_eq!(diagnostics[0]["file"], "pyproject.toml");
+    assert_eq!(diagnostics[0]["line"], 5);
+    assert_eq!(diagnostics[0]["suggestion"], "did you mean `lazy_imports`?");
+    assert_eq!(diagnostics[1]["file"], "pybun.toml");
+}
+
+#[test]
+fn text_output_lists_locations() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[tool.pybun.seed]\nenabled = \"yes\"\n",
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join(".home"))
+        .args(["config", "validate"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "pyproject.toml:2:11: `tool.pybun.seed.enabled` must be a boolean, found string"
+        ),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn pybun_toml_settings_apply_and_invalid_entries_are_skipped() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\n\n[tool.pybun.alias]\nhello = \"run -c 'print(1)'\"\n",
+    )
+    .unwrap();
+    // `pybun.toml` replaces the alias table; the malformed entry is dropped
+    // without discarding the valid one.
+    fs::write(
+        temp.path().join("pybun.toml"),
+        "[alias]\ngreet = \"run -c 'print(2)'\"\nbroken = 3\n",
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join(".home"))
+        .args(["--format=json", "alias", "list"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let names: Vec<&str> = json["detail"]["aliases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["greet"]);
+}
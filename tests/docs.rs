@@ -36,7 +36,7 @@ fn readme_has_ga_docs_sections() {
         "Quick Start",
         "JSON output examples",
         "Sandbox usage",
-        "MCP server (stdio)",
+        "MCP server (stdio / HTTP)",
         "Upgrade guide",
     ] {
         assert!(
@@ -0,0 +1,128 @@
+//! E2E tests for the deep environment checks `pybun doctor` runs against the
+//! project environment.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::{Lockfile, Package, PackageSource};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn package(name: &str, version: &str) -> Package {
+    Package {
+        name: name.into(),
+        version: version.into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: format!("{name}-{version}-py3-none-any.whl"),
+        hash: "sha256:placeholder".into(),
+        dependencies: Vec::new(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
+    }
+}
+
+fn dist_info(site_packages: &Path, name: &str, version: &str, record: &str) {
+    let dir = site_packages.join(format!("{name}-{version}.dist-info"));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("RECORD"), record).unwrap();
+}
+
+fn doctor(root: &Path, venv: &Path) -> Value {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(root)
+        .env("PYBUN_HOME", root.join("home"))
+        .env("PYBUN_ENV", venv)
+        .env("PYBUN_PYPI_BASE_URL", "http://127.0.0.1:9")
+        .env("PYBUN_PYPI_CACHE_DIR", root.join("pypi-cache"))
+        .env_remove("PYBUN_PYTHON")
+        .args(["--format=json", "doctor"])
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn check<'a>(json: &'a Value, name: &str) -> &'a Value {
+    json["detail"]["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == name)
+        .unwrap_or_else(|| panic!("{name} check missing"))
+}
+
+#[test]
+fn doctor_reports_each_environment_problem_as_its_own_check() {
+    let temp = tempdir().unwrap();
+    let root = temp.path();
+    fs::write(
+        root.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"idna\", \"urllib3\"]\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+    lock.add_package(package("idna", "3.7"));
+    lock.add_package(package("urllib3", "2.2.0"));
+    lock.save_to_path(root.join("pybun.lockb")).unwrap();
+
+    let venv = root.join(".venv");
+    let site_packages = venv.join("lib/python3.11/site-packages");
+    fs::create_dir_all(site_packages.join("idna")).unwrap();
+    fs::write(
+        venv.join("pyvenv.cfg"),
+        format!("home = {}\n", root.display()),
+    )
+    .unwrap();
+    fs::write(site_packages.join("idna/core.py"), "edited\n").unwrap();
+    dist_info(
+        &site_packages,
+        "idna",
+        "3.7",
+        "idna/core.py,sha256=AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA,9\n",
+    );
+    dist_info(&site_packages, "idna", "3.6", "");
+
+    let json = doctor(root, &venv);
+    assert_eq!(check(&json, "venv_links")["status"], "ok");
+    assert_eq!(check(&json, "record_hashes")["status"], "warning");
+    assert_eq!(
+        check(&json, "record_hashes")["findings"][0]["package"],
+        "idna"
+    );
+    let duplicates = check(&json, "duplicate_distributions");
+    assert_eq!(duplicates["status"], "error");
+    assert_eq!(
+        duplicates["message"],
+        "idna is installed 2 times (3.6, 3.7)"
+    );
+    let drift = check(&json, "lock_drift");
+    assert_eq!(drift["status"], "warning");
+    assert_eq!(
+        drift["message"],
+        "urllib3 2.2.0 is locked but not installed"
+    );
+    assert_eq!(json["detail"]["status"], "issues_found");
+
+    let codes: Vec<&str> = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect();
+    for code in [
+        "W_DOCTOR_RECORD_MISMATCH",
+        "E_DOCTOR_DUPLICATE_DISTRIBUTION",
+        "W_DOCTOR_LOCK_DRIFT",
+    ] {
+        assert!(codes.contains(&code), "{code} missing from {codes:?}");
+    }
+}
@@ -192,3 +192,93 @@ fn doctor_fix_apply_removes_stale_pypi_cache_entry() {
         "--fix --apply should have removed the stale cache entry"
     );
 }
+
+#[test]
+fn doctor_fix_simulates_steps_before_applying() {
+    let temp = tempdir().unwrap();
+    let pypi_cache = temp.path().join("pypi-cache");
+    write_stale_pypi_cache_entry(&pypi_cache);
+
+    let output = pybun_bin()
+        .env("PYBUN_PYPI_CACHE_DIR", &pypi_cache)
+        .args(["--format=json", "doctor", "--fix"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+
+    let steps = json["detail"]["fix_steps"]
+        .as_array()
+        .expect("fix_steps should be an array");
+    assert_eq!(steps.len(), 1, "{json}");
+    let step = &steps[0];
+    assert_eq!(step["id"], "I_DOCTOR_STALE_PYPI_CACHE#1");
+    assert_eq!(step["order"], 1);
+    assert_eq!(step["command"], "pybun gc");
+    assert_eq!(step["status"], "ready");
+    assert_eq!(step["preconditions"][0]["kind"], "permissions");
+    assert_eq!(step["preconditions"][0]["ok"], true);
+    assert_eq!(step["predicted"]["stale_count"], 0);
+    assert_eq!(step["predicted"]["files_removed"], 1);
+    assert!(step["blocked_reasons"].as_array().unwrap().is_empty());
+    assert!(
+        pypi_cache.join("stale-package.bin").exists(),
+        "the simulation must not delete anything"
+    );
+    assert_eq!(
+        fs::read_dir(&pypi_cache).unwrap().count(),
+        1,
+        "the permission probe leaves no files behind"
+    );
+
+    let output = pybun_bin()
+        .env("PYBUN_PYPI_CACHE_DIR", &pypi_cache)
+        .args(["--format=json", "doctor", "--fix", "--apply"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(json["detail"]["applied_fixes"][0]["applied"], true);
+    assert_eq!(
+        json["detail"]["applied_fixes"][0]["postconditions_met"],
+        true
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn doctor_fix_blocks_fixes_whose_preconditions_fail() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempdir().unwrap();
+    let pypi_cache = temp.path().join("pypi-cache");
+    write_stale_pypi_cache_entry(&pypi_cache);
+    fs::set_permissions(&pypi_cache, fs::Permissions::from_mode(0o555)).unwrap();
+    // Permission bits do not bind root.
+    if fs::write(pypi_cache.join("probe"), b"").is_ok() {
+        eprintln!("skipping: read-only directories are writable for this user");
+        fs::set_permissions(&pypi_cache, fs::Permissions::from_mode(0o755)).unwrap();
+        return;
+    }
+
+    let output = pybun_bin()
+        .env("PYBUN_PYPI_CACHE_DIR", &pypi_cache)
+        .args(["--format=json", "doctor", "--fix", "--apply"])
+        .output()
+        .unwrap();
+    fs::set_permissions(&pypi_cache, fs::Permissions::from_mode(0o755)).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+
+    let step = &json["detail"]["fix_steps"][0];
+    assert_eq!(step["status"], "blocked", "{json}");
+    assert_eq!(step["preconditions"][0]["ok"], false);
+    assert!(
+        step["blocked_reasons"][0]
+            .as_str()
+            .is_some_and(|r| r.contains("not writable")),
+        "{json}"
+    );
+    let applied = &json["detail"]["applied_fixes"][0];
+    assert_eq!(applied["applied"], false);
+    assert_eq!(applied["blocked"], true);
+    assert!(pypi_cache.join("stale-package.bin").exists());
+}
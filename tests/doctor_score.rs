@@ -0,0 +1,171 @@
+//! E2E tests for `pybun doctor --score`: signals from the lockfile, a mock
+//! PyPI and the recorded test history become a weighted score and an
+//! ordered list of fix commands.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::prelude::*;
+use pybun::lockfile::{Lockfile, Package, PackageSource};
+use pybun::test_history::{RunRecord, TestHistory, TestRecord};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn package(name: &str, version: &str) -> Package {
+    Package {
+        name: name.into(),
+        version: version.into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: format!("{name}-{version}-py3-none-any.whl"),
+        hash: "sha256:placeholder".into(),
+        dependencies: Vec::new(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
+    }
+}
+
+/// PyPI serving `releases` of `name`.
+fn mock_project(server: &MockServer, name: &str, releases: &[&str]) {
+    let files: serde_json::Map<String, Value> = releases
+        .iter()
+        .map(|version| {
+            (
+                version.to_string(),
+                json!([{
+                    "filename": format!("{name}-{version}-py3-none-any.whl"),
+                    "packagetype": "bdist_wheel",
+                    "url": format!("{}/files/{name}-{version}-py3-none-any.whl", server.base_url()),
+                    "yanked": false,
+                    "digests": { "sha256": "00" }
+                }]),
+            )
+        })
+        .collect();
+    let body = json!({
+        "info": { "name": name, "version": releases.last().unwrap() },
+        "releases": files,
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/pypi/{name}/json"));
+        then.status(200).body(body.clone());
+    });
+}
+
+fn doctor_score(root: &Path, base_url: &str) -> Value {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(root)
+        .env("PYBUN_HOME", root.join("home"))
+        .env("PYBUN_PYPI_BASE_URL", base_url)
+        .env("PYBUN_PYPI_CACHE_DIR", root.join("pypi-cache"))
+        .env("PYBUN_OSV_URL", "http://127.0.0.1:9")
+        .env_remove("PYBUN_ENV")
+        .env_remove("PYBUN_PYTHON")
+        .args(["--format=json", "doctor", "--score"])
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+#[test]
+fn doctor_score_prioritizes_lock_drift_outdated_packages_and_flaky_tests() {
+    let temp = tempdir().unwrap();
+    let root = temp.path();
+    fs::write(
+        root.join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\"alpha>=2\", \"beta\"]\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+    lock.add_package(package("alpha", "1.0.0"));
+    lock.add_package(package("beta", "1.0.0"));
+    lock.save_to_path(root.join("pybun.lockb")).unwrap();
+
+    let history = TestHistory::for_project(&root.join("home"), root);
+    for outcome in ["passed", "failed", "passed"] {
+        history
+            .record(&RunRecord::now(
+                "pybun",
+                10,
+                outcome == "passed",
+                vec![
+                    TestRecord::new("tests/test_api.py::test_retry", outcome, 5),
+                    TestRecord::new("tests/test_api.py::test_get", "passed", 5),
+                ],
+            ))
+            .unwrap();
+    }
+
+    let server = MockServer::start();
+    mock_project(&server, "alpha", &["1.0.0", "3.0.0"]);
+    mock_project(&server, "beta", &["1.0.0", "1.0.1"]);
+
+    let json = doctor_score(root, &server.base_url());
+    let score = &json["detail"]["score"];
+    let component = |name: &str| {
+        score["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["component"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(component("lock")["score"], 75, "{score}");
+    assert_eq!(component("lock")["status"], "critical");
+    assert_eq!(component("outdated")["score"], 25, "{score}");
+    assert_eq!(component("tests")["score"], 90, "{score}");
+    // No project environment to audit.
+    assert_eq!(component("audit")["status"], "skipped");
+    assert!(score["score"].as_u64().unwrap() < 90, "{score}");
+
+    let plan: Vec<(u64, &str, &str)> = score["recommendations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["priority"].as_u64().unwrap(),
+                r["component"].as_str().unwrap(),
+                r["command"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(plan[0], (1, "lock", "pybun install"), "{score}");
+    assert!(
+        plan.contains(&(2, "outdated", "pybun upgrade alpha")),
+        "{score}"
+    );
+    assert!(
+        plan.iter()
+            .any(|(_, component, command)| *component == "tests"
+                && *command == "pybun test --history"),
+        "{score}"
+    );
+}
+
+#[test]
+fn doctor_score_skips_what_cannot_be_measured() {
+    let temp = tempdir().unwrap();
+    let json = doctor_score(temp.path(), "http://127.0.0.1:9");
+    let score = &json["detail"]["score"];
+    let skipped: Vec<&str> = score["components"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|c| c["status"] == "skipped")
+        .map(|c| c["component"].as_str().unwrap())
+        .collect();
+    assert_eq!(skipped, ["audit", "lock", "outdated", "tests"], "{score}");
+    assert!(score["score"].is_u64(), "{score}");
+}
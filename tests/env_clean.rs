@@ -0,0 +1,68 @@
+//! E2E tests for `pybun env clean`.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn fake_venv(root: &std::path::Path) -> std::path::PathBuf {
+    let venv = root.join("venv");
+    let site_packages = venv.join("lib/python3.12/site-packages");
+    fs::create_dir_all(site_packages.join("stale/__pycache__")).unwrap();
+    fs::write(
+        site_packages.join("stale/__pycache__/mod.cpython-312.pyc"),
+        b"bytecode",
+    )
+    .unwrap();
+    fs::create_dir_all(site_packages.join("half-1.0.dist-info")).unwrap();
+    fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+    venv
+}
+
+#[test]
+fn env_clean_dry_run_reports_without_deleting() {
+    let temp = TempDir::new().unwrap();
+    let venv = fake_venv(temp.path());
+
+    let output = cargo_bin_cmd!("pybun")
+        .args(["--format=json", "env", "clean", "--dry-run", "--venv"])
+        .arg(&venv)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let findings = json["detail"]["findings"].as_array().unwrap();
+    assert_eq!(findings.len(), 2);
+    assert!(json["detail"]["reclaimed_bytes"].as_u64().unwrap() >= 8);
+    assert!(
+        venv.join("lib/python3.12/site-packages/stale/__pycache__")
+            .exists()
+    );
+}
+
+#[test]
+fn env_clean_removes_debris() {
+    let temp = TempDir::new().unwrap();
+    let venv = fake_venv(temp.path());
+
+    cargo_bin_cmd!("pybun")
+        .args(["env", "clean", "--venv"])
+        .arg(&venv)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 item(s) removed"));
+    let site_packages = venv.join("lib/python3.12/site-packages");
+    assert!(!site_packages.join("stale/__pycache__").exists());
+    assert!(!site_packages.join("half-1.0.dist-info").exists());
+}
+
+#[test]
+fn env_clean_rejects_non_venv() {
+    let temp = TempDir::new().unwrap();
+    cargo_bin_cmd!("pybun")
+        .args(["env", "clean", "--venv"])
+        .arg(temp.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("not a virtual environment"));
+}
@@ -0,0 +1,56 @@
+//! E2E tests for `pybun env list`.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn env_list_reports_installed_metadata() {
+    let temp = TempDir::new().unwrap();
+    let venv = temp.path().join("venv");
+    let site_packages = venv.join("lib/python3.12/site-packages");
+    let dist_info = site_packages.join("urllib3-2.2.0.dist-info");
+    fs::create_dir_all(&dist_info).unwrap();
+    fs::create_dir_all(site_packages.join("idna-3.7.dist-info")).unwrap();
+    fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+    fs::write(
+        dist_info.join("METADATA"),
+        "Metadata-Version: 2.4\nName: urllib3\nVersion: 2.2.0\nLicense-Expression: MIT\nRequires-Dist: brotli; extra == 'brotli'\n\n# urllib3\n",
+    )
+    .unwrap();
+    fs::write(
+        dist_info.join("RECORD"),
+        "urllib3/__init__.py,sha256=x,1200\nurllib3-2.2.0.dist-info/RECORD,,\n",
+    )
+    .unwrap();
+
+    let output = cargo_bin_cmd!("pybun")
+        .args(["--format=json", "env", "list", "--venv"])
+        .arg(&venv)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let packages = json["detail"]["packages"].as_array().unwrap();
+    assert_eq!(packages.len(), 2);
+    assert_eq!(packages[0]["name"], "idna");
+    assert!(packages[0]["license"].is_null());
+    assert_eq!(packages[1]["license"], "MIT");
+    assert_eq!(packages[1]["size_bytes"], 1200);
+    assert_eq!(packages[1]["requires"][0], "brotli; extra == 'brotli'");
+    assert_eq!(json["detail"]["total_size_bytes"], 1200);
+}
+
+#[test]
+fn env_list_requires_an_environment() {
+    let temp = TempDir::new().unwrap();
+    cargo_bin_cmd!("pybun")
+        .args(["env", "list", "--venv"])
+        .arg(temp.path().join("missing"))
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "site-packages directory not found",
+        ));
+}
@@ -0,0 +1,219 @@
+//! E2E tests for `pybun env push` / `pybun env pull`: chunked snapshots in a
+//! directory remote, with dedup across pushes and projects, and in an HTTP
+//! remote cache (served by httpmock).
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::Method;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A fake environment with one large, incompressible file.
+fn venv(dir: &Path, seed: u64) -> Vec<u8> {
+    let site_packages = dir.join("lib/python3.12/site-packages/pkg");
+    fs::create_dir_all(&site_packages).unwrap();
+    fs::write(dir.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+    let mut state = seed;
+    let data: Vec<u8> = (0..2 * 1024 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    fs::write(site_packages.join("_core.so"), &data).unwrap();
+    data
+}
+
+fn env_cmd(home: &Path, args: &[&str], venv: &Path, remote: &Path) -> Value {
+    let output = cargo_bin_cmd!("pybun")
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_ENV")
+        .env_remove("PYBUN_CACHE_URL")
+        .args(["--format=json", "env"])
+        .args(args)
+        .arg("--venv")
+        .arg(venv)
+        .arg("--remote")
+        .arg(remote)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn push_sends_only_changed_chunks_and_pull_restores() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let remote = temp.path().join("remote");
+    let app = temp.path().join("app-venv");
+    let mut data = venv(&app, 5);
+
+    let first = env_cmd(&home, &["push", "--name", "app"], &app, &remote);
+    let transfer = &first["detail"]["transfer"];
+    assert_eq!(transfer["files"], 2);
+    assert_eq!(transfer["reused_chunks"], 0, "{first}");
+
+    // A small in-place edit re-uploads only the chunks around it.
+    data[1_000_000] ^= 0xff;
+    fs::write(app.join("lib/python3.12/site-packages/pkg/_core.so"), &data).unwrap();
+    let second = env_cmd(&home, &["push", "--name", "app"], &app, &remote);
+    let transfer = &second["detail"]["transfer"];
+    assert!(
+        transfer["transferred_chunks"].as_u64().unwrap() <= 3,
+        "{second}"
+    );
+    assert!(
+        transfer["savings_percent"].as_f64().unwrap() > 80.0,
+        "{second}"
+    );
+
+    // Another project with the same files shares every chunk.
+    let other = temp.path().join("other-venv");
+    fs::create_dir_all(&other).unwrap();
+    venv(&other, 5);
+    let shared = env_cmd(&home, &["push", "--name", "other"], &other, &remote);
+    assert!(
+        shared["detail"]["transfer"]["savings_percent"]
+            .as_f64()
+            .unwrap()
+            > 90.0,
+        "{shared}"
+    );
+
+    let restored = temp.path().join("restored");
+    let pulled = env_cmd(&home, &["pull", "--name", "app"], &restored, &remote);
+    assert_eq!(
+        fs::read(restored.join("lib/python3.12/site-packages/pkg/_core.so")).unwrap(),
+        data
+    );
+    assert!(
+        pulled["detail"]["transfer"]["transferred_bytes"]
+            .as_u64()
+            .unwrap()
+            > 0
+    );
+
+    // Pulling again (or pulling a similar environment) uses the local chunks.
+    let again = env_cmd(&home, &["pull", "--name", "other"], &restored, &remote);
+    let transfer = &again["detail"]["transfer"];
+    assert!(
+        transfer["savings_percent"].as_f64().unwrap() > 90.0,
+        "{again}"
+    );
+    assert!(
+        fs::read_to_string(restored.join("pyvenv.cfg"))
+            .unwrap()
+            .contains("home")
+    );
+}
+
+#[test]
+fn pull_of_unknown_snapshot_fails() {
+    let temp = TempDir::new().unwrap();
+    let output = cargo_bin_cmd!("pybun")
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .args([
+            "--format=json",
+            "env",
+            "pull",
+            "--name",
+            "missing",
+            "--remote",
+        ])
+        .arg(temp.path().join("remote"))
+        .arg("--venv")
+        .arg(temp.path().join("venv"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let error = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_ENV_PULL_FAILED")
+        .unwrap_or_else(|| panic!("pull error: {json}"));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("snapshot 'missing' not found")
+    );
+}
+
+#[test]
+fn push_and_pull_through_the_http_cache_named_by_pybun_cache_url() {
+    let temp = TempDir::new().unwrap();
+    let app = temp.path().join("app-venv");
+    fs::create_dir_all(&app).unwrap();
+    fs::write(app.join("a.py"), "print('a')\n").unwrap();
+    let id = hex::encode(Sha256::digest(b"print('a')\n"));
+    let chunk_path = format!("/cache/chunks/{}/{id}", &id[..2]);
+
+    let server = MockServer::start();
+    let head = server.mock(|when, then| {
+        when.method(Method::HEAD).path(chunk_path.as_str());
+        then.status(404);
+    });
+    let put_chunk = server.mock(|when, then| {
+        when.method(PUT)
+            .path(chunk_path.as_str())
+            .body("print('a')\n");
+        then.status(200);
+    });
+    let put_manifest = server.mock(|when, then| {
+        when.method(PUT)
+            .path("/cache/snapshots/app.json")
+            .body_includes(id.as_str());
+        then.status(200);
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/cache/snapshots/app.json");
+        then.status(200).json_body(json!({
+            "name": "app",
+            "created": 0,
+            "entries": [{ "path": "a.py", "type": "file", "size": 11, "chunks": [id] }]
+        }));
+    });
+    let get_chunk = server.mock(|when, then| {
+        when.method(GET).path(chunk_path.as_str());
+        then.status(200).body("print('a')\n");
+    });
+    let url = server.url("/cache");
+    let env = |args: &[&str], venv: &Path| {
+        let output = cargo_bin_cmd!("pybun")
+            .env("PYBUN_HOME", temp.path().join("home"))
+            .env("PYBUN_CACHE_URL", &url)
+            .env_remove("PYBUN_ENV")
+            .args(["--format=json", "env"])
+            .args(args)
+            .args(["--name", "app", "--venv"])
+            .arg(venv)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()
+    };
+
+    let pushed = env(&["push"], &app);
+    head.assert();
+    put_chunk.assert();
+    put_manifest.assert();
+    assert_eq!(pushed["detail"]["transfer"]["transferred_chunks"], 1);
+    assert_eq!(pushed["detail"]["transfer"]["remote"], url.as_str());
+
+    let restored = temp.path().join("restored");
+    let pulled = env(&["pull"], &restored);
+    get_chunk.assert();
+    assert_eq!(pulled["detail"]["transfer"]["transferred_chunks"], 1);
+    assert_eq!(
+        fs::read_to_string(restored.join("a.py")).unwrap(),
+        "print('a')\n"
+    );
+}
@@ -0,0 +1,116 @@
+//! E2E tests for `pybun env snapshot` / `pybun env restore`: deterministic
+//! archives restored into another location.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn venv(dir: &Path) {
+    let site_packages = dir.join("lib/python3.12/site-packages");
+    fs::create_dir_all(site_packages.join("requests-2.32.3.dist-info")).unwrap();
+    fs::create_dir_all(dir.join("bin")).unwrap();
+    fs::write(
+        dir.join("pyvenv.cfg"),
+        "home = /nonexistent/python/bin\nversion = 3.12.4\n",
+    )
+    .unwrap();
+    fs::write(site_packages.join("requests.py"), "API = 1\n").unwrap();
+    fs::write(
+        dir.join("bin/activate"),
+        format!("VIRTUAL_ENV=\"{}\"\n", dir.display()),
+    )
+    .unwrap();
+}
+
+fn env_cmd(project: &Path, home: &Path, args: &[&str]) -> (Value, bool) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(project)
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_ENV")
+        .args(["--format=json", "env"])
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    (json, output.status.success())
+}
+
+fn codes(json: &Value) -> Vec<&str> {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect()
+}
+
+#[test]
+fn snapshot_restores_elsewhere_and_flags_mismatches() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let project = temp.path().join("app");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("pyproject.toml"),
+        "[project]\nname = \"app\"\n",
+    )
+    .unwrap();
+    fs::write(project.join("pybun.lockb"), "lock v1").unwrap();
+    venv(&project.join(".venv"));
+
+    let (first, ok) = env_cmd(&project, &home, &["snapshot", "--venv", ".venv"]);
+    assert!(ok, "{first}");
+    let snapshot = &first["detail"]["snapshot"];
+    assert_eq!(snapshot["distributions"], 1);
+    assert_eq!(snapshot["python"]["version"], "3.12.4");
+    assert!(project.join("env.tar.zst").is_file());
+
+    let (second, _) = env_cmd(
+        &project,
+        &home,
+        &["snapshot", "--venv", ".venv", "-o", "again.tar.zst"],
+    );
+    assert_eq!(second["detail"]["snapshot"]["id"], snapshot["id"]);
+    assert_eq!(
+        fs::read(project.join("env.tar.zst")).unwrap(),
+        fs::read(project.join("again.tar.zst")).unwrap()
+    );
+
+    fs::write(project.join("pybun.lockb"), "lock v2").unwrap();
+    let target = temp.path().join("ci/venv");
+    let (restored, ok) = env_cmd(
+        &project,
+        &home,
+        &["restore", "--venv", target.to_str().unwrap()],
+    );
+    assert!(ok, "{restored}");
+    assert_eq!(restored["detail"]["restore"]["id"], snapshot["id"]);
+    assert_eq!(restored["detail"]["restore"]["relocated"], 1);
+    assert_eq!(
+        fs::read_to_string(target.join("bin/activate")).unwrap(),
+        format!("VIRTUAL_ENV=\"{}\"\n", target.display())
+    );
+    assert!(
+        target
+            .join("lib/python3.12/site-packages/requests.py")
+            .is_file()
+    );
+    let codes = codes(&restored);
+    assert!(codes.contains(&"W_ENV_SNAPSHOT_INTERPRETER"), "{restored}");
+    assert!(codes.contains(&"W_ENV_SNAPSHOT_STALE"), "{restored}");
+}
+
+#[test]
+fn restore_of_missing_archive_fails() {
+    let temp = TempDir::new().unwrap();
+    let (json, ok) = env_cmd(
+        temp.path(),
+        &temp.path().join("home"),
+        &["restore", "--input", "missing.tar.zst", "--venv", "venv"],
+    );
+    assert!(!ok);
+    assert!(codes(&json).contains(&"E_ENV_RESTORE_FAILED"), "{json}");
+    assert!(!temp.path().join("venv").exists());
+}
@@ -0,0 +1,173 @@
+//! `pybun-policy.toml` constrains which indexes and packages PyBun may use
+//! and whether `pybun run -c` may execute inline code; refusals surface as
+//! `E_POLICY_VIOLATION` and a non-zero exit.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const POLICY: &str = r#"[index]
+allowed = ["http://127.0.0.1:9/*"]
+
+[packages]
+blocked = ["reqeusts", "colour*ma"]
+
+[run]
+allow-code = false
+"#;
+
+fn pybun(dir: &Path, envs: &[(&str, &str)], args: &[&str]) -> (Value, i32) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .env("PYBUN_PYPI_BASE_URL", "http://127.0.0.1:9")
+        .env("PYBUN_PYPI_CACHE_DIR", dir.join("pypi-cache"))
+        .env_remove("PYBUN_INDEX_URL")
+        .env_remove("PYBUN_POLICY")
+        .envs(envs.iter().copied())
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (json, output.status.code().unwrap_or(-1))
+}
+
+fn violation(json: &Value) -> &Value {
+    &json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_POLICY_VIOLATION")
+        .unwrap_or_else(|| panic!("no E_POLICY_VIOLATION diagnostic: {json}"))["context"]
+}
+
+fn project(dir: &Path, dependency: &str) {
+    fs::write(
+        dir.join("pyproject.toml"),
+        format!(
+            "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"{dependency}\"]\n"
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn blocked_packages_are_refused_before_any_download() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("pybun-policy.toml"), POLICY).unwrap();
+    project(temp.path(), "Colourama==0.4.6");
+
+    let (json, code) = pybun(temp.path(), &[], &["install"]);
+    assert_ne!(code, 0);
+    assert_eq!(json["status"], "error");
+    let context = violation(&json);
+    assert_eq!(context["rule"], "package");
+    assert_eq!(context["subject"], "colourama");
+    assert_eq!(context["pattern"], "colour*ma");
+    assert!(
+        context["policy_file"]
+            .as_str()
+            .unwrap()
+            .ends_with("pybun-policy.toml")
+    );
+}
+
+#[test]
+fn indexes_outside_the_allowlist_are_refused() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("pybun-policy.toml"), POLICY).unwrap();
+    project(temp.path(), "requests==2.32.3");
+
+    let (json, code) = pybun(
+        temp.path(),
+        &[("PYBUN_PYPI_BASE_URL", "http://pypi.blocked.test")],
+        &["install"],
+    );
+    assert_ne!(code, 0);
+    let context = violation(&json);
+    assert_eq!(context["rule"], "index");
+    assert!(
+        context["subject"]
+            .as_str()
+            .unwrap()
+            .starts_with("http://pypi.blocked.test")
+    );
+}
+
+#[test]
+fn inline_code_is_refused_but_scripts_still_run() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("pybun-policy.toml"), POLICY).unwrap();
+    fs::write(temp.path().join("hello.py"), "print('hello')\n").unwrap();
+
+    let (json, code) = pybun(temp.path(), &[], &["run", "-c", "print('hi')"]);
+    assert_ne!(code, 0);
+    assert_eq!(violation(&json)["rule"], "run-code");
+    let errors = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|d| d["level"] == "error")
+        .count();
+    assert_eq!(errors, 1, "{json}");
+
+    let (json, code) = pybun(temp.path(), &[], &["run", "hello.py"]);
+    assert_eq!(code, 0, "{json}");
+    assert_eq!(json["detail"]["stdout"], "hello\n");
+}
+
+#[test]
+fn an_invalid_policy_named_by_env_fails_closed() {
+    let temp = tempdir().unwrap();
+    let policy = temp.path().join("agent-policy.toml");
+    fs::write(&policy, "[run]\nallow_code = true\n").unwrap();
+
+    let (json, code) = pybun(
+        temp.path(),
+        &[("PYBUN_POLICY", policy.to_str().unwrap())],
+        &["run", "-c", "print('hi')"],
+    );
+    assert_ne!(code, 0);
+    let context = violation(&json);
+    assert_eq!(context["rule"], "invalid");
+    assert!(context["subject"].as_str().unwrap().contains("allow_code"));
+}
+
+#[test]
+fn policy_network_section_replaces_the_project_allowlist() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("pybun-policy.toml"),
+        "[network]\nrun = []\n",
+    )
+    .unwrap();
+    fs::write(
+        temp.path().join("connect.py"),
+        "import socket\ntry:\n    socket.getaddrinfo('example.com', 80)\n    print('connected')\nexcept PermissionError:\n    print('refused')\n",
+    )
+    .unwrap();
+
+    let (json, _) = pybun(temp.path(), &[], &["run", "connect.py"]);
+    assert_eq!(json["detail"]["stdout"], "refused\n");
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_NETWORK_POLICY")
+        .unwrap_or_else(|| panic!("no E_NETWORK_POLICY diagnostic: {json}"));
+    assert!(
+        diagnostic["suggestion"]
+            .as_str()
+            .unwrap()
+            .contains("under [network] in")
+    );
+}
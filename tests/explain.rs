@@ -0,0 +1,76 @@
+//! E2E tests for the error catalog: `pybun explain` and the stable
+//! `error_code` on diagnostics.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn json_output(args: &[&str]) -> (Value, Option<i32>) {
+    let temp = tempdir().unwrap();
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (json, output.status.code())
+}
+
+#[test]
+fn explain_describes_a_code_given_by_id_or_diagnostic_code() {
+    let (json, code) = json_output(&["explain", "pybun-resolve-002"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(json["detail"]["code"], "PYBUN-RESOLVE-002");
+    assert_eq!(json["detail"]["diagnostic_codes"][0], "E_RESOLVE_CONFLICT");
+    assert!(!json["detail"]["fixes"].as_array().unwrap().is_empty());
+
+    let (by_diagnostic, _) = json_output(&["explain", "E_RESOLVE_CONFLICT"]);
+    assert_eq!(by_diagnostic["detail"], json["detail"]);
+
+    let output = cargo_bin_cmd!("pybun")
+        .args(["explain", "PYBUN-RESOLVE-002"])
+        .output()
+        .unwrap();
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.starts_with("PYBUN-RESOLVE-002: Conflicting requirements\n"));
+    assert!(text.contains("Common fixes:"));
+}
+
+#[test]
+fn explain_lists_codes_and_rejects_unknown_ones() {
+    let (json, code) = json_output(&["explain"]);
+    assert_eq!(code, Some(0));
+    let codes = json["detail"]["codes"].as_array().unwrap();
+    assert!(codes.iter().any(|c| c["code"] == "PYBUN-RUN-001"));
+
+    let (json, code) = json_output(&["explain", "PYBUN-NOPE-001"]);
+    assert_eq!(code, Some(1));
+    assert_eq!(json["diagnostics"][0]["code"], "E_EXPLAIN_UNKNOWN_CODE");
+}
+
+#[test]
+fn error_diagnostics_carry_the_catalogued_code() {
+    let (json, _) = json_output(&["run", "missing.py"]);
+    let diagnostic = &json["diagnostics"][0];
+    assert_eq!(diagnostic["code"], "E_RUN_TARGET_NOT_FOUND");
+    assert_eq!(diagnostic["error_code"], "PYBUN-RUN-001");
+
+    let temp = tempdir().unwrap();
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(temp.path())
+        .args(["run", "missing.py"])
+        .output()
+        .unwrap();
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        text.contains("error code: PYBUN-RUN-001 (run `pybun explain PYBUN-RUN-001` for details)"),
+        "{text}"
+    );
+}
@@ -268,3 +268,366 @@ fn gc_dry_run_shows_what_would_be_deleted() {
         stdout
     );
 }
+
+#[test]
+fn gc_json_reports_canonical_sizes_with_human_siblings() {
+    let temp = tempdir().unwrap();
+    let run_with = |legacy: &str, humanize: &str| {
+        let output = pybun_bin()
+            .env("PYBUN_HOME", temp.path())
+            .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("pypi"))
+            .env("PYBUN_LEGACY_FIELDS", legacy)
+            .env("PYBUN_HUMANIZE", humanize)
+            .args(["--format=json", "gc", "--dry-run"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let run = |legacy: &str| run_with(legacy, "1");
+
+    let json = run("1");
+    let detail = &json["detail"];
+    assert!(detail["freed_bytes"].is_u64());
+    assert!(detail["freed_human"].is_string());
+    assert_eq!(detail["size_before_bytes"], detail["size_before"]);
+    assert!(detail["size_before_human"].is_string());
+    assert!(detail["pep723_cache"]["freed_human"].is_string());
+
+    let json = run("0");
+    let detail = &json["detail"];
+    assert!(detail["size_after_bytes"].is_u64());
+    assert!(detail.get("size_after").is_none());
+
+    // The humanized sizes gc always printed are legacy fields, not siblings.
+    let json = run_with("1", "0");
+    let detail = &json["detail"];
+    assert!(detail["freed_human"].is_string());
+    assert!(detail["size_before_human"].is_string());
+    assert!(detail["size_after_human"].is_string());
+}
+
+#[test]
+fn readonly_cache_gc_only_collects_overlay() {
+    let temp = tempdir().unwrap();
+    let shared = temp.path().join("shared");
+    let overlay = temp.path().join("overlay");
+    let shared_wheel = shared.join("packages/shared-pkg/shared_pkg-1.0-py3-none-any.whl");
+    fs::create_dir_all(shared_wheel.parent().unwrap()).unwrap();
+    fs::write(&shared_wheel, vec![0u8; 4096]).unwrap();
+    let overlay_wheel = overlay.join("packages/job-pkg/job_pkg-1.0-py3-none-any.whl");
+    fs::create_dir_all(overlay_wheel.parent().unwrap()).unwrap();
+    fs::write(&overlay_wheel, vec![0u8; 4096]).unwrap();
+
+    let output = pybun_bin()
+        .env("PYBUN_HOME", &shared)
+        .env("PYBUN_READONLY_CACHE", "1")
+        .env("PYBUN_CACHE_OVERLAY", &overlay)
+        .args(["--format=json", "gc", "--max-size", "0"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["files_removed"], 1);
+    assert_eq!(json["detail"]["shared_cache"], shared.display().to_string());
+    assert!(shared_wheel.exists(), "shared layer must not be collected");
+    assert!(!overlay_wheel.exists());
+}
+
+#[test]
+fn readonly_cache_gc_leaves_the_shared_pypi_cache_alone() {
+    let temp = tempdir().unwrap();
+    let shared = temp.path().join("shared");
+    let overlay = temp.path().join("overlay");
+    let stale = b"\xff\xff\xff\xffnot-bincode";
+    let shared_entry = shared.join("pypi/requests.bin");
+    fs::create_dir_all(shared_entry.parent().unwrap()).unwrap();
+    fs::write(&shared_entry, stale).unwrap();
+    let overlay_entry = overlay.join("pypi/requests.bin");
+    fs::create_dir_all(overlay_entry.parent().unwrap()).unwrap();
+    fs::write(&overlay_entry, stale).unwrap();
+
+    let output = pybun_bin()
+        .env("PYBUN_HOME", &shared)
+        .env("PYBUN_PYPI_CACHE_DIR", shared.join("pypi"))
+        .env("PYBUN_READONLY_CACHE", "1")
+        .env("PYBUN_CACHE_OVERLAY", &overlay)
+        .args(["--format=json", "gc"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let pypi_cache = &json["detail"]["pypi_cache"];
+    assert_eq!(pypi_cache["files_removed"], 1);
+    assert_eq!(
+        pypi_cache["path"],
+        overlay.join("pypi").display().to_string()
+    );
+    assert!(shared_entry.exists(), "shared layer must not be collected");
+    assert!(!overlay_entry.exists());
+}
+
+#[test]
+fn readonly_cache_overlay_export_and_discard() {
+    let temp = tempdir().unwrap();
+    let shared = temp.path().join("shared");
+    let overlay = temp.path().join("overlay");
+    let wheel = overlay.join("packages/job-pkg/job_pkg-1.0-py3-none-any.whl");
+    fs::create_dir_all(wheel.parent().unwrap()).unwrap();
+    fs::write(&wheel, b"wheel").unwrap();
+    let delta = temp.path().join("delta");
+
+    let run = |args: &[&str]| {
+        let output = pybun_bin()
+            .env("PYBUN_HOME", &shared)
+            .env("PYBUN_READONLY_CACHE", "1")
+            .env("PYBUN_CACHE_OVERLAY", &overlay)
+            .arg("--format=json")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let json = run(&["gc", "--export-overlay", delta.to_str().unwrap()]);
+    assert_eq!(json["detail"]["action"], "export");
+    assert_eq!(json["detail"]["total_bytes"], 5);
+    assert!(
+        delta
+            .join("packages/job-pkg/job_pkg-1.0-py3-none-any.whl")
+            .is_file()
+    );
+    assert!(delta.join("pybun-overlay.json").is_file());
+
+    let json = run(&["gc", "--discard-overlay"]);
+    assert_eq!(json["detail"]["freed_bytes"], 5);
+    assert!(!overlay.exists());
+}
+
+#[test]
+fn readonly_cache_requires_an_explicit_overlay() {
+    let temp = tempdir().unwrap();
+    let output = pybun_bin()
+        .env("PYBUN_HOME", temp.path().join("shared"))
+        .env("PYBUN_READONLY_CACHE", "1")
+        .env_remove("PYBUN_CACHE_OVERLAY")
+        .args(["--format=json", "gc", "--discard-overlay"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("PYBUN_CACHE_OVERLAY is not"), "{stdout}");
+}
+
+#[test]
+fn overlay_commands_require_readonly_mode() {
+    let temp = tempdir().unwrap();
+    let output = pybun_bin()
+        .env("PYBUN_HOME", temp.path())
+        .env_remove("PYBUN_READONLY_CACHE")
+        .args(["--format=json", "gc", "--discard-overlay"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let codes: Vec<_> = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect();
+    assert!(codes.contains(&"E_CACHE_NOT_READONLY"), "{json}");
+}
+
+#[test]
+fn gc_dry_run_sweep_writes_deterministic_plan() {
+    let temp = tempdir().unwrap();
+    let pkg_dir = temp.path().join("packages").join("demo");
+    fs::create_dir_all(&pkg_dir).unwrap();
+    fs::write(pkg_dir.join("demo-1.0-py3-none-any.whl"), vec![0u8; 1024]).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    fs::write(pkg_dir.join("demo-2.0-py3-none-any.whl"), vec![0u8; 1024]).unwrap();
+    fs::write(
+        temp.path().join("cache-stats.json"),
+        serde_json::to_vec(&serde_json::json!({
+            "version": 1,
+            "entries": {
+                "packages/demo/demo-1.0-py3-none-any.whl": {"hits": 1, "misses": 1},
+                "packages/demo/demo-2.0-py3-none-any.whl": {"hits": 9, "misses": 1},
+            }
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let plan_path = temp.path().join("plan.json");
+    let run = || {
+        let output = pybun_bin()
+            .env("PYBUN_HOME", temp.path())
+            .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("pypi"))
+            .args(["--format=json", "gc", "--dry-run", "--sweep", "2K,1K"])
+            .arg("--plan")
+            .arg(&plan_path)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        fs::read_to_string(&plan_path).unwrap()
+    };
+
+    let first = run();
+    assert_eq!(first, run(), "plan output should be byte-identical");
+
+    let plan: serde_json::Value = serde_json::from_str(&first).unwrap();
+    assert_eq!(plan["recorded_hits"], 10);
+    let candidates = plan["candidates"].as_array().unwrap();
+    assert_eq!(candidates[0]["limit"], "1K");
+    assert_eq!(
+        candidates[0]["evicted"][0]["key"],
+        "packages/demo/demo-1.0-py3-none-any.whl"
+    );
+    assert_eq!(candidates[0]["retained_hit_ratio"], 0.9);
+    assert_eq!(candidates[1]["evicted_entries"], 0);
+    assert_eq!(plan["recommendation"]["limit"], "2K");
+
+    // Nothing was deleted.
+    assert!(pkg_dir.join("demo-1.0-py3-none-any.whl").exists());
+}
+
+#[test]
+fn gc_sweep_requires_dry_run() {
+    let temp = tempdir().unwrap();
+    let output = pybun_bin()
+        .env("PYBUN_HOME", temp.path())
+        .args(["gc", "--sweep", "1G"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+fn aged_wheel(home: &std::path::Path, name: &str, age_days: u64) -> std::path::PathBuf {
+    let path = home.join("packages").join("demo").join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, vec![0u8; 1024]).unwrap();
+    let mtime = std::time::SystemTime::now() - std::time::Duration::from_secs(age_days * 86_400);
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+    path
+}
+
+#[test]
+fn gc_max_age_keeps_wheels_used_by_lockfiles() {
+    use pybun::lockfile::{Lockfile, Package, PackageSource};
+    use pybun::project_registry::{Activity, ProjectRegistry};
+
+    let temp = tempdir().unwrap();
+    let home = temp.path().join("home");
+    let old = aged_wheel(&home, "old-1.0-py3-none-any.whl", 60);
+    let locked = aged_wheel(&home, "locked-1.0-py3-none-any.whl", 90);
+    let fresh = aged_wheel(&home, "fresh-1.0-py3-none-any.whl", 1);
+
+    let project = temp.path().join("app");
+    fs::create_dir_all(&project).unwrap();
+    let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+    lock.add_package(Package {
+        name: "locked".into(),
+        version: "1.0".into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: "locked-1.0-py3-none-any.whl".into(),
+        hash: "sha256:placeholder".into(),
+        dependencies: Vec::new(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
+    });
+    lock.save_to_path(project.join("pybun.lockb")).unwrap();
+    ProjectRegistry::with_path(home.join("projects.json"))
+        .record(&Activity {
+            root: &project,
+            command: "install",
+            lockfile: Some(std::path::Path::new("pybun.lockb")),
+            env: None,
+        })
+        .unwrap();
+
+    let gc = |extra: &[&str]| {
+        let output = pybun_bin()
+            .env("PYBUN_HOME", &home)
+            .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("pypi"))
+            .args([
+                "--format=json",
+                "gc",
+                "--max-age",
+                "30d",
+                "--keep-used-by-lockfiles",
+            ])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let json = gc(&["--dry-run"]);
+    let policy = &json["detail"]["policy"];
+    let evictions = policy["evictions"].as_array().unwrap();
+    assert_eq!(evictions.len(), 1, "{json}");
+    assert_eq!(evictions[0]["id"], "packages/demo/old-1.0-py3-none-any.whl");
+    assert_eq!(evictions[0]["reason"], "max_age");
+    assert_eq!(policy["categories"][0]["kept_locked"], 1);
+    assert!(old.exists(), "a dry run deletes nothing");
+
+    let json = gc(&[]);
+    assert_eq!(json["detail"]["freed_bytes"], 1024, "{json}");
+    assert!(!old.exists());
+    assert!(locked.exists());
+    assert!(fresh.exists());
+}
+
+#[test]
+fn gc_budget_evicts_least_recently_used_per_category() {
+    let temp = tempdir().unwrap();
+    let home = temp.path();
+    let oldest = aged_wheel(home, "a-1.0-py3-none-any.whl", 3);
+    let newer = aged_wheel(home, "b-1.0-py3-none-any.whl", 2);
+    let newest = aged_wheel(home, "c-1.0-py3-none-any.whl", 1);
+
+    let output = pybun_bin()
+        .env("PYBUN_HOME", home)
+        .env("PYBUN_PYPI_CACHE_DIR", home.join("pypi"))
+        .args(["--format=json", "gc", "--budget", "wheels=2K,envs=1G"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let wheels = &json["detail"]["policy"]["categories"][0];
+    assert_eq!(wheels["category"], "wheels");
+    assert_eq!(wheels["budget"], 2048);
+    assert_eq!(wheels["evicted_entries"], 1);
+    assert_eq!(json["detail"]["policy"]["evictions"][0]["reason"], "budget");
+    assert!(!oldest.exists());
+    assert!(newer.exists() && newest.exists());
+
+    let output = pybun_bin()
+        .env("PYBUN_HOME", home)
+        .args(["gc", "--budget", "disks=1G"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
@@ -0,0 +1,194 @@
+//! `name @ git+<url>@<ref>` dependencies: the ref is resolved to a commit
+//! pinned in the lockfile, and the wheel built from a commit is reused until
+//! the pin changes.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::{Lockfile, PackageSource};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn bin() -> Command {
+    cargo_bin_cmd!("pybun")
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?}: {output:?}");
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Repository of `gitpkg`, tagged `v<version>` for each version. Its
+/// in-tree backend writes a pure-Python wheel without build requirements.
+fn gitpkg_repo(dir: &Path, versions: &[&str]) {
+    let backend = r#"
+import os, zipfile
+
+def build_wheel(wheel_directory, config_settings=None, metadata_directory=None):
+    version = open("VERSION").read().strip()
+    name = f"gitpkg-{version}-py3-none-any.whl"
+    with zipfile.ZipFile(os.path.join(wheel_directory, name), "w") as whl:
+        whl.writestr("gitpkg/__init__.py", f"VERSION = {version!r}\n")
+        whl.writestr(f"gitpkg-{version}.dist-info/METADATA", f"Metadata-Version: 2.1\nName: gitpkg\nVersion: {version}\n")
+    return name
+"#;
+    fs::create_dir_all(dir).unwrap();
+    fs::write(
+        dir.join("pyproject.toml"),
+        "[build-system]\nrequires = []\nbuild-backend = \"backend\"\nbackend-path = [\".\"]\n",
+    )
+    .unwrap();
+    fs::write(dir.join("backend.py"), backend).unwrap();
+    git(dir, &["init", "--quiet", "-b", "main"]);
+    for version in versions {
+        fs::write(dir.join("VERSION"), version).unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "--quiet", "-m", version]);
+        git(dir, &["tag", &format!("v{version}")]);
+    }
+}
+
+fn create_venv(dir: &Path) -> Option<PathBuf> {
+    let venv = dir.join(".venv");
+    let ok = std::process::Command::new("python3")
+        .args(["-m", "venv", "--without-pip"])
+        .arg(&venv)
+        .status()
+        .is_ok_and(|s| s.success());
+    ok.then_some(venv)
+}
+
+fn pybun(dir: &Path, home: &Path, venv: &Path, args: &[&str]) -> Value {
+    let output = bin()
+        .current_dir(dir)
+        .env("PYBUN_HOME", home)
+        .env("PYBUN_PYPI_CACHE_DIR", home.join("pypi"))
+        .env("PYBUN_ENV", venv)
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    assert!(output.status.success(), "{json}");
+    json
+}
+
+fn git_result(json: &Value) -> Value {
+    json["detail"]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["name"] == "gitpkg")
+        .cloned()
+        .unwrap_or_else(|| panic!("no gitpkg result: {json}"))
+}
+
+fn locked_commit(project: &Path) -> String {
+    let lock = Lockfile::load_from_path(project.join("pybun.lockb")).unwrap();
+    match &lock.packages["gitpkg"].source {
+        PackageSource::Git { commit, .. } => commit.clone(),
+        other => panic!("gitpkg is not locked as a git source: {other:?}"),
+    }
+}
+
+#[test]
+fn add_git_dependency_pins_commit_and_rebuilds_only_when_it_changes() {
+    let temp = tempdir().unwrap();
+    let repo = temp.path().join("repo");
+    gitpkg_repo(&repo, &["1.0", "1.1"]);
+    let project = temp.path().join("app");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = []\n",
+    )
+    .unwrap();
+    let Some(venv) = create_venv(&project) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let home = temp.path().join("home");
+    let url = format!("file://{}", repo.display());
+
+    pybun(
+        &project,
+        &home,
+        &venv,
+        &["add", &format!("gitpkg @ git+{url}@v1.0")],
+    );
+    assert_eq!(locked_commit(&project), git(&repo, &["rev-parse", "v1.0"]));
+    let lock = Lockfile::load_from_path(project.join("pybun.lockb")).unwrap();
+    let PackageSource::Git {
+        url: locked_url,
+        reference,
+        ..
+    } = &lock.packages["gitpkg"].source
+    else {
+        unreachable!()
+    };
+    assert_eq!(locked_url, &url);
+    assert_eq!(reference.as_deref(), Some("v1.0"));
+    let pyproject = fs::read_to_string(project.join("pyproject.toml")).unwrap();
+    assert!(pyproject.contains("gitpkg @ git+file://"), "{pyproject}");
+
+    // The tag still names the same commit: its wheel is reused.
+    let json = pybun(&project, &home, &venv, &["install"]);
+    let record = git_result(&json);
+    assert_eq!(record["version"], "1.0");
+    assert_eq!(record["built"], true);
+    assert_eq!(record["cached"], true, "{record}");
+    assert_eq!(record["status"], "already_installed");
+    let site_packages = json["detail"]["environment"]["site_packages"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // A new pin is built and installed.
+    pybun(
+        &project,
+        &home,
+        &venv,
+        &["add", &format!("gitpkg @ git+{url}@v1.1")],
+    );
+    assert_eq!(locked_commit(&project), git(&repo, &["rev-parse", "v1.1"]));
+    let module = fs::read_to_string(Path::new(&site_packages).join("gitpkg/__init__.py")).unwrap();
+    assert_eq!(module, "VERSION = '1.1'\n");
+    let json = pybun(&project, &home, &venv, &["install", "--frozen"]);
+    let record = git_result(&json);
+    assert_eq!(record["version"], "1.1");
+    assert_eq!(record["cached"], true, "{record}");
+}
+
+#[test]
+fn non_git_direct_references_are_rejected() {
+    let temp = tempdir().unwrap();
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .args([
+            "--format=json",
+            "install",
+            "--require",
+            "demo @ https://example.invalid/demo-1.0-py3-none-any.whl",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("E_INSTALL_DIRECT_URL_UNSUPPORTED"),
+        "{stdout}"
+    );
+}
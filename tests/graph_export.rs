@@ -0,0 +1,156 @@
+//! `pybun graph` exports the locked dependency graph with per-package
+//! attributes for tools that should not have to re-resolve the project.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::{Lockfile, Package, PackageSource};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+    Package {
+        name: name.into(),
+        version: version.into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: format!("{name}-{version}-py3-none-any.whl"),
+        hash: format!("sha256:{}", "cd".repeat(32)),
+        dependencies: deps.iter().map(|d| d.to_string()).collect(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
+    }
+}
+
+/// Project `demo` depending on `requests`, which depends on `urllib3`, with
+/// a venv where only `urllib3` is installed.
+fn setup_project(root: &Path) {
+    fs::write(
+        root.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"requests>=2.31\"]\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+    lock.add_package(package(
+        "requests",
+        "2.32.0",
+        &["urllib3>=1.21; python_version >= \"3.8\""],
+    ));
+    lock.add_package(package("urllib3", "2.2.0", &[]));
+    lock.save_to_path(root.join("pybun.lockb")).unwrap();
+
+    let dist_info = root.join(".venv/lib/python3.11/site-packages/urllib3-2.2.0.dist-info");
+    fs::create_dir_all(&dist_info).unwrap();
+    fs::write(
+        dist_info.join("METADATA"),
+        "Metadata-Version: 2.4\nName: urllib3\nVersion: 2.2.0\nLicense-Expression: MIT\n",
+    )
+    .unwrap();
+    fs::write(
+        dist_info.join("RECORD"),
+        "urllib3/__init__.py,sha256=abc,4096\nurllib3-2.2.0.dist-info/RECORD,,\n",
+    )
+    .unwrap();
+}
+
+fn pybun(dir: &Path, args: &[&str]) -> (bool, Value) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .env_remove("PYBUN_ENV")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+#[test]
+fn graphml_export_carries_package_and_edge_attributes() {
+    let temp = tempdir().unwrap();
+    setup_project(temp.path());
+    let out = temp.path().join("out/deps.graphml");
+
+    let (ok, json) = pybun(
+        temp.path(),
+        &[
+            "graph",
+            "--export",
+            "graphml",
+            "--venv",
+            ".venv",
+            "--output",
+            out.to_str().unwrap(),
+        ],
+    );
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["format"], "graphml");
+    let packages = json["detail"]["packages"].as_array().unwrap();
+    assert_eq!(packages[0]["direct"], true);
+    assert_eq!(packages[1]["license"], "MIT");
+    assert_eq!(packages[1]["size_bytes"], 4096);
+
+    let xml = fs::read_to_string(&out).unwrap();
+    assert!(xml.contains("<edge source=\"demo\" target=\"requests\">"));
+    assert!(xml.contains("<data key=\"marker\">python_version &gt;= &quot;3.8&quot;</data>"));
+    assert!(xml.contains("<data key=\"size_bytes\">4096</data>"));
+}
+
+#[test]
+fn spdx_export_lists_dependency_relationships() {
+    let temp = tempdir().unwrap();
+    setup_project(temp.path());
+
+    let (ok, json) = pybun(
+        temp.path(),
+        &["graph", "--export", "spdx", "--venv", ".venv"],
+    );
+    assert!(ok, "{json}");
+    let document: Value =
+        serde_json::from_str(json["detail"]["content"].as_str().unwrap()).unwrap();
+    assert_eq!(document["spdxVersion"], "SPDX-2.3");
+    let depends_on: Vec<(&str, &str)> = document["relationships"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|r| r["relationshipType"] == "DEPENDS_ON")
+        .map(|r| {
+            (
+                r["spdxElementId"].as_str().unwrap(),
+                r["relatedSpdxElement"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        depends_on,
+        vec![
+            ("SPDXRef-Package-demo", "SPDXRef-Package-requests"),
+            ("SPDXRef-Package-requests", "SPDXRef-Package-urllib3"),
+        ]
+    );
+    let urllib3 = &document["packages"][2];
+    assert_eq!(urllib3["licenseDeclared"], "MIT");
+    assert_eq!(
+        urllib3["externalRefs"][0]["referenceLocator"],
+        "pkg:pypi/urllib3@2.2.0"
+    );
+}
+
+#[test]
+fn graph_requires_a_lockfile() {
+    let temp = tempdir().unwrap();
+    let (ok, json) = pybun(temp.path(), &["graph"]);
+    assert!(!ok);
+    assert_eq!(json["diagnostics"][0]["code"], "E_LOCKFILE_NOT_FOUND");
+}
@@ -224,6 +224,68 @@ mod polling_watch_cli_tests {
         );
     }
 
+    #[test]
+    fn test_watch_polling_fallback_reports_syntax_error_without_running() {
+        let temp = TempDir::new().unwrap();
+        let py_file = temp.path().join("main.py");
+        File::create(&py_file).unwrap();
+
+        let mut child = StdCommand::new(env!("CARGO_BIN_EXE_pybun"))
+            .args([
+                "--format=json",
+                "watch",
+                "main.py",
+                "-p",
+                temp.path().to_str().unwrap(),
+                "--debounce",
+                "100",
+            ])
+            .env("PYBUN_WATCH_MAX_ITERATIONS", "20")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to start pybun watch");
+
+        let mut reader = BufReader::new(child.stderr.take().unwrap());
+        let mut banner = String::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).expect("read stderr") == 0 {
+                break;
+            }
+            banner.push_str(&line);
+            if line.contains("watching for changes...") {
+                break;
+            }
+        }
+
+        let mut file = File::create(&py_file).unwrap();
+        writeln!(file, "if True:\n    print('changed'").unwrap();
+        file.sync_all().unwrap();
+        drop(file);
+
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).expect("read stderr");
+        let stderr = banner + &rest;
+        let output = child.wait_with_output().expect("failed to wait on child");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(output.status.success(), "stderr: {stderr}");
+        assert!(
+            stderr.contains(
+                "line 2\n    print('changed'\n         ^\nSyntaxError: '(' was never closed"
+            ),
+            "expected a caret overlay, got: {stderr}"
+        );
+        assert!(!stderr.contains("running:"), "stderr: {stderr}");
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        let detail = &json["detail"];
+        assert_eq!(detail["runs"], 0);
+        assert!(detail["skipped_runs"].as_u64().unwrap() >= 1);
+        assert_eq!(detail["syntax_error"]["line"], 2);
+        assert_eq!(detail["syntax_error"]["code"], "E_RUNTIME_SYNTAX_ERROR");
+    }
+
     #[test]
     fn test_watch_polling_fallback_no_target_directory_errors() {
         let output = StdCommand::new(env!("CARGO_BIN_EXE_pybun"))
@@ -0,0 +1,357 @@
+//! `pybun import` brings requirements files, Pipfiles and Poetry sections
+//! into pyproject.toml and the lock; `pybun export` writes the lock back out
+//! as a hash-pinned requirements.txt or a uv.lock. `pybun lock --from`
+//! converts a uv.lock or poetry.lock into pybun.lockb.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::Lockfile;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn index_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/index.json")
+}
+
+fn pybun(dir: &Path, args: &[&str]) -> (bool, Value) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".home"))
+        .env("PYBUN_FORCE_CP_TAG", "cp312")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+fn codes(json: &Value) -> Vec<&str> {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect()
+}
+
+#[test]
+fn requirements_round_trip_through_the_lock() {
+    let temp = tempdir().unwrap();
+    let dir = temp.path();
+    fs::write(
+        dir.join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("requirements.txt"),
+        "lib-a==1.0.0 --hash=sha256:liba100\n\
+         lib-b>=2 ; python_version >= \"3.8\"  # optional speedups\n\
+         -e ./vendor/local\n",
+    )
+    .unwrap();
+
+    let index = index_path();
+    let (ok, json) = pybun(
+        dir,
+        &[
+            "import",
+            "requirements.txt",
+            "--index",
+            index.to_str().unwrap(),
+        ],
+    );
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["format"], "requirements.txt");
+    assert_eq!(detail["locked"], true);
+    assert_eq!(detail["skipped"][0]["entry"], "-e ./vendor/local");
+    assert!(codes(&json).contains(&"W_IMPORT_SKIPPED"));
+
+    let pyproject = fs::read_to_string(dir.join("pyproject.toml")).unwrap();
+    assert!(pyproject.contains("\"lib-a==1.0.0\""), "{pyproject}");
+    assert!(
+        pyproject.contains("'lib-b>=2; python_version >= \"3.8\"'"),
+        "{pyproject}"
+    );
+    let lock = Lockfile::load_from_path(dir.join("pybun.lockb")).unwrap();
+    let locked: Vec<&str> = lock.packages.keys().map(String::as_str).collect();
+    assert_eq!(locked, ["lib-a", "lib-b", "lib-c"]);
+
+    let (ok, json) = pybun(dir, &["export"]);
+    assert!(ok, "{json}");
+    let content = json["detail"]["content"].as_str().unwrap();
+    assert!(
+        content.contains("lib-a==1.0.0 \\\n    --hash=sha256:liba100\n"),
+        "{content}"
+    );
+    assert!(
+        content
+            .contains("lib-b==2.0.0 ; python_version >= \"3.8\" \\\n    --hash=sha256:libb200\n"),
+        "{content}"
+    );
+    assert!(content.contains("lib-c==1.0.0 \\\n"), "{content}");
+
+    let (ok, json) = pybun(
+        dir,
+        &["export", "--no-hashes", "-o", "out/requirements.txt"],
+    );
+    assert!(ok, "{json}");
+    let written = fs::read_to_string(dir.join("out/requirements.txt")).unwrap();
+    assert!(!written.contains("--hash"));
+    assert!(written.contains("lib-a==1.0.0\n"));
+    assert_eq!(json["detail"]["packages"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn pipfile_and_poetry_sections_without_locking() {
+    let temp = tempdir().unwrap();
+    let dir = temp.path();
+    fs::write(
+        dir.join("Pipfile"),
+        "[[source]]\nurl = \"https://mirror.example/simple\"\nname = \"mirror\"\n\n\
+         [packages]\nlib-a = \"==1.0.0\"\n\n[dev-packages]\nlib-b = \"*\"\n\n\
+         [requires]\npython_version = \"3.12\"\n",
+    )
+    .unwrap();
+
+    // No pyproject.toml yet: import creates one.
+    let (ok, json) = pybun(dir, &["import", "Pipfile", "--no-lock"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["locked"], false);
+    assert_eq!(json["detail"]["requires_python"], "==3.12.*");
+    assert!(codes(&json).contains(&"W_IMPORT_INDEX"));
+    assert!(!dir.join("pybun.lockb").exists());
+    let pyproject: toml::Value =
+        toml::from_str(&fs::read_to_string(dir.join("pyproject.toml")).unwrap()).unwrap();
+    assert_eq!(
+        pyproject["project"]["dependencies"],
+        toml::Value::Array(vec!["lib-a==1.0.0".into()])
+    );
+    assert_eq!(
+        pyproject["dependency-groups"]["dev"],
+        toml::Value::Array(vec!["lib-b".into()])
+    );
+
+    let poetry = dir.join("legacy");
+    fs::create_dir_all(&poetry).unwrap();
+    fs::write(
+        poetry.join("pyproject.toml"),
+        "[tool.poetry]\nname = \"legacy\"\n\n[tool.poetry.dependencies]\n\
+         python = \"^3.10\"\nlib-c = \"^1.0\"\n",
+    )
+    .unwrap();
+    let (ok, json) = pybun(dir, &["import", "legacy/pyproject.toml", "--no-lock"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["format"], "poetry");
+    assert_eq!(json["detail"]["dependencies"][0], "lib-c>=1.0,<2.0");
+    // The Pipfile's requires-python is kept.
+    assert!(json["detail"]["requires_python"].is_null());
+}
+
+#[test]
+fn failures_leave_the_project_untouched() {
+    let temp = tempdir().unwrap();
+    let dir = temp.path();
+    let pyproject = "[project]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = []\n";
+    fs::write(dir.join("pyproject.toml"), pyproject).unwrap();
+    fs::write(dir.join("deps.cfg"), "lib-a\n").unwrap();
+
+    let (ok, json) = pybun(dir, &["import", "deps.cfg"]);
+    assert!(!ok);
+    assert!(codes(&json).contains(&"E_IMPORT_FAILED"), "{json}");
+
+    // lib-z is not in the index, so locking fails and pyproject.toml is restored.
+    fs::write(dir.join("requirements.txt"), "lib-z==9.9\n").unwrap();
+    let index = index_path();
+    let (ok, json) = pybun(
+        dir,
+        &[
+            "import",
+            "requirements.txt",
+            "--index",
+            index.to_str().unwrap(),
+        ],
+    );
+    assert!(!ok, "{json}");
+    assert_eq!(
+        fs::read_to_string(dir.join("pyproject.toml")).unwrap(),
+        pyproject
+    );
+    assert!(!dir.join("pybun.lockb").exists());
+
+    let (ok, json) = pybun(dir, &["export"]);
+    assert!(!ok);
+    assert!(codes(&json).contains(&"E_LOCKFILE_NOT_FOUND"), "{json}");
+}
+
+const UV_LOCK: &str = r#"version = 1
+requires-python = ">=3.12"
+
+[[package]]
+name = "app"
+version = "0.1.0"
+source = { virtual = "." }
+dependencies = [{ name = "lib-a" }]
+
+[package.dev-dependencies]
+dev = [{ name = "lib-b" }]
+
+[[package]]
+name = "lib-a"
+version = "1.0.0"
+source = { registry = "https://pypi.org/simple" }
+dependencies = [{ name = "lib-c" }]
+wheels = [{ url = "https://files.example/lib_a-1.0.0-py3-none-any.whl", hash = "sha256:liba100" }]
+
+[[package]]
+name = "lib-b"
+version = "2.0.0"
+source = { registry = "https://pypi.org/simple" }
+sdist = { url = "https://files.example/lib_b-2.0.0.tar.gz", hash = "sha256:libb200src" }
+wheels = [{ url = "https://files.example/lib_b-2.0.0-py3-none-any.whl", hash = "sha256:libb200" }]
+
+[[package]]
+name = "lib-c"
+version = "1.0.0"
+source = { registry = "https://pypi.org/simple" }
+wheels = [{ url = "https://files.example/lib_c-1.0.0-py3-none-any.whl", hash = "sha256:libc100" }]
+"#;
+
+#[test]
+fn uv_lock_converts_to_pybun_lock_and_back() {
+    let temp = tempdir().unwrap();
+    let dir = temp.path();
+    fs::write(
+        dir.join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\nrequires-python = \">=3.12\"\n\
+         dependencies = [\"lib-a>=1.0\"]\n\n[dependency-groups]\ndev = [\"lib-b\"]\n",
+    )
+    .unwrap();
+    fs::write(dir.join("uv.lock"), UV_LOCK).unwrap();
+
+    let (ok, json) = pybun(dir, &["lock", "--from", "uv.lock"]);
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["format"], "uv.lock");
+    assert_eq!(detail["python"][0], "3.12");
+    assert_eq!(detail["groups"], serde_json::json!(["dev", "main"]));
+    let lock = Lockfile::load_from_path(dir.join("pybun.lockb")).unwrap();
+    let locked: Vec<&str> = lock.packages.keys().map(String::as_str).collect();
+    assert_eq!(locked, ["lib-a", "lib-b", "lib-c"]);
+    assert_eq!(lock.packages["lib-c"].groups, ["main"]);
+    assert_eq!(lock.packages["lib-b"].groups, ["dev"]);
+    assert_eq!(lock.packages["lib-b"].artifacts.len(), 2);
+    assert_eq!(
+        lock.packages["lib-a"].artifacts[0].url.as_deref(),
+        Some("https://files.example/lib_a-1.0.0-py3-none-any.whl")
+    );
+
+    let (ok, json) = pybun(dir, &["export", "--export", "uv.lock", "-o", "out/uv.lock"]);
+    assert!(ok, "{json}");
+    assert!(json["detail"]["unlocated"].as_array().unwrap().is_empty());
+    let written = fs::read_to_string(dir.join("out/uv.lock")).unwrap();
+    assert!(
+        written.contains(
+            "name = \"app\"\nversion = \"0.1.0\"\nsource = { virtual = \".\" }\n\
+             dependencies = [\n    { name = \"lib-a\" },\n]\n"
+        ),
+        "{written}"
+    );
+    assert!(
+        written
+            .contains("[package.metadata.requires-dev]\ndev = [\n    { name = \"lib-b\" },\n]\n")
+    );
+    assert!(written.contains(
+        "sdist = { url = \"https://files.example/lib_b-2.0.0.tar.gz\", hash = \"sha256:libb200src\" }\n"
+    ));
+
+    // The export reads back into the same lock.
+    let (ok, json) = pybun(dir, &["lock", "--from", "out/uv.lock"]);
+    assert!(ok, "{json}");
+    assert_eq!(
+        Lockfile::load_from_path(dir.join("pybun.lockb")).unwrap(),
+        lock
+    );
+}
+
+#[test]
+fn poetry_lock_converts_with_groups_and_reports_gaps() {
+    let temp = tempdir().unwrap();
+    let dir = temp.path();
+    fs::write(
+        dir.join("poetry.lock"),
+        r#"[[package]]
+name = "lib-a"
+version = "1.0.0"
+groups = ["main"]
+files = [{file = "lib_a-1.0.0-py3-none-any.whl", hash = "sha256:liba100"}]
+
+[package.dependencies]
+lib-c = "^1.0"
+
+[[package]]
+name = "lib-c"
+version = "1.0.0"
+groups = ["main"]
+files = [{file = "lib_c-1.0.0-py3-none-any.whl", hash = "sha256:libc100"}]
+
+[[package]]
+name = "tool"
+version = "0.1.0"
+groups = ["dev"]
+files = []
+
+[package.source]
+type = "directory"
+url = "../tool"
+
+[metadata]
+lock-version = "2.1"
+python-versions = "^3.12"
+content-hash = "0"
+"#,
+    )
+    .unwrap();
+    fs::write(dir.join("pybun.lockb"), b"previous").unwrap();
+
+    // Not a lockfile: nothing is written.
+    fs::write(dir.join("other.lock"), "[tool]\nname = \"x\"\n").unwrap();
+    let (ok, json) = pybun(dir, &["lock", "--from", "other.lock"]);
+    assert!(!ok);
+    assert!(codes(&json).contains(&"E_LOCK_FROM_FAILED"), "{json}");
+    assert_eq!(fs::read(dir.join("pybun.lockb")).unwrap(), b"previous");
+
+    // The fixture index publishes no file URLs, so they stay unknown.
+    let index = index_path();
+    let (ok, json) = pybun(
+        dir,
+        &[
+            "lock",
+            "--from",
+            "poetry.lock",
+            "--index",
+            index.to_str().unwrap(),
+        ],
+    );
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["format"], "poetry.lock");
+    assert_eq!(detail["requires_python"], ">=3.12,<4.0");
+    assert_eq!(detail["skipped"][0]["entry"], "tool 0.1.0");
+    assert_eq!(detail["unlocated"], serde_json::json!(["lib-a", "lib-c"]));
+    let codes = codes(&json);
+    assert!(codes.contains(&"W_LOCK_FROM_SKIPPED"));
+    assert!(codes.contains(&"W_LOCK_FROM_NO_URL"));
+    let lock = Lockfile::load_from_path(dir.join("pybun.lockb")).unwrap();
+    assert_eq!(lock.packages["lib-a"].dependencies, ["lib-c>=1.0,<2.0"]);
+    assert_eq!(lock.packages["lib-c"].groups, ["main"]);
+}
@@ -419,6 +419,10 @@ dependencies = ["pkg-a>=1.0.0"]
         wheel: "pkg_a-1.0.0-py3-none-any.whl".into(),
         hash: "sha256:placeholder".into(),
         dependencies: vec![],
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
     });
     lock.save_to_path(&lock_path).unwrap();
 
@@ -674,3 +678,101 @@ fn install_python_incompatible_hint_names_newest_compatible_release() {
         .expect("expected H_RESOLVE_PYTHON_NEWEST_COMPATIBLE hint");
     assert_eq!(hint["context"]["version"], "1.13.1");
 }
+
+#[test]
+fn oversized_output_sections_are_spilled_to_disk() {
+    let temp = tempdir().unwrap();
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path())
+        .args([
+            "--format=json",
+            "--max-inline-bytes=1KB",
+            "run",
+            "-c",
+            "print('x' * 5000)",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let parsed: Value = serde_json::from_slice(&output).expect("json output");
+
+    let stdout = &parsed["detail"]["stdout"];
+    assert_eq!(stdout["spilled"], true, "{parsed}");
+    assert_eq!(stdout["format"], "text");
+    assert_eq!(stdout["size_bytes"], 5001);
+    let path = std::path::Path::new(stdout["path"].as_str().unwrap());
+    assert!(path.starts_with(temp.path().join("payloads")));
+    assert_eq!(
+        fs::read_to_string(path).unwrap(),
+        format!("{}\n", "x".repeat(5000))
+    );
+    let diagnostic = parsed["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "I_PAYLOAD_SPILLED")
+        .expect("spill diagnostic");
+    assert_eq!(diagnostic["context"]["sections"][0]["location"], "stdout");
+
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path())
+        .args([
+            "--format=json",
+            "--max-inline-bytes=0",
+            "run",
+            "-c",
+            "print('x' * 5000)",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let parsed: Value = serde_json::from_slice(&output).expect("json output");
+    assert_eq!(
+        parsed["detail"]["stdout"],
+        format!("{}\n", "x".repeat(5000))
+    );
+}
+
+#[test]
+fn oversized_test_output_is_captured_straight_to_disk() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("test_big.py"),
+        "import unittest\n\nclass Big(unittest.TestCase):\n    def test_big(self):\n        print('y' * 200000)\n",
+    )
+    .unwrap();
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path())
+        .args([
+            "--format=json",
+            "--max-inline-bytes=1KB",
+            "test",
+            "--backend=unittest",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let parsed: Value = serde_json::from_slice(&output).expect("json output");
+
+    let stdout = &parsed["detail"]["stdout"];
+    assert_eq!(stdout["spilled"], true, "{parsed}");
+    assert_eq!(stdout["size_bytes"], 200001);
+    assert!(stdout["preview"].as_str().unwrap().starts_with("yyy"));
+    let path = std::path::Path::new(stdout["path"].as_str().unwrap());
+    assert!(
+        path.file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("capture-")
+    );
+    assert_eq!(fs::metadata(path).unwrap().len(), 200001);
+}
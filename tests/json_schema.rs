@@ -390,9 +390,11 @@ if __name__ == "__main__":
     }
 
     #[test]
-    fn mcp_serve_stub_json() {
+    fn mcp_serve_json() {
+        // Refused before binding, so the server does not keep running.
         let output = Command::new(env!("CARGO_BIN_EXE_pybun"))
-            .args(["--format=json", "mcp", "serve"])
+            .args(["--format=json", "mcp", "serve", "--host", "0.0.0.0"])
+            .env_remove("PYBUN_MCP_TOKEN")
             .output()
             .expect("failed to execute pybun");
 
@@ -85,7 +85,7 @@ fn test_lazy_import_generate() {
         .args(["lazy-import", "--generate"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("class LazyModule"))
+        .stdout(predicate::str::contains("class LazyLoader"))
         .stdout(predicate::str::contains("class LazyFinder"));
 }
 
@@ -98,6 +98,36 @@ fn test_lazy_import_generate_with_allowlist() {
         .stdout(predicate::str::contains("numpy"));
 }
 
+#[test]
+fn test_lazy_import_generated_hook_defers_until_use() {
+    let temp = TempDir::new().unwrap();
+    pybun()
+        .current_dir(temp.path())
+        .args(["lazy-import", "--generate", "-o", "lazy_hook.py"])
+        .assert()
+        .success();
+    std::fs::write(
+        temp.path().join("main.py"),
+        "import lazy_hook\nimport json, decimal\nprint(json.dumps([lazy_hook.is_lazy(decimal)]))\nprint(lazy_hook.report()['never_loaded'])\n",
+    )
+    .unwrap();
+
+    let output = std::process::Command::new("python3")
+        .arg("main.py")
+        .current_dir(temp.path())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[true]\n['decimal']\n"
+    );
+}
+
 #[test]
 fn test_lazy_import_generate_with_log() {
     pybun()
@@ -126,7 +156,7 @@ fn test_lazy_import_generate_to_file() {
     // Verify file was created
     assert!(output_file.exists());
     let content = std::fs::read_to_string(&output_file).unwrap();
-    assert!(content.contains("class LazyModule"));
+    assert!(content.contains("class LazyLoader"));
 }
 
 #[test]
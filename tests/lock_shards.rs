@@ -0,0 +1,248 @@
+//! E2E tests for per-member lock shards: `pybun install` at a workspace root
+//! writes `pybun.lock.d/<member>.lockb`, `pybun install --package` installs
+//! one member's closure from its shard, and drifted shards are refused.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn bin() -> Command {
+    cargo_bin_cmd!("pybun")
+}
+
+fn wheel_bytes(name: &str) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file(format!("{name}/__init__.py"), options)
+        .unwrap();
+    zip.write_all(b"VALUE = 1\n").unwrap();
+    zip.start_file(format!("{name}-1.0.0.dist-info/METADATA"), options)
+        .unwrap();
+    zip.write_all(format!("Metadata-Version: 2.1\nName: {name}\nVersion: 1.0.0\n").as_bytes())
+        .unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+/// Fake PyPI JSON API serving `alpha`, `beta` and `gamma` 1.0.0.
+fn mock_pypi(server: &MockServer) {
+    for name in ["alpha", "beta", "gamma"] {
+        let wheel = wheel_bytes(name);
+        let filename = format!("{name}-1.0.0-py3-none-any.whl");
+        let project = json!({
+            "info": { "name": name, "version": "1.0.0" },
+            "releases": { "1.0.0": [{
+                "filename": filename,
+                "packagetype": "bdist_wheel",
+                "url": format!("{}/files/{filename}", server.base_url()),
+                "yanked": false,
+                "digests": { "sha256": hex::encode(Sha256::digest(&wheel)) }
+            }]}
+        })
+        .to_string();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/pypi/{name}/json"));
+            then.status(200).body(project.clone());
+        });
+        let release = json!({
+            "info": { "name": name, "version": "1.0.0", "requires_dist": [] }
+        })
+        .to_string();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/pypi/{name}/1.0.0/json"));
+            then.status(200).body(release.clone());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/files/{filename}"));
+            then.status(200).body(wheel.clone());
+        });
+    }
+}
+
+/// `svc-api` needs `alpha` and the `lib-core` member (`beta`); `svc-worker`
+/// needs `gamma`.
+fn write_monorepo(root: &Path) {
+    fs::write(
+        root.join("pyproject.toml"),
+        "[tool.pybun.workspace]\nmembers = [\"services/api\", \"services/worker\", \"libs/core\"]\n",
+    )
+    .unwrap();
+    for (dir, name, deps) in [
+        (
+            "services/api",
+            "svc-api",
+            "[\"alpha==1.0.0\", \"lib-core\"]",
+        ),
+        ("services/worker", "svc-worker", "[\"gamma==1.0.0\"]"),
+        ("libs/core", "lib-core", "[\"beta==1.0.0\"]"),
+    ] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(
+            root.join(dir).join("pyproject.toml"),
+            format!("[project]\nname = \"{name}\"\nversion = \"0.1.0\"\ndependencies = {deps}\n"),
+        )
+        .unwrap();
+    }
+}
+
+fn create_venv(dir: &Path) -> Option<PathBuf> {
+    let ok = std::process::Command::new("python3")
+        .args(["-m", "venv", "--without-pip"])
+        .arg(dir)
+        .status()
+        .is_ok_and(|s| s.success());
+    ok.then(|| dir.to_path_buf())
+}
+
+fn pybun(root: &Path, base_url: &str, venv: &Path, args: &[&str]) -> (bool, Value) {
+    let output = bin()
+        .current_dir(root)
+        .env("PYBUN_PYPI_BASE_URL", base_url)
+        .env("PYBUN_PYPI_CACHE_DIR", venv.join("cache"))
+        .env("PYBUN_ENV", venv)
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+fn has_diagnostic(json: &Value, code: &str) -> bool {
+    json["diagnostics"]
+        .as_array()
+        .is_some_and(|d| d.iter().any(|d| d["code"] == code))
+}
+
+#[test]
+fn install_package_installs_one_member_from_its_shard() {
+    let temp = tempdir().unwrap();
+    let root = temp.path().join("mono");
+    fs::create_dir_all(&root).unwrap();
+    write_monorepo(&root);
+    let Some(venv) = create_venv(&temp.path().join("venv")) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let server = MockServer::start();
+    mock_pypi(&server);
+
+    let (ok, json) = pybun(&root, &server.base_url(), &venv, &["install"]);
+    assert!(ok, "{json}");
+    assert!(
+        json["detail"]["workspace"]["shards"]
+            .as_str()
+            .is_some_and(|dir| dir.ends_with("pybun.lock.d")),
+        "{json}"
+    );
+    for member in ["svc-api", "svc-worker", "lib-core"] {
+        assert!(
+            root.join(format!("pybun.lock.d/{member}.lockb")).is_file(),
+            "{member}"
+        );
+    }
+
+    // A fresh environment, with an index that cannot be reached: only the
+    // svc-api closure is installed, from its shard.
+    let fresh = create_venv(&temp.path().join("fresh")).unwrap();
+    let (ok, json) = pybun(
+        &root,
+        "http://127.0.0.1:9",
+        &fresh,
+        &["install", "--package", "svc-api"],
+    );
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["packages"], json!(["alpha", "beta"]), "{json}");
+    assert_eq!(detail["workspace"]["scope"], "package");
+    assert_eq!(
+        detail["workspace"]["selected_members"],
+        json!(["svc-api", "lib-core"])
+    );
+    let editable: Vec<&str> = detail["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|r| r["editable"].is_string())
+        .map(|r| r["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(editable, ["svc-api", "lib-core"], "{json}");
+}
+
+#[test]
+fn drifted_shards_are_reported_and_rederived() {
+    let temp = tempdir().unwrap();
+    let root = temp.path().join("mono");
+    fs::create_dir_all(&root).unwrap();
+    write_monorepo(&root);
+    let Some(venv) = create_venv(&temp.path().join("venv")) else {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    };
+    let server = MockServer::start();
+    mock_pypi(&server);
+    let base = server.base_url();
+
+    let (ok, json) = pybun(&root, &base, &venv, &["install"]);
+    assert!(ok, "{json}");
+    let (ok, json) = pybun(&root, &base, &venv, &["lock", "--check-shards"]);
+    assert!(ok, "{json}");
+
+    // The worker shard goes missing and the API shard is replaced by the
+    // worker's: both no longer follow the workspace lockfile.
+    let shards = root.join("pybun.lock.d");
+    fs::rename(
+        shards.join("svc-worker.lockb"),
+        shards.join("svc-api.lockb"),
+    )
+    .unwrap();
+
+    let (ok, json) = pybun(&root, &base, &venv, &["lock", "--check-shards"]);
+    assert!(!ok, "{json}");
+    assert!(has_diagnostic(&json, "E_LOCK_SHARD_DRIFT"), "{json}");
+    let drift = json["detail"]["drift"].as_array().unwrap();
+    assert_eq!(drift[0]["member"], "svc-api");
+    assert_eq!(drift[0]["kind"], "stale");
+    assert_eq!(drift[0]["missing"], json!(["alpha==1.0.0", "beta==1.0.0"]));
+    assert_eq!(drift[0]["unexpected"], json!(["gamma==1.0.0"]));
+    assert_eq!(drift[1]["member"], "svc-worker");
+    assert_eq!(drift[1]["kind"], "missing");
+
+    let (ok, json) = pybun(&root, &base, &venv, &["install", "--package", "svc-api"]);
+    assert!(!ok, "{json}");
+    assert!(has_diagnostic(&json, "E_LOCK_SHARD_DRIFT"), "{json}");
+
+    let (ok, json) = pybun(&root, &base, &venv, &["lock", "--shards"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["written"].as_array().unwrap().len(), 3);
+    let (ok, json) = pybun(&root, &base, &venv, &["lock", "--check-shards"]);
+    assert!(ok, "{json}");
+}
+
+#[test]
+fn install_package_rejects_unknown_members() {
+    let temp = tempdir().unwrap();
+    write_monorepo(temp.path());
+    let output = bin()
+        .current_dir(temp.path())
+        .args(["install", "--package", "svc-missing"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        format!("{stdout}{stderr}").contains("available: svc-api, svc-worker, lib-core"),
+        "{stdout}{stderr}"
+    );
+}
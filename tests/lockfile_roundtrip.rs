@@ -1,4 +1,4 @@
-use pybun::lockfile::{Lockfile, Package, PackageSource};
+use pybun::lockfile::{Artifact, Lockfile, MAIN_GROUP, Package, PackageSource, SourceBuild};
 
 #[test]
 fn roundtrip_preserves_data() {
@@ -13,6 +13,10 @@ fn roundtrip_preserves_data() {
         wheel: "requests-2.31.0-py3-none-any.whl".into(),
         hash: "sha256:deadbeef".into(),
         dependencies: vec!["urllib3>=1.26".into(), "certifi>=2023.0".into()],
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
     });
 
     let bytes = lock.to_bytes().expect("encode");
@@ -35,6 +39,10 @@ fn serialization_is_deterministic() {
             wheel: format!("{name}-1.0.0-py3-none-any.whl"),
             hash: "sha256:abc123".into(),
             dependencies: vec![],
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
         });
     }
 
@@ -50,6 +58,10 @@ fn serialization_is_deterministic() {
             wheel: format!("{name}-1.0.0-py3-none-any.whl"),
             hash: "sha256:abc123".into(),
             dependencies: vec![],
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
         });
     }
 
@@ -69,3 +81,394 @@ fn invalid_magic_is_rejected() {
     let err = Lockfile::from_bytes(&corrupted).expect_err("should reject bad magic");
     assert!(err.to_string().contains("magic"));
 }
+
+#[test]
+fn version_1_lockfile_decodes_without_dynamic_metadata() {
+    #[derive(serde::Serialize)]
+    struct V1 {
+        python_versions: Vec<String>,
+        platforms: Vec<String>,
+        packages: std::collections::BTreeMap<String, V1Package>,
+    }
+    #[derive(serde::Serialize)]
+    struct V1Package {
+        name: String,
+        version: String,
+        source: PackageSource,
+        wheel: String,
+        hash: String,
+        dependencies: Vec<String>,
+    }
+
+    let v1 = V1 {
+        python_versions: vec!["3.11".into()],
+        platforms: vec!["linux-x86_64".into()],
+        packages: [(
+            "a".to_string(),
+            V1Package {
+                name: "a".into(),
+                version: "1.0.0".into(),
+                source: PackageSource::Url {
+                    url: "https://example.invalid/a.whl".into(),
+                },
+                wheel: "a-1.0.0-py3-none-any.whl".into(),
+                hash: "sha256:abc123".into(),
+                dependencies: vec!["b".into()],
+            },
+        )]
+        .into(),
+    };
+    let mut bytes = b"PYBUNLK1".to_vec();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&bincode::serialize(&v1).unwrap());
+
+    let decoded = Lockfile::from_bytes(&bytes).expect("decode v1");
+    let pkg = &decoded.packages["a"];
+    assert_eq!(pkg.dependencies, vec!["b".to_string()]);
+    assert!(!pkg.dynamic_metadata);
+    assert!(decoded.dynamic_metadata_packages().is_empty());
+}
+
+#[test]
+fn source_build_inputs_roundtrip() {
+    let mut lock = Lockfile::new(vec!["3.12".into()], vec!["linux-x86_64".into()]);
+    lock.add_package(Package {
+        name: "legacy".into(),
+        version: "0.3.0".into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: "legacy-0.3.0.tar.gz".into(),
+        hash: "sha256:5d1st".into(),
+        dependencies: vec![],
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: Some(SourceBuild {
+            backend: "setuptools.build_meta".into(),
+            requires: vec!["setuptools>=61".into(), "wheel".into()],
+            python: "cp312".into(),
+            platform: "manylinux_2_17_x86_64".into(),
+            wheel: "legacy-0.3.0-cp312-cp312-linux_x86_64.whl".into(),
+            wheel_hash: "sha256:b1lt".into(),
+        }),
+        artifacts: Vec::new(),
+    });
+
+    let decoded = Lockfile::from_bytes(&lock.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, lock);
+}
+
+#[test]
+fn version_2_lockfile_decodes_without_source_builds() {
+    #[derive(serde::Serialize)]
+    struct V2 {
+        python_versions: Vec<String>,
+        platforms: Vec<String>,
+        packages: std::collections::BTreeMap<String, V2Package>,
+    }
+    #[derive(serde::Serialize)]
+    struct V2Package {
+        name: String,
+        version: String,
+        source: PackageSource,
+        wheel: String,
+        hash: String,
+        dependencies: Vec<String>,
+        dynamic_metadata: bool,
+    }
+
+    let v2 = V2 {
+        python_versions: vec!["3.11".into()],
+        platforms: vec!["linux-x86_64".into()],
+        packages: [(
+            "a".to_string(),
+            V2Package {
+                name: "a".into(),
+                version: "1.0.0".into(),
+                source: PackageSource::Url {
+                    url: "https://example.invalid/a.whl".into(),
+                },
+                wheel: "a-1.0.0-py3-none-any.whl".into(),
+                hash: "sha256:abc123".into(),
+                dependencies: vec![],
+                dynamic_metadata: true,
+            },
+        )]
+        .into(),
+    };
+    let mut bytes = b"PYBUNLK1".to_vec();
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    bytes.extend_from_slice(&bincode::serialize(&v2).unwrap());
+
+    let decoded = Lockfile::from_bytes(&bytes).expect("decode v2");
+    let pkg = &decoded.packages["a"];
+    assert!(pkg.dynamic_metadata);
+    assert_eq!(pkg.build, None);
+}
+
+#[test]
+fn multi_target_artifacts_roundtrip() {
+    let mut lock = Lockfile::new(
+        vec!["3.11".into(), "3.12".into()],
+        vec!["macosx_14_0_arm64".into(), "manylinux_2_28_x86_64".into()],
+    );
+    let wheel = |filename: &str, platform: &str| Artifact {
+        filename: filename.into(),
+        url: Some(format!("https://files.example.invalid/{filename}")),
+        hash: format!("sha256:{platform}"),
+        platforms: vec![platform.into()],
+    };
+    lock.add_package(Package {
+        name: "native".into(),
+        version: "1.0.0".into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: "native-1.0.0-cp311-abi3-macosx_11_0_arm64.whl".into(),
+        hash: "sha256:macosx_11_0_arm64".into(),
+        dependencies: vec![],
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: vec![
+            wheel(
+                "native-1.0.0-cp311-abi3-macosx_11_0_arm64.whl",
+                "macosx_11_0_arm64",
+            ),
+            wheel(
+                "native-1.0.0-cp311-abi3-manylinux_2_17_x86_64.whl",
+                "manylinux_2_17_x86_64",
+            ),
+        ],
+    });
+
+    let decoded = Lockfile::from_bytes(&lock.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, lock);
+    assert!(decoded.packages["native"].artifacts[0].is_wheel());
+}
+
+#[test]
+fn version_3_lockfile_decodes_without_artifacts() {
+    #[derive(serde::Serialize)]
+    struct V3 {
+        python_versions: Vec<String>,
+        platforms: Vec<String>,
+        packages: std::collections::BTreeMap<String, V3Package>,
+    }
+    #[derive(serde::Serialize)]
+    struct V3Package {
+        name: String,
+        version: String,
+        source: PackageSource,
+        wheel: String,
+        hash: String,
+        dependencies: Vec<String>,
+        dynamic_metadata: bool,
+        build: Option<SourceBuild>,
+    }
+
+    let v3 = V3 {
+        python_versions: vec!["3.12".into()],
+        platforms: vec!["manylinux_2_17_x86_64".into()],
+        packages: [(
+            "a".to_string(),
+            V3Package {
+                name: "a".into(),
+                version: "1.0.0".into(),
+                source: PackageSource::Url {
+                    url: "https://example.invalid/a.whl".into(),
+                },
+                wheel: "a-1.0.0-py3-none-any.whl".into(),
+                hash: "sha256:abc123".into(),
+                dependencies: vec![],
+                dynamic_metadata: false,
+                build: None,
+            },
+        )]
+        .into(),
+    };
+    let mut bytes = b"PYBUNLK1".to_vec();
+    bytes.extend_from_slice(&3u32.to_le_bytes());
+    bytes.extend_from_slice(&bincode::serialize(&v3).unwrap());
+
+    let decoded = Lockfile::from_bytes(&bytes).expect("decode v3");
+    let pkg = &decoded.packages["a"];
+    assert_eq!(pkg.wheel, "a-1.0.0-py3-none-any.whl");
+    assert!(pkg.artifacts.is_empty());
+}
+
+#[test]
+fn version_4_lockfile_decodes_without_groups() {
+    #[derive(serde::Serialize)]
+    struct V4 {
+        python_versions: Vec<String>,
+        platforms: Vec<String>,
+        packages: std::collections::BTreeMap<String, V4Package>,
+    }
+    #[derive(serde::Serialize)]
+    struct V4Package {
+        name: String,
+        version: String,
+        source: PackageSource,
+        wheel: String,
+        hash: String,
+        dependencies: Vec<String>,
+        dynamic_metadata: bool,
+        build: Option<SourceBuild>,
+        artifacts: Vec<Artifact>,
+    }
+
+    let v4 = V4 {
+        python_versions: vec!["3.12".into()],
+        platforms: vec!["manylinux_2_17_x86_64".into()],
+        packages: [(
+            "a".to_string(),
+            V4Package {
+                name: "a".into(),
+                version: "1.0.0".into(),
+                source: PackageSource::Url {
+                    url: "https://example.invalid/a.whl".into(),
+                },
+                wheel: "a-1.0.0-py3-none-any.whl".into(),
+                hash: "sha256:abc123".into(),
+                dependencies: vec![],
+                dynamic_metadata: false,
+                build: None,
+                artifacts: Vec::new(),
+            },
+        )]
+        .into(),
+    };
+    let mut bytes = b"PYBUNLK1".to_vec();
+    bytes.extend_from_slice(&4u32.to_le_bytes());
+    bytes.extend_from_slice(&bincode::serialize(&v4).unwrap());
+
+    let decoded = Lockfile::from_bytes(&bytes).expect("decode v4");
+    let pkg = &decoded.packages["a"];
+    assert!(pkg.groups.is_empty());
+    assert!(Lockfile::in_groups(pkg, &[]));
+}
+
+#[test]
+fn version_6_lockfile_decodes_without_container_builds() {
+    #[derive(serde::Serialize)]
+    struct V6 {
+        python_versions: Vec<String>,
+        platforms: Vec<String>,
+        packages: std::collections::BTreeMap<String, Package>,
+    }
+
+    let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+    lock.add_package(Package {
+        name: "a".into(),
+        version: "1.0.0".into(),
+        source: PackageSource::Url {
+            url: "https://example.invalid/a.whl".into(),
+        },
+        wheel: "a-1.0.0-py3-none-any.whl".into(),
+        hash: "sha256:abc123".into(),
+        dependencies: vec![],
+        dynamic_metadata: false,
+        groups: vec![MAIN_GROUP.to_string()],
+        build: None,
+        artifacts: Vec::new(),
+    });
+    let v6 = V6 {
+        python_versions: lock.python_versions.clone(),
+        platforms: lock.platforms.clone(),
+        packages: lock.packages.clone(),
+    };
+    let mut bytes = b"PYBUNLK1".to_vec();
+    bytes.extend_from_slice(&6u32.to_le_bytes());
+    bytes.extend_from_slice(&bincode::serialize(&v6).unwrap());
+
+    let decoded = Lockfile::from_bytes(&bytes).expect("decode v6");
+    assert_eq!(decoded, lock);
+    assert!(decoded.container_builds.is_empty());
+
+    lock.container_builds = vec!["a".into()];
+    let decoded = Lockfile::from_bytes(&lock.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.container_builds, vec!["a"]);
+}
+
+#[test]
+fn groups_follow_transitive_dependencies() {
+    let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+    for (name, deps) in [
+        ("app-core", vec!["Shared-Util>=1"]),
+        ("pytest", vec!["pluggy", "shared_util"]),
+        ("pluggy", vec![]),
+        ("shared-util", vec![]),
+    ] {
+        lock.add_package(Package {
+            name: name.into(),
+            version: "1.0.0".into(),
+            source: PackageSource::Url {
+                url: format!("https://example.invalid/{name}.whl"),
+            },
+            wheel: format!("{name}-1.0.0-py3-none-any.whl"),
+            hash: "sha256:abc123".into(),
+            dependencies: deps.into_iter().map(String::from).collect(),
+            dynamic_metadata: false,
+            groups: Vec::new(),
+            build: None,
+            artifacts: Vec::new(),
+        });
+    }
+
+    lock.assign_groups(&std::collections::BTreeMap::from([
+        (MAIN_GROUP.to_string(), vec!["app-core>=1".to_string()]),
+        ("test".to_string(), vec!["pytest".to_string()]),
+    ]));
+
+    assert_eq!(lock.packages["app-core"].groups, vec!["main"]);
+    assert_eq!(lock.packages["pytest"].groups, vec!["test"]);
+    assert_eq!(lock.packages["pluggy"].groups, vec!["test"]);
+    assert_eq!(lock.packages["shared-util"].groups, vec!["main", "test"]);
+
+    let decoded = Lockfile::from_bytes(&lock.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, lock);
+    assert!(Lockfile::in_groups(&decoded.packages["shared-util"], &[]));
+    assert!(!Lockfile::in_groups(&decoded.packages["pluggy"], &[]));
+    assert!(Lockfile::in_groups(&decoded.packages["pluggy"], &["test"]));
+}
+
+#[test]
+fn subset_keeps_the_closure_of_its_roots() {
+    let mut lock = Lockfile::new(vec!["3.12".into()], vec!["any".into()]);
+    for (name, deps) in [
+        ("api", vec!["Shared-Util>=1"]),
+        ("worker", vec!["celery"]),
+        ("celery", vec!["shared_util"]),
+        ("shared-util", vec![]),
+    ] {
+        lock.add_package(Package {
+            name: name.into(),
+            version: "1.0.0".into(),
+            source: PackageSource::Url {
+                url: format!("https://example.invalid/{name}.whl"),
+            },
+            wheel: format!("{name}-1.0.0-py3-none-any.whl"),
+            hash: "sha256:abc123".into(),
+            dependencies: deps.into_iter().map(String::from).collect(),
+            dynamic_metadata: false,
+            groups: vec![MAIN_GROUP.to_string()],
+            build: None,
+            artifacts: Vec::new(),
+        });
+    }
+
+    let subset = lock.subset(&std::collections::BTreeMap::from([(
+        MAIN_GROUP.to_string(),
+        vec!["api".to_string()],
+    )]));
+
+    assert_eq!(
+        subset.packages.keys().collect::<Vec<_>>(),
+        ["api", "shared-util"]
+    );
+    assert_eq!(subset.packages["api"], lock.packages["api"]);
+    assert_eq!(subset.python_versions, lock.python_versions);
+}
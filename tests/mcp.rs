@@ -690,13 +690,27 @@ fn mcp_tools_call_resolve_no_index() {
 // Issue #284: pybun_install must not report false "installed" success
 // =============================================================================
 
-/// Minimal wheel payload (a valid zip) shared by the pybun_install honesty tests.
+/// Minimal wheel payload (a module plus its `.dist-info`) shared by the
+/// pybun_install honesty tests.
 fn issue284_wheel_bytes() -> Vec<u8> {
     let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
     let options = zip::write::SimpleFileOptions::default();
-    zip.start_file("dummy.txt", options)
-        .expect("start wheel entry");
-    zip.write_all(b"ok").expect("write wheel entry");
+    for (name, contents) in [
+        ("app.py", "VALUE = 'ok'\n"),
+        (
+            "app-1.0.0.dist-info/METADATA",
+            "Metadata-Version: 2.1\nName: app\nVersion: 1.0.0\n",
+        ),
+        (
+            "app-1.0.0.dist-info/WHEEL",
+            "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+        ),
+        ("app-1.0.0.dist-info/RECORD", ""),
+    ] {
+        zip.start_file(name, options).expect("start wheel entry");
+        zip.write_all(contents.as_bytes())
+            .expect("write wheel entry");
+    }
     let cursor = zip.finish().expect("finish wheel zip");
     cursor.into_inner()
 }
@@ -2454,3 +2468,540 @@ fn mcp_tools_call_resolve_filters_by_requires_python() {
         stdout
     );
 }
+
+/// Start `pybun mcp serve` in HTTP mode on a free port. Returns the child,
+/// the base URL, and a handle yielding the rest of its stderr.
+fn spawn_http_server(
+    home: &Path,
+    extra: &[&str],
+) -> (std::process::Child, String, std::thread::JoinHandle<String>) {
+    use std::io::BufRead;
+
+    let mut child = pybun_bin()
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_MCP_TOKEN")
+        .args(["mcp", "serve", "--port", "0"])
+        .args(extra)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start MCP server");
+    let mut lines = std::io::BufReader::new(child.stderr.take().unwrap()).lines();
+    let banner = lines
+        .by_ref()
+        .map_while(Result::ok)
+        .find(|line| line.contains("listening on"))
+        .expect("server should print its address");
+    let addr = banner
+        .split("http://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap()
+        .to_string();
+    let rest =
+        std::thread::spawn(move || lines.map_while(Result::ok).collect::<Vec<_>>().join("\n"));
+    (child, format!("http://{addr}"), rest)
+}
+
+const INITIALIZE: &str = r#"{"jsonrpc":"2.0","method":"initialize","id":1,"params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"test","version":"0.1.0"}}}"#;
+
+#[test]
+fn mcp_http_streamable_requires_token_and_keeps_sessions() {
+    let temp = tempdir().unwrap();
+    let (mut child, base, _) = spawn_http_server(temp.path(), &["--token", "s3cret"]);
+    let client = reqwest::blocking::Client::new();
+    let url = format!("{base}/mcp");
+
+    let denied = client.post(&url).body(INITIALIZE).send().unwrap();
+    assert_eq!(denied.status(), 401);
+    assert_eq!(denied.headers()["www-authenticate"], "Bearer");
+
+    let initialized = client
+        .post(&url)
+        .bearer_auth("s3cret")
+        .header("Accept", "application/json, text/event-stream")
+        .body(INITIALIZE)
+        .send()
+        .unwrap();
+    assert_eq!(initialized.status(), 200);
+    let session = initialized.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body: serde_json::Value = initialized.json().unwrap();
+    assert_eq!(body["id"], 1);
+    assert_eq!(body["result"]["serverInfo"]["name"], "pybun-mcp");
+
+    let no_session = client
+        .post(&url)
+        .bearer_auth("s3cret")
+        .body(r#"{"jsonrpc":"2.0","method":"tools/list","id":2}"#)
+        .send()
+        .unwrap();
+    assert_eq!(no_session.status(), 400);
+
+    let batch = client
+        .post(&url)
+        .bearer_auth("s3cret")
+        .header("Mcp-Session-Id", &session)
+        .body(
+            r#"[{"jsonrpc":"2.0","method":"notifications/initialized"},{"jsonrpc":"2.0","method":"tools/list","id":2}]"#,
+        )
+        .send()
+        .unwrap();
+    assert_eq!(batch.status(), 200);
+    let body: serde_json::Value = batch.json().unwrap();
+    let responses = body.as_array().unwrap();
+    assert_eq!(responses.len(), 1, "notifications get no reply: {body}");
+    assert!(
+        responses[0]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|tool| tool["name"] == "pybun_install")
+    );
+
+    let notification = client
+        .post(&url)
+        .bearer_auth("s3cret")
+        .header("Mcp-Session-Id", &session)
+        .body(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+        .send()
+        .unwrap();
+    assert_eq!(notification.status(), 202);
+
+    let closed = client
+        .delete(&url)
+        .bearer_auth("s3cret")
+        .header("Mcp-Session-Id", &session)
+        .send()
+        .unwrap();
+    assert_eq!(closed.status(), 204);
+    let gone = client
+        .post(&url)
+        .bearer_auth("s3cret")
+        .header("Mcp-Session-Id", &session)
+        .body(r#"{"jsonrpc":"2.0","method":"tools/list","id":3}"#)
+        .send()
+        .unwrap();
+    assert_eq!(gone.status(), 404);
+
+    child.kill().ok();
+    child.wait().ok();
+}
+
+#[test]
+fn mcp_http_sse_answers_on_the_event_stream() {
+    use std::io::BufRead;
+
+    let temp = tempdir().unwrap();
+    let (mut child, base, _) = spawn_http_server(temp.path(), &[]);
+    let client = reqwest::blocking::Client::new();
+
+    let stream = client.get(format!("{base}/sse")).send().unwrap();
+    assert_eq!(stream.status(), 200);
+    assert_eq!(stream.headers()["content-type"], "text/event-stream");
+    let mut events = std::io::BufReader::new(stream)
+        .lines()
+        .map_while(Result::ok);
+    assert_eq!(events.next().unwrap(), "event: endpoint");
+    let endpoint = events
+        .next()
+        .unwrap()
+        .strip_prefix("data: ")
+        .unwrap()
+        .to_string();
+    assert!(endpoint.starts_with("/messages?sessionId="), "{endpoint}");
+
+    let accepted = client
+        .post(format!("{base}{endpoint}"))
+        .body(INITIALIZE)
+        .send()
+        .unwrap();
+    assert_eq!(accepted.status(), 202);
+
+    let data = events
+        .skip_while(|line| line != "event: message")
+        .nth(1)
+        .unwrap();
+    let message: serde_json::Value =
+        serde_json::from_str(data.strip_prefix("data: ").unwrap()).unwrap();
+    assert_eq!(message["id"], 1);
+    assert_eq!(message["result"]["serverInfo"]["name"], "pybun-mcp");
+
+    let foreign = client
+        .post(format!("{base}/mcp"))
+        .header("Origin", "https://evil.example")
+        .body(INITIALIZE)
+        .send()
+        .unwrap();
+    assert_eq!(foreign.status(), 403);
+
+    child.kill().ok();
+    child.wait().ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn mcp_http_stops_gracefully_on_interrupt() {
+    let temp = tempdir().unwrap();
+    let (mut child, base, stderr) = spawn_http_server(temp.path(), &[]);
+    // An open event stream must not keep the server alive.
+    let stream = reqwest::blocking::get(format!("{base}/sse")).unwrap();
+    assert_eq!(stream.status(), 200);
+
+    unsafe {
+        libc::kill(child.id() as i32, libc::SIGINT);
+    }
+    let status = child.wait().unwrap();
+    assert!(status.success(), "server should exit cleanly: {status:?}");
+    let stderr = stderr.join().unwrap();
+    assert!(stderr.contains("PyBun MCP server stopped."), "{stderr}");
+    drop(stream);
+}
+
+#[test]
+fn mcp_http_requires_token_on_non_loopback_address() {
+    let temp = tempdir().unwrap();
+    let output = pybun_bin()
+        .env("PYBUN_HOME", temp.path())
+        .env_remove("PYBUN_MCP_TOKEN")
+        .args([
+            "--format", "json", "mcp", "serve", "--host", "0.0.0.0", "--port", "0",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("E_MCP_TOKEN_REQUIRED"), "{stdout}");
+}
+
+#[test]
+fn mcp_tools_call_remove_returns_cli_envelope_and_audits_writes() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"requests>=2.31\", \"rich\"]\n",
+    )
+    .unwrap();
+    let audit_log = project.path().join("audit.jsonl");
+
+    let stdout = mcp_call_in(
+        &[
+            r#"{"jsonrpc":"2.0","method":"tools/call","id":2,"params":{"name":"pybun_remove","arguments":{"packages":["requests"]}}}"#,
+        ],
+        project.path(),
+        &[("PYBUN_AUDIT_LOG", audit_log.clone().into_os_string())],
+    );
+
+    let envelope = tool_result_json(&stdout, 2);
+    assert_eq!(envelope["version"], "1");
+    assert_eq!(envelope["command"], "pybun remove");
+    assert_eq!(envelope["status"], "ok");
+    assert_eq!(envelope["detail"]["packages"][0]["name"], "requests");
+    let pyproject = fs::read_to_string(project.path().join("pyproject.toml")).unwrap();
+    assert!(!pyproject.contains("requests"), "{pyproject}");
+    assert!(pyproject.contains("rich"), "{pyproject}");
+
+    let responses = json_rpc_lines(&stdout);
+    let removed = responses.iter().find(|r| r["id"] == 2).unwrap();
+    assert_eq!(removed["result"]["structuredContent"], envelope);
+    assert!(removed["result"].get("isError").is_none());
+
+    // Without a pyproject.toml the command fails: a tool error that still
+    // carries the envelope and its diagnostics.
+    let stdout = mcp_call(&[
+        r#"{"jsonrpc":"2.0","method":"tools/call","id":3,"params":{"name":"pybun_remove","arguments":{"packages":["flask"]}}}"#,
+    ]);
+    let responses = json_rpc_lines(&stdout);
+    let failed = responses.iter().find(|r| r["id"] == 3).unwrap();
+    assert_eq!(failed["result"]["isError"], true, "{failed}");
+    assert_eq!(failed["result"]["structuredContent"]["status"], "error");
+    assert_eq!(
+        failed["result"]["structuredContent"]["diagnostics"][0]["code"],
+        "E_REMOVE_FAILED"
+    );
+
+    let entries = audit_log_entries(&audit_log);
+    let writes = &entries[0]["file_writes"];
+    assert!(
+        writes
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|w| w["path"].as_str().unwrap().ends_with("pyproject.toml")),
+        "{entries:?}"
+    );
+}
+
+#[test]
+fn mcp_tools_call_module_find_and_python_list() {
+    let project = tempdir().unwrap();
+    let src = project.path().join("src");
+    fs::create_dir_all(src.join("pkg")).unwrap();
+    fs::write(src.join("pkg/__init__.py"), "").unwrap();
+    fs::write(src.join("helper.py"), "").unwrap();
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","method":"tools/call","id":2,"params":{{"name":"pybun_module_find","arguments":{{"module":"helper","paths":["{}"]}}}}}}"#,
+        src.display()
+    );
+
+    let stdout = mcp_call_in(
+        &[
+            &request,
+            r#"{"jsonrpc":"2.0","method":"tools/call","id":3,"params":{"name":"pybun_python_list","arguments":{}}}"#,
+            r#"{"jsonrpc":"2.0","method":"tools/call","id":4,"params":{"name":"pybun_python_install","arguments":{}}}"#,
+        ],
+        project.path(),
+        &[],
+    );
+
+    let found = tool_result_json(&stdout, 2);
+    assert_eq!(found["command"], "pybun module-find");
+    assert_eq!(found["status"], "ok", "{found}");
+    assert!(found["detail"].to_string().contains("helper.py"), "{found}");
+
+    let listed = tool_result_json(&stdout, 3);
+    assert_eq!(listed["command"], "pybun python list");
+    assert_eq!(listed["status"], "ok", "{listed}");
+
+    let responses = json_rpc_lines(&stdout);
+    let invalid = responses.iter().find(|r| r["id"] == 4).unwrap();
+    assert_eq!(invalid["result"]["isError"], true);
+    assert!(
+        invalid["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Missing required argument: version")
+    );
+}
+
+fn resource_json(stdout: &str, id: i64) -> serde_json::Value {
+    let responses = json_rpc_lines(stdout);
+    let response = responses
+        .iter()
+        .find(|value| value["id"].as_i64() == Some(id))
+        .unwrap_or_else(|| {
+            panic!("resources/read response with id {id} should be present: {stdout}")
+        });
+    let text = response["result"]["contents"][0]["text"]
+        .as_str()
+        .unwrap_or_else(|| panic!("resources/read response should contain text: {response}"));
+    serde_json::from_str(text).unwrap()
+}
+
+#[test]
+fn mcp_project_resources_read_and_notify_on_change() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"requests>=2.31\", \"rich\"]\n\n[dependency-groups]\ndev = [\"pytest\"]\n",
+    )
+    .unwrap();
+
+    let stdout = mcp_call_in(
+        &[
+            r#"{"jsonrpc":"2.0","method":"resources/list","id":2}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/read","id":3,"params":{"uri":"pybun://project/pyproject"}}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/read","id":4,"params":{"uri":"pybun://project/lockfile"}}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/read","id":5,"params":{"uri":"pybun://env/snapshot"}}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/subscribe","id":6,"params":{"uri":"pybun://project/pyproject"}}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/subscribe","id":7,"params":{"uri":"pybun://diagnostics/latest"}}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/subscribe","id":8,"params":{"uri":"pybun://cache/info"}}"#,
+            r#"{"jsonrpc":"2.0","method":"tools/call","id":9,"params":{"name":"pybun_remove","arguments":{"packages":["requests"]}}}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/read","id":10,"params":{"uri":"pybun://diagnostics/latest"}}"#,
+            r#"{"jsonrpc":"2.0","method":"resources/read","id":11,"params":{"uri":"pybun://project/pyproject"}}"#,
+        ],
+        project.path(),
+        &[],
+    );
+    let responses = json_rpc_lines(&stdout);
+
+    let initialize = responses.iter().find(|r| r["id"] == 1).unwrap();
+    assert_eq!(
+        initialize["result"]["capabilities"]["resources"]["subscribe"],
+        true
+    );
+    let listed = responses.iter().find(|r| r["id"] == 2).unwrap();
+    let uris: Vec<&str> = listed["result"]["resources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|r| r["uri"].as_str())
+        .collect();
+    for uri in [
+        "pybun://project/pyproject",
+        "pybun://project/lockfile",
+        "pybun://env/snapshot",
+        "pybun://diagnostics/latest",
+    ] {
+        assert!(uris.contains(&uri), "{uri} missing from {uris:?}");
+    }
+
+    let pyproject = resource_json(&stdout, 3);
+    assert_eq!(pyproject["exists"], true);
+    assert_eq!(pyproject["content"]["project"]["name"], "demo");
+    assert_eq!(pyproject["dependencies"][0], "requests>=2.31");
+    assert_eq!(pyproject["dependency_groups"]["dev"][0], "pytest");
+    assert_eq!(resource_json(&stdout, 4)["exists"], false);
+    assert!(resource_json(&stdout, 5)["packages"].is_array());
+
+    let unsupported = responses.iter().find(|r| r["id"] == 8).unwrap();
+    assert_eq!(unsupported["error"]["code"], -32602);
+
+    // The tool call rewrites pyproject.toml and records new diagnostics; both
+    // subscriptions fire once, right after the call's response.
+    let updated: Vec<&str> = responses
+        .iter()
+        .filter(|r| r["method"] == "notifications/resources/updated")
+        .filter_map(|r| r["params"]["uri"].as_str())
+        .collect();
+    assert_eq!(
+        updated,
+        ["pybun://diagnostics/latest", "pybun://project/pyproject"]
+    );
+    let position = |predicate: &dyn Fn(&serde_json::Value) -> bool| {
+        responses.iter().position(predicate).unwrap()
+    };
+    assert!(
+        position(&|r| r["id"] == 9)
+            < position(&|r| r["method"] == "notifications/resources/updated")
+    );
+
+    let diagnostics = resource_json(&stdout, 10);
+    assert_eq!(diagnostics["tool"], "pybun_remove");
+    assert_eq!(diagnostics["status"], "ok");
+    assert!(diagnostics["diagnostics"].is_array());
+    let pyproject = resource_json(&stdout, 11);
+    assert_eq!(pyproject["dependencies"], serde_json::json!(["rich"]));
+}
+
+#[cfg(unix)]
+#[test]
+fn mcp_pybun_test_streams_progress_per_test() {
+    let project = tempdir().unwrap();
+    fs::write(
+        project.path().join("test_progress.py"),
+        "def test_one():\n    assert True\n\ndef test_two():\n    assert 1 == 2\n",
+    )
+    .unwrap();
+    let venv = make_pytest_venv(project.path());
+
+    let stdout = mcp_call_in(
+        &[
+            r#"{"jsonrpc":"2.0","method":"tools/call","id":2,"params":{"name":"pybun_test","arguments":{},"_meta":{"progressToken":"run-1"}}}"#,
+        ],
+        project.path(),
+        &[("PYBUN_ENV", venv.into_os_string())],
+    );
+    let messages = json_rpc_lines(&stdout);
+    let progress: Vec<&serde_json::Value> = messages
+        .iter()
+        .filter(|m| m["method"] == "notifications/progress")
+        .map(|m| &m["params"])
+        .collect();
+    assert_eq!(progress.len(), 2, "{stdout}");
+    assert!(progress.iter().all(|p| p["progressToken"] == "run-1"));
+    assert_eq!(progress[0]["progress"], 1.0);
+    assert_eq!(progress[1]["progress"], 2.0);
+    assert_eq!(progress[1]["total"], 2.0);
+    let messages_text: Vec<&str> = progress
+        .iter()
+        .filter_map(|p| p["message"].as_str())
+        .collect();
+    assert!(
+        messages_text
+            .iter()
+            .any(|m| m.starts_with("passed ") && m.contains("test_one")),
+        "{messages_text:?}"
+    );
+    assert!(
+        messages_text
+            .iter()
+            .any(|m| m.starts_with("failed ") && m.contains("test_two")),
+        "{messages_text:?}"
+    );
+
+    let response = messages.iter().position(|m| m["id"] == 2).unwrap();
+    let last_progress = messages
+        .iter()
+        .rposition(|m| m["method"] == "notifications/progress")
+        .unwrap();
+    assert!(last_progress < response, "progress must precede the result");
+}
+
+#[cfg(unix)]
+#[test]
+fn mcp_cancelled_test_run_stops_and_sends_no_response() {
+    let project = tempdir().unwrap();
+    let home = tempdir().unwrap();
+    // Sequential run (two tests): the cancellation is sent once the slow
+    // first test has started, so the second one never starts.
+    fs::write(
+        project.path().join("test_slow.py"),
+        "import time\n\ndef test_a_slow():\n    open('started_a', 'w').close()\n    time.sleep(1)\n\ndef test_b_never():\n    open('ran_b', 'w').close()\n",
+    )
+    .unwrap();
+    let venv = make_pytest_venv(project.path());
+
+    let mut child = pybun_bin()
+        .env("PYBUN_HOME", home.path())
+        .env("PYBUN_ENV", &venv)
+        .current_dir(project.path())
+        .args(["mcp", "serve", "--stdio"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to start MCP server");
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(
+        stdin,
+        r#"{{"jsonrpc":"2.0","method":"initialize","id":1,"params":{{"protocolVersion":"2024-11-05","capabilities":{{}},"clientInfo":{{"name":"test","version":"0.1.0"}}}}}}"#
+    )
+    .unwrap();
+    writeln!(
+        stdin,
+        r#"{{"jsonrpc":"2.0","method":"tools/call","id":2,"params":{{"name":"pybun_test","arguments":{{}},"_meta":{{"progressToken":7}}}}}}"#
+    )
+    .unwrap();
+    stdin.flush().unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+    while !project.path().join("started_a").exists() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "first test never started"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    writeln!(
+        stdin,
+        r#"{{"jsonrpc":"2.0","method":"notifications/cancelled","params":{{"requestId":2,"reason":"user"}}}}"#
+    )
+    .unwrap();
+    writeln!(stdin, r#"{{"jsonrpc":"2.0","method":"tools/list","id":3}}"#).unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let messages = json_rpc_lines(&stdout);
+    assert!(
+        messages.iter().all(|m| m["id"] != 2),
+        "a cancelled call gets no response"
+    );
+    assert!(messages.iter().any(|m| m["id"] == 3));
+    let progress: Vec<&serde_json::Value> = messages
+        .iter()
+        .filter(|m| m["method"] == "notifications/progress")
+        .map(|m| &m["params"])
+        .collect();
+    assert_eq!(progress.len(), 1, "{progress:?}");
+    assert_eq!(progress[0]["progressToken"], 7);
+    assert!(
+        progress[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("test_a_slow")
+    );
+    assert!(!project.path().join("ran_b").exists());
+}
@@ -0,0 +1,186 @@
+//! E2E tests for the native installer behind `pybun x` and PEP 723
+//! `pybun run`: wheels from a mock PyPI are installed into the environment
+//! without pip, with their console scripts, and reported per package.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// `hellotool` 1.0.0: a module and a `hellotool` console script.
+fn wheel_bytes() -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, content) in [
+        (
+            "hellotool/__init__.py",
+            "def main():\n    print('hello from hellotool')\n    return 0\n",
+        ),
+        (
+            "hellotool-1.0.0.dist-info/METADATA",
+            "Metadata-Version: 2.1\nName: hellotool\nVersion: 1.0.0\n",
+        ),
+        ("hellotool-1.0.0.dist-info/WHEEL", "Wheel-Version: 1.0\n"),
+        (
+            "hellotool-1.0.0.dist-info/entry_points.txt",
+            "[console_scripts]\nhellotool = hellotool:main\n",
+        ),
+        ("hellotool-1.0.0.dist-info/RECORD", ""),
+    ] {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn mock_pypi(server: &MockServer) {
+    let wheel = wheel_bytes();
+    let filename = "hellotool-1.0.0-py3-none-any.whl";
+    let project = json!({
+        "info": { "name": "hellotool", "version": "1.0.0" },
+        "releases": {
+            "1.0.0": [{
+                "filename": filename,
+                "packagetype": "bdist_wheel",
+                "url": format!("{}/files/{filename}", server.base_url()),
+                "yanked": false,
+                "digests": { "sha256": hex::encode(Sha256::digest(&wheel)) }
+            }]
+        }
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/hellotool/json");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(project.clone());
+    });
+    let release = json!({
+        "info": { "name": "hellotool", "version": "1.0.0", "requires_dist": [] }
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/hellotool/1.0.0/json");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(release.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/files/{filename}"));
+        then.status(200)
+            .header("Content-Type", "application/octet-stream")
+            .body(wheel.clone());
+    });
+}
+
+fn pybun(temp: &TempDir, server: &MockServer) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PYPI_BASE_URL", server.base_url())
+        .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("pypi-cache"))
+        .env_remove("PYBUN_INDEX_URL")
+        .env_remove("PYBUN_INSTALLER")
+        .env_remove("PYBUN_X_DRY_RUN")
+        .env_remove("PYBUN_PEP723_DRY_RUN")
+        .env("PYBUN_PEP723_BACKEND", "pybun");
+    cmd
+}
+
+fn json_output(output: &std::process::Output) -> Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .rev()
+        .find(|line| line.starts_with('{'))
+        .unwrap_or_else(|| panic!("no JSON in stdout: {stdout}"));
+    serde_json::from_str(line).unwrap()
+}
+
+fn has_pip(venv: &Path) -> bool {
+    fs::read_dir(venv.join("lib"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|entry| entry.path().join("site-packages/pip").exists())
+}
+
+#[test]
+fn x_installs_the_tool_natively_and_reports_timing() {
+    let temp = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_pypi(&server);
+
+    let output = pybun(&temp, &server)
+        .args(["--format=json", "x", "hellotool"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout.contains("hello from hellotool"), "{stdout}");
+
+    let json = json_output(&output);
+    let install = &json["detail"]["install"];
+    assert_eq!(install["installer"], "native", "{json}");
+    assert_eq!(install["packages"][0]["name"], "hellotool");
+    assert_eq!(install["packages"][0]["version"], "1.0.0");
+    assert_eq!(install["packages"][0]["cached"], false);
+    assert!(install["packages"][0]["install_ms"].is_u64());
+    assert!(install["fallback"].as_array().unwrap().is_empty());
+
+    // Rebuilding the tool environment installs the wheel from the cache.
+    let output = pybun(&temp, &server)
+        .args(["--format=json", "x", "--upgrade", "hellotool"])
+        .output()
+        .unwrap();
+    let json = json_output(&output);
+    assert_eq!(json["detail"]["install"]["packages"][0]["cached"], true);
+}
+
+#[test]
+fn run_installs_pep723_dependencies_without_pip() {
+    let temp = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_pypi(&server);
+    let script = temp.path().join("hello.py");
+    fs::write(
+        &script,
+        "# /// script\n# dependencies = [\"hellotool\"]\n# ///\nimport hellotool\nhellotool.main()\n",
+    )
+    .unwrap();
+
+    let output = pybun(&temp, &server)
+        .args(["--format=json", "run", script.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let json = json_output(&output);
+    let detail = &json["detail"];
+    assert_eq!(detail["exit_code"], 0, "{json}");
+    assert!(
+        detail["stdout"]
+            .as_str()
+            .unwrap()
+            .contains("hello from hellotool")
+    );
+    assert_eq!(detail["install"]["installer"], "native");
+    assert_eq!(detail["install"]["packages"][0]["name"], "hellotool");
+
+    let venv = Path::new(detail["temp_env"].as_str().unwrap());
+    assert!(!has_pip(venv), "native installs need no pip in the venv");
+    let dist_info = fs::read_dir(venv.join("lib"))
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.path().join("site-packages/hellotool-1.0.0.dist-info"))
+        .find(|path| path.is_dir())
+        .expect("hellotool dist-info");
+    assert_eq!(
+        fs::read_to_string(dist_info.join("INSTALLER")).unwrap(),
+        "pybun\n"
+    );
+    assert!(venv.join("bin/hellotool").is_file());
+}
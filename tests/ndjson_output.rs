@@ -0,0 +1,79 @@
+//! E2E tests for `--format ndjson`: one JSON record per line, streamed as
+//! events and diagnostics happen, ending with the result.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn records(stdout: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .map(|line| {
+            serde_json::from_str(line).unwrap_or_else(|_| panic!("not a JSON line: {line}"))
+        })
+        .collect()
+}
+
+#[test]
+fn install_streams_events_then_the_result() {
+    let temp = tempdir().unwrap();
+    let index = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/index.json");
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .args(["--format=ndjson", "--progress=always", "install", "--index"])
+        .arg(&index)
+        .args(["--require", "app==1.0.0", "--lock"])
+        .arg(temp.path().join("pybun.lockb"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "no progress UI in ndjson mode");
+
+    let records = records(&output.stdout);
+    let types: Vec<&str> = records
+        .iter()
+        .filter(|r| r["kind"] == "event")
+        .map(|r| r["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types.first(), Some(&"command_start"));
+    assert!(types.contains(&"resolve_start") && types.contains(&"install_start"));
+    assert_eq!(types.last(), Some(&"command_end"));
+
+    let result = records.last().unwrap();
+    assert_eq!(result["kind"], "result");
+    assert_eq!(result["command"], "pybun install");
+    assert_eq!(result["status"], "ok");
+    assert!(result["detail"]["packages"].is_array());
+    assert!(result.get("events").is_none());
+    assert_eq!(records.iter().filter(|r| r["kind"] == "result").count(), 1);
+}
+
+#[test]
+fn failures_stream_their_diagnostic_before_the_result() {
+    let temp = tempdir().unwrap();
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .args(["--format=ndjson", "run", "missing.py"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let records = records(&output.stdout);
+    let diagnostic = records
+        .iter()
+        .position(|r| r["kind"] == "diagnostic" && r["code"] == "E_RUN_TARGET_NOT_FOUND")
+        .expect("E_RUN_TARGET_NOT_FOUND diagnostic");
+    assert_eq!(
+        records
+            .iter()
+            .filter(|r| r["kind"] == "diagnostic" && r["code"] == "E_RUN_TARGET_NOT_FOUND")
+            .count(),
+        1,
+        "streamed diagnostics are not repeated"
+    );
+    let result = records.len() - 1;
+    assert!(diagnostic < result);
+    assert_eq!(records[result]["status"], "error");
+}
@@ -0,0 +1,97 @@
+//! `[tool.pybun.network]` restricts the hosts PyBun and the Python processes
+//! it starts may contact; refused hosts surface as `E_NETWORK_POLICY`.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const PYPROJECT: &str = r#"[project]
+name = "demo"
+version = "0.1.0"
+dependencies = ["app==1.0.0"]
+
+[tool.pybun.network]
+index = ["mirror.example"]
+"#;
+
+const CONNECT_SCRIPT: &str = r#"
+import socket
+try:
+    socket.getaddrinfo("example.com", 80)
+    print("connected")
+except PermissionError:
+    print("refused")
+"#;
+
+fn pybun(dir: &Path, args: &[&str]) -> Value {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .env("PYBUN_PYPI_BASE_URL", "http://pypi.blocked.test")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn policy_diagnostic(json: &Value) -> &Value {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_NETWORK_POLICY")
+        .unwrap_or_else(|| panic!("no E_NETWORK_POLICY diagnostic: {json}"))
+}
+
+#[test]
+fn index_requests_outside_the_allowlist_are_refused() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PYPROJECT).unwrap();
+
+    let json = pybun(temp.path(), &["install"]);
+    assert_eq!(json["status"], "error");
+    let context = &policy_diagnostic(&json)["context"];
+    assert_eq!(context["operation"], "index");
+    assert_eq!(context["host"], "pypi.blocked.test");
+    assert_eq!(context["allowed"][0], "mirror.example");
+}
+
+#[test]
+fn run_denies_network_by_default_under_a_policy() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PYPROJECT).unwrap();
+    fs::write(temp.path().join("connect.py"), CONNECT_SCRIPT).unwrap();
+
+    let json = pybun(temp.path(), &["run", "connect.py"]);
+    assert_eq!(json["detail"]["stdout"], "refused\n");
+    let context = &policy_diagnostic(&json)["context"];
+    assert_eq!(context["operation"], "run");
+    assert_eq!(context["host"], "example.com");
+}
+
+#[test]
+fn sandbox_network_opt_in_is_limited_to_the_run_allowlist() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PYPROJECT).unwrap();
+    fs::write(temp.path().join("connect.py"), CONNECT_SCRIPT).unwrap();
+
+    let json = pybun(
+        temp.path(),
+        &["run", "--sandbox", "--allow-network", "connect.py"],
+    );
+    assert_eq!(json["detail"]["stdout"], "refused\n");
+    assert_eq!(
+        json["detail"]["sandbox"]["audit"]["blocked_hosts"][0],
+        "example.com"
+    );
+    assert_eq!(policy_diagnostic(&json)["context"]["operation"], "run");
+}
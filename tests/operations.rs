@@ -0,0 +1,132 @@
+//! `--max-duration` checkpoints and `pybun status --operation`.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+fn bin(home: &Path) -> Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.env("PYBUN_HOME", home);
+    cmd
+}
+
+fn json_output(cmd: &mut Command) -> (Option<i32>, Value) {
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json = serde_json::from_str(stdout.trim())
+        .unwrap_or_else(|e| panic!("invalid JSON ({e}): {stdout}"));
+    (output.status.code(), json)
+}
+
+fn status(home: &Path, id: &str) -> Value {
+    json_output(bin(home).args(["--format=json", "status", "--operation", id])).1
+}
+
+#[test]
+fn command_within_budget_passes_through() {
+    let temp = tempdir().unwrap();
+    let (code, json) = json_output(bin(temp.path()).args([
+        "--format=json",
+        "--max-duration=30s",
+        "run",
+        "-c",
+        "print('quick')",
+    ]));
+    assert_eq!(code, Some(0));
+    assert_eq!(json["command"], "pybun run");
+    assert_eq!(json["detail"]["exit_code"], 0);
+    assert!(json["detail"].get("operation").is_none(), "{json}");
+}
+
+#[test]
+fn expired_command_is_cancelled_with_checkpoint() {
+    let temp = tempdir().unwrap();
+    let (code, json) = json_output(bin(temp.path()).args([
+        "--format=json",
+        "--max-duration=500ms",
+        "run",
+        "-c",
+        "import time; time.sleep(30)",
+    ]));
+    assert_eq!(code, Some(1));
+    assert_eq!(json["status"], "error");
+    assert_eq!(json["diagnostics"][0]["code"], "E_MAX_DURATION_EXCEEDED");
+    let operation = &json["detail"]["operation"];
+    assert_eq!(operation["state"], "cancelled");
+    assert_eq!(operation["detached"], false);
+    assert!(operation["elapsed_ms"].as_u64().unwrap() >= 500);
+
+    let id = operation["id"].as_str().unwrap();
+    let status = status(temp.path(), id);
+    assert_eq!(status["status"], "ok");
+    assert_eq!(status["detail"]["operation"]["state"], "cancelled");
+}
+
+#[test]
+fn detached_command_finishes_in_background() {
+    let temp = tempdir().unwrap();
+    let (code, json) = json_output(bin(temp.path()).args([
+        "--format=json",
+        "--max-duration=200ms",
+        "--on-timeout=detach",
+        "run",
+        "-c",
+        "import time; time.sleep(1); print('finished')",
+    ]));
+    assert_eq!(code, Some(0));
+    assert_eq!(json["diagnostics"][0]["code"], "W_MAX_DURATION_DETACHED");
+    let operation = &json["detail"]["operation"];
+    assert_eq!(operation["state"], "running");
+    assert_eq!(operation["detached"], true);
+    let id = operation["id"].as_str().unwrap().to_string();
+    assert_eq!(
+        json["detail"]["status_command"],
+        format!("pybun status --operation {id}")
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let finished = loop {
+        let status = status(temp.path(), &id);
+        if status["detail"]["operation"]["state"] != "running" {
+            break status;
+        }
+        assert!(Instant::now() < deadline, "operation never finished");
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    let detail = &finished["detail"];
+    assert_eq!(detail["operation"]["state"], "completed", "{finished}");
+    assert_eq!(detail["operation"]["exit_code"], 0);
+    assert_eq!(detail["operation"]["percent"], 100);
+    assert_eq!(detail["result"]["command"], "pybun run");
+    assert!(
+        detail["result"]["detail"]["stdout"]
+            .as_str()
+            .unwrap()
+            .contains("finished")
+    );
+}
+
+#[test]
+fn unknown_operation_is_an_error() {
+    let temp = tempdir().unwrap();
+    let (code, json) = json_output(bin(temp.path()).args([
+        "--format=json",
+        "status",
+        "--operation",
+        "does-not-exist",
+    ]));
+    assert_eq!(code, Some(1));
+    assert_eq!(json["diagnostics"][0]["code"], "E_OPERATION_NOT_FOUND");
+}
+
+#[test]
+fn on_timeout_requires_max_duration() {
+    let temp = tempdir().unwrap();
+    bin(temp.path())
+        .args(["--on-timeout=detach", "run", "-c", "pass"])
+        .assert()
+        .failure();
+}
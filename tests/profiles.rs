@@ -169,3 +169,206 @@ fn test_profile_benchmark_config_values() {
         .stdout(predicate::str::contains("\"tracing\":true"))
         .stdout(predicate::str::contains("\"timing\":true"));
 }
+
+fn profile_imports_json(dir: &std::path::Path, args: &[&str]) -> (bool, serde_json::Value) {
+    let output = pybun()
+        .current_dir(dir)
+        .args(["--format=json", "profile", "imports"])
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    (output.status.success(), json)
+}
+
+#[test]
+fn test_profile_imports_ranks_imports_and_suggests_lazy_candidates() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("heavy")).unwrap();
+    std::fs::write(
+        temp.path().join("heavy/__init__.py"),
+        "import time\ntime.sleep(0.05)\n",
+    )
+    .unwrap();
+    std::fs::write(
+        temp.path().join("main.py"),
+        "import sys\nimport heavy\nimport logging\nsys.exit(int(sys.argv[1]))\n",
+    )
+    .unwrap();
+
+    let (ok, json) = profile_imports_json(temp.path(), &["main.py", "--", "0"]);
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["exit_code"], 0);
+    assert_eq!(detail["top"][0]["module"], "heavy");
+    assert!(detail["top"][0]["self_ms"].as_f64().unwrap() >= 50.0);
+    assert_eq!(detail["lazy_candidates"]["allow"][0]["module"], "heavy");
+    assert_eq!(detail["lazy_candidates"]["allow"][0]["line"], 2);
+    assert!(
+        detail["lazy_candidates"]["allow"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|c| c["module"] != "logging")
+    );
+    assert!(
+        detail["lazy_import_command"]
+            .as_str()
+            .unwrap()
+            .starts_with("pybun lazy-import --generate --allow heavy")
+    );
+    assert!(
+        detail["tree"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|node| node["module"] == "heavy")
+    );
+
+    // A failing script still yields the imports it made, with a warning.
+    let (ok, json) = profile_imports_json(temp.path(), &["main.py", "--", "3"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["exit_code"], 3);
+    assert_eq!(json["diagnostics"][0]["code"], "W_PROFILE_SCRIPT_FAILED");
+
+    let (ok, json) = profile_imports_json(temp.path(), &["missing.py"]);
+    assert!(!ok);
+    assert_eq!(json["diagnostics"][0]["code"], "E_PROFILE_IMPORTS_FAILED");
+}
+
+fn profile_run_json(
+    dir: &std::path::Path,
+    path: Option<&std::ffi::OsStr>,
+    args: &[&str],
+) -> (bool, serde_json::Value) {
+    let mut cmd = pybun();
+    if let Some(path) = path {
+        cmd.env("PATH", path);
+    }
+    let output = cmd
+        .current_dir(dir)
+        .args(["--format=json", "profile", "run"])
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    (output.status.success(), json)
+}
+
+const BUSY_SCRIPT: &str = "\
+import sys, time
+
+def spin():
+    end = time.perf_counter() + 0.05
+    while time.perf_counter() < end:
+        pass
+
+def main():
+    data = [bytes(1000) for _ in range(200)]
+    spin()
+    return data
+
+kept = main()
+sys.exit(int(sys.argv[1]))
+";
+
+#[test]
+fn test_profile_run_cprofile_reports_hot_functions_and_writes_pstats() {
+    let temp = TempDir::new().unwrap();
+    std::fs::write(temp.path().join("busy.py"), BUSY_SCRIPT).unwrap();
+
+    let (ok, json) = profile_run_json(temp.path(), None, &["busy.py", "--memory", "--", "0"]);
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["sampler"], "cProfile");
+    assert_eq!(detail["exit_code"], 0);
+    assert_eq!(detail["artifact"]["format"], "pstats");
+    let artifact = std::path::Path::new(detail["artifact"]["path"].as_str().unwrap());
+    assert!(artifact.ends_with(".pybun/profiles/busy.prof"));
+    assert!(artifact.is_file());
+    let spin = detail["hot"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["function"] == "spin")
+        .expect("spin is among the hot functions");
+    assert!(spin["total_ms"].as_f64().unwrap() >= 50.0);
+    assert_eq!(spin["calls"], 1);
+    assert!(spin["file"].as_str().unwrap().ends_with("busy.py"));
+    assert!(detail["memory"]["peak_bytes"].as_u64().unwrap() >= 200_000);
+
+    // A failing script is still profiled, with a warning.
+    let (ok, json) = profile_run_json(temp.path(), None, &["busy.py", "--", "3"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["exit_code"], 3);
+    assert_eq!(json["detail"]["memory"], serde_json::Value::Null);
+    assert_eq!(json["diagnostics"][0]["code"], "W_PROFILE_SCRIPT_FAILED");
+
+    let (ok, json) = profile_run_json(temp.path(), None, &["missing.py"]);
+    assert!(!ok);
+    assert_eq!(json["diagnostics"][0]["code"], "E_PROFILE_RUN_FAILED");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_profile_run_py_spy_reads_the_speedscope_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    std::fs::write(temp.path().join("busy.py"), BUSY_SCRIPT).unwrap();
+    // A stand-in py-spy: runs the command and writes a fixed speedscope file.
+    let bin = temp.path().join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let fake = bin.join("py-spy");
+    std::fs::write(
+        &fake,
+        r#"#!/bin/sh
+while [ "$1" != "--" ]; do
+  if [ "$1" = "--output" ]; then out="$2"; fi
+  shift
+done
+shift
+cat > "$out" <<'JSON'
+{"shared": {"frames": [
+  {"name": "<module>", "file": "busy.py", "line": 14},
+  {"name": "main", "file": "busy.py", "line": 10},
+  {"name": "spin", "file": "busy.py", "line": 5}
+]},
+"profiles": [{"type": "sampled", "samples": [[0, 1], [0, 1, 2], [0, 1, 2]], "weights": [1, 1, 1]}]}
+JSON
+exec "$@"
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = std::env::join_paths(
+        std::iter::once(bin).chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+
+    let (ok, json) = profile_run_json(
+        temp.path(),
+        Some(&path),
+        &[
+            "busy.py",
+            "--sampler",
+            "py-spy",
+            "--rate",
+            "50",
+            "-o",
+            "out.json",
+            "--",
+            "0",
+        ],
+    );
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["sampler"], "py-spy");
+    assert_eq!(detail["artifact"]["format"], "speedscope");
+    assert!(temp.path().join("out.json").is_file());
+    assert_eq!(detail["duration_ms"], 60.0);
+    assert_eq!(detail["hot"][0]["function"], "spin");
+    assert_eq!(detail["hot"][0]["self_ms"], 40.0);
+    assert_eq!(detail["hot"][1]["function"], "main");
+    assert_eq!(detail["hot"][1]["total_ms"], 60.0);
+}
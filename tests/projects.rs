@@ -0,0 +1,124 @@
+//! Tests for the machine-wide project registry (`pybun projects`).
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::Lockfile;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn bin(home: &Path) -> Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.env("PYBUN_HOME", home);
+    cmd.env("PYBUN_PYPI_CACHE_DIR", home.join("pypi"));
+    cmd
+}
+
+fn index_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/index.json")
+}
+
+fn json_detail(cmd: &mut Command) -> Value {
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: Value = serde_json::from_str(&stdout).unwrap_or_else(|e| {
+        panic!("invalid json ({e}): {stdout}");
+    });
+    value["detail"].clone()
+}
+
+fn locked_project(home: &Path, root: &Path) {
+    fs::create_dir_all(root).unwrap();
+    fs::write(
+        root.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"app==1.0.0\"]\n",
+    )
+    .unwrap();
+    bin(home)
+        .current_dir(root)
+        .args(["--format=json", "lock", "--index"])
+        .arg(index_path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn lock_registers_project_and_status_reports_lock_changes() {
+    let temp = tempdir().unwrap();
+    let home = temp.path().join("home");
+    let project = temp.path().join("demo");
+    locked_project(&home, &project);
+
+    let detail = json_detail(bin(&home).args(["--format=json", "projects", "list"]));
+    let projects = detail["projects"].as_array().unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0]["name"], "demo");
+    assert_eq!(projects[0]["last_command"], "lock");
+    assert_eq!(
+        PathBuf::from(projects[0]["root"].as_str().unwrap()),
+        fs::canonicalize(&project).unwrap()
+    );
+
+    let detail =
+        json_detail(bin(&home).args(["--format=json", "projects", "status", "--no-drift"]));
+    assert_eq!(detail["projects"][0]["status"], "ok");
+
+    fs::write(project.join("pybun.lockb"), b"edited elsewhere").unwrap();
+    let detail =
+        json_detail(bin(&home).args(["--format=json", "projects", "status", "--no-drift"]));
+    assert_eq!(detail["projects"][0]["status"], "attention");
+    assert!(
+        detail["projects"][0]["issues"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("lock_changed"))
+    );
+
+    fs::remove_dir_all(&project).unwrap();
+    let detail = json_detail(bin(&home).args(["--format=json", "projects", "forget", "--missing"]));
+    assert_eq!(detail["forgotten"].as_array().unwrap().len(), 1);
+    let detail = json_detail(bin(&home).args(["--format=json", "projects", "list"]));
+    assert!(detail["projects"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn gc_keeps_wheels_of_pinned_projects() {
+    let temp = tempdir().unwrap();
+    let home = temp.path().join("home");
+    let project = temp.path().join("demo");
+    locked_project(&home, &project);
+
+    let lock = Lockfile::load_from_path(project.join("pybun.lockb")).unwrap();
+    let pinned_wheel = lock.packages["app"].wheel.clone();
+    let packages = home.join("packages").join("app");
+    fs::create_dir_all(&packages).unwrap();
+    fs::write(packages.join(&pinned_wheel), vec![0u8; 512]).unwrap();
+    fs::write(packages.join("other-1.0-py3-none-any.whl"), vec![0u8; 512]).unwrap();
+
+    bin(&home)
+        .current_dir(&project)
+        .args(["projects", "pin"])
+        .assert()
+        .success();
+
+    let detail = json_detail(bin(&home).args(["--format=json", "gc", "--max-size", "0"]));
+    assert_eq!(detail["pinned"]["files_kept"], 1);
+    assert!(packages.join(&pinned_wheel).exists());
+    assert!(!packages.join("other-1.0-py3-none-any.whl").exists());
+
+    let detail = json_detail(bin(&home).args(["--format=json", "projects", "list"]));
+    assert_eq!(detail["projects"][0]["pinned"], true);
+    assert_eq!(detail["projects"][0]["cache_bytes"], 512);
+}
+
+#[test]
+fn pin_unregistered_project_fails() {
+    let temp = tempdir().unwrap();
+    let home = temp.path().join("home");
+    bin(&home)
+        .args(["projects", "pin"])
+        .arg(temp.path())
+        .assert()
+        .failure();
+}
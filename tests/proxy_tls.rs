@@ -0,0 +1,128 @@
+//! `[tool.pybun.http]` proxy and CA settings, and the `pybun doctor` checks
+//! that report them.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const TEST_CA_PEM: &str = include_str!("fixtures/tls/test-ca.pem");
+
+fn doctor(dir: &Path, envs: &[(&str, &str)]) -> Value {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        // Plain HTTP: the HTTPS probe is skipped, keeping the test offline.
+        .env("PYBUN_PYPI_BASE_URL", "http://127.0.0.1:9")
+        .env("PYBUN_SYSTEM_CERTS", "0");
+    for name in [
+        "PYBUN_CA_BUNDLE",
+        "PYBUN_PROXY",
+        "PYBUN_NO_PROXY",
+        "SSL_CERT_FILE",
+        "REQUESTS_CA_BUNDLE",
+        "PIP_CERT",
+        "HTTPS_PROXY",
+        "https_proxy",
+        "HTTP_PROXY",
+        "http_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+    ] {
+        cmd.env_remove(name);
+    }
+    let output = cmd
+        .envs(envs.iter().copied())
+        .args(["--format=json", "doctor", "--fix"])
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+fn check<'a>(json: &'a Value, name: &str) -> &'a Value {
+    json["detail"]["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap_or_else(|| panic!("no {name} check: {json}"))
+}
+
+#[test]
+fn doctor_reports_project_ca_bundle_and_proxy() {
+    let temp = tempdir().unwrap();
+    fs::create_dir_all(temp.path().join("certs")).unwrap();
+    fs::write(temp.path().join("certs/corp.pem"), TEST_CA_PEM).unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        r#"[project]
+name = "demo"
+version = "0.1.0"
+
+[tool.pybun.http]
+ca-bundle = "certs/corp.pem"
+proxy = "http://proxy.corp.example:3128"
+no-proxy = ["*.corp.example"]
+"#,
+    )
+    .unwrap();
+
+    let json = doctor(temp.path(), &[]);
+    let tls = check(&json, "tls");
+    assert_eq!(tls["status"], "ok", "{json}");
+    assert_eq!(tls["trust"][0]["certificates"], 1);
+    assert!(
+        tls["trust"][0]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("corp.pem")
+    );
+
+    let proxy = check(&json, "proxy");
+    assert_eq!(proxy["url"], "http://proxy.corp.example:3128");
+    assert_eq!(proxy["explicit"], true);
+    assert_eq!(proxy["no_proxy"][0], "*.corp.example");
+    assert_eq!(check(&json, "tls_probe")["status"], "info");
+}
+
+#[test]
+fn doctor_flags_unusable_ca_bundle_with_fix_candidates() {
+    let temp = tempdir().unwrap();
+    let json = doctor(temp.path(), &[("PYBUN_CA_BUNDLE", "/nonexistent/ca.pem")]);
+
+    assert_eq!(json["detail"]["status"], "issues_found");
+    assert_eq!(check(&json, "tls")["status"], "error");
+    let plan = json["detail"]["fix_plan"].as_array().unwrap();
+    let item = plan
+        .iter()
+        .find(|item| item["code"] == "E_DOCTOR_HTTP_CONFIG")
+        .unwrap_or_else(|| panic!("no E_DOCTOR_HTTP_CONFIG in {json}"));
+    assert!(
+        item["message"]
+            .as_str()
+            .unwrap()
+            .contains("/nonexistent/ca.pem")
+    );
+    assert!(
+        item["fix_candidates"][0]["command"]
+            .as_str()
+            .unwrap()
+            .contains("PYBUN_CA_BUNDLE")
+    );
+}
+
+#[test]
+fn environment_proxy_is_reported() {
+    let temp = tempdir().unwrap();
+    let json = doctor(temp.path(), &[("HTTPS_PROXY", "http://envproxy:8080")]);
+    let proxy = check(&json, "proxy");
+    assert_eq!(proxy["origin"], "HTTPS_PROXY");
+    assert_eq!(proxy["explicit"], false);
+}
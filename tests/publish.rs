@@ -0,0 +1,309 @@
+//! `pybun publish` uploads wheels and sdists with the legacy upload API,
+//! authenticating from the environment, stored credentials, or trusted
+//! publishing.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use base64::Engine;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::tempdir;
+
+const METADATA: &str =
+    "Metadata-Version: 2.1\nName: demo-pkg\nVersion: 0.1.0\nSummary: Demo\n\n# Demo\n";
+
+/// A project with a built wheel and sdist of demo-pkg 0.1.0 in `dist/`.
+fn project(dir: &Path) {
+    fs::write(
+        dir.join("pyproject.toml"),
+        "[project]\nname = \"demo-pkg\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    let dist = dir.join("dist");
+    fs::create_dir_all(&dist).unwrap();
+
+    let mut wheel = zip::ZipWriter::new(
+        fs::File::create(dist.join("demo_pkg-0.1.0-py3-none-any.whl")).unwrap(),
+    );
+    for (name, data) in [
+        ("demo_pkg/__init__.py", ""),
+        ("demo_pkg-0.1.0.dist-info/METADATA", METADATA),
+    ] {
+        wheel
+            .start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        wheel.write_all(data.as_bytes()).unwrap();
+    }
+    wheel.finish().unwrap();
+
+    let encoder = flate2::write::GzEncoder::new(
+        fs::File::create(dist.join("demo_pkg-0.1.0.tar.gz")).unwrap(),
+        flate2::Compression::default(),
+    );
+    let mut sdist = tar::Builder::new(encoder);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(METADATA.len() as u64);
+    header.set_mode(0o644);
+    sdist
+        .append_data(&mut header, "demo_pkg-0.1.0/PKG-INFO", METADATA.as_bytes())
+        .unwrap();
+    sdist.into_inner().unwrap().finish().unwrap();
+    fs::write(dist.join("pybun-sbom.json"), "{}").unwrap();
+}
+
+fn pybun(dir: &Path, env: &[(&str, &str)], args: &[&str]) -> (Value, i32) {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .env("PYBUN_AUTH_BACKEND", "file")
+        .env_remove("PYBUN_PUBLISH_TOKEN")
+        .env_remove("PYBUN_PUBLISH_USERNAME")
+        .env_remove("PYBUN_PUBLISH_PASSWORD")
+        .env_remove("PYBUN_PUBLISH_REPOSITORY")
+        .env_remove("PYBUN_OIDC_TOKEN")
+        .env_remove("ACTIONS_ID_TOKEN_REQUEST_URL")
+        .env_remove("ACTIONS_ID_TOKEN_REQUEST_TOKEN")
+        .env_remove("PYBUN_POLICY");
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+    let output = cmd
+        .arg("--format=json")
+        .arg("publish")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (json, output.status.code().unwrap_or(-1))
+}
+
+fn basic(username: &str, password: &str) -> String {
+    format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"))
+    )
+}
+
+fn statuses(json: &Value) -> Vec<(&str, &str)> {
+    json["detail"]["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| {
+            (
+                f["filename"].as_str().unwrap(),
+                f["status"].as_str().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn dry_run_checks_files_without_uploading() {
+    let temp = tempdir().unwrap();
+    project(temp.path());
+
+    let (json, code) = pybun(temp.path(), &[], &["--dry-run"]);
+    assert_eq!(code, 0, "{json}");
+    assert_eq!(
+        json["detail"]["upload_url"],
+        "https://upload.pypi.org/legacy/"
+    );
+    assert_eq!(
+        statuses(&json),
+        [
+            ("demo_pkg-0.1.0.tar.gz", "dry_run"),
+            ("demo_pkg-0.1.0-py3-none-any.whl", "dry_run"),
+        ]
+    );
+    let wheel = &json["detail"]["files"][1];
+    assert_eq!(wheel["filetype"], "bdist_wheel");
+    assert_eq!(wheel["name"], "demo-pkg");
+    assert_eq!(wheel["sha256"].as_str().unwrap().len(), 64);
+    assert!(json["detail"]["credentials"].is_null());
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "W_PUBLISH_NO_CREDENTIALS")
+    );
+
+    let (json, _) = pybun(
+        temp.path(),
+        &[("PYBUN_PUBLISH_TOKEN", "pypi-secret")],
+        &["--dry-run"],
+    );
+    assert_eq!(json["detail"]["credentials"]["source"], "env");
+}
+
+#[test]
+fn uploads_each_file_with_the_environment_token() {
+    let temp = tempdir().unwrap();
+    project(temp.path());
+    let server = MockServer::start();
+    let sdist = server.mock(|when, then| {
+        when.method(POST)
+            .path("/legacy/")
+            .header("authorization", basic("__token__", "pypi-secret"))
+            .body_includes("name=\":action\"\r\n\r\nfile_upload")
+            .body_includes("name=\"filetype\"\r\n\r\nsdist")
+            .body_includes("name=\"pyversion\"\r\n\r\nsource")
+            .body_includes("name=\"name\"\r\n\r\ndemo-pkg")
+            .body_includes("filename=\"demo_pkg-0.1.0.tar.gz\"");
+        then.status(200);
+    });
+    let wheel = server.mock(|when, then| {
+        when.method(POST)
+            .path("/legacy/")
+            .body_includes("name=\"filetype\"\r\n\r\nbdist_wheel")
+            .body_includes("name=\"pyversion\"\r\n\r\npy3");
+        then.status(200);
+    });
+
+    let (json, code) = pybun(
+        temp.path(),
+        &[("PYBUN_PUBLISH_TOKEN", "pypi-secret")],
+        &["--repository", &server.url("/legacy/")],
+    );
+    assert_eq!(code, 0, "{json}");
+    sdist.assert();
+    wheel.assert();
+    assert_eq!(
+        statuses(&json),
+        [
+            ("demo_pkg-0.1.0.tar.gz", "uploaded"),
+            ("demo_pkg-0.1.0-py3-none-any.whl", "uploaded"),
+        ]
+    );
+    assert_eq!(json["detail"]["files"][0]["http_status"], 200);
+    assert_eq!(json["detail"]["credentials"]["username"], "__token__");
+    assert_eq!(json["detail"]["summary"]["uploaded"], 2);
+}
+
+#[test]
+fn existing_files_fail_unless_skipped() {
+    let temp = tempdir().unwrap();
+    project(temp.path());
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/legacy/")
+            .body_includes("filename=\"demo_pkg-0.1.0.tar.gz\"");
+        then.status(400).body(
+            "<html><body><h1>400 File already exists ('demo_pkg-0.1.0.tar.gz').</h1></body></html>",
+        );
+    });
+    let wheel = server.mock(|when, then| {
+        when.method(POST)
+            .path("/legacy/")
+            .body_includes("filename=\"demo_pkg-0.1.0-py3-none-any.whl\"");
+        then.status(200);
+    });
+    let env = [("PYBUN_PUBLISH_TOKEN", "pypi-secret")];
+    let repository = server.url("/legacy/");
+
+    let (json, code) = pybun(temp.path(), &env, &["-r", &repository]);
+    assert_ne!(code, 0, "{json}");
+    assert_eq!(
+        statuses(&json),
+        [
+            ("demo_pkg-0.1.0.tar.gz", "failed"),
+            ("demo_pkg-0.1.0-py3-none-any.whl", "not_attempted"),
+        ]
+    );
+    assert_eq!(wheel.calls(), 0);
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_PUBLISH_UPLOAD_FAILED")
+        .unwrap_or_else(|| panic!("missing E_PUBLISH_UPLOAD_FAILED: {json}"));
+    assert!(
+        diagnostic["message"]
+            .as_str()
+            .unwrap()
+            .contains("File already exists")
+    );
+    assert_eq!(diagnostic["error_code"], "PYBUN-PUBLISH-001");
+
+    let (json, code) = pybun(temp.path(), &env, &["-r", &repository, "--skip-existing"]);
+    assert_eq!(code, 0, "{json}");
+    assert_eq!(
+        statuses(&json),
+        [
+            ("demo_pkg-0.1.0.tar.gz", "exists"),
+            ("demo_pkg-0.1.0-py3-none-any.whl", "uploaded"),
+        ]
+    );
+}
+
+#[test]
+fn trusted_publishing_exchanges_the_ci_id_token() {
+    let temp = tempdir().unwrap();
+    project(temp.path());
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/_/oidc/audience");
+        then.status(200)
+            .json_body(json!({ "audience": "demo-index" }));
+    });
+    let id_token = server.mock(|when, then| {
+        when.method(GET)
+            .path("/github/token")
+            .query_param("audience", "demo-index")
+            .header("authorization", "Bearer gh-request-token");
+        then.status(200).json_body(json!({ "value": "oidc-jwt" }));
+    });
+    let mint = server.mock(|when, then| {
+        when.method(POST)
+            .path("/_/oidc/mint-token")
+            .json_body(json!({ "token": "oidc-jwt" }));
+        then.status(200)
+            .json_body(json!({ "token": "minted-token" }));
+    });
+    let upload = server.mock(|when, then| {
+        when.method(POST)
+            .path("/legacy/")
+            .header("authorization", basic("__token__", "minted-token"));
+        then.status(200);
+    });
+
+    let request_url = format!("{}?api-version=2.0", server.url("/github/token"));
+    let (json, code) = pybun(
+        temp.path(),
+        &[
+            ("ACTIONS_ID_TOKEN_REQUEST_URL", &request_url),
+            ("ACTIONS_ID_TOKEN_REQUEST_TOKEN", "gh-request-token"),
+        ],
+        &["-r", &server.url("/legacy/")],
+    );
+    assert_eq!(code, 0, "{json}");
+    id_token.assert();
+    mint.assert();
+    upload.assert_calls(2);
+    assert_eq!(
+        json["detail"]["credentials"]["source"],
+        "trusted_publishing"
+    );
+
+    // Without an ID token source there are no credentials at all.
+    let (json, code) = pybun(temp.path(), &[], &["-r", &server.url("/legacy/")]);
+    assert_ne!(code, 0);
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "E_PUBLISH_NO_CREDENTIALS"),
+        "{json}"
+    );
+}
@@ -28,12 +28,27 @@ fn ensure_venv(project_root: &Path) -> PathBuf {
     venv
 }
 
+/// One wheel body served for every mocked filename, so all of them share
+/// [`wheel_sha256`]. Its `.dist-info` is named for no mocked project.
 fn wheel_bytes() -> Vec<u8> {
     let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
     let options = zip::write::SimpleFileOptions::default();
-    zip.start_file("dummy.txt", options)
-        .expect("start wheel entry");
-    zip.write_all(b"ok").expect("write wheel entry");
+    for (name, contents) in [
+        ("dummy.txt", "ok"),
+        (
+            "fixture-0.0.0.dist-info/METADATA",
+            "Metadata-Version: 2.1\nName: fixture\nVersion: 0.0.0\n",
+        ),
+        (
+            "fixture-0.0.0.dist-info/WHEEL",
+            "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+        ),
+        ("fixture-0.0.0.dist-info/RECORD", ""),
+    ] {
+        zip.start_file(name, options).expect("start wheel entry");
+        zip.write_all(contents.as_bytes())
+            .expect("write wheel entry");
+    }
     let cursor = zip.finish().expect("finish wheel zip");
     cursor.into_inner()
 }
@@ -413,6 +428,105 @@ dependencies = ["app==1.0.0"]
         .failure();
 }
 
+fn network_required(output: &std::process::Output) -> Vec<serde_json::Value> {
+    assert!(!output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).expect("JSON output");
+    let diagnostic = value["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_NETWORK_REQUIRED")
+        .unwrap_or_else(|| panic!("no E_NETWORK_REQUIRED diagnostic: {value}"))
+        .clone();
+    diagnostic["context"]["missing"].as_array().unwrap().clone()
+}
+
+#[test]
+fn global_offline_reports_missing_metadata_without_network() {
+    let temp = tempdir().unwrap();
+    let server = MockServer::start();
+    let metadata = server.mock(|when, then| {
+        when.method(GET).path("/pypi/app/json");
+        then.status(500);
+    });
+    let venv = ensure_venv(temp.path());
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"app==1.0.0\"]\n",
+    )
+    .unwrap();
+
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PYPI_BASE_URL", server.base_url())
+        .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("cache"))
+        .env("PYBUN_ENV", &venv)
+        .args(["--format=json", "--offline", "install"])
+        .output()
+        .unwrap();
+
+    let missing = network_required(&output);
+    assert_eq!(missing, vec![json!({ "kind": "metadata", "name": "app" })]);
+    assert_eq!(metadata.calls(), 0);
+}
+
+#[test]
+fn global_offline_lists_wheels_missing_from_the_cache() {
+    let temp = tempdir().unwrap();
+    let cache_dir = temp.path().join("cache");
+    let server = MockServer::start();
+    let base_url = setup_package_mocks(&server);
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"app==1.0.0\"]\n",
+    )
+    .unwrap();
+    let venv = ensure_venv(temp.path());
+    bin()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PYPI_BASE_URL", &base_url)
+        .env("PYBUN_PYPI_CACHE_DIR", &cache_dir)
+        .env("PYBUN_ENV", &venv)
+        .arg("install")
+        .assert()
+        .success();
+
+    // Index metadata stays cached; the wheels are gone.
+    for entry in fs::read_dir(cache_dir.join("artifacts")).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "whl") {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    fs::remove_dir_all(temp.path().join("home")).unwrap();
+    let other = temp.path().join("other");
+    fs::create_dir_all(&other).unwrap();
+    let output = bin()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PYPI_BASE_URL", &base_url)
+        .env("PYBUN_PYPI_CACHE_DIR", &cache_dir)
+        .env("PYBUN_ENV", ensure_venv(&other))
+        .env("PYBUN_OFFLINE", "1")
+        .args(["--format=json", "install"])
+        .output()
+        .unwrap();
+
+    let missing = network_required(&output);
+    let names: Vec<&str> = missing
+        .iter()
+        .map(|m| m["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["app==1.0.0", "dep==2.0.0"], "{missing:?}");
+    assert!(missing.iter().all(|m| m["kind"] == "wheel"));
+    assert_eq!(
+        missing[0]["url"],
+        format!("{base_url}/files/app-1.0.0-py3-none-any.whl")
+    );
+}
+
 #[test]
 fn install_uses_fresh_cache_without_network() {
     let temp = tempdir().unwrap();
@@ -782,3 +896,82 @@ dependencies = ["app==1.0.0"]
     let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
     assert!(lock.packages.contains_key("app"));
 }
+
+#[test]
+fn install_resolves_against_simple_index_url() {
+    let temp = tempdir().unwrap();
+    let cache_dir = temp.path().join("cache");
+    let server = MockServer::start();
+    let base = server.base_url();
+    let sha256 = wheel_sha256();
+    let venv = ensure_venv(temp.path());
+
+    // PEP 503 HTML for `app`, PEP 691 JSON for `dep`.
+    let app_page = format!(
+        r#"<html><body>
+<a href="{base}/files/app-1.0.0-py3-none-any.whl#sha256={sha256}" data-core-metadata="true">app-1.0.0-py3-none-any.whl</a>
+<a href="{base}/files/app-1.1.0-py3-none-any.whl#sha256={sha256}" data-yanked="">app-1.1.0-py3-none-any.whl</a>
+</body></html>"#
+    );
+    server.mock(|when, then| {
+        when.method(GET).path("/simple/app/");
+        then.status(200)
+            .header("Content-Type", "text/html")
+            .body(app_page.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/files/app-1.0.0-py3-none-any.whl.metadata");
+        then.status(200)
+            .body("Metadata-Version: 2.1\nName: app\nVersion: 1.0.0\nRequires-Dist: dep>=2.0\n");
+    });
+    let dep_page = json!({
+        "meta": { "api-version": "1.1" },
+        "name": "dep",
+        "files": [{
+            "filename": "dep-2.0.0-py3-none-any.whl",
+            "url": "/files/dep-2.0.0-py3-none-any.whl",
+            "hashes": { "sha256": sha256 },
+            "core-metadata": true
+        }]
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/simple/dep/");
+        then.status(200)
+            .header("Content-Type", "application/vnd.pypi.simple.v1+json")
+            .body(dep_page.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/files/dep-2.0.0-py3-none-any.whl.metadata");
+        then.status(200)
+            .body("Metadata-Version: 2.1\nName: dep\nVersion: 2.0.0\n");
+    });
+    mock_download(&server, "app-1.0.0-py3-none-any.whl");
+    mock_download(&server, "dep-2.0.0-py3-none-any.whl");
+
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        r#"[project]
+name = "demo"
+version = "0.1.0"
+dependencies = ["app"]
+"#,
+    )
+    .unwrap();
+
+    bin()
+        .current_dir(temp.path())
+        .env("PYBUN_INDEX_URL", format!("{base}/simple"))
+        .env("PYBUN_PYPI_BASE_URL", "http://127.0.0.1:9") // JSON API must not be used
+        .env("PYBUN_PYPI_CACHE_DIR", cache_dir.to_str().unwrap())
+        .env("PYBUN_ENV", venv.to_str().unwrap())
+        .args(["install"])
+        .assert()
+        .success();
+
+    let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+    assert_eq!(lock.packages["app"].version, "1.0.0");
+    assert_eq!(lock.packages["dep"].version, "2.0.0");
+}
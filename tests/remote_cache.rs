@@ -0,0 +1,262 @@
+//! E2E tests for `pybun cache push` / `pybun cache pull` against an HTTP
+//! and an S3-compatible remote cache (both served by httpmock).
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::Method;
+use httpmock::prelude::*;
+use pybun::cache::Cache;
+use pybun::lockfile::{Artifact, Lockfile, Package, PackageSource};
+use pybun::remote_cache::{environment_key, lock_artifacts};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const WHEEL: &[u8] = b"pretend wheel contents";
+
+fn sha256(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn package(name: &str, filename: &str, hash: &str) -> Package {
+    Package {
+        name: name.into(),
+        version: "1.0".into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: filename.into(),
+        hash: format!("sha256:{hash}"),
+        dependencies: Vec::new(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: vec![Artifact {
+            filename: filename.into(),
+            url: None,
+            hash: format!("sha256:{hash}"),
+            platforms: Vec::new(),
+        }],
+    }
+}
+
+/// A project locking a pure wheel (cached in `home`) and a Windows-only
+/// wheel that is not cached anywhere.
+fn project(temp: &TempDir) -> (std::path::PathBuf, Lockfile) {
+    let dir = temp.path().join("app");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+    lock.add_package(package(
+        "alpha",
+        "alpha-1.0-py3-none-any.whl",
+        &sha256(WHEEL),
+    ));
+    lock.add_package(package(
+        "winonly",
+        "winonly-1.0-cp311-cp311-win_amd64.whl",
+        &sha256(b"windows"),
+    ));
+    lock.save_to_path(dir.join("pybun.lockb")).unwrap();
+    (dir, lock)
+}
+
+fn cache_wheel(home: &Path) {
+    let path = home.join(Cache::stored_wheel_relative(
+        &sha256(WHEEL),
+        "alpha-1.0-py3-none-any.whl",
+    ));
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, WHEEL).unwrap();
+}
+
+fn pybun_cache(dir: &Path, home: &Path, env: &[(&str, &str)], args: &[&str]) -> (Value, bool) {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.current_dir(dir)
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_CACHE_URL")
+        .env_remove("PYBUN_CACHE_TOKEN")
+        .env_remove("AWS_ENDPOINT_URL")
+        .env_remove("AWS_ENDPOINT_URL_S3")
+        .env_remove("AWS_PROFILE");
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+    let output = cmd
+        .args(["--format=json", "cache"])
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|e| panic!("{e}: {}", String::from_utf8_lossy(&output.stderr)));
+    (json, output.status.success())
+}
+
+fn codes(json: &Value) -> Vec<&str> {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect()
+}
+
+#[test]
+fn push_then_pull_through_an_http_cache() {
+    let temp = TempDir::new().unwrap();
+    let (dir, lock) = project(&temp);
+    let env_key = environment_key(&lock_artifacts(&lock));
+    let wheel_path = format!(
+        "/cache/artifacts/{}/alpha-1.0-py3-none-any.whl",
+        sha256(WHEEL)
+    );
+    let manifest_path = format!("/cache/manifests/{env_key}.json");
+    let server = MockServer::start();
+    let head = server.mock(|when, then| {
+        when.method(Method::HEAD)
+            .path(wheel_path.as_str())
+            .header("authorization", "Bearer s3cret");
+        then.status(404);
+    });
+    let put_wheel = server.mock(|when, then| {
+        when.method(PUT)
+            .path(wheel_path.as_str())
+            .body("pretend wheel contents");
+        then.status(200);
+    });
+    let mut no_manifest = server.mock(|when, then| {
+        when.method(GET).path(manifest_path.as_str());
+        then.status(404);
+    });
+    let put_manifest = server.mock(|when, then| {
+        when.method(PUT)
+            .path(manifest_path.as_str())
+            .body_includes("alpha-1.0-py3-none-any.whl");
+        then.status(200);
+    });
+    let url = server.url("/cache");
+    let env = [
+        ("PYBUN_CACHE_URL", url.as_str()),
+        ("PYBUN_CACHE_TOKEN", "s3cret"),
+    ];
+
+    let pusher = temp.path().join("pusher-home");
+    cache_wheel(&pusher);
+    let (json, ok) = pybun_cache(&dir, &pusher, &env, &["push"]);
+    assert!(ok, "{json}");
+    head.assert();
+    put_wheel.assert();
+    no_manifest.assert();
+    put_manifest.assert();
+    let summary = &json["detail"]["summary"];
+    assert_eq!(summary["uploaded"], 1, "{json}");
+    assert_eq!(summary["absent"], 1);
+    assert_eq!(json["detail"]["backend"], "http");
+    assert_eq!(json["detail"]["manifest"]["key"], env_key.as_str());
+
+    // A fresh machine pulls the wheel it can use; the Windows wheel is skipped.
+    no_manifest.delete();
+    server.mock(|when, then| {
+        when.method(GET).path(manifest_path.as_str());
+        then.status(200).json_body(json!({
+            "artifacts": [{ "sha256": sha256(WHEEL), "filename": "alpha-1.0-py3-none-any.whl" }]
+        }));
+    });
+    let mut get_wheel = server.mock(|when, then| {
+        when.method(GET).path(wheel_path.as_str());
+        then.status(200).body(WHEEL);
+    });
+    let puller = temp.path().join("puller-home");
+    let (json, ok) = pybun_cache(&dir, &puller, &env, &["pull"]);
+    assert!(ok, "{json}");
+    get_wheel.assert();
+    let summary = &json["detail"]["summary"];
+    assert_eq!(summary["artifacts"], 1, "{json}");
+    assert_eq!(summary["hits"], 1);
+    assert_eq!(summary["hit_rate_percent"], 100.0);
+    assert_eq!(json["detail"]["manifest"]["status"], "hit");
+    let stored = puller.join(Cache::stored_wheel_relative(
+        &sha256(WHEEL),
+        "alpha-1.0-py3-none-any.whl",
+    ));
+    assert_eq!(fs::read(stored).unwrap(), WHEEL);
+
+    // Pulling again finds the wheel locally.
+    let (json, _) = pybun_cache(&dir, &puller, &env, &["pull"]);
+    assert_eq!(json["detail"]["summary"]["local"], 1, "{json}");
+
+    // A tampered object is rejected, not stored.
+    get_wheel.delete();
+    server.mock(|when, then| {
+        when.method(GET).path(wheel_path.as_str());
+        then.status(200).body("tampered");
+    });
+    let other = temp.path().join("other-home");
+    let (json, ok) = pybun_cache(&dir, &other, &env, &["pull"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["summary"]["corrupt"], 1, "{json}");
+    assert!(codes(&json).contains(&"W_REMOTE_CACHE_CORRUPT"));
+    assert!(!other.join("wheels").join(&sha256(WHEEL)[..2]).exists());
+}
+
+#[test]
+fn push_signs_requests_for_s3_compatible_storage() {
+    let temp = TempDir::new().unwrap();
+    let (dir, lock) = project(&temp);
+    let env_key = environment_key(&lock_artifacts(&lock));
+    let server = MockServer::start();
+    let head = server.mock(|when, then| {
+        when.method(Method::HEAD)
+            .path(format!(
+                "/wheels/ci/artifacts/{}/alpha-1.0-py3-none-any.whl",
+                sha256(WHEEL)
+            ))
+            .header_exists("x-amz-date")
+            .header_exists("x-amz-content-sha256")
+            .header_prefix("authorization", "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/")
+            .header_includes("authorization", "/eu-west-1/s3/aws4_request");
+        then.status(200);
+    });
+    let manifest = server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/wheels/ci/manifests/{env_key}.json"));
+        then.status(200).json_body(json!({
+            "artifacts": [{ "sha256": sha256(WHEEL), "filename": "alpha-1.0-py3-none-any.whl" }]
+        }));
+    });
+    let home = temp.path().join("home");
+    cache_wheel(&home);
+    let endpoint = server.base_url();
+    let (json, ok) = pybun_cache(
+        &dir,
+        &home,
+        &[
+            ("AWS_ENDPOINT_URL", endpoint.as_str()),
+            ("AWS_REGION", "eu-west-1"),
+            ("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+        ],
+        &["push", "--remote", "s3://wheels/ci"],
+    );
+    assert!(ok, "{json}");
+    head.assert();
+    manifest.assert();
+    assert_eq!(json["detail"]["backend"], "s3");
+    assert_eq!(json["detail"]["summary"]["present"], 1, "{json}");
+    assert_eq!(json["detail"]["manifest"]["status"], "present");
+}
+
+#[test]
+fn cache_commands_report_a_missing_remote() {
+    let temp = TempDir::new().unwrap();
+    let (dir, _) = project(&temp);
+    let (json, ok) = pybun_cache(&dir, &temp.path().join("home"), &[], &["pull"]);
+    assert!(!ok);
+    assert!(codes(&json).contains(&"E_REMOTE_CACHE_FAILED"), "{json}");
+}
@@ -0,0 +1,155 @@
+//! `pybun rename-dep`: preview by default, atomic apply with re-lock and an
+//! audit record, and rollback when re-locking fails.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::command_history;
+use pybun::lockfile::Lockfile;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn bin() -> Command {
+    cargo_bin_cmd!("pybun")
+}
+
+fn index_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/index.json")
+}
+
+const MAIN_PY: &str = "import lib_a\nfrom lib_a.sub import thing\n\nprint(lib_a.VERSION)\n";
+
+/// Project depending on `dependency`, with an importing module and a lockfile.
+fn project(dir: &Path, dependency: &str) {
+    fs::write(
+        dir.join("pyproject.toml"),
+        format!(
+            "[project]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\"{dependency}\"]\n"
+        ),
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/main.py"), MAIN_PY).unwrap();
+    fs::write(dir.join("src/other.py"), "import os\n").unwrap();
+    let output = pybun(dir)
+        .args(["lock", "--index"])
+        .arg(index_path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+}
+
+fn pybun(dir: &Path) -> Command {
+    let mut cmd = bin();
+    cmd.current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".home"))
+        .env("PYBUN_FORCE_CP_TAG", "cp312")
+        .arg("--format=json");
+    cmd
+}
+
+fn rename(dir: &Path, extra: &[&str]) -> (bool, Value) {
+    let output = pybun(dir)
+        .args(["rename-dep", "lib-a", "lib-b", "--imports", "lib_a=lib_b"])
+        .args(extra)
+        .arg("--index")
+        .arg(index_path())
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+#[test]
+fn preview_shows_diff_without_writing() {
+    let temp = tempdir().unwrap();
+    project(temp.path(), "lib-a");
+    let pyproject = fs::read_to_string(temp.path().join("pyproject.toml")).unwrap();
+    let lock = fs::read(temp.path().join("pybun.lockb")).unwrap();
+
+    let (ok, json) = rename(temp.path(), &[]);
+    assert!(ok, "{json}");
+    let detail = &json["detail"];
+    assert_eq!(detail["applied"], false);
+    assert_eq!(detail["sections"][0], "project.dependencies");
+    assert_eq!(detail["files"].as_array().unwrap().len(), 1, "{json}");
+    assert_eq!(detail["files"][0]["file"], "src/main.py");
+    assert_eq!(detail["files"][0]["lines"], serde_json::json!([1, 2]));
+    let diff = detail["diff"].as_str().unwrap();
+    assert!(diff.contains("+import lib_b as lib_a"), "{diff}");
+    assert!(diff.contains("+from lib_b.sub import thing"), "{diff}");
+    assert!(diff.contains("\"lib-b\""), "{diff}");
+
+    assert_eq!(
+        fs::read_to_string(temp.path().join("pyproject.toml")).unwrap(),
+        pyproject
+    );
+    assert_eq!(
+        fs::read_to_string(temp.path().join("src/main.py")).unwrap(),
+        MAIN_PY
+    );
+    assert_eq!(fs::read(temp.path().join("pybun.lockb")).unwrap(), lock);
+    assert!(
+        command_history::load(temp.path()).unwrap().is_empty(),
+        "preview is not recorded"
+    );
+}
+
+#[test]
+fn apply_rewrites_files_relocks_and_records_history() {
+    let temp = tempdir().unwrap();
+    project(temp.path(), "lib-a");
+
+    let (ok, json) = rename(temp.path(), &["--apply"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["relocked"], true);
+
+    let pyproject = fs::read_to_string(temp.path().join("pyproject.toml")).unwrap();
+    assert!(pyproject.contains("\"lib-b\""), "{pyproject}");
+    assert!(!pyproject.contains("lib-a"), "{pyproject}");
+    let main = fs::read_to_string(temp.path().join("src/main.py")).unwrap();
+    assert_eq!(
+        main,
+        "import lib_b as lib_a\nfrom lib_b.sub import thing\n\nprint(lib_a.VERSION)\n"
+    );
+    let lock = Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+    assert!(lock.packages.contains_key("lib-b"));
+    assert!(!lock.packages.contains_key("lib-a"));
+
+    let history = command_history::load(temp.path()).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].command, "rename-dep");
+    assert_eq!(
+        history[0].files,
+        vec!["pyproject.toml", "src/main.py", "pybun.lockb"]
+    );
+    assert_eq!(history[0].detail["old"], "lib-a");
+}
+
+#[test]
+fn failed_relock_leaves_every_file_untouched() {
+    let temp = tempdir().unwrap();
+    // lib-b has no 1.0.0 in the index, so the renamed requirement can't lock.
+    project(temp.path(), "lib-a==1.0.0");
+    let pyproject = fs::read_to_string(temp.path().join("pyproject.toml")).unwrap();
+    let lock = fs::read(temp.path().join("pybun.lockb")).unwrap();
+
+    let (ok, json) = rename(temp.path(), &["--apply"]);
+    assert!(!ok, "{json}");
+    assert_eq!(
+        fs::read_to_string(temp.path().join("pyproject.toml")).unwrap(),
+        pyproject
+    );
+    assert_eq!(
+        fs::read_to_string(temp.path().join("src/main.py")).unwrap(),
+        MAIN_PY
+    );
+    assert_eq!(fs::read(temp.path().join("pybun.lockb")).unwrap(), lock);
+    assert!(command_history::load(temp.path()).unwrap().is_empty());
+}
@@ -0,0 +1,131 @@
+//! `pybun run` runs Python in UTF-8 mode by default, honours the
+//! `encoding = "locale"` opt-out, and reports encoding failures with a
+//! targeted diagnostic.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+/// `pybun --format=json run` in `dir` under the C locale, with no encoding
+/// variables inherited from the test environment.
+fn run(dir: &Path) -> Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".home"))
+        .env("LC_ALL", "C")
+        .env_remove("PYTHONUTF8")
+        .env_remove("PYTHONIOENCODING")
+        .env_remove("PYBUN_ENCODING")
+        .args(["--format=json", "run"]);
+    cmd
+}
+
+fn json(cmd: &mut Command) -> Value {
+    let output = cmd.output().unwrap();
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+const CHECK: &str = "import sys; print(sys.flags.utf8_mode, sys.stdout.encoding)";
+
+#[test]
+fn utf8_mode_is_on_by_default() {
+    let temp = tempdir().unwrap();
+    let json = json(run(temp.path()).args(["-c", CHECK]));
+    let detail = &json["detail"];
+    assert_eq!(
+        detail["stdout"].as_str().unwrap().trim(),
+        "1 utf-8",
+        "{json}"
+    );
+    assert_eq!(detail["encoding"]["policy"], "utf8");
+    assert_eq!(detail["encoding"]["locale"]["name"], "C");
+    assert_eq!(detail["encoding"]["env"]["PYTHONUTF8"], "1");
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|d| d["code"] != "W_LOCALE_NOT_UTF8"),
+        "{json}"
+    );
+}
+
+#[test]
+fn locale_opt_out_leaves_environment_alone_and_warns() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"legacy\"\n\n[tool.pybun]\nencoding = \"locale\"\n",
+    )
+    .unwrap();
+
+    let json =
+        json(run(temp.path()).args(["-c", "import os; print(os.environ.get('PYTHONUTF8'))"]));
+    let detail = &json["detail"];
+    assert_eq!(detail["stdout"].as_str().unwrap().trim(), "None", "{json}");
+    assert_eq!(detail["encoding"]["policy"], "locale");
+    assert!(detail["encoding"]["env"].as_object().unwrap().is_empty());
+    let warning = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "W_LOCALE_NOT_UTF8")
+        .unwrap_or_else(|| panic!("locale warning: {json}"));
+    assert!(warning["message"].as_str().unwrap().contains("'C'"));
+}
+
+#[test]
+fn encoding_failures_get_a_targeted_diagnostic() {
+    let temp = tempdir().unwrap();
+    let script = temp.path().join("accent.py");
+    fs::write(&script, "print('caf\\u00e9')\n").unwrap();
+
+    let json = json(
+        run(temp.path())
+            .env("PYBUN_ENCODING", "locale")
+            .env("PYTHONIOENCODING", "ascii")
+            .arg(&script),
+    );
+    assert_eq!(json["detail"]["exit_code"], 1, "{json}");
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_RUNTIME_ENCODING_ERROR")
+        .unwrap_or_else(|| panic!("encoding diagnostic: {json}"));
+    assert_eq!(diagnostic["exception_type"], "UnicodeEncodeError");
+    assert!(
+        diagnostic["suggestion"]
+            .as_str()
+            .unwrap()
+            .contains("locale encoding"),
+        "{diagnostic}"
+    );
+}
+
+#[test]
+fn invalid_encoding_override_fails_the_run() {
+    let temp = tempdir().unwrap();
+    let output = run(temp.path())
+        .env("PYBUN_ENCODING", "latin1")
+        .args(["-c", "print(1)"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(
+        json["diagnostics"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("invalid encoding policy 'latin1'"),
+        "{json}"
+    );
+}
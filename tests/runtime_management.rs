@@ -4,6 +4,7 @@
 //! - Version listing and availability
 //! - Offline mode behavior
 //! - ABI compatibility checking
+//! - Source builds against a stub CPython repository
 //!
 //! Note: Actual download tests are skipped in CI to avoid network dependencies.
 //! They can be run locally with `cargo test -- --ignored`
@@ -60,10 +61,136 @@ fn python_list_json_output() {
     assert!(json["detail"].get("available").is_some());
 }
 
+#[test]
+#[cfg(unix)]
+fn python_list_json_reports_lifecycle_sizes_and_project_fit() {
+    let home = TempDir::new().unwrap();
+    let project = TempDir::new().unwrap();
+    let bin_dir = home.path().join("python/3.11.10/python/bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    std::fs::write(bin_dir.join("python3"), b"#!/bin/sh\n").unwrap();
+    std::fs::write(
+        project.path().join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nrequires-python = \">=3.10\"\n",
+    )
+    .unwrap();
+    let venv = project.path().join(".venv");
+    std::fs::create_dir_all(venv.join("bin")).unwrap();
+    std::fs::write(venv.join("bin/python"), b"").unwrap();
+    std::fs::write(
+        venv.join("pyvenv.cfg"),
+        format!("home = {}\n", bin_dir.display()),
+    )
+    .unwrap();
+
+    let output = pybun()
+        .env("PYBUN_HOME", home.path())
+        .current_dir(project.path())
+        .args(["--format=json", "python", "list", "--all"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["requires_python"], ">=3.10");
+    let versions = json["detail"]["versions"].as_array().unwrap();
+    let find = |prefix: &str| {
+        versions
+            .iter()
+            .find(|v| v["version"].as_str().unwrap().starts_with(prefix))
+            .unwrap()
+    };
+
+    let py311 = find("3.11");
+    assert_eq!(py311["installed"], true);
+    assert_eq!(py311["release_date"], "2024-09-07");
+    assert_eq!(py311["end_of_life"], "2027-10-31");
+    assert!(py311["support_status"].is_string());
+    assert!(py311["installed_size_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(py311["satisfies_requires_python"], true);
+    let used_by = py311["used_by"].as_array().unwrap();
+    assert_eq!(used_by.len(), 1);
+
+    let py39 = find("3.9");
+    assert_eq!(py39["installed"], false);
+    assert_eq!(py39["satisfies_requires_python"], false);
+    assert!(py39["download_size_bytes"].is_null());
+    assert!(py39["used_by"].as_array().unwrap().is_empty());
+}
+
 // ---------------------------------------------------------------------------
 // pybun python which
 // ---------------------------------------------------------------------------
 
+#[test]
+fn python_list_tsv_output_with_columns() {
+    let output = pybun()
+        .args([
+            "--format=tsv",
+            "--columns=version,installed",
+            "python",
+            "list",
+            "--all",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("version\tinstalled"));
+    let rows: Vec<&str> = lines.collect();
+    assert!(!rows.is_empty());
+    for row in rows {
+        let fields: Vec<&str> = row.split('\t').collect();
+        assert_eq!(fields.len(), 2);
+        assert!(fields[0].starts_with("3.") || fields[0].starts_with("pypy3."));
+        assert!(fields[1] == "true" || fields[1] == "false");
+    }
+}
+
+#[test]
+fn python_list_reports_interpreter_flavors() {
+    let temp = TempDir::new().unwrap();
+    let output = pybun()
+        .env("PYBUN_HOME", temp.path())
+        .args(["--format=json", "python", "list", "--all"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let versions = json["detail"]["versions"].as_array().unwrap();
+    let find = |name: &str| {
+        versions
+            .iter()
+            .find(|v| v["version"] == name)
+            .unwrap_or_else(|| panic!("{name} not listed: {json}"))
+    };
+    let free_threaded = find("3.13.0t");
+    assert_eq!(free_threaded["free_threaded"], true);
+    assert_eq!(free_threaded["interpreter_tag"], "cp313t");
+    let pypy = find("pypy3.10.14");
+    assert_eq!(pypy["implementation"], "pypy");
+    assert_eq!(pypy["abi_tag"], "pypy310_pp73");
+    assert!(pypy["support_status"].is_null());
+    assert_eq!(find("3.12.7")["interpreter_tag"], "cp312");
+}
+
+#[test]
+fn python_list_rejects_unknown_column() {
+    pybun()
+        .args(["--format=tsv", "--columns=sizes", "python", "list"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("unknown column 'sizes'"));
+}
+
+#[test]
+fn tsv_format_rejected_for_non_list_command() {
+    pybun()
+        .args(["--format=tsv", "python", "which"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("does not produce tabular output"));
+}
+
 #[test]
 fn python_which_shows_default_python() {
     // This test requires Python to be installed on the system
@@ -99,6 +226,96 @@ fn python_which_json_output() {
     }
 }
 
+// ---------------------------------------------------------------------------
+// pybun python pin
+// ---------------------------------------------------------------------------
+
+fn pin_json(home: &std::path::Path, project: &std::path::Path, args: &[&str]) -> serde_json::Value {
+    let output = pybun()
+        .current_dir(project)
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_ENV")
+        .env_remove("PYBUN_PYTHON")
+        .args(["--format=json", "--offline"])
+        .args(args)
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).expect("valid JSON")
+}
+
+fn diagnostic_codes(json: &serde_json::Value) -> Vec<String> {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str().map(str::to_string))
+        .collect()
+}
+
+#[test]
+fn python_pin_writes_python_version_and_checks_requires_python() {
+    let home = TempDir::new().unwrap();
+    let project = TempDir::new().unwrap();
+    std::fs::write(
+        project.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\nrequires-python = \">=3.11\"\n",
+    )
+    .unwrap();
+    let subdir = project.path().join("src");
+    std::fs::create_dir_all(&subdir).unwrap();
+    let pin_file = project.path().join(".python-version");
+
+    let json = pin_json(home.path(), &subdir, &["python", "pin", "3.12"]);
+    assert_eq!(json["status"], "ok", "{json}");
+    assert_eq!(std::fs::read_to_string(&pin_file).unwrap(), "3.12\n");
+    assert_eq!(json["detail"]["runtime"], "3.12.7");
+    assert_eq!(json["detail"]["installed"], false);
+    assert_eq!(json["detail"]["conflict"], false);
+
+    let json = pin_json(home.path(), &subdir, &["python", "pin", "3.10"]);
+    assert_eq!(json["status"], "error", "{json}");
+    assert!(diagnostic_codes(&json).contains(&"E_PYTHON_PIN_FAILED".to_string()));
+    assert_eq!(std::fs::read_to_string(&pin_file).unwrap(), "3.12\n");
+
+    let json = pin_json(home.path(), &subdir, &["python", "pin", "3.10", "--force"]);
+    assert_eq!(json["status"], "ok", "{json}");
+    assert!(diagnostic_codes(&json).contains(&"W_PYTHON_PIN_CONFLICT".to_string()));
+
+    let json = pin_json(home.path(), &subdir, &["python", "pin"]);
+    assert_eq!(json["detail"]["version"], "3.10", "{json}");
+    assert_eq!(json["detail"]["pinned"], false);
+    assert_eq!(json["detail"]["conflict"], true);
+
+    // `pybun run` reports the conflict before running anything.
+    let json = pin_json(home.path(), project.path(), &["run", "-c", "pass"]);
+    assert!(
+        diagnostic_codes(&json).contains(&"W_PYTHON_PIN_CONFLICT".to_string()),
+        "{json}"
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn pinned_managed_runtime_is_selected() {
+    let home = TempDir::new().unwrap();
+    let project = TempDir::new().unwrap();
+    let python = home.path().join("python/3.12.7/python/bin/python3");
+    std::fs::create_dir_all(python.parent().unwrap()).unwrap();
+    std::fs::write(&python, "").unwrap();
+
+    let json = pin_json(home.path(), project.path(), &["python", "pin", "3.12"]);
+    assert_eq!(json["detail"]["installed"], true, "{json}");
+    assert_eq!(json["detail"]["runtime"], "3.12.7");
+
+    let json = pin_json(home.path(), project.path(), &["python", "which"]);
+    assert_eq!(
+        json["detail"]["path"],
+        python.display().to_string(),
+        "{json}"
+    );
+    assert_eq!(json["detail"]["managed"], true);
+}
+
 // ---------------------------------------------------------------------------
 // pybun python install (offline mode behavior)
 // ---------------------------------------------------------------------------
@@ -116,6 +333,94 @@ fn python_install_unsupported_version_fails() {
         .stdout(predicate::str::contains("not supported"));
 }
 
+/// Release `20241016` as the releases API and download mirror `server`
+/// publish it: the 3.12.7 archive for this platform weighs 4321 bytes and
+/// has PyBun's built-in checksum, but the mirror serves other bytes.
+fn tampered_release(server: &httpmock::MockServer) -> String {
+    use httpmock::prelude::*;
+    let info = pybun::runtime::find_version("3.12.7").unwrap();
+    let archive = pybun::runtime::archive_name(&info).unwrap();
+    let platform = pybun::runtime::Platform::current().unwrap();
+    let sha256 = info.checksums[platform.checksum_key()].clone();
+    server.mock(|when, then| {
+        when.method(GET).path("/releases/tags/20241016");
+        then.status(200).json_body(serde_json::json!({
+            "published_at": "2024-10-16T21:04:15Z",
+            "assets": [{ "name": archive, "size": 4321, "digest": format!("sha256:{sha256}") }]
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/download/20241016/{archive}"));
+        then.status(200).body("not a python runtime");
+    });
+    sha256
+}
+
+#[test]
+fn python_list_reports_sizes_and_build_dates_from_release_manifest() {
+    let server = httpmock::MockServer::start();
+    let sha256 = tampered_release(&server);
+    let temp = TempDir::new().unwrap();
+    let list = |fetch: bool| {
+        let mut cmd = pybun();
+        cmd.env("PYBUN_HOME", temp.path())
+            .env("PYBUN_PYTHON_RELEASES_API", server.url("/releases"))
+            .args(["--format=json", "python", "list", "--all"]);
+        if fetch {
+            cmd.arg("--fetch-sizes");
+        }
+        let output = cmd.output().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        json["detail"]["versions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|v| v["version"] == "3.12.7")
+            .unwrap()
+            .clone()
+    };
+
+    assert!(list(false)["download_size_bytes"].is_null());
+    let py312 = list(true);
+    assert_eq!(py312["download_size_bytes"], 4321);
+    assert_eq!(py312["build_date"], "2024-10-16");
+    assert_eq!(py312["archive_sha256"], sha256.as_str());
+    // The manifest is cached, so listing again needs no flag.
+    assert_eq!(list(false)["download_size_bytes"], 4321);
+}
+
+#[test]
+fn python_install_rejects_archive_not_matching_its_checksum() {
+    let server = httpmock::MockServer::start();
+    tampered_release(&server);
+    let temp = TempDir::new().unwrap();
+
+    let output = pybun()
+        .env("PYBUN_HOME", temp.path())
+        .env("PYBUN_PYTHON_RELEASES_API", server.url("/releases"))
+        .env("PYBUN_PYTHON_MIRROR", server.url("/download"))
+        .args(["--format=json", "python", "install", "3.12.7"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(
+        json.to_string()
+            .contains("Checksum mismatch for Python 3.12.7"),
+        "{json}"
+    );
+    let events: Vec<&str> = json["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e["type"].as_str())
+        .collect();
+    assert!(events.contains(&"download_start"), "{events:?}");
+    assert!(!events.contains(&"download_complete"), "{events:?}");
+    assert!(!temp.path().join("python/3.12.7").exists());
+}
+
 // ---------------------------------------------------------------------------
 // pybun python remove (not installed)
 // ---------------------------------------------------------------------------
@@ -133,6 +438,55 @@ fn python_remove_not_installed_fails() {
         .stdout(predicate::str::contains("not installed"));
 }
 
+#[test]
+#[cfg(unix)]
+fn python_remove_protects_runtimes_in_use() {
+    let home = TempDir::new().unwrap();
+    let project = TempDir::new().unwrap();
+    let bin_dir = home.path().join("python/3.11.10/python/bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    std::fs::write(bin_dir.join("python3"), b"").unwrap();
+    let script_env = home.path().join("pep723-envs/report-0123/venv");
+    std::fs::create_dir_all(script_env.join("bin")).unwrap();
+    std::fs::write(script_env.join("bin/python"), b"").unwrap();
+    std::fs::write(
+        script_env.join("pyvenv.cfg"),
+        format!("home = {}\n", bin_dir.display()),
+    )
+    .unwrap();
+
+    let remove = |force: bool| {
+        let mut cmd = pybun();
+        cmd.current_dir(project.path())
+            .env("PYBUN_HOME", home.path())
+            .args(["--format=json", "python", "remove", "3.11.10"]);
+        if force {
+            cmd.arg("--force");
+        }
+        let output = cmd.output().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        (json, output.status.success())
+    };
+
+    let (json, ok) = remove(false);
+    assert!(!ok, "{json}");
+    assert_eq!(json["detail"]["status"], "in_use");
+    assert_eq!(json["detail"]["dependents"][0]["kind"], "script_env");
+    assert_eq!(
+        json["detail"]["dependents"][0]["path"],
+        script_env.display().to_string()
+    );
+    assert_eq!(json["diagnostics"][0]["code"], "E_PYTHON_RUNTIME_IN_USE");
+    assert!(bin_dir.exists());
+
+    let (json, ok) = remove(true);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["status"], "removed");
+    assert_eq!(json["detail"]["dependents"].as_array().unwrap().len(), 1);
+    assert_eq!(json["diagnostics"][0]["code"], "W_PYTHON_RUNTIME_IN_USE");
+    assert!(!bin_dir.exists());
+}
+
 // ---------------------------------------------------------------------------
 // ABI compatibility tests (unit-level, not E2E)
 // ---------------------------------------------------------------------------
@@ -234,3 +588,259 @@ fn python_reuse_from_cache() {
         .success()
         .stdout(predicate::str::contains("already installed"));
 }
+
+// ---------------------------------------------------------------------------
+// pybun python install --from-source
+// ---------------------------------------------------------------------------
+
+/// A git repository standing in for CPython: `configure` writes a Makefile
+/// whose `install` target copies a stub `python3` into the prefix.
+#[cfg(unix)]
+fn fake_cpython_repo(dir: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let write_executable = |name: &str, content: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    };
+    std::fs::create_dir_all(dir).unwrap();
+    write_executable(
+        "configure",
+        r#"#!/bin/sh
+src=$(cd "$(dirname "$0")" && pwd)
+for arg in "$@"; do
+  case "$arg" in --prefix=*) prefix="${arg#--prefix=}" ;; esac
+done
+printf 'all:\n\ttouch built\ninstall:\n\tmkdir -p %s/bin\n\tcp %s/python3 %s/bin/python3\n' "$prefix" "$src" "$prefix" > Makefile
+"#,
+    );
+    write_executable("python3", "#!/bin/sh\necho 'Python 3.99.0a0+'\n");
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "git {args:?}: {status:?}");
+    };
+    git(&["init", "--quiet", "-b", "main"]);
+    git(&["add", "."]);
+    git(&["commit", "--quiet", "-m", "stub"]);
+}
+
+#[test]
+#[cfg(unix)]
+fn python_install_from_source_builds_and_registers_runtime() {
+    let temp = TempDir::new().unwrap();
+    let repo = temp.path().join("cpython");
+    let home = temp.path().join("home");
+    fake_cpython_repo(&repo);
+
+    let install = || {
+        let output = pybun()
+            .env("PYBUN_HOME", &home)
+            .env("PYBUN_CPYTHON_REPO", &repo)
+            .args([
+                "--format=json",
+                "python",
+                "install",
+                "3.99",
+                "--from-source",
+                "--ref",
+                "main",
+                "--pydebug",
+            ])
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let json = install();
+    assert_eq!(json["detail"]["status"], "built");
+    assert_eq!(json["detail"]["version"], "3.99+main.debug");
+    let build = &json["detail"]["source_build"];
+    assert_eq!(build["git_ref"], "main");
+    assert_eq!(build["configure_flags"][0], "--with-pydebug");
+    assert_eq!(build["commit"].as_str().unwrap().len(), 40);
+
+    // Same commit and flags: nothing is rebuilt.
+    assert_eq!(install()["detail"]["status"], "already_installed");
+
+    let output = pybun()
+        .env("PYBUN_HOME", &home)
+        .args(["--format=json", "python", "list"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let version = &json["detail"]["versions"][0];
+    assert_eq!(version["version"], "3.99+main.debug");
+    assert_eq!(version["source_build"]["build_key"], build["build_key"]);
+}
+
+#[test]
+fn python_install_build_flags_require_from_source() {
+    pybun()
+        .args(["python", "install", "3.12", "--pydebug"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--from-source"));
+}
+
+// ---------------------------------------------------------------------------
+// Managed runtimes for PEP 723 and `pybun x` environments
+// ---------------------------------------------------------------------------
+
+/// Register the PATH Python as managed runtime `version` under `home`.
+#[cfg(unix)]
+fn fake_managed_runtime(home: &std::path::Path, version: &str) -> std::path::PathBuf {
+    let bin = home.join("python").join(version).join("python/bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let python = which_python();
+    std::os::unix::fs::symlink(&python, bin.join("python3")).unwrap();
+    bin.join("python3")
+}
+
+#[cfg(unix)]
+fn which_python() -> std::path::PathBuf {
+    let output = std::process::Command::new("python3")
+        .args(["-c", "import sys; print(sys.executable)"])
+        .output()
+        .expect("python3 on PATH");
+    std::path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+#[test]
+#[cfg(unix)]
+fn pep723_requires_python_selects_managed_runtime() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    fake_managed_runtime(&home, "3.9.20");
+    let script = temp.path().join("old.py");
+    std::fs::write(
+        &script,
+        "# /// script\n# requires-python = \"<3.10\"\n# dependencies = [\"cowsay\"]\n# ///\nprint('ok')\n",
+    )
+    .unwrap();
+
+    pybun()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", &home)
+        .env("PYBUN_PEP723_DRY_RUN", "1")
+        .env_remove("PYBUN_ENV")
+        .env_remove("PYBUN_PYTHON")
+        .arg("run")
+        .arg(&script)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "using Python from managed runtime 3.9.20",
+        ));
+}
+
+#[test]
+#[cfg(unix)]
+fn run_and_x_accept_python_flag() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    fake_managed_runtime(&home, "3.12.7");
+    let script = temp.path().join("tool.py");
+    std::fs::write(
+        &script,
+        "# /// script\n# dependencies = [\"cowsay\"]\n# ///\nprint('ok')\n",
+    )
+    .unwrap();
+
+    pybun()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", &home)
+        .env("PYBUN_PEP723_DRY_RUN", "1")
+        .args(["run", "--python", "3.12"])
+        .arg(&script)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "using Python from managed runtime 3.12.7",
+        ));
+
+    let output = pybun()
+        .env("PYBUN_HOME", &home)
+        .env("PYBUN_X_DRY_RUN", "1")
+        .args(["--format=json", "x", "--python", "3.12", "cowsay"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["python_version"], "3.12.7");
+}
+
+#[test]
+fn python_flag_conflicting_with_requires_python_fails() {
+    let temp = TempDir::new().unwrap();
+    let script = temp.path().join("new.py");
+    std::fs::write(
+        &script,
+        "# /// script\n# requires-python = \">=3.12\"\n# dependencies = [\"cowsay\"]\n# ///\nprint('ok')\n",
+    )
+    .unwrap();
+
+    pybun()
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PEP723_DRY_RUN", "1")
+        .args(["run", "--python", "3.10"])
+        .arg(&script)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Python 3.10.15 does not satisfy requires-python >=3.12",
+        ));
+}
+
+#[test]
+fn unsatisfiable_requires_python_reports_install_fix() {
+    let temp = TempDir::new().unwrap();
+    let script = temp.path().join("legacy.py");
+    std::fs::write(
+        &script,
+        "# /// script\n# requires-python = \">=3.9,<3.10\"\n# dependencies = [\"cowsay\"]\n# ///\nprint('ok')\n",
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PEP723_DRY_RUN", "1")
+        .env_remove("PYBUN_ENV")
+        .env_remove("PYBUN_PYTHON")
+        .args(["--offline", "--format=json", "run"])
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_RUN_PYTHON_INCOMPATIBLE")
+        .unwrap_or_else(|| panic!("incompatible-Python diagnostic: {json}"));
+    assert_eq!(diagnostic["context"]["requires_python"], ">=3.9,<3.10");
+    assert_eq!(diagnostic["context"]["candidate"], "3.9");
+    assert_eq!(
+        diagnostic["fix_candidates"][0]["command"],
+        "pybun python install 3.9"
+    );
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|d| d["code"] != "E_RUN_FAILED"),
+        "{json}"
+    );
+}
@@ -308,7 +308,8 @@ fn sandbox_allow_read_permits_allowed_path() {
         script.to_str().unwrap(),
     ])
     .success()
-    .stdout(predicate::str::contains("\"exit_code\":0"));
+    .stdout(predicate::str::contains("\"exit_code\":0"))
+    .stdout(predicate::str::contains("+reads-shim-only"));
 }
 
 #[test]
@@ -1103,3 +1104,91 @@ assert blocked == len(calls), "expected all os.spawn* variants to be blocked, go
     .success()
     .stdout(predicate::str::contains("\"blocked_subprocesses\":8"));
 }
+
+#[test]
+fn sandbox_allow_host_limits_network_to_listed_hosts() {
+    let temp = tempdir().unwrap();
+    let script = temp.path().join("hosts.py");
+    fs::write(
+        &script,
+        r#"
+import socket
+try:
+    socket.getaddrinfo("blocked.example", 80)
+except PermissionError:
+    print("blocked.example refused")
+"#,
+    )
+    .unwrap();
+
+    run_sandbox(&[
+        "--format=json",
+        "run",
+        "--sandbox",
+        "--allow-host=pypi.org",
+        script.to_str().unwrap(),
+    ])
+    .success()
+    .stdout(predicate::str::contains("blocked.example refused"))
+    .stdout(predicate::str::contains("\"allow_network\":true"))
+    .stdout(predicate::str::contains("\"allow_host\":[\"pypi.org\"]"))
+    .stdout(predicate::str::contains(
+        "\"blocked_hosts\":[\"blocked.example\"]",
+    ));
+}
+
+/// Native code bypasses the Python shim; where the kernel confines the
+/// process, raw sockets and writes outside the project, the environment and
+/// the temp dir fail too.
+#[test]
+fn sandbox_kernel_confinement_covers_native_calls() {
+    let base = tempdir().unwrap();
+    let project = base.path().join("project");
+    let tmp = base.path().join("tmp");
+    let outside = base.path().join("outside");
+    for dir in [&project, &tmp, &outside] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(
+        project.join("native.py"),
+        format!(
+            r#"
+import _socket
+import os
+
+def attempt(label, call):
+    try:
+        call()
+        print(label, "allowed")
+    except PermissionError:
+        print(label, "denied")
+
+attempt("socket", lambda: _socket.socket())
+attempt("outside", lambda: os.open({outside:?}, os.O_WRONLY | os.O_CREAT))
+attempt("project", lambda: os.open("written.txt", os.O_WRONLY | os.O_CREAT))
+"#,
+            outside = outside.join("escape.txt").to_str().unwrap()
+        ),
+    )
+    .unwrap();
+
+    let output = bin()
+        .current_dir(&project)
+        .env("TMPDIR", &tmp)
+        .args(["--format=json", "run", "--sandbox", "native.py"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let detail = &json["detail"];
+    let enforcement = detail["sandbox"]["enforcement"].as_str().unwrap();
+    assert!(enforcement.starts_with("python-sitecustomize"), "{json}");
+    let stdout = detail["stdout"].as_str().unwrap_or_default();
+    assert!(stdout.contains("project allowed"), "{json}");
+    if enforcement.contains("seccomp") || enforcement.contains("sandbox-exec") {
+        assert!(stdout.contains("socket denied"), "{json}");
+    }
+    if enforcement.contains("landlock") || enforcement.contains("sandbox-exec") {
+        assert!(stdout.contains("outside denied"), "{json}");
+        assert!(!outside.join("escape.txt").exists());
+    }
+}
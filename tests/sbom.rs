@@ -0,0 +1,135 @@
+//! `pybun sbom` writes CycloneDX and SPDX documents from the lockfile, with
+//! licenses from the installed environment.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::{Lockfile, Package, PackageSource};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+    Package {
+        name: name.into(),
+        version: version.into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: format!("{name}-{version}-py3-none-any.whl"),
+        hash: format!("sha256:{}", "cd".repeat(32)),
+        dependencies: deps.iter().map(|d| d.to_string()).collect(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
+    }
+}
+
+/// Project `demo` depending on `requests`, which depends on `urllib3`, with
+/// a venv where `urllib3` is installed.
+fn setup_project(root: &Path) {
+    fs::write(
+        root.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"requests>=2.31\"]\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+    lock.add_package(package("requests", "2.32.0", &["urllib3>=1.21"]));
+    lock.add_package(package("urllib3", "2.2.0", &[]));
+    lock.save_to_path(root.join("pybun.lockb")).unwrap();
+
+    let dist_info = root.join(".venv/lib/python3.11/site-packages/urllib3-2.2.0.dist-info");
+    fs::create_dir_all(&dist_info).unwrap();
+    fs::write(
+        dist_info.join("METADATA"),
+        "Metadata-Version: 2.4\nName: urllib3\nVersion: 2.2.0\nLicense-Expression: MIT\n",
+    )
+    .unwrap();
+}
+
+fn pybun(dir: &Path, args: &[&str]) -> (bool, Value) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .env_remove("PYBUN_ENV")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+#[test]
+fn cyclonedx_sbom_lists_locked_packages_and_dependencies() {
+    let temp = tempdir().unwrap();
+    setup_project(temp.path());
+
+    let (ok, json) = pybun(temp.path(), &["sbom", "--venv", ".venv"]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["components"], 2);
+    let bom = &json["detail"]["document"];
+    assert_eq!(bom["bomFormat"], "CycloneDX");
+    assert_eq!(bom["metadata"]["component"]["name"], "demo");
+
+    let urllib3 = bom["components"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "urllib3")
+        .unwrap();
+    assert_eq!(urllib3["purl"], "pkg:pypi/urllib3@2.2.0");
+    assert_eq!(urllib3["licenses"], json!([{ "expression": "MIT" }]));
+    assert_eq!(urllib3["hashes"][0]["alg"], "SHA-256");
+
+    let requests = bom["dependencies"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["ref"] == "pkg:pypi/requests@2.32.0")
+        .unwrap();
+    assert_eq!(requests["dependsOn"], json!(["pkg:pypi/urllib3@2.2.0"]));
+}
+
+#[test]
+fn spdx_sbom_is_written_to_a_file() {
+    let temp = tempdir().unwrap();
+    setup_project(temp.path());
+
+    let (ok, json) = pybun(
+        temp.path(),
+        &["sbom", "--export", "spdx", "--output", "out/sbom.spdx.json"],
+    );
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["format"], "spdx");
+    let doc: Value =
+        serde_json::from_str(&fs::read_to_string(temp.path().join("out/sbom.spdx.json")).unwrap())
+            .unwrap();
+    assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+    assert!(
+        doc["relationships"].as_array().unwrap().iter().any(|r| {
+            r["spdxElementId"] == "SPDXRef-Package-requests"
+                && r["relationshipType"] == "DEPENDS_ON"
+                && r["relatedSpdxElement"] == "SPDXRef-Package-urllib3"
+        }),
+        "{doc}"
+    );
+}
+
+#[test]
+fn sbom_requires_a_lockfile() {
+    let temp = tempdir().unwrap();
+    let (ok, json) = pybun(temp.path(), &["sbom"]);
+    assert!(!ok);
+    assert_eq!(
+        json["diagnostics"][0]["code"], "E_LOCKFILE_NOT_FOUND",
+        "{json}"
+    );
+}
@@ -0,0 +1,198 @@
+//! `pybun script lock` keeps a PEP 723 script's `<script>.lock` in step with
+//! its declared dependencies; `pybun run` warns when they diverge.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::Lockfile;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn script_lock_path(script: &Path) -> PathBuf {
+    let mut lock_path = script.as_os_str().to_os_string();
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
+
+fn multi_version_index() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/index_multi_version.json")
+}
+
+fn write_script(path: &Path, dependency: &str) {
+    fs::write(
+        path,
+        format!("# /// script\n# dependencies = [\"{dependency}\"]\n# ///\nprint('hi')\n"),
+    )
+    .unwrap();
+}
+
+fn pybun(home: &Path, args: &[&str]) -> (bool, Value) {
+    let output = cargo_bin_cmd!("pybun")
+        .env("PYBUN_HOME", home)
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (output.status.success(), json)
+}
+
+fn locked_version(script: &Path, name: &str) -> String {
+    Lockfile::load_from_path(script_lock_path(script))
+        .unwrap()
+        .packages[name]
+        .version
+        .clone()
+}
+
+#[test]
+fn script_lock_check_detects_drift_and_relock_fixes_it() {
+    let temp = tempdir().unwrap();
+    let script = temp.path().join("tool.py");
+    let script_arg = script.to_str().unwrap();
+    let index = multi_version_index();
+    let index_arg = index.to_str().unwrap();
+    write_script(&script, "lib<2");
+
+    let (ok, json) = pybun(temp.path(), &["script", "lock", "--check", script_arg]);
+    assert!(!ok);
+    assert_eq!(json["diagnostics"][0]["code"], "E_SCRIPT_LOCK_MISSING");
+
+    let (ok, json) = pybun(
+        temp.path(),
+        &["script", "lock", script_arg, "--index", index_arg],
+    );
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["updated"], true);
+    assert_eq!(locked_version(&script, "lib"), "1.5.0");
+
+    let (ok, json) = pybun(temp.path(), &["script", "lock", "--check", script_arg]);
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["up_to_date"], true);
+
+    write_script(&script, "lib>=2");
+    let (ok, json) = pybun(temp.path(), &["script", "lock", "--check", script_arg]);
+    assert!(!ok);
+    let diagnostic = &json["diagnostics"][0];
+    assert_eq!(diagnostic["code"], "E_SCRIPT_LOCK_DRIFT");
+    assert_eq!(
+        json["detail"]["drift"]["unsatisfied"][0]["locked_version"],
+        "1.5.0"
+    );
+
+    let (ok, json) = pybun(
+        temp.path(),
+        &["script", "lock", script_arg, "--index", index_arg],
+    );
+    assert!(ok, "{json}");
+    assert_eq!(
+        json["detail"]["changes"],
+        json!([{ "name": "lib", "from": "1.5.0", "to": "2.0.0" }])
+    );
+}
+
+#[test]
+fn script_lock_upgrade_rewrites_a_satisfied_lock() {
+    let temp = tempdir().unwrap();
+    let script = temp.path().join("tool.py");
+    let script_arg = script.to_str().unwrap();
+    let old_index = temp.path().join("old-index.json");
+    fs::write(
+        &old_index,
+        json!([{
+            "name": "lib",
+            "version": "1.0.0",
+            "dependencies": [],
+            "wheels": [{ "file": "lib-1.0.0-py3-none-any.whl", "hash": "sha256:lib100" }]
+        }])
+        .to_string(),
+    )
+    .unwrap();
+    write_script(&script, "lib");
+
+    let (ok, _) = pybun(
+        temp.path(),
+        &[
+            "script",
+            "lock",
+            script_arg,
+            "--index",
+            old_index.to_str().unwrap(),
+        ],
+    );
+    assert!(ok);
+    assert_eq!(locked_version(&script, "lib"), "1.0.0");
+
+    let index = multi_version_index();
+    let (ok, json) = pybun(
+        temp.path(),
+        &[
+            "script",
+            "lock",
+            script_arg,
+            "--index",
+            index.to_str().unwrap(),
+        ],
+    );
+    assert!(ok);
+    assert_eq!(json["detail"]["updated"], false, "satisfied lock is kept");
+    assert_eq!(locked_version(&script, "lib"), "1.0.0");
+
+    let (ok, json) = pybun(
+        temp.path(),
+        &[
+            "script",
+            "lock",
+            "--upgrade",
+            script_arg,
+            "--index",
+            index.to_str().unwrap(),
+        ],
+    );
+    assert!(ok, "{json}");
+    assert_eq!(json["detail"]["updated"], true);
+    assert_eq!(locked_version(&script, "lib"), "2.0.0");
+}
+
+#[test]
+fn run_warns_when_script_lock_drifts() {
+    let temp = tempdir().unwrap();
+    let script = temp.path().join("tool.py");
+    let script_arg = script.to_str().unwrap();
+    let index = multi_version_index();
+    write_script(&script, "lib<2");
+    let (ok, _) = pybun(
+        temp.path(),
+        &[
+            "script",
+            "lock",
+            script_arg,
+            "--index",
+            index.to_str().unwrap(),
+        ],
+    );
+    assert!(ok);
+    write_script(&script, "app");
+
+    let output = cargo_bin_cmd!("pybun")
+        .env("PYBUN_HOME", temp.path())
+        .env("PYBUN_PEP723_DRY_RUN", "1")
+        .args(["--format=json", "run", script_arg])
+        .output()
+        .unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let drift = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "W_SCRIPT_LOCK_DRIFT")
+        .expect("drift diagnostic");
+    assert_eq!(drift["context"]["missing"], json!(["app"]));
+    assert_eq!(drift["context"]["stale"], json!(["lib"]));
+}
@@ -0,0 +1,146 @@
+//! Dependency metadata for sdist-only releases during resolution.
+
+use httpmock::prelude::*;
+use pybun::pypi::{PyPiClient, PyPiIndex};
+use pybun::resolver::PackageIndex;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tempfile::tempdir;
+
+fn sdist_bytes(root: &str, files: &[(&str, &str)]) -> Vec<u8> {
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for (path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{root}/{path}"), content.as_bytes())
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+/// Mock an sdist-only `demo 1.0` release with no `requires_dist` in the index.
+fn mock_sdist_release(server: &MockServer, sdist: Vec<u8>) -> httpmock::Mock<'_> {
+    let base = server.base_url();
+    let sha256 = hex::encode(Sha256::digest(&sdist));
+    let file = json!({
+        "filename": "demo-1.0.tar.gz",
+        "packagetype": "sdist",
+        "url": format!("{}/files/demo-1.0.tar.gz", base),
+        "yanked": false,
+        "digests": { "sha256": sha256 }
+    });
+    let project = json!({
+        "info": { "name": "demo", "version": "1.0" },
+        "releases": { "1.0": [file.clone()] }
+    })
+    .to_string();
+    let version = json!({
+        "info": { "name": "demo", "version": "1.0", "requires_dist": null },
+        "urls": [file]
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/demo/json");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(project.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/demo/1.0/json");
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(version.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/files/demo-1.0.tar.gz");
+        then.status(200).body(sdist.clone());
+    })
+}
+
+fn dependency_names(pkg: &pybun::resolver::ResolvedPackage) -> Vec<String> {
+    pkg.dependencies.iter().map(ToString::to_string).collect()
+}
+
+fn python3() -> &'static Path {
+    Path::new("python3")
+}
+
+#[tokio::test]
+async fn static_pkg_info_is_used_without_building() {
+    let temp = tempdir().unwrap();
+    let server = MockServer::start();
+    let sdist = sdist_bytes(
+        "demo-1.0",
+        &[(
+            "PKG-INFO",
+            "Metadata-Version: 2.2\nName: demo\nVersion: 1.0\nRequires-Dist: six>=1.0\n",
+        )],
+    );
+    let download = mock_sdist_release(&server, sdist);
+
+    let client = PyPiClient::with_config(&server.base_url(), temp.path().join("cache"), false)
+        .unwrap()
+        .with_sdist_python(temp.path().join("no-python-needed"));
+    let index = PyPiIndex::new(client);
+    let pkg = index.get("demo", "1.0").await.unwrap().unwrap();
+
+    assert_eq!(dependency_names(&pkg), vec!["six>=1.0"]);
+    assert!(index.dynamic_metadata_packages().is_empty());
+    assert_eq!(download.calls(), 1);
+}
+
+#[tokio::test]
+async fn dynamic_metadata_runs_backend_and_is_cached_by_hash() {
+    let temp = tempdir().unwrap();
+    let server = MockServer::start();
+    let backend = r#"
+import os
+
+def prepare_metadata_for_build_wheel(metadata_directory, config_settings=None):
+    dist_info = os.path.join(metadata_directory, "demo-1.0.dist-info")
+    os.makedirs(dist_info)
+    with open(os.path.join(dist_info, "METADATA"), "w") as f:
+        f.write("Metadata-Version: 2.1\nName: demo\nVersion: 1.0\nRequires-Dist: attrs\n")
+    return "demo-1.0.dist-info"
+"#;
+    let sdist = sdist_bytes(
+        "demo-1.0",
+        &[
+            (
+                "pyproject.toml",
+                "[build-system]\nrequires = []\nbuild-backend = \"backend\"\nbackend-path = [\".\"]\n",
+            ),
+            ("backend.py", backend),
+        ],
+    );
+    let download = mock_sdist_release(&server, sdist);
+    let cache_dir = temp.path().join("cache");
+
+    for _ in 0..2 {
+        let client = PyPiClient::with_config(&server.base_url(), cache_dir.clone(), false)
+            .unwrap()
+            .with_sdist_python(python3());
+        let index = PyPiIndex::new(client);
+        let pkg = index.get("demo", "1.0").await.unwrap().unwrap();
+        assert_eq!(dependency_names(&pkg), vec!["attrs"]);
+        assert_eq!(
+            index
+                .dynamic_metadata_packages()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["demo".to_string()]
+        );
+    }
+    assert_eq!(download.calls(), 1, "second run should hit the sdist cache");
+    assert_eq!(
+        std::fs::read_dir(cache_dir.join("sdist-metadata"))
+            .unwrap()
+            .count(),
+        1
+    );
+}
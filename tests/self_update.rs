@@ -174,6 +174,61 @@ fn write_manifest(path: &Path, asset: ManifestAsset<'_>) {
     fs::write(path, serde_json::to_string_pretty(&manifest).unwrap()).unwrap();
 }
 
+/// Add a `rollout` section to a manifest written by [`write_manifest`].
+fn set_rollout(path: &Path, rollout: serde_json::Value) {
+    let mut manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    manifest["rollout"] = rollout;
+    fs::write(path, serde_json::to_string_pretty(&manifest).unwrap()).unwrap();
+}
+
+/// Write a signed release of `new_bytes` as version 9.9.9 next to a fake
+/// installed binary containing `old-version-binary`. Returns
+/// `(manifest_path, current_binary)`.
+fn prepare_signed_release(root: &Path, new_bytes: &[u8]) -> (PathBuf, PathBuf) {
+    let target = current_release_target().expect("supported release target");
+    let manifest_path = root.join("pybun-release.json");
+    let current_binary = root.join(release_binary_name());
+    fs::write(&current_binary, b"old-version-binary").unwrap();
+    make_executable(&current_binary);
+
+    let archive_path = create_release_archive(root, &target, new_bytes);
+    let sha256 = archive_sha256(&archive_path);
+    let (signature, public_key) = sign_payload(&fs::read(&archive_path).unwrap());
+    write_manifest(
+        &manifest_path,
+        ManifestAsset {
+            target: &target,
+            version: "9.9.9",
+            asset_url: &file_url(&archive_path),
+            sha256: &sha256,
+            signature_type: "ed25519",
+            signature: &signature,
+            public_key: &public_key,
+        },
+    );
+    (manifest_path, current_binary)
+}
+
+fn run_self(root: &Path, manifest: &Path, binary: &Path, args: &[&str]) -> serde_json::Value {
+    let output = pybun_bin()
+        .env("PYBUN_SELF_UPDATE_MANIFEST", manifest)
+        .env("PYBUN_SELF_UPDATE_BIN", binary)
+        .env("PYBUN_HOME", root.join("home"))
+        .env("PYBUN_UPDATE_COHORT", "50")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
 #[test]
 fn self_update_help_shows_channel_option() {
     let output = pybun_bin()
@@ -480,6 +535,7 @@ fn self_update_applies_update_from_local_manifest() {
     let output = pybun_bin()
         .env("PYBUN_SELF_UPDATE_MANIFEST", &manifest_path)
         .env("PYBUN_SELF_UPDATE_BIN", &current_binary)
+        .env("PYBUN_HOME", temp.path().join("home"))
         .args(["--format=json", "self", "update"])
         .output()
         .unwrap();
@@ -527,6 +583,7 @@ fn self_update_rejects_signature_mismatch() {
     let output = pybun_bin()
         .env("PYBUN_SELF_UPDATE_MANIFEST", &manifest_path)
         .env("PYBUN_SELF_UPDATE_BIN", &current_binary)
+        .env("PYBUN_HOME", temp.path().join("home"))
         .args(["--format=json", "self", "update"])
         .output()
         .unwrap();
@@ -575,6 +632,7 @@ fn self_update_rolls_back_when_swap_fails() {
     let output = pybun_bin()
         .env("PYBUN_SELF_UPDATE_MANIFEST", &manifest_path)
         .env("PYBUN_SELF_UPDATE_BIN", &current_binary)
+        .env("PYBUN_HOME", temp.path().join("home"))
         .env("PYBUN_SELF_UPDATE_TEST_FAIL_SWAP", "1")
         .args(["--format=json", "self", "update"])
         .output()
@@ -623,6 +681,7 @@ fn self_update_applies_update_with_minisign_signature() {
     let output = pybun_bin()
         .env("PYBUN_SELF_UPDATE_MANIFEST", &manifest_path)
         .env("PYBUN_SELF_UPDATE_BIN", &current_binary)
+        .env("PYBUN_HOME", temp.path().join("home"))
         .args(["--format=json", "self", "update"])
         .output()
         .unwrap();
@@ -640,3 +699,95 @@ fn self_update_applies_update_with_minisign_signature() {
     assert_eq!(json["status"], "ok");
     assert_eq!(json["detail"]["update_applied"].as_bool(), Some(true));
 }
+
+#[test]
+fn self_update_defers_when_cohort_outside_rollout() {
+    let temp = tempdir().unwrap();
+    let (manifest, binary) = prepare_signed_release(temp.path(), b"new-version-binary");
+    set_rollout(&manifest, serde_json::json!({ "percentage": 10 }));
+
+    let json = run_self(temp.path(), &manifest, &binary, &["self", "update"]);
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["detail"]["update_available"], true);
+    assert_eq!(json["detail"]["update_applied"], false);
+    assert_eq!(json["detail"]["cohort"], 50);
+    assert_eq!(json["detail"]["rollout"]["decision"], "deferred");
+    assert_eq!(fs::read(&binary).unwrap(), b"old-version-binary");
+
+    let json = run_self(
+        temp.path(),
+        &manifest,
+        &binary,
+        &["self", "update", "--force"],
+    );
+    assert_eq!(json["detail"]["update_applied"], true);
+    assert_eq!(fs::read(&binary).unwrap(), b"new-version-binary");
+}
+
+#[test]
+fn self_update_respects_kill_switch_even_with_force() {
+    let temp = tempdir().unwrap();
+    let (manifest, binary) = prepare_signed_release(temp.path(), b"new-version-binary");
+    set_rollout(
+        &manifest,
+        serde_json::json!({ "percentage": 100, "halted": true }),
+    );
+
+    let json = run_self(
+        temp.path(),
+        &manifest,
+        &binary,
+        &["self", "update", "--force"],
+    );
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["detail"]["rollout"]["decision"], "halted");
+    assert_eq!(json["detail"]["update_applied"], false);
+    assert_eq!(fs::read(&binary).unwrap(), b"old-version-binary");
+}
+
+#[test]
+fn self_rollback_restores_previous_binary_and_holds_version_back() {
+    let temp = tempdir().unwrap();
+    let (manifest, binary) = prepare_signed_release(temp.path(), b"new-version-binary");
+
+    let json = run_self(temp.path(), &manifest, &binary, &["self", "update"]);
+    assert_eq!(json["detail"]["update_applied"], true);
+    assert!(json["detail"]["backup_path"].is_string());
+
+    let json = run_self(
+        temp.path(),
+        &manifest,
+        &binary,
+        &["self", "rollback", "--dry-run"],
+    );
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["detail"]["rolled_back"], false);
+    assert_eq!(fs::read(&binary).unwrap(), b"new-version-binary");
+
+    let json = run_self(temp.path(), &manifest, &binary, &["self", "rollback"]);
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["detail"]["from_version"], "9.9.9");
+    assert_eq!(fs::read(&binary).unwrap(), b"old-version-binary");
+
+    let json = run_self(temp.path(), &manifest, &binary, &["self", "history"]);
+    let history = json["detail"]["history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["action"], "update");
+    assert_eq!(history[1]["action"], "rollback");
+    assert_eq!(json["detail"]["held_back"], serde_json::json!(["9.9.9"]));
+
+    // The rolled-back release is skipped until --force.
+    let json = run_self(temp.path(), &manifest, &binary, &["self", "update"]);
+    assert_eq!(json["detail"]["held_back"], true);
+    assert_eq!(json["detail"]["update_applied"], false);
+    assert_eq!(fs::read(&binary).unwrap(), b"old-version-binary");
+}
+
+#[test]
+fn self_rollback_without_history_fails() {
+    let temp = tempdir().unwrap();
+    let (manifest, binary) = prepare_signed_release(temp.path(), b"new-version-binary");
+    let json = run_self(temp.path(), &manifest, &binary, &["self", "rollback"]);
+    assert_eq!(json["status"], "error");
+    assert_eq!(fs::read(&binary).unwrap(), b"old-version-binary");
+}
@@ -0,0 +1,154 @@
+//! E2E tests for the shell activation hook.
+//!
+//! Tests for `pybun hook install|status|enable|disable|allow|deny|export`.
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+fn pybun_with_home(home: &std::path::Path) -> Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.env("PYBUN_HOME", home.join(".pybun"))
+        .env("HOME", home)
+        .env_remove("PYBUN_HOOK")
+        .env_remove("PYBUN_HOOK_ACTIVE")
+        .env_remove("PYBUN_HOOK_VENV")
+        .env_remove("PYBUN_HOOK_BLOCKED")
+        .env_remove("PYBUN_HOOK_PREVIOUS_VIRTUAL_ENV")
+        .env_remove("VIRTUAL_ENV");
+    cmd
+}
+
+fn project_with_venv(temp: &TempDir) -> std::path::PathBuf {
+    let project = temp.path().join("demo");
+    std::fs::create_dir_all(project.join(".pybun/venv/bin")).unwrap();
+    std::fs::write(project.join(".pybun/venv/bin/python"), "").unwrap();
+    std::fs::write(
+        project.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\n",
+    )
+    .unwrap();
+    project
+}
+
+#[test]
+fn test_hook_install_writes_rc_block_once() {
+    let temp = TempDir::new().unwrap();
+    let rc = temp.path().join(".bashrc");
+
+    pybun_with_home(temp.path())
+        .args(["hook", "install", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("installed bash hook"));
+    pybun_with_home(temp.path())
+        .args(["hook", "install", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already installed"));
+
+    let content = std::fs::read_to_string(&rc).unwrap();
+    assert_eq!(content.matches("# >>> pybun hook >>>").count(), 1);
+    assert!(content.contains("hook export bash"));
+}
+
+#[test]
+fn test_hook_export_activates_project() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_venv(&temp);
+
+    pybun_with_home(temp.path())
+        .current_dir(&project)
+        .args(["hook", "allow"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Allowed the hook"));
+    pybun_with_home(temp.path())
+        .current_dir(&project)
+        .env("PATH", "/usr/bin:/bin")
+        .args(["hook", "export", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".pybun/venv/bin:/usr/bin:/bin"))
+        .stdout(predicate::str::contains(
+            "export PYBUN_PROMPT='(pybun:demo) '",
+        ));
+}
+
+#[test]
+fn test_hook_disable_deactivates_active_project() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_venv(&temp);
+
+    pybun_with_home(temp.path())
+        .args(["hook", "disable"])
+        .assert()
+        .success();
+
+    let venv_bin = project.join(".pybun/venv/bin");
+    pybun_with_home(temp.path())
+        .current_dir(&project)
+        .env("PATH", format!("{}:/usr/bin", venv_bin.display()))
+        .env("PYBUN_HOOK_ACTIVE", &project)
+        .env("PYBUN_HOOK_VENV", project.join(".pybun/venv"))
+        .args(["hook", "export", "zsh"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("export PATH='/usr/bin';"))
+        .stdout(predicate::str::contains("unset PYBUN_HOOK_ACTIVE;"));
+}
+
+#[test]
+fn test_hook_export_skips_projects_that_are_not_allowed() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_venv(&temp);
+
+    pybun_with_home(temp.path())
+        .current_dir(&project)
+        .env("PATH", "/usr/bin:/bin")
+        .args(["hook", "export", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("PATH").not())
+        .stdout(predicate::str::contains("pybun hook allow"));
+
+    pybun_with_home(temp.path())
+        .args(["hook", "allow"])
+        .arg(&project)
+        .assert()
+        .success();
+    pybun_with_home(temp.path())
+        .args(["hook", "deny"])
+        .arg(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Denied the hook"));
+    pybun_with_home(temp.path())
+        .current_dir(&project)
+        .env("PATH", "/usr/bin:/bin")
+        .args(["hook", "export", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("VIRTUAL_ENV").not());
+}
+
+#[test]
+fn test_hook_status_json() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_venv(&temp);
+
+    let output = pybun_with_home(temp.path())
+        .current_dir(&project)
+        .args(["--format=json", "hook", "status"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let detail = &json["detail"];
+    assert_eq!(detail["enabled"], true);
+    assert_eq!(detail["source"], "default");
+    assert_eq!(detail["shells"].as_array().unwrap().len(), 3);
+    assert!(detail["project"].as_str().unwrap().ends_with("demo"));
+    assert_eq!(detail["allowed"], false);
+}
@@ -0,0 +1,291 @@
+//! `pybun add`/`pybun install` refuse new dependencies that look like
+//! typosquats, are brand-new, or changed maintainers, unless
+//! `--allow-suspicious` is given or `pybun-policy.toml` approves them.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tempfile::tempdir;
+
+fn pybun(dir: &Path, index: &str, args: &[&str]) -> (Value, i32) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .env("PYBUN_PYPI_BASE_URL", index)
+        .env("PYBUN_PYPI_CACHE_DIR", dir.join("pypi-cache"))
+        .env_remove("PYBUN_INDEX_URL")
+        .env_remove("PYBUN_POLICY")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stdout: {} stderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (json, output.status.code().unwrap_or(-1))
+}
+
+fn diagnostics<'a>(json: &'a Value, code: &str) -> Vec<&'a Value> {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|d| d["code"] == code)
+        .collect()
+}
+
+fn project(dir: &Path, dependencies: &[&str]) -> String {
+    let text = format!(
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = {dependencies:?}\n"
+    );
+    fs::write(dir.join("pyproject.toml"), &text).unwrap();
+    text
+}
+
+/// `YYYY-MM-DD` of `days_ago` days before today.
+fn date_days_ago(days_ago: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let z = (now / 86_400 - days_ago) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// A PyPI JSON API serving `name` with `releases` of (version, upload
+/// date, author email).
+fn mock_project(server: &MockServer, name: &str, releases: &[(&str, &str, &str)]) {
+    let latest = releases.last().unwrap();
+    let files = |version: &str, date: &str| {
+        json!([{
+            "filename": format!("{name}-{version}-py3-none-any.whl"),
+            "packagetype": "bdist_wheel",
+            "url": format!("{}/files/{name}-{version}-py3-none-any.whl", server.base_url()),
+            "digests": { "sha256": "0".repeat(64) },
+            "upload_time_iso_8601": format!("{date}T12:00:00.000000Z"),
+        }])
+    };
+    let body = json!({
+        "info": { "name": name, "version": latest.0, "author_email": latest.2 },
+        "releases": releases
+            .iter()
+            .map(|(version, date, _)| (version.to_string(), files(version, date)))
+            .collect::<serde_json::Map<_, _>>(),
+    });
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/pypi/{name}/json"));
+        then.status(200).json_body(body);
+    });
+    for (version, _, email) in releases {
+        let body = json!({
+            "info": { "requires_dist": [], "author_email": email },
+            "urls": [],
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/pypi/{name}/{version}/json"));
+            then.status(200).json_body(body);
+        });
+    }
+}
+
+#[test]
+fn add_refuses_typosquats_before_touching_pyproject() {
+    let temp = tempdir().unwrap();
+    let before = project(temp.path(), &[]);
+
+    let (json, code) = pybun(temp.path(), "http://127.0.0.1:9", &["add", "reqeusts"]);
+    assert_ne!(code, 0);
+    let found = diagnostics(&json, "E_SUSPICIOUS_PACKAGE");
+    assert_eq!(found.len(), 1, "{json}");
+    assert_eq!(found[0]["context"]["kind"], "typosquat");
+    assert_eq!(found[0]["context"]["similar_to"], "requests");
+    assert!(
+        found[0]["suggestion"]
+            .as_str()
+            .unwrap()
+            .contains("Did you mean `requests`?")
+    );
+    assert_eq!(
+        fs::read_to_string(temp.path().join("pyproject.toml")).unwrap(),
+        before
+    );
+}
+
+#[test]
+fn allow_suspicious_and_policy_approval_let_the_package_through() {
+    let temp = tempdir().unwrap();
+    project(temp.path(), &["colourama"]);
+
+    let (json, _) = pybun(
+        temp.path(),
+        "http://127.0.0.1:9",
+        &["install", "--allow-suspicious"],
+    );
+    assert!(
+        diagnostics(&json, "E_SUSPICIOUS_PACKAGE").is_empty(),
+        "{json}"
+    );
+    let warnings = diagnostics(&json, "W_SUSPICIOUS_PACKAGE");
+    assert_eq!(warnings[0]["context"]["similar_to"], "colorama");
+
+    fs::write(
+        temp.path().join("pybun-policy.toml"),
+        "[packages]\napproved = [\"colourama\"]\n",
+    )
+    .unwrap();
+    let (json, _) = pybun(temp.path(), "http://127.0.0.1:9", &["install"]);
+    assert!(
+        diagnostics(&json, "E_SUSPICIOUS_PACKAGE").is_empty(),
+        "{json}"
+    );
+    assert!(
+        diagnostics(&json, "W_SUSPICIOUS_PACKAGE").is_empty(),
+        "{json}"
+    );
+}
+
+#[test]
+fn install_refuses_brand_new_packages() {
+    let server = MockServer::start();
+    let released = date_days_ago(3);
+    mock_project(
+        &server,
+        "fresh-widget",
+        &[("0.1.0", &released, "dev@fresh.example")],
+    );
+    let temp = tempdir().unwrap();
+    project(temp.path(), &["fresh-widget"]);
+
+    let (json, code) = pybun(temp.path(), &server.base_url(), &["install"]);
+    assert_ne!(code, 0);
+    let found = diagnostics(&json, "E_SUSPICIOUS_PACKAGE");
+    assert_eq!(found.len(), 1, "{json}");
+    let context = &found[0]["context"];
+    assert_eq!(context["kind"], "new-package");
+    assert_eq!(context["package"], "fresh-widget");
+    assert_eq!(context["first_release"], released);
+    assert_eq!(context["age_days"], 3);
+}
+
+#[test]
+fn install_refuses_packages_whose_maintainers_were_replaced() {
+    let server = MockServer::start();
+    mock_project(
+        &server,
+        "steady-widget",
+        &[
+            ("1.0.0", "2020-01-01", "Alice <alice@widgets.example>"),
+            ("2.0.0", "2021-06-01", "mallory@evil.example"),
+        ],
+    );
+    let temp = tempdir().unwrap();
+    project(temp.path(), &["steady-widget"]);
+
+    let (json, code) = pybun(temp.path(), &server.base_url(), &["install"]);
+    assert_ne!(code, 0);
+    let found = diagnostics(&json, "E_SUSPICIOUS_PACKAGE");
+    assert_eq!(found.len(), 1, "{json}");
+    let context = &found[0]["context"];
+    assert_eq!(context["kind"], "maintainer-change");
+    assert_eq!(context["version"], "2.0.0");
+    assert_eq!(context["previous_version"], "1.0.0");
+    assert_eq!(context["maintainers"][0], "mallory@evil.example");
+    assert_eq!(context["previous_maintainers"][0], "alice@widgets.example");
+}
+
+/// A PyPI JSON API serving `colourama 1.0` as an sdist only.
+fn mock_sdist_only(server: &MockServer) {
+    let sdist = b"not built: the container runtime is missing".to_vec();
+    let sha256 = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(&sdist));
+    let body = json!({
+        "info": { "name": "colourama", "version": "1.0" },
+        "releases": { "1.0": [{
+            "filename": "colourama-1.0.tar.gz",
+            "packagetype": "sdist",
+            "url": format!("{}/files/colourama-1.0.tar.gz", server.base_url()),
+            "digests": { "sha256": sha256 },
+            "upload_time_iso_8601": "2020-01-01T12:00:00.000000Z",
+        }]},
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/colourama/json");
+        then.status(200).json_body(body);
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/colourama/1.0/json");
+        then.status(200)
+            .json_body(json!({ "info": { "requires_dist": [] }, "urls": [] }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/files/colourama-1.0.tar.gz");
+        then.status(200).body(sdist);
+    });
+}
+
+#[test]
+fn allowed_suspicious_packages_stay_container_built_across_installs() {
+    let temp = tempdir().unwrap();
+    let venv = temp.path().join(".venv");
+    let created = std::process::Command::new("python3")
+        .args(["-m", "venv", "--without-pip"])
+        .arg(&venv)
+        .status()
+        .is_ok_and(|s| s.success());
+    if !created {
+        eprintln!("skipping: python3 -m venv unavailable");
+        return;
+    }
+    let server = MockServer::start();
+    mock_sdist_only(&server);
+    project(temp.path(), &["colourama"]);
+    let install = |args: &[&str]| {
+        let output = cargo_bin_cmd!("pybun")
+            .current_dir(temp.path())
+            .env("PYBUN_HOME", temp.path().join("home"))
+            .env("PYBUN_PYPI_BASE_URL", server.base_url())
+            .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("pypi-cache"))
+            .env("PYBUN_ENV", &venv)
+            .env(
+                "PYBUN_CONTAINER_RUNTIME",
+                temp.path().join("missing/podman"),
+            )
+            .env_remove("PYBUN_INDEX_URL")
+            .env_remove("PYBUN_POLICY")
+            .args(["--format=json", "install"])
+            .args(args)
+            .output()
+            .unwrap();
+        let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(!output.status.success(), "{json}");
+        json
+    };
+
+    // Each invocation needs the (missing) container runtime to build the
+    // sdist; the second one no longer runs the suspicious checks.
+    for args in [&["--allow-suspicious"][..], &[]] {
+        let json = install(args);
+        assert!(
+            json.to_string().contains("neither podman nor docker"),
+            "{json}"
+        );
+        let lock =
+            pybun::lockfile::Lockfile::load_from_path(temp.path().join("pybun.lockb")).unwrap();
+        assert_eq!(lock.container_builds, vec!["colourama"]);
+    }
+}
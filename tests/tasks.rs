@@ -0,0 +1,120 @@
+//! E2E tests for `[tool.pybun.tasks]`: `pybun run <task>` and
+//! `pybun run --list`.
+
+#![cfg(unix)]
+
+use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn project_with_tasks(temp: &TempDir, tasks: &str) -> PathBuf {
+    let project = temp.path().join("demo");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::write(
+        project.join("pyproject.toml"),
+        format!("[project]\nname = \"demo\"\n\n[tool.pybun.tasks]\n{tasks}"),
+    )
+    .unwrap();
+    project
+}
+
+fn pybun_in(dir: &Path) -> Command {
+    let mut cmd = cargo_bin_cmd!("pybun");
+    cmd.current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".pybun-home"))
+        .env_remove("PYBUN_ENV");
+    cmd
+}
+
+fn run_json(dir: &Path, args: &[&str]) -> (serde_json::Value, Option<i32>) {
+    let output = pybun_in(dir)
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap_or_else(|_| {
+        panic!(
+            "valid JSON. stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+    });
+    (json, output.status.code())
+}
+
+#[test]
+fn task_runs_after_its_dependencies_in_the_project_environment() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_tasks(
+        &temp,
+        r#"lint = "echo lint:$VIRTUAL_ENV"
+tool = ["demo-tool", "--flag"]
+test = { cmd = "echo test:$MODE", depends = ["lint", "tool"], env = { MODE = "ci" }, help = "Run the tests" }
+"#,
+    );
+    // A tool installed into the project environment is found on PATH.
+    let bin = project.join(".venv/bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    std::fs::write(project.join(".venv/pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+    std::fs::write(bin.join("python"), "").unwrap();
+    let tool = bin.join("demo-tool");
+    std::fs::write(&tool, "#!/bin/sh\necho \"tool:$*\"\n").unwrap();
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let (json, code) = run_json(&project, &["run", "test", "--", "extra arg"]);
+    assert_eq!(code, Some(0), "{}", json["diagnostics"]);
+    let tasks = json["detail"]["tasks"].as_array().unwrap();
+    let names: Vec<&str> = tasks.iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["lint", "tool", "test"]);
+    let venv = project.join(".venv").canonicalize().unwrap();
+    let lint_out = tasks[0]["stdout"].as_str().unwrap().trim();
+    assert!(
+        lint_out.starts_with("lint:") && Path::new(&lint_out[5..]).canonicalize().unwrap() == venv,
+        "{lint_out}"
+    );
+    assert_eq!(tasks[1]["stdout"], "tool:--flag\n");
+    assert_eq!(tasks[2]["stdout"], "test:ci extra arg\n");
+}
+
+#[test]
+fn failing_dependency_stops_the_run_with_its_exit_code() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_tasks(
+        &temp,
+        "lint = \"exit 3\"\ntest = { cmd = \"echo never\", depends = [\"lint\"] }\n",
+    );
+    let (json, code) = run_json(&project, &["run", "test"]);
+    assert_eq!(code, Some(3));
+    assert_eq!(json["detail"]["tasks"].as_array().unwrap().len(), 1);
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "E_TASK_FAILED")
+    );
+}
+
+#[test]
+fn cycles_are_rejected_and_list_shows_tasks() {
+    let temp = TempDir::new().unwrap();
+    let project = project_with_tasks(
+        &temp,
+        "a = { cmd = \"true\", depends = [\"b\"] }\nb = { depends = [\"a\"], help = \"Group\" }\n",
+    );
+    let (json, code) = run_json(&project, &["run", "a"]);
+    assert_ne!(code, Some(0));
+    assert_eq!(
+        json["detail"]["error"],
+        "task dependency cycle: a -> b -> a"
+    );
+
+    let (json, code) = run_json(&project, &["run", "--list"]);
+    assert_eq!(code, Some(0));
+    let tasks = json["detail"]["tasks"].as_array().unwrap();
+    assert_eq!(tasks[0]["name"], "a");
+    assert_eq!(tasks[0]["command"], "true");
+    assert_eq!(tasks[1]["depends"], serde_json::json!(["a"]));
+    assert_eq!(tasks[1]["help"], "Group");
+}
@@ -0,0 +1,101 @@
+//! E2E tests for `pybun test --changed`: outcomes and dependency hashes are
+//! recorded per project, and only tests affected by changed files (or that
+//! did not pass last time) are re-run.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn pybun_test(dir: &Path, home: &Path, args: &[&str]) -> Value {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_TEST_HISTORY")
+        .args(["--format=json", "test", "--backend=native", "--parallel=1"])
+        .args(args)
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).expect("Valid JSON")
+}
+
+fn ran(json: &Value) -> Vec<&str> {
+    let mut names: Vec<&str> = json["detail"]["results"]
+        .as_array()
+        .map(|results| {
+            results
+                .iter()
+                .map(|r| r["name"].as_str().unwrap())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort_unstable();
+    names
+}
+
+#[test]
+fn changed_reruns_affected_and_previously_failing_tests() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let dir = temp.path().join("app");
+    fs::create_dir_all(dir.join("tests")).unwrap();
+    fs::write(dir.join("calc.py"), "def add(a, b):\n    return a + b\n").unwrap();
+    fs::write(
+        dir.join("tests/test_calc.py"),
+        "from calc import add\n\ndef test_add():\n    assert add(1, 2) == 3\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("tests/test_other.py"),
+        "from pathlib import Path\n\ndef test_ok():\n    pass\n\n\
+         def test_broken():\n    assert Path(__file__).with_name('flag').exists()\n",
+    )
+    .unwrap();
+
+    let json = pybun_test(&dir, &home, &[]);
+    assert_eq!(ran(&json), ["test_add", "test_broken", "test_ok"]);
+    assert_eq!(json["detail"]["changed"], Value::Null);
+
+    // Only the failing test is re-run; the flag is not a Python dependency.
+    fs::write(dir.join("tests/flag"), "").unwrap();
+    let json = pybun_test(&dir, &home, &["--changed"]);
+    assert_eq!(ran(&json), ["test_broken"], "{json}");
+    assert_eq!(json["detail"]["changed"]["failed_last_run"], 1);
+    assert_eq!(json["detail"]["changed"]["unchanged"], 2);
+
+    let json = pybun_test(&dir, &home, &["--changed"]);
+    assert_eq!(json["status"], "ok", "{json}");
+    assert_eq!(json["detail"]["tests_found"], 0);
+    assert_eq!(json["detail"]["changed"]["unchanged"], 3);
+
+    fs::write(dir.join("calc.py"), "def add(a, b):\n    return b + a\n").unwrap();
+    let json = pybun_test(&dir, &home, &["--changed"]);
+    assert_eq!(ran(&json), ["test_add"], "{json}");
+    assert_eq!(json["detail"]["changed"]["changed_files"][0], "calc.py");
+}
+
+#[test]
+fn changed_can_be_the_default_and_all_bypasses_it() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let dir = temp.path().join("app");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("pyproject.toml"),
+        "[project]\nname = \"app\"\nversion = \"0.1.0\"\n\n[tool.pybun.test]\nchanged = true\n",
+    )
+    .unwrap();
+    fs::write(dir.join("test_app.py"), "def test_app():\n    pass\n").unwrap();
+
+    let json = pybun_test(&dir, &home, &[]);
+    assert_eq!(ran(&json), ["test_app"], "{json}");
+    assert_eq!(json["detail"]["changed"]["new"], 1);
+
+    let json = pybun_test(&dir, &home, &[]);
+    assert_eq!(ran(&json), Vec::<&str>::new(), "{json}");
+
+    let json = pybun_test(&dir, &home, &["--all"]);
+    assert_eq!(ran(&json), ["test_app"], "{json}");
+    assert_eq!(json["detail"]["changed"], Value::Null);
+}
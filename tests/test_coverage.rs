@@ -0,0 +1,237 @@
+//! E2E tests for `pybun test --coverage`. coverage.py is not installed in
+//! the test environment, so each project carries a small stand-in
+//! `coverage` package implementing the API and commands pybun uses.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const COVERAGE_INIT: &str = r#"import json, os, sys, uuid
+
+
+class Coverage:
+    def __init__(self, data_file=".coverage", data_suffix=False, **_):
+        self.data_file = data_file
+        self.suffix = data_suffix
+        self.lines = {}
+
+    def _trace(self, frame, event, arg):
+        path = os.path.abspath(frame.f_code.co_filename)
+        if not os.path.isfile(path) or not path.startswith(os.getcwd()) or os.sep + "coverage" + os.sep in path:
+            return None
+        if event == "line":
+            self.lines.setdefault(path, set()).add(frame.f_lineno)
+        return self._trace
+
+    def start(self):
+        sys.settrace(self._trace)
+
+    def stop(self):
+        sys.settrace(None)
+
+    def save(self):
+        name = self.data_file + ("." + uuid.uuid4().hex if self.suffix else "")
+        with open(name, "w") as handle:
+            json.dump({path: sorted(lines) for path, lines in self.lines.items()}, handle)
+"#;
+
+const COVERAGE_MAIN: &str = r#"import ast, glob, json, os, runpy, sys
+from coverage import Coverage
+
+command, args = sys.argv[1], sys.argv[2:]
+data_file = next((a.split("=", 1)[1] for a in args if a.startswith("--data-file=")), ".coverage")
+output = args[args.index("-o") + 1] if "-o" in args else None
+
+if command == "run":
+    index = args.index("-m")
+    sys.argv = [args[index + 1]] + args[index + 2:]
+    cov = Coverage(data_file=data_file, data_suffix="--parallel-mode" in args)
+    cov.start()
+    code = 0
+    try:
+        runpy.run_module(args[index + 1], run_name="__main__", alter_sys=True)
+    except SystemExit as exc:
+        code = exc.code
+    finally:
+        cov.stop()
+        cov.save()
+    sys.exit(code)
+
+if command == "combine":
+    merged = {}
+    for directory in [a for a in args if not a.startswith("-")]:
+        for name in glob.glob(os.path.join(directory, ".coverage.*")):
+            with open(name) as handle:
+                for path, lines in json.load(handle).items():
+                    merged.setdefault(path, set()).update(lines)
+    if not merged:
+        print("No data to combine", file=sys.stderr)
+        sys.exit(1)
+    with open(data_file, "w") as handle:
+        json.dump({path: sorted(lines) for path, lines in merged.items()}, handle)
+    sys.exit(0)
+
+with open(data_file) as handle:
+    data = json.load(handle)
+files, covered, statements = {}, 0, 0
+for path, lines in sorted(data.items()):
+    with open(path) as handle:
+        tree = ast.parse(handle.read())
+    body = sorted({node.lineno for node in ast.walk(tree) if isinstance(node, ast.stmt)})
+    hit = [line for line in body if line in lines]
+    covered += len(hit)
+    statements += len(body)
+    files[os.path.relpath(path)] = {
+        "executed_lines": hit,
+        "missing_lines": [line for line in body if line not in lines],
+        "summary": {"covered_lines": len(hit), "num_statements": len(body),
+                    "percent_covered": 100.0 * len(hit) / max(len(body), 1)},
+    }
+with open(output, "w") as handle:
+    if command == "json":
+        json.dump({"files": files, "totals": {"covered_lines": covered, "num_statements": statements,
+                   "percent_covered": 100.0 * covered / max(statements, 1),
+                   "missing_lines": statements - covered}}, handle)
+    else:
+        for path, file in files.items():
+            handle.write(f"SF:{path}\n" + "".join(f"DA:{line},1\n" for line in file["executed_lines"]) + "end_of_record\n")
+"#;
+
+fn project(temp: &TempDir, with_coverage: bool) -> std::path::PathBuf {
+    let dir = temp.path().join("app");
+    fs::create_dir_all(dir.join("tests")).unwrap();
+    if with_coverage {
+        fs::create_dir_all(dir.join("coverage")).unwrap();
+        fs::write(dir.join("coverage/__init__.py"), COVERAGE_INIT).unwrap();
+        fs::write(dir.join("coverage/__main__.py"), COVERAGE_MAIN).unwrap();
+    }
+    fs::write(
+        dir.join("calc.py"),
+        "def add(a, b):\n    return a + b\n\n\ndef sub(a, b):\n    return a - b\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("tests/test_add.py"),
+        "from calc import add\n\n\ndef test_add():\n    assert add(1, 2) == 3\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("tests/test_more.py"),
+        "from calc import add\n\n\ndef test_zero():\n    assert add(0, 0) == 0\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn pybun_test(dir: &Path, args: &[&str]) -> Value {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join(".pybun-home"))
+        .env("PYBUN_TEST_HISTORY", "0")
+        .env_remove("PYBUN_ENV")
+        .args(["--format=json", "test"])
+        .args(args)
+        .output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).expect("Valid JSON")
+}
+
+fn diagnostic_codes(json: &Value) -> Vec<&str> {
+    json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|d| d["code"].as_str())
+        .collect()
+}
+
+#[test]
+fn coverage_combines_per_process_data_and_enforces_fail_under() {
+    let temp = TempDir::new().unwrap();
+    let dir = project(&temp, true);
+
+    let json = pybun_test(
+        &dir,
+        &[
+            "--backend=native",
+            "--parallel=2",
+            "--coverage",
+            "--coverage-report",
+            "lcov",
+            "--coverage-fail-under",
+            "95",
+        ],
+    );
+    let coverage = &json["detail"]["coverage"];
+    let calc = coverage["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["path"] == "calc.py")
+        .unwrap_or_else(|| panic!("calc.py measured: {json}"));
+    // `sub` never runs; both test processes' data are combined.
+    assert_eq!(calc["missing"], serde_json::json!([6]), "{json}");
+    assert_eq!(calc["covered_lines"], 3);
+    assert!(coverage["files"].as_array().unwrap().len() >= 3, "{json}");
+    assert!(coverage["percent"].as_f64().unwrap() < 95.0);
+    assert_eq!(coverage["data_file"], ".coverage");
+    assert_eq!(coverage["reports"]["lcov"], "coverage.lcov");
+    assert_eq!(coverage["fail_under"], 95.0);
+    assert!(dir.join(".coverage").is_file());
+    let lcov = fs::read_to_string(dir.join("coverage.lcov")).unwrap();
+    assert!(lcov.contains("SF:calc.py"), "{lcov}");
+
+    assert_eq!(json["status"], "error", "{json}");
+    assert!(diagnostic_codes(&json).contains(&"E_TEST_COVERAGE_BELOW"));
+    assert_eq!(json["detail"]["summary"]["passed"], 2);
+}
+
+#[test]
+fn coverage_wraps_unittest_and_names_shard_data_files() {
+    let temp = TempDir::new().unwrap();
+    let dir = project(&temp, true);
+    fs::write(
+        dir.join("tests/test_sub.py"),
+        "import unittest\nfrom calc import sub\n\n\nclass TestSub(unittest.TestCase):\n    def test_sub(self):\n        self.assertEqual(sub(3, 1), 2)\n",
+    )
+    .unwrap();
+
+    let json = pybun_test(
+        &dir,
+        &[
+            "--backend=unittest",
+            "--coverage",
+            "--coverage-report",
+            "json",
+            "--shard",
+            "1/1",
+            "tests/test_sub.py",
+        ],
+    );
+    assert_eq!(json["status"], "ok", "{json}");
+    let coverage = &json["detail"]["coverage"];
+    assert_eq!(coverage["data_file"], ".coverage.shard-1-of-1");
+    assert_eq!(coverage["reports"]["json"], "coverage.json");
+    let calc = coverage["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["path"] == "calc.py")
+        .unwrap_or_else(|| panic!("calc.py measured: {json}"));
+    assert_eq!(calc["missing"], serde_json::json!([2]), "{json}");
+    assert!(dir.join(".coverage.shard-1-of-1").is_file());
+    assert!(dir.join("coverage.json").is_file());
+}
+
+#[test]
+fn coverage_outside_a_virtual_environment_requires_coverage_installed() {
+    let temp = TempDir::new().unwrap();
+    let dir = project(&temp, false);
+
+    let json = pybun_test(&dir, &["--backend=native", "--coverage"]);
+    assert_eq!(json["status"], "error", "{json}");
+    assert!(diagnostic_codes(&json).contains(&"E_TEST_COVERAGE_UNAVAILABLE"));
+    assert_eq!(json["detail"]["coverage"], Value::Null);
+}
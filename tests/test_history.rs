@@ -0,0 +1,118 @@
+//! E2E tests for `pybun test --history`: runs are recorded per project in the
+//! cache and reported as trends, with JSON/CSV export.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn project(temp: &TempDir) -> std::path::PathBuf {
+    let dir = temp.path().join("app");
+    fs::create_dir_all(dir.join("tests")).unwrap();
+    fs::write(
+        dir.join("tests/test_math.py"),
+        "def test_add():\n    assert 1 + 1 == 2\n\ndef test_sub():\n    assert 2 - 1 == 1\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn pybun_test(dir: &Path, home: &Path, args: &[&str]) -> std::process::Output {
+    cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", home)
+        .env_remove("PYBUN_TEST_HISTORY")
+        .args(["--format=json", "test"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn history_reports_recorded_runs_and_exports_csv() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let dir = project(&temp);
+
+    for _ in 0..2 {
+        pybun_test(&dir, &home, &["--backend=pybun"]);
+    }
+    let output = pybun_test(
+        &dir,
+        &home,
+        &["--history", "--history-export", "reports/history.csv"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let detail = &json["detail"];
+    assert_eq!(detail["recorded_runs"], 2, "{json}");
+    assert_eq!(detail["runs"].as_array().unwrap().len(), 2);
+    assert_eq!(detail["runs"][0]["backend"], "pybun");
+    let ids: Vec<&str> = detail["tests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            "tests/test_math.py::test_add",
+            "tests/test_math.py::test_sub"
+        ]
+    );
+    assert_eq!(detail["tests"][0]["runs"], 2);
+    assert_eq!(detail["slowest"].as_array().unwrap().len(), 2);
+
+    let csv = fs::read_to_string(dir.join("reports/history.csv")).unwrap();
+    assert!(csv.starts_with("test_id,runs,failures,"), "{csv}");
+    assert!(csv.contains("\ntests/test_math.py::test_add,2,"), "{csv}");
+
+    // The window narrows the report, not the stored history.
+    let output = pybun_test(&dir, &home, &["--history", "--history-runs", "1"]);
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["runs"].as_array().unwrap().len(), 1);
+    assert_eq!(json["detail"]["recorded_runs"], 2);
+}
+
+#[test]
+fn history_is_empty_when_recording_is_disabled() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path().join("home");
+    let dir = project(&temp);
+
+    cargo_bin_cmd!("pybun")
+        .current_dir(&dir)
+        .env("PYBUN_HOME", &home)
+        .env("PYBUN_TEST_HISTORY", "0")
+        .args(["test", "--backend=pybun"])
+        .output()
+        .unwrap();
+    let output = pybun_test(&dir, &home, &["--history"]);
+    assert!(output.status.success(), "{output:?}");
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["detail"]["recorded_runs"], 0, "{json}");
+    assert!(json["detail"]["tests"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn history_export_rejects_unknown_format() {
+    let temp = TempDir::new().unwrap();
+    let dir = project(&temp);
+    let output = pybun_test(
+        &dir,
+        &temp.path().join("home"),
+        &["--history", "--history-export", "history.txt"],
+    );
+    assert!(!output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "E_TEST_HISTORY_EXPORT"),
+        "{json}"
+    );
+}
@@ -611,6 +611,122 @@ def test_bar_one():
     assert!(detail.get("filter").is_some());
 }
 
+#[test]
+fn test_keyword_expression_selects_by_name_and_marker() {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("test_expr.py"),
+        r#"
+import pytest
+
+def test_api_create():
+    pass
+
+@pytest.mark.slow
+def test_api_bulk():
+    pass
+
+def test_db_connect():
+    pass
+"#,
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args(["test", "--format=json", "-k", "api and not slow"])
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .output()
+        .unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let selected = json["detail"]["selected_tests"].as_array().unwrap();
+    assert_eq!(selected.len(), 1, "selected: {selected:?}");
+    assert!(selected[0].as_str().unwrap().ends_with("::test_api_create"));
+}
+
+#[test]
+fn test_invalid_keyword_expression_fails() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("test_bad.py"), "def test_a():\n    pass\n").unwrap();
+
+    pybun()
+        .current_dir(temp.path())
+        .args(["test", "-k", "foo and"])
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("-k expression"));
+}
+
+#[test]
+fn test_node_id_and_line_targets() {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("test_api.py"),
+        r#"def test_health():
+    pass
+
+
+class TestUser:
+    def test_create(self):
+        value = 1
+        assert value == 1
+
+    def test_delete(self):
+        pass
+"#,
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args([
+            "test",
+            "--format=json",
+            "test_api.py::TestUser::test_delete",
+            "test_api.py:8",
+        ])
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .output()
+        .unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let detail = &json["detail"];
+    let selected: Vec<&str> = detail["selected_tests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_str().unwrap())
+        .collect();
+    assert_eq!(
+        selected,
+        [
+            "test_api.py::TestUser::test_delete",
+            "test_api.py::TestUser::test_create"
+        ]
+    );
+    let targets = detail["targets"].as_array().unwrap();
+    assert_eq!(targets[0]["kind"], "node_id");
+    assert_eq!(targets[1]["kind"], "line");
+}
+
+#[test]
+fn test_unmatched_node_id_fails() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("test_api.py"), "def test_a():\n    pass\n").unwrap();
+
+    pybun()
+        .current_dir(temp.path())
+        .args(["test", "test_api.py::test_missing"])
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("no test matches"));
+}
+
 #[test]
 fn test_discover_verbose_output() {
     let temp = TempDir::new().unwrap();
@@ -2294,3 +2410,516 @@ def test_addition():
         diagnostics
     );
 }
+
+// ---------------------------------------------------------------------------
+// Test parameters (--env / --fixture)
+// ---------------------------------------------------------------------------
+
+const PARAMS_PYPROJECT: &str = r#"[project]
+name = "params-demo"
+version = "0.1.0"
+
+[tool.pybun.test.env]
+MODE = "config"
+KEEP = "yes"
+
+[tool.pybun.test.fixtures]
+replicas = 3
+region = "us-east-1"
+"#;
+
+#[test]
+fn test_params_are_recorded_in_dry_run() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PARAMS_PYPROJECT).unwrap();
+    fs::write(temp.path().join("test_a.py"), "def test_a(): pass\n").unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args([
+            "test",
+            "--format=json",
+            "--env",
+            "MODE=ci",
+            "--fixture",
+            "region=eu-west-1",
+        ])
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let params = &json["detail"]["params"];
+    assert_eq!(params["env"]["MODE"], "ci");
+    assert_eq!(params["env"]["KEEP"], "yes");
+    assert_eq!(params["fixtures"]["replicas"], 3);
+    assert_eq!(params["fixtures"]["region"], "eu-west-1");
+}
+
+#[test]
+fn test_params_reject_malformed_pairs() {
+    pybun()
+        .args(["test", "--env", "NO_EQUALS_SIGN"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected KEY=VALUE"));
+}
+
+#[test]
+fn test_params_reach_unittest_processes() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PARAMS_PYPROJECT).unwrap();
+    fs::write(
+        temp.path().join("test_params.py"),
+        r#"
+import os
+import unittest
+
+from pybun_test_params import params
+
+
+class TestParams(unittest.TestCase):
+    def test_env_and_params(self):
+        self.assertEqual(os.environ["MODE"], "ci")
+        self.assertEqual(params, {"region": "eu-west-1", "replicas": 3})
+"#,
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args([
+            "test",
+            "--backend=unittest",
+            "--format=json",
+            "--env",
+            "MODE=ci",
+            "--fixture",
+            "region=eu-west-1",
+        ])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["status"], "ok", "{json}");
+    assert_eq!(json["detail"]["params"]["env"]["MODE"], "ci");
+}
+
+#[test]
+fn test_pybun_params_fixture_with_pytest() {
+    if !pytest_available() {
+        eprintln!("Skipping test_pybun_params_fixture_with_pytest: pytest not installed");
+        return;
+    }
+
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PARAMS_PYPROJECT).unwrap();
+    fs::write(
+        temp.path().join("test_fixture.py"),
+        r#"
+def test_fixture(pybun_params):
+    assert pybun_params == {"region": "eu-west-1", "replicas": 3}
+"#,
+    )
+    .unwrap();
+
+    for backend in ["--backend=pytest", "--backend=pybun"] {
+        let output = pybun()
+            .current_dir(temp.path())
+            .args([
+                "test",
+                backend,
+                "--format=json",
+                "--fixture",
+                "region=eu-west-1",
+            ])
+            .output()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["status"], "ok", "{backend}: {json}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Discovery plugins ([tool.pybun.test.plugins])
+// ---------------------------------------------------------------------------
+
+const PLUGINS_PYPROJECT: &str = r#"[project]
+name = "plugins-demo"
+version = "0.1.0"
+
+[tool.pybun.test.plugins.behave]
+
+[tool.pybun.test.plugins.checks]
+file-patterns = ["check_*.py"]
+command = ["{files}"]
+"#;
+
+const LOGIN_FEATURE: &str = r#"Feature: Login
+  Scenario: Successful login
+    Given a user
+
+  @skip
+  Scenario: Locked account
+    Given a locked user
+"#;
+
+#[test]
+fn test_plugins_report_items_in_discover_and_dry_run() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PLUGINS_PYPROJECT).unwrap();
+    fs::write(temp.path().join("test_a.py"), "def test_a(): pass\n").unwrap();
+    fs::write(temp.path().join("login.feature"), LOGIN_FEATURE).unwrap();
+    fs::write(temp.path().join("check_io.py"), "assert True\n").unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args(["test", "--discover", "--format=json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let detail = &json["detail"];
+    assert_eq!(detail["plugins"], serde_json::json!(["behave", "checks"]));
+    let tests = detail["tests"].as_array().unwrap();
+    let plugin_of = |name: &str| {
+        tests
+            .iter()
+            .find(|t| t["name"] == name)
+            .map(|t| t["plugin"].clone())
+            .unwrap_or_else(|| panic!("{name} not discovered: {detail}"))
+    };
+    assert_eq!(plugin_of("Login::Successful login"), "behave");
+    assert_eq!(plugin_of("check_io"), "checks");
+    assert_eq!(plugin_of("test_a"), serde_json::Value::Null);
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args(["test", "--format=json", "login.feature:3"])
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let plugins = json["detail"]["plugins"].as_array().unwrap();
+    assert_eq!(plugins.len(), 1, "{json}");
+    assert_eq!(plugins[0]["name"], "behave");
+    assert_eq!(
+        plugins[0]["command"],
+        serde_json::json!(["-m", "behave", "login.feature:2"])
+    );
+}
+
+#[test]
+fn test_plugin_adapter_results_are_reported() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("pyproject.toml"), PLUGINS_PYPROJECT).unwrap();
+    fs::write(temp.path().join("check_ok.py"), "assert 1 + 1 == 2\n").unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args(["test", "--format=json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["status"], "ok", "{json}");
+    assert_eq!(json["detail"]["test_runner"], "plugins");
+    let run = &json["detail"]["plugins"][0];
+    assert_eq!(run["name"], "checks");
+    assert_eq!(run["passed"], true);
+
+    fs::write(temp.path().join("check_bad.py"), "assert 1 + 1 == 3\n").unwrap();
+    let output = pybun()
+        .current_dir(temp.path())
+        .args(["test", "--format=json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["status"], "error", "{json}");
+    assert_eq!(json["detail"]["plugins"][0]["passed"], false);
+    let diagnostics = json["diagnostics"].as_array().unwrap();
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| d["code"] == "E_TEST_PLUGIN_FAILED"),
+        "{json}"
+    );
+}
+
+#[test]
+fn test_plugin_config_errors_fail_fast() {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("pyproject.toml"),
+        "[project]\nname = \"x\"\nversion = \"0.1.0\"\n\n[tool.pybun.test.plugins.mamba]\nfile-patterns = [\"*_spec.py\"]\n",
+    )
+    .unwrap();
+
+    pybun()
+        .current_dir(temp.path())
+        .args(["test"])
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "test plugin 'mamba' requires `command`",
+        ));
+}
+
+// ---------------------------------------------------------------------------
+// Built-in harness (--backend=native) Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_native_backend_runs_each_test_without_pytest() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir_all(temp.path().join("tests")).unwrap();
+    fs::write(
+        temp.path().join("tests/conftest.py"),
+        "import pytest\n\n@pytest.fixture\ndef greeting():\n    return 'hello'\n",
+    )
+    .unwrap();
+    fs::write(
+        temp.path().join("tests/test_native.py"),
+        r#"import unittest
+
+def test_uses_conftest_fixture(greeting, tmp_path):
+    assert greeting == "hello" and tmp_path.is_dir()
+
+def test_fails():
+    assert 1 == 2
+
+class TestCase(unittest.TestCase):
+    def test_method(self):
+        self.assertTrue(True)
+"#,
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args(["--format=json", "test", "--backend=native", "--parallel=2"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    let detail = &json["detail"];
+    assert_eq!(detail["backend"], "native");
+    assert_eq!(detail["summary"]["passed"], 2, "{detail}");
+    assert_eq!(detail["summary"]["failed"], 1, "{detail}");
+    let failed = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_TEST_FAILED")
+        .expect("failure diagnostic");
+    assert_eq!(failed["message"], "FAILED test_fails");
+    assert!(
+        failed["context"]
+            .as_str()
+            .unwrap()
+            .contains("assert 1 == 2")
+    );
+}
+
+#[test]
+fn test_native_backend_fail_fast_stops_at_the_first_failing_test() {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("test_order.py"),
+        "def test_a():\n    assert False\n\ndef test_b():\n    pass\n\ndef test_c():\n    pass\n",
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args([
+            "--format=json",
+            "test",
+            "--backend=native",
+            "--parallel=1",
+            "--fail-fast",
+        ])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    let summary = &json["detail"]["summary"];
+    assert_eq!(summary["failed"], 1, "{summary}");
+    assert_eq!(summary["stopped_early"], true, "{summary}");
+    assert_eq!(json["detail"]["results"].as_array().unwrap().len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// Per-test reports (--report, detail.tests)
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_report_writes_junit_and_lists_tests_in_json() {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("test_report.py"),
+        "def test_ok():\n    pass\n\ndef test_broken():\n    raise ValueError('bad input')\n",
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args([
+            "--format=json",
+            "test",
+            "--backend=native",
+            "--report",
+            "reports/junit.xml",
+        ])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    let tests = json["detail"]["tests"].as_array().unwrap();
+    let broken = tests
+        .iter()
+        .find(|t| t["name"] == "test_broken")
+        .expect("test_broken reported");
+    assert_eq!(broken["file"], "test_report.py");
+    assert_eq!(broken["line"], 4);
+    assert_eq!(broken["outcome"], "failed");
+    assert_eq!(broken["message"], "ValueError: bad input");
+    assert!(broken["duration_ms"].is_u64());
+    assert_eq!(json["detail"]["report"], "reports/junit.xml");
+
+    let xml = fs::read_to_string(temp.path().join("reports/junit.xml")).unwrap();
+    assert!(
+        xml.contains(r#"tests="2" failures="1" errors="0" skipped="0""#),
+        "{xml}"
+    );
+    assert!(xml.contains(
+        r#"<testcase classname="test_report" name="test_ok" file="test_report.py" line="1""#
+    ));
+    assert!(
+        xml.contains(r#"<failure message="ValueError: bad input">"#),
+        "{xml}"
+    );
+}
+
+#[test]
+fn test_report_is_not_written_for_unittest() {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("test_plain.py"),
+        "import unittest\n\nclass T(unittest.TestCase):\n    def test_ok(self):\n        pass\n",
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .args([
+            "--format=json",
+            "test",
+            "--backend=unittest",
+            "--report=junit.xml",
+        ])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    assert!(
+        json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "W_TEST_REPORT_UNSUPPORTED")
+    );
+    assert!(!temp.path().join("junit.xml").exists());
+}
+
+// ---------------------------------------------------------------------------
+// Scheduled pytest workers (-j N)
+// ---------------------------------------------------------------------------
+
+/// A stand-in `python -m pytest` that records which node ids it was given,
+/// prints `-v` result lines and writes a JUnit report.
+const PYTEST_STUB: &str = r#"import os, sys
+ids = [a for a in sys.argv[1:] if "::" in a]
+report = next(a.split("=", 1)[1] for a in sys.argv[1:] if a.startswith("--junitxml="))
+with open("worker-%d.txt" % os.getpid(), "w") as f:
+    f.write("\n".join(ids))
+cases = []
+for node in ids:
+    outcome = "FAILED" if "fail" in node else "PASSED"
+    print("%s %s" % (node, outcome))
+    path, name = node.split("::", 1)
+    body = '<failure message="boom">trace</failure>' if outcome == "FAILED" else ""
+    cases.append('<testcase classname="%s" file="%s" line="0" name="%s" time="0.01">%s</testcase>'
+                 % (path[:-3], path, name, body))
+with open(report, "w") as f:
+    f.write("<testsuites><testsuite>%s</testsuite></testsuites>" % "".join(cases))
+sys.exit(1 if any("fail" in n for n in ids) else 0)
+"#;
+
+#[test]
+fn test_parallel_pytest_runs_scheduled_workers_without_xdist() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir_all(temp.path().join("pytest")).unwrap();
+    fs::write(temp.path().join("pytest/__init__.py"), "").unwrap();
+    fs::write(temp.path().join("pytest/__main__.py"), PYTEST_STUB).unwrap();
+    fs::write(
+        temp.path().join("test_sched.py"),
+        "def test_a():\n    pass\n\ndef test_b():\n    pass\n\ndef test_c():\n    pass\n\ndef test_fails():\n    pass\n",
+    )
+    .unwrap();
+
+    let output = pybun()
+        .current_dir(temp.path())
+        .env("PYBUN_TEST_HISTORY", "0")
+        .args([
+            "--format=json",
+            "test",
+            "--backend=pytest",
+            "-j",
+            "2",
+            "test_sched.py",
+        ])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Valid JSON");
+    let detail = &json["detail"];
+    assert_eq!(detail["scheduler"]["workers"], 2, "{detail}");
+    assert_eq!(detail["exit_code"], 1);
+
+    // Every test ran exactly once, split across two processes.
+    let mut seen: Vec<String> = fs::read_dir(temp.path())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("worker-"))
+        .flat_map(|e| {
+            fs::read_to_string(e.path())
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    seen.sort();
+    assert_eq!(
+        seen,
+        [
+            "test_sched.py::test_a",
+            "test_sched.py::test_b",
+            "test_sched.py::test_c",
+            "test_sched.py::test_fails",
+        ]
+    );
+
+    // Worker reports are merged, and results were streamed as events.
+    let tests = detail["tests"].as_array().unwrap();
+    assert_eq!(tests.len(), 4);
+    assert_eq!(
+        tests
+            .iter()
+            .filter(|t| t["outcome"] == "failed")
+            .map(|t| t["name"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        ["test_fails"]
+    );
+    let streamed = json["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["type"] == "test_pass" || e["type"] == "test_fail")
+        .count();
+    assert_eq!(streamed, 4);
+}
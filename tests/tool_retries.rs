@@ -0,0 +1,130 @@
+//! E2E tests for supervised external tool runs: a flaky `uv pip install` is
+//! retried, and a failing one leaves its captured log on the diagnostics.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::{TempDir, tempdir};
+
+/// A fake `uv` whose `pip install` runs `install`; `uv run` always fails.
+fn fake_uv(dir: &Path, install: &str) -> PathBuf {
+    let bin_dir = dir.join("fake-bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    let uv = bin_dir.join("uv");
+    fs::write(
+        &uv,
+        format!(
+            "#!/usr/bin/env sh\nif [ \"$1\" = \"run\" ]; then\n  echo UV_RUN_WAS_CALLED >&2\n  exit 1\nfi\n{install}\n"
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&uv, fs::Permissions::from_mode(0o755)).unwrap();
+    bin_dir
+}
+
+/// A PEP 723 script with a lockfile, so `pybun run` installs its pins with
+/// `uv pip install`.
+fn locked_script(temp: &TempDir, path_env: &std::ffi::OsStr) -> PathBuf {
+    let script = temp.path().join("hello.py");
+    fs::write(
+        &script,
+        "# /// script\n# dependencies = [\"app==1.0.0\"]\n# ///\nprint(\"hello from the script\")\n",
+    )
+    .unwrap();
+    cargo_bin_cmd!("pybun")
+        .env("PATH", path_env)
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .args([
+            "--format=json",
+            "lock",
+            "--script",
+            script.to_str().unwrap(),
+            "--index",
+            "tests/fixtures/index.json",
+        ])
+        .assert()
+        .success();
+    script
+}
+
+fn run(temp: &TempDir, path_env: &std::ffi::OsStr, script: &Path) -> Value {
+    let output = cargo_bin_cmd!("pybun")
+        .env("PATH", path_env)
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_INSTALLER", "pip")
+        .env("PYBUN_TOOL_RETRIES", "2")
+        .env_remove("PYBUN_TOOL_TIMEOUT")
+        .env_remove("PYBUN_PEP723_DRY_RUN")
+        .args(["--format=json", "run", script.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).unwrap_or_else(|_| panic!("valid JSON, got: {stdout}"))
+}
+
+fn path_with(dir: &Path) -> std::ffi::OsString {
+    let mut entries = vec![dir.to_path_buf()];
+    if let Some(existing) = std::env::var_os("PATH") {
+        entries.extend(std::env::split_paths(&existing));
+    }
+    std::env::join_paths(entries).unwrap()
+}
+
+#[test]
+fn transient_installer_failures_are_retried() {
+    let temp = tempdir().unwrap();
+    let marker = temp.path().join("attempted");
+    let bin_dir = fake_uv(
+        temp.path(),
+        &format!(
+            "if [ -e {0} ]; then exit 0; fi\ntouch {0}\necho 'error: Connection reset by peer (os error 104)' >&2\nexit 2",
+            marker.display()
+        ),
+    );
+    let path_env = path_with(&bin_dir);
+    let script = locked_script(&temp, &path_env);
+
+    let json = run(&temp, &path_env, &script);
+    assert_eq!(json["status"], "ok", "{json}");
+    assert!(
+        json["detail"]["stdout"]
+            .as_str()
+            .is_some_and(|out| out.contains("hello from the script")),
+        "{json}"
+    );
+    assert!(marker.exists());
+}
+
+#[test]
+fn permanent_installer_failures_carry_the_tool_log() {
+    let temp = tempdir().unwrap();
+    let bin_dir = fake_uv(
+        temp.path(),
+        "echo 'error: No solution found when resolving dependencies' >&2\nexit 1",
+    );
+    let path_env = path_with(&bin_dir);
+    let script = locked_script(&temp, &path_env);
+
+    let json = run(&temp, &path_env, &script);
+    assert_eq!(json["status"], "error", "{json}");
+    let tool = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find_map(|d| d["context"].get("tool"))
+        .unwrap_or_else(|| panic!("no tool log in diagnostics: {json}"));
+    assert_eq!(tool["tool"], "uv");
+    assert_eq!(tool["class"], "failed");
+    assert_eq!(tool["command"][1], "pip");
+    // Not a transient failure: it ran once.
+    let attempts = tool["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 1, "{json}");
+    assert_eq!(attempts[0]["exit_code"], 1);
+    assert_eq!(
+        attempts[0]["stderr"],
+        "error: No solution found when resolving dependencies"
+    );
+}
@@ -0,0 +1,127 @@
+//! `pybun tree` renders the locked dependency graph as a tree, inverted on
+//! one package, or as DOT.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::{Lockfile, Package, PackageSource};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+    Package {
+        name: name.into(),
+        version: version.into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: format!("{name}-{version}-py3-none-any.whl"),
+        hash: format!("sha256:{}", "cd".repeat(32)),
+        dependencies: deps.iter().map(|d| d.to_string()).collect(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
+    }
+}
+
+/// `demo` depends on `requests` and `httpx`, which both depend on `idna`.
+fn setup_project(root: &Path) {
+    fs::write(
+        root.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"requests>=2.31\", \"httpx\"]\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+    lock.add_package(package("requests", "2.32.0", &["idna<4", "urllib3>=1.21"]));
+    lock.add_package(package("httpx", "0.27.0", &["idna"]));
+    lock.add_package(package("idna", "3.7", &[]));
+    lock.add_package(package("urllib3", "2.2.0", &[]));
+    lock.save_to_path(root.join("pybun.lockb")).unwrap();
+}
+
+fn pybun(dir: &Path, args: &[&str]) -> std::process::Output {
+    cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn text_tree_shows_requirements_on_each_edge() {
+    let dir = tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = pybun(dir.path(), &["tree"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("demo 0.1.0\n"), "{stdout}");
+    assert!(stdout.contains("├── httpx 0.27.0 (httpx)"), "{stdout}");
+    assert!(stdout.contains("    ├── idna 3.7 (idna<4)"), "{stdout}");
+    assert!(
+        stdout.contains("    └── urllib3 2.2.0 (urllib3>=1.21)"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn inverted_json_tree_explains_why_a_package_is_locked() {
+    let dir = tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = pybun(
+        dir.path(),
+        &["--format=json", "tree", "--invert", "IDNA", "--depth", "1"],
+    );
+    assert!(output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let detail = &json["detail"];
+    assert_eq!(detail["invert"], "idna");
+    assert_eq!(detail["tree"]["name"], "idna");
+    let dependents: Vec<&str> = detail["tree"]["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(dependents, ["httpx", "requests"]);
+    assert_eq!(detail["tree"]["children"][1]["requirement"], "idna<4");
+    // --depth 1 stops before the project.
+    assert!(
+        detail["tree"]["children"][0]["children"]
+            .as_array()
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn dot_export_contains_only_the_shown_edges() {
+    let dir = tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = pybun(
+        dir.path(),
+        &["tree", "--invert", "urllib3", "--export", "dot"],
+    );
+    assert!(output.status.success());
+    let dot = String::from_utf8_lossy(&output.stdout);
+    assert!(dot.starts_with("digraph dependencies {"), "{dot}");
+    assert!(dot.contains("\"requests\" -> \"urllib3\""), "{dot}");
+    assert!(dot.contains("\"demo\" -> \"requests\""), "{dot}");
+    assert!(!dot.contains("httpx"), "{dot}");
+}
+
+#[test]
+fn inverting_on_an_unlocked_package_fails() {
+    let dir = tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = pybun(dir.path(), &["--format=json", "tree", "--invert", "flask"]);
+    assert!(!output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["diagnostics"][0]["code"], "E_TREE_PACKAGE_NOT_LOCKED");
+}
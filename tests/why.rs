@@ -0,0 +1,103 @@
+//! `pybun why` lists the requirement chains that pull a package into the
+//! lock.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use pybun::lockfile::{Lockfile, Package, PackageSource};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn package(name: &str, version: &str, deps: &[&str]) -> Package {
+    Package {
+        name: name.into(),
+        version: version.into(),
+        source: PackageSource::Registry {
+            index: "pypi".into(),
+            url: "https://pypi.org/simple".into(),
+        },
+        wheel: format!("{name}-{version}-py3-none-any.whl"),
+        hash: format!("sha256:{}", "cd".repeat(32)),
+        dependencies: deps.iter().map(|d| d.to_string()).collect(),
+        dynamic_metadata: false,
+        groups: Vec::new(),
+        build: None,
+        artifacts: Vec::new(),
+    }
+}
+
+/// `demo` requires `requests`; its `test` group requires `pytest`, which
+/// also requires `requests`.
+fn setup_project(root: &Path) {
+    fs::write(
+        root.join("pyproject.toml"),
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\ndependencies = [\"requests>=2.31\"]\n\n\
+         [dependency-groups]\ntest = [\"pytest>=8\"]\n",
+    )
+    .unwrap();
+    let mut lock = Lockfile::new(vec!["3.11".into()], vec!["any".into()]);
+    lock.add_package(package(
+        "requests",
+        "2.32.0",
+        &["urllib3<3,>=1.21; python_version >= \"3.8\""],
+    ));
+    lock.add_package(package("pytest", "8.0.0", &["requests"]));
+    lock.add_package(package("urllib3", "2.2.0", &[]));
+    lock.save_to_path(root.join("pybun.lockb")).unwrap();
+}
+
+fn pybun(dir: &Path, args: &[&str]) -> std::process::Output {
+    cargo_bin_cmd!("pybun")
+        .current_dir(dir)
+        .env("PYBUN_HOME", dir.join("home"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn text_output_shows_one_chain_per_line() {
+    let dir = tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = pybun(dir.path(), &["why", "urllib3"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "urllib3 2.2.0\n  \
+         main: demo → requests 2.32.0 [requests>=2.31] → urllib3 2.2.0 [urllib3<3,>=1.21; python_version >= \"3.8\"]\n  \
+         test: demo → pytest 8.0.0 [pytest>=8] → requests 2.32.0 [requests] → urllib3 2.2.0 [urllib3<3,>=1.21; python_version >= \"3.8\"]\n"
+    );
+}
+
+#[test]
+fn json_output_carries_groups_requirements_and_markers() {
+    let dir = tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = pybun(dir.path(), &["--format=json", "why", "Requests"]);
+    assert!(output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let detail = &json["detail"];
+    assert_eq!(detail["package"], "requests");
+    assert_eq!(detail["direct"], true);
+    let chains = detail["chains"].as_array().unwrap();
+    assert_eq!(chains.len(), 2);
+    assert_eq!(chains[0]["group"], "main");
+    assert_eq!(chains[0]["links"][0]["requirement"], "requests>=2.31");
+    assert_eq!(chains[1]["group"], "test");
+    assert_eq!(chains[1]["links"][1]["from"], "pytest");
+    assert!(chains[1]["links"][1].get("marker").is_none());
+}
+
+#[test]
+fn unlocked_package_is_an_error() {
+    let dir = tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = pybun(dir.path(), &["--format=json", "why", "flask"]);
+    assert!(!output.status.success());
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["diagnostics"][0]["code"], "E_WHY_PACKAGE_NOT_LOCKED");
+}
@@ -659,3 +659,98 @@ fn upgrade_with_unknown_group_falls_back_to_empty_dependency_set() {
         .stdout(predicate::str::contains("\"upgraded\":[]"))
         .stdout(predicate::str::contains("\"scope\":\"group\""));
 }
+
+#[test]
+fn workspace_install_links_members_editable_for_run_package() {
+    let temp = tempdir().unwrap();
+    let root = temp.path();
+    fs::write(
+        root.join("pyproject.toml"),
+        "[tool.pybun.workspace]\nmembers = [\"packages/api\", \"packages/sdk\"]\n",
+    )
+    .unwrap();
+    // `api` depends on its sibling member, which no index serves.
+    write_pyproject(
+        &root.join("packages/api/pyproject.toml"),
+        &["sdk>=0.1", "lib-a==1.0.0"],
+    );
+    write_pyproject(&root.join("packages/sdk/pyproject.toml"), &["lib-b==2.0.0"]);
+    fs::create_dir_all(root.join("packages/sdk/src/sdk")).unwrap();
+    fs::write(
+        root.join("packages/sdk/src/sdk/__init__.py"),
+        "GREETING = 'hello from sdk'\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("packages/api/main.py"),
+        "import os\nimport sdk\nprint(sdk.GREETING, os.path.basename(os.getcwd()))\n",
+    )
+    .unwrap();
+
+    let index = fixture_index();
+    let output = bin()
+        .current_dir(root)
+        .args([
+            "--format=json",
+            "install",
+            "--no-seed",
+            "--member",
+            "api",
+            "--index",
+            index.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run pybun install --member");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "install should not resolve the sdk member: {stdout}"
+    );
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let packages = json["detail"]["packages"].as_array().unwrap();
+    assert!(packages.iter().any(|p| p == "lib-b"), "{stdout}");
+    assert!(!packages.iter().any(|p| p == "sdk"), "{stdout}");
+    let editable: Vec<&str> = json["detail"]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|r| r["editable"].is_string())
+        .map(|r| r["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(editable, vec!["api", "sdk"], "{stdout}");
+
+    let output = bin()
+        .current_dir(root)
+        .args(["--format=json", "run", "--package", "api", "main.py"])
+        .output()
+        .expect("run pybun run --package");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(
+        json["detail"]["stdout"].as_str().unwrap().trim(),
+        "hello from sdk api"
+    );
+    assert_eq!(json["detail"]["workspace"]["selected_members"][0], "api");
+}
+
+#[test]
+fn test_command_accepts_package_alias_for_member() {
+    let temp = tempdir().unwrap();
+    let root = temp.path();
+    write_monorepo(root);
+    fs::write(
+        root.join("packages/sdk/test_sdk.py"),
+        "def test_ok():\n    assert True\n",
+    )
+    .unwrap();
+
+    bin()
+        .current_dir(root)
+        .env("PYBUN_TEST_DRY_RUN", "1")
+        .args(["--format=json", "test", "--package", "sdk"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("test_sdk.py"))
+        .stdout(predicate::str::contains("\"selected_members\":[\"sdk\"]"));
+}
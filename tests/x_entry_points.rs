@@ -0,0 +1,160 @@
+//! E2E tests for `pybun x` command discovery: the command comes from the
+//! installed `entry_points.txt`, `--from` picks one of several, and a failed
+//! guess lists what the package provides.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use tempfile::TempDir;
+
+/// `webcli` 1.0.0, whose console scripts are `web` and `webs` (no `webcli`),
+/// like httpie's `http` and `https`.
+fn wheel_bytes() -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, content) in [
+        (
+            "webcli/cli.py",
+            "import sys\n\ndef web():\n    print('web', sys.argv[1:])\n\ndef webs():\n    print('webs', sys.argv[1:])\n",
+        ),
+        (
+            "webcli-1.0.0.dist-info/METADATA",
+            "Metadata-Version: 2.1\nName: webcli\nVersion: 1.0.0\n",
+        ),
+        ("webcli-1.0.0.dist-info/WHEEL", "Wheel-Version: 1.0\n"),
+        (
+            "webcli-1.0.0.dist-info/entry_points.txt",
+            "[console_scripts]\nweb = webcli.cli:web\nwebs = webcli.cli:webs\n",
+        ),
+        ("webcli-1.0.0.dist-info/RECORD", ""),
+    ] {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn mock_pypi(server: &MockServer) {
+    let wheel = wheel_bytes();
+    let filename = "webcli-1.0.0-py3-none-any.whl";
+    let project = json!({
+        "info": { "name": "webcli", "version": "1.0.0" },
+        "releases": { "1.0.0": [{
+            "filename": filename,
+            "packagetype": "bdist_wheel",
+            "url": format!("{}/files/{filename}", server.base_url()),
+            "yanked": false,
+            "digests": { "sha256": hex::encode(Sha256::digest(&wheel)) }
+        }]}
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/webcli/json");
+        then.status(200).body(project.clone());
+    });
+    let release = json!({
+        "info": { "name": "webcli", "version": "1.0.0", "requires_dist": [] }
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/webcli/1.0.0/json");
+        then.status(200).body(release.clone());
+    });
+    server.mock(|when, then| {
+        when.method(GET).path(format!("/files/{filename}"));
+        then.status(200).body(wheel.clone());
+    });
+}
+
+fn pybun(temp: &TempDir, base_url: &str, args: &[&str]) -> (std::process::Output, Value) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PYPI_BASE_URL", base_url)
+        .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("pypi-cache"))
+        .env_remove("PYBUN_INDEX_URL")
+        .env_remove("PYBUN_INSTALLER")
+        .env_remove("PYBUN_X_DRY_RUN")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .rev()
+        .find(|line| line.starts_with('{'))
+        .unwrap_or_else(|| panic!("no JSON in stdout: {stdout}"));
+    let json = serde_json::from_str(line).unwrap();
+    (output, json)
+}
+
+#[test]
+fn x_from_runs_a_command_the_package_declares() {
+    let temp = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_pypi(&server);
+
+    // No `webcli` command: the guess fails and names the real ones.
+    let (output, json) = pybun(&temp, &server.base_url(), &["x", "webcli"]);
+    assert!(!output.status.success(), "{json}");
+    let diagnostic = json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["code"] == "E_X_COMMAND_NOT_FOUND")
+        .unwrap_or_else(|| panic!("{json}"));
+    assert_eq!(diagnostic["context"]["available"], json!(["web", "webs"]));
+    assert!(
+        diagnostic["suggestion"]
+            .as_str()
+            .unwrap()
+            .contains("pybun x --from webcli web"),
+        "{json}"
+    );
+    assert!(
+        !json["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|d| d["code"] == "E_X_FAILED"),
+        "{json}"
+    );
+
+    // The environment was kept: --from reuses it without the index.
+    let (output, json) = pybun(
+        &temp,
+        "http://127.0.0.1:9",
+        &["x", "--from", "webcli", "webs", "--", "--verbose"],
+    );
+    assert!(output.status.success(), "{json}");
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("webs ['--verbose']"),
+        "{json}"
+    );
+    let detail = &json["detail"];
+    assert_eq!(detail["package"], "webcli");
+    assert_eq!(detail["command"], "webs");
+    assert_eq!(detail["entry_point"], "webcli.cli:webs");
+    assert_eq!(detail["tool_env"]["reused"], true);
+}
+
+#[test]
+fn x_from_rejects_commands_the_package_lacks() {
+    let temp = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_pypi(&server);
+
+    let (output, json) = pybun(
+        &temp,
+        &server.base_url(),
+        &["x", "--from", "webcli", "curl"],
+    );
+    assert!(!output.status.success(), "{json}");
+    assert_eq!(
+        json["detail"]["error"],
+        "webcli has no command `curl` (available: web, webs)"
+    );
+}
@@ -0,0 +1,199 @@
+//! E2E tests for the persistent `pybun x` tool environments: a tool is
+//! installed once and reused, and `--list`, `--upgrade` and `--remove`
+//! manage the cache.
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use httpmock::prelude::*;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use tempfile::TempDir;
+
+/// `hellotool` `version`: a `hellotool` console script printing the version.
+fn wheel_bytes(version: &str) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    let dist_info = format!("hellotool-{version}.dist-info");
+    for (name, content) in [
+        (
+            "hellotool/__init__.py".to_string(),
+            format!("def main():\n    print('hellotool {version}')\n    return 0\n"),
+        ),
+        (
+            format!("{dist_info}/METADATA"),
+            format!("Metadata-Version: 2.1\nName: hellotool\nVersion: {version}\n"),
+        ),
+        (
+            format!("{dist_info}/WHEEL"),
+            "Wheel-Version: 1.0\n".to_string(),
+        ),
+        (
+            format!("{dist_info}/entry_points.txt"),
+            "[console_scripts]\nhellotool = hellotool:main\n".to_string(),
+        ),
+        (format!("{dist_info}/RECORD"), String::new()),
+    ] {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Fake PyPI serving `versions` of `hellotool`; the last one is the latest.
+fn mock_pypi(server: &MockServer, versions: &[&str]) {
+    let mut releases = serde_json::Map::new();
+    for version in versions {
+        let wheel = wheel_bytes(version);
+        let filename = format!("hellotool-{version}-py3-none-any.whl");
+        releases.insert(
+            version.to_string(),
+            json!([{
+                "filename": filename,
+                "packagetype": "bdist_wheel",
+                "url": format!("{}/files/{filename}", server.base_url()),
+                "yanked": false,
+                "digests": { "sha256": hex::encode(Sha256::digest(&wheel)) }
+            }]),
+        );
+        let release = json!({
+            "info": { "name": "hellotool", "version": version, "requires_dist": [] }
+        })
+        .to_string();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/pypi/hellotool/{version}/json"));
+            then.status(200).body(release.clone());
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/files/{filename}"));
+            then.status(200).body(wheel.clone());
+        });
+    }
+    let project = json!({
+        "info": { "name": "hellotool", "version": versions.last().unwrap() },
+        "releases": releases,
+    })
+    .to_string();
+    server.mock(|when, then| {
+        when.method(GET).path("/pypi/hellotool/json");
+        then.status(200).body(project.clone());
+    });
+}
+
+fn pybun(temp: &TempDir, base_url: &str, args: &[&str]) -> (std::process::Output, Value) {
+    let output = cargo_bin_cmd!("pybun")
+        .current_dir(temp.path())
+        .env("PYBUN_HOME", temp.path().join("home"))
+        .env("PYBUN_PYPI_BASE_URL", base_url)
+        .env("PYBUN_PYPI_CACHE_DIR", temp.path().join("pypi-cache"))
+        .env_remove("PYBUN_INDEX_URL")
+        .env_remove("PYBUN_INSTALLER")
+        .env_remove("PYBUN_X_DRY_RUN")
+        .arg("--format=json")
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .rev()
+        .find(|line| line.starts_with('{'))
+        .unwrap_or_else(|| panic!("no JSON in stdout: {stdout}"));
+    let json = serde_json::from_str(line).unwrap();
+    (output, json)
+}
+
+#[test]
+fn tool_environments_are_reused_upgraded_and_removed() {
+    let temp = TempDir::new().unwrap();
+    let v1 = MockServer::start();
+    mock_pypi(&v1, &["1.0.0"]);
+
+    let (output, json) = pybun(&temp, &v1.base_url(), &["x", "hellotool"]);
+    assert!(output.status.success(), "{json}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hellotool 1.0.0"));
+    let tool_env = &json["detail"]["tool_env"];
+    assert_eq!(tool_env["reused"], false);
+    assert_eq!(tool_env["version"], "1.0.0");
+
+    // The second run needs no index at all.
+    let (output, json) = pybun(&temp, "http://127.0.0.1:9", &["x", "hellotool"]);
+    assert!(output.status.success(), "{json}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hellotool 1.0.0"));
+    assert_eq!(json["detail"]["tool_env"]["reused"], true);
+    assert!(json["detail"]["install"].is_null());
+
+    // A failed upgrade keeps the working environment.
+    let (output, json) = pybun(
+        &temp,
+        "http://127.0.0.1:9",
+        &["x", "--upgrade", "hellotool"],
+    );
+    assert!(!output.status.success(), "{json}");
+    let (output, _) = pybun(&temp, "http://127.0.0.1:9", &["x", "hellotool"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hellotool 1.0.0"));
+
+    let v2 = MockServer::start();
+    mock_pypi(&v2, &["1.0.0", "2.0.0"]);
+    let (output, json) = pybun(&temp, &v2.base_url(), &["x", "--upgrade", "hellotool"]);
+    assert!(output.status.success(), "{json}");
+    assert_eq!(json["detail"]["previous_version"], "1.0.0");
+    assert_eq!(json["detail"]["version"], "2.0.0");
+    assert!(
+        !String::from_utf8_lossy(&output.stdout).contains("hellotool 2.0.0"),
+        "--upgrade does not run the tool"
+    );
+    let (output, _) = pybun(&temp, "http://127.0.0.1:9", &["x", "hellotool"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hellotool 2.0.0"));
+
+    // A pinned requirement gets its own environment.
+    let (output, json) = pybun(&temp, &v2.base_url(), &["x", "hellotool==1.0.0"]);
+    assert!(output.status.success(), "{json}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hellotool 1.0.0"));
+
+    let (_, json) = pybun(&temp, &v2.base_url(), &["x", "--list"]);
+    let tools = json["detail"]["tools"].as_array().unwrap();
+    let listed: Vec<(&str, &str)> = tools
+        .iter()
+        .map(|t| {
+            (
+                t["requirement"].as_str().unwrap(),
+                t["version"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        listed,
+        [("hellotool", "2.0.0"), ("hellotool==1.0.0", "1.0.0")]
+    );
+    assert!(tools[0]["size_bytes"].as_u64().unwrap() > 0);
+
+    let (output, json) = pybun(&temp, &v2.base_url(), &["x", "--remove", "hellotool"]);
+    assert!(output.status.success(), "{json}");
+    assert_eq!(json["detail"]["removed"].as_array().unwrap().len(), 2);
+    let (_, json) = pybun(&temp, &v2.base_url(), &["x", "--list"]);
+    assert_eq!(json["detail"]["tools"], json!([]));
+}
+
+#[test]
+fn sandboxed_tools_run_under_the_sandbox_policy() {
+    let temp = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_pypi(&server, &["1.0.0"]);
+
+    let (output, json) = pybun(&temp, &server.base_url(), &["x", "--sandbox", "hellotool"]);
+    assert!(output.status.success(), "{json}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hellotool 1.0.0"));
+    let sandbox = &json["detail"]["sandbox"];
+    assert_eq!(sandbox["enabled"], true);
+    assert_eq!(sandbox["allow_network"], false);
+    assert!(sandbox["audit"].is_object(), "{json}");
+
+    // --pass-env does not apply inside the sandbox; --allow-env does.
+    let output = cargo_bin_cmd!("pybun")
+        .args(["x", "--sandbox", "--pass-env", "HOME", "hellotool"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--pass-env"));
+}